@@ -30,7 +30,7 @@ use std::{
     path::{Path, PathBuf},
     process::{ExitCode, Termination},
     str::FromStr,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use cedar_policy::*;
@@ -66,6 +66,17 @@ pub struct Cli {
         value_enum
     )]
     pub err_fmt: ErrorFormat,
+    /// The output format to use for the results of commands. With `json`,
+    /// every command prints a single JSON object, and errors are reported in
+    /// JSON.
+    #[arg(
+        global = true,
+        long = "format",
+        env = "CEDAR_OUTPUT_FORMAT",
+        default_value_t,
+        value_enum
+    )]
+    pub output_format: OutputFormat,
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
@@ -95,6 +106,29 @@ impl Display for ErrorFormat {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output.
+    #[default]
+    Human,
+    /// Machine-readable JSON output: a single JSON object per command, with a
+    /// `success` field and, if the command failed, its `errors`.
+    Json,
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OutputFormat::Human => "human",
+                OutputFormat::Json => "json",
+            }
+        )
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Evaluate an authorization request
@@ -116,7 +150,8 @@ pub enum Commands {
     /// Visualize a set of JSON entities to the graphviz format.
     /// Warning: Entity visualization is best-effort and not well tested.
     Visualize(VisualizeArgs),
-    /// Create a Cedar project
+    /// Create a Cedar project with a starter schema, policies and a
+    /// template, sample entities, and a test suite
     New(NewArgs),
     /// Partially evaluate an authorization request
     PartiallyAuthorize(PartiallyAuthorizeArgs),
@@ -177,6 +212,8 @@ pub enum SchemaFormat {
     Cedar,
     /// JSON format
     Json,
+    /// YAML with the structure of the JSON format
+    Yaml,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -206,6 +243,24 @@ pub struct ValidateArgs {
     /// experimental feature `permissive-validate` and `partial-validate`, respectively, enabled.
     #[arg(long, value_enum, default_value_t = ValidationMode::Strict)]
     pub validation_mode: ValidationMode,
+    /// Validate the policies in both strict and permissive mode, and report
+    /// the policies for which the modes report different errors, with the
+    /// errors only reported in strict mode.
+    /// This option is experimental and will cause the CLI to exit if it was
+    /// not built with the experimental feature `permissive-validate` enabled.
+    #[arg(long, conflicts_with = "validation_mode")]
+    pub compare_modes: bool,
+    /// Report how long validating each policy and template took and how much
+    /// work typechecking it took, slowest first
+    #[arg(long, conflicts_with = "compare_modes")]
+    pub profile: bool,
+    /// Warn about policies whose `@expires` date is within this many days
+    #[arg(long, default_value_t = 30, value_name = "DAYS")]
+    pub expiration_warning_days: u32,
+    /// Report policies whose `@expires` date has passed as warnings rather
+    /// than errors
+    #[arg(long)]
+    pub allow_expired: bool,
 }
 
 #[derive(Args, Debug)]
@@ -527,6 +582,8 @@ fn read_schema_from_file(path: impl AsRef<Path>, format: SchemaFormat) -> Result
     match format {
         SchemaFormat::Json => Schema::from_json_str(&schema_src)
             .wrap_err_with(|| format!("failed to parse schema from file {}", path.display())),
+        SchemaFormat::Yaml => Schema::from_yaml_str(&schema_src)
+            .wrap_err_with(|| format!("failed to parse schema from file {}", path.display())),
         SchemaFormat::Cedar => {
             let (schema, warnings) = Schema::from_cedarschema_str(&schema_src)
                 .wrap_err_with(|| format!("failed to parse schema from file {}", path.display()))?;
@@ -553,7 +610,8 @@ pub struct AuthorizeArgs {
     /// parsing of entity hierarchy, if present
     #[command(flatten)]
     pub schema: OptionalSchemaArgs,
-    /// File containing JSON representation of the Cedar entity hierarchy
+    /// File containing JSON (or, if named `*.yaml` or `*.yml`, YAML)
+    /// representation of the Cedar entity hierarchy
     #[arg(long = "entities", value_name = "FILE")]
     pub entities_file: String,
     /// More verbose output. (For instance, indicate which policies applied to the request, if any.)
@@ -573,7 +631,8 @@ pub struct PartiallyAuthorizeArgs {
     /// Policies args (incorporated by reference)
     #[command(flatten)]
     pub policies: PoliciesArgs,
-    /// File containing JSON representation of the Cedar entity hierarchy
+    /// File containing JSON (or, if named `*.yaml` or `*.yml`, YAML)
+    /// representation of the Cedar entity hierarchy
     #[arg(long = "entities", value_name = "FILE")]
     pub entities_file: String,
     /// Time authorization and report timing information
@@ -637,6 +696,16 @@ pub struct FormatArgs {
     /// Check that the policies formats without any changes. Mutually exclusive with `write`.
     #[arg(short, long, group = "action")]
     pub check: bool,
+
+    /// Schema args (incorporated by reference), used by `--qualify-names`
+    #[command(flatten)]
+    pub schema: OptionalSchemaArgs,
+
+    /// Rewrite entity type and action references which the schema does not
+    /// declare, but which abbreviate exactly one declared name (e.g., `User`
+    /// for `Acme::User`), to that fully qualified name. Requires a schema.
+    #[arg(long, requires = "schema_file")]
+    pub qualify_names: bool,
 }
 
 #[derive(Args, Debug)]
@@ -715,7 +784,8 @@ pub struct EvaluateArgs {
     /// parsing of entity hierarchy, if present
     #[command(flatten)]
     pub schema: OptionalSchemaArgs,
-    /// File containing JSON representation of the Cedar entity hierarchy.
+    /// File containing JSON (or, if named `*.yaml` or `*.yml`, YAML)
+    /// representation of the Cedar entity hierarchy.
     /// This is optional; if not present, we'll just use an empty hierarchy.
     #[arg(long = "entities", value_name = "FILE")]
     pub entities_file: Option<String>,
@@ -756,17 +826,91 @@ impl Termination for CedarExitCode {
     }
 }
 
+/// Render `diagnostic` as JSON with miette's JSON report handler: an object
+/// with its `message`, `code`, `severity`, `help`, and `labels` with their
+/// spans
+fn diagnostic_json(diagnostic: &dyn miette::Diagnostic) -> serde_json::Value {
+    let mut rendered = String::new();
+    match miette::JSONReportHandler::new().render_report(&mut rendered, diagnostic) {
+        Ok(()) => serde_json::from_str(&rendered)
+            .unwrap_or_else(|_| serde_json::json!({ "message": diagnostic.to_string() })),
+        Err(_) => serde_json::json!({ "message": diagnostic.to_string() }),
+    }
+}
+
+/// Render `diagnostic`, which was found in the policy `policy_id`, as JSON
+/// like [`diagnostic_json`], additionally with its `policyId`
+fn policy_diagnostic_json(
+    diagnostic: &dyn miette::Diagnostic,
+    policy_id: &PolicyId,
+) -> serde_json::Value {
+    let mut json = diagnostic_json(diagnostic);
+    if let Some(object) = json.as_object_mut() {
+        object.insert("policyId".into(), policy_id.to_string().into());
+    }
+    json
+}
+
+/// Print the result of a command in JSON format
+fn print_json(result: &serde_json::Value) {
+    println!("{result}");
+}
+
+/// Print the errors which made a command fail, in JSON format
+fn print_json_errors<'a>(errors: impl IntoIterator<Item = &'a Report>) {
+    print_json(&serde_json::json!({
+        "success": false,
+        "errors": errors.into_iter().map(|e| diagnostic_json(e.as_ref())).collect::<Vec<_>>(),
+    }));
+}
+
+/// Print the successful result of a command in `format`: `human` as printed
+/// by `print_human`, and `json` as `json` with `"success": true` added
+fn print_success(
+    format: OutputFormat,
+    print_human: impl FnOnce(),
+    json: serde_json::Value,
+) -> CedarExitCode {
+    match format {
+        OutputFormat::Human => print_human(),
+        OutputFormat::Json => {
+            let mut json = json;
+            if let Some(object) = json.as_object_mut() {
+                object.insert("success".into(), true.into());
+            }
+            print_json(&json);
+        }
+    }
+    CedarExitCode::Success
+}
+
 pub fn check_parse(args: &CheckParseArgs) -> CedarExitCode {
+    check_parse_with_format(args, OutputFormat::Human)
+}
+
+pub fn check_parse_with_format(args: &CheckParseArgs, format: OutputFormat) -> CedarExitCode {
     match args.policies.get_policy_set() {
-        Ok(_) => CedarExitCode::Success,
+        Ok(_) => print_success(format, || {}, serde_json::json!({})),
         Err(e) => {
-            println!("{e:?}");
+            match format {
+                OutputFormat::Human => println!("{e:?}"),
+                OutputFormat::Json => print_json_errors([&e]),
+            }
             CedarExitCode::Failure
         }
     }
 }
 
 pub fn validate(args: &ValidateArgs) -> CedarExitCode {
+    validate_with_format(args, OutputFormat::Human)
+}
+
+pub fn validate_with_format(args: &ValidateArgs, format: OutputFormat) -> CedarExitCode {
+    #[cfg(not(feature = "permissive-validate"))]
+    if args.compare_modes {
+        eprintln!("Error: arguments include the experimental option `--compare-modes`, but this executable was not built with `permissive-validate` experimental feature enabled");
+        return CedarExitCode::Failure;
+    }
     let mode = match args.validation_mode {
         ValidationMode::Strict => cedar_policy::ValidationMode::Strict,
         ValidationMode::Permissive => {
@@ -789,151 +933,360 @@ pub fn validate(args: &ValidateArgs) -> CedarExitCode {
         }
     };
 
+    let fail = |e: Report| {
+        match format {
+            OutputFormat::Human => println!("{e:?}"),
+            OutputFormat::Json => print_json_errors([&e]),
+        }
+        CedarExitCode::Failure
+    };
     let pset = match args.policies.get_policy_set() {
         Ok(pset) => pset,
-        Err(e) => {
-            println!("{e:?}");
-            return CedarExitCode::Failure;
-        }
+        Err(e) => return fail(e),
     };
 
     let schema = match args.schema.get_schema() {
         Ok(schema) => schema,
-        Err(e) => {
-            println!("{e:?}");
-            return CedarExitCode::Failure;
-        }
+        Err(e) => return fail(e),
     };
 
-    let validator = Validator::new(schema);
-    let result = validator.validate(&pset, mode);
+    let expiration_rules = ExpirationRules {
+        warn_within_days: args.expiration_warning_days,
+        expired_is_error: !args.allow_expired,
+        ..ExpirationRules::new(ExpirationDate::today(&SystemClock))
+    };
+    let validator = Validator::new(schema).with_expiration_rules(expiration_rules);
+    #[cfg(feature = "permissive-validate")]
+    if args.compare_modes {
+        return report_mode_comparison(&validator.compare_validation_modes(&pset), format);
+    }
+    let (result, profiles) = if args.profile {
+        let (result, profiles) = validator.validate_with_profile(&pset, mode);
+        (result, Some(profiles))
+    } else {
+        (validator.validate(&pset, mode), None)
+    };
 
-    if !result.validation_passed()
-        || (args.deny_warnings && !result.validation_passed_without_warnings())
-    {
-        println!(
-            "{:?}",
-            Report::new(result).wrap_err("policy set validation failed")
-        );
-        CedarExitCode::ValidationFailure
+    let passed = result.validation_passed()
+        && (!args.deny_warnings || result.validation_passed_without_warnings());
+    match format {
+        OutputFormat::Human => {
+            if let Some(profiles) = &profiles {
+                report_validation_profiles(profiles);
+            }
+            let summary = if passed {
+                "policy set validation passed"
+            } else {
+                "policy set validation failed"
+            };
+            println!("{:?}", Report::new(result).wrap_err(summary));
+        }
+        OutputFormat::Json => {
+            let mut json = serde_json::json!({
+                "success": passed,
+                "errors": result
+                    .validation_errors()
+                    .map(|e| policy_diagnostic_json(e, e.policy_id()))
+                    .collect::<Vec<_>>(),
+                "warnings": result
+                    .validation_warnings()
+                    .map(|w| policy_diagnostic_json(w, w.policy_id()))
+                    .collect::<Vec<_>>(),
+            });
+            if let (Some(profiles), Some(object)) = (&profiles, json.as_object_mut()) {
+                object.insert(
+                    "profiles".into(),
+                    profiles
+                        .iter()
+                        .map(|profile| {
+                            serde_json::json!({
+                                "policyId": profile.policy_id().to_string(),
+                                "durationMicros": profile.duration().as_micros(),
+                                "expressions": profile.expressions(),
+                                "requestEnvs": profile.request_envs(),
+                            })
+                        })
+                        .collect(),
+                );
+            }
+            print_json(&json);
+        }
+    }
+    if passed {
+        CedarExitCode::Success
     } else {
+        CedarExitCode::ValidationFailure
+    }
+}
+
+/// Print how long validating each policy took, in the order given
+fn report_validation_profiles(profiles: &[cedar_policy::ValidationProfile]) {
+    for profile in profiles {
         println!(
-            "{:?}",
-            Report::new(result).wrap_err("policy set validation passed")
+            "policy `{}`: validated in {:?}, typechecked {} expressions in {} request environments",
+            profile.policy_id(),
+            profile.duration(),
+            profile.expressions(),
+            profile.request_envs()
         );
+    }
+}
+
+/// Print the policies for which strict and permissive validation report
+/// different errors, failing if there are any
+#[cfg(feature = "permissive-validate")]
+fn report_mode_comparison(
+    comparison: &cedar_policy::ValidationModeComparison,
+    format: OutputFormat,
+) -> CedarExitCode {
+    match format {
+        OutputFormat::Human => {
+            for policy in comparison.policies() {
+                let summary = match (policy.passes_strict(), policy.passes_permissive()) {
+                    (false, true) => "passes permissive validation but fails strict validation",
+                    (true, false) => "passes strict validation but fails permissive validation",
+                    _ => "fails strict and permissive validation with different errors",
+                };
+                println!("policy `{}` {summary}", policy.id());
+                for error in policy.strict_only_errors() {
+                    println!(
+                        "{:?}",
+                        Report::new(error.clone()).wrap_err("error only in strict validation")
+                    );
+                }
+                for error in policy.permissive_only_errors() {
+                    println!(
+                        "{:?}",
+                        Report::new(error.clone()).wrap_err("error only in permissive validation")
+                    );
+                }
+            }
+            if comparison.modes_agree() {
+                println!(
+                    "strict and permissive validation report the same errors for every policy"
+                );
+            }
+        }
+        OutputFormat::Json => {
+            let policies = comparison
+                .policies()
+                .map(|policy| {
+                    serde_json::json!({
+                        "policyId": policy.id().to_string(),
+                        "passesStrict": policy.passes_strict(),
+                        "passesPermissive": policy.passes_permissive(),
+                        "strictOnlyErrors": policy.strict_only_errors().map(|e| diagnostic_json(e)).collect::<Vec<_>>(),
+                        "permissiveOnlyErrors": policy.permissive_only_errors().map(|e| diagnostic_json(e)).collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            print_json(&serde_json::json!({
+                "success": comparison.modes_agree(),
+                "policies": policies,
+            }));
+        }
+    }
+    if comparison.modes_agree() {
         CedarExitCode::Success
+    } else {
+        CedarExitCode::ValidationFailure
     }
 }
 
 pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
-    println!();
+    evaluate_with_format(args, OutputFormat::Human)
+}
+
+pub fn evaluate_with_format(
+    args: &EvaluateArgs,
+    format: OutputFormat,
+) -> (CedarExitCode, EvalResult) {
+    if format == OutputFormat::Human {
+        println!();
+    }
+    let fail = |e: Report| {
+        match format {
+            OutputFormat::Human => println!("{e:?}"),
+            OutputFormat::Json => print_json_errors([&e]),
+        }
+        (CedarExitCode::Failure, EvalResult::Bool(false))
+    };
     let schema = match args.schema.get_schema() {
         Ok(opt) => opt,
-        Err(e) => {
-            println!("{e:?}");
-            return (CedarExitCode::Failure, EvalResult::Bool(false));
-        }
+        Err(e) => return fail(e),
     };
     let request = match args.request.get_request(schema.as_ref()) {
         Ok(q) => q,
-        Err(e) => {
-            println!("{e:?}");
-            return (CedarExitCode::Failure, EvalResult::Bool(false));
-        }
+        Err(e) => return fail(e),
     };
     let expr =
         match Expression::from_str(&args.expression).wrap_err("failed to parse the expression") {
             Ok(expr) => expr,
-            Err(e) => {
-                println!("{:?}", e.with_source_code(args.expression.clone()));
-                return (CedarExitCode::Failure, EvalResult::Bool(false));
-            }
+            Err(e) => return fail(e.with_source_code(args.expression.clone())),
         };
     let entities = match &args.entities_file {
         None => Entities::empty(),
         Some(file) => match load_entities(file, schema.as_ref()) {
             Ok(entities) => entities,
-            Err(e) => {
-                println!("{e:?}");
-                return (CedarExitCode::Failure, EvalResult::Bool(false));
-            }
+            Err(e) => return fail(e),
         },
     };
     match eval_expression(&request, &entities, &expr).wrap_err("failed to evaluate the expression")
     {
-        Err(e) => {
-            println!("{e:?}");
-            return (CedarExitCode::Failure, EvalResult::Bool(false));
-        }
-        Ok(result) => {
-            println!("{result}");
-            return (CedarExitCode::Success, result);
-        }
+        Err(e) => fail(e),
+        Ok(result) => (
+            print_success(
+                format,
+                || println!("{result}"),
+                serde_json::json!({ "value": result.to_string() }),
+            ),
+            result,
+        ),
     }
 }
 
 pub fn link(args: &LinkArgs) -> CedarExitCode {
-    if let Err(err) = link_inner(args) {
-        println!("{err:?}");
-        CedarExitCode::Failure
-    } else {
-        CedarExitCode::Success
+    link_with_format(args, OutputFormat::Human)
+}
+
+pub fn link_with_format(args: &LinkArgs, format: OutputFormat) -> CedarExitCode {
+    match link_inner(args) {
+        Ok(linked) => print_success(
+            format,
+            || println!("Template-linked policy added: {linked}"),
+            serde_json::json!({
+                "policyId": args.new_id,
+                "policy": linked.to_string(),
+            }),
+        ),
+        Err(err) => {
+            match format {
+                OutputFormat::Human => println!("{err:?}"),
+                OutputFormat::Json => print_json_errors([&err]),
+            }
+            CedarExitCode::Failure
+        }
     }
 }
 
 pub fn visualize(args: &VisualizeArgs) -> CedarExitCode {
+    visualize_with_format(args, OutputFormat::Human)
+}
+
+pub fn visualize_with_format(args: &VisualizeArgs, format: OutputFormat) -> CedarExitCode {
     match load_entities(&args.entities_file, None) {
         Ok(entities) => {
-            println!("{}", entities.to_dot_str());
-            CedarExitCode::Success
+            let dot = entities.to_dot_str();
+            print_success(
+                format,
+                || println!("{dot}"),
+                serde_json::json!({ "output": dot }),
+            )
         }
         Err(report) => {
-            eprintln!("{report:?}");
+            match format {
+                OutputFormat::Human => eprintln!("{report:?}"),
+                OutputFormat::Json => print_json_errors([&report]),
+            }
             CedarExitCode::Failure
         }
     }
 }
 
-/// Format the policies in the given file or stdin.
-///
-/// Returns a boolean indicating whether the formatted policies are the same as the original
-/// policies.
-fn format_policies_inner(args: &FormatArgs) -> Result<bool> {
+/// The result of formatting a policy set
+struct FormattedPolicies {
+    /// The formatted policies
+    policies: String,
+    /// Whether formatting did not change the policies
+    unchanged: bool,
+    /// Why some names were not qualified, with `--qualify-names`
+    warnings: Vec<String>,
+}
+
+/// Format the policies in the given file or stdin, writing them back to the
+/// file if requested.
+fn format_policies_inner(args: &FormatArgs) -> Result<FormattedPolicies> {
     let policies_str = read_from_file_or_stdin(args.policies_file.as_ref(), "policy set")?;
     let config = Config {
         line_width: args.line_width,
         indent_width: args.indent_width,
     };
-    let formatted_policy = policies_str_to_pretty(&policies_str, &config)?;
+    let mut warnings = Vec::new();
+    let formatted_policy = match args.schema.get_schema()? {
+        Some(schema) if args.qualify_names => {
+            let qualified = Validator::new(schema).qualify_names(&policies_str)?;
+            warnings.extend(qualified.skipped().map(|skipped| {
+                format!("in policy `{}`, {}", skipped.policy_id(), skipped.reason())
+            }));
+            policies_str_to_pretty(qualified.policies(), &config)?
+        }
+        _ => policies_str_to_pretty(&policies_str, &config)?,
+    };
     let are_policies_equivalent = policies_str == formatted_policy;
 
-    match &args.policies_file {
-        Some(policies_file) if args.write => {
-            let mut file = OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .open(policies_file)
-                .into_diagnostic()
-                .wrap_err(format!("failed to open {policies_file} for writing"))?;
-            file.write_all(formatted_policy.as_bytes())
-                .into_diagnostic()
-                .wrap_err(format!(
-                    "failed to write formatted policies to {policies_file}"
-                ))?;
-        }
-        _ => print!("{}", formatted_policy),
+    if let Some(policies_file) = args.policies_file.as_ref().filter(|_| args.write) {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(policies_file)
+            .into_diagnostic()
+            .wrap_err(format!("failed to open {policies_file} for writing"))?;
+        file.write_all(formatted_policy.as_bytes())
+            .into_diagnostic()
+            .wrap_err(format!(
+                "failed to write formatted policies to {policies_file}"
+            ))?;
     }
-    Ok(are_policies_equivalent)
+    Ok(FormattedPolicies {
+        policies: formatted_policy,
+        unchanged: are_policies_equivalent,
+        warnings,
+    })
 }
 
 pub fn format_policies(args: &FormatArgs) -> CedarExitCode {
-    match format_policies_inner(args) {
-        Ok(false) if args.check => CedarExitCode::Failure,
+    format_policies_with_format(args, OutputFormat::Human)
+}
+
+pub fn format_policies_with_format(args: &FormatArgs, format: OutputFormat) -> CedarExitCode {
+    let formatted = match format_policies_inner(args) {
+        Ok(formatted) => formatted,
         Err(err) => {
-            println!("{err:?}");
-            CedarExitCode::Failure
+            match format {
+                OutputFormat::Human => println!("{err:?}"),
+                OutputFormat::Json => print_json_errors([&err]),
+            }
+            return CedarExitCode::Failure;
+        }
+    };
+    // The policies are printed unless they were written back to their file
+    let written = args.write && args.policies_file.is_some();
+    let passed = formatted.unchanged || !args.check;
+    match format {
+        OutputFormat::Human => {
+            for warning in &formatted.warnings {
+                eprintln!("warning: {warning}");
+            }
+            if !written {
+                print!("{}", formatted.policies);
+            }
+        }
+        OutputFormat::Json => {
+            let mut json = serde_json::json!({
+                "success": passed,
+                "changed": !formatted.unchanged,
+                "warnings": formatted.warnings,
+            });
+            if let (false, Some(object)) = (written, json.as_object_mut()) {
+                object.insert("formatted".into(), formatted.policies.into());
+            }
+            print_json(&json);
         }
-        _ => CedarExitCode::Success,
+    }
+    if passed {
+        CedarExitCode::Success
+    } else {
+        CedarExitCode::Failure
     }
 }
 
@@ -951,13 +1304,24 @@ fn translate_policy_inner(args: &TranslatePolicyArgs) -> Result<String> {
 }
 
 pub fn translate_policy(args: &TranslatePolicyArgs) -> CedarExitCode {
+    translate_policy_with_format(args, OutputFormat::Human)
+}
+
+pub fn translate_policy_with_format(
+    args: &TranslatePolicyArgs,
+    format: OutputFormat,
+) -> CedarExitCode {
     match translate_policy_inner(args) {
-        Ok(sf) => {
-            println!("{sf}");
-            CedarExitCode::Success
-        }
+        Ok(sf) => print_success(
+            format,
+            || println!("{sf}"),
+            serde_json::json!({ "output": sf }),
+        ),
         Err(err) => {
-            eprintln!("{err:?}");
+            match format {
+                OutputFormat::Human => eprintln!("{err:?}"),
+                OutputFormat::Json => print_json_errors([&err]),
+            }
             CedarExitCode::Failure
         }
     }
@@ -965,12 +1329,13 @@ pub fn translate_policy(args: &TranslatePolicyArgs) -> CedarExitCode {
 
 fn translate_schema_to_cedar(json_src: impl AsRef<str>) -> Result<String> {
     let fragment = SchemaFragment::from_json_str(json_src.as_ref())?;
-    let output = fragment.to_cedarschema()?;
+    let output = fragment.to_cedarschema_with_doc_comments()?;
     Ok(output)
 }
 
 fn translate_schema_to_json(cedar_src: impl AsRef<str>) -> Result<String> {
-    let (fragment, warnings) = SchemaFragment::from_cedarschema_str(cedar_src.as_ref())?;
+    let (fragment, warnings) =
+        SchemaFragment::from_cedarschema_str_with_doc_comments(cedar_src.as_ref())?;
     for warning in warnings {
         let report = miette::Report::new(warning);
         eprintln!("{:?}", report);
@@ -988,124 +1353,247 @@ fn translate_schema_inner(args: &TranslateSchemaArgs) -> Result<String> {
 }
 
 pub fn translate_schema(args: &TranslateSchemaArgs) -> CedarExitCode {
+    translate_schema_with_format(args, OutputFormat::Human)
+}
+
+pub fn translate_schema_with_format(
+    args: &TranslateSchemaArgs,
+    format: OutputFormat,
+) -> CedarExitCode {
     match translate_schema_inner(args) {
-        Ok(sf) => {
-            println!("{sf}");
-            CedarExitCode::Success
-        }
+        Ok(sf) => print_success(
+            format,
+            || println!("{sf}"),
+            serde_json::json!({ "output": sf }),
+        ),
         Err(err) => {
-            eprintln!("{err:?}");
+            match format {
+                OutputFormat::Human => eprintln!("{err:?}"),
+                OutputFormat::Json => print_json_errors([&err]),
+            }
             CedarExitCode::Failure
         }
     }
 }
 
-/// Write a schema (in JSON format) to `path`
-fn generate_schema(path: &Path) -> Result<()> {
-    std::fs::write(
-        path,
-        serde_json::to_string_pretty(&serde_json::json!(
+/// The schema of a new project, in Cedar syntax
+const NEW_PROJECT_SCHEMA: &str = r#"// Users, and the groups they belong to
+entity Group;
+entity User in [Group];
+
+// Photos, and the albums they are in
+entity Album;
+entity Photo in [Album] = {
+  owner: User,
+  private: Bool,
+};
+
+action view, edit appliesTo {
+  principal: User,
+  resource: Photo,
+};
+"#;
+
+/// The policies and templates of a new project
+const NEW_PROJECT_POLICIES: &str = r#"// Anyone can view photos which are not private
+permit (
+  principal,
+  action == Action::"view",
+  resource
+)
+when { !resource.private };
+
+// The owner of a photo can view and edit it
+permit (
+  principal,
+  action in [Action::"view", Action::"edit"],
+  resource
+)
+when { resource.owner == principal };
+
+// Share the photos in an album with a user or group
+@id("share-album")
+permit (
+  principal in ?principal,
+  action == Action::"view",
+  resource in ?resource
+);
+"#;
+
+/// The README of a new project, describing its files and how to use them
+const NEW_PROJECT_README: &str = r#"# Cedar project
+
+This project was created with `cedar new`. It contains:
+
+- `schema.cedarschema`: the schema, declaring the entity types and actions
+- `policies.cedar`: the policies, and the template `share-album`
+- `links.json`: the policies linked from the template
+- `entities.json`: sample entities
+- `tests.json`: requests with their expected decisions, in the format of the
+  Cedar integration tests (template-linked policies are not loaded)
+- `cedar.json`: the roles of the files above, for tools working with the
+  project
+
+Validate the policies against the schema:
+
+    cedar validate --schema schema.cedarschema --policies policies.cedar --template-linked links.json
+
+Check whether Carol, who is in the group shared with, can view a private photo:
+
+    cedar authorize --schema schema.cedarschema --policies policies.cedar --template-linked links.json \
+        --entities entities.json --principal 'User::"carol"' --action 'Action::"view"' --resource 'Photo::"beach"'
+"#;
+
+/// The template links of a new project
+fn generate_links() -> serde_json::Value {
+    serde_json::json!([
         {
-            "": {
-                "entityTypes": {
-                    "A": {
-                        "memberOfTypes": [
-                            "B"
-                        ]
-                    },
-                    "B": {
-                        "memberOfTypes": []
-                    },
-                    "C": {
-                        "memberOfTypes": []
-                    }
-                },
-                "actions": {
-                    "action": {
-                        "appliesTo": {
-                            "resourceTypes": [
-                                "C"
-                            ],
-                            "principalTypes": [
-                                "A",
-                                "B"
-                            ]
-                        }
-                    }
-                }
+            "template_id": "share-album",
+            "link_id": "share-vacation-with-family",
+            "args": {
+                "?principal": "Group::\"family\"",
+                "?resource": "Album::\"vacation\""
             }
-        }))
-        .into_diagnostic()?,
-    )
-    .into_diagnostic()
-}
-
-fn generate_policy(path: &Path) -> Result<()> {
-    std::fs::write(
-        path,
-        r#"permit (
-  principal in A::"a",
-  action == Action::"action",
-  resource == C::"c"
-) when { true };
-"#,
-    )
-    .into_diagnostic()
+        }
+    ])
 }
 
-fn generate_entities(path: &Path) -> Result<()> {
-    std::fs::write(
-        path,
-        serde_json::to_string_pretty(&serde_json::json!(
-        [
-            {
-                "uid": { "type": "A", "id": "a"} ,
-                "attrs": {},
-                "parents": [{"type": "B", "id": "b"}]
-            },
-            {
-                "uid": { "type": "B", "id": "b"} ,
-                "attrs": {},
-                "parents": []
+/// The entities of a new project
+fn generate_entities() -> serde_json::Value {
+    let user = |id: &str, groups: &[&str]| {
+        serde_json::json!({
+            "uid": { "type": "User", "id": id },
+            "attrs": {},
+            "parents": groups.iter().map(|group| serde_json::json!({ "type": "Group", "id": group })).collect::<Vec<_>>()
+        })
+    };
+    let photo = |id: &str, private: bool| {
+        serde_json::json!({
+            "uid": { "type": "Photo", "id": id },
+            "attrs": {
+                "owner": { "__entity": { "type": "User", "id": "alice" } },
+                "private": private
             },
-            {
-                "uid": { "type": "C", "id": "c"} ,
-                "attrs": {},
-                "parents": []
-            }
-        ]))
-        .into_diagnostic()?,
-    )
-    .into_diagnostic()
+            "parents": [{ "type": "Album", "id": "vacation" }]
+        })
+    };
+    serde_json::json!([
+        user("alice", &[]),
+        user("bob", &[]),
+        user("carol", &["family"]),
+        { "uid": { "type": "Group", "id": "family" }, "attrs": {}, "parents": [] },
+        { "uid": { "type": "Album", "id": "vacation" }, "attrs": {}, "parents": [] },
+        photo("beach", true),
+        photo("sunset", false)
+    ])
+}
+
+/// The test suite of a new project, in the format of the Cedar integration
+/// tests
+fn generate_tests() -> serde_json::Value {
+    let request = |description: &str,
+                   principal: &str,
+                   action: &str,
+                   resource: &str,
+                   decision: &str,
+                   reason: &[&str]| {
+        serde_json::json!({
+            "description": description,
+            "principal": { "type": "User", "id": principal },
+            "action": { "type": "Action", "id": action },
+            "resource": { "type": "Photo", "id": resource },
+            "context": {},
+            "decision": decision,
+            "reason": reason,
+            "errors": []
+        })
+    };
+    serde_json::json!({
+        "policies": "policies.cedar",
+        "entities": "entities.json",
+        "schema": "schema.cedarschema",
+        "shouldValidate": true,
+        "requests": [
+            request("anyone can view a public photo", "bob", "view", "sunset", "allow", &["policy0"]),
+            request("only the owner can view a private photo", "bob", "view", "beach", "deny", &[]),
+            request("the owner can edit a photo", "alice", "edit", "beach", "allow", &["policy1"]),
+            request("others cannot edit a photo", "bob", "edit", "sunset", "deny", &[])
+        ]
+    })
+}
+
+/// The configuration of a new project, recording the role of each file
+fn generate_config() -> serde_json::Value {
+    serde_json::json!({
+        "schema": "schema.cedarschema",
+        "schemaFormat": "cedar",
+        "policies": "policies.cedar",
+        "templateLinks": "links.json",
+        "entities": "entities.json",
+        "tests": "tests.json"
+    })
 }
 
 fn new_inner(args: &NewArgs) -> Result<()> {
     let dir = &std::env::current_dir().into_diagnostic()?.join(&args.name);
     std::fs::create_dir(dir).into_diagnostic()?;
-    let schema_path = dir.join("schema.cedarschema.json");
-    let policy_path = dir.join("policy.cedar");
-    let entities_path = dir.join("entities.json");
-    generate_schema(&schema_path)?;
-    generate_policy(&policy_path)?;
-    generate_entities(&entities_path)
+    let files = [
+        ("schema.cedarschema", NEW_PROJECT_SCHEMA.to_string()),
+        ("policies.cedar", NEW_PROJECT_POLICIES.to_string()),
+        ("links.json", to_pretty_json(&generate_links())?),
+        ("entities.json", to_pretty_json(&generate_entities())?),
+        ("tests.json", to_pretty_json(&generate_tests())?),
+        ("cedar.json", to_pretty_json(&generate_config())?),
+        ("README.md", NEW_PROJECT_README.to_string()),
+    ];
+    for (name, contents) in files {
+        std::fs::write(dir.join(name), contents)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write {name}"))?;
+    }
+    Ok(())
+}
+
+fn to_pretty_json(value: &serde_json::Value) -> Result<String> {
+    let mut json = serde_json::to_string_pretty(value).into_diagnostic()?;
+    json.push('\n');
+    Ok(json)
 }
 
 pub fn new(args: &NewArgs) -> CedarExitCode {
+    new_with_format(args, OutputFormat::Human)
+}
+
+pub fn new_with_format(args: &NewArgs, format: OutputFormat) -> CedarExitCode {
     if let Err(err) = new_inner(args) {
-        println!("{err:?}");
+        match format {
+            OutputFormat::Human => println!("{err:?}"),
+            OutputFormat::Json => print_json_errors([&err]),
+        }
         CedarExitCode::Failure
     } else {
-        CedarExitCode::Success
+        print_success(format, || {}, serde_json::json!({}))
     }
 }
 
 pub fn language_version() -> CedarExitCode {
+    language_version_with_format(OutputFormat::Human)
+}
+
+pub fn language_version_with_format(format: OutputFormat) -> CedarExitCode {
     let version = get_lang_version();
-    println!(
-        "Cedar language version: {}.{}",
-        version.major, version.minor
-    );
-    CedarExitCode::Success
+    print_success(
+        format,
+        || {
+            println!(
+                "Cedar language version: {}.{}",
+                version.major, version.minor
+            );
+        },
+        serde_json::json!({
+            "languageVersion": format!("{}.{}", version.major, version.minor),
+        }),
+    )
 }
 
 fn create_slot_env(data: &HashMap<SlotId, String>) -> Result<HashMap<SlotId, EntityUid>> {
@@ -1114,7 +1602,8 @@ fn create_slot_env(data: &HashMap<SlotId, String>) -> Result<HashMap<SlotId, Ent
         .collect::<Result<HashMap<SlotId, EntityUid>>>()
 }
 
-fn link_inner(args: &LinkArgs) -> Result<()> {
+/// Link the template, returning the linked policy
+fn link_inner(args: &LinkArgs) -> Result<Policy> {
     let mut policies = args.policies.get_policy_set()?;
     let slotenv = create_slot_env(&args.arguments.data)?;
     policies.link(
@@ -1124,8 +1613,8 @@ fn link_inner(args: &LinkArgs) -> Result<()> {
     )?;
     let linked = policies
         .policy(&PolicyId::new(&args.new_id))
-        .ok_or_else(|| miette!("Failed to find newly-added template-linked policy"))?;
-    println!("Template-linked policy added: {linked}");
+        .ok_or_else(|| miette!("Failed to find newly-added template-linked policy"))?
+        .clone();
 
     // If a `--template-linked` / `-k` option was provided, update that file with the new link
     if let Some(links_filename) = args.policies.template_linked_file.as_ref() {
@@ -1139,7 +1628,7 @@ fn link_inner(args: &LinkArgs) -> Result<()> {
         )?;
     }
 
-    Ok(())
+    Ok(linked)
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -1256,16 +1745,25 @@ fn write_template_linked_file(linked: &[TemplateLinked], path: impl AsRef<Path>)
 }
 
 pub fn authorize(args: &AuthorizeArgs) -> CedarExitCode {
-    println!();
+    authorize_with_format(args, OutputFormat::Human)
+}
+
+pub fn authorize_with_format(args: &AuthorizeArgs, format: OutputFormat) -> CedarExitCode {
     let ans = execute_request(
         &args.request,
         &args.policies,
         &args.entities_file,
         &args.schema,
-        args.timing,
     );
-    match ans {
-        Ok(ans) => {
+    match (ans, format) {
+        (Ok((ans, auth_dur)), OutputFormat::Human) => {
+            println!();
+            if args.timing {
+                println!(
+                    "Authorization Time (micro seconds) : {}",
+                    auth_dur.as_micros()
+                );
+            }
             let status = match ans.decision() {
                 Decision::Allow => {
                     println!("ALLOW");
@@ -1296,17 +1794,54 @@ pub fn authorize(args: &AuthorizeArgs) -> CedarExitCode {
             }
             status
         }
-        Err(errs) => {
+        (Ok((ans, auth_dur)), OutputFormat::Json) => {
+            let mut json = serde_json::json!({
+                "success": true,
+                "decision": ans.decision(),
+                "reasons": ans.diagnostics().reason().map(ToString::to_string).collect::<Vec<_>>(),
+                "errors": ans
+                    .diagnostics()
+                    .errors()
+                    .map(|err| {
+                        let AuthorizationError::PolicyEvaluationError(e) = err;
+                        policy_diagnostic_json(err, e.policy_id())
+                    })
+                    .collect::<Vec<_>>(),
+            });
+            if let (true, Some(object)) = (args.timing, json.as_object_mut()) {
+                object.insert(
+                    "durationMicros".into(),
+                    u64::try_from(auth_dur.as_micros())
+                        .unwrap_or(u64::MAX)
+                        .into(),
+                );
+            }
+            print_json(&json);
+            match ans.decision() {
+                Decision::Allow => CedarExitCode::Success,
+                Decision::Deny => CedarExitCode::AuthorizeDeny,
+            }
+        }
+        (Err(errs), OutputFormat::Human) => {
+            println!();
             for err in errs {
                 println!("{err:?}");
             }
             CedarExitCode::Failure
         }
+        (Err(errs), OutputFormat::Json) => {
+            print_json_errors(&errs);
+            CedarExitCode::Failure
+        }
     }
 }
 
+pub fn partial_authorize(args: &PartiallyAuthorizeArgs) -> CedarExitCode {
+    partial_authorize_with_format(args, OutputFormat::Human)
+}
+
 #[cfg(not(feature = "partial-eval"))]
-pub fn partial_authorize(_: &PartiallyAuthorizeArgs) -> CedarExitCode {
+pub fn partial_authorize_with_format(_: &PartiallyAuthorizeArgs, _: OutputFormat) -> CedarExitCode {
     {
         eprintln!("Error: option `partially-authorize` is experimental, but this executable was not built with `partial-eval` experimental feature enabled");
         return CedarExitCode::Failure;
@@ -1314,39 +1849,71 @@ pub fn partial_authorize(_: &PartiallyAuthorizeArgs) -> CedarExitCode {
 }
 
 #[cfg(feature = "partial-eval")]
-pub fn partial_authorize(args: &PartiallyAuthorizeArgs) -> CedarExitCode {
-    println!();
-    let ans = execute_partial_request(
-        &args.request,
-        &args.policies,
-        &args.entities_file,
-        args.timing,
-    );
-    match ans {
-        Ok(ans) => match ans.decision() {
-            Some(Decision::Allow) => {
-                println!("ALLOW");
-                CedarExitCode::Success
-            }
-            Some(Decision::Deny) => {
-                println!("DENY");
-                CedarExitCode::AuthorizeDeny
+pub fn partial_authorize_with_format(
+    args: &PartiallyAuthorizeArgs,
+    format: OutputFormat,
+) -> CedarExitCode {
+    let ans = execute_partial_request(&args.request, &args.policies, &args.entities_file);
+    match (ans, format) {
+        (Ok((ans, auth_dur)), OutputFormat::Human) => {
+            println!();
+            if args.timing {
+                println!(
+                    "Authorization Time (micro seconds) : {}",
+                    auth_dur.as_micros()
+                );
             }
-            None => {
-                println!("UNKNOWN");
-                println!("All policy residuals:");
-                for p in ans.nontrivial_residuals() {
-                    println!("{p}");
+            match ans.decision() {
+                Some(Decision::Allow) => {
+                    println!("ALLOW");
+                    CedarExitCode::Success
+                }
+                Some(Decision::Deny) => {
+                    println!("DENY");
+                    CedarExitCode::AuthorizeDeny
+                }
+                None => {
+                    println!("UNKNOWN");
+                    println!("All policy residuals:");
+                    for p in ans.nontrivial_residuals() {
+                        println!("{p}");
+                    }
+                    CedarExitCode::Unknown
                 }
-                CedarExitCode::Unknown
             }
-        },
-        Err(errs) => {
+        }
+        (Ok((ans, auth_dur)), OutputFormat::Json) => {
+            let mut json = serde_json::json!({
+                "success": true,
+                "decision": ans.decision(),
+                "residuals": ans.nontrivial_residuals().map(|p| p.to_string()).collect::<Vec<_>>(),
+            });
+            if let (true, Some(object)) = (args.timing, json.as_object_mut()) {
+                object.insert(
+                    "durationMicros".into(),
+                    u64::try_from(auth_dur.as_micros())
+                        .unwrap_or(u64::MAX)
+                        .into(),
+                );
+            }
+            print_json(&json);
+            match ans.decision() {
+                Some(Decision::Allow) => CedarExitCode::Success,
+                Some(Decision::Deny) => CedarExitCode::AuthorizeDeny,
+                None => CedarExitCode::Unknown,
+            }
+        }
+        (Err(errs), OutputFormat::Human) => {
+            println!();
             for err in errs {
                 println!("{err:?}");
             }
             CedarExitCode::Failure
         }
+        (Err(errs), OutputFormat::Json) => {
+            print_json_errors(&errs);
+            CedarExitCode::Failure
+        }
     }
 }
 
@@ -1356,12 +1923,23 @@ fn load_entities(entities_filename: impl AsRef<Path>, schema: Option<&Schema>) -
         .read(true)
         .open(entities_filename.as_ref())
     {
-        Ok(f) => Entities::from_json_file(f, schema).wrap_err_with(|| {
-            format!(
-                "failed to parse entities from file {}",
-                entities_filename.as_ref().display()
-            )
-        }),
+        Ok(f) => {
+            let is_yaml = entities_filename
+                .as_ref()
+                .extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml");
+            let entities = if is_yaml {
+                Entities::from_yaml_file(f, schema).map_err(miette::Report::new)
+            } else {
+                Entities::from_json_file(f, schema).map_err(miette::Report::new)
+            };
+            entities.wrap_err_with(|| {
+                format!(
+                    "failed to parse entities from file {}",
+                    entities_filename.as_ref().display()
+                )
+            })
+        }
         Err(e) => Err(e).into_diagnostic().wrap_err_with(|| {
             format!(
                 "failed to open entities file {}",
@@ -1512,8 +2090,7 @@ fn execute_request(
     policies: &PoliciesArgs,
     entities_filename: impl AsRef<Path>,
     schema: &OptionalSchemaArgs,
-    compute_duration: bool,
-) -> Result<Response, Vec<Report>> {
+) -> Result<(Response, Duration), Vec<Report>> {
     let mut errs = vec![];
     let policies = match policies.get_policy_set() {
         Ok(pset) => pset,
@@ -1541,14 +2118,7 @@ fn execute_request(
             let authorizer = Authorizer::new();
             let auth_start = Instant::now();
             let ans = authorizer.is_authorized(&request, &policies, &entities);
-            let auth_dur = auth_start.elapsed();
-            if compute_duration {
-                println!(
-                    "Authorization Time (micro seconds) : {}",
-                    auth_dur.as_micros()
-                );
-            }
-            Ok(ans)
+            Ok((ans, auth_start.elapsed()))
         }
         Ok(_) => Err(errs),
         Err(e) => {
@@ -1563,8 +2133,7 @@ fn execute_partial_request(
     request: &PartialRequestArgs,
     policies: &PoliciesArgs,
     entities_filename: impl AsRef<Path>,
-    compute_duration: bool,
-) -> Result<PartialResponse, Vec<Report>> {
+) -> Result<(PartialResponse, Duration), Vec<Report>> {
     let mut errs = vec![];
     let policies = match policies.get_policy_set() {
         Ok(pset) => pset,
@@ -1585,14 +2154,7 @@ fn execute_partial_request(
             let authorizer = Authorizer::new();
             let auth_start = Instant::now();
             let ans = authorizer.is_authorized_partial(&request, &policies, &entities);
-            let auth_dur = auth_start.elapsed();
-            if compute_duration {
-                println!(
-                    "Authorization Time (micro seconds) : {}",
-                    auth_dur.as_micros()
-                );
-            }
-            Ok(ans)
+            Ok((ans, auth_start.elapsed()))
         }
         Ok(_) => Err(errs),
         Err(e) => {
@@ -20,9 +20,10 @@ use clap::Parser;
 use miette::ErrorHook;
 
 use cedar_policy_cli::{
-    authorize, check_parse, evaluate, format_policies, language_version, link, new,
-    partial_authorize, translate_policy, translate_schema, validate, visualize, CedarExitCode, Cli,
-    Commands, ErrorFormat,
+    authorize_with_format, check_parse_with_format, evaluate_with_format,
+    format_policies_with_format, language_version_with_format, link_with_format, new_with_format,
+    partial_authorize_with_format, translate_policy_with_format, translate_schema_with_format,
+    validate_with_format, visualize_with_format, CedarExitCode, Cli, Commands, ErrorFormat,
 };
 
 #[cfg(feature = "protobufs")]
@@ -44,23 +45,24 @@ fn main() -> CedarExitCode {
         miette::set_hook(err_hook).expect("failed to install error-reporting hook");
     }
 
+    let fmt = cli.output_format;
     match cli.command {
-        Commands::Authorize(args) => authorize(&args),
-        Commands::Evaluate(args) => evaluate(&args).0,
-        Commands::CheckParse(args) => check_parse(&args),
-        Commands::Validate(args) => validate(&args),
-        Commands::Format(args) => format_policies(&args),
-        Commands::Link(args) => link(&args),
-        Commands::TranslatePolicy(args) => translate_policy(&args),
-        Commands::Visualize(args) => visualize(&args),
-        Commands::TranslateSchema(args) => translate_schema(&args),
-        Commands::New(args) => new(&args),
-        Commands::PartiallyAuthorize(args) => partial_authorize(&args),
+        Commands::Authorize(args) => authorize_with_format(&args, fmt),
+        Commands::Evaluate(args) => evaluate_with_format(&args, fmt).0,
+        Commands::CheckParse(args) => check_parse_with_format(&args, fmt),
+        Commands::Validate(args) => validate_with_format(&args, fmt),
+        Commands::Format(args) => format_policies_with_format(&args, fmt),
+        Commands::Link(args) => link_with_format(&args, fmt),
+        Commands::TranslatePolicy(args) => translate_policy_with_format(&args, fmt),
+        Commands::Visualize(args) => visualize_with_format(&args, fmt),
+        Commands::TranslateSchema(args) => translate_schema_with_format(&args, fmt),
+        Commands::New(args) => new_with_format(&args, fmt),
+        Commands::PartiallyAuthorize(args) => partial_authorize_with_format(&args, fmt),
         #[cfg(feature = "protobufs")]
         Commands::WriteDRTProto(acmd) => write_drt_proto(acmd),
         #[cfg(feature = "protobufs")]
         Commands::WriteDRTProtoFromJSON(acmd) => write_drt_proto_from_json(acmd),
-        Commands::LanguageVersion => language_version(),
+        Commands::LanguageVersion => language_version_with_format(fmt),
     }
 }
 
@@ -581,6 +581,10 @@ fn test_validate_samples(
         },
         deny_warnings: false,
         validation_mode: cedar_policy_cli::ValidationMode::Strict,
+        compare_modes: false,
+        profile: false,
+        expiration_warning_days: 30,
+        allow_expired: false,
     };
     let output = validate(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd);
@@ -598,6 +602,10 @@ fn test_validate_samples(
         },
         deny_warnings: false,
         validation_mode: cedar_policy_cli::ValidationMode::Strict,
+        compare_modes: false,
+        profile: false,
+        expiration_warning_days: 30,
+        allow_expired: false,
     };
     let output = validate(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd)
@@ -1165,3 +1173,390 @@ fn test_translate_schema() {
         .assert()
         .code(0);
 }
+
+#[test]
+fn test_validate_expiration() {
+    const POLICY_SOURCE: &str = "sample-data/tiny_sandboxes/expiration/policy.cedar";
+    const SCHEMA_SOURCE: &str = "sample-data/tiny_sandboxes/expiration/schema.cedarschema";
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("validate")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("-s")
+        .arg(SCHEMA_SOURCE)
+        .assert()
+        .code(3)
+        .stdout(predicates::str::contains(
+            "policy `policy0` expired on 2000-01-01",
+        ));
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("validate")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("-s")
+        .arg(SCHEMA_SOURCE)
+        .arg("--allow-expired")
+        .assert()
+        .code(0)
+        .stdout(predicates::str::contains(
+            "policy `policy0` expired on 2000-01-01",
+        ));
+}
+
+#[test]
+fn test_validate_compare_modes() {
+    const POLICY_SOURCE: &str = "sample-data/tiny_sandboxes/compare-modes/policy.cedar";
+    const SCHEMA_SOURCE: &str = "sample-data/tiny_sandboxes/compare-modes/schema.cedarschema";
+
+    let assert = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("validate")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("-s")
+        .arg(SCHEMA_SOURCE)
+        .arg("--compare-modes")
+        .assert();
+    #[cfg(feature = "permissive-validate")]
+    assert
+        .code(3)
+        .stdout(predicates::str::contains(
+            "policy `policy0` passes permissive validation but fails strict validation",
+        ))
+        .stdout(predicates::str::contains("error only in strict validation"))
+        .stdout(predicates::str::contains("policy `policy1`").not());
+    #[cfg(not(feature = "permissive-validate"))]
+    assert.code(1).stderr(predicates::str::contains(
+        "this executable was not built with `permissive-validate` experimental feature enabled",
+    ));
+}
+
+#[test]
+fn test_validate_profile() {
+    const POLICY_SOURCE: &str = "sample-data/tiny_sandboxes/compare-modes/policy.cedar";
+    const SCHEMA_SOURCE: &str = "sample-data/tiny_sandboxes/compare-modes/schema.cedarschema";
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("validate")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("-s")
+        .arg(SCHEMA_SOURCE)
+        .arg("--profile")
+        .assert()
+        .code(3)
+        .stdout(predicates::str::is_match(
+            "policy `policy0`: validated in .*, typechecked [0-9]+ expressions in [0-9]+ request environments",
+        )
+        .unwrap())
+        .stdout(predicates::str::contains("policy `policy1`: validated in"))
+        .stdout(predicates::str::contains("policy set validation failed"));
+}
+
+#[test]
+fn test_format_qualify_names() {
+    const POLICY_SOURCE: &str = "sample-data/tiny_sandboxes/qualify-names/unqualified.cedar";
+    const POLICY_QUALIFIED: &str = "sample-data/tiny_sandboxes/qualify-names/qualified.cedar";
+    const SCHEMA_SOURCE: &str = "sample-data/tiny_sandboxes/qualify-names/schema.cedarschema";
+
+    let qualified = std::fs::read_to_string(POLICY_QUALIFIED).unwrap();
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("-s")
+        .arg(SCHEMA_SOURCE)
+        .arg("--qualify-names")
+        .assert()
+        .success()
+        .stdout(qualified);
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("-s")
+        .arg(SCHEMA_SOURCE)
+        .arg("--qualify-names")
+        .arg("-c")
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_require_schema_for_qualify_names() {
+    const POLICY_SOURCE: &str = "sample-data/tiny_sandboxes/qualify-names/unqualified.cedar";
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(POLICY_SOURCE)
+        .arg("--qualify-names")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "the following required arguments were not provided:\n  --schema <FILE>",
+        ));
+}
+
+#[test]
+fn test_new_project() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .current_dir(tmp_dir.path())
+        .arg("new")
+        .arg("-n")
+        .arg("photos")
+        .assert()
+        .success();
+    let project = tmp_dir.path().join("photos");
+    let cedar_in_project = || {
+        let mut cmd = assert_cmd::Command::cargo_bin("cedar").expect("bin exists");
+        cmd.current_dir(&project);
+        cmd
+    };
+
+    cedar_in_project()
+        .args([
+            "validate",
+            "-s",
+            "schema.cedarschema",
+            "-p",
+            "policies.cedar",
+        ])
+        .args(["--template-linked", "links.json"])
+        .assert()
+        .success();
+    cedar_in_project()
+        .args(["format", "-p", "policies.cedar", "-c"])
+        .assert()
+        .success();
+
+    // The requests of the test suite have the expected decisions
+    let tests: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(project.join("tests.json")).unwrap())
+            .unwrap();
+    let requests = tests
+        .get("requests")
+        .and_then(serde_json::Value::as_array)
+        .unwrap();
+    assert!(!requests.is_empty());
+    for request in requests {
+        let uid = |var: &str| {
+            let uid = request.get(var).unwrap();
+            format!(
+                "{}::{}",
+                uid.get("type").and_then(serde_json::Value::as_str).unwrap(),
+                uid.get("id").unwrap()
+            )
+        };
+        let decision = request.get("decision").and_then(serde_json::Value::as_str);
+        let code = if decision == Some("allow") {
+            0
+        } else {
+            assert_eq!(decision, Some("deny"));
+            2
+        };
+        cedar_in_project()
+            .args([
+                "authorize",
+                "-s",
+                "schema.cedarschema",
+                "-p",
+                "policies.cedar",
+            ])
+            .args(["--entities", "entities.json"])
+            .args([
+                "-l",
+                &uid("principal"),
+                "-a",
+                &uid("action"),
+                "-r",
+                &uid("resource"),
+            ])
+            .assert()
+            .code(code);
+    }
+
+    // The template-linked policy applies to the members of the linked group
+    cedar_in_project()
+        .args([
+            "authorize",
+            "-s",
+            "schema.cedarschema",
+            "-p",
+            "policies.cedar",
+        ])
+        .args([
+            "--template-linked",
+            "links.json",
+            "--entities",
+            "entities.json",
+        ])
+        .args(["-l", r#"User::"carol""#, "-a", r#"Action::"view""#])
+        .args(["-r", r#"Photo::"beach""#])
+        .assert()
+        .code(0);
+}
+
+/// Run `cedar` with `args` and `--format json`, check its exit code, and
+/// parse its output
+fn run_json(args: &[&str], stdin: &str, code: i32) -> serde_json::Value {
+    let output = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .args(args)
+        .arg("--format")
+        .arg("json")
+        .write_stdin(stdin)
+        .assert()
+        .code(code)
+        .get_output()
+        .stdout
+        .clone();
+    serde_json::from_slice(&output).expect("output should be JSON")
+}
+
+#[test]
+fn test_json_output_validate() {
+    let json = run_json(
+        &[
+            "validate",
+            "-p",
+            "sample-data/tiny_sandboxes/compare-modes/policy.cedar",
+            "-s",
+            "sample-data/tiny_sandboxes/compare-modes/schema.cedarschema",
+        ],
+        "",
+        3,
+    );
+    assert_eq!(json.get("success"), Some(&serde_json::Value::Bool(false)));
+    let errors = json
+        .get("errors")
+        .and_then(serde_json::Value::as_array)
+        .expect("errors should be an array");
+    assert_eq!(errors.len(), 1);
+    let error = errors.first().expect("one error");
+    assert_eq!(
+        error.get("policyId"),
+        Some(&serde_json::Value::from("policy0"))
+    );
+    assert!(error
+        .get("message")
+        .and_then(serde_json::Value::as_str)
+        .is_some());
+    assert!(error
+        .get("labels")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|labels| !labels.is_empty()));
+}
+
+#[test]
+fn test_json_output_check_parse() {
+    let json = run_json(&["check-parse"], "permit(principal, action, resource);", 0);
+    assert_eq!(json, serde_json::json!({ "success": true }));
+
+    let json = run_json(
+        &["check-parse"],
+        "permit(principal, action, resource) when { 1 + };",
+        1,
+    );
+    assert_eq!(json.get("success"), Some(&serde_json::Value::Bool(false)));
+    assert!(json
+        .get("errors")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|errors| !errors.is_empty()));
+}
+
+#[test]
+fn test_json_output_authorize() {
+    let json = run_json(
+        &[
+            "authorize",
+            "--policies",
+            "sample-data/tiny_sandboxes/sample1/policy.cedar",
+            "--entities",
+            "sample-data/tiny_sandboxes/sample1/entity.json",
+            "--request-json",
+            "sample-data/tiny_sandboxes/sample1/request.json",
+        ],
+        "",
+        0,
+    );
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "success": true,
+            "decision": "allow",
+            "reasons": ["policy0"],
+            "errors": [],
+        })
+    );
+}
+
+#[test]
+fn test_yaml_schema_and_entities() {
+    let json = run_json(
+        &[
+            "authorize",
+            "--policies",
+            "sample-data/tiny_sandboxes/yaml/policy.cedar",
+            "--schema",
+            "sample-data/tiny_sandboxes/yaml/schema.yaml",
+            "--schema-format",
+            "yaml",
+            "--entities",
+            "sample-data/tiny_sandboxes/yaml/entities.yaml",
+            "--request-json",
+            "sample-data/tiny_sandboxes/yaml/request.json",
+        ],
+        "",
+        0,
+    );
+    assert_eq!(json.get("decision"), Some(&serde_json::json!("allow")));
+}
+
+#[test]
+fn test_json_output_format_check() {
+    let json = run_json(
+        &[
+            "format",
+            "-p",
+            "sample-data/tiny_sandboxes/format/unformatted.cedar",
+            "-c",
+        ],
+        "",
+        1,
+    );
+    assert_eq!(json.get("success"), Some(&serde_json::Value::Bool(false)));
+    assert_eq!(json.get("changed"), Some(&serde_json::Value::Bool(true)));
+
+    let json = run_json(
+        &[
+            "format",
+            "-p",
+            "sample-data/tiny_sandboxes/format/formatted.cedar",
+            "-c",
+        ],
+        "",
+        0,
+    );
+    assert_eq!(json.get("success"), Some(&serde_json::Value::Bool(true)));
+    assert_eq!(json.get("changed"), Some(&serde_json::Value::Bool(false)));
+}
+
+#[test]
+fn test_json_output_language_version() {
+    let json = run_json(&["language-version"], "", 0);
+    assert!(json
+        .get("languageVersion")
+        .and_then(serde_json::Value::as_str)
+        .is_some());
+}
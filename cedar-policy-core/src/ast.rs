@@ -46,6 +46,8 @@ mod policy;
 pub use policy::*;
 mod policy_set;
 pub use policy_set::*;
+mod provenance;
+pub use provenance::*;
 mod request;
 pub use request::*;
 mod restricted_expr;
@@ -58,3 +60,6 @@ mod expr_iterator;
 pub use expr_iterator::*;
 mod annotation;
 pub use annotation::*;
+mod metrics;
+pub use metrics::*;
+mod fold;
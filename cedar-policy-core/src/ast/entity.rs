@@ -31,7 +31,7 @@ use serde_with::{serde_as, TryFromInto};
 use smol_str::SmolStr;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use thiserror::Error;
 
 /// The entity type that Actions must have
@@ -351,6 +351,13 @@ pub struct Entity {
     /// the serialized form of `Entity`.
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     tags: BTreeMap<SmolStr, PartialValueSerializedAsExpr>,
+
+    /// Attributes whose values are computed on first access (see [`LazyAttr`]).
+    ///
+    /// An attribute name appears in at most one of `attrs` and `lazy_attrs`.
+    /// Lazy attributes are not included in the serialized form of `Entity`.
+    #[serde(skip)]
+    lazy_attrs: BTreeMap<SmolStr, LazyAttr>,
 }
 
 impl std::hash::Hash for Entity {
@@ -396,6 +403,7 @@ impl Entity {
             attrs: evaluated_attrs,
             ancestors,
             tags: evaluated_tags,
+            lazy_attrs: BTreeMap::new(),
         })
     }
 
@@ -433,6 +441,7 @@ impl Entity {
             attrs,
             ancestors,
             tags: BTreeMap::new(),
+            lazy_attrs: BTreeMap::new(),
         }
     }
 
@@ -442,8 +451,41 @@ impl Entity {
     }
 
     /// Get the value for the given attribute, or `None` if not present
+    ///
+    /// If the attribute is lazy, this computes its value (if it hasn't been
+    /// computed already). A lazy attribute whose computation failed is
+    /// reported as `None`, so use [`Entity::try_get`] wherever that failure
+    /// must not be mistaken for a missing attribute.
     pub fn get(&self, attr: &str) -> Option<&PartialValue> {
-        self.attrs.get(attr).map(|v| v.as_ref())
+        self.try_get(attr).and_then(Result::ok)
+    }
+
+    /// Get the value for the given attribute, or `None` if not present.
+    ///
+    /// Like [`Entity::get`], but surfaces the error if the attribute is lazy
+    /// and computing its value failed.
+    pub fn try_get(&self, attr: &str) -> Option<Result<&PartialValue, EvaluationError>> {
+        match self.attrs.get(attr) {
+            Some(v) => Some(Ok(v.as_ref())),
+            None => self
+                .lazy_attrs
+                .get(attr)
+                .map(|lazy| lazy.force().map_err(Clone::clone)),
+        }
+    }
+
+    /// Does this entity have the given attribute?
+    ///
+    /// Unlike [`Entity::get`], this never computes the value of a lazy attribute.
+    pub fn has_attr(&self, attr: &str) -> bool {
+        self.attrs.contains_key(attr) || self.lazy_attrs.contains_key(attr)
+    }
+
+    /// Add an attribute whose value is computed by `lazy` the first time it
+    /// is accessed. Replaces any existing attribute of the same name.
+    pub fn add_lazy_attr(&mut self, attr: SmolStr, lazy: LazyAttr) {
+        self.attrs.remove(&attr);
+        self.lazy_attrs.insert(attr, lazy);
     }
 
     /// Get the value for the given tag, or `None` if not present
@@ -463,7 +505,7 @@ impl Entity {
 
     /// Get the number of attributes on this entity
     pub fn attrs_len(&self) -> usize {
-        self.attrs.len()
+        self.attrs.len() + self.lazy_attrs.len()
     }
 
     /// Get the number of tags on this entity
@@ -473,7 +515,7 @@ impl Entity {
 
     /// Iterate over this entity's attribute names
     pub fn keys(&self) -> impl Iterator<Item = &SmolStr> {
-        self.attrs.keys()
+        self.attrs.keys().chain(self.lazy_attrs.keys())
     }
 
     /// Iterate over this entity's tag names
@@ -482,8 +524,43 @@ impl Entity {
     }
 
     /// Iterate over this entity's attributes
+    ///
+    /// This computes the value of every lazy attribute. Lazy attributes whose
+    /// computation failed are omitted, so use [`Entity::try_attrs`] wherever
+    /// every attribute is needed.
     pub fn attrs(&self) -> impl Iterator<Item = (&SmolStr, &PartialValue)> {
-        self.attrs.iter().map(|(k, v)| (k, v.as_ref()))
+        self.attrs.iter().map(|(k, v)| (k, v.as_ref())).chain(
+            self.lazy_attrs
+                .iter()
+                .filter_map(|(k, lazy)| lazy.force().ok().map(|v| (k, v))),
+        )
+    }
+
+    /// Iterate over this entity's attributes, computing the value of every
+    /// lazy attribute and reporting the lazy attributes whose computation
+    /// failed as errors
+    pub fn try_attrs(
+        &self,
+    ) -> impl Iterator<Item = Result<(&SmolStr, &PartialValue), EntityAttrEvaluationError>> {
+        self.attrs
+            .iter()
+            .map(|(k, v)| Ok((k, v.as_ref())))
+            .chain(self.lazy_attrs.iter().map(|(k, lazy)| {
+                lazy.force()
+                    .map(|v| (k, v))
+                    .map_err(|err| self.lazy_attr_error(k, err))
+            }))
+    }
+
+    /// The error for the lazy attribute `attr`, whose computation failed with
+    /// `err`
+    fn lazy_attr_error(&self, attr: &SmolStr, err: &EvaluationError) -> EntityAttrEvaluationError {
+        EntityAttrEvaluationError {
+            uid: self.uid.clone(),
+            attr_or_tag: attr.clone(),
+            was_attr: true,
+            err: err.clone(),
+        }
     }
 
     /// Iterate over this entity's tags
@@ -498,6 +575,7 @@ impl Entity {
             attrs: BTreeMap::new(),
             ancestors: HashSet::new(),
             tags: BTreeMap::new(),
+            lazy_attrs: BTreeMap::new(),
         }
     }
 
@@ -505,7 +583,14 @@ impl Entity {
     /// That is, not only do they have the same UID, but also the same
     /// attributes, attribute values, and ancestors.
     pub(crate) fn deep_eq(&self, other: &Self) -> bool {
-        self.uid == other.uid && self.attrs == other.attrs && self.ancestors == other.ancestors
+        self.uid == other.uid
+            && self.ancestors == other.ancestors
+            && if self.lazy_attrs.is_empty() && other.lazy_attrs.is_empty() {
+                self.attrs == other.attrs
+            } else {
+                self.attrs().collect::<BTreeMap<_, _>>()
+                    == other.attrs().collect::<BTreeMap<_, _>>()
+            }
     }
 
     /// Set the UID to the given value.
@@ -548,27 +633,42 @@ impl Entity {
         self.ancestors.insert(uid);
     }
 
+    /// Replace the ancestors of this `Entity`. The transitive closure is not
+    /// recomputed; callers are responsible for that.
+    pub fn set_ancestors(&mut self, ancestors: HashSet<EntityUID>) {
+        self.ancestors = ancestors;
+    }
+
     /// Consume the entity and return the entity's owned Uid, attributes, parents, and tags.
-    pub fn into_inner(
-        self,
-    ) -> (
-        EntityUID,
-        HashMap<SmolStr, PartialValue>,
-        HashSet<EntityUID>,
-        HashMap<SmolStr, PartialValue>,
-    ) {
+    ///
+    /// This computes the value of every lazy attribute, and fails if the
+    /// computation of any of them fails.
+    pub fn into_inner(self) -> Result<EntityParts, EntityAttrEvaluationError> {
+        let lazy_attrs = self
+            .lazy_attrs
+            .iter()
+            .map(|(k, lazy)| match lazy.force() {
+                Ok(v) => Ok((k.clone(), v.clone())),
+                Err(err) => Err(self.lazy_attr_error(k, err)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         let Self {
             uid,
             attrs,
             ancestors,
             tags,
+            ..
         } = self;
-        (
+        Ok((
             uid,
-            attrs.into_iter().map(|(k, v)| (k, v.0)).collect(),
+            attrs
+                .into_iter()
+                .map(|(k, v)| (k, v.0))
+                .chain(lazy_attrs)
+                .collect(),
             ancestors,
             tags.into_iter().map(|(k, v)| (k, v.0)).collect(),
-        )
+        ))
     }
 
     /// Write the entity to a json document
@@ -789,6 +889,63 @@ impl std::fmt::Display for PartialValueSerializedAsExpr {
     }
 }
 
+/// The computation backing a [`LazyAttr`]
+type LazyAttrFn = dyn Fn() -> Result<PartialValue, EvaluationError> + Send + Sync;
+
+/// An entity attribute whose value is computed by a callback the first time it
+/// is accessed, and then cached.
+///
+/// Cloning a `LazyAttr` is cheap, and clones share the cached value, so the
+/// callback runs at most once no matter how many copies of the owning
+/// `Entity` exist.
+#[derive(Clone)]
+pub struct LazyAttr {
+    /// Callback computing the value
+    compute: Arc<LazyAttrFn>,
+    /// Cached result of `compute`, once it has been called
+    value: Arc<OnceLock<Result<PartialValue, EvaluationError>>>,
+}
+
+impl LazyAttr {
+    /// Create a new `LazyAttr` whose value is computed by `compute`
+    pub fn new(
+        compute: impl Fn() -> Result<PartialValue, EvaluationError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            compute: Arc::new(compute),
+            value: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Get the value of this attribute, running the callback if this is the
+    /// first access
+    pub fn force(&self) -> Result<&PartialValue, &EvaluationError> {
+        self.value.get_or_init(|| (self.compute)()).as_ref()
+    }
+
+    /// Has the callback already been run?
+    pub fn is_computed(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl std::fmt::Debug for LazyAttr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value.get() {
+            Some(v) => f.debug_tuple("LazyAttr").field(v).finish(),
+            None => f.write_str("LazyAttr(<not yet computed>)"),
+        }
+    }
+}
+
+/// The owned Uid, attributes, parents, and tags of an [`Entity`]
+type EntityParts = (
+    EntityUID,
+    HashMap<SmolStr, PartialValue>,
+    HashSet<EntityUID>,
+    HashMap<SmolStr, PartialValue>,
+);
+
 /// Error type for evaluation errors when evaluating an entity attribute or tag.
 /// Contains some extra contextual information and the underlying
 /// `EvaluationError`.
@@ -132,7 +132,8 @@ pub enum ExprKind<T = ()> {
     Like {
         /// Expression to test. Must evaluate to String type
         expr: Arc<Expr<T>>,
-        /// Pattern to match on; can include the wildcard *, which matches any string.
+        /// Pattern to match on; can include the wildcard *, which matches any string,
+        /// character classes like `\[a-z]`, and repetitions like `\[0-9]\{1,4}`.
         /// To match a literal `*` in the test expression, users can use `\*`.
         /// Be careful the backslash in `\*` must not be another escape sequence. For instance, `\\*` matches a backslash plus an arbitrary string.
         pattern: Pattern,
@@ -269,6 +270,31 @@ impl<T> Expr<T> {
         expr_iterator::ExprIterator::new(self)
     }
 
+    /// The immediate sub-expressions of this expression
+    pub fn children(&self) -> Vec<&Self> {
+        match &self.expr_kind {
+            ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+                vec![]
+            }
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => vec![test_expr, then_expr, else_expr],
+            ExprKind::And { left, right } | ExprKind::Or { left, right } => vec![left, right],
+            ExprKind::UnaryApp { arg, .. } => vec![arg],
+            ExprKind::BinaryApp { arg1, arg2, .. } => vec![arg1, arg2],
+            ExprKind::GetAttr { expr, .. }
+            | ExprKind::HasAttr { expr, .. }
+            | ExprKind::Like { expr, .. }
+            | ExprKind::Is { expr, .. } => vec![expr],
+            ExprKind::ExtensionFunctionApp { args, .. } | ExprKind::Set(args) => {
+                args.iter().collect()
+            }
+            ExprKind::Record(attrs) => attrs.values().collect(),
+        }
+    }
+
     /// Iterate over all of the slots in this policy AST
     pub fn slots(&self) -> impl Iterator<Item = Slot> + '_ {
         self.subexpressions()
@@ -340,7 +366,7 @@ impl<T> Expr<T> {
                 ..
             } => Some(Type::Bool),
             ExprKind::BinaryApp {
-                op: BinaryOp::Add | BinaryOp::Mul | BinaryOp::Sub,
+                op: BinaryOp::Add | BinaryOp::Mul | BinaryOp::Sub | BinaryOp::Div | BinaryOp::Rem,
                 ..
             } => Some(Type::Long),
             ExprKind::BinaryApp {
@@ -358,6 +384,10 @@ impl<T> Expr<T> {
                 op: BinaryOp::HasTag,
                 ..
             } => Some(Type::Bool),
+            ExprKind::BinaryApp {
+                op: BinaryOp::Union | BinaryOp::Intersection,
+                ..
+            } => Some(Type::Set),
             ExprKind::ExtensionFunctionApp { fn_name, .. } => extensions
                 .func(fn_name)
                 .ok()?
@@ -481,6 +511,16 @@ impl Expr {
         ExprBuilder::new().mul(e1, e2)
     }
 
+    /// Create a 'div' expression. Arguments must evaluate to Long type
+    pub fn div(e1: Expr, e2: Expr) -> Self {
+        ExprBuilder::new().div(e1, e2)
+    }
+
+    /// Create a 'rem' expression. Arguments must evaluate to Long type
+    pub fn rem(e1: Expr, e2: Expr) -> Self {
+        ExprBuilder::new().rem(e1, e2)
+    }
+
     /// Create a 'neg' expression. `e` must evaluate to Long type.
     pub fn neg(e: Expr) -> Self {
         ExprBuilder::new().neg(e)
@@ -526,6 +566,16 @@ impl Expr {
         ExprBuilder::new().has_tag(expr, tag)
     }
 
+    /// Create a `union` expression. Arguments must evaluate to Set type
+    pub fn union(e1: Expr, e2: Expr) -> Self {
+        ExprBuilder::new().union(e1, e2)
+    }
+
+    /// Create an `intersection` expression. Arguments must evaluate to Set type
+    pub fn intersection(e1: Expr, e2: Expr) -> Self {
+        ExprBuilder::new().intersection(e1, e2)
+    }
+
     /// Create an `Expr` which evaluates to a Set of the given `Expr`s
     pub fn set(exprs: impl IntoIterator<Item = Expr>) -> Self {
         ExprBuilder::new().set(exprs)
@@ -1346,6 +1396,24 @@ impl<T> ExprBuilder<T> {
         })
     }
 
+    /// Create a 'div' expression. Arguments must evaluate to Long type
+    pub fn div(self, e1: Expr<T>, e2: Expr<T>) -> Expr<T> {
+        self.with_expr_kind(ExprKind::BinaryApp {
+            op: BinaryOp::Div,
+            arg1: Arc::new(e1),
+            arg2: Arc::new(e2),
+        })
+    }
+
+    /// Create a 'rem' expression. Arguments must evaluate to Long type
+    pub fn rem(self, e1: Expr<T>, e2: Expr<T>) -> Expr<T> {
+        self.with_expr_kind(ExprKind::BinaryApp {
+            op: BinaryOp::Rem,
+            arg1: Arc::new(e1),
+            arg2: Arc::new(e2),
+        })
+    }
+
     /// Create a 'neg' expression. `e` must evaluate to Long type.
     pub fn neg(self, e: Expr<T>) -> Expr<T> {
         self.with_expr_kind(ExprKind::UnaryApp {
@@ -1421,6 +1489,24 @@ impl<T> ExprBuilder<T> {
         })
     }
 
+    /// Create a 'union' expression. Arguments must evaluate to Set type
+    pub fn union(self, e1: Expr<T>, e2: Expr<T>) -> Expr<T> {
+        self.with_expr_kind(ExprKind::BinaryApp {
+            op: BinaryOp::Union,
+            arg1: Arc::new(e1),
+            arg2: Arc::new(e2),
+        })
+    }
+
+    /// Create an 'intersection' expression. Arguments must evaluate to Set type
+    pub fn intersection(self, e1: Expr<T>, e2: Expr<T>) -> Expr<T> {
+        self.with_expr_kind(ExprKind::BinaryApp {
+            op: BinaryOp::Intersection,
+            arg1: Arc::new(e1),
+            arg2: Arc::new(e2),
+        })
+    }
+
     /// Create an `Expr` which evaluates to a Set of the given `Expr`s
     pub fn set(self, exprs: impl IntoIterator<Item = Expr<T>>) -> Expr<T> {
         self.with_expr_kind(ExprKind::Set(Arc::new(exprs.into_iter().collect())))
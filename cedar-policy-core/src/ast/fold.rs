@@ -0,0 +1,309 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Constant folding for policy conditions
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{
+    BinaryOp, EntityUIDEntry, Expr, ExprKind, Literal, PolicySet, Request, Template, Value,
+    ValueKind,
+};
+use crate::entities::Entities;
+use crate::evaluator::Evaluator;
+use crate::extensions::Extensions;
+
+impl Expr {
+    /// Get a copy of this expression with every sub-expression which can be
+    /// evaluated without a request or entities replaced by its value, and
+    /// with `&&`, `||`, and `if` expressions whose first operand is a
+    /// constant boolean replaced by the branch that would be evaluated.
+    ///
+    /// The folded expression evaluates to the same value, or the same kind of
+    /// error, as the original for every request. Sub-expressions whose
+    /// evaluation fails are left as they are, so errors are still reported
+    /// when (and if) the policy is evaluated, and extension values are kept
+    /// as constructor calls.
+    pub fn fold_constants(&self) -> Expr {
+        let entities = Entities::new();
+        let unknown = || EntityUIDEntry::Unknown { loc: None };
+        let request = Request::new_unchecked(unknown(), unknown(), unknown(), None);
+        let eval = Evaluator::new(request, &entities, Extensions::all_available());
+        fold(&eval, self)
+    }
+}
+
+impl Template {
+    /// Get a copy of this template with the constants in its conditions
+    /// folded (see [`Expr::fold_constants`])
+    pub fn fold_constants(&self) -> Self {
+        Self::new_shared(
+            self.id().clone(),
+            self.loc().cloned(),
+            self.annotations_arc().clone(),
+            self.effect(),
+            self.principal_constraint().clone(),
+            self.action_constraint().clone(),
+            self.resource_constraint().clone(),
+            Arc::new(self.non_scope_constraints().fold_constants()),
+        )
+    }
+}
+
+impl PolicySet {
+    /// Get a copy of this policy set with the constants in the conditions of
+    /// every static policy and template folded (see [`Expr::fold_constants`]).
+    /// Template-linked policies are relinked to the folded templates.
+    pub fn fold_constants(&self) -> Self {
+        let templates = self
+            .all_templates()
+            .map(|t| (t.id().clone(), Arc::new(t.fold_constants())))
+            .collect::<HashMap<_, _>>();
+        let mut folded = PolicySet::new();
+        // PANIC SAFETY: every policy's template is in `templates`, and the ids
+        // of policies and templates are unique because they are in `self`
+        #[allow(clippy::expect_used)]
+        for p in self.policies() {
+            let template = templates
+                .get(p.template().id())
+                .expect("template of a policy in the set must be in the set");
+            folded
+                .add(p.with_template(Arc::clone(template)))
+                .expect("policies in the set must have unique ids");
+        }
+        // PANIC SAFETY: template ids are unique because they are in `self`
+        #[allow(clippy::expect_used)]
+        for (id, template) in templates {
+            if folded.get_template(&id).is_none() {
+                folded
+                    .add_template(Arc::unwrap_or_clone(template))
+                    .expect("templates in the set must have unique ids");
+            }
+        }
+        folded
+    }
+}
+
+/// Fold `e` bottom-up
+fn fold(eval: &Evaluator<'_>, e: &Expr) -> Expr {
+    let loc = e.source_loc().cloned();
+    let rebuilt = match e.expr_kind() {
+        ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+            return e.clone()
+        }
+        ExprKind::If {
+            test_expr,
+            then_expr,
+            else_expr,
+        } => {
+            let test_expr = fold(eval, test_expr);
+            match test_expr.expr_kind() {
+                ExprKind::Lit(Literal::Bool(true)) => return fold(eval, then_expr),
+                ExprKind::Lit(Literal::Bool(false)) => return fold(eval, else_expr),
+                _ => Expr::ite(test_expr, fold(eval, then_expr), fold(eval, else_expr)),
+            }
+        }
+        ExprKind::And { left, right } => {
+            let left = fold(eval, left);
+            match left.expr_kind() {
+                // `right` is never evaluated
+                ExprKind::Lit(Literal::Bool(false)) => return left,
+                _ => Expr::and(left, fold(eval, right)),
+            }
+        }
+        ExprKind::Or { left, right } => {
+            let left = fold(eval, left);
+            match left.expr_kind() {
+                // `right` is never evaluated
+                ExprKind::Lit(Literal::Bool(true)) => return left,
+                _ => Expr::or(left, fold(eval, right)),
+            }
+        }
+        ExprKind::UnaryApp { op, arg } => Expr::unary_app(*op, fold(eval, arg)),
+        ExprKind::BinaryApp { op, arg1, arg2 } => {
+            Expr::binary_app(*op, fold(eval, arg1), fold(eval, arg2))
+        }
+        ExprKind::ExtensionFunctionApp { fn_name, args } => Expr::call_extension_fn(
+            fn_name.clone(),
+            args.iter().map(|arg| fold(eval, arg)).collect(),
+        ),
+        ExprKind::GetAttr { expr, attr } => Expr::get_attr(fold(eval, expr), attr.clone()),
+        ExprKind::HasAttr { expr, attr } => Expr::has_attr(fold(eval, expr), attr.clone()),
+        ExprKind::Like { expr, pattern } => Expr::like(fold(eval, expr), pattern.clone()),
+        ExprKind::Is { expr, entity_type } => {
+            Expr::is_entity_type(fold(eval, expr), entity_type.clone())
+        }
+        ExprKind::Set(elems) => Expr::set(elems.iter().map(|elem| fold(eval, elem))),
+        ExprKind::Record(attrs) => Expr::record_arc(Arc::new(
+            attrs
+                .iter()
+                .map(|(k, v)| (k.clone(), fold(eval, v)))
+                .collect(),
+        )),
+    }
+    .with_maybe_source_loc(loc);
+
+    if !is_foldable(&rebuilt) {
+        return rebuilt;
+    }
+    match eval.interpret(&rebuilt, &HashMap::new()) {
+        Ok(v) if !contains_extension_value(&v) => {
+            Expr::from(v).with_maybe_source_loc(rebuilt.source_loc().cloned())
+        }
+        _ => rebuilt,
+    }
+}
+
+/// Can `e` be evaluated without a request or entities? Its operands must be
+/// constants, and the operator must not read the entity store.
+fn is_foldable(e: &Expr) -> bool {
+    let reads_entities = match e.expr_kind() {
+        // literals, sets, and records are already as folded as they can be
+        ExprKind::Lit(_)
+        | ExprKind::Var(_)
+        | ExprKind::Slot(_)
+        | ExprKind::Unknown(_)
+        | ExprKind::Set(_)
+        | ExprKind::Record(_) => return false,
+        ExprKind::BinaryApp { op, .. } => {
+            matches!(op, BinaryOp::In | BinaryOp::GetTag | BinaryOp::HasTag)
+        }
+        ExprKind::GetAttr { expr, .. } | ExprKind::HasAttr { expr, .. } => {
+            !matches!(expr.expr_kind(), ExprKind::Record(_))
+        }
+        _ => false,
+    };
+    !reads_entities && e.children().into_iter().all(is_constant)
+}
+
+/// Is `e` a literal, or a set, record, or extension function call whose
+/// operands are constants?
+fn is_constant(e: &Expr) -> bool {
+    match e.expr_kind() {
+        ExprKind::Lit(_) => true,
+        ExprKind::Set(_) | ExprKind::Record(_) | ExprKind::ExtensionFunctionApp { .. } => {
+            e.children().into_iter().all(is_constant)
+        }
+        _ => false,
+    }
+}
+
+fn contains_extension_value(v: &Value) -> bool {
+    match &v.value {
+        ValueKind::Lit(_) => false,
+        ValueKind::Set(set) => set.iter().any(contains_extension_value),
+        ValueKind::Record(attrs) => attrs.values().any(contains_extension_value),
+        ValueKind::ExtensionValue(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{PolicyID, SlotId};
+    use crate::parser::parse_policyset;
+
+    #[track_caller]
+    fn assert_folds_to(src: &str, expected: &str) {
+        let e: Expr = src.parse().unwrap();
+        let expected: Expr = expected.parse().unwrap();
+        let folded = e.fold_constants();
+        assert!(
+            folded.eq_shape(&expected),
+            "`{src}` folded to `{folded}`, expected `{expected}`"
+        );
+    }
+
+    #[test]
+    fn folds_constant_subexpressions() {
+        assert_folds_to("1 + 2 * 3", "7");
+        assert_folds_to("principal.age > 10 + 8", "principal.age > 18");
+        assert_folds_to(r#""abc" like "a*" && context.flag"#, "true && context.flag");
+        assert_folds_to(r#"{a: 1, b: [1, 2]}.b.contains(2)"#, "true");
+        assert_folds_to(r#"{a: 1} has b || context.flag"#, "false || context.flag");
+        assert_folds_to(r#"User::"alice" is User"#, "true");
+        assert_folds_to(
+            r#"decimal("1.5").lessThan(decimal("2.5")) && context.flag"#,
+            "true && context.flag",
+        );
+        assert_folds_to(
+            r#"if 1 < 2 then principal.name else "none""#,
+            "principal.name",
+        );
+    }
+
+    #[test]
+    fn prunes_statically_decided_operands() {
+        assert_folds_to("1 > 2 && principal.age > 18", "false");
+        assert_folds_to("1 < 2 || principal.age > 18", "true");
+        assert_folds_to(
+            "if 1 > 2 then principal.name else resource.name",
+            "resource.name",
+        );
+        // `context.flag` must still be evaluated, and could fail
+        assert_folds_to("context.flag && 1 > 2", "context.flag && false");
+    }
+
+    #[test]
+    fn keeps_unfoldable_expressions() {
+        // errors are left to be reported during evaluation
+        assert_folds_to(
+            "9223372036854775807 + 1 == principal.age",
+            "9223372036854775807 + 1 == principal.age",
+        );
+        assert_folds_to(r#"1 && context.flag"#, r#"1 && context.flag"#);
+        // these read the entity store
+        assert_folds_to(
+            r#"User::"alice" in Group::"admins""#,
+            r#"User::"alice" in Group::"admins""#,
+        );
+        assert_folds_to(r#"User::"alice".age > 1"#, r#"User::"alice".age > 1"#);
+        assert_folds_to(r#"User::"alice" has age"#, r#"User::"alice" has age"#);
+        // extension values stay as constructor calls
+        assert_folds_to(r#"[decimal("1.5")]"#, r#"[decimal("1.5")]"#);
+        assert_folds_to(r#"ip("10.0.0.1")"#, r#"ip("10.0.0.1")"#);
+    }
+
+    #[test]
+    fn policy_set() {
+        let mut pset = parse_policyset(
+            r#"
+            permit(principal, action, resource) when { 1 > 2 && principal.age > 18 };
+            permit(principal == ?principal, action, resource) when { 60 * 60 > principal.age };
+            "#,
+        )
+        .unwrap();
+        pset.link(
+            PolicyID::from_string("policy1"),
+            PolicyID::from_string("link"),
+            HashMap::from([(SlotId::principal(), r#"User::"alice""#.parse().unwrap())]),
+        )
+        .unwrap();
+        let folded = pset.fold_constants();
+        assert_eq!(folded.policies().count(), 2);
+        assert_eq!(folded.all_templates().count(), 2);
+        let static_policy = folded.get(&PolicyID::from_string("policy0")).unwrap();
+        assert!(static_policy
+            .non_scope_constraints()
+            .eq_shape(&Expr::val(false)));
+        let link = folded.get(&PolicyID::from_string("link")).unwrap();
+        assert!(link
+            .non_scope_constraints()
+            .eq_shape(&"3600 > principal.age".parse().unwrap()));
+        assert_eq!(link.env(), pset.get(link.id()).unwrap().env());
+    }
+}
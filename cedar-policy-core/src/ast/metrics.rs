@@ -0,0 +1,236 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Complexity metrics for policies and policy sets
+
+use super::{Expr, ExprKind, PolicySet, Template};
+use serde::{Deserialize, Serialize};
+
+/// Complexity metrics for the conditions (`when` and `unless` clauses) of a
+/// single policy or template. The policy scope is not included.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyMetrics {
+    depth: usize,
+    node_count: usize,
+    attribute_dereferences: usize,
+    set_literal_sizes: Vec<usize>,
+}
+
+impl PolicyMetrics {
+    /// Compute the metrics of the conditions of `t`
+    pub fn new(t: &Template) -> Self {
+        let mut metrics = Self::default();
+        metrics.depth = metrics.visit(t.non_scope_constraints());
+        metrics
+    }
+
+    /// Record every node of `e`, returning the depth of `e`, i.e., the number
+    /// of nodes on the longest path from the root to a leaf. Nodes are
+    /// visited in source order with an explicit stack, so deeply nested
+    /// expressions can't overflow the call stack.
+    fn visit<T>(&mut self, e: &Expr<T>) -> usize {
+        let mut depth = 0;
+        let mut stack = vec![(e, 1)];
+        while let Some((e, d)) = stack.pop() {
+            depth = depth.max(d);
+            self.node_count += 1;
+            // Children are pushed in reverse so that they are popped in order
+            let mut push = |child| stack.push((child, d + 1));
+            match e.expr_kind() {
+                ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {}
+                ExprKind::If {
+                    test_expr,
+                    then_expr,
+                    else_expr,
+                } => {
+                    push(else_expr);
+                    push(then_expr);
+                    push(test_expr);
+                }
+                ExprKind::And { left, right } | ExprKind::Or { left, right } => {
+                    push(right);
+                    push(left);
+                }
+                ExprKind::BinaryApp { arg1, arg2, .. } => {
+                    push(arg2);
+                    push(arg1);
+                }
+                ExprKind::GetAttr { expr, .. } | ExprKind::HasAttr { expr, .. } => {
+                    self.attribute_dereferences += 1;
+                    push(expr);
+                }
+                ExprKind::UnaryApp { arg: expr, .. }
+                | ExprKind::Like { expr, .. }
+                | ExprKind::Is { expr, .. } => push(expr),
+                ExprKind::ExtensionFunctionApp { args, .. } => args.iter().rev().for_each(push),
+                ExprKind::Set(elems) => {
+                    self.set_literal_sizes.push(elems.len());
+                    elems.iter().rev().for_each(push);
+                }
+                ExprKind::Record(attrs) => attrs.values().rev().for_each(push),
+            }
+        }
+        depth
+    }
+
+    /// Number of nodes on the longest path from the root of the condition
+    /// expression to a leaf
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Total number of nodes in the condition expression
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Number of attribute accesses (`e.attr`) and attribute tests
+    /// (`e has attr`)
+    pub fn attribute_dereferences(&self) -> usize {
+        self.attribute_dereferences
+    }
+
+    /// Number of elements in each set literal, in the order they appear
+    pub fn set_literal_sizes(&self) -> &[usize] {
+        &self.set_literal_sizes
+    }
+
+    /// Number of elements in the largest set literal, or 0 if there are none
+    pub fn largest_set_literal(&self) -> usize {
+        self.set_literal_sizes.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Aggregate complexity metrics for the static policies and templates of a
+/// policy set. Template-linked policies are not counted separately, since they
+/// share the conditions of their template.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicySetMetrics {
+    policy_count: usize,
+    max_depth: usize,
+    total_node_count: usize,
+    max_node_count: usize,
+    total_attribute_dereferences: usize,
+    largest_set_literal: usize,
+}
+
+impl PolicySetMetrics {
+    /// Compute the aggregate metrics of `pset`
+    pub fn new(pset: &PolicySet) -> Self {
+        pset.all_templates()
+            .map(PolicyMetrics::new)
+            .fold(Self::default(), |mut acc, m| {
+                acc.policy_count += 1;
+                acc.max_depth = acc.max_depth.max(m.depth);
+                acc.total_node_count += m.node_count;
+                acc.max_node_count = acc.max_node_count.max(m.node_count);
+                acc.total_attribute_dereferences += m.attribute_dereferences;
+                acc.largest_set_literal = acc.largest_set_literal.max(m.largest_set_literal());
+                acc
+            })
+    }
+
+    /// Number of static policies and templates
+    pub fn policy_count(&self) -> usize {
+        self.policy_count
+    }
+
+    /// Greatest condition depth of any policy
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Total number of condition nodes across all policies
+    pub fn total_node_count(&self) -> usize {
+        self.total_node_count
+    }
+
+    /// Greatest number of condition nodes in any one policy
+    pub fn max_node_count(&self) -> usize {
+        self.max_node_count
+    }
+
+    /// Total number of attribute dereferences across all policies
+    pub fn total_attribute_dereferences(&self) -> usize {
+        self.total_attribute_dereferences
+    }
+
+    /// Number of elements in the largest set literal of any policy
+    pub fn largest_set_literal(&self) -> usize {
+        self.largest_set_literal
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{parse_policy, parse_policyset};
+    use std::sync::Arc;
+
+    #[test]
+    fn policy_metrics() {
+        let p = parse_policy(
+            None,
+            r#"permit(principal, action, resource) when { principal.level > 3 && resource.tags.contains("a") } unless { [1, 2, 3].contains(context.n) };"#,
+        )
+        .unwrap();
+        let m = PolicyMetrics::new(&Arc::<Template>::from(p));
+        assert_eq!(m.attribute_dereferences(), 3);
+        assert_eq!(m.set_literal_sizes(), &[3]);
+        assert_eq!(m.largest_set_literal(), 3);
+        // `when && !unless`, where `>` is represented as `!(<=)`, so the
+        // longest path is `&&`, `&&`, `!`, `<=`, `.level`, `principal`
+        assert_eq!(m.node_count(), 19);
+        assert_eq!(m.depth(), 6);
+    }
+
+    #[test]
+    fn policy_set_metrics() {
+        let pset = parse_policyset(
+            r#"
+            permit(principal, action, resource);
+            forbid(principal, action, resource) when { principal.a.b.c };
+            permit(principal == ?principal, action, resource) when { [1, 2] == [] };
+            "#,
+        )
+        .unwrap();
+        let m = PolicySetMetrics::new(&pset);
+        assert_eq!(m.policy_count(), 3);
+        assert_eq!(m.total_attribute_dereferences(), 3);
+        assert_eq!(m.largest_set_literal(), 2);
+        assert_eq!(m.max_depth(), 4);
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let e = (0..10_000).fold(Expr::val(true), |e, _| Expr::not(e));
+        let mut m = PolicyMetrics::default();
+        assert_eq!(m.visit(&e), 10_001);
+        assert_eq!(m.node_count(), 10_001);
+        // Dropping an expression this deep is itself recursive
+        std::mem::forget(e);
+
+        let e = Expr::set([
+            Expr::set([Expr::val(1)]),
+            Expr::set([Expr::val(1), Expr::val(2)]),
+        ]);
+        let mut m = PolicyMetrics::default();
+        assert_eq!(m.visit(&e), 3);
+        assert_eq!(m.set_literal_sizes(), &[2, 1, 2]);
+    }
+}
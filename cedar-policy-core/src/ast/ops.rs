@@ -104,6 +104,17 @@ pub enum BinaryOp {
     /// Arguments must have Long type
     Mul,
 
+    /// Integer division, rounding toward zero. Dividing by zero is an error.
+    ///
+    /// Arguments must have Long type
+    Div,
+
+    /// Integer remainder, which has the sign of the first argument. Taking
+    /// the remainder of dividing by zero is an error.
+    ///
+    /// Arguments must have Long type
+    Rem,
+
     /// Hierarchy membership. Specifically, is the first arg a member of the
     /// second.
     ///
@@ -137,6 +148,17 @@ pub enum BinaryOp {
     ///
     /// First argument must have Entity type, second argument must have String type.
     HasTag,
+
+    /// Set union. The result contains the elements of both sets.
+    ///
+    /// Arguments must have Set type
+    Union,
+
+    /// Set intersection. The result contains the elements the sets have in
+    /// common.
+    ///
+    /// Arguments must have Set type
+    Intersection,
 }
 
 impl std::fmt::Display for BinaryOp {
@@ -148,12 +170,16 @@ impl std::fmt::Display for BinaryOp {
             BinaryOp::Add => write!(f, "+"),
             BinaryOp::Sub => write!(f, "-"),
             BinaryOp::Mul => write!(f, "*"),
+            BinaryOp::Div => write!(f, "/"),
+            BinaryOp::Rem => write!(f, "%"),
             BinaryOp::In => write!(f, "in"),
             BinaryOp::Contains => write!(f, "contains"),
             BinaryOp::ContainsAll => write!(f, "containsAll"),
             BinaryOp::ContainsAny => write!(f, "containsAny"),
             BinaryOp::GetTag => write!(f, "getTag"),
             BinaryOp::HasTag => write!(f, "hasTag"),
+            BinaryOp::Union => write!(f, "union"),
+            BinaryOp::Intersection => write!(f, "intersection"),
         }
     }
 }
@@ -168,12 +194,16 @@ impl From<&proto::expr::binary_app::Op> for BinaryOp {
             proto::expr::binary_app::Op::Add => BinaryOp::Add,
             proto::expr::binary_app::Op::Sub => BinaryOp::Sub,
             proto::expr::binary_app::Op::Mul => BinaryOp::Mul,
+            proto::expr::binary_app::Op::Div => BinaryOp::Div,
+            proto::expr::binary_app::Op::Rem => BinaryOp::Rem,
             proto::expr::binary_app::Op::In => BinaryOp::In,
             proto::expr::binary_app::Op::Contains => BinaryOp::Contains,
             proto::expr::binary_app::Op::ContainsAll => BinaryOp::ContainsAll,
             proto::expr::binary_app::Op::ContainsAny => BinaryOp::ContainsAny,
             proto::expr::binary_app::Op::GetTag => BinaryOp::GetTag,
             proto::expr::binary_app::Op::HasTag => BinaryOp::HasTag,
+            proto::expr::binary_app::Op::Union => BinaryOp::Union,
+            proto::expr::binary_app::Op::Intersection => BinaryOp::Intersection,
         }
     }
 }
@@ -188,12 +218,16 @@ impl From<&BinaryOp> for proto::expr::binary_app::Op {
             BinaryOp::Add => proto::expr::binary_app::Op::Add,
             BinaryOp::Sub => proto::expr::binary_app::Op::Sub,
             BinaryOp::Mul => proto::expr::binary_app::Op::Mul,
+            BinaryOp::Div => proto::expr::binary_app::Op::Div,
+            BinaryOp::Rem => proto::expr::binary_app::Op::Rem,
             BinaryOp::In => proto::expr::binary_app::Op::In,
             BinaryOp::Contains => proto::expr::binary_app::Op::Contains,
             BinaryOp::ContainsAll => proto::expr::binary_app::Op::ContainsAll,
             BinaryOp::ContainsAny => proto::expr::binary_app::Op::ContainsAny,
             BinaryOp::GetTag => proto::expr::binary_app::Op::GetTag,
             BinaryOp::HasTag => proto::expr::binary_app::Op::HasTag,
+            BinaryOp::Union => proto::expr::binary_app::Op::Union,
+            BinaryOp::Intersection => proto::expr::binary_app::Op::Intersection,
         }
     }
 }
@@ -19,16 +19,113 @@ use std::sync::Arc;
 #[cfg(feature = "protobufs")]
 use crate::ast::proto;
 
+use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The largest repetition bound allowed in a pattern, e.g., `\[0-9]\{1,1000}`
+pub const MAX_PATTERN_REPETITION: u32 = 1000;
 
 /// Represent an element in a pattern literal (the RHS of the like operation)
-#[derive(Deserialize, Serialize, Hash, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Hash, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum PatternElem {
     /// A character literal
     Char(char),
     /// The wildcard `*`
     Wildcard,
+    /// A character class, e.g., `\[a-z_]`, which matches exactly one character
+    Class(CharClass),
+    /// Between `min` and `max` (inclusive) consecutive matches of `pattern`,
+    /// e.g., `\[0-9]\{3}` or `a\{1,2}`.
+    /// The text syntax only allows repeating a single character or a
+    /// character class; see [`Pattern::validate`].
+    Repeat {
+        /// The repeated pattern
+        pattern: Pattern,
+        /// Minimum number of repetitions
+        min: u32,
+        /// Maximum number of repetitions
+        max: u32,
+    },
+}
+
+/// A character class in a pattern literal, e.g., `\[a-z_]` or `\[^0-9]`
+#[derive(Deserialize, Serialize, Hash, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CharClass {
+    /// If `true`, the class matches any character *not* in `ranges`
+    negated: bool,
+    /// Inclusive character ranges; a single character `c` is the range `(c, c)`
+    ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    /// Create a character class from inclusive character ranges
+    pub fn new(negated: bool, ranges: impl IntoIterator<Item = (char, char)>) -> Self {
+        Self {
+            negated,
+            ranges: ranges.into_iter().collect(),
+        }
+    }
+
+    /// Does this class match the characters *not* in its ranges?
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    /// Inclusive character ranges of this class
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    /// Does this class match `c`?
+    pub fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != self.negated
+    }
+}
+
+/// Errors for pattern literals which are syntactically or semantically invalid
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Diagnostic, Error)]
+pub enum PatternError {
+    /// A `\[` without a matching `]`
+    #[error("unterminated character class in pattern")]
+    #[diagnostic(help("close the class with `]`, or match a literal `[` without the `\\`"))]
+    UnterminatedClass,
+    /// A character class with no characters, e.g., `\[]`
+    #[error("empty character class in pattern")]
+    EmptyClass,
+    /// A character range whose start comes after its end, e.g., `\[z-a]`
+    #[error("invalid character range `{lo}-{hi}` in pattern")]
+    #[diagnostic(help("the first character of a range must not come after the last"))]
+    InvalidRange {
+        /// Start of the range
+        lo: char,
+        /// End of the range
+        hi: char,
+    },
+    /// A repetition which is not of the form `\{n}` or `\{min,max}`
+    #[error("malformed repetition in pattern")]
+    #[diagnostic(help(
+        "a repetition must have the form `\\{{n}}` or `\\{{min,max}}`; match a literal `{{` without the `\\`"
+    ))]
+    MalformedRepetition,
+    /// A repetition of something other than a single character or class
+    #[error("a repetition in a pattern must follow a single character or a character class")]
+    InvalidRepetitionTarget,
+    /// A repetition whose minimum is greater than its maximum
+    #[error(
+        "invalid repetition `\\{{{min},{max}}}` in pattern: the minimum is greater than the maximum"
+    )]
+    InvalidRepetitionBounds {
+        /// Minimum number of repetitions
+        min: u32,
+        /// Maximum number of repetitions
+        max: u32,
+    },
+    /// A repetition bound above [`MAX_PATTERN_REPETITION`]
+    #[error("repetition bound {0} in pattern exceeds the limit of {MAX_PATTERN_REPETITION}")]
+    RepetitionTooLarge(u32),
 }
 
 #[cfg(feature = "protobufs")]
@@ -52,6 +149,24 @@ impl From<&proto::expr::like::PatternElem> for PatternElem {
                     proto::expr::like::pattern_elem::Ty::Wildcard => PatternElem::Wildcard,
                 }
             }
+
+            proto::expr::like::pattern_elem::Data::Class(class) => {
+                PatternElem::Class(CharClass::new(
+                    class.negated,
+                    class.ranges.iter().map(|range| {
+                        (
+                            range.lo.chars().next().expect("lo is non-empty"),
+                            range.hi.chars().next().expect("hi is non-empty"),
+                        )
+                    }),
+                ))
+            }
+
+            proto::expr::like::pattern_elem::Data::Repeat(repeat) => PatternElem::Repeat {
+                pattern: repeat.pattern.iter().map(PatternElem::from).collect(),
+                min: repeat.min,
+                max: repeat.max,
+            },
         }
     }
 }
@@ -68,6 +183,30 @@ impl From<&PatternElem> for proto::expr::like::PatternElem {
                     proto::expr::like::pattern_elem::Ty::Wildcard.into(),
                 )),
             },
+            PatternElem::Class(class) => Self {
+                data: Some(proto::expr::like::pattern_elem::Data::Class(
+                    proto::expr::like::pattern_elem::CharClass {
+                        negated: class.negated,
+                        ranges: class
+                            .ranges
+                            .iter()
+                            .map(|(lo, hi)| proto::expr::like::pattern_elem::CharRange {
+                                lo: lo.to_string(),
+                                hi: hi.to_string(),
+                            })
+                            .collect(),
+                    },
+                )),
+            },
+            PatternElem::Repeat { pattern, min, max } => Self {
+                data: Some(proto::expr::like::pattern_elem::Data::Repeat(
+                    proto::expr::like::pattern_elem::Repeat {
+                        pattern: pattern.iter().map(Self::from).collect(),
+                        min: *min,
+                        max: *max,
+                    },
+                )),
+            },
         }
     }
 }
@@ -75,6 +214,7 @@ impl From<&PatternElem> for proto::expr::like::PatternElem {
 /// Represent a pattern literal (the RHS of the like operator)
 /// Also provides an implementation of the Display trait as well as a wildcard matching method.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[serde(transparent)]
 pub struct Pattern {
     /// A vector of pattern elements
@@ -101,6 +241,13 @@ impl Pattern {
     pub fn len(&self) -> usize {
         self.elems.len()
     }
+
+    /// Check that every character class and repetition in the pattern is
+    /// well-formed. Patterns produced by the parser are always valid, but
+    /// patterns constructed programmatically or from JSON may not be.
+    pub fn validate(&self) -> Result<(), PatternError> {
+        self.iter().try_for_each(PatternElem::validate)
+    }
 }
 
 impl From<Arc<Vec<PatternElem>>> for Pattern {
@@ -121,29 +268,150 @@ impl FromIterator<PatternElem> for Pattern {
     }
 }
 
+/// Characters which must be escaped to be matched literally in a character class
+fn is_class_special(c: char) -> bool {
+    matches!(c, ']' | '-' | '^')
+}
+
 impl std::fmt::Display for Pattern {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for pc in self.elems.as_ref() {
-            match pc {
-                PatternElem::Char('*') => write!(f, r#"\*"#)?,
-                PatternElem::Char(c) => write!(f, "{}", c.escape_debug())?,
-                PatternElem::Wildcard => write!(f, r#"*"#)?,
-            }
+            write!(f, "{pc}")?;
         }
         Ok(())
     }
 }
 
+impl std::fmt::Display for PatternElem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternElem::Char('*') => write!(f, r#"\*"#),
+            PatternElem::Char(c) => write!(f, "{}", c.escape_debug()),
+            PatternElem::Wildcard => write!(f, "*"),
+            PatternElem::Class(class) => write!(f, "{class}"),
+            PatternElem::Repeat { pattern, min, max } if min == max => {
+                write!(f, "{pattern}\\{{{min}}}")
+            }
+            PatternElem::Repeat { pattern, min, max } => write!(f, "{pattern}\\{{{min},{max}}}"),
+        }
+    }
+}
+
+impl std::fmt::Display for CharClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_char = |f: &mut std::fmt::Formatter<'_>, c: char| {
+            if is_class_special(c) {
+                write!(f, "\\{c}")
+            } else {
+                write!(f, "{}", c.escape_debug())
+            }
+        };
+        write!(f, "\\[")?;
+        if self.negated {
+            write!(f, "^")?;
+        }
+        for (lo, hi) in &self.ranges {
+            write_char(f, *lo)?;
+            if lo != hi {
+                write!(f, "-")?;
+                write_char(f, *hi)?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
 impl PatternElem {
     fn match_char(&self, text_char: &char) -> bool {
         match self {
             PatternElem::Char(c) => text_char == c,
             PatternElem::Wildcard => true,
+            PatternElem::Class(class) => class.contains(*text_char),
+            // Repetitions are never matched one character at a time; see
+            // `Pattern::wildcard_match`
+            PatternElem::Repeat { .. } => false,
         }
     }
     fn is_wildcard(&self) -> bool {
         matches!(self, PatternElem::Wildcard)
     }
+    fn validate(&self) -> Result<(), PatternError> {
+        match self {
+            PatternElem::Char(_) | PatternElem::Wildcard => Ok(()),
+            PatternElem::Class(class) => {
+                if class.ranges.is_empty() {
+                    return Err(PatternError::EmptyClass);
+                }
+                match class.ranges.iter().find(|(lo, hi)| lo > hi) {
+                    Some((lo, hi)) => Err(PatternError::InvalidRange { lo: *lo, hi: *hi }),
+                    None => Ok(()),
+                }
+            }
+            PatternElem::Repeat { pattern, min, max } => {
+                match pattern.get_elems() {
+                    [elem @ (PatternElem::Char(_) | PatternElem::Class(_))] => elem.validate()?,
+                    _ => return Err(PatternError::InvalidRepetitionTarget),
+                }
+                if min > max {
+                    Err(PatternError::InvalidRepetitionBounds {
+                        min: *min,
+                        max: *max,
+                    })
+                } else if *max > MAX_PATTERN_REPETITION {
+                    Err(PatternError::RepetitionTooLarge(*max))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Given the set of positions in `text` at which matching `pattern` may
+/// start, compute the set of positions at which the match may end.
+/// Both sets are represented as `text.len() + 1` booleans.
+fn match_positions(pattern: &[PatternElem], text: &[char], starts: Vec<bool>) -> Vec<bool> {
+    pattern.iter().fold(starts, |reachable, elem| match elem {
+        PatternElem::Wildcard => reachable
+            .iter()
+            .scan(false, |seen, r| {
+                *seen |= *r;
+                Some(*seen)
+            })
+            .collect(),
+        PatternElem::Repeat { pattern, min, max } => {
+            let mut ends = if *min == 0 {
+                reachable.clone()
+            } else {
+                vec![false; reachable.len()]
+            };
+            let mut current = reachable;
+            // Valid patterns never repeat more often than this, but patterns
+            // constructed without `Pattern::validate` may, and may repeat
+            // something that matches the empty string, so that `current`
+            // never runs out of positions
+            for count in 1..=(*max).min(MAX_PATTERN_REPETITION) {
+                if !current.contains(&true) {
+                    break;
+                }
+                current = match_positions(pattern.get_elems(), text, current);
+                if count >= *min {
+                    for (end, r) in ends.iter_mut().zip(&current) {
+                        *end |= *r;
+                    }
+                }
+            }
+            ends
+        }
+        PatternElem::Char(_) | PatternElem::Class(_) => std::iter::once(false)
+            .chain(
+                reachable
+                    .iter()
+                    .zip(text)
+                    .map(|(r, c)| *r && elem.match_char(c)),
+            )
+            .collect(),
+    })
 }
 
 impl Pattern {
@@ -164,6 +432,23 @@ impl Pattern {
         //    created vectors will match .chars().count()
         let text: Vec<char> = text.chars().collect();
 
+        // Repetitions can match a variable number of characters, so the
+        // single-star backtracking below does not apply. Instead, track every
+        // position in the text each prefix of the pattern can match up to.
+        if pattern
+            .iter()
+            .any(|elem| matches!(elem, PatternElem::Repeat { .. }))
+        {
+            let mut starts = vec![false; text.len() + 1];
+            if let Some(start) = starts.first_mut() {
+                *start = true;
+            }
+            return match_positions(pattern, &text, starts)
+                .last()
+                .copied()
+                .unwrap_or(false);
+        }
+
         let mut i: usize = 0; // index into text
         let mut j: usize = 0; // index into pattern
         let mut star_idx: usize = 0; // index in pattern (j) of the most recent *
@@ -280,4 +565,114 @@ mod test {
         // Patterns that do not match "ḛ̶͑͝x̶͔͛a̵̰̯͛m̴͉̋́p̷̠͂l̵͇̍̔ȩ̶̣͝"
         assert!(!(string_map("y") + star()).wildcard_match("ḛ̶͑͝x̶͔͛a̵̰̯͛m̴͉̋́p̷̠͂l̵͇̍̔ȩ̶̣͝"));
     }
+
+    fn class(negated: bool, ranges: &[(char, char)]) -> Pattern {
+        Pattern::from(vec![PatternElem::Class(CharClass::new(
+            negated,
+            ranges.iter().copied(),
+        ))])
+    }
+
+    fn repeat(pattern: Pattern, min: u32, max: u32) -> Pattern {
+        Pattern::from(vec![PatternElem::Repeat { pattern, min, max }])
+    }
+
+    #[test]
+    fn test_class_match() {
+        let lower = class(false, &[('a', 'z')]);
+        assert!(lower.wildcard_match("q"));
+        assert!(!lower.wildcard_match("Q"));
+        assert!(!lower.wildcard_match("ab"));
+        assert!(!lower.wildcard_match(""));
+
+        let not_digit = class(true, &[('0', '9')]);
+        assert!(not_digit.wildcard_match("x"));
+        assert!(!not_digit.wildcard_match("7"));
+
+        let ident_start = class(false, &[('a', 'z'), ('A', 'Z'), ('_', '_')]);
+        assert!((ident_start.clone() + star()).wildcard_match("_foo"));
+        assert!((ident_start + star()).wildcard_match("Foo"));
+        assert!((star() + class(false, &[('0', '9')])).wildcard_match("abc1"));
+        assert!(!(star() + class(false, &[('0', '9')])).wildcard_match("1abc"));
+    }
+
+    #[test]
+    fn test_repeat_match() {
+        let digits = |min, max| repeat(class(false, &[('0', '9')]), min, max);
+        assert!(digits(3, 3).wildcard_match("123"));
+        assert!(!digits(3, 3).wildcard_match("12"));
+        assert!(!digits(3, 3).wildcard_match("1234"));
+        assert!(!digits(3, 3).wildcard_match("12a"));
+        assert!(digits(1, 4).wildcard_match("1"));
+        assert!(digits(1, 4).wildcard_match("1234"));
+        assert!(!digits(1, 4).wildcard_match("12345"));
+        assert!(digits(0, 2).wildcard_match(""));
+
+        // `user-\[0-9]\{1,4}*`
+        let pattern = string_map("user-") + digits(1, 4) + star();
+        assert!(pattern.wildcard_match("user-1"));
+        assert!(pattern.wildcard_match("user-12345"));
+        assert!(pattern.wildcard_match("user-42-admin"));
+        assert!(!pattern.wildcard_match("user-"));
+        assert!(!pattern.wildcard_match("user-x1"));
+
+        // `a\{2}*a`, which needs to backtrack over the wildcard
+        let pattern = repeat(string_map("a"), 2, 2) + star() + string_map("a");
+        assert!(pattern.wildcard_match("aaa"));
+        assert!(pattern.wildcard_match("aabca"));
+        assert!(!pattern.wildcard_match("aa"));
+
+        // Invalid repetitions of something matching the empty string still
+        // terminate
+        let pattern = repeat(star(), 0, u32::MAX) + string_map("b");
+        assert!(pattern.wildcard_match("aab"));
+        assert!(!pattern.wildcard_match("aaa"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!((string_map("ab") + star()).validate(), Ok(()));
+        assert_eq!(
+            repeat(class(false, &[('a', 'z')]), 1, MAX_PATTERN_REPETITION).validate(),
+            Ok(())
+        );
+        assert_eq!(class(false, &[]).validate(), Err(PatternError::EmptyClass));
+        assert_eq!(
+            class(false, &[('z', 'a')]).validate(),
+            Err(PatternError::InvalidRange { lo: 'z', hi: 'a' })
+        );
+        assert_eq!(
+            repeat(string_map("a"), 3, 1).validate(),
+            Err(PatternError::InvalidRepetitionBounds { min: 3, max: 1 })
+        );
+        assert_eq!(
+            repeat(string_map("a"), 0, MAX_PATTERN_REPETITION + 1).validate(),
+            Err(PatternError::RepetitionTooLarge(MAX_PATTERN_REPETITION + 1))
+        );
+        assert_eq!(
+            repeat(string_map("ab"), 1, 2).validate(),
+            Err(PatternError::InvalidRepetitionTarget)
+        );
+        assert_eq!(
+            repeat(star(), 1, 2).validate(),
+            Err(PatternError::InvalidRepetitionTarget)
+        );
+        assert_eq!(
+            repeat(class(false, &[('z', 'a')]), 1, 2).validate(),
+            Err(PatternError::InvalidRange { lo: 'z', hi: 'a' })
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!((string_map("a*[]{}-^") + star()).to_string(), r"a\*[]{}-^*");
+        assert_eq!(
+            class(true, &[('a', 'z'), ('-', '-'), ('^', '^'), ('[', ']')]).to_string(),
+            r"\[^a-z\-\^[-\]]"
+        );
+        assert_eq!(
+            (repeat(class(false, &[('0', '9')]), 3, 3) + repeat(string_map("x"), 0, 2)).to_string(),
+            r"\[0-9]\{3}x\{0,2}"
+        );
+    }
 }
@@ -23,7 +23,10 @@ use miette::Diagnostic;
 use nonempty::{nonempty, NonEmpty};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use thiserror::Error;
 
 #[cfg(feature = "wasm")]
@@ -33,7 +36,8 @@ extern crate tsify;
 /// Contains both the AST for template, and the list of open slots in the template.
 ///
 /// Note that this "template" may have no slots, in which case this `Template` represents a static policy
-#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Educe, Clone, Debug, Serialize, Deserialize)]
+#[educe(Hash, Eq, PartialEq)]
 #[serde(from = "TemplateBody")]
 #[serde(into = "TemplateBody")]
 pub struct Template {
@@ -43,6 +47,10 @@ pub struct Template {
     ///
     /// Note that `slots` may be empty, in which case this `Template` represents a static policy
     slots: Vec<Slot>,
+    /// Where this template was deployed from, if known. This is metadata
+    /// only, so it is ignored by equality and hashing.
+    #[educe(Hash(ignore), PartialEq(ignore))]
+    provenance: Option<Arc<Provenance>>,
 }
 
 impl From<Template> for TemplateBody {
@@ -157,9 +165,25 @@ impl Template {
         Template {
             body: self.body.new_id(id),
             slots: self.slots.clone(),
+            provenance: self.provenance.clone(),
         }
     }
 
+    /// Get a copy of this template recording that it was deployed from
+    /// `provenance`
+    #[must_use]
+    pub fn with_provenance(&self, provenance: Provenance) -> Self {
+        Template {
+            provenance: Some(Arc::new(provenance)),
+            ..self.clone()
+        }
+    }
+
+    /// Get where this template was deployed from, if known
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_deref()
+    }
+
     /// Get the location of this policy
     pub fn loc(&self) -> Option<&Loc> {
         self.body.loc()
@@ -194,6 +218,27 @@ impl Template {
         self.body.condition()
     }
 
+    /// Names of the context attributes this template reads or tests for, or
+    /// `None` if it uses `context` other than by reading or testing an
+    /// attribute (so that it may depend on any attribute)
+    pub fn context_attrs_read(&self) -> Option<HashSet<SmolStr>> {
+        let mut names = HashSet::new();
+        let (mut uses, mut attr_uses) = (0, 0);
+        for e in self.condition().subexpressions() {
+            match e.expr_kind() {
+                ExprKind::Var(Var::Context) => uses += 1,
+                ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr }
+                    if matches!(expr.expr_kind(), ExprKind::Var(Var::Context)) =>
+                {
+                    attr_uses += 1;
+                    names.insert(attr.clone());
+                }
+                _ => (),
+            }
+        }
+        (uses == attr_uses).then_some(names)
+    }
+
     /// List of open slots in this template
     pub fn slots(&self) -> impl Iterator<Item = &Slot> {
         self.slots.iter()
@@ -269,6 +314,7 @@ impl Template {
         let t = Arc::new(Self {
             body,
             slots: vec![],
+            provenance: None,
         });
         #[cfg(test)]
         {
@@ -289,7 +335,11 @@ impl From<TemplateBody> for Template {
         // INVARIANT: (slot cache correctness)
         // Pull all the slots out of the template body's condition.
         let slots = body.condition().slots().collect::<Vec<_>>();
-        Self { body, slots }
+        Self {
+            body,
+            slots,
+            provenance: None,
+        }
     }
 }
 
@@ -397,6 +447,12 @@ impl Policy {
         }
     }
 
+    /// Get a copy of this policy with its template replaced by `template`,
+    /// which must have the same id and slots as the current template
+    pub(crate) fn with_template(&self, template: Arc<Template>) -> Self {
+        Self::new(template, self.link.clone(), self.values.clone())
+    }
+
     /// Build a policy with a given effect, given when clause, and unconstrained scope variables
     pub fn from_when_clause(effect: Effect, when: Expr, id: PolicyID, loc: Option<Loc>) -> Self {
         Self::from_when_clause_annos(
@@ -537,6 +593,20 @@ impl Policy {
         self.template.loc()
     }
 
+    /// Get where this policy was deployed from, if known. Template-linked
+    /// policies share the provenance of their template.
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.template.provenance()
+    }
+
+    /// Get a copy of this static policy recording that it was deployed from
+    /// `provenance`. For a template-linked policy, this changes the
+    /// provenance of its copy of the template.
+    #[must_use]
+    pub fn with_provenance(&self, provenance: Provenance) -> Self {
+        self.with_template(Arc::new(self.template.with_provenance(provenance)))
+    }
+
     /// Returns true if this policy is an inline policy
     pub fn is_static(&self) -> bool {
         self.link.is_none()
@@ -561,6 +631,70 @@ impl std::fmt::Display for Policy {
 /// Map from Slot Ids to Entity UIDs which fill the slots
 pub type SlotEnv = HashMap<SlotId, EntityUID>;
 
+/// The template a template-linked policy was created from and the values
+/// filling its slots, for reporting alongside diagnostics for the link
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LinkBindings {
+    /// ID of the template
+    pub template_id: PolicyID,
+    /// Values of the slots, ordered by slot
+    pub values: Vec<(SlotId, EntityUID)>,
+}
+
+impl LinkBindings {
+    /// Get the bindings of `p`, or `None` if `p` is a static policy
+    pub fn of(p: &Policy) -> Option<Self> {
+        if p.is_static() {
+            None
+        } else {
+            Some(Self {
+                template_id: p.template().id().clone(),
+                values: p
+                    .env()
+                    .iter()
+                    .map(|(slot, euid)| (*slot, euid.clone()))
+                    .sorted_unstable()
+                    .collect(),
+            })
+        }
+    }
+
+    /// Get a copy of these bindings with entity ids replaced by a placeholder
+    pub fn redacted(&self) -> Self {
+        Self {
+            template_id: self.template_id.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|(slot, euid)| {
+                    (
+                        *slot,
+                        EntityUID::from_components(
+                            euid.entity_type().clone(),
+                            Eid::new(crate::evaluator::REDACTED),
+                            None,
+                        ),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for LinkBindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "linked from template `{}` with {}",
+            self.template_id,
+            self.values
+                .iter()
+                .map(|(slot, euid)| format!("{slot} = {euid}"))
+                .join(", ")
+        )
+    }
+}
+
 /// Represents either an static policy or a template linked policy
 /// This is the serializable version because it simply refers to the Template by its Id;
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -1386,6 +1520,12 @@ impl PrincipalConstraint {
             PrincipalOrResourceConstraint::In(EntityReference::Slot(_)) => Self {
                 constraint: PrincipalOrResourceConstraint::In(EntityReference::EUID(euid)),
             },
+            PrincipalOrResourceConstraint::IsIn(entity_type, EntityReference::Slot(_)) => Self {
+                constraint: PrincipalOrResourceConstraint::IsIn(
+                    entity_type,
+                    EntityReference::EUID(euid),
+                ),
+            },
             _ => self,
         }
     }
@@ -1517,6 +1657,12 @@ impl ResourceConstraint {
             PrincipalOrResourceConstraint::In(EntityReference::Slot(_)) => Self {
                 constraint: PrincipalOrResourceConstraint::In(EntityReference::EUID(euid)),
             },
+            PrincipalOrResourceConstraint::IsIn(entity_type, EntityReference::Slot(_)) => Self {
+                constraint: PrincipalOrResourceConstraint::IsIn(
+                    entity_type,
+                    EntityReference::EUID(euid),
+                ),
+            },
             _ => self,
         }
     }
@@ -1655,7 +1801,7 @@ impl Diagnostic for UnexpectedSlotError {
 
     fn source_code(&self) -> Option<&dyn miette::SourceCode> {
         match self {
-            Self::FoundSlot(Slot { loc, .. }) => loc.as_ref().map(|l| l as &dyn miette::SourceCode),
+            Self::FoundSlot(Slot { loc, .. }) => loc.as_ref().and_then(Loc::source_code),
         }
     }
 }
@@ -18,11 +18,16 @@ use super::{
     EntityUID, LinkingError, LiteralPolicy, Policy, PolicyID, ReificationError, SlotId,
     StaticPolicy, Template,
 };
+use crate::parser::Loc;
 use itertools::Itertools;
 use miette::Diagnostic;
 use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
 use std::collections::{hash_map::Entry, HashMap, HashSet};
-use std::{borrow::Borrow, sync::Arc};
+use std::{
+    borrow::Borrow,
+    sync::{Arc, OnceLock},
+};
 use thiserror::Error;
 
 #[cfg(feature = "protobufs")]
@@ -32,6 +37,10 @@ use crate::ast::proto;
 use super::TemplateBody;
 
 /// Represents a set of `Policy`s
+///
+/// The contents are stored behind `Arc`s, so cloning a `PolicySet` is cheap
+/// and clones can be shared between threads. Modifying a clone copies the
+/// underlying storage first (copy-on-write), so other clones are unaffected.
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(try_from = "LiteralPolicySet")]
 #[serde(into = "LiteralPolicySet")]
@@ -41,20 +50,39 @@ pub struct PolicySet {
     /// - A Body of a `Template`, which has slots that need to be filled in
     /// - A Body of a `StaticPolicy`, which has been converted into a `Template` that has zero slots.
     ///   The static policy's [`PolicyID`] is the same in both `templates` and `links`.
-    templates: HashMap<PolicyID, Arc<Template>>,
+    templates: Arc<HashMap<PolicyID, Arc<Template>>>,
     /// `links` contains all of the executable policies in the `PolicySet`
     /// A `StaticPolicy` must have exactly one `Policy` in `links`
     ///   (this is managed by `PolicySet::add`)
     ///   The static policy's PolicyID is the same in both `templates` and `links`
     /// A `Template` may have zero or many links
-    links: HashMap<PolicyID, Policy>,
+    links: Arc<HashMap<PolicyID, Policy>>,
 
     /// Map from a template `PolicyID` to the set of `PolicyID`s in `links` that are linked to that template.
     /// There is a key `t` iff `templates` contains the key `t`. The value of `t` will be a (possibly empty)
     /// set of every `p` in `links` s.t. `p.template().id() == t`.
-    template_to_links_map: HashMap<PolicyID, HashSet<PolicyID>>,
+    template_to_links_map: Arc<HashMap<PolicyID, HashSet<PolicyID>>>,
+
+    /// Names of the context attributes read by the templates, computed on
+    /// first use (see [`PolicySet::context_attrs_read`]) and reset whenever
+    /// `templates` changes
+    context_attrs: ContextAttrsCache,
 }
 
+/// Cached result of [`PolicySet::context_attrs_read`]. Clones share the
+/// cache, and since it is derived from the templates, it is ignored when
+/// comparing policy sets.
+#[derive(Debug, Default, Clone)]
+struct ContextAttrsCache(Arc<OnceLock<Option<HashSet<SmolStr>>>>);
+
+impl PartialEq for ContextAttrsCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ContextAttrsCache {}
+
 /// Converts a LiteralPolicySet into a PolicySet, ensuring the invariants are met
 /// Every `Policy` must point to a `Template` that exists in the set.
 impl TryFrom<LiteralPolicySet> for PolicySet {
@@ -85,9 +113,10 @@ impl TryFrom<LiteralPolicySet> for PolicySet {
         }
 
         Ok(Self {
-            templates,
-            links,
-            template_to_links_map,
+            templates: Arc::new(templates),
+            links: Arc::new(links),
+            template_to_links_map: Arc::new(template_to_links_map),
+            context_attrs: ContextAttrsCache::default(),
         })
     }
 }
@@ -103,11 +132,10 @@ impl From<PolicySet> for LiteralPolicySet {
     fn from(pset: PolicySet) -> Self {
         let templates = pset
             .templates
-            .into_iter()
-            .map(|(id, template)| (id, template.as_ref().clone()))
+            .iter()
+            .map(|(id, template)| (id.clone(), template.as_ref().clone()))
             .collect();
-        let links = pset
-            .links
+        let links = Arc::unwrap_or_clone(pset.links)
             .into_iter()
             .map(|(id, p)| (id, p.into()))
             .collect();
@@ -166,7 +194,7 @@ impl From<&PolicySet> for proto::LiteralPolicySet {
     fn from(v: &PolicySet) -> Self {
         let mut templates: HashMap<String, proto::TemplateBody> =
             HashMap::with_capacity(v.templates.len());
-        for (key, value) in &v.templates {
+        for (key, value) in v.templates.iter() {
             templates.insert(
                 String::from(key.as_ref()),
                 proto::TemplateBody::from(value.as_ref()),
@@ -175,7 +203,7 @@ impl From<&PolicySet> for proto::LiteralPolicySet {
 
         let mut links: HashMap<String, proto::LiteralPolicy> =
             HashMap::with_capacity(v.links.len());
-        for (key, value) in &v.links {
+        for (key, value) in v.links.iter() {
             links.insert(
                 String::from(key.as_ref()),
                 proto::LiteralPolicy::from(value),
@@ -203,6 +231,12 @@ pub enum PolicySetError {
     Occupied {
         /// [`PolicyID`] that was duplicate
         id: PolicyID,
+        /// Source location of the template or policy already in the set
+        /// with this id
+        existing_loc: Option<Loc>,
+        /// Source location of the template or policy that could not be
+        /// added
+        new_loc: Option<Loc>,
     },
 }
 
@@ -260,61 +294,64 @@ impl PolicySet {
     /// Create a fresh empty `PolicySet`
     pub fn new() -> Self {
         Self {
-            templates: HashMap::new(),
-            links: HashMap::new(),
-            template_to_links_map: HashMap::new(),
+            templates: Arc::new(HashMap::new()),
+            links: Arc::new(HashMap::new()),
+            template_to_links_map: Arc::new(HashMap::new()),
+            context_attrs: ContextAttrsCache::default(),
         }
     }
 
+    /// Mutable access to `templates`, copying it first if it is shared
+    fn templates_mut(&mut self) -> &mut HashMap<PolicyID, Arc<Template>> {
+        self.context_attrs = ContextAttrsCache::default();
+        Arc::make_mut(&mut self.templates)
+    }
+
     /// Add a `Policy` to the `PolicySet`.
     pub fn add(&mut self, policy: Policy) -> Result<(), PolicySetError> {
         let t = policy.template_arc();
 
         // we need to check for all possible errors before making any
-        // modifications to `self`.
-        // So we just collect the `ventry` here, and we only do the insertion
-        // once we know there will be no error
-        let template_ventry = match self.templates.entry(t.id().clone()) {
-            Entry::Vacant(ventry) => Some(ventry),
-            Entry::Occupied(oentry) => {
-                if oentry.get() != &t {
-                    return Err(PolicySetError::Occupied {
-                        id: oentry.key().clone(),
-                    });
-                }
-                None
-            }
-        };
-
-        let link_ventry = match self.links.entry(policy.id().clone()) {
-            Entry::Vacant(ventry) => Some(ventry),
-            Entry::Occupied(oentry) => {
+        // modifications to `self`, including the copy-on-write of storage
+        // shared with clones of `self` by `Arc::make_mut`.
+        let new_template = match self.templates.get(t.id()) {
+            Some(existing) if existing != &t => {
                 return Err(PolicySetError::Occupied {
-                    id: oentry.key().clone(),
+                    id: t.id().clone(),
+                    existing_loc: existing.loc().cloned(),
+                    new_loc: t.loc().cloned(),
                 });
             }
+            Some(_) => false,
+            None => true,
         };
 
+        if let Some(existing) = self.links.get(policy.id()) {
+            return Err(PolicySetError::Occupied {
+                id: policy.id().clone(),
+                existing_loc: existing.loc().cloned(),
+                new_loc: policy.loc().cloned(),
+            });
+        }
+
         // if we get here, there will be no errors.  So actually do the
         // insertions.
-        if let Some(ventry) = template_ventry {
-            self.template_to_links_map.insert(
+        if new_template {
+            Arc::make_mut(&mut self.template_to_links_map).insert(
                 t.id().clone(),
                 vec![policy.id().clone()]
                     .into_iter()
                     .collect::<HashSet<PolicyID>>(),
             );
-            ventry.insert(t);
+            self.templates_mut().insert(t.id().clone(), t);
         } else {
-            //`template_ventry` is None, so `templates` has `t` and we never use the `HashSet::new()`
-            self.template_to_links_map
+            //`templates` has `t`, so we never use the `HashSet::new()`
+            Arc::make_mut(&mut self.template_to_links_map)
                 .entry(t.id().clone())
                 .or_default()
                 .insert(policy.id().clone());
         }
-        if let Some(ventry) = link_ventry {
-            ventry.insert(policy);
-        }
+        Arc::make_mut(&mut self.links).insert(policy.id().clone(), policy);
 
         Ok(())
     }
@@ -326,77 +363,74 @@ impl PolicySet {
     ) -> Result<Policy, PolicySetPolicyRemovalError> {
         // Invariant: if `policy_id` is a key in both `self.links` and `self.templates`,
         // then self.templates[policy_id] has exactly one link: self.links[policy_id]
-        let policy = match self.links.remove(policy_id) {
-            Some(p) => p,
-            None => {
-                return Err(PolicySetPolicyRemovalError::RemovePolicyNoLinkError(
-                    policy_id.clone(),
-                ))
-            }
-        };
-        //links mapped by `PolicyId`, so `policy` is unique
-        match self.templates.remove(policy_id) {
-            Some(_) => {
-                self.template_to_links_map.remove(policy_id);
-                Ok(policy)
-            }
-            None => {
-                //If we removed the link but failed to remove the template
-                //restore the link and return an error
-                self.links.insert(policy_id.clone(), policy);
-                Err(PolicySetPolicyRemovalError::RemovePolicyNoTemplateError(
-                    policy_id.clone(),
-                ))
-            }
+        if !self.links.contains_key(policy_id) {
+            return Err(PolicySetPolicyRemovalError::RemovePolicyNoLinkError(
+                policy_id.clone(),
+            ));
         }
+        if !self.templates.contains_key(policy_id) {
+            return Err(PolicySetPolicyRemovalError::RemovePolicyNoTemplateError(
+                policy_id.clone(),
+            ));
+        }
+        // Only copy the maps on write once we know the removal will succeed
+        self.templates_mut().remove(policy_id);
+        Arc::make_mut(&mut self.template_to_links_map).remove(policy_id);
+        // PANIC SAFETY: we just checked that `policy_id` is in `self.links`
+        #[allow(clippy::expect_used)]
+        let policy = Arc::make_mut(&mut self.links)
+            .remove(policy_id)
+            .expect("policy should be in links");
+        Ok(policy)
     }
 
     /// Add a `StaticPolicy` to the `PolicySet`.
     pub fn add_static(&mut self, policy: StaticPolicy) -> Result<(), PolicySetError> {
         let (t, p) = Template::link_static_policy(policy);
 
-        match (
-            self.templates.entry(t.id().clone()),
-            self.links.entry(t.id().clone()),
-        ) {
-            (Entry::Vacant(templates_entry), Entry::Vacant(links_entry)) => {
-                self.template_to_links_map.insert(
-                    t.id().clone(),
-                    vec![p.id().clone()]
-                        .into_iter()
-                        .collect::<HashSet<PolicyID>>(),
-                );
-                templates_entry.insert(t);
-                links_entry.insert(p);
-                Ok(())
-            }
-            (Entry::Occupied(oentry), _) => Err(PolicySetError::Occupied {
-                id: oentry.key().clone(),
-            }),
-            (_, Entry::Occupied(oentry)) => Err(PolicySetError::Occupied {
-                id: oentry.key().clone(),
-            }),
+        if let Some(existing_loc) = self
+            .templates
+            .get(t.id())
+            .map(|existing| existing.loc())
+            .or_else(|| self.links.get(t.id()).map(|existing| existing.loc()))
+        {
+            return Err(PolicySetError::Occupied {
+                id: t.id().clone(),
+                existing_loc: existing_loc.cloned(),
+                new_loc: t.loc().cloned(),
+            });
         }
+
+        Arc::make_mut(&mut self.template_to_links_map).insert(
+            t.id().clone(),
+            vec![p.id().clone()]
+                .into_iter()
+                .collect::<HashSet<PolicyID>>(),
+        );
+        self.templates_mut().insert(t.id().clone(), t);
+        Arc::make_mut(&mut self.links).insert(p.id().clone(), p);
+        Ok(())
     }
 
     /// Add a template to the policy set.
     /// If a link, static policy or template with the same name already exists, this will error.
     pub fn add_template(&mut self, t: Template) -> Result<(), PolicySetError> {
-        if self.links.contains_key(t.id()) {
-            return Err(PolicySetError::Occupied { id: t.id().clone() });
+        if let Some(existing_loc) = self
+            .links
+            .get(t.id())
+            .map(|existing| existing.loc())
+            .or_else(|| self.templates.get(t.id()).map(|existing| existing.loc()))
+        {
+            return Err(PolicySetError::Occupied {
+                id: t.id().clone(),
+                existing_loc: existing_loc.cloned(),
+                new_loc: t.loc().cloned(),
+            });
         }
 
-        match self.templates.entry(t.id().clone()) {
-            Entry::Occupied(oentry) => Err(PolicySetError::Occupied {
-                id: oentry.key().clone(),
-            }),
-            Entry::Vacant(ventry) => {
-                self.template_to_links_map
-                    .insert(t.id().clone(), HashSet::new());
-                ventry.insert(Arc::new(t));
-                Ok(())
-            }
-        }
+        Arc::make_mut(&mut self.template_to_links_map).insert(t.id().clone(), HashSet::new());
+        self.templates_mut().insert(t.id().clone(), Arc::new(t));
+        Ok(())
     }
 
     /// Remove a template from the policy set.
@@ -430,9 +464,9 @@ impl PolicySet {
 
         // PANIC SAFETY: every linked policy should have a template
         #[allow(clippy::panic)]
-        match self.templates.remove(policy_id) {
+        match self.templates_mut().remove(policy_id) {
             Some(t) => {
-                self.template_to_links_map.remove(policy_id);
+                Arc::make_mut(&mut self.template_to_links_map).remove(policy_id);
                 Ok((*t).clone())
             }
             None => panic!("Found in template_to_links_map but not in templates"),
@@ -472,25 +506,16 @@ impl PolicySet {
         let r = Template::link(t, new_id.clone(), values)?;
 
         // Both maps must not contain the `new_id`
-        match (
-            self.links.entry(new_id.clone()),
-            self.templates.entry(new_id.clone()),
-        ) {
-            (Entry::Vacant(links_entry), Entry::Vacant(_)) => {
-                //We will never use the .or_default() because we just found `t` above
-                self.template_to_links_map
-                    .entry(template_id)
-                    .or_default()
-                    .insert(new_id);
-                Ok(links_entry.insert(r))
-            }
-            (Entry::Occupied(oentry), _) => Err(LinkingError::PolicyIdConflict {
-                id: oentry.key().clone(),
-            }),
-            (_, Entry::Occupied(oentry)) => Err(LinkingError::PolicyIdConflict {
-                id: oentry.key().clone(),
-            }),
+        if self.links.contains_key(&new_id) || self.templates.contains_key(&new_id) {
+            return Err(LinkingError::PolicyIdConflict { id: new_id });
         }
+
+        //We will never use the .or_default() because we just found `t` above
+        Arc::make_mut(&mut self.template_to_links_map)
+            .entry(template_id)
+            .or_default()
+            .insert(new_id.clone());
+        Ok(Arc::make_mut(&mut self.links).entry(new_id).or_insert(r))
     }
 
     /// Unlink `policy_id`
@@ -500,11 +525,16 @@ impl PolicySet {
         if self.templates.contains_key(policy_id) {
             return Err(PolicySetUnlinkError::NotLinkError(policy_id.clone()));
         }
-        match self.links.remove(policy_id) {
+        if !self.links.contains_key(policy_id) {
+            return Err(PolicySetUnlinkError::UnlinkingError(policy_id.clone()));
+        }
+        match Arc::make_mut(&mut self.links).remove(policy_id) {
             Some(p) => {
                 // PANIC SAFETY: every linked policy should have a template
                 #[allow(clippy::panic)]
-                match self.template_to_links_map.entry(p.template().id().clone()) {
+                match Arc::make_mut(&mut self.template_to_links_map)
+                    .entry(p.template().id().clone())
+                {
                     Entry::Occupied(t) => t.into_mut().remove(policy_id),
                     Entry::Vacant(_) => {
                         panic!("No template found for linked policy")
@@ -523,7 +553,7 @@ impl PolicySet {
 
     /// Consume the `PolicySet`, producing an iterator of all the policies in it
     pub fn into_policies(self) -> impl Iterator<Item = Policy> {
-        self.links.into_values()
+        Arc::unwrap_or_clone(self.links).into_values()
     }
 
     /// Iterate over everything stored as template, including static policies.
@@ -532,6 +562,25 @@ impl PolicySet {
         self.templates.values().map(|t| t.borrow())
     }
 
+    /// Names of the context attributes read or tested for by the policies in
+    /// this set, or `None` if some policy uses `context` other than by
+    /// reading or testing an attribute (see [`Template::context_attrs_read`]).
+    ///
+    /// This is computed once and cached until templates are added or removed.
+    pub fn context_attrs_read(&self) -> Option<&HashSet<SmolStr>> {
+        self.context_attrs
+            .0
+            .get_or_init(|| {
+                self.all_templates()
+                    .map(Template::context_attrs_read)
+                    .fold_options(HashSet::new(), |mut names, read| {
+                        names.extend(read);
+                        names
+                    })
+            })
+            .as_ref()
+    }
+
     /// Iterate over templates with slots
     pub fn templates(&self) -> impl Iterator<Item = &Template> {
         self.all_templates().filter(|t| t.slots().count() != 0)
@@ -648,6 +697,48 @@ mod test {
         };
     }
 
+    /// Failed insertions and removals don't copy the storage shared with clones
+    #[test]
+    fn failed_updates_keep_storage_shared() {
+        let mut pset = PolicySet::new();
+        let p = parser::parse_policy(
+            Some(PolicyID::from_string("id")),
+            "permit(principal,action,resource);",
+        )
+        .expect("Failed to parse");
+        pset.add_static(p.clone()).expect("Failed to add!");
+        let template = parser::parse_policy_or_template(
+            Some(PolicyID::from_string("t")),
+            "permit(principal == ?principal, action, resource);",
+        )
+        .expect("Failed to parse");
+        pset.add_template(template.clone()).expect("Add failed");
+
+        let mut copy = pset.clone();
+        assert!(copy.add_static(p.clone()).is_err());
+        assert!(copy.add(Policy::from(p)).is_err());
+        assert!(copy.add_template(template).is_err());
+        let env = HashMap::from([(
+            SlotId::principal(),
+            r#"Test::"test""#.parse().expect("Failed to parse"),
+        )]);
+        assert!(copy
+            .link(PolicyID::from_string("t"), PolicyID::from_string("id"), env)
+            .is_err());
+        assert!(copy.remove_static(&PolicyID::from_string("t")).is_err());
+        assert!(copy.remove_static(&PolicyID::from_string("nope")).is_err());
+        assert!(copy.remove_template(&PolicyID::from_string("id")).is_err());
+        assert!(copy.unlink(&PolicyID::from_string("id")).is_err());
+        assert!(copy.unlink(&PolicyID::from_string("nope")).is_err());
+
+        assert!(Arc::ptr_eq(&copy.templates, &pset.templates));
+        assert!(Arc::ptr_eq(&copy.links, &pset.links));
+        assert!(Arc::ptr_eq(
+            &copy.template_to_links_map,
+            &pset.template_to_links_map
+        ));
+    }
+
     /// This test focuses on `PolicySet::add()`, while other tests mostly use
     /// `PolicySet::add_static()` and `PolicySet::link()`.
     #[test]
@@ -701,7 +792,9 @@ mod test {
         .expect("Failed to link");
         match pset.add(p2) {
             Ok(_) => panic!("Should have failed due to conflict with existing link id"),
-            Err(PolicySetError::Occupied { id }) => assert_eq!(id, PolicyID::from_string("link")),
+            Err(PolicySetError::Occupied { id, .. }) => {
+                assert_eq!(id, PolicyID::from_string("link"))
+            }
         }
 
         let p3 = Template::link(Arc::clone(&template), PolicyID::from_string("link2"), env2)
@@ -732,7 +825,7 @@ mod test {
         .expect("Failed to link");
         match pset.add(p4) {
             Ok(_) => panic!("Should have failed due to conflict on template id"),
-            Err(PolicySetError::Occupied { id }) => {
+            Err(PolicySetError::Occupied { id, .. }) => {
                 assert_eq!(id, PolicyID::from_string("t"))
             }
         }
@@ -754,7 +847,7 @@ mod test {
         pset.add_static(p1).expect("Failed to add!");
         match pset.add_static(p2) {
             Ok(_) => panic!("Should have failed to due name conflict"),
-            Err(PolicySetError::Occupied { id }) => assert_eq!(id, PolicyID::from_string("id")),
+            Err(PolicySetError::Occupied { id, .. }) => assert_eq!(id, PolicyID::from_string("id")),
         }
     }
 
@@ -0,0 +1,144 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Provenance metadata identifying where a policy was deployed from
+
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+/// Where a policy or template came from: the file and line it was loaded
+/// from, the version control revision of that file, and its author. Every
+/// part is optional. Provenance is not part of the policy itself, so it does
+/// not affect evaluation or policy equality.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revision: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<SmolStr>,
+}
+
+impl Provenance {
+    /// Create an empty `Provenance`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the file the policy was loaded from
+    #[must_use]
+    pub fn with_file(self, file: impl Into<SmolStr>) -> Self {
+        Self {
+            file: Some(file.into()),
+            ..self
+        }
+    }
+
+    /// Set the (1-based) line of the file on which the policy starts
+    #[must_use]
+    pub fn with_line(self, line: u32) -> Self {
+        Self {
+            line: Some(line),
+            ..self
+        }
+    }
+
+    /// Set the version control revision of the file
+    #[must_use]
+    pub fn with_revision(self, revision: impl Into<SmolStr>) -> Self {
+        Self {
+            revision: Some(revision.into()),
+            ..self
+        }
+    }
+
+    /// Set the author of the policy
+    #[must_use]
+    pub fn with_author(self, author: impl Into<SmolStr>) -> Self {
+        Self {
+            author: Some(author.into()),
+            ..self
+        }
+    }
+
+    /// The file the policy was loaded from
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// The (1-based) line of the file on which the policy starts
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// The version control revision of the file
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
+    /// The author of the policy
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    /// Formats as `file:line @ revision by author`, leaving out missing parts
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => parts.push(format!("{file}:{line}")),
+            (Some(file), None) => parts.push(file.to_string()),
+            (None, Some(line)) => parts.push(format!("line {line}")),
+            (None, None) => (),
+        }
+        if let Some(revision) = &self.revision {
+            parts.push(format!("@ {revision}"));
+        }
+        if let Some(author) = &self.author {
+            parts.push(format!("by {author}"));
+        }
+        if parts.is_empty() {
+            write!(f, "unknown provenance")
+        } else {
+            write!(f, "{}", parts.join(" "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let p = Provenance::new()
+            .with_file("policies/photos.cedar")
+            .with_line(12)
+            .with_revision("3f2a9c1")
+            .with_author("alice");
+        assert_eq!(p.to_string(), "policies/photos.cedar:12 @ 3f2a9c1 by alice");
+        assert_eq!(
+            Provenance::new().with_revision("3f2a9c1").to_string(),
+            "@ 3f2a9c1"
+        );
+        assert_eq!(Provenance::new().to_string(), "unknown provenance");
+    }
+}
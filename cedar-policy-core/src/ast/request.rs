@@ -435,6 +435,14 @@ impl Context {
         }
     }
 
+    /// Whether this `Context` has the attribute `key`
+    pub fn contains_key(&self, key: &str) -> bool {
+        match self {
+            Context::Value(record) => record.contains_key(key),
+            Context::RestrictedResidual(record) => record.contains_key(key),
+        }
+    }
+
     /// Private helper function to implement `into_iter()` for `Context`.
     /// Gets an iterator over the (key, value) pairs in the `Context`, cloning
     /// only if necessary.
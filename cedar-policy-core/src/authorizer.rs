@@ -22,19 +22,26 @@
 
 use crate::ast::*;
 use crate::entities::Entities;
-use crate::evaluator::Evaluator;
+use crate::evaluator::{Cancellation, EvaluationError, Evaluator, OverflowBehavior};
 use crate::extensions::Extensions;
+use crate::limits::ResourceLimits;
 use itertools::{Either, Itertools};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::sync::Arc;
 
 #[cfg(feature = "wasm")]
 extern crate tsify;
 
+mod context_provider;
+mod decision_record;
 mod err;
 mod partial_response;
+mod profile;
+pub use context_provider::{ContextProvider, ContextSchema};
+pub use decision_record::{DecisionLogger, DecisionRecord};
 pub use err::{AuthorizationError, ConcretizationError, ReauthorizationError};
+pub use profile::{EvaluationProfiler, PolicyProfile};
 
 pub use partial_response::ErrorState;
 pub use partial_response::PartialResponse;
@@ -46,6 +53,28 @@ pub struct Authorizer {
     extensions: &'static Extensions<'static>,
     /// Error-handling behavior of this `Authorizer`
     error_handling: ErrorHandling,
+    /// If `true`, errors in responses are redacted (see [`crate::evaluator::EvaluationError::redacted`])
+    redact_errors: bool,
+    /// Limits on the requests accepted by this `Authorizer`
+    limits: ResourceLimits,
+    /// If present, receives the evaluation cost of every policy
+    profiler: Option<Arc<EvaluationProfiler>>,
+    /// If `true`, policies are evaluated in order of their ids
+    deterministic_order: bool,
+    /// What to do when integer arithmetic overflows
+    overflow: OverflowBehavior,
+    /// If `true`, the values of expensive sub-expressions are memoized for
+    /// the duration of each request
+    subexpression_cache: bool,
+    /// Providers consulted for context attributes the caller didn't supply,
+    /// in order of precedence
+    context_providers: Vec<Arc<dyn ContextProvider>>,
+    /// If present, values from `context_providers` are checked against it
+    context_schema: Option<Arc<dyn ContextSchema>>,
+    /// Loggers receiving a [`DecisionRecord`] for every authorization call
+    decision_loggers: Vec<Arc<dyn DecisionLogger>>,
+    /// Caller-defined version of the policies, recorded in [`DecisionRecord`]s
+    policy_set_version: Option<String>,
 }
 
 /// Describes the possible Cedar error-handling modes.
@@ -71,6 +100,112 @@ impl Authorizer {
         Self {
             extensions: Extensions::all_available(), // set at compile time
             error_handling: Default::default(),
+            redact_errors: false,
+            limits: ResourceLimits::default(),
+            profiler: None,
+            deterministic_order: false,
+            overflow: OverflowBehavior::default(),
+            subexpression_cache: false,
+            context_providers: Vec::new(),
+            context_schema: None,
+            decision_loggers: Vec::new(),
+            policy_set_version: None,
+        }
+    }
+
+    /// Reject requests exceeding the given `limits`. Every policy evaluated
+    /// for such a request fails with [`crate::evaluator::EvaluationError::LimitExceeded`],
+    /// so the request is denied.
+    #[must_use]
+    pub fn with_limits(self, limits: ResourceLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Redact sensitive data from evaluation errors reported in responses from
+    /// this `Authorizer` (see [`crate::evaluator::EvaluationError::redacted`])
+    #[must_use]
+    pub fn with_redacted_errors(self) -> Self {
+        Self {
+            redact_errors: true,
+            ..self
+        }
+    }
+
+    /// Evaluate policies in order of their ids, rather than in the order they
+    /// are stored in the policy set, so that the errors in responses are
+    /// always reported in the same order (and a cancelled request always
+    /// reports the same policy) across runs and platforms
+    #[must_use]
+    pub fn with_deterministic_order(self) -> Self {
+        Self {
+            deterministic_order: true,
+            ..self
+        }
+    }
+
+    /// Handle integer overflow during evaluation according to `overflow`.
+    /// Policies whose evaluation saturated are listed in
+    /// [`Diagnostics::saturated_policies`].
+    #[must_use]
+    pub fn with_overflow_behavior(self, overflow: OverflowBehavior) -> Self {
+        Self { overflow, ..self }
+    }
+
+    /// Evaluate each distinct expensive sub-expression once per request, no
+    /// matter how many times it occurs in the policies (see
+    /// [`Evaluator::with_subexpression_cache`])
+    #[must_use]
+    pub fn with_subexpression_cache(self) -> Self {
+        Self {
+            subexpression_cache: true,
+            ..self
+        }
+    }
+
+    /// Record the time taken and the number of expression nodes visited by
+    /// every policy evaluated by this `Authorizer` in `profiler`
+    #[must_use]
+    pub fn with_profiler(self, profiler: Arc<EvaluationProfiler>) -> Self {
+        Self {
+            profiler: Some(profiler),
+            ..self
+        }
+    }
+
+    /// Consult `provider` for context attributes that requests don't supply
+    /// themselves. If several providers resolve the same attribute, the one
+    /// added first takes precedence.
+    #[must_use]
+    pub fn with_context_provider(mut self, provider: Arc<dyn ContextProvider>) -> Self {
+        self.context_providers.push(provider);
+        self
+    }
+
+    /// Check the values supplied by [`ContextProvider`]s against `schema`
+    #[must_use]
+    pub fn with_context_schema(self, schema: Arc<dyn ContextSchema>) -> Self {
+        Self {
+            context_schema: Some(schema),
+            ..self
+        }
+    }
+
+    /// Send a [`DecisionRecord`] to `logger` for every call to
+    /// [`Authorizer::is_authorized`] or
+    /// [`Authorizer::is_authorized_with_cancellation`]
+    #[must_use]
+    pub fn with_decision_logger(mut self, logger: Arc<dyn DecisionLogger>) -> Self {
+        self.decision_loggers.push(logger);
+        self
+    }
+
+    /// Set the version of the policies being authorized against, as recorded
+    /// in [`DecisionRecord`]s
+    #[must_use]
+    pub fn with_policy_set_version(self, version: String) -> Self {
+        Self {
+            policy_set_version: Some(version),
+            ..self
         }
     }
 
@@ -79,7 +214,54 @@ impl Authorizer {
     /// The language spec and formal model give a precise definition of how this is
     /// computed.
     pub fn is_authorized(&self, q: Request, pset: &PolicySet, entities: &Entities) -> Response {
-        self.is_authorized_core(q, pset, entities).concretize()
+        self.is_authorized_with_cancellation(q, pset, entities, &Cancellation::default())
+    }
+
+    /// Like [`Authorizer::is_authorized`], but abandons evaluation once
+    /// `cancellation` says to. The response to an abandoned request is `Deny`,
+    /// with a single [`crate::evaluator::EvaluationError::Cancelled`] error.
+    pub fn is_authorized_with_cancellation(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+        cancellation: &Cancellation,
+    ) -> Response {
+        if self.decision_loggers.is_empty() {
+            return self.authorize_concrete(q, pset, entities, cancellation);
+        }
+        let started = std::time::SystemTime::now();
+        let timer = std::time::Instant::now();
+        let response = self.authorize_concrete(q.clone(), pset, entities, cancellation);
+        let record = DecisionRecord::new(
+            &q,
+            pset,
+            &response,
+            started,
+            timer.elapsed(),
+            self.policy_set_version.clone(),
+        );
+        for logger in &self.decision_loggers {
+            logger.log(&record);
+        }
+        response
+    }
+
+    /// [`Authorizer::is_authorized_with_cancellation`], without logging the
+    /// decision
+    fn authorize_concrete(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+        cancellation: &Cancellation,
+    ) -> Response {
+        let response = self
+            .is_authorized_core_with_cancellation(q, pset, entities, cancellation)
+            .concretize();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(decision = ?response.decision, "authorization decision");
+        response
     }
 
     /// Returns an authorization response for `q` with respect to the given `Slice`.
@@ -91,7 +273,31 @@ impl Authorizer {
         pset: &PolicySet,
         entities: &Entities,
     ) -> PartialResponse {
-        let eval = Evaluator::new(q.clone(), entities, self.extensions);
+        self.is_authorized_core_with_cancellation(q, pset, entities, &Cancellation::default())
+    }
+
+    /// Like [`Authorizer::is_authorized_core`], but abandons evaluation once
+    /// `cancellation` says to (see [`Authorizer::is_authorized_with_cancellation`])
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(request = %q))
+    )]
+    pub fn is_authorized_core_with_cancellation(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+        cancellation: &Cancellation,
+    ) -> PartialResponse {
+        let (q, provided_context_errors) = self.provide_context(q, pset);
+        let eval = Evaluator::new(q.clone(), entities, self.extensions)
+            .with_cancellation(cancellation)
+            .with_overflow_behavior(self.overflow);
+        let eval = if self.subexpression_cache {
+            eval.with_subexpression_cache()
+        } else {
+            eval
+        };
         let mut true_permits = vec![];
         let mut true_forbids = vec![];
         let mut false_permits = vec![];
@@ -99,10 +305,37 @@ impl Authorizer {
         let mut residual_permits = vec![];
         let mut residual_forbids = vec![];
         let mut errors = vec![];
-
-        for p in pset.policies() {
+        let mut profile = vec![];
+        let mut saturated_policies = vec![];
+        let limit_error = self.limits.check_entity_store(entities).err();
+
+        let policies = if self.deterministic_order {
+            Either::Left(
+                pset.policies()
+                    .sorted_unstable_by(|p1, p2| p1.id().cmp(p2.id())),
+            )
+        } else {
+            Either::Right(pset.policies())
+        };
+        for p in policies {
             let (id, annotations) = (p.id().clone(), p.annotations_arc().clone());
-            match eval.partial_evaluate(p) {
+            let start = self
+                .profiler
+                .as_ref()
+                .map(|_| (std::time::Instant::now(), eval.node_visits()));
+            let saturations = eval.saturations();
+            let result = match (&limit_error, provided_context_errors.for_policy(p)) {
+                (Some(e), _) => Err(e.clone().into()),
+                (None, Some(e)) => Err(e),
+                (None, None) => eval.partial_evaluate(p),
+            };
+            if eval.saturations() > saturations {
+                saturated_policies.push(id.clone());
+            }
+            if let Some((time, node_visits)) = start {
+                profile.push((id.clone(), time.elapsed(), eval.node_visits() - node_visits));
+            }
+            match result {
                 Ok(Either::Left(satisfied)) => match (satisfied, p.effect()) {
                     (true, Effect::Permit) => true_permits.push((id, annotations)),
                     (true, Effect::Forbid) => true_forbids.push((id, annotations)),
@@ -121,11 +354,36 @@ impl Authorizer {
                         residual_forbids.push((id, (Arc::new(residual), annotations)))
                     }
                 },
+                Err(e @ EvaluationError::Cancelled(_)) => {
+                    if let Some(profiler) = &self.profiler {
+                        profiler.record(profile);
+                    }
+                    // Don't let a partial set of results decide the request
+                    let error = AuthorizationError::PolicyEvaluationError {
+                        id,
+                        link: self.link_bindings(p),
+                        error: if self.redact_errors { e.redacted() } else { e },
+                    };
+                    return PartialResponse::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![error],
+                        Arc::new(q),
+                    );
+                }
                 Err(e) => {
-                    errors.push(AuthorizationError::PolicyEvaluationError {
+                    let error = AuthorizationError::PolicyEvaluationError {
                         id: id.clone(),
-                        error: e,
-                    });
+                        link: self.link_bindings(p),
+                        error: if self.redact_errors { e.redacted() } else { e },
+                    };
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(policy_id = %id, %error, "policy evaluation error");
+                    errors.push(error);
                     let satisfied = match self.error_handling {
                         ErrorHandling::Skip => false,
                     };
@@ -143,7 +401,10 @@ impl Authorizer {
             };
         }
 
-        PartialResponse::new(
+        if let Some(profiler) = &self.profiler {
+            profiler.record(profile);
+        }
+        let response = PartialResponse::new(
             true_permits,
             false_permits,
             residual_permits,
@@ -153,6 +414,22 @@ impl Authorizer {
             errors,
             Arc::new(q),
         )
+        .with_saturated_policies(saturated_policies);
+        if self.deterministic_order {
+            response.with_deterministic_order()
+        } else {
+            response
+        }
+    }
+
+    /// Bindings of `p` to report with its errors, redacted like the errors
+    fn link_bindings(&self, p: &Policy) -> Option<LinkBindings> {
+        let link = LinkBindings::of(p)?;
+        Some(if self.redact_errors {
+            link.redacted()
+        } else {
+            link
+        })
     }
 }
 
@@ -205,6 +482,42 @@ mod test {
         assert_eq!(ans.decision, Decision::Deny);
     }
 
+    #[test]
+    fn profiling() {
+        let profiler = Arc::new(EvaluationProfiler::new());
+        let a = Authorizer::new().with_profiler(profiler.clone());
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let pset = crate::parser::parse_policyset(
+            r#"
+            permit(principal, action, resource);
+            forbid(principal, action, resource) when { 1 + 2 + 3 + 4 == 5 };
+            "#,
+        )
+        .unwrap();
+        let entities = Entities::new();
+        a.is_authorized(q.clone(), &pset, &entities);
+        a.is_authorized(q, &pset, &entities);
+
+        let cheap = profiler.profile(&PolicyID::from_string("policy0")).unwrap();
+        let costly = profiler.profile(&PolicyID::from_string("policy1")).unwrap();
+        assert_eq!(cheap.evaluations(), 2);
+        assert_eq!(costly.evaluations(), 2);
+        assert_eq!(costly.total_node_visits(), 2 * costly.max_node_visits());
+        assert!(costly.max_node_visits() > cheap.max_node_visits());
+        assert!(costly.total_time() >= costly.max_time());
+        assert_eq!(profiler.profiles().len(), 2);
+        profiler.reset();
+        assert!(profiler.profiles().is_empty());
+    }
+
     /// Simple tests of skip-on-error semantics
     #[test]
     fn skip_on_error_tests() {
@@ -672,21 +985,32 @@ pub struct EvaluationResponse {
 pub struct Diagnostics {
     /// `PolicyID`s of the policies that contributed to the decision. If no
     /// policies applied to the request, this set will be empty.
-    pub reason: HashSet<PolicyID>,
+    pub reason: BTreeSet<PolicyID>,
     /// List of errors that occurred
     pub errors: Vec<AuthorizationError>,
+    /// `PolicyID`s of the policies whose evaluation saturated an integer
+    /// overflow instead of failing (see [`OverflowBehavior::Saturate`])
+    pub saturated_policies: BTreeSet<PolicyID>,
+    /// `PolicyID`s of the [`Effect::Permit`] policies whose evaluation
+    /// errored, any of which might have allowed the request otherwise
+    pub errored_permits: BTreeSet<PolicyID>,
 }
 
 impl Response {
     /// Create a new `Response`
     pub fn new(
         decision: Decision,
-        reason: HashSet<PolicyID>,
+        reason: BTreeSet<PolicyID>,
         errors: Vec<AuthorizationError>,
     ) -> Self {
         Response {
             decision,
-            diagnostics: Diagnostics { reason, errors },
+            diagnostics: Diagnostics {
+                reason,
+                errors,
+                saturated_policies: BTreeSet::new(),
+                errored_permits: BTreeSet::new(),
+            },
         }
     }
 }
@@ -0,0 +1,153 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Context attributes resolved during authorization
+
+use std::collections::btree_map::{BTreeMap, Entry};
+
+use smol_str::SmolStr;
+
+use super::Authorizer;
+use crate::ast::{
+    Context, EntityUID, EntityUIDEntry, PartialValue, Policy, PolicySet, Request, RestrictedExpr,
+};
+use crate::evaluator::{EvaluationError, RestrictedEvaluator};
+use crate::extensions::Extensions;
+
+/// Supplies context attributes that are resolved during authorization (see
+/// [`Authorizer::with_context_provider`])
+pub trait ContextProvider: Send + Sync {
+    /// Names of the context attributes this provider can resolve
+    fn attributes(&self) -> Vec<SmolStr>;
+
+    /// Resolve the context attribute `name` for `request`, or return `None`
+    /// if the attribute has no value for this request.
+    ///
+    /// Only called with names returned by [`ContextProvider::attributes`].
+    fn resolve(&self, name: &str, request: &Request) -> Option<RestrictedExpr>;
+}
+
+/// Types of context attributes, which the values supplied by
+/// [`ContextProvider`]s are checked against (see
+/// [`Authorizer::with_context_schema`])
+pub trait ContextSchema: Send + Sync {
+    /// Whether `value` conforms to the type declared for the context
+    /// attribute `attr` of requests for `action`
+    fn context_attr_conforms(
+        &self,
+        action: &EntityUID,
+        attr: &str,
+        value: &PartialValue,
+        extensions: &Extensions<'_>,
+    ) -> bool;
+}
+
+/// Errors for the context attributes whose provided values were invalid, by
+/// attribute name
+#[derive(Debug, Default)]
+pub(super) struct ProvidedContextErrors(BTreeMap<SmolStr, EvaluationError>);
+
+impl ProvidedContextErrors {
+    /// The error to report for `policy`, if it may read an attribute whose
+    /// provided value was invalid
+    pub(super) fn for_policy(&self, policy: &Policy) -> Option<EvaluationError> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let mut errors = self.0.iter();
+        let error = match policy.template().context_attrs_read() {
+            Some(read) => errors.find(|(attr, _)| read.contains(*attr)),
+            None => errors.next(),
+        };
+        error.map(|(_, e)| e.clone())
+    }
+}
+
+impl Authorizer {
+    /// Fill in the context attributes of `q` supplied by this `Authorizer`'s
+    /// [`ContextProvider`]s.
+    ///
+    /// Only attributes that `q` doesn't already contain and that some policy
+    /// in `pset` may read are resolved. Provided values that fail to evaluate
+    /// or don't conform to the [`ContextSchema`] are left out of the context,
+    /// and their errors are returned instead.
+    pub(super) fn provide_context(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+    ) -> (Request, ProvidedContextErrors) {
+        let mut errors = BTreeMap::new();
+        let context = match q.context() {
+            Some(context) if !self.context_providers.is_empty() => context,
+            _ => return (q, ProvidedContextErrors(errors)),
+        };
+        let read = pset.context_attrs_read();
+        let evaluator = RestrictedEvaluator::new(self.extensions);
+        let mut provided: BTreeMap<SmolStr, RestrictedExpr> = BTreeMap::new();
+        for provider in &self.context_providers {
+            for name in provider.attributes() {
+                if context.contains_key(&name)
+                    || provided.contains_key(&name)
+                    || read.is_some_and(|read| !read.contains(&name))
+                {
+                    continue;
+                }
+                let Some(expr) = provider.resolve(&name, &q) else {
+                    continue;
+                };
+                let error = match evaluator.partial_interpret(expr.as_borrowed()) {
+                    Ok(value) if self.provided_value_conforms(&q, &name, &value) => {
+                        errors.remove(&name);
+                        provided.insert(name, expr);
+                        continue;
+                    }
+                    Ok(_) => EvaluationError::context_provider(name.clone(), None),
+                    Err(e) => EvaluationError::context_provider(name.clone(), Some(e)),
+                };
+                // An earlier provider's error takes precedence
+                if let Entry::Vacant(entry) = errors.entry(name) {
+                    entry.insert(error);
+                }
+            }
+        }
+        if provided.is_empty() {
+            return (q, ProvidedContextErrors(errors));
+        }
+        // PANIC SAFETY: `provided` has no keys in common with `context`, and every provided value evaluates successfully
+        #[allow(clippy::expect_used)]
+        let context =
+            Context::from_pairs(context.clone().into_iter().chain(provided), self.extensions)
+                .expect("merging disjoint, evaluable context attributes should succeed");
+        let q = Request::new_unchecked(
+            q.principal().clone(),
+            q.action().clone(),
+            q.resource().clone(),
+            Some(context),
+        );
+        (q, ProvidedContextErrors(errors))
+    }
+
+    /// Whether `value`, provided for the context attribute `attr` of `q`,
+    /// conforms to this `Authorizer`'s [`ContextSchema`], if it has one
+    fn provided_value_conforms(&self, q: &Request, attr: &str, value: &PartialValue) -> bool {
+        match (&self.context_schema, q.action()) {
+            (Some(schema), EntityUIDEntry::Known { euid, .. }) => {
+                schema.context_attr_conforms(euid, attr, value, self.extensions)
+            }
+            _ => true,
+        }
+    }
+}
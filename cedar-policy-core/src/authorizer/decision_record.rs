@@ -0,0 +1,167 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Audit records of authorization decisions
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Decision, Response};
+use crate::ast::{EntityUIDEntry, PolicySet, Provenance, Request};
+
+/// Receives a [`DecisionRecord`] for every authorization call made by an
+/// [`super::Authorizer`]; see [`super::Authorizer::with_decision_logger`].
+///
+/// This is implemented for any `Fn(&DecisionRecord)` closure.
+pub trait DecisionLogger: Send + Sync {
+    /// Record a single authorization decision
+    fn log(&self, record: &DecisionRecord);
+}
+
+impl<F: Fn(&DecisionRecord) + Send + Sync> DecisionLogger for F {
+    fn log(&self, record: &DecisionRecord) {
+        self(record);
+    }
+}
+
+/// Serializable summary of a single authorization decision, for audit logs
+///
+/// The request's context is intentionally not recorded, since it frequently
+/// contains sensitive data.
+//
+// CAUTION: this type is publicly exported in `cedar-policy`.
+// Don't make fields `pub`, don't make breaking changes, and use caution when
+// adding public methods.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionRecord {
+    /// Principal of the request, or `None` if it was unknown
+    principal: Option<String>,
+    /// Action of the request, or `None` if it was unknown
+    action: Option<String>,
+    /// Resource of the request, or `None` if it was unknown
+    resource: Option<String>,
+    /// The authorization decision
+    decision: Decision,
+    /// IDs of the policies that determined the decision, sorted
+    determining_policies: Vec<String>,
+    /// Provenance of the determining policies, where known
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    provenance: BTreeMap<String, Provenance>,
+    /// Errors encountered during authorization
+    errors: Vec<String>,
+    /// When authorization started, in milliseconds since the Unix epoch
+    timestamp_millis: u64,
+    /// How long authorization took, in microseconds
+    duration_micros: u64,
+    /// Version of the policies, if one was set with
+    /// [`super::Authorizer::with_policy_set_version`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    policy_set_version: Option<String>,
+}
+
+impl DecisionRecord {
+    /// Summarize the decision `response` for request `q` against `pset`
+    pub(super) fn new(
+        q: &Request,
+        pset: &PolicySet,
+        response: &Response,
+        started: SystemTime,
+        duration: Duration,
+        policy_set_version: Option<String>,
+    ) -> Self {
+        let uid = |entry: &EntityUIDEntry| entry.uid().map(ToString::to_string);
+        let reason = &response.diagnostics.reason;
+        Self {
+            principal: uid(q.principal()),
+            action: uid(q.action()),
+            resource: uid(q.resource()),
+            decision: response.decision,
+            determining_policies: reason.iter().map(ToString::to_string).collect(),
+            provenance: reason
+                .iter()
+                .filter_map(|id| Some((id.to_string(), pset.get(id)?.provenance()?.clone())))
+                .collect(),
+            errors: response
+                .diagnostics
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            timestamp_millis: started
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX)),
+            duration_micros: u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+            policy_set_version,
+        }
+    }
+
+    /// Principal of the request, or `None` if it was unknown
+    pub fn principal(&self) -> Option<&str> {
+        self.principal.as_deref()
+    }
+
+    /// Action of the request, or `None` if it was unknown
+    pub fn action(&self) -> Option<&str> {
+        self.action.as_deref()
+    }
+
+    /// Resource of the request, or `None` if it was unknown
+    pub fn resource(&self) -> Option<&str> {
+        self.resource.as_deref()
+    }
+
+    /// The authorization decision
+    pub fn decision(&self) -> Decision {
+        self.decision
+    }
+
+    /// IDs of the policies that determined the decision, sorted
+    pub fn determining_policies(&self) -> impl Iterator<Item = &str> {
+        self.determining_policies.iter().map(String::as_str)
+    }
+
+    /// Where the determining policy with the given id was deployed from, if
+    /// known
+    pub fn provenance(&self, policy_id: &str) -> Option<&Provenance> {
+        self.provenance.get(policy_id)
+    }
+
+    /// Errors encountered during authorization
+    pub fn errors(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().map(String::as_str)
+    }
+
+    /// When authorization started, according to the system clock, in
+    /// milliseconds since the Unix epoch
+    pub fn timestamp_millis(&self) -> u64 {
+        self.timestamp_millis
+    }
+
+    /// How long authorization took, as measured by the monotonic clock, in
+    /// microseconds
+    pub fn duration_micros(&self) -> u64 {
+        self.duration_micros
+    }
+
+    /// Version of the policies, if one was set with
+    /// [`super::Authorizer::with_policy_set_version`]
+    pub fn policy_set_version(&self) -> Option<&str> {
+        self.policy_set_version.as_deref()
+    }
+}
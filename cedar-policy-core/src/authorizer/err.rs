@@ -24,16 +24,25 @@ use thiserror::Error;
 #[derive(Debug, PartialEq, Eq, Clone, Diagnostic, Error)]
 pub enum AuthorizationError {
     /// An error occurred when evaluating a policy.
-    #[error("while evaluating policy `{id}`: {error}")]
+    #[error("while evaluating policy `{id}`{}: {error}", display_link(.link))]
+    #[diagnostic(forward(error))]
     PolicyEvaluationError {
         /// Id of the policy with an error
         id: PolicyID,
+        /// Template and slot values of the policy, if it is a template-linked
+        /// policy
+        link: Option<LinkBindings>,
         /// Underlying evaluation error
-        #[diagnostic(transparent)]
         error: EvaluationError,
     },
 }
 
+/// Display the bindings of a template-linked policy as a parenthetical
+/// following its id in an error message
+pub(crate) fn display_link(link: &Option<LinkBindings>) -> String {
+    link.as_ref().map(|l| format!(" ({l})")).unwrap_or_default()
+}
+
 /// Errors that occur during concretizing a partial request
 #[derive(Debug, Error, Diagnostic)]
 pub enum ConcretizationError {
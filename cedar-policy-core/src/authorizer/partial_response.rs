@@ -14,9 +14,10 @@
  * limitations under the License.
  */
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use either::Either;
+use itertools::Itertools;
 use smol_str::SmolStr;
 use std::sync::Arc;
 
@@ -60,6 +61,12 @@ pub struct PartialResponse {
     pub residual_forbids: HashMap<PolicyID, (Arc<Expr>, Arc<Annotations>)>,
     /// All of the policy errors encountered during evaluation
     pub errors: Vec<AuthorizationError>,
+    /// The policies whose evaluation saturated an integer overflow (see
+    /// [`crate::evaluator::OverflowBehavior::Saturate`])
+    pub saturated_policies: BTreeSet<PolicyID>,
+    /// If `true`, the errors for residual policies are reported in order of
+    /// policy id (see [`crate::authorizer::Authorizer::with_deterministic_order`])
+    deterministic_order: bool,
     /// The trivial `true` expression, used for materializing a residual for satisfied policies
     true_expr: Arc<Expr>,
     /// The trivial `false` expression, used for materializing a residual for non-satisfied policies
@@ -89,12 +96,33 @@ impl PartialResponse {
             false_forbids: false_forbids.into_iter().collect(),
             residual_forbids: residual_forbids.into_iter().collect(),
             errors: errors.into_iter().collect(),
+            saturated_policies: BTreeSet::new(),
+            deterministic_order: false,
             true_expr: Arc::new(Expr::val(true)),
             false_expr: Arc::new(Expr::val(false)),
             request,
         }
     }
 
+    /// Record that the evaluation of `policies` saturated an integer overflow
+    #[must_use]
+    pub fn with_saturated_policies(self, policies: impl IntoIterator<Item = PolicyID>) -> Self {
+        Self {
+            saturated_policies: policies.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Report the errors for residual policies in order of policy id when
+    /// concretizing this response
+    #[must_use]
+    pub fn with_deterministic_order(self) -> Self {
+        Self {
+            deterministic_order: true,
+            ..self
+        }
+    }
+
     /// Convert this response into a concrete evaluation response.
     /// All residuals are treated as errors
     pub fn concretize(self) -> Response {
@@ -443,13 +471,29 @@ impl PartialResponse {
         })
     }
 
+    /// Errors for the residual policies, forbids first (each in order of
+    /// policy id if [`Self::with_deterministic_order`] was set), followed by
+    /// the evaluation errors in the order they were encountered
     fn errors(self) -> impl Iterator<Item = AuthorizationError> {
-        self.residual_forbids
-            .into_iter()
-            .chain(self.residual_permits)
+        let deterministic_order = self.deterministic_order;
+        let ordered = |residuals: HashMap<PolicyID, _>| {
+            if deterministic_order {
+                Either::Left(
+                    residuals
+                        .into_iter()
+                        .sorted_unstable_by(|(id1, _), (id2, _)| id1.cmp(id2)),
+                )
+            } else {
+                Either::Right(residuals.into_iter())
+            }
+        };
+        ordered(self.residual_forbids)
+            .chain(ordered(self.residual_permits))
             .map(
                 |(id, (expr, _))| AuthorizationError::PolicyEvaluationError {
                     id,
+                    // Residuals already have their slots filled in
+                    link: None,
                     error: EvaluationError::non_value(expr.as_ref().clone()),
                 },
             )
@@ -466,11 +510,24 @@ impl From<PartialResponse> for Response {
         } else {
             Decision::Deny
         };
-        Response::new(
+        let saturated_policies = p.saturated_policies.clone();
+        // Residuals are reported as errors, like the policies which errored
+        let errored_permits = p
+            .false_permits
+            .iter()
+            .filter(|(_, (state, _))| *state == ErrorState::Error)
+            .map(|(id, _)| id)
+            .chain(p.residual_permits.keys())
+            .cloned()
+            .collect();
+        let mut response = Response::new(
             decision,
             p.must_be_determining().map(|p| p.id().clone()).collect(),
             p.errors().collect(),
-        )
+        );
+        response.diagnostics.saturated_policies = saturated_policies;
+        response.diagnostics.errored_permits = errored_permits;
+        response
     }
 }
 
@@ -850,4 +907,53 @@ mod test {
             Some(Decision::Deny)
         );
     }
+
+    #[test]
+    fn deterministic_order_sorts_residual_errors() {
+        let residual = |id: &str| {
+            (
+                PolicyID::from_string(id),
+                (
+                    Arc::new(Expr::unknown(Unknown::new_untyped("u"))),
+                    Arc::default(),
+                ),
+            )
+        };
+        let pr = PartialResponse::new(
+            empty(),
+            empty(),
+            ["m", "b", "z", "a", "q", "k", "c", "x"].map(residual),
+            empty(),
+            empty(),
+            ["y", "d", "l"].map(residual),
+            empty(),
+            Arc::new(Request::new_unchecked(
+                EntityUIDEntry::Unknown { loc: None },
+                EntityUIDEntry::Unknown { loc: None },
+                EntityUIDEntry::Unknown { loc: None },
+                Some(Context::empty()),
+            )),
+        );
+
+        let response = pr.with_deterministic_order().concretize();
+        let errored = response
+            .diagnostics
+            .errors
+            .iter()
+            .map(|AuthorizationError::PolicyEvaluationError { id, .. }| id.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errored,
+            ["d", "l", "y", "a", "b", "c", "k", "m", "q", "x", "z"]
+        );
+        assert_eq!(
+            response
+                .diagnostics
+                .errored_permits
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["a", "b", "c", "k", "m", "q", "x", "z"]
+        );
+    }
 }
@@ -0,0 +1,143 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Per-policy evaluation profiling
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::ast::PolicyID;
+
+/// Aggregate evaluation cost of one policy across all the requests an
+/// [`EvaluationProfiler`] has recorded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyProfile {
+    evaluations: u64,
+    total_time: Duration,
+    max_time: Duration,
+    total_node_visits: u64,
+    max_node_visits: u64,
+}
+
+impl PolicyProfile {
+    fn record(&mut self, time: Duration, node_visits: u64) {
+        self.evaluations += 1;
+        self.total_time += time;
+        self.max_time = self.max_time.max(time);
+        self.total_node_visits += node_visits;
+        self.max_node_visits = self.max_node_visits.max(node_visits);
+    }
+
+    /// Number of requests the policy was evaluated for
+    pub fn evaluations(&self) -> u64 {
+        self.evaluations
+    }
+
+    /// Total time spent evaluating the policy
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Longest time spent evaluating the policy for a single request
+    pub fn max_time(&self) -> Duration {
+        self.max_time
+    }
+
+    /// Average time spent evaluating the policy per request
+    pub fn mean_time(&self) -> Duration {
+        match u32::try_from(self.evaluations) {
+            Ok(0) => Duration::ZERO,
+            Ok(n) => self.total_time / n,
+            Err(_) => {
+                Duration::from_secs_f64(self.total_time.as_secs_f64() / self.evaluations as f64)
+            }
+        }
+    }
+
+    /// Total number of expression nodes visited while evaluating the policy
+    pub fn total_node_visits(&self) -> u64 {
+        self.total_node_visits
+    }
+
+    /// Greatest number of expression nodes visited while evaluating the
+    /// policy for a single request
+    pub fn max_node_visits(&self) -> u64 {
+        self.max_node_visits
+    }
+}
+
+/// Collects the time and number of expression nodes visited for each policy
+/// evaluated by an [`super::Authorizer`] with profiling enabled (see
+/// [`super::Authorizer::with_profiler`]).
+///
+/// One profiler can be shared by several authorizers and threads. Profiling
+/// has a small cost per policy evaluated, so it is meant to be enabled while
+/// investigating latency rather than permanently.
+#[derive(Debug, Default)]
+pub struct EvaluationProfiler {
+    profiles: Mutex<HashMap<PolicyID, PolicyProfile>>,
+}
+
+impl EvaluationProfiler {
+    /// Create a profiler which has not recorded anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the cost of evaluating each policy for one request
+    pub(crate) fn record(&self, samples: impl IntoIterator<Item = (PolicyID, Duration, u64)>) {
+        // A panic while holding the lock cannot leave a profile partially
+        // updated in a way that matters, so poisoning can be ignored
+        let mut profiles = self.profiles.lock().unwrap_or_else(PoisonError::into_inner);
+        for (id, time, node_visits) in samples {
+            profiles.entry(id).or_default().record(time, node_visits);
+        }
+    }
+
+    /// The profile of every policy evaluated so far, most expensive (by total
+    /// time) first
+    pub fn profiles(&self) -> Vec<(PolicyID, PolicyProfile)> {
+        let mut profiles = self
+            .profiles
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(id, profile)| (id.clone(), profile.clone()))
+            .collect::<Vec<_>>();
+        profiles.sort_by(|(id1, p1), (id2, p2)| {
+            p2.total_time.cmp(&p1.total_time).then_with(|| id1.cmp(id2))
+        });
+        profiles
+    }
+
+    /// The profile of the policy `id`, if it has been evaluated
+    pub fn profile(&self, id: &PolicyID) -> Option<PolicyProfile> {
+        self.profiles
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .cloned()
+    }
+
+    /// Discard everything recorded so far
+    pub fn reset(&self) {
+        self.profiles
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
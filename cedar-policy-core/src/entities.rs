@@ -18,18 +18,22 @@
 
 use crate::ast::*;
 use crate::extensions::Extensions;
-use crate::transitive_closure::{compute_tc, enforce_tc_and_dag};
-use std::collections::{hash_map, HashMap};
+use crate::transitive_closure::{compute_tc, enforce_tc_and_dag, TcError};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::sync::Arc;
 
 use serde::Serialize;
 use serde_with::serde_as;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
 /// Module for checking that entities conform with a schema
 pub mod conformance;
+pub mod csv;
 /// Module for error types
 pub mod err;
 pub mod json;
+pub mod text;
 use json::err::JsonSerializationError;
 
 pub use json::{
@@ -60,6 +64,14 @@ pub struct Entities {
     #[serde_as(as = "Vec<(_, _)>")]
     entities: HashMap<EntityUID, Arc<Entity>>,
 
+    /// Index from each entity type to the UIDs of the entities of that type
+    /// in `entities`, so that lookups by type don't need to scan every entity.
+    ///
+    /// Important internal invariant: this contains exactly the keys of
+    /// `entities`, grouped by entity type.
+    #[serde(skip_serializing)]
+    uids_by_type: HashMap<EntityType, HashSet<EntityUID>>,
+
     /// The mode flag determines whether this store functions as a partial store or
     /// as a fully concrete store.
     /// Mode::Concrete means that the store is fully concrete, and failed dereferences are an error.
@@ -75,6 +87,7 @@ impl Entities {
     pub fn new() -> Self {
         Self {
             entities: HashMap::new(),
+            uids_by_type: HashMap::new(),
             mode: Mode::default(),
         }
     }
@@ -86,6 +99,7 @@ impl Entities {
     pub fn partial(self) -> Self {
         Self {
             entities: self.entities,
+            uids_by_type: self.uids_by_type,
             mode: Mode::Partial,
         }
     }
@@ -107,11 +121,59 @@ impl Entities {
         }
     }
 
+    /// Number of `Entity`s in the `Entities`
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if there are no `Entity`s in the `Entities`
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
     /// Iterate over the `Entity`s in the `Entities`
     pub fn iter(&self) -> impl Iterator<Item = &Entity> {
         self.entities.values().map(|e| e.as_ref())
     }
 
+    /// Iterate over the `Entity`s of the given entity type. This uses an
+    /// index on entity types rather than scanning every entity.
+    pub fn iter_of_type<'a>(&'a self, ty: &EntityType) -> impl Iterator<Item = &'a Entity> {
+        self.uids_by_type
+            .get(ty)
+            .into_iter()
+            .flatten()
+            .filter_map(|uid| self.entities.get(uid).map(Arc::as_ref))
+    }
+
+    /// Iterate over the entity types which have at least one `Entity` in
+    /// the `Entities`
+    pub fn entity_types(&self) -> impl Iterator<Item = &EntityType> {
+        self.uids_by_type.keys()
+    }
+
+    /// Iterate over the `Entity`s which satisfy `predicate`
+    pub fn find<'a>(
+        &'a self,
+        predicate: impl Fn(&Entity) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Entity> {
+        self.iter().filter(move |e| predicate(e))
+    }
+
+    /// Iterate over the `Entity`s which are (transitive) descendants of the
+    /// entity with the given UID, not including that entity itself.
+    /// Since the `ancestor` relation is transitively closed, this is every
+    /// `Entity` which lists `uid` as an ancestor.
+    pub fn descendants<'a>(&'a self, uid: &'a EntityUID) -> impl Iterator<Item = &'a Entity> {
+        self.iter().filter(move |e| e.is_descendant_of(uid))
+    }
+
+    /// Iterate over the `Entity`s which have no ancestors, i.e., the roots of
+    /// the entity hierarchy
+    pub fn roots(&self) -> impl Iterator<Item = &Entity> {
+        self.iter().filter(|e| e.ancestors().next().is_none())
+    }
+
     /// Adds the [`crate::ast::Entity`]s in the iterator to this [`Entities`].
     /// Fails if the passed iterator contains any duplicate entities with this structure,
     /// or if any error is encountered in the transitive closure computation.
@@ -140,6 +202,10 @@ impl Entities {
                     return Err(EntitiesError::duplicate(entity.uid().clone()))
                 }
                 hash_map::Entry::Vacant(vacant_entry) => {
+                    self.uids_by_type
+                        .entry(entity.uid().entity_type().clone())
+                        .or_default()
+                        .insert(entity.uid().clone());
                     vacant_entry.insert(entity);
                 }
             }
@@ -152,6 +218,185 @@ impl Entities {
         Ok(self)
     }
 
+    /// Insert the [`crate::ast::Entity`]s in the iterator into this
+    /// [`Entities`] in place, replacing any existing entities with the same
+    /// UIDs.
+    /// Fails if the passed iterator contains any duplicate entities, or if
+    /// any error is encountered in the transitive closure computation.
+    ///
+    /// The transitive closure of the inserted entities is computed from their
+    /// ancestors and the (already closed) ancestors of those in this
+    /// [`Entities`]. Descendants of the inserted entities which are already in
+    /// this [`Entities`] gain any new ancestors of those entities. Since only
+    /// the closed ancestors of entities are stored, not their parents, the
+    /// descendants keep the ancestors the inserted entities no longer have;
+    /// to remove those, insert the descendants too.
+    ///
+    /// If `schema` is present, then the inserted entities will be validated
+    /// against the `schema`, returning an error if they do not conform to the
+    /// schema.
+    ///
+    /// If you pass [`TCComputation::AssumeAlreadyComputed`], then the caller is
+    /// responsible for ensuring that TC and DAG hold before calling this method.
+    pub fn upsert_entities(
+        &mut self,
+        collection: impl IntoIterator<Item = Entity>,
+        schema: Option<&impl Schema>,
+        tc_computation: TCComputation,
+        extensions: &Extensions<'_>,
+    ) -> Result<()> {
+        let mut upserted = create_entity_map(collection.into_iter().map(Arc::new))?;
+        if let Some(schema) = schema {
+            let checker = EntitySchemaConformanceChecker::new(schema, extensions);
+            for entity in upserted.values() {
+                checker.validate_entity(entity)?;
+            }
+        }
+        // The ancestors of entities which are not being replaced are already
+        // closed, so adding them leaves only the edges between inserted
+        // entities to close
+        let closed_ancestors = |entity: &Entity| -> HashSet<EntityUID> {
+            entity
+                .ancestors()
+                .filter(|ancestor| !upserted.contains_key(*ancestor))
+                .filter_map(|ancestor| self.entities.get(ancestor))
+                .flat_map(|ancestor| ancestor.ancestors().cloned())
+                .collect()
+        };
+        match tc_computation {
+            TCComputation::AssumeAlreadyComputed => (),
+            TCComputation::EnforceAlreadyComputed => {
+                for entity in upserted.values() {
+                    if let Some(missing) = closed_ancestors(entity)
+                        .into_iter()
+                        .find(|ancestor| !entity.is_descendant_of(ancestor))
+                    {
+                        // PANIC SAFETY: `missing` is an ancestor of a parent of `entity`
+                        #[allow(clippy::expect_used)]
+                        let parent = entity
+                            .ancestors()
+                            .find(|parent| {
+                                self.entities
+                                    .get(*parent)
+                                    .is_some_and(|parent| parent.is_descendant_of(&missing))
+                            })
+                            .expect("`missing` was found through a parent of `entity`");
+                        return Err(TcError::missing_tc_edge(
+                            entity.uid().clone(),
+                            parent.clone(),
+                            missing,
+                        )
+                        .into());
+                    }
+                }
+                enforce_tc_and_dag(&upserted)?;
+                if let Some((descendant, parent, missing)) = self
+                    .ancestors_missing_from_descendants(&upserted)
+                    .into_iter()
+                    .next()
+                {
+                    return Err(TcError::missing_tc_edge(descendant, parent, missing).into());
+                }
+            }
+            TCComputation::ComputeNow => {
+                let additions: Vec<_> = upserted
+                    .iter()
+                    .map(|(uid, entity)| (uid.clone(), closed_ancestors(entity)))
+                    .collect();
+                for (uid, ancestors) in additions {
+                    if let Some(entity) = upserted.get_mut(&uid) {
+                        let entity = Arc::make_mut(entity);
+                        for ancestor in ancestors {
+                            entity.add_ancestor(ancestor);
+                        }
+                    }
+                }
+                compute_tc(&mut upserted, true)?;
+                for (descendant, _, ancestor) in self.ancestors_missing_from_descendants(&upserted)
+                {
+                    if let Some(descendant) = self.entities.get_mut(&descendant) {
+                        Arc::make_mut(descendant).add_ancestor(ancestor);
+                    }
+                }
+            }
+        }
+        for (uid, entity) in upserted {
+            self.uids_by_type
+                .entry(uid.entity_type().clone())
+                .or_default()
+                .insert(uid.clone());
+            self.entities.insert(uid, entity);
+        }
+        Ok(())
+    }
+
+    /// Ancestors which entities in this [`Entities`], other than those in
+    /// `upserted`, are missing because they descend from an entity in
+    /// `upserted` with ancestors its current version lacks, as
+    /// `(descendant, upserted entity, missing ancestor)` triples
+    fn ancestors_missing_from_descendants(
+        &self,
+        upserted: &HashMap<EntityUID, Arc<Entity>>,
+    ) -> Vec<(EntityUID, EntityUID, EntityUID)> {
+        let gained: Vec<(&EntityUID, Vec<&EntityUID>)> = upserted
+            .iter()
+            .filter_map(|(uid, entity)| {
+                let current = self.entities.get(uid);
+                let gained: Vec<_> = entity
+                    .ancestors()
+                    .filter(|ancestor| {
+                        !current.is_some_and(|current| current.is_descendant_of(ancestor))
+                    })
+                    .collect();
+                (!gained.is_empty()).then_some((uid, gained))
+            })
+            .collect();
+        if gained.is_empty() {
+            return Vec::new();
+        }
+        self.entities
+            .iter()
+            .filter(|(uid, _)| !upserted.contains_key(*uid))
+            .flat_map(|(uid, entity)| {
+                gained
+                    .iter()
+                    .filter(|(parent, _)| entity.is_descendant_of(parent))
+                    .flat_map(move |(parent, ancestors)| {
+                        ancestors
+                            .iter()
+                            .filter(|ancestor| !entity.is_descendant_of(ancestor))
+                            .map(move |ancestor| {
+                                (uid.clone(), (*parent).clone(), (*ancestor).clone())
+                            })
+                    })
+            })
+            .collect()
+    }
+
+    /// Remove the entities with the given UIDs from this [`Entities`] in
+    /// place, returning the removed entities. UIDs which are not in the store
+    /// are ignored. Descendants of removed entities keep them as ancestors,
+    /// just as entities may have ancestors which are not in the store.
+    pub fn remove_entities<'a>(
+        &mut self,
+        uids: impl IntoIterator<Item = &'a EntityUID>,
+    ) -> Vec<Arc<Entity>> {
+        uids.into_iter()
+            .filter_map(|uid| {
+                let removed = self.entities.remove(uid)?;
+                if let hash_map::Entry::Occupied(mut of_type) =
+                    self.uids_by_type.entry(uid.entity_type().clone())
+                {
+                    of_type.get_mut().remove(uid);
+                    if of_type.get().is_empty() {
+                        of_type.remove();
+                    }
+                }
+                Some(removed)
+            })
+            .collect()
+    }
+
     /// Create an `Entities` object with the given entities.
     ///
     /// If `schema` is present, then action entities from that schema will also
@@ -217,11 +462,76 @@ impl Entities {
             );
         }
         Ok(Self {
+            uids_by_type: index_by_type(entity_map.keys()),
             entities: entity_map,
             mode: Mode::default(),
         })
     }
 
+    /// Create an `Entities` object with the given entities, like
+    /// [`Entities::from_entities`] with a `schema`, except that entities which
+    /// do not conform to the schema are kept rather than refused. Every way in
+    /// which they do not conform is returned alongside the `Entities` as a
+    /// warning, in order of entity UID.
+    ///
+    /// Action entities from `schema` are added, replacing any action entities
+    /// with the same UID in `entities`.
+    ///
+    /// # Errors
+    /// - [`EntitiesError::Duplicate`] if there are any duplicate entities in `entities`
+    /// - [`EntitiesError::TransitiveClosureError`] if `tc_computation ==
+    ///   TCComputation::EnforceAlreadyComputed` and the entities are not transitivly closed
+    pub fn from_entities_with_warnings(
+        entities: impl IntoIterator<Item = Entity>,
+        schema: &impl Schema,
+        tc_computation: TCComputation,
+        extensions: &Extensions<'_>,
+    ) -> Result<(Self, Vec<conformance::err::EntitySchemaConformanceError>)> {
+        let entities = Self::from_entities(
+            entities,
+            None::<&NoEntitiesSchema>,
+            tc_computation,
+            extensions,
+        )?;
+        let warnings = entities.conformance_errors(schema, extensions, usize::MAX);
+        let mut entity_map = entities.entities;
+        entity_map.extend(
+            schema
+                .action_entities()
+                .into_iter()
+                .map(|e: Arc<Entity>| (e.uid().clone(), e)),
+        );
+        Ok((
+            Self {
+                uids_by_type: index_by_type(entity_map.keys()),
+                entities: entity_map,
+                mode: Mode::default(),
+            },
+            warnings,
+        ))
+    }
+
+    /// Check every entity in this [`Entities`] against `schema`, returning
+    /// all of the conformance errors found instead of only the first, so that
+    /// they can be fixed in one pass. At most `max_errors` errors are
+    /// returned. Entities are checked in order of their UIDs, so which errors
+    /// are returned when there are more than `max_errors` is deterministic.
+    pub fn conformance_errors(
+        &self,
+        schema: &impl Schema,
+        extensions: &Extensions<'_>,
+        max_errors: usize,
+    ) -> Vec<conformance::err::EntitySchemaConformanceError> {
+        let checker = EntitySchemaConformanceChecker::new(schema, extensions);
+        let mut uids = self.entities.keys().collect::<Vec<_>>();
+        uids.sort_unstable();
+        uids.into_iter()
+            .filter_map(|uid| self.entities.get(uid))
+            .flat_map(|entity| checker.entity_errors(entity))
+            .take(max_errors)
+            .collect()
+    }
+
     /// Convert an `Entities` object into a JSON value suitable for parsing in
     /// via `EntityJsonParser`.
     ///
@@ -258,20 +568,6 @@ impl Entities {
             .map_err(Into::into)
     }
 
-    fn get_entities_by_entity_type(&self) -> HashMap<EntityType, Vec<&Entity>> {
-        let mut entities_by_type: HashMap<EntityType, Vec<&Entity>> = HashMap::new();
-        for entity in self.iter() {
-            let euid = entity.uid();
-            let entity_type = euid.entity_type();
-            if let Some(entities) = entities_by_type.get_mut(entity_type) {
-                entities.push(entity);
-            } else {
-                entities_by_type.insert(entity_type.clone(), Vec::from([entity]));
-            }
-        }
-        entities_by_type
-    }
-
     /// Write entities into a DOT graph
     pub fn to_dot_str(&self) -> String {
         let mut dot_str = String::new();
@@ -291,14 +587,12 @@ impl Entities {
         }
 
         // write clusters (subgraphs)
-        let entities_by_type = self.get_entities_by_entity_type();
-
-        for (et, entities) in entities_by_type {
+        for et in self.entity_types() {
             dot_str.push_str(&format!(
                 "\tsubgraph \"cluster_{et}\" {{\n\t\tlabel={}\n",
                 to_dot_id(&et)
             ));
-            for entity in entities {
+            for entity in self.iter_of_type(et) {
                 let euid = to_dot_id(&entity.uid());
                 let label = format!(r#"[label={}]"#, to_dot_id(&entity.uid().eid().escaped()));
                 dot_str.push_str(&format!("\t\t{euid} {label}\n"));
@@ -322,6 +616,20 @@ impl Entities {
     }
 }
 
+/// Group the given `EntityUID`s by entity type
+fn index_by_type<'a>(
+    uids: impl IntoIterator<Item = &'a EntityUID>,
+) -> HashMap<EntityType, HashSet<EntityUID>> {
+    let mut index: HashMap<EntityType, HashSet<EntityUID>> = HashMap::new();
+    for uid in uids {
+        index
+            .entry(uid.entity_type().clone())
+            .or_default()
+            .insert(uid.clone());
+    }
+    index
+}
+
 /// Create a map from EntityUids to Entities, erroring if there are any duplicates
 fn create_entity_map(
     es: impl Iterator<Item = Arc<Entity>>,
@@ -511,7 +819,7 @@ pub enum TCComputation {
 mod json_parsing_tests {
 
     use super::*;
-    use crate::{extensions::Extensions, test_utils::*, transitive_closure::TcError};
+    use crate::{extensions::Extensions, test_utils::*};
     use cool_asserts::assert_matches;
 
     #[test]
@@ -1965,6 +2273,7 @@ mod json_parsing_tests {
 #[allow(clippy::panic)]
 mod entities_tests {
     use super::*;
+    use crate::evaluator::Evaluator;
 
     #[test]
     fn empty_entities() {
@@ -2047,6 +2356,212 @@ mod entities_tests {
         )
         .expect("Should have succeeded");
     }
+
+    #[test]
+    fn upsert_entities_in_place() {
+        // Hierarchy
+        // a -> b -> c
+        // d -> e
+        // then b is moved from c to e, and d is removed
+        let mut a = Entity::with_uid(EntityUID::with_eid("a"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("b"));
+        let c = Entity::with_uid(EntityUID::with_eid("c"));
+        let mut d = Entity::with_uid(EntityUID::with_eid("d"));
+        let e = Entity::with_uid(EntityUID::with_eid("e"));
+        a.add_ancestor(EntityUID::with_eid("b"));
+        b.add_ancestor(EntityUID::with_eid("c"));
+        d.add_ancestor(EntityUID::with_eid("e"));
+        let mut es = Entities::from_entities(
+            [a.clone(), b, c, d, e],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        let unaffected = Arc::clone(es.entities.get(&EntityUID::with_eid("c")).unwrap());
+
+        let mut b = Entity::with_uid(EntityUID::with_eid("b"));
+        b.add_ancestor(EntityUID::with_eid("e"));
+        es.upsert_entities(
+            [a, b],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to upsert entities");
+        let removed = es.remove_entities([&EntityUID::with_eid("d")]);
+        assert_eq!(
+            removed.iter().map(|e| e.uid()).collect::<Vec<_>>(),
+            vec![&EntityUID::with_eid("d")]
+        );
+
+        let a = es.entity(&EntityUID::with_eid("a")).unwrap();
+        assert!(a.is_descendant_of(&EntityUID::with_eid("b")));
+        assert!(a.is_descendant_of(&EntityUID::with_eid("e")));
+        assert!(!a.is_descendant_of(&EntityUID::with_eid("c")));
+        assert!(matches!(
+            es.entity(&EntityUID::with_eid("d")),
+            Dereference::NoSuchEntity
+        ));
+        assert!(Arc::ptr_eq(
+            &unaffected,
+            es.entities.get(&EntityUID::with_eid("c")).unwrap()
+        ));
+        assert_eq!(es.iter().count(), 4);
+    }
+
+    #[test]
+    fn upsert_entities_updates_descendants() {
+        // Hierarchy
+        // user -> g1 -> g2
+        // g3
+        // then g1 is moved from g2 to g3
+        let user = EntityUID::with_eid("user");
+        let mut u = Entity::with_uid(user.clone());
+        let mut g1 = Entity::with_uid(EntityUID::with_eid("g1"));
+        u.add_ancestor(EntityUID::with_eid("g1"));
+        g1.add_ancestor(EntityUID::with_eid("g2"));
+        let mut es = Entities::from_entities(
+            [
+                u,
+                g1,
+                Entity::with_uid(EntityUID::with_eid("g2")),
+                Entity::with_uid(EntityUID::with_eid("g3")),
+            ],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+
+        let mut g1 = Entity::with_uid(EntityUID::with_eid("g1"));
+        g1.add_ancestor(EntityUID::with_eid("g3"));
+        // With TC enforced, `user` is missing its new ancestor
+        match es.clone().upsert_entities(
+            [g1.clone()],
+            None::<&NoEntitiesSchema>,
+            TCComputation::EnforceAlreadyComputed,
+            Extensions::all_available(),
+        ) {
+            Ok(_) => panic!("Should have detected the missing ancestor"),
+            Err(EntitiesError::TransitiveClosureError(_)) => (),
+            Err(_) => panic!("Wrong Error!"),
+        };
+        es.upsert_entities(
+            [g1],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to upsert entities");
+
+        let u = es.entity(&user).unwrap();
+        assert!(u.is_descendant_of(&EntityUID::with_eid("g1")));
+        assert!(u.is_descendant_of(&EntityUID::with_eid("g3")));
+        let request = Request::new(
+            (user, None),
+            (EntityUID::with_eid("action"), None),
+            (EntityUID::with_eid("resource"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let eval = Evaluator::new(request, &es, Extensions::none());
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::is_in(
+                Expr::var(Var::Principal),
+                Expr::val(EntityUID::with_eid("g3")),
+            ))
+            .unwrap(),
+            Value::from(true)
+        );
+    }
+
+    #[test]
+    fn upsert_entities_detects_cycles() {
+        // Hierarchy
+        // a -> b
+        // then b is given the parent a
+        let mut a = Entity::with_uid(EntityUID::with_eid("a"));
+        let b = Entity::with_uid(EntityUID::with_eid("b"));
+        a.add_ancestor(EntityUID::with_eid("b"));
+        let mut es = Entities::from_entities(
+            [a, b],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+
+        let mut b = Entity::with_uid(EntityUID::with_eid("b"));
+        b.add_ancestor(EntityUID::with_eid("a"));
+        match es.upsert_entities(
+            [b],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        ) {
+            Ok(_) => panic!("Should have detected a cycle"),
+            Err(EntitiesError::TransitiveClosureError(_)) => (),
+            Err(_) => panic!("Wrong Error!"),
+        };
+    }
+
+    #[test]
+    fn index_by_type() {
+        let alice = EntityUID::with_eid_and_type("User", "alice").unwrap();
+        let bob = EntityUID::with_eid_and_type("User", "bob").unwrap();
+        let photo = EntityUID::with_eid_and_type("Photo", "p").unwrap();
+        let user_ty = alice.entity_type().clone();
+        let photo_ty = photo.entity_type().clone();
+        let es = Entities::from_entities(
+            [
+                Entity::with_uid(alice.clone()),
+                Entity::with_uid(photo.clone()),
+            ],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        assert_eq!(
+            es.iter_of_type(&user_ty)
+                .map(Entity::uid)
+                .collect::<Vec<_>>(),
+            vec![&alice]
+        );
+        let es = es
+            .add_entities(
+                [Arc::new(Entity::with_uid(bob.clone()))],
+                None::<&NoEntitiesSchema>,
+                TCComputation::ComputeNow,
+                Extensions::all_available(),
+            )
+            .expect("Failed to add entities");
+        assert_eq!(
+            es.iter_of_type(&user_ty)
+                .map(Entity::uid)
+                .collect::<HashSet<_>>(),
+            HashSet::from([&alice, &bob])
+        );
+        assert_eq!(
+            es.iter_of_type(&photo_ty)
+                .map(Entity::uid)
+                .collect::<Vec<_>>(),
+            vec![&photo]
+        );
+        assert_eq!(
+            es.entity_types().collect::<HashSet<_>>(),
+            HashSet::from([&user_ty, &photo_ty])
+        );
+        assert_eq!(
+            es.find(|e| e.uid().eid().escaped() == "bob")
+                .map(Entity::uid)
+                .collect::<Vec<_>>(),
+            vec![&bob]
+        );
+    }
 }
 
 // PANIC SAFETY: Unit Test Code
@@ -2504,7 +3019,7 @@ mod schema_based_parsing_tests {
                         "isFullTime": true,
                         "numDirectReports": 3,
                         "department": "Sales",
-                        "manager": "34FB87",
+                        "manager": 34,
                         "hr_contacts": [
                             { "type": "HR", "id": "aaaaa" },
                             { "type": "HR", "id": "bbbbb" }
@@ -2533,7 +3048,7 @@ mod schema_based_parsing_tests {
                 &entitiesjson,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error("error during entity deserialization")
-                    .source(r#"in attribute `manager` on `Employee::"12UA45"`, expected a literal entity reference, but got `"34FB87"`"#)
+                    .source(r#"in attribute `manager` on `Employee::"12UA45"`, expected a literal entity reference, but got `34`"#)
                     .help(r#"literal entity references can be made with `{ "type": "SomeType", "id": "SomeId" }`"#)
                     .build()
             );
@@ -2631,6 +3146,121 @@ mod schema_based_parsing_tests {
         });
     }
 
+    /// the employee entity used in most of these tests, with the given
+    /// `manager` and `hr_contacts`
+    fn employee_json(
+        manager: serde_json::Value,
+        hr_contacts: serde_json::Value,
+    ) -> serde_json::Value {
+        json!(
+            [
+                {
+                    "uid": { "type": "Employee", "id": "12UA45" },
+                    "attrs": {
+                        "isFullTime": true,
+                        "numDirectReports": 3,
+                        "department": "Sales",
+                        "manager": manager,
+                        "hr_contacts": hr_contacts,
+                        "json_blob": {
+                            "inner1": false,
+                            "inner2": "-*/",
+                            "inner3": { "innerinner": "09AE76" },
+                        },
+                        "home_ip": "222.222.222.101",
+                        "work_ip": { "fn": "ip", "arg": "2.2.2.0/24" },
+                        "trust_score": "5.7",
+                        "tricky": { "type": "Employee", "id": "34FB87" }
+                    },
+                    "parents": []
+                }
+            ]
+        )
+    }
+
+    #[cfg(all(feature = "decimal", feature = "ipaddr"))]
+    /// entity references given as strings are coerced to the expected type
+    #[test]
+    fn entity_refs_as_strings() {
+        let eparser = EntityJsonParser::new(
+            Some(&MockSchema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        );
+        for manager in [json!("34FB87"), json!(r#"Employee::"34FB87""#)] {
+            let entitiesjson = employee_json(manager, json!(["aaaaa", r#"HR::"bbbbb""#]));
+            let parsed = eparser
+                .from_json_value(entitiesjson)
+                .unwrap_or_else(|e| panic!("{:?}", &miette::Report::new(e)));
+            let parsed = parsed
+                .entity(&r#"Employee::"12UA45""#.parse().unwrap())
+                .expect("that should be the employee id");
+            assert_eq!(
+                parsed.get("manager"),
+                Some(&PartialValue::Value(Value::from(
+                    r#"Employee::"34FB87""#.parse::<EntityUID>().unwrap()
+                ))),
+            );
+            assert_eq!(
+                parsed.get("hr_contacts"),
+                Some(&PartialValue::Value(Value::set(
+                    [
+                        Value::from(r#"HR::"aaaaa""#.parse::<EntityUID>().unwrap()),
+                        Value::from(r#"HR::"bbbbb""#.parse::<EntityUID>().unwrap()),
+                    ],
+                    None
+                ))),
+            );
+            assert_matches!(parsed.get("json_blob"), Some(PartialValue::Value(Value { value: ValueKind::Record(record), .. })) => {
+                assert_matches!(record.get("inner3"), Some(Value { value: ValueKind::Record(inner3), .. }) => {
+                    assert_eq!(
+                        inner3.get("innerinner"),
+                        Some(&Value::from(r#"Employee::"09AE76""#.parse::<EntityUID>().unwrap()))
+                    );
+                });
+            });
+        }
+    }
+
+    #[cfg(all(feature = "decimal", feature = "ipaddr"))]
+    /// an entity reference given as a string must have the expected type
+    #[test]
+    fn entity_ref_string_type_mismatch() {
+        let entitiesjson = employee_json(json!(r#"HR::"34FB87""#), json!([]));
+        let eparser = EntityJsonParser::new(
+            Some(&MockSchema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        );
+        assert_matches!(eparser.from_json_value(entitiesjson.clone()), Err(e) => {
+            expect_err(
+                &entitiesjson,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error("error during entity deserialization")
+                    .source(r#"in attribute `manager` on `Employee::"12UA45"`, type mismatch: value was expected to have type `Employee`, but it actually has type (entity of type `HR`): `HR::"34FB87"`"#)
+                    .build()
+            );
+        });
+    }
+
+    #[cfg(all(feature = "decimal", feature = "ipaddr"))]
+    /// an entity reference given as a string in Cedar syntax must parse
+    #[test]
+    fn entity_ref_string_parse_error() {
+        let entitiesjson = employee_json(json!(r#"Employee::34FB87"#), json!([]));
+        let eparser = EntityJsonParser::new(
+            Some(&MockSchema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        );
+        assert_matches!(
+            eparser.from_json_value(entitiesjson),
+            Err(EntitiesError::Deserialization(
+                json::err::JsonDeserializationError::ParseEscape(_)
+            ))
+        );
+    }
+
     #[cfg(all(feature = "decimal", feature = "ipaddr"))]
     /// type mismatch where we're expecting an extension type and get a
     /// different extension type
@@ -3192,7 +3822,7 @@ mod schema_based_parsing_tests {
                 &entitiesjson,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error("entity does not conform to the schema")
-                    .source(r#"definition of action `Action::"view"` does not match its schema declaration"#)
+                    .source(r#"definition of action `Action::"view"` does not match its schema declaration: attribute `foo` differs"#)
                     .help(r#"to use the schema's definition of `Action::"view"`, simply omit it from the entities input data"#)
                     .build()
             );
@@ -3225,7 +3855,7 @@ mod schema_based_parsing_tests {
                 &entitiesjson,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error("entity does not conform to the schema")
-                    .source(r#"definition of action `Action::"view"` does not match its schema declaration"#)
+                    .source(r#"definition of action `Action::"view"` does not match its schema declaration: attribute `foo` differs"#)
                     .help(r#"to use the schema's definition of `Action::"view"`, simply omit it from the entities input data"#)
                     .build()
             );
@@ -3256,7 +3886,7 @@ mod schema_based_parsing_tests {
                 &entitiesjson,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error("entity does not conform to the schema")
-                    .source(r#"definition of action `Action::"view"` does not match its schema declaration"#)
+                    .source(r#"definition of action `Action::"view"` does not match its schema declaration: attribute `foo` differs"#)
                     .help(r#"to use the schema's definition of `Action::"view"`, simply omit it from the entities input data"#)
                     .build()
             );
@@ -3290,7 +3920,7 @@ mod schema_based_parsing_tests {
                 &entitiesjson,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error("entity does not conform to the schema")
-                    .source(r#"definition of action `Action::"view"` does not match its schema declaration"#)
+                    .source(r#"definition of action `Action::"view"` does not match its schema declaration: attribute `foo` differs"#)
                     .help(r#"to use the schema's definition of `Action::"view"`, simply omit it from the entities input data"#)
                     .build()
             );
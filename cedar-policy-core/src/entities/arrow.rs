@@ -0,0 +1,318 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Loading entities from Arrow record batches and Parquet files, with the
+//! same [`CsvMapping`] from columns to the type, id, attributes, and parents
+//! of entities as CSV data.
+//!
+//! Boolean, integer, string, list, and struct columns are read as the
+//! corresponding JSON values, and converted to the attribute types declared
+//! in the schema like entity JSON data. In addition, strings are converted
+//! like the cells of CSV data, so a string column may hold, e.g., entity ids
+//! or the arguments of extension constructors. Columns of other types, like
+//! floating point, decimal, and date columns, are read as their text in
+//! Arrow's display format.
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int64Type;
+use arrow_array::{Array, RecordBatch};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{ArrowError, DataType};
+
+use super::csv::{Cell, CsvError, CsvErrors, CsvMapping, EntityCsvParser};
+use super::{NoEntitiesSchema, Schema};
+use crate::ast::Entity;
+use crate::extensions::Extensions;
+
+/// Read every value of `array` as JSON, with nulls as `null`. Boolean,
+/// integer, string, list, and struct values are read as the corresponding
+/// JSON values, and other values as their text in Arrow's display format.
+pub fn array_to_json(array: &dyn Array) -> Result<Vec<serde_json::Value>, ArrowError> {
+    let values = match array.data_type() {
+        DataType::Boolean => array
+            .as_boolean()
+            .iter()
+            .map(|v| v.map_or(serde_json::Value::Null, Into::into))
+            .collect(),
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32 => arrow_cast::cast(array, &DataType::Int64)?
+            .as_primitive::<Int64Type>()
+            .iter()
+            .map(|v| v.map_or(serde_json::Value::Null, Into::into))
+            .collect(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            arrow_cast::cast(array, &DataType::Utf8)?
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.map_or(serde_json::Value::Null, Into::into))
+                .collect()
+        }
+        DataType::List(_) => lists_to_json(array, array.as_list::<i32>().iter())?,
+        DataType::LargeList(_) => lists_to_json(array, array.as_list::<i64>().iter())?,
+        DataType::FixedSizeList(_, _) => lists_to_json(array, array.as_fixed_size_list().iter())?,
+        DataType::Struct(fields) => {
+            let columns = array
+                .as_struct()
+                .columns()
+                .iter()
+                .map(|column| array_to_json(column))
+                .collect::<Result<Vec<_>, _>>()?;
+            (0..array.len())
+                .map(|i| {
+                    if array.is_null(i) {
+                        return serde_json::Value::Null;
+                    }
+                    fields
+                        .iter()
+                        .zip(&columns)
+                        .filter_map(|(field, values)| {
+                            let value = values.get(i)?;
+                            (!value.is_null()).then(|| (field.name().clone(), value.clone()))
+                        })
+                        .collect::<serde_json::Map<_, _>>()
+                        .into()
+                })
+                .collect()
+        }
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, &FormatOptions::default())?;
+            (0..array.len())
+                .map(|i| {
+                    if array.is_null(i) {
+                        serde_json::Value::Null
+                    } else {
+                        formatter.value(i).to_string().into()
+                    }
+                })
+                .collect()
+        }
+    };
+    Ok(values)
+}
+
+/// Read every list in `lists`, the values of the list array `array`, as JSON
+fn lists_to_json(
+    array: &dyn Array,
+    lists: impl Iterator<Item = Option<arrow_array::ArrayRef>>,
+) -> Result<Vec<serde_json::Value>, ArrowError> {
+    lists
+        .enumerate()
+        .map(|(i, list)| match list {
+            Some(list) if !array.is_null(i) => array_to_json(&list).map(Into::into),
+            _ => Ok(serde_json::Value::Null),
+        })
+        .collect()
+}
+
+/// Struct used to parse entities from Arrow record batches and Parquet files
+#[derive(Debug, Clone)]
+pub struct EntityArrowParser<'e, 's, S: Schema = NoEntitiesSchema> {
+    /// Parser for the rows of the record batches, as for CSV data
+    rows: EntityCsvParser<'e, 's, S>,
+}
+
+impl<'e, 's, S: Schema> EntityArrowParser<'e, 's, S> {
+    /// Create a new `EntityArrowParser`. If a `schema` is given, attribute
+    /// values are converted to the types it declares, and the parsed entities
+    /// must conform to it.
+    pub fn new(schema: Option<&'s S>, extensions: &'e Extensions<'e>) -> Self {
+        Self {
+            rows: EntityCsvParser::new(schema, extensions),
+        }
+    }
+
+    /// Parse the entities declared by the rows of `batches`, which must all
+    /// have the same columns, reporting every error found rather than only
+    /// the first. In errors, the first row of the first batch is row 1.
+    ///
+    /// Like [`EntityCsvParser::from_csv_str`], this does not add the action
+    /// entities of the schema or compute the transitive closure of the
+    /// parents of the entities.
+    pub fn from_record_batches<'a>(
+        &self,
+        batches: impl IntoIterator<Item = &'a RecordBatch>,
+        mapping: &CsvMapping,
+    ) -> Result<Vec<Entity>, CsvErrors> {
+        let mut batches = batches.into_iter().peekable();
+        let Some(first) = batches.peek() else {
+            return Ok(Vec::new());
+        };
+        let header = first
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        for batch in batches {
+            let columns = batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect::<Vec<_>>();
+            if columns != header {
+                return Err(CsvError::new(
+                    Some(rows.len() + 1),
+                    None,
+                    format!(
+                        "expected the columns {} as in the first batch, found {}",
+                        header.join(", "),
+                        columns.join(", ")
+                    ),
+                )
+                .into());
+            }
+            let mut values = batch
+                .columns()
+                .iter()
+                .zip(&header)
+                .map(|(column, name)| {
+                    array_to_json(column)
+                        .map(Vec::into_iter)
+                        .map_err(|e| CsvError::new(None, Some(name), e.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for _ in 0..batch.num_rows() {
+                let cells = values
+                    .iter_mut()
+                    .map(|column| Cell::Value(column.next().unwrap_or_default()))
+                    .collect();
+                rows.push((rows.len() + 1, cells));
+            }
+        }
+        self.rows.parse_rows(&header, rows, mapping)
+    }
+
+    /// Parse the entities declared by the rows of the Parquet file `reader`,
+    /// like [`EntityArrowParser::from_record_batches`]
+    #[cfg(feature = "parquet")]
+    pub fn from_parquet(
+        &self,
+        reader: impl parquet::file::reader::ChunkReader + 'static,
+        mapping: &CsvMapping,
+    ) -> Result<Vec<Entity>, CsvErrors> {
+        let read_err = |e: &dyn std::fmt::Display| {
+            CsvErrors::from(CsvError::new(
+                None,
+                None,
+                format!("failed to read Parquet file: {e}"),
+            ))
+        };
+        let batches =
+            parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(reader)
+                .and_then(|builder| builder.build())
+                .map_err(|e| read_err(&e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| read_err(&e))?;
+        self.from_record_batches(&batches, mapping)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{EntityUID, Value};
+    use arrow_array::builder::{ListBuilder, StringBuilder};
+    use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int32Array, StringArray};
+    use cool_asserts::assert_matches;
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        let mut groups = ListBuilder::new(StringBuilder::new());
+        groups.values().append_value("admins");
+        groups.values().append_value("staff");
+        groups.append(true);
+        groups.append(false);
+        RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(StringArray::from(vec!["alice", "bob"])) as ArrayRef,
+            ),
+            ("age", Arc::new(Int32Array::from(vec![Some(42), None]))),
+            ("admin", Arc::new(BooleanArray::from(vec![true, false]))),
+            ("score", Arc::new(Float64Array::from(vec![1.5, 2.0]))),
+            ("groups", Arc::new(groups.finish())),
+        ])
+        .unwrap()
+    }
+
+    fn mapping() -> CsvMapping {
+        let mut mapping = CsvMapping::new("id");
+        mapping.entity_type = Some("User".into());
+        for attr in ["age", "admin", "score"] {
+            mapping.attributes.insert(attr.into(), attr.into());
+        }
+        mapping.parents.push(super::super::csv::CsvParentColumn {
+            column: "groups".into(),
+            entity_type: Some("Group".into()),
+        });
+        mapping
+    }
+
+    fn parse(batches: &[RecordBatch]) -> Result<Vec<Entity>, CsvErrors> {
+        EntityArrowParser::<NoEntitiesSchema>::new(None, Extensions::all_available())
+            .from_record_batches(batches, &mapping())
+    }
+
+    #[test]
+    fn parses_record_batches() {
+        let entities = parse(&[batch(), batch().slice(0, 1)]);
+        assert_matches!(entities, Err(errs) => {
+            assert_eq!(
+                errs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                vec![r#"row 3: duplicate entity `User::"alice"`, also declared in row 1"#]
+            );
+        });
+
+        let entities = parse(&[batch()]).unwrap();
+        assert_matches!(entities.as_slice(), [alice, bob] => {
+            assert_eq!(alice.get("age"), Some(&Value::from(42).into()));
+            assert_eq!(alice.get("admin"), Some(&Value::from(true).into()));
+            assert_eq!(alice.get("score"), Some(&Value::from("1.5").into()));
+            assert!(alice.is_descendant_of(&EntityUID::with_eid_and_type("Group", "staff").unwrap()));
+            assert_eq!(bob.get("age"), None);
+            assert_eq!(bob.ancestors().count(), 0);
+        });
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parses_parquet() {
+        let path =
+            std::env::temp_dir().join(format!("cedar-entities-{}.parquet", std::process::id()));
+        let batch = batch();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(
+            std::fs::File::create(&path).unwrap(),
+            batch.schema(),
+            None,
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let entities =
+            EntityArrowParser::<NoEntitiesSchema>::new(None, Extensions::all_available())
+                .from_parquet(std::fs::File::open(&path).unwrap(), &mapping());
+        std::fs::remove_file(&path).unwrap();
+        let entities = entities.unwrap();
+        assert_eq!(entities.len(), 2);
+    }
+}
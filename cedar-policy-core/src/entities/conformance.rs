@@ -18,10 +18,12 @@ use std::collections::BTreeMap;
 
 use super::{json::err::TypeMismatchError, EntityTypeDescription, Schema, SchemaType};
 use crate::ast::{
-    BorrowedRestrictedExpr, Entity, PartialValue, PartialValueToRestrictedExprError, RestrictedExpr,
+    BorrowedRestrictedExpr, Entity, EntityUID, PartialValue, PartialValueToRestrictedExprError,
+    RestrictedExpr,
 };
 use crate::entities::ExprKind;
 use crate::extensions::{ExtensionFunctionLookupError, Extensions};
+use itertools::Itertools;
 use miette::Diagnostic;
 use smol_str::SmolStr;
 use thiserror::Error;
@@ -47,35 +49,52 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
     /// Validate an entity against the schema, returning an
     /// [`EntitySchemaConformanceError`] if it does not comply.
     pub fn validate_entity(&self, entity: &Entity) -> Result<(), EntitySchemaConformanceError> {
+        match self.entity_errors(entity).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate an entity against the schema, returning every
+    /// [`EntitySchemaConformanceError`] found rather than only the first.
+    /// The result is empty if the entity complies with the schema.
+    pub fn entity_errors(&self, entity: &Entity) -> Vec<EntitySchemaConformanceError> {
+        let mut errs = Vec::new();
         let uid = entity.uid();
         let etype = uid.entity_type();
         if etype.is_action() {
-            let schema_action = self
-                .schema
-                .action(uid)
-                .ok_or_else(|| EntitySchemaConformanceError::undeclared_action(uid.clone()))?;
-            // check that the action exactly matches the schema's definition
-            if !entity.deep_eq(&schema_action) {
-                return Err(EntitySchemaConformanceError::action_declaration_mismatch(
-                    uid.clone(),
-                ));
+            match self.schema.action(uid) {
+                None => errs.push(EntitySchemaConformanceError::undeclared_action(uid.clone())),
+                Some(schema_action) => {
+                    // check that the action exactly matches the schema's definition
+                    if !entity.deep_eq(&schema_action) {
+                        errs.push(EntitySchemaConformanceError::action_declaration_mismatch(
+                            uid.clone(),
+                            first_differing_attr(entity, &schema_action),
+                        ));
+                    }
+                }
             }
         } else {
-            let schema_etype = self.schema.entity_type(etype).ok_or_else(|| {
+            let Some(schema_etype) = self.schema.entity_type(etype) else {
                 let suggested_types = self
                     .schema
                     .entity_types_with_basename(&etype.name().basename())
                     .collect();
-                UnexpectedEntityTypeError {
-                    uid: uid.clone(),
-                    suggested_types,
-                }
-            })?;
+                errs.push(
+                    UnexpectedEntityTypeError {
+                        uid: uid.clone(),
+                        suggested_types,
+                    }
+                    .into(),
+                );
+                return errs;
+            };
             // Ensure that all required attributes for `etype` are actually
             // included in `entity`
             for required_attr in schema_etype.required_attrs() {
-                if entity.get(&required_attr).is_none() {
-                    return Err(EntitySchemaConformanceError::missing_entity_attr(
+                if !entity.has_attr(&required_attr) {
+                    errs.push(EntitySchemaConformanceError::missing_entity_attr(
                         uid.clone(),
                         required_attr,
                     ));
@@ -83,13 +102,20 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
             }
             // For each attribute that actually appears in `entity`, ensure it
             // complies with the schema
-            for (attr, val) in entity.attrs() {
+            for attr_val in entity.try_attrs() {
+                let (attr, val) = match attr_val {
+                    Ok(attr_val) => attr_val,
+                    Err(err) => {
+                        errs.push(err.into());
+                        continue;
+                    }
+                };
                 match schema_etype.attr_type(attr) {
                     None => {
                         // `None` indicates the attribute shouldn't exist -- see
                         // docs on the `attr_type()` trait method
                         if !schema_etype.open_attributes() {
-                            return Err(EntitySchemaConformanceError::unexpected_entity_attr(
+                            errs.push(EntitySchemaConformanceError::unexpected_entity_attr(
                                 uid.clone(),
                                 attr.clone(),
                             ));
@@ -102,20 +128,18 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
                         {
                             Ok(()) => {} // typecheck passes
                             Err(TypecheckError::TypeMismatch(err)) => {
-                                return Err(EntitySchemaConformanceError::type_mismatch(
+                                errs.push(EntitySchemaConformanceError::type_mismatch(
                                     uid.clone(),
                                     attr.clone(),
                                     err,
                                 ));
                             }
                             Err(TypecheckError::ExtensionFunctionLookup(err)) => {
-                                return Err(
-                                    EntitySchemaConformanceError::extension_function_lookup(
-                                        uid.clone(),
-                                        attr.clone(),
-                                        err,
-                                    ),
-                                );
+                                errs.push(EntitySchemaConformanceError::extension_function_lookup(
+                                    uid.clone(),
+                                    attr.clone(),
+                                    err,
+                                ));
                             }
                         }
                     }
@@ -123,25 +147,37 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
             }
             // For each ancestor that actually appears in `entity`, ensure the
             // ancestor type is allowed by the schema
-            for ancestor_euid in entity.ancestors() {
-                let ancestor_type = ancestor_euid.entity_type();
+            for ancestor_type in entity.ancestors().map(EntityUID::entity_type).unique() {
                 if schema_etype.allowed_parent_types().contains(ancestor_type) {
                     // note that `allowed_parent_types()` was transitively
                     // closed, so it's actually `allowed_ancestor_types()`
                     //
                     // thus, the check passes in this case
                 } else {
-                    return Err(EntitySchemaConformanceError::invalid_ancestor_type(
+                    errs.push(EntitySchemaConformanceError::invalid_ancestor_type(
                         uid.clone(),
                         ancestor_type.clone(),
                     ));
                 }
             }
         }
-        Ok(())
+        errs
     }
 }
 
+/// The first attribute (in name order) that `entity` and `schema_action`
+/// disagree on, including attributes only one of them has
+fn first_differing_attr(entity: &Entity, schema_action: &Entity) -> Option<SmolStr> {
+    let attrs: BTreeMap<_, _> = entity.attrs().collect();
+    let schema_attrs: BTreeMap<_, _> = schema_action.attrs().collect();
+    attrs
+        .keys()
+        .chain(schema_attrs.keys())
+        .sorted()
+        .find(|attr| attrs.get(*attr) != schema_attrs.get(*attr))
+        .map(|attr| (*attr).clone())
+}
+
 /// Check whether the given `PartialValue` typechecks with the given `SchemaType`.
 /// If the typecheck passes, return `Ok(())`.
 /// If the typecheck fails, return an appropriate `Err`.
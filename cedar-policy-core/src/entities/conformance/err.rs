@@ -15,7 +15,7 @@
  */
 //! This module cotnains errors around entities not conforming to schemas
 use super::TypeMismatchError;
-use crate::ast::{EntityType, EntityUID};
+use crate::ast::{EntityAttrEvaluationError, EntityType, EntityUID};
 use crate::extensions::ExtensionFunctionLookupError;
 use miette::Diagnostic;
 use smol_str::SmolStr;
@@ -70,6 +70,11 @@ pub enum EntitySchemaConformanceError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     ExtensionFunctionLookup(ExtensionFunctionLookup),
+    /// Computing the value of a lazy entity attribute failed, so it couldn't
+    /// be checked against the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    EntityAttrEvaluation(#[from] EntityAttrEvaluationError),
 }
 
 impl EntitySchemaConformanceError {
@@ -117,8 +122,8 @@ impl EntitySchemaConformanceError {
         Self::UndeclaredAction(UndeclaredAction { uid })
     }
 
-    pub(crate) fn action_declaration_mismatch(uid: EntityUID) -> Self {
-        Self::ActionDeclarationMismatch(ActionDeclarationMismatch { uid })
+    pub(crate) fn action_declaration_mismatch(uid: EntityUID, attr: Option<SmolStr>) -> Self {
+        Self::ActionDeclarationMismatch(ActionDeclarationMismatch { uid, attr })
     }
 
     pub(crate) fn extension_function_lookup(
@@ -160,13 +165,33 @@ pub struct ExtensionFunctionLookup {
 // Don't make fields `pub`, don't make breaking changes, and use caution
 // when adding public methods.
 #[derive(Debug, Error, Diagnostic)]
-#[error("definition of action `{uid}` does not match its schema declaration")]
+#[error("definition of action `{uid}` does not match its schema declaration{}", match .attr {
+    Some(attr) => format!(": attribute `{attr}` differs"),
+    None => String::new(),
+})]
 #[diagnostic(help(
     "to use the schema's definition of `{uid}`, simply omit it from the entities input data"
 ))]
 pub struct ActionDeclarationMismatch {
     /// Action whose definition mismatched between entity data and schema
     uid: EntityUID,
+    /// First attribute (in name order) whose value differs between entity
+    /// data and schema, if the mismatch is in the attributes
+    attr: Option<SmolStr>,
+}
+
+impl ActionDeclarationMismatch {
+    /// The action whose definition mismatched between entity data and schema
+    pub fn action(&self) -> &EntityUID {
+        &self.uid
+    }
+
+    /// The first attribute (in name order) whose value differs between the
+    /// entity data and the schema, or `None` if the attributes agree and the
+    /// mismatch is in the action's parents
+    pub fn attr(&self) -> Option<&SmolStr> {
+        self.attr.as_ref()
+    }
 }
 
 /// Encountered an action which was not declared in the schema
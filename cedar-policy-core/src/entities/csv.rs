@@ -0,0 +1,789 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Loading entities from CSV (or TSV) data, such as spreadsheet and data
+//! warehouse exports. Each row after the header row declares one entity, and
+//! a [`CsvMapping`] says which columns hold its type, id, attributes, and
+//! parents.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use super::conformance::EntitySchemaConformanceChecker;
+use super::json::err::JsonDeserializationErrorContext;
+use super::json::ValueParser;
+use super::{EntityTypeDescription, NoEntitiesSchema, Schema, SchemaType};
+use crate::ast::{Eid, Entity, EntityType, EntityUID};
+use crate::evaluator::RestrictedEvaluator;
+use crate::extensions::Extensions;
+
+/// Which columns of CSV data hold the type, id, attributes, and parents of
+/// the entity declared by each row
+///
+/// Cells are strings, so attribute values are converted to the attribute
+/// types declared in the schema: `Long` and `Bool` cells are parsed, entity
+/// cells hold either an entity uid or just the id of an entity of the declared
+/// type, extension cells hold the argument of the extension type's
+/// constructor (e.g., `10.0.0.1` for an `ipaddr`), set cells hold elements
+/// separated by [`CsvMapping::set_separator`], and record cells hold a JSON
+/// record. Without a schema, every attribute is a string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CsvMapping {
+    /// The entity type of every entity, if every row declares an entity of
+    /// the same type. Exactly one of `entity_type` and `type_column` must be
+    /// given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_type: Option<String>,
+    /// The column holding the entity type of the entity declared by each row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_column: Option<String>,
+    /// The column holding the entity id
+    pub id_column: String,
+    /// Map from columns to the attributes they hold. Columns which are not
+    /// mapped to an attribute, and are not the type, id, or a parent column,
+    /// are ignored. Empty cells leave the attribute unset, except for
+    /// attributes of set type, which are set to the empty set.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    /// Columns holding parents of the entity
+    #[serde(default)]
+    pub parents: Vec<CsvParentColumn>,
+    /// The character separating the cells of a row, `,` by default. Use `\t`
+    /// for TSV data.
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// The character separating the elements of sets, and parents, within a
+    /// cell, `;` by default
+    #[serde(default = "default_set_separator")]
+    pub set_separator: char,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_set_separator() -> char {
+    ';'
+}
+
+impl CsvMapping {
+    /// A mapping for CSV data with the entity id in column `id_column`, and
+    /// no type, attribute, or parent columns yet
+    pub fn new(id_column: impl Into<String>) -> Self {
+        Self {
+            entity_type: None,
+            type_column: None,
+            id_column: id_column.into(),
+            attributes: BTreeMap::new(),
+            parents: Vec::new(),
+            delimiter: default_delimiter(),
+            set_separator: default_set_separator(),
+        }
+    }
+}
+
+/// A column of CSV data holding parents of the entity declared by each row,
+/// separated by [`CsvMapping::set_separator`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CsvParentColumn {
+    /// The column
+    pub column: String,
+    /// The entity type of the parents, in which case the column holds their
+    /// ids. Otherwise, the column holds entity uids, like `Group::"admins"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_type: Option<String>,
+}
+
+/// An error in CSV or Arrow entity data, at a row and column of the data when it is
+/// specific to one
+#[derive(Debug, Clone, PartialEq, Eq, Error, Diagnostic)]
+#[error("{}{message}", location(*.row, .column.as_deref()))]
+pub struct CsvError {
+    /// The row, counting the header of CSV data as row 1, and the first row
+    /// of Arrow data as row 1
+    row: Option<usize>,
+    /// The column name
+    column: Option<SmolStr>,
+    /// What is wrong
+    message: String,
+}
+
+/// Describe the position of an error, for prefixing its message
+fn location(row: Option<usize>, column: Option<&str>) -> String {
+    match (row, column) {
+        (Some(row), Some(column)) => format!("row {row}, column `{column}`: "),
+        (Some(row), None) => format!("row {row}: "),
+        (None, Some(column)) => format!("column `{column}`: "),
+        (None, None) => String::new(),
+    }
+}
+
+impl CsvError {
+    pub(crate) fn new(
+        row: Option<usize>,
+        column: Option<&str>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            row,
+            column: column.map(SmolStr::from),
+            message: message.into(),
+        }
+    }
+
+    /// The row the error is in, if the error is specific to one row. The
+    /// header of CSV data is row 1, while the first row of Arrow data is row 1.
+    pub fn row(&self) -> Option<usize> {
+        self.row
+    }
+
+    /// The name of the column the error is in, if the error is specific to one
+    /// column
+    pub fn column(&self) -> Option<&str> {
+        self.column.as_deref()
+    }
+
+    /// What is wrong, without the position of the error
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Every error found in CSV or Arrow entity data. There is at least one.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Diagnostic)]
+#[error("failed to load entities: {}", .errors.first())]
+pub struct CsvErrors {
+    #[related]
+    errors: nonempty::NonEmpty<CsvError>,
+}
+
+impl CsvErrors {
+    /// Iterate over the errors, in order of their rows
+    pub fn iter(&self) -> impl Iterator<Item = &CsvError> {
+        self.errors.iter()
+    }
+
+    fn from_vec(errors: Vec<CsvError>) -> Option<Self> {
+        nonempty::NonEmpty::from_vec(errors).map(|errors| Self { errors })
+    }
+}
+
+impl From<CsvError> for CsvErrors {
+    fn from(error: CsvError) -> Self {
+        Self {
+            errors: nonempty::NonEmpty::new(error),
+        }
+    }
+}
+
+/// Split `src` into records, each a list of fields separated by `delimiter`.
+/// Fields quoted with `"` may contain delimiters and line breaks, and `""`
+/// for a quote. Empty lines are skipped.
+fn records(src: &str, delimiter: char) -> Result<Vec<Vec<String>>, CsvError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    // Whether the current field started with a quote, and whether it has
+    // been closed
+    let mut quoted = false;
+    let mut closed = false;
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        let row = records.len() + 1;
+        if quoted && !closed {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                field.push('"');
+            } else {
+                closed = true;
+            }
+        } else if c == delimiter {
+            record.push(std::mem::take(&mut field));
+            quoted = false;
+            closed = false;
+        } else if c == '\n' || c == '\r' {
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            if !record.is_empty() || !field.is_empty() || quoted {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            quoted = false;
+            closed = false;
+        } else if closed {
+            return Err(CsvError::new(
+                Some(row),
+                None,
+                format!("unexpected `{c}` after the closing quote of a field"),
+            ));
+        } else if c == '"' && field.is_empty() {
+            quoted = true;
+            closed = false;
+        } else {
+            field.push(c);
+        }
+    }
+    if quoted && !closed {
+        return Err(CsvError::new(
+            Some(records.len() + 1),
+            None,
+            "quoted field is never closed",
+        ));
+    }
+    if !record.is_empty() || !field.is_empty() || quoted {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Where the entity type of each row comes from
+enum TypeSource {
+    Fixed(EntityType),
+    Column(usize),
+}
+
+/// A [`CsvMapping`] with columns resolved to their indices in the header
+struct Columns {
+    ty: TypeSource,
+    id: usize,
+    attrs: Vec<(usize, SmolStr)>,
+    parents: Vec<(usize, Option<EntityType>)>,
+}
+
+impl Columns {
+    fn resolve(mapping: &CsvMapping, header: &[String]) -> Result<Self, CsvErrors> {
+        let mut errs = Vec::new();
+        let mut missing = Vec::new();
+        let mut column = |name: &str| {
+            let index = header.iter().position(|h| h == name);
+            if index.is_none() {
+                missing.push(CsvError::new(
+                    Some(1),
+                    Some(name),
+                    "the header has no such column",
+                ));
+            }
+            index
+        };
+        let ty = match (&mapping.entity_type, &mapping.type_column) {
+            (Some(ty), None) => parse_entity_type(ty).map(TypeSource::Fixed),
+            (None, Some(ty_column)) => column(ty_column).map(TypeSource::Column),
+            _ => {
+                errs.push(CsvError::new(
+                    None,
+                    None,
+                    "exactly one of the entity type and the type column must be given",
+                ));
+                None
+            }
+        };
+        let id = column(&mapping.id_column);
+        let attrs = mapping
+            .attributes
+            .iter()
+            .filter_map(|(col, attr)| Some((column(col)?, SmolStr::from(attr))))
+            .collect();
+        let parents = mapping
+            .parents
+            .iter()
+            .filter_map(|parent| {
+                let index = column(&parent.column)?;
+                match &parent.entity_type {
+                    Some(ty) => Some((index, Some(parse_entity_type(ty)?))),
+                    None => Some((index, None)),
+                }
+            })
+            .collect();
+        errs.append(&mut missing);
+        // Invalid entity types were skipped above, and are reported here
+        let mut bad_types = mapping
+            .entity_type
+            .iter()
+            .chain(
+                mapping
+                    .parents
+                    .iter()
+                    .filter_map(|p| p.entity_type.as_ref()),
+            )
+            .filter(|ty| parse_entity_type(ty).is_none())
+            .map(|ty| CsvError::new(None, None, format!("`{ty}` is not a valid entity type")))
+            .collect();
+        errs.append(&mut bad_types);
+        match (CsvErrors::from_vec(errs), ty, id) {
+            (None, Some(ty), Some(id)) => Ok(Self {
+                ty,
+                id,
+                attrs,
+                parents,
+            }),
+            (Some(errs), _, _) => Err(errs),
+            // Unreachable, since a missing type or id column is an error
+            (None, _, _) => Err(CsvError::new(None, None, "invalid mapping").into()),
+        }
+    }
+}
+
+fn parse_entity_type(ty: &str) -> Option<EntityType> {
+    ty.parse().ok()
+}
+
+/// Convert the text of a cell to the JSON value of an attribute with type
+/// `expected_ty`, for parsing with a [`ValueParser`]
+fn cell_to_json(
+    cell: &str,
+    expected_ty: Option<&SchemaType>,
+    set_separator: char,
+) -> Result<serde_json::Value, String> {
+    match expected_ty {
+        None | Some(SchemaType::String | SchemaType::Extension { .. }) => Ok(cell.into()),
+        Some(SchemaType::Bool) => match cell.trim().to_lowercase().as_str() {
+            "true" => Ok(true.into()),
+            "false" => Ok(false.into()),
+            _ => Err(format!("expected `true` or `false`, found `{cell}`")),
+        },
+        Some(SchemaType::Long) => cell
+            .trim()
+            .parse::<i64>()
+            .map(Into::into)
+            .map_err(|_| format!("expected an integer, found `{cell}`")),
+        Some(SchemaType::Entity { ty }) => {
+            let uid = match cell.trim().parse::<EntityUID>() {
+                Ok(uid) => uid,
+                Err(_) => EntityUID::from_components(ty.clone(), Eid::new(cell.trim()), None),
+            };
+            Ok(serde_json::json!({
+                "__entity": {
+                    "type": uid.entity_type().to_string(),
+                    "id": <Eid as AsRef<str>>::as_ref(uid.eid()),
+                }
+            }))
+        }
+        Some(SchemaType::Set { element_ty }) => cell
+            .split(set_separator)
+            .map(str::trim)
+            .filter(|element| !element.is_empty())
+            .map(|element| cell_to_json(element, Some(element_ty), set_separator))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Into::into),
+        Some(SchemaType::EmptySet) => Ok(serde_json::Value::Array(Vec::new())),
+        Some(SchemaType::Record { .. }) => serde_json::from_str(cell)
+            .map_err(|err| format!("expected a JSON record, found `{cell}`: {err}")),
+    }
+}
+
+/// A cell of tabular entity data: text in CSV data, or a JSON value
+/// converted from a typed value in Arrow data
+#[derive(Debug, Clone)]
+pub(crate) enum Cell {
+    Text(String),
+    Value(serde_json::Value),
+}
+
+impl Cell {
+    /// Empty text and nulls are empty
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Text(text) => text.is_empty(),
+            Self::Value(value) => value.is_null(),
+        }
+    }
+
+    /// The text of a cell holding a string or a number
+    fn text(&self) -> Option<String> {
+        match self {
+            Self::Text(text) => Some(text.clone()),
+            Self::Value(serde_json::Value::String(text)) => Some(text.clone()),
+            Self::Value(serde_json::Value::Number(n)) => Some(n.to_string()),
+            Self::Value(_) => None,
+        }
+    }
+
+    /// The items of a cell holding a list, or a string of items separated
+    /// by `set_separator`
+    fn items(&self, set_separator: char) -> Vec<String> {
+        match self {
+            Self::Value(serde_json::Value::Array(items)) => items
+                .iter()
+                .filter_map(|item| Self::Value(item.clone()).text())
+                .collect(),
+            _ => self
+                .text()
+                .unwrap_or_default()
+                .split(set_separator)
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Convert the cell to the JSON value of an attribute with type
+    /// `expected_ty`, for parsing with a [`ValueParser`]. Strings are
+    /// converted like the text of CSV cells.
+    fn to_json(
+        &self,
+        expected_ty: Option<&SchemaType>,
+        set_separator: char,
+    ) -> Result<serde_json::Value, String> {
+        match (self, expected_ty) {
+            (Self::Text(text) | Self::Value(serde_json::Value::String(text)), _) => {
+                cell_to_json(text, expected_ty, set_separator)
+            }
+            (
+                Self::Value(serde_json::Value::Array(items)),
+                Some(SchemaType::Set { element_ty }),
+            ) => items
+                .iter()
+                .map(|item| Self::Value(item.clone()).to_json(Some(element_ty), set_separator))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Into::into),
+            (Self::Value(value), _) => Ok(value.clone()),
+        }
+    }
+}
+
+/// Struct used to parse entities from CSV data
+#[derive(Debug, Clone)]
+pub struct EntityCsvParser<'e, 's, S: Schema = NoEntitiesSchema> {
+    /// The schema which determines the types of attribute values, and which
+    /// the entities must conform to
+    schema: Option<&'s S>,
+    /// Extensions which are active for the parsing
+    extensions: &'e Extensions<'e>,
+}
+
+impl<'e, 's, S: Schema> EntityCsvParser<'e, 's, S> {
+    /// Create a new `EntityCsvParser`. If a `schema` is given, attribute
+    /// values are converted to the types it declares, and the parsed entities
+    /// must conform to it.
+    pub fn new(schema: Option<&'s S>, extensions: &'e Extensions<'e>) -> Self {
+        Self { schema, extensions }
+    }
+
+    /// Parse the entities declared by the rows of the CSV data `src` after its
+    /// header row, reporting every error found rather than only the first.
+    ///
+    /// Unlike [`super::EntityJsonParser`], this does not add the action
+    /// entities of the schema or compute the transitive closure of the
+    /// parents of the entities.
+    pub fn from_csv_str(&self, src: &str, mapping: &CsvMapping) -> Result<Vec<Entity>, CsvErrors> {
+        let mut records = records(src, mapping.delimiter)?.into_iter();
+        let Some(header) = records.next() else {
+            return Ok(Vec::new());
+        };
+        let rows = (2..).zip(records.map(|record| record.into_iter().map(Cell::Text).collect()));
+        self.parse_rows(&header, rows, mapping)
+    }
+
+    /// Parse the entities declared by `rows`, each numbered and with a cell
+    /// for each column in `header`
+    pub(crate) fn parse_rows(
+        &self,
+        header: &[String],
+        rows: impl IntoIterator<Item = (usize, Vec<Cell>)>,
+        mapping: &CsvMapping,
+    ) -> Result<Vec<Entity>, CsvErrors> {
+        let columns = Columns::resolve(mapping, header)?;
+        let mut errs = Vec::new();
+        let mut entities = Vec::new();
+        let mut declared = HashMap::new();
+        for (row, record) in rows {
+            if record.len() != header.len() {
+                errs.push(CsvError::new(
+                    Some(row),
+                    None,
+                    format!(
+                        "expected {} cells as in the header, found {}",
+                        header.len(),
+                        record.len()
+                    ),
+                ));
+                continue;
+            }
+            match self.parse_row(row, &record, header, &columns, mapping) {
+                Ok(entity) => match declared.insert(entity.uid().clone(), row) {
+                    Some(first) => errs.push(CsvError::new(
+                        Some(row),
+                        None,
+                        format!(
+                            "duplicate entity `{}`, also declared in row {first}",
+                            entity.uid()
+                        ),
+                    )),
+                    None => entities.push(entity),
+                },
+                Err(mut row_errs) => errs.append(&mut row_errs),
+            }
+        }
+        match CsvErrors::from_vec(errs) {
+            Some(errs) => Err(errs),
+            None => Ok(entities),
+        }
+    }
+
+    /// Parse the entity declared by the row `row` with cells `record`
+    fn parse_row(
+        &self,
+        row: usize,
+        record: &[Cell],
+        header: &[String],
+        columns: &Columns,
+        mapping: &CsvMapping,
+    ) -> Result<Entity, Vec<CsvError>> {
+        let empty = Cell::Text(String::new());
+        let cell = |index: usize| {
+            let cell = record.get(index).unwrap_or(&empty);
+            let column = header.get(index).map(String::as_str);
+            let err = move |message: String| CsvError::new(Some(row), column, message);
+            (cell, err)
+        };
+        let ty = match &columns.ty {
+            TypeSource::Fixed(ty) => ty.clone(),
+            TypeSource::Column(index) => {
+                let (cell, err) = cell(*index);
+                let text = cell.text().unwrap_or_default();
+                parse_entity_type(text.trim())
+                    .ok_or_else(|| vec![err(format!("`{text}` is not a valid entity type"))])?
+            }
+        };
+        let (id, id_err) = cell(columns.id);
+        let id = match id.text() {
+            Some(id) if !id.is_empty() => id,
+            _ => return Err(vec![id_err("the entity id is empty".into())]),
+        };
+        let uid = EntityUID::from_components(ty, Eid::new(id), None);
+        let description = match self.schema {
+            Some(schema) => match schema.entity_type(uid.entity_type()) {
+                Some(description) => Some(description),
+                None if uid.entity_type().is_action() => None,
+                None => {
+                    let index = match &columns.ty {
+                        TypeSource::Column(index) => Some(*index),
+                        TypeSource::Fixed(_) => None,
+                    };
+                    return Err(vec![CsvError::new(
+                        Some(row),
+                        index.and_then(|i| header.get(i)).map(String::as_str),
+                        format!(
+                            "entity type `{}` is not declared in the schema",
+                            uid.entity_type()
+                        ),
+                    )]);
+                }
+            },
+            None => None,
+        };
+
+        let mut errs = Vec::new();
+        let value_parser = ValueParser::new(self.extensions);
+        let evaluator = RestrictedEvaluator::new(self.extensions);
+        let mut attrs = Vec::new();
+        for (index, attr) in &columns.attrs {
+            let (value, err) = cell(*index);
+            let declared_ty = description.as_ref().map(|d| d.attr_type(attr));
+            if value.is_empty() && !matches!(declared_ty, Some(Some(SchemaType::Set { .. }))) {
+                continue;
+            }
+            let expected_ty = match (declared_ty, &description) {
+                (Some(Some(ty)), _) => Some(ty),
+                (Some(None), Some(description)) if !description.open_attributes() => {
+                    errs.push(err(format!(
+                        "entity type `{}` has no attribute `{attr}` in the schema",
+                        uid.entity_type()
+                    )));
+                    continue;
+                }
+                _ => None,
+            };
+            let value = value
+                .to_json(expected_ty.as_ref(), mapping.set_separator)
+                .map_err(&err)
+                .and_then(|json| {
+                    value_parser
+                        .val_into_restricted_expr(json, expected_ty.as_ref(), || {
+                            JsonDeserializationErrorContext::EntityAttribute {
+                                uid: uid.clone(),
+                                attr: attr.clone(),
+                            }
+                        })
+                        .map_err(|e| err(e.to_string()))
+                })
+                .and_then(|expr| {
+                    evaluator
+                        .partial_interpret(expr.as_borrowed())
+                        .map_err(|e| err(e.to_string()))
+                });
+            match value {
+                Ok(value) => attrs.push((attr.clone(), value)),
+                Err(e) => errs.push(e),
+            }
+        }
+        let mut parents = HashSet::new();
+        for (index, parent_ty) in &columns.parents {
+            let (cell, err) = cell(*index);
+            for parent in cell.items(mapping.set_separator) {
+                match parent_ty {
+                    Some(ty) => {
+                        parents.insert(EntityUID::from_components(
+                            ty.clone(),
+                            Eid::new(parent.as_str()),
+                            None,
+                        ));
+                    }
+                    None => match parent.parse::<EntityUID>() {
+                        Ok(uid) => {
+                            parents.insert(uid);
+                        }
+                        Err(_) => errs.push(err(format!("`{parent}` is not a valid entity uid"))),
+                    },
+                }
+            }
+        }
+        if !errs.is_empty() {
+            return Err(errs);
+        }
+
+        let entity = Entity::new_with_attr_partial_value(uid, attrs, parents);
+        if let Some(schema) = self.schema {
+            let errs = EntitySchemaConformanceChecker::new(schema, self.extensions)
+                .entity_errors(&entity)
+                .into_iter()
+                .map(|e| CsvError::new(Some(row), None, e.to_string()))
+                .collect::<Vec<_>>();
+            if !errs.is_empty() {
+                return Err(errs);
+            }
+        }
+        Ok(entity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::Value;
+    use cool_asserts::assert_matches;
+
+    fn parse(src: &str, mapping: &CsvMapping) -> Result<Vec<Entity>, CsvErrors> {
+        EntityCsvParser::<NoEntitiesSchema>::new(None, Extensions::all_available())
+            .from_csv_str(src, mapping)
+    }
+
+    #[test]
+    fn splits_records() {
+        assert_eq!(
+            records("a,b,c\r\n1,\"x, \"\"y\"\"\nz\",\n\n3,,\"\"", ',').unwrap(),
+            vec![
+                vec!["a", "b", "c"],
+                vec!["1", "x, \"y\"\nz", ""],
+                vec!["3", "", ""],
+            ]
+        );
+        assert_eq!(
+            records("a\tb\n1\t2\n", '\t').unwrap(),
+            vec![vec!["a", "b"], vec!["1", "2"]]
+        );
+        assert_eq!(
+            records("a,b\n1,\"2", ',').unwrap_err().to_string(),
+            "row 2: quoted field is never closed"
+        );
+        assert_eq!(
+            records("a,b\n\"1\"x,2", ',').unwrap_err().to_string(),
+            "row 2: unexpected `x` after the closing quote of a field"
+        );
+    }
+
+    #[test]
+    fn parses_entities() {
+        let mut mapping = CsvMapping::new("name");
+        mapping.type_column = Some("kind".into());
+        mapping
+            .attributes
+            .insert("dept".into(), "department".into());
+        mapping.parents.push(CsvParentColumn {
+            column: "groups".into(),
+            entity_type: Some("Group".into()),
+        });
+        mapping.parents.push(CsvParentColumn {
+            column: "other".into(),
+            entity_type: None,
+        });
+        let entities = parse(
+            "kind,name,dept,groups,other,ignored\n\
+             User,alice,eng,admins; staff,,x\n\
+             Group,admins,,,\"Org::\"\"acme\"\"\",y\n",
+            &mapping,
+        )
+        .unwrap();
+        assert_matches!(entities.as_slice(), [alice, admins] => {
+            assert_eq!(alice.uid().to_string(), r#"User::"alice""#);
+            assert_eq!(alice.get("department"), Some(&Value::from("eng").into()));
+            assert_eq!(alice.ancestors().count(), 2);
+            assert_eq!(admins.uid().to_string(), r#"Group::"admins""#);
+            assert_eq!(admins.get("department"), None);
+            assert!(admins.is_descendant_of(&r#"Org::"acme""#.parse().unwrap()));
+        });
+    }
+
+    #[test]
+    fn reports_addressed_errors() {
+        let mut mapping = CsvMapping::new("id");
+        mapping.entity_type = Some("User".into());
+        mapping.parents.push(CsvParentColumn {
+            column: "parents".into(),
+            entity_type: None,
+        });
+        let errs = parse("id,parents\nalice,Group::bad\n,\nalice,\nalice\n", &mapping).unwrap_err();
+        assert_eq!(
+            errs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "row 2, column `parents`: `Group::bad` is not a valid entity uid",
+                "row 3, column `id`: the entity id is empty",
+                "row 5: expected 2 cells as in the header, found 1",
+            ]
+        );
+
+        let errs = parse("id,parents\nalice,\nalice,\n", &mapping).unwrap_err();
+        assert_eq!(
+            errs.to_string(),
+            r#"failed to load entities: row 3: duplicate entity `User::"alice"`, also declared in row 2"#
+        );
+
+        let mut mapping = CsvMapping::new("missing");
+        mapping.attributes.insert("absent".into(), "a".into());
+        let errs = parse("id\nalice\n", &mapping).unwrap_err();
+        assert_eq!(
+            errs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "exactly one of the entity type and the type column must be given",
+                "row 1, column `missing`: the header has no such column",
+                "row 1, column `absent`: the header has no such column",
+            ]
+        );
+    }
+}
@@ -146,6 +146,40 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
         self.parse_ejsons(ejsons)
     }
 
+    /// Parse an entities JSON file (in [`&str`] form) into an [`Entities`]
+    /// object without refusing entities which do not conform to the schema.
+    /// See [`EntityJsonParser::from_json_value_with_warnings()`].
+    pub fn from_json_str_with_warnings(
+        &self,
+        json: &str,
+    ) -> Result<(Entities, Vec<EntitySchemaConformanceError>), EntitiesError> {
+        let ejsons: Vec<EntityJson> =
+            serde_json::from_str(json).map_err(JsonDeserializationError::from)?;
+        self.parse_ejsons_with_warnings(ejsons)
+    }
+
+    /// Parse an entities JSON file (in [`serde_json::Value`] form) into an
+    /// [`Entities`] object without refusing entities which do not conform to
+    /// the schema. Instead, every way in which they do not conform is returned
+    /// as a warning; see [`Entities::from_entities_with_warnings()`].
+    ///
+    /// The schema still informs the parsing where it can. Attributes and tags
+    /// which the schema doesn't declare, attributes of undeclared entity
+    /// types, and values which don't have the declared type are parsed as if
+    /// there were no schema, so `__entity` and `__extn` escapes must be
+    /// explicit in them.
+    ///
+    /// If the `EntityJsonParser` has no `schema`, this is the same as
+    /// [`EntityJsonParser::from_json_value()`] and there are no warnings.
+    pub fn from_json_value_with_warnings(
+        &self,
+        json: serde_json::Value,
+    ) -> Result<(Entities, Vec<EntitySchemaConformanceError>), EntitiesError> {
+        let ejsons: Vec<EntityJson> =
+            serde_json::from_value(json).map_err(JsonDeserializationError::from)?;
+        self.parse_ejsons_with_warnings(ejsons)
+    }
+
     /// Parse an entities JSON file (in [`std::io::Read`] form) into an [`Entities`] object.
     ///
     /// If the `EntityJsonParser` has a `schema`, this also adds `Action`
@@ -266,11 +300,56 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
         Entities::from_entities(entities, self.schema, self.tc_computation, self.extensions)
     }
 
+    /// Internal function that creates an [`Entities`] from a stream of
+    /// [`EntityJson`], reporting the ways the entities do not conform to the
+    /// `schema` instead of refusing them.
+    fn parse_ejsons_with_warnings(
+        &self,
+        ejsons: impl IntoIterator<Item = EntityJson>,
+    ) -> Result<(Entities, Vec<EntitySchemaConformanceError>), EntitiesError> {
+        let entities: Vec<Entity> = ejsons
+            .into_iter()
+            .map(|ejson| self.parse_ejson_lenient(ejson))
+            .collect::<Result<_, _>>()?;
+        match self.schema {
+            None => Ok((
+                Entities::from_entities(
+                    entities,
+                    self.schema,
+                    self.tc_computation,
+                    self.extensions,
+                )?,
+                Vec::new(),
+            )),
+            Some(schema) => Entities::from_entities_with_warnings(
+                entities,
+                schema,
+                self.tc_computation,
+                self.extensions,
+            ),
+        }
+    }
+
     /// Internal function that parses an `EntityJson` into an `Entity`.
     ///
     /// This function is not responsible for fully validating the `Entity`
     /// against the `schema`; that happens on construction of an `Entities`
     fn parse_ejson(&self, ejson: EntityJson) -> Result<Entity, JsonDeserializationError> {
+        self.parse_ejson_with(ejson, false)
+    }
+
+    /// Like [`Self::parse_ejson()`], but parsing attributes and tags as if
+    /// there were no schema where schema-based parsing fails, so that the
+    /// failure can be reported by the conformance checker instead
+    fn parse_ejson_lenient(&self, ejson: EntityJson) -> Result<Entity, JsonDeserializationError> {
+        self.parse_ejson_with(ejson, true)
+    }
+
+    fn parse_ejson_with(
+        &self,
+        ejson: EntityJson,
+        lenient: bool,
+    ) -> Result<Entity, JsonDeserializationError> {
         let uid = ejson
             .uid
             .into_euid(|| JsonDeserializationErrorContext::EntityUid)?;
@@ -282,18 +361,22 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                     // Action entities do not have attribute type information in the schema.
                     EntitySchemaInfo::NoSchema
                 } else {
-                    EntitySchemaInfo::NonAction(schema.entity_type(etype).ok_or_else(|| {
-                        let suggested_types = schema
-                            .entity_types_with_basename(&etype.name().basename())
-                            .collect();
-                        JsonDeserializationError::EntitySchemaConformance(
-                            UnexpectedEntityTypeError {
-                                uid: uid.clone(),
-                                suggested_types,
-                            }
-                            .into(),
-                        )
-                    })?)
+                    match schema.entity_type(etype) {
+                        Some(desc) => EntitySchemaInfo::NonAction(desc),
+                        None if lenient => EntitySchemaInfo::NoSchema,
+                        None => {
+                            let suggested_types = schema
+                                .entity_types_with_basename(&etype.name().basename())
+                                .collect();
+                            return Err(JsonDeserializationError::EntitySchemaConformance(
+                                UnexpectedEntityTypeError {
+                                    uid: uid.clone(),
+                                    suggested_types,
+                                }
+                                .into(),
+                            ));
+                        }
+                    }
                 }
             }
         };
@@ -318,7 +401,7 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                         // `None` indicates the attribute shouldn't exist -- see
                         // docs on the `attr_type()` trait method
                         None => {
-                            if desc.open_attributes() {
+                            if desc.open_attributes() || lenient {
                                 vparser.val_into_restricted_expr(v.into(), None, || {
                                     JsonDeserializationErrorContext::EntityAttribute {
                                         uid: uid.clone(),
@@ -334,14 +417,23 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                                 ));
                             }
                         }
-                        Some(expected_ty) => vparser.val_into_restricted_expr(
-                            v.into(),
-                            Some(&expected_ty),
-                            || JsonDeserializationErrorContext::EntityAttribute {
+                        Some(expected_ty) => {
+                            let ctx = || JsonDeserializationErrorContext::EntityAttribute {
                                 uid: uid.clone(),
                                 attr: k.clone(),
-                            },
-                        )?,
+                            };
+                            let v: serde_json::Value = v.into();
+                            match vparser.val_into_restricted_expr(
+                                v.clone(),
+                                Some(&expected_ty),
+                                ctx,
+                            ) {
+                                Err(err) if lenient => vparser
+                                    .val_into_restricted_expr(v, None, ctx)
+                                    .map_err(|_| err)?,
+                                res => res?,
+                            }
+                        }
                     };
                     Ok((k, rexpr))
                 }
@@ -366,19 +458,36 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                     let rexpr = match desc.tag_type() {
                         // `None` indicates no tags should exist -- see docs on
                         // the `tag_type()` trait method
+                        None if lenient => {
+                            vparser.val_into_restricted_expr(v.into(), None, || {
+                                JsonDeserializationErrorContext::EntityTag {
+                                    uid: uid.clone(),
+                                    tag: k.clone(),
+                                }
+                            })?
+                        }
                         None => {
                             return Err(JsonDeserializationError::EntitySchemaConformance(
                                 EntitySchemaConformanceError::unexpected_entity_tag(uid.clone(), k),
                             ));
                         }
-                        Some(expected_ty) => vparser.val_into_restricted_expr(
-                            v.into(),
-                            Some(&expected_ty),
-                            || JsonDeserializationErrorContext::EntityTag {
+                        Some(expected_ty) => {
+                            let ctx = || JsonDeserializationErrorContext::EntityTag {
                                 uid: uid.clone(),
                                 tag: k.clone(),
-                            },
-                        )?,
+                            };
+                            let v: serde_json::Value = v.into();
+                            match vparser.val_into_restricted_expr(
+                                v.clone(),
+                                Some(&expected_ty),
+                                ctx,
+                            ) {
+                                Err(err) if lenient => vparser
+                                    .val_into_restricted_expr(v, None, ctx)
+                                    .map_err(|_| err)?,
+                                res => res?,
+                            }
+                        }
                     };
                     Ok((k, rexpr))
                 }
@@ -444,8 +553,8 @@ impl EntityJson {
             // for now, we encode `uid` and `parents` using an implied `__entity` escape
             uid: EntityUidJson::ImplicitEntityEscape(TypeAndId::from(entity.uid())),
             attrs: entity
-                .attrs()
-                .map(serialize_kpvalue)
+                .try_attrs()
+                .map(|kv| serialize_kpvalue(kv?))
                 .collect::<Result<_, JsonSerializationError>>()?,
             parents: entity
                 .ancestors()
@@ -387,6 +387,10 @@ pub enum JsonSerializationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     Residual(Residual),
+    /// Computing the value of a lazy entity attribute failed
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    EntityAttributeEvaluation(#[from] EntityAttrEvaluationError),
 }
 
 impl JsonSerializationError {
@@ -37,6 +37,7 @@ use serde_with::serde_as;
 use serde_with::{DeserializeAs, SerializeAs};
 use smol_str::{SmolStr, ToSmolStr};
 use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
 use std::sync::Arc;
 
 #[cfg(feature = "wasm")]
@@ -519,10 +520,29 @@ impl<'e> ValueParser<'e> {
             // apply: for instance, the `__entity` escape can optionally be omitted.
             // What this means is that we parse the contents as `EntityUidJson`, and
             // then convert that into an entity reference `RestrictedExpr`
-            Some(SchemaType::Entity { .. }) => {
-                let uidjson: EntityUidJson = serde_json::from_value(val)?;
-                Ok(RestrictedExpr::val(uidjson.into_euid(ctx)?))
-            }
+            //
+            // A string is also accepted, either as the id of an entity of the
+            // expected type (`"alice"`) or as an entity reference in Cedar
+            // syntax (`"User::\"alice\""`), which must have the expected type.
+            Some(expected_ty @ SchemaType::Entity { ty }) => match val {
+                serde_json::Value::String(s) if s.contains("::") => {
+                    let euid = EntityUID::from_str(&s).map_err(|errs| {
+                        JsonDeserializationError::parse_escape(EscapeKind::Entity, s, errs)
+                    })?;
+                    if euid.entity_type() == ty {
+                        Ok(RestrictedExpr::val(euid))
+                    } else {
+                        Err(self.type_mismatch(expected_ty, RestrictedExpr::val(euid), ctx()))
+                    }
+                }
+                serde_json::Value::String(s) => Ok(RestrictedExpr::val(
+                    EntityUID::from_components(ty.clone(), Eid::new(s), None),
+                )),
+                val => {
+                    let uidjson: EntityUidJson = serde_json::from_value(val)?;
+                    Ok(RestrictedExpr::val(uidjson.into_euid(ctx)?))
+                }
+            },
             // The expected type is an extension type. Special parsing rules apply:
             // for instance, the `__extn` escape can optionally be omitted. What
             // this means is that we parse the contents as `ExtnValueJson`, and then
@@ -547,19 +567,7 @@ impl<'e> ValueParser<'e> {
                         let jvalue: CedarValueJson = serde_json::from_value(val)?;
                         jvalue.into_expr(ctx.clone())?
                     };
-                    let err = TypeMismatchError::type_mismatch(
-                        expected_ty.clone(),
-                        actual_val.try_type_of(self.extensions),
-                        actual_val,
-                    );
-                    match ctx() {
-                        JsonDeserializationErrorContext::EntityAttribute { uid, attr } => {
-                            Err(JsonDeserializationError::EntitySchemaConformance(
-                                EntitySchemaConformanceError::type_mismatch(uid, attr, err),
-                            ))
-                        }
-                        ctx => Err(JsonDeserializationError::type_mismatch(ctx, err)),
-                    }
+                    Err(self.type_mismatch(expected_ty, actual_val, ctx()))
                 }
             },
             // The expected type is a record type. No special parsing rules
@@ -616,19 +624,7 @@ impl<'e> ValueParser<'e> {
                         let jvalue: CedarValueJson = serde_json::from_value(val)?;
                         jvalue.into_expr(ctx.clone())?
                     };
-                    let err = TypeMismatchError::type_mismatch(
-                        expected_ty.clone(),
-                        actual_val.try_type_of(self.extensions),
-                        actual_val,
-                    );
-                    match ctx() {
-                        JsonDeserializationErrorContext::EntityAttribute { uid, attr } => {
-                            Err(JsonDeserializationError::EntitySchemaConformance(
-                                EntitySchemaConformanceError::type_mismatch(uid, attr, err),
-                            ))
-                        }
-                        ctx => Err(JsonDeserializationError::type_mismatch(ctx, err)),
-                    }
+                    Err(self.type_mismatch(expected_ty, actual_val, ctx()))
                 }
             },
             // The expected type is any other type, or we don't have an expected type.
@@ -642,6 +638,29 @@ impl<'e> ValueParser<'e> {
         }
     }
 
+    /// internal function that builds the error for a value which doesn't have
+    /// the expected type
+    fn type_mismatch(
+        &self,
+        expected_ty: &SchemaType,
+        actual_val: RestrictedExpr,
+        ctx: JsonDeserializationErrorContext,
+    ) -> JsonDeserializationError {
+        let err = TypeMismatchError::type_mismatch(
+            expected_ty.clone(),
+            actual_val.try_type_of(self.extensions),
+            actual_val,
+        );
+        match ctx {
+            JsonDeserializationErrorContext::EntityAttribute { uid, attr } => {
+                JsonDeserializationError::EntitySchemaConformance(
+                    EntitySchemaConformanceError::type_mismatch(uid, attr, err),
+                )
+            }
+            ctx => JsonDeserializationError::type_mismatch(ctx, err),
+        }
+    }
+
     /// internal function that converts an `ExtnValueJson` into a
     /// `RestrictedExpr`, which will be an extension constructor call.
     ///
@@ -0,0 +1,285 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The Cedar entity text format, a human-readable alternative to the JSON
+//! entity format in which entities are written like
+//!
+//! ```cedar
+//! entity User::"alice" { dept: "eng", level: 3 } in [Group::"staff"];
+//! entity Document::"plan" tags { classification: "secret" };
+//! ```
+//!
+//! Attributes and tags are written as record literals, and their values may
+//! be any restricted expression. The attributes, parents, and tags of an
+//! entity are all optional.
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use smol_str::SmolStr;
+
+use crate::ast::{Entity, EntityAttrEvaluationError, EntityUID, Id, PartialValue, RestrictedExpr};
+use crate::extensions::Extensions;
+use crate::FromNormalizedStr;
+
+/// An entity declaration in the Cedar entity text format, whose attribute and
+/// tag values have not been evaluated yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityDecl {
+    pub(crate) uid: EntityUID,
+    pub(crate) attrs: Vec<(SmolStr, RestrictedExpr)>,
+    pub(crate) parents: HashSet<EntityUID>,
+    pub(crate) tags: Vec<(SmolStr, RestrictedExpr)>,
+}
+
+impl EntityDecl {
+    /// The uid of the declared entity
+    pub fn uid(&self) -> &EntityUID {
+        &self.uid
+    }
+
+    /// Evaluate the attributes and tags of the declared entity
+    pub fn into_entity(
+        self,
+        extensions: &Extensions<'_>,
+    ) -> Result<Entity, EntityAttrEvaluationError> {
+        Entity::new(self.uid, self.attrs, self.parents, self.tags, extensions)
+    }
+}
+
+impl Entity {
+    /// Write this entity in the Cedar entity text format. Its attributes,
+    /// parents, and tags are written in sorted order.
+    ///
+    /// This computes the value of every lazy attribute, and fails if the
+    /// computation of any of them fails.
+    pub fn to_cedar_text(&self) -> Result<String, EntityAttrEvaluationError> {
+        let mut text = format!("entity {}", self.uid());
+        let attrs = self.try_attrs().collect::<Result<Vec<_>, _>>()?;
+        write_record(&mut text, attrs.into_iter());
+        if self.ancestors().next().is_some() {
+            let parents = self
+                .ancestors()
+                .map(ToString::to_string)
+                .sorted()
+                .join(", ");
+            text.push_str(&format!(" in [{parents}]"));
+        }
+        if self.tags().next().is_some() {
+            text.push_str(" tags");
+            write_record(&mut text, self.tags());
+        }
+        text.push(';');
+        Ok(text)
+    }
+}
+
+/// Write the record literal with fields `fields`, one per line, unless there
+/// are no fields
+fn write_record<'a>(
+    text: &mut String,
+    fields: impl Iterator<Item = (&'a SmolStr, &'a PartialValue)>,
+) {
+    let fields = fields
+        .sorted_by_key(|(k, _)| *k)
+        .map(|(k, v)| {
+            if Id::from_normalized_str(k).is_ok() {
+                format!("  {k}: {v}")
+            } else {
+                format!("  \"{}\": {v}", k.escape_debug())
+            }
+        })
+        .join(",\n");
+    if !fields.is_empty() {
+        text.push_str(" {\n");
+        text.push_str(&fields);
+        text.push_str("\n}");
+    }
+}
+
+/// Write `entities` in the Cedar entity text format, in order of their uids
+pub fn entities_to_cedar_text<'a>(
+    entities: impl IntoIterator<Item = &'a Entity>,
+) -> Result<String, EntityAttrEvaluationError> {
+    Ok(entities
+        .into_iter()
+        .sorted_by_cached_key(|e| e.uid().to_string())
+        .map(Entity::to_cedar_text)
+        .collect::<Result<Vec<_>, _>>()?
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{err::ParseErrors, parse_entities};
+    use crate::test_utils::{expect_err, ExpectedErrorMessageBuilder};
+    use cool_asserts::assert_matches;
+    use std::str::FromStr;
+
+    fn euid(s: &str) -> EntityUID {
+        EntityUID::from_str(s).expect("valid uid")
+    }
+
+    #[track_caller]
+    fn parse_err(src: &str) -> ParseErrors {
+        parse_entities(src).expect_err("should fail to parse")
+    }
+
+    #[test]
+    fn parses_declarations() {
+        let decls = parse_entities(
+            r#"
+            // comments are allowed
+            entity User::"alice" { dept: "eng", "home town": "Seattle", "if": true } in [Group::"staff", Group::"eng"];
+            entity Group::"staff" in Org::"acme";
+            entity Ns::Doc::"plan" tags { secret: true };
+            entity Org::"acme";
+            "#,
+        )
+        .unwrap();
+        assert_matches!(decls.as_slice(), [alice, staff, plan, acme] => {
+            assert_eq!(alice.uid(), &euid(r#"User::"alice""#));
+            assert_eq!(
+                alice.attrs.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+                ["dept", "home town", "if"]
+            );
+            assert_eq!(
+                alice.parents,
+                HashSet::from([euid(r#"Group::"staff""#), euid(r#"Group::"eng""#)])
+            );
+            assert!(alice.tags.is_empty());
+            assert_eq!(staff.parents, HashSet::from([euid(r#"Org::"acme""#)]));
+            assert_eq!(plan.uid(), &euid(r#"Ns::Doc::"plan""#));
+            assert_eq!(plan.tags.len(), 1);
+            assert!(acme.attrs.is_empty() && acme.parents.is_empty());
+        });
+        assert_eq!(parse_entities("").unwrap(), vec![]);
+    }
+
+    #[cfg(all(feature = "ipaddr", feature = "decimal"))]
+    #[test]
+    fn round_trips() {
+        let src = r#"
+            entity User::"alice" {
+                name: "Alice \"A\" Smith",
+                age: -3,
+                "if": true,
+                "my attr": [1, 2, { nested: User::"bob" }],
+                addr: ip("10.0.0.1"),
+                limit: decimal("1.25")
+            } in [Group::"staff"] tags { level: 7 };
+            entity User::"bob";
+        "#;
+        let entities = parse_entities(src)
+            .unwrap()
+            .into_iter()
+            .map(|decl| decl.into_entity(Extensions::all_available()).unwrap())
+            .collect::<Vec<_>>();
+        let text = entities_to_cedar_text(&entities).unwrap();
+        let reparsed = parse_entities(&text)
+            .unwrap()
+            .into_iter()
+            .map(|decl| decl.into_entity(Extensions::all_available()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entities_to_cedar_text(&reparsed).unwrap(), text);
+        assert!(
+            text.starts_with("entity User::\"alice\" {\n  addr: "),
+            "unexpected text: {text}"
+        );
+        assert!(text.contains("  \"if\": true,"), "unexpected text: {text}");
+        assert!(
+            text.contains("  \"my attr\": [1, 2, {nested: User::\"bob\"}],"),
+            "unexpected text: {text}"
+        );
+        assert!(
+            text.ends_with("} in [Group::\"staff\"] tags {\n  level: 7\n};\nentity User::\"bob\";"),
+            "unexpected text: {text}"
+        );
+    }
+
+    #[test]
+    fn invalid_keywords() {
+        let src = r#"entitty User::"alice";"#;
+        expect_err(
+            src,
+            &miette::Report::new(parse_err(src)),
+            &ExpectedErrorMessageBuilder::error("invalid entity declaration: entitty")
+                .help(r#"entity declarations should have a form like `entity User::"alice" { dept: "eng" } in [Group::"staff"];`"#)
+                .exactly_one_underline("entitty")
+                .build(),
+        );
+        let src = r#"entity User::"alice" tag { a: 1 };"#;
+        expect_err(
+            src,
+            &miette::Report::new(parse_err(src)),
+            &ExpectedErrorMessageBuilder::error(
+                "invalid entity declaration: expected `tags`, found `tag`",
+            )
+            .exactly_one_underline("tag")
+            .build(),
+        );
+    }
+
+    #[test]
+    fn invalid_parents() {
+        let src = r#"entity User::"alice" in [Group::"staff", "eng"];"#;
+        expect_err(
+            src,
+            &miette::Report::new(parse_err(src)),
+            &ExpectedErrorMessageBuilder::error(
+                "the parents of an entity must be an entity literal or a list of entity literals",
+            )
+            .exactly_one_underline(r#""eng""#)
+            .build(),
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let src = r#"entity User::"alice" { a: principal };"#;
+        expect_err(
+            src,
+            &miette::Report::new(parse_err(src)),
+            &ExpectedErrorMessageBuilder::error(
+                "not allowed to use variables in a restricted expression: `principal`",
+            )
+            .exactly_one_underline("principal")
+            .build(),
+        );
+        assert_matches!(
+            parse_entities(r#"entity User::"alice" { a: 1, a: 2 };"#),
+            Err(_)
+        );
+        assert_matches!(parse_entities(r#"entity User::"alice" { a: 1 }"#), Err(_));
+        // errors in every declaration are reported
+        assert_eq!(
+            parse_err(r#"entity User::"alice" { a: principal }; entity User::"bob" in 1;"#).len(),
+            2
+        );
+    }
+
+    #[cfg(feature = "ipaddr")]
+    #[test]
+    fn evaluation_errors() {
+        let decls = parse_entities(r#"entity User::"alice" { addr: ip("not an ip") };"#).unwrap();
+        assert_matches!(decls.as_slice(), [decl] => {
+            assert_matches!(decl.clone().into_entity(Extensions::all_available()), Err(err) => {
+                assert_eq!(err.attr_or_tag, "addr");
+            });
+        });
+    }
+}
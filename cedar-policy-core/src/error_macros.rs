@@ -21,7 +21,7 @@
 macro_rules! impl_diagnostic_from_source_loc_field {
     ( $i:ident ) => {
         fn source_code(&self) -> Option<&dyn miette::SourceCode> {
-            Some(&self.$i.src as &dyn miette::SourceCode)
+            self.$i.source_code()
         }
 
         fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
@@ -41,7 +41,7 @@ macro_rules! impl_diagnostic_from_source_loc_opt_field {
         fn source_code(&self) -> Option<&dyn miette::SourceCode> {
             self.$($id).+
                 .as_ref()
-                .map(|loc| &loc.src as &dyn miette::SourceCode)
+                .and_then(|loc| loc.source_code())
         }
 
         fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
@@ -62,7 +62,7 @@ macro_rules! impl_diagnostic_from_two_source_loc_fields {
         fn source_code(&self) -> Option<&dyn miette::SourceCode> {
             // use the `src` from the first location and assume it is the same
             // as the `src` from the second location
-            Some(&self.$i.src as &dyn miette::SourceCode)
+            self.$i.source_code()
         }
 
         fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
@@ -88,12 +88,8 @@ macro_rules! impl_diagnostic_from_two_source_loc_opt_fields {
         fn source_code(&self) -> Option<&dyn miette::SourceCode> {
             self.$i
                 .as_ref()
-                .map(|loc| &loc.src as &dyn miette::SourceCode)
-                .or_else(|| {
-                    self.$j
-                        .as_ref()
-                        .map(|loc| &loc.src as &dyn miette::SourceCode)
-                })
+                .or(self.$j.as_ref())
+                .and_then(|loc| loc.source_code())
         }
 
         fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
@@ -125,7 +121,7 @@ macro_rules! impl_diagnostic_from_expr_field {
             self.$i
                 .source_loc()
                 .as_ref()
-                .map(|loc| &loc.src as &dyn miette::SourceCode)
+                .and_then(|loc| loc.source_code())
         }
 
         fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
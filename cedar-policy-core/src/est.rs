@@ -209,6 +209,26 @@ impl TryFrom<cst::Cond> for Clause {
 }
 
 impl Policy {
+    /// Get the principal scope constraint of the policy
+    pub fn principal_constraint(&self) -> &PrincipalConstraint {
+        &self.principal
+    }
+
+    /// Get the action scope constraint of the policy
+    pub fn action_constraint(&self) -> &ActionConstraint {
+        &self.action
+    }
+
+    /// Get the resource scope constraint of the policy
+    pub fn resource_constraint(&self) -> &ResourceConstraint {
+        &self.resource
+    }
+
+    /// Get the `when` and `unless` clauses of the policy, in order
+    pub fn conditions(&self) -> &[Clause] {
+        &self.conditions
+    }
+
     /// Try to convert a [`Policy`] into a [`ast::Policy`].
     ///
     /// This process requires a policy ID. If not supplied, this method will
@@ -2363,6 +2383,88 @@ mod test {
         assert_eq!(circular_roundtrip(est.clone()), est);
     }
 
+    #[test]
+    fn union_intersection() {
+        let policy = r#"
+            permit(principal, action, resource)
+            when {
+                principal.roles.union(["admin"]).intersection(resource.roles).isEmpty()
+            };
+        "#;
+        let cst = parser::text_to_cst::parse_policy(policy)
+            .unwrap()
+            .node
+            .unwrap();
+        let est: Policy = cst.try_into().unwrap();
+        let expected_json = json!(
+            {
+                "effect": "permit",
+                "principal": {
+                    "op": "All",
+                },
+                "action": {
+                    "op": "All",
+                },
+                "resource": {
+                    "op": "All",
+                },
+                "conditions": [
+                    {
+                        "kind": "when",
+                        "body": {
+                            "isEmpty": {
+                                "arg": {
+                                    "intersection": {
+                                        "left": {
+                                            "union": {
+                                                "left": {
+                                                    ".": {
+                                                        "left": {
+                                                            "Var": "principal"
+                                                        },
+                                                        "attr": "roles"
+                                                    }
+                                                },
+                                                "right": {
+                                                    "Set": [
+                                                        { "Value": "admin" }
+                                                    ]
+                                                }
+                                            }
+                                        },
+                                        "right": {
+                                            ".": {
+                                                "left": {
+                                                    "Var": "resource"
+                                                },
+                                                "attr": "roles"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                ]
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&est).unwrap(),
+            expected_json,
+            "\nExpected:\n{}\n\nActual:\n{}\n\n",
+            serde_json::to_string_pretty(&expected_json).unwrap(),
+            serde_json::to_string_pretty(&est).unwrap()
+        );
+        let old_est = est.clone();
+        let roundtripped = est_roundtrip(est);
+        assert_eq!(&old_est, &roundtripped);
+        let est = text_roundtrip(&old_est);
+        assert_eq!(&old_est, &est);
+
+        assert_eq!(ast_roundtrip(est.clone()), est);
+        assert_eq!(circular_roundtrip(est.clone()), est);
+    }
+
     #[test]
     fn entity_tags() {
         let policy = r#"
@@ -4339,6 +4441,190 @@ mod test {
         }
     }
 
+    #[test]
+    fn is_type_list() {
+        let policy = r#"permit(principal, action, resource) when { resource is [Doc, Sheet] in Folder::"f" };"#;
+        let cst = parser::text_to_cst::parse_policy(policy)
+            .unwrap()
+            .node
+            .unwrap();
+        let est: Policy = cst.try_into().unwrap();
+        let expected_json = json!(
+            {
+                "effect": "permit",
+                "principal": { "op": "All" },
+                "action": { "op": "All" },
+                "resource": { "op": "All" },
+                "conditions": [
+                    {
+                        "kind": "when",
+                        "body": {
+                            "&&": {
+                                "left": {
+                                    "||": {
+                                        "left": {
+                                            "is": {
+                                                "left": { "Var": "resource" },
+                                                "entity_type": "Doc"
+                                            }
+                                        },
+                                        "right": {
+                                            "is": {
+                                                "left": { "Var": "resource" },
+                                                "entity_type": "Sheet"
+                                            }
+                                        }
+                                    }
+                                },
+                                "right": {
+                                    "in": {
+                                        "left": { "Var": "resource" },
+                                        "right": { "Value": { "__entity": { "type": "Folder", "id": "f" } } }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                ]
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&est).unwrap(),
+            expected_json,
+            "\nExpected:\n{}\n\nActual:\n{}\n\n",
+            serde_json::to_string_pretty(&expected_json).unwrap(),
+            serde_json::to_string_pretty(&est).unwrap()
+        );
+        let roundtripped = circular_roundtrip(est.clone());
+        assert_eq!(est, roundtripped);
+    }
+
+    #[test]
+    fn get_tag_or() {
+        let est = parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) when { resource.getTagOr("level", 0) == 3 };"#,
+        )
+        .unwrap();
+        let expected = parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) when { (if resource.hasTag("level") then resource.getTag("level") else 0) == 3 };"#,
+        )
+        .unwrap();
+        assert_eq!(est, expected);
+        assert_eq!(circular_roundtrip(est.clone()), est);
+    }
+
+    #[test]
+    fn get_or() {
+        let est = parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) when { principal.getOr("tier", "free") == "gold" };"#,
+        )
+        .unwrap();
+        let expected = parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) when { (if principal has tier then principal.tier else "free") == "gold" };"#,
+        )
+        .unwrap();
+        assert_eq!(est, expected);
+        assert_eq!(circular_roundtrip(est.clone()), est);
+
+        let src = r#"permit(principal, action, resource) when { principal.getOr(context.attr, "free") == "gold" };"#;
+        assert_matches!(parse_policy_or_template_to_est(src), Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error(
+                    "the first argument to `getOr` must be a string literal naming an attribute",
+                )
+                .exactly_one_underline("context.attr")
+                .build(),
+            );
+        });
+    }
+
+    #[test]
+    fn division_and_remainder() {
+        let est = parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) when { context.used / context.quota % 10 == 0 };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&est).unwrap()["conditions"][0]["body"]["=="]["left"],
+            json!({
+                "%": {
+                    "left": {
+                        "/": {
+                            "left": { ".": { "left": { "Var": "context" }, "attr": "used" } },
+                            "right": { ".": { "left": { "Var": "context" }, "attr": "quota" } },
+                        }
+                    },
+                    "right": { "Value": 10 },
+                }
+            })
+        );
+        assert_eq!(est_roundtrip(est.clone()), est);
+        assert_eq!(text_roundtrip(&est), est);
+        assert_eq!(ast_roundtrip(est.clone()), est);
+        assert_eq!(circular_roundtrip(est.clone()), est);
+    }
+
+    #[test]
+    fn like_char_classes() {
+        let est = parse_policy_or_template_to_est(
+            r#"permit(principal, action, resource) when { context.id like "u\[0-9]\{2}\[^a-z\-]*" };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&est).unwrap()["conditions"][0]["body"]["like"]["pattern"],
+            json!([
+                { "Literal": "u" },
+                {
+                    "Repeat": {
+                        "pattern": [{ "Class": { "ranges": [["0", "9"]] } }],
+                        "min": 2,
+                        "max": 2,
+                    }
+                },
+                { "Class": { "ranges": [["a", "z"], ["-", "-"]], "negated": true } },
+                "Wildcard",
+            ])
+        );
+        assert_eq!(est_roundtrip(est.clone()), est);
+        assert_eq!(text_roundtrip(&est), est);
+        assert_eq!(ast_roundtrip(est.clone()), est);
+        assert_eq!(circular_roundtrip(est.clone()), est);
+    }
+
+    #[test]
+    fn like_invalid_patterns() {
+        let like = |pattern: serde_json::Value| -> Policy {
+            serde_json::from_value(json!({
+                "effect": "permit",
+                "principal": { "op": "All" },
+                "action": { "op": "All" },
+                "resource": { "op": "All" },
+                "conditions": [{
+                    "kind": "when",
+                    "body": { "like": { "left": { "Var": "context" }, "pattern": pattern } }
+                }]
+            }))
+            .unwrap()
+        };
+        assert_matches!(
+            like(json!([{ "Class": { "ranges": [["z", "a"]] } }])).try_into_ast_policy(None),
+            Err(FromJsonError::InvalidPattern(
+                ast::PatternError::InvalidRange { lo: 'z', hi: 'a' }
+            ))
+        );
+        assert_matches!(
+            like(
+                json!([{ "Repeat": { "pattern": ["Wildcard"], "min": 0, "max": 4000000000u32 } }])
+            )
+            .try_into_ast_policy(None),
+            Err(FromJsonError::InvalidPattern(
+                ast::PatternError::InvalidRepetitionTarget
+            ))
+        );
+    }
+
     mod reserved_names {
         use cool_asserts::assert_matches;
 
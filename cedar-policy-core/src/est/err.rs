@@ -80,6 +80,11 @@ pub enum FromJsonError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     InvalidActionType(#[from] parse_errors::InvalidActionType),
+    /// EST contained a `like` pattern with a malformed character class or
+    /// repetition
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidPattern(#[from] ast::PatternError),
 }
 
 /// Errors arising while converting a policy set from its JSON representation (aka EST) into an AST
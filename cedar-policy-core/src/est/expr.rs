@@ -122,6 +122,24 @@ pub enum PatternElem {
     Wildcard,
     /// A string without any wildcards
     Literal(SmolStr),
+    /// A character class, matching exactly one character
+    Class {
+        /// Inclusive character ranges; a single character `c` is the range `[c, c]`
+        ranges: Vec<(char, char)>,
+        /// If `true`, the class matches any character *not* in `ranges`
+        #[serde(default)]
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        negated: bool,
+    },
+    /// Between `min` and `max` (inclusive) consecutive matches of `pattern`
+    Repeat {
+        /// The repeated pattern, a single character or character class
+        pattern: Vec<PatternElem>,
+        /// Minimum number of repetitions
+        min: u32,
+        /// Maximum number of repetitions
+        max: u32,
+    },
 }
 
 impl From<&[PatternElem]> for crate::ast::Pattern {
@@ -135,6 +153,19 @@ impl From<&[PatternElem]> for crate::ast::Pattern {
                 PatternElem::Literal(s) => {
                     elems.extend(s.chars().map(crate::ast::PatternElem::Char));
                 }
+                PatternElem::Class { ranges, negated } => {
+                    elems.push(crate::ast::PatternElem::Class(crate::ast::CharClass::new(
+                        *negated,
+                        ranges.iter().copied(),
+                    )));
+                }
+                PatternElem::Repeat { pattern, min, max } => {
+                    elems.push(crate::ast::PatternElem::Repeat {
+                        pattern: crate::ast::Pattern::from(pattern.as_slice()),
+                        min: *min,
+                        max: *max,
+                    });
+                }
             }
         }
         Self::from(elems)
@@ -146,13 +177,22 @@ impl From<crate::ast::PatternElem> for PatternElem {
         match value {
             crate::ast::PatternElem::Wildcard => Self::Wildcard,
             crate::ast::PatternElem::Char(c) => Self::Literal(c.to_smolstr()),
+            crate::ast::PatternElem::Class(class) => Self::Class {
+                ranges: class.ranges().to_vec(),
+                negated: class.is_negated(),
+            },
+            crate::ast::PatternElem::Repeat { pattern, min, max } => Self::Repeat {
+                pattern: pattern.into(),
+                min,
+                max,
+            },
         }
     }
 }
 
 impl From<crate::ast::Pattern> for Vec<PatternElem> {
     fn from(value: crate::ast::Pattern) -> Self {
-        value.iter().map(|elem| (*elem).into()).collect()
+        value.iter().map(|elem| elem.clone().into()).collect()
     }
 }
 
@@ -279,6 +319,22 @@ pub enum ExprNoExt {
         /// Right-hand argument
         right: Arc<Expr>,
     },
+    /// `/`
+    #[serde(rename = "/")]
+    Div {
+        /// Left-hand argument
+        left: Arc<Expr>,
+        /// Right-hand argument
+        right: Arc<Expr>,
+    },
+    /// `%`
+    #[serde(rename = "%")]
+    Rem {
+        /// Left-hand argument
+        left: Arc<Expr>,
+        /// Right-hand argument
+        right: Arc<Expr>,
+    },
     /// `contains()`
     #[serde(rename = "contains")]
     Contains {
@@ -325,6 +381,22 @@ pub enum ExprNoExt {
         /// Right-hand argument (inside the `()`)
         right: Arc<Expr>,
     },
+    /// `union()`
+    #[serde(rename = "union")]
+    Union {
+        /// Left-hand argument (receiver)
+        left: Arc<Expr>,
+        /// Right-hand argument (inside the `()`)
+        right: Arc<Expr>,
+    },
+    /// `intersection()`
+    #[serde(rename = "intersection")]
+    Intersection {
+        /// Left-hand argument (receiver)
+        left: Arc<Expr>,
+        /// Right-hand argument (inside the `()`)
+        right: Arc<Expr>,
+    },
     /// Get-attribute
     #[serde(rename = ".")]
     GetAttr {
@@ -539,6 +611,22 @@ impl Expr {
         })
     }
 
+    /// `/`
+    pub fn div(left: Expr, right: Expr) -> Self {
+        Expr::ExprNoExt(ExprNoExt::Div {
+            left: Arc::new(left),
+            right: Arc::new(right),
+        })
+    }
+
+    /// `%`
+    pub fn rem(left: Expr, right: Expr) -> Self {
+        Expr::ExprNoExt(ExprNoExt::Rem {
+            left: Arc::new(left),
+            right: Arc::new(right),
+        })
+    }
+
     /// `left.contains(right)`
     pub fn contains(left: Arc<Expr>, right: Expr) -> Self {
         Expr::ExprNoExt(ExprNoExt::Contains {
@@ -584,6 +672,22 @@ impl Expr {
         })
     }
 
+    /// `left.union(right)`
+    pub fn union(left: Arc<Expr>, right: Expr) -> Self {
+        Expr::ExprNoExt(ExprNoExt::Union {
+            left,
+            right: Arc::new(right),
+        })
+    }
+
+    /// `left.intersection(right)`
+    pub fn intersection(left: Arc<Expr>, right: Expr) -> Self {
+        Expr::ExprNoExt(ExprNoExt::Intersection {
+            left,
+            right: Arc::new(right),
+        })
+    }
+
     /// `left.attr`
     pub fn get_attr(left: Expr, attr: SmolStr) -> Self {
         Expr::ExprNoExt(ExprNoExt::GetAttr {
@@ -726,6 +830,14 @@ impl Expr {
                     left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
                     right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
                 })),
+                ExprNoExt::Div { left, right } => Ok(Expr::ExprNoExt(ExprNoExt::Div {
+                    left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
+                    right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
+                })),
+                ExprNoExt::Rem { left, right } => Ok(Expr::ExprNoExt(ExprNoExt::Rem {
+                    left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
+                    right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
+                })),
                 ExprNoExt::Contains { left, right } => Ok(Expr::ExprNoExt(ExprNoExt::Contains {
                     left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
                     right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
@@ -753,6 +865,16 @@ impl Expr {
                     left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
                     right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
                 })),
+                ExprNoExt::Union { left, right } => Ok(Expr::ExprNoExt(ExprNoExt::Union {
+                    left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
+                    right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
+                })),
+                ExprNoExt::Intersection { left, right } => {
+                    Ok(Expr::ExprNoExt(ExprNoExt::Intersection {
+                        left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
+                        right: Arc::new((*right).clone().sub_entity_literals(mapping)?),
+                    }))
+                }
                 ExprNoExt::GetAttr { left, attr } => Ok(Expr::ExprNoExt(ExprNoExt::GetAttr {
                     left: Arc::new((*left).clone().sub_entity_literals(mapping)?),
                     attr,
@@ -886,6 +1008,14 @@ impl Expr {
                 (*left).clone().try_into_ast(id.clone())?,
                 (*right).clone().try_into_ast(id)?,
             )),
+            Expr::ExprNoExt(ExprNoExt::Div { left, right }) => Ok(ast::Expr::div(
+                (*left).clone().try_into_ast(id.clone())?,
+                (*right).clone().try_into_ast(id)?,
+            )),
+            Expr::ExprNoExt(ExprNoExt::Rem { left, right }) => Ok(ast::Expr::rem(
+                (*left).clone().try_into_ast(id.clone())?,
+                (*right).clone().try_into_ast(id)?,
+            )),
             Expr::ExprNoExt(ExprNoExt::Contains { left, right }) => Ok(ast::Expr::contains(
                 (*left).clone().try_into_ast(id.clone())?,
                 (*right).clone().try_into_ast(id)?,
@@ -909,16 +1039,27 @@ impl Expr {
                 (*left).clone().try_into_ast(id.clone())?,
                 (*right).clone().try_into_ast(id)?,
             )),
+            Expr::ExprNoExt(ExprNoExt::Union { left, right }) => Ok(ast::Expr::union(
+                (*left).clone().try_into_ast(id.clone())?,
+                (*right).clone().try_into_ast(id)?,
+            )),
+            Expr::ExprNoExt(ExprNoExt::Intersection { left, right }) => {
+                Ok(ast::Expr::intersection(
+                    (*left).clone().try_into_ast(id.clone())?,
+                    (*right).clone().try_into_ast(id)?,
+                ))
+            }
             Expr::ExprNoExt(ExprNoExt::GetAttr { left, attr }) => {
                 Ok(ast::Expr::get_attr((*left).clone().try_into_ast(id)?, attr))
             }
             Expr::ExprNoExt(ExprNoExt::HasAttr { left, attr }) => {
                 Ok(ast::Expr::has_attr((*left).clone().try_into_ast(id)?, attr))
             }
-            Expr::ExprNoExt(ExprNoExt::Like { left, pattern }) => Ok(ast::Expr::like(
-                (*left).clone().try_into_ast(id)?,
-                crate::ast::Pattern::from(pattern.as_slice()),
-            )),
+            Expr::ExprNoExt(ExprNoExt::Like { left, pattern }) => {
+                let pattern = crate::ast::Pattern::from(pattern.as_slice());
+                pattern.validate()?;
+                Ok(ast::Expr::like((*left).clone().try_into_ast(id)?, pattern))
+            }
             Expr::ExprNoExt(ExprNoExt::Is {
                 left,
                 entity_type,
@@ -1042,11 +1183,15 @@ impl<T: Clone> From<ast::Expr<T>> for Expr {
                     ast::BinaryOp::Add => Expr::add(arg1, arg2),
                     ast::BinaryOp::Sub => Expr::sub(arg1, arg2),
                     ast::BinaryOp::Mul => Expr::mul(arg1, arg2),
+                    ast::BinaryOp::Div => Expr::div(arg1, arg2),
+                    ast::BinaryOp::Rem => Expr::rem(arg1, arg2),
                     ast::BinaryOp::Contains => Expr::contains(Arc::new(arg1), arg2),
                     ast::BinaryOp::ContainsAll => Expr::contains_all(Arc::new(arg1), arg2),
                     ast::BinaryOp::ContainsAny => Expr::contains_any(Arc::new(arg1), arg2),
                     ast::BinaryOp::GetTag => Expr::get_tag(Arc::new(arg1), arg2),
                     ast::BinaryOp::HasTag => Expr::has_tag(Arc::new(arg1), arg2),
+                    ast::BinaryOp::Union => Expr::union(Arc::new(arg1), arg2),
+                    ast::BinaryOp::Intersection => Expr::intersection(Arc::new(arg1), arg2),
                 }
             }
             ast::ExprKind::ExtensionFunctionApp { fn_name, args } => {
@@ -1210,22 +1355,41 @@ impl TryFrom<&Node<Option<cst::Relation>>> for Expr {
                 pattern
                     .to_expr_or_special()?
                     .into_pattern()
-                    .map(|pat| Expr::like(target_expr, pat.into_iter().map(PatternElem::from)))
+                    .map(|pat| Expr::like(target_expr, Vec::<PatternElem>::from(pat)))
             }
             cst::Relation::IsIn {
                 target,
                 entity_type,
                 in_entity,
             } => {
-                let target = target.try_into()?;
-                let type_str = entity_type.try_as_inner()?.to_string().into();
+                let target: Expr = target.try_into()?;
+                // `e is [A, B]` is shorthand for `e is A || e is B`
+                let Some(elems) = entity_type.to_set_literal() else {
+                    let type_str = entity_type.try_as_inner()?.to_string().into();
+                    return match in_entity {
+                        Some(in_entity) => Ok(Expr::is_entity_type_in(
+                            target,
+                            type_str,
+                            in_entity.try_into()?,
+                        )),
+                        None => Ok(Expr::is_entity_type(target, type_str)),
+                    };
+                };
+                let is_expr = elems
+                    .iter()
+                    .map(|elem| {
+                        let type_str = elem.try_as_inner()?.to_string().into();
+                        Ok(Expr::is_entity_type(target.clone(), type_str))
+                    })
+                    .collect::<Result<Vec<_>, ParseErrors>>()?
+                    .into_iter()
+                    .reduce(Expr::or)
+                    .ok_or_else(|| entity_type.to_ast_err(ToASTErrorKind::EmptyIsTypeList))?;
                 match in_entity {
-                    Some(in_entity) => Ok(Expr::is_entity_type_in(
-                        target,
-                        type_str,
-                        in_entity.try_into()?,
-                    )),
-                    None => Ok(Expr::is_entity_type(target, type_str)),
+                    Some(in_entity) => {
+                        Ok(Expr::and(is_expr, Expr::_in(target, in_entity.try_into()?)))
+                    }
+                    None => Ok(is_expr),
                 }
             }
         }
@@ -1264,10 +1428,10 @@ impl TryFrom<&Node<Option<cst::Mult>>> for Expr {
                     expr = Expr::mul(expr, rhs);
                 }
                 cst::MultOp::Divide => {
-                    return Err(node.to_ast_err(ToASTErrorKind::UnsupportedDivision).into())
+                    expr = Expr::div(expr, rhs);
                 }
                 cst::MultOp::Mod => {
-                    return Err(node.to_ast_err(ToASTErrorKind::UnsupportedModulo).into())
+                    expr = Expr::rem(expr, rhs);
                 }
             }
         }
@@ -1522,6 +1686,45 @@ impl TryFrom<&Node<Option<cst::Member>>> for Expr {
                                     left,
                                     extract_single_argument(args, "hasTag()", &access.loc)?,
                                 )),
+                                "getOr" => {
+                                    // `e.getOr("a", d)` is shorthand for
+                                    // `if e has a then e.a else d`
+                                    let (attr, default) =
+                                        extract_two_arguments(args, "getOr()", &access.loc)?;
+                                    let Expr::ExprNoExt(ExprNoExt::Value(CedarValueJson::String(
+                                        attr,
+                                    ))) = attr
+                                    else {
+                                        return Err(access
+                                            .to_ast_err(ToASTErrorKind::NonStringGetOrAttribute)
+                                            .into());
+                                    };
+                                    let left = Arc::unwrap_or_clone(left);
+                                    Either::Right(Expr::ite(
+                                        Expr::has_attr(left.clone(), attr.clone()),
+                                        Expr::get_attr(left, attr),
+                                        default,
+                                    ))
+                                }
+                                "getTagOr" => {
+                                    // `e.getTagOr(k, d)` is shorthand for
+                                    // `if e.hasTag(k) then e.getTag(k) else d`
+                                    let (tag, default) =
+                                        extract_two_arguments(args, "getTagOr()", &access.loc)?;
+                                    Either::Right(Expr::ite(
+                                        Expr::has_tag(Arc::clone(&left), tag.clone()),
+                                        Expr::get_tag(left, tag),
+                                        default,
+                                    ))
+                                }
+                                "union" => Either::Right(Expr::union(
+                                    left,
+                                    extract_single_argument(args, "union()", &access.loc)?,
+                                )),
+                                "intersection" => Either::Right(Expr::intersection(
+                                    left,
+                                    extract_single_argument(args, "intersection()", &access.loc)?,
+                                )),
                                 _ => {
                                     // have to add the "receiver" argument as
                                     // first in the list for the method call
@@ -1582,6 +1785,24 @@ pub fn extract_single_argument<T>(
     }
 }
 
+/// Return the two arguments in `args` iterator, or return a wrong arity error
+/// if the iterator does not have exactly 2 elements.
+pub fn extract_two_arguments<T>(
+    args: impl ExactSizeIterator<Item = T>,
+    fn_name: &'static str,
+    loc: &Loc,
+) -> Result<(T, T), ParseErrors> {
+    let got = args.len();
+    let mut iter = args.fuse();
+    match (iter.next(), iter.next(), got) {
+        (Some(first), Some(second), 2) => Ok((first, second)),
+        _ => Err(ParseErrors::singleton(ToASTError::new(
+            ToASTErrorKind::wrong_arity(fn_name, 2, got),
+            loc.clone(),
+        ))),
+    }
+}
+
 /// Return a wrong arity error if the iterator has any elements.
 pub fn require_zero_arguments<T>(
     args: impl ExactSizeIterator<Item = T>,
@@ -1875,6 +2096,16 @@ impl BoundedDisplay for ExprNoExt {
                 write!(f, " * ")?;
                 maybe_with_parens(f, right, n)
             }
+            ExprNoExt::Div { left, right } => {
+                maybe_with_parens(f, left, n)?;
+                write!(f, " / ")?;
+                maybe_with_parens(f, right, n)
+            }
+            ExprNoExt::Rem { left, right } => {
+                maybe_with_parens(f, left, n)?;
+                write!(f, " % ")?;
+                maybe_with_parens(f, right, n)
+            }
             ExprNoExt::Contains { left, right } => {
                 maybe_with_parens(f, left, n)?;
                 write!(f, ".contains({right})")
@@ -1899,6 +2130,14 @@ impl BoundedDisplay for ExprNoExt {
                 maybe_with_parens(f, left, n)?;
                 write!(f, ".hasTag({right})")
             }
+            ExprNoExt::Union { left, right } => {
+                maybe_with_parens(f, left, n)?;
+                write!(f, ".union({right})")
+            }
+            ExprNoExt::Intersection { left, right } => {
+                maybe_with_parens(f, left, n)?;
+                write!(f, ".intersection({right})")
+            }
             ExprNoExt::GetAttr { left, attr } => {
                 maybe_with_parens(f, left, n)?;
                 write!(f, "[\"{}\"]", attr.escape_debug())
@@ -2068,6 +2307,8 @@ fn maybe_with_parens(
         Expr::ExprNoExt(ExprNoExt::Add { .. }) |
         Expr::ExprNoExt(ExprNoExt::Sub { .. }) |
         Expr::ExprNoExt(ExprNoExt::Mul { .. }) |
+        Expr::ExprNoExt(ExprNoExt::Div { .. }) |
+        Expr::ExprNoExt(ExprNoExt::Rem { .. }) |
         Expr::ExprNoExt(ExprNoExt::Contains { .. }) |
         Expr::ExprNoExt(ExprNoExt::ContainsAll { .. }) |
         Expr::ExprNoExt(ExprNoExt::ContainsAny { .. }) |
@@ -2076,6 +2317,8 @@ fn maybe_with_parens(
         Expr::ExprNoExt(ExprNoExt::HasAttr { .. }) |
         Expr::ExprNoExt(ExprNoExt::GetTag { .. }) |
         Expr::ExprNoExt(ExprNoExt::HasTag { .. }) |
+        Expr::ExprNoExt(ExprNoExt::Union { .. }) |
+        Expr::ExprNoExt(ExprNoExt::Intersection { .. }) |
         Expr::ExprNoExt(ExprNoExt::Like { .. }) |
         Expr::ExprNoExt(ExprNoExt::Is { .. }) |
         Expr::ExprNoExt(ExprNoExt::If { .. }) |
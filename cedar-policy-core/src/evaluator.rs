@@ -20,19 +20,30 @@ use crate::ast::*;
 use crate::entities::{Dereference, Entities};
 use crate::extensions::Extensions;
 use crate::parser::Loc;
+use std::cell::Cell;
 use std::collections::BTreeMap;
 #[cfg(test)]
 use std::collections::HashMap;
 use std::sync::Arc;
 
+mod cancel;
+pub use cancel::{Cancellation, CancellationReason, CancellationToken};
+mod clock;
+pub use clock::{Clock, FixedClock, SystemClock};
 mod err;
 pub use err::evaluation_errors;
+mod memo;
+use memo::SubexpressionCache;
+mod overflow;
+mod steps;
 pub use err::EvaluationError;
 pub(crate) use err::*;
 use evaluation_errors::*;
 use itertools::Either;
 use nonempty::nonempty;
+pub use overflow::OverflowBehavior;
 use smol_str::SmolStr;
+pub use steps::{EvaluationStep, EvaluationSteps};
 
 #[cfg(not(target_arch = "wasm32"))]
 const REQUIRED_STACK_SPACE: usize = 1024 * 100;
@@ -68,6 +79,18 @@ pub struct Evaluator<'e> {
     entities: &'e Entities,
     /// Extensions which are active for this evaluation
     extensions: &'e Extensions<'e>,
+    /// When to abandon this evaluation, if ever
+    cancellation: Option<&'e Cancellation>,
+    /// Number of expression nodes interpreted so far
+    node_visits: Cell<u64>,
+    /// What to do when integer arithmetic overflows
+    overflow: OverflowBehavior,
+    /// Number of integer operations whose result was saturated so far
+    saturations: Cell<u64>,
+    /// If present, memoizes the values of expensive sub-expressions
+    cache: Option<SubexpressionCache>,
+    /// If present, records the steps of each evaluation
+    steps: Option<&'e EvaluationSteps>,
 }
 
 /// Evaluator for "restricted" expressions. See notes on `RestrictedExpr`.
@@ -202,6 +225,82 @@ impl<'e> Evaluator<'e> {
             },
             entities,
             extensions,
+            cancellation: None,
+            node_visits: Cell::new(0),
+            overflow: OverflowBehavior::default(),
+            saturations: Cell::new(0),
+            cache: None,
+            steps: None,
+        }
+    }
+
+    /// Abandon evaluation with an [`EvaluationError::Cancelled`] error once
+    /// `cancellation` says to
+    #[must_use]
+    pub fn with_cancellation(self, cancellation: &'e Cancellation) -> Self {
+        Self {
+            cancellation: Some(cancellation),
+            ..self
+        }
+    }
+
+    /// Total number of expression nodes this evaluator has interpreted, across
+    /// all policies and expressions it has evaluated
+    pub fn node_visits(&self) -> u64 {
+        self.node_visits.get()
+    }
+
+    /// Handle integer overflow according to `overflow`, instead of failing
+    /// with an error
+    #[must_use]
+    pub fn with_overflow_behavior(self, overflow: OverflowBehavior) -> Self {
+        Self { overflow, ..self }
+    }
+
+    /// Total number of integer operations whose result this evaluator has
+    /// saturated, across all policies and expressions it has evaluated. Always
+    /// 0 unless the [`OverflowBehavior`] is [`OverflowBehavior::Saturate`].
+    pub fn saturations(&self) -> u64 {
+        self.saturations.get()
+    }
+
+    /// The result of an integer operation which overflowed, if the
+    /// [`OverflowBehavior`] gives it one, given its `saturated` result
+    fn on_overflow(&self, saturated: Integer) -> Option<Integer> {
+        match self.overflow {
+            OverflowBehavior::Error => None,
+            OverflowBehavior::Saturate => {
+                self.saturations.set(self.saturations.get() + 1);
+                Some(saturated)
+            }
+        }
+    }
+
+    /// Memoize the values of expensive sub-expressions (entity hierarchy and
+    /// tag lookups, set membership tests, and extension function calls), so
+    /// that each distinct one is evaluated once, no matter how many times it
+    /// occurs in the policies and expressions evaluated by this evaluator
+    #[must_use]
+    pub fn with_subexpression_cache(self) -> Self {
+        Self {
+            cache: Some(SubexpressionCache::default()),
+            ..self
+        }
+    }
+
+    /// Number of sub-expression evaluations this evaluator has avoided by
+    /// memoization (see [`Evaluator::with_subexpression_cache`])
+    pub fn subexpression_cache_hits(&self) -> u64 {
+        self.cache.as_ref().map_or(0, SubexpressionCache::hits)
+    }
+
+    /// Record every sub-expression this evaluator starts and finishes
+    /// evaluating in `steps`, for step-through debugging
+    #[must_use]
+    pub fn with_steps(self, steps: &'e EvaluationSteps) -> Self {
+        Self {
+            steps: Some(steps),
+            ..self
         }
     }
 
@@ -250,8 +349,20 @@ impl<'e> Evaluator<'e> {
     /// attribute that doesn't exist.
     pub fn partial_interpret(&self, expr: &Expr, slots: &SlotEnv) -> Result<PartialValue> {
         stack_size_check()?;
+        let node_visits = self.node_visits.get() + 1;
+        self.node_visits.set(node_visits);
+        if let Some(reason) = self.cancellation.and_then(|c| c.check(node_visits)) {
+            return Err(EvaluationError::cancelled(
+                reason,
+                expr.source_loc().cloned(),
+            ));
+        }
 
-        let res = self.partial_interpret_internal(expr, slots);
+        if let Some(steps) = self.steps {
+            steps.start(expr);
+        }
+
+        let res = self.partial_interpret_memoized(expr, slots);
 
         // set the returned value's source location to the same source location
         // as the input expression had.
@@ -260,11 +371,36 @@ impl<'e> Evaluator<'e> {
         // also, if there is an error, set its source location to the source
         // location of the input expression as well, unless it already had a
         // more specific location
-        res.map(|pval| pval.with_maybe_source_loc(expr.source_loc().cloned()))
+        let res = res
+            .map(|pval| pval.with_maybe_source_loc(expr.source_loc().cloned()))
             .map_err(|err| match err.source_loc() {
                 None => err.with_maybe_source_loc(expr.source_loc().cloned()),
                 Some(_) => err,
-            })
+            });
+        if let Some(steps) = self.steps {
+            steps.finish(expr, &res);
+        }
+        res
+    }
+
+    /// Interpret `expr` with `partial_interpret_internal()`, unless its value
+    /// is memoized (see [`Evaluator::with_subexpression_cache`])
+    fn partial_interpret_memoized(&self, expr: &Expr, slots: &SlotEnv) -> Result<PartialValue> {
+        let Some((cache, key)) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| Some((cache, SubexpressionCache::key(expr)?)))
+        else {
+            return self.partial_interpret_internal(expr, slots);
+        };
+        if let Some(v) = cache.get(key, expr) {
+            return Ok(v.into());
+        }
+        let res = self.partial_interpret_internal(expr, slots);
+        if let Ok(PartialValue::Value(v)) = &res {
+            cache.insert(key, expr, v.clone());
+        }
+        res
     }
 
     /// Internal function to interpret an `Expr`. (External callers, use
@@ -357,7 +493,10 @@ impl<'e> Evaluator<'e> {
                     },
                     UnaryOp::Neg => {
                         let i = arg.get_as_long()?;
-                        match i.checked_neg() {
+                        match i
+                            .checked_neg()
+                            .or_else(|| self.on_overflow(i.saturating_neg()))
+                        {
                             Some(v) => Ok(v.into()),
                             None => Err(IntegerOverflowError::UnaryOp(UnaryOpOverflowError {
                                 op: *op,
@@ -434,11 +573,21 @@ impl<'e> Evaluator<'e> {
                             }
                         }
                     }
-                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul => {
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Rem => {
                         let i1 = arg1.get_as_long()?;
                         let i2 = arg2.get_as_long()?;
+                        if i2 == 0 && matches!(op, BinaryOp::Div | BinaryOp::Rem) {
+                            return Err(EvaluationError::division_by_zero(*op, arg1, loc.cloned()));
+                        }
                         match op {
-                            BinaryOp::Add => match i1.checked_add(i2) {
+                            BinaryOp::Add => match i1
+                                .checked_add(i2)
+                                .or_else(|| self.on_overflow(i1.saturating_add(i2)))
+                            {
                                 Some(sum) => Ok(sum.into()),
                                 None => {
                                     Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
@@ -450,7 +599,10 @@ impl<'e> Evaluator<'e> {
                                     .into())
                                 }
                             },
-                            BinaryOp::Sub => match i1.checked_sub(i2) {
+                            BinaryOp::Sub => match i1
+                                .checked_sub(i2)
+                                .or_else(|| self.on_overflow(i1.saturating_sub(i2)))
+                            {
                                 Some(diff) => Ok(diff.into()),
                                 None => {
                                     Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
@@ -462,7 +614,10 @@ impl<'e> Evaluator<'e> {
                                     .into())
                                 }
                             },
-                            BinaryOp::Mul => match i1.checked_mul(i2) {
+                            BinaryOp::Mul => match i1
+                                .checked_mul(i2)
+                                .or_else(|| self.on_overflow(i1.saturating_mul(i2)))
+                            {
                                 Some(prod) => Ok(prod.into()),
                                 None => {
                                     Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
@@ -474,6 +629,25 @@ impl<'e> Evaluator<'e> {
                                     .into())
                                 }
                             },
+                            // only `i64::MIN / -1` overflows
+                            BinaryOp::Div => match i1
+                                .checked_div(i2)
+                                .or_else(|| self.on_overflow(i1.saturating_div(i2)))
+                            {
+                                Some(quot) => Ok(quot.into()),
+                                None => {
+                                    Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
+                                        op: *op,
+                                        arg1,
+                                        arg2,
+                                        source_loc: loc.cloned(),
+                                    })
+                                    .into())
+                                }
+                            },
+                            // `i64::MIN % -1` is 0, even though computing it
+                            // with `checked_rem` overflows
+                            BinaryOp::Rem => Ok(i1.wrapping_rem(i2).into()),
                             // PANIC SAFETY `op` is checked to be one of the above
                             #[allow(clippy::unreachable)]
                             _ => {
@@ -590,7 +764,7 @@ impl<'e> Evaluator<'e> {
                                                 Arc::new(uid.clone()),
                                                 tag.clone(),
                                                 entity.tag_keys(),
-                                                entity.get(tag).is_some(),
+                                                entity.has_attr(tag),
                                                 entity.tags_len(),
                                                 loc.cloned(), // intentionally using the location of the entire `GetTag` expression
                                             )
@@ -614,6 +788,31 @@ impl<'e> Evaluator<'e> {
                             }
                         }
                     }
+                    // union and intersection, which work on Sets and produce a Set
+                    BinaryOp::Union => {
+                        let arg1_set = arg1.get_as_set()?;
+                        let arg2_set = arg2.get_as_set()?;
+                        Ok(Value::set(
+                            arg1_set
+                                .authoritative
+                                .union(&arg2_set.authoritative)
+                                .cloned(),
+                            loc.cloned(),
+                        )
+                        .into())
+                    }
+                    BinaryOp::Intersection => {
+                        let arg1_set = arg1.get_as_set()?;
+                        let arg2_set = arg2.get_as_set()?;
+                        Ok(Value::set(
+                            arg1_set
+                                .authoritative
+                                .intersection(&arg2_set.authoritative)
+                                .cloned(),
+                            loc.cloned(),
+                        )
+                        .into())
+                    }
                 }
             }
             ExprKind::ExtensionFunctionApp { fn_name, args } => {
@@ -646,7 +845,7 @@ impl<'e> Evaluator<'e> {
                     Dereference::Residual(r) => {
                         Ok(PartialValue::Residual(Expr::has_attr(r, attr.clone())))
                     }
-                    Dereference::Data(e) => Ok(e.get(attr).is_some().into()),
+                    Dereference::Data(e) => Ok(e.has_attr(attr).into()),
                 },
                 PartialValue::Value(val) => Err(err::EvaluationError::type_error(
                     nonempty![
@@ -848,19 +1047,20 @@ impl<'e> Evaluator<'e> {
                 Dereference::Residual(r) => {
                     Ok(PartialValue::Residual(Expr::get_attr(r, attr.clone())))
                 }
-                Dereference::Data(entity) => entity
-                    .get(attr)
-                    .ok_or_else(|| {
-                        EvaluationError::entity_attr_does_not_exist(
-                            uid,
-                            attr.clone(),
-                            entity.keys(),
-                            entity.get_tag(attr).is_some(),
-                            entity.attrs_len(),
-                            source_loc.cloned(),
-                        )
-                    })
-                    .cloned(),
+                Dereference::Data(entity) => match entity.try_get(attr) {
+                    Some(Err(e @ EvaluationError::EntityLoad(_))) => {
+                        Err(e.with_maybe_source_loc(source_loc.cloned()))
+                    }
+                    Some(v) => v.cloned(),
+                    None => Err(EvaluationError::entity_attr_does_not_exist(
+                        uid,
+                        attr.clone(),
+                        entity.keys(),
+                        entity.get_tag(attr).is_some(),
+                        entity.attrs_len(),
+                        source_loc.cloned(),
+                    )),
+                },
             },
             PartialValue::Value(v) => {
                 // PANIC SAFETY Entity type name is fully static and a valid unqualified `Name`
@@ -3562,6 +3762,65 @@ pub(crate) mod test {
         );
     }
 
+    #[test]
+    fn interpret_division_and_remainder() {
+        let request = basic_request();
+        let entities = rich_entities();
+        let eval = Evaluator::new(request, &entities, Extensions::none());
+        // division rounds toward zero, and the remainder has the sign of the dividend
+        for (lhs, rhs, quot, rem) in [(7, 2, 3, 1), (-7, 2, -3, -1), (7, -2, -3, 1), (0, 5, 0, 0)] {
+            assert_eq!(
+                eval.interpret_inline_policy(&Expr::div(Expr::val(lhs), Expr::val(rhs))),
+                Ok(Value::from(quot)),
+                "{lhs} / {rhs}"
+            );
+            assert_eq!(
+                eval.interpret_inline_policy(&Expr::rem(Expr::val(lhs), Expr::val(rhs))),
+                Ok(Value::from(rem)),
+                "{lhs} % {rhs}"
+            );
+        }
+        // division by zero
+        let err = eval
+            .interpret_inline_policy(&Expr::div(Expr::val(7), Expr::val(0)))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            EvaluationError::division_by_zero(BinaryOp::Div, Value::from(7), None)
+        );
+        assert_eq!(err.to_string(), "attempted to divide the value `7` by zero");
+        let err = eval
+            .interpret_inline_policy(&Expr::rem(Expr::val(7), Expr::val(0)))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "attempted to take the remainder of dividing the value `7` by zero"
+        );
+        // overflow
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::div(Expr::val(Integer::MIN), Expr::val(-1))),
+            Err(IntegerOverflowError::BinaryOp(BinaryOpOverflowError {
+                op: BinaryOp::Div,
+                arg1: Value::from(Integer::MIN),
+                arg2: Value::from(-1),
+                source_loc: None,
+            })
+            .into())
+        );
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::rem(Expr::val(Integer::MIN), Expr::val(-1))),
+            Ok(Value::from(0))
+        );
+        // "5" / 1
+        assert_matches!(
+            eval.interpret_inline_policy(&Expr::div(Expr::val("5"), Expr::val(1))),
+            Err(EvaluationError::TypeError(TypeError { expected, actual, .. })) => {
+                assert_eq!(expected, nonempty![Type::Long]);
+                assert_eq!(actual, Type::String);
+            }
+        );
+    }
+
     #[test]
     fn interpret_set_and_map_membership() {
         let request = basic_request();
@@ -4703,6 +4962,86 @@ pub(crate) mod test {
         );
     }
 
+    #[test]
+    fn interpret_union_and_intersection() {
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, Extensions::none());
+        // [1, -22, 34].union([1, 5])
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::union(
+                Expr::set(vec![Expr::val(1), Expr::val(-22), Expr::val(34)]),
+                Expr::set(vec![Expr::val(1), Expr::val(5)])
+            )),
+            Ok(Value::set(
+                [
+                    Value::from(1),
+                    Value::from(-22),
+                    Value::from(34),
+                    Value::from(5)
+                ],
+                None
+            ))
+        );
+        // [1, -22, 34].intersection([1, 5])
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::intersection(
+                Expr::set(vec![Expr::val(1), Expr::val(-22), Expr::val(34)]),
+                Expr::set(vec![Expr::val(1), Expr::val(5)])
+            )),
+            Ok(Value::set([Value::from(1)], None))
+        );
+        // [1].intersection([])
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::intersection(
+                Expr::set(vec![Expr::val(1)]),
+                Expr::set(vec![])
+            )),
+            Ok(Value::set([], None))
+        );
+        // [{a: 1}, "x"].union([{a: 1}]), with non-literal elements
+        assert_eq!(
+            eval.interpret_inline_policy(&Expr::union(
+                Expr::set(vec![
+                    Expr::record(vec![("a".into(), Expr::val(1))]).unwrap(),
+                    Expr::val("x")
+                ]),
+                Expr::set(vec![Expr::record(vec![("a".into(), Expr::val(1))]).unwrap()])
+            )),
+            Ok(Value::set(
+                [
+                    Value::record(vec![("a", Value::from(1))], None),
+                    Value::from("x")
+                ],
+                None
+            ))
+        );
+        // "ham".union(["ham"])
+        assert_matches!(
+            eval.interpret_inline_policy(&Expr::union(
+                Expr::val("ham"),
+                Expr::set(vec![Expr::val("ham")])
+            )),
+            Err(EvaluationError::TypeError(TypeError { expected, actual, advice, .. })) => {
+                assert_eq!(expected, nonempty![Type::Set]);
+                assert_eq!(actual, Type::String);
+                assert_eq!(advice, None);
+            }
+        );
+        // ["ham"].intersection(1)
+        assert_matches!(
+            eval.interpret_inline_policy(&Expr::intersection(
+                Expr::set(vec![Expr::val("ham")]),
+                Expr::val(1)
+            )),
+            Err(EvaluationError::TypeError(TypeError { expected, actual, advice, .. })) => {
+                assert_eq!(expected, nonempty![Type::Set]);
+                assert_eq!(actual, Type::Long);
+                assert_eq!(advice, None);
+            }
+        );
+    }
+
     #[test]
     fn eval_and_or() -> Result<()> {
         use crate::parser;
@@ -6286,4 +6625,104 @@ pub(crate) mod test {
             assert_eq!(err.attr, "d");
         });
     }
+
+    #[test]
+    fn subexpression_cache() {
+        let entities = basic_entities();
+        let src = r#"
+            ip("10.0.0.1").isInRange(ip("10.0.0.0/8")) && [1, 2].contains(2) &&
+            ip("10.0.0.1").isInRange(ip("10.0.0.0/8")) && [1, 2].contains(2) &&
+            (principal in test_entity_type::"foo") == (principal in test_entity_type::"foo")
+        "#;
+        let e = parse_expr(src).unwrap();
+        let eval = Evaluator::new(basic_request(), &entities, Extensions::all_available());
+        assert_eq!(eval.interpret_inline_policy(&e), Ok(Value::from(true)));
+        assert_eq!(eval.subexpression_cache_hits(), 0);
+
+        let eval = Evaluator::new(basic_request(), &entities, Extensions::all_available())
+            .with_subexpression_cache();
+        assert_eq!(eval.interpret_inline_policy(&e), Ok(Value::from(true)));
+        assert_eq!(eval.subexpression_cache_hits(), 3);
+        // the cache lasts as long as the evaluator
+        assert_eq!(eval.interpret_inline_policy(&e), Ok(Value::from(true)));
+        assert_eq!(eval.subexpression_cache_hits(), 9);
+
+        // errors are not memoized
+        let e = parse_expr(r#"ip("bad").isIpv4() || ip("bad").isIpv4()"#).unwrap();
+        assert_matches!(eval.interpret_inline_policy(&e), Err(_));
+        assert_matches!(eval.interpret_inline_policy(&e), Err(_));
+        assert_eq!(eval.subexpression_cache_hits(), 9);
+    }
+
+    #[test]
+    fn subexpression_cache_ignores_slots() {
+        // e.g., the scope constraint `principal in ?principal` of a template
+        let e = Expr::is_in(
+            Expr::slot(SlotId::principal()),
+            Expr::val(EntityUID::with_eid("foo")),
+        );
+        assert_eq!(SubexpressionCache::key(&e), None);
+
+        let entities = basic_entities();
+        let eval = Evaluator::new(basic_request(), &entities, Extensions::all_available())
+            .with_subexpression_cache();
+        let foo = HashMap::from([(SlotId::principal(), EntityUID::with_eid("foo"))]);
+        let bar = HashMap::from([(SlotId::principal(), EntityUID::with_eid("bar"))]);
+        let e = Expr::is_in(
+            Expr::slot(SlotId::principal()),
+            Expr::val(EntityUID::with_eid("foo")),
+        );
+        assert_eq!(eval.interpret(&e, &foo), Ok(Value::from(true)));
+        assert_eq!(eval.interpret(&e, &bar), Ok(Value::from(false)));
+    }
+
+    #[test]
+    fn records_steps() {
+        let entities = basic_entities();
+        let steps = EvaluationSteps::new();
+        let eval =
+            Evaluator::new(basic_request(), &entities, Extensions::none()).with_steps(&steps);
+        let e = parse_expr("if 1 < 2 then 3 else 4").unwrap();
+        assert_eq!(eval.interpret_inline_policy(&e), Ok(Value::from(3)));
+        let recorded = steps
+            .take()
+            .into_iter()
+            .map(|step| {
+                let result = step.result().map(|res| format!("{}", res.clone().unwrap()));
+                (format!("{}", step.expr()), step.depth(), result)
+            })
+            .collect::<Vec<_>>();
+        let step = |e: &str, depth, result: Option<&str>| {
+            (e.to_string(), depth, result.map(ToString::to_string))
+        };
+        assert_eq!(
+            recorded,
+            vec![
+                step("if (1 < 2) then 3 else 4", 0, None),
+                step("1 < 2", 1, None),
+                step("1", 2, None),
+                step("1", 2, Some("1")),
+                step("2", 2, None),
+                step("2", 2, Some("2")),
+                step("1 < 2", 1, Some("true")),
+                step("3", 1, None),
+                step("3", 1, Some("3")),
+                step("if (1 < 2) then 3 else 4", 0, Some("3")),
+            ]
+        );
+        assert_eq!(steps.take().len(), 0);
+
+        // errors are recorded at every level they propagate through
+        let e = parse_expr(r#"!(1 + "a" == 2)"#).unwrap();
+        assert_matches!(eval.interpret_inline_policy(&e), Err(_));
+        let finished = steps
+            .take()
+            .into_iter()
+            .filter_map(|step| Some((step.depth(), step.result()?.is_err())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            finished,
+            vec![(3, false), (3, false), (2, true), (1, true), (0, true)]
+        );
+    }
 }
@@ -0,0 +1,167 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deadlines and cancellation for evaluation
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Evaluation checks the deadline on the first expression node it visits and
+/// every this many nodes after that, rather than reading the clock at every
+/// node
+const DEADLINE_CHECK_INTERVAL: u64 = 64;
+
+/// Handle for cancelling evaluation from another thread. Clones share the
+/// same state, so cancelling any clone cancels them all.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel any evaluation observing this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Has this token been cancelled?
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Conditions under which evaluation should be abandoned: an explicit
+/// [`CancellationToken`], a deadline, or both. The default never cancels.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation {
+    token: Option<CancellationToken>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Never cancel
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel once `token` is cancelled
+    #[must_use]
+    pub fn with_token(self, token: CancellationToken) -> Self {
+        Self {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Cancel once `deadline` passes. The deadline is measured with the
+    /// monotonic clock, so it is unaffected by changes to the system time.
+    #[must_use]
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// Cancel once `timeout` has passed from now. A timeout too large to
+    /// represent never passes.
+    #[must_use]
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now().checked_add(timeout),
+            ..self
+        }
+    }
+
+    /// Why evaluation should stop now, if it should, given that this is the
+    /// `nodes_visited`th expression node of the evaluation (counting from 1)
+    pub(crate) fn check(&self, nodes_visited: u64) -> Option<CancellationReason> {
+        if self
+            .token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            Some(CancellationReason::Cancelled)
+        } else if nodes_visited % DEADLINE_CHECK_INTERVAL == 1
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(CancellationReason::DeadlineExceeded)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why evaluation was abandoned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CancellationReason {
+    /// The [`CancellationToken`] was cancelled
+    Cancelled,
+    /// The deadline passed
+    DeadlineExceeded,
+}
+
+impl std::fmt::Display for CancellationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "evaluation was cancelled"),
+            Self::DeadlineExceeded => write!(f, "evaluation deadline exceeded"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check() {
+        assert_eq!(Cancellation::new().check(1), None);
+
+        let token = CancellationToken::new();
+        let cancellation = Cancellation::new()
+            .with_token(token.clone())
+            .with_timeout(Duration::from_secs(3600));
+        assert_eq!(cancellation.check(1), None);
+        token.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(cancellation.check(2), Some(CancellationReason::Cancelled));
+    }
+
+    #[test]
+    fn deadline_is_checked_periodically() {
+        let cancellation = Cancellation::new().with_deadline(Instant::now());
+        assert_eq!(
+            cancellation.check(1),
+            Some(CancellationReason::DeadlineExceeded)
+        );
+        assert_eq!(cancellation.check(2), None);
+        assert_eq!(
+            cancellation.check(DEADLINE_CHECK_INTERVAL + 1),
+            Some(CancellationReason::DeadlineExceeded)
+        );
+        assert_eq!(
+            Cancellation::new().with_timeout(Duration::MAX).check(1),
+            None
+        );
+    }
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Time sources
+
+use std::time::SystemTime;
+
+/// Source of the current time.
+///
+/// Anything that depends on the current time should take a clock rather than
+/// ask the system, so that callers can make it deterministic (e.g., in tests)
+/// or replay it for a past date by supplying a [`FixedClock`].
+pub trait Clock: Send + Sync {
+    /// The current time, according to this clock
+    fn now(&self) -> SystemTime;
+}
+
+/// [`Clock`] reading the system's wall-clock time. This is the default.
+///
+/// Note that reading the system time is unsupported (and panics) on
+/// `wasm32-unknown-unknown`; callers on that target should supply their own
+/// [`Clock`] if anything they evaluate depends on the current time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// [`Clock`] that always reports the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn fixed() {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = FixedClock(t);
+        assert_eq!(clock.now(), t);
+        assert_eq!(clock.now(), t);
+    }
+}
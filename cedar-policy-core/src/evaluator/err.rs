@@ -15,7 +15,9 @@
  */
 
 use crate::ast::*;
+use crate::evaluator::CancellationReason;
 use crate::extensions::ExtensionFunctionLookupError;
+use crate::limits::LimitError;
 use crate::parser::Loc;
 use miette::Diagnostic;
 use nonempty::{nonempty, NonEmpty};
@@ -26,6 +28,9 @@ use thiserror::Error;
 // How many attrs or tags will we store in an error before cutting off for performance reason
 const TOO_MANY_ATTRS: usize = 5;
 
+/// Placeholder replacing sensitive values in redacted errors
+pub(crate) const REDACTED: &str = "<redacted>";
+
 /// Enumeration of the possible errors that can occur during evaluation
 //
 // CAUTION: this type is publicly exported in `cedar-policy`.
@@ -72,6 +77,11 @@ pub enum EvaluationError {
     #[diagnostic(transparent)]
     IntegerOverflow(#[from] evaluation_errors::IntegerOverflowError),
 
+    /// Integer division or remainder with a divisor of zero
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DivisionByZero(#[from] evaluation_errors::DivisionByZeroError),
+
     /// Not all template slots were linked
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -93,6 +103,27 @@ pub enum EvaluationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     RecursionLimit(#[from] evaluation_errors::RecursionLimitError),
+
+    /// Evaluation was cancelled, or its deadline passed, before it completed
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Cancelled(#[from] evaluation_errors::CancelledError),
+
+    /// The request exceeded a configured [`crate::limits::ResourceLimits`]
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    LimitExceeded(#[from] LimitError),
+
+    /// A lazily computed entity attribute could not be loaded
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    EntityLoad(#[from] evaluation_errors::EntityLoadError),
+
+    /// A context attribute the policy reads could not be supplied by a
+    /// [`crate::authorizer::ContextProvider`]
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ContextProvider(#[from] evaluation_errors::ContextProviderError),
 }
 
 impl EvaluationError {
@@ -106,10 +137,15 @@ impl EvaluationError {
             Self::TypeError(e) => e.source_loc.as_ref(),
             Self::WrongNumArguments(e) => e.source_loc.as_ref(),
             Self::IntegerOverflow(e) => e.source_loc(),
+            Self::DivisionByZero(e) => e.source_loc.as_ref(),
             Self::UnlinkedSlot(e) => e.source_loc.as_ref(),
             Self::FailedExtensionFunctionExecution(e) => e.source_loc.as_ref(),
             Self::NonValue(e) => e.source_loc.as_ref(),
             Self::RecursionLimit(e) => e.source_loc.as_ref(),
+            Self::Cancelled(e) => e.source_loc.as_ref(),
+            Self::LimitExceeded(e) => e.source_loc(),
+            Self::EntityLoad(e) => e.source_loc.as_ref(),
+            Self::ContextProvider(e) => e.source_loc.as_ref(),
         }
     }
 
@@ -145,6 +181,9 @@ impl EvaluationError {
                 })
             }
             Self::IntegerOverflow(e) => Self::IntegerOverflow(e.with_maybe_source_loc(source_loc)),
+            Self::DivisionByZero(e) => {
+                Self::DivisionByZero(evaluation_errors::DivisionByZeroError { source_loc, ..e })
+            }
             Self::UnlinkedSlot(e) => {
                 Self::UnlinkedSlot(evaluation_errors::UnlinkedSlotError { source_loc, ..e })
             }
@@ -157,9 +196,105 @@ impl EvaluationError {
             Self::RecursionLimit(_) => {
                 Self::RecursionLimit(evaluation_errors::RecursionLimitError { source_loc })
             }
+            Self::Cancelled(e) => {
+                Self::Cancelled(evaluation_errors::CancelledError { source_loc, ..e })
+            }
+            Self::LimitExceeded(e) => Self::LimitExceeded(e),
+            Self::EntityLoad(e) => {
+                Self::EntityLoad(evaluation_errors::EntityLoadError { source_loc, ..e })
+            }
+            Self::ContextProvider(e) => {
+                Self::ContextProvider(evaluation_errors::ContextProviderError { source_loc, ..e })
+            }
         }
     }
 
+    /// Get a copy of this error with request-specific data replaced by
+    /// placeholders, so that it can be logged without leaking sensitive data.
+    ///
+    /// Entity ids, literal values, and extension function error messages are
+    /// replaced, as are string and numeric literals in the attached source
+    /// code (see [`Loc::redacted`]). The kind of error, entity types,
+    /// attribute names, types, and source spans are kept.
+    pub fn redacted(&self) -> Self {
+        let redact_uid = |uid: &EntityUID| {
+            Arc::new(EntityUID::from_components(
+                uid.entity_type().clone(),
+                Eid::new(REDACTED),
+                None,
+            ))
+        };
+        let redact_value = |_: &Value| Value::new(Literal::from(REDACTED), None);
+        let redacted = match self {
+            Self::EntityDoesNotExist(e) => {
+                Self::EntityDoesNotExist(evaluation_errors::EntityDoesNotExistError {
+                    uid: redact_uid(&e.uid),
+                    ..e.clone()
+                })
+            }
+            Self::EntityAttrDoesNotExist(e) => {
+                Self::EntityAttrDoesNotExist(evaluation_errors::EntityAttrDoesNotExistError {
+                    entity: redact_uid(&e.entity),
+                    ..e.clone()
+                })
+            }
+            Self::IntegerOverflow(evaluation_errors::IntegerOverflowError::BinaryOp(e)) => {
+                Self::IntegerOverflow(evaluation_errors::IntegerOverflowError::BinaryOp(
+                    evaluation_errors::BinaryOpOverflowError {
+                        arg1: redact_value(&e.arg1),
+                        arg2: redact_value(&e.arg2),
+                        ..e.clone()
+                    },
+                ))
+            }
+            Self::IntegerOverflow(evaluation_errors::IntegerOverflowError::UnaryOp(e)) => {
+                Self::IntegerOverflow(evaluation_errors::IntegerOverflowError::UnaryOp(
+                    evaluation_errors::UnaryOpOverflowError {
+                        arg: redact_value(&e.arg),
+                        ..e.clone()
+                    },
+                ))
+            }
+            Self::DivisionByZero(e) => {
+                Self::DivisionByZero(evaluation_errors::DivisionByZeroError {
+                    dividend: redact_value(&e.dividend),
+                    ..e.clone()
+                })
+            }
+            Self::FailedExtensionFunctionExecution(e) => Self::FailedExtensionFunctionExecution(
+                evaluation_errors::ExtensionFunctionExecutionError {
+                    msg: REDACTED.into(),
+                    ..e.clone()
+                },
+            ),
+            Self::NonValue(e) => Self::NonValue(evaluation_errors::NonValueError {
+                expr: Expr::val(REDACTED),
+                ..e.clone()
+            }),
+            Self::EntityLoad(e) => Self::EntityLoad(evaluation_errors::EntityLoadError {
+                entity: redact_uid(&e.entity),
+                message: REDACTED.into(),
+                ..e.clone()
+            }),
+            Self::ContextProvider(e) => {
+                Self::ContextProvider(evaluation_errors::ContextProviderError {
+                    error: e.error.as_ref().map(|e| Box::new(e.redacted())),
+                    ..e.clone()
+                })
+            }
+            Self::RecordAttrDoesNotExist(_)
+            | Self::FailedExtensionFunctionLookup(_)
+            | Self::TypeError(_)
+            | Self::WrongNumArguments(_)
+            | Self::UnlinkedSlot(_)
+            | Self::RecursionLimit(_)
+            | Self::Cancelled(_)
+            | Self::LimitExceeded(_) => self.clone(),
+        };
+        let source_loc = self.source_loc().map(Loc::redacted);
+        redacted.with_maybe_source_loc(source_loc)
+    }
+
     /// Construct a [`EntityDoesNotExist`] error
     pub(crate) fn entity_does_not_exist(uid: Arc<EntityUID>, source_loc: Option<Loc>) -> Self {
         evaluation_errors::EntityDoesNotExistError { uid, source_loc }.into()
@@ -269,6 +404,10 @@ impl EvaluationError {
         .into()
     }
 
+    #[cfg_attr(
+        not(any(feature = "ipaddr", feature = "decimal", feature = "datetime")),
+        allow(dead_code)
+    )]
     pub(crate) fn type_error_with_advice_single(
         expected: Type,
         actual: &Value,
@@ -299,6 +438,10 @@ impl EvaluationError {
     }
 
     /// Construct a [`FailedExtensionFunctionApplication`] error
+    #[cfg_attr(
+        not(any(feature = "ipaddr", feature = "decimal", feature = "datetime")),
+        allow(dead_code)
+    )]
     pub(crate) fn failed_extension_function_application(
         extension_name: Name,
         msg: String,
@@ -314,6 +457,16 @@ impl EvaluationError {
         .into()
     }
 
+    /// Construct a [`DivisionByZero`] error
+    pub(crate) fn division_by_zero(op: BinaryOp, dividend: Value, source_loc: Option<Loc>) -> Self {
+        evaluation_errors::DivisionByZeroError {
+            op,
+            dividend,
+            source_loc,
+        }
+        .into()
+    }
+
     /// Construct a [`NonValue`] error
     pub(crate) fn non_value(expr: Expr) -> Self {
         let source_loc = expr.source_loc().cloned();
@@ -325,6 +478,41 @@ impl EvaluationError {
     pub(crate) fn recursion_limit(source_loc: Option<Loc>) -> Self {
         evaluation_errors::RecursionLimitError { source_loc }.into()
     }
+
+    /// Construct a [`Cancelled`] error
+    pub(crate) fn cancelled(reason: CancellationReason, source_loc: Option<Loc>) -> Self {
+        evaluation_errors::CancelledError { reason, source_loc }.into()
+    }
+
+    /// Construct an [`EntityLoad`] error, for when computing the lazy
+    /// attribute `attr` of `entity` failed for the reason `kind`
+    pub fn entity_load(
+        entity: EntityUID,
+        attr: SmolStr,
+        kind: evaluation_errors::EntityLoadErrorKind,
+        message: String,
+    ) -> Self {
+        evaluation_errors::EntityLoadError {
+            entity: Arc::new(entity),
+            attr,
+            kind,
+            message,
+            source_loc: None,
+        }
+        .into()
+    }
+
+    /// Construct a [`ContextProvider`] error, for when the value provided
+    /// for the context attribute `attr` failed to evaluate with `error`, or
+    /// (if `error` is `None`) did not conform to the schema
+    pub(crate) fn context_provider(attr: SmolStr, error: Option<EvaluationError>) -> Self {
+        evaluation_errors::ContextProviderError {
+            attr,
+            error: error.map(Box::new),
+            source_loc: None,
+        }
+        .into()
+    }
 }
 
 /// Error subtypes for [`EvaluationError`]
@@ -338,7 +526,7 @@ pub mod evaluation_errors {
     use std::sync::Arc;
     use thiserror::Error;
 
-    use super::Name;
+    use super::{CancellationReason, Name};
 
     /// Tried to lookup an entity UID, but it didn't exist in the provided entities
     //
@@ -585,7 +773,7 @@ pub mod evaluation_errors {
     // Don't make fields `pub`, don't make breaking changes, and use caution
     // when adding public methods.
     #[derive(Debug, PartialEq, Eq, Clone, Error)]
-    #[error("integer overflow while attempting to {} the values `{arg1}` and `{arg2}`", match .op { BinaryOp::Add => "add", BinaryOp::Sub => "subtract", BinaryOp::Mul => "multiply", _ => "perform an operation on" })]
+    #[error("integer overflow while attempting to {} the values `{arg1}` and `{arg2}`", match .op { BinaryOp::Add => "add", BinaryOp::Sub => "subtract", BinaryOp::Mul => "multiply", BinaryOp::Div => "divide", _ => "perform an operation on" })]
     pub struct BinaryOpOverflowError {
         /// overflow while evaluating this operator
         pub(crate) op: BinaryOp,
@@ -601,6 +789,32 @@ pub mod evaluation_errors {
         impl_diagnostic_from_source_loc_opt_field!(source_loc);
     }
 
+    /// Integer division or remainder with a divisor of zero
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, PartialEq, Eq, Clone, Error)]
+    #[error("attempted to {} the value `{dividend}` by zero", match .op { BinaryOp::Rem => "take the remainder of dividing", _ => "divide" })]
+    pub struct DivisionByZeroError {
+        /// `/` or `%`
+        pub(crate) op: BinaryOp,
+        /// first argument to that operator
+        pub(crate) dividend: Value,
+        /// Source location
+        pub(crate) source_loc: Option<Loc>,
+    }
+
+    impl Diagnostic for DivisionByZeroError {
+        impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+        fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+            Some(Box::new(
+                "guard the operation with a check that the divisor is not 0",
+            ))
+        }
+    }
+
     /// Overflow during a unary operation
     //
     // CAUTION: this type is publicly exported in `cedar-policy`.
@@ -711,6 +925,155 @@ pub mod evaluation_errors {
     impl Diagnostic for RecursionLimitError {
         impl_diagnostic_from_source_loc_opt_field!(source_loc);
     }
+
+    /// Evaluation was cancelled, or its deadline passed, before it completed
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, PartialEq, Eq, Clone, Error)]
+    #[error("{reason}")]
+    pub struct CancelledError {
+        /// Why evaluation was abandoned
+        pub(crate) reason: CancellationReason,
+        /// Source location
+        pub(crate) source_loc: Option<Loc>,
+    }
+
+    impl CancelledError {
+        /// Why evaluation was abandoned
+        pub fn reason(&self) -> CancellationReason {
+            self.reason
+        }
+    }
+
+    impl Diagnostic for CancelledError {
+        impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    }
+
+    /// A lazily computed entity attribute could not be loaded, for instance
+    /// because the service it is loaded from timed out
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, PartialEq, Eq, Clone, Error)]
+    #[error("failed to load attribute `{attr}` of entity `{entity}` ({kind}): {message}")]
+    pub struct EntityLoadError {
+        /// Entity whose attribute could not be loaded
+        pub(crate) entity: Arc<EntityUID>,
+        /// Attribute which could not be loaded
+        pub(crate) attr: SmolStr,
+        /// Why the attribute could not be loaded
+        pub(crate) kind: EntityLoadErrorKind,
+        /// Error message from the loader
+        pub(crate) message: String,
+        /// Source location
+        pub(crate) source_loc: Option<Loc>,
+    }
+
+    impl EntityLoadError {
+        /// Entity whose attribute could not be loaded
+        pub fn entity(&self) -> &EntityUID {
+            &self.entity
+        }
+
+        /// Attribute which could not be loaded
+        pub fn attr(&self) -> &str {
+            &self.attr
+        }
+
+        /// Why the attribute could not be loaded
+        pub fn kind(&self) -> EntityLoadErrorKind {
+            self.kind
+        }
+
+        /// Error message from the loader
+        pub fn message(&self) -> &str {
+            &self.message
+        }
+    }
+
+    impl Diagnostic for EntityLoadError {
+        impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    }
+
+    /// Why a lazily computed entity attribute could not be loaded
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[non_exhaustive]
+    pub enum EntityLoadErrorKind {
+        /// Loading the attribute took too long
+        Timeout,
+        /// The data the attribute is loaded from does not exist
+        NotFound,
+        /// The service the attribute is loaded from is unavailable
+        Unavailable,
+        /// Any other failure
+        Other,
+    }
+
+    impl std::fmt::Display for EntityLoadErrorKind {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Timeout => write!(f, "timed out"),
+                Self::NotFound => write!(f, "not found"),
+                Self::Unavailable => write!(f, "unavailable"),
+                Self::Other => write!(f, "failed"),
+            }
+        }
+    }
+
+    /// The value a [`crate::authorizer::ContextProvider`] supplied for a
+    /// context attribute failed to evaluate, or did not conform to the type
+    /// the schema declares for the attribute
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, PartialEq, Eq, Clone, Error)]
+    pub struct ContextProviderError {
+        /// Context attribute whose provided value was invalid
+        pub(crate) attr: SmolStr,
+        /// Error evaluating the provided value, or `None` if it evaluated
+        /// successfully but did not conform to the schema
+        pub(crate) error: Option<Box<super::EvaluationError>>,
+        /// Source location
+        pub(crate) source_loc: Option<Loc>,
+    }
+
+    impl ContextProviderError {
+        /// Context attribute whose provided value was invalid
+        pub fn attr(&self) -> &str {
+            &self.attr
+        }
+
+        /// Error evaluating the provided value, or `None` if it evaluated
+        /// successfully but did not conform to the schema
+        pub fn evaluation_error(&self) -> Option<&super::EvaluationError> {
+            self.error.as_deref()
+        }
+    }
+
+    impl std::fmt::Display for ContextProviderError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match &self.error {
+                Some(error) => write!(
+                    f,
+                    "value provided for context attribute `{}` failed to evaluate: {error}",
+                    self.attr
+                ),
+                None => write!(
+                    f,
+                    "value provided for context attribute `{}` does not conform to the schema",
+                    self.attr
+                ),
+            }
+        }
+    }
+
+    impl Diagnostic for ContextProviderError {
+        impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    }
 }
 
 /// Type alias for convenience
@@ -0,0 +1,92 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Memoization of sub-expression results within a single request
+
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+use crate::ast::{BinaryOp, Expr, ExprKind, Value};
+
+/// Values of the expensive sub-expressions an [`super::Evaluator`] has
+/// evaluated, keyed by their structural hash (ignoring source locations), so
+/// that a sub-expression repeated within or across policies is evaluated
+/// once per request.
+///
+/// Only sub-expressions which read the entity hierarchy or tags, test set
+/// membership, or call extension functions are memoized, since hashing an
+/// expression is not free. Sub-expressions containing template slots are
+/// not memoized, because their values depend on the policy being evaluated,
+/// and neither are errors and residuals.
+#[derive(Debug, Default)]
+pub(crate) struct SubexpressionCache {
+    values: RefCell<HashMap<u64, Vec<(Expr, Value)>>>,
+    hits: Cell<u64>,
+}
+
+impl SubexpressionCache {
+    /// The structural hash of `e`, if it is worth memoizing
+    pub(crate) fn key(e: &Expr) -> Option<u64> {
+        let expensive = match e.expr_kind() {
+            ExprKind::BinaryApp { op, .. } => matches!(
+                op,
+                BinaryOp::In
+                    | BinaryOp::Contains
+                    | BinaryOp::ContainsAll
+                    | BinaryOp::ContainsAny
+                    | BinaryOp::GetTag
+                    | BinaryOp::HasTag
+            ),
+            ExprKind::ExtensionFunctionApp { .. } => true,
+            _ => false,
+        };
+        if !expensive || e.slots().next().is_some() {
+            return None;
+        }
+        let mut hasher = DefaultHasher::new();
+        e.hash_shape(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// The memoized value of `e`, whose key is `key`
+    pub(crate) fn get(&self, key: u64, e: &Expr) -> Option<Value> {
+        let value = self
+            .values
+            .borrow()
+            .get(&key)?
+            .iter()
+            .find(|(cached, _)| cached.eq_shape(e))
+            .map(|(_, v)| v.clone())?;
+        self.hits.set(self.hits.get() + 1);
+        Some(value)
+    }
+
+    /// Memoize the value of `e`, whose key is `key`
+    pub(crate) fn insert(&self, key: u64, e: &Expr, value: Value) {
+        self.values
+            .borrow_mut()
+            .entry(key)
+            .or_default()
+            .push((e.clone(), value));
+    }
+
+    /// Number of sub-expressions whose value was found in the cache
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.get()
+    }
+}
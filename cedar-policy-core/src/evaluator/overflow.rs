@@ -0,0 +1,34 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Configurable behavior on integer overflow
+
+use serde::{Deserialize, Serialize};
+
+/// What to do when `+`, `-`, `*`, `/`, or unary `-` on `Long`s overflows.
+/// Division only overflows for `i64::MIN / -1`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverflowBehavior {
+    /// Fail with an [`super::EvaluationError::IntegerOverflow`] error, so
+    /// the policy is skipped. This is the standard Cedar semantics.
+    #[default]
+    Error,
+    /// Clamp the result to the minimum or maximum `Long`. This deviates from
+    /// the standard Cedar semantics, so every policy whose evaluation
+    /// saturated is reported in the authorization response.
+    Saturate,
+}
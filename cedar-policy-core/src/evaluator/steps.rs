@@ -0,0 +1,92 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Recording the steps of an evaluation, for step-through debugging
+
+use std::cell::{Cell, RefCell};
+
+use super::EvaluationError;
+use crate::ast::{Expr, PartialValue};
+
+/// One step of an evaluation: the evaluator starting to evaluate a
+/// sub-expression, or finishing it with a value, residual, or error
+#[derive(Debug, Clone)]
+pub struct EvaluationStep {
+    expr: Expr,
+    depth: usize,
+    result: Option<Result<PartialValue, EvaluationError>>,
+}
+
+impl EvaluationStep {
+    /// The sub-expression being evaluated
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// How deeply the sub-expression is nested in the expression whose
+    /// evaluation was recorded, which has depth 0
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The result of the sub-expression, or `None` if this step starts its
+    /// evaluation
+    pub fn result(&self) -> Option<&Result<PartialValue, EvaluationError>> {
+        self.result.as_ref()
+    }
+}
+
+/// The steps of the evaluations performed by an [`super::Evaluator`] using
+/// it (see [`super::Evaluator::with_steps`]), in order
+#[derive(Debug, Default)]
+pub struct EvaluationSteps {
+    steps: RefCell<Vec<EvaluationStep>>,
+    depth: Cell<usize>,
+}
+
+impl EvaluationSteps {
+    /// Create an empty recording
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the steps recorded so far, leaving the recording empty
+    pub fn take(&self) -> Vec<EvaluationStep> {
+        self.steps.take()
+    }
+
+    /// Record the start of the evaluation of `expr`
+    pub(crate) fn start(&self, expr: &Expr) {
+        let depth = self.depth.get();
+        self.steps.borrow_mut().push(EvaluationStep {
+            expr: expr.clone(),
+            depth,
+            result: None,
+        });
+        self.depth.set(depth + 1);
+    }
+
+    /// Record that the evaluation of `expr` finished with `result`
+    pub(crate) fn finish(&self, expr: &Expr, result: &Result<PartialValue, EvaluationError>) {
+        let depth = self.depth.get().saturating_sub(1);
+        self.depth.set(depth);
+        self.steps.borrow_mut().push(EvaluationStep {
+            expr: expr.clone(),
+            depth,
+            result: Some(result.clone()),
+        });
+    }
+}
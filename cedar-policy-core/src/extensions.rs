@@ -169,6 +169,7 @@ impl<'a> Extensions<'a> {
             FuncDoesNotExistError {
                 name: name.clone(),
                 source_loc: name.loc().cloned(),
+                suggestion: util::suggest_function_name(name, self.functions.keys().copied()),
             }
             .into()
         })
@@ -177,7 +178,7 @@ impl<'a> Extensions<'a> {
     /// Iterate over all extension functions defined by all of these extensions.
     ///
     /// No guarantee that this list won't have duplicates or repeated names.
-    pub(crate) fn all_funcs(&self) -> impl Iterator<Item = &'a ExtensionFunction> {
+    pub fn all_funcs(&self) -> impl Iterator<Item = &'a ExtensionFunction> {
         self.extensions.iter().flat_map(|ext| ext.funcs())
     }
 
@@ -286,10 +287,18 @@ pub mod extension_function_lookup_errors {
         pub(crate) name: Name,
         /// Source location
         pub(crate) source_loc: Option<Loc>,
+        /// Name of a defined extension function similar to `name`
+        pub(crate) suggestion: Option<String>,
     }
 
     impl Diagnostic for FuncDoesNotExistError {
         impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+        fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+            self.suggestion
+                .as_ref()
+                .map(|suggestion| Box::new(format!("did you mean `{suggestion}`?")) as _)
+        }
     }
 }
 
@@ -298,8 +307,33 @@ pub type Result<T> = std::result::Result<T, ExtensionFunctionLookupError>;
 
 /// Utilities shared with the `cedar-policy-validator` extensions module.
 pub mod util {
+    use crate::ast::Name;
+    use crate::fuzzy_match::fuzzy_search_limited;
     use std::collections::{hash_map::Entry, HashMap};
 
+    /// Maximum edit distance between the name of an undefined function and a
+    /// defined one for the latter to be suggested in place of the former
+    const SUGGEST_FUNCTION_MAX_DISTANCE: usize = 3;
+
+    /// Get the name among `candidates` which is most similar to the undefined
+    /// function `name`, if one is similar enough to suggest in its place.
+    pub fn suggest_function_name<'a>(
+        name: &Name,
+        candidates: impl IntoIterator<Item = &'a Name>,
+    ) -> Option<String> {
+        let mut fnames = candidates
+            .into_iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        // sort so that ties are broken the same way every time
+        fnames.sort();
+        fuzzy_search_limited(
+            &name.to_string(),
+            fnames.as_slice(),
+            Some(SUGGEST_FUNCTION_MAX_DISTANCE),
+        )
+    }
+
     /// Utility to build a `HashMap` of key value pairs from an iterator,
     /// returning an `Err` result if there are any duplicate keys in the
     /// iterator.
@@ -327,6 +361,7 @@ pub mod util {
 #[cfg(test)]
 mod test {
     use super::*;
+    use cool_asserts::assert_matches;
     use std::collections::HashSet;
 
     #[test]
@@ -345,4 +380,21 @@ mod test {
         let dedup_names: HashSet<_> = all_names.iter().collect();
         assert_eq!(all_names.len(), dedup_names.len());
     }
+
+    #[test]
+    #[cfg(all(feature = "decimal", feature = "ipaddr"))]
+    fn suggest_similar_function_names() {
+        let suggestion = |exts: &Extensions<'_>, name: &str| {
+            assert_matches!(
+                exts.func(&Name::parse_unqualified_name(name).unwrap()),
+                Err(ExtensionFunctionLookupError::FuncDoesNotExist(e)) => e.suggestion
+            )
+        };
+        let exts = Extensions::all_available();
+        assert_eq!(suggestion(exts, "Decimal").as_deref(), Some("decimal"));
+        assert_eq!(suggestion(exts, "inRange").as_deref(), Some("isInRange"));
+        assert_eq!(suggestion(exts, "frobnicate"), None);
+        // only functions from active extensions are suggested
+        assert_eq!(suggestion(Extensions::none(), "Decimal"), None);
+    }
 }
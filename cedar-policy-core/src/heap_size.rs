@@ -0,0 +1,457 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Estimates of the heap memory used by policy sets and entities.
+//!
+//! The estimates are computed from the sizes of the values and collections
+//! involved, not by instrumenting the allocator, so they ignore allocator
+//! overhead and spare capacity. Data shared between several values (e.g.,
+//! through an [`Arc`]) is counted once per reference. They are meant for
+//! comparing tenants or spotting pathological policies and entities, not
+//! for exact accounting.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use crate::ast::{
+    ActionConstraint, Annotation, AnyId, Eid, Entity, EntityReference, EntityType, EntityUID, Expr,
+    ExprKind, Id, InternalName, Literal, Name, PartialValue, Pattern, PatternElem, PolicyID,
+    PolicySet, PrincipalOrResourceConstraint, SlotId, Template, Value, ValueKind,
+};
+use crate::entities::Entities;
+
+/// Values whose heap memory usage can be estimated
+pub trait HeapSize {
+    /// Approximate number of bytes of heap memory owned by `self`, not
+    /// counting `size_of_val(self)`
+    fn heap_size(&self) -> usize;
+}
+
+/// Bytes used by the reference counts of an [`Arc`]
+const ARC_HEADER: usize = 2 * size_of::<usize>();
+
+/// Strings of at most this many bytes are stored inline in a [`SmolStr`]
+const SMOL_STR_INLINE: usize = 23;
+
+/// Estimated heap memory of a value of type `T` behind an [`Arc`]
+pub fn arc_heap_size<T: HeapSize>(value: &T) -> usize {
+    ARC_HEADER + size_of::<T>() + value.heap_size()
+}
+
+/// Estimated heap memory of a collection of `len` items of type `T`, not
+/// counting the heap memory owned by the items
+pub fn collection_heap_size<T>(len: usize) -> usize {
+    len * size_of::<T>()
+}
+
+/// Estimated heap memory of a hash map, not counting the heap memory owned by
+/// its keys and values
+pub fn hash_map_heap_size<K, V>(map: &HashMap<K, V>) -> usize {
+    // One control byte per bucket, in addition to the entry
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+impl HeapSize for str {
+    fn heap_size(&self) -> usize {
+        if self.len() > SMOL_STR_INLINE {
+            ARC_HEADER + self.len()
+        } else {
+            0
+        }
+    }
+}
+
+impl HeapSize for SmolStr {
+    fn heap_size(&self) -> usize {
+        self.as_str().heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl HeapSize for Id {
+    fn heap_size(&self) -> usize {
+        self.as_ref().heap_size()
+    }
+}
+
+impl HeapSize for AnyId {
+    fn heap_size(&self) -> usize {
+        self.as_ref().heap_size()
+    }
+}
+
+impl HeapSize for PolicyID {
+    fn heap_size(&self) -> usize {
+        self.as_ref().heap_size()
+    }
+}
+
+impl HeapSize for Eid {
+    fn heap_size(&self) -> usize {
+        <Eid as AsRef<str>>::as_ref(self).heap_size()
+    }
+}
+
+impl HeapSize for InternalName {
+    fn heap_size(&self) -> usize {
+        let path = self.namespace_components().collect::<Vec<_>>();
+        self.basename().heap_size()
+            + ARC_HEADER
+            + collection_heap_size::<Id>(path.len())
+            + path.into_iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl HeapSize for Name {
+    fn heap_size(&self) -> usize {
+        self.as_ref().heap_size()
+    }
+}
+
+impl HeapSize for EntityType {
+    fn heap_size(&self) -> usize {
+        self.name().heap_size()
+    }
+}
+
+impl HeapSize for EntityUID {
+    fn heap_size(&self) -> usize {
+        self.entity_type().heap_size() + self.eid().heap_size()
+    }
+}
+
+impl HeapSize for Literal {
+    fn heap_size(&self) -> usize {
+        match self {
+            Literal::Bool(_) | Literal::Long(_) => 0,
+            Literal::String(s) => s.heap_size(),
+            Literal::EntityUID(euid) => arc_heap_size(euid.as_ref()),
+        }
+    }
+}
+
+impl HeapSize for Pattern {
+    fn heap_size(&self) -> usize {
+        ARC_HEADER
+            + std::mem::size_of_val(self.get_elems())
+            + self
+                .iter()
+                .map(|elem| match elem {
+                    PatternElem::Char(_) | PatternElem::Wildcard => 0,
+                    PatternElem::Class(class) => {
+                        collection_heap_size::<(char, char)>(class.ranges().len())
+                    }
+                    PatternElem::Repeat { pattern, .. } => pattern.heap_size(),
+                })
+                .sum::<usize>()
+    }
+}
+
+impl<T> HeapSize for Expr<T> {
+    fn heap_size(&self) -> usize {
+        let node = match self.expr_kind() {
+            ExprKind::Lit(lit) => lit.heap_size(),
+            ExprKind::Unknown(u) => u.name.heap_size(),
+            ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                fn_name.heap_size() + ARC_HEADER + collection_heap_size::<Expr<T>>(args.len())
+            }
+            ExprKind::GetAttr { attr, .. } | ExprKind::HasAttr { attr, .. } => attr.heap_size(),
+            ExprKind::Like { pattern, .. } => pattern.heap_size(),
+            ExprKind::Is { entity_type, .. } => entity_type.heap_size(),
+            ExprKind::Set(elems) => ARC_HEADER + collection_heap_size::<Expr<T>>(elems.len()),
+            ExprKind::Record(fields) => {
+                ARC_HEADER
+                    + collection_heap_size::<(SmolStr, Expr<T>)>(fields.len())
+                    + fields.keys().map(HeapSize::heap_size).sum::<usize>()
+            }
+            _ => 0,
+        };
+        // Children of sets, records, and extension function calls are stored
+        // inline in their collection, which is counted above; all others are
+        // behind their own `Arc`
+        let children_inline = matches!(
+            self.expr_kind(),
+            ExprKind::Set(_) | ExprKind::Record(_) | ExprKind::ExtensionFunctionApp { .. }
+        );
+        node + self
+            .children()
+            .into_iter()
+            .map(|child| {
+                if children_inline {
+                    child.heap_size()
+                } else {
+                    arc_heap_size(child)
+                }
+            })
+            .sum::<usize>()
+    }
+}
+
+impl HeapSize for Value {
+    fn heap_size(&self) -> usize {
+        match self.value_kind() {
+            ValueKind::Lit(lit) => lit.heap_size(),
+            ValueKind::Set(set) => {
+                ARC_HEADER
+                    + collection_heap_size::<Value>(set.authoritative.len())
+                    + set
+                        .authoritative
+                        .iter()
+                        .map(HeapSize::heap_size)
+                        .sum::<usize>()
+                    + set.fast.as_ref().map_or(0, |fast| {
+                        ARC_HEADER + fast.capacity() * (size_of::<Literal>() + 1)
+                    })
+            }
+            ValueKind::Record(fields) => {
+                ARC_HEADER
+                    + collection_heap_size::<(SmolStr, Value)>(fields.len())
+                    + fields
+                        .iter()
+                        .map(|(k, v)| k.heap_size() + v.heap_size())
+                        .sum::<usize>()
+            }
+            ValueKind::ExtensionValue(ev) => ARC_HEADER + std::mem::size_of_val(ev.as_ref()),
+        }
+    }
+}
+
+impl HeapSize for PartialValue {
+    fn heap_size(&self) -> usize {
+        match self {
+            PartialValue::Value(v) => v.heap_size(),
+            PartialValue::Residual(e) => e.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Entity {
+    fn heap_size(&self) -> usize {
+        self.uid().heap_size()
+            + collection_heap_size::<(SmolStr, PartialValue)>(self.attrs_len() + self.tags_len())
+            + self
+                .attrs()
+                .chain(self.tags())
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+            + self
+                .ancestors()
+                .map(|a| size_of::<EntityUID>() + 1 + a.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl HeapSize for Annotation {
+    fn heap_size(&self) -> usize {
+        self.val.heap_size()
+    }
+}
+
+/// Estimated heap memory used by the entity references in a policy scope
+fn scope_heap_size(t: &Template) -> usize {
+    let principal_or_resource = |c: &PrincipalOrResourceConstraint| match c {
+        PrincipalOrResourceConstraint::Any => 0,
+        PrincipalOrResourceConstraint::In(r) | PrincipalOrResourceConstraint::Eq(r) => {
+            entity_reference_heap_size(r)
+        }
+        PrincipalOrResourceConstraint::Is(ety) => arc_heap_size(ety.as_ref()),
+        PrincipalOrResourceConstraint::IsIn(ety, r) => {
+            arc_heap_size(ety.as_ref()) + entity_reference_heap_size(r)
+        }
+    };
+    let action = match t.action_constraint() {
+        ActionConstraint::Any => 0,
+        ActionConstraint::In(euids) => {
+            collection_heap_size::<Arc<EntityUID>>(euids.len())
+                + euids
+                    .iter()
+                    .map(|euid| arc_heap_size(euid.as_ref()))
+                    .sum::<usize>()
+        }
+        ActionConstraint::Eq(euid) => arc_heap_size(euid.as_ref()),
+    };
+    principal_or_resource(t.principal_constraint().as_inner())
+        + action
+        + principal_or_resource(t.resource_constraint().as_inner())
+}
+
+fn entity_reference_heap_size(r: &EntityReference) -> usize {
+    match r {
+        EntityReference::EUID(euid) => arc_heap_size(euid.as_ref()),
+        EntityReference::Slot(_) => 0,
+    }
+}
+
+/// Estimated heap memory used by a [`PolicySet`], broken down by component.
+/// See the [module documentation](self) for how the estimates are computed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicySetMemoryUsage {
+    asts: usize,
+    annotations: usize,
+    links: usize,
+}
+
+impl PolicySetMemoryUsage {
+    /// Estimate the heap memory used by `pset`
+    pub fn new(pset: &PolicySet) -> Self {
+        let mut usage = Self::default();
+        for t in pset.all_templates() {
+            usage.asts +=
+                arc_heap_size(t.non_scope_constraints()) + scope_heap_size(t) + t.id().heap_size();
+            let annotations = t.annotations().collect::<Vec<_>>();
+            usage.annotations += ARC_HEADER
+                + collection_heap_size::<(AnyId, Annotation)>(annotations.len())
+                + annotations
+                    .into_iter()
+                    .map(|(k, v)| k.heap_size() + v.heap_size())
+                    .sum::<usize>();
+        }
+        // Static policies are stored as links without slot values
+        for p in pset.policies() {
+            usage.links += size_of::<(PolicyID, Arc<Template>)>()
+                + p.id().heap_size()
+                + hash_map_heap_size::<SlotId, EntityUID>(p.env())
+                + p.env().values().map(HeapSize::heap_size).sum::<usize>();
+        }
+        usage
+    }
+
+    /// Memory used by the policy scopes and conditions
+    pub fn asts(&self) -> usize {
+        self.asts
+    }
+
+    /// Memory used by policy annotations
+    pub fn annotations(&self) -> usize {
+        self.annotations
+    }
+
+    /// Memory used by static policies and template links, other than the
+    /// policies and templates they refer to
+    pub fn links(&self) -> usize {
+        self.links
+    }
+
+    /// Total memory used by the policy set
+    pub fn total(&self) -> usize {
+        self.asts + self.annotations + self.links
+    }
+}
+
+/// Estimated heap memory used by a set of [`Entities`], broken down by
+/// component. See the [module documentation](self) for how the estimates are
+/// computed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitiesMemoryUsage {
+    uids: usize,
+    attribute_values: usize,
+    closure: usize,
+}
+
+impl EntitiesMemoryUsage {
+    /// Estimate the heap memory used by `entities`
+    pub fn new(entities: &Entities) -> Self {
+        let mut usage = Self::default();
+        for e in entities.iter() {
+            // The map entry, the `Arc`ed entity, and its UID, which is also
+            // the map key
+            usage.uids += size_of::<(EntityUID, Arc<Entity>)>()
+                + 1
+                + ARC_HEADER
+                + size_of::<Entity>()
+                + 2 * e.uid().heap_size();
+            usage.attribute_values +=
+                collection_heap_size::<(SmolStr, PartialValue)>(e.attrs_len() + e.tags_len())
+                    + e.attrs()
+                        .chain(e.tags())
+                        .map(|(k, v)| k.heap_size() + v.heap_size())
+                        .sum::<usize>();
+            usage.closure += e
+                .ancestors()
+                .map(|a| size_of::<EntityUID>() + 1 + a.heap_size())
+                .sum::<usize>();
+        }
+        usage
+    }
+
+    /// Memory used by entity UIDs and per-entity bookkeeping
+    pub fn uids(&self) -> usize {
+        self.uids
+    }
+
+    /// Memory used by attribute and tag names and values
+    pub fn attribute_values(&self) -> usize {
+        self.attribute_values
+    }
+
+    /// Memory used by the transitively closed ancestor sets of the entities
+    pub fn closure(&self) -> usize {
+        self.closure
+    }
+
+    /// Total memory used by the entities
+    pub fn total(&self) -> usize {
+        self.uids + self.attribute_values + self.closure
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_policyset;
+
+    #[test]
+    fn larger_policies_use_more_memory() {
+        let small = parse_policyset(r#"permit(principal, action, resource);"#).unwrap();
+        let large = parse_policyset(
+            r#"
+            @id("a long annotation value which is not stored inline")
+            permit(principal == User::"alice", action in [Action::"view", Action::"edit"], resource)
+            when { resource.tags.containsAny(["a", "b", "c"]) && context.time > 5 };
+            "#,
+        )
+        .unwrap();
+        let small = PolicySetMemoryUsage::new(&small);
+        let large = PolicySetMemoryUsage::new(&large);
+        assert!(large.asts() > small.asts());
+        assert!(large.annotations() > small.annotations());
+        assert_eq!(small.links(), large.links());
+        assert_eq!(
+            large.total(),
+            large.asts() + large.annotations() + large.links()
+        );
+    }
+
+    #[test]
+    fn smol_str_inline() {
+        assert_eq!(SmolStr::new("short").heap_size(), 0);
+        assert_eq!(
+            SmolStr::new("a string which is too long to inline").heap_size(),
+            ARC_HEADER + 36
+        );
+    }
+}
@@ -32,7 +32,9 @@ pub mod est;
 pub mod evaluator;
 pub mod extensions;
 pub mod fuzzy_match;
+pub mod heap_size;
 pub mod jsonvalue;
+pub mod limits;
 pub mod parser;
 pub mod transitive_closure;
 
@@ -0,0 +1,348 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Configurable limits on the size of policies and requests, for services
+//! that accept untrusted input.
+
+use crate::ast::{Expr, ExprKind, PolicySet, Template};
+use crate::entities::Entities;
+use crate::parser::Loc;
+use miette::Diagnostic;
+use thiserror::Error;
+
+/// Limits on the resources used to parse policies and authorize requests.
+/// Every limit is unset by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    max_policy_text_size: Option<usize>,
+    max_expression_depth: Option<usize>,
+    max_set_literal_size: Option<usize>,
+    max_entity_store_size: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the length (in bytes) of policy text accepted by the parser
+    #[must_use]
+    pub fn with_max_policy_text_size(self, limit: usize) -> Self {
+        Self {
+            max_policy_text_size: Some(limit),
+            ..self
+        }
+    }
+
+    /// Limit the nesting depth of policy condition expressions.
+    ///
+    /// Before parsing, policy text is also rejected if its brackets are
+    /// nested more deeply than this (not counting the outermost brackets of a
+    /// policy, such as those around its scope or a `when` condition), even if
+    /// some of the brackets are redundant parentheses.
+    #[must_use]
+    pub fn with_max_expression_depth(self, limit: usize) -> Self {
+        Self {
+            max_expression_depth: Some(limit),
+            ..self
+        }
+    }
+
+    /// Limit the number of elements in a set literal in a policy
+    #[must_use]
+    pub fn with_max_set_literal_size(self, limit: usize) -> Self {
+        Self {
+            max_set_literal_size: Some(limit),
+            ..self
+        }
+    }
+
+    /// Limit the number of entities in the entity store that requests are
+    /// authorized against
+    #[must_use]
+    pub fn with_max_entity_store_size(self, limit: usize) -> Self {
+        Self {
+            max_entity_store_size: Some(limit),
+            ..self
+        }
+    }
+
+    /// Check the length and bracket nesting of policy text, before parsing it
+    pub fn check_policy_text(&self, text: &str) -> Result<(), LimitError> {
+        match self.max_policy_text_size {
+            Some(limit) if text.len() > limit => Err(LimitError::PolicyTextTooLarge {
+                size: text.len(),
+                limit,
+            }),
+            _ => self.check_bracket_nesting(text),
+        }
+    }
+
+    /// Check that the brackets in `text`, ignoring those in string literals
+    /// and comments, aren't nested more deeply than the maximum expression
+    /// depth. The outermost level of brackets doesn't count, since it
+    /// encloses the scope or a condition of a policy rather than an
+    /// expression.
+    ///
+    /// This bounds the depth of the parse tree, so that deeply nested input
+    /// is rejected before the parser recurses into it.
+    fn check_bracket_nesting(&self, text: &str) -> Result<(), LimitError> {
+        let Some(limit) = self.max_expression_depth else {
+            return Ok(());
+        };
+        let mut depth: usize = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut in_comment = false;
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if in_comment {
+                in_comment = c != '\n';
+            } else if in_string {
+                in_string = escaped || c != '"';
+                escaped = !escaped && c == '\\';
+            } else {
+                match c {
+                    '"' => in_string = true,
+                    '/' if chars.peek().is_some_and(|(_, c)| *c == '/') => in_comment = true,
+                    '(' | '[' | '{' => {
+                        depth += 1;
+                        if depth > limit + 1 {
+                            return Err(LimitError::ExpressionTooDeep {
+                                limit,
+                                source_loc: Some(Loc::new(i..i + 1, text.into())),
+                            });
+                        }
+                    }
+                    ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the conditions of every static policy and template in `pset`,
+    /// returning the first violation found in each
+    pub fn check_policy_set(&self, pset: &PolicySet) -> Vec<LimitError> {
+        pset.all_templates()
+            .filter_map(|t| self.check_template(t).err())
+            .collect()
+    }
+
+    /// Check the conditions of a single static policy or template
+    pub fn check_template(&self, t: &Template) -> Result<(), LimitError> {
+        if self.max_expression_depth.is_none() && self.max_set_literal_size.is_none() {
+            return Ok(());
+        }
+        self.check_expr(t.non_scope_constraints())
+    }
+
+    /// Check `e` and its subexpressions in source order, using an explicit
+    /// stack so that deeply nested expressions can't overflow the call stack
+    fn check_expr<T>(&self, e: &Expr<T>) -> Result<(), LimitError> {
+        let mut stack = vec![(e, 1)];
+        while let Some((e, depth)) = stack.pop() {
+            if let Some(limit) = self.max_expression_depth {
+                if depth > limit {
+                    return Err(LimitError::ExpressionTooDeep {
+                        limit,
+                        source_loc: e.source_loc().cloned(),
+                    });
+                }
+            }
+            if let (ExprKind::Set(elems), Some(limit)) = (e.expr_kind(), self.max_set_literal_size)
+            {
+                if elems.len() > limit {
+                    return Err(LimitError::SetLiteralTooLarge {
+                        size: elems.len(),
+                        limit,
+                        source_loc: e.source_loc().cloned(),
+                    });
+                }
+            }
+            stack.extend(
+                e.children()
+                    .into_iter()
+                    .rev()
+                    .map(|child| (child, depth + 1)),
+            );
+        }
+        Ok(())
+    }
+
+    /// Check the number of entities in the entity store a request is
+    /// authorized against
+    pub fn check_entity_store(&self, entities: &Entities) -> Result<(), LimitError> {
+        match self.max_entity_store_size {
+            Some(limit) if entities.len() > limit => Err(LimitError::EntityStoreTooLarge {
+                size: entities.len(),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Errors raised when input exceeds a configured [`ResourceLimits`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LimitError {
+    /// The policy text is longer than allowed
+    #[error("policy text is {size} bytes long, which exceeds the limit of {limit} bytes")]
+    PolicyTextTooLarge {
+        /// Length of the policy text
+        size: usize,
+        /// Configured limit
+        limit: usize,
+    },
+    /// A policy condition is nested more deeply than allowed
+    #[error("expression exceeds the maximum nesting depth of {limit}")]
+    ExpressionTooDeep {
+        /// Configured limit
+        limit: usize,
+        /// Source location of the first subexpression beyond the limit
+        source_loc: Option<Loc>,
+    },
+    /// A set literal has more elements than allowed
+    #[error("set literal has {size} elements, which exceeds the limit of {limit}")]
+    SetLiteralTooLarge {
+        /// Number of elements in the set literal
+        size: usize,
+        /// Configured limit
+        limit: usize,
+        /// Source location of the set literal
+        source_loc: Option<Loc>,
+    },
+    /// The entity store a request was authorized against has more entities
+    /// than allowed
+    #[error("entity store has {size} entities, which exceeds the limit of {limit}")]
+    EntityStoreTooLarge {
+        /// Number of entities in the entity store
+        size: usize,
+        /// Configured limit
+        limit: usize,
+    },
+}
+
+impl LimitError {
+    /// Extract the source location of the error, if one is attached
+    pub fn source_loc(&self) -> Option<&Loc> {
+        match self {
+            Self::ExpressionTooDeep { source_loc, .. }
+            | Self::SetLiteralTooLarge { source_loc, .. } => source_loc.as_ref(),
+            Self::PolicyTextTooLarge { .. } | Self::EntityStoreTooLarge { .. } => None,
+        }
+    }
+}
+
+impl Diagnostic for LimitError {
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.source_loc().and_then(Loc::source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        self.source_loc()
+            .map(|loc| Box::new(std::iter::once(miette::LabeledSpan::underline(loc.span))) as _)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{err::ParseError, parse_policyset, parse_policyset_with_limits};
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn policy_text() {
+        let limits = ResourceLimits::new().with_max_policy_text_size(10);
+        assert_eq!(limits.check_policy_text("0123456789"), Ok(()));
+        assert_eq!(
+            limits.check_policy_text("0123456789a"),
+            Err(LimitError::PolicyTextTooLarge {
+                size: 11,
+                limit: 10
+            })
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        let pset = parse_policyset(
+            r#"
+            permit(principal, action, resource) when { [1, 2, 3].contains(1) };
+            permit(principal, action, resource) when { if true then (if true then 1 else 2) == 1 else false };
+            "#,
+        )
+        .unwrap();
+        assert_eq!(ResourceLimits::new().check_policy_set(&pset), vec![]);
+        assert_matches!(
+            ResourceLimits::new().with_max_set_literal_size(2).check_policy_set(&pset).as_slice(),
+            [LimitError::SetLiteralTooLarge { size: 3, limit: 2, source_loc: Some(loc) }] => {
+                assert_eq!(loc.snippet(), Some("[1, 2, 3]"));
+            }
+        );
+        assert_eq!(
+            ResourceLimits::new()
+                .with_max_expression_depth(4)
+                .check_policy_set(&pset)
+                .len(),
+            0
+        );
+        assert_matches!(
+            ResourceLimits::new()
+                .with_max_expression_depth(3)
+                .check_policy_set(&pset)
+                .as_slice(),
+            [LimitError::ExpressionTooDeep { limit: 3, .. }]
+        );
+    }
+
+    #[test]
+    fn bracket_nesting() {
+        let limits = ResourceLimits::new().with_max_expression_depth(2);
+        assert_eq!(
+            limits.check_policy_text(
+                r#"permit(principal, action in [Action::"a"], resource) when { [[1]] == "(((" }; // (((("#
+            ),
+            Ok(())
+        );
+        assert_matches!(
+            limits.check_policy_text("permit(principal, action, resource) when { [[(1)]] };"),
+            Err(LimitError::ExpressionTooDeep { limit: 2, source_loc: Some(loc) }) => {
+                assert_eq!(loc.start(), 45);
+            }
+        );
+
+        let deep = format!(
+            "permit(principal, action, resource) when {{ {}1{} }};",
+            "(".repeat(100_000),
+            ")".repeat(100_000)
+        );
+        assert_matches!(
+            parse_policyset_with_limits(&deep, &ResourceLimits::new().with_max_expression_depth(50)),
+            Err(errs) => {
+                assert_matches!(errs.first(), ParseError::Limit(LimitError::ExpressionTooDeep { limit: 50, .. }));
+            }
+        );
+    }
+
+    #[test]
+    fn entity_store() {
+        let limits = ResourceLimits::new().with_max_entity_store_size(0);
+        assert_eq!(limits.check_entity_store(&Entities::new()), Ok(()));
+    }
+}
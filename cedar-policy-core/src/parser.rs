@@ -44,12 +44,28 @@ use std::collections::HashMap;
 use crate::ast;
 use crate::ast::RestrictedExpressionParseError;
 use crate::est;
+use crate::limits::ResourceLimits;
+
+/// Emit a `tracing` event for each parse error, when the `tracing` feature is
+/// enabled
+fn trace_errors(errs: err::ParseErrors) -> err::ParseErrors {
+    #[cfg(feature = "tracing")]
+    for e in &errs {
+        tracing::debug!(error = %e, "parse error");
+    }
+    errs
+}
 
 /// simple main function for parsing policies
 /// generates numbered ids
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(bytes = text.len()))
+)]
 pub fn parse_policyset(text: &str) -> Result<ast::PolicySet, err::ParseErrors> {
-    let cst = text_to_cst::parse_policies(text)?;
-    cst.to_policyset()
+    text_to_cst::parse_policies(text)
+        .and_then(|cst| cst.to_policyset())
+        .map_err(trace_errors)
 }
 
 /// Like `parse_policyset()`, but also returns the (lossless) original text of
@@ -57,11 +73,24 @@ pub fn parse_policyset(text: &str) -> Result<ast::PolicySet, err::ParseErrors> {
 /// INVARIANT: The `PolicyId` of every `Policy` and `Template` returned by the
 /// `policies()` and `templates()` methods on the returned `Policy` _must_
 /// appear as a key in the returned map.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(bytes = text.len()))
+)]
 pub fn parse_policyset_and_also_return_policy_text(
     text: &str,
 ) -> Result<(HashMap<ast::PolicyID, &str>, ast::PolicySet), err::ParseErrors> {
-    let cst = text_to_cst::parse_policies(text)?;
-    let pset = cst.to_policyset()?;
+    to_policyset_and_policy_text(text, text_to_cst::parse_policies(text))
+}
+
+/// Convert `cst`, parsed from `text`, as in
+/// `parse_policyset_and_also_return_policy_text()`
+fn to_policyset_and_policy_text(
+    text: &str,
+    cst: Result<Node<Option<cst::Policies>>, err::ParseErrors>,
+) -> Result<(HashMap<ast::PolicyID, &str>, ast::PolicySet), err::ParseErrors> {
+    let cst = cst.map_err(trace_errors)?;
+    let pset = cst.to_policyset().map_err(trace_errors)?;
     // PANIC SAFETY Shouldn't be `none` since `parse_policies()` and `to_policyset()` didn't return `Err`
     #[allow(clippy::expect_used)]
     // PANIC SAFETY Indexing is safe because of how the `SourceSpan` is constructed
@@ -79,6 +108,45 @@ pub fn parse_policyset_and_also_return_policy_text(
     Ok((texts, pset))
 }
 
+/// Like `parse_policyset_and_also_return_policy_text()`, but rejects `text`
+/// if it, or any policy it contains, exceeds the given `limits`.
+pub fn parse_policyset_with_limits<'a>(
+    text: &'a str,
+    limits: &ResourceLimits,
+) -> Result<(HashMap<ast::PolicyID, &'a str>, ast::PolicySet), err::ParseErrors> {
+    limits.check_policy_text(text)?;
+    let (texts, pset) = parse_policyset_and_also_return_policy_text(text)?;
+    check_policy_set_limits(texts, pset, limits)
+}
+
+/// Like `parse_policyset_with_limits()`, but the source locations in the
+/// returned policy set don't retain `text`, which saves a copy of it for as
+/// long as the policies are kept. The locations still locate each node by
+/// offset, but errors about the policies (e.g., validation errors) are
+/// reported without source snippets. Errors from parsing `text` itself are
+/// reported with snippets as usual.
+pub fn parse_policyset_without_source<'a>(
+    text: &'a str,
+    limits: &ResourceLimits,
+) -> Result<(HashMap<ast::PolicyID, &'a str>, ast::PolicySet), err::ParseErrors> {
+    limits.check_policy_text(text)?;
+    let (texts, pset) =
+        to_policyset_and_policy_text(text, text_to_cst::parse_policies_without_source(text))?;
+    check_policy_set_limits(texts, pset, limits)
+}
+
+/// Return `texts` and `pset`, unless `pset` exceeds the given `limits`
+fn check_policy_set_limits<'a>(
+    texts: HashMap<ast::PolicyID, &'a str>,
+    pset: ast::PolicySet,
+    limits: &ResourceLimits,
+) -> Result<(HashMap<ast::PolicyID, &'a str>, ast::PolicySet), err::ParseErrors> {
+    match err::ParseErrors::from_iter(limits.check_policy_set(&pset).into_iter().map(Into::into)) {
+        Some(errs) => Err(errs),
+        None => Ok((texts, pset)),
+    }
+}
+
 /// Like `parse_policyset()`, but also returns the (lossless) ESTs -- that is,
 /// the ESTs of the original policies without any of the lossy transforms
 /// involved in converting to AST.
@@ -104,13 +172,18 @@ pub fn parse_policyset_to_ests_and_pset(
 /// returned value will be a [`ast::Template`].
 /// If `id` is Some, then the resulting template will have that `id`.
 /// If the `id` is None, the parser will use "policy0".
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(id = ?id, bytes = text.len()))
+)]
 pub fn parse_policy_or_template(
     id: Option<ast::PolicyID>,
     text: &str,
 ) -> Result<ast::Template, err::ParseErrors> {
     let id = id.unwrap_or_else(|| ast::PolicyID::from_string("policy0"));
-    let cst = text_to_cst::parse_policy(text)?;
-    cst.to_policy_template(id)
+    text_to_cst::parse_policy(text)
+        .and_then(|cst| cst.to_policy_template(id))
+        .map_err(trace_errors)
 }
 
 /// Like `parse_policy_or_template()`, but also returns the (lossless) EST -- that
@@ -131,15 +204,21 @@ pub fn parse_policy_or_template_to_est_and_ast(
 /// Will return an error if provided with a static policy.
 /// If `id` is Some, then the resulting policy will have that `id`.
 /// If the `id` is None, the parser will use "policy0".
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(id = ?id, bytes = text.len()))
+)]
 pub fn parse_template(
     id: Option<ast::PolicyID>,
     text: &str,
 ) -> Result<ast::Template, err::ParseErrors> {
     let id = id.unwrap_or_else(|| ast::PolicyID::from_string("policy0"));
-    let cst = text_to_cst::parse_policy(text)?;
-    let template = cst.to_policy_template(id)?;
+    let cst = text_to_cst::parse_policy(text).map_err(trace_errors)?;
+    let template = cst.to_policy_template(id).map_err(trace_errors)?;
     if template.slots().count() == 0 {
-        Err(err::ToASTError::new(err::ToASTErrorKind::expected_template(), cst.loc).into())
+        Err(trace_errors(
+            err::ToASTError::new(err::ToASTErrorKind::expected_template(), cst.loc).into(),
+        ))
     } else {
         Ok(template)
     }
@@ -149,13 +228,18 @@ pub fn parse_template(
 /// Will return an error if provided with a template.
 /// If `id` is Some, then the resulting policy will have that `id`.
 /// If the `id` is None, the parser will use "policy0".
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(id = ?id, bytes = text.len()))
+)]
 pub fn parse_policy(
     id: Option<ast::PolicyID>,
     text: &str,
 ) -> Result<ast::StaticPolicy, err::ParseErrors> {
     let id = id.unwrap_or_else(|| ast::PolicyID::from_string("policy0"));
-    let cst = text_to_cst::parse_policy(text)?;
-    cst.to_policy(id)
+    text_to_cst::parse_policy(text)
+        .and_then(|cst| cst.to_policy(id))
+        .map_err(trace_errors)
 }
 
 /// Like `parse_policy()`, but also returns the (lossless) EST -- that is, the
@@ -181,6 +265,18 @@ pub fn parse_policy_or_template_to_est(text: &str) -> Result<est::Policy, err::P
     parse_policy_or_template_to_est_and_ast(None, text).map(|(est, _ast)| est)
 }
 
+/// parse entity declarations in the Cedar entity text format, in order
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(bytes = text.len()))
+)]
+pub fn parse_entities(
+    text: &str,
+) -> Result<Vec<crate::entities::text::EntityDecl>, err::ParseErrors> {
+    let cst = text_to_cst::parse_entities(text)?;
+    cst.to_entity_decls().map_err(trace_errors)
+}
+
 /// parse an Expr
 ///
 /// Private to this crate. Users outside Core should use `Expr`'s `FromStr` impl
@@ -1228,3 +1324,79 @@ mod tests {
         test_invalid(r"\aaa\u{}", vec!["\\a", "\\u{}"]);
     }
 }
+
+#[cfg(all(test, feature = "tracing"))]
+// PANIC SAFETY unit tests
+#[allow(clippy::unwrap_used)]
+mod tracing_tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::{field, span, Event, Metadata, Subscriber};
+
+    /// Records the name of each new span and the `error` field of each event
+    #[derive(Debug, Default, Clone)]
+    struct Recorder {
+        spans: Arc<Mutex<Vec<&'static str>>>,
+        errors: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct ErrorField(Option<String>);
+
+    impl field::Visit for ErrorField {
+        fn record_debug(&mut self, field: &field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "error" {
+                self.0 = Some(format!("{value:?}"));
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata().name());
+            span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut error = ErrorField(None);
+            event.record(&mut error);
+            self.errors.lock().unwrap().extend(error.0);
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn parse_errors_are_traced() {
+        let recorder = Recorder::default();
+        let src = "permit(principal, action, resource) when { 1 + };\nforbid(principal, action, resource) when { ?principal };";
+        let errs = tracing::subscriber::with_default(recorder.clone(), || parse_policyset(src))
+            .unwrap_err();
+        assert_eq!(*recorder.spans.lock().unwrap(), vec!["parse_policyset"]);
+        assert_eq!(
+            *recorder.errors.lock().unwrap(),
+            errs.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn successful_parse_is_quiet() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            parse_policy(None, "permit(principal, action, resource);")
+        })
+        .unwrap();
+        assert_eq!(*recorder.spans.lock().unwrap(), vec!["parse_policy"]);
+        assert!(recorder.errors.lock().unwrap().is_empty());
+    }
+}
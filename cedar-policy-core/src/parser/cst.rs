@@ -25,6 +25,26 @@ type Node<N> = super::node::Node<Option<N>>;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Policies(pub Vec<Node<Policy>>);
 
+/// The set of entity declarations in the Cedar entity text format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entities(pub Vec<Node<EntityDecl>>);
+
+/// One entity declaration in the Cedar entity text format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityDecl {
+    /// keyword, expected: `entity`
+    pub keyword: Node<Ident>,
+    /// uid of the entity
+    pub uid: Node<Ref>,
+    /// attributes, expected to be a record literal
+    pub attrs: Option<Node<Primary>>,
+    /// parents, expected to be an entity reference or a list of them
+    pub parents: Option<Node<Primary>>,
+    /// tags keyword (expected: `tags`) and tags, expected to be a record
+    /// literal
+    pub tags: Option<(Node<Ident>, Node<Primary>)>,
+}
+
 /// Annotations: application-defined data, as a key-value pair
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Annotation {
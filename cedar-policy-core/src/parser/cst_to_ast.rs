@@ -37,13 +37,15 @@ use super::cst;
 use super::err::{parse_errors, ParseError, ParseErrors, ToASTError, ToASTErrorKind};
 use super::loc::Loc;
 use super::node::Node;
-use super::unescape::{to_pattern, to_unescaped_string};
+use super::unescape::{parse_pattern, to_pattern_tokens, to_unescaped_string};
 use super::util::{flatten_tuple_2, flatten_tuple_3, flatten_tuple_4};
 use crate::ast::{
-    self, ActionConstraint, CallStyle, Integer, Pattern, PatternElem, PolicySetError,
-    PrincipalConstraint, PrincipalOrResourceConstraint, ResourceConstraint, UnreservedId,
+    self, ActionConstraint, CallStyle, Integer, Pattern, PolicySetError, PrincipalConstraint,
+    PrincipalOrResourceConstraint, ResourceConstraint, UnreservedId,
 };
-use crate::est::{extract_single_argument, require_zero_arguments};
+use crate::entities::text::EntityDecl;
+use crate::est::{extract_single_argument, extract_two_arguments, require_zero_arguments};
+use crate::extensions::util::suggest_function_name;
 use crate::fuzzy_match::fuzzy_search_limited;
 use itertools::Either;
 use nonempty::nonempty;
@@ -128,7 +130,7 @@ impl Node<Option<cst::Policies>> {
                 Ok(Either::Right(template)) => {
                     if let Err(e) = pset.add_template(template) {
                         match e {
-                            PolicySetError::Occupied { id } => all_errs.push(
+                            PolicySetError::Occupied { id, .. } => all_errs.push(
                                 self.to_ast_err(ToASTErrorKind::DuplicateTemplateId(id))
                                     .into(),
                             ),
@@ -138,7 +140,7 @@ impl Node<Option<cst::Policies>> {
                 Ok(Either::Left(inline_policy)) => {
                     if let Err(e) = pset.add_static(inline_policy) {
                         match e {
-                            PolicySetError::Occupied { id } => all_errs.push(
+                            PolicySetError::Occupied { id, .. } => all_errs.push(
                                 self.to_ast_err(ToASTErrorKind::DuplicatePolicyId(id))
                                     .into(),
                             ),
@@ -160,6 +162,53 @@ impl Node<Option<cst::Policies>> {
     }
 }
 
+impl Node<Option<cst::Entities>> {
+    /// convert `cst::Entities` to the declared entities, in order
+    pub fn to_entity_decls(&self) -> Result<Vec<EntityDecl>> {
+        let entities = self.try_as_inner()?;
+        ParseErrors::transpose(entities.0.iter().map(|e| e.to_entity_decl()))
+    }
+}
+
+impl Node<Option<cst::EntityDecl>> {
+    /// convert `cst::EntityDecl` to an `EntityDecl`
+    pub fn to_entity_decl(&self) -> Result<EntityDecl> {
+        let decl = self.try_as_inner()?;
+
+        let maybe_keyword = decl.keyword.to_entity_decl_keyword();
+        let maybe_uid = decl.uid.to_ref();
+        let maybe_attrs = decl
+            .attrs
+            .as_ref()
+            .map_or(Ok(Vec::new()), |attrs| attrs.to_restricted_record());
+        let maybe_parents = decl
+            .parents
+            .as_ref()
+            .map_or(Ok(HashSet::new()), |parents| parents.to_entity_parents());
+        let maybe_tags = decl
+            .tags
+            .as_ref()
+            .map_or(Ok(Vec::new()), |(keyword, tags)| {
+                let (_, tags) = flatten_tuple_2(
+                    keyword.to_entity_tags_keyword(),
+                    tags.to_restricted_record(),
+                )?;
+                Ok(tags)
+            });
+        let (((), uid), (attrs, parents), tags) = flatten_tuple_3(
+            flatten_tuple_2(maybe_keyword, maybe_uid),
+            flatten_tuple_2(maybe_attrs, maybe_parents),
+            maybe_tags,
+        )?;
+        Ok(EntityDecl {
+            uid,
+            attrs,
+            parents,
+            tags,
+        })
+    }
+}
+
 impl Node<Option<cst::Policy>> {
     /// Convert `cst::Policy` to an AST `InlinePolicy` or `Template`
     pub fn to_policy_or_template(
@@ -452,6 +501,24 @@ impl Node<Option<cst::Ident>> {
         }
     }
 
+    fn to_entity_decl_keyword(&self) -> Result<()> {
+        match self.try_as_inner()? {
+            cst::Ident::Ident(id) if id == "entity" => Ok(()),
+            ident => Err(self
+                .to_ast_err(ToASTErrorKind::InvalidEntityDecl(ident.clone()))
+                .into()),
+        }
+    }
+
+    fn to_entity_tags_keyword(&self) -> Result<()> {
+        match self.try_as_inner()? {
+            cst::Ident::Ident(id) if id == "tags" => Ok(()),
+            ident => Err(self
+                .to_ast_err(ToASTErrorKind::InvalidEntityTags(ident.clone()))
+                .into()),
+        }
+    }
+
     /// Returns `Ok(true)` if the condition is "when" and `Ok(false)` if the
     /// condition is "unless"
     pub(crate) fn to_cond_is_when(&self) -> Result<bool> {
@@ -497,6 +564,28 @@ impl ast::UnreservedId {
                 .map(|arg| construct_method_get_tag(e, arg, loc.clone())),
             "hasTag" => extract_single_argument(args.into_iter(), "hasTag", loc)
                 .map(|arg| construct_method_has_tag(e, arg, loc.clone())),
+            "getTagOr" => extract_two_arguments(args.into_iter(), "getTagOr", loc)
+                .map(|(tag, default)| construct_method_get_tag_or(e, tag, default, loc.clone())),
+            "getOr" => {
+                let (attr, default) = extract_two_arguments(args.into_iter(), "getOr", loc)?;
+                match attr.expr_kind() {
+                    ast::ExprKind::Lit(ast::Literal::String(attr)) => Ok(construct_method_get_or(
+                        e,
+                        attr.clone(),
+                        default,
+                        loc.clone(),
+                    )),
+                    _ => Err(ToASTError::new(
+                        ToASTErrorKind::NonStringGetOrAttribute,
+                        attr.source_loc().cloned().unwrap_or_else(|| loc.clone()),
+                    )
+                    .into()),
+                }
+            }
+            "union" => extract_single_argument(args.into_iter(), "union", loc)
+                .map(|arg| construct_method_union(e, arg, loc.clone())),
+            "intersection" => extract_single_argument(args.into_iter(), "intersection", loc)
+                .map(|arg| construct_method_intersection(e, arg, loc.clone())),
             _ => {
                 if EXTENSION_STYLES.methods.contains(self) {
                     let args = NonEmpty {
@@ -605,7 +694,7 @@ impl Node<Option<cst::VariableDef>> {
                         Ok(et) => Ok(PrincipalOrResourceConstraint::IsIn(Arc::new(et), eref)),
                         Err(eos) => Err(eos.to_ast_err(ToASTErrorKind::InvalidIsType {
                             lhs: var.to_string(),
-                            rhs: eos.loc().snippet().unwrap_or("<invalid>").to_string(),
+                            rhs: eos.source_text(),
                         })),
                     }
                 }
@@ -619,7 +708,7 @@ impl Node<Option<cst::VariableDef>> {
                 Ok(et) => Ok(PrincipalOrResourceConstraint::Is(Arc::new(et))),
                 Err(eos) => Err(eos.to_ast_err(ToASTErrorKind::InvalidIsType {
                     lhs: var.to_string(),
-                    rhs: eos.loc().snippet().unwrap_or("<invalid>").to_string(),
+                    rhs: eos.source_text(),
                 })),
             }
         } else {
@@ -766,7 +855,7 @@ pub(crate) enum ExprOrSpecial<'a> {
     /// Name that isn't an expr and couldn't be converted to var
     Name { name: ast::Name, loc: Loc },
     /// String literal, not yet unescaped
-    /// Must be processed with to_unescaped_string or to_pattern before inclusion in the AST
+    /// Must be processed with to_unescaped_string or to_pattern_tokens before inclusion in the AST
     StrLit { lit: &'a SmolStr, loc: Loc },
 }
 
@@ -784,6 +873,20 @@ impl ExprOrSpecial<'_> {
         ToASTError::new(kind.into(), self.loc().clone())
     }
 
+    /// The source text of this node, for error messages. If the node was
+    /// parsed without retaining its source text, it is printed instead.
+    fn source_text(&self) -> String {
+        match self.loc().snippet() {
+            Some(snippet) => snippet.to_string(),
+            None => match self {
+                Self::Expr { expr, .. } => expr.to_string(),
+                Self::Var { var, .. } => var.to_string(),
+                Self::Name { name, .. } => name.to_string(),
+                Self::StrLit { lit, .. } => format!("\"{lit}\""),
+            },
+        }
+    }
+
     fn into_expr(self) -> Result<ast::Expr> {
         match self {
             Self::Expr { expr, .. } => Ok(expr),
@@ -823,13 +926,16 @@ impl ExprOrSpecial<'_> {
         }
     }
 
-    pub(crate) fn into_pattern(self) -> Result<Vec<PatternElem>> {
+    pub(crate) fn into_pattern(self) -> Result<Pattern> {
         match &self {
-            Self::StrLit { lit, .. } => to_pattern(lit).map_err(|escape_errs| {
-                ParseErrors::new_from_nonempty(
-                    escape_errs.map(|e| self.to_ast_err(ToASTErrorKind::Unescape(e)).into()),
-                )
-            }),
+            Self::StrLit { lit, .. } => {
+                let tokens = to_pattern_tokens(lit).map_err(|escape_errs| {
+                    ParseErrors::new_from_nonempty(
+                        escape_errs.map(|e| self.to_ast_err(ToASTErrorKind::Unescape(e)).into()),
+                    )
+                })?;
+                parse_pattern(&tokens).map_err(|e| self.to_ast_err(e).into())
+            }
             Self::Var { var, .. } => Err(self
                 .to_ast_err(ToASTErrorKind::InvalidPattern(var.to_string()))
                 .into()),
@@ -1003,26 +1109,51 @@ impl Node<Option<cst::Relation>> {
                 in_entity,
             } => {
                 let maybe_target = target.to_expr();
-                let maybe_entity_type = entity_type
-                    .to_expr_or_special()?
-                    .into_entity_type()
-                    .map_err(|eos| {
+                let to_entity_type = |eos: ExprOrSpecial<'_>| {
+                    eos.into_entity_type().map_err(|eos| {
                         eos.to_ast_err(ToASTErrorKind::InvalidIsType {
                             lhs: maybe_target
                                 .as_ref()
                                 .map(|expr| expr.to_string())
                                 .unwrap_or_else(|_| "..".to_string()),
-                            rhs: eos.loc().snippet().unwrap_or("<invalid>").to_string(),
+                            rhs: eos.source_text(),
                         })
                         .into()
-                    });
-                let (t, n) = flatten_tuple_2(maybe_target, maybe_entity_type)?;
+                    })
+                };
+                // `e is [A, B]` is shorthand for `e is A || e is B`
+                let maybe_entity_types = match entity_type.to_set_literal() {
+                    Some(elems) => ParseErrors::transpose(
+                        elems
+                            .iter()
+                            .map(|elem| elem.to_expr_or_special().and_then(to_entity_type)),
+                    ),
+                    None => entity_type
+                        .to_expr_or_special()
+                        .and_then(to_entity_type)
+                        .map(|n| vec![n]),
+                };
+                let (t, ns) = flatten_tuple_2(maybe_target, maybe_entity_types)?;
+                let mut is_exprs = ns
+                    .into_iter()
+                    .map(|n| construct_expr_is(t.clone(), n, self.loc.clone()));
+                let is_expr = match (is_exprs.next(), is_exprs.next()) {
+                    (Some(first), None) => first,
+                    (Some(first), Some(second)) => {
+                        construct_expr_or(first, second, is_exprs, &self.loc)
+                    }
+                    (None, _) => {
+                        return Err(entity_type
+                            .to_ast_err(ToASTErrorKind::EmptyIsTypeList)
+                            .into())
+                    }
+                };
                 match in_entity {
                     Some(in_entity) => {
                         let in_expr = in_entity.to_expr()?;
                         Ok(ExprOrSpecial::Expr {
                             expr: construct_expr_and(
-                                construct_expr_is(t.clone(), n, self.loc.clone()),
+                                is_expr,
                                 construct_expr_rel(t, cst::RelOp::In, in_expr, self.loc.clone())?,
                                 std::iter::empty(),
                                 &self.loc,
@@ -1031,7 +1162,7 @@ impl Node<Option<cst::Relation>> {
                         })
                     }
                     None => Ok(ExprOrSpecial::Expr {
-                        expr: construct_expr_is(t, n, self.loc.clone()),
+                        expr: is_expr,
                         loc: self.loc.clone(),
                     }),
                 }
@@ -1045,6 +1176,28 @@ impl Node<Option<cst::Add>> {
         self.to_expr_or_special()?.into_expr()
     }
 
+    /// Try to convert `cst::Add` into the elements of a set literal, i.e.,
+    /// match `Add(Mult(Unary(Member(Primary(EList(_)), []))))`. Used for the
+    /// list of entity types in `e is [A, B]`, whose elements are names rather
+    /// than expressions.
+    pub(crate) fn to_set_literal(&self) -> Option<&[Node<Option<cst::Expr>>]> {
+        let add = self.as_inner()?;
+        let mult = add.initial.as_inner().filter(|_| add.extended.is_empty())?;
+        let unary = mult
+            .initial
+            .as_inner()
+            .filter(|_| mult.extended.is_empty())?;
+        let member = unary.item.as_inner().filter(|_| unary.op.is_none())?;
+        match member
+            .item
+            .as_inner()
+            .filter(|_| member.access.is_empty())?
+        {
+            cst::Primary::EList(elems) => Some(elems),
+            _ => None,
+        }
+    }
+
     // Peel the grammar onion until we see valid RHS
     // This function is added to implement RFC 62 (extended `has` operator).
     // We could modify existing code instead of having this function. However,
@@ -1183,15 +1336,11 @@ impl Node<Option<cst::Mult>> {
         let mult = self.try_as_inner()?;
 
         let maybe_first = mult.initial.to_expr_or_special();
-        let maybe_rest = ParseErrors::transpose(mult.extended.iter().map(|&(op, ref i)| {
-            i.to_expr().and_then(|e| match op {
-                cst::MultOp::Times => Ok(e),
-                cst::MultOp::Divide => {
-                    Err(self.to_ast_err(ToASTErrorKind::UnsupportedDivision).into())
-                }
-                cst::MultOp::Mod => Err(self.to_ast_err(ToASTErrorKind::UnsupportedModulo).into()),
-            })
-        }));
+        let maybe_rest = ParseErrors::transpose(
+            mult.extended
+                .iter()
+                .map(|&(op, ref i)| i.to_expr().map(|e| (op, e))),
+        );
 
         let (first, rest) = flatten_tuple_2(maybe_first, maybe_rest)?;
         if !rest.is_empty() {
@@ -1494,6 +1643,43 @@ impl Node<Option<cst::Primary>> {
     pub(crate) fn to_expr(&self) -> Result<ast::Expr> {
         self.to_expr_or_special()?.into_expr()
     }
+    /// convert a record literal to its fields, each of which must be a
+    /// restricted expression
+    fn to_restricted_record(&self) -> Result<Vec<(SmolStr, ast::RestrictedExpr)>> {
+        let expr = self.to_expr()?;
+        match expr.into_expr_kind() {
+            ast::ExprKind::Record(fields) => {
+                ParseErrors::transpose(Arc::unwrap_or_clone(fields).into_iter().map(|(k, v)| {
+                    let loc = v.source_loc().cloned().unwrap_or_else(|| self.loc.clone());
+                    ast::RestrictedExpr::new(v)
+                        .map(|v| (k, v))
+                        .map_err(|err| ToASTError::new(err.into(), loc).into())
+                }))
+            }
+            // PANIC SAFETY: the grammar only allows record literals here
+            #[allow(clippy::unreachable)]
+            _ => unreachable!("entity attributes and tags should be parsed as a record literal"),
+        }
+    }
+    /// convert the parents of an entity, an entity literal or a list of
+    /// entity literals, to their uids
+    fn to_entity_parents(&self) -> Result<HashSet<ast::EntityUID>> {
+        let expr = self.to_expr()?;
+        let as_uid = |e: &ast::Expr| match e.expr_kind() {
+            ast::ExprKind::Lit(ast::Literal::EntityUID(euid)) => Ok(euid.as_ref().clone()),
+            _ => Err(ToASTError::new(
+                ToASTErrorKind::InvalidEntityParents,
+                e.source_loc().cloned().unwrap_or_else(|| self.loc.clone()),
+            )
+            .into()),
+        };
+        match expr.expr_kind() {
+            ast::ExprKind::Set(elems) => {
+                ParseErrors::transpose(elems.iter().map(as_uid)).map(HashSet::from_iter)
+            }
+            _ => as_uid(&expr).map(|euid| HashSet::from([euid])),
+        }
+    }
     fn to_expr_or_special(&self) -> Result<ExprOrSpecial<'_>> {
         let prim = self.try_as_inner()?;
 
@@ -1675,7 +1861,16 @@ impl ast::Name {
             if EXTENSION_STYLES.methods.contains(&id)
                 || matches!(
                     id.as_ref(),
-                    "contains" | "containsAll" | "containsAny" | "isEmpty" | "getTag" | "hasTag"
+                    "contains"
+                        | "containsAll"
+                        | "containsAny"
+                        | "isEmpty"
+                        | "getTag"
+                        | "hasTag"
+                        | "getTagOr"
+                        | "getOr"
+                        | "union"
+                        | "intersection"
                 )
             {
                 return Err(ToASTError::new(
@@ -1688,17 +1883,8 @@ impl ast::Name {
         if EXTENSION_STYLES.functions.contains(&self) {
             Ok(construct_ext_func(self, args, loc))
         } else {
-            fn suggest_function(name: &ast::Name, funs: &HashSet<&ast::Name>) -> Option<String> {
-                const SUGGEST_FUNCTION_MAX_DISTANCE: usize = 3;
-                let fnames = funs.iter().map(ToString::to_string).collect::<Vec<_>>();
-                let suggested_function = fuzzy_search_limited(
-                    &name.to_string(),
-                    fnames.as_slice(),
-                    Some(SUGGEST_FUNCTION_MAX_DISTANCE),
-                );
-                suggested_function.map(|f| format!("did you mean `{f}`?"))
-            }
-            let hint = suggest_function(&self, &EXTENSION_STYLES.functions);
+            let hint = suggest_function_name(&self, EXTENSION_STYLES.functions.iter().copied())
+                .map(|f| format!("did you mean `{f}`?"));
             Err(ToASTError::new(ToASTErrorKind::UnknownFunction { id: self, hint }, loc).into())
         }
     }
@@ -1923,14 +2109,17 @@ fn construct_expr_add(
 /// used for a chain of multiplication only (no division or mod)
 fn construct_expr_mul(
     f: ast::Expr,
-    chained: impl IntoIterator<Item = ast::Expr>,
+    chained: impl IntoIterator<Item = (cst::MultOp, ast::Expr)>,
     loc: &Loc,
 ) -> ast::Expr {
     let mut expr = f;
-    for next_expr in chained {
-        expr = ast::ExprBuilder::new()
-            .with_source_loc(loc.clone())
-            .mul(expr, next_expr);
+    for (op, next_expr) in chained {
+        let builder = ast::ExprBuilder::new().with_source_loc(loc.clone());
+        expr = match op {
+            cst::MultOp::Times => builder.mul(expr, next_expr),
+            cst::MultOp::Divide => builder.div(expr, next_expr),
+            cst::MultOp::Mod => builder.rem(expr, next_expr),
+        };
     }
     expr
 }
@@ -1941,6 +2130,15 @@ fn construct_expr_has_attr(t: ast::Expr, s: SmolStr, loc: Loc) -> ast::Expr {
 fn construct_expr_get_attr(t: ast::Expr, s: SmolStr, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new().with_source_loc(loc).get_attr(t, s)
 }
+/// `t.getOr("s", d)` is shorthand for `if t has s then t.s else d`
+fn construct_method_get_or(t: ast::Expr, s: SmolStr, d: ast::Expr, loc: Loc) -> ast::Expr {
+    construct_expr_if(
+        construct_expr_has_attr(t.clone(), s.clone(), loc.clone()),
+        construct_expr_get_attr(t, s, loc.clone()),
+        d,
+        loc,
+    )
+}
 fn construct_exprs_extended_has(t: ast::Expr, attrs: NonEmpty<SmolStr>, loc: Loc) -> ast::Expr {
     let (first, rest) = attrs.split_first();
     let has_expr = construct_expr_has_attr(t.clone(), first.to_owned(), loc.clone());
@@ -1983,10 +2181,8 @@ fn construct_exprs_extended_has(t: ast::Expr, attrs: NonEmpty<SmolStr>, loc: Loc
 fn construct_expr_attr(e: ast::Expr, s: SmolStr, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new().with_source_loc(loc).get_attr(e, s)
 }
-fn construct_expr_like(e: ast::Expr, s: Vec<PatternElem>, loc: Loc) -> ast::Expr {
-    ast::ExprBuilder::new()
-        .with_source_loc(loc)
-        .like(e, Pattern::from(s))
+fn construct_expr_like(e: ast::Expr, s: Pattern, loc: Loc) -> ast::Expr {
+    ast::ExprBuilder::new().with_source_loc(loc).like(e, s)
 }
 fn construct_expr_is(e: ast::Expr, n: ast::EntityType, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new()
@@ -2024,6 +2220,23 @@ fn construct_method_get_tag(e0: ast::Expr, e1: ast::Expr, loc: Loc) -> ast::Expr
 fn construct_method_has_tag(e0: ast::Expr, e1: ast::Expr, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new().with_source_loc(loc).has_tag(e0, e1)
 }
+/// `e0.getTagOr(e1, e2)` is shorthand for `if e0.hasTag(e1) then e0.getTag(e1) else e2`
+fn construct_method_get_tag_or(e0: ast::Expr, e1: ast::Expr, e2: ast::Expr, loc: Loc) -> ast::Expr {
+    construct_expr_if(
+        construct_method_has_tag(e0.clone(), e1.clone(), loc.clone()),
+        construct_method_get_tag(e0, e1, loc.clone()),
+        e2,
+        loc,
+    )
+}
+fn construct_method_union(e0: ast::Expr, e1: ast::Expr, loc: Loc) -> ast::Expr {
+    ast::ExprBuilder::new().with_source_loc(loc).union(e0, e1)
+}
+fn construct_method_intersection(e0: ast::Expr, e1: ast::Expr, loc: Loc) -> ast::Expr {
+    ast::ExprBuilder::new()
+        .with_source_loc(loc)
+        .intersection(e0, e1)
+}
 
 fn construct_ext_meth(n: UnreservedId, args: NonEmpty<ast::Expr>, loc: Loc) -> ast::Expr {
     let name = ast::Name::unqualified_name(n);
@@ -2050,7 +2263,7 @@ fn construct_expr_record(kvs: Vec<(SmolStr, ast::Expr)>, loc: Loc) -> Result<ast
 mod tests {
     use super::*;
     use crate::{
-        ast::{EntityUID, Expr},
+        ast::{EntityUID, Expr, PatternElem},
         parser::{err::ParseErrors, test_utils::*, *},
         test_utils::*,
     };
@@ -4150,6 +4363,204 @@ mod tests {
         }
     }
 
+    #[test]
+    fn is_type_list() {
+        for (src, expected) in [
+            (
+                "resource is [Doc, Sheet]",
+                "resource is Doc || resource is Sheet",
+            ),
+            ("resource is [Doc]", "resource is Doc"),
+            (
+                "resource is [A::Doc, Sheet, Slide]",
+                "(resource is A::Doc || resource is Sheet) || resource is Slide",
+            ),
+            (
+                r#"resource is [Doc, Sheet] in Folder::"f""#,
+                r#"(resource is Doc || resource is Sheet) && resource in Folder::"f""#,
+            ),
+        ] {
+            let e: Expr = src.parse().unwrap();
+            let expected: Expr = expected.parse().unwrap();
+            assert!(e.eq_shape(&expected), "`{src}` parsed to `{e}`");
+        }
+
+        let src = r#"permit(principal, action, resource) when { resource is [] };"#;
+        assert_matches!(parse_policy_or_template(None, src), Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error(
+                    "right hand side of an `is` expression must list at least one entity type",
+                )
+                .exactly_one_underline("[]")
+                .build(),
+            );
+        });
+        let src = r#"permit(principal, action, resource) when { resource is [Doc, "Sheet"] };"#;
+        assert_matches!(parse_policy_or_template(None, src), Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error(
+                    r#"right hand side of an `is` expression must be an entity type name, but got `"Sheet"`"#,
+                )
+                .help("try removing the quotes: `resource is Sheet`")
+                .exactly_one_underline("\"Sheet\"")
+                .build(),
+            );
+        });
+    }
+
+    #[test]
+    fn get_tag_or() {
+        let e: Expr = r#"principal.getTagOr(context.key, "none")"#.parse().unwrap();
+        let expected: Expr =
+            r#"if principal.hasTag(context.key) then principal.getTag(context.key) else "none""#
+                .parse()
+                .unwrap();
+        assert!(e.eq_shape(&expected), "parsed to `{e}`");
+    }
+
+    #[test]
+    fn get_or() {
+        let e: Expr = r#"principal.getOr("tier", "free")"#.parse().unwrap();
+        let expected: Expr = r#"if principal has tier then principal.tier else "free""#
+            .parse()
+            .unwrap();
+        assert!(e.eq_shape(&expected), "parsed to `{e}`");
+
+        let src = r#"principal.getOr(context.attr, "free")"#;
+        assert_matches!(parse_expr(src), Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error(
+                    "the first argument to `getOr` must be a string literal naming an attribute",
+                )
+                .exactly_one_underline("context.attr")
+                .build(),
+            );
+        });
+        let src = r#"principal.getOr("tier")"#;
+        assert_matches!(parse_expr(src), Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error(
+                    "call to `getOr` requires exactly 2 arguments, but got 1 argument",
+                )
+                .exactly_one_underline(src)
+                .build(),
+            );
+        });
+    }
+
+    #[test]
+    fn like_char_classes() {
+        let e = assert_parse_expr_succeeds(r#"context.id like "user-\[0-9]\{1,4}_\[^a-z\-]*""#);
+        assert_matches!(e.expr_kind(), ast::ExprKind::Like { pattern, .. } => {
+            assert_eq!(
+                pattern.get_elems()[5..8],
+                [
+                    PatternElem::Repeat {
+                        pattern: ast::Pattern::from(vec![PatternElem::Class(ast::CharClass::new(
+                            false,
+                            [('0', '9')]
+                        ))]),
+                        min: 1,
+                        max: 4,
+                    },
+                    PatternElem::Char('_'),
+                    PatternElem::Class(ast::CharClass::new(true, [('a', 'z'), ('-', '-')])),
+                ]
+            );
+            assert_eq!(pattern.to_string(), r"user-\[0-9]\{1,4}_\[^a-z\-]*");
+        });
+
+        // without the `\`, the special characters of classes and repetitions
+        // are literal, as they always have been, and they are literal inside
+        // classes
+        let e = assert_parse_expr_succeeds(r#"context.id like "[{x}]-^\][*{]""#);
+        assert_matches!(e.expr_kind(), ast::ExprKind::Like { pattern, .. } => {
+            assert_eq!(
+                pattern.get_elems(),
+                [
+                    PatternElem::Char('['),
+                    PatternElem::Char('{'),
+                    PatternElem::Char('x'),
+                    PatternElem::Char('}'),
+                    PatternElem::Char(']'),
+                    PatternElem::Char('-'),
+                    PatternElem::Char('^'),
+                    PatternElem::Char(']'),
+                    PatternElem::Char('['),
+                    PatternElem::Wildcard,
+                    PatternElem::Char('{'),
+                    PatternElem::Char(']'),
+                ]
+            );
+            assert_eq!(pattern.to_string(), r"[{x}]-^][*{]");
+        });
+        let e = assert_parse_expr_succeeds(r#"context.id like "\[[*{]""#);
+        assert_matches!(e.expr_kind(), ast::ExprKind::Like { pattern, .. } => {
+            assert_eq!(
+                pattern.get_elems(),
+                [PatternElem::Class(ast::CharClass::new(false, [('[', '['), ('*', '*'), ('{', '{')]))]
+            );
+        });
+
+        for (src, msg, help) in [
+            (
+                r#"context.id like "\[a-z""#,
+                "unterminated character class in pattern",
+                Some(r"close the class with `]`, or match a literal `[` without the `\`"),
+            ),
+            (
+                r#"context.id like "\[]""#,
+                "empty character class in pattern",
+                None,
+            ),
+            (
+                r#"context.id like "\[z-a]""#,
+                "invalid character range `z-a` in pattern",
+                Some("the first character of a range must not come after the last"),
+            ),
+            (
+                r#"context.id like "a\{x}""#,
+                "malformed repetition in pattern",
+                Some(
+                    r"a repetition must have the form `\{n}` or `\{min,max}`; match a literal `{` without the `\`",
+                ),
+            ),
+            (
+                r#"context.id like "*\{2}""#,
+                "a repetition in a pattern must follow a single character or a character class",
+                None,
+            ),
+            (
+                r#"context.id like "a\{3,1}""#,
+                r"invalid repetition `\{3,1}` in pattern: the minimum is greater than the maximum",
+                None,
+            ),
+            (
+                r#"context.id like "a\{1,1001}""#,
+                "repetition bound 1001 in pattern exceeds the limit of 1000",
+                None,
+            ),
+        ] {
+            let pattern = &src[16..];
+            assert_matches!(parse_expr(src), Err(e) => {
+                let expected = ExpectedErrorMessageBuilder::error(msg).exactly_one_underline(pattern);
+                let expected = match help {
+                    Some(help) => expected.help(help),
+                    None => expected,
+                };
+                expect_err(src, &miette::Report::new(e), &expected.build());
+            });
+        }
+    }
+
     #[test]
     fn issue_255() {
         let policy = r#"
@@ -4230,6 +4641,22 @@ mod tests {
                 .exactly_one_underline("[].containsAny(1, 2)")
                 .build(),
             ),
+            (
+                r#"principal.getTagOr("foo")"#,
+                ExpectedErrorMessageBuilder::error(
+                    "call to `getTagOr` requires exactly 2 arguments, but got 1 argument",
+                )
+                .exactly_one_underline(r#"principal.getTagOr("foo")"#)
+                .build(),
+            ),
+            (
+                r#"principal.getTagOr("foo", 1, 2)"#,
+                ExpectedErrorMessageBuilder::error(
+                    "call to `getTagOr` requires exactly 2 arguments, but got 3 arguments",
+                )
+                .exactly_one_underline(r#"principal.getTagOr("foo", 1, 2)"#)
+                .build(),
+            ),
             (
                 r#"[].isEmpty([])"#,
                 ExpectedErrorMessageBuilder::error(
@@ -4724,16 +5151,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn division_and_remainder() {
+        // left-associative, with the same precedence as `*`
+        let e = assert_parse_expr_succeeds("context.a * context.b / 2 % 3 + 1");
+        let ctx = |attr: &str| Expr::get_attr(Expr::var(ast::Var::Context), attr.into());
+        let expected = Expr::add(
+            Expr::rem(
+                Expr::div(Expr::mul(ctx("a"), ctx("b")), Expr::val(2)),
+                Expr::val(3),
+            ),
+            Expr::val(1),
+        );
+        assert!(e.eq_shape(&expected), "parsed to `{e}`");
+    }
+
     #[test]
     fn unsupported_ops() {
-        let src = "1/2";
-        assert_matches!(parse_expr(src), Err(e) => {
-            expect_err(src, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error("division is not supported").exactly_one_underline("1/2").build());
-        });
-        let src = "7 % 3";
-        assert_matches!(parse_expr(src), Err(e) => {
-            expect_err(src, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error("remainder/modulo is not supported").exactly_one_underline("7 % 3").build());
-        });
         let src = "7 = 3";
         assert_matches!(parse_expr(src), Err(e) => {
             expect_err(src, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error("'=' is not a valid operator in Cedar").exactly_one_underline("7 = 3").help("try using '==' instead").build());
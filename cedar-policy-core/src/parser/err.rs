@@ -30,6 +30,7 @@ use smol_str::SmolStr;
 use thiserror::Error;
 
 use crate::ast::{self, ReservedNameError};
+use crate::limits::LimitError;
 use crate::parser::fmt::join_with_conjunction;
 use crate::parser::loc::Loc;
 use crate::parser::node::Node;
@@ -57,6 +58,10 @@ pub enum ParseError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     ToAST(#[from] ToASTError),
+    /// The input exceeds a configured [`crate::limits::ResourceLimits`]
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Limit(#[from] LimitError),
 }
 
 /// Errors possible from `Literal::from_str()`
@@ -257,6 +262,10 @@ pub enum ToASTErrorKind {
     /// Returned when the right hand side of a `like` expression is not a constant pattern literal
     #[error("right hand side of a `like` expression must be a pattern literal, but got `{0}`")]
     InvalidPattern(String),
+    /// Returned when a pattern literal has a malformed character class or repetition
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MalformedPattern(#[from] ast::PatternError),
     /// Returned when the right hand side of a `is` expression is not an entity type name
     #[error("right hand side of an `is` expression must be an entity type name, but got `{rhs}`")]
     #[diagnostic(help("{}", invalid_is_help(lhs, rhs)))]
@@ -266,6 +275,10 @@ pub enum ToASTErrorKind {
         /// RHS of the invalid `is` expression, as a string
         rhs: String,
     },
+    /// Returned when the right hand side of a `is` expression is an empty
+    /// list of entity types
+    #[error("right hand side of an `is` expression must list at least one entity type")]
+    EmptyIsTypeList,
     /// Returned when an unexpected node is in the policy scope
     #[error("expected {expected}, found {got}")]
     WrongNode {
@@ -281,12 +294,6 @@ pub enum ToASTErrorKind {
     /// This can be resolved by using parenthesis to make order explicit
     #[error("multiple relational operators (>, ==, in, etc.) must be used with parentheses to make ordering explicit")]
     AmbiguousOperators,
-    /// Returned when a policy uses the division operator (`/`), which is not supported
-    #[error("division is not supported")]
-    UnsupportedDivision,
-    /// Returned when a policy uses the remainder/modulo operator (`%`), which is not supported
-    #[error("remainder/modulo is not supported")]
-    UnsupportedModulo,
     /// Any `ExpressionConstructionError` can also happen while converting CST to AST
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -352,6 +359,9 @@ pub enum ToASTErrorKind {
     /// Returned when the contents of an indexing expression is not a string literal
     #[error("the contents of an index expression must be a string literal")]
     NonStringIndex,
+    /// Returned when the attribute passed to `getOr` is not a string literal
+    #[error("the first argument to `getOr` must be a string literal naming an attribute")]
+    NonStringGetOrAttribute,
     /// Returned when a user attempts to use type-constraint `:` syntax. This
     /// syntax was not adopted, but `is` can be used to write type constraints
     /// in the policy scope.
@@ -406,6 +416,20 @@ pub enum ToASTErrorKind {
     #[error("when `is` and `in` are used together, `is` must come first")]
     #[diagnostic(help("try `_ is _ in _`"))]
     InvertedIsIn,
+    /// Returned when an entity declaration starts with a keyword other than `entity`
+    #[error("invalid entity declaration: {0}")]
+    #[diagnostic(help("entity declarations should have a form like `entity User::\"alice\" {{ dept: \"eng\" }} in [Group::\"staff\"];`"))]
+    InvalidEntityDecl(cst::Ident),
+    /// Returned when the tags of an entity are introduced by a keyword other than `tags`
+    #[error("invalid entity declaration: expected `tags`, found `{0}`")]
+    InvalidEntityTags(cst::Ident),
+    /// Returned when the parents of an entity are not entity literals
+    #[error("the parents of an entity must be an entity literal or a list of entity literals")]
+    InvalidEntityParents,
+    /// Returned when an attribute or tag value of an entity is not a restricted expression
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidRestrictedExpression(#[from] ast::RestrictedExpressionError),
 }
 
 fn invalid_is_help(lhs: &str, rhs: &str) -> String {
@@ -113,6 +113,35 @@ impl fmt::Display for Policy {
     }
 }
 
+impl fmt::Display for Entities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut es = self.0.iter();
+        let sep = if f.alternate() { "\n" } else { " " };
+        if let Some(e) = es.next() {
+            write!(f, "{}", View(e))?;
+        }
+        for e in es {
+            write!(f, "{sep}{}", View(e))?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for EntityDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", View(&self.keyword), View(&self.uid))?;
+        if let Some(attrs) = &self.attrs {
+            write!(f, " {}", View(attrs))?;
+        }
+        if let Some(parents) = &self.parents {
+            write!(f, " in {}", View(parents))?;
+        }
+        if let Some((keyword, tags)) = &self.tags {
+            write!(f, " {} {}", View(keyword), View(tags))?;
+        }
+        write!(f, ";")
+    }
+}
+
 impl fmt::Display for Annotation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.value.as_ref() {
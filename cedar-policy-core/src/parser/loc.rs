@@ -37,6 +37,20 @@ impl Loc {
         }
     }
 
+    /// The source code diagnostics should render this `Loc`'s labels in.
+    ///
+    /// This is `None` if the `Loc` doesn't retain its source text (e.g., it
+    /// was parsed with [`crate::parser::parse_policyset_without_source`]), so
+    /// that diagnostics are reported without snippets rather than with labels
+    /// pointing into an empty source.
+    pub fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        if self.src.is_empty() {
+            None
+        } else {
+            Some(&self.src as &dyn miette::SourceCode)
+        }
+    }
+
     /// Create a new `Loc` with the same source code but a different span
     pub fn span(&self, span: impl Into<miette::SourceSpan>) -> Self {
         Self {
@@ -61,6 +75,60 @@ impl Loc {
     pub fn snippet(&self) -> Option<&str> {
         self.src.get(self.start()..self.end())
     }
+
+    /// Get a copy of this `Loc` in which the contents of string literals and
+    /// numeric literals in the source code are masked with `*`.
+    ///
+    /// Every masked character is replaced by as many `*`s as it has UTF-8
+    /// bytes, so the span still points at the same part of the source.
+    pub fn redacted(&self) -> Self {
+        Self {
+            span: self.span,
+            src: redact_literals(&self.src).into(),
+        }
+    }
+}
+
+/// Mask the contents of string and numeric literals in `src`, preserving byte
+/// offsets. `//` comments are left as they are. See [`Loc::redacted`].
+fn redact_literals(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut in_comment = false;
+    let mut prev_is_ident = false;
+    let mut in_number = false;
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_comment {
+            in_comment = c != '\n';
+            out.push(c);
+        } else if c == '/' && chars.peek() == Some(&'/') && !in_string {
+            in_comment = true;
+            in_number = false;
+            out.push(c);
+        } else if in_string {
+            if !escaped && c == '"' {
+                in_string = false;
+                out.push(c);
+            } else {
+                escaped = !escaped && c == '\\';
+                out.extend(std::iter::repeat('*').take(c.len_utf8()));
+            }
+        } else if c == '"' {
+            in_string = true;
+            in_number = false;
+            out.push(c);
+        } else if c.is_ascii_digit() && (in_number || !prev_is_ident) {
+            in_number = true;
+            out.push('*');
+        } else {
+            in_number = false;
+            out.push(c);
+        }
+        prev_is_ident = c.is_alphanumeric() || c == '_';
+    }
+    out
 }
 
 impl From<Loc> for miette::SourceSpan {
@@ -98,3 +166,54 @@ impl miette::SourceCode for &Loc {
             .read_span(span, context_lines_before, context_lines_after)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn redacted() {
+        let src = r#"permit(principal, action, resource) when { context.ssn == "123-45-\"é" && ip4 + 21 > 0 };"#;
+        let loc = Loc::new(43..54, src.into());
+        let redacted = loc.redacted();
+        assert_eq!(
+            &*redacted.src,
+            r#"permit(principal, action, resource) when { context.ssn == "***********" && ip4 + ** > * };"#
+        );
+        assert_eq!(redacted.span, loc.span);
+        assert_eq!(redacted.snippet(), loc.snippet());
+    }
+
+    #[test]
+    fn redacted_skips_comments() {
+        let src = "permit(principal, action, resource) // don't \"quote\" 42\nwhen { context.pin == 1234 }; // \"\n// 7\n";
+        let redacted = Loc::new(0..src.len(), src.into()).redacted();
+        assert_eq!(
+            &*redacted.src,
+            "permit(principal, action, resource) // don't \"quote\" 42\nwhen { context.pin == **** }; // \"\n// 7\n"
+        );
+    }
+
+    #[test]
+    fn parsed_src() {
+        let src = "permit(principal, action, resource) when { 1 < 2 };";
+        let template = crate::parser::parse_policy_or_template(None, src).unwrap();
+        let loc = template.loc().unwrap();
+        assert_eq!(loc.span, (0..src.len()).into());
+        assert_eq!(&*loc.src, src);
+        assert_eq!(loc.snippet(), Some(src));
+        assert!(loc.source_code().is_some());
+    }
+
+    #[test]
+    fn parsed_without_src() {
+        let src = "permit(principal, action, resource) when { 1 < 2 };";
+        let limits = crate::limits::ResourceLimits::default();
+        let (_, pset) = crate::parser::parse_policyset_without_source(src, &limits).unwrap();
+        let loc = pset.policies().next().unwrap().loc().unwrap();
+        assert_eq!(loc.span, (0..src.len()).into());
+        assert_eq!(&*loc.src, "");
+        assert_eq!(loc.snippet(), None);
+        assert!(loc.source_code().is_none());
+    }
+}
@@ -46,9 +46,25 @@ fn parse_collect_errors<'a, P, T>(
         &'a str,
     ) -> Result<T, err::RawParseError<'a>>,
     text: &'a str,
+) -> Result<T, err::ParseErrors> {
+    parse_collect_errors_with_src(parser, parse, text, &Arc::from(text))
+}
+
+/// Like `parse_collect_errors()`, but the `Loc`s of the parsed nodes refer to
+/// `src` rather than to `text`. Parse errors always refer to `text`.
+fn parse_collect_errors_with_src<'a, P, T>(
+    parser: &P,
+    parse: impl FnOnce(
+        &P,
+        &mut Vec<err::RawErrorRecovery<'a>>,
+        &Arc<str>,
+        &'a str,
+    ) -> Result<T, err::RawParseError<'a>>,
+    text: &'a str,
+    src: &Arc<str>,
 ) -> Result<T, err::ParseErrors> {
     let mut errs = Vec::new();
-    let result = parse(parser, &mut errs, &Arc::from(text), text);
+    let result = parse(parser, &mut errs, src, text);
 
     let errors = errs
         .into_iter()
@@ -73,6 +89,7 @@ fn parse_collect_errors<'a, P, T>(
 lazy_static::lazy_static! {
     static ref POLICIES_PARSER: grammar::PoliciesParser = grammar::PoliciesParser::new();
     static ref POLICY_PARSER: grammar::PolicyParser = grammar::PolicyParser::new();
+    static ref ENTITIES_PARSER: grammar::EntitiesParser = grammar::EntitiesParser::new();
     static ref EXPR_PARSER: grammar::ExprParser = grammar::ExprParser::new();
     static ref REF_PARSER: grammar::RefParser = grammar::RefParser::new();
     static ref PRIMARY_PARSER: grammar::PrimaryParser = grammar::PrimaryParser::new();
@@ -85,11 +102,29 @@ pub fn parse_policies(text: &str) -> Result<Node<Option<cst::Policies>>, err::Pa
     parse_collect_errors(&*POLICIES_PARSER, grammar::PoliciesParser::parse, text)
 }
 
+/// Like `parse_policies()`, but the source locations in the CST don't retain
+/// `text`: they have an empty source and locate each node by offset only.
+pub fn parse_policies_without_source(
+    text: &str,
+) -> Result<Node<Option<cst::Policies>>, err::ParseErrors> {
+    parse_collect_errors_with_src(
+        &*POLICIES_PARSER,
+        grammar::PoliciesParser::parse,
+        text,
+        &Arc::from(""),
+    )
+}
+
 /// Create CST for one policy statement from text
 pub fn parse_policy(text: &str) -> Result<Node<Option<cst::Policy>>, err::ParseErrors> {
     parse_collect_errors(&*POLICY_PARSER, grammar::PolicyParser::parse, text)
 }
 
+/// Create CST for entity declarations in the Cedar entity text format
+pub fn parse_entities(text: &str) -> Result<Node<Option<cst::Entities>>, err::ParseErrors> {
+    parse_collect_errors(&*ENTITIES_PARSER, grammar::EntitiesParser::parse, text)
+}
+
 /// Create CST for one Expression from text
 pub fn parse_expr(text: &str) -> Result<Node<Option<cst::Expr>>, err::ParseErrors> {
     parse_collect_errors(&*EXPR_PARSER, grammar::ExprParser::parse, text)
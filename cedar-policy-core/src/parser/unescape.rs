@@ -14,12 +14,13 @@
  * limitations under the License.
  */
 
-use crate::ast::PatternElem;
+use crate::ast::{CharClass, Pattern, PatternElem, PatternError};
 use itertools::Itertools;
 use miette::Diagnostic;
 use nonempty::NonEmpty;
 use rustc_lexer::unescape::{unescape_str, EscapeError};
 use smol_str::SmolStr;
+use std::iter::Peekable;
 use std::ops::Range;
 use thiserror::Error;
 
@@ -46,21 +47,31 @@ pub fn to_unescaped_string(s: &str) -> Result<SmolStr, NonEmpty<UnescapeError>>
     }
 }
 
-pub(crate) fn to_pattern(s: &str) -> Result<Vec<PatternElem>, NonEmpty<UnescapeError>> {
+/// Unescape a pattern literal. In addition to Cedar's string escapes, the
+/// characters with a special meaning in patterns may be escaped, e.g., `\*`,
+/// and `\[` and `\{` open a character class and a repetition.
+pub(crate) fn to_pattern_tokens(s: &str) -> Result<Vec<PatternToken>, NonEmpty<UnescapeError>> {
     let mut unescaped_str = Vec::new();
     let mut errs = Vec::new();
     let bytes = s.as_bytes(); // to inspect string element in O(1) time
     let mut callback = |range: Range<usize>, r| match r {
-        Ok(c) => unescaped_str.push(if c == '*' { PatternElem::Wildcard }else { PatternElem::Char(c) }),
+        Ok(c) if is_pattern_special(c) => unescaped_str.push(PatternToken::Special(c)),
+        Ok(c) => unescaped_str.push(PatternToken::Char(c)),
         // PANIC SAFETY By invariant, all passed in ranges must be in range
         #[allow(clippy::indexing_slicing)]
         Err(EscapeError::InvalidEscape)
         // note that the range argument refers to the *byte* offset into the string.
-        // so we can compare the byte slice against the bytes of the ``star'' escape sequence.
-        if &bytes[range.clone()] == br"\*"
+        // so we can compare the byte slice against the bytes of the pattern escape sequences,
+        // e.g., ``star'' (`\*`).
+        if matches!(&bytes[range.clone()], [b'\\', c] if is_pattern_escape(char::from(*c)))
             =>
         {
-            unescaped_str.push(PatternElem::Char('*'))
+            let c = char::from(bytes[range.end - 1]);
+            unescaped_str.push(if matches!(c, '[' | '{') {
+                PatternToken::Special(c)
+            } else {
+                PatternToken::Char(c)
+            })
         }
         Err(err) => errs.push(UnescapeError { err, input: s.to_owned(), range }),
     };
@@ -75,6 +86,108 @@ pub(crate) fn to_pattern(s: &str) -> Result<Vec<PatternElem>, NonEmpty<UnescapeE
     }
 }
 
+/// A character of a pattern literal after unescaping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PatternToken {
+    /// A character to be matched literally
+    Char(char),
+    /// An unescaped `*`, `]`, `}`, `-` or `^`, or an escaped `\[` or `\{`.
+    /// Only `*`, `\[` and `\{` are special outside of a character class or
+    /// repetition; the others are matched literally there, as they always
+    /// have been.
+    Special(char),
+}
+
+/// Unescaped characters which may have a special meaning in patterns
+fn is_pattern_special(c: char) -> bool {
+    matches!(c, '*' | ']' | '}' | '-' | '^')
+}
+
+/// Characters which may be escaped with `\` in patterns
+fn is_pattern_escape(c: char) -> bool {
+    matches!(c, '*' | '[' | ']' | '{' | '}' | '-' | '^')
+}
+
+/// Parse the tokens of an unescaped pattern literal into a [`Pattern`]
+pub(crate) fn parse_pattern(tokens: &[PatternToken]) -> Result<Pattern, PatternError> {
+    let mut elems = Vec::new();
+    let mut tokens = tokens.iter().copied().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            PatternToken::Special('*') => elems.push(PatternElem::Wildcard),
+            PatternToken::Special('[') => elems.push(PatternElem::Class(parse_class(&mut tokens)?)),
+            PatternToken::Special('{') => {
+                let (min, max) = parse_repetition(&mut tokens)?;
+                let elem = match elems.pop() {
+                    Some(elem @ (PatternElem::Char(_) | PatternElem::Class(_))) => elem,
+                    _ => return Err(PatternError::InvalidRepetitionTarget),
+                };
+                elems.push(PatternElem::Repeat {
+                    pattern: Pattern::from(vec![elem]),
+                    min,
+                    max,
+                });
+            }
+            PatternToken::Special(c) | PatternToken::Char(c) => elems.push(PatternElem::Char(c)),
+        }
+    }
+    let pattern = Pattern::from(elems);
+    pattern.validate()?;
+    Ok(pattern)
+}
+
+/// Parse a character class, after the opening `\[`
+fn parse_class(
+    tokens: &mut Peekable<impl Iterator<Item = PatternToken>>,
+) -> Result<CharClass, PatternError> {
+    let negated = tokens.next_if_eq(&PatternToken::Special('^')).is_some();
+    let mut ranges = Vec::new();
+    loop {
+        let lo = match tokens.next() {
+            None => return Err(PatternError::UnterminatedClass),
+            Some(PatternToken::Special(']')) => break,
+            Some(PatternToken::Special(c) | PatternToken::Char(c)) => c,
+        };
+        if tokens.next_if_eq(&PatternToken::Special('-')).is_none() {
+            ranges.push((lo, lo));
+            continue;
+        }
+        match tokens.next() {
+            None => return Err(PatternError::UnterminatedClass),
+            // a trailing `-` is matched literally, as in `\[a-]`
+            Some(PatternToken::Special(']')) => {
+                ranges.extend([(lo, lo), ('-', '-')]);
+                break;
+            }
+            Some(PatternToken::Special(hi) | PatternToken::Char(hi)) => ranges.push((lo, hi)),
+        }
+    }
+    Ok(CharClass::new(negated, ranges))
+}
+
+/// Parse the bounds of a repetition `\{n}` or `\{min,max}`, after the opening `\{`
+fn parse_repetition(
+    tokens: &mut impl Iterator<Item = PatternToken>,
+) -> Result<(u32, u32), PatternError> {
+    let mut body = String::new();
+    loop {
+        match tokens.next() {
+            Some(PatternToken::Special('}')) => break,
+            Some(PatternToken::Char(c)) if c.is_ascii_digit() || c == ',' => body.push(c),
+            _ => return Err(PatternError::MalformedRepetition),
+        }
+    }
+    let parse_bound = |bound: &str| {
+        bound
+            .parse::<u32>()
+            .map_err(|_| PatternError::MalformedRepetition)
+    };
+    match body.split_once(',') {
+        None => parse_bound(&body).map(|n| (n, n)),
+        Some((min, max)) => Ok((parse_bound(min)?, parse_bound(max)?)),
+    }
+}
+
 /// Errors generated when processing escapes
 #[derive(Debug, Diagnostic, Error, PartialEq, Eq)]
 pub struct UnescapeError {
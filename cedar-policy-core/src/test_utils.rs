@@ -132,6 +132,20 @@ impl<'a> ExpectedErrorMessageBuilder<'a> {
         }
     }
 
+    /// Add expected underlined text. The error message will be expected to have
+    /// exactly two miette labels, and the underlined portions should be `snippet1`
+    /// and `snippet2`, in that order, with label texts `label1` and `label2`.
+    pub fn exactly_two_underlines_with_labels(
+        self,
+        (snippet1, label1): (&'a str, &'a str),
+        (snippet2, label2): (&'a str, &'a str),
+    ) -> Self {
+        Self {
+            underlines: vec![(snippet1, Some(label1)), (snippet2, Some(label2))],
+            ..self
+        }
+    }
+
     /// Add expected contents of `source()`, or expected prefix of `source()` if
     /// this builder was originally constructed with `error_starts_with()`
     pub fn source(self, msg: &'a str) -> Self {
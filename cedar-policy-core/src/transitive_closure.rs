@@ -55,6 +55,15 @@ where
     K: Clone + Eq + Hash + Debug + Display,
     V: TCNode<K>,
 {
+    if enforce_dag {
+        // Look for a cycle before computing the TC, since the TC no longer
+        // records which edges formed the cycle.
+        if let Some(cycle) = find_cycle(nodes) {
+            if let Some(vertex_with_loop) = cycle.first() {
+                return Err(TcError::has_cycle_along(vertex_with_loop.clone(), cycle));
+            }
+        }
+    }
     compute_tc_internal::<K, V>(nodes);
     if enforce_dag {
         return enforce_dag_from_tc(nodes);
@@ -62,6 +71,56 @@ where
     Ok(())
 }
 
+/// Find a cycle in the graph given as a map from keys with type `K` to
+/// implementations of `TCNode` with type `V`, if there is one. The cycle is
+/// returned as the keys along it, starting and ending with the same key
+/// (e.g., `[A, B, C, A]`). Edges to keys which are not in the map are ignored.
+pub fn find_cycle<K, V>(nodes: &HashMap<K, V>) -> Option<Vec<K>>
+where
+    K: Clone + Eq + Hash,
+    V: TCNode<K>,
+{
+    // Nodes whose descendants have all been explored without finding a cycle
+    let mut finished: HashSet<&K> = HashSet::new();
+    for (start, start_node) in nodes {
+        if finished.contains(start) {
+            continue;
+        }
+        // Depth-first search, keeping the current path and the remaining
+        // out-edges of each node on it. We avoid recursion here so that deep
+        // hierarchies can't overflow the stack.
+        let mut path: Vec<&K> = vec![start];
+        let mut stack = vec![start_node.out_edges()];
+        while let Some(edges) = stack.last_mut() {
+            match edges.next() {
+                Some(next) if path.contains(&next) => {
+                    let cycle = path
+                        .iter()
+                        .skip_while(|k| **k != next)
+                        .map(|k| (*k).clone())
+                        .chain(std::iter::once(next.clone()))
+                        .collect();
+                    return Some(cycle);
+                }
+                Some(next) if finished.contains(next) => (),
+                Some(next) => {
+                    if let Some(node) = nodes.get(next) {
+                        path.push(next);
+                        stack.push(node.out_edges());
+                    }
+                }
+                None => {
+                    stack.pop();
+                    if let Some(done) = path.pop() {
+                        finished.insert(done);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
 /// Given graph as a map from keys with type `K` to implementations of `TCNode`
 /// with type `V`, compute the transitive closure of the hierarchy. In case of
 /// error, the result contains an error structure `Err<K>` which contains the
@@ -753,4 +812,61 @@ mod tests {
             Err(_) => panic!("Unexpected error in enforce_dag_from_tc"),
         }
     }
+
+    #[test]
+    fn find_cycle_path() {
+        //          D
+        //        /
+        // A -> B -> C -> B
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        b.add_ancestor(EntityUID::with_eid("D"));
+        let mut c = Entity::with_uid(EntityUID::with_eid("C"));
+        c.add_ancestor(EntityUID::with_eid("B"));
+        let d = Entity::with_uid(EntityUID::with_eid("D"));
+        let mut entities = HashMap::from([
+            (a.uid().clone(), a),
+            (b.uid().clone(), b),
+            (c.uid().clone(), c),
+            (d.uid().clone(), d),
+        ]);
+        let cycle = find_cycle(&entities).expect("should have found a cycle");
+        let b = EntityUID::with_eid("B");
+        let c = EntityUID::with_eid("C");
+        assert!(
+            cycle == [b.clone(), c.clone(), b.clone()] || cycle == [c.clone(), b, c],
+            "unexpected cycle {cycle:?}"
+        );
+        match compute_tc(&mut entities, true) {
+            Ok(_) => panic!("compute_tc should have returned an error"),
+            Err(TcError::HasCycle(err)) => {
+                assert_eq!(err.path().first(), Some(err.vertex_with_loop()));
+                assert_eq!(err.path().last(), Some(err.vertex_with_loop()));
+                assert_eq!(err.path().len(), 3);
+            }
+            Err(_) => panic!("Unexpected error in compute_tc"),
+        }
+    }
+
+    #[test]
+    fn find_cycle_none() {
+        // A -> B -> C, A -> C, D -> C
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        a.add_ancestor(EntityUID::with_eid("C"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        let c = Entity::with_uid(EntityUID::with_eid("C"));
+        let mut d = Entity::with_uid(EntityUID::with_eid("D"));
+        d.add_ancestor(EntityUID::with_eid("C"));
+        let entities = HashMap::from([
+            (a.uid().clone(), a),
+            (b.uid().clone(), b),
+            (c.uid().clone(), c),
+            (d.uid().clone(), d),
+        ]);
+        assert_eq!(find_cycle(&entities), None);
+    }
 }
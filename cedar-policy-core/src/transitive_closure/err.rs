@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use itertools::Itertools;
 use miette::Diagnostic;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -30,7 +31,7 @@ pub enum TcError<K: Debug + Display> {
     #[error("expected all transitive edges to exist, but `{}` -> `{}` and `{}` -> `{}` exists, while `{}` -> `{}` does not", .0.child, .0.parent, .0.parent, .0.grandparent, .0.child, .0.grandparent)]
     MissingTcEdge(MissingTcEdge<K>),
     /// Error raised when enforce_dag finds that the graph is not a DAG
-    #[error("input graph has a cycle containing vertex `{}`", .0.vertex_with_loop)]
+    #[error("input graph has a cycle containing vertex `{}`{}", .0.vertex_with_loop, .0.display_path())]
     HasCycle(HasCycle<K>),
 }
 
@@ -44,7 +45,19 @@ impl<K: Debug + Display> TcError<K> {
     }
 
     pub(crate) fn has_cycle(vertex_with_loop: K) -> Self {
-        Self::HasCycle(HasCycle { vertex_with_loop })
+        Self::HasCycle(HasCycle {
+            vertex_with_loop,
+            path: Vec::new(),
+        })
+    }
+
+    /// `path` lists the vertices along a cycle containing `vertex_with_loop`,
+    /// starting and ending with that vertex
+    pub(crate) fn has_cycle_along(vertex_with_loop: K, path: Vec<K>) -> Self {
+        Self::HasCycle(HasCycle {
+            vertex_with_loop,
+            path,
+        })
     }
 }
 
@@ -62,6 +75,10 @@ pub struct MissingTcEdge<K: Debug + Display> {
 pub struct HasCycle<K: Debug + Display> {
     /// Because DAG enforcement can only be called after compute_tc/enforce_tc, a cycle will manifest as a vertex with a loop
     vertex_with_loop: K,
+    /// The vertices along the cycle, starting and ending with
+    /// `vertex_with_loop`, if the cycle was found before computing the
+    /// transitive closure. Empty otherwise.
+    path: Vec<K>,
 }
 
 impl<K: Debug + Display> HasCycle<K> {
@@ -69,6 +86,25 @@ impl<K: Debug + Display> HasCycle<K> {
     pub fn vertex_with_loop(&self) -> &K {
         &self.vertex_with_loop
     }
+
+    /// Graph vertices along the cycle, starting and ending with
+    /// `vertex_with_loop`. This is empty if the cycle was only detected after
+    /// computing the transitive closure, at which point the edges forming the
+    /// cycle are no longer known.
+    pub fn path(&self) -> &[K] {
+        &self.path
+    }
+
+    fn display_path(&self) -> String {
+        if self.path.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ": {}",
+                self.path.iter().map(|k| format!("`{k}`")).join(" -> ")
+            )
+        }
+    }
 }
 
 /// Type alias for convenience
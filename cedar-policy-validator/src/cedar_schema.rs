@@ -17,6 +17,7 @@
 //! The Cedar syntax for schemas
 
 mod ast;
+mod doc_comments;
 pub use ast::Path;
 mod err;
 pub mod fmt;
@@ -18,6 +18,7 @@ use std::{collections::BTreeMap, iter::once};
 
 use cedar_policy_core::{
     ast::{Annotation, Annotations, AnyId, Id, InternalName},
+    entities::CedarValueJson,
     parser::{Loc, Node},
 };
 use itertools::{Either, Itertools};
@@ -46,6 +47,21 @@ pub struct Annotated<T> {
 
 pub type Schema = Vec<Annotated<Namespace>>;
 
+/// Collect the literal attributes of an action declaration (or of a record
+/// nested in one), rejecting duplicate attribute names
+pub fn action_attrs_record(
+    attrs: Vec<(Node<SmolStr>, CedarValueJson)>,
+) -> Result<BTreeMap<SmolStr, CedarValueJson>, UserError> {
+    let mut record = BTreeMap::new();
+    for (name, value) in attrs {
+        if record.contains_key(&name.node) {
+            return Err(UserError::DuplicateAttribute(name));
+        }
+        record.insert(name.node, value);
+    }
+    Ok(record)
+}
+
 pub fn deduplicate_annotations<T>(
     data: T,
     annotations: Vec<Node<(Node<AnyId>, Option<Node<SmolStr>>)>>,
@@ -330,8 +346,9 @@ impl std::fmt::Display for PR {
 pub struct PRAppDecl {
     /// Is this constraining the `principal` or the `resource`
     pub kind: Node<PR>,
-    /// What entity types are allowed?
-    pub entity_tys: NonEmpty<Path>,
+    /// What entity types are allowed? `None` means any entity type (written
+    /// `*`)
+    pub entity_tys: Option<NonEmpty<Path>>,
 }
 
 /// A declaration of constraints on an action type
@@ -353,6 +370,8 @@ pub struct ActionDecl {
     pub parents: Option<NonEmpty<Node<QualName>>>,
     /// The constraining clauses in this declarations
     pub app_decls: Option<Node<NonEmpty<Node<AppDecl>>>>,
+    /// The literal attributes of this action
+    pub attrs: BTreeMap<SmolStr, CedarValueJson>,
 }
 
 impl Decl for ActionDecl {
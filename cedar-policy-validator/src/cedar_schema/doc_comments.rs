@@ -0,0 +1,273 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Lifting `//` comments in the Cedar schema syntax into `@doc` annotations.
+//!
+//! A block of consecutive lines holding only a `//` comment documents the
+//! namespace, declaration, or attribute that immediately follows it, possibly
+//! after some annotations. A blank line between the block and the item
+//! detaches the block, and comments at the end of a line are never lifted.
+
+use std::collections::HashMap;
+use std::iter::once;
+
+use cedar_policy_core::ast::{Annotation, Annotations, AnyId};
+use cedar_policy_core::parser::Node;
+use itertools::Either;
+
+use super::ast::{Annotated, AppDecl, AttrDecl, Declaration, Schema, Type};
+
+/// The annotation key that doc comments are lifted into
+pub(crate) const DOC_ANNOTATION: &str = "doc";
+
+/// Doc comments found in a schema source, keyed by the source offset of the
+/// item they document
+#[derive(Debug, Default)]
+struct DocComments {
+    /// Keyed by the offset of the first token after the annotations following
+    /// the comment: the keyword of a declaration or the name of an attribute
+    items: HashMap<usize, String>,
+    /// Keyed by the offset of the path of a namespace
+    namespaces: HashMap<usize, String>,
+}
+
+/// A `//` comment on a line of its own
+struct LineComment<'a> {
+    /// Offset of the line the comment is on
+    line_start: usize,
+    /// Offset just past the end of the comment
+    end: usize,
+    /// The comment text, without `//`
+    text: &'a str,
+}
+
+/// Find all comments in `src` which are alone on their line
+fn line_comments(src: &str) -> Vec<LineComment<'_>> {
+    let mut comments = Vec::new();
+    let mut line_start = 0;
+    let mut only_whitespace = true;
+    let mut in_string = false;
+    let mut chars = src.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = i + 1;
+                only_whitespace = true;
+                continue;
+            }
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            '/' if !in_string && matches!(chars.peek(), Some((_, '/'))) => {
+                let end = src[i..].find('\n').map_or(src.len(), |len| i + len);
+                if only_whitespace {
+                    comments.push(LineComment {
+                        line_start,
+                        end,
+                        text: &src[i + 2..end],
+                    });
+                }
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+                continue;
+            }
+            _ => (),
+        }
+        only_whitespace &= c.is_whitespace();
+    }
+    comments
+}
+
+/// Skip whitespace, comments, and annotations starting at `pos`, returning
+/// the offset of the next token
+fn skip_annotations(src: &str, mut pos: usize) -> usize {
+    loop {
+        let rest = &src[pos..];
+        let trimmed = rest.trim_start();
+        pos += rest.len() - trimmed.len();
+        if trimmed.starts_with("//") {
+            pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        } else if let Some(anno) = trimmed.strip_prefix('@') {
+            let key_len = anno
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(anno.len());
+            pos += 1 + key_len;
+            let rest = &src[pos..];
+            let value = rest.trim_start();
+            if value.starts_with('(') {
+                pos += rest.len() - value.len() + skip_parenthesized_string(value);
+            }
+        } else {
+            return pos;
+        }
+    }
+}
+
+/// The length of the `("string")` at the start of `src`
+fn skip_parenthesized_string(src: &str) -> usize {
+    let mut in_string = false;
+    let mut chars = src.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            ')' if !in_string => return i + 1,
+            _ => (),
+        }
+    }
+    src.len()
+}
+
+impl DocComments {
+    /// Find the doc comments in `src`
+    fn new(src: &str) -> Self {
+        let mut doc_comments = Self::default();
+        let comments = line_comments(src);
+        let mut comments = comments.iter().peekable();
+        while let Some(first) = comments.next() {
+            let mut block = vec![first];
+            while let Some(next) = comments.next_if(|next| {
+                block
+                    .last()
+                    .is_some_and(|prev| src[prev.end..next.line_start].matches('\n').count() == 1)
+            }) {
+                block.push(next);
+            }
+            let end = block.last().map_or(first.end, |last| last.end);
+            let rest = &src[end..];
+            let item_start = end + rest.len() - rest.trim_start().len();
+            // A blank line separates the block from whatever follows
+            if src[end..item_start].matches('\n').count() > 1 {
+                continue;
+            }
+            let text = block
+                .iter()
+                .map(|comment| {
+                    let text = comment.text.strip_prefix(' ').unwrap_or(comment.text);
+                    text.trim_end()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let target = skip_annotations(src, item_start);
+            if let Some(path) = src[target..].strip_prefix("namespace") {
+                if path.starts_with(char::is_whitespace) {
+                    let path_start = src.len() - path.trim_start().len();
+                    Self::add(&mut doc_comments.namespaces, path_start, text.clone());
+                }
+            }
+            Self::add(&mut doc_comments.items, target, text);
+        }
+        doc_comments
+    }
+
+    /// Add the comment `text` at `offset`, after any other comment there
+    fn add(comments: &mut HashMap<usize, String>, offset: usize, text: String) {
+        comments
+            .entry(offset)
+            .and_modify(|existing| {
+                existing.push('\n');
+                existing.push_str(&text);
+            })
+            .or_insert(text);
+    }
+}
+
+/// Add a `@doc` annotation holding `text` to `annotations`, unless it already
+/// has one
+fn add_doc_annotation(annotations: &mut Annotations, text: Option<&String>) {
+    // PANIC SAFETY: `doc` is a valid `AnyId`
+    #[allow(clippy::unwrap_used)]
+    let key: AnyId = DOC_ANNOTATION.parse().unwrap();
+    if let Some(text) = text {
+        if annotations.get(&key).is_none() {
+            *annotations = std::mem::take(annotations)
+                .into_iter()
+                .chain(once((
+                    key,
+                    Annotation::with_optional_value(Some(text.into()), None),
+                )))
+                .collect();
+        }
+    }
+}
+
+/// Lift the doc comments in `src` into `@doc` annotations on the items of
+/// `schema`, which must have been parsed from `src`. Items which already have
+/// a `@doc` annotation are left as they are.
+pub(crate) fn lift_doc_comments(src: &str, schema: &mut Schema) {
+    let doc_comments = DocComments::new(src);
+    for ns in schema {
+        if let Some(path) = &ns.data.name {
+            add_doc_annotation(
+                &mut ns.annotations,
+                doc_comments.namespaces.get(&path.loc().start()),
+            );
+        }
+        for decl in &mut ns.data.decls {
+            doc_comments.annotate_decl(decl);
+        }
+    }
+}
+
+impl DocComments {
+    fn annotate_decl(&self, decl: &mut Annotated<Node<Declaration>>) {
+        add_doc_annotation(
+            &mut decl.annotations,
+            self.items.get(&decl.data.loc.start()),
+        );
+        match &mut decl.data.node {
+            Declaration::Entity(entity) => {
+                self.annotate_attrs(&mut entity.attrs);
+                if let Some(tags) = &mut entity.tags {
+                    self.annotate_type(tags);
+                }
+            }
+            Declaration::Action(action) => {
+                let app_decls = action
+                    .app_decls
+                    .iter_mut()
+                    .flat_map(|decls| decls.node.iter_mut());
+                for app_decl in app_decls {
+                    if let AppDecl::Context(Either::Right(attrs)) = &mut app_decl.node {
+                        self.annotate_attrs(attrs);
+                    }
+                }
+            }
+            Declaration::Type(ty) => self.annotate_type(&mut ty.def),
+        }
+    }
+
+    fn annotate_attrs(&self, attrs: &mut [Node<Annotated<AttrDecl>>]) {
+        for attr in attrs {
+            let attr = &mut attr.node;
+            add_doc_annotation(
+                &mut attr.annotations,
+                self.items.get(&attr.data.name.loc.start()),
+            );
+            self.annotate_type(&mut attr.data.ty);
+        }
+    }
+
+    fn annotate_type(&self, ty: &mut Node<Type>) {
+        match &mut ty.node {
+            Type::Set(element) => self.annotate_type(element),
+            Type::Record(attrs) => self.annotate_attrs(attrs),
+            Type::Ident(_) => (),
+        }
+    }
+}
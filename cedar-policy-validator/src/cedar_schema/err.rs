@@ -25,7 +25,6 @@ use std::{
 use cedar_policy_core::{
     ast::AnyId,
     impl_diagnostic_from_source_loc_field, impl_diagnostic_from_two_source_loc_fields,
-    impl_diagnostic_from_two_source_loc_opt_fields,
     parser::{
         err::{expected_to_string, ExpectedTokenConfig},
         unescape::UnescapeError,
@@ -51,6 +50,12 @@ pub enum UserError {
     ReservedIdentifierUsed(Node<SmolStr>),
     #[error("duplicate annotations: `{}`", .0)]
     DuplicateAnnotations(AnyId, Node<()>, Node<()>),
+    #[error("integer literal `{0}` is out of range")]
+    IntegerOutOfRange(Node<SmolStr>),
+    #[error("`{0}` is not a valid attribute value; expected a string, integer, `true`, `false`, set, or record")]
+    InvalidAttributeValue(Node<SmolStr>),
+    #[error("duplicate attribute `{0}`")]
+    DuplicateAttribute(Node<SmolStr>),
 }
 
 impl UserError {
@@ -62,6 +67,9 @@ impl UserError {
             Self::ReservedIdentifierUsed(n) => n.loc.span,
             // use the first occurrence as the primary source span
             Self::DuplicateAnnotations(_, n, _) => n.loc.span,
+            Self::IntegerOutOfRange(n)
+            | Self::InvalidAttributeValue(n)
+            | Self::DuplicateAttribute(n) => n.loc.span,
         }
     }
 }
@@ -616,7 +624,19 @@ pub struct DuplicateDeclarations {
 }
 
 impl Diagnostic for DuplicateDeclarations {
-    impl_diagnostic_from_two_source_loc_fields!(loc1, loc2);
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.loc1.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(
+            [
+                LabeledSpan::new_with_span(Some("first declared here".into()), self.loc1.span),
+                LabeledSpan::new_with_span(Some("declared again here".into()), self.loc2.span),
+            ]
+            .into_iter(),
+        ))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
@@ -648,7 +668,27 @@ pub struct DuplicateNamespace {
 }
 
 impl Diagnostic for DuplicateNamespace {
-    impl_diagnostic_from_two_source_loc_opt_fields!(loc1, loc2);
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.loc1
+            .as_ref()
+            .or(self.loc2.as_ref())
+            .and_then(Loc::source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let first = self
+            .loc1
+            .as_ref()
+            .map(|loc| LabeledSpan::new_with_span(Some("first declared here".into()), loc.span));
+        let second = self
+            .loc2
+            .as_ref()
+            .map(|loc| LabeledSpan::new_with_span(Some("declared again here".into()), loc.span));
+        match (first, second) {
+            (None, None) => None,
+            (first, second) => Some(Box::new(first.into_iter().chain(second))),
+        }
+    }
 }
 
 /// Error subtypes for [`SchemaWarning`]
@@ -704,7 +744,7 @@ pub mod schema_warnings {
             // just have to pick one; we assume `entity_loc` and `common_loc`
             // have the same source code.
             // if that isn't true we'll have a confusing underline.
-            Some(&self.entity_loc.src as _)
+            self.entity_loc.source_code()
         }
 
         fn severity(&self) -> Option<miette::Severity> {
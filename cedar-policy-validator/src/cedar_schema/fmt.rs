@@ -19,20 +19,78 @@
 
 use std::{collections::HashSet, fmt::Display};
 
+use cedar_policy_core::{entities::CedarValueJson, est::Annotations};
 use itertools::Itertools;
 use miette::Diagnostic;
 use nonempty::NonEmpty;
 use smol_str::{SmolStr, ToSmolStr};
 use thiserror::Error;
 
+use super::doc_comments::DOC_ANNOTATION;
 use crate::{json_schema, RawName};
 
+// The alternate form of these implementations (`{:#}`) writes `@doc`
+// annotations as `//` comments instead.
+
+/// Write `value`, in the alternate form if `f` is in the alternate form
+fn fmt_nested(f: &mut std::fmt::Formatter<'_>, value: &impl Display) -> std::fmt::Result {
+    if f.alternate() {
+        write!(f, "{value:#}")
+    } else {
+        write!(f, "{value}")
+    }
+}
+
+/// The `@doc` annotation in `annotations` which `f` writes as a comment, if any
+fn doc_comment<'a>(f: &std::fmt::Formatter<'_>, annotations: &'a Annotations) -> Option<&'a str> {
+    if !f.alternate() {
+        return None;
+    }
+    annotations
+        .0
+        .iter()
+        .find(|(key, _)| key.as_ref() == DOC_ANNOTATION)
+        .and_then(|(_, anno)| anno.as_ref())
+        .map(|anno| anno.val.as_str())
+        .filter(|doc| !doc.is_empty())
+}
+
+/// Write `annotations`, each on its own line
+fn fmt_annotations(f: &mut std::fmt::Formatter<'_>, annotations: &Annotations) -> std::fmt::Result {
+    match doc_comment(f, annotations) {
+        Some(doc) => {
+            for line in doc.lines() {
+                if line.is_empty() {
+                    writeln!(f, "//")?;
+                } else {
+                    writeln!(f, "// {line}")?;
+                }
+            }
+            let others = Annotations(
+                annotations
+                    .0
+                    .iter()
+                    .filter(|(key, _)| key.as_ref() != DOC_ANNOTATION)
+                    .map(|(key, anno)| (key.clone(), anno.clone()))
+                    .collect(),
+            );
+            write!(f, "{others}")
+        }
+        None => write!(f, "{annotations}"),
+    }
+}
+
 impl<N: Display> Display for json_schema::Fragment<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (ns, def) in &self.0 {
             match ns {
-                None => write!(f, "{def}")?,
-                Some(ns) => write!(f, "{}namespace {ns} {{\n{}}}\n", def.annotations, def)?,
+                None => fmt_nested(f, def)?,
+                Some(ns) => {
+                    fmt_annotations(f, &def.annotations)?;
+                    writeln!(f, "namespace {ns} {{")?;
+                    fmt_nested(f, def)?;
+                    writeln!(f, "}}")?;
+                }
             }
         }
         Ok(())
@@ -42,13 +100,22 @@ impl<N: Display> Display for json_schema::Fragment<N> {
 impl<N: Display> Display for json_schema::NamespaceDefinition<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (n, ty) in &self.common_types {
-            writeln!(f, "{}type {n} = {};", ty.annotations, ty.ty)?
+            fmt_annotations(f, &ty.annotations)?;
+            write!(f, "type {n} = ")?;
+            fmt_nested(f, &ty.ty)?;
+            writeln!(f, ";")?;
         }
         for (n, ty) in &self.entity_types {
-            writeln!(f, "{}entity {n}{};", ty.annotations, ty)?
+            fmt_annotations(f, &ty.annotations)?;
+            write!(f, "entity {n}")?;
+            fmt_nested(f, ty)?;
+            writeln!(f, ";")?;
         }
         for (n, a) in &self.actions {
-            writeln!(f, "{}action \"{}\"{};", a.annotations, n.escape_debug(), a)?
+            fmt_annotations(f, &a.annotations)?;
+            write!(f, "action \"{}\"", n.escape_debug())?;
+            fmt_nested(f, a)?;
+            writeln!(f, ";")?;
         }
         Ok(())
     }
@@ -65,8 +132,12 @@ impl<N: Display> Display for json_schema::Type<N> {
                 }
                 json_schema::TypeVariant::Extension { name } => write!(f, "__cedar::{name}"),
                 json_schema::TypeVariant::Long => write!(f, "__cedar::Long"),
-                json_schema::TypeVariant::Record(rty) => write!(f, "{rty}"),
-                json_schema::TypeVariant::Set { element } => write!(f, "Set < {element} >"),
+                json_schema::TypeVariant::Record(rty) => fmt_nested(f, rty),
+                json_schema::TypeVariant::Set { element } => {
+                    write!(f, "Set < ")?;
+                    fmt_nested(f, element.as_ref())?;
+                    write!(f, " >")
+                }
                 json_schema::TypeVariant::String => write!(f, "__cedar::String"),
             },
             json_schema::Type::CommonTypeRef { type_name } => write!(f, "{type_name}"),
@@ -77,15 +148,21 @@ impl<N: Display> Display for json_schema::Type<N> {
 impl<N: Display> Display for json_schema::RecordType<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{")?;
+        // A comment must start on a line of its own
+        if let Some((_, ty)) = self.attributes.first_key_value() {
+            if doc_comment(f, &ty.annotations).is_some() {
+                writeln!(f)?;
+            }
+        }
         for (i, (n, ty)) in self.attributes.iter().enumerate() {
+            fmt_annotations(f, &ty.annotations)?;
             write!(
                 f,
-                "{}\"{}\"{}: {}",
-                ty.annotations,
+                "\"{}\"{}: ",
                 n.escape_debug(),
                 if ty.required { "" } else { "?" },
-                ty.ty
             )?;
+            fmt_nested(f, &ty.ty)?;
             if i < (self.attributes.len() - 1) {
                 writeln!(f, ", ")?;
             }
@@ -105,6 +182,28 @@ fn fmt_vec<T: Display>(f: &mut std::fmt::Formatter<'_>, ets: NonEmpty<T>) -> std
     write!(f, "[{contents}]")
 }
 
+/// The principal or resource types of an `appliesTo`: `None` if the action
+/// applies to none, and `Some(None)` if it applies to any
+fn applies_to_targets<N>(any: bool, tys: &[N]) -> Option<Option<NonEmpty<&N>>> {
+    if any {
+        Some(None)
+    } else {
+        non_empty_slice(tys).map(Some)
+    }
+}
+
+/// Format the principal or resource types of an `appliesTo`, where `None`
+/// means any entity type
+fn fmt_targets<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    ets: Option<NonEmpty<T>>,
+) -> std::fmt::Result {
+    match ets {
+        Some(ets) => fmt_vec(f, ets),
+        None => write!(f, "*"),
+    }
+}
+
 impl<N: Display> Display for json_schema::EntityType<N> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(non_empty) = non_empty_slice(&self.member_of_types) {
@@ -115,11 +214,13 @@ impl<N: Display> Display for json_schema::EntityType<N> {
         let ty = &self.shape;
         // Don't print `= { }`
         if !ty.is_empty_record() {
-            write!(f, " = {ty}")?;
+            write!(f, " = ")?;
+            fmt_nested(f, ty)?;
         }
 
         if let Some(tags) = &self.tags {
-            write!(f, " tags {tags}")?;
+            write!(f, " tags ")?;
+            fmt_nested(f, tags)?;
         }
 
         Ok(())
@@ -138,8 +239,8 @@ impl<N: Display> Display for json_schema::ActionType<N> {
         }
         if let Some(spec) = &self.applies_to {
             match (
-                non_empty_slice(spec.principal_types.as_slice()),
-                non_empty_slice(spec.resource_types.as_slice()),
+                applies_to_targets(spec.any_principal, &spec.principal_types),
+                applies_to_targets(spec.any_resource, &spec.resource_types),
             ) {
                 // One of the lists is empty
                 // This can only be represented by the empty action
@@ -151,19 +252,56 @@ impl<N: Display> Display for json_schema::ActionType<N> {
                 (Some(ps), Some(rs)) => {
                     write!(f, " appliesTo {{")?;
                     write!(f, "\n  principal: ")?;
-                    fmt_vec(f, ps)?;
+                    fmt_targets(f, ps)?;
                     write!(f, ",\n  resource: ")?;
-                    fmt_vec(f, rs)?;
-                    write!(f, ",\n  context: {}", &spec.context.0)?;
+                    fmt_targets(f, rs)?;
+                    write!(f, ",\n  context: ")?;
+                    fmt_nested(f, &spec.context.0)?;
                     write!(f, "\n}}")?;
                 }
             }
         }
         // No `appliesTo` key: action does not apply to anything
+        if let Some(attrs) = self.attributes.as_ref().filter(|attrs| !attrs.is_empty()) {
+            write!(f, " attributes {{")?;
+            for (name, value) in attrs.iter().sorted_by(|(a, _), (b, _)| a.cmp(b)) {
+                // Values the Cedar syntax can't express are rejected by
+                // `check_action_attributes` before we get here
+                if let Some(value) = attr_value_literal(value) {
+                    write!(f, "\n  \"{}\": {value},", name.escape_debug())?;
+                }
+            }
+            write!(f, "\n}}")?;
+        }
         Ok(())
     }
 }
 
+/// The Cedar schema syntax for an action attribute value, or `None` if the
+/// syntax can't express it (entity and extension values, and `null`)
+fn attr_value_literal(value: &CedarValueJson) -> Option<String> {
+    match value {
+        CedarValueJson::Bool(b) => Some(b.to_string()),
+        CedarValueJson::Long(i) => Some(i.to_string()),
+        CedarValueJson::String(s) => Some(format!("\"{}\"", s.escape_debug())),
+        CedarValueJson::Set(vs) => vs
+            .iter()
+            .map(attr_value_literal)
+            .collect::<Option<Vec<_>>>()
+            .map(|vs| format!("[{}]", vs.join(", "))),
+        CedarValueJson::Record(record) => record
+            .iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(k, v)| attr_value_literal(v).map(|v| format!("\"{}\": {v}", k.escape_debug())))
+            .collect::<Option<Vec<_>>>()
+            .map(|attrs| format!("{{{}}}", attrs.join(", "))),
+        CedarValueJson::ExprEscape { .. }
+        | CedarValueJson::EntityEscape { .. }
+        | CedarValueJson::ExtnEscape { .. }
+        | CedarValueJson::Null => None,
+    }
+}
+
 /// Error converting a schema to the Cedar syntax
 #[derive(Debug, Diagnostic, Error)]
 pub enum ToCedarSchemaSyntaxError {
@@ -171,6 +309,10 @@ pub enum ToCedarSchemaSyntaxError {
     #[diagnostic(transparent)]
     #[error(transparent)]
     NameCollisions(#[from] NameCollisionsError),
+    /// An action attribute has a value the Cedar syntax can't express
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    UnsupportedActionAttribute(#[from] UnsupportedActionAttributeError),
 }
 
 /// Duplicate names were found in the schema
@@ -188,6 +330,32 @@ impl NameCollisionsError {
     }
 }
 
+/// An action attribute has a value the Cedar syntax can't express: an entity
+/// or extension value, or `null`
+#[derive(Debug, Error, Diagnostic)]
+#[error("attribute `{attr}` of action `{action}` has a value that cannot be written in the Cedar schema syntax")]
+#[diagnostic(help(
+    "action attributes in the Cedar schema syntax may only be strings, integers, booleans, sets, and records"
+))]
+pub struct UnsupportedActionAttributeError {
+    /// The action with the attribute
+    action: SmolStr,
+    /// The attribute
+    attr: SmolStr,
+}
+
+impl UnsupportedActionAttributeError {
+    /// Get the (fully-qualified) name of the action with the attribute
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// Get the name of the attribute
+    pub fn attr(&self) -> &str {
+        &self.attr
+    }
+}
+
 /// Convert a [`json_schema::Fragment`] to a string containing the Cedar schema syntax
 ///
 /// As of this writing, this existing code throws an error if any
@@ -206,6 +374,28 @@ impl NameCollisionsError {
 pub fn json_schema_to_cedar_schema_str<N: Display>(
     json_schema: &json_schema::Fragment<N>,
 ) -> Result<String, ToCedarSchemaSyntaxError> {
+    check_name_collisions(json_schema)?;
+    check_action_attributes(json_schema)?;
+    Ok(json_schema.to_string())
+}
+
+/// Convert a [`json_schema::Fragment`] to a string containing the Cedar schema
+/// syntax, like [`json_schema_to_cedar_schema_str`], but writing `@doc`
+/// annotations as `//` comments
+pub fn json_schema_to_cedar_schema_str_with_doc_comments<N: Display>(
+    json_schema: &json_schema::Fragment<N>,
+) -> Result<String, ToCedarSchemaSyntaxError> {
+    check_name_collisions(json_schema)?;
+    check_action_attributes(json_schema)?;
+    Ok(format!("{json_schema:#}"))
+}
+
+/// Check that no fully-qualified name in a non-empty namespace of
+/// `json_schema` is both a common type and an entity type; see
+/// [`json_schema_to_cedar_schema_str`]
+fn check_name_collisions<N: Display>(
+    json_schema: &json_schema::Fragment<N>,
+) -> Result<(), ToCedarSchemaSyntaxError> {
     let mut name_collisions: Vec<SmolStr> = Vec::new();
     for (name, ns) in json_schema.0.iter().filter(|(name, _)| !name.is_none()) {
         let entity_types: HashSet<SmolStr> = ns
@@ -234,7 +424,37 @@ pub fn json_schema_to_cedar_schema_str<N: Display>(
         }
         .into());
     }
-    Ok(json_schema.to_string())
+    Ok(())
+}
+
+/// Check that every action attribute value in `json_schema` can be written in
+/// the Cedar syntax
+fn check_action_attributes<N: Display>(
+    json_schema: &json_schema::Fragment<N>,
+) -> Result<(), ToCedarSchemaSyntaxError> {
+    for (ns_name, ns) in &json_schema.0 {
+        for (action, ty) in &ns.actions {
+            let unsupported = ty
+                .attributes
+                .iter()
+                .flatten()
+                .filter(|(_, value)| attr_value_literal(value).is_none())
+                .map(|(attr, _)| attr)
+                .min();
+            if let Some(attr) = unsupported {
+                let action = match ns_name {
+                    Some(ns_name) => format!("{ns_name}::Action::\"{}\"", action.escape_debug()),
+                    None => format!("Action::\"{}\"", action.escape_debug()),
+                };
+                return Err(UnsupportedActionAttributeError {
+                    action: action.into(),
+                    attr: attr.clone(),
+                }
+                .into());
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -265,6 +485,53 @@ mod tests {
         test_round_trip(src);
     }
 
+    #[test]
+    fn any_targets() {
+        let src = "entity User;
+          action health appliesTo { principal: *, resource: * };
+          action view appliesTo { principal: User, resource: * };";
+        test_round_trip(src);
+    }
+
+    #[test]
+    fn action_attributes() {
+        let src = r#"entity User;
+          action view appliesTo { principal: User, resource: User } attributes {
+            category: "read",
+            "quoted \"name\"": -7,
+            flags: [true, false],
+            meta: { owner: "ops\n", nested: { empty: [] } },
+          };
+          action group attributes { level: 3 };"#;
+        test_round_trip(src);
+    }
+
+    #[test]
+    fn unsupported_action_attributes() {
+        let fragment =
+            crate::json_schema::Fragment::<crate::RawName>::from_json_value(serde_json::json!({
+                "NS": {
+                    "entityTypes": {},
+                    "actions": {
+                        "view": {
+                            "attributes": {
+                                "category": "read",
+                                "owner": { "__entity": { "type": "User", "id": "alice" } },
+                            },
+                        },
+                    },
+                },
+            }))
+            .expect("should parse");
+        cool_asserts::assert_matches!(
+            fragment.to_cedarschema(),
+            Err(super::ToCedarSchemaSyntaxError::UnsupportedActionAttribute(err)) => {
+                assert_eq!(err.action(), "NS::Action::\"view\"");
+                assert_eq!(err.attr(), "owner");
+            }
+        );
+    }
+
     #[test]
     fn annotations() {
         let src = r#"@doc("this is the namespace")
@@ -299,4 +566,125 @@ namespace TinyTodo {
 }"#;
         test_round_trip(src);
     }
+
+    // PANIC SAFETY: unit tests
+    #[allow(clippy::indexing_slicing)]
+    mod doc_comments {
+        use cedar_policy_core::extensions::Extensions;
+        use serde_json::json;
+
+        use crate::cedar_schema::parser::{
+            parse_cedar_schema_fragment, parse_cedar_schema_fragment_with_doc_comments,
+        };
+
+        const SRC: &str = r#"// The application
+// namespace
+namespace App {
+    // A user
+    @deprecated
+    entity User = {
+        // the user's name
+        "name": String,
+        "address": {
+            // the street
+            street: String, // not lifted
+        },
+    };
+
+    // detached comment
+
+    @doc("explicit doc")
+    // the comment is ignored when there is a `@doc` annotation
+    entity Team;
+
+    // a set of users
+    type Users = Set<User>;
+
+    // viewing
+    action "view" appliesTo {
+        principal: User,
+        resource: Team,
+        context: {
+            // "true" if remote
+            remote: Bool,
+        }
+    };
+}"#;
+
+        #[test]
+        fn lifted() {
+            let (fragment, _) =
+                parse_cedar_schema_fragment_with_doc_comments(SRC, Extensions::none())
+                    .expect("should parse");
+            let json = serde_json::to_value(&fragment).unwrap();
+            let app = &json["App"];
+            assert_eq!(
+                app["annotations"],
+                json!({ "doc": "The application\nnamespace" })
+            );
+            let user = &app["entityTypes"]["User"];
+            assert_eq!(
+                user["annotations"],
+                json!({ "doc": "A user", "deprecated": "" })
+            );
+            let attrs = &user["shape"]["attributes"];
+            assert_eq!(
+                attrs["name"]["annotations"],
+                json!({ "doc": "the user's name" })
+            );
+            assert_eq!(attrs["address"].get("annotations"), None);
+            assert_eq!(
+                attrs["address"]["attributes"]["street"]["annotations"],
+                json!({ "doc": "the street" })
+            );
+            assert_eq!(
+                app["entityTypes"]["Team"]["annotations"],
+                json!({ "doc": "explicit doc" })
+            );
+            assert_eq!(
+                app["commonTypes"]["Users"]["annotations"],
+                json!({ "doc": "a set of users" })
+            );
+            let view = &app["actions"]["view"];
+            assert_eq!(view["annotations"], json!({ "doc": "viewing" }));
+            assert_eq!(
+                view["appliesTo"]["context"]["attributes"]["remote"]["annotations"],
+                json!({ "doc": "\"true\" if remote" })
+            );
+
+            // Without lifting, comments are dropped
+            let (fragment, _) =
+                parse_cedar_schema_fragment(SRC, Extensions::none()).expect("should parse");
+            let json = serde_json::to_value(&fragment).unwrap();
+            assert_eq!(json["App"].get("annotations"), None);
+        }
+
+        #[test]
+        fn round_trip() {
+            let (fragment, _) =
+                parse_cedar_schema_fragment_with_doc_comments(SRC, Extensions::none())
+                    .expect("should parse");
+            let printed = fragment
+                .to_cedarschema_with_doc_comments()
+                .expect("should convert");
+            assert!(
+                printed.starts_with("// The application\n// namespace\nnamespace App {\n"),
+                "unexpected schema: {printed}"
+            );
+            assert!(
+                printed.contains("\"address\": {\n// the street\n\"street\": "),
+                "unexpected schema: {printed}"
+            );
+            assert!(!printed.contains("@doc(\"A user\")"));
+            let (reparsed, _) =
+                parse_cedar_schema_fragment_with_doc_comments(&printed, Extensions::none())
+                    .expect("should parse");
+            assert_eq!(fragment, reparsed);
+            // The usual printing keeps `@doc` annotations
+            assert!(fragment
+                .to_cedarschema()
+                .expect("should convert")
+                .contains("@doc(\"A user\")"));
+        }
+    }
 }
@@ -24,6 +24,7 @@ use thiserror::Error;
 
 use super::{
     ast::Schema,
+    doc_comments::lift_doc_comments,
     err::{self, ParseError, ParseErrors, SchemaWarning, ToJsonSchemaErrors},
     to_json_schema::cedar_schema_to_json_schema,
 };
@@ -119,6 +120,26 @@ pub fn parse_cedar_schema_fragment<'a>(
     Ok(tuple)
 }
 
+/// Parse a schema fragment, in the Cedar syntax, into a [`json_schema::Fragment`],
+/// possibly generating warnings. Unlike [`parse_cedar_schema_fragment`],
+/// comments on their own line(s) directly before a namespace, declaration, or
+/// attribute become its `@doc` annotation, unless it already has one.
+pub fn parse_cedar_schema_fragment_with_doc_comments<'a>(
+    src: &str,
+    extensions: &Extensions<'a>,
+) -> Result<
+    (
+        json_schema::Fragment<crate::RawName>,
+        impl Iterator<Item = SchemaWarning> + 'a,
+    ),
+    CedarSchemaParseErrors,
+> {
+    let mut ast: Schema = parse_collect_errors(&*SCHEMA_PARSER, grammar::SchemaParser::parse, src)?;
+    lift_doc_comments(src, &mut ast);
+    let tuple = cedar_schema_to_json_schema(ast, extensions)?;
+    Ok(tuple)
+}
+
 /// Parse schema from text
 pub fn parse_schema(text: &str) -> Result<Schema, err::ParseErrors> {
     parse_collect_errors(&*SCHEMA_PARSER, grammar::SchemaParser::parse, text)
@@ -237,6 +237,36 @@ mod demo_tests {
         );
     }
 
+    #[test]
+    fn any_targets() {
+        let src = r#"
+            entity a;
+            action "Foo" appliesTo {
+                principal: *,
+                resource: [a]
+            };
+            action "Bar" appliesTo {
+                principal: a,
+                resource: *
+            };
+        "#;
+        let (schema, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available()).unwrap();
+        let unqual = schema.0.get(&None).unwrap();
+        assert_matches!(unqual.actions.get("Foo").unwrap().applies_to.as_ref(),
+            Some(json_schema::ApplySpec { principal_types, any_principal: true, resource_types, any_resource: false, .. }) => {
+                assert!(principal_types.is_empty());
+                assert_eq!(resource_types, &["a".parse().unwrap()]);
+            }
+        );
+        assert_matches!(unqual.actions.get("Bar").unwrap().applies_to.as_ref(),
+            Some(json_schema::ApplySpec { principal_types, any_principal: false, resource_types, any_resource: true, .. }) => {
+                assert_eq!(principal_types, &["a".parse().unwrap()]);
+                assert!(resource_types.is_empty());
+            }
+        );
+    }
+
     #[test]
     fn both_targets_flipped() {
         let src = r#"
@@ -448,6 +478,8 @@ namespace Baz {action "Foo" appliesTo {
                     applies_to: Some(json_schema::ApplySpec::<RawName> {
                         resource_types: vec![],
                         principal_types: vec!["a".parse().unwrap()],
+                        any_resource: false,
+                        any_principal: false,
                         context: json_schema::AttributesOrContext::default(),
                     }),
                     member_of: None,
@@ -1201,14 +1233,26 @@ mod translator_tests {
     /// Test that duplicate namespaces are not allowed
     #[test]
     fn duplicate_namespace() {
-        let schema = collect_warnings(json_schema::Fragment::from_cedarschema_str(
-            r#"
+        let src = r#"
           namespace A {}
           namespace A {}
-        "#,
+        "#;
+        let schema = collect_warnings(json_schema::Fragment::from_cedarschema_str(
+            src,
             Extensions::all_available(),
         ));
-        assert_matches!(schema, Err(_));
+        assert_matches!(schema, Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error("error parsing schema: duplicate namespace id: `A`")
+                    .exactly_two_underlines_with_labels(
+                        ("A", "first declared here"),
+                        ("A", "declared again here"),
+                    )
+                    .build(),
+            );
+        });
     }
 
     /// Test that duplicate action names are not allowed
@@ -1256,14 +1300,26 @@ mod translator_tests {
     /// Test that duplicate entity type names are not allowed
     #[test]
     fn duplicate_entity_types() {
-        let schema = collect_warnings(json_schema::Fragment::from_cedarschema_str(
-            r#"
+        let src = r#"
           entity A;
           entity A {};
-        "#,
+        "#;
+        let schema = collect_warnings(json_schema::Fragment::from_cedarschema_str(
+            src,
             Extensions::all_available(),
         ));
-        assert_matches!(schema, Err(_));
+        assert_matches!(schema, Err(e) => {
+            expect_err(
+                src,
+                &miette::Report::new(e),
+                &ExpectedErrorMessageBuilder::error("error parsing schema: `A` is declared twice")
+                    .exactly_two_underlines_with_labels(
+                        ("A", "first declared here"),
+                        ("A", "declared again here"),
+                    )
+                    .build(),
+            );
+        });
         assert_matches!(
             collect_warnings(json_schema::Fragment::from_cedarschema_str(
                 r#"
@@ -2764,3 +2820,80 @@ namespace TinyTodo {
         );
     }
 }
+
+/// Tests involving literal action attributes
+#[cfg(test)]
+mod action_attributes {
+    use cedar_policy_core::{entities::CedarValueJson, extensions::Extensions};
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    use crate::{
+        cedar_schema::parser::parse_schema, json_schema, schema::test::utils::collect_warnings,
+    };
+
+    #[test]
+    fn literals() {
+        let src = r#"
+            action view attributes {
+                category: "read",
+                "level": -2,
+                audited: true,
+                tags: ["a", "b",],
+                meta: { owner: "ops", retries: [1, 2] },
+            };
+        "#;
+        assert_matches!(collect_warnings(json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available())), Ok((frag, warnings)) => {
+            assert!(warnings.is_empty());
+            let action = frag.0.get(&None).unwrap().actions.get("view").unwrap();
+            let attrs = action.attributes.as_ref().unwrap();
+            assert_eq!(attrs.len(), 5);
+            assert_eq!(attrs.get("category"), Some(&CedarValueJson::String("read".into())));
+            assert_eq!(attrs.get("level"), Some(&CedarValueJson::Long(-2)));
+            assert_eq!(attrs.get("audited"), Some(&CedarValueJson::Bool(true)));
+            assert_eq!(
+                serde_json::to_value(attrs.get("meta").unwrap()).unwrap(),
+                json!({ "owner": "ops", "retries": [1, 2] })
+            );
+        });
+    }
+
+    #[test]
+    fn empty_attributes_are_omitted() {
+        let src = "action view attributes {};";
+        assert_matches!(collect_warnings(json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available())), Ok((frag, _)) => {
+            let action = frag.0.get(&None).unwrap().actions.get("view").unwrap();
+            assert_matches!(action.attributes, None);
+        });
+    }
+
+    #[test]
+    fn extreme_integers() {
+        let src = "action view attributes { min: -9223372036854775808, max: 9223372036854775807 };";
+        assert_matches!(collect_warnings(json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available())), Ok((frag, _)) => {
+            let attrs = frag.0.get(&None).unwrap().actions.get("view").unwrap().attributes.clone().unwrap();
+            assert_eq!(attrs.get("min"), Some(&CedarValueJson::Long(i64::MIN)));
+            assert_eq!(attrs.get("max"), Some(&CedarValueJson::Long(i64::MAX)));
+        });
+    }
+
+    #[test]
+    fn errors() {
+        assert_matches!(parse_schema("action view attributes { level: 9223372036854775808 };"), Err(errs) => {
+            assert_eq!(errs.0.as_ref().first().to_string(), "integer literal `9223372036854775808` is out of range");
+        });
+        assert_matches!(parse_schema("action view attributes { owner: User };"), Err(errs) => {
+            assert_eq!(
+                errs.0.as_ref().first().to_string(),
+                "`User` is not a valid attribute value; expected a string, integer, `true`, `false`, set, or record"
+            );
+        });
+        assert_matches!(parse_schema("action view attributes { a: 1, \"a\": 2 };"), Err(errs) => {
+            assert_eq!(errs.0.as_ref().first().to_string(), "duplicate attribute `a`");
+        });
+        assert_matches!(parse_schema("action view attributes { m: { a: 1, a: 2 } };"), Err(errs) => {
+            assert_eq!(errs.0.as_ref().first().to_string(), "duplicate attribute `a`");
+        });
+        assert_matches!(parse_schema("action view attributes { a: 1,, };"), Err(_));
+    }
+}
@@ -225,6 +225,7 @@ fn convert_action_decl(
         names,
         parents,
         app_decls,
+        attrs,
     } = a.data;
     // Create the internal type from the 'applies_to' clause and 'member_of'
     let applies_to = app_decls
@@ -233,11 +234,13 @@ fn convert_action_decl(
         .unwrap_or_else(|| json_schema::ApplySpec {
             resource_types: vec![],
             principal_types: vec![],
+            any_resource: false,
+            any_principal: false,
             context: json_schema::AttributesOrContext::default(),
         });
     let member_of = parents.map(|parents| parents.into_iter().map(convert_qual_name).collect());
     let ty = json_schema::ActionType {
-        attributes: None, // Action attributes are currently unsupported in the Cedar schema format
+        attributes: (!attrs.is_empty()).then(|| attrs.into_iter().collect()),
         applies_to: Some(applies_to),
         member_of,
         annotations: a.annotations.into(),
@@ -261,8 +264,9 @@ fn convert_app_decls(
 ) -> Result<json_schema::ApplySpec<RawName>, ToJsonSchemaErrors> {
     // Split AppDecl's into context/principal/resource decls
     let (decls, _) = decls.into_inner();
-    let mut principal_types: Option<Node<Vec<RawName>>> = None;
-    let mut resource_types: Option<Node<Vec<RawName>>> = None;
+    // `None` inside the node means any entity type
+    let mut principal_types: Option<Node<Option<Vec<RawName>>>> = None;
+    let mut resource_types: Option<Node<Option<Vec<RawName>>>> = None;
     let mut context: Option<Node<json_schema::AttributesOrContext<RawName>>> = None;
 
     for decl in decls {
@@ -308,7 +312,7 @@ fn convert_app_decls(
                 }
                 None => {
                     principal_types = Some(Node::with_source_loc(
-                        entity_tys.iter().map(|n| n.clone().into()).collect(),
+                        entity_tys.map(|tys| tys.into_iter().map(Into::into).collect()),
                         loc,
                     ))
                 }
@@ -331,20 +335,24 @@ fn convert_app_decls(
                 }
                 None => {
                     resource_types = Some(Node::with_source_loc(
-                        entity_tys.iter().map(|n| n.clone().into()).collect(),
+                        entity_tys.map(|tys| tys.into_iter().map(Into::into).collect()),
                         loc,
                     ))
                 }
             },
         }
     }
+    let resource_types = resource_types
+        .map(|node| node.node)
+        .ok_or_else(|| ToJsonSchemaError::no_resource(&name, name_loc.clone()))?;
+    let principal_types = principal_types
+        .map(|node| node.node)
+        .ok_or_else(|| ToJsonSchemaError::no_principal(&name, name_loc.clone()))?;
     Ok(json_schema::ApplySpec {
-        resource_types: resource_types
-            .map(|node| node.node)
-            .ok_or_else(|| ToJsonSchemaError::no_resource(&name, name_loc.clone()))?,
-        principal_types: principal_types
-            .map(|node| node.node)
-            .ok_or_else(|| ToJsonSchemaError::no_principal(&name, name_loc.clone()))?,
+        any_resource: resource_types.is_none(),
+        resource_types: resource_types.unwrap_or_default(),
+        any_principal: principal_types.is_none(),
+        principal_types: principal_types.unwrap_or_default(),
         context: context.map(|c| c.node).unwrap_or_default(),
     })
 }
@@ -612,8 +620,8 @@ fn update_namespace_record(
     match map.entry(name.clone()) {
         Entry::Occupied(entry) => Err(ToJsonSchemaError::duplicate_namespace(
             &name.map_or("".into(), |n| n.to_smolstr()),
-            record.loc,
             entry.get().loc.clone(),
+            record.loc,
         )
         .into()),
         Entry::Vacant(entry) => {
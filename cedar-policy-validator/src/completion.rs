@@ -0,0 +1,535 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Completing partial policy text at a cursor position, using the schema to
+//! find the entity types, actions, and attributes which may come next
+
+use std::collections::BTreeMap;
+
+use cedar_policy_core::ast::{CallStyle, EntityUID, Expr, PolicyID, Template};
+use cedar_policy_core::entities::SchemaType;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_core::parser::parse_policy_or_template;
+
+use crate::typecheck::Typechecker;
+use crate::types::{EntityRecordKind, RequestEnv, Type};
+use crate::{ValidationMode, Validator};
+
+/// What a [`Completion`] inserts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CompletionKind {
+    /// One of the variables `principal`, `action`, `resource`, and `context`
+    Variable,
+    /// An attribute of the expression before the `.`
+    Attribute,
+    /// A method which can be called on the expression before the `.`
+    Method,
+    /// An extension function called in function style, e.g., `ip`
+    Function,
+    /// An entity type, or the rest of its name after a namespace
+    EntityType,
+    /// An action, or its entity id after the action entity type
+    Action,
+}
+
+/// One possible continuation of partial policy text
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Completion {
+    /// The text to insert in place of the partial word before the cursor
+    pub label: String,
+    /// What the text refers to
+    pub kind: CompletionKind,
+    /// Extra information to show with the completion, e.g., the type of an
+    /// attribute
+    pub detail: Option<String>,
+}
+
+/// The continuations of partial policy text at a cursor position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completions {
+    /// Byte offset where the partial word before the cursor starts. A
+    /// completion replaces the text from here up to the cursor.
+    pub start: usize,
+    /// The possible continuations, sorted by kind and label
+    pub items: Vec<Completion>,
+}
+
+impl Validator {
+    /// Find the continuations of the partial policy text `src` at the byte
+    /// offset `cursor` which are valid for the schema: entity types and
+    /// actions, attributes and methods of the expression before a `.`,
+    /// variables, and extension functions. Only continuations starting with
+    /// the partial word before the cursor are returned.
+    ///
+    /// `src` need not parse. Attributes are found by typechecking the
+    /// expression before the `.` in every request environment admitted by the
+    /// scope of the policy the cursor is in, or in every request environment
+    /// if that scope is not complete yet.
+    pub fn completions(&self, src: &str, cursor: usize, mode: ValidationMode) -> Completions {
+        let prefix = src.get(..cursor).unwrap_or(src);
+
+        // Inside the entity id of an action, e.g., `Action::"vi`
+        if let Some((path, partial)) = action_id_prefix(prefix) {
+            let items = self
+                .actions_of_type(path)
+                .map(|action| Completion {
+                    label: action.eid().escaped().to_string(),
+                    kind: CompletionKind::Action,
+                    detail: None,
+                })
+                .collect();
+            return Completions::new(prefix.len() - partial.len(), partial, items);
+        }
+
+        let word_len = prefix
+            .chars()
+            .rev()
+            .take_while(|c| is_ident_char(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        let start = prefix.len() - word_len;
+        let (before, word) = prefix.split_at(start);
+
+        let items = if let Some(path) = before.strip_suffix("::") {
+            self.path_completions(trailing_path(path))
+        } else if let Some(receiver) = before.strip_suffix('.') {
+            self.member_completions(src, trailing_receiver(receiver), start, mode)
+        } else {
+            let before = before.trim_end();
+            if ends_with_word(before, "is") {
+                self.entity_type_completions().collect()
+            } else if ["==", "in"]
+                .iter()
+                .filter_map(|op| before.strip_suffix(op))
+                .any(|lhs| ends_with_word(lhs.trim_end(), "action"))
+            {
+                self.action_completions().collect()
+            } else {
+                ["principal", "action", "resource", "context"]
+                    .into_iter()
+                    .map(|var| Completion {
+                        label: var.to_string(),
+                        kind: CompletionKind::Variable,
+                        detail: None,
+                    })
+                    .chain(
+                        Extensions::all_available()
+                            .all_funcs()
+                            .filter(|f| f.style() == CallStyle::FunctionStyle)
+                            .map(|f| Completion {
+                                label: f.name().to_string(),
+                                kind: CompletionKind::Function,
+                                detail: None,
+                            }),
+                    )
+                    .chain(self.entity_type_completions())
+                    .collect()
+            }
+        };
+        Completions::new(start, word, items)
+    }
+
+    fn entity_type_completions(&self) -> impl Iterator<Item = Completion> + '_ {
+        self.schema.entity_types().map(|(name, _)| Completion {
+            label: name.to_string(),
+            kind: CompletionKind::EntityType,
+            detail: None,
+        })
+    }
+
+    fn action_completions(&self) -> impl Iterator<Item = Completion> + '_ {
+        self.schema.actions().map(|action| Completion {
+            label: action.to_string(),
+            kind: CompletionKind::Action,
+            detail: None,
+        })
+    }
+
+    fn actions_of_type<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a EntityUID> {
+        self.schema
+            .actions()
+            .filter(move |action| action.entity_type().to_string() == path)
+    }
+
+    /// Completions after `path::`: the rest of the names of entity types in
+    /// the namespace `path`, or the quoted ids of actions of type `path`
+    fn path_completions(&self, path: &str) -> Vec<Completion> {
+        let namespace = format!("{path}::");
+        self.schema
+            .entity_types()
+            .filter_map(|(name, _)| {
+                Some(Completion {
+                    label: name.to_string().strip_prefix(&namespace)?.to_string(),
+                    kind: CompletionKind::EntityType,
+                    detail: None,
+                })
+            })
+            .chain(self.actions_of_type(path).map(|action| Completion {
+                label: format!("\"{}\"", action.eid().escaped()),
+                kind: CompletionKind::Action,
+                detail: None,
+            }))
+            .collect()
+    }
+
+    /// Completions after `receiver.`: the attributes and methods of the type
+    /// of `receiver` in any request environment
+    fn member_completions(
+        &self,
+        src: &str,
+        receiver: &str,
+        cursor: usize,
+        mode: ValidationMode,
+    ) -> Vec<Completion> {
+        let Ok(receiver) = receiver.parse::<Expr>() else {
+            return Vec::new();
+        };
+        let scope = enclosing_scope(src, cursor);
+        let typechecker = Typechecker::new(&self.schema, mode, PolicyID::from_string("completion"));
+        let mut items = Vec::new();
+        for env in typechecker.unlinked_request_envs() {
+            if !scope
+                .as_ref()
+                .map_or(true, |t| self.scope_admits_request_env(t, &env))
+            {
+                continue;
+            }
+            if let Some(ty) = typechecker.infer_standalone_expr_type(&env, &receiver) {
+                items.extend(self.attributes_of(&ty));
+                items.extend(self.methods_of(&ty));
+            }
+        }
+        items
+    }
+
+    fn scope_admits_request_env(&self, t: &Template, env: &RequestEnv<'_>) -> bool {
+        match env {
+            RequestEnv::DeclaredAction {
+                principal,
+                action,
+                resource,
+                ..
+            } => {
+                self.get_principals_satisfying_constraint(t.principal_constraint())
+                    .any(|ty| ty == *principal)
+                    && self
+                        .get_actions_satisfying_constraint(t.action_constraint())
+                        .any(|a| a == *action)
+                    && self
+                        .get_resources_satisfying_constraint(t.resource_constraint())
+                        .any(|ty| ty == *resource)
+            }
+            RequestEnv::UndeclaredAction => true,
+        }
+    }
+
+    fn attributes_of(&self, ty: &Type) -> Vec<Completion> {
+        ty.all_attributes(&self.schema)
+            .into_iter()
+            .map(|attr| {
+                let detail = Type::lookup_attribute_type(&self.schema, ty, &attr).map(|attr_ty| {
+                    if attr_ty.is_required {
+                        attr_ty.attr_type.to_string()
+                    } else {
+                        format!("{} (optional)", attr_ty.attr_type)
+                    }
+                });
+                Completion {
+                    label: attr.to_string(),
+                    kind: CompletionKind::Attribute,
+                    detail,
+                }
+            })
+            .collect()
+    }
+
+    fn methods_of(&self, ty: &Type) -> Vec<Completion> {
+        let method = |name: &str| Completion {
+            label: name.to_string(),
+            kind: CompletionKind::Method,
+            detail: None,
+        };
+        match ty {
+            Type::Set { .. } => ["contains", "containsAll", "containsAny", "isEmpty"]
+                .into_iter()
+                .map(method)
+                .collect(),
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub))
+                if lub.iter().any(|name| {
+                    self.schema
+                        .get_entity_type(name)
+                        .is_some_and(|ety| ety.tag_type().is_some())
+                }) =>
+            {
+                ["getTag", "hasTag"].into_iter().map(method).collect()
+            }
+            Type::ExtensionType { name } => {
+                let receiver = SchemaType::Extension { name: name.clone() };
+                Extensions::all_available()
+                    .all_funcs()
+                    .filter(|f| {
+                        f.style() == CallStyle::MethodStyle
+                            && f.arg_types().first() == Some(&receiver)
+                    })
+                    .map(|f| method(&f.name().to_string()))
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Completions {
+    /// Keep the `items` starting with `word`, sorted and without duplicates
+    fn new(start: usize, word: &str, items: Vec<Completion>) -> Self {
+        // Attributes may have different types in different request
+        // environments, so keep only the first detail found for each label
+        let mut by_label = BTreeMap::new();
+        for item in items
+            .into_iter()
+            .filter(|item| item.label.starts_with(word))
+        {
+            by_label
+                .entry((item.kind, item.label.clone()))
+                .or_insert(item);
+        }
+        Self {
+            start,
+            items: by_label.into_values().collect(),
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Does `s` end with the whole word `word`?
+fn ends_with_word(s: &str, word: &str) -> bool {
+    s.strip_suffix(word)
+        .is_some_and(|rest| !rest.ends_with(is_ident_char))
+}
+
+/// The `::`-separated path at the end of `s`, e.g., `NS::Action` in
+/// `action == NS::Action`
+fn trailing_path(s: &str) -> &str {
+    let len = s
+        .chars()
+        .rev()
+        .take_while(|c| is_ident_char(*c) || *c == ':')
+        .map(char::len_utf8)
+        .sum::<usize>();
+    s[s.len() - len..].trim_start_matches(':')
+}
+
+/// The chain of attribute accesses at the end of `s`, e.g.,
+/// `principal.manager` in `principal.manager.`
+fn trailing_receiver(s: &str) -> &str {
+    let len = s
+        .chars()
+        .rev()
+        .take_while(|c| is_ident_char(*c) || *c == '.')
+        .map(char::len_utf8)
+        .sum::<usize>();
+    &s[s.len() - len..]
+}
+
+/// If `prefix` ends inside the quoted id of an entity, e.g., `Action::"vi`,
+/// the path of its entity type and the part of the id typed so far
+fn action_id_prefix(prefix: &str) -> Option<(&str, &str)> {
+    let quote = prefix.rfind('"')?;
+    let (before, partial) = (&prefix[..quote], &prefix[quote + 1..]);
+    // The quote must open a string, so there must be an even number of
+    // quotes before it
+    if before.matches('"').count() % 2 != 0 {
+        return None;
+    }
+    let path = before.strip_suffix("::")?;
+    Some((trailing_path(path), partial))
+}
+
+/// The scope of the policy `cursor` is in, if the whole scope precedes the
+/// cursor and parses
+fn enclosing_scope(src: &str, cursor: usize) -> Option<Template> {
+    let prefix = src.get(..cursor)?;
+    let head_start = ["permit", "forbid"]
+        .iter()
+        .filter_map(|effect| prefix.rfind(effect))
+        .max()?;
+    let head_end = head_start + prefix[head_start..].find(')')?;
+    parse_policy_or_template(None, &format!("{};", &prefix[head_start..=head_end])).ok()
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                namespace App {
+                    entity Team;
+                    entity User in Team = { name: String, manager?: User, emails: Set<String> };
+                    entity Device = { ip: ipaddr } tags String;
+                    action view, "edit doc" appliesTo { principal: User, resource: Device, context: { mfa: Bool } };
+                    action reboot appliesTo { principal: Team, resource: Device };
+                }
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    /// Complete `src` at the position of `|`, which is removed
+    #[track_caller]
+    fn complete(src: &str) -> Vec<(CompletionKind, String)> {
+        let cursor = src.find('|').unwrap();
+        let src = src.replacen('|', "", 1);
+        validator()
+            .completions(&src, cursor, ValidationMode::Strict)
+            .items
+            .into_iter()
+            .map(|item| (item.kind, item.label))
+            .collect()
+    }
+
+    fn labels(kind: CompletionKind, labels: &[&str]) -> Vec<(CompletionKind, String)> {
+        labels.iter().map(|l| (kind, l.to_string())).collect()
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(
+            complete(
+                r#"permit(principal, action == App::Action::"view", resource) when { principal.| };"#
+            ),
+            labels(CompletionKind::Attribute, &["emails", "manager", "name"])
+        );
+        assert_eq!(
+            complete(r#"permit(principal, action, resource) when { principal.manager.na| };"#),
+            labels(CompletionKind::Attribute, &["name"])
+        );
+        assert_eq!(
+            complete(
+                r#"permit(principal, action == App::Action::"view", resource) when { context.| };"#
+            ),
+            labels(CompletionKind::Attribute, &["mfa"])
+        );
+    }
+
+    #[test]
+    fn attributes_narrowed_by_scope() {
+        // `principal` is a `Team` for `reboot`, which has no attributes
+        assert_eq!(
+            complete(
+                r#"permit(principal, action == App::Action::"reboot", resource) when { principal.| };"#
+            ),
+            vec![]
+        );
+        // Without a complete scope, every request environment is considered
+        assert_eq!(
+            complete(r#"permit(principal, action, resource) when { principal.| "#),
+            labels(CompletionKind::Attribute, &["emails", "manager", "name"])
+        );
+    }
+
+    #[test]
+    fn methods() {
+        assert_eq!(
+            complete(r#"permit(principal, action, resource) when { principal.emails.| };"#),
+            labels(
+                CompletionKind::Method,
+                &["contains", "containsAll", "containsAny", "isEmpty"]
+            )
+        );
+        let items = complete(r#"permit(principal, action, resource) when { resource.| };"#);
+        assert!(items.contains(&(CompletionKind::Attribute, "ip".to_string())));
+        assert!(items.contains(&(CompletionKind::Method, "getTag".to_string())));
+        let items = complete(r#"permit(principal, action, resource) when { resource.ip.isI| };"#);
+        assert_eq!(
+            items,
+            labels(CompletionKind::Method, &["isInRange", "isIpv4", "isIpv6"])
+        );
+    }
+
+    #[test]
+    fn entity_types_and_actions() {
+        assert_eq!(
+            complete(r#"permit(principal is App::U|"#),
+            labels(CompletionKind::EntityType, &["User"])
+        );
+        assert_eq!(
+            complete(r#"permit(principal is |"#),
+            labels(
+                CompletionKind::EntityType,
+                &["App::Device", "App::Team", "App::User"]
+            )
+        );
+        assert_eq!(
+            complete(r#"permit(principal, action == |"#),
+            labels(
+                CompletionKind::Action,
+                &[
+                    r#"App::Action::"edit doc""#,
+                    r#"App::Action::"reboot""#,
+                    r#"App::Action::"view""#
+                ]
+            )
+        );
+        assert_eq!(
+            complete(r#"permit(principal, action in [App::Action::|"#),
+            labels(
+                CompletionKind::Action,
+                &[r#""edit doc""#, r#""reboot""#, r#""view""#]
+            )
+        );
+        assert_eq!(
+            complete(r#"permit(principal, action == App::Action::"r|"#),
+            labels(CompletionKind::Action, &["reboot"])
+        );
+    }
+
+    #[test]
+    fn expression_start() {
+        let items = complete(r#"permit(principal, action, resource) when { |"#);
+        for expected in [
+            (CompletionKind::Variable, "principal"),
+            (CompletionKind::Function, "ip"),
+            (CompletionKind::Function, "decimal"),
+            (CompletionKind::EntityType, "App::User"),
+        ] {
+            assert!(items.contains(&(expected.0, expected.1.to_string())));
+        }
+        assert!(!items.iter().any(|(_, label)| label == "isIpv4"));
+        assert_eq!(
+            complete(r#"permit(principal, action, resource) when { res|"#),
+            labels(CompletionKind::Variable, &["resource"])
+        );
+    }
+
+    #[test]
+    fn replaced_range() {
+        let src = r#"permit(principal, action, resource) when { principal.na"#;
+        let completions = validator().completions(src, src.len(), ValidationMode::Strict);
+        assert_eq!(completions.start, src.len() - 2);
+    }
+}
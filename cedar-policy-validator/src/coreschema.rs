@@ -13,9 +13,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+use crate::types::{EntityRecordKind, Type};
 use crate::{ValidatorEntityType, ValidatorSchema};
 use cedar_policy_core::extensions::{ExtensionFunctionLookupError, Extensions};
-use cedar_policy_core::{ast, entities};
+use cedar_policy_core::{ast, authorizer, entities};
 use miette::Diagnostic;
 use smol_str::SmolStr;
 use std::collections::hash_map::Values;
@@ -158,6 +159,114 @@ impl entities::EntityTypeDescription for EntityTypeDescription {
     }
 }
 
+impl ValidatorSchema {
+    /// Check that `principal` is of an entity type declared in the schema,
+    /// and, if `action` is given and declared in the schema, that it is of a
+    /// valid principal type for `action`.
+    pub fn validate_request_principal(
+        &self,
+        principal: &ast::EntityUID,
+        action: Option<&ast::EntityUID>,
+    ) -> std::result::Result<(), RequestValidationError> {
+        if self.get_entity_type(principal.entity_type()).is_none() {
+            return Err(request_validation_errors::UndeclaredPrincipalTypeError {
+                principal_ty: principal.entity_type().clone(),
+            }
+            .into());
+        }
+        let Some((action, validator_action_id)) =
+            action.and_then(|action| Some((action, self.get_action_id(action)?)))
+        else {
+            return Ok(());
+        };
+        if !validator_action_id.is_applicable_principal_type(principal.entity_type()) {
+            return Err(request_validation_errors::InvalidPrincipalTypeError {
+                principal_ty: principal.entity_type().clone(),
+                action: Arc::new(action.clone()),
+                valid_principal_tys: validator_action_id
+                    .applies_to_principals()
+                    .cloned()
+                    .collect(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Check that `resource` is of an entity type declared in the schema, and,
+    /// if `action` is given and declared in the schema, that it is of a valid
+    /// resource type for `action`.
+    pub fn validate_request_resource(
+        &self,
+        resource: &ast::EntityUID,
+        action: Option<&ast::EntityUID>,
+    ) -> std::result::Result<(), RequestValidationError> {
+        if self.get_entity_type(resource.entity_type()).is_none() {
+            return Err(request_validation_errors::UndeclaredResourceTypeError {
+                resource_ty: resource.entity_type().clone(),
+            }
+            .into());
+        }
+        let Some((action, validator_action_id)) =
+            action.and_then(|action| Some((action, self.get_action_id(action)?)))
+        else {
+            return Ok(());
+        };
+        if !validator_action_id.is_applicable_resource_type(resource.entity_type()) {
+            return Err(request_validation_errors::InvalidResourceTypeError {
+                resource_ty: resource.entity_type().clone(),
+                action: Arc::new(action.clone()),
+                valid_resource_tys: validator_action_id
+                    .applies_to_resources()
+                    .cloned()
+                    .collect(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Check that `action` is declared in the schema.
+    pub fn validate_request_action(
+        &self,
+        action: &ast::EntityUID,
+    ) -> std::result::Result<(), RequestValidationError> {
+        match self.get_action_id(action) {
+            Some(_) => Ok(()),
+            None => Err(request_validation_errors::UndeclaredActionError {
+                action: Arc::new(action.clone()),
+            }
+            .into()),
+        }
+    }
+
+    /// Check that `context` has the shape the schema specifies for `action`.
+    /// Succeeds if `action` is not declared in the schema, as there is then
+    /// nothing to check `context` against.
+    pub fn validate_request_context(
+        &self,
+        context: &ast::Context,
+        action: &ast::EntityUID,
+        extensions: &Extensions<'_>,
+    ) -> std::result::Result<(), RequestValidationError> {
+        let Some(validator_action_id) = self.get_action_id(action) else {
+            return Ok(());
+        };
+        let expected_context_ty = validator_action_id.context_type();
+        if !expected_context_ty
+            .typecheck_partial_value(&context.clone().into(), extensions)
+            .map_err(RequestValidationError::TypeOfContext)?
+        {
+            return Err(request_validation_errors::InvalidContextError {
+                context: context.clone(),
+                action: Arc::new(action.clone()),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
 impl ast::RequestSchema for ValidatorSchema {
     type Error = RequestValidationError;
     fn validate_request(
@@ -172,71 +281,27 @@ impl ast::RequestSchema for ValidatorSchema {
             euid: principal, ..
         } = request.principal()
         {
-            if self.get_entity_type(principal.entity_type()).is_none() {
-                return Err(request_validation_errors::UndeclaredPrincipalTypeError {
-                    principal_ty: principal.entity_type().clone(),
-                }
-                .into());
-            }
+            self.validate_request_principal(principal, None)?;
         }
         if let EntityUIDEntry::Known { euid: resource, .. } = request.resource() {
-            if self.get_entity_type(resource.entity_type()).is_none() {
-                return Err(request_validation_errors::UndeclaredResourceTypeError {
-                    resource_ty: resource.entity_type().clone(),
-                }
-                .into());
-            }
+            self.validate_request_resource(resource, None)?;
         }
 
         // the remaining checks require knowing about the action.
         match request.action() {
             EntityUIDEntry::Known { euid: action, .. } => {
-                let validator_action_id = self.get_action_id(action).ok_or_else(|| {
-                    request_validation_errors::UndeclaredActionError {
-                        action: Arc::clone(action),
-                    }
-                })?;
+                self.validate_request_action(action)?;
                 if let EntityUIDEntry::Known {
                     euid: principal, ..
                 } = request.principal()
                 {
-                    if !validator_action_id.is_applicable_principal_type(principal.entity_type()) {
-                        return Err(request_validation_errors::InvalidPrincipalTypeError {
-                            principal_ty: principal.entity_type().clone(),
-                            action: Arc::clone(action),
-                            valid_principal_tys: validator_action_id
-                                .applies_to_principals()
-                                .cloned()
-                                .collect(),
-                        }
-                        .into());
-                    }
+                    self.validate_request_principal(principal, Some(action))?;
                 }
                 if let EntityUIDEntry::Known { euid: resource, .. } = request.resource() {
-                    if !validator_action_id.is_applicable_resource_type(resource.entity_type()) {
-                        return Err(request_validation_errors::InvalidResourceTypeError {
-                            resource_ty: resource.entity_type().clone(),
-                            action: Arc::clone(action),
-                            valid_resource_tys: validator_action_id
-                                .applies_to_resources()
-                                .cloned()
-                                .collect(),
-                        }
-                        .into());
-                    }
+                    self.validate_request_resource(resource, Some(action))?;
                 }
                 if let Some(context) = request.context() {
-                    let expected_context_ty = validator_action_id.context_type();
-                    if !expected_context_ty
-                        .typecheck_partial_value(&context.clone().into(), extensions)
-                        .map_err(RequestValidationError::TypeOfContext)?
-                    {
-                        return Err(request_validation_errors::InvalidContextError {
-                            context: context.clone(),
-                            action: Arc::clone(action),
-                        }
-                        .into());
-                    }
+                    self.validate_request_context(context, action, extensions)?;
                 }
             }
             EntityUIDEntry::Unknown { .. } => {
@@ -252,6 +317,35 @@ impl ast::RequestSchema for ValidatorSchema {
     }
 }
 
+impl authorizer::ContextSchema for ValidatorSchema {
+    fn context_attr_conforms(
+        &self,
+        action: &ast::EntityUID,
+        attr: &str,
+        value: &ast::PartialValue,
+        extensions: &Extensions<'_>,
+    ) -> bool {
+        // As for `validate_request_context`, there is nothing to check the
+        // value against if the action is not declared
+        let Some(context_ty) = self.context_type(action) else {
+            return true;
+        };
+        match context_ty {
+            Type::EntityOrRecord(EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            }) => match attrs.attrs.get(attr) {
+                Some(attr_ty) => attr_ty
+                    .attr_type
+                    .typecheck_partial_value(value, extensions)
+                    .unwrap_or(false),
+                None => open_attributes.is_open(),
+            },
+            _ => false,
+        }
+    }
+}
+
 impl ast::RequestSchema for CoreSchema<'_> {
     type Error = RequestValidationError;
     fn validate_request(
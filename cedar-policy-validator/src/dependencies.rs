@@ -0,0 +1,412 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Building the graph of the schema elements and extension functions each
+//! policy depends on
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Write};
+
+use cedar_policy_core::ast::{
+    EntityType, EntityUID, ExprKind, Literal, Name, PolicyID, PolicySet, Var,
+};
+use serde_json::json;
+use smol_str::SmolStr;
+
+use crate::typecheck::Typechecker;
+use crate::types::{EntityRecordKind, RequestEnv, Type};
+use crate::{ValidationMode, Validator};
+
+/// Something a policy depends on, as recorded in a [`DependencyGraph`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dependency {
+    /// An entity type, referenced in an entity literal or an `is` test
+    EntityType(EntityType),
+    /// An action, referenced in an entity literal
+    Action(EntityUID),
+    /// A top-level attribute of an entity type, read with `.`, `[]`, or `has`
+    Attribute {
+        /// The entity type declaring the attribute
+        entity_type: EntityType,
+        /// The attribute
+        attr: SmolStr,
+    },
+    /// A top-level attribute of the context of an action, read with `.`,
+    /// `[]`, or `has`
+    ContextAttribute {
+        /// The action
+        action: EntityUID,
+        /// The attribute
+        attr: SmolStr,
+    },
+    /// An extension function, called as a function or as a method
+    ExtensionFunction(Name),
+}
+
+impl Dependency {
+    /// The kind of the dependency, as used in the JSON and DOT renderings of
+    /// a [`DependencyGraph`]
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::EntityType(_) => "entityType",
+            Self::Action(_) => "action",
+            Self::Attribute { .. } => "attribute",
+            Self::ContextAttribute { .. } => "contextAttribute",
+            Self::ExtensionFunction(_) => "extensionFunction",
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::EntityType(ty) => json!({ "kind": self.kind(), "entityType": ty.to_string() }),
+            Self::Action(action) => json!({ "kind": self.kind(), "action": action.to_string() }),
+            Self::Attribute { entity_type, attr } => json!({
+                "kind": self.kind(),
+                "entityType": entity_type.to_string(),
+                "attr": attr,
+            }),
+            Self::ContextAttribute { action, attr } => json!({
+                "kind": self.kind(),
+                "action": action.to_string(),
+                "attr": attr,
+            }),
+            Self::ExtensionFunction(name) => {
+                json!({ "kind": self.kind(), "function": name.to_string() })
+            }
+        }
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EntityType(ty) => write!(f, "{ty}"),
+            Self::Action(action) => write!(f, "{action}"),
+            Self::Attribute { entity_type, attr } => write!(f, "{entity_type}.{attr}"),
+            Self::ContextAttribute { action, attr } => write!(f, "{action}.context.{attr}"),
+            Self::ExtensionFunction(name) => write!(f, "{name}()"),
+        }
+    }
+}
+
+/// The schema elements and extension functions each policy or template of a
+/// policy set depends on, as computed by [`Validator::dependency_graph`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    dependencies: BTreeMap<PolicyID, BTreeSet<Dependency>>,
+}
+
+impl DependencyGraph {
+    /// The ids of the policies and templates in the graph, in sorted order
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyID> {
+        self.dependencies.keys()
+    }
+
+    /// The dependencies of the policy or template `id`, in sorted order
+    pub fn dependencies(&self, id: &PolicyID) -> impl Iterator<Item = &Dependency> {
+        self.dependencies.get(id).into_iter().flatten()
+    }
+
+    /// The ids of the policies and templates depending on `dependency`, in
+    /// sorted order
+    pub fn dependents<'a>(
+        &'a self,
+        dependency: &'a Dependency,
+    ) -> impl Iterator<Item = &'a PolicyID> {
+        self.dependencies
+            .iter()
+            .filter(|(_, dependencies)| dependencies.contains(dependency))
+            .map(|(id, _)| id)
+    }
+
+    /// Render the graph as JSON, as an object mapping every policy id to the
+    /// list of its dependencies. Each dependency is an object with a `kind`
+    /// (`entityType`, `action`, `attribute`, `contextAttribute`, or
+    /// `extensionFunction`) and the fields identifying it.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.dependencies
+                .iter()
+                .map(|(id, dependencies)| {
+                    (
+                        id.to_string(),
+                        dependencies.iter().map(Dependency::to_json).collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Render the graph in the DOT language of Graphviz, with an edge from
+    /// every policy to each of its dependencies
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        // PANIC SAFETY: `std::fmt::Write` does not return errors when writing to a `String`
+        #[allow(clippy::expect_used)]
+        {
+            for id in self.policies() {
+                writeln!(
+                    dot,
+                    "    {} [shape=box, label={}];",
+                    dot_id("policy", id),
+                    dot_string(id)
+                )
+                .expect("writing to a `String` should not fail");
+            }
+            let dependencies = self
+                .dependencies
+                .values()
+                .flatten()
+                .collect::<BTreeSet<_>>();
+            for dependency in &dependencies {
+                writeln!(
+                    dot,
+                    "    {} [shape=ellipse, label={}];",
+                    dot_id(dependency.kind(), dependency),
+                    dot_string(dependency)
+                )
+                .expect("writing to a `String` should not fail");
+            }
+            for (id, dependencies) in &self.dependencies {
+                for dependency in dependencies {
+                    writeln!(
+                        dot,
+                        "    {} -> {};",
+                        dot_id("policy", id),
+                        dot_id(dependency.kind(), dependency)
+                    )
+                    .expect("writing to a `String` should not fail");
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// A DOT node id for `node`, prefixed with its `kind` so policies and
+/// dependencies with the same text get different nodes
+fn dot_id(kind: &str, node: impl Display) -> String {
+    dot_string(format!("{kind}:{node}"))
+}
+
+/// `s` as a quoted DOT string
+fn dot_string(s: impl Display) -> String {
+    format!(
+        "\"{}\"",
+        s.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+impl Validator {
+    /// Build the graph of the schema elements and extension functions each
+    /// policy and template in `policies` depends on.
+    ///
+    /// Entity types and actions are dependencies of the policies mentioning
+    /// them in entity literals and `is` tests. Attributes are dependencies of
+    /// the policies reading them with `.`, `[]`, or `has`, using the types
+    /// the validator infers for the expressions they are read from: an
+    /// attribute read from an expression which may be of several entity
+    /// types is a dependency on the attribute of each, and one read from an
+    /// expression of unknown type is a dependency on the attribute of every
+    /// entity type declaring it. Attributes read from `context` are
+    /// dependencies on the context attribute of every action the policy
+    /// applies to.
+    pub fn dependency_graph(&self, policies: &PolicySet) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        for t in policies.all_templates() {
+            let dependencies = graph.dependencies.entry(t.id().clone()).or_default();
+            for e in t.condition().subexpressions() {
+                match e.expr_kind() {
+                    ExprKind::Lit(Literal::EntityUID(uid)) if uid.is_action() => {
+                        dependencies.insert(Dependency::Action(uid.as_ref().clone()));
+                    }
+                    ExprKind::Lit(Literal::EntityUID(uid)) => {
+                        dependencies.insert(Dependency::EntityType(uid.entity_type().clone()));
+                    }
+                    ExprKind::Is { entity_type, .. } => {
+                        dependencies.insert(Dependency::EntityType(entity_type.clone()));
+                    }
+                    ExprKind::ExtensionFunctionApp { fn_name, .. } => {
+                        dependencies.insert(Dependency::ExtensionFunction(fn_name.clone()));
+                    }
+                    _ => {}
+                }
+            }
+
+            let typechecker =
+                Typechecker::new(&self.schema, ValidationMode::Permissive, t.id().clone());
+            for (env, e) in typechecker.typed_condition_by_request_env(t) {
+                for e in e.subexpressions() {
+                    let (ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr }) =
+                        e.expr_kind()
+                    else {
+                        continue;
+                    };
+                    match (expr.expr_kind(), expr.data()) {
+                        (ExprKind::Var(Var::Context), _) => {
+                            if let RequestEnv::DeclaredAction { action, .. } = env {
+                                dependencies.insert(Dependency::ContextAttribute {
+                                    action: action.clone(),
+                                    attr: attr.clone(),
+                                });
+                            }
+                        }
+                        (_, Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub)))) => {
+                            dependencies.extend(lub.iter().map(|ty| Dependency::Attribute {
+                                entity_type: ty.clone(),
+                                attr: attr.clone(),
+                            }));
+                        }
+                        (_, Some(Type::EntityOrRecord(EntityRecordKind::AnyEntity)) | None) => {
+                            dependencies.extend(
+                                self.schema
+                                    .entity_types()
+                                    .filter(|(_, ety)| ety.attr(attr).is_some())
+                                    .map(|(ty, _)| Dependency::Attribute {
+                                        entity_type: ty.clone(),
+                                        attr: attr.clone(),
+                                    }),
+                            );
+                        }
+                        (_, Some(_)) => {}
+                    }
+                }
+            }
+        }
+        graph
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::parser::parse_policyset;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                entity Team = { name: String };
+                entity User in Team = { name: String, team: Team };
+                entity Doc = { owner: User };
+                action view appliesTo {
+                    principal: [User, Team],
+                    resource: Doc,
+                    context: { ip: ipaddr }
+                };
+                action edit appliesTo { principal: User, resource: Doc };
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[track_caller]
+    fn dependencies(graph: &DependencyGraph, id: &str) -> Vec<String> {
+        graph
+            .dependencies(&PolicyID::from_string(id))
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_of_policies() {
+        let pset = parse_policyset(
+            r#"
+            permit(principal is User, action == Action::"view", resource)
+            when { context.ip.isInRange(ip("10.0.0.0/8")) && principal.name == "" };
+            permit(principal, action, resource == Doc::"d")
+            when { resource.owner.team has name };
+            "#,
+        )
+        .unwrap();
+        let graph = validator().dependency_graph(&pset);
+        assert_eq!(
+            dependencies(&graph, "policy0"),
+            vec![
+                "User",
+                r#"Action::"view""#,
+                "User.name",
+                r#"Action::"view".context.ip"#,
+                "ip()",
+                "isInRange()",
+            ]
+        );
+        assert_eq!(
+            dependencies(&graph, "policy1"),
+            vec!["Doc", "Doc.owner", "Team.name", "User.team"]
+        );
+        assert_eq!(
+            graph
+                .dependents(&Dependency::EntityType("Doc".parse().unwrap()))
+                .collect::<Vec<_>>(),
+            vec![&PolicyID::from_string("policy1")]
+        );
+    }
+
+    #[test]
+    fn attribute_of_several_types() {
+        // `principal` may be a `User` or a `Team` for `view`, and is of
+        // unknown type where the action is unknown
+        let pset = parse_policyset(
+            r#"permit(principal, action, resource) when { principal.name == "" };"#,
+        )
+        .unwrap();
+        let graph = validator().dependency_graph(&pset);
+        assert_eq!(
+            dependencies(&graph, "policy0"),
+            vec!["Team.name", "User.name"]
+        );
+    }
+
+    #[test]
+    fn renderings() {
+        let pset = parse_policyset(
+            r#"permit(principal, action in [Action::"view", Action::"say \"hi\""], resource) when { resource.owner == principal };"#,
+        )
+        .unwrap();
+        let graph = validator().dependency_graph(&pset);
+        assert_eq!(
+            graph.to_json(),
+            json!({
+                "policy0": [
+                    { "kind": "action", "action": r#"Action::"say \"hi\"""# },
+                    { "kind": "action", "action": r#"Action::"view""# },
+                    { "kind": "attribute", "entityType": "Doc", "attr": "owner" },
+                ]
+            })
+        );
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph dependencies {
+    "policy:policy0" [shape=box, label="policy0"];
+    "action:Action::\"say \\\"hi\\\"\"" [shape=ellipse, label="Action::\"say \\\"hi\\\"\""];
+    "action:Action::\"view\"" [shape=ellipse, label="Action::\"view\""];
+    "attribute:Doc.owner" [shape=ellipse, label="Doc.owner"];
+    "policy:policy0" -> "action:Action::\"say \\\"hi\\\"\"";
+    "policy:policy0" -> "action:Action::\"view\"";
+    "policy:policy0" -> "attribute:Doc.owner";
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,225 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checks for policies referencing entity types, attributes, or actions
+//! annotated as deprecated (`@deprecated`) in the schema
+
+use super::*;
+use cedar_policy_core::ast::{Expr, ExprKind, Literal};
+use cedar_policy_core::parser::Loc;
+use schema::Deprecation;
+use typecheck::PolicyCheck;
+use types::{EntityRecordKind, Type};
+use validation_warnings::DeprecatedSchemaElement;
+
+impl Validator {
+    /// Check `t` for references to deprecated schema elements. Entity types
+    /// and actions are found syntactically; attribute accesses are only found
+    /// in policies which typecheck, since we otherwise can't tell which
+    /// entity types they refer to.
+    pub(crate) fn deprecation_checks(
+        &self,
+        t: &Template,
+        mode: ValidationMode,
+    ) -> impl Iterator<Item = ValidationWarning> {
+        let mut warnings = HashSet::new();
+        let has_deprecated_types = self
+            .schema
+            .entity_types()
+            .any(|(_, ety)| ety.deprecation.is_some() || !ety.deprecated_attributes.is_empty());
+        let has_deprecated_actions = self.schema.actions().any(|euid| {
+            self.schema
+                .get_action_id(euid)
+                .is_some_and(|action| action.deprecation.is_some())
+        });
+        if !has_deprecated_types && !has_deprecated_actions {
+            return warnings.into_iter();
+        }
+        let mut warn = |loc: Option<&Loc>, element, deprecation: &Deprecation| {
+            warnings.insert(ValidationWarning::deprecated_schema_element_use(
+                loc.or_else(|| t.loc()).cloned(),
+                t.id().clone(),
+                element,
+                deprecation.replacement.clone(),
+            ));
+        };
+
+        let condition = t.condition();
+        for sub in condition.subexpressions() {
+            let ety = match sub.expr_kind() {
+                ExprKind::Lit(Literal::EntityUID(euid)) => {
+                    if let Some(deprecation) = self
+                        .schema
+                        .get_action_id(euid)
+                        .and_then(|action| action.deprecation.as_ref())
+                    {
+                        warn(
+                            sub.source_loc(),
+                            DeprecatedSchemaElement::Action(euid.as_ref().clone()),
+                            deprecation,
+                        );
+                    }
+                    euid.entity_type()
+                }
+                ExprKind::Is { entity_type, .. } => entity_type,
+                _ => continue,
+            };
+            if let Some(deprecation) = self
+                .schema
+                .get_entity_type(ety)
+                .and_then(|vety| vety.deprecation.as_ref())
+            {
+                warn(
+                    sub.source_loc(),
+                    DeprecatedSchemaElement::EntityType(ety.clone()),
+                    deprecation,
+                );
+            }
+        }
+
+        if has_deprecated_types {
+            let typechecker = Typechecker::new(&self.schema, mode, t.id().clone());
+            for (_, policy_check) in typechecker.typecheck_by_request_env(t) {
+                match policy_check {
+                    PolicyCheck::Success(e) | PolicyCheck::Irrelevant(_, e) => {
+                        for sub in e.subexpressions() {
+                            if let Some((element, deprecation)) = self.deprecated_attr_access(sub) {
+                                warn(sub.source_loc(), element, deprecation);
+                            }
+                        }
+                    }
+                    PolicyCheck::Fail(_) => (),
+                }
+            }
+        }
+        warnings.into_iter()
+    }
+
+    /// If `e` reads or tests for a deprecated attribute of an entity, return
+    /// the attribute and its deprecation
+    fn deprecated_attr_access(
+        &self,
+        e: &Expr<Option<Type>>,
+    ) -> Option<(DeprecatedSchemaElement, &Deprecation)> {
+        let (ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr }) = e.expr_kind()
+        else {
+            return None;
+        };
+        let Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub))) = expr.data() else {
+            return None;
+        };
+        lub.iter().find_map(|ety| {
+            let deprecation = self
+                .schema
+                .get_entity_type(ety)?
+                .deprecated_attributes
+                .get(attr)?;
+            Some((
+                DeprecatedSchemaElement::Attribute {
+                    entity_ty: ety.clone(),
+                    attr: attr.clone(),
+                },
+                deprecation,
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::parser::parse_policyset;
+    use itertools::Itertools;
+    use smol_str::SmolStr;
+
+    fn schema() -> ValidatorSchema {
+        ValidatorSchema::from_cedarschema_str(
+            r#"
+            @deprecated("Person")
+            entity User {
+                @deprecated("fullName")
+                name: String,
+                fullName: String,
+            };
+            entity Person {
+                @deprecated
+                nickname?: String,
+            };
+            entity Doc;
+            @deprecated("read")
+            action view appliesTo { principal: [User, Person], resource: Doc };
+            action read appliesTo { principal: [User, Person], resource: Doc };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap()
+        .0
+    }
+
+    fn warnings(src: &str) -> Vec<(String, Option<SmolStr>)> {
+        let pset = parse_policyset(src).unwrap();
+        let result = Validator::new(schema()).validate(&pset, ValidationMode::Strict);
+        result
+            .validation_warnings()
+            .filter_map(|w| match w {
+                ValidationWarning::DeprecatedSchemaElementUse(w) => {
+                    Some((w.element.to_string(), w.replacement.clone()))
+                }
+                _ => None,
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn deprecated_entity_type_and_action() {
+        assert_eq!(
+            warnings(r#"permit(principal is User, action == Action::"view", resource);"#),
+            vec![
+                (
+                    r#"action `Action::"view"`"#.to_string(),
+                    Some("read".into())
+                ),
+                ("entity type `User`".to_string(), Some("Person".into())),
+            ]
+        );
+        assert_eq!(
+            warnings(
+                r#"permit(principal == Person::"alice", action == Action::"read", resource);"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn deprecated_attributes() {
+        assert_eq!(
+            warnings(
+                r#"permit(principal is Person, action == Action::"read", resource) when { principal has nickname && principal.nickname == "al" };"#
+            ),
+            vec![
+                ("attribute `nickname` of `Person`".to_string(), None),
+                ("attribute `nickname` of `Person`".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            warnings(
+                r#"permit(principal, action == Action::"read", resource) when { principal.fullName == "al" };"#
+            ),
+            vec![]
+        );
+    }
+}
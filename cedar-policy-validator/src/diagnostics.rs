@@ -23,8 +23,9 @@ use validation_errors::UnrecognizedActionIdHelp;
 
 use std::collections::BTreeSet;
 
-use cedar_policy_core::ast::{EntityType, Expr, PolicyID};
+use cedar_policy_core::ast::{EntityType, EntityUID, Expr, LinkBindings, PatternError, PolicyID};
 use cedar_policy_core::parser::Loc;
+use smol_str::SmolStr;
 
 use crate::types::{EntityLUB, Type};
 
@@ -36,6 +37,10 @@ pub mod validation_warnings;
 /// Validation succeeds if there are no fatal errors. There may still be
 /// non-fatal warnings present when validation passes.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ValidationResult {
     validation_errors: Vec<ValidationError>,
     validation_warnings: Vec<ValidationWarning>,
@@ -48,10 +53,20 @@ impl ValidationResult {
         errors: impl IntoIterator<Item = ValidationError>,
         warnings: impl IntoIterator<Item = ValidationWarning>,
     ) -> Self {
-        Self {
+        let result = Self {
             validation_errors: errors.into_iter().collect(),
             validation_warnings: warnings.into_iter().collect(),
+        };
+        #[cfg(feature = "tracing")]
+        {
+            for error in &result.validation_errors {
+                tracing::debug!(%error, "validation error");
+            }
+            for warning in &result.validation_warnings {
+                tracing::debug!(%warning, "validation warning");
+            }
         }
+        result
     }
 
     /// True when validation passes. There are no errors, but there may be
@@ -70,6 +85,23 @@ impl ValidationResult {
         self.validation_warnings.iter()
     }
 
+    /// Get a copy of this result with literal values in all errors and
+    /// warnings replaced by placeholders (see [`ValidationError::redacted`])
+    pub fn redacted(&self) -> Self {
+        Self {
+            validation_errors: self
+                .validation_errors
+                .iter()
+                .map(ValidationError::redacted)
+                .collect(),
+            validation_warnings: self
+                .validation_warnings
+                .iter()
+                .map(ValidationWarning::redacted)
+                .collect(),
+        }
+    }
+
     /// Get an iterator over the errors and warnings found by the validator.
     pub fn into_errors_and_warnings(
         self,
@@ -91,6 +123,10 @@ impl ValidationResult {
 //
 // This is NOT a publicly exported error type.
 #[derive(Clone, Debug, Diagnostic, Error, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ValidationError {
     /// A policy contains an entity type that is not declared in the schema.
     #[error(transparent)]
@@ -120,6 +156,11 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     UnsafeAttributeAccess(#[from] validation_errors::UnsafeAttributeAccess),
+    /// The typechecker detected an access to an attribute of an action which
+    /// the action does not declare in the schema.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UndefinedActionAttribute(#[from] validation_errors::UndefinedActionAttribute),
     /// The typechecker could not conclude that an access to an optional
     /// attribute was safe.
     #[error(transparent)]
@@ -166,6 +207,35 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     InternalInvariantViolation(#[from] validation_errors::InternalInvariantViolation),
+    /// The `@validation` annotation of a policy does not name a validation mode
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidValidationModeAnnotation(#[from] validation_errors::InvalidValidationModeAnnotation),
+    /// The `@expires` annotation of a policy is not a date
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidExpiresAnnotation(#[from] validation_errors::InvalidExpiresAnnotation),
+    /// The date in the `@expires` annotation of a policy has passed
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ExpiredPolicy(#[from] validation_errors::ExpiredPolicy),
+    /// A policy references an entity type or action outside the namespaces
+    /// the validator allows
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DisallowedNamespace(#[from] validation_errors::DisallowedNamespace),
+    /// Integer arithmetic on constants which is guaranteed to overflow
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ArithmeticOverflow(#[from] validation_errors::ArithmeticOverflow),
+    /// Integer division or remainder by a constant zero
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DivisionByZero(#[from] validation_errors::DivisionByZero),
+    /// A `like` pattern with a malformed character class or repetition
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidPattern(#[from] validation_errors::InvalidPattern),
     #[cfg(feature = "level-validate")]
     /// If a entity dereference level was provided, the policies cannot deref
     /// more than `level` hops away from PARX
@@ -175,6 +245,43 @@ pub enum ValidationError {
 }
 
 impl ValidationError {
+    /// Get a copy of this error with literal values replaced by placeholders,
+    /// so that it can be logged without leaking sensitive data.
+    ///
+    /// String and numeric literals in the attached source code are masked
+    /// (see [`Loc::redacted`]), as are messages that may quote them. The kind
+    /// of error, policy ID, types, and source span are kept.
+    pub fn redacted(&self) -> Self {
+        match self {
+            Self::UnrecognizedEntityType(e) => e.redacted().into(),
+            Self::UnrecognizedActionId(e) => e.redacted().into(),
+            Self::InvalidActionApplication(e) => e.redacted().into(),
+            Self::UnexpectedType(e) => e.redacted().into(),
+            Self::IncompatibleTypes(e) => e.redacted().into(),
+            Self::UnsafeAttributeAccess(e) => e.redacted().into(),
+            Self::UndefinedActionAttribute(e) => e.redacted().into(),
+            Self::UnsafeOptionalAttributeAccess(e) => e.redacted().into(),
+            Self::UnsafeTagAccess(e) => e.redacted().into(),
+            Self::NoTagsAllowed(e) => e.redacted().into(),
+            Self::UndefinedFunction(e) => e.redacted().into(),
+            Self::WrongNumberArguments(e) => e.redacted().into(),
+            Self::FunctionArgumentValidation(e) => e.redacted().into(),
+            Self::EmptySetForbidden(e) => e.redacted().into(),
+            Self::NonLitExtConstructor(e) => e.redacted().into(),
+            Self::HierarchyNotRespected(e) => e.redacted().into(),
+            Self::InternalInvariantViolation(e) => e.redacted().into(),
+            Self::InvalidValidationModeAnnotation(e) => e.redacted().into(),
+            Self::InvalidExpiresAnnotation(e) => e.redacted().into(),
+            Self::ExpiredPolicy(e) => e.redacted().into(),
+            Self::DisallowedNamespace(e) => e.redacted().into(),
+            Self::ArithmeticOverflow(e) => e.redacted().into(),
+            Self::DivisionByZero(e) => e.redacted().into(),
+            Self::InvalidPattern(e) => e.redacted().into(),
+            #[cfg(feature = "level-validate")]
+            Self::EntityDerefLevelViolation(e) => e.redacted().into(),
+        }
+    }
+
     pub(crate) fn unrecognized_entity_type(
         source_loc: Option<Loc>,
         policy_id: PolicyID,
@@ -209,12 +316,14 @@ impl ValidationError {
     pub(crate) fn invalid_action_application(
         source_loc: Option<Loc>,
         policy_id: PolicyID,
+        link: Option<LinkBindings>,
         would_in_fix_principal: bool,
         would_in_fix_resource: bool,
     ) -> Self {
         validation_errors::InvalidActionApplication {
             source_loc,
             policy_id,
+            link,
             would_in_fix_principal,
             would_in_fix_resource,
         }
@@ -275,6 +384,23 @@ impl ValidationError {
         .into()
     }
 
+    pub(crate) fn undefined_action_attribute(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        attr: SmolStr,
+        actions: BTreeSet<EntityUID>,
+        declared_on: BTreeSet<EntityUID>,
+    ) -> Self {
+        validation_errors::UndefinedActionAttribute {
+            source_loc,
+            policy_id,
+            attr,
+            actions,
+            declared_on,
+        }
+        .into()
+    }
+
     pub(crate) fn unsafe_optional_attribute_access(
         source_loc: Option<Loc>,
         policy_id: PolicyID,
@@ -320,11 +446,13 @@ impl ValidationError {
         source_loc: Option<Loc>,
         policy_id: PolicyID,
         name: String,
+        suggestion: Option<String>,
     ) -> Self {
         validation_errors::UndefinedFunction {
             source_loc,
             policy_id,
             name,
+            suggestion,
         }
         .into()
     }
@@ -398,11 +526,99 @@ impl ValidationError {
         }
         .into()
     }
+
+    pub(crate) fn invalid_validation_mode_annotation(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        value: SmolStr,
+    ) -> Self {
+        validation_errors::InvalidValidationModeAnnotation {
+            source_loc,
+            policy_id,
+            value,
+        }
+        .into()
+    }
+
+    pub(crate) fn invalid_expires_annotation(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        value: SmolStr,
+    ) -> Self {
+        validation_errors::InvalidExpiresAnnotation {
+            source_loc,
+            policy_id,
+            value,
+        }
+        .into()
+    }
+
+    pub(crate) fn expired_policy(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        expires: SmolStr,
+    ) -> Self {
+        validation_errors::ExpiredPolicy {
+            source_loc,
+            policy_id,
+            expires,
+        }
+        .into()
+    }
+
+    pub(crate) fn disallowed_namespace(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        entity_type: EntityType,
+        allowed_namespaces: Vec<String>,
+    ) -> Self {
+        validation_errors::DisallowedNamespace {
+            source_loc,
+            policy_id,
+            entity_type,
+            allowed_namespaces,
+        }
+        .into()
+    }
+
+    pub(crate) fn arithmetic_overflow(source_loc: Option<Loc>, policy_id: PolicyID) -> Self {
+        validation_errors::ArithmeticOverflow {
+            source_loc,
+            policy_id,
+        }
+        .into()
+    }
+
+    pub(crate) fn division_by_zero(source_loc: Option<Loc>, policy_id: PolicyID) -> Self {
+        validation_errors::DivisionByZero {
+            source_loc,
+            policy_id,
+        }
+        .into()
+    }
+
+    pub(crate) fn invalid_pattern(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        error: &PatternError,
+    ) -> Self {
+        validation_errors::InvalidPattern {
+            source_loc,
+            policy_id,
+            msg: error.to_string(),
+            help: error.help().map(|help| help.to_string()),
+        }
+        .into()
+    }
 }
 
 /// Represents the different kinds of validation warnings and information
 /// specific to that warning.
 #[derive(Debug, Clone, PartialEq, Diagnostic, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ValidationWarning {
     /// A string contains mixed scripts. Different scripts can contain visually similar characters which may be confused for each other.
     #[diagnostic(transparent)]
@@ -428,9 +644,48 @@ pub enum ValidationWarning {
     #[diagnostic(transparent)]
     #[error(transparent)]
     ImpossiblePolicy(#[from] validation_warnings::ImpossiblePolicy),
+    /// A policy uses an attribute the schema marks as sensitive in a way that is not allowed.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    SensitiveAttributeUse(#[from] validation_warnings::SensitiveAttributeUse),
+    /// A policy references an entity type, attribute, or action the schema marks as deprecated.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    DeprecatedSchemaElementUse(#[from] validation_warnings::DeprecatedSchemaElementUse),
+    /// A policy is validated in a different mode than the rest of the policy set, as requested by its `@validation` annotation.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    ValidationModeOverride(#[from] validation_warnings::ValidationModeOverride),
+    /// A policy has expired or will soon expire, according to its `@expires` annotation.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    PolicyExpiring(#[from] validation_warnings::PolicyExpiring),
+    /// An `==` or `in` compares entities whose types can never coincide, so
+    /// it always evaluates to `false`.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    IncompatibleEntityComparison(#[from] validation_warnings::IncompatibleEntityComparison),
 }
 
 impl ValidationWarning {
+    /// Get a copy of this warning with literal values replaced by
+    /// placeholders (see [`ValidationError::redacted`])
+    pub fn redacted(&self) -> Self {
+        match self {
+            Self::MixedScriptString(w) => w.redacted().into(),
+            Self::BidiCharsInString(w) => w.redacted().into(),
+            Self::BidiCharsInIdentifier(w) => w.redacted().into(),
+            Self::MixedScriptIdentifier(w) => w.redacted().into(),
+            Self::ConfusableIdentifier(w) => w.redacted().into(),
+            Self::ImpossiblePolicy(w) => w.redacted().into(),
+            Self::SensitiveAttributeUse(w) => w.redacted().into(),
+            Self::DeprecatedSchemaElementUse(w) => w.redacted().into(),
+            Self::ValidationModeOverride(w) => w.redacted().into(),
+            Self::PolicyExpiring(w) => w.redacted().into(),
+            Self::IncompatibleEntityComparison(w) => w.redacted().into(),
+        }
+    }
+
     pub(crate) fn mixed_script_string(
         source_loc: Option<Loc>,
         policy_id: PolicyID,
@@ -505,4 +760,85 @@ impl ValidationWarning {
         }
         .into()
     }
+
+    pub(crate) fn sensitive_attribute_use(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        entity_ty: EntityType,
+        attr: SmolStr,
+        kind: validation_warnings::SensitiveAttributeUseKind,
+    ) -> Self {
+        validation_warnings::SensitiveAttributeUse {
+            source_loc,
+            policy_id,
+            entity_ty,
+            attr,
+            kind,
+        }
+        .into()
+    }
+
+    pub(crate) fn deprecated_schema_element_use(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        element: validation_warnings::DeprecatedSchemaElement,
+        replacement: Option<SmolStr>,
+    ) -> Self {
+        validation_warnings::DeprecatedSchemaElementUse {
+            source_loc,
+            policy_id,
+            element,
+            replacement,
+        }
+        .into()
+    }
+
+    pub(crate) fn validation_mode_override(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        mode: SmolStr,
+    ) -> Self {
+        validation_warnings::ValidationModeOverride {
+            source_loc,
+            policy_id,
+            mode,
+        }
+        .into()
+    }
+
+    pub(crate) fn policy_expiring(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        expires: SmolStr,
+        days_remaining: i64,
+    ) -> Self {
+        validation_warnings::PolicyExpiring {
+            source_loc,
+            policy_id,
+            expires,
+            days_remaining,
+        }
+        .into()
+    }
+
+    pub(crate) fn incompatible_entity_comparison(
+        source_loc: Option<Loc>,
+        policy_id: PolicyID,
+        lhs_loc: Option<Loc>,
+        rhs_loc: Option<Loc>,
+        is_in: bool,
+        lhs_types: impl IntoIterator<Item = EntityType>,
+        rhs_types: impl IntoIterator<Item = EntityType>,
+    ) -> Self {
+        validation_warnings::IncompatibleEntityComparison {
+            source_loc,
+            policy_id,
+            lhs_loc,
+            rhs_loc,
+            is_in,
+            lhs_types: lhs_types.into_iter().collect(),
+            rhs_types: rhs_types.into_iter().collect(),
+        }
+        .into()
+    }
 }
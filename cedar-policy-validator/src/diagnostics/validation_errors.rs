@@ -28,7 +28,9 @@ use cedar_policy_core::parser::Loc;
 
 use std::collections::BTreeSet;
 
-use cedar_policy_core::ast::{Eid, EntityType, EntityUID, Expr, ExprKind, PolicyID, Var};
+use cedar_policy_core::ast::{
+    Eid, EntityType, EntityUID, Expr, ExprKind, LinkBindings, PolicyID, Var,
+};
 use cedar_policy_core::parser::join_with_conjunction;
 
 use crate::types::{EntityLUB, EntityRecordKind, RequestEnv, Type};
@@ -38,6 +40,10 @@ use smol_str::SmolStr;
 
 /// Structure containing details about an unrecognized entity type error.
 #[derive(Debug, Clone, Error, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 // #[error(error_in_policy!("unrecognized entity type `{actual_entity_type}`"))]
 #[error("for policy `{policy_id}`, unrecognized entity type `{actual_entity_type}`")]
 pub struct UnrecognizedEntityType {
@@ -65,6 +71,10 @@ impl Diagnostic for UnrecognizedEntityType {
 
 /// Structure containing details about an unrecognized action id error.
 #[derive(Debug, Clone, Error, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, unrecognized action `{actual_action_id}`")]
 pub struct UnrecognizedActionId {
     /// Source location
@@ -89,6 +99,10 @@ impl Diagnostic for UnrecognizedActionId {
 
 /// Help for resolving an unrecognized action id error
 #[derive(Debug, Clone, Error, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum UnrecognizedActionIdHelp {
     /// Draw attention to action id including action type (e.g., `Action::"Action::view"`)
     #[error("did you intend to include the type in action `{0}`?")]
@@ -117,24 +131,41 @@ pub fn unrecognized_action_id_help(
             id.to_string(),
         ))
     } else {
-        // Otherwise, suggest using another id
-        let euids_strs = schema
-            .known_action_ids()
-            .map(ToString::to_string)
+        // Otherwise, suggest the action with the most similar id, searching
+        // every namespace and preferring the namespace of `euid` on ties. The
+        // suggestion is fully qualified, so it also points out a missing or
+        // wrong namespace.
+        let mut action_ids = schema.known_action_ids().collect::<Vec<_>>();
+        action_ids.sort_by_key(|id| (id.entity_type() != euid.entity_type(), *id));
+        let eids = action_ids
+            .iter()
+            .map(|id| <Eid as AsRef<str>>::as_ref(id.eid()))
             .collect::<Vec<_>>();
-        fuzzy_search(euid.eid().as_ref(), &euids_strs)
-            .map(UnrecognizedActionIdHelp::SuggestAlternative)
+        let closest_eid = fuzzy_search(euid.eid().as_ref(), &eids)?;
+        action_ids
+            .into_iter()
+            .find(|id| <Eid as AsRef<str>>::as_ref(id.eid()) == closest_eid)
+            .map(|id| UnrecognizedActionIdHelp::SuggestAlternative(id.to_string()))
     }
 }
 
 /// Structure containing details about an invalid action application error.
 #[derive(Debug, Clone, Error, Hash, Eq, PartialEq)]
-#[error("for policy `{policy_id}`, unable to find an applicable action given the policy scope constraints")]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`{}, unable to find an applicable action given the policy scope constraints",
+    .link.as_ref().map(|l| format!(" ({l})")).unwrap_or_default()
+)]
 pub struct InvalidActionApplication {
     /// Source location
     pub source_loc: Option<Loc>,
     /// Policy ID where the error occurred
     pub policy_id: PolicyID,
+    /// Template and slot values of the policy, if it is a template-linked
+    /// policy
+    pub link: Option<LinkBindings>,
     /// `true` if changing `==` to `in` wouuld fix the principal clause
     pub would_in_fix_principal: bool,
     /// `true` if changing `==` to `in` wouuld fix the resource clause
@@ -162,6 +193,10 @@ impl Diagnostic for InvalidActionApplication {
 
 /// Structure containing details about an unexpected type error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, unexpected type: expected {} but saw {}",
     match .expected.iter().next() {
         Some(single) if .expected.len() == 1 => format!("{}", single),
@@ -191,6 +226,10 @@ impl Diagnostic for UnexpectedType {
 
 /// Help for resolving a type error
 #[derive(Error, Debug, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum UnexpectedTypeHelp {
     /// Try using `like`
     #[error("try using `like` to examine the contents of a string")]
@@ -221,13 +260,24 @@ pub enum UnexpectedTypeHelp {
     /// Cedar doesn't support string concatenation
     #[error("Cedar does not support string concatenation")]
     ConcatenationNotSupported,
-    /// Cedar doesn't support set union, intersection, or difference
-    #[error("Cedar does not support computing the union, intersection, or difference of sets")]
+    /// Cedar doesn't support arithmetic on sets, only the `union` and
+    /// `intersection` methods
+    #[error(
+        "Cedar does not support arithmetic on sets; try using `.union()` or `.intersection()`"
+    )]
     SetOperationsNotSupported,
+    /// A string literal holding an entity UID is used where an entity is
+    /// expected
+    #[error("try using the entity literal `{0}` instead of a string")]
+    TryUsingEntityLiteral(EntityUID),
 }
 
 /// Structure containing details about an incompatible type error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct IncompatibleTypes {
     /// Source location
     pub source_loc: Option<Loc>,
@@ -262,6 +312,10 @@ impl Display for IncompatibleTypes {
 
 /// Hints for resolving an incompatible-types error
 #[derive(Error, Debug, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum LubHelp {
     /// Attribute qualifier problems
     #[error("Corresponding attributes of compatible record types must have the same optionality, either both being required or both being optional")]
@@ -275,6 +329,9 @@ pub enum LubHelp {
     /// Entity and record types are never compatible
     #[error("Entity and record types are never compatible even when their attributes would be compatible")]
     EntityRecord,
+    /// A string literal holding an entity UID is compared with an entity
+    #[error("Strings are never equal to entities; try using the entity literal `{0}` instead of a string")]
+    EntityUidString(EntityUID),
     /// Catchall
     #[error("Types must be exactly equal to be compatible")]
     None,
@@ -282,6 +339,10 @@ pub enum LubHelp {
 
 /// Text describing where the incompatible-types error was found
 #[derive(Error, Debug, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum LubContext {
     /// In the elements of a set
     #[error("elements of a set")]
@@ -301,10 +362,17 @@ pub enum LubContext {
     /// While computing the type of a `.getTag()` operation
     #[error("tag types for a `.getTag()` operation")]
     GetTag,
+    /// In the operands of `union` or `intersection`
+    #[error("elements of both set operands to a `union` or `intersection` expression")]
+    SetOperation,
 }
 
 /// Structure containing details about a missing attribute error.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, attribute {attribute_access} not found")]
 pub struct UnsafeAttributeAccess {
     /// Source location
@@ -333,8 +401,69 @@ impl Diagnostic for UnsafeAttributeAccess {
     }
 }
 
+/// Structure containing details about an access to an attribute of an action
+/// which the action does not declare in the schema
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UndefinedActionAttribute {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// The attribute being accessed
+    pub attr: SmolStr,
+    /// The actions the access may be on which don't declare the attribute
+    pub actions: BTreeSet<EntityUID>,
+    /// The actions in the schema which do declare the attribute
+    pub declared_on: BTreeSet<EntityUID>,
+}
+
+impl std::error::Error for UndefinedActionAttribute {}
+
+impl Display for UndefinedActionAttribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "for policy `{}`, attribute `{}` is not declared on action{} ",
+            self.policy_id,
+            self.attr,
+            if self.actions.len() == 1 { "" } else { "s" }
+        )?;
+        join_with_conjunction(f, "and", self.actions.iter(), |f, a| write!(f, "`{a}`"))
+    }
+}
+
+impl Diagnostic for UndefinedActionAttribute {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        use std::fmt::Write;
+        if self.declared_on.is_empty() {
+            return Some(Box::new(format!(
+                "no action in the schema declares attribute `{}`",
+                self.attr
+            )));
+        }
+        let mut help = format!("attribute `{}` is only declared on ", self.attr);
+        // PANIC SAFETY: writing to a `String` can't fail
+        #[allow(clippy::unwrap_used)]
+        join_with_conjunction(&mut help, "and", &self.declared_on, |f, a| {
+            write!(f, "`{a}`")
+        })
+        .unwrap();
+        Some(Box::new(help))
+    }
+}
+
 /// Structure containing details about an unsafe optional attribute error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, unable to guarantee safety of access to optional attribute {attribute_access}")]
 pub struct UnsafeOptionalAttributeAccess {
     /// Source location
@@ -358,6 +487,10 @@ impl Diagnostic for UnsafeOptionalAttributeAccess {
 
 /// Structure containing details about an unsafe tag access error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error(
     "for policy `{policy_id}`, unable to guarantee safety of access to tag `{tag}`{}",
     match .entity_ty.as_ref().and_then(|lub| lub.get_single_entity()) {
@@ -389,6 +522,10 @@ impl Diagnostic for UnsafeTagAccess {
 
 /// Structure containing details about a no-tags-allowed error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error(
     "for policy `{policy_id}`, `.getTag()` is not allowed on entities of {} because no `tags` were declared on the entity type in the schema",
     match .entity_ty.as_ref() {
@@ -413,6 +550,10 @@ impl Diagnostic for NoTagsAllowed {
 
 /// Structure containing details about an undefined function error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, undefined extension function: {name}")]
 pub struct UndefinedFunction {
     /// Source location
@@ -421,14 +562,26 @@ pub struct UndefinedFunction {
     pub policy_id: PolicyID,
     /// Name of the undefined function
     pub name: String,
+    /// Name of a defined extension function similar to `name`
+    pub suggestion: Option<String>,
 }
 
 impl Diagnostic for UndefinedFunction {
     impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.suggestion
+            .as_ref()
+            .map(|suggestion| Box::new(format!("did you mean `{suggestion}`?")) as _)
+    }
 }
 
 /// Structure containing details about a wrong number of arguments error.
 #[derive(Error, Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, wrong number of arguments in extension function application. Expected {expected}, got {actual}")]
 pub struct WrongNumberArguments {
     /// Source location
@@ -447,6 +600,10 @@ impl Diagnostic for WrongNumberArguments {
 
 /// Structure containing details about a function argument validation error.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, error during extension function argument validation: {msg}")]
 pub struct FunctionArgumentValidation {
     /// Source location
@@ -463,6 +620,10 @@ impl Diagnostic for FunctionArgumentValidation {
 
 /// Structure containing details about a hierarchy not respected error
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, operands to `in` do not respect the entity hierarchy")]
 pub struct HierarchyNotRespected {
     /// Source location
@@ -490,6 +651,10 @@ impl Diagnostic for HierarchyNotRespected {
 
 /// Represents how many entity dereferences can be applied to a node.
 #[derive(Default, Debug, Clone, Hash, Eq, PartialEq, Error, Copy, Ord, PartialOrd)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct EntityDerefLevel {
     /// A negative value `-n` represents `n` too many dereferences
     pub level: i64,
@@ -538,6 +703,10 @@ impl EntityDerefLevel {
 
 /// Structure containing details about entity dereference level violation
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, the maximum allowed level {allowed_level} is violated. Actual level is {}", (allowed_level.add(actual_level.neg())))]
 pub struct EntityDerefLevelViolation {
     /// Source location
@@ -560,6 +729,10 @@ impl Diagnostic for EntityDerefLevelViolation {
 
 /// The policy uses an empty set literal in a way that is forbidden
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, empty set literals are forbidden in policies")]
 pub struct EmptySetForbidden {
     /// Source location
@@ -575,6 +748,10 @@ impl Diagnostic for EmptySetForbidden {
 /// The policy passes a non-literal to an extension constructor, which is
 /// forbidden in strict validation
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, extension constructors may not be called with non-literal expressions")]
 pub struct NonLitExtConstructor {
     /// Source location
@@ -596,6 +773,10 @@ impl Diagnostic for NonLitExtConstructor {
 /// Returned when an internal invariant is violated (should not happen; if
 /// this is ever returned, please file an issue)
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("internal invariant violated")]
 pub struct InternalInvariantViolation {
     /// Source location
@@ -614,6 +795,213 @@ impl Diagnostic for InternalInvariantViolation {
     }
 }
 
+/// Returned when the `@validation` annotation of a policy does not name a
+/// validation mode
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, `@validation` annotation has unsupported value `{value}`")]
+pub struct InvalidValidationModeAnnotation {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// Value of the annotation
+    pub value: SmolStr,
+}
+
+impl Diagnostic for InvalidValidationModeAnnotation {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(
+            "the validation mode of a policy can be overridden with `@validation(\"strict\")` or `@validation(\"permissive\")`",
+        ))
+    }
+}
+
+/// Structure containing details about an `@expires` annotation which is not a
+/// date
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, `@expires` annotation has invalid date `{value}`")]
+pub struct InvalidExpiresAnnotation {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// Value of the annotation
+    pub value: SmolStr,
+}
+
+impl Diagnostic for InvalidExpiresAnnotation {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(
+            "expiration dates must have the form `YYYY-MM-DD`, e.g., `@expires(\"2025-12-31\")`",
+        ))
+    }
+}
+
+/// Structure containing details about a policy whose `@expires` date has
+/// passed
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("policy `{policy_id}` expired on {expires}")]
+pub struct ExpiredPolicy {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// The expiration date of the policy
+    pub expires: SmolStr,
+}
+
+impl Diagnostic for ExpiredPolicy {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(
+            "remove the policy, or move its `@expires` date if it is still needed",
+        ))
+    }
+}
+
+/// Structure containing details about a reference to an entity type or action
+/// outside the namespaces the validator allows
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, `{entity_type}` is not in an allowed namespace")]
+pub struct DisallowedNamespace {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// The entity type referenced, which for actions is the action entity
+    /// type (e.g., `NS::Action`)
+    pub entity_type: EntityType,
+    /// The allowed namespaces, where the empty string is the empty namespace
+    pub allowed_namespaces: Vec<String>,
+}
+
+impl Diagnostic for DisallowedNamespace {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        use std::fmt::Write;
+        if self.allowed_namespaces.is_empty() {
+            return Some(Box::new(
+                "policies may not reference any entity types or actions",
+            ));
+        }
+        let mut help = "policies may only reference entity types and actions in ".to_string();
+        // PANIC SAFETY: writing to a `String` can't fail
+        #[allow(clippy::unwrap_used)]
+        join_with_conjunction(&mut help, "or", &self.allowed_namespaces, |f, ns| {
+            if ns.is_empty() {
+                write!(f, "the empty namespace")
+            } else {
+                write!(f, "namespace `{ns}`")
+            }
+        })
+        .unwrap();
+        Some(Box::new(help))
+    }
+}
+
+/// Structure containing details about integer arithmetic on constants which is
+/// guaranteed to overflow
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, integer arithmetic on constants is guaranteed to overflow")]
+pub struct ArithmeticOverflow {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+}
+
+impl Diagnostic for ArithmeticOverflow {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(format!(
+            "the result must be between {} and {}, otherwise evaluating the policy fails",
+            i64::MIN,
+            i64::MAX
+        )))
+    }
+}
+
+/// Structure containing details about integer division or remainder by a
+/// constant zero
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, integer division by zero")]
+pub struct DivisionByZero {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+}
+
+impl Diagnostic for DivisionByZero {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(
+            "dividing by zero or taking the remainder of dividing by zero is an evaluation error",
+        ))
+    }
+}
+
+/// Structure containing details about a `like` pattern with a malformed
+/// character class or repetition
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Error)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, invalid pattern in `like` expression: {msg}")]
+pub struct InvalidPattern {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the error occurred
+    pub policy_id: PolicyID,
+    /// Error message
+    pub msg: String,
+    /// Suggestion for fixing the pattern, if any
+    pub help: Option<String>,
+}
+
+impl Diagnostic for InvalidPattern {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn Display + 'a>)
+    }
+}
+
 /// Contains more detailed information about an attribute access when it occurs
 /// on an entity type expression or on the `context` variable. Track a `Vec` of
 /// attributes rather than a single attribute so that on `principal.foo.bar` can
@@ -621,6 +1009,10 @@ impl Diagnostic for InternalInvariantViolation {
 /// needs attributes `bar` instead of giving up when the immediate target of the
 /// attribute access is not a entity.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum AttributeAccess {
     /// The attribute access is some sequence of attributes accesses eventually
     /// targeting an [`EntityLUB`].
@@ -717,6 +1109,67 @@ impl Display for AttributeAccess {
     }
 }
 
+/// Placeholder replacing sensitive values in redacted diagnostics
+pub(crate) const REDACTED: &str = "<redacted>";
+
+// Generates a `redacted()` method for a diagnostic struct, which masks literals
+// in the attached source code (see `Loc::redacted`) and replaces the listed
+// `String` fields with a placeholder.
+macro_rules! impl_redacted {
+    ($s:ident $(, $field:ident)*) => {
+        impl $s {
+            /// Get a copy of this diagnostic with literal values replaced by
+            /// placeholders, keeping its structure and source span
+            pub fn redacted(&self) -> Self {
+                Self {
+                    source_loc: self.source_loc.as_ref().map(Loc::redacted),
+                    $($field: $crate::diagnostics::validation_errors::REDACTED.into(),)*
+                    ..self.clone()
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_redacted;
+
+impl_redacted!(UnrecognizedEntityType);
+impl_redacted!(UnrecognizedActionId);
+
+impl InvalidActionApplication {
+    /// Get a copy of this diagnostic with literal values replaced by
+    /// placeholders, keeping its structure and source span
+    pub fn redacted(&self) -> Self {
+        Self {
+            source_loc: self.source_loc.as_ref().map(Loc::redacted),
+            link: self.link.as_ref().map(LinkBindings::redacted),
+            ..self.clone()
+        }
+    }
+}
+
+impl_redacted!(UnexpectedType);
+impl_redacted!(IncompatibleTypes);
+impl_redacted!(UnsafeAttributeAccess);
+impl_redacted!(UndefinedActionAttribute);
+impl_redacted!(UnsafeOptionalAttributeAccess);
+impl_redacted!(UnsafeTagAccess);
+impl_redacted!(NoTagsAllowed);
+impl_redacted!(UndefinedFunction);
+impl_redacted!(WrongNumberArguments);
+impl_redacted!(FunctionArgumentValidation, msg);
+impl_redacted!(HierarchyNotRespected);
+impl_redacted!(EntityDerefLevelViolation);
+impl_redacted!(EmptySetForbidden);
+impl_redacted!(NonLitExtConstructor);
+impl_redacted!(InternalInvariantViolation);
+impl_redacted!(InvalidValidationModeAnnotation);
+impl_redacted!(InvalidExpiresAnnotation);
+impl_redacted!(ExpiredPolicy);
+impl_redacted!(DisallowedNamespace);
+impl_redacted!(ArithmeticOverflow);
+impl_redacted!(DivisionByZero);
+impl_redacted!(InvalidPattern, msg);
+
 // These tests all assume that the typechecker found an error while checking the
 // outermost `GetAttr` in the expressions. If the attribute didn't exist at all,
 // only the primary message would included in the final error. If it was an
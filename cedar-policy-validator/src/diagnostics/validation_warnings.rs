@@ -27,12 +27,24 @@ macro_rules! impl_diagnostic_warning {
     };
 }
 
-use cedar_policy_core::{ast::PolicyID, impl_diagnostic_from_source_loc_opt_field, parser::Loc};
+use cedar_policy_core::{
+    ast::{EntityType, EntityUID, PolicyID},
+    impl_diagnostic_from_source_loc_opt_field,
+    parser::Loc,
+};
+use itertools::Itertools;
 use miette::Diagnostic;
+use smol_str::SmolStr;
 use thiserror::Error;
 
+use super::validation_errors::impl_redacted;
+
 /// Warning for strings containing mixed scripts
 #[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, string `\"{string}\"` contains mixed scripts")]
 pub struct MixedScriptString {
     /// Source location
@@ -50,6 +62,10 @@ impl Diagnostic for MixedScriptString {
 
 /// Warning for strings containing BIDI control characters
 #[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, string `\"{string}\"` contains BIDI control characters")]
 pub struct BidiCharsInString {
     /// Source location
@@ -67,6 +83,10 @@ impl Diagnostic for BidiCharsInString {
 
 /// Warning for identifiers containing BIDI control characters
 #[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, identifier `{id}` contains BIDI control characters")]
 pub struct BidiCharsInIdentifier {
     /// Source location
@@ -84,6 +104,10 @@ impl Diagnostic for BidiCharsInIdentifier {
 
 /// Warning for identifiers containing mixed scripts
 #[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, identifier `{id}` contains mixed scripts")]
 pub struct MixedScriptIdentifier {
     /// Source location
@@ -100,6 +124,10 @@ impl Diagnostic for MixedScriptIdentifier {
 
 /// Warning for identifiers containing confusable characters
 #[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error(
     "for policy `{policy_id}`, identifier `{}` contains the character `{}` which is not a printable ASCII character and falls outside of the General Security Profile for Identifiers",
     .id.escape_debug(),
@@ -123,6 +151,10 @@ impl Diagnostic for ConfusableIdentifier {
 
 /// Warning for policies that are impossible (evaluate to `false` for all valid requests)
 #[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[error("for policy `{policy_id}`, policy is impossible: the policy expression evaluates to false for all valid requests")]
 pub struct ImpossiblePolicy {
     /// Source location
@@ -135,3 +167,266 @@ impl Diagnostic for ImpossiblePolicy {
     impl_diagnostic_from_source_loc_opt_field!(source_loc);
     impl_diagnostic_warning!();
 }
+
+/// Warning for policies that use an attribute annotated as sensitive (`@pii`)
+/// in the schema in a way that is not allowed
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, sensitive attribute `{attr}` of `{entity_ty}` is {kind}")]
+pub struct SensitiveAttributeUse {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// Entity type declaring the sensitive attribute
+    pub entity_ty: EntityType,
+    /// Name of the sensitive attribute
+    pub attr: SmolStr,
+    /// How the attribute was used
+    pub kind: SensitiveAttributeUseKind,
+}
+
+impl Diagnostic for SensitiveAttributeUse {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            "attributes annotated with `@pii` in the schema should not be used to grant access or be compared against fixed values",
+        ))
+    }
+}
+
+/// The forbidden ways a sensitive attribute may be used in a policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SensitiveAttributeUseKind {
+    /// The attribute is read in the condition of a `permit` policy
+    PermitCondition,
+    /// The attribute is compared for equality with a string literal
+    LiteralComparison,
+}
+
+impl std::fmt::Display for SensitiveAttributeUseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PermitCondition => write!(f, "used in the condition of a `permit` policy"),
+            Self::LiteralComparison => write!(f, "compared with a string literal"),
+        }
+    }
+}
+
+/// Warning for policies that reference an entity type, attribute, or action
+/// annotated as deprecated (`@deprecated`) in the schema
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, {element} is deprecated")]
+pub struct DeprecatedSchemaElementUse {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The deprecated schema element referenced by the policy
+    pub element: DeprecatedSchemaElement,
+    /// What the schema says to use instead, if anything
+    pub replacement: Option<SmolStr>,
+}
+
+impl Diagnostic for DeprecatedSchemaElementUse {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.replacement
+            .as_ref()
+            .map(|r| Box::new(format!("use `{r}` instead")) as Box<dyn std::fmt::Display>)
+    }
+}
+
+/// A schema element which may be annotated as deprecated
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum DeprecatedSchemaElement {
+    /// An entity type
+    EntityType(EntityType),
+    /// An attribute of an entity type
+    Attribute {
+        /// Entity type declaring the attribute
+        entity_ty: EntityType,
+        /// Name of the attribute
+        attr: SmolStr,
+    },
+    /// An action
+    Action(EntityUID),
+}
+
+impl std::fmt::Display for DeprecatedSchemaElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EntityType(ety) => write!(f, "entity type `{ety}`"),
+            Self::Attribute { entity_ty, attr } => {
+                write!(f, "attribute `{attr}` of `{entity_ty}`")
+            }
+            Self::Action(euid) => write!(f, "action `{euid}`"),
+        }
+    }
+}
+
+/// Warning for policies validated in a different mode than the rest of the
+/// policy set, as requested by their `@validation` annotation
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, validation mode is overridden to `{mode}` by the `@validation` annotation")]
+pub struct ValidationModeOverride {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The mode the policy is validated in, as named in the annotation
+    pub mode: SmolStr,
+}
+
+impl Diagnostic for ValidationModeOverride {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+}
+
+/// Warning for policies which have expired or will soon expire, according to
+/// their `@expires` annotation
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("policy `{policy_id}` {}", describe_expiration(.expires, *.days_remaining))]
+pub struct PolicyExpiring {
+    /// Source location
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// The expiration date of the policy
+    pub expires: SmolStr,
+    /// Days until the policy expires, which is negative if it has expired
+    pub days_remaining: i64,
+}
+
+fn describe_expiration(expires: &str, days_remaining: i64) -> String {
+    match days_remaining {
+        ..=-1 => format!("expired on {expires}"),
+        0 => format!("expires today ({expires})"),
+        1 => format!("expires on {expires}, in 1 day"),
+        _ => format!("expires on {expires}, in {days_remaining} days"),
+    }
+}
+
+impl Diagnostic for PolicyExpiring {
+    impl_diagnostic_from_source_loc_opt_field!(source_loc);
+    impl_diagnostic_warning!();
+}
+
+/// Warning for `==` or `in` comparisons between entities whose types can
+/// never coincide according to the schema, so that the comparison always
+/// evaluates to `false`
+#[derive(Debug, Clone, PartialEq, Error, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[error("for policy `{policy_id}`, comparison is always false: {} can never be {} {}", describe_entity_types(.lhs_types), if *.is_in { "in" } else { "equal to" }, describe_entity_types(.rhs_types))]
+pub struct IncompatibleEntityComparison {
+    /// Source location of the comparison
+    pub source_loc: Option<Loc>,
+    /// Policy ID where the warning occurred
+    pub policy_id: PolicyID,
+    /// Source location of the left operand
+    pub lhs_loc: Option<Loc>,
+    /// Source location of the right operand
+    pub rhs_loc: Option<Loc>,
+    /// Whether the comparison is `in` rather than `==`
+    pub is_in: bool,
+    /// Entity types the left operand may have
+    pub lhs_types: Vec<EntityType>,
+    /// Entity types the right operand may have
+    pub rhs_types: Vec<EntityType>,
+}
+
+fn describe_entity_types(types: &[EntityType]) -> String {
+    format!(
+        "an entity of type {}",
+        types.iter().map(|ty| format!("`{ty}`")).join(" or ")
+    )
+}
+
+impl Diagnostic for IncompatibleEntityComparison {
+    impl_diagnostic_warning!();
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.source_loc.as_ref().and_then(Loc::source_code)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        match (&self.lhs_loc, &self.rhs_loc) {
+            (Some(lhs_loc), Some(rhs_loc)) => Some(Box::new(
+                [
+                    miette::LabeledSpan::new_with_span(
+                        Some(describe_entity_types(&self.lhs_types)),
+                        lhs_loc.span,
+                    ),
+                    miette::LabeledSpan::new_with_span(
+                        Some(describe_entity_types(&self.rhs_types)),
+                        rhs_loc.span,
+                    ),
+                ]
+                .into_iter(),
+            )),
+            _ => self.source_loc.as_ref().map(|loc| {
+                Box::new(std::iter::once(miette::LabeledSpan::underline(loc.span))) as _
+            }),
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            "check whether one of these entity types was renamed or moved to a different namespace",
+        ))
+    }
+}
+
+impl IncompatibleEntityComparison {
+    /// Get a copy of this diagnostic with literal values replaced by
+    /// placeholders, keeping its structure and source spans
+    pub fn redacted(&self) -> Self {
+        Self {
+            source_loc: self.source_loc.as_ref().map(Loc::redacted),
+            lhs_loc: self.lhs_loc.as_ref().map(Loc::redacted),
+            rhs_loc: self.rhs_loc.as_ref().map(Loc::redacted),
+            ..self.clone()
+        }
+    }
+}
+
+impl_redacted!(MixedScriptString, string);
+impl_redacted!(BidiCharsInString, string);
+impl_redacted!(BidiCharsInIdentifier);
+impl_redacted!(MixedScriptIdentifier);
+impl_redacted!(ConfusableIdentifier);
+impl_redacted!(ImpossiblePolicy);
+impl_redacted!(SensitiveAttributeUse);
+impl_redacted!(DeprecatedSchemaElementUse);
+impl_redacted!(ValidationModeOverride);
+impl_redacted!(PolicyExpiring);
@@ -530,7 +530,14 @@ fn entity_manifest_from_expr(
         ExprKind::And { left, right }
         | ExprKind::Or { left, right }
         | ExprKind::BinaryApp {
-            op: BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul,
+            op:
+                BinaryOp::Less
+                | BinaryOp::LessEq
+                | BinaryOp::Add
+                | BinaryOp::Sub
+                | BinaryOp::Mul
+                | BinaryOp::Div
+                | BinaryOp::Rem,
             arg1: left,
             arg2: right,
         } => Ok(entity_manifest_from_expr(left)?
@@ -559,7 +566,9 @@ fn entity_manifest_from_expr(
                 | BinaryOp::In
                 | BinaryOp::Contains
                 | BinaryOp::ContainsAll
-                | BinaryOp::ContainsAny,
+                | BinaryOp::ContainsAny
+                | BinaryOp::Union
+                | BinaryOp::Intersection,
             arg1,
             arg2,
         } => {
@@ -594,7 +603,8 @@ fn entity_manifest_from_expr(
             }
 
             // Load all fields using `full_type_required`, since
-            // these operations do equality checks.
+            // these operations do equality checks. The sets produced by
+            // `union` and `intersection` then need no further data.
             Ok(arg1_res
                 .full_type_required(ty1)
                 .union(&arg2_res.full_type_required(ty2))
@@ -0,0 +1,385 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checks for policies whose `@expires` annotation has passed or is about to
+
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::UNIX_EPOCH;
+
+use cedar_policy_core::ast::{PolicyID, PolicySet, Template};
+use cedar_policy_core::evaluator::Clock;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use crate::{ValidationError, ValidationWarning, Validator};
+
+/// Policy annotation giving the date after which a policy should no longer be
+/// in use
+pub(crate) const EXPIRES_ANNOTATION: &str = "expires";
+
+/// A calendar date, as written in `@expires` annotations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExpirationDate {
+    /// Days since 1970-01-01
+    days: i64,
+}
+
+impl ExpirationDate {
+    /// Construct a date from its year, month (1-12), and day of the month.
+    /// Returns `None` if there is no such day.
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        let month_len = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > month_len {
+            return None;
+        }
+        // Days since 1970-01-01 in the proleptic Gregorian calendar, counting
+        // years from March so that leap days fall at the end of the year
+        let (month, day) = (i64::from(month), i64::from(day));
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Self {
+            days: era * 146097 + day_of_era - 719468,
+        })
+    }
+
+    /// Today's date in UTC, according to `clock`
+    pub fn today(clock: &dyn Clock) -> Self {
+        let secs = match clock.now().duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => i64::try_from(since_epoch.as_secs()).unwrap_or(i64::MAX),
+            Err(before_epoch) => {
+                -i64::try_from(before_epoch.duration().as_secs()).unwrap_or(i64::MAX)
+            }
+        };
+        Self {
+            days: secs.div_euclid(86400),
+        }
+    }
+
+    /// The number of days from `self` until `other`, which is negative if
+    /// `other` is before `self`
+    pub fn days_until(self, other: Self) -> i64 {
+        other.days - self.days
+    }
+
+    /// The year, month, and day of the month of this date
+    fn ymd(self) -> (i64, i64, i64) {
+        let days = self.days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = (shifted_month + 2) % 12 + 1;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+}
+
+impl Display for ExpirationDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// Error parsing an [`ExpirationDate`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid date `{0}`, expected a date of the form `YYYY-MM-DD`")]
+pub struct ParseExpirationDateError(SmolStr);
+
+impl FromStr for ExpirationDate {
+    type Err = ParseExpirationDateError;
+
+    /// Parse a date of the form `YYYY-MM-DD`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseExpirationDateError(s.into());
+        let mut parts = s.split('-');
+        let (Some(year), Some(month), Some(day), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(err());
+        };
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(err());
+        }
+        if !(year.chars().chain(month.chars()).chain(day.chars())).all(|c| c.is_ascii_digit()) {
+            return Err(err());
+        }
+        match (year.parse(), month.parse(), day.parse()) {
+            (Ok(year), Ok(month), Ok(day)) => Self::from_ymd(year, month, day).ok_or_else(err),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// How the validator treats policies with an `@expires` annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpirationRules {
+    /// The date policy expiration dates are compared against
+    pub today: ExpirationDate,
+    /// Warn about policies which expire within this many days of `today`
+    pub warn_within_days: u32,
+    /// Report policies whose expiration date is before `today` as errors,
+    /// rather than warnings
+    pub expired_is_error: bool,
+}
+
+impl ExpirationRules {
+    /// Rules comparing expiration dates against `today`, warning about
+    /// policies expiring within 30 days, and reporting expired policies as
+    /// errors
+    pub fn new(today: ExpirationDate) -> Self {
+        Self {
+            today,
+            warn_within_days: 30,
+            expired_is_error: true,
+        }
+    }
+}
+
+/// A policy which has expired or will expire soon, as found by
+/// [`expiring_policies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyExpiration {
+    /// The policy
+    pub policy_id: PolicyID,
+    /// The date in its `@expires` annotation
+    pub expires: ExpirationDate,
+    /// Days from [`ExpirationRules::today`] until `expires`, which is negative
+    /// if the policy has expired
+    pub days_remaining: i64,
+}
+
+/// Find the static and template-linked policies in `policies` which have
+/// expired, or will expire within [`ExpirationRules::warn_within_days`],
+/// sorted by expiration date and then policy id. Policies whose `@expires`
+/// annotation is not a valid date are not included; the validator reports
+/// those as errors.
+pub fn expiring_policies(policies: &PolicySet, rules: &ExpirationRules) -> Vec<PolicyExpiration> {
+    let mut expiring = policies
+        .policies()
+        .filter_map(|p| {
+            let (_, annotation) = p
+                .annotations()
+                .find(|(key, _)| key.as_ref() == EXPIRES_ANNOTATION)?;
+            let expires = annotation.val.parse::<ExpirationDate>().ok()?;
+            let days_remaining = rules.today.days_until(expires);
+            (days_remaining <= i64::from(rules.warn_within_days)).then(|| PolicyExpiration {
+                policy_id: p.id().clone(),
+                expires,
+                days_remaining,
+            })
+        })
+        .collect::<Vec<_>>();
+    expiring.sort_by(|a, b| (a.expires, &a.policy_id).cmp(&(b.expires, &b.policy_id)));
+    expiring
+}
+
+impl Validator {
+    /// Check the `@expires` annotation of `t` against this validator's
+    /// [`ExpirationRules`], if it has any. Returns an error if the annotation
+    /// is not a date or (depending on the rules) if it has passed, and a
+    /// warning if it is within the warning period.
+    pub(crate) fn expiration_check(
+        &self,
+        t: &Template,
+    ) -> (Option<ValidationError>, Option<ValidationWarning>) {
+        let Some(rules) = &self.expiration_rules else {
+            return (None, None);
+        };
+        let Some((_, annotation)) = t
+            .annotations()
+            .find(|(key, _)| key.as_ref() == EXPIRES_ANNOTATION)
+        else {
+            return (None, None);
+        };
+        let loc = annotation.loc.clone().or_else(|| t.loc().cloned());
+        let Ok(expires) = annotation.val.parse::<ExpirationDate>() else {
+            return (
+                Some(ValidationError::invalid_expires_annotation(
+                    loc,
+                    t.id().clone(),
+                    annotation.val.clone(),
+                )),
+                None,
+            );
+        };
+        let days_remaining = rules.today.days_until(expires);
+        if days_remaining < 0 && rules.expired_is_error {
+            (
+                Some(ValidationError::expired_policy(
+                    loc,
+                    t.id().clone(),
+                    expires.to_string().into(),
+                )),
+                None,
+            )
+        } else if days_remaining <= i64::from(rules.warn_within_days) {
+            (
+                None,
+                Some(ValidationWarning::policy_expiring(
+                    loc,
+                    t.id().clone(),
+                    expires.to_string().into(),
+                    days_remaining,
+                )),
+            )
+        } else {
+            (None, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ValidationMode, ValidatorSchema};
+    use cedar_policy_core::parser::parse_policyset;
+    use itertools::Itertools;
+
+    fn date(s: &str) -> ExpirationDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_dates() {
+        for s in [
+            "1970-01-01",
+            "2024-02-29",
+            "2025-12-31",
+            "1969-07-20",
+            "0001-01-01",
+        ] {
+            assert_eq!(date(s).to_string(), s);
+        }
+        assert_eq!(date("1970-01-02").days, 1);
+        assert_eq!(date("1969-12-31").days, -1);
+        assert_eq!(date("2000-03-01").days - date("2000-02-28").days, 2);
+        assert_eq!(date("2100-03-01").days - date("2100-02-28").days, 1);
+        let noon = UNIX_EPOCH
+            + std::time::Duration::from_secs(date("2025-06-15").days as u64 * 86400 + 43200);
+        assert_eq!(
+            ExpirationDate::today(&cedar_policy_core::evaluator::FixedClock(noon)),
+            date("2025-06-15")
+        );
+        for s in [
+            "2025-13-01",
+            "2025-02-29",
+            "2025-1-01",
+            "2025-01-01-01",
+            "+025-01-01",
+            "tomorrow",
+        ] {
+            assert!(s.parse::<ExpirationDate>().is_err(), "{s}");
+        }
+    }
+
+    fn validator(rules: ExpirationRules) -> Validator {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        Validator::new(schema).with_expiration_rules(rules)
+    }
+
+    const POLICIES: &str = r#"
+        @expires("2025-01-31")
+        permit(principal, action, resource);
+        @expires("2025-03-01")
+        permit(principal, action, resource);
+        @expires("2024-12-31")
+        permit(principal, action, resource);
+        @expires("next year")
+        permit(principal, action, resource);
+        permit(principal, action, resource);
+    "#;
+
+    #[test]
+    fn expired_and_expiring() {
+        let pset = parse_policyset(POLICIES).unwrap();
+        let mut rules = ExpirationRules::new(date("2025-01-01"));
+        let result = validator(rules).validate(&pset, ValidationMode::Strict);
+        let errs = result
+            .validation_errors()
+            .map(ToString::to_string)
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errs,
+            vec![
+                "for policy `policy3`, `@expires` annotation has invalid date `next year`",
+                "policy `policy2` expired on 2024-12-31",
+            ]
+        );
+        let warnings = result
+            .validation_warnings()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec!["policy `policy0` expires on 2025-01-31, in 30 days"]
+        );
+
+        rules.expired_is_error = false;
+        rules.warn_within_days = 0;
+        let result = validator(rules).validate(&pset, ValidationMode::Strict);
+        assert_eq!(result.validation_errors().count(), 1);
+        let warnings = result
+            .validation_warnings()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(warnings, vec!["policy `policy2` expired on 2024-12-31"]);
+    }
+
+    #[test]
+    fn no_rules_no_checks() {
+        let pset = parse_policyset(POLICIES).unwrap();
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(result.validation_warnings().count(), 0);
+    }
+
+    #[test]
+    fn list_expiring() {
+        let pset = parse_policyset(POLICIES).unwrap();
+        let expiring = expiring_policies(&pset, &ExpirationRules::new(date("2025-01-01")))
+            .into_iter()
+            .map(|e| (e.policy_id.to_string(), e.days_remaining))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expiring,
+            vec![("policy2".to_string(), -1), ("policy0".to_string(), 30)]
+        );
+    }
+}
@@ -33,6 +33,15 @@ impl std::fmt::Debug for ExtensionSchema {
 
 impl ExtensionSchema {
     /// Create a new `ExtensionSchema`
+    #[cfg_attr(
+        not(any(
+            feature = "ipaddr",
+            feature = "decimal",
+            feature = "datetime",
+            feature = "partial-eval"
+        )),
+        allow(dead_code)
+    )]
     pub fn new(
         name: Name,
         function_types: impl IntoIterator<Item = ExtensionFunctionType>,
@@ -74,6 +83,15 @@ pub struct ExtensionFunctionType {
 
 impl ExtensionFunctionType {
     /// Create a new `ExtensionFunctionType`
+    #[cfg_attr(
+        not(any(
+            feature = "ipaddr",
+            feature = "decimal",
+            feature = "datetime",
+            feature = "partial-eval"
+        )),
+        allow(dead_code)
+    )]
     pub fn new(
         name: Name,
         argument_types: Vec<Type>,
@@ -106,10 +106,20 @@ impl<'a> ExtensionSchemas<'a> {
     pub fn func_type(&self, name: &Name) -> Option<&ExtensionFunctionType> {
         self.function_types.get(name).copied()
     }
+
+    /// Get the name of the extension function most similar to `name`, if any
+    /// is similar enough to suggest in place of `name`.
+    pub fn suggest_func(&self, name: &Name) -> Option<String> {
+        util::suggest_function_name(name, self.function_types.keys().copied())
+    }
 }
 
 /// Evaluates ane extension function on a single string literal argument. Used
 /// to validate arguments to extension constructor functions.
+#[cfg_attr(
+    not(any(feature = "ipaddr", feature = "decimal", feature = "datetime")),
+    allow(dead_code)
+)]
 fn eval_extension_constructor(
     constructor_name: Name,
     lit_str_arg: SmolStr,
@@ -41,7 +41,10 @@ use thiserror::Error;
 
 use crate::{
     cedar_schema::{
-        self, fmt::ToCedarSchemaSyntaxError, parser::parse_cedar_schema_fragment, SchemaWarning,
+        self,
+        fmt::ToCedarSchemaSyntaxError,
+        parser::{parse_cedar_schema_fragment, parse_cedar_schema_fragment_with_doc_comments},
+        SchemaWarning,
     },
     err::{schema_errors::*, Result},
     AllDefs, CedarSchemaError, CedarSchemaParseError, ConditionalName, RawName, ReferenceType,
@@ -146,22 +149,38 @@ impl<N: Serialize> Serialize for Fragment<N> {
 impl Fragment<RawName> {
     /// Create a [`Fragment`] from a string containing JSON (which should
     /// be an object of the appropriate shape).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err(level = "debug"))
+    )]
     pub fn from_json_str(json: &str) -> Result<Self> {
         serde_json::from_str(json).map_err(|e| JsonDeserializationError::new(e, Some(json)).into())
     }
 
     /// Create a [`Fragment`] from a JSON value (which should be an object
     /// of the appropriate shape).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err(level = "debug"))
+    )]
     pub fn from_json_value(json: serde_json::Value) -> Result<Self> {
         serde_json::from_value(json).map_err(|e| JsonDeserializationError::new(e, None).into())
     }
 
     /// Create a [`Fragment`] directly from a file containing a JSON object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err(level = "debug"))
+    )]
     pub fn from_json_file(file: impl std::io::Read) -> Result<Self> {
         serde_json::from_reader(file).map_err(|e| JsonDeserializationError::new(e, None).into())
     }
 
     /// Parse the schema (in the Cedar schema syntax) from a string
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err(level = "debug"))
+    )]
     pub fn from_cedarschema_str<'a>(
         src: &str,
         extensions: &Extensions<'a>,
@@ -171,6 +190,22 @@ impl Fragment<RawName> {
             .map_err(|e| CedarSchemaParseError::new(e, src).into())
     }
 
+    /// Parse the schema (in the Cedar schema syntax) from a string, turning
+    /// comments on their own line(s) directly before a namespace, declaration,
+    /// or attribute into its `@doc` annotation, unless it already has one
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err(level = "debug"))
+    )]
+    pub fn from_cedarschema_str_with_doc_comments<'a>(
+        src: &str,
+        extensions: &Extensions<'a>,
+    ) -> std::result::Result<(Self, impl Iterator<Item = SchemaWarning> + 'a), CedarSchemaError>
+    {
+        parse_cedar_schema_fragment_with_doc_comments(src, extensions)
+            .map_err(|e| CedarSchemaParseError::new(e, src).into())
+    }
+
     /// Parse the schema (in the Cedar schema syntax) from a reader
     pub fn from_cedarschema_file<'a>(
         mut file: impl std::io::Read,
@@ -189,6 +224,15 @@ impl<N: Display> Fragment<N> {
         let src = cedar_schema::fmt::json_schema_to_cedar_schema_str(self)?;
         Ok(src)
     }
+
+    /// Pretty print this [`Fragment`], writing `@doc` annotations as `//`
+    /// comments
+    pub fn to_cedarschema_with_doc_comments(
+        &self,
+    ) -> std::result::Result<String, ToCedarSchemaSyntaxError> {
+        let src = cedar_schema::fmt::json_schema_to_cedar_schema_str_with_doc_comments(self)?;
+        Ok(src)
+    }
 }
 
 /// An [`UnreservedId`] that cannot be reserved JSON schema keywords
@@ -657,7 +701,9 @@ impl ActionType<ConditionalName> {
 /// with.  This specification can either be done through containing to entity
 /// types.
 /// An empty list is interpreted as specifying that there are no principals or
-/// resources that an action applies to.
+/// resources that an action applies to, unless `anyPrincipal` (resp.
+/// `anyResource`) is set, in which case the action applies to every entity
+/// type declared in the schema.
 ///
 /// The parameter `N` is the type of entity type names and common type names in
 /// this [`ApplySpec`], including recursively.
@@ -673,6 +719,16 @@ pub struct ApplySpec<N> {
     pub resource_types: Vec<N>,
     /// Principal types that are valid for the action
     pub principal_types: Vec<N>,
+    /// Whether the action applies to resources of any entity type. If set,
+    /// `resource_types` must be empty.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub any_resource: bool,
+    /// Whether the action applies to principals of any entity type. If set,
+    /// `principal_types` must be empty.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub any_principal: bool,
     /// Context type that this action expects
     #[serde(default)]
     #[serde(skip_serializing_if = "AttributesOrContext::is_empty_record")]
@@ -696,6 +752,8 @@ impl ApplySpec<RawName> {
                 .into_iter()
                 .map(|rname| rname.conditionally_qualify_with(ns, ReferenceType::Entity)) // Only entity, not common, here for now; see #1064
                 .collect(),
+            any_resource: self.any_resource,
+            any_principal: self.any_principal,
             context: self.context.conditionally_qualify_type_references(ns),
         }
     }
@@ -723,6 +781,8 @@ impl ApplySpec<ConditionalName> {
                 .into_iter()
                 .map(|cname| cname.resolve(all_defs))
                 .collect::<std::result::Result<_, TypeNotDefinedError>>()?,
+            any_resource: self.any_resource,
+            any_principal: self.any_principal,
             context: self.context.fully_qualify_type_references(all_defs)?,
         })
     }
@@ -769,6 +829,12 @@ impl ActionEntityUID<RawName> {
     pub fn default_type(id: SmolStr) -> Self {
         Self { id, ty: None }
     }
+
+    /// Get the type of this `ActionEntityUID`, as written in the schema.
+    /// `None` is shorthand for `Action`.
+    pub fn raw_ty(&self) -> Option<&RawName> {
+        self.ty.as_ref()
+    }
 }
 
 impl<N: std::fmt::Display> std::fmt::Display for ActionEntityUID<N> {
@@ -850,7 +916,7 @@ impl ActionEntityUID<ConditionalName> {
                 }
             }
         }
-        Err(ActionNotDefinedError(nonempty!(self)))
+        Err(ActionNotDefinedError(nonempty!(self), vec![]))
     }
 
     /// Get the possible fully-qualified [`ActionEntityUID<InternalName>`]s
@@ -1882,6 +1948,8 @@ mod test {
         let spec = ApplySpec {
             resource_types: vec!["Album".parse().unwrap()],
             principal_types: vec!["User".parse().unwrap()],
+            any_resource: false,
+            any_principal: false,
             context: AttributesOrContext::default(),
         };
         assert_eq!(at.applies_to, Some(spec));
@@ -2826,6 +2894,8 @@ mod test_json_roundtrip {
                         applies_to: Some(ApplySpec {
                             resource_types: vec!["a".parse().unwrap()],
                             principal_types: vec!["a".parse().unwrap()],
+                            any_resource: false,
+                            any_principal: false,
                             context: AttributesOrContext(Type::Type(TypeVariant::Record(
                                 RecordType {
                                     attributes: BTreeMap::new(),
@@ -2884,6 +2954,8 @@ mod test_json_roundtrip {
                             applies_to: Some(ApplySpec {
                                 resource_types: vec!["foo::a".parse().unwrap()],
                                 principal_types: vec!["foo::a".parse().unwrap()],
+                                any_resource: false,
+                                any_principal: false,
                                 context: AttributesOrContext(Type::Type(TypeVariant::Record(
                                     RecordType {
                                         attributes: BTreeMap::new(),
@@ -3260,7 +3332,8 @@ mod annotations {
         "`commonTypes`, `entityTypes`, `actions`, `annotations`";
     const ATTRIBUTE_TYPE_EXPECTED_ATTRIBUTES: &str =
         "`type`, `element`, `attributes`, `additionalAttributes`, `name`";
-    const APPLIES_TO_EXPECTED_ATTRIBUTES: &str = "`resourceTypes`, `principalTypes`, `context`";
+    const APPLIES_TO_EXPECTED_ATTRIBUTES: &str =
+        "`resourceTypes`, `principalTypes`, `anyResource`, `anyPrincipal`, `context`";
 
     #[test]
     fn unknown_fields() {
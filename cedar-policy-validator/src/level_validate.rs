@@ -43,7 +43,7 @@ impl Validator {
         if peekable_errors.peek().is_none() {
             let levels_errors = self.check_entity_deref_level(
                 p,
-                mode,
+                Self::policy_mode(p, mode).0,
                 &EntityDerefLevel::from(max_deref_level),
                 p.id(),
             );
@@ -34,32 +34,61 @@ pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/cedar_policy_validator.rs"));
 }
 
-use cedar_policy_core::ast::{Policy, PolicySet, Template};
+use cedar_policy_core::ast::{EntityType, Expr, Policy, PolicyID, PolicySet, SlotId, Template};
 use serde::Serialize;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 #[cfg(feature = "level-validate")]
 mod level_validate;
 
+mod completion;
+pub use completion::{Completion, CompletionKind, Completions};
 mod coreschema;
 #[cfg(feature = "entity-manifest")]
 pub mod entity_manifest;
 pub use coreschema::*;
+mod dependencies;
+mod deprecation;
+pub use dependencies::{Dependency, DependencyGraph};
 mod diagnostics;
 pub use diagnostics::*;
+mod expiration;
+pub use expiration::{
+    expiring_policies, ExpirationDate, ExpirationRules, ParseExpirationDateError, PolicyExpiration,
+};
 mod expr_iterator;
 mod extension_schema;
 mod extensions;
+mod permit_conflicts;
+pub use permit_conflicts::PermitConflict;
+mod namespaces;
 mod rbac;
+mod reachability;
+pub use reachability::ActionReachability;
+mod references;
+pub use references::{Reference, ReferenceKind, SchemaElement};
+mod policy_mode;
+mod profile;
+mod qualify;
+pub use profile::{TypecheckStats, ValidationProfile};
+mod rename;
+pub use rename::{PolicyEdit, PolicyRename, Rename, RenameError, SkippedOccurrence};
+mod type_at_offset;
+pub use type_at_offset::{TypeAtOffset, TypeInRequestEnv};
+mod unused_attrs;
+pub use unused_attrs::UnusedAttribute;
 mod schema;
 pub use schema::err::*;
 pub use schema::*;
 pub mod json_schema;
+mod sensitive_attrs;
+pub use sensitive_attrs::SensitiveAttributeRules;
 mod str_checks;
 pub use str_checks::confusable_string_checks;
 pub mod cedar_schema;
 pub mod typecheck;
 use typecheck::Typechecker;
 pub mod types;
+use types::{RequestEnv, Type};
 
 /// Used to select how a policy will be validated.
 #[derive(Default, Eq, PartialEq, Copy, Clone, Debug, Serialize)]
@@ -126,31 +155,88 @@ impl From<&proto::ValidationMode> for ValidationMode {
 #[derive(Debug, Clone)]
 pub struct Validator {
     schema: ValidatorSchema,
+    sensitive_attribute_rules: SensitiveAttributeRules,
+    expiration_rules: Option<ExpirationRules>,
+    allowed_namespaces: Option<BTreeSet<String>>,
 }
 
 impl Validator {
     /// Construct a new Validator from a schema file.
     pub fn new(schema: ValidatorSchema) -> Validator {
-        Self { schema }
+        Self {
+            schema,
+            sensitive_attribute_rules: SensitiveAttributeRules::default(),
+            expiration_rules: None,
+            allowed_namespaces: None,
+        }
+    }
+
+    /// Get the schema policies are validated against
+    pub fn schema(&self) -> &ValidatorSchema {
+        &self.schema
+    }
+
+    /// Set which uses of attributes annotated as sensitive (`@pii`) in the
+    /// schema are reported as warnings.
+    pub fn with_sensitive_attribute_rules(mut self, rules: SensitiveAttributeRules) -> Self {
+        self.sensitive_attribute_rules = rules;
+        self
+    }
+
+    /// Check the `@expires` annotations of policies against `rules`. Without
+    /// expiration rules, these annotations are not checked.
+    pub fn with_expiration_rules(mut self, rules: ExpirationRules) -> Self {
+        self.expiration_rules = Some(rules);
+        self
     }
 
     /// Validate all templates, links, and static policies in a policy set.
     /// Return a `ValidationResult`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(mode = ?mode))
+    )]
     pub fn validate(&self, policies: &PolicySet, mode: ValidationMode) -> ValidationResult {
         let validate_policy_results: (Vec<_>, Vec<_>) = policies
             .all_templates()
             .map(|p| self.validate_policy(p, mode))
             .unzip();
-        let template_and_static_policy_errs = validate_policy_results.0.into_iter().flatten();
-        let template_and_static_policy_warnings = validate_policy_results.1.into_iter().flatten();
+        self.policy_set_result(
+            policies,
+            mode,
+            validate_policy_results.0.into_iter().flatten(),
+            validate_policy_results.1.into_iter().flatten(),
+        )
+    }
+
+    /// Combine the errors and warnings found in the templates and static
+    /// policies of `policies` with the results of the checks of its links and
+    /// of the checks applying to the whole policy set
+    fn policy_set_result(
+        &self,
+        policies: &PolicySet,
+        mode: ValidationMode,
+        template_and_static_policy_errs: impl Iterator<Item = ValidationError>,
+        template_and_static_policy_warnings: impl Iterator<Item = ValidationWarning>,
+    ) -> ValidationResult {
         let link_errs = policies
             .policies()
             .filter_map(|p| self.validate_slots(p, mode))
-            .flatten();
+            .flatten()
+            .chain(
+                policies
+                    .policies()
+                    .flat_map(|p| self.validate_namespaces_in_slots(p)),
+            );
         ValidationResult::new(
             template_and_static_policy_errs.chain(link_errs),
             template_and_static_policy_warnings
-                .chain(confusable_string_checks(policies.all_templates())),
+                .chain(confusable_string_checks(policies.all_templates()))
+                .chain(
+                    policies
+                        .all_templates()
+                        .flat_map(|p| self.deprecation_checks(p, Self::policy_mode(p, mode).0)),
+                ),
         )
     }
 
@@ -159,6 +245,10 @@ impl Validator {
     /// If validation passes, also run level validation with `max_deref_level`
     /// (see RFC 76).
     /// Return a `ValidationResult`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(mode = ?mode))
+    )]
     pub fn validate_with_level(
         &self,
         policies: &PolicySet,
@@ -174,14 +264,126 @@ impl Validator {
         let link_errs = policies
             .policies()
             .filter_map(|p| self.validate_slots(p, mode))
-            .flatten();
+            .flatten()
+            .chain(
+                policies
+                    .policies()
+                    .flat_map(|p| self.validate_namespaces_in_slots(p)),
+            );
         ValidationResult::new(
             template_and_static_policy_errs.chain(link_errs),
             template_and_static_policy_warnings
-                .chain(confusable_string_checks(policies.all_templates())),
+                .chain(confusable_string_checks(policies.all_templates()))
+                .chain(
+                    policies
+                        .all_templates()
+                        .flat_map(|p| self.deprecation_checks(p, Self::policy_mode(p, mode).0)),
+                ),
         )
     }
 
+    /// Validate a single template on its own, before any links of it exist.
+    /// Each slot is typechecked with every entity type it could be linked
+    /// with according to the template scope and the schema, narrowed to the
+    /// entity types in `slot_bounds` for slots which have an entry there.
+    /// Errors are reported against the template source.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(mode = ?mode))
+    )]
+    pub fn validate_template(
+        &self,
+        t: &Template,
+        slot_bounds: HashMap<SlotId, HashSet<EntityType>>,
+        mode: ValidationMode,
+    ) -> ValidationResult {
+        let bound_errs = if mode.is_partial() {
+            Vec::new()
+        } else {
+            self.validate_slot_bounds(t, &slot_bounds).collect()
+        };
+        let (errs, warnings) = self.validate_policy_with_slot_bounds(
+            t,
+            slot_bounds,
+            mode,
+            &mut TypecheckStats::default(),
+        );
+        let policy_mode = Self::policy_mode(t, mode).0;
+        ValidationResult::new(
+            bound_errs.into_iter().chain(errs),
+            warnings
+                .chain(confusable_string_checks(std::iter::once(t)))
+                .chain(self.deprecation_checks(t, policy_mode)),
+        )
+    }
+
+    /// Typecheck the standalone expression `e` under `request_env`, which need
+    /// not be built from the schema, returning the type of `e`. Unlike a
+    /// policy condition, `e` may have any type. Errors are reported against
+    /// the policy id `expression`.
+    pub fn typecheck_expr(
+        &self,
+        e: &Expr,
+        request_env: &RequestEnv<'_>,
+        mode: ValidationMode,
+    ) -> std::result::Result<Type, Vec<ValidationError>> {
+        let typechecker = Typechecker::new(&self.schema, mode, PolicyID::from_string("expression"));
+        let mut type_errors = Vec::new();
+        let ty = typechecker.typecheck_standalone_expr(request_env, e, &mut type_errors);
+        ty.ok_or(type_errors)
+    }
+
+    /// Validate policies one at a time as `policies` produces them, yielding
+    /// each policy with its result as soon as it has been checked,
+    /// instead of waiting for all of them like [`Validator::validate`]. The
+    /// diagnostics for a template are reported with the first of its links;
+    /// the other links only report errors in their slot values.
+    pub fn validate_stream<'a>(
+        &'a self,
+        policies: impl IntoIterator<Item = Policy> + 'a,
+        mode: ValidationMode,
+    ) -> impl Iterator<Item = (Policy, ValidationResult)> + 'a {
+        let mut seen_templates = HashSet::new();
+        policies.into_iter().map(move |p| {
+            let new_template = seen_templates.insert(p.template().id().clone());
+            let result = self.validate_one(&p, new_template, mode);
+            (p, result)
+        })
+    }
+
+    /// Validate a single policy, including its template if `with_template`
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(id = %p.id()))
+    )]
+    fn validate_one(
+        &self,
+        p: &Policy,
+        with_template: bool,
+        mode: ValidationMode,
+    ) -> ValidationResult {
+        let (errs, warnings): (Vec<_>, Vec<_>) = if with_template {
+            let t = p.template();
+            let policy_mode = Self::policy_mode(t, mode).0;
+            let (errs, warnings) = self.validate_policy(t, mode);
+            (
+                errs.collect(),
+                warnings
+                    .chain(confusable_string_checks(std::iter::once(t)))
+                    .chain(self.deprecation_checks(t, policy_mode))
+                    .collect(),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        let link_errs = self
+            .validate_slots(p, mode)
+            .into_iter()
+            .flatten()
+            .chain(self.validate_namespaces_in_slots(p));
+        ValidationResult::new(errs.into_iter().chain(link_errs), warnings)
+    }
+
     /// Run all validations against a single static policy or template (note
     /// that Core `Template` includes static policies as well), gathering all
     /// validation errors and warnings in the returned iterators.
@@ -193,6 +395,30 @@ impl Validator {
         impl Iterator<Item = ValidationError> + 'a,
         impl Iterator<Item = ValidationWarning> + 'a,
     ) {
+        self.validate_policy_with_slot_bounds(
+            p,
+            HashMap::new(),
+            mode,
+            &mut TypecheckStats::default(),
+        )
+    }
+
+    /// Run all validations against a single static policy or template,
+    /// typechecking its slots only with links to the entity types in
+    /// `slot_bounds` (see [`Validator::validate_template`]). How much work
+    /// typechecking did is recorded in `stats`.
+    fn validate_policy_with_slot_bounds<'a>(
+        &'a self,
+        p: &'a Template,
+        slot_bounds: HashMap<SlotId, HashSet<EntityType>>,
+        mode: ValidationMode,
+        stats: &mut TypecheckStats,
+    ) -> (
+        impl Iterator<Item = ValidationError> + 'a,
+        impl Iterator<Item = ValidationWarning> + 'a,
+    ) {
+        let (mode, mode_error, mode_warning) = Self::policy_mode(p, mode);
+        let (expiration_error, expiration_warning) = self.expiration_check(p);
         let validation_errors = if mode.is_partial() {
             // We skip `validate_entity_types`, `validate_action_ids`, and
             // `validate_action_application` passes for partial schema
@@ -212,8 +438,19 @@ impl Validator {
         }
         .into_iter()
         .flatten();
-        let (errors, warnings) = self.typecheck_policy(p, mode);
-        (validation_errors.chain(errors), warnings)
+        let (errors, warnings) = self.typecheck_policy(p, slot_bounds, mode, stats);
+        (
+            mode_error
+                .into_iter()
+                .chain(expiration_error)
+                .chain(self.validate_namespaces(p))
+                .chain(validation_errors)
+                .chain(errors),
+            mode_warning
+                .into_iter()
+                .chain(expiration_warning)
+                .chain(warnings),
+        )
     }
 
     /// Run relevant validations against a single template-linked policy,
@@ -237,7 +474,7 @@ impl Validator {
         // `Policy::resource_constraint()` return a copy of the constraint with
         // the slot filled by the appropriate value.
         Some(
-            self.validate_entity_types_in_slots(p.id(), p.env())
+            self.validate_entity_types_in_slots(p)
                 .chain(self.validate_linked_action_application(p)),
         )
     }
@@ -246,25 +483,36 @@ impl Validator {
     /// the argument static policy or template (note that Core `Template`
     /// includes static policies as well) in the context of the schema for this
     /// validator. Any detected type errors are wrapped and returned as
-    /// `ValidationErrorKind`s.
+    /// `ValidationErrorKind`s. How much work typechecking did is recorded in
+    /// `stats`.
     fn typecheck_policy<'a>(
         &'a self,
         t: &'a Template,
+        slot_bounds: HashMap<SlotId, HashSet<EntityType>>,
         mode: ValidationMode,
+        stats: &mut TypecheckStats,
     ) -> (
         impl Iterator<Item = ValidationError> + 'a,
         impl Iterator<Item = ValidationWarning> + 'a,
     ) {
-        let typecheck = Typechecker::new(&self.schema, mode, t.id().clone());
+        let typecheck =
+            Typechecker::new(&self.schema, mode, t.id().clone()).with_slot_bounds(slot_bounds);
         let mut errors = HashSet::new();
         let mut warnings = HashSet::new();
-        typecheck.typecheck_policy(t, &mut errors, &mut warnings);
-        (errors.into_iter(), warnings.into_iter())
+        let typecheck_answers = typecheck.typecheck_by_request_env(t);
+        let sensitive_warnings = self.sensitive_attribute_checks(t, &typecheck_answers);
+        typecheck.consolidate_request_env_checks(t, typecheck_answers, &mut errors, &mut warnings);
+        *stats = typecheck.stats();
+        (
+            errors.into_iter(),
+            warnings.into_iter().chain(sensitive_warnings),
+        )
     }
 }
 
 #[cfg(test)]
 mod test {
+    use cool_asserts::assert_matches;
     use itertools::Itertools;
     use std::{collections::HashMap, sync::Arc};
 
@@ -312,6 +560,8 @@ mod test {
                     applies_to: Some(json_schema::ApplySpec {
                         principal_types: vec!["foo_type".parse().unwrap()],
                         resource_types: vec!["bar_type".parse().unwrap()],
+                        any_resource: false,
+                        any_principal: false,
                         context: json_schema::AttributesOrContext::default(),
                     }),
                     member_of: None,
@@ -427,6 +677,7 @@ mod test {
         )
         .expect("Parse Error");
         let loc = t.loc().cloned();
+        let slot_loc = loc.as_ref().map(|loc| loc.span(71..80));
         set.add_template(t)
             .expect("Template already present in PolicySet");
 
@@ -476,7 +727,7 @@ mod test {
         assert!(!result.validation_passed());
         assert_eq!(result.validation_errors().count(), 2);
         let undefined_err = ValidationError::unrecognized_entity_type(
-            None,
+            slot_loc,
             PolicyID::from_string("link2"),
             "some_namespace::Undefined".to_string(),
             Some("some_namespace::User".to_string()),
@@ -484,6 +735,17 @@ mod test {
         let invalid_action_err = ValidationError::invalid_action_application(
             loc.clone(),
             PolicyID::from_string("link2"),
+            Some(ast::LinkBindings {
+                template_id: PolicyID::from_string("template"),
+                values: vec![(
+                    ast::SlotId::resource(),
+                    ast::EntityUID::from_components(
+                        "some_namespace::Undefined".parse().unwrap(),
+                        ast::Eid::new("foo"),
+                        None,
+                    ),
+                )],
+            }),
             false,
             false,
         );
@@ -513,6 +775,17 @@ mod test {
         let invalid_action_err = ValidationError::invalid_action_application(
             loc,
             PolicyID::from_string("link3"),
+            Some(ast::LinkBindings {
+                template_id: PolicyID::from_string("template"),
+                values: vec![(
+                    ast::SlotId::resource(),
+                    ast::EntityUID::from_components(
+                        "some_namespace::User".parse().unwrap(),
+                        ast::Eid::new("foo"),
+                        None,
+                    ),
+                )],
+            }),
             false,
             false,
         );
@@ -572,4 +845,215 @@ mod test {
             )]
         );
     }
+
+    #[test]
+    fn validate_template_with_slot_bounds() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity Group;
+            entity User in Group;
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let template = parser::parse_policy_or_template(
+            Some(PolicyID::from_string("t")),
+            r#"permit(principal in ?principal, action == Action::"view", resource);"#,
+        )
+        .unwrap();
+        let bounds = |tys: &[&str]| {
+            HashMap::from([(
+                SlotId::principal(),
+                tys.iter().map(|ty| ty.parse().unwrap()).collect(),
+            )])
+        };
+
+        let result = validator.validate_template(&template, HashMap::new(), ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(result.validation_warnings().count(), 0);
+
+        let result =
+            validator.validate_template(&template, bounds(&["Group"]), ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(result.validation_warnings().count(), 0);
+
+        // `principal` is a `User`, so it can't be in a `Doc`
+        let result =
+            validator.validate_template(&template, bounds(&["Doc"]), ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(
+            result.validation_warnings().collect::<Vec<_>>(),
+            vec![&ValidationWarning::impossible_policy(
+                template.loc().cloned(),
+                PolicyID::from_string("t")
+            )]
+        );
+
+        let result =
+            validator.validate_template(&template, bounds(&["Grop"]), ValidationMode::Strict);
+        assert_eq!(
+            result.validation_errors().collect::<Vec<_>>(),
+            vec![&ValidationError::unrecognized_entity_type(
+                template.loc().cloned(),
+                PolicyID::from_string("t"),
+                "Grop".to_string(),
+                Some("Group".to_string()),
+            )]
+        );
+    }
+
+    #[test]
+    fn typecheck_standalone_expr() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { name: String, age?: Long };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let principal: EntityType = "User".parse().unwrap();
+        let resource: EntityType = "Doc".parse().unwrap();
+        let action: ast::EntityUID = r#"Action::"view""#.parse().unwrap();
+        let context = Type::record_with_attributes(
+            [(
+                "ip".into(),
+                types::AttributeType::required_attribute(Type::primitive_string()),
+            )],
+            types::OpenTag::ClosedAttributes,
+        );
+        let env = RequestEnv::DeclaredAction {
+            principal: &principal,
+            action: &action,
+            resource: &resource,
+            context: &context,
+            principal_slot: None,
+            resource_slot: None,
+        };
+        let typecheck = |src: &str| {
+            let e: Expr = src.parse().unwrap();
+            validator.typecheck_expr(&e, &env, ValidationMode::Strict)
+        };
+
+        assert_eq!(typecheck("principal.name"), Ok(Type::primitive_string()));
+        assert_eq!(typecheck("context.ip"), Ok(Type::primitive_string()));
+        assert_eq!(
+            typecheck("[resource]"),
+            Ok(Type::set(Type::named_entity_reference(resource.clone())))
+        );
+        assert_matches!(typecheck("principal has age && principal.age > 3"), Ok(ty) => {
+            assert!(Type::is_subtype(
+                validator.schema(),
+                &ty,
+                &Type::primitive_boolean(),
+                ValidationMode::Strict
+            ));
+        });
+        assert_matches!(typecheck("principal.age"), Err(errs) => {
+            assert_matches!(errs.as_slice(), [ValidationError::UnsafeOptionalAttributeAccess(_)]);
+        });
+        assert_matches!(typecheck("context.ip + 1"), Err(errs) => {
+            assert_matches!(errs.as_slice(), [ValidationError::UnexpectedType(_)]);
+        });
+    }
+
+    #[test]
+    fn validate_template_reports_body_errors() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { name: String };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let src = r#"permit(principal, action == Action::"view", resource in ?resource) when { principal.name > 1 };"#;
+        let template =
+            parser::parse_policy_or_template(Some(PolicyID::from_string("t")), src).unwrap();
+        let result = validator.validate_template(&template, HashMap::new(), ValidationMode::Strict);
+        assert_eq!(
+            result.validation_errors().collect::<Vec<_>>(),
+            vec![&ValidationError::expected_type(
+                typecheck::test::test_utils::get_loc(src, "principal.name"),
+                PolicyID::from_string("t"),
+                Type::primitive_long(),
+                Type::primitive_string(),
+                None,
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_stream_matches_validate() {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { name: String };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let mut set = PolicySet::new();
+        set.add_static(
+            parser::parse_policy(
+                Some(PolicyID::from_string("static")),
+                r#"permit(principal, action == Action::"view", resource) when { principal.name > 1 };"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        set.add_template(
+            parser::parse_policy_or_template(
+                Some(PolicyID::from_string("t")),
+                r#"permit(principal, action == Action::"view", resource == ?resource) when { principal.name == "" && 1 };"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        for (id, eid) in [("link0", r#"Doc::"a""#), ("link1", r#"User::"b""#)] {
+            set.link(
+                PolicyID::from_string("t"),
+                PolicyID::from_string(id),
+                HashMap::from([(ast::SlotId::resource(), eid.parse().unwrap())]),
+            )
+            .unwrap();
+        }
+
+        let policies = ["static", "link0", "link1"]
+            .map(|id| set.get(&PolicyID::from_string(id)).unwrap().clone());
+        let results = validator
+            .validate_stream(policies, ValidationMode::Strict)
+            .map(|(p, result)| (p.id().to_string(), result))
+            .collect::<Vec<_>>();
+        // the template error is reported with its first link only, and the
+        // second link only has an error for its slot value
+        assert_eq!(
+            results
+                .iter()
+                .map(|(id, result)| (id.as_str(), result.validation_errors().count()))
+                .collect::<Vec<_>>(),
+            vec![("static", 1), ("link0", 1), ("link1", 1)]
+        );
+        // and all together, the errors are the same as from `validate()`
+        let streamed = results
+            .iter()
+            .flat_map(|(_, result)| result.validation_errors())
+            .sorted_by_key(|e| e.to_string())
+            .collect::<Vec<_>>();
+        let result = validator.validate(&set, ValidationMode::Strict);
+        let batched = result
+            .validation_errors()
+            .sorted_by_key(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(streamed, batched);
+    }
 }
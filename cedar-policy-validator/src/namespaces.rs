@@ -0,0 +1,192 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checks that policies only reference entity types and actions in an
+//! allow-listed set of namespaces
+
+use super::*;
+use cedar_policy_core::ast::{ExprKind, Literal, Name, PolicyID};
+use cedar_policy_core::parser::Loc;
+
+impl Validator {
+    /// Only allow policies to reference entity types and actions in the given
+    /// namespaces, where `None` is the empty namespace. References to anything
+    /// in another namespace, including in the slot values of template-linked
+    /// policies, are reported as errors. Namespaces must match exactly, so
+    /// allowing `A` does not allow `A::B`.
+    pub fn with_allowed_namespaces(
+        mut self,
+        namespaces: impl IntoIterator<Item = Option<Name>>,
+    ) -> Self {
+        self.allowed_namespaces = Some(
+            namespaces
+                .into_iter()
+                .map(|ns| ns.map(|ns| ns.to_string()).unwrap_or_default())
+                .collect(),
+        );
+        self
+    }
+
+    /// Check that the entity types and actions `t` references are in the
+    /// allowed namespaces, if there are any
+    pub(crate) fn validate_namespaces(
+        &self,
+        t: &Template,
+    ) -> impl Iterator<Item = ValidationError> {
+        let mut errors = HashSet::new();
+        if self.allowed_namespaces.is_some() {
+            for sub in t.condition().subexpressions() {
+                let ety = match sub.expr_kind() {
+                    ExprKind::Lit(Literal::EntityUID(euid)) => euid.entity_type(),
+                    ExprKind::Is { entity_type, .. } => entity_type,
+                    _ => continue,
+                };
+                errors.extend(self.check_namespace(
+                    ety,
+                    sub.source_loc().or_else(|| t.loc()),
+                    t.id(),
+                ));
+            }
+        }
+        errors.into_iter()
+    }
+
+    /// Check that the slot values of the template-linked policy `p` are in the
+    /// allowed namespaces, if there are any
+    pub(crate) fn validate_namespaces_in_slots<'a>(
+        &'a self,
+        p: &'a Policy,
+    ) -> impl Iterator<Item = ValidationError> + 'a {
+        p.env().iter().filter_map(|(slot, euid)| {
+            self.check_namespace(euid.entity_type(), crate::rbac::slot_loc(p, *slot), p.id())
+        })
+    }
+
+    fn check_namespace(
+        &self,
+        ety: &EntityType,
+        loc: Option<&Loc>,
+        policy_id: &PolicyID,
+    ) -> Option<ValidationError> {
+        let allowed = self.allowed_namespaces.as_ref()?;
+        let namespace = ety.name().as_ref().namespace();
+        if allowed.contains(&namespace) {
+            None
+        } else {
+            Some(ValidationError::disallowed_namespace(
+                loc.cloned(),
+                policy_id.clone(),
+                ety.clone(),
+                allowed.iter().cloned().collect(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::ast::SlotId;
+    use cedar_policy_core::parser::parse_policyset;
+    use itertools::Itertools;
+
+    fn validator(namespaces: &[&str]) -> Validator {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            namespace TenantA {
+                entity User;
+                action view appliesTo { principal: User, resource: User };
+            }
+            namespace TenantB {
+                entity User;
+                action view appliesTo { principal: User, resource: User };
+            }
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        Validator::new(schema).with_allowed_namespaces(
+            namespaces
+                .iter()
+                .map(|ns| (!ns.is_empty()).then(|| ns.parse().unwrap())),
+        )
+    }
+
+    fn disallowed(validator: &Validator, pset: &PolicySet) -> Vec<String> {
+        validator
+            .validate(pset, ValidationMode::Strict)
+            .validation_errors()
+            .filter_map(|e| match e {
+                ValidationError::DisallowedNamespace(e) => Some(e.entity_type.to_string()),
+                _ => None,
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn references_outside_allowed_namespaces() {
+        let pset = parse_policyset(
+            r#"
+            permit(principal is TenantA::User, action == TenantA::Action::"view", resource);
+            permit(principal == TenantB::User::"bob", action, resource)
+            when { resource in TenantB::User::"alice" };
+            permit(principal, action in [TenantA::Action::"view", TenantB::Action::"view"], resource);
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            disallowed(&validator(&["TenantA"]), &pset),
+            vec!["TenantB::Action", "TenantB::User", "TenantB::User"]
+        );
+        assert_eq!(
+            disallowed(&validator(&["TenantA", "TenantB"]), &pset),
+            Vec::<String>::new()
+        );
+        assert_eq!(disallowed(&validator(&[""]), &pset).len(), 6);
+    }
+
+    #[test]
+    fn linked_slot_values() {
+        let mut pset = parse_policyset(
+            r#"permit(principal == ?principal, action == TenantA::Action::"view", resource);"#,
+        )
+        .unwrap();
+        for (i, user) in [r#"TenantA::User::"alice""#, r#"TenantB::User::"bob""#]
+            .iter()
+            .enumerate()
+        {
+            pset.link(
+                PolicyID::from_string("policy0"),
+                PolicyID::from_string(format!("link{i}")),
+                HashMap::from([(SlotId::principal(), user.parse().unwrap())]),
+            )
+            .unwrap();
+        }
+        let result = validator(&["TenantA"]).validate(&pset, ValidationMode::Strict);
+        let errs = result
+            .validation_errors()
+            .filter(|e| matches!(e, ValidationError::DisallowedNamespace(_)))
+            .collect::<Vec<_>>();
+        assert!(
+            matches!(
+                errs.as_slice(),
+                [ValidationError::DisallowedNamespace(e)] if e.policy_id.to_string() == "link1"
+            ),
+            "{errs:?}"
+        );
+    }
+}
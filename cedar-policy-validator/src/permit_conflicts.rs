@@ -0,0 +1,197 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Analysis finding `permit` policies with different owners which may grant
+//! access to the same kinds of requests
+
+use std::collections::BTreeSet;
+
+use cedar_policy_core::ast::{Effect, EntityType, EntityUID, Policy, PolicyID, PolicySet};
+use smol_str::SmolStr;
+
+use crate::Validator;
+
+/// Two `permit` policies, owned by different owners, whose scopes overlap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermitConflict {
+    /// The two policies, ordered by policy id
+    pub policies: (PolicyID, PolicyID),
+    /// The owners of the two policies, in the same order as `policies`
+    pub owners: (SmolStr, SmolStr),
+    /// Every (principal type, action, resource type) triple both policies
+    /// may apply to
+    pub shared_request_types: BTreeSet<(EntityType, EntityUID, EntityType)>,
+}
+
+impl Validator {
+    /// Find all pairs of static or template-linked `permit` policies in
+    /// `policies` which have different values for the `owner_annotation`
+    /// annotation and which may both apply to some (principal type, action,
+    /// resource type) triple in the schema. Policies without the annotation
+    /// are not considered. As for [`Validator::policy_may_apply_to`], only
+    /// the policy scopes are compared, so the conditions of two conflicting
+    /// policies may still be disjoint.
+    ///
+    /// The results are sorted by policy ids.
+    pub fn permit_conflicts(
+        &self,
+        policies: &PolicySet,
+        owner_annotation: &str,
+    ) -> Vec<PermitConflict> {
+        let owned = policies
+            .policies()
+            .filter(|p| p.effect() == Effect::Permit)
+            .filter_map(|p| {
+                let (_, owner) = p
+                    .annotations()
+                    .find(|(key, _)| key.as_ref() == owner_annotation)?;
+                Some((p, owner.val.clone(), self.request_types(p)))
+            })
+            .collect::<Vec<_>>();
+        let mut conflicts = Vec::new();
+        for (i, (p1, owner1, types1)) in owned.iter().enumerate() {
+            for (p2, owner2, types2) in owned.iter().skip(i + 1) {
+                if owner1 == owner2 {
+                    continue;
+                }
+                let shared = types1
+                    .intersection(types2)
+                    .cloned()
+                    .collect::<BTreeSet<_>>();
+                if shared.is_empty() {
+                    continue;
+                }
+                let ((p1, owner1), (p2, owner2)) = if p1.id() <= p2.id() {
+                    ((p1, owner1), (p2, owner2))
+                } else {
+                    ((p2, owner2), (p1, owner1))
+                };
+                conflicts.push(PermitConflict {
+                    policies: (p1.id().clone(), p2.id().clone()),
+                    owners: (owner1.clone(), owner2.clone()),
+                    shared_request_types: shared,
+                });
+            }
+        }
+        conflicts.sort_by(|a, b| a.policies.cmp(&b.policies));
+        conflicts
+    }
+
+    /// All (principal type, action, resource type) triples in the schema
+    /// which `p` may apply to
+    fn request_types(&self, p: &Policy) -> BTreeSet<(EntityType, EntityUID, EntityType)> {
+        self.schema
+            .actions()
+            .filter_map(|action| self.schema.get_action_id(action))
+            .flat_map(|action| {
+                action.principals().flat_map(move |principal| {
+                    action
+                        .resources()
+                        .map(move |resource| (principal, &action.name, resource))
+                })
+            })
+            .filter(|(principal, action, resource)| {
+                self.policy_may_apply_to(p, principal, action, resource)
+            })
+            .map(|(principal, action, resource)| {
+                (principal.clone(), action.clone(), resource.clone())
+            })
+            .collect()
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::parser::parse_policyset;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                entity Team;
+                entity User in [Team];
+                entity Doc;
+                entity Device;
+                action view appliesTo { principal: User, resource: [Doc, Device] };
+                action edit appliesTo { principal: User, resource: Doc };
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[test]
+    fn overlapping_owners() {
+        let pset = parse_policyset(
+            r#"
+            @id("a") @owner("alice")
+            permit(principal, action == Action::"view", resource is Doc);
+            @id("b") @owner("bob")
+            permit(principal in Team::"admins", action, resource);
+            @id("c") @owner("bob")
+            permit(principal, action == Action::"view", resource is Device);
+            @id("d") @owner("carol")
+            forbid(principal, action, resource);
+            @id("e")
+            permit(principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let conflicts = validator().permit_conflicts(&pset, "owner");
+        let [conflict] = conflicts.as_slice() else {
+            panic!("expected exactly one conflict, got {conflicts:?}");
+        };
+        assert_eq!(
+            conflict.policies,
+            (
+                PolicyID::from_string("policy0"),
+                PolicyID::from_string("policy1")
+            )
+        );
+        assert_eq!(conflict.owners, ("alice".into(), "bob".into()));
+        assert_eq!(
+            conflict.shared_request_types,
+            BTreeSet::from([(
+                "User".parse().unwrap(),
+                r#"Action::"view""#.parse().unwrap(),
+                "Doc".parse().unwrap()
+            )])
+        );
+    }
+
+    #[test]
+    fn same_owner_is_not_a_conflict() {
+        let pset = parse_policyset(
+            r#"
+            @owner("alice")
+            permit(principal, action, resource);
+            @owner("alice")
+            permit(principal, action == Action::"edit", resource);
+            @team("bob")
+            permit(principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        assert_eq!(validator().permit_conflicts(&pset, "owner"), vec![]);
+        assert_eq!(validator().permit_conflicts(&pset, "team").len(), 0);
+    }
+}
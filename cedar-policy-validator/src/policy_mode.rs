@@ -0,0 +1,167 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Per-policy overrides of the validation mode using the `@validation`
+//! policy annotation
+
+use super::*;
+
+/// Policy annotation overriding the validation mode for that policy
+pub(crate) const VALIDATION_MODE_ANNOTATION: &str = "validation";
+
+impl Validator {
+    /// Get the mode to validate `t` in, given that the whole policy set is
+    /// validated in `mode`. This is `mode` unless `t` has a `@validation`
+    /// annotation choosing `"strict"` or `"permissive"` mode. Partial
+    /// validation is never overridden, since it depends on the schema rather
+    /// than the policy.
+    ///
+    /// Also returns an error if the annotation value is not a mode, or a
+    /// warning if the annotation changes the mode, so that the override is
+    /// visible in the validation result.
+    pub(crate) fn policy_mode(
+        t: &Template,
+        mode: ValidationMode,
+    ) -> (
+        ValidationMode,
+        Option<ValidationError>,
+        Option<ValidationWarning>,
+    ) {
+        if mode.is_partial() {
+            return (mode, None, None);
+        }
+        let Some((_, annotation)) = t
+            .annotations()
+            .find(|(key, _)| key.as_ref() == VALIDATION_MODE_ANNOTATION)
+        else {
+            return (mode, None, None);
+        };
+        let overridden = match annotation.val.as_str() {
+            "strict" => ValidationMode::Strict,
+            "permissive" => ValidationMode::Permissive,
+            _ => {
+                return (
+                    mode,
+                    Some(ValidationError::invalid_validation_mode_annotation(
+                        annotation.loc.clone().or_else(|| t.loc().cloned()),
+                        t.id().clone(),
+                        annotation.val.clone(),
+                    )),
+                    None,
+                )
+            }
+        };
+        if overridden == mode {
+            (mode, None, None)
+        } else {
+            (
+                overridden,
+                None,
+                Some(ValidationWarning::validation_mode_override(
+                    annotation.loc.clone().or_else(|| t.loc().cloned()),
+                    t.id().clone(),
+                    annotation.val.clone(),
+                )),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::parser::parse_policyset;
+
+    fn validator() -> Validator {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User = { age?: Long, tags: Set<String> };
+            action view appliesTo { principal: User, resource: User };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    // `principal.tags == []` needs permissive mode, since strict mode forbids
+    // empty set literals
+    const PERMISSIVE_ONLY: &str = r#"principal.tags == []"#;
+
+    #[test]
+    fn permissive_override() {
+        let pset = parse_policyset(&format!(
+            r#"
+            @validation("permissive")
+            permit(principal, action, resource) when {{ {PERMISSIVE_ONLY} }};
+            permit(principal, action, resource) when {{ {PERMISSIVE_ONLY} }};
+            "#
+        ))
+        .unwrap();
+        let result = validator().validate(&pset, ValidationMode::Strict);
+        let errs = result.validation_errors().collect::<Vec<_>>();
+        assert!(
+            matches!(
+                errs.as_slice(),
+                [ValidationError::EmptySetForbidden(e)] if e.policy_id.to_string() == "policy1"
+            ),
+            "{errs:?}"
+        );
+        let warnings = result.validation_warnings().collect::<Vec<_>>();
+        assert!(
+            matches!(
+                warnings.as_slice(),
+                [ValidationWarning::ValidationModeOverride(w)] if w.policy_id.to_string() == "policy0" && w.mode == "permissive"
+            ),
+            "{warnings:?}"
+        );
+    }
+
+    #[test]
+    fn strict_override() {
+        let pset = parse_policyset(&format!(
+            r#"
+            @validation("strict")
+            permit(principal, action, resource) when {{ {PERMISSIVE_ONLY} }};
+            "#
+        ))
+        .unwrap();
+        let result = validator().validate(&pset, ValidationMode::Permissive);
+        assert!(matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::EmptySetForbidden(_)]
+        ));
+        // Requesting the mode the policy is validated in anyway is not an override
+        let result = validator().validate(&pset, ValidationMode::Strict);
+        assert_eq!(result.validation_warnings().count(), 0);
+    }
+
+    #[test]
+    fn invalid_mode() {
+        let pset = parse_policyset(
+            r#"
+            @validation("lenient")
+            permit(principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let result = validator().validate(&pset, ValidationMode::Strict);
+        assert!(matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::InvalidValidationModeAnnotation(e)] if e.value == "lenient"
+        ));
+    }
+}
@@ -0,0 +1,181 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Measuring how long validation takes for each policy, to find the policies
+//! which make validation slow
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
+use std::time::Duration;
+
+use cedar_policy_core::ast::PolicyID;
+#[cfg(not(target_arch = "wasm32"))]
+use cedar_policy_core::ast::PolicySet;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{ValidationMode, ValidationResult, Validator};
+
+/// How much work typechecking a policy took
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TypecheckStats {
+    /// The number of request environments the policy was typechecked in, one
+    /// for each combination of principal type, action, and resource type (and
+    /// of linked slot types, for templates) which the policy could apply to
+    pub request_envs: usize,
+    /// The number of expressions typechecked, over all request environments
+    pub expressions: usize,
+}
+
+/// How long validating one static policy or template took, as computed by
+/// [`Validator::validate_with_profile`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationProfile {
+    /// The id of the policy or template
+    pub policy_id: PolicyID,
+    /// The time taken by all validation checks of the policy
+    pub duration: Duration,
+    /// How much work typechecking the policy took
+    pub typecheck: TypecheckStats,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Validator {
+    /// Validate `policies` like [`Validator::validate`], additionally
+    /// measuring how long the checks of each template and static policy take
+    /// and how much work typechecking it takes. The profiles are sorted with
+    /// the slowest policy first. Checks of template links and checks of the
+    /// policy set as a whole are not included in any profile.
+    pub fn validate_with_profile(
+        &self,
+        policies: &PolicySet,
+        mode: ValidationMode,
+    ) -> (ValidationResult, Vec<ValidationProfile>) {
+        let mut profiles = Vec::new();
+        let (errs, warnings): (Vec<_>, Vec<_>) = policies
+            .all_templates()
+            .map(|p| {
+                let start = std::time::Instant::now();
+                let mut stats = TypecheckStats::default();
+                let (errs, warnings) =
+                    self.validate_policy_with_slot_bounds(p, HashMap::new(), mode, &mut stats);
+                let errs = errs.collect::<Vec<_>>();
+                let warnings = warnings.collect::<Vec<_>>();
+                profiles.push(ValidationProfile {
+                    policy_id: p.id().clone(),
+                    duration: start.elapsed(),
+                    typecheck: stats,
+                });
+                (errs, warnings)
+            })
+            .unzip();
+        profiles.sort_by(|a, b| {
+            b.duration
+                .cmp(&a.duration)
+                .then_with(|| a.policy_id.cmp(&b.policy_id))
+        });
+        let result = self.policy_set_result(
+            policies,
+            mode,
+            errs.into_iter().flatten(),
+            warnings.into_iter().flatten(),
+        );
+        (result, profiles)
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::parser::parse_policyset;
+
+    #[test]
+    fn profiles() {
+        let validator = Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                entity User = { name: String };
+                entity Group;
+                entity Photo;
+                action view, edit appliesTo { principal: [User, Group], resource: Photo };
+                action delete appliesTo { principal: User, resource: Photo };
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        );
+        let pset = parse_policyset(
+            r#"
+            permit(principal, action, resource);
+            permit(principal is User, action == Action::"delete", resource)
+            when { principal.name == "admin" };
+            permit(principal, action, resource) when { principal.missing };
+            "#,
+        )
+        .unwrap();
+        let (result, profiles) = validator.validate_with_profile(&pset, ValidationMode::Strict);
+        assert_eq!(
+            result.validation_errors().count(),
+            validator
+                .validate(&pset, ValidationMode::Strict)
+                .validation_errors()
+                .count()
+        );
+        assert!(!result.validation_passed());
+
+        let mut stats = profiles
+            .iter()
+            .map(|p| (p.policy_id.to_string(), p.typecheck))
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            stats,
+            vec![
+                // Every policy is typechecked in every request environment:
+                // (User, Group) x (view, edit), and User x delete
+                (
+                    "policy0".to_string(),
+                    TypecheckStats {
+                        request_envs: 5,
+                        expressions: 5
+                    }
+                ),
+                // Typechecking stops early in the environments where the
+                // scope cannot apply
+                (
+                    "policy1".to_string(),
+                    TypecheckStats {
+                        request_envs: 5,
+                        expressions: 39
+                    }
+                ),
+                (
+                    "policy2".to_string(),
+                    TypecheckStats {
+                        request_envs: 5,
+                        expressions: 20
+                    }
+                ),
+            ]
+        );
+        assert!(profiles
+            .windows(2)
+            .all(|w| matches!(w, [a, b] if a.duration >= b.duration)));
+    }
+}
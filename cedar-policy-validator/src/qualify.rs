@@ -0,0 +1,189 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Rewriting partially qualified entity type and action references in the
+//! text of a policy set to their fully qualified names in the schema
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use cedar_policy_core::ast::Eid;
+use cedar_policy_core::parser::err::ParseErrors;
+use cedar_policy_core::parser::unescape::to_unescaped_string;
+use cedar_policy_core::parser::{parse_policyset, Loc};
+use itertools::Itertools;
+
+use crate::rename::{paths, tokenize};
+use crate::{PolicyEdit, PolicyRename, SkippedOccurrence, Validator};
+
+impl Validator {
+    /// Compute the edits to the source `src` of a policy set which rewrite
+    /// the entity type and action references the schema does not declare to
+    /// the fully qualified name of the declared entity type or action they
+    /// abbreviate. A reference abbreviates a declared name if it is a suffix
+    /// of that name, e.g., `User` and `Photos::User` both abbreviate
+    /// `Acme::Photos::User`, and `Action::"view"` abbreviates
+    /// `Acme::Photos::Action::"view"`.
+    ///
+    /// Cedar policies do not resolve names relative to a namespace, so the
+    /// fully qualified name is the only form which refers to the declared
+    /// type. References which abbreviate more than one declared name are not
+    /// rewritten, and are reported as skipped.
+    pub fn qualify_in_policies(&self, src: &str) -> Result<PolicyRename, ParseErrors> {
+        let pset = parse_policyset(src)?;
+        let entity_types = self
+            .schema
+            .entity_types()
+            .map(|(ty, _)| ty.to_string())
+            .collect::<BTreeSet<_>>();
+        let src_arc: Arc<str> = Arc::from(src);
+        let mut result = PolicyRename::default();
+        for path in paths(&tokenize(src)) {
+            let candidates = match &path.eid {
+                // Action types are named `Action`, optionally in a namespace
+                Some((eid, _)) if path.name.rsplit("::").next() == Some("Action") => {
+                    let Ok(eid) = to_unescaped_string(eid) else {
+                        continue;
+                    };
+                    let actions = self
+                        .schema
+                        .actions()
+                        .filter(|uid| <Eid as AsRef<str>>::as_ref(uid.eid()) == eid.as_str())
+                        .map(|uid| uid.entity_type().to_string())
+                        .collect::<BTreeSet<_>>();
+                    if actions.contains(&path.name) {
+                        continue;
+                    }
+                    actions
+                }
+                Some(_) => {
+                    if entity_types.contains(&path.name) {
+                        continue;
+                    }
+                    entity_types.clone()
+                }
+                None if path.after_is => {
+                    if entity_types.contains(&path.name) {
+                        continue;
+                    }
+                    entity_types.clone()
+                }
+                None => continue,
+            };
+            let suffix = format!("::{}", path.name);
+            let matches = candidates
+                .into_iter()
+                .filter(|name| name.ends_with(&suffix))
+                .collect::<Vec<_>>();
+            match matches.as_slice() {
+                [] => {}
+                [name] => result.edits.push(PolicyEdit {
+                    span: path.span.into(),
+                    text: name.clone(),
+                }),
+                names => {
+                    let Some(t) = pset.all_templates().find(|t| {
+                        t.loc().is_some_and(|loc| {
+                            (loc.span.offset()..loc.span.offset() + loc.span.len())
+                                .contains(&path.span.start)
+                        })
+                    }) else {
+                        continue;
+                    };
+                    result.skipped.push(SkippedOccurrence {
+                        policy_id: t.id().clone(),
+                        loc: Some(Loc::new(path.span.clone(), Arc::clone(&src_arc))),
+                        reason: format!(
+                            "`{}` could refer to any of {}",
+                            path.name,
+                            names.iter().map(|name| format!("`{name}`")).join(", ")
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                namespace Acme::Photos {
+                    entity User;
+                    entity Photo;
+                    action view appliesTo { principal: User, resource: Photo };
+                }
+                namespace Acme::Docs {
+                    entity User;
+                    entity Doc;
+                    action edit appliesTo { principal: User, resource: Doc };
+                }
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[test]
+    fn qualifies_unambiguous_references() {
+        let src = r#"permit(principal, action == Action::"view", resource is Photo)
+when { resource in Photos::Photo::"a" && principal == Acme::Photos::User::"b" };
+permit(principal, action == Acme::Docs::Action :: "edit", resource is Docs::Doc);
+forbid(principal, action, resource) when { resource is [Photo, Docs::Doc] };"#;
+        let result = validator().qualify_in_policies(src).unwrap();
+        assert_eq!(result.skipped, vec![]);
+        assert_eq!(
+            result.apply(src).unwrap(),
+            r#"permit(principal, action == Acme::Photos::Action::"view", resource is Acme::Photos::Photo)
+when { resource in Acme::Photos::Photo::"a" && principal == Acme::Photos::User::"b" };
+permit(principal, action == Acme::Docs::Action :: "edit", resource is Acme::Docs::Doc);
+forbid(principal, action, resource) when { resource is [Acme::Photos::Photo, Acme::Docs::Doc] };"#
+        );
+    }
+
+    #[test]
+    fn skips_ambiguous_references() {
+        let src =
+            r#"permit(principal is User, action, resource) when { Unknown::"a" == resource };"#;
+        let result = validator().qualify_in_policies(src).unwrap();
+        assert_eq!(result.edits, vec![]);
+        match result.skipped.as_slice() {
+            [skipped] => {
+                assert_eq!(skipped.policy_id.to_string(), "policy0");
+                assert_eq!(
+                    skipped.reason,
+                    "`User` could refer to any of `Acme::Docs::User`, `Acme::Photos::User`"
+                );
+                assert_eq!(
+                    skipped.loc.as_ref().map(|loc| loc.snippet()),
+                    Some(Some("User"))
+                );
+            }
+            skipped => panic!("expected one skipped occurrence, got {skipped:?}"),
+        }
+    }
+}
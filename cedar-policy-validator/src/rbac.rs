@@ -18,14 +18,18 @@
 
 use cedar_policy_core::{
     ast::{
-        self, ActionConstraint, EntityReference, EntityUID, Policy, PolicyID, PrincipalConstraint,
-        PrincipalOrResourceConstraint, ResourceConstraint, SlotEnv, Template,
+        self, ActionConstraint, EntityReference, EntityType, EntityUID, LinkBindings, Policy,
+        PolicyID, PrincipalConstraint, PrincipalOrResourceConstraint, ResourceConstraint, SlotId,
+        Template,
     },
     fuzzy_match::fuzzy_search,
     parser::Loc,
 };
 
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use crate::{
     expr_iterator::{policy_entity_type_names, policy_entity_uids},
@@ -35,6 +39,16 @@ use crate::{
 
 use super::{schema::*, Validator};
 
+/// Location of `slot` in the template of the template-linked policy `p`,
+/// falling back to the location of the whole template
+pub(crate) fn slot_loc(p: &Policy, slot: SlotId) -> Option<&Loc> {
+    p.template()
+        .slots()
+        .find(|s| s.id == slot)
+        .and_then(|s| s.loc.as_ref())
+        .or_else(|| p.loc())
+}
+
 impl Validator {
     /// Generate `UnrecognizedEntityType` error for every entity type in the
     /// expression that could not also be found in the schema.
@@ -93,12 +107,12 @@ impl Validator {
         })
     }
 
-    /// Generate `UnrecognizedEntityType` error for
-    /// every entity type in the slot environment that is not in the schema
+    /// Generate `UnrecognizedEntityType` error for every entity type in the
+    /// slot values of the template-linked policy `p` that is not in the
+    /// schema, reported at the slot in the template source
     pub(crate) fn validate_entity_types_in_slots<'a>(
         &'a self,
-        policy_id: &'a PolicyID,
-        slots: &'a SlotEnv,
+        p: &'a Policy,
     ) -> impl Iterator<Item = ValidationError> + 'a {
         // All valid entity types in the schema. These will be used to generate
         // suggestion when an entity type is not found.
@@ -108,15 +122,15 @@ impl Validator {
             .map(ToString::to_string)
             .collect::<Vec<_>>();
 
-        slots.values().filter_map(move |euid| {
+        p.env().iter().filter_map(move |(slot, euid)| {
             let entity_type = euid.entity_type();
             if !self.schema.is_known_entity_type(entity_type) {
                 let actual_entity_type = entity_type.to_string();
                 let suggested_entity_type =
                     fuzzy_search(&actual_entity_type, known_entity_types.as_slice());
                 Some(ValidationError::unrecognized_entity_type(
-                    None,
-                    policy_id.clone(),
+                    slot_loc(p, *slot).cloned(),
+                    p.id().clone(),
                     actual_entity_type,
                     suggested_entity_type,
                 ))
@@ -126,6 +140,36 @@ impl Validator {
         })
     }
 
+    /// Generate `UnrecognizedEntityType` error for every entity type in the
+    /// declared slot bounds of `template` that is not in the schema
+    pub(crate) fn validate_slot_bounds<'a>(
+        &'a self,
+        template: &'a Template,
+        slot_bounds: &'a HashMap<SlotId, HashSet<EntityType>>,
+    ) -> impl Iterator<Item = ValidationError> + 'a {
+        let known_entity_types = self
+            .schema
+            .known_entity_types()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        slot_bounds
+            .values()
+            .flatten()
+            .filter(|ety| !self.schema.is_known_entity_type(ety))
+            .map(move |ety| {
+                let actual_entity_type = ety.to_string();
+                let suggested_entity_type =
+                    fuzzy_search(&actual_entity_type, known_entity_types.as_slice());
+                ValidationError::unrecognized_entity_type(
+                    template.loc().cloned(),
+                    template.id().clone(),
+                    actual_entity_type,
+                    suggested_entity_type,
+                )
+            })
+    }
+
     fn check_if_in_fixes_principal(
         &self,
         principal_constraint: &PrincipalConstraint,
@@ -231,6 +275,7 @@ impl Validator {
         self.validate_action_application(
             p.loc(),
             p.id(),
+            LinkBindings::of(p),
             &p.principal_constraint(),
             p.action_constraint(),
             &p.resource_constraint(),
@@ -244,6 +289,7 @@ impl Validator {
         self.validate_action_application(
             t.loc(),
             t.id(),
+            None,
             t.principal_constraint(),
             t.action_constraint(),
             t.resource_constraint(),
@@ -258,6 +304,7 @@ impl Validator {
         &self,
         source_loc: Option<&Loc>,
         policy_id: &PolicyID,
+        link: Option<LinkBindings>,
         principal_constraint: &PrincipalConstraint,
         action_constraint: &ActionConstraint,
         resource_constraint: &ResourceConstraint,
@@ -278,6 +325,7 @@ impl Validator {
         Some(ValidationError::invalid_action_application(
             source_loc.cloned(),
             policy_id.clone(),
+            link,
             would_in_fix_principal,
             would_in_fix_resource,
         ))
@@ -293,6 +341,37 @@ impl Validator {
         .into_iter()
     }
 
+    /// Could the static or template-linked policy `p` apply to a request with
+    /// the given principal type, action, and resource type? Only the policy
+    /// scope and the schema are considered; the policy conditions are not
+    /// evaluated, so a `true` result means the policy _may_ be satisfied.
+    pub fn policy_may_apply_to(
+        &self,
+        p: &Policy,
+        principal: &ast::EntityType,
+        action: &EntityUID,
+        resource: &ast::EntityType,
+    ) -> bool {
+        let Some(action_id) = self.schema.get_action_id(action) else {
+            return false;
+        };
+        if !action_id.applies_to.is_applicable_principal_type(principal)
+            || !action_id.applies_to.is_applicable_resource_type(resource)
+        {
+            return false;
+        }
+        let principal_constraint = p.principal_constraint();
+        let resource_constraint = p.resource_constraint();
+        self.get_actions_satisfying_constraint(p.action_constraint())
+            .any(|a| a == action)
+            && self
+                .get_principals_satisfying_constraint(&principal_constraint)
+                .any(|ty| ty == principal)
+            && self
+                .get_resources_satisfying_constraint(&resource_constraint)
+                .any(|ty| ty == resource)
+    }
+
     /// Gather all `ApplySpec` objects for all actions in the schema.
     pub(crate) fn get_apply_specs_for_action<'a>(
         &'a self,
@@ -307,7 +386,7 @@ impl Validator {
 
     /// Get the set of actions (action entity id strings) that satisfy the
     /// action scope constraint of the policy.
-    fn get_actions_satisfying_constraint<'a>(
+    pub(crate) fn get_actions_satisfying_constraint<'a>(
         &'a self,
         action_constraint: &'a ActionConstraint,
     ) -> Box<dyn Iterator<Item = &'a EntityUID> + 'a> {
@@ -673,19 +752,23 @@ mod test {
             .parse()
             .expect("Expected entity UID to parse.");
         let env = HashMap::from([(ast::SlotId::principal(), undefined_euid)]);
+        let src = "permit(principal == ?principal, action, resource);";
+        let template = parse_policy_or_template(None, src).unwrap();
+        let link = Template::link(Arc::new(template), PolicyID::from_string("0"), env).unwrap();
 
         let validator = Validator::new(schema);
-        let notes: Vec<ValidationError> = validator
-            .validate_entity_types_in_slots(&PolicyID::from_string("0"), &env)
-            .collect();
+        let notes: Vec<ValidationError> = validator.validate_entity_types_in_slots(&link).collect();
 
         assert_eq!(1, notes.len());
         match notes.first() {
             Some(ValidationError::UnrecognizedEntityType(UnrecognizedEntityType {
+                source_loc,
                 actual_entity_type,
                 suggested_entity_type,
                 ..
             })) => {
+                // reported at the slot in the template
+                assert_eq!(source_loc, &Some(Loc::new(20..30, Arc::from(src))));
                 assert_eq!("Undefined", actual_entity_type);
                 assert_eq!(
                     "User",
@@ -860,6 +943,52 @@ mod test {
         assert_eq!(notes.len(), 1, "{:?}", notes);
     }
 
+    #[test]
+    fn validate_invalid_action_suggests_other_namespace() {
+        let descriptors = json_schema::Fragment::from_json_str(
+            r#"
+                {
+                    "Bank": {
+                        "entityTypes": {},
+                        "actions": { "withdraw": {}, "deposit": {}, "view": {} }
+                    },
+                    "Store": {
+                        "entityTypes": {},
+                        "actions": { "purchase": {}, "view": {} }
+                    }
+                }"#,
+        )
+        .expect("Expected schema parse.");
+        let schema: ValidatorSchema = descriptors.try_into().unwrap();
+        let validate = Validator::new(schema);
+
+        for (action, suggestion) in [
+            (r#"Action::"withdraw""#, r#"Bank::Action::"withdraw""#),
+            (
+                r#"Bank::Action::"withdrawl""#,
+                r#"Bank::Action::"withdraw""#,
+            ),
+            (r#"Action::"purchse""#, r#"Store::Action::"purchase""#),
+            (r#"Store::Action::"veiw""#, r#"Store::Action::"view""#),
+            (r#"Bank::Action::"veiw""#, r#"Bank::Action::"view""#),
+        ] {
+            let src = format!("permit(principal, action == {action}, resource);");
+            let policy = parse_policy_or_template(None, &src).unwrap();
+            let notes: Vec<ValidationError> = validate.validate_action_ids(&policy).collect();
+            expect_err(
+                src.as_str(),
+                &Report::new(notes.first().unwrap().clone()),
+                &ExpectedErrorMessageBuilder::error(&format!(
+                    "for policy `policy0`, unrecognized action `{action}`"
+                ))
+                .exactly_one_underline(action)
+                .help(&format!("did you mean `{suggestion}`?"))
+                .build(),
+            );
+            assert_eq!(notes.len(), 1, "{:?}", notes);
+        }
+    }
+
     #[test]
     fn validate_namespaced_entity_type_in_schema() {
         let descriptors = json_schema::Fragment::from_json_str(
@@ -1078,6 +1207,8 @@ mod test {
                     applies_to: Some(json_schema::ApplySpec {
                         resource_types: vec![resource_type.parse().unwrap()],
                         principal_types: vec![principal_type.parse().unwrap()],
+                        any_resource: false,
+                        any_principal: false,
                         context: json_schema::AttributesOrContext::default(),
                     }),
                     member_of: Some(vec![]),
@@ -1266,6 +1397,7 @@ mod test {
             vec![ValidationError::invalid_action_application(
                 Some(Loc::new(0..43, Arc::from(src))),
                 PolicyID::from_string("policy0"),
+                None,
                 false,
                 false,
             )],
@@ -1294,6 +1426,7 @@ mod test {
                 ValidationError::invalid_action_application(
                     Some(Loc::new(0..55, Arc::from(src))),
                     PolicyID::from_string("policy0"),
+                    None,
                     false,
                     false,
                 ),
@@ -1310,6 +1443,7 @@ mod test {
             vec![ValidationError::invalid_action_application(
                 Some(Loc::new(0..57, Arc::from(src))),
                 PolicyID::from_string("policy0"),
+                None,
                 false,
                 false,
             )],
@@ -1350,6 +1484,7 @@ mod test {
             vec![ValidationError::invalid_action_application(
                 Some(Loc::new(0..43, Arc::from(src))),
                 PolicyID::from_string("policy0"),
+                None,
                 false,
                 false,
             )],
@@ -1365,6 +1500,7 @@ mod test {
             vec![ValidationError::invalid_action_application(
                 Some(Loc::new(0..57, Arc::from(src))),
                 PolicyID::from_string("policy0"),
+                None,
                 false,
                 false,
             )],
@@ -1393,6 +1529,7 @@ mod test {
                 ValidationError::invalid_action_application(
                     Some(Loc::new(0..55, Arc::from(src))),
                     PolicyID::from_string("policy0"),
+                    None,
                     false,
                     false,
                 ),
@@ -1491,6 +1628,8 @@ mod test {
                         applies_to: Some(json_schema::ApplySpec {
                             resource_types: vec![resource_type.parse().unwrap()],
                             principal_types: vec![principal_type.parse().unwrap()],
+                            any_resource: false,
+                            any_principal: false,
                             context: json_schema::AttributesOrContext::default(),
                         }),
                         member_of: Some(vec![json_schema::ActionEntityUID::new(
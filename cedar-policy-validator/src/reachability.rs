@@ -0,0 +1,260 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Analysis of which entity types each action in the schema can reach, and
+//! whether any policy can apply to or permit it
+
+use std::collections::{BTreeSet, HashSet};
+
+use cedar_policy_core::ast::{Effect, EntityType, EntityUID, PolicySet, Template};
+
+use crate::{Validator, ValidatorActionId};
+
+/// The entity types reachable from one action in the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionReachability {
+    /// The action
+    pub action: EntityUID,
+    /// Entity types the action applies to as a principal, together with all
+    /// of their ancestor types in the entity hierarchy
+    pub principal_types: BTreeSet<EntityType>,
+    /// Entity types the action applies to as a resource, together with all
+    /// of their ancestor types in the entity hierarchy
+    pub resource_types: BTreeSet<EntityType>,
+    /// Whether the scope of some policy or template admits this action
+    /// together with one of its principal types and one of its resource types.
+    /// If not, no policy in the policy set can ever apply to the action.
+    pub has_applicable_policy: bool,
+}
+
+impl Validator {
+    /// Compute the [`ActionReachability`] of every action in the schema with
+    /// respect to the policies and templates in `policies`, sorted by action
+    pub fn action_reachability(&self, policies: &PolicySet) -> Vec<ActionReachability> {
+        let templates = policies.all_templates().collect::<Vec<_>>();
+        let mut reachability = self
+            .schema
+            .actions()
+            .filter_map(|action| self.schema.get_action_id(action))
+            .map(|action| ActionReachability {
+                action: action.name.clone(),
+                principal_types: self.with_ancestors(action.principals()),
+                resource_types: self.with_ancestors(action.resources()),
+                has_applicable_policy: templates
+                    .iter()
+                    .any(|t| self.scope_admits_action(t, action)),
+            })
+            .collect::<Vec<_>>();
+        reachability.sort_by(|a, b| a.action.cmp(&b.action));
+        reachability
+    }
+
+    /// The actions in the schema which no `permit` policy or template in
+    /// `policies` has a scope admitting, together with one of their principal
+    /// and resource types, sorted. Every request for one of these actions is
+    /// denied, whatever the conditions of the policies.
+    pub fn unpermitted_actions(&self, policies: &PolicySet) -> Vec<EntityUID> {
+        let permits = policies
+            .all_templates()
+            .filter(|t| t.effect() == Effect::Permit)
+            .collect::<Vec<_>>();
+        let mut actions = self
+            .schema
+            .actions()
+            .filter_map(|action| self.schema.get_action_id(action))
+            .filter(|action| !permits.iter().any(|t| self.scope_admits_action(t, action)))
+            .map(|action| action.name.clone())
+            .collect::<Vec<_>>();
+        actions.sort();
+        actions
+    }
+
+    /// The resource types of the requests which the scope of `t` admits
+    /// together with some action and principal type the action applies to.
+    /// Slots admit any entity type.
+    pub fn resource_types_in_scope(&self, t: &Template) -> BTreeSet<EntityType> {
+        let principals = self
+            .get_principals_satisfying_constraint(t.principal_constraint())
+            .collect::<HashSet<_>>();
+        let resources = self
+            .get_resources_satisfying_constraint(t.resource_constraint())
+            .collect::<HashSet<_>>();
+        self.get_actions_satisfying_constraint(t.action_constraint())
+            .filter_map(|action| self.schema.get_action_id(action))
+            .filter(|action| action.principals().any(|ty| principals.contains(ty)))
+            .flat_map(|action| action.resources().filter(|ty| resources.contains(ty)))
+            .cloned()
+            .collect()
+    }
+
+    fn with_ancestors<'a>(
+        &self,
+        tys: impl Iterator<Item = &'a EntityType>,
+    ) -> BTreeSet<EntityType> {
+        tys.flat_map(|ty| {
+            std::iter::once(ty).chain(self.schema.ancestors(ty).into_iter().flatten())
+        })
+        .cloned()
+        .collect()
+    }
+
+    /// Does the scope of `t` admit `action` and some principal and resource
+    /// type it applies to? Slots admit any entity type.
+    fn scope_admits_action(&self, t: &Template, action: &ValidatorActionId) -> bool {
+        let principals = self
+            .get_principals_satisfying_constraint(t.principal_constraint())
+            .collect::<HashSet<_>>();
+        let resources = self
+            .get_resources_satisfying_constraint(t.resource_constraint())
+            .collect::<HashSet<_>>();
+        self.get_actions_satisfying_constraint(t.action_constraint())
+            .any(|a| a == &action.name)
+            && action.principals().any(|ty| principals.contains(ty))
+            && action.resources().any(|ty| resources.contains(ty))
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::parser::{parse_policy_or_template, parse_policyset};
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                entity Org;
+                entity Team in [Org];
+                entity User in [Team];
+                entity Doc in [Org];
+                entity Device;
+                action view appliesTo { principal: User, resource: Doc };
+                action reboot appliesTo { principal: User, resource: Device };
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[test]
+    fn reachable_types() {
+        let reachability = validator().action_reachability(&PolicySet::new());
+        let [reboot, view] = reachability.as_slice() else {
+            panic!("expected two actions, got {reachability:?}");
+        };
+        assert_eq!(reboot.action, r#"Action::"reboot""#.parse().unwrap());
+        assert_eq!(
+            view.principal_types,
+            BTreeSet::from_iter(["Org", "Team", "User"].map(|ty| ty.parse().unwrap()))
+        );
+        assert_eq!(
+            view.resource_types,
+            BTreeSet::from_iter(["Doc", "Org"].map(|ty| ty.parse().unwrap()))
+        );
+        assert!(!view.has_applicable_policy);
+    }
+
+    #[test]
+    fn applicable_policies() {
+        let validator = validator();
+        let applicable = |src: &str| {
+            validator
+                .action_reachability(&parse_policyset(src).unwrap())
+                .into_iter()
+                .filter(|r| r.has_applicable_policy)
+                .map(|r| r.action.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            applicable("permit(principal, action, resource);"),
+            vec![r#"Action::"reboot""#, r#"Action::"view""#]
+        );
+        assert_eq!(
+            applicable(r#"permit(principal, action, resource in Org::"o");"#),
+            vec![r#"Action::"view""#]
+        );
+        assert_eq!(
+            applicable("permit(principal, action, resource == ?resource);"),
+            vec![r#"Action::"reboot""#, r#"Action::"view""#]
+        );
+        assert_eq!(
+            applicable(r#"permit(principal, action == Action::"view", resource is Device);"#),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn unpermitted_actions() {
+        let validator = validator();
+        let unpermitted = |src: &str| {
+            validator
+                .unpermitted_actions(&parse_policyset(src).unwrap())
+                .into_iter()
+                .map(|action| action.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            unpermitted(""),
+            vec![r#"Action::"reboot""#, r#"Action::"view""#]
+        );
+        // Forbid policies never permit anything
+        assert_eq!(
+            unpermitted("forbid(principal, action, resource);"),
+            vec![r#"Action::"reboot""#, r#"Action::"view""#]
+        );
+        assert_eq!(
+            unpermitted("permit(principal, action, resource is Doc) when { false };"),
+            vec![r#"Action::"reboot""#]
+        );
+        assert_eq!(
+            unpermitted("permit(principal == ?principal, action, resource);"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn resource_types_in_scope() {
+        let validator = validator();
+        let types = |src: &str| {
+            validator
+                .resource_types_in_scope(&parse_policy_or_template(None, src).unwrap())
+                .into_iter()
+                .map(|ty| ty.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            types("permit(principal, action, resource);"),
+            vec!["Device", "Doc"]
+        );
+        assert_eq!(
+            types(r#"permit(principal, action, resource in Org::"o");"#),
+            vec!["Doc"]
+        );
+        assert_eq!(
+            types(r#"permit(principal, action == Action::"reboot", resource == ?resource);"#),
+            vec!["Device"]
+        );
+        assert_eq!(
+            types("permit(principal is Doc, action, resource);"),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,300 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Finding the references to a schema element in the source of a policy set
+
+use cedar_policy_core::ast::{Eid, EntityType, EntityUID, PolicyID};
+use cedar_policy_core::parser::err::ParseErrors;
+use cedar_policy_core::parser::parse_policyset;
+use cedar_policy_core::parser::unescape::to_unescaped_string;
+use smol_str::SmolStr;
+
+use crate::rename::{attribute_token, paths, tokenize, Access};
+use crate::Validator;
+
+/// An entity type, action, or attribute declared in a schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaElement {
+    /// An entity type
+    EntityType(EntityType),
+    /// An action
+    Action(EntityUID),
+    /// An attribute of an entity type
+    Attribute {
+        /// The entity type declaring the attribute
+        entity_type: EntityType,
+        /// The attribute
+        attr: SmolStr,
+    },
+}
+
+/// Whether a [`Reference`] certainly refers to the schema element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReferenceKind {
+    /// The reference refers to the schema element
+    Definite,
+    /// The reference is to an attribute of the same name, accessed on an
+    /// expression which may or may not be of the entity type declaring the
+    /// attribute
+    Possible,
+}
+
+/// A reference to a schema element in the source of a policy set, see
+/// [`Validator::find_references`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The policy or template containing the reference
+    pub policy_id: PolicyID,
+    /// The referring text. For an entity type, this is its name; for an
+    /// action, the entity reference `Action::"id"`; for an attribute, its name
+    /// in the access, or the whole access expression if the name could not be
+    /// located in it.
+    pub span: miette::SourceSpan,
+    /// Whether the reference certainly refers to the schema element
+    pub kind: ReferenceKind,
+}
+
+impl Validator {
+    /// Find every reference to `element` in the source `src` of a policy
+    /// set, sorted by position. Entity types and actions are found wherever
+    /// they appear in a policy or template. Attribute accesses are found
+    /// using the types the validator infers for their receivers: an access on
+    /// an expression which is always of the declaring entity type is a
+    /// definite reference, and one on an expression which may be of that type
+    /// or of another is a possible reference.
+    pub fn find_references(
+        &self,
+        src: &str,
+        element: &SchemaElement,
+    ) -> Result<Vec<Reference>, ParseErrors> {
+        let pset = parse_policyset(src)?;
+        let mut references = Vec::new();
+        match element {
+            SchemaElement::EntityType(_) | SchemaElement::Action(_) => {
+                let tokens = tokenize(src);
+                for path in paths(&tokens) {
+                    let span = match (element, path.eid) {
+                        (SchemaElement::EntityType(ty), eid)
+                            if path.name == ty.to_string() && (eid.is_some() || path.after_is) =>
+                        {
+                            path.span
+                        }
+                        (SchemaElement::Action(uid), Some((eid, eid_span)))
+                            if path.name == uid.entity_type().to_string()
+                                && to_unescaped_string(eid).is_ok_and(|eid| {
+                                    eid.as_str() == <Eid as AsRef<str>>::as_ref(uid.eid())
+                                }) =>
+                        {
+                            path.span.start..eid_span.end
+                        }
+                        _ => continue,
+                    };
+                    // Skip text outside of any policy, e.g., in a comment
+                    // between policies
+                    let Some(t) = pset.all_templates().find(|t| {
+                        t.loc().is_some_and(|loc| {
+                            loc.span.offset() <= span.start
+                                && span.end <= loc.span.offset() + loc.span.len()
+                        })
+                    }) else {
+                        continue;
+                    };
+                    references.push(Reference {
+                        policy_id: t.id().clone(),
+                        span: span.into(),
+                        kind: ReferenceKind::Definite,
+                    });
+                }
+            }
+            SchemaElement::Attribute { entity_type, attr } => {
+                for t in pset.all_templates() {
+                    for (access, e) in self.attribute_accesses(t, entity_type, attr) {
+                        let token = attribute_token(src, &e, attr);
+                        let (span, kind) = match (access, token) {
+                            (Access::Matches, Some(token)) => {
+                                (token.span.into(), ReferenceKind::Definite)
+                            }
+                            (Access::Ambiguous, Some(token)) => {
+                                (token.span.into(), ReferenceKind::Possible)
+                            }
+                            // Report the whole access if the attribute cannot
+                            // be found in it
+                            (_, None) => match e.source_loc() {
+                                Some(loc) => (loc.span, ReferenceKind::Possible),
+                                None => continue,
+                            },
+                        };
+                        references.push(Reference {
+                            policy_id: t.id().clone(),
+                            span,
+                            kind,
+                        });
+                    }
+                }
+            }
+        }
+        references.sort_by_key(|r| (r.span.offset(), r.span.len()));
+        references.dedup();
+        Ok(references)
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                namespace App {
+                    entity Team = { name: String };
+                    entity User in Team = { name: String, manager?: User };
+                    entity Doc = { owner: User };
+                    action view appliesTo { principal: [User, Team], resource: Doc };
+                    action edit appliesTo { principal: User, resource: Doc };
+                }
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    /// The text and kind of every reference
+    #[track_caller]
+    fn references<'a>(
+        src: &'a str,
+        element: &SchemaElement,
+    ) -> Vec<(PolicyID, &'a str, ReferenceKind)> {
+        validator()
+            .find_references(src, element)
+            .unwrap()
+            .into_iter()
+            .map(|r| {
+                let text = src
+                    .get(r.span.offset()..r.span.offset() + r.span.len())
+                    .unwrap();
+                (r.policy_id, text, r.kind)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn entity_type() {
+        let src = r#"
+            // App::User::"comment"
+            permit(principal is App::User, action, resource == App::Doc::"d");
+            forbid(principal, action, resource) when { resource.owner == App::User :: "bob" };
+            permit(principal, action, resource) when { resource.owner is [App::Team, App::User] };
+        "#;
+        assert_eq!(
+            references(
+                src,
+                &SchemaElement::EntityType("App::User".parse().unwrap())
+            ),
+            vec![
+                (
+                    PolicyID::from_string("policy0"),
+                    "App::User",
+                    ReferenceKind::Definite
+                ),
+                (
+                    PolicyID::from_string("policy1"),
+                    "App::User",
+                    ReferenceKind::Definite
+                ),
+                (
+                    PolicyID::from_string("policy2"),
+                    "App::User",
+                    ReferenceKind::Definite
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn action() {
+        let src = r#"
+            permit(principal, action in [App::Action::"view", App::Action::"edit"], resource);
+            permit(principal, action, resource) when { action == App::Action::"view" };
+        "#;
+        assert_eq!(
+            references(
+                src,
+                &SchemaElement::Action(r#"App::Action::"view""#.parse().unwrap())
+            ),
+            vec![
+                (
+                    PolicyID::from_string("policy0"),
+                    r#"App::Action::"view""#,
+                    ReferenceKind::Definite
+                ),
+                (
+                    PolicyID::from_string("policy1"),
+                    r#"App::Action::"view""#,
+                    ReferenceKind::Definite
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn attribute() {
+        let src = r#"
+            permit(principal, action == App::Action::"edit", resource)
+            when { principal.name == "" && resource.owner has name && resource.owner["name"] == "" };
+            permit(principal, action == App::Action::"view", resource)
+            when { principal.name == "" };
+        "#;
+        assert_eq!(
+            references(
+                src,
+                &SchemaElement::Attribute {
+                    entity_type: "App::User".parse().unwrap(),
+                    attr: "name".into()
+                }
+            ),
+            vec![
+                (
+                    PolicyID::from_string("policy0"),
+                    "name",
+                    ReferenceKind::Definite
+                ),
+                (
+                    PolicyID::from_string("policy0"),
+                    "name",
+                    ReferenceKind::Definite
+                ),
+                (
+                    PolicyID::from_string("policy0"),
+                    r#""name""#,
+                    ReferenceKind::Definite
+                ),
+                // `principal` may be a `Team` for `view`
+                (
+                    PolicyID::from_string("policy1"),
+                    "name",
+                    ReferenceKind::Possible
+                ),
+            ]
+        );
+    }
+}
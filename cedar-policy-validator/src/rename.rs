@@ -0,0 +1,1151 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Renaming an entity type, action, or attribute consistently across a schema
+//! and the text of a policy set
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
+use cedar_policy_core::ast::{
+    Eid, EntityType, EntityUID, Expr, ExprKind, Id, InternalName, Name, PolicyID, Template,
+    UnreservedId,
+};
+use cedar_policy_core::parser::err::ParseErrors;
+use cedar_policy_core::parser::unescape::to_unescaped_string;
+use cedar_policy_core::parser::{parse_policyset, Loc};
+use miette::Diagnostic;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use crate::json_schema::{
+    ActionEntityUID, AttributesOrContext, Fragment, NamespaceDefinition, Type, TypeVariant,
+};
+use crate::typecheck::Typechecker;
+use crate::types::{self, EntityRecordKind};
+use crate::{RawName, ValidationMode, Validator};
+
+/// Something to rename across a schema and a policy set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rename {
+    /// Rename the entity type `from` to `to`, which must be in the same
+    /// namespace
+    EntityType {
+        /// The entity type to rename
+        from: EntityType,
+        /// Its new name
+        to: EntityType,
+    },
+    /// Rename the action `from` to have the entity id `to`
+    Action {
+        /// The action to rename
+        from: EntityUID,
+        /// Its new entity id
+        to: SmolStr,
+    },
+    /// Rename the attribute `from` of entity type `entity_type` to `to`
+    Attribute {
+        /// The entity type declaring the attribute
+        entity_type: EntityType,
+        /// The attribute to rename
+        from: SmolStr,
+        /// Its new name
+        to: SmolStr,
+    },
+}
+
+/// Errors renaming with a [`Rename`]
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+pub enum RenameError {
+    /// The entity type to rename is not declared in the schema
+    #[error("entity type `{0}` is not declared in the schema")]
+    UndeclaredEntityType(EntityType),
+    /// The action to rename is not declared in the schema
+    #[error("action `{0}` is not declared in the schema")]
+    UndeclaredAction(EntityUID),
+    /// The attribute to rename is not declared in the schema
+    #[error("entity type `{entity_type}` has no attribute `{attr}` in the schema")]
+    UndeclaredAttribute {
+        /// The entity type
+        entity_type: EntityType,
+        /// The attribute
+        attr: SmolStr,
+    },
+    /// The new name is already declared in the schema
+    #[error("`{0}` is already declared in the schema")]
+    AlreadyDeclared(String),
+    /// An entity type can only be renamed within its namespace
+    #[error("cannot rename entity type `{from}` to `{to}` in a different namespace")]
+    #[diagnostic(help("rename the entity type to a name in its own namespace"))]
+    NamespaceChange {
+        /// The entity type to rename
+        from: EntityType,
+        /// Its new name
+        to: EntityType,
+    },
+    /// The attributes of the entity type are declared by a common type, which
+    /// may be shared with other types
+    #[error("the attributes of entity type `{0}` are declared by a common type")]
+    #[diagnostic(help(
+        "declare the attributes of the entity type directly, or rename the attribute of the common type"
+    ))]
+    SharedAttributes(EntityType),
+}
+
+/// A replacement of the text in `span` of the source of a policy set with
+/// `text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyEdit {
+    /// The replaced text
+    pub span: miette::SourceSpan,
+    /// The replacement
+    pub text: String,
+}
+
+/// An occurrence of the renamed item in a policy which was not rewritten,
+/// because it could not be done safely
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedOccurrence {
+    /// The policy the occurrence is in
+    pub policy_id: PolicyID,
+    /// Source location of the expression containing the occurrence
+    pub loc: Option<Loc>,
+    /// Why the occurrence was not rewritten
+    pub reason: String,
+}
+
+/// The edits renaming an item in the source of a policy set, see
+/// [`Validator::rename_in_policies`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PolicyRename {
+    /// Non-overlapping edits, sorted by position
+    pub edits: Vec<PolicyEdit>,
+    /// Occurrences which were not rewritten
+    pub skipped: Vec<SkippedOccurrence>,
+}
+
+impl PolicyRename {
+    /// Apply the edits to `src`, the source they were computed from.
+    ///
+    /// Returns `None` if the edits don't fit `src`, i.e., if they overlap,
+    /// aren't sorted, or replace text outside of `src` or within a character.
+    pub fn apply(&self, src: &str) -> Option<String> {
+        let mut out = String::with_capacity(src.len());
+        let mut pos = 0;
+        for edit in &self.edits {
+            out.push_str(src.get(pos..edit.span.offset())?);
+            out.push_str(&edit.text);
+            pos = edit.span.offset() + edit.span.len();
+        }
+        out.push_str(src.get(pos..)?);
+        Some(out)
+    }
+}
+
+impl Rename {
+    /// Apply the rename to the schema `fragment`, returning the renamed
+    /// fragment. Entity type references are resolved within `fragment`.
+    pub fn rename_in_schema(
+        &self,
+        fragment: &Fragment<RawName>,
+    ) -> Result<Fragment<RawName>, RenameError> {
+        let mut fragment = fragment.clone();
+        match self {
+            Self::EntityType { from, to } => {
+                let (ns, from_id) = split_name(from.name());
+                let (to_ns, to_id) = split_name(to.name());
+                if ns != to_ns {
+                    return Err(RenameError::NamespaceChange {
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                }
+                let defs = Definitions::new(&fragment);
+                let nsdef = fragment
+                    .0
+                    .get_mut(&ns)
+                    .ok_or_else(|| RenameError::UndeclaredEntityType(from.clone()))?;
+                if nsdef.entity_types.contains_key(&to_id)
+                    || nsdef.common_types.keys().any(|id| id.as_ref() == &to_id)
+                {
+                    return Err(RenameError::AlreadyDeclared(to.to_string()));
+                }
+                let def = nsdef
+                    .entity_types
+                    .remove(&from_id)
+                    .ok_or_else(|| RenameError::UndeclaredEntityType(from.clone()))?;
+                nsdef.entity_types.insert(to_id, def);
+                let from: InternalName = from.name().clone().into();
+                let to: InternalName = to.name().clone().into();
+                for (ns, nsdef) in &mut fragment.0 {
+                    let renamer = TypeRenamer {
+                        defs: &defs,
+                        ns: ns.as_ref(),
+                        from: &from,
+                        to: &to,
+                    };
+                    renamer.rename_in_namespace(nsdef);
+                }
+            }
+            Self::Action { from, to } => {
+                let (ns, _) = split_name(from.entity_type().name());
+                // Action ids declared in each namespace, before renaming
+                let action_ids = fragment
+                    .0
+                    .iter()
+                    .map(|(ns, nsdef)| (ns.clone(), nsdef.actions.keys().cloned().collect()))
+                    .collect::<BTreeMap<_, HashSet<_>>>();
+                let nsdef = fragment
+                    .0
+                    .get_mut(&ns)
+                    .filter(|_| from.entity_type().is_action())
+                    .ok_or_else(|| RenameError::UndeclaredAction(from.clone()))?;
+                if nsdef.actions.contains_key(to) {
+                    let to = EntityUID::from_components(
+                        from.entity_type().clone(),
+                        Eid::new(to.clone()),
+                        None,
+                    );
+                    return Err(RenameError::AlreadyDeclared(to.to_string()));
+                }
+                let def = nsdef
+                    .actions
+                    .remove(<Eid as AsRef<str>>::as_ref(from.eid()))
+                    .ok_or_else(|| RenameError::UndeclaredAction(from.clone()))?;
+                nsdef.actions.insert(to.clone(), def);
+                let from_ty: InternalName = from.entity_type().name().clone().into();
+                for (ns, nsdef) in &mut fragment.0 {
+                    for action in nsdef.actions.values_mut() {
+                        for parent in action.member_of.iter_mut().flatten() {
+                            let ty = resolve_action_type(parent.raw_ty(), ns.as_ref(), |ns| {
+                                action_ids
+                                    .get(&ns)
+                                    .is_some_and(|ids| ids.contains(&parent.id))
+                            });
+                            if ty == from_ty
+                                && parent.id.as_str() == <Eid as AsRef<str>>::as_ref(from.eid())
+                            {
+                                *parent =
+                                    ActionEntityUID::new(parent.raw_ty().cloned(), to.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            Self::Attribute {
+                entity_type,
+                from,
+                to,
+            } => {
+                let (ns, id) = split_name(entity_type.name());
+                let def = fragment
+                    .0
+                    .get_mut(&ns)
+                    .and_then(|nsdef| nsdef.entity_types.get_mut(&id))
+                    .ok_or_else(|| RenameError::UndeclaredEntityType(entity_type.clone()))?;
+                let AttributesOrContext(Type::Type(TypeVariant::Record(record))) = &mut def.shape
+                else {
+                    return Err(RenameError::SharedAttributes(entity_type.clone()));
+                };
+                if record.attributes.contains_key(to) {
+                    return Err(RenameError::AlreadyDeclared(format!("{entity_type}.{to}")));
+                }
+                let attr = record.attributes.remove(from).ok_or_else(|| {
+                    RenameError::UndeclaredAttribute {
+                        entity_type: entity_type.clone(),
+                        attr: from.clone(),
+                    }
+                })?;
+                record.attributes.insert(to.clone(), attr);
+            }
+        }
+        Ok(fragment)
+    }
+}
+
+/// The namespace and basename of `name`, as used for the keys of a
+/// [`Fragment`] and its [`NamespaceDefinition`]s
+fn split_name(name: &Name) -> (Option<Name>, UnreservedId) {
+    let ns = name.as_ref().namespace();
+    let ns = if ns.is_empty() {
+        None
+    } else {
+        // PANIC SAFETY: the namespace of a `Name` is a valid `Name`
+        #[allow(clippy::unwrap_used)]
+        Some(ns.parse().unwrap())
+    };
+    (ns, name.basename())
+}
+
+/// Resolve the type of an action group reference written in namespace `ns`.
+/// An unqualified type refers to the action type of `ns` if `declared_in`
+/// declares the action there, and of the empty namespace otherwise.
+fn resolve_action_type(
+    ty: Option<&RawName>,
+    ns: Option<&Name>,
+    declared_in: impl Fn(Option<Name>) -> bool,
+) -> InternalName {
+    let ty = ty.cloned().unwrap_or_else(|| {
+        // PANIC SAFETY: `Action` is a valid name
+        #[allow(clippy::unwrap_used)]
+        RawName::parse_unqualified_name("Action").unwrap()
+    });
+    if ty.is_unqualified() && !declared_in(ns.cloned()) {
+        ty.qualify_with(None)
+    } else {
+        ty.qualify_with_name(ns)
+    }
+}
+
+/// The fully qualified names of the entity and common types declared in a
+/// [`Fragment`]
+struct Definitions {
+    entity_types: HashSet<InternalName>,
+    common_types: HashSet<InternalName>,
+}
+
+impl Definitions {
+    fn new(fragment: &Fragment<RawName>) -> Self {
+        let qualify = |ns: &Option<Name>, id: &UnreservedId| {
+            RawName::new_from_unreserved(id.clone()).qualify_with_name(ns.as_ref())
+        };
+        Self {
+            entity_types: fragment
+                .0
+                .iter()
+                .flat_map(|(ns, nsdef)| nsdef.entity_types.keys().map(move |id| qualify(ns, id)))
+                .collect(),
+            common_types: fragment
+                .0
+                .iter()
+                .flat_map(|(ns, nsdef)| {
+                    nsdef
+                        .common_types
+                        .keys()
+                        .map(move |id| qualify(ns, id.as_ref()))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Renames references to the entity type `from` in the namespace `ns`
+struct TypeRenamer<'a> {
+    defs: &'a Definitions,
+    ns: Option<&'a Name>,
+    from: &'a InternalName,
+    to: &'a InternalName,
+}
+
+impl TypeRenamer<'_> {
+    fn rename_in_namespace(&self, nsdef: &mut NamespaceDefinition<RawName>) {
+        for common in nsdef.common_types.values_mut() {
+            self.rename_in_type(&mut common.ty);
+        }
+        for ety in nsdef.entity_types.values_mut() {
+            for parent in &mut ety.member_of_types {
+                self.rename_entity_ref(parent);
+            }
+            self.rename_in_type(&mut ety.shape.0);
+            if let Some(tags) = &mut ety.tags {
+                self.rename_in_type(tags);
+            }
+        }
+        for action in nsdef.actions.values_mut() {
+            if let Some(applies_to) = &mut action.applies_to {
+                for ty in applies_to
+                    .principal_types
+                    .iter_mut()
+                    .chain(applies_to.resource_types.iter_mut())
+                {
+                    self.rename_entity_ref(ty);
+                }
+                self.rename_in_type(&mut applies_to.context.0);
+            }
+        }
+    }
+
+    fn rename_in_type(&self, ty: &mut Type<RawName>) {
+        match ty {
+            Type::Type(TypeVariant::Set { element }) => self.rename_in_type(element),
+            Type::Type(TypeVariant::Record(record)) => {
+                for attr in record.attributes.values_mut() {
+                    self.rename_in_type(&mut attr.ty);
+                }
+            }
+            Type::Type(TypeVariant::Entity { name }) => self.rename_entity_ref(name),
+            Type::Type(TypeVariant::EntityOrCommon { type_name }) => {
+                // A common type of the same name takes precedence
+                if !self.resolves_to(type_name, &self.defs.common_types) {
+                    self.rename_entity_ref(type_name);
+                }
+            }
+            Type::Type(
+                TypeVariant::String
+                | TypeVariant::Long
+                | TypeVariant::Boolean
+                | TypeVariant::Extension { .. },
+            )
+            | Type::CommonTypeRef { .. } => {}
+        }
+    }
+
+    fn resolves_to(&self, name: &RawName, defs: &HashSet<InternalName>) -> bool {
+        let qualified = name.clone().qualify_with_name(self.ns);
+        defs.contains(&qualified)
+            || (name.is_unqualified() && defs.contains(&name.clone().qualify_with(None)))
+    }
+
+    /// Rename `name` if it refers to `from`, keeping it unqualified if it was
+    fn rename_entity_ref(&self, name: &mut RawName) {
+        let qualified = name.clone().qualify_with_name(self.ns);
+        let resolved = if name.is_unqualified() && !self.defs.entity_types.contains(&qualified) {
+            name.clone().qualify_with(None)
+        } else {
+            qualified
+        };
+        if &resolved == self.from {
+            *name = if name.is_unqualified() {
+                RawName::new(self.to.basename().clone())
+            } else {
+                RawName::from_name(self.to.clone())
+            };
+        }
+    }
+}
+
+impl Validator {
+    /// Compute the edits to the source `src` of a policy set which apply
+    /// `rename` to its policies and templates. Entity types and actions are
+    /// renamed wherever they appear. Attributes are renamed where the
+    /// validator infers that they are accessed on the entity type declaring
+    /// them; accesses on expressions which may also have a different type are
+    /// reported as skipped, as renaming them could break the other type.
+    pub fn rename_in_policies(
+        &self,
+        src: &str,
+        rename: &Rename,
+    ) -> Result<PolicyRename, ParseErrors> {
+        let pset = parse_policyset(src)?;
+        let tokens = tokenize(src);
+        let mut result = PolicyRename::default();
+        match rename {
+            Rename::EntityType { from, to } => {
+                let from = from.to_string();
+                for path in paths(&tokens) {
+                    if path.name == from && (path.eid.is_some() || path.after_is) {
+                        result.edits.push(PolicyEdit {
+                            span: path.span.into(),
+                            text: to.to_string(),
+                        });
+                    }
+                }
+            }
+            Rename::Action { from, to } => {
+                let ty = from.entity_type().to_string();
+                for path in paths(&tokens) {
+                    if let Some((eid, span)) = path.eid {
+                        if path.name == ty
+                            && to_unescaped_string(eid).is_ok_and(|eid| {
+                                eid.as_str() == <Eid as AsRef<str>>::as_ref(from.eid())
+                            })
+                        {
+                            result.edits.push(PolicyEdit {
+                                span: span.into(),
+                                text: format!("\"{}\"", to.escape_debug()),
+                            });
+                        }
+                    }
+                }
+            }
+            Rename::Attribute {
+                entity_type,
+                from,
+                to,
+            } => {
+                for t in pset.all_templates() {
+                    self.rename_attribute(t, src, entity_type, from, to, &mut result);
+                }
+            }
+        }
+        result.edits.sort_by_key(|edit| edit.span.offset());
+        result
+            .edits
+            .dedup_by_key(|edit| (edit.span.offset(), edit.span.len()));
+        Ok(result)
+    }
+
+    fn rename_attribute(
+        &self,
+        t: &Template,
+        src: &str,
+        entity_type: &EntityType,
+        from: &str,
+        to: &str,
+        result: &mut PolicyRename,
+    ) {
+        for (access, e) in self.attribute_accesses(t, entity_type, from) {
+            let skip = |reason: &str| SkippedOccurrence {
+                policy_id: t.id().clone(),
+                loc: e.source_loc().cloned(),
+                reason: reason.to_string(),
+            };
+            match access {
+                Access::Ambiguous => result.skipped.push(skip(&format!(
+                    "`{from}` is accessed on an expression which may not be a `{entity_type}`"
+                ))),
+                Access::Matches => match attribute_edit(src, &e, from, to) {
+                    Some(edit) => result.edits.push(edit),
+                    None => result
+                        .skipped
+                        .push(skip(&format!("could not find `{from}` in the source"))),
+                },
+            }
+        }
+    }
+
+    /// Every access of the attribute `attr` in the condition of `t` whose
+    /// receiver may be an `entity_type`, with how the receiver relates to
+    /// `entity_type` across all request environments
+    pub(crate) fn attribute_accesses(
+        &self,
+        t: &Template,
+        entity_type: &EntityType,
+        attr: &str,
+    ) -> Vec<(Access, Expr<Option<types::Type>>)> {
+        // Keyed by the position of the access in the source, which is shared
+        // between request environments
+        let mut accesses = BTreeMap::new();
+        let typechecker = Typechecker::new(&self.schema, ValidationMode::default(), t.id().clone());
+        for (_, typed) in typechecker.typed_condition_by_request_env(t) {
+            for e in typed.subexpressions() {
+                let (ExprKind::GetAttr { expr, attr: a } | ExprKind::HasAttr { expr, attr: a }) =
+                    e.expr_kind()
+                else {
+                    continue;
+                };
+                if a != attr {
+                    continue;
+                }
+                let access = match expr.data() {
+                    Some(types::Type::EntityOrRecord(EntityRecordKind::Entity(lub))) => {
+                        if lub.get_single_entity() == Some(entity_type) {
+                            Some(Access::Matches)
+                        } else if lub.iter().any(|ty| ty == entity_type) {
+                            Some(Access::Ambiguous)
+                        } else {
+                            None
+                        }
+                    }
+                    Some(types::Type::EntityOrRecord(EntityRecordKind::AnyEntity)) => {
+                        Some(Access::Ambiguous)
+                    }
+                    Some(_) => None,
+                    None => continue,
+                };
+                let key = match e.source_loc() {
+                    Some(loc) => (loc.span.offset(), loc.span.len(), accessor_index(e)),
+                    None => (0, 0, 0),
+                };
+                accesses
+                    .entry(key)
+                    .and_modify(|(prev, _): &mut (Option<Access>, _)| {
+                        *prev = Access::join(*prev, access);
+                    })
+                    .or_insert_with(|| (access, e.clone()));
+            }
+        }
+        accesses
+            .into_values()
+            .filter_map(|(access, e)| Some((access?, e)))
+            .collect()
+    }
+}
+
+/// How the receiver of an attribute access relates to an entity type, across
+/// request environments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// The receiver is always the entity type
+    Matches,
+    /// The receiver may be the entity type or something else
+    Ambiguous,
+}
+
+impl Access {
+    /// Combine the relations in two request environments, where `None` means
+    /// the receiver is not the entity type
+    fn join(a: Option<Self>, b: Option<Self>) -> Option<Self> {
+        if a == b {
+            a
+        } else {
+            Some(Self::Ambiguous)
+        }
+    }
+}
+
+/// The parser gives every access in a chain like `principal.a.b` the source
+/// location of the whole chain. The index of the access `e` in its chain is
+/// the number of accesses below it with the same location.
+fn accessor_index<T>(e: &Expr<T>) -> usize {
+    let mut index = 0;
+    let mut cur = receiver(e);
+    while let Some(inner) = cur {
+        if inner.source_loc() != e.source_loc() {
+            break;
+        }
+        index += 1;
+        cur = receiver(inner);
+    }
+    index
+}
+
+/// The receiver of an access or method call
+fn receiver<T>(e: &Expr<T>) -> Option<&Expr<T>> {
+    match e.expr_kind() {
+        ExprKind::GetAttr { expr, .. } | ExprKind::HasAttr { expr, .. } => Some(expr),
+        ExprKind::BinaryApp { arg1, .. } => Some(arg1),
+        ExprKind::UnaryApp { arg, .. } => Some(arg),
+        ExprKind::ExtensionFunctionApp { args, .. } => args.first(),
+        _ => None,
+    }
+}
+
+/// The token naming an attribute in an access
+pub(crate) struct AttributeToken {
+    /// Byte range of the name, including quotes if it is a string literal
+    pub(crate) span: Range<usize>,
+    /// How the name is written
+    syntax: AttributeSyntax,
+}
+
+/// How the name of an attribute is written in an access
+enum AttributeSyntax {
+    /// An identifier after `has`, at position `index` of `e has a.b.c`
+    Has { index: usize },
+    /// An identifier after a `.` at byte `dot`
+    Dot { dot: usize },
+    /// A string literal, in `e has "a"` or `e["a"]`
+    Quoted,
+}
+
+/// Find the token naming the attribute `attr` accessed by `e` in `src`
+pub(crate) fn attribute_token<T>(src: &str, e: &Expr<T>, attr: &str) -> Option<AttributeToken> {
+    let loc = e.source_loc()?;
+    let end = loc.span.offset() + loc.span.len();
+    // Find the receiver at the start of the chain
+    let mut head = receiver(e)?;
+    while head.source_loc() == Some(loc) {
+        head = receiver(head)?;
+    }
+    let head_loc = head.source_loc()?;
+    let start = head_loc.span.offset() + head_loc.span.len();
+    let tokens = tokenize(src.get(start..end)?)
+        .into_iter()
+        .map(|(tok, span)| (tok, span.start + start..span.end + start))
+        .collect::<Vec<_>>();
+    let index = accessor_index(e);
+
+    if let Some((Token::Ident("has"), _)) = tokens.first() {
+        // `e has a.b.c`, or `e has "a"`
+        let (tok, span) = tokens
+            .iter()
+            .skip(1)
+            .filter(|(tok, _)| *tok != Token::Punct('.'))
+            .nth(index)?;
+        let syntax = match tok {
+            Token::Ident(name) if *name == attr => AttributeSyntax::Has { index },
+            Token::Str(name)
+                if index == 0 && to_unescaped_string(name).is_ok_and(|name| name == attr) =>
+            {
+                AttributeSyntax::Quoted
+            }
+            _ => return None,
+        };
+        return Some(AttributeToken {
+            span: span.clone(),
+            syntax,
+        });
+    }
+
+    // `e.a.b["c"].d(...)`
+    let mut accessors = Vec::new();
+    let mut tokens = tokens.iter().peekable();
+    while let Some((tok, tok_span)) = tokens.next() {
+        match (tok, tokens.next()) {
+            (Token::Punct('.'), Some((Token::Ident(name), span))) => {
+                if let Some((Token::Punct('('), _)) = tokens.peek() {
+                    // Skip the arguments of a method call
+                    let mut depth = 0;
+                    for (tok, _) in tokens.by_ref() {
+                        match tok {
+                            Token::Punct('(') => depth += 1,
+                            Token::Punct(')') => depth -= 1,
+                            _ => {}
+                        }
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    accessors.push(None);
+                } else {
+                    accessors.push((*name == attr).then(|| AttributeToken {
+                        span: span.clone(),
+                        syntax: AttributeSyntax::Dot {
+                            dot: tok_span.start,
+                        },
+                    }));
+                }
+            }
+            (Token::Punct('['), Some((Token::Str(name), span))) => {
+                let Some((Token::Punct(']'), _)) = tokens.next() else {
+                    return None;
+                };
+                let name = to_unescaped_string(name).ok()?;
+                accessors.push((name == attr).then(|| AttributeToken {
+                    span: span.clone(),
+                    syntax: AttributeSyntax::Quoted,
+                }));
+            }
+            _ => return None,
+        }
+    }
+    accessors.into_iter().nth(index)?
+}
+
+/// The edit renaming the attribute accessed by `e` in `src`, if the access
+/// can be found in the source and rewritten
+fn attribute_edit<T>(src: &str, e: &Expr<T>, from: &str, to: &str) -> Option<PolicyEdit> {
+    let token = attribute_token(src, e, from)?;
+    let is_ident = to.parse::<Id>().is_ok();
+    let quoted = format!("\"{}\"", to.escape_debug());
+    let (span, text) = match token.syntax {
+        AttributeSyntax::Has { .. } if is_ident => (token.span, to.to_string()),
+        // A string can only be written as the first attribute after `has`
+        AttributeSyntax::Has { index: 0 } | AttributeSyntax::Quoted => (token.span, quoted),
+        AttributeSyntax::Has { .. } => return None,
+        AttributeSyntax::Dot { dot } if is_ident => (dot..token.span.end, format!(".{to}")),
+        AttributeSyntax::Dot { dot } => (dot..token.span.end, format!("[{quoted}]")),
+    };
+    Some(PolicyEdit {
+        span: span.into(),
+        text,
+    })
+}
+
+/// A token of Cedar policy text, as far as renaming needs to know
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    /// An identifier or keyword
+    Ident(&'a str),
+    /// The contents of a string literal, without the quotes
+    Str(&'a str),
+    /// `::`
+    PathSep,
+    /// Any other character
+    Punct(char),
+}
+
+/// Split `src` into tokens with their byte ranges, skipping whitespace and
+/// comments
+pub(crate) fn tokenize(src: &str) -> Vec<(Token<'_>, Range<usize>)> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '/' if src[start..].starts_with("//") => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            ':' if src[start..].starts_with("::") => {
+                chars.next();
+                Token::PathSep
+            }
+            '"' => {
+                let mut end = src.len();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = i;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                tokens.push((
+                    Token::Str(&src[start + 1..end]),
+                    start..(end + 1).min(src.len()),
+                ));
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some((i, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || *c == '_' {
+                        end = i + 1;
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((Token::Ident(&src[start..end]), start..end));
+                continue;
+            }
+            c => Token::Punct(c),
+        };
+        tokens.push((token, start..start + c.len_utf8()));
+    }
+    tokens
+}
+
+/// A `::`-separated path in policy text which names an entity type
+pub(crate) struct Path<'a> {
+    /// The path, with `::` between its components
+    pub(crate) name: String,
+    /// Byte range of the path
+    pub(crate) span: Range<usize>,
+    /// If the path is the type of an entity reference, the contents of its
+    /// entity id literal and the byte range of the literal
+    pub(crate) eid: Option<(&'a str, Range<usize>)>,
+    /// Whether the path is the type after `is`, or one of the types in a list
+    /// after `is`, as in `e is [A, B]`
+    pub(crate) after_is: bool,
+}
+
+/// The paths in `tokens`
+pub(crate) fn paths<'a>(tokens: &[(Token<'a>, Range<usize>)]) -> Vec<Path<'a>> {
+    let mut paths = Vec::new();
+    let mut i = 0;
+    // Whether `tokens[i]` is in the list of types of `e is [A, B]`, whose
+    // elements can't contain brackets
+    let mut in_is_list = false;
+    while let Some((tok, span)) = tokens.get(i) {
+        let follows_is = i
+            .checked_sub(1)
+            .and_then(|prev| tokens.get(prev))
+            .is_some_and(|(prev, _)| *prev == Token::Ident("is"));
+        let first = match tok {
+            Token::Ident(first) => first,
+            Token::Punct('[') => {
+                in_is_list = follows_is;
+                i += 1;
+                continue;
+            }
+            Token::Punct(']') => {
+                in_is_list = false;
+                i += 1;
+                continue;
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let after_is = follows_is || in_is_list;
+        let mut name = first.to_string();
+        let mut end = span.end;
+        let mut j = i + 1;
+        let mut eid = None;
+        while let Some((Token::PathSep, _)) = tokens.get(j) {
+            match tokens.get(j + 1) {
+                Some((Token::Ident(next), next_span)) => {
+                    name.push_str("::");
+                    name.push_str(next);
+                    end = next_span.end;
+                    j += 2;
+                }
+                Some((Token::Str(id), id_span)) => {
+                    eid = Some((*id, id_span.clone()));
+                    j += 2;
+                    break;
+                }
+                _ => break,
+            }
+        }
+        paths.push(Path {
+            name,
+            span: span.start..end,
+            eid,
+            after_is,
+        });
+        i = j;
+    }
+    paths
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cool_asserts::assert_matches;
+
+    const SCHEMA: &str = r#"
+        namespace App {
+            type Info = { owner: User };
+            entity Team;
+            entity User in Team = { name: String, manager?: User, info: Info };
+            entity Doc = { owner: User, name: String, readers: Set<User> };
+            action view appliesTo { principal: User, resource: Doc };
+            action edit in [view] appliesTo { principal: [User, Team], resource: Doc };
+        }
+    "#;
+
+    fn fragment() -> Fragment<RawName> {
+        Fragment::from_cedarschema_str(
+            SCHEMA,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap()
+        .0
+    }
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                SCHEMA,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[track_caller]
+    fn rename_policies(src: &str, rename: &Rename) -> (String, PolicyRename) {
+        let result = validator().rename_in_policies(src, rename).unwrap();
+        (result.apply(src).unwrap(), result)
+    }
+
+    fn entity_type(name: &str) -> EntityType {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn entity_type_in_schema() {
+        let rename = Rename::EntityType {
+            from: entity_type("App::User"),
+            to: entity_type("App::Member"),
+        };
+        let renamed = rename.rename_in_schema(&fragment()).unwrap();
+        let src = renamed.to_cedarschema().unwrap();
+        assert!(!src.contains("User"), "{src}");
+        assert!(src.contains("entity Member in [Team]"), "{src}");
+        assert!(src.contains(r#""manager"?: Member"#), "{src}");
+        assert!(src.contains(r#""owner": Member"#), "{src}");
+        assert!(src.contains(r#""readers": Set < Member >"#), "{src}");
+        assert!(src.contains("principal: [Member, Team]"), "{src}");
+
+        assert_matches!(
+            Rename::EntityType {
+                from: entity_type("App::User"),
+                to: entity_type("App::Team"),
+            }
+            .rename_in_schema(&fragment()),
+            Err(RenameError::AlreadyDeclared(_))
+        );
+        assert_matches!(
+            Rename::EntityType {
+                from: entity_type("App::User"),
+                to: entity_type("Member"),
+            }
+            .rename_in_schema(&fragment()),
+            Err(RenameError::NamespaceChange { .. })
+        );
+        assert_matches!(
+            Rename::EntityType {
+                from: entity_type("App::Nobody"),
+                to: entity_type("App::Member"),
+            }
+            .rename_in_schema(&fragment()),
+            Err(RenameError::UndeclaredEntityType(_))
+        );
+    }
+
+    #[test]
+    fn entity_type_in_policies() {
+        let rename = Rename::EntityType {
+            from: entity_type("App::User"),
+            to: entity_type("App::Member"),
+        };
+        let src = r#"
+            // App::User::"comment" stays
+            @note("App::User")
+            permit(principal is App::User in App::Team::"a", action, resource)
+            when { resource.owner == App::User :: "alice" || App::User::"bob" in resource.readers };
+            permit(principal, action, resource)
+            when { principal is [App::Team, App::User] && [resource.owner] is [App::User] in App::Team::"a" };
+        "#;
+        let (renamed, result) = rename_policies(src, &rename);
+        assert_eq!(
+            renamed,
+            r#"
+            // App::User::"comment" stays
+            @note("App::User")
+            permit(principal is App::Member in App::Team::"a", action, resource)
+            when { resource.owner == App::Member :: "alice" || App::Member::"bob" in resource.readers };
+            permit(principal, action, resource)
+            when { principal is [App::Team, App::Member] && [resource.owner] is [App::Member] in App::Team::"a" };
+        "#
+        );
+        assert!(result.skipped.is_empty());
+    }
+
+    #[test]
+    fn apply_edits_which_do_not_fit() {
+        let result = PolicyRename {
+            edits: vec![PolicyEdit {
+                span: (3..10).into(),
+                text: "x".into(),
+            }],
+            skipped: Vec::new(),
+        };
+        assert_eq!(result.apply("0123456789").as_deref(), Some("012x"));
+        assert_eq!(result.apply("01234"), None);
+        assert_eq!(result.apply("01é3456789"), None);
+    }
+
+    #[test]
+    fn action() {
+        let rename = Rename::Action {
+            from: r#"App::Action::"view""#.parse().unwrap(),
+            to: "read".into(),
+        };
+        let renamed = rename.rename_in_schema(&fragment()).unwrap();
+        let src = renamed.to_cedarschema().unwrap();
+        assert!(src.contains(r#"action "read""#), "{src}");
+        assert!(
+            src.contains(r#"action "edit" in [Action::"read"]"#),
+            "{src}"
+        );
+
+        let src =
+            r#"permit(principal, action in [App::Action::"view", App::Action::"edit"], resource);"#;
+        let (renamed, _) = rename_policies(src, &rename);
+        assert_eq!(
+            renamed,
+            r#"permit(principal, action in [App::Action::"read", App::Action::"edit"], resource);"#
+        );
+
+        assert_matches!(
+            Rename::Action {
+                from: r#"App::Action::"view""#.parse().unwrap(),
+                to: "edit".into(),
+            }
+            .rename_in_schema(&fragment()),
+            Err(RenameError::AlreadyDeclared(_))
+        );
+    }
+
+    #[test]
+    fn attribute_in_schema() {
+        let rename = Rename::Attribute {
+            entity_type: entity_type("App::Doc"),
+            from: "name".into(),
+            to: "title".into(),
+        };
+        let renamed = rename.rename_in_schema(&fragment()).unwrap();
+        let src = renamed.to_cedarschema().unwrap();
+        assert!(src.contains(r#""title": String"#), "{src}");
+        // `User` has its own `name`
+        assert!(src.contains(r#""name": String"#), "{src}");
+
+        assert_matches!(
+            Rename::Attribute {
+                entity_type: entity_type("App::Doc"),
+                from: "title".into(),
+                to: "subject".into(),
+            }
+            .rename_in_schema(&fragment()),
+            Err(RenameError::UndeclaredAttribute { .. })
+        );
+    }
+
+    #[test]
+    fn attribute_in_policies() {
+        let rename = Rename::Attribute {
+            entity_type: entity_type("App::User"),
+            from: "name".into(),
+            to: "full name".into(),
+        };
+        let src = r#"
+            permit(principal, action == App::Action::"view", resource)
+            when {
+                principal.name == resource.name &&
+                resource.owner.name like "a*" &&
+                resource.owner has name &&
+                principal["name"] != "" &&
+                principal has manager && principal.manager.name.contains("x")
+            };
+        "#;
+        let (renamed, result) = rename_policies(src, &rename);
+        assert_eq!(
+            renamed,
+            r#"
+            permit(principal, action == App::Action::"view", resource)
+            when {
+                principal["full name"] == resource.name &&
+                resource.owner["full name"] like "a*" &&
+                resource.owner has "full name" &&
+                principal["full name"] != "" &&
+                principal has manager && principal.manager["full name"].contains("x")
+            };
+        "#
+        );
+        assert!(result.skipped.is_empty(), "{:?}", result.skipped);
+    }
+
+    #[test]
+    fn attribute_on_ambiguous_receiver() {
+        // `principal` is a `User` for `view`, but may be a `Team` for `edit`,
+        // and `Team` has no `name`, so the access is an error there
+        let rename = Rename::Attribute {
+            entity_type: entity_type("App::Doc"),
+            from: "owner".into(),
+            to: "author".into(),
+        };
+        let src = r#"
+            permit(principal, action, resource) when { principal.info.owner == resource.owner };
+        "#;
+        let (renamed, result) = rename_policies(src, &rename);
+        assert!(
+            renamed.contains("principal.info.owner == resource.author"),
+            "{renamed}"
+        );
+        assert!(result.skipped.is_empty());
+
+        let rename = Rename::Attribute {
+            entity_type: entity_type("App::User"),
+            from: "name".into(),
+            to: "title".into(),
+        };
+        let src = r#"
+            permit(principal, action, resource) when { (if principal is App::User then principal else resource).name == "" };
+        "#;
+        let (renamed, result) = rename_policies(src, &rename);
+        assert_eq!(renamed, src);
+        assert_eq!(result.skipped.len(), 1);
+    }
+}
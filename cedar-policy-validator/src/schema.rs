@@ -21,8 +21,9 @@
 //! computed to obtain a `descendants` relation.
 
 use cedar_policy_core::{
-    ast::{Entity, EntityType, EntityUID, InternalName, Name, UnreservedId},
+    ast::{Entity, EntityType, EntityUID, InternalName, Name, RestrictedExpr, UnreservedId, Value},
     entities::{err::EntitiesError, Entities, TCComputation},
+    evaluator::RestrictedEvaluator,
     extensions::Extensions,
     transitive_closure::compute_tc,
 };
@@ -30,7 +31,7 @@ use itertools::Itertools;
 use nonempty::NonEmpty;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use smol_str::ToSmolStr;
+use smol_str::{SmolStr, ToSmolStr};
 use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -38,7 +39,7 @@ use std::sync::Arc;
 use crate::{
     cedar_schema::SchemaWarning,
     json_schema,
-    types::{Attributes, EntityRecordKind, OpenTag, Type},
+    types::{Attributes, EntityRecordKind, OpenTag, Primitive, Type},
 };
 
 #[cfg(feature = "protobufs")]
@@ -52,6 +53,8 @@ pub use action::ValidatorActionId;
 pub(crate) use action::ValidatorApplySpec;
 mod entity_type;
 pub use entity_type::ValidatorEntityType;
+mod heap_size;
+pub use heap_size::SchemaMemoryUsage;
 mod namespace_def;
 pub(crate) use namespace_def::try_jsonschema_type_into_validator_type;
 pub use namespace_def::ValidatorNamespaceDef;
@@ -65,12 +68,13 @@ use err::{schema_errors::*, *};
 pub enum ActionBehavior {
     /// Action entities cannot have attributes. Attempting to declare attributes
     /// will result in a error when constructing the schema.
+    ProhibitAttributes,
+    /// Action entities may have attributes. Their types are inferred from the
+    /// declared values, so policies can access them (e.g.,
+    /// `action.category == "read"`) and be typechecked.
     ///
-    /// Since we do not have a formal model for action attributes, this behavior
-    /// (disabling/prohibiting them) is the default.
+    /// This is the default.
     #[default]
-    ProhibitAttributes,
-    /// Action entities may have attributes.
     PermitAttributes,
 }
 
@@ -178,6 +182,12 @@ pub struct ValidatorSchema {
     /// cache it's O(1).
     #[serde_as(as = "Vec<(_, _)>")]
     pub(crate) actions: HashMap<EntityUID, Arc<Entity>>,
+
+    /// Map from the fully-qualified names of the common types declared in the
+    /// schema to their (fully expanded) definitions. Does not include the
+    /// builtin primitive and extension types.
+    #[serde_as(as = "Vec<(_, _)>")]
+    common_types: HashMap<InternalName, Type>,
 }
 
 /// Construct [`ValidatorSchema`] from a string containing a schema formatted
@@ -290,6 +300,83 @@ impl ValidatorSchema {
         self.action_ids.keys()
     }
 
+    /// Returns an iterator over the actions in this schema which declare the
+    /// attribute `attr`
+    pub(crate) fn actions_with_attr<'a>(
+        &'a self,
+        attr: &'a str,
+    ) -> impl Iterator<Item = &'a EntityUID> + 'a {
+        self.action_ids
+            .values()
+            .filter(move |action| action.attribute_types.get_attr(attr).is_some())
+            .map(|action| &action.name)
+    }
+
+    /// Returns an iterator over all the action groups that `action` is a
+    /// member of, directly or transitively
+    ///
+    /// # Errors
+    ///
+    /// Returns [`None`] if `action` is not found in the schema
+    pub fn action_ancestors(&self, action: &EntityUID) -> Option<impl Iterator<Item = &EntityUID>> {
+        self.actions.get(action).map(|entity| entity.ancestors())
+    }
+
+    /// Returns an iterator over all the actions that are members of the
+    /// action group `group`, directly or transitively
+    ///
+    /// # Errors
+    ///
+    /// Returns [`None`] if `group` is not found in the schema
+    pub fn action_descendants(
+        &self,
+        group: &EntityUID,
+    ) -> Option<impl Iterator<Item = &EntityUID>> {
+        self.action_ids
+            .get(group)
+            .map(|action| action.descendants.iter())
+    }
+
+    /// Fill in the defaults declared (with `@default`) for `action`'s context
+    /// attributes which are missing from `context`. Returns `context`
+    /// unchanged if `action` is not found in the schema.
+    pub fn fill_context_defaults(
+        &self,
+        action: &EntityUID,
+        context: cedar_policy_core::ast::Context,
+    ) -> cedar_policy_core::ast::Context {
+        use cedar_policy_core::ast::{Context, Expr};
+        let Some(defaults) = self
+            .action_ids
+            .get(action)
+            .map(|action| &action.context_defaults)
+            .filter(|defaults| !defaults.is_empty())
+        else {
+            return context;
+        };
+        match context {
+            Context::Value(attrs) => {
+                let mut attrs = Arc::unwrap_or_clone(attrs);
+                for (attr, val) in defaults {
+                    attrs.entry(attr.clone()).or_insert_with(|| val.clone());
+                }
+                Context::Value(Arc::new(attrs))
+            }
+            Context::RestrictedResidual(attrs) => {
+                // Values are restricted expressions, and we don't remove any
+                // existing attribute, so the invariants on
+                // `RestrictedResidual` still hold.
+                let mut attrs = Arc::unwrap_or_clone(attrs);
+                for (attr, val) in defaults {
+                    attrs
+                        .entry(attr.clone())
+                        .or_insert_with(|| Expr::from(val.clone()));
+                }
+                Context::RestrictedResidual(Arc::new(attrs))
+            }
+        }
+    }
+
     /// Create a [`ValidatorSchema`] without any definitions (of entity types,
     /// common types, or actions).
     pub fn empty() -> ValidatorSchema {
@@ -297,11 +384,13 @@ impl ValidatorSchema {
             entity_types: HashMap::new(),
             action_ids: HashMap::new(),
             actions: HashMap::new(),
+            common_types: HashMap::new(),
         }
     }
 
     /// Construct a [`ValidatorSchema`] from a JSON value in the appropriate
     /// shape.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn from_json_value(json: serde_json::Value, extensions: &Extensions<'_>) -> Result<Self> {
         Self::from_schema_frag(
             json_schema::Fragment::<RawName>::from_json_value(json)?,
@@ -312,6 +401,7 @@ impl ValidatorSchema {
 
     /// Construct a [`ValidatorSchema`] from a string containing JSON in the
     /// appropriate shape.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn from_json_str(json: &str, extensions: &Extensions<'_>) -> Result<Self> {
         Self::from_schema_frag(
             json_schema::Fragment::<RawName>::from_json_str(json)?,
@@ -322,6 +412,7 @@ impl ValidatorSchema {
 
     /// Construct a [`ValidatorSchema`] directly from a file containing JSON
     /// in the appropriate shape.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn from_json_file(file: impl std::io::Read, extensions: &Extensions<'_>) -> Result<Self> {
         Self::from_schema_frag(
             json_schema::Fragment::<RawName>::from_json_file(file)?,
@@ -332,6 +423,7 @@ impl ValidatorSchema {
 
     /// Construct a [`ValidatorSchema`] directly from a file containing the
     /// Cedar schema syntax.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn from_cedarschema_file<'a>(
         r: impl std::io::Read,
         extensions: &'a Extensions<'a>,
@@ -346,6 +438,7 @@ impl ValidatorSchema {
 
     /// Construct a [`ValidatorSchema`] from a string containing the Cedar
     /// schema syntax.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn from_cedarschema_str<'a>(
         src: &str,
         extensions: &Extensions<'a>,
@@ -375,16 +468,27 @@ impl ValidatorSchema {
     }
 
     /// Construct a [`ValidatorSchema`] from some number of [`ValidatorSchemaFragment`]s.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err(level = "debug"))
+    )]
     pub fn from_schema_fragments(
         fragments: impl IntoIterator<Item = ValidatorSchemaFragment<ConditionalName, ConditionalName>>,
         extensions: &Extensions<'_>,
     ) -> Result<ValidatorSchema> {
-        let mut fragments = fragments
-            .into_iter()
-            // All schemas implicitly include the following fragment as well,
-            // defining the items in the `__cedar` namespace.
-            .chain(std::iter::once(cedar_fragment(extensions)))
-            .collect::<Vec<_>>();
+        let mut fragments = fragments.into_iter().collect::<Vec<_>>();
+
+        // Remember which common types were declared by the user, as opposed
+        // to the builtin types and aliases we add below.
+        let declared_common_types = fragments
+            .iter()
+            .flat_map(|f| f.0.iter())
+            .flat_map(|ns_def| ns_def.all_declared_common_type_names().cloned())
+            .collect::<HashSet<_>>();
+
+        // All schemas implicitly include the following fragment as well,
+        // defining the items in the `__cedar` namespace.
+        fragments.push(cedar_fragment(extensions));
 
         // Build the sets of all entity type, common type, and action definitions
         // (fully-qualified names) in all fragments.
@@ -506,11 +610,11 @@ impl ValidatorSchema {
                 // error for any other undeclared entity types by
                 // `check_for_undeclared`.
                 let descendants = entity_children.remove(&name).unwrap_or_default();
+                let sensitive_attributes = sensitive_attributes(&entity_type.attributes.0);
+                let deprecated_attributes = deprecated_attributes(&entity_type.attributes.0);
+                let (shape, open_shape) = open_entity_shape(entity_type.attributes.0);
                 let (attributes, open_attributes) = {
-                    let unresolved = try_jsonschema_type_into_validator_type(
-                        entity_type.attributes.0,
-                        extensions,
-                    )?;
+                    let unresolved = try_jsonschema_type_into_validator_type(shape, extensions)?;
                     Self::record_attributes_or_none(
                         unresolved.resolve_common_type_refs(&common_types)?,
                     )
@@ -518,6 +622,11 @@ impl ValidatorSchema {
                         ContextOrShapeNotRecordError(ContextOrShape::EntityTypeShape(name.clone()))
                     })?
                 };
+                let open_attributes = if open_shape {
+                    OpenTag::OpenAttributes
+                } else {
+                    open_attributes
+                };
                 let tags = entity_type
                     .tags
                     .map(|tags| try_jsonschema_type_into_validator_type(tags, extensions))
@@ -532,6 +641,9 @@ impl ValidatorSchema {
                         attributes,
                         open_attributes,
                         tags,
+                        sensitive_attributes,
+                        deprecation: entity_type.deprecation,
+                        deprecated_attributes,
                     },
                 ))
             })
@@ -546,10 +658,13 @@ impl ValidatorSchema {
                     .insert(euid.clone());
             }
         }
+        // Keep the direct edges so we can report a cycle precisely
+        let direct_action_children = action_children.clone();
         let mut action_ids = action_fragments
             .into_iter()
             .map(|(name, action)| -> Result<_> {
                 let descendants = action_children.remove(&name).unwrap_or_default();
+                let declared_defaults = declared_context_defaults(&action.context);
                 let (context, open_context_attributes) = {
                     let unresolved =
                         try_jsonschema_type_into_validator_type(action.context, extensions)?;
@@ -560,11 +675,31 @@ impl ValidatorSchema {
                         ContextOrShapeNotRecordError(ContextOrShape::ActionContext(name.clone()))
                     })?
                 };
+                // An action applying to any principal or resource type applies
+                // to every entity type declared in the schema
+                let mut applies_to = action.applies_to;
+                if action.any_principal {
+                    applies_to.principal_apply_spec = entity_types.keys().cloned().collect();
+                }
+                if action.any_resource {
+                    applies_to.resource_apply_spec = entity_types.keys().cloned().collect();
+                }
+                let context_defaults =
+                    Self::context_defaults(&name, &context, declared_defaults, extensions)?;
+                // Requests constructed with this schema always have the
+                // defaulted attributes, so policies may use them as if they
+                // were required
+                let mut context = context;
+                for attr in context_defaults.keys() {
+                    if let Some(ty) = context.attrs.get_mut(attr) {
+                        ty.is_required = true;
+                    }
+                }
                 Ok((
                     name.clone(),
                     ValidatorActionId {
                         name,
-                        applies_to: action.applies_to,
+                        applies_to,
                         descendants,
                         context: Type::record_with_attributes(
                             context.attrs,
@@ -572,6 +707,8 @@ impl ValidatorSchema {
                         ),
                         attribute_types: action.attribute_types,
                         attributes: action.attributes,
+                        deprecation: action.deprecation,
+                        context_defaults,
                     },
                 ))
             })
@@ -583,7 +720,13 @@ impl ValidatorSchema {
             .map_err(|e| EntityTypeTransitiveClosureError::from(Box::new(e)))?;
         // Pass `true` here so that we also check that the action hierarchy does
         // not contain cycles.
-        compute_tc(&mut action_ids, true)?;
+        compute_tc(&mut action_ids, true).map_err(|e| match SchemaError::from(e) {
+            SchemaError::CycleInActionHierarchy(CycleInActionHierarchyError(euid, _)) => {
+                let cycle = find_action_cycle(&direct_action_children, &euid);
+                CycleInActionHierarchyError(euid, cycle).into()
+            }
+            e => e,
+        })?;
 
         // Return with an error if there is an undeclared entity or action
         // referenced in any fragment. `{entity,action}_children` are provided
@@ -596,17 +739,24 @@ impl ValidatorSchema {
             entity_children.into_keys(),
             &action_ids,
             action_children.into_keys(),
-            common_types.into_values(),
+            common_types.values().cloned(),
         )?;
 
         let actions = Self::action_entities_iter(&action_ids)
             .map(|e| (e.uid().clone(), Arc::new(e)))
             .collect();
 
+        let common_types = common_types
+            .into_iter()
+            .filter(|(name, _)| declared_common_types.contains(*name))
+            .map(|(name, ty)| (name.clone(), ty))
+            .collect();
+
         Ok(ValidatorSchema {
             entity_types,
             action_ids,
             actions,
+            common_types,
         })
     }
 
@@ -680,6 +830,61 @@ impl ValidatorSchema {
         Ok(())
     }
 
+    /// Evaluate the `@default` annotations declared on an action's context
+    /// attributes, checking that each is a value of the attribute's type and
+    /// that the attribute is optional. String attributes take the annotation
+    /// value as is; for other types, it is parsed as a Cedar expression.
+    fn context_defaults(
+        action: &EntityUID,
+        context: &Attributes,
+        declared: BTreeMap<SmolStr, SmolStr>,
+        extensions: &Extensions<'_>,
+    ) -> Result<BTreeMap<SmolStr, Value>> {
+        let evaluator = RestrictedEvaluator::new(extensions);
+        declared
+            .into_iter()
+            .filter_map(|(attr, raw)| {
+                // The defaults were read from the same record as `context`, so
+                // every attribute is present
+                let ty = context.get_attr(&attr)?;
+                let err = |problem| InvalidContextDefaultError {
+                    action: action.clone(),
+                    attr: attr.clone(),
+                    problem,
+                };
+                let val = if ty.is_required {
+                    Err(err(ContextDefaultProblem::RequiredAttribute))
+                } else {
+                    let rexpr = match &ty.attr_type {
+                        Type::Primitive {
+                            primitive_type: Primitive::String,
+                        } => Ok(RestrictedExpr::val(raw.clone())),
+                        _ => RestrictedExpr::from_str(&raw).map_err(|e| {
+                            err(ContextDefaultProblem::Unparsable(
+                                raw.clone(),
+                                e.to_string(),
+                            ))
+                        }),
+                    };
+                    rexpr
+                        .and_then(|rexpr| {
+                            evaluator.interpret(rexpr.as_borrowed()).map_err(|e| {
+                                err(ContextDefaultProblem::Unparsable(
+                                    raw.clone(),
+                                    e.to_string(),
+                                ))
+                            })
+                        })
+                        .and_then(|val| match ty.attr_type.typecheck_value(&val, extensions) {
+                            Ok(true) => Ok(val),
+                            _ => Err(err(ContextDefaultProblem::WrongType(raw.clone()))),
+                        })
+                };
+                Some(val.map(|val| (attr.clone(), val)).map_err(Into::into))
+            })
+            .collect()
+    }
+
     fn record_attributes_or_none(ty: Type) -> Option<(Attributes, OpenTag)> {
         match ty {
             Type::EntityOrRecord(EntityRecordKind::Record {
@@ -813,6 +1018,12 @@ impl ValidatorSchema {
             .map(|v| v.into_iter().flatten().collect::<Vec<_>>())
     }
 
+    /// An iterator over the common types declared in the schema, with their
+    /// fully-qualified names and fully expanded definitions
+    pub fn common_types(&self) -> impl Iterator<Item = (&InternalName, &Type)> {
+        self.common_types.iter()
+    }
+
     /// Get the `Type` of context expected for the given `action`.
     /// This always returns a closed record type.
     ///
@@ -936,6 +1147,8 @@ impl From<&proto::ValidatorSchema> for ValidatorSchema {
                 .collect(),
             action_ids,
             actions,
+            // Common types are not part of the protobuf format
+            common_types: HashMap::new(),
         }
     }
 }
@@ -967,6 +1180,42 @@ impl TryInto<ValidatorSchema> for NamespaceDefinitionWithActionAttributes<RawNam
     }
 }
 
+/// Find a shortest cycle through `start` in the action hierarchy, given the
+/// direct children of each action. Returns the actions on the cycle in
+/// `memberOf` order, starting and ending with `start`, or an empty vector if
+/// there is no such cycle.
+fn find_action_cycle(
+    children: &HashMap<EntityUID, HashSet<EntityUID>>,
+    start: &EntityUID,
+) -> Vec<EntityUID> {
+    // Breadth-first search down the hierarchy, remembering the parent through
+    // which we first reached each action
+    let mut reached_from: HashMap<&EntityUID, &EntityUID> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some(euid) = queue.pop_front() {
+        for child in children.get(euid).into_iter().flatten() {
+            if child == start {
+                // `start` is a member of `euid`. Walk back up to `start` to
+                // get the rest of the cycle.
+                let mut cycle = vec![start.clone()];
+                let mut cur = Some(euid);
+                while let Some(parent) = cur {
+                    cycle.push(parent.clone());
+                    cur = (parent != start)
+                        .then(|| reached_from.get(parent).copied())
+                        .flatten();
+                }
+                return cycle;
+            }
+            if !reached_from.contains_key(child) {
+                reached_from.insert(child, euid);
+                queue.push_back(child);
+            }
+        }
+    }
+    vec![]
+}
+
 /// Get a [`ValidatorSchemaFragment`] describing the items that implicitly exist
 /// in the `__cedar` namespace.
 fn cedar_fragment(
@@ -1040,6 +1289,135 @@ fn primitive_types<N>() -> impl Iterator<Item = (UnreservedId, json_schema::Type
     .into_iter()
 }
 
+/// Entity types may declare `additionalAttributes` on their own shape,
+/// allowing entities of that type to carry attributes not listed in the
+/// schema. Unlike open records elsewhere in the schema, this doesn't require
+/// the `partial-validate` feature. Returns the shape with the flag cleared,
+/// along with whether it was set.
+fn open_entity_shape<N>(shape: json_schema::Type<N>) -> (json_schema::Type<N>, bool) {
+    match shape {
+        json_schema::Type::Type(json_schema::TypeVariant::Record(rty))
+            if rty.additional_attributes =>
+        {
+            (
+                json_schema::Type::Type(json_schema::TypeVariant::Record(
+                    json_schema::RecordType {
+                        additional_attributes: false,
+                        ..rty
+                    },
+                )),
+                true,
+            )
+        }
+        shape => (shape, false),
+    }
+}
+
+/// Annotation marking an entity attribute as sensitive in the schema
+pub(crate) const SENSITIVE_ANNOTATION: &str = "pii";
+
+/// Names of the attributes annotated with [`SENSITIVE_ANNOTATION`] in an
+/// entity type's shape. Only attributes declared directly in the shape are
+/// considered; if the shape is a reference to a common type, none are.
+fn sensitive_attributes<N>(shape: &json_schema::Type<N>) -> BTreeSet<SmolStr> {
+    match shape {
+        json_schema::Type::Type(json_schema::TypeVariant::Record(json_schema::RecordType {
+            attributes,
+            ..
+        })) => attributes
+            .iter()
+            .filter(|(_, ty)| {
+                ty.annotations
+                    .0
+                    .keys()
+                    .any(|k| k.as_ref() == SENSITIVE_ANNOTATION)
+            })
+            .map(|(attr, _)| attr.clone())
+            .collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+/// Annotation marking an entity type, attribute, or action as deprecated in
+/// the schema. The annotation's value, if not empty, names the replacement.
+pub(crate) const DEPRECATED_ANNOTATION: &str = "deprecated";
+
+/// A schema element annotated with [`DEPRECATED_ANNOTATION`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Deprecation {
+    /// What policies should use instead, if the schema says
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) replacement: Option<SmolStr>,
+}
+
+impl Deprecation {
+    /// Read the [`DEPRECATED_ANNOTATION`] out of a schema element's annotations
+    pub(crate) fn from_annotations(
+        annotations: &cedar_policy_core::est::Annotations,
+    ) -> Option<Self> {
+        annotations
+            .0
+            .iter()
+            .find(|(k, _)| k.as_ref() == DEPRECATED_ANNOTATION)
+            .map(|(_, v)| Self {
+                replacement: v
+                    .as_ref()
+                    .map(|a| a.val.clone())
+                    .filter(|val| !val.is_empty()),
+            })
+    }
+}
+
+/// Attributes annotated with [`DEPRECATED_ANNOTATION`] in an entity type's
+/// shape. As for [`sensitive_attributes`], only attributes declared directly
+/// in the shape are considered.
+fn deprecated_attributes<N>(shape: &json_schema::Type<N>) -> BTreeMap<SmolStr, Deprecation> {
+    match shape {
+        json_schema::Type::Type(json_schema::TypeVariant::Record(json_schema::RecordType {
+            attributes,
+            ..
+        })) => attributes
+            .iter()
+            .filter_map(|(attr, ty)| {
+                Deprecation::from_annotations(&ty.annotations).map(|d| (attr.clone(), d))
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Annotation declaring a default value for an optional context attribute.
+/// `Request` construction with a schema fills in the default when the
+/// attribute is missing from the context.
+pub(crate) const DEFAULT_ANNOTATION: &str = "default";
+
+/// Raw values of the [`DEFAULT_ANNOTATION`]s in an action's context type. As
+/// for [`sensitive_attributes`], only attributes declared directly in the
+/// context record are considered.
+fn declared_context_defaults<N>(context: &json_schema::Type<N>) -> BTreeMap<SmolStr, SmolStr> {
+    match context {
+        json_schema::Type::Type(json_schema::TypeVariant::Record(json_schema::RecordType {
+            attributes,
+            ..
+        })) => attributes
+            .iter()
+            .filter_map(|(attr, ty)| {
+                ty.annotations
+                    .0
+                    .iter()
+                    .find(|(k, _)| k.as_ref() == DEFAULT_ANNOTATION)
+                    .map(|(_, v)| {
+                        (
+                            attr.clone(),
+                            v.as_ref().map(|a| a.val.clone()).unwrap_or_default(),
+                        )
+                    })
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
 /// Convert an [`InternalName`] to an [`EntityType`].
 /// If this fails (because the name contained `__cedar`), this throws a
 /// `ReservedNameError`. As of this writing, there are no valid entity types
@@ -1715,7 +2093,7 @@ pub(crate) mod test {
                 &src,
                 &miette::Report::new(e),
                 &ExpectedErrorMessageBuilder::error(r#"undeclared action: Action::"photo_action""#)
-                    .help("any actions appearing as parents need to be declared as actions")
+                    .help(r#"`Action::"view_photo"` is declared as a member of `Action::"photo_action"`; any actions appearing as parents need to be declared as actions"#)
                     .build());
         });
     }
@@ -1738,8 +2116,9 @@ pub(crate) mod test {
         let schema: Result<ValidatorSchema> = schema_file.try_into();
         assert_matches!(
             schema,
-            Err(SchemaError::CycleInActionHierarchy(CycleInActionHierarchyError(euid))) => {
+            Err(SchemaError::CycleInActionHierarchy(CycleInActionHierarchyError(euid, cycle))) => {
                 assert_eq!(euid, r#"Action::"view_photo""#.parse().unwrap());
+                assert_eq!(cycle, vec![euid.clone(), euid]);
             }
         )
     }
@@ -1776,6 +2155,45 @@ pub(crate) mod test {
         )
     }
 
+    #[test]
+    fn action_cycle_help_names_edges() {
+        use miette::Diagnostic;
+        let schema = ValidatorSchema::from_cedarschema_str(
+            r#"
+            action a in [b];
+            action b in [c];
+            action c in [a];
+            action d in [b];
+            "#,
+            Extensions::all_available(),
+        )
+        .map(|(schema, _)| schema);
+        assert_matches!(
+            schema,
+            Err(CedarSchemaError::Schema(SchemaError::CycleInActionHierarchy(e))) => {
+                // The exact action reported as being in the cycle isn't
+                // deterministic, but the cycle always has the same edges.
+                let CycleInActionHierarchyError(euid, cycle) = &e;
+                let names = ["a", "b", "c"];
+                let start = names
+                    .iter()
+                    .position(|n| euid == &format!(r#"Action::"{n}""#).parse().unwrap())
+                    .unwrap();
+                let expected = (0..4)
+                    .map(|i| format!(r#"Action::"{}""#, names[(start + i) % 3]).parse().unwrap())
+                    .collect::<Vec<EntityUID>>();
+                assert_eq!(cycle, &expected);
+                assert_eq!(
+                    e.help().map(|h| h.to_string()),
+                    Some(format!(
+                        "`{}` is a member of `{}`, which is a member of `{}`, which is a member of `{}`",
+                        expected[0], expected[1], expected[2], expected[3]
+                    ))
+                );
+            }
+        );
+    }
+
     #[test]
     fn namespaced_schema() {
         let src = r#"
@@ -17,7 +17,7 @@
 //! This module contains the definition of `ValidatorActionId` and the types it relies on
 
 use cedar_policy_core::{
-    ast::{self, EntityType, EntityUID, PartialValueSerializedAsExpr},
+    ast::{self, EntityType, EntityUID, PartialValueSerializedAsExpr, Value},
     transitive_closure::TCNode,
 };
 use itertools::Itertools;
@@ -26,7 +26,7 @@ use serde::Serialize;
 use smol_str::SmolStr;
 use std::collections::{BTreeMap, HashSet};
 
-use super::internal_name_to_entity_type;
+use super::{internal_name_to_entity_type, Deprecation};
 use crate::{
     schema::{AllDefs, SchemaError},
     types::{Attributes, Type},
@@ -70,6 +70,15 @@ pub struct ValidatorActionId {
     /// Attributes are serialized as `RestrictedExpr`s, so that roundtripping
     /// works seamlessly.
     pub(crate) attributes: BTreeMap<SmolStr, PartialValueSerializedAsExpr>,
+
+    /// Set if this action is annotated as deprecated in the schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecation: Option<Deprecation>,
+
+    /// Values for optional context attributes which are filled in when a
+    /// request omits them, declared with `@default` in the schema
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) context_defaults: BTreeMap<SmolStr, Value>,
 }
 
 impl ValidatorActionId {
@@ -90,6 +99,12 @@ impl ValidatorActionId {
         &self.context
     }
 
+    /// The defaults declared (with `@default`) for optional attributes of
+    /// this action's context
+    pub fn context_defaults(&self) -> impl Iterator<Item = (&SmolStr, &Value)> {
+        self.context_defaults.iter()
+    }
+
     /// The [`ast::EntityType`]s that can be the `principal` for this action.
     pub fn applies_to_principals(&self) -> impl Iterator<Item = &ast::EntityType> {
         self.applies_to.applicable_principal_types()
@@ -199,6 +214,8 @@ impl From<&proto::ValidatorActionId> for ValidatorActionId {
                     (k.into(), pval.into())
                 })
                 .collect(),
+            deprecation: None,
+            context_defaults: BTreeMap::new(),
         }
     }
 }
@@ -217,10 +234,10 @@ impl From<&proto::ValidatorActionId> for ValidatorActionId {
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ValidatorApplySpec<N> {
     /// The principal entity types the action can be applied to.
-    principal_apply_spec: HashSet<N>,
+    pub(super) principal_apply_spec: HashSet<N>,
 
     /// The resource entity types the action can be applied to.
-    resource_apply_spec: HashSet<N>,
+    pub(super) resource_apply_spec: HashSet<N>,
 }
 
 #[cfg(feature = "protobufs")]
@@ -361,6 +378,8 @@ mod test {
             context: Type::any_record(),
             attribute_types: Attributes::default(),
             attributes: BTreeMap::default(),
+            deprecation: None,
+            context_defaults: BTreeMap::new(),
         }
     }
 
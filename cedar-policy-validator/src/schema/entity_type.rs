@@ -18,10 +18,11 @@
 
 use serde::Serialize;
 use smol_str::SmolStr;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use cedar_policy_core::{ast::EntityType, transitive_closure::TCNode};
 
+use super::Deprecation;
 use crate::types::{AttributeType, Attributes, OpenTag, Type};
 
 #[cfg(feature = "protobufs")]
@@ -58,6 +59,18 @@ pub struct ValidatorEntityType {
     /// type are not allowed to have tags.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) tags: Option<Type>,
+
+    /// Attributes annotated as sensitive (with `@pii`) in the schema
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub(crate) sensitive_attributes: BTreeSet<SmolStr>,
+
+    /// Set if this entity type is annotated as deprecated in the schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deprecation: Option<Deprecation>,
+
+    /// Attributes annotated as deprecated in the schema
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) deprecated_attributes: BTreeMap<SmolStr, Deprecation>,
 }
 
 impl ValidatorEntityType {
@@ -71,6 +84,11 @@ impl ValidatorEntityType {
         self.attributes.iter()
     }
 
+    /// Is the given attribute annotated as sensitive (with `@pii`) in the schema?
+    pub fn is_sensitive_attr(&self, attr: &str) -> bool {
+        self.sensitive_attributes.contains(attr)
+    }
+
     /// Return `true` if this entity type has an [`EntityType`] declared as a
     /// possible descendant in the schema.
     pub fn has_descendant_entity_type(&self, ety: &EntityType) -> bool {
@@ -147,6 +165,9 @@ impl From<&proto::ValidatorEntityType> for ValidatorEntityType {
                 &proto::OpenTag::try_from(v.open_attributes).expect("decode should succeed"),
             ),
             tags,
+            sensitive_attributes: BTreeSet::new(),
+            deprecation: None,
+            deprecated_attributes: BTreeMap::new(),
         }
     }
 }
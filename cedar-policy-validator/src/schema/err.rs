@@ -230,6 +230,16 @@ pub enum SchemaError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     ActionAttrEval(#[from] schema_errors::ActionAttrEvalError),
+    /// A `@default` annotation on a context attribute is not a valid default
+    /// value for that attribute
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidContextDefault(#[from] schema_errors::InvalidContextDefaultError),
+    /// An action is declared to apply to any principal (or resource) type,
+    /// but also lists specific principal (or resource) types
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ConflictingAppliesTo(#[from] schema_errors::ConflictingAppliesToError),
     /// Error thrown when the schema contains the `__expr` escape.
     /// Support for this escape form has been dropped.
     #[error(transparent)]
@@ -265,7 +275,8 @@ impl From<transitive_closure::TcError<EntityUID>> for SchemaError {
                 SchemaError::ActionTransitiveClosure(Box::new(e).into())
             }
             transitive_closure::TcError::HasCycle(err) => {
-                schema_errors::CycleInActionHierarchyError(err.vertex_with_loop().clone()).into()
+                schema_errors::CycleInActionHierarchyError(err.vertex_with_loop().clone(), vec![])
+                    .into()
             }
         }
     }
@@ -405,10 +416,15 @@ pub mod schema_errors {
     // CAUTION: this type is publicly exported in `cedar-policy`.
     // Don't make fields `pub`, don't make breaking changes, and use caution
     // when adding public methods.
-    #[derive(Debug, Diagnostic, Error)]
-    #[diagnostic(help("any actions appearing as parents need to be declared as actions"))]
+    #[derive(Debug, Error)]
     pub struct ActionNotDefinedError(
         pub(crate) NonEmpty<crate::json_schema::ActionEntityUID<crate::ConditionalName>>,
+        /// `memberOf` edges referencing the undeclared actions, as pairs of
+        /// the declared member and the undeclared parent, where known
+        pub(crate)  Vec<(
+            EntityUID,
+            crate::json_schema::ActionEntityUID<crate::ConditionalName>,
+        )>,
     );
 
     impl ActionNotDefinedError {
@@ -417,7 +433,43 @@ pub mod schema_errors {
         /// This cannot fail, because `NonEmpty` guarantees there is at least
         /// one error to join.
         pub(crate) fn join_nonempty(errs: NonEmpty<ActionNotDefinedError>) -> Self {
-            Self(errs.flat_map(|err| err.0))
+            let (undeclared, edges): (Vec<_>, Vec<_>) =
+                errs.into_iter().map(|err| (err.0, err.1)).unzip();
+            // PANIC SAFETY: `errs` is nonempty, and each of its elements is nonempty
+            #[allow(clippy::expect_used)]
+            let undeclared =
+                NonEmpty::flatten(NonEmpty::from_vec(undeclared).expect("`errs` is nonempty"));
+            Self(undeclared, edges.into_iter().flatten().collect())
+        }
+
+        /// Record that the undeclared actions were referenced in the
+        /// `memberOf` list of the action `member`
+        pub(crate) fn referenced_by(self, member: &EntityUID) -> Self {
+            let edges = self
+                .0
+                .iter()
+                .map(|parent| (member.clone(), parent.clone()))
+                .collect();
+            Self(self.0, edges)
+        }
+    }
+
+    impl Diagnostic for ActionNotDefinedError {
+        fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+            let help = self
+                .1
+                .iter()
+                .map(|(member, parent)| {
+                    format!(
+                        "`{member}` is declared as a member of `{}`",
+                        parent.as_raw()
+                    )
+                })
+                .chain(std::iter::once(
+                    "any actions appearing as parents need to be declared as actions".to_string(),
+                ))
+                .join("; ");
+            Some(Box::new(help))
         }
     }
 
@@ -511,9 +563,28 @@ pub mod schema_errors {
     // CAUTION: this type is publicly exported in `cedar-policy`.
     // Don't make fields `pub`, don't make breaking changes, and use caution
     // when adding public methods.
-    #[derive(Debug, Diagnostic, Error)]
+    #[derive(Debug, Error)]
     #[error("cycle in action hierarchy containing `{0}`")]
-    pub struct CycleInActionHierarchyError(pub(crate) EntityUID);
+    pub struct CycleInActionHierarchyError(
+        pub(crate) EntityUID,
+        /// Actions on the cycle in `memberOf` order, starting and ending with
+        /// the action above, if known
+        pub(crate) Vec<EntityUID>,
+    );
+
+    impl Diagnostic for CycleInActionHierarchyError {
+        fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+            let (first, rest) = self.1.split_first()?;
+            let (second, rest) = rest.split_first()?;
+            let rest = rest
+                .iter()
+                .map(|parent| format!(", which is a member of `{parent}`"))
+                .join("");
+            Some(Box::new(format!(
+                "`{first}` is a member of `{second}`{rest}"
+            )))
+        }
+    }
 
     /// Cycle in common type hierarchy error
     //
@@ -587,6 +658,53 @@ pub mod schema_errors {
     #[diagnostic(transparent)]
     pub struct ActionAttrEvalError(#[from] pub(crate) EntityAttrEvaluationError);
 
+    /// Invalid context attribute default error
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("invalid default for context attribute `{attr}` of action `{action}`: {problem}")]
+    #[diagnostic(help(
+        "defaults can only be declared for optional context attributes, and must have the attribute's type"
+    ))]
+    pub struct InvalidContextDefaultError {
+        /// Action whose context declares the default
+        pub(crate) action: EntityUID,
+        /// Context attribute the default is declared for
+        pub(crate) attr: SmolStr,
+        /// What's wrong with the default
+        pub(crate) problem: ContextDefaultProblem,
+    }
+
+    /// Conflicting `appliesTo` error
+    //
+    // CAUTION: this type is publicly exported in `cedar-policy`.
+    // Don't make fields `pub`, don't make breaking changes, and use caution
+    // when adding public methods.
+    #[derive(Debug, Diagnostic, Error)]
+    #[error(
+        "action `{action}` applies to any {target} type, but also lists specific {target} types"
+    )]
+    #[diagnostic(help("either list the {target} types the action applies to, or declare that it applies to any, but not both"))]
+    pub struct ConflictingAppliesToError {
+        /// Action with the conflicting declaration
+        pub(crate) action: EntityUID,
+        /// `principal` or `resource`
+        pub(crate) target: &'static str,
+    }
+
+    /// Reasons a context attribute default may be invalid
+    #[derive(Debug, Error)]
+    pub(crate) enum ContextDefaultProblem {
+        #[error("the attribute is required, so a default would never be used")]
+        RequiredAttribute,
+        #[error("`{0}` is not a valid Cedar value: {1}")]
+        Unparsable(SmolStr, String),
+        #[error("`{0}` does not have the attribute's declared type")]
+        WrongType(SmolStr),
+    }
+
     /// Unsupported feature error
     //
     // CAUTION: this type is publicly exported in `cedar-policy`.
@@ -616,8 +734,8 @@ pub mod schema_errors {
 
     #[derive(Debug, Diagnostic, Error)]
     pub(crate) enum UnsupportedFeature {
-        #[error("records and entities with `additionalAttributes` are experimental, but the experimental `partial-validate` feature is not enabled")]
-        OpenRecordsAndEntities,
+        #[error("records with `additionalAttributes` are experimental, but the experimental `partial-validate` feature is not enabled")]
+        OpenRecords,
         // Action attributes are allowed if `ActionBehavior` is `PermitAttributes`
         #[error("action declared with attributes: [{}]", .0.iter().join(", "))]
         ActionAttributes(Vec<String>),
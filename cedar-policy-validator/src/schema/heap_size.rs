@@ -0,0 +1,190 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Estimates of the heap memory used by a [`ValidatorSchema`], in the style
+//! of [`cedar_policy_core::heap_size`]
+
+use std::mem::size_of;
+
+use cedar_policy_core::ast::{Entity, EntityType};
+use cedar_policy_core::heap_size::{
+    arc_heap_size, collection_heap_size, hash_map_heap_size, HeapSize,
+};
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use super::{ValidatorActionId, ValidatorEntityType, ValidatorSchema};
+use crate::types::{AttributeType, Attributes, EntityRecordKind, Type};
+
+impl HeapSize for Type {
+    fn heap_size(&self) -> usize {
+        match self {
+            Type::Never | Type::True | Type::False | Type::Primitive { .. } => 0,
+            Type::Set { element_type } => element_type
+                .as_ref()
+                .map_or(0, |ty| size_of::<Type>() + ty.heap_size()),
+            Type::EntityOrRecord(kind) => match kind {
+                EntityRecordKind::Record { attrs, .. } => attrs.heap_size(),
+                EntityRecordKind::AnyEntity => 0,
+                EntityRecordKind::Entity(lub) => {
+                    let elements = lub.iter().collect::<Vec<_>>();
+                    collection_heap_size::<EntityType>(elements.len())
+                        + elements.into_iter().map(HeapSize::heap_size).sum::<usize>()
+                }
+                EntityRecordKind::ActionEntity { name, attrs } => {
+                    name.heap_size() + attrs.heap_size()
+                }
+            },
+            Type::ExtensionType { name } => name.heap_size(),
+        }
+    }
+}
+
+impl HeapSize for Attributes {
+    fn heap_size(&self) -> usize {
+        collection_heap_size::<(SmolStr, AttributeType)>(self.attrs.len())
+            + self
+                .attrs
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.attr_type.heap_size())
+                .sum::<usize>()
+    }
+}
+
+/// Estimated heap memory used by a [`ValidatorSchema`], broken down by
+/// component. The estimates are computed as described in
+/// [`cedar_policy_core::heap_size`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaMemoryUsage {
+    entity_types: usize,
+    actions: usize,
+    common_types: usize,
+    hierarchy: usize,
+}
+
+impl SchemaMemoryUsage {
+    /// Estimate the heap memory used by `schema`
+    pub fn new(schema: &ValidatorSchema) -> Self {
+        let mut usage = Self {
+            entity_types: hash_map_heap_size(&schema.entity_types),
+            actions: hash_map_heap_size(&schema.action_ids) + hash_map_heap_size(&schema.actions),
+            common_types: hash_map_heap_size(&schema.common_types),
+            hierarchy: 0,
+        };
+        for (name, ety) in &schema.entity_types {
+            usage.entity_types += name.heap_size() + entity_type_heap_size(ety);
+            usage.hierarchy += set_heap_size(ety.descendants.iter());
+        }
+        for (name, action) in &schema.action_ids {
+            usage.actions += name.heap_size() + action_heap_size(action);
+            usage.hierarchy += set_heap_size(action.descendants.iter());
+        }
+        for (name, action) in &schema.actions {
+            usage.actions += name.heap_size() + arc_heap_size::<Entity>(action.as_ref());
+        }
+        for (name, ty) in &schema.common_types {
+            usage.common_types += name.heap_size() + ty.heap_size();
+        }
+        usage
+    }
+
+    /// Memory used by entity type definitions, including their attribute
+    /// and tag types
+    pub fn entity_types(&self) -> usize {
+        self.entity_types
+    }
+
+    /// Memory used by action definitions, including their `appliesTo`
+    /// lists, context types, and action entities
+    pub fn actions(&self) -> usize {
+        self.actions
+    }
+
+    /// Memory used by common type definitions
+    pub fn common_types(&self) -> usize {
+        self.common_types
+    }
+
+    /// Memory used by the transitively closed entity type and action
+    /// hierarchies
+    pub fn hierarchy(&self) -> usize {
+        self.hierarchy
+    }
+
+    /// Total memory used by the schema
+    pub fn total(&self) -> usize {
+        self.entity_types + self.actions + self.common_types + self.hierarchy
+    }
+}
+
+fn entity_type_heap_size(ety: &ValidatorEntityType) -> usize {
+    ety.attributes.heap_size()
+        + ety
+            .tags
+            .as_ref()
+            .map_or(0, |ty| size_of::<Type>() + ty.heap_size())
+}
+
+fn action_heap_size(action: &ValidatorActionId) -> usize {
+    set_heap_size(action.applies_to.principal_apply_spec.iter())
+        + set_heap_size(action.applies_to.resource_apply_spec.iter())
+        + action.context.heap_size()
+        + action.attribute_types.heap_size()
+}
+
+/// Estimated heap memory of a hash set with the given elements, including the
+/// heap memory owned by the elements
+fn set_heap_size<'a, T: HeapSize + 'a>(elements: impl Iterator<Item = &'a T>) -> usize {
+    elements.map(|e| size_of::<T>() + 1 + e.heap_size()).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn usage(src: &str) -> SchemaMemoryUsage {
+        let (schema, _) = ValidatorSchema::from_cedarschema_str(
+            src,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap();
+        SchemaMemoryUsage::new(&schema)
+    }
+
+    #[test]
+    fn components() {
+        let small =
+            usage("entity User; action view appliesTo { principal: User, resource: User };");
+        let large = usage(
+            r#"
+            type Address = { street: String, city: String, country: String };
+            entity Team;
+            entity User in [Team] { home: Address, work: Address, tags: Set<String> };
+            action view appliesTo { principal: User, resource: [User, Team], context: { ip: ipaddr } };
+            action edit in [view] appliesTo { principal: User, resource: User };
+            "#,
+        );
+        assert!(large.entity_types() > small.entity_types());
+        assert!(large.actions() > small.actions());
+        assert!(large.common_types() > small.common_types());
+        assert!(large.hierarchy() > small.hierarchy());
+        assert_eq!(
+            large.total(),
+            large.entity_types() + large.actions() + large.common_types() + large.hierarchy()
+        );
+    }
+}
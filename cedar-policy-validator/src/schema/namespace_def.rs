@@ -33,7 +33,7 @@ use itertools::Itertools;
 use nonempty::{nonempty, NonEmpty};
 use smol_str::{SmolStr, ToSmolStr};
 
-use super::{internal_name_to_entity_type, AllDefs, ValidatorApplySpec};
+use super::{internal_name_to_entity_type, AllDefs, Deprecation, ValidatorApplySpec};
 use crate::{
     err::{schema_errors::*, SchemaError},
     json_schema::{self, CommonTypeId},
@@ -469,6 +469,8 @@ pub struct EntityTypeFragment<N> {
     /// resolved/inlined (e.g., because they are not defined in this schema
     /// fragment).
     pub(super) tags: Option<json_schema::Type<N>>,
+    /// Set if this entity type is annotated as deprecated
+    pub(super) deprecation: Option<Deprecation>,
 }
 
 impl EntityTypeFragment<ConditionalName> {
@@ -494,6 +496,7 @@ impl EntityTypeFragment<ConditionalName> {
             tags: schema_file_type
                 .tags
                 .map(|tags| tags.conditionally_qualify_type_references(schema_namespace)),
+            deprecation: Deprecation::from_annotations(&schema_file_type.annotations),
         }
     }
 
@@ -507,6 +510,7 @@ impl EntityTypeFragment<ConditionalName> {
         self,
         all_defs: &AllDefs,
     ) -> Result<EntityTypeFragment<InternalName>, TypeNotDefinedError> {
+        let deprecation = self.deprecation;
         // Fully qualify typenames appearing in `attributes`
         let fully_qual_attributes = self.attributes.fully_qualify_type_references(all_defs);
         // Fully qualify typenames appearing in `parents`
@@ -534,6 +538,7 @@ impl EntityTypeFragment<ConditionalName> {
                 attributes,
                 parents,
                 tags,
+                deprecation,
             }),
             (Ok(_), Ok(_), Some(undeclared_parents)) => {
                 Err(TypeNotDefinedError(undeclared_parents))
@@ -625,7 +630,11 @@ impl ActionsDef<ConditionalName, ConditionalName> {
             actions: self
                 .actions
                 .into_iter()
-                .map(|(k, v)| Ok((k, v.fully_qualify_type_references(all_defs)?)))
+                .map(|(k, v)| match v.fully_qualify_type_references(all_defs) {
+                    Ok(v) => Ok((k, v)),
+                    Err(SchemaError::ActionNotDefined(e)) => Err(e.referenced_by(&k).into()),
+                    Err(e) => Err(e),
+                })
                 .collect::<Result<_, SchemaError>>()?,
         })
     }
@@ -662,6 +671,14 @@ pub struct ActionFragment<N, A> {
     /// separately so that we can later extract these values to construct the
     /// actual `Entity` objects defined by the schema.
     pub(super) attributes: BTreeMap<SmolStr, PartialValueSerializedAsExpr>,
+    /// Set if this action is annotated as deprecated
+    pub(super) deprecation: Option<Deprecation>,
+    /// Whether the action applies to principals of any entity type, in which
+    /// case `applies_to` lists no principal types
+    pub(super) any_principal: bool,
+    /// Whether the action applies to resources of any entity type, in which
+    /// case `applies_to` lists no resource types
+    pub(super) any_resource: bool,
 }
 
 impl ActionFragment<ConditionalName, ConditionalName> {
@@ -671,16 +688,31 @@ impl ActionFragment<ConditionalName, ConditionalName> {
         schema_namespace: Option<&InternalName>,
         extensions: &Extensions<'_>,
     ) -> crate::err::Result<Self> {
-        let (principal_types, resource_types, context) = action_type
+        let (principal_types, resource_types, any_principal, any_resource, context) = action_type
             .applies_to
             .map(|applies_to| {
                 (
                     applies_to.principal_types,
                     applies_to.resource_types,
+                    applies_to.any_principal,
+                    applies_to.any_resource,
                     applies_to.context,
                 )
             })
             .unwrap_or_default();
+        for (any, tys, target) in [
+            (any_principal, &principal_types, "principal"),
+            (any_resource, &resource_types, "resource"),
+        ] {
+            if any && !tys.is_empty() {
+                return Err(ConflictingAppliesToError {
+                    action: action_uid.clone(),
+                    target,
+                }
+                .into());
+            }
+        }
+        let deprecation = Deprecation::from_annotations(&action_type.annotations);
         let (attribute_types, attributes) = Self::convert_attr_jsonval_map_to_attributes(
             action_type.attributes.unwrap_or_default(),
             action_uid,
@@ -704,6 +736,8 @@ impl ActionFragment<ConditionalName, ConditionalName> {
                     })
                     .collect(),
             ),
+            any_principal,
+            any_resource,
             parents: action_type
                 .member_of
                 .unwrap_or_default()
@@ -712,6 +746,7 @@ impl ActionFragment<ConditionalName, ConditionalName> {
                 .collect(),
             attribute_types,
             attributes,
+            deprecation,
         })
     }
 
@@ -739,6 +774,9 @@ impl ActionFragment<ConditionalName, ConditionalName> {
                 .collect::<Result<_, SchemaError>>()?,
             attribute_types: self.attribute_types,
             attributes: self.attributes,
+            deprecation: self.deprecation,
+            any_principal: self.any_principal,
+            any_resource: self.any_resource,
         })
     }
 
@@ -1025,7 +1063,7 @@ pub(crate) fn try_record_type_into_validator_type(
     extensions: &Extensions<'_>,
 ) -> crate::err::Result<WithUnresolvedCommonTypeRefs<Type>> {
     if cfg!(not(feature = "partial-validate")) && rty.additional_attributes {
-        Err(UnsupportedFeatureError(UnsupportedFeature::OpenRecordsAndEntities).into())
+        Err(UnsupportedFeatureError(UnsupportedFeature::OpenRecords).into())
     } else {
         Ok(
             parse_record_attributes(rty.attributes.into_iter(), extensions)?.map(move |attrs| {
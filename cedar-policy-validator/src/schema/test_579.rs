@@ -2131,11 +2131,11 @@ fn F1b() {
 fn F1c() {
     let expected_cedar =
         ExpectedErrorMessageBuilder::error("undeclared action: Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     let expected_json =
         ExpectedErrorMessageBuilder::error("undeclared action: Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     assert_parse_error_cedar(F1c_cedar(), &expected_cedar);
     assert_parse_error_json(F1c_json(), &expected_json);
@@ -2149,11 +2149,11 @@ fn F2a() {
 fn F2b() {
     let expected_cedar =
         ExpectedErrorMessageBuilder::error("undeclared action: NS1::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS1::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     let expected_json =
         ExpectedErrorMessageBuilder::error("undeclared action: NS1::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS1::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     assert_parse_error_cedar(F2b_cedar(), &expected_cedar);
     assert_parse_error_json(F2b_json(), &expected_json);
@@ -2162,11 +2162,11 @@ fn F2b() {
 fn F2c() {
     let expected_cedar =
         ExpectedErrorMessageBuilder::error("undeclared action: NS1::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS1::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     let expected_json =
         ExpectedErrorMessageBuilder::error("undeclared action: NS1::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS1::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     assert_parse_error_cedar(F2c_cedar(), &expected_cedar);
     assert_parse_error_json(F2c_json(), &expected_json);
@@ -2175,11 +2175,11 @@ fn F2c() {
 fn F3a() {
     let expected_cedar =
         ExpectedErrorMessageBuilder::error("undeclared action: NS2::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS2::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     let expected_json =
         ExpectedErrorMessageBuilder::error("undeclared action: NS2::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS2::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     assert_parse_error_cedar(F3a_cedar(), &expected_cedar);
     assert_parse_error_json(F3a_json(), &expected_json);
@@ -2188,11 +2188,11 @@ fn F3a() {
 fn F3b() {
     let expected_cedar =
         ExpectedErrorMessageBuilder::error("undeclared action: NS2::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS2::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     let expected_json =
         ExpectedErrorMessageBuilder::error("undeclared action: NS2::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS2::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     assert_parse_error_cedar(F3b_cedar(), &expected_cedar);
     assert_parse_error_json(F3b_json(), &expected_json);
@@ -2201,11 +2201,11 @@ fn F3b() {
 fn F3c() {
     let expected_cedar =
         ExpectedErrorMessageBuilder::error("undeclared action: NS2::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS2::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     let expected_json =
         ExpectedErrorMessageBuilder::error("undeclared action: NS2::Action::\"ActionGroup\"")
-            .help("any actions appearing as parents need to be declared as actions")
+            .help("`NS1::Action::\"Read\"` is declared as a member of `NS2::Action::\"ActionGroup\"`; any actions appearing as parents need to be declared as actions")
             .build();
     assert_parse_error_cedar(F3c_cedar(), &expected_cedar);
     assert_parse_error_json(F3c_json(), &expected_json);
@@ -0,0 +1,246 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checks for policies using attributes annotated as sensitive (`@pii`) in
+//! the schema
+
+use super::*;
+use cedar_policy_core::ast::{BinaryOp, Effect, EntityType, Expr, ExprKind, Literal};
+use cedar_policy_core::parser::Loc;
+use smol_str::SmolStr;
+use typecheck::PolicyCheck;
+use types::{EntityRecordKind, Type};
+use validation_warnings::SensitiveAttributeUseKind;
+
+/// A use of a sensitive attribute: where it occurs, how it is used, and the
+/// entity type and name of the attribute
+type SensitiveUse = (Option<Loc>, SensitiveAttributeUseKind, EntityType, SmolStr);
+
+/// Which uses of sensitive attributes the validator warns about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensitiveAttributeRules {
+    /// Warn when a sensitive attribute is read in the condition of a `permit`
+    /// policy
+    pub forbid_in_permit: bool,
+    /// Warn when a sensitive attribute is compared for equality with (or
+    /// matched by `like` against) a string literal
+    pub forbid_literal_comparison: bool,
+}
+
+impl Default for SensitiveAttributeRules {
+    fn default() -> Self {
+        Self {
+            forbid_in_permit: true,
+            forbid_literal_comparison: true,
+        }
+    }
+}
+
+impl Validator {
+    /// Check `t` for uses of sensitive attributes forbidden by this
+    /// validator's [`SensitiveAttributeRules`], given the results of
+    /// typechecking `t` under each request environment. Request environments
+    /// in which `t` fails to typecheck are skipped, since we can't tell which
+    /// entity types their attribute accesses refer to. The warnings are
+    /// ordered by source location.
+    pub(crate) fn sensitive_attribute_checks(
+        &self,
+        t: &Template,
+        typecheck_answers: &[(RequestEnv<'_>, PolicyCheck)],
+    ) -> impl Iterator<Item = ValidationWarning> {
+        let mut uses = BTreeSet::new();
+        let rules = self.sensitive_attribute_rules;
+        let has_sensitive_attrs = self
+            .schema
+            .entity_types()
+            .any(|(_, ety)| !ety.sensitive_attributes.is_empty());
+        if has_sensitive_attrs && (rules.forbid_in_permit || rules.forbid_literal_comparison) {
+            for (_, policy_check) in typecheck_answers {
+                match policy_check {
+                    PolicyCheck::Success(e) | PolicyCheck::Irrelevant(_, e) => {
+                        self.check_sensitive_uses(e, t, rules, &mut uses);
+                    }
+                    PolicyCheck::Fail(_) => (),
+                }
+            }
+        }
+        let policy_id = t.id().clone();
+        uses.into_iter().map(move |(loc, kind, ety, attr)| {
+            ValidationWarning::sensitive_attribute_use(loc, policy_id.clone(), ety, attr, kind)
+        })
+    }
+
+    fn check_sensitive_uses(
+        &self,
+        e: &Expr<Option<Type>>,
+        t: &Template,
+        rules: SensitiveAttributeRules,
+        uses: &mut BTreeSet<SensitiveUse>,
+    ) {
+        let mut warn = |e: &Expr<Option<Type>>, (ety, attr), kind| {
+            uses.insert((e.source_loc().cloned(), kind, ety, attr));
+        };
+        for sub in e.subexpressions() {
+            if rules.forbid_in_permit && t.effect() == Effect::Permit {
+                if let Some(used) = self.sensitive_attr_access(sub) {
+                    warn(sub, used, SensitiveAttributeUseKind::PermitCondition);
+                }
+            }
+            if rules.forbid_literal_comparison {
+                let compared = match sub.expr_kind() {
+                    ExprKind::BinaryApp {
+                        op: BinaryOp::Eq,
+                        arg1,
+                        arg2,
+                    } if is_string_lit(arg2) => Some(arg1),
+                    ExprKind::BinaryApp {
+                        op: BinaryOp::Eq,
+                        arg1,
+                        arg2,
+                    } if is_string_lit(arg1) => Some(arg2),
+                    ExprKind::Like { expr, .. } => Some(expr),
+                    _ => None,
+                };
+                if let Some(used) = compared.and_then(|e| self.sensitive_attr_access(e)) {
+                    warn(sub, used, SensitiveAttributeUseKind::LiteralComparison);
+                }
+            }
+        }
+    }
+
+    /// If `e` reads a sensitive attribute of an entity, return the entity type
+    /// declaring it and the attribute name
+    fn sensitive_attr_access(&self, e: &Expr<Option<Type>>) -> Option<(EntityType, SmolStr)> {
+        let ExprKind::GetAttr { expr, attr } = e.expr_kind() else {
+            return None;
+        };
+        let Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub))) = expr.data() else {
+            return None;
+        };
+        lub.iter()
+            .find(|ety| {
+                self.schema
+                    .get_entity_type(ety)
+                    .is_some_and(|vety| vety.is_sensitive_attr(attr))
+            })
+            .map(|ety| (ety.clone(), attr.clone()))
+    }
+}
+
+fn is_string_lit<T>(e: &Expr<T>) -> bool {
+    matches!(e.expr_kind(), ExprKind::Lit(Literal::String(_)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::parser::parse_policyset;
+    use itertools::Itertools;
+
+    fn schema() -> ValidatorSchema {
+        ValidatorSchema::from_cedarschema_str(
+            r#"
+            entity User {
+                @pii
+                email: String,
+                name: String,
+            };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            "#,
+            cedar_policy_core::extensions::Extensions::all_available(),
+        )
+        .unwrap()
+        .0
+    }
+
+    fn warnings(validator: &Validator, src: &str) -> Vec<SensitiveAttributeUseKind> {
+        let pset = parse_policyset(src).unwrap();
+        let result = validator.validate(&pset, ValidationMode::Strict);
+        result
+            .validation_warnings()
+            .filter_map(|w| match w {
+                ValidationWarning::SensitiveAttributeUse(w) => Some(w.kind),
+                _ => None,
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn permit_and_literal_comparison() {
+        let validator = Validator::new(schema());
+        assert_eq!(
+            warnings(
+                &validator,
+                r#"permit(principal, action, resource) when { principal.email == "a@example.com" };"#
+            ),
+            vec![
+                SensitiveAttributeUseKind::PermitCondition,
+                SensitiveAttributeUseKind::LiteralComparison,
+            ]
+        );
+        assert_eq!(
+            warnings(
+                &validator,
+                r#"forbid(principal, action, resource) when { principal.email like "*@evil.com" };"#
+            ),
+            vec![SensitiveAttributeUseKind::LiteralComparison]
+        );
+        assert_eq!(
+            warnings(
+                &validator,
+                r#"permit(principal, action, resource) when { principal.name == "alice" };"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn rules_can_be_disabled() {
+        let validator =
+            Validator::new(schema()).with_sensitive_attribute_rules(SensitiveAttributeRules {
+                forbid_in_permit: false,
+                forbid_literal_comparison: true,
+            });
+        assert_eq!(
+            warnings(
+                &validator,
+                r#"permit(principal, action, resource) when { principal.email == principal.name };"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn warnings_are_ordered_by_location() {
+        let validator = Validator::new(schema());
+        let src = r#"permit(principal, action, resource) when { principal.email == "a" && principal.email like "b*" && principal.email == principal.name };"#;
+        let pset = parse_policyset(src).unwrap();
+        let locs = validator
+            .validate(&pset, ValidationMode::Strict)
+            .validation_warnings()
+            .filter_map(|w| match w {
+                ValidationWarning::SensitiveAttributeUse(w) => {
+                    Some(w.source_loc.as_ref().unwrap().start())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(locs.len(), 5);
+        assert_eq!(locs, locs.iter().copied().sorted().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,208 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Querying the type inferred for the expression at a position in the source
+//! of a policy
+
+use cedar_policy_core::ast::{EntityType, EntityUID, Expr, Template};
+use cedar_policy_core::parser::Loc;
+
+use crate::typecheck::Typechecker;
+use crate::types::{RequestEnv, Type};
+use crate::{ValidationMode, Validator};
+
+/// The innermost expression of a policy whose source contains some offset,
+/// with the types inferred for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeAtOffset {
+    /// Source location of the expression
+    pub loc: Loc,
+    /// The type of the expression in each request environment where the
+    /// typechecker could infer one
+    pub types: Vec<TypeInRequestEnv>,
+}
+
+/// The type inferred for an expression in one request environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeInRequestEnv {
+    /// Principal type of the request environment
+    pub principal: EntityType,
+    /// Action of the request environment
+    pub action: EntityUID,
+    /// Resource type of the request environment
+    pub resource: EntityType,
+    /// The type of the expression
+    pub ty: Type,
+}
+
+impl Validator {
+    /// Find the innermost expression in the condition of `t` (including its
+    /// scope constraints) whose source location contains the byte `offset`,
+    /// and the type inferred for it in every request environment. Types are
+    /// inferred even if `t` fails to validate, so long as the expression
+    /// itself can be typed. Returns `None` if no typed expression contains
+    /// `offset`.
+    pub fn type_at_offset(
+        &self,
+        t: &Template,
+        offset: usize,
+        mode: ValidationMode,
+    ) -> Option<TypeAtOffset> {
+        let typechecker = Typechecker::new(&self.schema, mode, t.id().clone());
+        let typed = typechecker
+            .typed_condition_by_request_env(t)
+            .into_iter()
+            .filter_map(|(env, e)| match env {
+                RequestEnv::DeclaredAction {
+                    principal,
+                    action,
+                    resource,
+                    ..
+                } => Some((principal.clone(), action.clone(), resource.clone(), e)),
+                RequestEnv::UndeclaredAction => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The innermost expression may not be typed in every environment,
+        // e.g., when it is only reachable in some of them, so first find the
+        // innermost one across all environments.
+        let loc = typed
+            .iter()
+            .filter_map(|(_, _, _, e)| innermost_at(e, offset))
+            .map(|(loc, _)| loc)
+            .min_by_key(|loc| loc.span.len())?
+            .clone();
+        let types = typed
+            .into_iter()
+            .filter_map(|(principal, action, resource, e)| {
+                let ty = e
+                    .subexpressions()
+                    .find(|sub| sub.source_loc().map(|l| l.span) == Some(loc.span))?
+                    .data()
+                    .clone()?;
+                Some(TypeInRequestEnv {
+                    principal,
+                    action,
+                    resource,
+                    ty,
+                })
+            })
+            .collect();
+        Some(TypeAtOffset { loc, types })
+    }
+}
+
+/// The source location and type of the innermost typed sub-expression of `e`
+/// whose source location contains `offset`
+fn innermost_at(e: &Expr<Option<Type>>, offset: usize) -> Option<(&Loc, &Type)> {
+    e.subexpressions()
+        .filter_map(|sub| Some((sub.source_loc()?, sub.data().as_ref()?)))
+        .filter(|(loc, _)| {
+            let start = loc.span.offset();
+            start <= offset && offset < start + loc.span.len()
+        })
+        .min_by_key(|(loc, _)| loc.span.len())
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+// PANIC SAFETY unit tests
+#[allow(clippy::indexing_slicing)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::parser::parse_policy_or_template;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                entity User = { name: String, age: Long };
+                entity Admin = { name: String };
+                entity Doc = { owner: User, tags: Set<String> };
+                action view appliesTo { principal: [User, Admin], resource: Doc };
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[track_caller]
+    fn type_at(src: &str, needle: &str) -> Option<TypeAtOffset> {
+        let t = parse_policy_or_template(None, src).unwrap();
+        let offset = src.find(needle).unwrap();
+        validator().type_at_offset(&t, offset, ValidationMode::Strict)
+    }
+
+    fn snippet(at: &TypeAtOffset) -> &str {
+        let start = at.loc.span.offset();
+        &at.loc.src[start..start + at.loc.span.len()]
+    }
+
+    #[test]
+    fn innermost_expression() {
+        let src = r#"permit(principal is User, action, resource) when { resource.owner.age > 3 };"#;
+        let at = type_at(src, "resource.owner").unwrap();
+        assert_eq!(snippet(&at), "resource");
+        assert_eq!(at.types.len(), 1);
+        assert_eq!(
+            at.types[0].ty,
+            Type::named_entity_reference("Doc".parse().unwrap())
+        );
+
+        let at = type_at(src, "age").unwrap();
+        assert_eq!(snippet(&at), "resource.owner.age");
+        assert_eq!(at.types[0].ty, Type::primitive_long());
+
+        let at = type_at(src, "> 3").unwrap();
+        assert_eq!(snippet(&at), "resource.owner.age > 3");
+        assert_eq!(at.types[0].ty, Type::primitive_boolean());
+
+        let at = type_at(src, "3 }").unwrap();
+        assert_eq!(snippet(&at), "3");
+    }
+
+    #[test]
+    fn every_request_env() {
+        let src = r#"permit(principal, action, resource) when { principal.name == "a" };"#;
+        let at = type_at(src, "principal.name").unwrap();
+        assert_eq!(snippet(&at), "principal");
+        let mut principals = at
+            .types
+            .iter()
+            .map(|t| t.principal.to_string())
+            .collect::<Vec<_>>();
+        principals.sort();
+        assert_eq!(principals, vec!["Admin", "User"]);
+    }
+
+    #[test]
+    fn despite_type_errors() {
+        let src = r#"permit(principal is User, action, resource) when { resource.tags + principal.age == 1 };"#;
+        let at = type_at(src, "age").unwrap();
+        assert_eq!(snippet(&at), "principal.age");
+        assert_eq!(at.types[0].ty, Type::primitive_long());
+    }
+
+    #[test]
+    fn outside_expression() {
+        let src = r#"permit(principal, action, resource);   "#;
+        assert_eq!(type_at(src, "   "), None);
+    }
+}
@@ -24,28 +24,38 @@ mod typecheck_answer;
 use itertools::Itertools;
 pub(crate) use typecheck_answer::TypecheckAnswer;
 
-use std::{borrow::Cow, collections::HashSet, iter::zip};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
+    iter::zip,
+};
 
 use crate::{
     extension_schema::ExtensionFunctionType,
     extensions::ExtensionSchemas,
+    profile::TypecheckStats,
     schema::ValidatorSchema,
     types::{
         AttributeType, Capability, CapabilitySet, EntityRecordKind, OpenTag, Primitive, RequestEnv,
         Type,
     },
-    validation_errors::{AttributeAccess, LubContext, UnexpectedTypeHelp},
+    validation_errors::{
+        AttributeAccess, LubContext, LubHelp, UndefinedActionAttribute, UnexpectedTypeHelp,
+    },
     ValidationError, ValidationMode, ValidationWarning,
 };
 
 use cedar_policy_core::fuzzy_match::fuzzy_search;
 use cedar_policy_core::{
     ast::{
-        BinaryOp, EntityType, EntityUID, Expr, ExprBuilder, ExprKind, Literal, Name, PolicyID,
-        PrincipalOrResourceConstraint, SlotId, Template, UnaryOp, Var,
+        BinaryOp, EntityType, EntityUID, Expr, ExprBuilder, ExprKind, Integer, Literal, Name,
+        PolicyID, PrincipalOrResourceConstraint, SlotId, Template, UnaryOp, Var,
     },
     extensions::Extensions,
+    parser::Loc,
 };
+use smol_str::SmolStr;
 
 #[cfg(not(target_arch = "wasm32"))]
 const REQUIRED_STACK_SPACE: usize = 1024 * 100;
@@ -61,6 +71,17 @@ pub enum PolicyCheck {
     Fail(Vec<ValidationError>),
 }
 
+/// An `==` or `in` between entities which has been `false` in every request
+/// environment where it was typechecked so far.
+#[derive(Debug)]
+struct IncompatibleComparison {
+    lhs_loc: Option<Loc>,
+    rhs_loc: Option<Loc>,
+    is_in: bool,
+    lhs_types: BTreeSet<EntityType>,
+    rhs_types: BTreeSet<EntityType>,
+}
+
 /// This structure implements typechecking for Cedar policies through the
 /// entry point `typecheck_policy`.
 #[derive(Debug)]
@@ -69,6 +90,18 @@ pub struct Typechecker<'a> {
     extensions: &'static ExtensionSchemas<'static>,
     mode: ValidationMode,
     policy_id: PolicyID,
+    slot_bounds: HashMap<SlotId, HashSet<EntityType>>,
+    /// Entity comparisons seen while typechecking, keyed by their source
+    /// location. An entry is `None` once the comparison could be `true` in
+    /// some request environment.
+    entity_comparisons: RefCell<HashMap<Loc, Option<IncompatibleComparison>>>,
+    /// Attribute accesses with a default seen while typechecking, keyed by
+    /// their source location, with the errors for accessing an attribute
+    /// which is not declared. An entry is `None` once the attribute may be
+    /// declared in some request environment.
+    defaulted_attributes: RefCell<HashMap<Loc, Option<Vec<ValidationError>>>>,
+    /// How much work typechecking has done so far
+    stats: Cell<TypecheckStats>,
 }
 
 impl<'a> Typechecker<'a> {
@@ -85,9 +118,27 @@ impl<'a> Typechecker<'a> {
             extensions,
             mode,
             policy_id,
+            slot_bounds: HashMap::new(),
+            entity_comparisons: RefCell::new(HashMap::new()),
+            defaulted_attributes: RefCell::new(HashMap::new()),
+            stats: Cell::new(TypecheckStats::default()),
         }
     }
 
+    /// How much work this typechecker has done so far
+    pub fn stats(&self) -> TypecheckStats {
+        self.stats.get()
+    }
+
+    /// Only consider links of template slots with the given entity types.
+    /// Slots without an entry in `slot_bounds` may still be linked with any
+    /// entity type admitted by the template scope.
+    #[must_use]
+    pub fn with_slot_bounds(mut self, slot_bounds: HashMap<SlotId, HashSet<EntityType>>) -> Self {
+        self.slot_bounds = slot_bounds;
+        self
+    }
+
     /// The main entry point for typechecking policies. Checks that the policy
     /// expression has type boolean. If typechecking succeeds, then the method
     /// will return true, and no items will be added to the output list.
@@ -104,23 +155,44 @@ impl<'a> Typechecker<'a> {
         warnings: &mut HashSet<ValidationWarning>,
     ) -> bool {
         let typecheck_answers = self.typecheck_by_request_env(t);
+        self.consolidate_request_env_checks(t, typecheck_answers, type_errors, warnings)
+    }
 
+    /// Consolidate the results of typechecking `t` under each request
+    /// environment, as returned by `typecheck_by_request_env`, into the errors
+    /// and warnings for the policy. Returns true if typechecking succeeded,
+    /// like `typecheck_policy`.
+    pub(crate) fn consolidate_request_env_checks(
+        &self,
+        t: &Template,
+        typecheck_answers: Vec<(RequestEnv<'_>, PolicyCheck)>,
+        type_errors: &mut HashSet<ValidationError>,
+        warnings: &mut HashSet<ValidationWarning>,
+    ) -> bool {
         // consolidate the results from each query environment
-        let (all_false, all_succ) = typecheck_answers.into_iter().fold(
+        let mut errors = Vec::new();
+        let (all_false, mut all_succ) = typecheck_answers.into_iter().fold(
             (true, true),
             |(all_false, all_succ), (_, check)| match check {
                 PolicyCheck::Success(_) => (false, all_succ),
                 PolicyCheck::Irrelevant(err, _) => {
                     let no_err = err.is_empty();
-                    type_errors.extend(err);
+                    errors.extend(err);
                     (all_false, all_succ && no_err)
                 }
                 PolicyCheck::Fail(err) => {
-                    type_errors.extend(err);
+                    errors.extend(err);
                     (false, false)
                 }
             },
         );
+        // An attribute access with a default is an error only if the
+        // attribute is not declared in any request environment.
+        for attr_errors in self.defaulted_attributes.take().into_values().flatten() {
+            errors.extend(attr_errors);
+            all_succ = false;
+        }
+        type_errors.extend(Self::merge_undefined_action_attributes(errors));
 
         // If every policy typechecked with type false, then the policy cannot
         // possibly apply to any request.
@@ -131,9 +203,54 @@ impl<'a> Typechecker<'a> {
             ));
         }
 
+        // Report entity comparisons which were `false` in every request
+        // environment where they were reached.
+        for (loc, comparison) in self.entity_comparisons.take() {
+            if let Some(comparison) = comparison {
+                warnings.insert(ValidationWarning::incompatible_entity_comparison(
+                    Some(loc),
+                    t.id().clone(),
+                    comparison.lhs_loc,
+                    comparison.rhs_loc,
+                    comparison.is_in,
+                    comparison.lhs_types,
+                    comparison.rhs_types,
+                ));
+            }
+        }
+
         all_succ
     }
 
+    /// Each request environment reports the access to an undeclared action
+    /// attribute for its own action. Merge those reports into one per access,
+    /// naming every action which lacks the attribute.
+    fn merge_undefined_action_attributes(
+        errors: Vec<ValidationError>,
+    ) -> impl Iterator<Item = ValidationError> {
+        let mut merged: HashMap<(Option<Loc>, SmolStr), UndefinedActionAttribute> = HashMap::new();
+        let others = errors
+            .into_iter()
+            .filter_map(|err| match err {
+                ValidationError::UndefinedActionAttribute(err) => {
+                    match merged.entry((err.source_loc.clone(), err.attr.clone())) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(err);
+                        }
+                        Entry::Occupied(mut entry) => {
+                            entry.get_mut().actions.extend(err.actions);
+                        }
+                    }
+                    None
+                }
+                err => Some(err),
+            })
+            .collect::<Vec<_>>();
+        others
+            .into_iter()
+            .chain(merged.into_values().map(ValidationError::from))
+    }
+
     /// Secondary entry point for typechecking requests. This method takes a policy and
     /// typechecks it under every schema-defined request environment. The result contains
     /// these environments and the individual typechecking response for each, in no
@@ -167,6 +284,69 @@ impl<'a> Typechecker<'a> {
         })
     }
 
+    /// Entry point for typechecking a standalone expression, which need not
+    /// be boolean, under a single request environment. Returns the type of
+    /// `e` if it typechecks. Otherwise, returns `None` and `type_errors` is
+    /// populated with any errors encountered while typechecking.
+    pub fn typecheck_standalone_expr(
+        &self,
+        request_env: &RequestEnv<'_>,
+        e: &Expr,
+        type_errors: &mut Vec<ValidationError>,
+    ) -> Option<Type> {
+        let empty_prior_capability = CapabilitySet::new();
+        let ans = self.typecheck(request_env, &empty_prior_capability, e, type_errors);
+        if ans.typechecked() {
+            ans.into_typed_expr().and_then(|e| e.into_data())
+        } else {
+            None
+        }
+    }
+
+    /// Entry point for inferring the type of a standalone expression under a
+    /// single request environment. Unlike `typecheck_standalone_expr`, the
+    /// type is returned whenever one can be inferred, even if `e` fails to
+    /// typecheck.
+    pub fn infer_standalone_expr_type(
+        &self,
+        request_env: &RequestEnv<'_>,
+        e: &Expr,
+    ) -> Option<Type> {
+        let mut type_errors = Vec::new();
+        let empty_prior_capability = CapabilitySet::new();
+        self.typecheck(request_env, &empty_prior_capability, e, &mut type_errors)
+            .into_typed_expr()
+            .and_then(|e| e.into_data())
+    }
+
+    /// Entry point for querying the types inferred for the condition of a
+    /// policy. This method typechecks the policy under every schema-defined
+    /// request environment, returning each environment with the typed
+    /// condition. Unlike `typecheck_by_request_env`, the typed condition is
+    /// kept when typechecking fails, with types for the sub-expressions which
+    /// could still be typed.
+    pub fn typed_condition_by_request_env<'b>(
+        &'b self,
+        t: &'b Template,
+    ) -> Vec<(RequestEnv<'b>, Expr<Option<Type>>)> {
+        self.apply_typecheck_fn_by_request_env(t, |request, expr| {
+            let mut type_errors = Vec::new();
+            let empty_prior_capability = CapabilitySet::new();
+            self.expect_type(
+                request,
+                &empty_prior_capability,
+                expr,
+                Type::primitive_boolean(),
+                &mut type_errors,
+                |_| None,
+            )
+            .into_typed_expr()
+        })
+        .into_iter()
+        .filter_map(|(env, e)| Some((env, e?)))
+        .collect()
+    }
+
     /// Utility abstracting the common logic for strict and regular typechecking
     /// by request environment.
     fn apply_typecheck_fn_by_request_env<'b, F, C>(
@@ -188,6 +368,9 @@ impl<'a> Typechecker<'a> {
             .unlinked_request_envs()
             .flat_map(|env| self.link_request_env(env, t))
         {
+            let mut stats = self.stats.get();
+            stats.request_envs += 1;
+            self.stats.set(stats);
             let check = typecheck_fn(&requeste, policy_condition);
             result_checks.push((requeste, check))
         }
@@ -239,7 +422,7 @@ impl<'a> Typechecker<'a> {
         env_checks
     }
 
-    fn unlinked_request_envs(&self) -> impl Iterator<Item = RequestEnv<'_>> + '_ {
+    pub(crate) fn unlinked_request_envs(&self) -> impl Iterator<Item = RequestEnv<'_>> + '_ {
         // Gather all of the actions declared in the schema.
         let all_actions = self
             .schema
@@ -329,7 +512,7 @@ impl<'a> Typechecker<'a> {
     ) -> Box<dyn Iterator<Item = Option<EntityType>> + 'a> {
         if t.slots().any(|t_slot| t_slot.id == slot_id) {
             let all_entity_types = self.schema.entity_types();
-            match constraint {
+            let links: Box<dyn Iterator<Item = Option<EntityType>> + 'a> = match constraint {
                 // The condition is `var = ?slot`, so the policy can only apply
                 // if the slot has the same entity type as `var`.
                 PrincipalOrResourceConstraint::Eq(_) => {
@@ -354,6 +537,17 @@ impl<'a> Typechecker<'a> {
                 PrincipalOrResourceConstraint::Is(_) | PrincipalOrResourceConstraint::Any => {
                     Box::new(all_entity_types.map(|(name, _)| Some(name.clone())))
                 }
+            };
+            match self.slot_bounds.get(&slot_id) {
+                Some(bounds) => {
+                    let bounds = bounds.clone();
+                    Box::new(
+                        links.filter(move |ety| {
+                            ety.as_ref().is_some_and(|ety| bounds.contains(ety))
+                        }),
+                    )
+                }
+                None => links,
             }
         } else {
             // If the template does not contain this slot, then we don't need to
@@ -379,6 +573,9 @@ impl<'a> Typechecker<'a> {
         if stacker::remaining_stack().unwrap_or(0) < REQUIRED_STACK_SPACE {
             return TypecheckAnswer::RecursionLimit;
         }
+        let mut stats = self.stats.get();
+        stats.expressions += 1;
+        self.stats.set(stats);
 
         match e.expr_kind() {
             // Principal, resource, and context have types defined by
@@ -506,7 +703,12 @@ impl<'a> Typechecker<'a> {
                     type_errors,
                     |_| None,
                 );
+                let is_default_access = is_attribute_access_with_default(test_expr, then_expr);
                 ans_test.then_typecheck(|typ_test, test_capability| {
+                    if is_default_access && typ_test.data() != &Some(Type::singleton_boolean(false))
+                    {
+                        self.record_defaulted_attribute(e, None);
+                    }
                     // If the guard has type `true` or `false`, we short circuit,
                     // looking at only the relevant branch.
                     if typ_test.data() == &Some(Type::singleton_boolean(true)) {
@@ -530,6 +732,27 @@ impl<'a> Typechecker<'a> {
                             )
                         })
                     } else if typ_test.data() == &Some(Type::singleton_boolean(false)) {
+                        // An attribute access with a default, `e.getOr("a", d)`,
+                        // is parsed as `if e has a then e.a else d`. The `then`
+                        // branch is never evaluated, but typechecking it anyway
+                        // finds an attribute which is not declared for `e`.
+                        // That is only reported if no other request environment
+                        // declares it either, since it is most likely a typo.
+                        if is_default_access {
+                            let mut then_errors = Vec::new();
+                            let then_typechecks = self
+                                .typecheck(
+                                    request_env,
+                                    &prior_capability.union(&test_capability),
+                                    then_expr,
+                                    &mut then_errors,
+                                )
+                                .typechecked();
+                            self.record_defaulted_attribute(
+                                e,
+                                (!then_typechecks).then_some(then_errors),
+                            );
+                        }
                         // The `else` branch cannot use the `test` capability since
                         // we know in the `else` branch that the condition
                         // evaluated to `false`. It still can use the original
@@ -857,6 +1080,18 @@ impl<'a> Typechecker<'a> {
                                 )
                             }
                             None => {
+                                if let Some(action) =
+                                    Self::action_uid_of(request_env, &typ_expr_actual)
+                                {
+                                    type_errors.push(ValidationError::undefined_action_attribute(
+                                        e.source_loc().cloned(),
+                                        self.policy_id.clone(),
+                                        attr.clone(),
+                                        BTreeSet::from([action]),
+                                        self.schema.actions_with_attr(attr).cloned().collect(),
+                                    ));
+                                    return TypecheckAnswer::fail(annot_expr);
+                                }
                                 let borrowed =
                                     all_attrs.iter().map(|s| s.as_str()).collect::<Vec<_>>();
                                 let suggestion = fuzzy_search(attr, &borrowed);
@@ -1010,12 +1245,28 @@ impl<'a> Typechecker<'a> {
                         _ => None,
                     },
                 );
+                // Patterns from policy text are always valid, but patterns
+                // from JSON policies may have malformed classes or repetitions
+                let pattern_valid = match pattern.validate() {
+                    Ok(()) => true,
+                    Err(err) => {
+                        type_errors.push(ValidationError::invalid_pattern(
+                            e.source_loc().cloned(),
+                            self.policy_id.clone(),
+                            &err,
+                        ));
+                        false
+                    }
+                };
                 actual.then_typecheck(|actual_expr_ty, _| {
-                    TypecheckAnswer::success(
-                        ExprBuilder::with_data(Some(Type::primitive_boolean()))
-                            .with_same_source_loc(e)
-                            .like(actual_expr_ty, pattern.clone()),
-                    )
+                    let like = ExprBuilder::with_data(Some(Type::primitive_boolean()))
+                        .with_same_source_loc(e)
+                        .like(actual_expr_ty, pattern.clone());
+                    if pattern_valid {
+                        TypecheckAnswer::success(like)
+                    } else {
+                        TypecheckAnswer::fail(like)
+                    }
                 })
             }
 
@@ -1220,6 +1471,7 @@ impl<'a> Typechecker<'a> {
                             arg2,
                             rhs_ty.data(),
                         );
+                        self.record_entity_comparison(bin_expr, &lhs_ty, &rhs_ty, false);
 
                         if self.mode.is_strict() {
                             let annotated_eq = ExprBuilder::with_data(Some(type_of_eq))
@@ -1365,7 +1617,7 @@ impl<'a> Typechecker<'a> {
                 })
             }
 
-            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul => {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
                 let help_builder = |actual: &Type| match (op, actual) {
                     (
                         BinaryOp::Add,
@@ -1394,11 +1646,30 @@ impl<'a> Typechecker<'a> {
                         help_builder,
                     );
                     ans_arg2.then_typecheck(|expr_ty_arg2, _| {
-                        TypecheckAnswer::success(
-                            ExprBuilder::with_data(Some(Type::primitive_long()))
-                                .with_same_source_loc(bin_expr)
-                                .binary_app(*op, expr_ty_arg1, expr_ty_arg2),
-                        )
+                        let divides_by_zero = matches!(op, BinaryOp::Div | BinaryOp::Rem)
+                            && constant_long(&expr_ty_arg2) == Some(0);
+                        let overflows = matches!(
+                            (constant_long(&expr_ty_arg1), constant_long(&expr_ty_arg2)),
+                            (Some(lhs), Some(rhs)) if checked_long_op(*op, lhs, rhs).is_none()
+                        );
+                        let expr = ExprBuilder::with_data(Some(Type::primitive_long()))
+                            .with_same_source_loc(bin_expr)
+                            .binary_app(*op, expr_ty_arg1, expr_ty_arg2);
+                        if divides_by_zero {
+                            type_errors.push(ValidationError::division_by_zero(
+                                bin_expr.source_loc().cloned(),
+                                self.policy_id.clone(),
+                            ));
+                            TypecheckAnswer::fail(expr)
+                        } else if overflows {
+                            type_errors.push(ValidationError::arithmetic_overflow(
+                                bin_expr.source_loc().cloned(),
+                                self.policy_id.clone(),
+                            ));
+                            TypecheckAnswer::fail(expr)
+                        } else {
+                            TypecheckAnswer::success(expr)
+                        }
                     })
                 })
             }
@@ -1529,6 +1800,51 @@ impl<'a> Typechecker<'a> {
                 })
             }
 
+            BinaryOp::Union | BinaryOp::Intersection => {
+                // Both arguments to a `union` or `intersection` must be sets.
+                self.expect_type(
+                    request_env,
+                    prior_capability,
+                    arg1,
+                    Type::any_set(),
+                    type_errors,
+                    |_| None,
+                )
+                .then_typecheck(|expr_ty_arg1, _| {
+                    self.expect_type(
+                        request_env,
+                        prior_capability,
+                        arg2,
+                        Type::any_set(),
+                        type_errors,
+                        |_| None,
+                    )
+                    .then_typecheck(|expr_ty_arg2, _| {
+                        // The result is a set of the least upper bound of the
+                        // element types, as for a set literal containing the
+                        // elements of both sets.
+                        let set_lub = self.least_upper_bound_or_error(
+                            bin_expr,
+                            [expr_ty_arg1.data().clone(), expr_ty_arg2.data().clone()],
+                            type_errors,
+                            LubContext::SetOperation,
+                        );
+                        match set_lub {
+                            Some(set_lub) => TypecheckAnswer::success(
+                                ExprBuilder::with_data(Some(set_lub))
+                                    .with_same_source_loc(bin_expr)
+                                    .binary_app(*op, expr_ty_arg1, expr_ty_arg2),
+                            ),
+                            None => TypecheckAnswer::fail(
+                                ExprBuilder::new()
+                                    .with_same_source_loc(bin_expr)
+                                    .binary_app(*op, expr_ty_arg1, expr_ty_arg2),
+                            ),
+                        }
+                    })
+                })
+            }
+
             BinaryOp::HasTag => self
                 .expect_type(
                     request_env,
@@ -1767,7 +2083,8 @@ impl<'a> Typechecker<'a> {
                             unannotated_expr.source_loc().cloned(),
                             self.policy_id.clone(),
                             [lhs_ty.clone(), rhs_ty.clone()],
-                            lub_hint,
+                            Self::entity_uid_string_hint(unannotated_expr, lhs_ty, rhs_ty)
+                                .unwrap_or(lub_hint),
                             context,
                         ));
                         TypecheckAnswer::fail(annotated_expr)
@@ -1784,6 +2101,34 @@ impl<'a> Typechecker<'a> {
         }
     }
 
+    /// If `eq_expr` compares an entity with a string literal holding an entity
+    /// UID, as in `principal == "User::\"alice\""`, suggest using the entity
+    /// literal instead.
+    fn entity_uid_string_hint(eq_expr: &Expr, lhs_ty: &Type, rhs_ty: &Type) -> Option<LubHelp> {
+        let ExprKind::BinaryApp {
+            op: BinaryOp::Eq,
+            arg1,
+            arg2,
+        } = eq_expr.expr_kind()
+        else {
+            return None;
+        };
+        [(arg1, rhs_ty), (arg2, lhs_ty)]
+            .into_iter()
+            .filter(|(_, other_ty)| other_ty.is_entity_type())
+            .find_map(|(string_arg, _)| Self::euid_from_string_literal(string_arg))
+            .map(LubHelp::EntityUidString)
+    }
+
+    /// If `e` is a string literal holding an entity UID, such as
+    /// `"User::\"alice\""`, return that entity UID.
+    fn euid_from_string_literal(e: &Expr) -> Option<EntityUID> {
+        match e.expr_kind() {
+            ExprKind::Lit(Literal::String(s)) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
     /// Get the type for an `==` expression given the input types.
     fn type_of_equality<'b>(
         &self,
@@ -1880,7 +2225,14 @@ impl<'a> Typechecker<'a> {
             lhs,
             Type::any_entity_reference(),
             type_errors,
-            |_| Some(UnexpectedTypeHelp::TryUsingContains),
+            |_| {
+                Some(
+                    Self::euid_from_string_literal(lhs)
+                        .map_or(UnexpectedTypeHelp::TryUsingContains, |euid| {
+                            UnexpectedTypeHelp::TryUsingEntityLiteral(euid)
+                        }),
+                )
+            },
         );
         let ty_rhs = self.expect_one_of_types(
             request_env,
@@ -1895,7 +2247,12 @@ impl<'a> Typechecker<'a> {
                 Type::Set { .. } => Some(UnexpectedTypeHelp::TryUsingContains),
                 Type::Primitive {
                     primitive_type: Primitive::String,
-                } => Some(UnexpectedTypeHelp::TryUsingLike),
+                } => Some(
+                    Self::euid_from_string_literal(rhs)
+                        .map_or(UnexpectedTypeHelp::TryUsingLike, |euid| {
+                            UnexpectedTypeHelp::TryUsingEntityLiteral(euid)
+                        }),
+                ),
                 _ => None,
             },
         );
@@ -1914,6 +2271,7 @@ impl<'a> Typechecker<'a> {
                             .is_in(lhs_expr, rhs_expr),
                     );
                 }
+                self.record_entity_comparison(in_expr, &lhs_expr, &rhs_expr, true);
                 let lhs_ty = lhs_expr.data().clone();
                 let rhs_ty = rhs_expr.data().clone();
                 let lhs_as_euid_lit = Typechecker::replace_action_var_with_euid(request_env, lhs);
@@ -2056,6 +2414,115 @@ impl<'a> Typechecker<'a> {
         })
     }
 
+    /// Record the errors for an attribute access with a default in this
+    /// request environment, or `None` if the attribute may be declared.
+    fn record_defaulted_attribute(&self, e: &Expr, errors: Option<Vec<ValidationError>>) {
+        let Some(loc) = e.source_loc() else {
+            return;
+        };
+        match self.defaulted_attributes.borrow_mut().entry(loc.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(errors);
+            }
+            Entry::Occupied(mut entry) => match (entry.get_mut(), errors) {
+                (Some(seen), Some(errors)) => seen.extend(errors),
+                (seen, _) => *seen = None,
+            },
+        }
+    }
+
+    /// Record whether an `==` (or `in`, if `is_in`) between the typechecked
+    /// operands can be `true` in the current request environment, based only
+    /// on the entity types of the operands. Comparisons of anything other
+    /// than entities with known types are always considered possible.
+    fn record_entity_comparison(
+        &self,
+        cmp_expr: &Expr,
+        lhs_expr: &Expr<Option<Type>>,
+        rhs_expr: &Expr<Option<Type>>,
+        is_in: bool,
+    ) {
+        let Some(loc) = cmp_expr.source_loc() else {
+            return;
+        };
+        let lhs_types = lhs_expr.data().as_ref().and_then(Self::entity_types_of);
+        let rhs_types = rhs_expr.data().as_ref().and_then(|ty| match ty {
+            Type::Set {
+                element_type: Some(element_type),
+            } if is_in => Self::entity_types_of(element_type),
+            _ => Self::entity_types_of(ty),
+        });
+        let comparison = match (lhs_types, rhs_types) {
+            (Some(lhs_types), Some(rhs_types))
+                if !self.entity_types_may_coincide(&lhs_types, &rhs_types, is_in) =>
+            {
+                Some(IncompatibleComparison {
+                    lhs_loc: lhs_expr.source_loc().cloned(),
+                    rhs_loc: rhs_expr.source_loc().cloned(),
+                    is_in,
+                    lhs_types,
+                    rhs_types,
+                })
+            }
+            _ => None,
+        };
+        match self.entity_comparisons.borrow_mut().entry(loc.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(comparison);
+            }
+            Entry::Occupied(mut entry) => match (entry.get_mut(), comparison) {
+                (Some(seen), Some(comparison)) => {
+                    seen.lhs_types.extend(comparison.lhs_types);
+                    seen.rhs_types.extend(comparison.rhs_types);
+                }
+                (seen, _) => *seen = None,
+            },
+        }
+    }
+
+    /// The entity types an expression of type `ty` may have, if `ty` is an
+    /// entity type with a known least upper bound.
+    fn entity_types_of(ty: &Type) -> Option<BTreeSet<EntityType>> {
+        match ty {
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
+                Some(lub.iter().cloned().collect())
+            }
+            Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. }) => {
+                Some(BTreeSet::from([name.clone()]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if an entity with one of the `lhs_types` may be equal to (or
+    /// `in`, if `is_in`) an entity with one of the `rhs_types`. Entity types
+    /// not declared in the schema may coincide with anything.
+    fn entity_types_may_coincide(
+        &self,
+        lhs_types: &BTreeSet<EntityType>,
+        rhs_types: &BTreeSet<EntityType>,
+        is_in: bool,
+    ) -> bool {
+        if lhs_types
+            .iter()
+            .chain(rhs_types)
+            .any(|ty| !self.schema.is_known_entity_type(ty))
+        {
+            return true;
+        }
+        lhs_types.iter().any(|lhs_ty| {
+            rhs_types.iter().any(|rhs_ty| {
+                lhs_ty == rhs_ty
+                    || (is_in
+                        && ((lhs_ty.is_action() && rhs_ty.is_action())
+                            || self
+                                .schema
+                                .get_entity_type(rhs_ty)
+                                .is_some_and(|ety| ety.descendants.contains(lhs_ty))))
+            })
+        })
+    }
+
     fn get_as_single_entity_type(ty: Type) -> Option<EntityType> {
         match ty {
             Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => lub.into_single_entity(),
@@ -2383,11 +2850,20 @@ impl<'a> Typechecker<'a> {
                     |_| None,
                 );
                 ans_arg.then_typecheck(|typ_expr_arg, _| {
-                    TypecheckAnswer::success(
-                        ExprBuilder::with_data(Some(Type::primitive_long()))
-                            .with_same_source_loc(unary_expr)
-                            .neg(typ_expr_arg),
-                    )
+                    let overflows =
+                        matches!(constant_long(&typ_expr_arg), Some(i) if i.checked_neg().is_none());
+                    let expr = ExprBuilder::with_data(Some(Type::primitive_long()))
+                        .with_same_source_loc(unary_expr)
+                        .neg(typ_expr_arg);
+                    if overflows {
+                        type_errors.push(ValidationError::arithmetic_overflow(
+                            unary_expr.source_loc().cloned(),
+                            self.policy_id.clone(),
+                        ));
+                        TypecheckAnswer::fail(expr)
+                    } else {
+                        TypecheckAnswer::success(expr)
+                    }
                 })
             }
             UnaryOp::IsEmpty => {
@@ -2540,6 +3016,22 @@ impl<'a> Typechecker<'a> {
             })
     }
 
+    /// The action which `e` is, if `e` has an action entity type and is either
+    /// the `action` variable or an action entity literal
+    fn action_uid_of(request_env: &RequestEnv<'_>, e: &Expr<Option<Type>>) -> Option<EntityUID> {
+        if !matches!(
+            e.data(),
+            Some(Type::EntityOrRecord(EntityRecordKind::ActionEntity { .. }))
+        ) {
+            return None;
+        }
+        match e.expr_kind() {
+            ExprKind::Var(Var::Action) => request_env.action_entity_uid().cloned(),
+            ExprKind::Lit(Literal::EntityUID(euid)) => Some(euid.as_ref().clone()),
+            _ => None,
+        }
+    }
+
     /// If the `maybe_action_var` expression is `Expr::Var(Var::Action)`, return
     /// a expression for the entity uid for the action variable in the request
     /// environment. Otherwise, return the expression unchanged.
@@ -2567,6 +3059,7 @@ impl<'a> Typechecker<'a> {
                 e.source_loc().cloned(),
                 self.policy_id.clone(),
                 f.to_string(),
+                self.extensions.suggest_func(f),
             )
         })
     }
@@ -2680,3 +3173,51 @@ impl<'a> Typechecker<'a> {
         }
     }
 }
+
+/// The value of `e` if it is `Long` arithmetic on literals which does not
+/// overflow. Used to report arithmetic that is guaranteed to overflow at
+/// validation time instead of as an evaluation error.
+fn constant_long(e: &Expr<Option<Type>>) -> Option<Integer> {
+    match e.expr_kind() {
+        ExprKind::Lit(Literal::Long(i)) => Some(*i),
+        ExprKind::UnaryApp {
+            op: UnaryOp::Neg,
+            arg,
+        } => constant_long(arg)?.checked_neg(),
+        ExprKind::BinaryApp { op, arg1, arg2 } => {
+            checked_long_op(*op, constant_long(arg1)?, constant_long(arg2)?)
+        }
+        _ => None,
+    }
+}
+
+/// Apply the arithmetic operator `op` to `lhs` and `rhs`, or return `None` if
+/// the result overflows, divides by zero, or `op` is not an arithmetic operator
+fn checked_long_op(op: BinaryOp, lhs: Integer, rhs: Integer) -> Option<Integer> {
+    match op {
+        BinaryOp::Add => lhs.checked_add(rhs),
+        BinaryOp::Sub => lhs.checked_sub(rhs),
+        BinaryOp::Mul => lhs.checked_mul(rhs),
+        BinaryOp::Div => lhs.checked_div(rhs),
+        BinaryOp::Rem if rhs != 0 => Some(lhs.wrapping_rem(rhs)),
+        _ => None,
+    }
+}
+
+/// Is `if test_expr then then_expr else ..` of the form
+/// `if e has a then e.a else ..`, as produced by `e.getOr("a", ..)`?
+fn is_attribute_access_with_default(test_expr: &Expr, then_expr: &Expr) -> bool {
+    match (test_expr.expr_kind(), then_expr.expr_kind()) {
+        (
+            ExprKind::HasAttr {
+                expr: has_expr,
+                attr: has_attr,
+            },
+            ExprKind::GetAttr {
+                expr: get_expr,
+                attr: get_attr,
+            },
+        ) => has_attr == get_attr && has_expr.eq_shape(get_expr),
+        _ => false,
+    }
+}
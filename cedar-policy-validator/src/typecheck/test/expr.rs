@@ -20,12 +20,12 @@
 
 use std::{str::FromStr, vec};
 
+#[cfg(feature = "datetime")]
+use cedar_policy_core::ast::Name;
 use cedar_policy_core::{
-    ast::{BinaryOp, EntityUID, Expr, Name, Pattern, PatternElem, SlotId, Value, Var},
+    ast::{BinaryOp, CharClass, EntityUID, Expr, Pattern, PatternElem, PatternError, SlotId, Var},
     est::Annotations,
-    extensions::Extensions,
 };
-use itertools::Itertools;
 use serde_json::json;
 use smol_str::SmolStr;
 
@@ -984,6 +984,80 @@ fn contains_all_typecheck_literals_false() {
     );
 }
 
+#[test]
+fn union_intersection_typechecks() {
+    assert_typechecks_empty_schema(
+        Expr::union(Expr::set([Expr::val(1)]), Expr::set([Expr::val(2)])),
+        Type::set(Type::primitive_long()),
+    );
+    assert_typechecks_empty_schema(
+        Expr::intersection(Expr::set([Expr::val(true)]), Expr::set([Expr::val(false)])),
+        Type::set(Type::primitive_boolean()),
+    );
+}
+
+#[test]
+fn union_typecheck_fails() {
+    let src = "1.union(true)";
+    let errors = assert_typecheck_fails_empty_schema_without_type(src.parse().unwrap());
+    assert_sets_equal(
+        errors,
+        [
+            ValidationError::expected_type(
+                get_loc(src, "1"),
+                expr_id_placeholder(),
+                Type::any_set(),
+                Type::primitive_long(),
+                None,
+            ),
+            ValidationError::expected_type(
+                get_loc(src, "true"),
+                expr_id_placeholder(),
+                Type::any_set(),
+                Type::singleton_boolean(true),
+                None,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn intersection_heterogeneous_sets() {
+    let src = r#"[1].intersection(["a"])"#;
+    let errors = assert_typecheck_fails_empty_schema_without_type(src.parse().unwrap());
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::incompatible_types(
+            get_loc(src, src),
+            expr_id_placeholder(),
+            [
+                Type::set(Type::primitive_long()),
+                Type::set(Type::primitive_string()),
+            ],
+            LubHelp::None,
+            LubContext::SetOperation,
+        )
+    );
+}
+
+#[test]
+fn set_arithmetic_help() {
+    let src = "[1] + 2";
+    let errors = assert_typecheck_fails_empty_schema(src.parse().unwrap(), Type::primitive_long());
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::expected_type(
+            get_loc(src, "[1]"),
+            expr_id_placeholder(),
+            Type::primitive_long(),
+            Type::set(Type::primitive_long()),
+            Some(UnexpectedTypeHelp::SetOperationsNotSupported),
+        )
+    );
+}
+
 #[test]
 fn is_empty_typechecks() {
     assert_typechecks_empty_schema(
@@ -1043,18 +1117,60 @@ fn like_typecheck_fails() {
     );
 }
 
+#[test]
+fn like_char_class_typechecks() {
+    assert_typechecks_empty_schema(
+        r#""user-42" like "user-\[0-9]\{1,4}""#.parse().unwrap(),
+        Type::primitive_boolean(),
+    );
+}
+
+#[test]
+fn like_invalid_pattern_fails() {
+    // Only reachable for patterns constructed programmatically or from JSON,
+    // since the parser rejects them
+    let errors = assert_typecheck_fails_empty_schema(
+        Expr::like(
+            Expr::val("foo"),
+            Pattern::from(vec![PatternElem::Class(CharClass::new(
+                false,
+                [('z', 'a')],
+            ))]),
+        ),
+        Type::primitive_boolean(),
+    );
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::invalid_pattern(
+            None,
+            expr_id_placeholder(),
+            &PatternError::InvalidRange { lo: 'z', hi: 'a' },
+        )
+    );
+    assert_eq!(
+        error.to_string(),
+        "for policy `expr`, invalid pattern in `like` expression: invalid character range `z-a` in pattern"
+    );
+}
+
+#[cfg(feature = "datetime")]
 #[inline]
 fn get_datetime_constructor_name() -> Name {
     "datetime".parse().unwrap()
 }
 
+#[cfg(feature = "datetime")]
 #[inline]
 fn get_duration_constructor_name() -> Name {
     "duration".parse().unwrap()
 }
 
 #[test]
+#[cfg(feature = "datetime")]
 fn less_than_typechecks() {
+    use cedar_policy_core::ast::Value;
+
     assert_typechecks_empty_schema(
         Expr::less(Expr::val(1), Expr::val(2)),
         Type::primitive_boolean(),
@@ -1114,7 +1230,11 @@ fn less_than_typechecks() {
 }
 
 #[test]
+#[cfg(feature = "datetime")]
 fn less_than_typecheck_fails() {
+    use cedar_policy_core::extensions::Extensions;
+    use itertools::Itertools;
+
     let expected_types = Extensions::types_with_operator_overloading()
         .into_iter()
         .map(Type::extension)
@@ -1423,6 +1543,90 @@ fn add_sub_typecheck_fails() {
     );
 }
 
+#[test]
+fn constant_arithmetic_overflow_fails() {
+    for (src, overflowing) in [
+        ("9223372036854775807 + 1", "9223372036854775807 + 1"),
+        ("-9223372036854775807 - 2", "-9223372036854775807 - 2"),
+        ("4611686018427387904 * 2", "4611686018427387904 * 2"),
+        ("-(-9223372036854775807 - 1)", "-(-9223372036854775807 - 1)"),
+        (
+            "1 + 3037000500 * 3037000500 * 2",
+            "3037000500 * 3037000500 * 2",
+        ),
+        (
+            "(-9223372036854775807 - 1) / -1",
+            "(-9223372036854775807 - 1) / -1",
+        ),
+    ] {
+        let errors =
+            assert_typecheck_fails_empty_schema(src.parse().unwrap(), Type::primitive_long());
+        let error = assert_exactly_one_diagnostic(errors);
+        assert_eq!(
+            error,
+            ValidationError::arithmetic_overflow(get_loc(src, overflowing), expr_id_placeholder()),
+            "{src}"
+        );
+    }
+}
+
+#[test]
+fn constant_arithmetic_without_overflow_typechecks() {
+    for src in [
+        "9223372036854775806 + 1",
+        "-9223372036854775807 - 1",
+        "3037000499 * 3037000499",
+        "-(-9223372036854775807)",
+        "(9223372036854775807 + (-1)) + 1",
+        "(-9223372036854775807 - 1) % -1",
+        "7 / 2 * 3",
+    ] {
+        assert_typechecks_empty_schema(src.parse().unwrap(), Type::primitive_long());
+    }
+}
+
+#[test]
+fn division_typechecks() {
+    assert_typechecks_empty_schema(
+        r#"{"a": 10, "b": 3}.a / {"a": 10, "b": 3}.b % 2"#.parse().unwrap(),
+        Type::primitive_long(),
+    );
+}
+
+#[test]
+fn division_typecheck_fails() {
+    let src = r#"10 / "2""#;
+    let errors = assert_typecheck_fails_empty_schema(src.parse().unwrap(), Type::primitive_long());
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::expected_type(
+            get_loc(src, r#""2""#),
+            expr_id_placeholder(),
+            Type::primitive_long(),
+            Type::primitive_string(),
+            None,
+        )
+    );
+}
+
+#[test]
+fn division_by_constant_zero_fails() {
+    for (src, dividing) in [
+        (r#"{"a": 1}.a / 0"#, r#"{"a": 1}.a / 0"#),
+        ("1 + 7 % (1 - 1)", "7 % (1 - 1)"),
+    ] {
+        let errors =
+            assert_typecheck_fails_empty_schema(src.parse().unwrap(), Type::primitive_long());
+        let error = assert_exactly_one_diagnostic(errors);
+        assert_eq!(
+            error,
+            ValidationError::division_by_zero(get_loc(src, dividing), expr_id_placeholder()),
+            "{src}"
+        );
+    }
+}
+
 #[test]
 fn is_typecheck_fails() {
     let schema: json_schema::NamespaceDefinition<RawName> =
@@ -1474,3 +1678,28 @@ fn is_typechecks() {
         Type::singleton_boolean(false),
     );
 }
+
+#[test]
+#[cfg(all(feature = "decimal", feature = "ipaddr"))]
+fn undefined_extension_function_suggestion() {
+    for (name, suggestion) in [
+        ("Decimal", Some("decimal")),
+        ("inRange", Some("isInRange")),
+        ("frobnicate", None),
+    ] {
+        let errors = assert_typecheck_fails_empty_schema_without_type(Expr::call_extension_fn(
+            name.parse().unwrap(),
+            vec![Expr::val("1.0")],
+        ));
+        let error = assert_exactly_one_diagnostic(errors);
+        assert_eq!(
+            error,
+            ValidationError::undefined_extension(
+                None,
+                expr_id_placeholder(),
+                name.to_string(),
+                suggestion.map(ToString::to_string),
+            )
+        );
+    }
+}
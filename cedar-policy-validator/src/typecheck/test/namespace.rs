@@ -25,7 +25,7 @@ use std::str::FromStr;
 use std::vec;
 
 use cedar_policy_core::{
-    ast::{Expr, PolicyID, StaticPolicy},
+    ast::{EntityType, Expr, PolicyID, StaticPolicy},
     extensions::Extensions,
     parser::parse_policy,
 };
@@ -581,19 +581,27 @@ fn multi_namespace_action_eq() {
         .unwrap(),
     );
 
-    let policy = parse_policy(
-        None,
-        r#"permit(principal, action, resource) when { NS1::Action::"B" == NS2::Action::"B" };"#,
-    )
-    .unwrap();
+    let src =
+        r#"permit(principal, action, resource) when { NS1::Action::"B" == NS2::Action::"B" };"#;
+    let policy = parse_policy(None, src).unwrap();
     let warnings = assert_policy_typecheck_warns(schema, policy.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(
-            policy.loc().cloned(),
-            PolicyID::from_string("policy0"),
-        )
+    assert_sets_equal(
+        [
+            ValidationWarning::impossible_policy(
+                policy.loc().cloned(),
+                PolicyID::from_string("policy0"),
+            ),
+            ValidationWarning::incompatible_entity_comparison(
+                get_loc(src, r#"NS1::Action::"B" == NS2::Action::"B""#),
+                PolicyID::from_string("policy0"),
+                get_loc(src, r#"NS1::Action::"B""#),
+                get_loc(src, r#"NS2::Action::"B""#),
+                false,
+                [EntityType::from_normalized_str("NS1::Action").unwrap()],
+                [EntityType::from_normalized_str("NS2::Action").unwrap()],
+            ),
+        ],
+        warnings,
     );
 }
 
@@ -18,8 +18,11 @@
 //! access using the ability added by capabilities.
 // GRCOV_STOP_COVERAGE
 
+use std::collections::BTreeSet;
+
 use cedar_policy_core::{
     ast::{PolicyID, StaticPolicy},
+    extensions::Extensions,
     parser::parse_policy,
 };
 
@@ -758,12 +761,12 @@ fn action_attrs_failing() {
     let error = assert_exactly_one_diagnostic(errors);
     assert_eq!(
         error,
-        ValidationError::unsafe_attribute_access(
+        ValidationError::undefined_action_attribute(
             get_loc(src, "action.canUndo"),
             PolicyID::from_string("0"),
-            AttributeAccess::Other(vec!["canUndo".into()]),
-            Some("isReadOnly".to_string()),
-            false,
+            "canUndo".into(),
+            BTreeSet::from([r#"Action::"view""#.parse().unwrap()]),
+            BTreeSet::from([r#"Action::"edit""#.parse().unwrap()]),
         )
     );
 
@@ -803,3 +806,199 @@ fn action_attrs_failing() {
     let errors = assert_policy_typecheck_fails(schema, failing_policy);
     assert_sets_equal(errors, []);
 }
+
+#[test]
+fn undefined_action_attribute_names_every_action() {
+    let schema = serde_json::from_value::<NamespaceDefinitionWithActionAttributes<RawName>>(
+        serde_json::json!({
+            "entityTypes": { "User": {} },
+            "actions": {
+                "view": {
+                    "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] },
+                    "attributes": { "category": "read" }
+                },
+                "edit": {
+                    "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] },
+                    "attributes": { "category": "write", "canUndo": true }
+                },
+                "delete": {
+                    "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] }
+                }
+            }
+        }),
+    )
+    .expect("Expected valid schema.");
+    let view: cedar_policy_core::ast::EntityUID = r#"Action::"view""#.parse().unwrap();
+    let edit: cedar_policy_core::ast::EntityUID = r#"Action::"edit""#.parse().unwrap();
+    let delete: cedar_policy_core::ast::EntityUID = r#"Action::"delete""#.parse().unwrap();
+
+    // One error for the access, naming every action the policy applies to
+    // which lacks the attribute
+    let src = r#"permit(principal, action, resource) when { action.canUndo };"#;
+    let policy = parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+    let errors = assert_policy_typecheck_fails(schema.clone(), policy);
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::undefined_action_attribute(
+            get_loc(src, "action.canUndo"),
+            PolicyID::from_string("0"),
+            "canUndo".into(),
+            BTreeSet::from([view.clone(), delete.clone()]),
+            BTreeSet::from([edit.clone()]),
+        )
+    );
+    assert_eq!(
+        error.to_string(),
+        r#"for policy `0`, attribute `canUndo` is not declared on actions `Action::"delete"` and `Action::"view"`"#
+    );
+
+    // Action entity literals are reported the same way
+    let src =
+        r#"permit(principal, action, resource) when { Action::"delete".category == "read" };"#;
+    let policy = parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+    let errors = assert_policy_typecheck_fails(schema.clone(), policy);
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::undefined_action_attribute(
+            get_loc(src, r#"Action::"delete".category"#),
+            PolicyID::from_string("0"),
+            "category".into(),
+            BTreeSet::from([delete]),
+            BTreeSet::from([view, edit]),
+        )
+    );
+
+    // An attribute no action declares
+    let src =
+        r#"permit(principal, action == Action::"view", resource) when { action.owner == "ops" };"#;
+    let policy = parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+    let errors = assert_policy_typecheck_fails(schema, policy);
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        miette::Diagnostic::help(&error).map(|h| h.to_string()),
+        Some("no action in the schema declares attribute `owner`".to_string())
+    );
+}
+
+#[test]
+fn attribute_access_with_default() {
+    let policy = parse_policy(
+        Some(PolicyID::from_string("0")),
+        r#"permit(principal, action, resource) when { principal.getOr("name", "anonymous") == "foo" && resource.getOr("age", 0) > 18 };"#,
+    )
+    .expect("Policy should parse.");
+    assert_policy_typechecks(schema_with_optionals(), policy);
+}
+
+#[test]
+fn attribute_access_with_default_not_in_schema() {
+    let src = r#"permit(principal, action, resource) when { principal.getOr("nmae", "anonymous") == "foo" };"#;
+    let policy = parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+    let errors = assert_policy_typecheck_fails(schema_with_optionals(), policy);
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::unsafe_attribute_access(
+            get_loc(src, r#"principal.getOr("nmae", "anonymous")"#),
+            PolicyID::from_string("0"),
+            AttributeAccess::EntityLUB(
+                EntityLUB::single_entity("User".parse().unwrap()),
+                vec!["nmae".into()],
+            ),
+            Some("name".to_string()),
+            false,
+        )
+    );
+}
+
+#[test]
+fn attribute_access_with_default_some_entity_types() {
+    let schema = serde_json::from_str::<json_schema::NamespaceDefinition<RawName>>(
+        r#"
+{
+    "entityTypes": {
+        "User": {
+            "shape": {
+                "type": "Record",
+                "attributes": {
+                    "tier": { "type": "String", "required": false}
+                }
+            }
+        },
+        "Service": {}
+    },
+    "actions": {
+        "view_photo": {
+            "appliesTo": {
+                "principalTypes": ["User", "Service"],
+                "resourceTypes": ["User"]
+            }
+        }
+    }
+}
+    "#,
+    )
+    .expect("Expected valid schema.");
+    // `tier` is only an attribute of `User`, but `getOr` falls back to the
+    // default for a `Service`, just like the `if` it is shorthand for
+    for src in [
+        r#"permit(principal, action, resource) when { principal.getOr("tier", "free") == "gold" };"#,
+        r#"permit(principal, action, resource) when { (if principal has tier then principal.tier else "free") == "gold" };"#,
+    ] {
+        let policy =
+            parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+        assert_policy_typechecks(schema.clone(), policy);
+    }
+}
+
+fn schema_with_nested_optional_context() -> json_schema::Fragment<RawName> {
+    json_schema::Fragment::from_cedarschema_str(
+        r#"
+        entity User;
+        action view appliesTo {
+            principal: User,
+            resource: User,
+            context: { a?: { b?: { c?: Long, d: String }, e?: Bool } },
+        };
+        "#,
+        Extensions::all_available(),
+    )
+    .expect("Expected valid schema.")
+    .0
+}
+
+#[test]
+fn nested_context_has_chain() {
+    for src in [
+        r#"permit(principal, action, resource) when { context has a && context.a has b && context.a.b has c && context.a.b.c == 1 };"#,
+        r#"permit(principal, action, resource) when { (context has a && context.a has b) && context.a.b.d == "x" };"#,
+        r#"permit(principal, action, resource) when { context has a && ((context.a has b && context.a.b.d == "x") || (context.a has e && context.a.e)) };"#,
+    ] {
+        let policy =
+            parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+        assert_policy_typechecks(schema_with_nested_optional_context(), policy);
+    }
+}
+
+#[test]
+fn error_in_and_chain_keeps_guards() {
+    // Only the unguarded access to `context.a.e` is reported. The guards
+    // before and after it still protect the access to `context.a.b.d`.
+    let src = r#"permit(principal, action, resource) when { context has a && context.a.e && context.a has b && context.a.b.d == "x" };"#;
+    let policy = parse_policy(Some(PolicyID::from_string("0")), src).expect("Policy should parse.");
+    let errors = assert_policy_typecheck_fails(schema_with_nested_optional_context(), policy);
+    let error = assert_exactly_one_diagnostic(errors);
+    assert_eq!(
+        error,
+        ValidationError::unsafe_optional_attribute_access(
+            get_loc(src, "context.a.e"),
+            PolicyID::from_string("0"),
+            AttributeAccess::Context(
+                r#"Action::"view""#.parse().unwrap(),
+                vec!["e".into(), "a".into()],
+            ),
+        )
+    );
+}
@@ -18,19 +18,19 @@
 //! files.
 // GRCOV_STOP_COVERAGE
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use cedar_policy_core::{
-    ast::{EntityUID, Expr, PolicyID, Template},
+    ast::{EntityType, EntityUID, Expr, PolicyID, StaticPolicy, Template},
     extensions::Extensions,
-    parser::{parse_policy, parse_policy_or_template},
+    parser::{parse_policy, parse_policy_or_template, Loc},
 };
 
 use super::test_utils::{
     assert_exactly_one_diagnostic, assert_policy_typecheck_fails,
     assert_policy_typecheck_fails_for_mode, assert_policy_typecheck_warns,
     assert_policy_typecheck_warns_for_mode, assert_policy_typechecks,
-    assert_policy_typechecks_for_mode, assert_typechecks, get_loc,
+    assert_policy_typechecks_for_mode, assert_sets_equal, assert_typechecks, get_loc,
 };
 use crate::{
     diagnostics::ValidationError,
@@ -395,11 +395,22 @@ fn policy_impossible_literal_euids() {
         r#"permit(principal, action, resource) when { Group::"foo" in User::"bar" };"#,
     )
     .expect("Policy should parse.");
+    let src = p.loc().unwrap().src.clone();
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
+    assert_sets_equal(
+        [
+            ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0")),
+            ValidationWarning::incompatible_entity_comparison(
+                get_loc(&src, r#"Group::"foo" in User::"bar""#),
+                PolicyID::from_string("0"),
+                get_loc(&src, r#"Group::"foo""#),
+                get_loc(&src, r#"User::"bar""#),
+                true,
+                [EntityType::from_normalized_str("Group").unwrap()],
+                [EntityType::from_normalized_str("User").unwrap()],
+            ),
+        ],
+        warnings,
     );
 }
 
@@ -426,6 +437,20 @@ fn policy_if_entities_lub() {
         ).expect("Policy should parse."));
 }
 
+/// Assert that the only warnings for `p` are that it is impossible and that
+/// the entity comparison making it impossible is always false.
+#[track_caller]
+fn assert_impossible_entity_comparison(p: &StaticPolicy, warnings: HashSet<ValidationWarning>) {
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+    assert!(warnings.contains(&ValidationWarning::impossible_policy(
+        p.loc().cloned(),
+        p.id().clone()
+    )));
+    assert!(warnings
+        .iter()
+        .any(|w| matches!(w, ValidationWarning::IncompatibleEntityComparison(_))));
+}
+
 #[test]
 fn policy_in_action_impossible() {
     let p = parse_policy(
@@ -433,11 +458,22 @@ fn policy_in_action_impossible() {
         r#"permit(principal, action, resource) when { User::"alice" in [action] };"#,
     )
     .expect("Policy should parse.");
+    let src = p.loc().unwrap().src.clone();
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
+    assert_sets_equal(
+        [
+            ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0")),
+            ValidationWarning::incompatible_entity_comparison(
+                get_loc(&src, r#"User::"alice" in [action]"#),
+                PolicyID::from_string("0"),
+                get_loc(&src, r#"User::"alice""#),
+                get_loc(&src, r#"[action]"#),
+                true,
+                [EntityType::from_normalized_str("User").unwrap()],
+                [EntityType::from_normalized_str("Action").unwrap()],
+            ),
+        ],
+        warnings,
     );
 
     let p = parse_policy(
@@ -445,11 +481,22 @@ fn policy_in_action_impossible() {
         r#"permit(principal, action, resource) when { User::"alice" in [Action::"view_photo"] };"#,
     )
     .expect("Policy should parse.");
+    let src = p.loc().unwrap().src.clone();
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
+    assert_sets_equal(
+        [
+            ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0")),
+            ValidationWarning::incompatible_entity_comparison(
+                get_loc(&src, r#"User::"alice" in [Action::"view_photo"]"#),
+                PolicyID::from_string("0"),
+                get_loc(&src, r#"User::"alice""#),
+                get_loc(&src, r#"[Action::"view_photo"]"#),
+                true,
+                [EntityType::from_normalized_str("User").unwrap()],
+                [EntityType::from_normalized_str("Action").unwrap()],
+            ),
+        ],
+        warnings,
     );
 
     let p = parse_policy(
@@ -458,11 +505,7 @@ fn policy_in_action_impossible() {
     )
     .expect("Policy should parse.");
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
-    );
+    assert_impossible_entity_comparison(&p, warnings);
 
     let p = parse_policy(
         Some(PolicyID::from_string("0")),
@@ -470,11 +513,7 @@ fn policy_in_action_impossible() {
     )
     .expect("Policy should parse.");
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
-    );
+    assert_impossible_entity_comparison(&p, warnings);
 
     let p = parse_policy(
         Some(PolicyID::from_string("0")),
@@ -482,11 +521,7 @@ fn policy_in_action_impossible() {
     )
     .expect("Policy should parse.");
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
-    );
+    assert_impossible_entity_comparison(&p, warnings);
 
     let p = parse_policy(
         Some(PolicyID::from_string("0")),
@@ -494,11 +529,7 @@ fn policy_in_action_impossible() {
     )
     .expect("Policy should parse.");
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
-    );
+    assert_impossible_entity_comparison(&p, warnings);
 
     let p = parse_policy(
         Some(PolicyID::from_string("0")),
@@ -524,11 +555,22 @@ fn policy_action_in_impossible() {
         r#"permit(principal, action, resource) when { action in [User::"alice"] };"#,
     )
     .expect("Policy should parse.");
+    let src = p.loc().unwrap().src.clone();
     let warnings = assert_policy_typecheck_warns(simple_schema_file(), p.clone());
-    let warning = assert_exactly_one_diagnostic(warnings);
-    assert_eq!(
-        warning,
-        ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0"),)
+    assert_sets_equal(
+        [
+            ValidationWarning::impossible_policy(p.loc().cloned(), PolicyID::from_string("0")),
+            ValidationWarning::incompatible_entity_comparison(
+                get_loc(&src, r#"action in [User::"alice"]"#),
+                PolicyID::from_string("0"),
+                Some(Loc::new(43..49, src.clone())),
+                get_loc(&src, r#"[User::"alice"]"#),
+                true,
+                [EntityType::from_normalized_str("Action").unwrap()],
+                [EntityType::from_normalized_str("User").unwrap()],
+            ),
+        ],
+        warnings,
     );
 }
 
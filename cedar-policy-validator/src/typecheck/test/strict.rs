@@ -34,6 +34,7 @@ use crate::{
     types::{AttributeType, CapabilitySet, OpenTag, RequestEnv, Type},
     validation_errors::LubContext,
     validation_errors::LubHelp,
+    validation_errors::UnexpectedTypeHelp,
     RawName, ValidationError, ValidationMode,
 };
 
@@ -239,6 +240,51 @@ fn eq_strict_types_mismatch() {
     })
 }
 
+#[test]
+fn eq_entity_uid_string_mismatch() {
+    with_simple_schema_and_request(|s, q| {
+        assert_types_must_match(
+            s,
+            &q,
+            Expr::from_str(r#"principal == "User::\"alice\"""#).unwrap(),
+            r#"principal == "User::\"alice\"""#,
+            Type::primitive_boolean(),
+            [
+                Type::named_entity_reference_from_str("User"),
+                Type::primitive_string(),
+            ],
+            LubHelp::EntityUidString(EntityUID::from_str(r#"User::"alice""#).unwrap()),
+            LubContext::Equality,
+        )
+    })
+}
+
+#[test]
+fn in_entity_uid_string_mismatch() {
+    with_simple_schema_and_request(|s, q| {
+        let src = r#"principal in "User::\"alice\"""#;
+        let e = Expr::from_str(src).unwrap();
+        assert_strict_type_error(
+            s,
+            &q,
+            e,
+            Type::primitive_boolean(),
+            ValidationError::expected_one_of_types(
+                get_loc(src, r#""User::\"alice\"""#),
+                expr_id_placeholder(),
+                vec![
+                    Type::set(Type::any_entity_reference()),
+                    Type::any_entity_reference(),
+                ],
+                Type::primitive_string(),
+                Some(UnexpectedTypeHelp::TryUsingEntityLiteral(
+                    EntityUID::from_str(r#"User::"alice""#).unwrap(),
+                )),
+            ),
+        )
+    })
+}
+
 #[test]
 fn contains_strict_types_mismatch() {
     with_simple_schema_and_request(|s, q| {
@@ -293,6 +339,25 @@ fn contains_all_strict_types_mismatch() {
     })
 }
 
+#[test]
+fn union_strict_types_mismatch() {
+    with_simple_schema_and_request(|s, q| {
+        assert_types_must_match(
+            s,
+            &q,
+            Expr::from_str(r#"[principal].union([resource])"#).unwrap(),
+            r#"[principal].union([resource])"#,
+            Type::any_set(),
+            [
+                Type::set(Type::named_entity_reference_from_str("User")),
+                Type::set(Type::named_entity_reference_from_str("Photo")),
+            ],
+            LubHelp::EntityType,
+            LubContext::SetOperation,
+        )
+    })
+}
+
 #[test]
 fn if_false_else_only() {
     with_simple_schema_and_request(|s, q| {
@@ -201,6 +201,51 @@ fn tag_access_type_error() {
     );
 }
 
+#[test]
+fn tag_access_with_default() {
+    // constant-keys case
+    let policy = parse_policy(
+        Some(PolicyID::from_string("0")),
+        r#"
+        permit(principal, action == Action::"A1", resource) when {
+            principal.getTagOr("foo", "none") == "foo"
+        };
+        "#,
+    )
+    .unwrap();
+    assert_policy_typechecks(schema_with_tags(), policy);
+
+    // computed-keys case
+    let policy = parse_policy(
+        Some(PolicyID::from_string("0")),
+        r#"
+        permit(principal, action == Action::"A2", resource) when {
+            principal.getTagOr(resource.getTagOr("key", ""), ["none"]).contains("foo")
+        };
+        "#,
+    )
+    .unwrap();
+    assert_policy_typechecks(schema_with_tags(), policy);
+
+    // the default must have the tag type
+    let src = r#"
+        permit(principal, action == Action::"A1", resource) when {
+            principal.getTagOr("foo", 0) == "foo"
+        };
+    "#;
+    let policy = parse_policy(Some(PolicyID::from_string("0")), src).unwrap();
+    let errors = assert_policy_typecheck_fails(schema_with_tags(), policy);
+    let error = assert_exactly_one_diagnostic(errors);
+    expect_err(
+        src,
+        &miette::Report::new(error),
+        &ExpectedErrorMessageBuilder::error(r#"the types Long and String are not compatible"#)
+            .help("for policy `0`, both branches of a conditional must have compatible types. Types must be exactly equal to be compatible")
+            .exactly_one_underline(r#"principal.getTagOr("foo", 0)"#)
+            .build(),
+    );
+}
+
 #[test]
 fn no_tags_allowed() {
     // .hasTag() on an entity with no tags is allowed
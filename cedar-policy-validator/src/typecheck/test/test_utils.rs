@@ -355,7 +355,7 @@ pub(crate) fn assert_typecheck_fails_for_mode(
     let typechecker = Typechecker::new(&schema, mode, expr_id_placeholder());
     let mut type_errors = HashSet::new();
     let actual = typechecker.typecheck_expr(&expr, &mut type_errors);
-    assert_matches!(actual, TypecheckAnswer::TypecheckFail { expr_recovery_type } => {
+    assert_matches!(actual, TypecheckAnswer::TypecheckFail { expr_recovery_type, .. } => {
         match (expected_ty.as_ref(), expr_recovery_type.data()) {
             (None, None) => (),
             (Some(expected_ty), Some(actual_ty)) => {
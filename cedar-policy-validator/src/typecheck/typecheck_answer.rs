@@ -33,8 +33,13 @@ pub(crate) enum TypecheckAnswer<'a> {
     /// overall expression, but not always. For instance, an `&&` expression
     /// will always have type `boolean`, so we populate `expr_recovery_type`
     /// with `Some(boolean)` even when there is a type error in the expression.
+    /// Similarly, `expr_recovery_capability` holds the capability the
+    /// expression would have had without the error, so that an error in one
+    /// operand of an `&&` chain doesn't cause spurious errors for accesses
+    /// guarded by the other operands.
     TypecheckFail {
         expr_recovery_type: Expr<Option<Type>>,
+        expr_recovery_capability: CapabilitySet<'a>,
     },
 
     /// Recursion limit reached
@@ -66,6 +71,7 @@ impl<'a> TypecheckAnswer<'a> {
     pub fn fail(expr_type: Expr<Option<Type>>) -> Self {
         Self::TypecheckFail {
             expr_recovery_type: expr_type,
+            expr_recovery_capability: CapabilitySet::new(),
         }
     }
 
@@ -75,7 +81,9 @@ impl<'a> TypecheckAnswer<'a> {
     pub fn contains_type(&self, ty: &Type) -> bool {
         match self {
             TypecheckAnswer::TypecheckSuccess { expr_type, .. } => Some(expr_type),
-            TypecheckAnswer::TypecheckFail { expr_recovery_type } => Some(expr_recovery_type),
+            TypecheckAnswer::TypecheckFail {
+                expr_recovery_type, ..
+            } => Some(expr_recovery_type),
             TypecheckAnswer::RecursionLimit => None,
         }
         .and_then(|e| e.data().as_ref())
@@ -85,7 +93,9 @@ impl<'a> TypecheckAnswer<'a> {
     pub fn into_typed_expr(self) -> Option<Expr<Option<Type>>> {
         match self {
             TypecheckAnswer::TypecheckSuccess { expr_type, .. } => Some(expr_type),
-            TypecheckAnswer::TypecheckFail { expr_recovery_type } => Some(expr_recovery_type),
+            TypecheckAnswer::TypecheckFail {
+                expr_recovery_type, ..
+            } => Some(expr_recovery_type),
             TypecheckAnswer::RecursionLimit => None,
         }
     }
@@ -99,8 +109,8 @@ impl<'a> TypecheckAnswer<'a> {
         }
     }
 
-    /// Transform the capability of this [`TypecheckAnswer`] without modifying the
-    /// success or type.
+    /// Transform the (recovery) capability of this [`TypecheckAnswer`] without
+    /// modifying the success or type.
     pub fn map_capability<F>(self, f: F) -> Self
     where
         F: FnOnce(CapabilitySet<'a>) -> CapabilitySet<'a>,
@@ -113,7 +123,13 @@ impl<'a> TypecheckAnswer<'a> {
                 expr_type,
                 expr_capability: f(expr_capability),
             },
-            TypecheckAnswer::TypecheckFail { .. } => self,
+            TypecheckAnswer::TypecheckFail {
+                expr_recovery_type,
+                expr_recovery_capability,
+            } => TypecheckAnswer::TypecheckFail {
+                expr_recovery_type,
+                expr_recovery_capability: f(expr_recovery_capability),
+            },
             TypecheckAnswer::RecursionLimit => self,
         }
     }
@@ -121,10 +137,16 @@ impl<'a> TypecheckAnswer<'a> {
     /// Convert this [`TypecheckAnswer`] into an equivalent answer for an expression
     /// that has failed to typecheck. If this is already `TypecheckFail`, then no
     /// change is required, otherwise, a `TypecheckFail` is constructed containing
-    /// `Some` of the `expr_type`.
+    /// the `expr_type` and `expr_capability`.
     pub fn into_fail(self) -> Self {
         match self {
-            TypecheckAnswer::TypecheckSuccess { expr_type, .. } => TypecheckAnswer::fail(expr_type),
+            TypecheckAnswer::TypecheckSuccess {
+                expr_type,
+                expr_capability,
+            } => TypecheckAnswer::TypecheckFail {
+                expr_recovery_type: expr_type,
+                expr_recovery_capability: expr_capability,
+            },
             TypecheckAnswer::TypecheckFail { .. } => self,
             TypecheckAnswer::RecursionLimit => self,
         }
@@ -142,9 +164,10 @@ impl<'a> TypecheckAnswer<'a> {
                 expr_type,
                 expr_capability,
             } => f(expr_type, expr_capability),
-            TypecheckAnswer::TypecheckFail { expr_recovery_type } => {
-                f(expr_recovery_type, CapabilitySet::new()).into_fail()
-            }
+            TypecheckAnswer::TypecheckFail {
+                expr_recovery_type,
+                expr_recovery_capability,
+            } => f(expr_recovery_type, expr_recovery_capability).into_fail(),
             TypecheckAnswer::RecursionLimit => self,
         }
     }
@@ -170,9 +193,10 @@ impl<'a> TypecheckAnswer<'a> {
                     expr_type,
                     expr_capability,
                 } => (expr_type, expr_capability),
-                TypecheckAnswer::TypecheckFail { expr_recovery_type } => {
-                    (expr_recovery_type, CapabilitySet::new())
-                }
+                TypecheckAnswer::TypecheckFail {
+                    expr_recovery_type,
+                    expr_recovery_capability,
+                } => (expr_recovery_type, expr_recovery_capability),
                 TypecheckAnswer::RecursionLimit => {
                     recusion_limit_reached = true;
                     break;
@@ -53,6 +53,7 @@ use super::schema::{ValidatorActionId, ValidatorEntityType, ValidatorSchema};
 
 /// The main type structure.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 pub enum Type {
     /// Bottom type. Sub-type of all types.
     Never,
@@ -99,19 +100,22 @@ impl Type {
         }
     }
 
-    pub(crate) fn primitive_boolean() -> Type {
+    /// The type of booleans
+    pub fn primitive_boolean() -> Type {
         Type::Primitive {
             primitive_type: Primitive::Bool,
         }
     }
 
-    pub(crate) fn primitive_long() -> Type {
+    /// The type of longs
+    pub fn primitive_long() -> Type {
         Type::Primitive {
             primitive_type: Primitive::Long,
         }
     }
 
-    pub(crate) fn primitive_string() -> Type {
+    /// The type of strings
+    pub fn primitive_string() -> Type {
         Type::Primitive {
             primitive_type: Primitive::String,
         }
@@ -135,7 +139,8 @@ impl Type {
         Type::Set { element_type: None }
     }
 
-    pub(crate) fn set(ety: Type) -> Type {
+    /// The type of sets whose elements have type `ety`
+    pub fn set(ety: Type) -> Type {
         Type::Set {
             element_type: Some(Box::new(ety)),
         }
@@ -157,7 +162,9 @@ impl Type {
         })
     }
 
-    pub(crate) fn record_with_attributes(
+    /// The type of records with attributes `attrs`, which may have other
+    /// attributes if `open_attributes` is open
+    pub fn record_with_attributes(
         attrs: impl IntoIterator<Item = (SmolStr, AttributeType)>,
         open_attributes: OpenTag,
     ) -> Type {
@@ -180,7 +187,8 @@ impl Type {
         })
     }
 
-    pub(crate) fn named_entity_reference(name: EntityType) -> Type {
+    /// The type of entities of type `name`
+    pub fn named_entity_reference(name: EntityType) -> Type {
         Type::EntityOrRecord(EntityRecordKind::Entity(EntityLUB::single_entity(name)))
     }
 
@@ -188,7 +196,8 @@ impl Type {
         Type::EntityOrRecord(EntityRecordKind::AnyEntity)
     }
 
-    pub(crate) fn extension(name: Name) -> Type {
+    /// The extension type `name`
+    pub fn extension(name: Name) -> Type {
         Type::ExtensionType { name }
     }
 
@@ -662,7 +671,6 @@ impl Type {
     }
 
     /// Returns `true` when the type is a type of an entity
-    #[cfg(feature = "entity-manifest")]
     pub(crate) fn is_entity_type(&self) -> bool {
         matches!(
             self,
@@ -893,6 +901,7 @@ impl TryFrom<Type> for CoreSchemaType {
 /// to represent the least upper bound of a single entity type, in which case it
 /// is exactly that entity type.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 pub struct EntityLUB {
     /// We store `EntityType` here because these are entity types.
     /// As of this writing, `EntityType` is backed by `Name` (rather than
@@ -1030,6 +1039,7 @@ impl EntityLUB {
 /// Represents the attributes of a record or entity type. Each attribute has an
 /// identifier, a flag indicating weather it is required, and a type.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize, Default)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 pub struct Attributes {
     /// Attributes map
     pub attrs: BTreeMap<SmolStr, AttributeType>,
@@ -1196,6 +1206,7 @@ impl From<&Attributes> for proto::Attributes {
 /// Used to tag record types to indicate if their attributes record is open or
 /// closed.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Clone, Serialize)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 pub enum OpenTag {
     /// The attributes are open. A value of this type may have attributes other
     /// than those listed.
@@ -1239,6 +1250,7 @@ impl From<&OpenTag> for proto::OpenTag {
 /// The subtyping lattice for these types is that
 /// `Entity` <: `AnyEntity`. `Record` does not subtype anything.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 pub enum EntityRecordKind {
     /// A record type
     Record {
@@ -1632,6 +1644,7 @@ impl From<&EntityRecordKind> for proto::EntityRecordKind {
 
 /// Contains the type of a record attribute and if the attribute is required.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 #[serde(rename_all = "camelCase")]
 pub struct AttributeType {
     /// The type of the attribute.
@@ -1734,6 +1747,7 @@ impl From<&AttributeType> for proto::AttributeType {
 
 /// Represent the possible primitive types.
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Serialize)]
+#[cfg_attr(feature = "serde-validation", derive(serde::Deserialize))]
 pub enum Primitive {
     /// Primitive boolean type.
     Bool,
@@ -0,0 +1,263 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Finding the attributes declared in the schema which no policy reads
+
+use std::collections::HashSet;
+
+use cedar_policy_core::ast::{EntityType, EntityUID, ExprKind, PolicySet, Var};
+use smol_str::SmolStr;
+
+use crate::typecheck::Typechecker;
+use crate::types::{EntityRecordKind, RequestEnv, Type};
+use crate::{ValidationMode, Validator};
+
+/// An attribute declared in the schema which no policy reads, as computed by
+/// [`Validator::unused_attributes`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnusedAttribute {
+    /// A top-level attribute of an entity type
+    Entity {
+        /// The entity type
+        entity_type: EntityType,
+        /// The attribute
+        attr: SmolStr,
+    },
+    /// A top-level attribute of the context of an action
+    Context {
+        /// The action
+        action: EntityUID,
+        /// The attribute
+        attr: SmolStr,
+    },
+}
+
+/// The attributes read by a policy set
+#[derive(Debug, Default)]
+struct UsedAttributes {
+    /// Attributes read from an entity of a known type
+    entity: HashSet<(EntityType, SmolStr)>,
+    /// Attributes read from an entity of unknown type, which may be any
+    /// entity type declaring them
+    any_entity: HashSet<SmolStr>,
+    /// Attributes read from the context of an action
+    context: HashSet<(EntityUID, SmolStr)>,
+    /// Actions whose context is used as a whole, e.g., compared with a
+    /// record, which reads every attribute
+    whole_context: HashSet<EntityUID>,
+}
+
+impl Validator {
+    /// Find the top-level attributes of entity types and of action contexts
+    /// declared in the schema which no policy or template in `policies`
+    /// reads, sorted with entity attributes first.
+    ///
+    /// An attribute counts as read if it is accessed with `.`, `[]`, or `has`
+    /// on an expression which may be of the declaring entity type, or on the
+    /// `context` of the action. Where the validator cannot infer the type of
+    /// an expression, an access on it reads the attribute of every entity
+    /// type. Using `context` other than by accessing one of its attributes
+    /// reads all of its attributes.
+    pub fn unused_attributes(&self, policies: &PolicySet) -> Vec<UnusedAttribute> {
+        let mut used = UsedAttributes::default();
+        for t in policies.all_templates() {
+            let typechecker =
+                Typechecker::new(&self.schema, ValidationMode::Permissive, t.id().clone());
+            for (env, e) in typechecker.typed_condition_by_request_env(t) {
+                let action = match env {
+                    RequestEnv::DeclaredAction { action, .. } => Some(action),
+                    RequestEnv::UndeclaredAction => None,
+                };
+                let mut context_uses = 0;
+                let mut context_accesses = 0;
+                for e in e.subexpressions() {
+                    let (ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr }) =
+                        e.expr_kind()
+                    else {
+                        if matches!(e.expr_kind(), ExprKind::Var(Var::Context)) {
+                            context_uses += 1;
+                        }
+                        continue;
+                    };
+                    match (expr.expr_kind(), expr.data()) {
+                        (ExprKind::Var(Var::Context), _) => {
+                            context_accesses += 1;
+                            if let Some(action) = action {
+                                used.context.insert((action.clone(), attr.clone()));
+                            }
+                        }
+                        (_, Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub)))) => {
+                            used.entity
+                                .extend(lub.iter().map(|ty| (ty.clone(), attr.clone())));
+                        }
+                        (_, Some(Type::EntityOrRecord(EntityRecordKind::AnyEntity)) | None) => {
+                            used.any_entity.insert(attr.clone());
+                        }
+                        (_, Some(_)) => {}
+                    }
+                }
+                if context_uses > context_accesses {
+                    used.whole_context.extend(action.cloned());
+                }
+            }
+        }
+
+        let mut unused = self
+            .schema
+            .entity_types()
+            .flat_map(|(ty, ety)| {
+                ety.attributes().map(|(attr, _)| UnusedAttribute::Entity {
+                    entity_type: ty.clone(),
+                    attr: attr.clone(),
+                })
+            })
+            .chain(
+                self.schema
+                    .actions()
+                    .filter_map(|action| self.schema.get_action_id(action))
+                    .filter(|action| !used.whole_context.contains(&action.name))
+                    .flat_map(|action| {
+                        action
+                            .context_type()
+                            .all_attributes(&self.schema)
+                            .into_iter()
+                            .map(|attr| UnusedAttribute::Context {
+                                action: action.name.clone(),
+                                attr,
+                            })
+                    }),
+            )
+            .filter(|attr| match attr {
+                UnusedAttribute::Entity { entity_type, attr } => {
+                    !used.any_entity.contains(attr)
+                        && !used.entity.contains(&(entity_type.clone(), attr.clone()))
+                }
+                UnusedAttribute::Context { action, attr } => {
+                    !used.context.contains(&(action.clone(), attr.clone()))
+                }
+            })
+            .collect::<Vec<_>>();
+        unused.sort();
+        unused
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ValidatorSchema;
+    use cedar_policy_core::parser::parse_policyset;
+
+    fn validator() -> Validator {
+        Validator::new(
+            ValidatorSchema::from_cedarschema_str(
+                r#"
+                entity Team = { name: String, size: Long };
+                entity User in Team = { name: String, email: String, team: Team };
+                entity Doc = { owner: User, secret: Bool };
+                action view appliesTo {
+                    principal: [User, Team],
+                    resource: Doc,
+                    context: { ip: String, mfa: Bool }
+                };
+                action edit appliesTo {
+                    principal: User,
+                    resource: Doc,
+                    context: { reason: String }
+                };
+                "#,
+                cedar_policy_core::extensions::Extensions::all_available(),
+            )
+            .unwrap()
+            .0,
+        )
+    }
+
+    #[track_caller]
+    fn unused(src: &str) -> Vec<String> {
+        validator()
+            .unused_attributes(&parse_policyset(src).unwrap())
+            .into_iter()
+            .map(|attr| match attr {
+                UnusedAttribute::Entity { entity_type, attr } => format!("{entity_type}.{attr}"),
+                UnusedAttribute::Context { action, attr } => format!("{action}.context.{attr}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_policies() {
+        assert_eq!(
+            unused(""),
+            vec![
+                "Doc.owner",
+                "Doc.secret",
+                "Team.name",
+                "Team.size",
+                "User.email",
+                "User.name",
+                "User.team",
+                r#"Action::"edit".context.reason"#,
+                r#"Action::"view".context.ip"#,
+                r#"Action::"view".context.mfa"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn reads() {
+        let src = r#"
+            permit(principal is User, action == Action::"view", resource)
+            when { resource.owner.team has size && context.mfa };
+            forbid(principal, action == Action::"edit", resource)
+            when { resource["secret"] };
+        "#;
+        assert_eq!(
+            unused(src),
+            vec![
+                "Team.name",
+                "User.email",
+                "User.name",
+                r#"Action::"edit".context.reason"#,
+                r#"Action::"view".context.ip"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn receiver_of_several_types() {
+        // `principal` may be a `User` or a `Team` for `view`
+        let src = r#"permit(principal, action, resource) when { principal.name == "" };"#;
+        let unused = unused(src);
+        assert!(!unused.contains(&"User.name".to_string()));
+        assert!(!unused.contains(&"Team.name".to_string()));
+    }
+
+    #[test]
+    fn whole_context() {
+        let src = r#"
+            permit(principal, action == Action::"view", resource)
+            when { context == { ip: "", mfa: true } };
+        "#;
+        let unused = unused(src);
+        assert!(!unused
+            .iter()
+            .any(|attr| attr.starts_with(r#"Action::"view""#)));
+        assert!(unused.contains(&r#"Action::"edit".context.reason"#.to_string()));
+    }
+}
@@ -36,20 +36,130 @@ pub use id::*;
 mod err;
 pub use err::*;
 
+mod bundle;
+pub use bundle::*;
+
+mod handle;
+pub use handle::*;
+
+mod tenants;
+pub use tenants::*;
+
+#[cfg(feature = "directory")]
+mod directory;
+
+mod debugger;
+pub use debugger::*;
+
+mod docs;
+pub use docs::*;
+mod inventory;
+pub use inventory::*;
+#[cfg(feature = "permissive-validate")]
+mod mode_comparison;
+#[cfg(feature = "permissive-validate")]
+pub use mode_comparison::*;
+#[cfg(not(target_arch = "wasm32"))]
+mod validation_profile;
+#[cfg(not(target_arch = "wasm32"))]
+pub use validation_profile::*;
+
+mod rename;
+pub use rename::*;
+
+mod containment;
+pub use containment::*;
+
+mod cache_key;
+pub use cache_key::*;
+
+#[cfg(feature = "partial-eval")]
+mod fetch;
+#[cfg(feature = "partial-eval")]
+pub use fetch::*;
+
+mod denial;
+pub use denial::*;
+
+mod context_layers;
+pub use context_layers::*;
+mod entity_warnings;
+mod request_builder;
+pub use request_builder::*;
+
+#[cfg(feature = "rego")]
+mod rego;
+#[cfg(feature = "rego")]
+pub use rego::{RegoImport, RegoImportIssue, RegoImporter};
+#[cfg(feature = "xacml")]
+mod xacml;
+#[cfg(feature = "xacml")]
+pub use xacml::{
+    XacmlConverter, XacmlExport, XacmlImport, XacmlIssue, XacmlIssueKind, XacmlParseError,
+};
+#[cfg(feature = "iam")]
+mod iam;
+#[cfg(feature = "iam")]
+pub use iam::{IamImport, IamImportIssue, IamImporter, IamParseError};
+#[cfg(feature = "zanzibar")]
+mod zanzibar;
+#[cfg(feature = "zanzibar")]
+pub use zanzibar::{
+    ZanzibarImport, ZanzibarImportIssue, ZanzibarImporter, ZanzibarInput, ZanzibarParseError,
+};
+#[cfg(feature = "group-sync")]
+mod group_sync;
+#[cfg(feature = "group-sync")]
+pub use group_sync::{GroupSync, GroupSyncDelta, ScimParseError};
+
+#[cfg(feature = "arrow")]
+mod batch;
+#[cfg(feature = "arrow")]
+pub use batch::*;
+
+#[cfg(feature = "yaml")]
+mod yaml;
+#[cfg(feature = "yaml")]
+pub use yaml::*;
+
+#[cfg(feature = "tower")]
+mod middleware;
+#[cfg(feature = "tower")]
+pub use middleware::*;
+
+#[cfg(feature = "jwt")]
+mod jwt;
+#[cfg(feature = "jwt")]
+pub use jwt::*;
+
 pub use ast::Effect;
+pub use ast::{PolicyMetrics, PolicySetMetrics, Provenance};
 pub use authorizer::Decision;
+pub use authorizer::PolicyProfile;
+pub use authorizer::{DecisionLogger, DecisionRecord};
 #[cfg(feature = "partial-eval")]
 use cedar_policy_core::ast::BorrowedRestrictedExpr;
 use cedar_policy_core::ast::{self, RestrictedExpr};
 use cedar_policy_core::authorizer;
+pub use cedar_policy_core::entities::csv::{CsvMapping, CsvParentColumn};
 use cedar_policy_core::entities::{ContextSchema, Dereference};
 use cedar_policy_core::est::{self, TemplateLink};
 use cedar_policy_core::evaluator::Evaluator;
-#[cfg(feature = "partial-eval")]
 use cedar_policy_core::evaluator::RestrictedEvaluator;
+pub use cedar_policy_core::evaluator::{
+    Cancellation, CancellationReason, CancellationToken, Clock, FixedClock, OverflowBehavior,
+    SystemClock,
+};
 use cedar_policy_core::extensions::Extensions;
+pub use cedar_policy_core::heap_size::{EntitiesMemoryUsage, PolicySetMemoryUsage};
+pub use cedar_policy_core::limits::ResourceLimits;
 use cedar_policy_core::parser;
 use cedar_policy_core::FromNormalizedStr;
+pub use cedar_policy_validator::CompletionKind;
+pub use cedar_policy_validator::ReferenceKind;
+pub use cedar_policy_validator::SchemaMemoryUsage;
+pub use cedar_policy_validator::SensitiveAttributeRules;
+pub use cedar_policy_validator::{ExpirationDate, ExpirationRules, ParseExpirationDateError};
 use itertools::{Either, Itertools};
 use miette::Diagnostic;
 use ref_cast::RefCast;
@@ -134,6 +244,13 @@ pub trait Protobuf: Sized {
     fn decode(buf: impl prost::bytes::Buf) -> Result<Self, prost::DecodeError>;
 }
 
+/// The owned Uid, attributes and parents of an [`Entity`]
+type EntityParts = (
+    EntityUid,
+    HashMap<String, RestrictedExpression>,
+    HashSet<EntityUid>,
+);
+
 /// Entity datatype
 #[repr(transparent)]
 #[derive(Debug, Clone, PartialEq, Eq, RefCast, Hash)]
@@ -205,6 +322,48 @@ impl Entity {
         Self(ast::Entity::with_uid(uid.into()))
     }
 
+    /// Add an attribute whose value is computed by `compute` only when it is
+    /// first needed (for instance, when a policy reads it during
+    /// authorization), rather than up front.
+    ///
+    /// The computed value is cached, so `compute` runs at most once, even
+    /// across clones of this `Entity` or the `Entities` containing it. This is
+    /// useful for attributes that are expensive to obtain and only referenced
+    /// by some policies. If an attribute named `attr` already exists, it is
+    /// replaced.
+    ///
+    /// If evaluating the returned expression fails, evaluating any policy
+    /// that reads the attribute produces that error.
+    ///
+    /// Operations that need every attribute, such as serializing the entity
+    /// or validating it against a schema, compute all lazy attributes, and
+    /// fail with the error of any lazy attribute whose computation fails.
+    /// [`Entity::attr`] and [`Entity::into_inner`] treat such an attribute as
+    /// absent; use [`Entity::try_into_inner`] to get its error instead.
+    /// ```
+    /// # use cedar_policy::{Entity, EntityUid, EvalResult, RestrictedExpression};
+    /// # use std::str::FromStr;
+    /// let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let alice = Entity::with_uid(euid)
+    ///     .with_lazy_attr("groups", || RestrictedExpression::from_str(r#"["admins"]"#).unwrap());
+    /// assert!(alice.attr("groups").is_some());
+    /// ```
+    #[must_use]
+    pub fn with_lazy_attr(
+        mut self,
+        attr: &str,
+        compute: impl Fn() -> RestrictedExpression + Send + Sync + 'static,
+    ) -> Self {
+        self.0.add_lazy_attr(
+            attr.into(),
+            ast::LazyAttr::new(move || {
+                RestrictedEvaluator::new(Extensions::all_available())
+                    .partial_interpret(compute().0.as_borrowed())
+            }),
+        );
+        self
+    }
+
     /// Get the Uid of this entity
     /// ```
     /// # use cedar_policy::{Entity, EntityId, EntityTypeName, EntityUid};
@@ -248,6 +407,10 @@ impl Entity {
     }
 
     /// Consume the entity and return the entity's owned Uid, attributes and parents.
+    ///
+    /// Lazy attributes (see [`Entity::with_lazy_attr`]) whose computation
+    /// fails are left out; use [`Entity::try_into_inner`] to get their errors
+    /// instead.
     pub fn into_inner(
         self,
     ) -> (
@@ -255,8 +418,28 @@ impl Entity {
         HashMap<String, RestrictedExpression>,
         HashSet<EntityUid>,
     ) {
-        let (uid, attrs, ancestors, _) = self.0.into_inner();
+        let attrs = self
+            .0
+            .attrs()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>();
+        Self::from_inner_parts(self.0.uid().clone(), attrs, self.0.ancestors().cloned())
+    }
 
+    /// Consume the entity and return the entity's owned Uid, attributes and
+    /// parents, failing if the computation of a lazy attribute (see
+    /// [`Entity::with_lazy_attr`]) fails
+    pub fn try_into_inner(self) -> Result<EntityParts, EntityAttrEvaluationError> {
+        let (uid, attrs, ancestors, _) = self.0.into_inner()?;
+        Ok(Self::from_inner_parts(uid, attrs, ancestors))
+    }
+
+    /// The parts returned by [`Entity::into_inner`]
+    fn from_inner_parts(
+        uid: ast::EntityUID,
+        attrs: impl IntoIterator<Item = (SmolStr, ast::PartialValue)>,
+        ancestors: impl IntoIterator<Item = ast::EntityUID>,
+    ) -> EntityParts {
         let attrs = attrs
             .into_iter()
             .map(|(k, v)| {
@@ -355,6 +538,17 @@ impl Entity {
     pub fn to_json_string(&self) -> Result<String, EntitiesError> {
         self.0.to_json_string()
     }
+
+    /// Write this entity in the Cedar entity text format. Its attributes,
+    /// parents, and tags are written in sorted order.
+    ///
+    /// To read entities in this format, use [`Entities::from_cedar_str`].
+    ///
+    /// This fails if computing the value of a lazy attribute (see
+    /// [`Entity::with_lazy_attr`]) fails.
+    pub fn to_cedar(&self) -> Result<String, EntityAttrEvaluationError> {
+        Ok(self.0.to_cedar_text()?)
+    }
 }
 
 impl std::fmt::Display for Entity {
@@ -391,6 +585,12 @@ impl Entities {
         Self(cedar_policy_core::entities::Entities::new())
     }
 
+    /// Estimate the heap memory used by these entities, broken down into
+    /// UIDs, attribute values, and ancestor sets
+    pub fn memory_usage(&self) -> EntitiesMemoryUsage {
+        EntitiesMemoryUsage::new(&self.0)
+    }
+
     /// Get the `Entity` with the given Uid, if any
     pub fn get(&self, uid: &EntityUid) -> Option<&Entity> {
         match self.0.entity(uid.as_ref()) {
@@ -414,6 +614,52 @@ impl Entities {
         self.0.iter().map(Entity::ref_cast)
     }
 
+    /// Iterate over the `Entity`'s of the given entity type in the `Entities`.
+    ///
+    /// `Entities` keeps an index on entity types, so this does not need to
+    /// scan every entity.
+    /// ```
+    /// # use cedar_policy::{Entities, EntityTypeName};
+    /// let entities = Entities::from_json_str(r#"[
+    ///     {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": []},
+    ///     {"uid": {"type": "User", "id": "bob"}, "attrs": {}, "parents": []},
+    ///     {"uid": {"type": "Photo", "id": "vacation"}, "attrs": {}, "parents": []}
+    /// ]"#, None).unwrap();
+    /// let user: EntityTypeName = "User".parse().unwrap();
+    /// assert_eq!(entities.iter_of_type(&user).count(), 2);
+    /// ```
+    pub fn iter_of_type<'a>(&'a self, ty: &EntityTypeName) -> impl Iterator<Item = &'a Entity> {
+        self.0.iter_of_type(&ty.0).map(Entity::ref_cast)
+    }
+
+    /// Iterate over the entity types which have at least one `Entity` in the
+    /// `Entities`
+    pub fn entity_types(&self) -> impl Iterator<Item = &EntityTypeName> {
+        self.0.entity_types().map(EntityTypeName::ref_cast)
+    }
+
+    /// Iterate over the `Entity`'s in the `Entities` which satisfy `predicate`
+    /// ```
+    /// # use cedar_policy::{Entities, EvalResult};
+    /// let entities = Entities::from_json_str(r#"[
+    ///     {"uid": {"type": "User", "id": "alice"}, "attrs": {"age": 19}, "parents": []},
+    ///     {"uid": {"type": "User", "id": "bob"}, "attrs": {"age": 17}, "parents": []}
+    /// ]"#, None).unwrap();
+    /// let adults = entities
+    ///     .find(|e| matches!(e.attr("age"), Some(Ok(EvalResult::Long(age))) if age >= 18))
+    ///     .map(|e| e.uid().id().escaped().to_string())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(adults, vec!["alice"]);
+    /// ```
+    pub fn find<'a>(
+        &'a self,
+        predicate: impl Fn(&Entity) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Entity> {
+        self.0
+            .find(move |e| predicate(Entity::ref_cast(e)))
+            .map(Entity::ref_cast)
+    }
+
     /// Create an `Entities` object with the given entities.
     ///
     /// `schema` represents a source of `Action` entities, which will be added
@@ -479,6 +725,54 @@ impl Entities {
         ))
     }
 
+    /// Insert all of the [`Entity`]s in the collection into this [`Entities`]
+    /// structure in place, replacing any existing entities with the same
+    /// UIDs.
+    ///
+    /// Unlike [`Entities::add_entities`], this only computes the transitive
+    /// closure of the inserted entities, so it is suitable for applying small
+    /// changes to a large store. Descendants of the inserted entities gain
+    /// any new ancestors of those entities, but keep the ancestors those
+    /// entities no longer have; to remove those, insert the descendants too.
+    ///
+    /// If a `schema` is provided, this method will ensure that the inserted
+    /// entities fully conform to the schema.
+    /// (This method will not add action entities from the `schema`.)
+    /// ## Errors
+    /// - [`EntitiesError::Duplicate`] if there are any duplicate entities in `entities`
+    /// - [`EntitiesError::TransitiveClosureError`] if the inserted entities
+    ///   would create a cycle in the entity hierarchy
+    /// - [`EntitiesError::InvalidEntity`] if `schema` is not none and any entities do not conform
+    ///   to the schema
+    pub fn upsert_entities(
+        &mut self,
+        entities: impl IntoIterator<Item = Entity>,
+        schema: Option<&Schema>,
+    ) -> Result<(), EntitiesError> {
+        self.0.upsert_entities(
+            entities.into_iter().map(|e| e.0),
+            schema
+                .map(|s| cedar_policy_validator::CoreSchema::new(&s.0))
+                .as_ref(),
+            cedar_policy_core::entities::TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+    }
+
+    /// Remove the entities with the given UIDs from this [`Entities`]
+    /// structure in place, returning the removed entities. UIDs which are not
+    /// in the structure are ignored.
+    pub fn remove_entities<'a>(
+        &mut self,
+        uids: impl IntoIterator<Item = &'a EntityUid>,
+    ) -> Vec<Entity> {
+        self.0
+            .remove_entities(uids.into_iter().map(|uid| &uid.0))
+            .into_iter()
+            .map(|e| Entity(Arc::unwrap_or_clone(e)))
+            .collect()
+    }
+
     /// Parse an entities JSON file (in [&str] form) and add them into this
     /// [`Entities`] structure, re-computing the transitive closure
     ///
@@ -604,6 +898,9 @@ impl Entities {
     ///
     /// If a `schema` is present, this will also inform the parsing: for
     /// instance, it will allow `__entity` and `__extn` escapes to be implicit.
+    /// Entity references may also be given as strings, either the id alone or
+    /// the whole reference in Cedar syntax, such as `"alice"` or
+    /// `"User::\"alice\""` for an attribute of type `User`.
     ///
     /// Finally, if a `schema` is present, this function will ensure
     /// that the produced entities fully conform to the `schema` -- for
@@ -665,6 +962,9 @@ impl Entities {
     ///
     /// If a `schema` is present, this will also inform the parsing: for
     /// instance, it will allow `__entity` and `__extn` escapes to be implicit.
+    /// Entity references may also be given as strings, either the id alone or
+    /// the whole reference in Cedar syntax, such as `"alice"` or
+    /// `"User::\"alice\""` for an attribute of type `User`.
     ///
     /// Finally, if a `schema` is present, this function will ensure
     /// that the produced entities fully conform to the `schema` -- for
@@ -723,6 +1023,9 @@ impl Entities {
     ///
     /// If a `schema` is present, this will also inform the parsing: for
     /// instance, it will allow `__entity` and `__extn` escapes to be implicit.
+    /// Entity references may also be given as strings, either the id alone or
+    /// the whole reference in Cedar syntax, such as `"alice"` or
+    /// `"User::\"alice\""` for an attribute of type `User`.
     ///
     /// Finally, if a `schema` is present, this function will ensure
     /// that the produced entities fully conform to the `schema` -- for
@@ -748,6 +1051,186 @@ impl Entities {
         eparser.from_json_file(json).map(Entities)
     }
 
+    /// Parse entities in the Cedar entity text format, in which each entity
+    /// is declared with its attributes, parents, and tags, all of which are
+    /// optional. Attributes and tags are record literals whose values may be
+    /// any restricted expression.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, EntityUid};
+    /// # use std::str::FromStr;
+    /// let entities = Entities::from_cedar_str(r#"
+    ///     entity User::"alice" { dept: "eng", level: 3 } in [Group::"staff"];
+    ///     entity Group::"staff";
+    ///     entity Document::"plan" tags { classification: "secret" };
+    /// "#, None).unwrap();
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let staff = EntityUid::from_str(r#"Group::"staff""#).unwrap();
+    /// assert!(entities.is_ancestor_of(&staff, &alice));
+    /// ```
+    ///
+    /// `schema` is used in the same way as in [`Entities::from_entities`]:
+    /// its action entities are added, and every entity must conform to it.
+    pub fn from_cedar_str(src: &str, schema: Option<&Schema>) -> Result<Self, EntitiesTextError> {
+        let entities = parser::parse_entities(src)
+            .map_err(ParseErrors::from)?
+            .into_iter()
+            .map(|decl| decl.into_entity(Extensions::all_available()).map(Entity))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(EntityAttrEvaluationError::from)?;
+        Ok(Self::from_entities(entities, schema)?)
+    }
+
+    /// Write these entities in the Cedar entity text format, which can be read
+    /// with [`Entities::from_cedar_str`].
+    ///
+    /// Entities are written in order of their uids, and their attributes,
+    /// parents, and tags are sorted, so the output is deterministic. Every
+    /// ancestor of an entity is written as a parent, not just its direct
+    /// parents.
+    ///
+    /// This fails if computing the value of a lazy attribute (see
+    /// [`Entity::with_lazy_attr`]) fails.
+    pub fn to_cedar(&self) -> Result<String, EntityAttrEvaluationError> {
+        Ok(cedar_policy_core::entities::text::entities_to_cedar_text(
+            self.0.iter(),
+        )?)
+    }
+
+    /// Parse entities from CSV (or TSV) data, such as a spreadsheet export.
+    /// Each row after the header row declares one entity, and `mapping` says
+    /// which columns hold its type, id, attributes, and parents.
+    ///
+    /// ```
+    /// # use cedar_policy::{CsvMapping, CsvParentColumn, Entities, EntityUid, Schema};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str("
+    ///     entity Group;
+    ///     entity User in [Group] = { age: Long, admin: Bool };
+    /// ").unwrap();
+    /// let mut mapping = CsvMapping::new("name");
+    /// mapping.entity_type = Some("User".into());
+    /// mapping.attributes.insert("age".into(), "age".into());
+    /// mapping.attributes.insert("is admin".into(), "admin".into());
+    /// mapping.parents.push(CsvParentColumn {
+    ///     column: "groups".into(),
+    ///     entity_type: Some("Group".into()),
+    /// });
+    /// let entities = Entities::from_csv_str(
+    ///     "name,age,is admin,groups\n\
+    ///      alice,42,true,eng;staff\n\
+    ///      bob,37,false,staff\n",
+    ///     &mapping,
+    ///     Some(&schema),
+    /// ).unwrap();
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let staff = EntityUid::from_str(r#"Group::"staff""#).unwrap();
+    /// assert!(entities.is_ancestor_of(&staff, &alice));
+    /// ```
+    ///
+    /// Cells are converted to the attribute types `schema` declares, and the
+    /// entities must conform to it; see [`CsvMapping`] for how each type is
+    /// written. As in [`Entities::from_entities`], the action entities of
+    /// `schema` are added. Without a schema, every attribute is a string.
+    ///
+    /// Every error in the data is reported, each with the row and, where it
+    /// applies, the column it is in.
+    pub fn from_csv_str(
+        src: &str,
+        mapping: &CsvMapping,
+        schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesCsvError> {
+        let core_schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
+        let entities = cedar_policy_core::entities::csv::EntityCsvParser::new(
+            core_schema.as_ref(),
+            Extensions::all_available(),
+        )
+        .from_csv_str(src, mapping)?;
+        Ok(Self::from_entities(
+            entities.into_iter().map(Entity),
+            schema,
+        )?)
+    }
+
+    /// Parse entities from Arrow record batches, which must all have the
+    /// same columns, with `mapping` saying which columns hold the type, id,
+    /// attributes, and parents of the entity declared by each row.
+    ///
+    /// Boolean, integer, string, list, and struct columns are read as the
+    /// corresponding values, and strings are converted to the attribute types
+    /// declared in `schema` like the cells of [`Entities::from_csv_str`].
+    /// Columns of other types are read as their text in Arrow's display
+    /// format. Otherwise, `schema` is used as in [`Entities::from_csv_str`].
+    ///
+    /// The batches must be built with the same version of the `arrow-array`
+    /// crate as this crate uses.
+    #[cfg(feature = "arrow")]
+    pub fn from_record_batches<'a>(
+        batches: impl IntoIterator<Item = &'a arrow_array::RecordBatch>,
+        mapping: &CsvMapping,
+        schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesCsvError> {
+        let core_schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
+        let entities = cedar_policy_core::entities::arrow::EntityArrowParser::new(
+            core_schema.as_ref(),
+            Extensions::all_available(),
+        )
+        .from_record_batches(batches, mapping)?;
+        Ok(Self::from_entities(
+            entities.into_iter().map(Entity),
+            schema,
+        )?)
+    }
+
+    /// Parse entities from a Parquet file, like
+    /// [`Entities::from_record_batches`]. `reader` may be, e.g., a
+    /// [`std::fs::File`].
+    #[cfg(feature = "parquet")]
+    pub fn from_parquet(
+        reader: impl parquet::file::reader::ChunkReader + 'static,
+        mapping: &CsvMapping,
+        schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesCsvError> {
+        let core_schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
+        let entities = cedar_policy_core::entities::arrow::EntityArrowParser::new(
+            core_schema.as_ref(),
+            Extensions::all_available(),
+        )
+        .from_parquet(reader, mapping)?;
+        Ok(Self::from_entities(
+            entities.into_iter().map(Entity),
+            schema,
+        )?)
+    }
+
+    /// Check every entity against `schema`, returning all of the ways in
+    /// which they do not conform to it, up to `max_errors` errors. Unlike
+    /// constructing `Entities` with a schema, this does not stop at the first
+    /// error, so a bulk import of entities parsed without a schema can be
+    /// fixed in one pass.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, Schema};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str("entity User = { age: Long, name: String };").unwrap();
+    /// let entities = Entities::from_json_str(r#"[
+    ///     { "uid": { "type": "User", "id": "alice" }, "attrs": { "age": "old", "name": 1 }, "parents": [] },
+    ///     { "uid": { "type": "User", "id": "bob" }, "attrs": { "age": 3 }, "parents": [] }
+    /// ]"#, None).unwrap();
+    /// assert_eq!(entities.conformance_errors(&schema, 100).len(), 3);
+    /// ```
+    pub fn conformance_errors(
+        &self,
+        schema: &Schema,
+        max_errors: usize,
+    ) -> Vec<conformance_errors::EntitySchemaConformanceError> {
+        self.0.conformance_errors(
+            &cedar_policy_validator::CoreSchema::new(&schema.0),
+            Extensions::all_available(),
+            max_errors,
+        )
+    }
+
     /// Is entity `a` an ancestor of entity `b`?
     /// Same semantics as `b in a` in the Cedar language
     pub fn is_ancestor_of(&self, a: &EntityUid, b: &EntityUid) -> bool {
@@ -770,6 +1253,22 @@ impl Entities {
         Some(entity.ancestors().map(EntityUid::ref_cast))
     }
 
+    /// Get an iterator over the descendants of the given Euid, i.e., the
+    /// entities `e` for which `e in euid` holds (other than `euid` itself).
+    /// The iterator is empty if the given `Euid` does not exist or has no
+    /// descendants.
+    pub fn descendants<'a>(&'a self, euid: &'a EntityUid) -> impl Iterator<Item = &'a EntityUid> {
+        self.0
+            .descendants(euid.as_ref())
+            .map(|e| EntityUid::ref_cast(e.uid()))
+    }
+
+    /// Get an iterator over the roots of the entity hierarchy, i.e., the
+    /// entities which have no ancestors
+    pub fn roots(&self) -> impl Iterator<Item = &EntityUid> {
+        self.0.roots().map(|e| EntityUid::ref_cast(e.uid()))
+    }
+
     /// Dump an `Entities` object into an entities JSON file.
     ///
     /// The resulting JSON will be suitable for parsing in via
@@ -837,6 +1336,49 @@ impl Protobuf for Entities {
 #[derive(Debug, Clone, RefCast)]
 pub struct Authorizer(authorizer::Authorizer);
 
+/// Supplies context attributes that are resolved during authorization.
+///
+/// This lets context such as the current time, the geographic origin of the
+/// request, or a risk score be computed on demand rather than assembled by
+/// every caller beforehand.
+///
+/// Providers are registered with [`Authorizer::with_context_provider`]. For
+/// each request, the authorizer asks its providers for the attributes that
+/// some policy in the [`PolicySet`] reads but that the request's [`Context`]
+/// does not already contain. Attributes supplied by the caller always take
+/// precedence over those from providers.
+///
+/// If a provided value fails to evaluate, or does not conform to the schema
+/// given to [`Authorizer::with_context_schema`], the attribute is left out of
+/// the context. Each policy which may read it then fails with an
+/// [`EvaluationError::ContextProvider`] error, which is reported in the
+/// response, rather than being evaluated without it.
+pub trait ContextProvider: Send + Sync {
+    /// Names of the context attributes this provider can resolve
+    fn attributes(&self) -> Vec<String>;
+
+    /// Resolve the context attribute `name` for `request`, or return `None` if
+    /// the attribute has no value for this request.
+    ///
+    /// Only called with names returned by [`ContextProvider::attributes`].
+    fn resolve(&self, name: &str, request: &Request) -> Option<RestrictedExpression>;
+}
+
+/// Adapts a [`ContextProvider`] to the core authorizer's interface
+struct ProviderAdapter<P>(P);
+
+impl<P: ContextProvider> authorizer::ContextProvider for ProviderAdapter<P> {
+    fn attributes(&self) -> Vec<SmolStr> {
+        self.0.attributes().into_iter().map(SmolStr::from).collect()
+    }
+
+    fn resolve(&self, name: &str, request: &ast::Request) -> Option<RestrictedExpr> {
+        self.0
+            .resolve(name, Request::ref_cast(request))
+            .map(|value| value.0)
+    }
+}
+
 impl Default for Authorizer {
     fn default() -> Self {
         Self::new()
@@ -899,6 +1441,157 @@ impl Authorizer {
         Self(authorizer::Authorizer::new())
     }
 
+    /// Register a [`DecisionLogger`] to receive a [`DecisionRecord`] for
+    /// every call to [`Authorizer::is_authorized`].
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Decision, DecisionRecord, Entities, EntityUid,
+    /// # PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// # use std::sync::{Arc, Mutex};
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&log);
+    /// let authorizer = Authorizer::new()
+    ///     .with_policy_set_version("v42")
+    ///     .with_decision_logger(move |record: &DecisionRecord| {
+    ///         sink.lock().unwrap().push(serde_json::to_string(record).unwrap());
+    ///     });
+    /// # let policy = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+    /// # let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// # let request = Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap();
+    /// authorizer.is_authorized(&request, &policy, &Entities::empty());
+    /// assert_eq!(log.lock().unwrap().len(), 1);
+    /// ```
+    #[must_use]
+    pub fn with_decision_logger(self, logger: impl DecisionLogger + 'static) -> Self {
+        Self(self.0.with_decision_logger(Arc::new(logger)))
+    }
+
+    /// Redact sensitive data from the evaluation errors reported in responses
+    /// from this `Authorizer` (see [`EvaluationError::redacted`]), so that the
+    /// responses can be logged without leaking it.
+    #[must_use]
+    pub fn with_redacted_errors(self) -> Self {
+        Self(self.0.with_redacted_errors())
+    }
+
+    /// Deny requests that exceed the given [`ResourceLimits`] (currently, the
+    /// maximum size of the entity store), reporting
+    /// [`EvaluationError::LimitExceeded`] for each policy.
+    #[must_use]
+    pub fn with_limits(self, limits: ResourceLimits) -> Self {
+        Self(self.0.with_limits(limits))
+    }
+
+    /// Handle integer overflow according to `overflow`. By default
+    /// ([`OverflowBehavior::Error`]), `+`, `-`, `*`, `/` (for `i64::MIN / -1`),
+    /// or unary `-` overflowing is an evaluation error, so the policy is
+    /// skipped.
+    ///
+    /// With [`OverflowBehavior::Saturate`], the result is clamped to the
+    /// minimum or maximum `Long` instead, e.g., for quota policies multiplying
+    /// large context values. This deviates from the standard Cedar semantics,
+    /// so every policy whose evaluation saturated is listed in
+    /// [`Diagnostics::saturated_policies`].
+    #[must_use]
+    pub fn with_overflow_behavior(self, overflow: OverflowBehavior) -> Self {
+        Self(self.0.with_overflow_behavior(overflow))
+    }
+
+    /// Memoize the values of expensive sub-expressions for the duration of
+    /// each request, so that a sub-expression repeated within or across
+    /// policies (e.g., `resource.owner in principal.orgs` in several
+    /// clauses) is evaluated once per request.
+    ///
+    /// Memoized sub-expressions are those which read the entity hierarchy or
+    /// tags (`in`, `hasTag`, `getTag`), test set membership (`contains`,
+    /// `containsAll`, `containsAny`), or call extension functions. Since
+    /// Cedar expressions have no side effects, responses are unaffected.
+    /// Looking up each such sub-expression has a small cost, so this pays
+    /// off for policy sets where they are repeated.
+    #[must_use]
+    pub fn with_subexpression_cache(self) -> Self {
+        Self(self.0.with_subexpression_cache())
+    }
+
+    /// Evaluate policies in order of their ids, so that responses and
+    /// [`DecisionRecord`]s are reproducible across runs and platforms, e.g.,
+    /// for audits.
+    ///
+    /// By default, policies are evaluated in an unspecified order, which
+    /// determines the order of the errors in [`Diagnostics::errors`] and
+    /// which policy is reported if evaluation is cancelled. With this
+    /// option, errors are reported in order of policy id. The cost is
+    /// sorting the policies for every request.
+    #[must_use]
+    pub fn with_deterministic_order(self) -> Self {
+        Self(self.0.with_deterministic_order())
+    }
+
+    /// Record the time taken and the number of expression nodes visited by
+    /// every policy this `Authorizer` evaluates in `profiler`. Profiling has
+    /// a small cost per policy, so it is best enabled only while
+    /// investigating latency.
+    #[must_use]
+    pub fn with_profiler(self, profiler: &EvaluationProfiler) -> Self {
+        Self(self.0.with_profiler(Arc::clone(&profiler.0)))
+    }
+
+    /// Set the version of the policies being authorized against, as recorded
+    /// in [`DecisionRecord`]s. The format of the version is up to the caller.
+    #[must_use]
+    pub fn with_policy_set_version(self, version: impl Into<String>) -> Self {
+        Self(self.0.with_policy_set_version(version.into()))
+    }
+
+    /// Register a [`ContextProvider`] to consult for context attributes that
+    /// requests don't supply themselves.
+    ///
+    /// If several providers resolve the same attribute, the one registered
+    /// first takes precedence.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, ContextProvider, Decision, Entities, EntityUid,
+    /// # PolicySet, Request, RestrictedExpression};
+    /// # use std::str::FromStr;
+    /// struct RiskScore;
+    ///
+    /// impl ContextProvider for RiskScore {
+    ///     fn attributes(&self) -> Vec<String> {
+    ///         vec!["risk".to_string()]
+    ///     }
+    ///
+    ///     fn resolve(&self, _name: &str, _request: &Request) -> Option<RestrictedExpression> {
+    ///         Some(RestrictedExpression::new_long(3))
+    ///     }
+    /// }
+    ///
+    /// let authorizer = Authorizer::new().with_context_provider(RiskScore);
+    /// let policy = PolicySet::from_str("permit(principal, action, resource) when { context.risk < 5 };").unwrap();
+    /// let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let request = Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap();
+    /// let response = authorizer.is_authorized(&request, &policy, &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Allow);
+    /// ```
+    #[must_use]
+    pub fn with_context_provider(self, provider: impl ContextProvider + 'static) -> Self {
+        Self(
+            self.0
+                .with_context_provider(Arc::new(ProviderAdapter(provider))),
+        )
+    }
+
+    /// Check the values supplied by [`ContextProvider`]s against the context
+    /// types `schema` declares for each action.
+    ///
+    /// Requests validated against a schema when they are constructed don't
+    /// yet contain the attributes supplied by providers, so those attributes
+    /// are checked here instead. A provided value which doesn't conform is
+    /// reported as an [`EvaluationError::ContextProvider`] error for each
+    /// policy which may read it.
+    #[must_use]
+    pub fn with_context_schema(self, schema: &Schema) -> Self {
+        Self(self.0.with_context_schema(Arc::new(schema.0.clone())))
+    }
+
     /// Returns an authorization response for `r` with respect to the given
     /// `PolicySet` and `Entities`.
     ///
@@ -953,7 +1646,31 @@ impl Authorizer {
     /// assert_eq!(response.decision(), Decision::Allow);
     /// ```
     pub fn is_authorized(&self, r: &Request, p: &PolicySet, e: &Entities) -> Response {
-        self.0.is_authorized(r.0.clone(), &p.ast, &e.0).into()
+        self.is_authorized_with_cancellation(r, p, e, &Cancellation::default())
+    }
+
+    /// Like [`Authorizer::is_authorized`], but abandons evaluation once
+    /// `cancellation` says to, e.g., when the caller's deadline for the
+    /// request passes. The response to an abandoned request is
+    /// [`Decision::Deny`], with a single error whose
+    /// [`EvaluationError`] is [`EvaluationError::Cancelled`].
+    ///
+    /// Deadlines are measured with the monotonic clock, and are checked every
+    /// few dozen expression nodes rather than at every node.
+    pub fn is_authorized_with_cancellation(
+        &self,
+        r: &Request,
+        p: &PolicySet,
+        e: &Entities,
+        cancellation: &Cancellation,
+    ) -> Response {
+        Response::from(self.0.is_authorized_with_cancellation(
+            r.0.clone(),
+            &p.ast,
+            &e.0,
+            cancellation,
+        ))
+        .with_provenance(p)
     }
 
     /// A partially evaluated authorization request.
@@ -975,6 +1692,61 @@ impl Authorizer {
     }
 }
 
+/// Collects the time taken and the number of expression nodes visited by
+/// every policy evaluated by the [`Authorizer`]s it is registered with.
+///
+/// Register a profiler with [`Authorizer::with_profiler`] to find the
+/// policies dominating authorization latency.
+///
+/// Clones share the same recorded profiles, so a profiler can be registered
+/// with several authorizers, possibly on different threads, and inspected
+/// through any clone.
+/// ```
+/// # use cedar_policy::{Authorizer, Context, Entities, EntityUid, EvaluationProfiler, PolicySet, Request};
+/// # use std::str::FromStr;
+/// let profiler = EvaluationProfiler::new();
+/// let authorizer = Authorizer::new().with_profiler(&profiler);
+/// let policies = PolicySet::from_str(r#"
+///     permit(principal, action, resource);
+///     forbid(principal, action, resource) when { [1, 2, 3].contains(4) };
+/// "#).unwrap();
+/// let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+/// let request = Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap();
+/// authorizer.is_authorized(&request, &policies, &Entities::empty());
+/// let profiles = profiler.profiles();
+/// assert_eq!(profiles.len(), 2);
+/// assert!(profiles.iter().all(|(_, profile)| profile.evaluations() == 1));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationProfiler(Arc<authorizer::EvaluationProfiler>);
+
+impl EvaluationProfiler {
+    /// Create a profiler which has not recorded anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The profile of every policy evaluated so far, most expensive (by total
+    /// time) first
+    pub fn profiles(&self) -> Vec<(PolicyId, PolicyProfile)> {
+        self.0
+            .profiles()
+            .into_iter()
+            .map(|(id, profile)| (PolicyId::new(id), profile))
+            .collect()
+    }
+
+    /// The profile of the policy `id`, if it has been evaluated
+    pub fn profile(&self, id: &PolicyId) -> Option<PolicyProfile> {
+        self.0.profile(id.as_ref())
+    }
+
+    /// Discard everything recorded so far
+    pub fn reset(&self) {
+        self.0.reset();
+    }
+}
+
 /// Authorization response returned from the `Authorizer`
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Response {
@@ -1091,10 +1863,18 @@ impl From<cedar_policy_core::authorizer::PartialResponse> for PartialResponse {
 pub struct Diagnostics {
     /// `PolicyId`s of the policies that contributed to the decision.
     /// If no policies applied to the request, this set will be empty.
-    reason: HashSet<PolicyId>,
+    reason: BTreeSet<PolicyId>,
     /// Errors that occurred during authorization. The errors should be
-    /// treated as unordered, since policies may be evaluated in any order.
+    /// treated as unordered, since policies may be evaluated in any order,
+    /// unless [`Authorizer::with_deterministic_order`] is used.
     errors: Vec<AuthorizationError>,
+    /// `PolicyId`s of the policies whose evaluation saturated an integer
+    /// overflow (see [`Authorizer::with_overflow_behavior`])
+    saturated_policies: BTreeSet<PolicyId>,
+    /// `PolicyId`s of the permit policies whose evaluation errored
+    errored_permits: BTreeSet<PolicyId>,
+    /// Provenance of the policies in `reason` and `errors`, where known
+    provenance: HashMap<PolicyId, Provenance>,
 }
 
 #[doc(hidden)]
@@ -1103,12 +1883,24 @@ impl From<authorizer::Diagnostics> for Diagnostics {
         Self {
             reason: diagnostics.reason.into_iter().map(PolicyId::new).collect(),
             errors: diagnostics.errors.into_iter().map(Into::into).collect(),
+            saturated_policies: diagnostics
+                .saturated_policies
+                .into_iter()
+                .map(PolicyId::new)
+                .collect(),
+            errored_permits: diagnostics
+                .errored_permits
+                .into_iter()
+                .map(PolicyId::new)
+                .collect(),
+            provenance: HashMap::new(),
         }
     }
 }
 
 impl Diagnostics {
-    /// Get the `PolicyId`s of the policies that contributed to the decision.
+    /// Get the `PolicyId`s of the policies that contributed to the decision,
+    /// in order of policy id.
     /// If no policies applied to the request, this set will be empty.
     /// ```
     /// # use cedar_policy::{Authorizer, Context, Decision, Entities, EntityId, EntityTypeName,
@@ -1169,7 +1961,9 @@ impl Diagnostics {
     }
 
     /// Get the errors that occurred during authorization. The errors should be
-    /// treated as unordered, since policies may be evaluated in any order.
+    /// treated as unordered, since policies may be evaluated in any order,
+    /// unless the [`Authorizer`] was created with
+    /// [`Authorizer::with_deterministic_order`].
     /// ```
     /// # use cedar_policy::{Authorizer, Context, Decision, Entities, EntityId, EntityTypeName,
     /// # EntityUid, Request,PolicySet};
@@ -1227,6 +2021,29 @@ impl Diagnostics {
         self.errors.iter()
     }
 
+    /// Get the `PolicyId`s of the policies whose evaluation clamped the result
+    /// of an integer overflow to the minimum or maximum `Long`, rather than
+    /// failing, in order of policy id. Always empty unless the [`Authorizer`]
+    /// was configured with [`OverflowBehavior::Saturate`].
+    pub fn saturated_policies(&self) -> impl Iterator<Item = &PolicyId> {
+        self.saturated_policies.iter()
+    }
+
+    /// Get the `PolicyId`s of the permit policies whose evaluation errored,
+    /// in order of policy id.
+    /// Since a policy which errors is not satisfied, any of these might have
+    /// allowed a request which was denied (see [`Response::denial_cause`]).
+    pub fn errored_permits(&self) -> impl Iterator<Item = &PolicyId> {
+        self.errored_permits.iter()
+    }
+
+    /// Get where a policy in [`Diagnostics::reason`], or one which caused an
+    /// error in [`Diagnostics::errors`], was deployed from, if known (see
+    /// [`Policy::with_provenance`])
+    pub fn provenance(&self, policy_id: &PolicyId) -> Option<&Provenance> {
+        self.provenance.get(policy_id)
+    }
+
     /// Consume the `Diagnostics`, producing owned versions of `reason()` and `errors()`
     pub(crate) fn into_components(
         self,
@@ -1247,10 +2064,32 @@ impl Response {
     ) -> Self {
         Self {
             decision,
-            diagnostics: Diagnostics { reason, errors },
+            diagnostics: Diagnostics {
+                reason: reason.into_iter().collect(),
+                errors,
+                saturated_policies: BTreeSet::new(),
+                errored_permits: BTreeSet::new(),
+                provenance: HashMap::new(),
+            },
         }
     }
 
+    /// Record the provenance, according to `pset`, of the policies that
+    /// determined this response or errored
+    fn with_provenance(mut self, pset: &PolicySet) -> Self {
+        let diagnostics = &mut self.diagnostics;
+        let ids = diagnostics.reason.iter().chain(
+            diagnostics
+                .errors
+                .iter()
+                .map(|AuthorizationError::PolicyEvaluationError(e)| e.policy_id()),
+        );
+        diagnostics.provenance = ids
+            .filter_map(|id| Some((id.clone(), pset.provenance(id)?.clone())))
+            .collect();
+        self
+    }
+
     /// Get the authorization decision
     pub fn decision(&self) -> Decision {
         self.decision
@@ -1318,28 +2157,711 @@ impl Validator {
         Self(cedar_policy_validator::Validator::new(schema.0))
     }
 
-    /// Validate all policies in a policy set, collecting all validation errors
-    /// found into the returned `ValidationResult`. Each error is returned together with the
-    /// policy id of the policy where the error was found. If a policy id
-    /// included in the input policy set does not appear in the output iterator, then
-    /// that policy passed the validator. If the function `validation_passed`
-    /// returns true, then there were no validation errors found, so all
-    /// policies in the policy set have passed the validator.
-    pub fn validate(&self, pset: &PolicySet, mode: ValidationMode) -> ValidationResult {
-        ValidationResult::from(self.0.validate(&pset.ast, mode.into()))
+    /// Set which uses of attributes annotated as sensitive (`@pii`) in the
+    /// schema produce [`ValidationWarning::SensitiveAttributeUse`] warnings.
+    /// By default, all of them do.
+    #[must_use]
+    pub fn with_sensitive_attribute_rules(self, rules: SensitiveAttributeRules) -> Self {
+        Self(self.0.with_sensitive_attribute_rules(rules))
     }
 
-    #[cfg(feature = "level-validate")]
-    /// Validate all policies in a policy set, collecting all validation errors
-    /// found into the returned `ValidationResult`. If validation passes, run level
-    /// validation (RFC 76). Each error is returned together with the policy id of the policy
-    /// where the error was found. If a policy id included in the input policy set does not
-    /// appear in the output iterator, then that policy passed the validator. If the function
-    /// `validation_passed` returns true, then there were no validation errors found, so
-    /// all policies in the policy set have passed the validator.
-    pub fn validate_with_level(
-        &self,
-        pset: &PolicySet,
+    /// Check the `@expires` annotations of policies against `rules`,
+    /// reporting [`ValidationWarning::PolicyExpiring`] for policies which
+    /// expire soon and [`ValidationError::ExpiredPolicy`] for policies which
+    /// have expired. By default, these annotations are not checked.
+    ///
+    /// ```
+    /// # use cedar_policy::{ExpirationRules, PolicySet, Schema, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(
+    ///     "entity User; action view appliesTo { principal: User, resource: User };",
+    /// )
+    /// .unwrap();
+    /// let pset = PolicySet::from_str(
+    ///     r#"@expires("2025-01-31") permit(principal, action, resource);"#,
+    /// )
+    /// .unwrap();
+    /// let rules = ExpirationRules::new("2025-02-01".parse().unwrap());
+    /// let validator = Validator::new(schema).with_expiration_rules(rules);
+    /// let result = validator.validate(&pset, ValidationMode::Strict);
+    /// assert!(!result.validation_passed());
+    /// ```
+    #[must_use]
+    pub fn with_expiration_rules(self, rules: ExpirationRules) -> Self {
+        Self(self.0.with_expiration_rules(rules))
+    }
+
+    /// Only allow policies to reference entity types and actions in the given
+    /// namespaces, where `None` is the empty namespace. References to entity
+    /// types or actions in any other namespace, including in the slot values
+    /// of template-linked policies, are reported as
+    /// [`ValidationError::DisallowedNamespace`]. Namespaces must match
+    /// exactly, so allowing `A` does not allow `A::B`. By default, policies
+    /// may reference any namespace.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationError, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(
+    ///     "
+    ///     namespace TenantA { entity User; action view appliesTo { principal: User, resource: User }; }
+    ///     namespace TenantB { entity User; }
+    ///     ",
+    /// )
+    /// .unwrap();
+    /// let pset = PolicySet::from_str(
+    ///     r#"permit(principal, action == TenantA::Action::"view", resource) when { principal in TenantB::User::"bob" };"#,
+    /// )
+    /// .unwrap();
+    /// let validator =
+    ///     Validator::new(schema).with_allowed_namespaces([Some("TenantA".parse().unwrap())]);
+    /// let result = validator.validate(&pset, ValidationMode::Strict);
+    /// assert!(result
+    ///     .validation_errors()
+    ///     .any(|e| matches!(e, ValidationError::DisallowedNamespace(_))));
+    /// ```
+    #[must_use]
+    pub fn with_allowed_namespaces(
+        self,
+        namespaces: impl IntoIterator<Item = Option<EntityNamespace>>,
+    ) -> Self {
+        Self(
+            self.0
+                .with_allowed_namespaces(namespaces.into_iter().map(|ns| ns.map(|ns| ns.0))),
+        )
+    }
+
+    /// Validate all policies in a policy set, collecting all validation errors
+    /// found into the returned `ValidationResult`. Each error is returned together with the
+    /// policy id of the policy where the error was found. If a policy id
+    /// included in the input policy set does not appear in the output iterator, then
+    /// that policy passed the validator. If the function `validation_passed`
+    /// returns true, then there were no validation errors found, so all
+    /// policies in the policy set have passed the validator.
+    pub fn validate(&self, pset: &PolicySet, mode: ValidationMode) -> ValidationResult {
+        ValidationResult::from(self.0.validate(&pset.ast, mode.into()))
+            .with_provenance(|id| pset.provenance(id))
+    }
+
+    /// Validate all policies in a policy set like [`Validator::validate`],
+    /// additionally measuring how long validating each template and static
+    /// policy takes and how much work typechecking it takes, e.g., to find
+    /// the policies which make validation slow. The profiles are sorted with
+    /// the slowest policy first.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let pset: PolicySet = r#"
+    ///     permit(principal, action, resource);
+    ///     permit(principal, action, resource) when { principal == resource };
+    /// "#.parse().unwrap();
+    /// let (result, profiles) = Validator::new(schema).validate_with_profile(&pset, ValidationMode::Strict);
+    /// assert!(result.validation_passed());
+    /// assert_eq!(profiles.len(), 2);
+    /// assert!(profiles.iter().all(|profile| profile.request_envs() == 1));
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn validate_with_profile(
+        &self,
+        pset: &PolicySet,
+        mode: ValidationMode,
+    ) -> (ValidationResult, Vec<ValidationProfile>) {
+        let (result, profiles) = self.0.validate_with_profile(&pset.ast, mode.into());
+        (
+            ValidationResult::from(result).with_provenance(|id| pset.provenance(id)),
+            profiles.into_iter().map(ValidationProfile::from).collect(),
+        )
+    }
+
+    /// Rewrite the entity type and action references in the policy set
+    /// source `policies` which the schema does not declare, but which
+    /// abbreviate exactly one declared name, to that fully qualified name.
+    /// A reference abbreviates a declared name if it is a suffix of it, e.g.,
+    /// `User` abbreviates `Acme::User`. Cedar policies do not resolve names
+    /// relative to a namespace, so this makes the references from authors who
+    /// omitted the namespace refer to the declared types.
+    ///
+    /// The policies are rewritten with text edits, so their formatting and
+    /// comments are kept. References which abbreviate more than one declared
+    /// name are left unchanged and reported by [`QualifiedNames::skipped`].
+    ///
+    /// ```
+    /// # use cedar_policy::{Schema, Validator};
+    /// let schema: Schema = r#"
+    ///     namespace Acme {
+    ///         entity User;
+    ///         entity Photo;
+    ///         action view appliesTo { principal: User, resource: Photo };
+    ///     }
+    /// "#.parse().unwrap();
+    /// let qualified = Validator::new(schema)
+    ///     .qualify_names(r#"permit(principal is User, action == Action::"view", resource);"#)
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     qualified.policies(),
+    ///     r#"permit(principal is Acme::User, action == Acme::Action::"view", resource);"#
+    /// );
+    /// ```
+    pub fn qualify_names(&self, policies: &str) -> Result<QualifiedNames, ParseErrors> {
+        let result = self
+            .0
+            .qualify_in_policies(policies)
+            .map_err(ParseErrors::from)?;
+        Ok(QualifiedNames::new(policies, result))
+    }
+
+    /// Validate `pset` in both strict and permissive mode, and report the
+    /// policies for which the modes report different errors, e.g., to plan a
+    /// migration from permissive to strict validation. For each such policy,
+    /// the report gives the errors only reported in strict mode, which must
+    /// be fixed for the policy to pass strict validation.
+    #[doc = include_str!("../experimental_warning.md")]
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Admin;
+    ///     entity Photo;
+    ///     action view appliesTo { principal: [User, Admin], resource: Photo };
+    /// "#.parse().unwrap();
+    /// // Sets of different entity types are only an error in strict mode
+    /// let pset: PolicySet = r#"
+    ///     permit(principal, action, resource) when { [User::"a", Admin::"b"].contains(principal) };
+    ///     permit(principal is User, action, resource);
+    /// "#.parse().unwrap();
+    /// let comparison = Validator::new(schema).compare_validation_modes(&pset);
+    /// assert_eq!(
+    ///     comparison.failing_only_strict().map(ToString::to_string).collect::<Vec<_>>(),
+    ///     vec!["policy0"]
+    /// );
+    /// ```
+    #[cfg(feature = "permissive-validate")]
+    pub fn compare_validation_modes(&self, pset: &PolicySet) -> ValidationModeComparison {
+        ValidationModeComparison::new(
+            self.0
+                .validate(&pset.ast, cedar_policy_validator::ValidationMode::Strict),
+            self.0.validate(
+                &pset.ast,
+                cedar_policy_validator::ValidationMode::Permissive,
+            ),
+        )
+    }
+
+    /// Validate policies one at a time as `policies` produces them, yielding
+    /// the id and [`ValidationResult`] of each policy as soon as it has been
+    /// checked. Unlike [`Validator::validate`], this doesn't wait for the
+    /// whole batch, so problems can be reported while later policies are
+    /// still being read or checked. The diagnostics for a template are
+    /// reported with the first of its links; the other links of the template
+    /// only report errors in their slot values.
+    ///
+    /// ```
+    /// # use cedar_policy::{Policy, PolicyId, Schema, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     action view appliesTo { principal: User, resource: User };
+    /// "#.parse().unwrap();
+    /// let policies = [
+    ///     r#"permit(principal, action == Action::"view", resource);"#,
+    ///     r#"permit(principal, action == Action::"edit", resource);"#,
+    /// ]
+    /// .into_iter()
+    /// .enumerate()
+    /// .map(|(i, src)| Policy::parse(Some(PolicyId::new(format!("p{i}"))), src).unwrap());
+    /// let failed = Validator::new(schema)
+    ///     .validate_stream(policies, ValidationMode::Strict)
+    ///     .filter(|(_, result)| !result.validation_passed())
+    ///     .map(|(id, _)| id.to_string())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(failed, vec!["p1"]);
+    /// ```
+    pub fn validate_stream<'a>(
+        &'a self,
+        policies: impl IntoIterator<Item = Policy> + 'a,
+        mode: ValidationMode,
+    ) -> impl Iterator<Item = (PolicyId, ValidationResult)> + 'a {
+        let policies = policies.into_iter().map(|p| p.ast);
+        self.0
+            .validate_stream(policies, mode.into())
+            .map(|(p, result)| {
+                let result = ValidationResult::from(result).with_provenance(|_| p.provenance());
+                (PolicyId::new(p.id().clone()), result)
+            })
+    }
+
+    /// Validate a single template on its own, so that errors in it are found
+    /// before any links of it exist. Each slot is typechecked with every
+    /// entity type it could be linked with according to the template scope
+    /// and the schema. Errors are reported against the template source.
+    ///
+    /// ```
+    /// # use cedar_policy::{Schema, Template, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User = { name: String };
+    ///     entity Folder;
+    ///     entity Doc in Folder;
+    ///     action view appliesTo { principal: User, resource: Doc };
+    /// "#.parse().unwrap();
+    /// let template: Template = r#"
+    ///     permit(principal, action == Action::"view", resource in ?resource)
+    ///     when { principal.name > 1 };
+    /// "#.parse().unwrap();
+    /// let result = Validator::new(schema).validate_template(&template, ValidationMode::Strict);
+    /// assert!(!result.validation_passed());
+    /// ```
+    pub fn validate_template(&self, template: &Template, mode: ValidationMode) -> ValidationResult {
+        ValidationResult::from(
+            self.0
+                .validate_template(&template.ast, HashMap::new(), mode.into()),
+        )
+        .with_provenance(|_| template.provenance())
+    }
+
+    /// Like [`Validator::validate_template`], but only consider links of the
+    /// slots in `slot_types` with the entity types given for them. Slots
+    /// without an entry may still be linked with any entity type admitted by
+    /// the template scope. Entity types which are not declared in the schema
+    /// are reported as errors.
+    pub fn validate_template_with_slot_types(
+        &self,
+        template: &Template,
+        slot_types: &HashMap<SlotId, HashSet<EntityTypeName>>,
+        mode: ValidationMode,
+    ) -> ValidationResult {
+        let slot_bounds = slot_types
+            .iter()
+            .map(|(slot, tys)| {
+                (
+                    ast::SlotId::from(slot.clone()),
+                    tys.iter().map(|ty| ty.0.clone()).collect(),
+                )
+            })
+            .collect();
+        ValidationResult::from(
+            self.0
+                .validate_template(&template.ast, slot_bounds, mode.into()),
+        )
+        .with_provenance(|_| template.provenance())
+    }
+
+    /// Typecheck the standalone expression `expr` in the environment `env`,
+    /// returning its type. Unlike a policy condition, `expr` may have any
+    /// type, so this can check expressions built outside of a policy, e.g.,
+    /// in an expression editor.
+    ///
+    /// ```
+    /// # use cedar_policy::{Expression, ExpressionEnv, Schema, SchemaType, ValidationMode, Validator};
+    /// # use std::collections::BTreeMap;
+    /// let schema: Schema = r#"
+    ///     entity User = { age: Long };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let validator = Validator::new(schema);
+    /// let env = ExpressionEnv::new(
+    ///     "User".parse().unwrap(),
+    ///     r#"Action::"view""#.parse().unwrap(),
+    ///     "Photo".parse().unwrap(),
+    ///     SchemaType::Record { attributes: BTreeMap::new(), additional_attributes: false },
+    /// );
+    /// let expr: Expression = "principal.age + 1".parse().unwrap();
+    /// let ty = validator.typecheck_expression(&expr, &env, ValidationMode::Strict).unwrap();
+    /// assert_eq!(ty, SchemaType::Long);
+    ///
+    /// let expr: Expression = "principal.name".parse().unwrap();
+    /// assert!(validator.typecheck_expression(&expr, &env, ValidationMode::Strict).is_err());
+    /// ```
+    pub fn typecheck_expression(
+        &self,
+        expr: &Expression,
+        env: &ExpressionEnv,
+        mode: ValidationMode,
+    ) -> Result<SchemaType, ExpressionTypecheckError> {
+        if self.0.schema().get_action_id(&env.action.0).is_none() {
+            return Err(ExpressionTypecheckError::UndeclaredAction(
+                env.action.clone(),
+            ));
+        }
+        let context = env.context.to_type()?;
+        let request_env = cedar_policy_validator::types::RequestEnv::DeclaredAction {
+            principal: &env.principal.0,
+            action: &env.action.0,
+            resource: &env.resource.0,
+            context: &context,
+            principal_slot: None,
+            resource_slot: None,
+        };
+        let ty = self
+            .0
+            .typecheck_expr(&expr.0, &request_env, mode.into())
+            .map_err(|errs| {
+                ExpressionTypecheckError::Validation(errs.into_iter().map(Into::into).collect())
+            })?;
+        SchemaType::try_from_type(&ty)
+            .ok_or_else(|| ExpressionTypecheckError::UnsupportedType(ty.to_string()))
+    }
+
+    /// Find the innermost expression at byte `offset` in the policy text
+    /// `src`, and the type the validator infers for it in every request
+    /// environment, e.g., to show in a hover tooltip. Scope constraints count
+    /// as expressions. Types are inferred even if the policy fails to
+    /// validate, so long as the expression itself can be typed.
+    ///
+    /// Returns `Ok(None)` if there is no typed expression at `offset`, and an
+    /// error if `src` fails to parse.
+    ///
+    /// ```
+    /// # use cedar_policy::{Schema, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User = { age: Long };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let validator = Validator::new(schema);
+    /// let src = "permit(principal, action, resource) when { principal.age > 18 };";
+    /// let offset = src.find("age").unwrap();
+    /// let at = validator.type_at_offset(src, offset, ValidationMode::Strict).unwrap().unwrap();
+    /// assert_eq!(&src[at.span().offset()..][..at.span().len()], "principal.age");
+    /// let (_, ty) = at.types().next().unwrap();
+    /// assert_eq!(ty, "Long");
+    /// ```
+    pub fn type_at_offset(
+        &self,
+        src: &str,
+        offset: usize,
+        mode: ValidationMode,
+    ) -> Result<Option<TypeAtOffset>, ParseErrors> {
+        let pset = parser::parse_policyset(src)?;
+        let at = pset
+            .all_templates()
+            .find(|t| {
+                t.loc().is_some_and(|loc| {
+                    loc.span.offset() <= offset && offset < loc.span.offset() + loc.span.len()
+                })
+            })
+            .and_then(|t| self.0.type_at_offset(t, offset, mode.into()));
+        Ok(at.map(TypeAtOffset::new))
+    }
+
+    /// Get the static and template-linked policies in `pset` which could
+    /// apply to a request of the given [`RequestEnv`], judging only by the
+    /// policy scopes and the schema. Policy conditions are not evaluated, so
+    /// every returned policy _may_ be satisfied by such a request, and any
+    /// policy not returned can never be satisfied by one.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, RequestEnv, Schema, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User;
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let pset: PolicySet = r#"
+    ///     permit(principal, action == Action::"view", resource is Photo) when { false };
+    ///     permit(principal, action, resource is User);
+    /// "#.parse().unwrap();
+    /// let validator = Validator::new(schema);
+    /// let env = RequestEnv::new(
+    ///     "User".parse().unwrap(),
+    ///     r#"Action::"view""#.parse().unwrap(),
+    ///     "Photo".parse().unwrap(),
+    /// );
+    /// let ids = validator
+    ///     .applicable_policies(&pset, &env)
+    ///     .map(|p| p.id().to_string())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(ids, vec!["policy0"]);
+    /// ```
+    pub fn applicable_policies<'a>(
+        &'a self,
+        pset: &'a PolicySet,
+        env: &'a RequestEnv,
+    ) -> impl Iterator<Item = &'a Policy> + 'a {
+        pset.policies().filter(move |p| {
+            self.0.policy_may_apply_to(
+                &p.ast,
+                &env.principal.0,
+                env.action.as_ref(),
+                &env.resource.0,
+            )
+        })
+    }
+
+    /// For every action in the schema, get the entity types it can reach via
+    /// its `appliesTo` declaration and the entity hierarchy, and whether any
+    /// policy or template in `pset` has a scope admitting the action with
+    /// one of its principal and resource types. Actions for which there is no
+    /// such policy can never be allowed or denied explicitly, and may be
+    /// candidates for removal from the schema.
+    ///
+    /// The results are sorted by action.
+    pub fn action_reachability(&self, pset: &PolicySet) -> Vec<ActionReachability> {
+        self.0
+            .action_reachability(&pset.ast)
+            .into_iter()
+            .map(ActionReachability)
+            .collect()
+    }
+
+    /// Find the actions in the schema which no `permit` policy or template in
+    /// `pset` has a scope admitting, together with one of their principal and
+    /// resource types. Every request for one of these actions is denied,
+    /// whatever the conditions of the policies.
+    ///
+    /// The results are sorted.
+    pub fn unpermitted_actions(&self, pset: &PolicySet) -> Vec<EntityUid> {
+        self.0
+            .unpermitted_actions(&pset.ast)
+            .into_iter()
+            .map(EntityUid)
+            .collect()
+    }
+
+    /// Find the top-level attributes of entity types and of action contexts
+    /// declared in the schema which no policy or template in `pset` reads.
+    /// Nobody's authorization logic depends on these attributes, so their
+    /// producers may stop populating them.
+    ///
+    /// An attribute counts as read if it is accessed with `.`, `[]`, or `has`
+    /// on an expression which may be of the declaring entity type, or on the
+    /// `context` of the action. Where the validator cannot infer the type of
+    /// an expression, an access on it reads the attribute of every entity
+    /// type. Using `context` other than by accessing one of its attributes
+    /// reads all of its attributes.
+    ///
+    /// The results are sorted with entity attributes first.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, UnusedAttribute, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User = { name: String, email: String };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let pset: PolicySet = r#"permit(principal, action, resource) when { principal.name == "a" };"#
+    ///     .parse()
+    ///     .unwrap();
+    /// let unused = Validator::new(schema).unused_attributes(&pset);
+    /// assert_eq!(
+    ///     unused,
+    ///     vec![UnusedAttribute::Entity {
+    ///         entity_type: "User".parse().unwrap(),
+    ///         attr: "email".into(),
+    ///     }]
+    /// );
+    /// ```
+    pub fn unused_attributes(&self, pset: &PolicySet) -> Vec<UnusedAttribute> {
+        self.0
+            .unused_attributes(&pset.ast)
+            .into_iter()
+            .map(|attr| match attr {
+                cedar_policy_validator::UnusedAttribute::Entity { entity_type, attr } => {
+                    UnusedAttribute::Entity {
+                        entity_type: EntityTypeName(entity_type),
+                        attr: attr.to_string(),
+                    }
+                }
+                cedar_policy_validator::UnusedAttribute::Context { action, attr } => {
+                    UnusedAttribute::Context {
+                        action: EntityUid(action),
+                        attr: attr.to_string(),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Build the graph of the schema elements and extension functions each
+    /// policy and template in `pset` depends on, for impact analysis of
+    /// schema changes or diagrams of the authorization model.
+    ///
+    /// Entity types and actions are dependencies of the policies mentioning
+    /// them in entity literals and `is` tests. Attributes are dependencies of
+    /// the policies reading them with `.`, `[]`, or `has`, using the types
+    /// the validator infers for the expressions they are read from: an
+    /// attribute read from an expression which may be of several entity
+    /// types is a dependency on the attribute of each, and one read from an
+    /// expression of unknown type is a dependency on the attribute of every
+    /// entity type declaring it. Attributes read from `context` are
+    /// dependencies on the context attribute of every action the policy
+    /// applies to.
+    ///
+    /// ```
+    /// # use cedar_policy::{Dependency, PolicySet, Schema, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User = { name: String };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let pset: PolicySet = r#"permit(principal, action, resource is Photo) when { principal.name == "a" };"#
+    ///     .parse()
+    ///     .unwrap();
+    /// let graph = Validator::new(schema).dependency_graph(&pset);
+    /// assert_eq!(
+    ///     graph.dependencies(&"policy0".parse().unwrap()).collect::<Vec<_>>(),
+    ///     vec![
+    ///         Dependency::EntityType("Photo".parse().unwrap()),
+    ///         Dependency::Attribute {
+    ///             entity_type: "User".parse().unwrap(),
+    ///             attr: "name".into(),
+    ///         },
+    ///     ]
+    /// );
+    /// assert!(graph.to_dot().contains(r#""policy:policy0" -> "attribute:User.name";"#));
+    /// ```
+    pub fn dependency_graph(&self, pset: &PolicySet) -> DependencyGraph {
+        DependencyGraph(self.0.dependency_graph(&pset.ast))
+    }
+
+    /// Find all pairs of static or template-linked `permit` policies in
+    /// `pset` which are owned by different owners, as given by the value of
+    /// the `owner_annotation` annotation (e.g., `"owner"` for `@owner`), and
+    /// whose scopes may both apply to some principal type, action, and
+    /// resource type in the schema. Such pairs grant overlapping access to
+    /// shared resources and may need to be reviewed together. Policies
+    /// without the annotation are not considered, and policy conditions are
+    /// not compared.
+    ///
+    /// The results are sorted by policy ids.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, Validator};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str(
+    ///     "entity User; entity Photo; action view appliesTo { principal: User, resource: Photo };",
+    /// )
+    /// .unwrap();
+    /// let pset = PolicySet::from_str(
+    ///     r#"
+    ///     @owner("photos-team")
+    ///     permit(principal, action, resource is Photo);
+    ///     @owner("sharing-team")
+    ///     permit(principal, action == Action::"view", resource);
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// let conflicts = Validator::new(schema).permit_conflicts(&pset, "owner");
+    /// assert_eq!(conflicts.len(), 1);
+    /// assert_eq!(conflicts[0].owners(), ("photos-team", "sharing-team"));
+    /// ```
+    pub fn permit_conflicts(
+        &self,
+        pset: &PolicySet,
+        owner_annotation: &str,
+    ) -> Vec<PermitConflict> {
+        self.0
+            .permit_conflicts(&pset.ast, owner_annotation)
+            .into_iter()
+            .map(PermitConflict)
+            .collect()
+    }
+
+    /// Find the continuations of the partial policy text `src` at the byte
+    /// offset `cursor` which are valid for the schema, e.g., for completion in
+    /// an editor. These are entity types and actions, attributes and methods
+    /// of the expression before a `.`, variables, and extension functions.
+    /// Only continuations starting with the partial word before the cursor
+    /// are returned.
+    ///
+    /// `src` need not parse. Attributes are found by typechecking the
+    /// expression before the `.` in every request environment admitted by the
+    /// scope of the policy the cursor is in, or in every request environment
+    /// if that scope is not complete yet.
+    ///
+    /// ```
+    /// # use cedar_policy::{CompletionKind, Schema, ValidationMode, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User = { name: String, age: Long };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let validator = Validator::new(schema);
+    /// let src = "permit(principal, action, resource) when { principal.a";
+    /// let completions = validator.completions(src, src.len(), ValidationMode::Strict);
+    /// assert_eq!(completions.start(), src.len() - 1);
+    /// let item = completions.items().next().unwrap();
+    /// assert_eq!(item.label(), "age");
+    /// assert_eq!(item.kind(), CompletionKind::Attribute);
+    /// assert_eq!(item.detail(), Some("Long"));
+    /// ```
+    pub fn completions(&self, src: &str, cursor: usize, mode: ValidationMode) -> Completions {
+        Completions(self.0.completions(src, cursor, mode.into()))
+    }
+
+    /// Find every reference to a schema element in the source `src` of a
+    /// policy set, sorted by position, to assess the impact of changing the
+    /// element in the schema.
+    ///
+    /// Entity types and actions are found wherever they appear in a policy
+    /// or template. Attribute accesses are found using the types the
+    /// validator infers for their receivers: an access on an expression which
+    /// is always of the declaring entity type is a
+    /// [`ReferenceKind::Definite`] reference, and one on an expression which
+    /// may be of that type or of another is a [`ReferenceKind::Possible`]
+    /// reference.
+    ///
+    /// ```
+    /// # use cedar_policy::{ReferenceKind, Schema, SchemaElement, Validator};
+    /// let schema: Schema = r#"
+    ///     entity User = { name: String };
+    ///     entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };
+    /// "#.parse().unwrap();
+    /// let validator = Validator::new(schema);
+    /// let src = r#"permit(principal is User, action, resource) when { principal.name == "a" };"#;
+    /// let element = SchemaElement::Attribute {
+    ///     entity_type: "User".parse().unwrap(),
+    ///     attr: "name".into(),
+    /// };
+    /// let references = validator.find_references(src, &element).unwrap();
+    /// let reference = references.first().unwrap();
+    /// assert_eq!(reference.span().offset(), src.find("name").unwrap());
+    /// assert_eq!(reference.kind(), ReferenceKind::Definite);
+    /// ```
+    pub fn find_references(
+        &self,
+        src: &str,
+        element: &SchemaElement,
+    ) -> Result<Vec<Reference>, ParseErrors> {
+        let element = match element {
+            SchemaElement::EntityType(ty) => {
+                cedar_policy_validator::SchemaElement::EntityType(ty.0.clone())
+            }
+            SchemaElement::Action(uid) => {
+                cedar_policy_validator::SchemaElement::Action(uid.0.clone())
+            }
+            SchemaElement::Attribute { entity_type, attr } => {
+                cedar_policy_validator::SchemaElement::Attribute {
+                    entity_type: entity_type.0.clone(),
+                    attr: attr.into(),
+                }
+            }
+        };
+        Ok(self
+            .0
+            .find_references(src, &element)?
+            .into_iter()
+            .map(Reference)
+            .collect())
+    }
+
+    #[cfg(feature = "level-validate")]
+    /// Validate all policies in a policy set, collecting all validation errors
+    /// found into the returned `ValidationResult`. If validation passes, run level
+    /// validation (RFC 76). Each error is returned together with the policy id of the policy
+    /// where the error was found. If a policy id included in the input policy set does not
+    /// appear in the output iterator, then that policy passed the validator. If the function
+    /// `validation_passed` returns true, then there were no validation errors found, so
+    /// all policies in the policy set have passed the validator.
+    pub fn validate_with_level(
+        &self,
+        pset: &PolicySet,
         mode: ValidationMode,
         max_deref_level: u32,
     ) -> ValidationResult {
@@ -1347,6 +2869,277 @@ impl Validator {
             self.0
                 .validate_with_level(&pset.ast, mode.into(), max_deref_level),
         )
+        .with_provenance(|id| pset.provenance(id))
+    }
+}
+
+/// The entity types reachable from one action in a [`Schema`], as computed by
+/// [`Validator::action_reachability`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionReachability(cedar_policy_validator::ActionReachability);
+
+impl ActionReachability {
+    /// The action
+    pub fn action(&self) -> &EntityUid {
+        EntityUid::ref_cast(&self.0.action)
+    }
+
+    /// Entity types the action applies to as a principal, together with all
+    /// of their ancestor types in the entity hierarchy
+    pub fn principal_types(&self) -> impl Iterator<Item = &EntityTypeName> {
+        self.0.principal_types.iter().map(EntityTypeName::ref_cast)
+    }
+
+    /// Entity types the action applies to as a resource, together with all
+    /// of their ancestor types in the entity hierarchy
+    pub fn resource_types(&self) -> impl Iterator<Item = &EntityTypeName> {
+        self.0.resource_types.iter().map(EntityTypeName::ref_cast)
+    }
+
+    /// Whether the scope of some policy or template admits this action
+    /// together with one of its principal types and one of its resource types
+    pub fn has_applicable_policy(&self) -> bool {
+        self.0.has_applicable_policy
+    }
+}
+
+/// An attribute declared in a [`Schema`] which no policy reads, as computed
+/// by [`Validator::unused_attributes`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum UnusedAttribute {
+    /// A top-level attribute of an entity type
+    Entity {
+        /// The entity type
+        entity_type: EntityTypeName,
+        /// The attribute
+        attr: String,
+    },
+    /// A top-level attribute of the context of an action
+    Context {
+        /// The action
+        action: EntityUid,
+        /// The attribute
+        attr: String,
+    },
+}
+
+/// Something a policy depends on, as recorded in a [`DependencyGraph`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Dependency {
+    /// An entity type, referenced in an entity literal or an `is` test
+    EntityType(EntityTypeName),
+    /// An action, referenced in an entity literal
+    Action(EntityUid),
+    /// A top-level attribute of an entity type, read with `.`, `[]`, or `has`
+    Attribute {
+        /// The entity type declaring the attribute
+        entity_type: EntityTypeName,
+        /// The attribute
+        attr: String,
+    },
+    /// A top-level attribute of the context of an action, read with `.`,
+    /// `[]`, or `has`
+    ContextAttribute {
+        /// The action
+        action: EntityUid,
+        /// The attribute
+        attr: String,
+    },
+    /// An extension function, called as a function or as a method, e.g.,
+    /// `"ip"` or `"decimal"`
+    ExtensionFunction(String),
+}
+
+impl From<&cedar_policy_validator::Dependency> for Dependency {
+    fn from(dependency: &cedar_policy_validator::Dependency) -> Self {
+        match dependency {
+            cedar_policy_validator::Dependency::EntityType(ty) => {
+                Self::EntityType(EntityTypeName(ty.clone()))
+            }
+            cedar_policy_validator::Dependency::Action(action) => {
+                Self::Action(EntityUid(action.clone()))
+            }
+            cedar_policy_validator::Dependency::Attribute { entity_type, attr } => {
+                Self::Attribute {
+                    entity_type: EntityTypeName(entity_type.clone()),
+                    attr: attr.to_string(),
+                }
+            }
+            cedar_policy_validator::Dependency::ContextAttribute { action, attr } => {
+                Self::ContextAttribute {
+                    action: EntityUid(action.clone()),
+                    attr: attr.to_string(),
+                }
+            }
+            cedar_policy_validator::Dependency::ExtensionFunction(name) => {
+                Self::ExtensionFunction(name.to_string())
+            }
+        }
+    }
+}
+
+/// The schema elements and extension functions each policy or template of a
+/// [`PolicySet`] depends on, as computed by [`Validator::dependency_graph`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyGraph(cedar_policy_validator::DependencyGraph);
+
+impl DependencyGraph {
+    /// The ids of the policies and templates in the graph, in sorted order
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyId> {
+        self.0.policies().map(PolicyId::ref_cast)
+    }
+
+    /// The dependencies of the policy or template `id`, in sorted order
+    pub fn dependencies(&self, id: &PolicyId) -> impl Iterator<Item = Dependency> + '_ {
+        self.0.dependencies(id.as_ref()).map(Dependency::from)
+    }
+
+    /// The ids of the policies and templates depending on `dependency`, in
+    /// sorted order. These are the policies whose behavior may change when
+    /// the dependency is changed or removed.
+    pub fn dependents<'a>(
+        &'a self,
+        dependency: &'a Dependency,
+    ) -> impl Iterator<Item = &'a PolicyId> {
+        self.policies()
+            .filter(move |id| self.dependencies(id).any(|d| &d == dependency))
+    }
+
+    /// Render the graph as JSON, as an object mapping every policy id to the
+    /// list of its dependencies. Each dependency is an object with a `kind`
+    /// (`entityType`, `action`, `attribute`, `contextAttribute`, or
+    /// `extensionFunction`) and the fields identifying it.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.0.to_json()
+    }
+
+    /// Render the graph in the DOT language of Graphviz, with an edge from
+    /// every policy to each of its dependencies
+    pub fn to_dot(&self) -> String {
+        self.0.to_dot()
+    }
+}
+
+/// Two `permit` policies owned by different owners whose scopes overlap, as
+/// computed by [`Validator::permit_conflicts`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermitConflict(cedar_policy_validator::PermitConflict);
+
+impl PermitConflict {
+    /// The ids of the two policies, in sorted order
+    pub fn policies(&self) -> (&PolicyId, &PolicyId) {
+        (
+            PolicyId::ref_cast(&self.0.policies.0),
+            PolicyId::ref_cast(&self.0.policies.1),
+        )
+    }
+
+    /// The owners of the two policies, in the same order as
+    /// [`PermitConflict::policies`]
+    pub fn owners(&self) -> (&str, &str) {
+        (&self.0.owners.0, &self.0.owners.1)
+    }
+
+    /// Every principal type, action, and resource type which both policies
+    /// may apply to, in sorted order
+    pub fn shared_request_envs(&self) -> impl Iterator<Item = RequestEnv> + '_ {
+        self.0
+            .shared_request_types
+            .iter()
+            .map(|(principal, action, resource)| {
+                RequestEnv::new(
+                    principal.clone().into(),
+                    action.clone().into(),
+                    resource.clone().into(),
+                )
+            })
+    }
+}
+
+/// The continuations of partial policy text at a cursor position, as computed
+/// by [`Validator::completions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completions(cedar_policy_validator::Completions);
+
+impl Completions {
+    /// Byte offset where the partial word before the cursor starts. A
+    /// completion replaces the text from here up to the cursor.
+    pub fn start(&self) -> usize {
+        self.0.start
+    }
+
+    /// The possible continuations, sorted by kind and label
+    pub fn items(&self) -> impl Iterator<Item = &Completion> {
+        self.0.items.iter().map(Completion::ref_cast)
+    }
+}
+
+/// One possible continuation of partial policy text, see
+/// [`Validator::completions`]
+#[repr(transparent)]
+#[derive(Debug, Clone, PartialEq, Eq, RefCast)]
+pub struct Completion(cedar_policy_validator::Completion);
+
+impl Completion {
+    /// The text to insert in place of the partial word before the cursor
+    pub fn label(&self) -> &str {
+        &self.0.label
+    }
+
+    /// What the text refers to
+    pub fn kind(&self) -> CompletionKind {
+        self.0.kind
+    }
+
+    /// Extra information to show with the completion, e.g., the type of an
+    /// attribute
+    pub fn detail(&self) -> Option<&str> {
+        self.0.detail.as_deref()
+    }
+}
+
+/// An entity type, action, or attribute declared in a schema, see
+/// [`Validator::find_references`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaElement {
+    /// An entity type
+    EntityType(EntityTypeName),
+    /// An action
+    Action(EntityUid),
+    /// An attribute of an entity type
+    Attribute {
+        /// The entity type declaring the attribute
+        entity_type: EntityTypeName,
+        /// The attribute
+        attr: String,
+    },
+}
+
+/// A reference to a schema element in the source of a policy set, as found
+/// by [`Validator::find_references`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference(cedar_policy_validator::Reference);
+
+impl Reference {
+    /// The policy or template containing the reference
+    pub fn policy_id(&self) -> &PolicyId {
+        PolicyId::ref_cast(&self.0.policy_id)
+    }
+
+    /// The referring text. For an entity type, this is its name; for an
+    /// action, the entity reference `Action::"id"`; for an attribute, its name
+    /// in the access, or the whole access expression if the name could not be
+    /// located in it.
+    pub fn span(&self) -> miette::SourceSpan {
+        self.0.span
+    }
+
+    /// Whether the reference certainly refers to the schema element
+    pub fn kind(&self) -> ReferenceKind {
+        self.0.kind
     }
 }
 
@@ -1439,6 +3232,30 @@ impl SchemaFragment {
         ))
     }
 
+    /// Parse a [`SchemaFragment`] from a string containing the Cedar schema
+    /// syntax, turning comments on their own line(s) directly before a
+    /// namespace, declaration, or attribute into its `@doc` annotation, unless
+    /// it already has one.
+    ///
+    /// Together with [`SchemaFragment::to_cedarschema_with_doc_comments`], this
+    /// lets comments survive converting a schema to JSON and back.
+    pub fn from_cedarschema_str_with_doc_comments(
+        src: &str,
+    ) -> Result<(Self, impl Iterator<Item = SchemaWarning>), CedarSchemaError> {
+        let (lossless, warnings) =
+            cedar_policy_validator::json_schema::Fragment::from_cedarschema_str_with_doc_comments(
+                src,
+                Extensions::all_available(),
+            )?;
+        Ok((
+            Self {
+                value: lossless.clone().try_into()?,
+                lossless,
+            },
+            warnings,
+        ))
+    }
+
     /// Create a [`SchemaFragment`] directly from a JSON file (which should
     /// contain an object of the shape required for the JSON schema format).
     pub fn from_json_file(file: impl std::io::Read) -> Result<Self, SchemaError> {
@@ -1465,6 +3282,13 @@ impl SchemaFragment {
         let str = self.lossless.to_cedarschema()?;
         Ok(str)
     }
+
+    /// Serialize this [`SchemaFragment`] into a string in the Cedar schema
+    /// syntax, writing `@doc` annotations as `//` comments
+    pub fn to_cedarschema_with_doc_comments(&self) -> Result<String, ToCedarSchemaError> {
+        let str = self.lossless.to_cedarschema_with_doc_comments()?;
+        Ok(str)
+    }
 }
 
 impl TryInto<Schema> for SchemaFragment {
@@ -1515,6 +3339,13 @@ impl FromStr for Schema {
 }
 
 impl Schema {
+    /// Estimate the heap memory used by this schema, broken down into entity
+    /// types, actions, common types, and the entity type and action
+    /// hierarchies
+    pub fn memory_usage(&self) -> SchemaMemoryUsage {
+        SchemaMemoryUsage::new(&self.0)
+    }
+
     /// Create a [`Schema`] from multiple [`SchemaFragment`]. The individual
     /// fragments may reference entity or common types that are not declared in that
     /// fragment, but all referenced entity and common types must be declared in some
@@ -1674,35 +3505,283 @@ impl Schema {
             .map(|iter| iter.map(RefCast::ref_cast))
     }
 
-    /// Returns an iterator over all the entity types that can be an ancestor of `ty`
+    /// Returns an iterator over all the entity types that can be an ancestor of `ty`
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`None`] if the `ty` is not found in the schema
+    pub fn ancestors<'a>(
+        &'a self,
+        ty: &'a EntityTypeName,
+    ) -> Option<impl Iterator<Item = &'a EntityTypeName> + 'a> {
+        self.0
+            .ancestors(&ty.0)
+            .map(|iter| iter.map(RefCast::ref_cast))
+    }
+
+    /// Returns an iterator over all the action groups defined in this schema
+    pub fn action_groups(&self) -> impl Iterator<Item = &EntityUid> {
+        self.0.action_groups().map(RefCast::ref_cast)
+    }
+
+    /// Returns an iterator over all the action groups that `action` is a
+    /// member of, directly or transitively
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`None`] if `action` is not found in the schema
+    ///
+    /// # Examples
+    /// ```
+    /// use std::collections::HashSet;
+    /// use cedar_policy::{EntityUid, Schema};
+    /// let schema : Schema = r#"
+    ///     action read;
+    ///     action write in read;
+    ///     action delete in write;
+    /// "#.parse().unwrap();
+    /// let delete: EntityUid = r#"Action::"delete""#.parse().unwrap();
+    /// let groups = schema.action_ancestors(&delete).unwrap().map(ToString::to_string).collect::<HashSet<_>>();
+    /// assert_eq!(groups, HashSet::from([r#"Action::"read""#.to_string(), r#"Action::"write""#.to_string()]));
+    /// ```
+    pub fn action_ancestors(&self, action: &EntityUid) -> Option<impl Iterator<Item = &EntityUid>> {
+        self.0
+            .action_ancestors(&action.0)
+            .map(|iter| iter.map(RefCast::ref_cast))
+    }
+
+    /// Returns an iterator over all the actions that are members of the
+    /// action group `group`, directly or transitively
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`None`] if `group` is not found in the schema
+    pub fn action_descendants(
+        &self,
+        group: &EntityUid,
+    ) -> Option<impl Iterator<Item = &EntityUid>> {
+        self.0
+            .action_descendants(&group.0)
+            .map(|iter| iter.map(RefCast::ref_cast))
+    }
+
+    /// Returns an iterator over all entity types defined in this schema
+    pub fn entity_types(&self) -> impl Iterator<Item = &EntityTypeName> {
+        self.0
+            .entity_types()
+            .map(|(name, _)| RefCast::ref_cast(name))
+    }
+
+    /// Returns an iterator over all actions defined in this schema
+    pub fn actions(&self) -> impl Iterator<Item = &EntityUid> {
+        self.0.actions().map(RefCast::ref_cast)
+    }
+
+    /// Returns the attributes declared for entity type `ty`, with their types
+    /// and whether they are required
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`None`] if `ty` is not found in the schema
+    ///
+    /// # Examples
+    /// ```
+    /// use cedar_policy::{Schema, SchemaType};
+    /// let schema : Schema = r#"
+    ///     entity User { name: String, manager?: User };
+    /// "#.parse().unwrap();
+    /// let attrs = schema.entity_type_attributes(&"User".parse().unwrap()).unwrap();
+    /// assert_eq!(attrs["name"].ty(), &SchemaType::String);
+    /// assert!(!attrs["manager"].is_required());
+    /// ```
+    pub fn entity_type_attributes(
+        &self,
+        ty: &EntityTypeName,
+    ) -> Option<BTreeMap<String, SchemaAttribute>> {
+        self.0.get_entity_type(&ty.0).map(|ety| {
+            ety.attributes()
+                .map(|(name, attr)| (name.to_string(), SchemaAttribute::from_attribute_type(attr)))
+                .collect()
+        })
+    }
+
+    /// Returns the type of the tags declared for entity type `ty`
     ///
     /// ## Errors
     ///
-    /// Returns [`None`] if the `ty` is not found in the schema
-    pub fn ancestors<'a>(
-        &'a self,
-        ty: &'a EntityTypeName,
-    ) -> Option<impl Iterator<Item = &'a EntityTypeName> + 'a> {
+    /// Returns [`None`] if `ty` is not found in the schema, or does not have tags
+    pub fn entity_type_tags(&self, ty: &EntityTypeName) -> Option<SchemaType> {
         self.0
-            .ancestors(&ty.0)
-            .map(|iter| iter.map(RefCast::ref_cast))
+            .get_entity_type(&ty.0)
+            .and_then(|ety| ety.tag_type())
+            .map(SchemaType::from_type)
     }
 
-    /// Returns an iterator over all the action groups defined in this schema
-    pub fn action_groups(&self) -> impl Iterator<Item = &EntityUid> {
-        self.0.action_groups().map(RefCast::ref_cast)
+    /// Returns the type of the context for `action`. This is always a
+    /// [`SchemaType::Record`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`None`] if `action` is not found in the schema
+    pub fn action_context(&self, action: &EntityUid) -> Option<SchemaType> {
+        self.0.context_type(&action.0).map(SchemaType::from_type)
     }
 
-    /// Returns an iterator over all entity types defined in this schema
-    pub fn entity_types(&self) -> impl Iterator<Item = &EntityTypeName> {
+    /// Returns an iterator over the common types declared in this schema, with
+    /// their fully-qualified names. References to other common types are fully
+    /// expanded.
+    pub fn common_types(&self) -> impl Iterator<Item = (String, SchemaType)> + '_ {
         self.0
-            .entity_types()
-            .map(|(name, _)| RefCast::ref_cast(name))
+            .common_types()
+            .map(|(name, ty)| (name.to_string(), SchemaType::from_type(ty)))
     }
+}
 
-    /// Returns an iterator over all actions defined in this schema
-    pub fn actions(&self) -> impl Iterator<Item = &EntityUid> {
-        self.0.actions().map(RefCast::ref_cast)
+/// The type of an attribute, context, tag, or common type declared in a
+/// [`Schema`]. References to common types are fully expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaType {
+    /// Boolean
+    Bool,
+    /// Long (64-bit signed integer)
+    Long,
+    /// String
+    String,
+    /// Set whose elements have the given type
+    Set(Box<Self>),
+    /// Record with the given attributes
+    Record {
+        /// Declared attributes of the record
+        attributes: BTreeMap<String, SchemaAttribute>,
+        /// Whether values of this type may have attributes other than those
+        /// declared
+        additional_attributes: bool,
+    },
+    /// Entity of the given type
+    Entity(EntityTypeName),
+    /// Extension type, e.g., `decimal` or `ipaddr`
+    Extension(String),
+}
+
+impl SchemaType {
+    fn from_type(ty: &cedar_policy_validator::types::Type) -> Self {
+        // PANIC SAFETY: types declared in a schema are always fully specified,
+        // refer to exactly one entity type, and never refer to actions
+        #[allow(clippy::unreachable)]
+        Self::try_from_type(ty).unwrap_or_else(|| unreachable!("unexpected type in schema: {ty:?}"))
+    }
+
+    /// Convert the type `ty` inferred by the typechecker, returning `None` if
+    /// it cannot be declared in a schema
+    fn try_from_type(ty: &cedar_policy_validator::types::Type) -> Option<Self> {
+        use cedar_policy_validator::types::{EntityRecordKind, OpenTag, Primitive, Type};
+        match ty {
+            Type::True | Type::False => Some(Self::Bool),
+            Type::Primitive { primitive_type } => Some(match primitive_type {
+                Primitive::Bool => Self::Bool,
+                Primitive::Long => Self::Long,
+                Primitive::String => Self::String,
+            }),
+            Type::Set {
+                element_type: Some(elem),
+            } => Some(Self::Set(Box::new(Self::try_from_type(elem)?))),
+            Type::EntityOrRecord(EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            }) => Some(Self::Record {
+                attributes: attrs
+                    .iter()
+                    .map(|(name, attr)| {
+                        Some((
+                            name.to_string(),
+                            SchemaAttribute::new(
+                                Self::try_from_type(&attr.attr_type)?,
+                                attr.is_required,
+                            ),
+                        ))
+                    })
+                    .collect::<Option<_>>()?,
+                additional_attributes: matches!(open_attributes, OpenTag::OpenAttributes),
+            }),
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => lub
+                .get_single_entity()
+                .map(|ety| Self::Entity(EntityTypeName(ety.clone()))),
+            Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. }) => {
+                Some(Self::Entity(EntityTypeName(name.clone())))
+            }
+            Type::ExtensionType { name } => Some(Self::Extension(name.to_string())),
+            Type::Never
+            | Type::Set { element_type: None }
+            | Type::EntityOrRecord(EntityRecordKind::AnyEntity) => None,
+        }
+    }
+
+    /// Convert to the typechecker's representation of this type
+    fn to_type(&self) -> Result<cedar_policy_validator::types::Type, ExpressionTypecheckError> {
+        use cedar_policy_validator::types::{AttributeType, OpenTag, Type};
+        Ok(match self {
+            Self::Bool => Type::primitive_boolean(),
+            Self::Long => Type::primitive_long(),
+            Self::String => Type::primitive_string(),
+            Self::Set(elem) => Type::set(elem.to_type()?),
+            Self::Record {
+                attributes,
+                additional_attributes,
+            } => Type::record_with_attributes(
+                attributes
+                    .iter()
+                    .map(|(name, attr)| {
+                        Ok((
+                            name.into(),
+                            AttributeType::new(attr.ty.to_type()?, attr.required),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                if *additional_attributes {
+                    OpenTag::OpenAttributes
+                } else {
+                    OpenTag::ClosedAttributes
+                },
+            ),
+            Self::Entity(name) => Type::named_entity_reference(name.0.clone()),
+            Self::Extension(name) => Type::extension(
+                Extensions::all_available()
+                    .ext_types()
+                    .find(|ext| ext.to_string() == *name)
+                    .ok_or_else(|| ExpressionTypecheckError::UnknownExtensionType(name.clone()))?
+                    .clone(),
+            ),
+        })
+    }
+}
+
+/// An attribute of an entity type or record type declared in a [`Schema`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaAttribute {
+    ty: SchemaType,
+    required: bool,
+}
+
+impl SchemaAttribute {
+    /// Create an attribute with the given type, which may be required or
+    /// optional
+    pub fn new(ty: SchemaType, required: bool) -> Self {
+        Self { ty, required }
+    }
+
+    fn from_attribute_type(attr: &cedar_policy_validator::types::AttributeType) -> Self {
+        Self::new(SchemaType::from_type(&attr.attr_type), attr.is_required)
+    }
+
+    /// Type of the attribute
+    pub fn ty(&self) -> &SchemaType {
+        &self.ty
+    }
+
+    /// Whether the attribute is required, as opposed to optional
+    pub fn is_required(&self) -> bool {
+        self.required
     }
 }
 
@@ -1722,9 +3801,19 @@ impl Protobuf for Schema {
 /// Validation succeeds if there are no fatal errors. There may still be
 /// non-fatal warnings present when validation passes.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ValidationResult {
     validation_errors: Vec<ValidationError>,
     validation_warnings: Vec<ValidationWarning>,
+    /// Provenance of the policies with errors or warnings, where known
+    #[cfg_attr(
+        feature = "serde-validation",
+        serde(default, skip_serializing_if = "HashMap::is_empty")
+    )]
+    provenance: HashMap<PolicyId, Provenance>,
 }
 
 impl ValidationResult {
@@ -1751,6 +3840,52 @@ impl ValidationResult {
         self.validation_warnings.iter()
     }
 
+    /// Get where the policy with the given id was deployed from, if it has
+    /// errors or warnings in this result and its provenance is known
+    pub fn provenance(&self, policy_id: &PolicyId) -> Option<&Provenance> {
+        self.provenance.get(policy_id)
+    }
+
+    /// Record the provenance of every policy with errors or warnings, as
+    /// given by `provenance_of`
+    fn with_provenance<'a>(
+        mut self,
+        provenance_of: impl Fn(&PolicyId) -> Option<&'a Provenance>,
+    ) -> Self {
+        let ids = self
+            .validation_errors
+            .iter()
+            .map(ValidationError::policy_id)
+            .chain(
+                self.validation_warnings
+                    .iter()
+                    .map(ValidationWarning::policy_id),
+            );
+        self.provenance = ids
+            .filter_map(|id| Some((id.clone(), provenance_of(id)?.clone())))
+            .collect();
+        self
+    }
+
+    /// Get a copy of this result with literal values in all errors and
+    /// warnings replaced by placeholders (see [`ValidationError::redacted`])
+    #[must_use]
+    pub fn redacted(&self) -> Self {
+        Self {
+            validation_errors: self
+                .validation_errors
+                .iter()
+                .map(ValidationError::redacted)
+                .collect(),
+            validation_warnings: self
+                .validation_warnings
+                .iter()
+                .map(ValidationWarning::redacted)
+                .collect(),
+            provenance: self.provenance.clone(),
+        }
+    }
+
     fn first_error_or_warning(&self) -> Option<&dyn Diagnostic> {
         self.validation_errors
             .first()
@@ -1782,6 +3917,7 @@ impl From<cedar_policy_validator::ValidationResult> for ValidationResult {
         Self {
             validation_errors: errors.map(ValidationError::from).collect(),
             validation_warnings: warnings.map(ValidationWarning::from).collect(),
+            provenance: HashMap::new(),
         }
     }
 }
@@ -1921,15 +4057,19 @@ impl Protobuf for EntityNamespace {
 }
 
 /// Represents a set of `Policy`s
+///
+/// Cloning a `PolicySet` is cheap, since the policies are stored behind
+/// `Arc`s; clones can be handed to many threads as snapshots. Modifying a
+/// clone copies its storage first, so other clones are unaffected.
 #[derive(Debug, Clone, Default)]
 pub struct PolicySet {
     /// AST representation. Technically partially redundant with the other fields.
     /// Internally, we ensure that the duplicated information remains consistent.
     pub(crate) ast: ast::PolicySet,
     /// Policies in the set (this includes both static policies and template linked-policies)
-    policies: HashMap<PolicyId, Policy>,
+    policies: Arc<HashMap<PolicyId, Policy>>,
     /// Templates in the set
-    templates: HashMap<PolicyId, Template>,
+    templates: Arc<HashMap<PolicyId, Template>>,
 }
 
 impl PartialEq for PolicySet {
@@ -1950,7 +4090,62 @@ impl FromStr for PolicySet {
     ///
     /// See [`Policy`] for more.
     fn from_str(policies: &str) -> Result<Self, Self::Err> {
-        let (texts, pset) = parser::parse_policyset_and_also_return_policy_text(policies)?;
+        Self::from_str_with_limits(policies, &ResourceLimits::default())
+    }
+}
+
+/// A policy which has expired or will expire soon, as found by
+/// [`PolicySet::expiring_policies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyExpiration(cedar_policy_validator::PolicyExpiration);
+
+impl PolicyExpiration {
+    /// The id of the policy
+    pub fn policy_id(&self) -> &PolicyId {
+        PolicyId::ref_cast(&self.0.policy_id)
+    }
+
+    /// The date in the `@expires` annotation of the policy
+    pub fn expires(&self) -> ExpirationDate {
+        self.0.expires
+    }
+
+    /// Days from [`ExpirationRules::today`] until the policy expires, which is
+    /// negative if it has expired
+    pub fn days_remaining(&self) -> i64 {
+        self.0.days_remaining
+    }
+}
+
+impl PolicySet {
+    /// Like [`PolicySet::from_str`], but returns an error if `policies`, or
+    /// any policy it contains, exceeds the given [`ResourceLimits`]. Use this
+    /// when parsing untrusted policy text.
+    pub fn from_str_with_limits(
+        policies: &str,
+        limits: &ResourceLimits,
+    ) -> Result<Self, ParseErrors> {
+        let (texts, pset) = parser::parse_policyset_with_limits(policies, limits)?;
+        Ok(Self::from_parsed(&texts, pset))
+    }
+
+    /// Like [`PolicySet::from_str_with_limits`], but the source locations of
+    /// the parsed policies don't retain `policies`, for the smallest memory
+    /// footprint (e.g., when running the authorizer in lambdas or WASM edge
+    /// workers). Errors and warnings about the policies still carry their
+    /// labeled spans, but without source snippets. Errors from parsing
+    /// `policies` itself are reported with snippets as usual.
+    pub fn from_str_without_source(
+        policies: &str,
+        limits: &ResourceLimits,
+    ) -> Result<Self, ParseErrors> {
+        let (texts, pset) = parser::parse_policyset_without_source(policies, limits)?;
+        Ok(Self::from_parsed(&texts, pset))
+    }
+
+    /// Build a [`PolicySet`] from `pset`, parsed from policy text, and the
+    /// `texts` of its policies and templates
+    fn from_parsed(texts: &HashMap<ast::PolicyID, &str>, pset: ast::PolicySet) -> Self {
         // PANIC SAFETY: By the invariant on `parse_policyset_and_also_return_policy_text(policies)`, every `PolicyId` in `pset.policies()` occurs as a key in `text`.
         #[allow(clippy::expect_used)]
         let policies = pset.policies().map(|p|
@@ -1967,15 +4162,159 @@ impl FromStr for PolicySet {
                 Template { lossless: LosslessPolicy::policy_or_template_text(*texts.get(t.id()).expect("internal invariant violation: template id exists in asts but not ests")), ast: t.clone() }
             )
         ).collect();
-        Ok(Self {
+        Self {
             ast: pset,
-            policies,
-            templates,
-        })
+            policies: Arc::new(policies),
+            templates: Arc::new(templates),
+        }
+    }
+
+    /// Like [`PolicySet::from_str`], but record that every policy and
+    /// template was deployed from `provenance`, with the line on which each
+    /// one starts in `policies` filled in.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicyId, PolicySet, Provenance};
+    /// let src = "permit(principal, action, resource);\nforbid(principal, action, resource);";
+    /// let pset = PolicySet::from_str_with_provenance(
+    ///     src,
+    ///     Provenance::new().with_file("policies.cedar").with_revision("3f2a9c1"),
+    /// )
+    /// .unwrap();
+    /// let provenance = pset.provenance(&PolicyId::new("policy1")).unwrap();
+    /// assert_eq!(provenance.to_string(), "policies.cedar:2 @ 3f2a9c1");
+    /// ```
+    pub fn from_str_with_provenance(
+        policies: &str,
+        provenance: Provenance,
+    ) -> Result<Self, ParseErrors> {
+        let pset = Self::from_str(policies)?;
+        let at_line = move |loc: Option<&parser::Loc>| match (provenance.line(), loc) {
+            (None, Some(loc)) => {
+                let line = policies
+                    .get(..loc.start())
+                    .map_or(0, |before| before.matches('\n').count());
+                provenance
+                    .clone()
+                    .with_line(u32::try_from(line + 1).unwrap_or(u32::MAX))
+            }
+            _ => provenance.clone(),
+        };
+        let mut stamped = Self::new();
+        // Parsing never produces template-linked policies, so every policy is
+        // static and every template can be added on its own
+        for t in pset.templates() {
+            // PANIC SAFETY: the template ids are unique in `pset`, and so in `stamped`
+            #[allow(clippy::expect_used)]
+            stamped
+                .add_template(t.with_provenance(at_line(t.ast.loc())))
+                .expect("internal invariant violation: duplicate template id");
+        }
+        for p in pset.policies() {
+            // PANIC SAFETY: the policy ids are unique in `pset`, and so in `stamped`
+            #[allow(clippy::expect_used)]
+            stamped
+                .add(p.with_provenance(at_line(p.ast.loc())))
+                .expect("internal invariant violation: duplicate policy id");
+        }
+        Ok(stamped)
+    }
+
+    /// Parse several policy texts on multiple threads and merge them into a
+    /// single policy set. The result doesn't depend on how the work was
+    /// split between threads: policies and templates get the ids `policy0`,
+    /// `policy1`, and so on, in order of `sources` and of their position
+    /// within each source, exactly as if the sources were concatenated and
+    /// parsed with [`PolicySet::from_str`]. If any source fails to parse, the
+    /// errors from every failing source are returned together, in order of
+    /// `sources`.
+    ///
+    /// ```
+    /// # use cedar_policy::{PolicyId, PolicySet};
+    /// let sources = [
+    ///     "permit(principal, action, resource);",
+    ///     "forbid(principal, action, resource);\npermit(principal == ?principal, action, resource);",
+    /// ];
+    /// let pset = PolicySet::from_strs_parallel(sources).unwrap();
+    /// assert!(pset.policy(&PolicyId::new("policy1")).is_some());
+    /// assert!(pset.template(&PolicyId::new("policy2")).is_some());
+    /// ```
+    pub fn from_strs_parallel<'a>(
+        sources: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, ParseErrors> {
+        let sources = sources.into_iter().collect::<Vec<_>>();
+        let threads = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+        let chunk_size = sources.len().div_ceil(threads).max(1);
+        let parsed = std::thread::scope(|scope| {
+            // Spawn every thread before joining any of them
+            #[allow(clippy::needless_collect)]
+            let handles = sources
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|src| Self::from_str(src))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut merged = Self::new();
+        let mut errors: Option<ParseErrors> = None;
+        let mut next_id = 0;
+        for result in parsed {
+            let pset = match result {
+                Ok(pset) => pset,
+                Err(errs) => {
+                    match &mut errors {
+                        Some(all) => all.append(errs),
+                        None => errors = Some(errs),
+                    }
+                    continue;
+                }
+            };
+            for id in pset.ids_in_source_order() {
+                let new_id = PolicyId::new(format!("policy{next_id}"));
+                next_id += 1;
+                // PANIC SAFETY: every id in `merged` was generated by this loop, so `new_id` is fresh
+                #[allow(clippy::expect_used)]
+                match (pset.template(id), pset.policy(id)) {
+                    (Some(t), _) => merged.add_template(t.new_id(new_id)),
+                    (None, Some(p)) => merged.add(p.new_id(new_id)),
+                    (None, None) => continue,
+                }
+                .expect("internal invariant violation: duplicate generated policy id");
+            }
+        }
+        errors.map_or(Ok(merged), Err)
+    }
+
+    /// The ids of the policies and templates in this parsed policy set, in
+    /// order of position in the source, which is the order in which
+    /// [`PolicySet::from_str`] numbered them
+    pub(crate) fn ids_in_source_order(&self) -> Vec<&PolicyId> {
+        let mut ids = self
+            .templates()
+            .map(|t| (t.ast.loc().map_or(0, parser::Loc::start), t.id()))
+            .chain(
+                self.policies()
+                    .map(|p| (p.ast.loc().map_or(0, parser::Loc::start), p.id())),
+            )
+            .collect::<Vec<_>>();
+        ids.sort_unstable_by_key(|(start, _)| *start);
+        ids.into_iter().map(|(_, id)| id).collect()
     }
-}
 
-impl PolicySet {
     /// Build the policy set AST from the EST
     fn from_est(est: &est::PolicySet) -> Result<Self, PolicySetError> {
         let ast: ast::PolicySet = est.clone().try_into()?;
@@ -2013,8 +4352,8 @@ impl PolicySet {
             .collect();
         Ok(Self {
             ast,
-            policies,
-            templates,
+            policies: Arc::new(policies),
+            templates: Arc::new(templates),
         })
     }
 
@@ -2056,10 +4395,9 @@ impl PolicySet {
     /// Get the EST representation of the [`PolicySet`]
     fn est(self) -> Result<est::PolicySet, PolicyToJsonError> {
         let (static_policies, template_links): (Vec<_>, Vec<_>) =
-            fold_partition(self.policies, is_static_or_link)?;
+            fold_partition(Arc::unwrap_or_clone(self.policies), is_static_or_link)?;
         let static_policies = static_policies.into_iter().collect::<HashMap<_, _>>();
-        let templates = self
-            .templates
+        let templates = Arc::unwrap_or_clone(self.templates)
             .into_iter()
             .map(|(id, template)| template.lossless.est().map(|est| (id.into(), est)))
             .collect::<Result<HashMap<_, _>, _>>()?;
@@ -2076,8 +4414,8 @@ impl PolicySet {
     pub fn new() -> Self {
         Self {
             ast: ast::PolicySet::new(),
-            policies: HashMap::new(),
-            templates: HashMap::new(),
+            policies: Arc::new(HashMap::new()),
+            templates: Arc::new(HashMap::new()),
         }
     }
 
@@ -2099,7 +4437,7 @@ impl PolicySet {
         if policy.is_static() {
             let id = PolicyId::new(policy.ast.id().clone());
             self.ast.add(policy.ast.clone())?;
-            self.policies.insert(id, policy);
+            Arc::make_mut(&mut self.policies).insert(id, policy);
             Ok(())
         } else {
             Err(PolicySetError::ExpectedStatic(
@@ -2112,31 +4450,31 @@ impl PolicySet {
     ///
     /// This will error if the policy is not a static policy.
     pub fn remove_static(&mut self, policy_id: PolicyId) -> Result<Policy, PolicySetError> {
-        let Some(policy) = self.policies.remove(&policy_id) else {
+        // Look the policy up before `Arc::make_mut`, so that a failed removal
+        // doesn't copy the map
+        if !self.policies.contains_key(&policy_id)
+            || self
+                .ast
+                .remove_static(&ast::PolicyID::from_string(&policy_id))
+                .is_err()
+        {
             return Err(PolicySetError::PolicyNonexistent(
                 policy_set_errors::PolicyNonexistentError { policy_id },
             ));
-        };
-        if self
-            .ast
-            .remove_static(&ast::PolicyID::from_string(&policy_id))
-            .is_ok()
-        {
-            Ok(policy)
-        } else {
-            //Restore self.policies
-            self.policies.insert(policy_id.clone(), policy);
-            Err(PolicySetError::PolicyNonexistent(
-                policy_set_errors::PolicyNonexistentError { policy_id },
-            ))
         }
+        // PANIC SAFETY: we just checked that `policy_id` is in `self.policies`
+        #[allow(clippy::expect_used)]
+        let policy = Arc::make_mut(&mut self.policies)
+            .remove(&policy_id)
+            .expect("policy should be in self.policies");
+        Ok(policy)
     }
 
     /// Add a `Template` to the `PolicySet`
     pub fn add_template(&mut self, template: Template) -> Result<(), PolicySetError> {
         let id = PolicyId::new(template.ast.id().clone());
         self.ast.add_template(template.ast.clone())?;
-        self.templates.insert(id, template);
+        Arc::make_mut(&mut self.templates).insert(id, template);
         Ok(())
     }
 
@@ -2145,11 +4483,13 @@ impl PolicySet {
     /// This will error if any policy is linked to the template.
     /// This will error if `policy_id` is not a template.
     pub fn remove_template(&mut self, template_id: PolicyId) -> Result<Template, PolicySetError> {
-        let Some(template) = self.templates.remove(&template_id) else {
+        // Look the template up before `Arc::make_mut`, so that a failed
+        // removal doesn't copy the map
+        if !self.templates.contains_key(&template_id) {
             return Err(PolicySetError::TemplateNonexistent(
                 policy_set_errors::TemplateNonexistentError { template_id },
             ));
-        };
+        }
         // If self.templates and self.ast disagree, authorization cannot be trusted.
         // PANIC SAFETY: We just found the policy in self.templates.
         #[allow(clippy::panic)]
@@ -2157,23 +4497,27 @@ impl PolicySet {
             .ast
             .remove_template(&ast::PolicyID::from_string(&template_id))
         {
-            Ok(_) => Ok(template),
+            Ok(_) => {}
             Err(ast::PolicySetTemplateRemovalError::RemoveTemplateWithLinksError(_)) => {
-                self.templates.insert(template_id.clone(), template);
-                Err(PolicySetError::RemoveTemplateWithActiveLinks(
+                return Err(PolicySetError::RemoveTemplateWithActiveLinks(
                     policy_set_errors::RemoveTemplateWithActiveLinksError { template_id },
-                ))
+                ));
             }
             Err(ast::PolicySetTemplateRemovalError::NotTemplateError(_)) => {
-                self.templates.insert(template_id.clone(), template);
-                Err(PolicySetError::RemoveTemplateNotTemplate(
+                return Err(PolicySetError::RemoveTemplateNotTemplate(
                     policy_set_errors::RemoveTemplateNotTemplateError { template_id },
-                ))
+                ));
             }
             Err(ast::PolicySetTemplateRemovalError::RemovePolicyNoTemplateError(_)) => {
                 panic!("Found template policy in self.templates but not in self.ast");
             }
         }
+        // PANIC SAFETY: we just checked that `template_id` is in `self.templates`
+        #[allow(clippy::expect_used)]
+        let template = Arc::make_mut(&mut self.templates)
+            .remove(&template_id)
+            .expect("template should be in self.templates");
+        Ok(template)
     }
 
     /// Get policies linked to a `Template` in the `PolicySet`.
@@ -2206,6 +4550,55 @@ impl PolicySet {
         self.templates.values()
     }
 
+    /// Get aggregate complexity metrics for the static policies and templates
+    /// in this `PolicySet`, e.g., to enforce complexity budgets before
+    /// deploying it.
+    pub fn metrics(&self) -> PolicySetMetrics {
+        PolicySetMetrics::new(&self.ast)
+    }
+
+    /// Get a copy of this `PolicySet` with the constant sub-expressions in the
+    /// conditions of every policy and template folded into their values,
+    /// e.g., `1 + 2 * 3` becomes `7` and `context.n > 60 * 60` becomes
+    /// `context.n > 3600`.
+    ///
+    /// `&&` and `||` expressions whose left operand is a constant boolean
+    /// that decides them, and `if` expressions with a constant condition, are
+    /// replaced by the operand that would be evaluated. For instance, a policy
+    /// with a `when { false }` clause has its condition folded to `false`.
+    /// Sub-expressions that read the request or entities, or whose evaluation
+    /// fails, are kept as they are, so the folded policies produce the same
+    /// authorization responses as the originals, and may be evaluated
+    /// (or exported) faster.
+    ///
+    /// The folded policies are displayed and serialized from their folded
+    /// form rather than from their original text.
+    #[must_use]
+    pub fn fold_constants(&self) -> Self {
+        let ast = self.ast.fold_constants();
+        let policies = ast
+            .policies()
+            .map(|p| (PolicyId::new(p.id().clone()), Policy::from_ast(p.clone())))
+            .collect();
+        let templates = ast
+            .templates()
+            .map(|t| (PolicyId::new(t.id().clone()), Template::from_ast(t.clone())))
+            .collect();
+        Self {
+            ast,
+            policies: Arc::new(policies),
+            templates: Arc::new(templates),
+        }
+    }
+
+    /// Estimate the heap memory used by the policies, annotations, and
+    /// template links in this `PolicySet`, e.g., to budget memory per tenant.
+    /// The estimate covers the internal representation used for evaluation,
+    /// not the original policy text.
+    pub fn memory_usage(&self) -> PolicySetMemoryUsage {
+        PolicySetMemoryUsage::new(&self.ast)
+    }
+
     /// Get a `Template` by its `PolicyId`
     pub fn template(&self, id: &PolicyId) -> Option<&Template> {
         self.templates.get(id)
@@ -2216,6 +4609,16 @@ impl PolicySet {
         self.policies.get(id)
     }
 
+    /// Get where the policy or template with the given id was deployed from,
+    /// if known
+    pub fn provenance(&self, id: &PolicyId) -> Option<&Provenance> {
+        let id = id.as_ref();
+        match self.ast.get(id) {
+            Some(p) => p.provenance(),
+            None => self.ast.get_template(id)?.provenance(),
+        }
+    }
+
     /// Extract annotation data from a `Policy` by its `PolicyId` and annotation key.
     /// If the annotation is present without an explicit value (e.g., `@annotation`),
     /// then this function returns `Some("")`. It returns `None` only when the
@@ -2313,7 +4716,7 @@ impl PolicySet {
             // will have already errored if there are any unfilled slots in the
             // template.
             .expect("ast.link() didn't fail above, so this shouldn't fail");
-        self.policies.insert(
+        Arc::make_mut(&mut self.policies).insert(
             new_id,
             Policy {
                 ast: linked_ast.clone(),
@@ -2323,6 +4726,36 @@ impl PolicySet {
         Ok(())
     }
 
+    /// Get the static and template-linked policies in this set which have
+    /// expired, or will expire within [`ExpirationRules::warn_within_days`],
+    /// according to their `@expires` annotation. The results are sorted by
+    /// expiration date and then by policy id. Policies whose annotation is
+    /// not a valid date are skipped; the [`Validator`] reports those when it
+    /// has [`ExpirationRules`].
+    ///
+    /// ```
+    /// # use cedar_policy::{ExpirationRules, PolicySet};
+    /// # use std::str::FromStr;
+    /// let pset = PolicySet::from_str(
+    ///     r#"
+    ///     @expires("2025-01-10") permit(principal, action, resource);
+    ///     @expires("2026-01-01") permit(principal, action, resource);
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// let rules = ExpirationRules::new("2025-01-01".parse().unwrap());
+    /// let expiring = pset.expiring_policies(&rules);
+    /// assert_eq!(expiring.len(), 1);
+    /// assert_eq!(expiring[0].policy_id().to_string(), "policy0");
+    /// assert_eq!(expiring[0].days_remaining(), 9);
+    /// ```
+    pub fn expiring_policies(&self, rules: &ExpirationRules) -> Vec<PolicyExpiration> {
+        cedar_policy_validator::expiring_policies(&self.ast, rules)
+            .into_iter()
+            .map(PolicyExpiration)
+            .collect()
+    }
+
     /// Get all the unknown entities from the policy set
     #[doc = include_str!("../experimental_warning.md")]
     #[cfg(feature = "partial-eval")]
@@ -2337,27 +4770,33 @@ impl PolicySet {
     /// Unlink a template-linked policy from the policy set.
     /// Returns the policy that was unlinked.
     pub fn unlink(&mut self, policy_id: PolicyId) -> Result<Policy, PolicySetError> {
-        let Some(policy) = self.policies.remove(&policy_id) else {
+        // Look the policy up before `Arc::make_mut`, so that a failed removal
+        // doesn't copy the map
+        if !self.policies.contains_key(&policy_id) {
             return Err(PolicySetError::LinkNonexistent(
                 policy_set_errors::LinkNonexistentError { policy_id },
             ));
-        };
+        }
         // If self.policies and self.ast disagree, authorization cannot be trusted.
         // PANIC SAFETY: We just found the policy in self.policies.
         #[allow(clippy::panic)]
         match self.ast.unlink(&ast::PolicyID::from_string(&policy_id)) {
-            Ok(_) => Ok(policy),
+            Ok(_) => {}
             Err(ast::PolicySetUnlinkError::NotLinkError(_)) => {
-                //Restore self.policies
-                self.policies.insert(policy_id.clone(), policy);
-                Err(PolicySetError::UnlinkLinkNotLink(
+                return Err(PolicySetError::UnlinkLinkNotLink(
                     policy_set_errors::UnlinkLinkNotLinkError { policy_id },
-                ))
+                ));
             }
             Err(ast::PolicySetUnlinkError::UnlinkingError(_)) => {
                 panic!("Found linked policy in self.policies but not in self.ast")
             }
         }
+        // PANIC SAFETY: we just checked that `policy_id` is in `self.policies`
+        #[allow(clippy::expect_used)]
+        let policy = Arc::make_mut(&mut self.policies)
+            .remove(&policy_id)
+            .expect("policy should be in self.policies");
+        Ok(policy)
     }
 }
 
@@ -2462,6 +4901,113 @@ impl RequestEnv {
     }
 }
 
+/// The environment in which [`Validator::typecheck_expression`] typechecks an
+/// expression: the types of `principal`, `resource`, and `context`, and the
+/// action, which must be declared in the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionEnv {
+    principal: EntityTypeName,
+    action: EntityUid,
+    resource: EntityTypeName,
+    context: SchemaType,
+}
+
+impl ExpressionEnv {
+    /// Construct an [`ExpressionEnv`]
+    pub fn new(
+        principal: EntityTypeName,
+        action: EntityUid,
+        resource: EntityTypeName,
+        context: SchemaType,
+    ) -> Self {
+        Self {
+            principal,
+            action,
+            resource,
+            context,
+        }
+    }
+
+    /// Construct the [`ExpressionEnv`] of requests of type `env`, with the
+    /// context type declared in `schema` for its action.
+    ///
+    /// Returns [`None`] if the action of `env` is not declared in `schema`.
+    pub fn from_request_env(schema: &Schema, env: &RequestEnv) -> Option<Self> {
+        Some(Self::new(
+            env.principal.clone(),
+            env.action.clone(),
+            env.resource.clone(),
+            schema.action_context(&env.action)?,
+        ))
+    }
+
+    /// Get the principal type name
+    pub fn principal(&self) -> &EntityTypeName {
+        &self.principal
+    }
+
+    /// Get the action [`EntityUid`]
+    pub fn action(&self) -> &EntityUid {
+        &self.action
+    }
+
+    /// Get the resource type name
+    pub fn resource(&self) -> &EntityTypeName {
+        &self.resource
+    }
+
+    /// Get the context type
+    pub fn context(&self) -> &SchemaType {
+        &self.context
+    }
+}
+
+/// The innermost expression at a position in the source of a policy, with the
+/// types inferred for it. See [`Validator::type_at_offset`].
+#[derive(Debug, Clone)]
+pub struct TypeAtOffset {
+    span: miette::SourceSpan,
+    types: Vec<(RequestEnv, String, Option<SchemaType>)>,
+}
+
+impl TypeAtOffset {
+    fn new(at: cedar_policy_validator::TypeAtOffset) -> Self {
+        Self {
+            span: at.loc.span,
+            types: at
+                .types
+                .into_iter()
+                .map(|t| {
+                    (
+                        RequestEnv::new(t.principal.into(), t.action.into(), t.resource.into()),
+                        t.ty.to_string(),
+                        SchemaType::try_from_type(&t.ty),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// The span of the expression in the policy source
+    pub fn span(&self) -> miette::SourceSpan {
+        self.span
+    }
+
+    /// The type of the expression in each request environment where the
+    /// validator could infer one, written as in validation errors
+    pub fn types(&self) -> impl Iterator<Item = (&RequestEnv, &str)> {
+        self.types.iter().map(|(env, ty, _)| (env, ty.as_str()))
+    }
+
+    /// The type of the expression in each request environment where the
+    /// validator could infer one, as a [`SchemaType`]. This is `None` for
+    /// types which cannot be declared in a schema, e.g., unions of entity
+    /// types.
+    pub fn schema_types(&self) -> impl Iterator<Item = (&RequestEnv, Option<&SchemaType>)> {
+        self.types.iter().map(|(env, _, ty)| (env, ty.as_ref()))
+    }
+}
+
 // Get valid request envs
 // This function is called by [`Template::get_valid_request_envs`] and
 // [`Policy::get_valid_request_envs`]
@@ -2551,6 +5097,11 @@ impl Template {
         PolicyId::ref_cast(self.ast.id())
     }
 
+    /// Get complexity metrics for the conditions of this `Template`
+    pub fn metrics(&self) -> PolicyMetrics {
+        PolicyMetrics::new(&self.ast)
+    }
+
     /// Clone this `Template` with a new `PolicyId`
     #[must_use]
     pub fn new_id(&self, id: PolicyId) -> Self {
@@ -2560,6 +5111,21 @@ impl Template {
         }
     }
 
+    /// Clone this `Template`, recording that it was deployed from
+    /// `provenance`. Policies linked from the template share its provenance.
+    #[must_use]
+    pub fn with_provenance(&self, provenance: Provenance) -> Self {
+        Self {
+            ast: self.ast.with_provenance(provenance),
+            lossless: self.lossless.clone(),
+        }
+    }
+
+    /// Get where this `Template` was deployed from, if known
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.ast.provenance()
+    }
+
     /// Get the `Effect` (`Forbid` or `Permit`) of this `Template`
     pub fn effect(&self) -> Effect {
         self.ast.effect()
@@ -2685,7 +5251,6 @@ impl Template {
         })
     }
 
-    #[cfg_attr(not(feature = "protobufs"), allow(dead_code))]
     fn from_ast(ast: ast::Template) -> Self {
         Self {
             lossless: LosslessPolicy::Est(ast.clone().into()),
@@ -2909,6 +5474,12 @@ impl Policy {
         PolicyId::ref_cast(self.ast.id())
     }
 
+    /// Get complexity metrics for the conditions of this policy. For a
+    /// template-linked policy, these are the metrics of its template.
+    pub fn metrics(&self) -> PolicyMetrics {
+        PolicyMetrics::new(self.ast.template())
+    }
+
     /// Clone this `Policy` with a new `PolicyId`
     #[must_use]
     pub fn new_id(&self, id: PolicyId) -> Self {
@@ -2918,6 +5489,31 @@ impl Policy {
         }
     }
 
+    /// Clone this `Policy`, recording that it was deployed from `provenance`.
+    /// The provenance is reported with validation results and authorization
+    /// responses involving the policy.
+    ///
+    /// ```
+    /// # use cedar_policy::{Policy, Provenance};
+    /// let policy = Policy::parse(None, "permit(principal, action, resource);")
+    ///     .unwrap()
+    ///     .with_provenance(Provenance::new().with_file("policies.cedar").with_revision("3f2a9c1"));
+    /// assert_eq!(policy.provenance().unwrap().file(), Some("policies.cedar"));
+    /// ```
+    #[must_use]
+    pub fn with_provenance(&self, provenance: Provenance) -> Self {
+        Self {
+            ast: self.ast.with_provenance(provenance),
+            lossless: self.lossless.clone(),
+        }
+    }
+
+    /// Get where this `Policy` was deployed from, if known. Template-linked
+    /// policies share the provenance of their template.
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.ast.provenance()
+    }
+
     /// Returns `true` if this is a static policy, `false` otherwise.
     pub fn is_static(&self) -> bool {
         self.ast.is_static()
@@ -3207,10 +5803,6 @@ impl Policy {
     /// create the `Policy` from the policy text, CST, or EST instead, as the
     /// conversion to AST is lossy. ESTs for policies generated by this method
     /// will reflect the AST and not the original policy syntax.
-    #[cfg_attr(
-        not(any(feature = "partial-eval", feature = "protobufs")),
-        allow(unused)
-    )]
     pub(crate) fn from_ast(ast: ast::Policy) -> Self {
         let text = ast.to_string(); // assume that pretty-printing is faster than `est::Policy::from(ast.clone())`; is that true?
         Self {
@@ -3425,6 +6017,12 @@ impl FromStr for Expression {
     }
 }
 
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[cfg(feature = "protobufs")]
 impl Protobuf for Expression {
     fn encode(&self) -> Vec<u8> {
@@ -3664,11 +6262,17 @@ impl RequestBuilder<UnsetSchema> {
 impl RequestBuilder<&Schema> {
     /// Create the [`Request`]
     pub fn build(self) -> Result<Request, RequestValidationError> {
+        let context = match (&self.action, self.context) {
+            (ast::EntityUIDEntry::Known { euid, .. }, Some(context)) => {
+                Some(self.schema.0.fill_context_defaults(euid, context))
+            }
+            (_, context) => context,
+        };
         Ok(Request(ast::Request::new_with_unknowns(
             self.principal,
             self.action,
             self.resource,
-            self.context,
+            context,
             Some(&self.schema.0),
             Extensions::all_available(),
         )?))
@@ -3706,7 +6310,8 @@ impl Request {
     /// a unique entity UID that is not equal to any UID in the store.
     ///
     /// If `schema` is present, this constructor will validate that the
-    /// `Request` complies with the given `schema`.
+    /// `Request` complies with the given `schema`. Any context attributes
+    /// the schema declares a `@default` for are filled in first, if missing.
     pub fn new(
         principal: EntityUid,
         action: EntityUid,
@@ -3714,11 +6319,15 @@ impl Request {
         context: Context,
         schema: Option<&Schema>,
     ) -> Result<Self, RequestValidationError> {
+        let context = match schema {
+            Some(schema) => schema.0.fill_context_defaults(action.as_ref(), context.0),
+            None => context.0,
+        };
         Ok(Self(ast::Request::new(
             (principal.into(), None),
             (action.into(), None),
             (resource.into(), None),
-            context.0,
+            context,
             schema.map(|schema| &schema.0),
             Extensions::all_available(),
         )?))
@@ -4003,7 +6612,8 @@ impl Context {
 
     /// Merge this [`Context`] with another context (or iterator over
     /// `(String, RestrictedExpression)` pairs), returning an error if the two
-    /// contain overlapping keys
+    /// contain overlapping keys. To merge contexts where some take precedence
+    /// over others, see [`ContextLayers`].
     pub fn merge(
         self,
         other_context: impl IntoIterator<Item = (String, RestrictedExpression)>,
@@ -4202,10 +6812,40 @@ impl std::fmt::Display for EvalResult {
     }
 }
 
-/// Evaluates an expression.
+/// Evaluates an expression of any type outside of a policy.
+///
+/// `principal`, `action`, `resource`, and `context` are bound to the
+/// components of `request`, and entities are looked up in `entities`, so this
+/// can be used to preview the value of an expression.
 ///
 /// If evaluation results in an error (e.g., attempting to access a non-existent Entity or Record,
-/// passing the wrong number of arguments to a function etc.), that error is returned as a String
+/// passing the wrong number of arguments to a function etc.), that error is returned as an
+/// [`EvaluationError`].
+///
+/// ```
+/// # use cedar_policy::{eval_expression, Context, Entities, EvalResult, Expression, Request};
+/// let entities = Entities::from_json_str(
+///     r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 19 }, "parents": [] }]"#,
+///     None,
+/// )
+/// .unwrap();
+/// let request = Request::new(
+///     r#"User::"alice""#.parse().unwrap(),
+///     r#"Action::"view""#.parse().unwrap(),
+///     r#"Photo::"trip.jpg""#.parse().unwrap(),
+///     Context::from_pairs([("level".to_string(), "3".parse().unwrap())]).unwrap(),
+///     None,
+/// )
+/// .unwrap();
+/// let expr: Expression = "principal.age + context.level".parse().unwrap();
+/// assert_eq!(
+///     eval_expression(&request, &entities, &expr).unwrap(),
+///     EvalResult::Long(22)
+/// );
+///
+/// let expr: Expression = "resource.owner".parse().unwrap();
+/// assert!(eval_expression(&request, &entities, &expr).is_err());
+/// ```
 pub fn eval_expression(
     request: &Request,
     entities: &Entities,
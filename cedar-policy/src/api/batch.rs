@@ -0,0 +1,394 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Authorizing every request in an Arrow record batch
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use arrow_array::{BooleanArray, RecordBatch};
+use cedar_policy_core::entities::arrow::array_to_json;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use super::{
+    ActionConstraint, Authorizer, Context, Decision, Entities, EntityId, EntityTypeName, EntityUid,
+    PolicySet, Request, Schema,
+};
+
+/// Mapping from the columns of a record batch to the components of the
+/// request in each row, for [`Authorizer::is_authorized_batch`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct RequestColumns {
+    /// Column holding the principal of each request
+    pub principal: String,
+    /// Entity type of every principal. If set, the principal column holds
+    /// entity ids, and otherwise it holds entity uids like `User::"alice"`.
+    #[serde(default)]
+    pub principal_type: Option<String>,
+    /// Column holding the action of each request
+    pub action: String,
+    /// Entity type of every action, like `principal_type`
+    #[serde(default)]
+    pub action_type: Option<String>,
+    /// Column holding the resource of each request
+    pub resource: String,
+    /// Entity type of every resource, like `principal_type`
+    #[serde(default)]
+    pub resource_type: Option<String>,
+    /// Map from the names of columns to the context attributes they hold.
+    /// Null values are left out of the context.
+    #[serde(default)]
+    pub context: BTreeMap<String, String>,
+}
+
+impl RequestColumns {
+    /// Map the columns `principal`, `action`, and `resource` to the
+    /// components of the request, with an empty context
+    pub fn new(
+        principal: impl Into<String>,
+        action: impl Into<String>,
+        resource: impl Into<String>,
+    ) -> Self {
+        Self {
+            principal: principal.into(),
+            principal_type: None,
+            action: action.into(),
+            action_type: None,
+            resource: resource.into(),
+            resource_type: None,
+            context: BTreeMap::new(),
+        }
+    }
+}
+
+/// Error when a record batch as a whole cannot be authorized, because a
+/// column of its [`RequestColumns`] is missing or cannot be read
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+#[error("column `{column}`: {message}")]
+pub struct BatchColumnError {
+    /// The column with the error
+    column: SmolStr,
+    /// What went wrong
+    message: String,
+}
+
+impl BatchColumnError {
+    /// The column with the error
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// What went wrong
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Error when the request in one row of a record batch is not valid, for
+/// instance because an entity uid does not parse or the context does not
+/// conform to the schema
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+#[error("row {row}: {message}")]
+pub struct BatchRequestError {
+    /// The row with the error, where the first row of the batch is row 1
+    row: usize,
+    /// What went wrong
+    message: String,
+}
+
+impl BatchRequestError {
+    /// The row with the error, where the first row of the batch is row 1
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// What went wrong
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Decisions for every request in a record batch, as computed by
+/// [`Authorizer::is_authorized_batch`]
+#[derive(Debug, Clone)]
+pub struct BatchDecisions {
+    decisions: BooleanArray,
+    errors: Vec<BatchRequestError>,
+    distinct_requests: usize,
+}
+
+impl BatchDecisions {
+    /// The decision for each row of the batch: `true` for `Allow`, `false`
+    /// for `Deny`, and null if the request in the row is not valid
+    pub fn decisions(&self) -> &BooleanArray {
+        &self.decisions
+    }
+
+    /// Consume the `BatchDecisions`, returning the decision column
+    pub fn into_decisions(self) -> BooleanArray {
+        self.decisions
+    }
+
+    /// The errors for the rows whose requests are not valid, in row order
+    pub fn errors(&self) -> &[BatchRequestError] {
+        &self.errors
+    }
+
+    /// The number of distinct valid requests in the batch, each of which was
+    /// authorized once
+    pub fn distinct_requests(&self) -> usize {
+        self.distinct_requests
+    }
+}
+
+/// Request in one row of a batch, before it is checked against the schema
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RowRequest {
+    principal: EntityUid,
+    action: EntityUid,
+    resource: EntityUid,
+    /// The context, as the text of a JSON object, which identifies equal
+    /// contexts since their attributes are always in the same order
+    context: String,
+}
+
+/// One of the principal, action, and resource columns of a batch
+struct UidColumn<'a> {
+    values: Vec<serde_json::Value>,
+    ty: Option<EntityTypeName>,
+    /// Uids already parsed from the values of the column, by the text of
+    /// their JSON value
+    parsed: HashMap<String, Result<EntityUid, String>>,
+    name: &'a str,
+}
+
+impl<'a> UidColumn<'a> {
+    fn new(
+        batch: &RecordBatch,
+        name: &'a str,
+        ty: Option<&String>,
+    ) -> Result<Self, BatchColumnError> {
+        let ty = ty
+            .map(|ty| EntityTypeName::from_str(ty))
+            .transpose()
+            .map_err(|e| BatchColumnError {
+                column: name.into(),
+                message: format!("invalid entity type: {e}"),
+            })?;
+        Ok(Self {
+            values: read_column(batch, name)?,
+            ty,
+            parsed: HashMap::new(),
+            name,
+        })
+    }
+
+    /// The uid in row `i`, where the first row is row 0
+    fn uid(&mut self, i: usize) -> Result<EntityUid, String> {
+        let value = self.values.get(i).unwrap_or(&serde_json::Value::Null);
+        if value.is_null() {
+            return Err(format!("missing value in column `{}`", self.name));
+        }
+        let ty = &self.ty;
+        let name = self.name;
+        self.parsed
+            .entry(value.to_string())
+            .or_insert_with(|| {
+                match (value, ty) {
+                    (serde_json::Value::String(id), Some(ty)) => Ok(
+                        EntityUid::from_type_name_and_id(ty.clone(), EntityId::new(id)),
+                    ),
+                    (serde_json::Value::String(uid), None) => {
+                        EntityUid::from_str(uid).map_err(|e| e.to_string())
+                    }
+                    (value, _) => EntityUid::from_json(value.clone()).map_err(|e| e.to_string()),
+                }
+                .map_err(|e| format!("invalid entity in column `{name}`: {e}"))
+            })
+            .clone()
+    }
+}
+
+/// Read every value of the column `name` of `batch` as JSON
+fn read_column(
+    batch: &RecordBatch,
+    name: &str,
+) -> Result<Vec<serde_json::Value>, BatchColumnError> {
+    let column = batch.column_by_name(name).ok_or_else(|| BatchColumnError {
+        column: name.into(),
+        message: "no such column in the batch".into(),
+    })?;
+    array_to_json(column).map_err(|e| BatchColumnError {
+        column: name.into(),
+        message: e.to_string(),
+    })
+}
+
+/// Could a policy with the action constraint `constraint` apply to `action`?
+fn applies_to_action(
+    constraint: &ActionConstraint,
+    action: &EntityUid,
+    entities: &Entities,
+) -> bool {
+    match constraint {
+        ActionConstraint::Any => true,
+        ActionConstraint::Eq(uid) => uid == action,
+        ActionConstraint::In(uids) => uids
+            .iter()
+            .any(|uid| uid == action || entities.is_ancestor_of(uid, action)),
+    }
+}
+
+impl Authorizer {
+    /// Authorize the request in every row of the Arrow record batch `batch`,
+    /// whose components are read from the columns given by `columns`.
+    /// Context columns are read like the attribute columns of
+    /// [`Entities::from_record_batches`], and converted to the context types
+    /// declared in `schema` for the action of the row. If a `schema` is
+    /// given, the request in each row must also conform to it.
+    ///
+    /// This is meant for bulk offline jobs, like recertifying the access of
+    /// every principal to every resource, where many rows share components.
+    /// Each column is read once for the whole batch, each distinct entity uid
+    /// is parsed once, and each distinct request is authorized once, against
+    /// only the policies whose action constraint could apply to its action.
+    /// So, context providers and decision loggers are called once for each
+    /// distinct request rather than once for each row.
+    ///
+    /// Returns an error if a column of `columns` is missing from the batch
+    /// or cannot be read. Otherwise, rows whose requests are not valid have a
+    /// null decision, and an error in [`BatchDecisions::errors`].
+    ///
+    /// The batch must be built with the same version of the `arrow-array`
+    /// crate as this crate uses.
+    pub fn is_authorized_batch(
+        &self,
+        batch: &RecordBatch,
+        columns: &RequestColumns,
+        policies: &PolicySet,
+        entities: &Entities,
+        schema: Option<&Schema>,
+    ) -> Result<BatchDecisions, BatchColumnError> {
+        let mut principals =
+            UidColumn::new(batch, &columns.principal, columns.principal_type.as_ref())?;
+        let mut actions = UidColumn::new(batch, &columns.action, columns.action_type.as_ref())?;
+        let mut resources =
+            UidColumn::new(batch, &columns.resource, columns.resource_type.as_ref())?;
+        let context_columns = columns
+            .context
+            .iter()
+            .map(|(column, attr)| Ok((attr, read_column(batch, column)?)))
+            .collect::<Result<Vec<_>, BatchColumnError>>()?;
+
+        // Policies can only be removed from a set of static policies, so
+        // policy sets with templates are not sliced by action
+        let sliceable = policies.num_of_templates() == 0;
+        let mut sliced: HashMap<EntityUid, PolicySet> = HashMap::new();
+        let mut decided: HashMap<RowRequest, Result<bool, String>> = HashMap::new();
+        let mut decisions = Vec::with_capacity(batch.num_rows());
+        let mut errors = Vec::new();
+        for i in 0..batch.num_rows() {
+            let request = principals.uid(i).and_then(|principal| {
+                let context = context_columns
+                    .iter()
+                    .filter_map(|(attr, values)| {
+                        let value = values.get(i)?;
+                        (!value.is_null()).then(|| ((*attr).clone(), value.clone()))
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                let context = serde_json::Value::from(context);
+                let request = RowRequest {
+                    principal,
+                    action: actions.uid(i)?,
+                    resource: resources.uid(i)?,
+                    context: context.to_string(),
+                };
+                Ok((request, context))
+            });
+            let decision = request.and_then(|(request, context)| {
+                if let Some(decision) = decided.get(&request) {
+                    return decision.clone();
+                }
+                let policies = if sliceable {
+                    sliced.entry(request.action.clone()).or_insert_with(|| {
+                        // PANIC SAFETY: the policies are all static policies from a valid policy set
+                        #[allow(clippy::expect_used)]
+                        PolicySet::from_policies(
+                            policies
+                                .policies()
+                                .filter(|p| {
+                                    applies_to_action(
+                                        &p.action_constraint(),
+                                        &request.action,
+                                        entities,
+                                    )
+                                })
+                                .cloned(),
+                        )
+                        .expect("a subset of a valid policy set is a valid policy set")
+                    })
+                } else {
+                    policies
+                };
+                let decision = self.authorize_row(&request, context, policies, entities, schema);
+                decided.insert(request, decision.clone());
+                decision
+            });
+            match decision {
+                Ok(decision) => decisions.push(Some(decision)),
+                Err(message) => {
+                    decisions.push(None);
+                    errors.push(BatchRequestError {
+                        row: i + 1,
+                        message,
+                    });
+                }
+            }
+        }
+        Ok(BatchDecisions {
+            decisions: BooleanArray::from(decisions),
+            errors,
+            distinct_requests: decided.values().filter(|d| d.is_ok()).count(),
+        })
+    }
+
+    /// Authorize the request of one row, returning whether it is allowed
+    fn authorize_row(
+        &self,
+        request: &RowRequest,
+        context: serde_json::Value,
+        policies: &PolicySet,
+        entities: &Entities,
+        schema: Option<&Schema>,
+    ) -> Result<bool, String> {
+        let context = Context::from_json_value(context, schema.map(|s| (s, &request.action)))
+            .map_err(|e| format!("invalid context: {e}"))?;
+        let request = Request::new(
+            request.principal.clone(),
+            request.action.clone(),
+            request.resource.clone(),
+            context,
+            schema,
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(self.is_authorized(&request, policies, entities).decision() == Decision::Allow)
+    }
+}
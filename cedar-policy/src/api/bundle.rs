@@ -0,0 +1,278 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Policy bundles: a policy set and its schema serialized together, so that
+//! they can be signed and distributed as a unit
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::version::{get_lang_version, get_sdk_version};
+use super::{BundleError, BundleSignatureError, PolicySet, Schema, SchemaFragment};
+
+#[cfg(feature = "ed25519")]
+mod ed25519;
+#[cfg(feature = "ed25519")]
+pub use ed25519::*;
+
+/// The current version of the bundle archive format written by
+/// [`PolicyBundle::pack`]
+const FORMAT_VERSION: u32 = 1;
+
+/// Signs the serialized form of a [`PolicyBundle`].
+///
+/// With the `ed25519` feature, `Ed25519Signer` signs with an ed25519 key.
+/// For other signature schemes, or keys held in an HSM or a key management
+/// service, wrap the signing key in an implementation of this trait.
+pub trait BundleSigner {
+    /// Sign `message`, returning the signature
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BundleSignatureError`] if the message could not be signed
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BundleSignatureError>;
+}
+
+/// Verifies signatures created by a [`BundleSigner`], e.g., with
+/// `Ed25519Verifier` when the `ed25519` feature is enabled
+pub trait BundleVerifier {
+    /// Check that `signature` is a valid signature of `message`
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BundleSignatureError`] saying why the signature is not
+    /// valid, e.g., because it is malformed or does not match
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), BundleSignatureError>;
+}
+
+/// A policy set, including its templates and template links, together with
+/// the schema it is meant to be used with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyBundle {
+    /// The policy set, in the JSON policy set format
+    policies: serde_json::Value,
+    /// The schema, in the JSON schema format
+    schema: serde_json::Value,
+}
+
+impl PolicyBundle {
+    /// Bundle `policies` with `schema`
+    pub fn new(policies: PolicySet, schema: SchemaFragment) -> Result<Self, BundleError> {
+        Ok(Self {
+            policies: policies.to_json()?,
+            schema: schema.to_json_value()?,
+        })
+    }
+
+    /// Get the policy set in this bundle
+    pub fn policy_set(&self) -> Result<PolicySet, BundleError> {
+        Ok(PolicySet::from_json_value(self.policies.clone())?)
+    }
+
+    /// Get the schema in this bundle
+    pub fn schema(&self) -> Result<Schema, BundleError> {
+        Ok(Schema::from_json_value(self.schema.clone())?)
+    }
+
+    /// Pack this bundle into a single-file archive. The archive is a JSON
+    /// object with separate sections for the schema, static policies,
+    /// templates, and template links, and a [`BundleManifest`] recording the
+    /// archive format version, the Cedar versions it was packed with, and
+    /// the SHA-256 hash of each section. This is the message signed by
+    /// [`PolicyBundle::sign`].
+    pub fn pack(&self) -> Result<Vec<u8>, BundleError> {
+        let section = |key| self.policies.get(key).cloned().unwrap_or_default();
+        let mut archive = BundleArchive {
+            manifest: BundleManifest {
+                format_version: FORMAT_VERSION,
+                lang_version: get_lang_version().to_string(),
+                sdk_version: get_sdk_version().to_string(),
+                hashes: BTreeMap::new(),
+            },
+            schema: self.schema.clone(),
+            policies: section("staticPolicies"),
+            templates: section("templates"),
+            links: section("templateLinks"),
+        };
+        archive.manifest.hashes = archive.section_hashes()?;
+        serde_json::to_vec(&archive).map_err(BundleError::Malformed)
+    }
+
+    /// Unpack an archive created by [`PolicyBundle::pack`], checking that
+    /// every section matches its hash in the manifest. This does not check
+    /// any signature; load bundles from untrusted sources with
+    /// [`AuthorizerHandle::from_signed_bundle`](super::AuthorizerHandle::from_signed_bundle)
+    /// or [`SignedBundle::verify`].
+    pub fn unpack(archive: &[u8]) -> Result<Self, BundleError> {
+        let archive: BundleArchive =
+            serde_json::from_slice(archive).map_err(BundleError::Malformed)?;
+        if archive.manifest.format_version != FORMAT_VERSION {
+            return Err(BundleError::UnsupportedFormatVersion(
+                archive.manifest.format_version,
+            ));
+        }
+        let hashes = archive.section_hashes()?;
+        if let Some(section) = hashes
+            .iter()
+            .find(|(section, hash)| archive.manifest.hashes.get(*section) != Some(hash))
+            .map(|(section, _)| section)
+        {
+            return Err(BundleError::IntegrityCheckFailed(section.clone()));
+        }
+        Ok(Self {
+            policies: serde_json::json!({
+                "staticPolicies": archive.policies,
+                "templates": archive.templates,
+                "templateLinks": archive.links,
+            }),
+            schema: archive.schema,
+        })
+    }
+
+    /// Get the manifest of an archive created by [`PolicyBundle::pack`],
+    /// without checking the integrity of its sections
+    pub fn manifest(archive: &[u8]) -> Result<BundleManifest, BundleError> {
+        #[derive(Deserialize)]
+        struct ManifestOnly {
+            manifest: BundleManifest,
+        }
+        let archive: ManifestOnly =
+            serde_json::from_slice(archive).map_err(BundleError::Malformed)?;
+        Ok(archive.manifest)
+    }
+
+    /// Pack and sign this bundle
+    pub fn sign(&self, signer: &dyn BundleSigner) -> Result<SignedBundle, BundleError> {
+        let bundle = self.pack()?;
+        let signature = signer.sign(&bundle).map_err(BundleError::Signing)?;
+        Ok(SignedBundle { bundle, signature })
+    }
+}
+
+/// The SHA-256 digest of `data`, as a lowercase hex string. Also used for the
+/// policy hashes in policy inventories and for authorization cache keys.
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    use std::fmt::Write;
+    Sha256::digest(data)
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, b| {
+            // writing to a `String` cannot fail
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+}
+
+/// Metadata about a packed [`PolicyBundle`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct BundleManifest {
+    format_version: u32,
+    lang_version: String,
+    sdk_version: String,
+    hashes: BTreeMap<String, String>,
+}
+
+impl BundleManifest {
+    /// The version of the archive format
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    /// The Cedar language version the bundle was packed with
+    pub fn lang_version(&self) -> &str {
+        &self.lang_version
+    }
+
+    /// The Cedar SDK version the bundle was packed with
+    pub fn sdk_version(&self) -> &str {
+        &self.sdk_version
+    }
+
+    /// The hex-encoded SHA-256 hash of each section of the archive (`schema`,
+    /// `policies`, `templates`, and `links`), by section name
+    pub fn hashes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.hashes.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// The single-file archive format of a [`PolicyBundle`]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BundleArchive {
+    manifest: BundleManifest,
+    schema: serde_json::Value,
+    policies: serde_json::Value,
+    templates: serde_json::Value,
+    links: serde_json::Value,
+}
+
+impl BundleArchive {
+    /// Hash each section of the archive, other than the manifest
+    fn section_hashes(&self) -> Result<BTreeMap<String, String>, BundleError> {
+        [
+            ("schema", &self.schema),
+            ("policies", &self.policies),
+            ("templates", &self.templates),
+            ("links", &self.links),
+        ]
+        .into_iter()
+        .map(|(section, value)| {
+            let bytes = serde_json::to_vec(value).map_err(BundleError::Malformed)?;
+            Ok((section.to_string(), sha256_hex(&bytes)))
+        })
+        .collect()
+    }
+}
+
+/// A serialized [`PolicyBundle`] with a signature over its bytes.
+///
+/// The bundle can only be read after verifying the signature, so that
+/// policies which were tampered with after signing are never loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedBundle {
+    bundle: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl SignedBundle {
+    /// Construct a signed bundle from a [`PolicyBundle`] archive and its
+    /// signature, e.g., as received from a policy distribution service
+    pub fn new(bundle: Vec<u8>, signature: Vec<u8>) -> Self {
+        Self { bundle, signature }
+    }
+
+    /// The bundle archive, as created by [`PolicyBundle::pack`]
+    pub fn bundle_bytes(&self) -> &[u8] {
+        &self.bundle
+    }
+
+    /// The signature over [`SignedBundle::bundle_bytes`]
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// Check the signature with `verifier` and, only if it is valid,
+    /// unpack the bundle
+    pub fn verify(&self, verifier: &dyn BundleVerifier) -> Result<PolicyBundle, BundleError> {
+        verifier
+            .verify(&self.bundle, &self.signature)
+            .map_err(BundleError::InvalidSignature)?;
+        PolicyBundle::unpack(&self.bundle)
+    }
+}
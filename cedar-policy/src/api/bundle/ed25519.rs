@@ -0,0 +1,99 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Signing and verifying policy bundles with ed25519, using `ed25519-dalek`
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+use super::{BundleSignatureError, BundleSigner, BundleVerifier};
+
+/// Signs policy bundles with an ed25519 signing key
+///
+/// ```
+/// # use cedar_policy::{Ed25519Signer, PolicyBundle, PolicySet, SchemaFragment};
+/// # use std::str::FromStr;
+/// let signer = Ed25519Signer::from_bytes(&[7; 32]);
+/// let verifier = signer.verifier();
+/// # let (schema, _) = SchemaFragment::from_cedarschema_str("entity User;").unwrap();
+/// # let policies = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+/// let signed = PolicyBundle::new(policies, schema).unwrap().sign(&signer).unwrap();
+/// assert!(signed.verify(&verifier).is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ed25519Signer {
+    key: SigningKey,
+}
+
+impl Ed25519Signer {
+    /// Construct a signer from the 32 bytes of an ed25519 secret key
+    pub fn from_bytes(secret_key: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(secret_key),
+        }
+    }
+
+    /// The verifier for signatures made by this signer
+    pub fn verifier(&self) -> Ed25519Verifier {
+        Ed25519Verifier {
+            key: self.key.verifying_key(),
+        }
+    }
+}
+
+impl BundleSigner for Ed25519Signer {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BundleSignatureError> {
+        self.key
+            .try_sign(message)
+            .map(|signature| signature.to_bytes().to_vec())
+            .map_err(|e| BundleSignatureError::with_source("ed25519 signing failed", e))
+    }
+}
+
+/// Verifies ed25519 signatures of policy bundles. Verification is strict:
+/// signatures which are malleable or made with weak keys are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ed25519Verifier {
+    key: VerifyingKey,
+}
+
+impl Ed25519Verifier {
+    /// Construct a verifier from the 32 bytes of an ed25519 public key
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BundleSignatureError`] if the bytes are not a valid
+    /// ed25519 public key
+    pub fn from_bytes(public_key: &[u8; 32]) -> Result<Self, BundleSignatureError> {
+        VerifyingKey::from_bytes(public_key)
+            .map(|key| Self { key })
+            .map_err(|e| BundleSignatureError::with_source("invalid ed25519 public key", e))
+    }
+
+    /// The 32 bytes of the public key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.key.to_bytes()
+    }
+}
+
+impl BundleVerifier for Ed25519Verifier {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), BundleSignatureError> {
+        let signature = Signature::from_slice(signature)
+            .map_err(|e| BundleSignatureError::with_source("malformed ed25519 signature", e))?;
+        self.key
+            .verify_strict(message, &signature)
+            .map_err(|e| BundleSignatureError::with_source("ed25519 signature does not match", e))
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Stable keys for caching authorization decisions
+//!
+//! A [`DecisionCacheKey`] is the SHA-256 digest, as a lowercase hex string,
+//! of a canonical JSON document describing everything a decision depends on:
+//!
+//! ```json
+//! {"context":…,"entities":[…],"policySetVersion":"…","request":{"action":…,"principal":…,"resource":…},"scheme":1}
+//! ```
+//!
+//! - `principal`, `action`, and `resource` are `{"id":…,"type":…}` objects.
+//! - `context` is the context record in the JSON format of Cedar values,
+//!   with entity references and extension values written with the
+//!   `__entity` and `__extn` escapes.
+//! - `entities` holds each entity in the JSON entity format, with its
+//!   `parents` sorted, and the entities are sorted by their canonical text.
+//! - `scheme` is [`DecisionCacheKey::SCHEME`], which changes whenever this
+//!   encoding does, so keys from different encodings never collide.
+//!
+//! The document is written without whitespace, with the keys of every object
+//! sorted by their UTF-8 bytes, and with strings escaped as by `serde_json`.
+//! Sets are written in the order of Cedar's ordering on values, so equal
+//! requests and entities always produce the same document, and the same key,
+//! regardless of the order in which they were constructed.
+
+use std::fmt::{self, Display, Write};
+
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::CedarValueJson;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::bundle::sha256_hex;
+use super::entities_errors::EntitiesError;
+use super::entities_json_errors::JsonSerializationError;
+use super::{Entities, EntityUid, Request};
+
+/// Stable key identifying an authorization decision, for external decision
+/// caches like Redis or an in-process LRU cache.
+///
+/// The key covers the request, the entities given, and a caller-defined
+/// version of the policy set, so a cached decision may be reused for
+/// requests with the same key as long as the policy set version is changed
+/// whenever the policies do. The key is stable across processes, platforms,
+/// and releases of this crate with the same [`DecisionCacheKey::SCHEME`].
+/// See [`DecisionCacheKey::canonical_form`] for the document it digests.
+///
+/// The entities given should be the slice of the entity store relevant to
+/// the request, e.g., as computed with an entity manifest. Every entity
+/// given is part of the key, so including irrelevant entities only lowers
+/// the hit rate of the cache, but leaving out an entity the decision depends
+/// on means a cached decision may be reused after that entity changes.
+///
+/// ```
+/// # use cedar_policy::{Context, DecisionCacheKey, Entities, EntityUid, Request};
+/// # use std::str::FromStr;
+/// let request = Request::new(
+///     EntityUid::from_str(r#"User::"alice""#).unwrap(),
+///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+///     EntityUid::from_str(r#"Photo::"trip""#).unwrap(),
+///     Context::empty(),
+///     None,
+/// ).unwrap();
+/// let entities = Entities::from_json_str(r#"[
+///     { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] }
+/// ]"#, None).unwrap();
+/// let key = DecisionCacheKey::new(&request, &entities, "v42").unwrap();
+/// assert_eq!(key.as_str().len(), 64);
+/// assert_ne!(key, DecisionCacheKey::new(&request, &entities, "v43").unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DecisionCacheKey(String);
+
+impl DecisionCacheKey {
+    /// Version of the encoding of keys, which is part of every key
+    pub const SCHEME: u32 = 1;
+
+    /// Derive the key for authorizing `request` with `entities` and version
+    /// `policy_set_version` of the policy set
+    pub fn new(
+        request: &Request,
+        entities: &Entities,
+        policy_set_version: &str,
+    ) -> Result<Self, DecisionCacheKeyError> {
+        let canonical = Self::canonical_form(request, entities, policy_set_version)?;
+        Ok(Self(sha256_hex(canonical.as_bytes())))
+    }
+
+    /// The canonical JSON document whose digest is the key for authorizing
+    /// `request` with `entities` and version `policy_set_version` of the
+    /// policy set, which is useful for debugging, or for checking another
+    /// implementation of the encoding
+    pub fn canonical_form(
+        request: &Request,
+        entities: &Entities,
+        policy_set_version: &str,
+    ) -> Result<String, DecisionCacheKeyError> {
+        let component = |uid: Option<&EntityUid>, component| {
+            uid.map(uid_json)
+                .ok_or(DecisionCacheKeyError::UnknownComponent { component })
+        };
+        let context = request
+            .context()
+            .ok_or(DecisionCacheKeyError::UnknownComponent {
+                component: "context",
+            })?;
+        let context = serde_json::to_value(CedarValueJson::from_expr(
+            ast::RestrictedExpr::from(context.0.clone()).as_borrowed(),
+        )?)
+        .map_err(JsonSerializationError::from)?;
+        let mut entities = entities
+            .iter()
+            .map(|entity| {
+                let mut json = entity.to_json_value()?;
+                if let Some(serde_json::Value::Array(parents)) = json.get_mut("parents") {
+                    parents.sort_by_cached_key(canonical_json);
+                }
+                Ok(canonical_json(&json))
+            })
+            .collect::<Result<Vec<_>, EntitiesError>>()?;
+        entities.sort();
+
+        let mut out = String::new();
+        write!(
+            out,
+            r#"{{"context":{},"entities":[{}],"policySetVersion":{},"request":{},"scheme":{}}}"#,
+            canonical_json(&context),
+            entities.join(","),
+            serde_json::Value::from(policy_set_version),
+            canonical_json(&serde_json::json!({
+                "principal": component(request.principal(), "principal")?,
+                "action": component(request.action(), "action")?,
+                "resource": component(request.resource(), "resource")?,
+            })),
+            Self::SCHEME
+        )
+        // writing to a `String` cannot fail
+        .unwrap_or_default();
+        Ok(out)
+    }
+
+    /// The key, as a lowercase hex string of 64 characters
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for DecisionCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for DecisionCacheKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Errors deriving a [`DecisionCacheKey`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum DecisionCacheKeyError {
+    /// A component of the request is unknown, as in requests for partial
+    /// evaluation, so the decision is not a cacheable decision
+    #[error("the {component} of the request is unknown")]
+    UnknownComponent {
+        /// The unknown component
+        component: &'static str,
+    },
+    /// The context could not be written as JSON
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Context(#[from] JsonSerializationError),
+    /// An entity could not be written as JSON
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] EntitiesError),
+}
+
+/// The `{"id":…,"type":…}` object for `uid`
+fn uid_json(uid: &EntityUid) -> serde_json::Value {
+    serde_json::json!({
+        "type": uid.type_name().to_string(),
+        "id": uid.id().as_ref(),
+    })
+}
+
+/// The text of `json` without whitespace, and with the keys of every object
+/// sorted by their UTF-8 bytes
+fn canonical_json(json: &serde_json::Value) -> String {
+    let mut out = String::new();
+    write_canonical_json(json, &mut out);
+    out
+}
+
+fn write_canonical_json(json: &serde_json::Value, out: &mut String) {
+    match json {
+        serde_json::Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|(k1, _), (k2, _)| k1.as_bytes().cmp(k2.as_bytes()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_canonical_json(value, out);
+            }
+            out.push('}');
+        }
+        serde_json::Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(value, out);
+            }
+            out.push(']');
+        }
+        leaf => out.push_str(&leaf.to_string()),
+    }
+}
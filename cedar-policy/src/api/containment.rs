@@ -0,0 +1,204 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Checking that a policy set permits nothing outside a boundary policy set
+
+use std::collections::HashMap;
+
+use super::{
+    Authorizer, Context, Decision, Entities, EntityUid, PolicyId, PolicySet, Request, Schema,
+};
+
+/// Check that a policy set is contained within a boundary policy set, i.e.,
+/// that every request it allows is also allowed by the boundary.
+///
+/// This is how authoring policies can be delegated safely: policies written
+/// by a team may be deployed if they are contained within the boundary of
+/// what that team may grant.
+///
+/// The check is exhaustive over a finite universe of requests: every action
+/// in the schema, with every principal and resource in the entities whose
+/// type the action applies to, and each of the contexts given for the
+/// action. Actions without contexts given for them are checked with the
+/// empty context. So, containment holds for the entities checked, rather
+/// than for all possible entities.
+#[derive(Debug, Clone)]
+pub struct ContainmentCheck<'a> {
+    schema: &'a Schema,
+    entities: &'a Entities,
+    contexts: HashMap<EntityUid, Vec<Context>>,
+    max_counterexamples: usize,
+}
+
+impl<'a> ContainmentCheck<'a> {
+    /// Check requests built from the actions of `schema` and the principals
+    /// and resources in `entities`, which should be constructed with
+    /// `schema` so that they include its action entities
+    pub fn new(schema: &'a Schema, entities: &'a Entities) -> Self {
+        Self {
+            schema,
+            entities,
+            contexts: HashMap::new(),
+            max_counterexamples: 10,
+        }
+    }
+
+    /// Check requests for `action` with each of `contexts`, rather than with
+    /// the empty context
+    #[must_use]
+    pub fn with_contexts(
+        mut self,
+        action: EntityUid,
+        contexts: impl IntoIterator<Item = Context>,
+    ) -> Self {
+        self.contexts.entry(action).or_default().extend(contexts);
+        self
+    }
+
+    /// Stop after finding `max` counterexamples. The default is 10.
+    #[must_use]
+    pub fn with_max_counterexamples(mut self, max: usize) -> Self {
+        self.max_counterexamples = max;
+        self
+    }
+
+    /// Check whether every request in the universe allowed by `policies` is
+    /// also allowed by `boundary`
+    pub fn check(&self, policies: &PolicySet, boundary: &PolicySet) -> ContainmentResult {
+        let authorizer = Authorizer::new();
+        let empty = [Context::empty()];
+        let mut result = ContainmentResult::default();
+        for action in self.schema.actions() {
+            let (Some(principal_types), Some(resource_types)) = (
+                self.schema.principals_for_action(action),
+                self.schema.resources_for_action(action),
+            ) else {
+                continue;
+            };
+            let principals = principal_types
+                .flat_map(|ty| self.entities.iter_of_type(ty))
+                .collect::<Vec<_>>();
+            let resources = resource_types
+                .flat_map(|ty| self.entities.iter_of_type(ty))
+                .collect::<Vec<_>>();
+            let contexts = self.contexts.get(action).map_or(&empty[..], Vec::as_slice);
+            for principal in &principals {
+                for resource in &resources {
+                    for context in contexts {
+                        let request = match Request::new(
+                            principal.uid(),
+                            action.clone(),
+                            resource.uid(),
+                            context.clone(),
+                            Some(self.schema),
+                        ) {
+                            Ok(request) => request,
+                            Err(err) => {
+                                result.invalid_requests += 1;
+                                if !result.invalid_actions.contains_key(action) {
+                                    result
+                                        .invalid_actions
+                                        .insert(action.clone(), err.to_string());
+                                }
+                                continue;
+                            }
+                        };
+                        result.requests_checked += 1;
+                        let response = authorizer.is_authorized(&request, policies, self.entities);
+                        if response.decision() == Decision::Deny
+                            || authorizer
+                                .is_authorized(&request, boundary, self.entities)
+                                .decision()
+                                == Decision::Allow
+                        {
+                            continue;
+                        }
+                        result.counterexamples.push(Counterexample {
+                            request,
+                            policies: response.diagnostics().reason().cloned().collect(),
+                        });
+                        if result.counterexamples.len() >= self.max_counterexamples {
+                            return result;
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Request allowed by a policy set but not by its boundary
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    request: Request,
+    policies: Vec<PolicyId>,
+}
+
+impl Counterexample {
+    /// The request allowed outside the boundary
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// The policies which allowed the request
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyId> {
+        self.policies.iter()
+    }
+}
+
+/// Result of a [`ContainmentCheck`]
+#[derive(Debug, Clone, Default)]
+pub struct ContainmentResult {
+    counterexamples: Vec<Counterexample>,
+    requests_checked: usize,
+    invalid_requests: usize,
+    invalid_actions: HashMap<EntityUid, String>,
+}
+
+impl ContainmentResult {
+    /// Whether the policy set is contained within the boundary for every
+    /// request checked
+    pub fn is_contained(&self) -> bool {
+        self.counterexamples.is_empty()
+    }
+
+    /// Requests allowed by the policy set but not by the boundary, up to the
+    /// maximum number of counterexamples of the check
+    pub fn counterexamples(&self) -> impl Iterator<Item = &Counterexample> {
+        self.counterexamples.iter()
+    }
+
+    /// The number of requests checked
+    pub fn requests_checked(&self) -> usize {
+        self.requests_checked
+    }
+
+    /// The number of requests which were not checked because they do not
+    /// conform to the schema, usually because the context does not have the
+    /// attributes the schema requires for the action
+    pub fn invalid_requests(&self) -> usize {
+        self.invalid_requests
+    }
+
+    /// The actions with requests which were not checked, each with the
+    /// error of its first such request
+    pub fn invalid_actions(&self) -> impl Iterator<Item = (&EntityUid, &str)> {
+        self.invalid_actions
+            .iter()
+            .map(|(action, err)| (action, err.as_str()))
+    }
+}
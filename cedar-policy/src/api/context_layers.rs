@@ -0,0 +1,227 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Building a request context from layered sources
+
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::sync::Arc;
+
+use cedar_policy_core::ast;
+use cedar_policy_core::extensions::Extensions;
+
+use super::{Context, ContextCreationError};
+
+/// Named [`Context`]s merged into one, with later layers taking precedence
+/// over earlier ones.
+///
+/// For instance, the layers may be a static context for the whole service,
+/// then context contributed by middleware, then the context of the request.
+/// Layers are kept as they are until [`ContextLayers::merge`], which builds
+/// the merged context directly from the values of the layers, so middleware
+/// can each add a layer without rebuilding the context.
+///
+/// ```
+/// # use cedar_policy::{Context, ContextLayers};
+/// let service = Context::from_json_str(r#"{"region": "eu-west-1", "mfa": false}"#, None).unwrap();
+/// let request = Context::from_json_str(r#"{"mfa": true, "ip": "10.0.0.1"}"#, None).unwrap();
+/// let merged = ContextLayers::new()
+///     .with_layer("service", service)
+///     .with_layer("request", request)
+///     .merge()
+///     .unwrap();
+/// let conflict = &merged.conflicts()[0];
+/// assert_eq!(conflict.key(), "mfa");
+/// assert_eq!(conflict.layers(), ["service", "request"]);
+/// assert_eq!(merged.context().get("mfa"), Some(cedar_policy::EvalResult::Bool(true)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ContextLayers {
+    layers: Vec<(String, Context)>,
+}
+
+impl ContextLayers {
+    /// Create an empty stack of layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer named `name`, which takes precedence over the layers
+    /// already added
+    #[must_use]
+    pub fn with_layer(mut self, name: impl Into<String>, context: Context) -> Self {
+        self.add_layer(name, context);
+        self
+    }
+
+    /// Add a layer named `name`, which takes precedence over the layers
+    /// already added
+    pub fn add_layer(&mut self, name: impl Into<String>, context: Context) {
+        self.layers.push((name.into(), context));
+    }
+
+    /// The names of the layers, from lowest to highest precedence
+    pub fn layer_names(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Merge the layers, taking the value of each key from the layer with
+    /// the highest precedence which has it, and reporting keys with
+    /// different values in different layers.
+    ///
+    /// This only fails when some layer contains unknowns, for partial
+    /// evaluation, and re-evaluating the merged context fails.
+    pub fn merge(&self) -> Result<MergedContext, ContextCreationError> {
+        let mut conflicts = BTreeMap::new();
+        let name = |layer: usize| {
+            self.layers
+                .get(layer)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default()
+        };
+        let mut record_conflict = |key: &str, old: usize, new: usize| {
+            conflicts
+                .entry(key.to_string())
+                .or_insert_with(|| vec![name(old)])
+                .push(name(new));
+        };
+
+        let values = self
+            .layers
+            .iter()
+            .map(|(_, context)| match &context.0 {
+                ast::Context::Value(attrs) => Some(attrs),
+                ast::Context::RestrictedResidual(_) => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let context = if let Some(values) = values {
+            // Every layer is a value, so the values are merged as they are
+            let mut merged: BTreeMap<_, (usize, &ast::Value)> = BTreeMap::new();
+            for (layer, attrs) in values.into_iter().enumerate() {
+                for (key, value) in attrs.iter() {
+                    match merged.entry(key.clone()) {
+                        Entry::Vacant(entry) => {
+                            entry.insert((layer, value));
+                        }
+                        Entry::Occupied(mut entry) => {
+                            if entry.get().1 != value {
+                                record_conflict(key, entry.get().0, layer);
+                            }
+                            entry.insert((layer, value));
+                        }
+                    }
+                }
+            }
+            Context(ast::Context::Value(Arc::new(
+                merged
+                    .into_iter()
+                    .map(|(key, (_, value))| (key, value.clone()))
+                    .collect(),
+            )))
+        } else {
+            let mut merged: BTreeMap<_, (usize, ast::RestrictedExpr)> = BTreeMap::new();
+            for (layer, (_, context)) in self.layers.iter().enumerate() {
+                for (key, expr) in context.0.clone() {
+                    match merged.entry(key) {
+                        Entry::Vacant(entry) => {
+                            entry.insert((layer, expr));
+                        }
+                        Entry::Occupied(mut entry) => {
+                            if entry.get().1 != expr {
+                                record_conflict(entry.key(), entry.get().0, layer);
+                            }
+                            entry.insert((layer, expr));
+                        }
+                    }
+                }
+            }
+            Context(ast::Context::from_pairs(
+                merged.into_iter().map(|(key, (_, expr))| (key, expr)),
+                Extensions::all_available(),
+            )?)
+        };
+
+        Ok(MergedContext {
+            context,
+            conflicts: conflicts
+                .into_iter()
+                .map(|(key, layers)| ContextConflict { key, layers })
+                .collect(),
+        })
+    }
+}
+
+/// [`Context`] merged from [`ContextLayers`], with the conflicts between
+/// the layers
+#[derive(Debug, Clone)]
+pub struct MergedContext {
+    context: Context,
+    conflicts: Vec<ContextConflict>,
+}
+
+impl MergedContext {
+    /// The merged context
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Consume the `MergedContext`, returning the merged context
+    pub fn into_context(self) -> Context {
+        self.context
+    }
+
+    /// The keys with different values in different layers, sorted by key
+    pub fn conflicts(&self) -> &[ContextConflict] {
+        &self.conflicts
+    }
+}
+
+/// Key of a [`Context`] with different values in different
+/// [`ContextLayers`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextConflict {
+    key: String,
+    layers: Vec<String>,
+}
+
+impl ContextConflict {
+    /// The conflicting key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The names of the layers which set the key to a different value than
+    /// the layer before them, from lowest to highest precedence. The last
+    /// one is the layer whose value was used.
+    pub fn layers(&self) -> &[String] {
+        &self.layers
+    }
+}
+
+impl std::fmt::Display for ContextConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "context key `{}` is set by layers {}; the value from `{}` is used",
+            self.key,
+            self.layers
+                .iter()
+                .map(|l| format!("`{l}`"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.layers.last().map(String::as_str).unwrap_or_default(),
+        )
+    }
+}
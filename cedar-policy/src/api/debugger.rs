@@ -0,0 +1,223 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Step-through debugging of the evaluation of expressions and policies
+
+use cedar_policy_core::ast::{self, PartialValue};
+use cedar_policy_core::evaluator::{self, Evaluator};
+use cedar_policy_core::extensions::Extensions;
+use ref_cast::RefCast;
+
+use super::{Entities, EvalResult, EvaluationError, Expression, Policy, Request};
+
+/// One step of an evaluation recorded by an [`EvaluationDebugger`]: the
+/// evaluator starting to evaluate a sub-expression, or finishing it
+#[repr(transparent)]
+#[derive(Debug, Clone, RefCast)]
+pub struct EvaluationStep(evaluator::EvaluationStep);
+
+impl EvaluationStep {
+    /// The sub-expression being evaluated
+    pub fn expression(&self) -> &Expression {
+        Expression::ref_cast(self.0.expr())
+    }
+
+    /// How deeply the sub-expression is nested in the evaluated expression or
+    /// policy condition, which has depth 0
+    pub fn depth(&self) -> usize {
+        self.0.depth()
+    }
+
+    /// Whether this step finishes the evaluation of the sub-expression, as
+    /// opposed to starting it
+    pub fn is_finished(&self) -> bool {
+        self.0.result().is_some()
+    }
+
+    /// The value of the sub-expression, if this step finishes its evaluation
+    /// with a value
+    pub fn value(&self) -> Option<EvalResult> {
+        match self.0.result()? {
+            Ok(PartialValue::Value(v)) => Some(v.clone().into()),
+            Ok(PartialValue::Residual(_)) | Err(_) => None,
+        }
+    }
+
+    /// The error the sub-expression evaluated to, if this step finishes its
+    /// evaluation with an error
+    pub fn error(&self) -> Option<&EvaluationError> {
+        self.0.result()?.as_ref().err()
+    }
+}
+
+/// A debugger for stepping through the evaluation of an expression or of the
+/// condition of a policy.
+///
+/// The evaluation is recorded when the debugger is created, as a sequence of
+/// [`EvaluationStep`]s in which the evaluator starts and finishes every
+/// sub-expression it evaluates. Since evaluation has no side effects, stepping
+/// through the recording is indistinguishable from pausing the evaluator: the
+/// debugger starts before the first step, and can advance one step at a time,
+/// step over sub-expressions, or continue until a breakpoint.
+///
+/// ```
+/// # use cedar_policy::{Context, Entities, EvaluationDebugger, EvalResult, Expression, Request};
+/// let request = Request::new(
+///     r#"User::"alice""#.parse().unwrap(),
+///     r#"Action::"view""#.parse().unwrap(),
+///     r#"Photo::"trip.jpg""#.parse().unwrap(),
+///     Context::empty(),
+///     None,
+/// )
+/// .unwrap();
+/// let expr: Expression = "if 1 < 2 then 3 else 4".parse().unwrap();
+/// let mut debugger = EvaluationDebugger::for_expression(&request, &Entities::empty(), &expr);
+///
+/// // Pause once the condition of the `if` is evaluated
+/// let step = debugger
+///     .continue_until(|step| step.is_finished() && step.expression().to_string() == "1 < 2")
+///     .unwrap();
+/// assert_eq!(step.value(), Some(EvalResult::Bool(true)));
+///
+/// // Step into the `then` branch
+/// let step = debugger.step().unwrap();
+/// assert_eq!(step.expression().to_string(), "3");
+/// assert!(!step.is_finished());
+///
+/// // Run to the end
+/// debugger.continue_until(|_| false);
+/// assert_eq!(debugger.result(), Some(Ok(EvalResult::Long(3))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EvaluationDebugger {
+    request: Request,
+    steps: Vec<EvaluationStep>,
+    /// Index of the current step, or `None` before the first step
+    position: Option<usize>,
+}
+
+impl EvaluationDebugger {
+    /// Record the evaluation of `expr` for `request`, looking up entities in
+    /// `entities`
+    pub fn for_expression(request: &Request, entities: &Entities, expr: &Expression) -> Self {
+        Self::record(request, entities, |eval| {
+            // Evaluate under the empty slot map, as an expression should not have slots
+            let _ = eval.partial_interpret(&expr.0, &ast::SlotEnv::new());
+        })
+    }
+
+    /// Record the evaluation of the condition of `policy` for `request`,
+    /// looking up entities in `entities`
+    pub fn for_policy(request: &Request, entities: &Entities, policy: &Policy) -> Self {
+        Self::record(request, entities, |eval| {
+            let _ = eval.partial_interpret(&policy.ast.condition(), policy.ast.env());
+        })
+    }
+
+    fn record(
+        request: &Request,
+        entities: &Entities,
+        evaluate: impl FnOnce(&Evaluator<'_>),
+    ) -> Self {
+        let steps = evaluator::EvaluationSteps::new();
+        let eval = Evaluator::new(request.0.clone(), &entities.0, Extensions::all_available())
+            .with_steps(&steps);
+        evaluate(&eval);
+        Self {
+            request: request.clone(),
+            steps: steps.take().into_iter().map(EvaluationStep).collect(),
+            position: None,
+        }
+    }
+
+    /// The request the evaluation is for, which binds `principal`, `action`,
+    /// `resource`, and `context`
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// All steps of the evaluation, in order
+    pub fn steps(&self) -> &[EvaluationStep] {
+        &self.steps
+    }
+
+    /// The step the debugger is paused at, or `None` if it has not taken a
+    /// step yet
+    pub fn current(&self) -> Option<&EvaluationStep> {
+        self.steps.get(self.position?)
+    }
+
+    /// Advance to the next step, returning it, or `None` if evaluation has
+    /// already finished
+    pub fn step(&mut self) -> Option<&EvaluationStep> {
+        let next = self.position.map_or(0, |pos| pos + 1);
+        if next < self.steps.len() {
+            self.position = Some(next);
+            self.current()
+        } else {
+            None
+        }
+    }
+
+    /// Advance past the evaluation of the sub-expression the debugger is
+    /// paused at the start of, to the step which finishes it. Otherwise, the
+    /// same as [`EvaluationDebugger::step`].
+    pub fn step_over(&mut self) -> Option<&EvaluationStep> {
+        let depth = match self.current() {
+            Some(step) if !step.is_finished() => step.depth(),
+            _ => return self.step(),
+        };
+        self.continue_until(|step| step.depth() == depth)
+    }
+
+    /// Advance to the next step for which `breakpoint` returns `true`,
+    /// returning it. If there is no such step, advance to the end of the
+    /// evaluation and return `None`.
+    pub fn continue_until(
+        &mut self,
+        mut breakpoint: impl FnMut(&EvaluationStep) -> bool,
+    ) -> Option<&EvaluationStep> {
+        while let Some(step) = self.step() {
+            if breakpoint(step) {
+                return self.current();
+            }
+        }
+        None
+    }
+
+    /// Go back to before the first step
+    pub fn restart(&mut self) {
+        self.position = None;
+    }
+
+    /// Whether the debugger has reached the last step of the evaluation
+    pub fn is_finished(&self) -> bool {
+        self.position
+            .map_or(self.steps.is_empty(), |pos| pos + 1 >= self.steps.len())
+    }
+
+    /// The result of the whole evaluation, once the debugger has reached its
+    /// last step. This is `None` before then, and if the result is a
+    /// residual.
+    pub fn result(&self) -> Option<Result<EvalResult, &EvaluationError>> {
+        if !self.is_finished() {
+            return None;
+        }
+        let last = self.steps.last()?;
+        last.error()
+            .map_or_else(|| last.value().map(Ok), |err| Some(Err(err)))
+    }
+}
@@ -0,0 +1,216 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Telling denials by policy apart from denials caused by errors, like
+//! entity data which could not be loaded
+
+use cedar_policy_core::ast;
+use cedar_policy_core::evaluator::RestrictedEvaluator;
+use cedar_policy_core::extensions::Extensions;
+use smol_str::SmolStr;
+
+use super::authorization_errors::PolicyEvaluationError;
+use super::evaluation_errors::EntityLoadErrorKind;
+use super::{
+    AuthorizationError, CancellationReason, Decision, Entity, EvaluationError, Response,
+    RestrictedExpression,
+};
+
+/// Failure of a loader computing an entity attribute; see
+/// [`Entity::with_fallible_lazy_attr`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityLoadFailure {
+    kind: EntityLoadErrorKind,
+    message: String,
+}
+
+impl EntityLoadFailure {
+    /// Create an `EntityLoadFailure` of kind `kind`, explained by `message`
+    pub fn new(kind: EntityLoadErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Why the attribute could not be loaded
+    pub fn kind(&self) -> EntityLoadErrorKind {
+        self.kind
+    }
+
+    /// What went wrong
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Entity {
+    /// Like [`Entity::with_lazy_attr`], but `compute` may fail, for instance
+    /// when the attribute is loaded from a service which timed out.
+    ///
+    /// Evaluating a policy which reads the attribute after `compute` failed
+    /// produces an [`EvaluationError::EntityLoad`] error recording the
+    /// [`EntityLoadErrorKind`], so callers can tell such errors apart from
+    /// errors in the policies (see [`PolicyEvaluationError::category`]).
+    /// ```
+    /// # use cedar_policy::{Entity, EntityLoadFailure, EntityUid, RestrictedExpression};
+    /// # use cedar_policy::evaluation_errors::EntityLoadErrorKind;
+    /// # use std::str::FromStr;
+    /// let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let alice = Entity::with_uid(euid).with_fallible_lazy_attr("groups", || {
+    ///     Err(EntityLoadFailure::new(EntityLoadErrorKind::Timeout, "directory timed out"))
+    /// });
+    /// // the loader failed, so the attribute has no value
+    /// assert!(alice.attr("groups").is_none());
+    /// ```
+    #[must_use]
+    pub fn with_fallible_lazy_attr(
+        mut self,
+        attr: &str,
+        compute: impl Fn() -> Result<RestrictedExpression, EntityLoadFailure> + Send + Sync + 'static,
+    ) -> Self {
+        let uid = self.0.uid().clone();
+        let name = SmolStr::from(attr);
+        self.0.add_lazy_attr(
+            attr.into(),
+            ast::LazyAttr::new(move || match compute() {
+                Ok(expr) => RestrictedEvaluator::new(Extensions::all_available())
+                    .partial_interpret(expr.0.as_borrowed()),
+                Err(failure) => Err(EvaluationError::entity_load(
+                    uid.clone(),
+                    name.clone(),
+                    failure.kind,
+                    failure.message,
+                )),
+            }),
+        );
+        self
+    }
+}
+
+/// Broad category of an error evaluating a policy, for deciding whether to
+/// fail closed, retry, or alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// Entity data could not be loaded (see
+    /// [`Entity::with_fallible_lazy_attr`]), so retrying may succeed
+    EntityLoad(EntityLoadErrorKind),
+    /// An entity the policy refers to is missing from the entities given,
+    /// for instance because they were sliced incorrectly
+    MissingEntity,
+    /// Evaluation was cancelled, or its deadline passed
+    Cancelled(CancellationReason),
+    /// The request exceeded a configured resource limit
+    LimitExceeded,
+    /// A [`crate::ContextProvider`] supplied an invalid value for a context
+    /// attribute the policy reads
+    ContextProvider,
+    /// The policy depends on data which is unknown
+    Unknown,
+    /// Any other error, such as a type error or an overflow, which means the
+    /// policy does not handle the data it was given
+    Policy,
+}
+
+impl From<&EvaluationError> for ErrorCategory {
+    fn from(error: &EvaluationError) -> Self {
+        match error {
+            EvaluationError::EntityLoad(e) => Self::EntityLoad(e.kind()),
+            EvaluationError::EntityDoesNotExist(_) => Self::MissingEntity,
+            EvaluationError::Cancelled(e) => Self::Cancelled(e.reason()),
+            EvaluationError::LimitExceeded(_) => Self::LimitExceeded,
+            EvaluationError::ContextProvider(_) => Self::ContextProvider,
+            EvaluationError::NonValue(_) => Self::Unknown,
+            _ => Self::Policy,
+        }
+    }
+}
+
+impl PolicyEvaluationError {
+    /// The [`ErrorCategory`] of the underlying [`EvaluationError`]
+    pub fn category(&self) -> ErrorCategory {
+        self.inner().into()
+    }
+}
+
+/// Why a request was denied; see [`Response::denial_cause`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DenialCause {
+    /// A `forbid` policy was satisfied. Since `forbid` overrides `permit`,
+    /// the request is denied regardless of any errors.
+    ForbiddenByPolicy,
+    /// No `permit` policy was satisfied, and none errored
+    NoPermitApplied,
+    /// No `permit` policy was satisfied, but some errored, or evaluation was
+    /// abandoned, so the request might have been allowed without the errors
+    EvaluationFailed,
+}
+
+impl Response {
+    /// Why this request was denied, or `None` if it was allowed.
+    ///
+    /// When the cause is [`DenialCause::EvaluationFailed`], the errors in
+    /// [`super::Diagnostics::errors`] and their
+    /// [`PolicyEvaluationError::category`] tell which policies failed and
+    /// why, for instance to retry when entity data timed out, but alert when
+    /// a policy has a type error.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, DenialCause, Entities, Entity, EntityLoadFailure,
+    /// #     EntityUid, ErrorCategory, PolicySet, Request, AuthorizationError};
+    /// # use cedar_policy::evaluation_errors::EntityLoadErrorKind;
+    /// # use std::str::FromStr;
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let entities = Entities::from_entities([
+    ///     Entity::with_uid(alice.clone()).with_fallible_lazy_attr("level", || {
+    ///         Err(EntityLoadFailure::new(EntityLoadErrorKind::Timeout, "directory timed out"))
+    ///     }),
+    /// ], None).unwrap();
+    /// let policies = PolicySet::from_str("permit(principal, action, resource) when { principal.level > 3 };").unwrap();
+    /// let request = Request::new(
+    ///     alice,
+    ///     EntityUid::from_str(r#"Action::"view""#).unwrap(),
+    ///     EntityUid::from_str(r#"Photo::"trip""#).unwrap(),
+    ///     Context::empty(),
+    ///     None,
+    /// ).unwrap();
+    /// let response = Authorizer::new().is_authorized(&request, &policies, &entities);
+    /// assert_eq!(response.denial_cause(), Some(DenialCause::EvaluationFailed));
+    /// let AuthorizationError::PolicyEvaluationError(error) = response.diagnostics().errors().next().unwrap();
+    /// assert_eq!(error.category(), ErrorCategory::EntityLoad(EntityLoadErrorKind::Timeout));
+    /// ```
+    pub fn denial_cause(&self) -> Option<DenialCause> {
+        let diagnostics = &self.diagnostics;
+        match self.decision {
+            Decision::Allow => None,
+            Decision::Deny if diagnostics.reason().next().is_some() => {
+                Some(DenialCause::ForbiddenByPolicy)
+            }
+            Decision::Deny
+                if diagnostics.errored_permits().next().is_some()
+                    || diagnostics.errors().any(
+                        |AuthorizationError::PolicyEvaluationError(e)| {
+                            matches!(e.category(), ErrorCategory::Cancelled(_))
+                        },
+                    ) =>
+            {
+                Some(DenialCause::EvaluationFailed)
+            }
+            Decision::Deny => Some(DenialCause::NoPermitApplied),
+        }
+    }
+}
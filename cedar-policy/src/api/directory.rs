@@ -0,0 +1,186 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Loading a policy set from a directory of policy files
+
+use std::path::{Path, PathBuf};
+
+use cedar_policy_core::est::TemplateLink;
+use globset::{Glob, GlobMatcher};
+
+use super::policy_directory_errors::{InvalidGlobError, PolicyFileError, PolicyFileErrorKind};
+use super::{PolicyDirectoryError, PolicyId, PolicySet, Provenance};
+
+/// Files with names ending in this suffix contain template links
+const LINKS_SUFFIX: &str = ".links.json";
+
+impl PolicySet {
+    /// Load every file under `dir`, searched recursively, whose path relative
+    /// to `dir` matches `glob`.
+    ///
+    /// Files whose names end in `.links.json` contain a JSON list of template
+    /// links, in the same format as the `templateLinks` of a JSON policy set.
+    /// They are applied after all policies and templates have been loaded, so
+    /// they may link templates from any file. Every other file contains Cedar
+    /// policies and templates.
+    ///
+    /// A policy or template with an `@id` annotation has that id. Otherwise,
+    /// its id is the path of its file relative to `dir`, without the
+    /// extension, such as `photos/admin` for `photos/admin.cedar`. In files
+    /// with more than one policy, the position of the policy in the file is
+    /// added, as in `photos/admin#0`, `photos/admin#1`, and so on. Every
+    /// policy records its file and line as its [`Provenance`].
+    ///
+    /// Files are loaded in order of their paths, so the result doesn't
+    /// depend on the order in which the file system lists them. If any file
+    /// can't be loaded, the error for every such file is returned.
+    ///
+    /// ```no_run
+    /// # use cedar_policy::PolicySet;
+    /// let pset = PolicySet::from_directory("policies", "**/*.{cedar,links.json}").unwrap();
+    /// ```
+    pub fn from_directory(dir: impl AsRef<Path>, glob: &str) -> Result<Self, PolicyDirectoryError> {
+        let dir = dir.as_ref();
+        let matcher = Glob::new(glob)
+            .map_err(|err| InvalidGlobError { err })?
+            .compile_matcher();
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        find_files(dir, Path::new(""), &matcher, &mut files, &mut errors);
+        files.sort();
+        let (links_files, policy_files): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|file| file.to_string_lossy().ends_with(LINKS_SUFFIX));
+
+        let mut pset = Self::new();
+        for file in policy_files {
+            let path = dir.join(&file);
+            if let Err(kind) = pset.add_policy_file(&path, &file) {
+                errors.push(PolicyFileError { path, kind });
+            }
+        }
+        for file in links_files {
+            let path = dir.join(file);
+            if let Err(kind) = pset.add_links_file(&path) {
+                errors.push(PolicyFileError { path, kind });
+            }
+        }
+        if errors.is_empty() {
+            Ok(pset)
+        } else {
+            Err(PolicyDirectoryError::Files(errors))
+        }
+    }
+
+    /// Add the policies and templates in the file at `path`, with ids
+    /// derived from `file`, its path relative to the policy directory
+    fn add_policy_file(&mut self, path: &Path, file: &Path) -> Result<(), PolicyFileErrorKind> {
+        let src = std::fs::read_to_string(path)?;
+        let name = file
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let parsed = Self::from_str_with_provenance(
+            &src,
+            Provenance::new().with_file(file.to_string_lossy()),
+        )?;
+        let ids = parsed.ids_in_source_order();
+        let single = ids.len() == 1;
+        for (i, id) in ids.into_iter().enumerate() {
+            let default_id = || {
+                PolicyId::new(if single {
+                    name.clone()
+                } else {
+                    format!("{name}#{i}")
+                })
+            };
+            if let Some(t) = parsed.template(id) {
+                let new_id = t.annotation("id").map_or_else(default_id, PolicyId::new);
+                self.add_template(t.new_id(new_id))?;
+            } else if let Some(p) = parsed.policy(id) {
+                let new_id = p.annotation("id").map_or_else(default_id, PolicyId::new);
+                self.add(p.new_id(new_id))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the template links in the file at `path`
+    fn add_links_file(&mut self, path: &Path) -> Result<(), PolicyFileErrorKind> {
+        let src = std::fs::read_to_string(path)?;
+        let links: Vec<TemplateLink> =
+            serde_json::from_str(&src).map_err(PolicyFileErrorKind::Links)?;
+        for link in links {
+            self.link(
+                PolicyId::new(link.template_id),
+                PolicyId::new(link.new_id),
+                link.values
+                    .into_iter()
+                    .map(|(slot, euid)| (slot.into(), euid.into()))
+                    .collect(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Add the paths, relative to the policy directory, of all files under
+/// `dir.join(prefix)` which match `matcher` to `files`, and an error for
+/// every directory which can't be read to `errors`
+fn find_files(
+    dir: &Path,
+    prefix: &Path,
+    matcher: &GlobMatcher,
+    files: &mut Vec<PathBuf>,
+    errors: &mut Vec<PolicyFileError>,
+) {
+    let path = dir.join(prefix);
+    let entries = match std::fs::read_dir(&path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            errors.push(PolicyFileError {
+                path,
+                kind: err.into(),
+            });
+            return;
+        }
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                errors.push(PolicyFileError {
+                    path: path.clone(),
+                    kind: err.into(),
+                });
+                continue;
+            }
+        };
+        let file = prefix.join(entry.file_name());
+        let entry_path = entry.path();
+        // Don't follow symlinks to directories, which could form a cycle
+        let is_symlink = entry.file_type().is_ok_and(|ty| ty.is_symlink());
+        if entry_path.is_dir() {
+            if !is_symlink {
+                find_files(dir, &file, matcher, files, errors);
+            }
+        } else if matcher.is_match(&file) {
+            files.push(file);
+        }
+    }
+}
@@ -0,0 +1,449 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Rendering documentation for a policy set, for reviewers who would rather
+//! not read raw Cedar
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use cedar_policy_core::ast;
+use cedar_policy_core::est;
+
+use super::{Effect, PolicySet, Schema};
+
+/// How [`PolicyDocs`] groups the policies it documents into sections
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PolicyDocsGrouping {
+    /// One section per resource type. With a schema, a policy is listed under
+    /// every resource type its scope admits together with some action and
+    /// principal type. Without one, a policy is listed under the entity type
+    /// of a `resource is` or `resource ==` scope constraint, or under a
+    /// section for any resource type.
+    ResourceType,
+    /// One section per value of the annotation with this key. Policies without
+    /// the annotation are listed in a final section.
+    Annotation(String),
+}
+
+/// Documentation for the static policies and templates of a [`PolicySet`],
+/// rendered as Markdown or HTML.
+///
+/// Every policy is documented with its effect, its scope, its conditions, and
+/// its annotations. The value of its `@doc` annotation, or of another
+/// annotation chosen with [`PolicyDocs::with_doc_annotation`], is rendered as
+/// its description. Templates also list the policies linked from them.
+///
+/// ```
+/// # use cedar_policy::{PolicyDocs, PolicySet};
+/// let pset: PolicySet = r#"
+///     @doc("Anyone may view public photos")
+///     permit(principal, action == Action::"view", resource is Photo)
+///     when { resource.public };
+/// "#.parse().unwrap();
+/// let markdown = PolicyDocs::new(&pset).to_markdown();
+/// assert!(markdown.contains("## Resource type `Photo`"));
+/// assert!(markdown.contains("Anyone may view public photos"));
+/// assert!(markdown.contains(r#"when { resource["public"] }"#));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PolicyDocs<'a> {
+    policies: &'a PolicySet,
+    schema: Option<&'a Schema>,
+    grouping: PolicyDocsGrouping,
+    doc_annotation: String,
+}
+
+/// The documentation of one static policy or template
+#[derive(Debug, Clone)]
+struct PolicyDoc {
+    id: String,
+    effect: Effect,
+    /// The ids of the policies linked from a template, or `None` for a static
+    /// policy
+    links: Option<Vec<String>>,
+    description: Option<String>,
+    annotations: Vec<(String, String)>,
+    scope: [String; 3],
+    conditions: Vec<String>,
+}
+
+/// The title of a section of the documentation
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    /// Policies which may apply to a resource type
+    ResourceType(String),
+    /// Policies which may apply to any resource type
+    AnyResourceType,
+    /// Policies whose scope admits no request in the schema
+    NoResourceType,
+    /// Policies with a value of the grouping annotation
+    Annotation(String),
+    /// Policies without the grouping annotation
+    NoAnnotation,
+}
+
+impl<'a> PolicyDocs<'a> {
+    /// Documentation for `policies`, grouped by resource type
+    pub fn new(policies: &'a PolicySet) -> Self {
+        Self {
+            policies,
+            schema: None,
+            grouping: PolicyDocsGrouping::ResourceType,
+            doc_annotation: "doc".to_string(),
+        }
+    }
+
+    /// Use `schema` to find the resource types each policy may apply to
+    #[must_use]
+    pub fn with_schema(mut self, schema: &'a Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Group the policies into sections by `grouping`
+    #[must_use]
+    pub fn with_grouping(mut self, grouping: PolicyDocsGrouping) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Take the description of each policy from the annotation with key
+    /// `key`, instead of from `@doc`
+    #[must_use]
+    pub fn with_doc_annotation(mut self, key: impl Into<String>) -> Self {
+        self.doc_annotation = key.into();
+        self
+    }
+
+    /// Render the documentation as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        // PANIC SAFETY: `std::fmt::Write` does not return errors when writing to a `String`
+        #[allow(clippy::expect_used)]
+        self.write_markdown(&mut out)
+            .expect("writing to a `String` should not fail");
+        out
+    }
+
+    fn write_markdown(&self, out: &mut impl Write) -> std::fmt::Result {
+        out.write_str("# Policies\n")?;
+        for (section, docs) in self.sections() {
+            let title = match section {
+                Section::ResourceType(ty) => format!("Resource type `{ty}`"),
+                Section::AnyResourceType => "Any resource type".to_string(),
+                Section::NoResourceType => "No resource type".to_string(),
+                Section::Annotation(value) => {
+                    format!("`@{}(\"{}\")`", self.grouping_key(), value.escape_debug())
+                }
+                Section::NoAnnotation => format!("Without `@{}`", self.grouping_key()),
+            };
+            write!(out, "\n## {title}\n")?;
+            for doc in docs {
+                let kind = if doc.links.is_some() {
+                    "template"
+                } else {
+                    "policy"
+                };
+                write!(out, "\n### `{}` ({} {kind})\n", doc.id, doc.effect)?;
+                if let Some(description) = &doc.description {
+                    write!(out, "\n{description}\n")?;
+                }
+                out.write_char('\n')?;
+                for (label, scope) in ["Principal", "Action", "Resource"].iter().zip(&doc.scope) {
+                    writeln!(out, "- **{label}:** `{scope}`")?;
+                }
+                if !doc.annotations.is_empty() {
+                    let annotations = doc
+                        .annotations
+                        .iter()
+                        .map(|(key, value)| format!("`@{key}(\"{}\")`", value.escape_debug()))
+                        .collect::<Vec<_>>();
+                    writeln!(out, "- **Annotations:** {}", annotations.join(", "))?;
+                }
+                if let Some(links) = &doc.links {
+                    let links = if links.is_empty() {
+                        "none".to_string()
+                    } else {
+                        links
+                            .iter()
+                            .map(|id| format!("`{id}`"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    writeln!(out, "- **Linked policies:** {links}")?;
+                }
+                if !doc.conditions.is_empty() {
+                    write!(
+                        out,
+                        "\n**Conditions:**\n\n```cedar\n{}\n```\n",
+                        doc.conditions.join("\n")
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the documentation as an HTML fragment, with a `<section>` for
+    /// each group of policies and an `<article>` for each policy
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        // PANIC SAFETY: `std::fmt::Write` does not return errors when writing to a `String`
+        #[allow(clippy::expect_used)]
+        self.write_html(&mut out)
+            .expect("writing to a `String` should not fail");
+        out
+    }
+
+    fn write_html(&self, out: &mut impl Write) -> std::fmt::Result {
+        out.write_str("<h1>Policies</h1>\n")?;
+        for (section, docs) in self.sections() {
+            let title = match section {
+                Section::ResourceType(ty) => {
+                    format!("Resource type <code>{}</code>", escape_html(&ty))
+                }
+                Section::AnyResourceType => "Any resource type".to_string(),
+                Section::NoResourceType => "No resource type".to_string(),
+                Section::Annotation(value) => format!(
+                    "<code>@{}(\"{}\")</code>",
+                    escape_html(self.grouping_key()),
+                    escape_html(&value.escape_debug().to_string())
+                ),
+                Section::NoAnnotation => {
+                    format!("Without <code>@{}</code>", escape_html(self.grouping_key()))
+                }
+            };
+            write!(out, "<section>\n<h2>{title}</h2>\n")?;
+            for doc in docs {
+                let kind = if doc.links.is_some() {
+                    "template"
+                } else {
+                    "policy"
+                };
+                write!(
+                    out,
+                    "<article>\n<h3><code>{}</code> ({} {kind})</h3>\n",
+                    escape_html(&doc.id),
+                    doc.effect
+                )?;
+                if let Some(description) = &doc.description {
+                    writeln!(out, "<p>{}</p>", escape_html(description))?;
+                }
+                out.write_str("<dl>\n")?;
+                for (label, scope) in ["Principal", "Action", "Resource"].iter().zip(&doc.scope) {
+                    writeln!(
+                        out,
+                        "<dt>{label}</dt><dd><code>{}</code></dd>",
+                        escape_html(scope)
+                    )?;
+                }
+                for (key, value) in &doc.annotations {
+                    writeln!(
+                        out,
+                        "<dt>Annotation</dt><dd><code>@{}(\"{}\")</code></dd>",
+                        escape_html(key),
+                        escape_html(&value.escape_debug().to_string())
+                    )?;
+                }
+                if let Some(links) = &doc.links {
+                    for id in links {
+                        writeln!(
+                            out,
+                            "<dt>Linked policy</dt><dd><code>{}</code></dd>",
+                            escape_html(id)
+                        )?;
+                    }
+                }
+                out.write_str("</dl>\n")?;
+                if !doc.conditions.is_empty() {
+                    writeln!(
+                        out,
+                        "<pre><code class=\"language-cedar\">{}</code></pre>",
+                        escape_html(&doc.conditions.join("\n"))
+                    )?;
+                }
+                out.write_str("</article>\n")?;
+            }
+            out.write_str("</section>\n")?;
+        }
+        Ok(())
+    }
+
+    /// The key of the annotation policies are grouped by, if any
+    fn grouping_key(&self) -> &str {
+        match &self.grouping {
+            PolicyDocsGrouping::Annotation(key) => key,
+            PolicyDocsGrouping::ResourceType => "",
+        }
+    }
+
+    /// The documented policies, grouped into sorted sections and sorted by
+    /// id within each section
+    fn sections(&self) -> BTreeMap<Section, Vec<PolicyDoc>> {
+        let validator = self
+            .schema
+            .map(|schema| cedar_policy_validator::Validator::new(schema.0.clone()));
+        let mut sections: BTreeMap<Section, Vec<PolicyDoc>> = BTreeMap::new();
+        let statics = self
+            .policies
+            .policies()
+            .filter(|p| p.is_static())
+            .map(|p| (p.ast.template(), p.lossless.est().ok(), None));
+        let templates = self.policies.templates().map(|t| {
+            let mut links = self
+                .policies
+                .policies()
+                .filter(|p| p.template_id() == Some(t.id()))
+                .map(|p| p.id().to_string())
+                .collect::<Vec<_>>();
+            links.sort();
+            (&t.ast, t.lossless.est().ok(), Some(links))
+        });
+        for (t, est, links) in statics.chain(templates) {
+            let doc = self.document(t, est.as_ref(), links);
+            let in_sections = match &self.grouping {
+                PolicyDocsGrouping::ResourceType => resource_type_sections(t, validator.as_ref()),
+                PolicyDocsGrouping::Annotation(key) => {
+                    vec![doc
+                        .annotations
+                        .iter()
+                        .find(|(k, _)| k == key)
+                        .map_or(Section::NoAnnotation, |(_, value)| {
+                            Section::Annotation(value.clone())
+                        })]
+                }
+            };
+            if let Some((last, rest)) = in_sections.split_last() {
+                for section in rest {
+                    sections
+                        .entry(section.clone())
+                        .or_default()
+                        .push(doc.clone());
+                }
+                sections.entry(last.clone()).or_default().push(doc);
+            }
+        }
+        for docs in sections.values_mut() {
+            docs.sort_by(|a, b| a.id.cmp(&b.id));
+        }
+        sections
+    }
+
+    /// Document the static policy or template `t`, preferring its EST, which
+    /// keeps its `when` and `unless` clauses apart
+    fn document(
+        &self,
+        t: &ast::Template,
+        est: Option<&est::Policy>,
+        links: Option<Vec<String>>,
+    ) -> PolicyDoc {
+        let annotations = t
+            .annotations()
+            .map(|(key, value)| (key.to_string(), value.as_ref().to_string()))
+            .collect::<Vec<_>>();
+        let description = annotations
+            .iter()
+            .find(|(key, _)| *key == self.doc_annotation)
+            .map(|(_, value)| value.clone());
+        let (scope, conditions) = est.map_or_else(
+            || {
+                (
+                    [
+                        t.principal_constraint().to_string(),
+                        t.action_constraint().to_string(),
+                        t.resource_constraint().to_string(),
+                    ],
+                    match t.non_scope_constraints().expr_kind() {
+                        ast::ExprKind::Lit(ast::Literal::Bool(true)) => Vec::new(),
+                        _ => vec![format!("when {{ {} }}", t.non_scope_constraints())],
+                    },
+                )
+            },
+            |est| {
+                (
+                    [
+                        est.principal_constraint().to_string(),
+                        est.action_constraint().to_string(),
+                        est.resource_constraint().to_string(),
+                    ],
+                    est.conditions().iter().map(ToString::to_string).collect(),
+                )
+            },
+        );
+        PolicyDoc {
+            id: t.id().to_string(),
+            effect: t.effect(),
+            links,
+            description,
+            annotations: annotations
+                .into_iter()
+                .filter(|(key, _)| *key != self.doc_annotation)
+                .collect(),
+            scope,
+            conditions,
+        }
+    }
+}
+
+/// The resource type sections the static policy or template `t` belongs in
+fn resource_type_sections(
+    t: &ast::Template,
+    validator: Option<&cedar_policy_validator::Validator>,
+) -> Vec<Section> {
+    if let Some(validator) = validator {
+        let types = validator.resource_types_in_scope(t);
+        if types.is_empty() {
+            return vec![Section::NoResourceType];
+        }
+        return types
+            .iter()
+            .map(|ty| Section::ResourceType(ty.to_string()))
+            .collect();
+    }
+    match t.resource_constraint().as_inner() {
+        ast::PrincipalOrResourceConstraint::Is(ty)
+        | ast::PrincipalOrResourceConstraint::IsIn(ty, _) => {
+            vec![Section::ResourceType(ty.to_string())]
+        }
+        ast::PrincipalOrResourceConstraint::Eq(ast::EntityReference::EUID(uid)) => {
+            vec![Section::ResourceType(uid.entity_type().to_string())]
+        }
+        ast::PrincipalOrResourceConstraint::Any
+        | ast::PrincipalOrResourceConstraint::In(_)
+        | ast::PrincipalOrResourceConstraint::Eq(ast::EntityReference::Slot(_)) => {
+            vec![Section::AnyResourceType]
+        }
+    }
+}
+
+/// Escape the characters of `s` which are special in HTML text and attribute
+/// values
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
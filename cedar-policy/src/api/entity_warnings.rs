@@ -0,0 +1,125 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Constructing entities which do not conform to a schema, reporting how they
+//! don't conform as warnings
+//!
+//! During a schema migration, the services producing entity data may lag
+//! behind the schema: entities may have attributes the schema no longer
+//! declares, lack attributes it now requires, or have types it doesn't know
+//! yet. The constructors here build the [`Entities`] anyway, so that
+//! authorization can continue, and return every such finding so that it can
+//! be logged and fixed.
+
+use cedar_policy_core::entities::{EntityJsonParser, TCComputation};
+use cedar_policy_core::extensions::Extensions;
+
+use super::{conformance_errors::EntitySchemaConformanceError, Entities, EntitiesError};
+use super::{Entity, Schema};
+
+impl Entities {
+    /// Create an [`Entities`] object with the given entities, like
+    /// [`Entities::from_entities`] with a `schema`, except that entities which
+    /// do not conform to the schema are kept. Every way in which they do not
+    /// conform (undeclared entity types, undeclared attributes, missing
+    /// required attributes, attributes of the wrong type, and so on) is
+    /// returned as a warning, in order of entity UID.
+    ///
+    /// Action entities declared in `schema` are added, replacing any action
+    /// entities with the same UID in `entities`.
+    ///
+    /// ## Errors
+    /// - [`EntitiesError::Duplicate`] if there are any duplicate entities in `entities`
+    /// - [`EntitiesError::TransitiveClosureError`] if the entity hierarchy has a cycle
+    pub fn from_entities_with_warnings(
+        entities: impl IntoIterator<Item = Entity>,
+        schema: &Schema,
+    ) -> Result<(Self, impl Iterator<Item = EntitySchemaConformanceError>), EntitiesError> {
+        let (entities, warnings) =
+            cedar_policy_core::entities::Entities::from_entities_with_warnings(
+                entities.into_iter().map(|e| e.0),
+                &cedar_policy_validator::CoreSchema::new(&schema.0),
+                TCComputation::ComputeNow,
+                Extensions::all_available(),
+            )?;
+        Ok((Self(entities), warnings.into_iter()))
+    }
+
+    /// Parse an entities JSON file (in [`&str`] form) like
+    /// [`Entities::from_json_str`] with a `schema`, except that entities which
+    /// do not conform to the schema are kept. See
+    /// [`Entities::from_json_value_with_warnings`].
+    pub fn from_json_str_with_warnings(
+        json: &str,
+        schema: &Schema,
+    ) -> Result<(Self, impl Iterator<Item = EntitySchemaConformanceError>), EntitiesError> {
+        let schema = cedar_policy_validator::CoreSchema::new(&schema.0);
+        let (entities, warnings) = EntityJsonParser::new(
+            Some(&schema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_str_with_warnings(json)?;
+        Ok((Self(entities), warnings.into_iter()))
+    }
+
+    /// Parse an entities JSON file (in [`serde_json::Value`] form) like
+    /// [`Entities::from_json_value`] with a `schema`, except that entities
+    /// which do not conform to the schema are kept. Every way in which they do
+    /// not conform is returned as a warning, as for
+    /// [`Entities::from_entities_with_warnings`].
+    ///
+    /// The schema still informs parsing where it can, e.g., allowing
+    /// `__entity` and `__extn` escapes to be implicit. Where it can't
+    /// (attributes of undeclared entity types, undeclared attributes, and
+    /// values of the wrong type), values are parsed as if there were no
+    /// schema.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, Schema};
+    /// # use std::str::FromStr;
+    /// let schema = Schema::from_str("entity User = { name: String, team: String };").unwrap();
+    /// let (entities, warnings) = Entities::from_json_value_with_warnings(
+    ///     serde_json::json!([
+    ///         // `team` is missing and `department` is no longer declared
+    ///         { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice", "department": "ops" }, "parents": [] },
+    ///     ]),
+    ///     &schema,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(entities.iter().count(), 1);
+    /// assert_eq!(warnings.count(), 2);
+    /// ```
+    ///
+    /// ## Errors
+    /// - [`EntitiesError::Deserialization`] if the JSON is malformed, or a
+    ///   value can't be parsed even without the schema
+    /// - [`EntitiesError::Duplicate`] if there are any duplicate entities
+    /// - [`EntitiesError::TransitiveClosureError`] if the entity hierarchy has a cycle
+    pub fn from_json_value_with_warnings(
+        json: serde_json::Value,
+        schema: &Schema,
+    ) -> Result<(Self, impl Iterator<Item = EntitySchemaConformanceError>), EntitiesError> {
+        let schema = cedar_policy_validator::CoreSchema::new(&schema.0);
+        let (entities, warnings) = EntityJsonParser::new(
+            Some(&schema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_value_with_warnings(json)?;
+        Ok((Self(entities), warnings.into_iter()))
+    }
+}
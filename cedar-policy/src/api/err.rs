@@ -16,17 +16,19 @@
 
 //! This module defines the publicly exported error types.
 
-use crate::{EntityUid, PolicyId};
+use crate::{EntityTypeName, EntityUid, PolicyId};
 pub use cedar_policy_core::ast::{
     expression_construction_errors, restricted_expr_errors, ContainsUnknown,
     ExpressionConstructionError, PartialValueToValueError, RestrictedExpressionError,
 };
+pub use cedar_policy_core::entities::csv::{CsvError, CsvErrors};
 #[cfg(feature = "entity-manifest")]
 use cedar_policy_core::entities::err::EntitiesError;
 pub use cedar_policy_core::evaluator::{evaluation_errors, EvaluationError};
 pub use cedar_policy_core::extensions::{
     extension_function_lookup_errors, ExtensionFunctionLookupError,
 };
+pub use cedar_policy_core::limits::LimitError;
 use cedar_policy_core::{ast, authorizer, est};
 pub use cedar_policy_validator::cedar_schema::{schema_warnings, SchemaWarning};
 #[cfg(feature = "entity-manifest")]
@@ -40,7 +42,7 @@ use miette::Diagnostic;
 use ref_cast::RefCast;
 use smol_str::SmolStr;
 use thiserror::Error;
-use to_cedar_syntax_errors::NameCollisionsError;
+use to_cedar_syntax_errors::{NameCollisionsError, UnsupportedActionAttributeError};
 
 #[cfg(feature = "entity-manifest")]
 use super::ValidationResult;
@@ -80,20 +82,24 @@ pub enum AuthorizationError {
 
 /// Error subtypes for [`AuthorizationError`]
 pub mod authorization_errors {
-    use crate::{EvaluationError, PolicyId};
+    use crate::{EntityUid, EvaluationError, PolicyId, SlotId};
     use cedar_policy_core::{ast, authorizer};
     use miette::Diagnostic;
     use ref_cast::RefCast;
+    use std::collections::HashMap;
     use thiserror::Error;
 
     /// An error occurred when evaluating a policy
     #[derive(Debug, Diagnostic, PartialEq, Eq, Error, Clone)]
-    #[error("error while evaluating policy `{id}`: {error}")]
+    #[diagnostic(forward(error))]
+    #[error("error while evaluating policy `{id}`{}: {error}", .link.as_ref().map(|l| format!(" ({l})")).unwrap_or_default())]
     pub struct PolicyEvaluationError {
         /// Id of the policy with an error
         id: ast::PolicyID,
+        /// Template and slot values of the policy, if it is a template-linked
+        /// policy
+        link: Option<ast::LinkBindings>,
         /// Underlying evaluation error
-        #[diagnostic(transparent)]
         error: EvaluationError,
     }
 
@@ -103,6 +109,25 @@ pub mod authorization_errors {
             PolicyId::ref_cast(&self.id)
         }
 
+        /// If the erroring policy is a template-linked policy, get the
+        /// [`PolicyId`] of the template it was linked from
+        pub fn template_id(&self) -> Option<&PolicyId> {
+            self.link
+                .as_ref()
+                .map(|link| PolicyId::ref_cast(&link.template_id))
+        }
+
+        /// If the erroring policy is a template-linked policy, get the values
+        /// of its slots
+        pub fn template_links(&self) -> Option<HashMap<SlotId, EntityUid>> {
+            self.link.as_ref().map(|link| {
+                link.values
+                    .iter()
+                    .map(|(slot, euid)| ((*slot).into(), euid.clone().into()))
+                    .collect()
+            })
+        }
+
         /// Get the underlying [`EvaluationError`]
         pub fn inner(&self) -> &EvaluationError {
             &self.error
@@ -118,8 +143,8 @@ pub mod authorization_errors {
     impl From<authorizer::AuthorizationError> for PolicyEvaluationError {
         fn from(e: authorizer::AuthorizationError) -> Self {
             match e {
-                authorizer::AuthorizationError::PolicyEvaluationError { id, error } => {
-                    Self { id, error }
+                authorizer::AuthorizationError::PolicyEvaluationError { id, link, error } => {
+                    Self { id, link, error }
                 }
             }
         }
@@ -178,6 +203,10 @@ pub enum ToCedarSchemaError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     NameCollisions(#[from] to_cedar_syntax_errors::NameCollisionsError),
+    /// An action attribute has a value the Cedar syntax can't express
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnsupportedActionAttribute(#[from] to_cedar_syntax_errors::UnsupportedActionAttributeError),
 }
 
 /// Error subtypes for [`ToCedarSchemaError`]
@@ -199,6 +228,27 @@ pub mod to_cedar_syntax_errors {
             self.0.names()
         }
     }
+
+    /// An action attribute has a value the Cedar syntax can't express
+    #[derive(Debug, Error, Diagnostic)]
+    #[repr(transparent)]
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    pub struct UnsupportedActionAttributeError(
+        pub(super) cedar_policy_validator::cedar_schema::fmt::UnsupportedActionAttributeError,
+    );
+
+    impl UnsupportedActionAttributeError {
+        /// Get the (fully-qualified) name of the action with the attribute
+        pub fn action(&self) -> &str {
+            self.0.action()
+        }
+
+        /// Get the name of the attribute
+        pub fn attr(&self) -> &str {
+            self.0.attr()
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -210,6 +260,9 @@ impl From<cedar_policy_validator::cedar_schema::fmt::ToCedarSchemaSyntaxError>
             cedar_policy_validator::cedar_schema::fmt::ToCedarSchemaSyntaxError::NameCollisions(
                 name_collision_err,
             ) => NameCollisionsError(name_collision_err).into(),
+            cedar_policy_validator::cedar_schema::fmt::ToCedarSchemaSyntaxError::UnsupportedActionAttribute(
+                err,
+            ) => UnsupportedActionAttributeError(err).into(),
         }
     }
 }
@@ -258,6 +311,44 @@ impl From<cedar_policy_validator::CedarSchemaError> for CedarSchemaError {
     }
 }
 
+/// Errors reading entities in the Cedar entity text format with
+/// [`crate::Entities::from_cedar_str`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum EntitiesTextError {
+    /// The text is not valid Cedar entity text
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] ParseErrors),
+    /// The value of an attribute or tag could not be evaluated
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Evaluation(#[from] EntityAttrEvaluationError),
+    /// The entities are not a valid entity hierarchy, for instance because
+    /// an entity is declared twice or doesn't conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] entities_errors::EntitiesError),
+}
+
+/// Errors reading entities from CSV data with
+/// [`crate::Entities::from_csv_str`], or from Arrow data with
+/// `Entities::from_record_batches` and `Entities::from_parquet`
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum EntitiesCsvError {
+    /// The rows of the data do not declare valid entities, or the mapping
+    /// does not match the data
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Csv(#[from] CsvErrors),
+    /// The entities could not be combined with the action entities of the
+    /// schema into an entity hierarchy
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] entities_errors::EntitiesError),
+}
+
 /// Error when evaluating an entity attribute or tag
 #[derive(Debug, Diagnostic, Error)]
 #[error("in {} `{attr_or_tag}` of `{uid}`: {err}", if *.was_attr { "attribute" } else { "tag" })]
@@ -347,6 +438,10 @@ pub mod validation_errors;
 /// An error generated by the validator when it finds a potential problem in a
 /// policy.
 #[derive(Debug, Clone, Error, Diagnostic)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[non_exhaustive]
 pub enum ValidationError {
     /// A policy contains an entity type that is not declared in the schema.
@@ -377,6 +472,13 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     UnsafeAttributeAccess(#[from] validation_errors::UnsafeAttributeAccess),
+    /// The typechecker detected an access to an attribute of an action (e.g.,
+    /// `action.category`) which the action does not declare in the schema.
+    /// The error names every action the access may be on which lacks the
+    /// attribute.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UndefinedActionAttribute(#[from] validation_errors::UndefinedActionAttribute),
     /// The typechecker could not conclude that an access to an optional
     /// attribute was safe.
     #[error(transparent)]
@@ -421,6 +523,45 @@ pub enum ValidationError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     InternalInvariantViolation(#[from] validation_errors::InternalInvariantViolation),
+    /// The `@validation` annotation of a policy does not name a validation
+    /// mode. The annotation may only be `"strict"` or `"permissive"`.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidValidationModeAnnotation(#[from] validation_errors::InvalidValidationModeAnnotation),
+    /// The `@expires` annotation of a policy is not a date of the form
+    /// `YYYY-MM-DD`. Only reported if the [`crate::Validator`] has
+    /// [`crate::ExpirationRules`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidExpiresAnnotation(#[from] validation_errors::InvalidExpiresAnnotation),
+    /// The date in the `@expires` annotation of a policy has passed, and the
+    /// validator's [`crate::ExpirationRules`] report expired policies as
+    /// errors.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ExpiredPolicy(#[from] validation_errors::ExpiredPolicy),
+    /// A policy references an entity type or action outside the namespaces
+    /// allowed by [`crate::Validator::with_allowed_namespaces`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DisallowedNamespace(#[from] validation_errors::DisallowedNamespace),
+    /// Integer arithmetic on constants (e.g., `9223372036854775807 + 1`)
+    /// is guaranteed to overflow, so evaluating the policy would always
+    /// fail.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    ArithmeticOverflow(#[from] validation_errors::ArithmeticOverflow),
+    /// Integer division or remainder by a constant zero (e.g., `x / 0`),
+    /// so evaluating the policy would always fail.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DivisionByZero(#[from] validation_errors::DivisionByZero),
+    /// A `like` pattern has a malformed character class (e.g., `\[z-a]`) or
+    /// repetition (e.g., `a\{3,1}`). Patterns parsed from policy text are
+    /// always well-formed, but patterns from JSON policies may not be.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidPattern(#[from] validation_errors::InvalidPattern),
     /// Entity level violation
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -437,6 +578,7 @@ impl ValidationError {
             Self::UnexpectedType(e) => e.policy_id(),
             Self::IncompatibleTypes(e) => e.policy_id(),
             Self::UnsafeAttributeAccess(e) => e.policy_id(),
+            Self::UndefinedActionAttribute(e) => e.policy_id(),
             Self::UnsafeOptionalAttributeAccess(e) => e.policy_id(),
             Self::UnsafeTagAccess(e) => e.policy_id(),
             Self::NoTagsAllowed(e) => e.policy_id(),
@@ -447,9 +589,57 @@ impl ValidationError {
             Self::NonLitExtConstructor(e) => e.policy_id(),
             Self::HierarchyNotRespected(e) => e.policy_id(),
             Self::InternalInvariantViolation(e) => e.policy_id(),
+            Self::InvalidValidationModeAnnotation(e) => e.policy_id(),
+            Self::InvalidExpiresAnnotation(e) => e.policy_id(),
+            Self::ExpiredPolicy(e) => e.policy_id(),
+            Self::DisallowedNamespace(e) => e.policy_id(),
+            Self::ArithmeticOverflow(e) => e.policy_id(),
+            Self::DivisionByZero(e) => e.policy_id(),
+            Self::InvalidPattern(e) => e.policy_id(),
             Self::EntityDerefLevelViolation(e) => e.policy_id(),
         }
     }
+
+    /// Get a copy of this error with literal values replaced by placeholders,
+    /// so that it can be logged without leaking sensitive data.
+    ///
+    /// String and numeric literals in the attached source code are masked
+    /// with `*`, and messages that may quote them are replaced. The kind of
+    /// error, policy ID, types, and source span are kept.
+    #[must_use]
+    pub fn redacted(&self) -> Self {
+        match self {
+            Self::UnrecognizedEntityType(e) => Self::UnrecognizedEntityType(e.redacted()),
+            Self::UnrecognizedActionId(e) => Self::UnrecognizedActionId(e.redacted()),
+            Self::InvalidActionApplication(e) => Self::InvalidActionApplication(e.redacted()),
+            Self::UnexpectedType(e) => Self::UnexpectedType(e.redacted()),
+            Self::IncompatibleTypes(e) => Self::IncompatibleTypes(e.redacted()),
+            Self::UnsafeAttributeAccess(e) => Self::UnsafeAttributeAccess(e.redacted()),
+            Self::UndefinedActionAttribute(e) => Self::UndefinedActionAttribute(e.redacted()),
+            Self::UnsafeOptionalAttributeAccess(e) => {
+                Self::UnsafeOptionalAttributeAccess(e.redacted())
+            }
+            Self::UnsafeTagAccess(e) => Self::UnsafeTagAccess(e.redacted()),
+            Self::NoTagsAllowed(e) => Self::NoTagsAllowed(e.redacted()),
+            Self::UndefinedFunction(e) => Self::UndefinedFunction(e.redacted()),
+            Self::WrongNumberArguments(e) => Self::WrongNumberArguments(e.redacted()),
+            Self::FunctionArgumentValidation(e) => Self::FunctionArgumentValidation(e.redacted()),
+            Self::EmptySetForbidden(e) => Self::EmptySetForbidden(e.redacted()),
+            Self::NonLitExtConstructor(e) => Self::NonLitExtConstructor(e.redacted()),
+            Self::HierarchyNotRespected(e) => Self::HierarchyNotRespected(e.redacted()),
+            Self::InternalInvariantViolation(e) => Self::InternalInvariantViolation(e.redacted()),
+            Self::InvalidValidationModeAnnotation(e) => {
+                Self::InvalidValidationModeAnnotation(e.redacted())
+            }
+            Self::InvalidExpiresAnnotation(e) => Self::InvalidExpiresAnnotation(e.redacted()),
+            Self::ExpiredPolicy(e) => Self::ExpiredPolicy(e.redacted()),
+            Self::DisallowedNamespace(e) => Self::DisallowedNamespace(e.redacted()),
+            Self::ArithmeticOverflow(e) => Self::ArithmeticOverflow(e.redacted()),
+            Self::DivisionByZero(e) => Self::DivisionByZero(e.redacted()),
+            Self::InvalidPattern(e) => Self::InvalidPattern(e.redacted()),
+            Self::EntityDerefLevelViolation(e) => Self::EntityDerefLevelViolation(e.redacted()),
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -474,6 +664,9 @@ impl From<cedar_policy_validator::ValidationError> for ValidationError {
             cedar_policy_validator::ValidationError::UnsafeAttributeAccess(e) => {
                 Self::UnsafeAttributeAccess(e.into())
             }
+            cedar_policy_validator::ValidationError::UndefinedActionAttribute(e) => {
+                Self::UndefinedActionAttribute(e.into())
+            }
             cedar_policy_validator::ValidationError::UnsafeOptionalAttributeAccess(e) => {
                 Self::UnsafeOptionalAttributeAccess(e.into())
             }
@@ -504,6 +697,27 @@ impl From<cedar_policy_validator::ValidationError> for ValidationError {
             cedar_policy_validator::ValidationError::InternalInvariantViolation(e) => {
                 Self::InternalInvariantViolation(e.into())
             }
+            cedar_policy_validator::ValidationError::InvalidValidationModeAnnotation(e) => {
+                Self::InvalidValidationModeAnnotation(e.into())
+            }
+            cedar_policy_validator::ValidationError::InvalidExpiresAnnotation(e) => {
+                Self::InvalidExpiresAnnotation(e.into())
+            }
+            cedar_policy_validator::ValidationError::ExpiredPolicy(e) => {
+                Self::ExpiredPolicy(e.into())
+            }
+            cedar_policy_validator::ValidationError::DisallowedNamespace(e) => {
+                Self::DisallowedNamespace(e.into())
+            }
+            cedar_policy_validator::ValidationError::ArithmeticOverflow(e) => {
+                Self::ArithmeticOverflow(e.into())
+            }
+            cedar_policy_validator::ValidationError::DivisionByZero(e) => {
+                Self::DivisionByZero(e.into())
+            }
+            cedar_policy_validator::ValidationError::InvalidPattern(e) => {
+                Self::InvalidPattern(e.into())
+            }
             #[cfg(feature = "level-validate")]
             cedar_policy_validator::ValidationError::EntityDerefLevelViolation(e) => {
                 Self::EntityDerefLevelViolation(e.into())
@@ -520,6 +734,10 @@ pub mod validation_warnings;
 /// specific to that warning. Marked as `non_exhaustive` to allow adding
 /// additional warnings in the future as a non-breaking change.
 #[derive(Debug, Clone, Error, Diagnostic)]
+#[cfg_attr(
+    feature = "serde-validation",
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[non_exhaustive]
 pub enum ValidationWarning {
     /// A string contains a mix of characters for different scripts (e.g., latin
@@ -553,6 +771,34 @@ pub enum ValidationWarning {
     #[diagnostic(transparent)]
     #[error(transparent)]
     ImpossiblePolicy(#[from] validation_warnings::ImpossiblePolicy),
+    /// A policy uses an attribute annotated as sensitive (`@pii`) in the
+    /// schema in a way that is not allowed, e.g., in the condition of a
+    /// `permit` policy or in an equality comparison with a string literal.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    SensitiveAttributeUse(#[from] validation_warnings::SensitiveAttributeUse),
+    /// A policy references an entity type, attribute, or action annotated as
+    /// deprecated (`@deprecated`) in the schema. If the annotation has a
+    /// value, it names the replacement.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    DeprecatedSchemaElementUse(#[from] validation_warnings::DeprecatedSchemaElementUse),
+    /// A policy is validated in a different mode than the rest of the policy
+    /// set, as requested by its `@validation` annotation.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    ValidationModeOverride(#[from] validation_warnings::ValidationModeOverride),
+    /// A policy will expire within the warning period of the validator's
+    /// [`crate::ExpirationRules`], or has expired and the rules do not report
+    /// expired policies as errors.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    PolicyExpiring(#[from] validation_warnings::PolicyExpiring),
+    /// An `==` or `in` compares entities whose types can never coincide
+    /// according to the schema, so it always evaluates to `false`.
+    #[diagnostic(transparent)]
+    #[error(transparent)]
+    IncompatibleEntityComparison(#[from] validation_warnings::IncompatibleEntityComparison),
 }
 
 impl ValidationWarning {
@@ -565,6 +811,32 @@ impl ValidationWarning {
             Self::MixedScriptIdentifier(w) => w.policy_id(),
             Self::ConfusableIdentifier(w) => w.policy_id(),
             Self::ImpossiblePolicy(w) => w.policy_id(),
+            Self::SensitiveAttributeUse(w) => w.policy_id(),
+            Self::DeprecatedSchemaElementUse(w) => w.policy_id(),
+            Self::ValidationModeOverride(w) => w.policy_id(),
+            Self::PolicyExpiring(w) => w.policy_id(),
+            Self::IncompatibleEntityComparison(w) => w.policy_id(),
+        }
+    }
+
+    /// Get a copy of this warning with literal values replaced by
+    /// placeholders (see [`ValidationError::redacted`])
+    #[must_use]
+    pub fn redacted(&self) -> Self {
+        match self {
+            Self::MixedScriptString(w) => Self::MixedScriptString(w.redacted()),
+            Self::BidiCharsInString(w) => Self::BidiCharsInString(w.redacted()),
+            Self::BidiCharsInIdentifier(w) => Self::BidiCharsInIdentifier(w.redacted()),
+            Self::MixedScriptIdentifier(w) => Self::MixedScriptIdentifier(w.redacted()),
+            Self::ConfusableIdentifier(w) => Self::ConfusableIdentifier(w.redacted()),
+            Self::ImpossiblePolicy(w) => Self::ImpossiblePolicy(w.redacted()),
+            Self::SensitiveAttributeUse(w) => Self::SensitiveAttributeUse(w.redacted()),
+            Self::DeprecatedSchemaElementUse(w) => Self::DeprecatedSchemaElementUse(w.redacted()),
+            Self::ValidationModeOverride(w) => Self::ValidationModeOverride(w.redacted()),
+            Self::PolicyExpiring(w) => Self::PolicyExpiring(w.redacted()),
+            Self::IncompatibleEntityComparison(w) => {
+                Self::IncompatibleEntityComparison(w.redacted())
+            }
         }
     }
 }
@@ -591,6 +863,21 @@ impl From<cedar_policy_validator::ValidationWarning> for ValidationWarning {
             cedar_policy_validator::ValidationWarning::ImpossiblePolicy(w) => {
                 Self::ImpossiblePolicy(w.into())
             }
+            cedar_policy_validator::ValidationWarning::SensitiveAttributeUse(w) => {
+                Self::SensitiveAttributeUse(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::DeprecatedSchemaElementUse(w) => {
+                Self::DeprecatedSchemaElementUse(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::ValidationModeOverride(w) => {
+                Self::ValidationModeOverride(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::PolicyExpiring(w) => {
+                Self::PolicyExpiring(w.into())
+            }
+            cedar_policy_validator::ValidationWarning::IncompatibleEntityComparison(w) => {
+                Self::IncompatibleEntityComparison(w.into())
+            }
         }
     }
 }
@@ -599,15 +886,21 @@ impl From<cedar_policy_validator::ValidationWarning> for ValidationWarning {
 pub mod policy_set_errors {
     use super::Error;
     use crate::PolicyId;
-    use cedar_policy_core::ast;
-    use miette::Diagnostic;
+    use cedar_policy_core::{ast, impl_diagnostic_from_source_loc_field, parser::Loc};
+    use miette::{Diagnostic, LabeledSpan};
 
     /// There was a duplicate [`PolicyId`] encountered in either the set of
     /// templates or the set of policies.
-    #[derive(Debug, Diagnostic, Error)]
+    ///
+    /// Both the existing and the new definition are labeled, if their
+    /// source locations are known. When they come from different sources,
+    /// the existing one is reported as a related diagnostic.
+    #[derive(Debug, Error)]
     #[error("duplicate template or policy id `{id}`")]
     pub struct AlreadyDefined {
         pub(crate) id: PolicyId,
+        pub(crate) existing: Option<ExistingDefinition>,
+        pub(crate) new_loc: Option<Loc>,
     }
 
     impl AlreadyDefined {
@@ -615,6 +908,72 @@ pub mod policy_set_errors {
         pub fn duplicate_id(&self) -> &PolicyId {
             &self.id
         }
+
+        /// Is the existing definition in the same source as the new one, so
+        /// that both can be labeled in one snippet?
+        fn same_source(&self) -> bool {
+            match (&self.existing, &self.new_loc) {
+                (Some(existing), Some(new_loc)) => {
+                    std::sync::Arc::ptr_eq(&existing.loc.src, &new_loc.src)
+                }
+                _ => false,
+            }
+        }
+    }
+
+    impl Diagnostic for AlreadyDefined {
+        fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+            match (&self.new_loc, &self.existing) {
+                (Some(new_loc), _) => new_loc.source_code(),
+                (None, Some(existing)) => existing.loc.source_code(),
+                (None, None) => None,
+            }
+        }
+
+        fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+            let existing = self
+                .existing
+                .as_ref()
+                .filter(|_| self.new_loc.is_none() || self.same_source())
+                .map(|existing| {
+                    LabeledSpan::new_with_span(Some("first defined here".into()), existing.loc.span)
+                });
+            let new = self.new_loc.as_ref().map(|new_loc| {
+                LabeledSpan::new_with_span(Some("defined again here".into()), new_loc.span)
+            });
+            let labels = existing.into_iter().chain(new).collect::<Vec<_>>();
+            if labels.is_empty() {
+                None
+            } else {
+                Some(Box::new(labels.into_iter()))
+            }
+        }
+
+        fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+            match &self.existing {
+                Some(existing) if self.new_loc.is_some() && !self.same_source() => {
+                    Some(Box::new(std::iter::once(existing as &dyn Diagnostic)))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// The existing definition of a duplicate [`PolicyId`], reported as a
+    /// related diagnostic of [`AlreadyDefined`]
+    #[derive(Debug, Error)]
+    #[error("`{id}` was first defined here")]
+    pub struct ExistingDefinition {
+        pub(crate) id: PolicyId,
+        pub(crate) loc: Loc,
+    }
+
+    impl Diagnostic for ExistingDefinition {
+        impl_diagnostic_from_source_loc_field!(loc);
+
+        fn severity(&self) -> Option<miette::Severity> {
+            Some(miette::Severity::Advice)
+        }
     }
 
     /// Error when linking a template
@@ -816,11 +1175,18 @@ pub enum PolicySetError {
 impl From<ast::PolicySetError> for PolicySetError {
     fn from(e: ast::PolicySetError) -> Self {
         match e {
-            ast::PolicySetError::Occupied { id } => {
-                Self::AlreadyDefined(policy_set_errors::AlreadyDefined {
+            ast::PolicySetError::Occupied {
+                id,
+                existing_loc,
+                new_loc,
+            } => Self::AlreadyDefined(policy_set_errors::AlreadyDefined {
+                id: PolicyId::new(id.clone()),
+                existing: existing_loc.map(|loc| policy_set_errors::ExistingDefinition {
                     id: PolicyId::new(id),
-                })
-            }
+                    loc,
+                }),
+                new_loc,
+            }),
         }
     }
 }
@@ -866,6 +1232,11 @@ impl ParseErrors {
     pub fn iter(&self) -> impl Iterator<Item = &ParseError> {
         self.0.iter().map(ParseError::ref_cast)
     }
+
+    /// Add all of the errors in `other` after the errors in `self`
+    pub(crate) fn append(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
 }
 
 /// Errors that can occur when parsing policies or expressions.
@@ -881,6 +1252,17 @@ pub struct ParseError {
     inner: cedar_policy_core::parser::err::ParseError,
 }
 
+impl ParseError {
+    /// If this error was raised because the input exceeds a configured
+    /// [`crate::ResourceLimits`], get the limit that was exceeded
+    pub fn limit_error(&self) -> Option<&LimitError> {
+        match &self.inner {
+            cedar_policy_core::parser::err::ParseError::Limit(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 /// Errors that can happen when getting the JSON representation of a policy
 #[derive(Debug, Diagnostic, Error)]
 pub enum PolicyToJsonError {
@@ -936,6 +1318,232 @@ pub mod policy_to_json_errors {
     }
 }
 
+/// Errors creating, serializing, or verifying a [`crate::PolicyBundle`]
+#[derive(Debug, Diagnostic, Error)]
+pub enum BundleError {
+    /// The signature of a [`crate::SignedBundle`] is not valid for its
+    /// contents. Either the bundle was modified after it was signed, or it was
+    /// signed with a different key.
+    #[error("policy bundle signature is not valid")]
+    InvalidSignature(#[source] BundleSignatureError),
+    /// The [`crate::BundleSigner`] failed to sign the bundle
+    #[error("failed to sign policy bundle")]
+    Signing(#[source] BundleSignatureError),
+    /// The bundle could not be serialized or deserialized
+    #[error("malformed policy bundle")]
+    Malformed(#[source] serde_json::Error),
+    /// The bundle archive was written in a format version this version of
+    /// Cedar does not support
+    #[error("unsupported policy bundle format version {0}")]
+    UnsupportedFormatVersion(u32),
+    /// A section of the bundle archive does not match its hash in the
+    /// manifest
+    #[error("policy bundle section `{0}` does not match its hash in the manifest")]
+    IntegrityCheckFailed(String),
+    /// Error converting the policy set in the bundle from or to JSON
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    PolicySet(#[from] PolicySetError),
+    /// Error converting the schema in the bundle from or to JSON
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Schema(#[from] SchemaError),
+}
+
+/// Error from a [`crate::BundleSigner`] or [`crate::BundleVerifier`]
+#[derive(Debug, Diagnostic, Error)]
+#[error("{message}")]
+pub struct BundleSignatureError {
+    message: String,
+    #[source]
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl BundleSignatureError {
+    /// Construct an error with this message
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Construct an error with this message, caused by `source`, e.g., an
+    /// error from a cryptography library
+    pub fn with_source(
+        message: impl Into<String>,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+}
+
+/// Errors typechecking an expression with
+/// [`crate::Validator::typecheck_expression`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum ExpressionTypecheckError {
+    /// The action of the environment is not declared in the schema
+    #[error("action `{0}` is not declared in the schema")]
+    UndeclaredAction(EntityUid),
+    /// The context type of the environment refers to an extension type which
+    /// does not exist
+    #[error("unknown extension type `{0}` in the context type")]
+    UnknownExtensionType(String),
+    /// The expression failed to typecheck. Each problem has its own error.
+    #[error("expression failed to typecheck")]
+    Validation(#[related] Vec<ValidationError>),
+    /// The expression typechecks, but its type cannot be represented as a
+    /// [`crate::SchemaType`], e.g., because it is a union of entity types
+    #[error("the type of the expression, `{0}`, cannot be declared in a schema")]
+    UnsupportedType(String),
+}
+
+/// Errors renaming with [`crate::Rename::apply`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum RenameError {
+    /// The entity type to rename is not declared in the schema
+    #[error("entity type `{0}` is not declared in the schema")]
+    UndeclaredEntityType(EntityTypeName),
+    /// The action to rename is not declared in the schema
+    #[error("action `{0}` is not declared in the schema")]
+    UndeclaredAction(EntityUid),
+    /// The attribute to rename is not declared in the schema
+    #[error("entity type `{entity_type}` has no attribute `{attr}` in the schema")]
+    UndeclaredAttribute {
+        /// The entity type
+        entity_type: EntityTypeName,
+        /// The attribute
+        attr: String,
+    },
+    /// The new name is already declared in the schema
+    #[error("`{0}` is already declared in the schema")]
+    AlreadyDeclared(String),
+    /// An entity type can only be renamed within its namespace
+    #[error("cannot rename entity type `{from}` to `{to}` in a different namespace")]
+    #[diagnostic(help("rename the entity type to a name in its own namespace"))]
+    NamespaceChange {
+        /// The entity type to rename
+        from: EntityTypeName,
+        /// Its new name
+        to: EntityTypeName,
+    },
+    /// The attributes of the entity type are declared by a common type, which
+    /// may be shared with other types
+    #[error("the attributes of entity type `{0}` are declared by a common type")]
+    #[diagnostic(help(
+        "declare the attributes of the entity type directly, or rename the attribute of the common type"
+    ))]
+    SharedAttributes(EntityTypeName),
+    /// The schema, or the renamed schema, is not valid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Schema(#[from] SchemaError),
+    /// The policies failed to parse
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] ParseErrors),
+}
+
+impl From<cedar_policy_validator::RenameError> for RenameError {
+    fn from(err: cedar_policy_validator::RenameError) -> Self {
+        use cedar_policy_validator::RenameError as E;
+        match err {
+            E::UndeclaredEntityType(ty) => Self::UndeclaredEntityType(EntityTypeName(ty)),
+            E::UndeclaredAction(uid) => Self::UndeclaredAction(EntityUid(uid)),
+            E::UndeclaredAttribute { entity_type, attr } => Self::UndeclaredAttribute {
+                entity_type: EntityTypeName(entity_type),
+                attr: attr.to_string(),
+            },
+            E::AlreadyDeclared(name) => Self::AlreadyDeclared(name),
+            E::NamespaceChange { from, to } => Self::NamespaceChange {
+                from: EntityTypeName(from),
+                to: EntityTypeName(to),
+            },
+            E::SharedAttributes(ty) => Self::SharedAttributes(EntityTypeName(ty)),
+        }
+    }
+}
+
+/// Errors loading a policy set with [`crate::PolicySet::from_directory`]
+#[cfg(feature = "directory")]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum PolicyDirectoryError {
+    /// The glob pattern selecting the files to load is not valid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    InvalidGlob(#[from] policy_directory_errors::InvalidGlobError),
+    /// One or more files could not be loaded. Every file which could not be
+    /// loaded has its own error.
+    #[error("failed to load {} policy file(s)", .0.len())]
+    Files(#[related] Vec<policy_directory_errors::PolicyFileError>),
+}
+
+/// Error subtypes for [`PolicyDirectoryError`]
+#[cfg(feature = "directory")]
+pub mod policy_directory_errors {
+    use std::path::{Path, PathBuf};
+
+    use miette::Diagnostic;
+    use thiserror::Error;
+
+    use super::{ParseErrors, PolicySetError};
+
+    /// The glob pattern selecting the files to load is not valid
+    #[derive(Debug, Diagnostic, Error)]
+    #[error(transparent)]
+    pub struct InvalidGlobError {
+        pub(crate) err: globset::Error,
+    }
+
+    /// Error loading one file of a policy directory
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("failed to load `{}`: {kind}", .path.display())]
+    #[diagnostic(forward(kind))]
+    pub struct PolicyFileError {
+        pub(crate) path: PathBuf,
+        pub(crate) kind: PolicyFileErrorKind,
+    }
+
+    impl PolicyFileError {
+        /// The path of the file
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        /// What went wrong loading the file
+        pub fn kind(&self) -> &PolicyFileErrorKind {
+            &self.kind
+        }
+    }
+
+    /// What went wrong loading a file of a policy directory
+    #[derive(Debug, Diagnostic, Error)]
+    #[non_exhaustive]
+    pub enum PolicyFileErrorKind {
+        /// The file or directory could not be read
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        /// The file does not contain valid Cedar policies
+        #[error(transparent)]
+        #[diagnostic(transparent)]
+        Parse(#[from] ParseErrors),
+        /// The file does not contain a JSON list of template links
+        #[error("invalid template links: {0}")]
+        Links(serde_json::Error),
+        /// The policies or links in the file could not be added to the
+        /// policy set, for instance because their ids are already used
+        #[error(transparent)]
+        #[diagnostic(transparent)]
+        PolicySet(#[from] PolicySetError),
+    }
+}
+
 /// Error when converting a policy or template from JSON format
 #[derive(Debug, Diagnostic, Error)]
 #[error("error deserializing a policy/template from JSON")]
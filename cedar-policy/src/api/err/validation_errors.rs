@@ -18,7 +18,7 @@ use miette::Diagnostic;
 use ref_cast::RefCast;
 use thiserror::Error;
 
-use crate::PolicyId;
+use crate::{EntityTypeName, EntityUid, PolicyId};
 
 // Required for doc link to `ValidationError` without qualifying it with
 // `crate`, but not used otherwise, so non-doc builds warned about unused
@@ -35,6 +35,8 @@ use crate::ValidationError;
 macro_rules! wrap_core_error {
     ($s:ident) => {
         #[derive(Debug, Clone, Error, Diagnostic)]
+        #[cfg_attr(feature = "serde-validation", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde-validation", serde(transparent))]
         #[error(transparent)]
         #[diagnostic(transparent)]
         #[doc=concat!("Structure containing details about a [`ValidationError::", stringify!($s), "`].")]
@@ -45,6 +47,12 @@ macro_rules! wrap_core_error {
             pub fn policy_id(&self) -> &PolicyId {
                 PolicyId::ref_cast(&self.0.policy_id)
             }
+
+            /// Get a copy of this error with literal values replaced by placeholders.
+            #[must_use]
+            pub(crate) fn redacted(&self) -> Self {
+                Self(self.0.redacted())
+            }
         }
 
         #[doc(hidden)]
@@ -62,6 +70,7 @@ wrap_core_error!(InvalidActionApplication);
 wrap_core_error!(UnexpectedType);
 wrap_core_error!(IncompatibleTypes);
 wrap_core_error!(UnsafeAttributeAccess);
+wrap_core_error!(UndefinedActionAttribute);
 wrap_core_error!(UnsafeOptionalAttributeAccess);
 wrap_core_error!(UnsafeTagAccess);
 wrap_core_error!(NoTagsAllowed);
@@ -73,3 +82,35 @@ wrap_core_error!(EntityDerefLevelViolation);
 wrap_core_error!(EmptySetForbidden);
 wrap_core_error!(NonLitExtConstructor);
 wrap_core_error!(InternalInvariantViolation);
+wrap_core_error!(InvalidValidationModeAnnotation);
+wrap_core_error!(InvalidExpiresAnnotation);
+wrap_core_error!(ExpiredPolicy);
+wrap_core_error!(DisallowedNamespace);
+wrap_core_error!(ArithmeticOverflow);
+wrap_core_error!(DivisionByZero);
+wrap_core_error!(InvalidPattern);
+
+impl UndefinedActionAttribute {
+    /// The attribute being accessed
+    pub fn attr(&self) -> &str {
+        &self.0.attr
+    }
+
+    /// The actions the access may be on which don't declare the attribute
+    pub fn actions(&self) -> impl Iterator<Item = &EntityUid> {
+        self.0.actions.iter().map(EntityUid::ref_cast)
+    }
+
+    /// The actions in the schema which do declare the attribute
+    pub fn declared_on(&self) -> impl Iterator<Item = &EntityUid> {
+        self.0.declared_on.iter().map(EntityUid::ref_cast)
+    }
+}
+
+impl DisallowedNamespace {
+    /// The entity type referenced outside the allowed namespaces. For
+    /// actions, this is the action entity type (e.g., `NS::Action`).
+    pub fn entity_type(&self) -> &EntityTypeName {
+        EntityTypeName::ref_cast(&self.0.entity_type)
+    }
+}
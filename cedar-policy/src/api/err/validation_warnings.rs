@@ -35,6 +35,8 @@ use crate::ValidationWarning;
 macro_rules! wrap_core_warning {
     ($s:ident) => {
         #[derive(Debug, Clone, Error, Diagnostic)]
+        #[cfg_attr(feature = "serde-validation", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde-validation", serde(transparent))]
         #[error(transparent)]
         #[diagnostic(transparent)]
         #[doc=concat!("Structure containing details about a [`ValidationWarning::", stringify!($s), "`].")]
@@ -45,6 +47,12 @@ macro_rules! wrap_core_warning {
             pub fn policy_id(&self) -> &PolicyId {
                 PolicyId::ref_cast(&self.0.policy_id)
             }
+
+            /// Get a copy of this warning with literal values replaced by placeholders.
+            #[must_use]
+            pub(crate) fn redacted(&self) -> Self {
+                Self(self.0.redacted())
+            }
         }
 
         #[doc(hidden)]
@@ -62,3 +70,30 @@ wrap_core_warning!(BidiCharsInIdentifier);
 wrap_core_warning!(MixedScriptIdentifier);
 wrap_core_warning!(ConfusableIdentifier);
 wrap_core_warning!(ImpossiblePolicy);
+wrap_core_warning!(SensitiveAttributeUse);
+wrap_core_warning!(DeprecatedSchemaElementUse);
+wrap_core_warning!(ValidationModeOverride);
+wrap_core_warning!(PolicyExpiring);
+wrap_core_warning!(IncompatibleEntityComparison);
+
+impl SensitiveAttributeUse {
+    /// Name of the sensitive attribute
+    pub fn attribute(&self) -> &str {
+        &self.0.attr
+    }
+}
+
+impl PolicyExpiring {
+    /// Days until the policy expires, which is negative if it has expired
+    pub fn days_remaining(&self) -> i64 {
+        self.0.days_remaining
+    }
+}
+
+impl DeprecatedSchemaElementUse {
+    /// What the schema says to use instead of the deprecated element, if
+    /// the `@deprecated` annotation names a replacement
+    pub fn replacement(&self) -> Option<&str> {
+        self.0.replacement.as_deref()
+    }
+}
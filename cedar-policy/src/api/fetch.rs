@@ -0,0 +1,181 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Authorizing requests while fetching only the entities they need
+
+use std::collections::HashSet;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::entities_errors::EntitiesError;
+use super::{Authorizer, Entities, Entity, EntityUid, PolicySet, Request, Response, Schema};
+
+/// Source of entity data for [`Authorizer::is_authorized_with_fetch`], like
+/// a database or a directory service.
+///
+/// Closures taking a slice of [`EntityUid`]s and returning the entities
+/// found are fetchers.
+pub trait EntityFetcher {
+    /// Fetch the entities with the uids `uids`, with all of their attributes,
+    /// tags, and parents. Uids of entities which don't exist are left out of
+    /// the result.
+    fn fetch(
+        &self,
+        uids: &[EntityUid],
+    ) -> Result<Vec<Entity>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+impl<F> EntityFetcher for F
+where
+    F: Fn(&[EntityUid]) -> Result<Vec<Entity>, Box<dyn std::error::Error + Send + Sync>>,
+{
+    fn fetch(
+        &self,
+        uids: &[EntityUid],
+    ) -> Result<Vec<Entity>, Box<dyn std::error::Error + Send + Sync>> {
+        self(uids)
+    }
+}
+
+/// Response to a request authorized with
+/// [`Authorizer::is_authorized_with_fetch`]
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    response: Response,
+    entities: Entities,
+    rounds: usize,
+}
+
+impl FetchResponse {
+    /// The response to the request
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Consume the `FetchResponse`, returning the response to the request
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+
+    /// The entities fetched to reach the decision, with the action entities
+    /// of the schema if one was given, which is the slice of the entity
+    /// store relevant to the request
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// The number of times entities were fetched
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+}
+
+/// Errors authorizing a request with [`Authorizer::is_authorized_with_fetch`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum EntityFetchError {
+    /// The fetcher failed
+    #[error("failed to fetch entities: {0}")]
+    Fetch(Box<dyn std::error::Error + Send + Sync>),
+    /// The fetched entities do not form a valid entity hierarchy, for
+    /// instance because they do not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] EntitiesError),
+}
+
+impl Authorizer {
+    /// Authorize `request` starting with no entity data, fetching entities
+    /// from `fetcher` only when they are needed.
+    ///
+    /// The request is partially evaluated with every entity unknown, and the
+    /// entities the residual policies still depend on are fetched, along
+    /// with their ancestors, so that `in` is decided exactly. This repeats
+    /// until a decision is reached, or until the residuals only depend on
+    /// entities which don't exist, in which case they are evaluated as if
+    /// those entities were absent from a complete entity store. So, the
+    /// decision is the same as with the complete entity store, while only
+    /// the entities it depends on are loaded.
+    ///
+    /// If a `schema` is given, the fetched entities must conform to it, and
+    /// the action entities it declares are used rather than fetched.
+    ///
+    /// When a decision is reached while some policies still depend on
+    /// entities which were not fetched, those policies are reported as
+    /// errors in the response, as with [`super::PartialResponse::concretize`].
+    #[doc = include_str!("../../experimental_warning.md")]
+    pub fn is_authorized_with_fetch(
+        &self,
+        request: &Request,
+        policies: &PolicySet,
+        fetcher: &dyn EntityFetcher,
+        schema: Option<&Schema>,
+    ) -> Result<FetchResponse, EntityFetchError> {
+        let mut entities = Entities::from_entities([], schema)?;
+        // Uids which were fetched, but don't exist
+        let mut missing = HashSet::new();
+        let mut rounds = 0;
+        loop {
+            let partial =
+                self.is_authorized_partial(request, policies, &entities.clone().partial());
+            let needed = partial
+                .nontrivial_residuals()
+                .flat_map(|policy| policy.unknown_entities())
+                .filter(|uid| !missing.contains(uid))
+                .collect::<Vec<_>>();
+            if partial.decision().is_some() || needed.is_empty() {
+                let response = if needed.is_empty() {
+                    self.is_authorized(request, policies, &entities)
+                } else {
+                    partial.concretize()
+                };
+                return Ok(FetchResponse {
+                    response,
+                    entities,
+                    rounds,
+                });
+            }
+
+            // Fetch the needed entities, and then their ancestors until every
+            // ancestor is fetched
+            let mut to_fetch = needed;
+            let mut fetched = Vec::new();
+            while !to_fetch.is_empty() {
+                rounds += 1;
+                let found = fetcher.fetch(&to_fetch).map_err(EntityFetchError::Fetch)?;
+                let found_uids = found.iter().map(Entity::uid).collect::<HashSet<_>>();
+                missing.extend(to_fetch.into_iter().filter(|uid| !found_uids.contains(uid)));
+                let known = |uid: &EntityUid| {
+                    found_uids.contains(uid)
+                        || missing.contains(uid)
+                        || entities.get(uid).is_some()
+                        || fetched.iter().any(|e: &Entity| &e.uid() == uid)
+                };
+                to_fetch = found
+                    .iter()
+                    .flat_map(|entity| entity.0.ancestors())
+                    .map(|uid| EntityUid::from(uid.clone()))
+                    .filter(|uid| !known(uid))
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .collect();
+                fetched.extend(found);
+            }
+            entities = entities.add_entities(fetched, schema)?;
+        }
+    }
+}
@@ -0,0 +1,554 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Keeping the group memberships in an [`Entities`] store in sync with a
+//! directory, such as a SCIM service provider or an LDAP server.
+//!
+//! [`GroupSync`] records the groups each user and group is directly a member
+//! of, as the directory reports them, and turns every change into a
+//! [`GroupSyncDelta`] of added and removed membership edges.
+//! [`GroupSync::apply`] then updates the store in place, recomputing the
+//! ancestors of only the entities the delta affects: the members whose edges
+//! changed, and their members.
+//!
+//! [`Entities`] stores the transitive closure of the hierarchy rather than
+//! direct parents, so the direct edges recorded here are the source of truth
+//! for ancestors of the group type. Ancestors of other types are kept, except
+//! those an entity only had through its groups.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use cedar_policy_core::ast;
+use miette::Diagnostic;
+use ref_cast::RefCast;
+use thiserror::Error;
+
+use super::{Entities, EntitiesError, Entity, EntityId, EntityTypeName, EntityUid, Schema};
+
+/// Maintains user→group and group→group parent edges from directory data;
+/// see the [module documentation](self).
+///
+/// ```
+/// # use cedar_policy::{Entities, EntityUid, GroupSync};
+/// # use std::str::FromStr;
+/// let mut sync = GroupSync::new("User".parse().unwrap(), "Group".parse().unwrap());
+/// let delta = sync
+///     .apply_scim_group(r#"{ "id": "eng", "members": [{ "value": "alice" }] }"#)
+///     .unwrap();
+/// let mut entities = Entities::empty();
+/// sync.apply(&delta, &mut entities, None).unwrap();
+/// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+/// let eng = EntityUid::from_str(r#"Group::"eng""#).unwrap();
+/// assert!(entities.is_ancestor_of(&eng, &alice));
+///
+/// let delta = sync
+///     .apply_scim_patch(
+///         "eng",
+///         r#"{ "Operations": [{ "op": "remove", "path": "members[value eq \"alice\"]" }] }"#,
+///     )
+///     .unwrap();
+/// sync.apply(&delta, &mut entities, None).unwrap();
+/// assert!(!entities.is_ancestor_of(&eng, &alice));
+/// ```
+#[derive(Debug, Clone)]
+pub struct GroupSync {
+    user_type: EntityTypeName,
+    group_type: EntityTypeName,
+    ldap_group_base: Option<String>,
+    /// The groups each member is directly in
+    groups: BTreeMap<EntityUid, BTreeSet<EntityUid>>,
+    /// The direct members of each group
+    members: BTreeMap<EntityUid, BTreeSet<EntityUid>>,
+}
+
+impl GroupSync {
+    /// Create an adapter with no memberships, whose directory users and
+    /// groups are entities of `user_type` and `group_type`
+    pub fn new(user_type: EntityTypeName, group_type: EntityTypeName) -> Self {
+        Self {
+            user_type,
+            group_type,
+            ldap_group_base: None,
+            groups: BTreeMap::new(),
+            members: BTreeMap::new(),
+        }
+    }
+
+    /// Treat LDAP members whose DN is under `base_dn`, e.g.,
+    /// `ou=groups,dc=example,dc=com`, as nested groups rather than users.
+    /// Without a base, every LDAP member is a user.
+    #[must_use]
+    pub fn ldap_group_base(mut self, base_dn: impl Into<String>) -> Self {
+        self.ldap_group_base = Some(base_dn.into());
+        self
+    }
+
+    /// The groups `member` is directly in
+    pub fn groups_of<'a>(&'a self, member: &EntityUid) -> impl Iterator<Item = &'a EntityUid> {
+        self.groups.get(member).into_iter().flatten()
+    }
+
+    /// The direct members of `group`
+    pub fn members_of<'a>(&'a self, group: &EntityUid) -> impl Iterator<Item = &'a EntityUid> {
+        self.members.get(group).into_iter().flatten()
+    }
+
+    /// Replace the direct members of `group`
+    pub fn set_members(
+        &mut self,
+        group: &EntityUid,
+        members: impl IntoIterator<Item = EntityUid>,
+    ) -> GroupSyncDelta {
+        let members: BTreeSet<_> = members.into_iter().collect();
+        let current: Vec<_> = self.members_of(group).cloned().collect();
+        let mut delta = GroupSyncDelta::default();
+        for member in current.iter().filter(|m| !members.contains(*m)) {
+            self.unlink(&mut delta, member, group);
+        }
+        for member in members {
+            self.link(&mut delta, member, group.clone());
+        }
+        delta
+    }
+
+    /// Add direct members to `group`
+    pub fn add_members(
+        &mut self,
+        group: &EntityUid,
+        members: impl IntoIterator<Item = EntityUid>,
+    ) -> GroupSyncDelta {
+        let mut delta = GroupSyncDelta::default();
+        for member in members {
+            self.link(&mut delta, member, group.clone());
+        }
+        delta
+    }
+
+    /// Remove direct members from `group`
+    pub fn remove_members(
+        &mut self,
+        group: &EntityUid,
+        members: impl IntoIterator<Item = EntityUid>,
+    ) -> GroupSyncDelta {
+        let mut delta = GroupSyncDelta::default();
+        for member in members {
+            self.unlink(&mut delta, &member, group);
+        }
+        delta
+    }
+
+    /// Replace the groups `member` is directly in, as an LDAP `memberOf`
+    /// attribute lists them
+    pub fn set_groups(
+        &mut self,
+        member: &EntityUid,
+        groups: impl IntoIterator<Item = EntityUid>,
+    ) -> GroupSyncDelta {
+        let groups: BTreeSet<_> = groups.into_iter().collect();
+        let current: Vec<_> = self.groups_of(member).cloned().collect();
+        let mut delta = GroupSyncDelta::default();
+        for group in current.iter().filter(|g| !groups.contains(*g)) {
+            self.unlink(&mut delta, member, group);
+        }
+        for group in groups {
+            self.link(&mut delta, member.clone(), group);
+        }
+        delta
+    }
+
+    /// Remove every edge to and from `group`, e.g., when the directory
+    /// deletes it. The group entity itself stays in the store.
+    pub fn remove_group(&mut self, group: &EntityUid) -> GroupSyncDelta {
+        let mut delta = self.set_members(group, []);
+        delta.merge(self.set_groups(group, []));
+        delta
+    }
+
+    /// Apply a SCIM `Group` resource, or a `ListResponse` of them, replacing
+    /// the members of each group. Members whose `type` is `Group` are nested
+    /// groups; all others are users.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ScimParseError`] if `json` is not a `Group` resource or a
+    /// `ListResponse`, or if a group has no `id` or a member has no `value`.
+    pub fn apply_scim_group(&mut self, json: &str) -> Result<GroupSyncDelta, ScimParseError> {
+        let document = parse_json(json)?;
+        let resources = match document.get("Resources") {
+            Some(serde_json::Value::Array(resources)) => resources.iter().collect(),
+            Some(_) => return Err(ScimParseError::new("`Resources` must be an array")),
+            None => vec![&document],
+        };
+        let mut delta = GroupSyncDelta::default();
+        for resource in resources {
+            let Some(id) = resource.get("id").and_then(serde_json::Value::as_str) else {
+                return Err(ScimParseError::new("a group has no `id`"));
+            };
+            let members = match resource.get("members") {
+                Some(members) => self.scim_members(members)?,
+                None => Vec::new(),
+            };
+            let group = self.group(id);
+            delta.merge(self.set_members(&group, members));
+        }
+        Ok(delta)
+    }
+
+    /// Apply a SCIM `PatchOp` request to the group whose id is `group_id`.
+    /// `add`, `remove` and `replace` operations on `members` are applied,
+    /// including removals filtered as `members[value eq "id"]`; operations
+    /// on other attributes are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ScimParseError`] if `json` has no `Operations`, or if an
+    /// operation or its members are malformed. Operations before the
+    /// malformed one have already been applied.
+    pub fn apply_scim_patch(
+        &mut self,
+        group_id: &str,
+        json: &str,
+    ) -> Result<GroupSyncDelta, ScimParseError> {
+        let document = parse_json(json)?;
+        let Some(serde_json::Value::Array(operations)) = document.get("Operations") else {
+            return Err(ScimParseError::new("expected an array of `Operations`"));
+        };
+        let group = self.group(group_id);
+        let mut delta = GroupSyncDelta::default();
+        for operation in operations {
+            let Some(op) = operation.get("op").and_then(serde_json::Value::as_str) else {
+                return Err(ScimParseError::new("an operation has no `op`"));
+            };
+            let op = op.to_ascii_lowercase();
+            let value = operation.get("value");
+            // Without a path, the value holds the attributes to change
+            let (path, value) = operation
+                .get("path")
+                .and_then(serde_json::Value::as_str)
+                .map_or_else(
+                    || ("members", value.and_then(|v| v.get("members"))),
+                    |path| (path.trim(), value),
+                );
+            if let Some(id) = member_filter(path) {
+                if op != "remove" {
+                    return Err(ScimParseError::new(format!(
+                        "`{op}` of a filtered member is not supported"
+                    )));
+                }
+                let members = [self.user(id), self.group(id)];
+                delta.merge(self.remove_members(&group, members));
+                continue;
+            }
+            if !path.eq_ignore_ascii_case("members") || (value.is_none() && op != "remove") {
+                continue;
+            }
+            let members = value.map_or(Ok(Vec::new()), |v| self.scim_members(v))?;
+            delta.merge(match op.as_str() {
+                "add" => self.add_members(&group, members),
+                "replace" => self.set_members(&group, members),
+                "remove" if value.is_none() => self.set_members(&group, []),
+                "remove" => self.remove_members(&group, members),
+                _ => return Err(ScimParseError::new(format!("unsupported operation `{op}`"))),
+            });
+        }
+        Ok(delta)
+    }
+
+    /// Apply an LDAP group entry, replacing the members of the group whose
+    /// DN is `dn` with the DNs its `member` (or `uniqueMember`) attribute
+    /// lists. DNs are used as entity ids verbatim.
+    pub fn apply_ldap_group<S: AsRef<str>>(
+        &mut self,
+        dn: &str,
+        member_dns: impl IntoIterator<Item = S>,
+    ) -> GroupSyncDelta {
+        let members: Vec<_> = member_dns
+            .into_iter()
+            .map(|member| self.ldap_member(member.as_ref()))
+            .collect();
+        let group = self.group(dn);
+        self.set_members(&group, members)
+    }
+
+    /// Apply the `memberOf` attribute of the LDAP user entry whose DN is
+    /// `dn`, replacing the groups the user is directly in
+    pub fn apply_ldap_member_of<S: AsRef<str>>(
+        &mut self,
+        dn: &str,
+        group_dns: impl IntoIterator<Item = S>,
+    ) -> GroupSyncDelta {
+        let groups: Vec<_> = group_dns
+            .into_iter()
+            .map(|group| self.group(group.as_ref()))
+            .collect();
+        let user = self.user(dn);
+        self.set_groups(&user, groups)
+    }
+
+    /// Update `entities` in place with the changes in `delta`, which must
+    /// have come from this adapter. Only the members whose edges changed and
+    /// their (transitive) members in this adapter are updated; members and
+    /// groups missing from `entities` are added without attributes. Other
+    /// entities are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EntitiesError`] if the updated hierarchy has a cycle, or
+    /// if `schema` is provided and an added entity does not conform to it.
+    /// `entities` is unchanged in that case.
+    pub fn apply(
+        &self,
+        delta: &GroupSyncDelta,
+        entities: &mut Entities,
+        schema: Option<&Schema>,
+    ) -> Result<(), EntitiesError> {
+        let mut affected = BTreeSet::new();
+        let mut queue: Vec<_> = delta.edges().map(|(member, _)| member).collect();
+        while let Some(uid) = queue.pop() {
+            if affected.insert(uid) {
+                queue.extend(self.members_of(uid));
+            }
+        }
+        if affected.is_empty() {
+            return Ok(());
+        }
+        let mut updated = Vec::with_capacity(affected.len());
+        for uid in &affected {
+            let mut entity = entities.get(uid).map_or_else(
+                || ast::Entity::with_uid(uid.as_ref().clone()),
+                |entity| entity.0.clone(),
+            );
+            let ancestors = self.ancestors(entities, &entity, uid);
+            entity.set_ancestors(ancestors);
+            updated.push(Entity(entity));
+        }
+        let missing: BTreeSet<_> = affected
+            .iter()
+            .flat_map(|uid| self.groups_of(uid))
+            .filter(|group| !affected.contains(group) && entities.get(group).is_none())
+            .collect();
+        let missing = missing
+            .into_iter()
+            .map(|group| Entity::with_uid(group.clone()));
+        // Upserting the affected entities together closes them over each
+        // other and over the unaffected groups they are in
+        entities.upsert_entities(updated.into_iter().chain(missing), schema)
+    }
+
+    /// The new direct ancestors of `entity`: its groups in this adapter, and
+    /// the ancestors of other types it did not only have through its groups
+    fn ancestors(
+        &self,
+        entities: &Entities,
+        entity: &ast::Entity,
+        uid: &EntityUid,
+    ) -> HashSet<ast::EntityUID> {
+        let is_group = |ancestor: &ast::EntityUID| ancestor.entity_type() == &self.group_type.0;
+        let inherited: HashSet<_> = entity
+            .ancestors()
+            .filter(|ancestor| is_group(ancestor))
+            .filter_map(|group| entities.get(EntityUid::ref_cast(group)))
+            .flat_map(|group| group.0.ancestors())
+            .collect();
+        entity
+            .ancestors()
+            .filter(|ancestor| !is_group(ancestor) && !inherited.contains(ancestor))
+            .cloned()
+            .chain(self.groups_of(uid).map(|group| group.as_ref().clone()))
+            .collect()
+    }
+
+    fn link(&mut self, delta: &mut GroupSyncDelta, member: EntityUid, group: EntityUid) {
+        if self
+            .members
+            .entry(group.clone())
+            .or_default()
+            .insert(member.clone())
+        {
+            self.groups
+                .entry(member.clone())
+                .or_default()
+                .insert(group.clone());
+            delta.add(member, group);
+        }
+    }
+
+    fn unlink(&mut self, delta: &mut GroupSyncDelta, member: &EntityUid, group: &EntityUid) {
+        let removed = remove_edge(&mut self.members, group, member);
+        remove_edge(&mut self.groups, member, group);
+        if removed {
+            delta.remove(member.clone(), group.clone());
+        }
+    }
+
+    fn user(&self, id: &str) -> EntityUid {
+        EntityUid::from_type_name_and_id(self.user_type.clone(), EntityId::new(id))
+    }
+
+    fn group(&self, id: &str) -> EntityUid {
+        EntityUid::from_type_name_and_id(self.group_type.clone(), EntityId::new(id))
+    }
+
+    fn scim_members(&self, members: &serde_json::Value) -> Result<Vec<EntityUid>, ScimParseError> {
+        let members = match members {
+            serde_json::Value::Array(members) => members.iter().collect(),
+            member @ serde_json::Value::Object(_) => vec![member],
+            _ => return Err(ScimParseError::new("`members` must be an array")),
+        };
+        members
+            .into_iter()
+            .map(|member| {
+                let Some(id) = member.get("value").and_then(serde_json::Value::as_str) else {
+                    return Err(ScimParseError::new("a member has no `value`"));
+                };
+                let is_group = member
+                    .get("type")
+                    .and_then(serde_json::Value::as_str)
+                    .is_some_and(|ty| ty.eq_ignore_ascii_case("Group"));
+                Ok(if is_group {
+                    self.group(id)
+                } else {
+                    self.user(id)
+                })
+            })
+            .collect()
+    }
+
+    fn ldap_member(&self, dn: &str) -> EntityUid {
+        let is_group = self.ldap_group_base.as_ref().is_some_and(|base| {
+            let dn = dn.to_ascii_lowercase();
+            let base = base.to_ascii_lowercase();
+            dn.strip_suffix(&base)
+                .is_some_and(|rdn| rdn.trim_end().ends_with(','))
+        });
+        if is_group {
+            self.group(dn)
+        } else {
+            self.user(dn)
+        }
+    }
+}
+
+/// Remove `to` from the set of `from`, dropping the set once it is empty.
+/// Returns whether `to` was in the set.
+fn remove_edge(
+    edges: &mut BTreeMap<EntityUid, BTreeSet<EntityUid>>,
+    from: &EntityUid,
+    to: &EntityUid,
+) -> bool {
+    let Some(set) = edges.get_mut(from) else {
+        return false;
+    };
+    let removed = set.remove(to);
+    if set.is_empty() {
+        edges.remove(from);
+    }
+    removed
+}
+
+/// The id in a SCIM filter path `members[value eq "id"]`
+fn member_filter(path: &str) -> Option<&str> {
+    let filter = path
+        .strip_prefix("members[")
+        .or_else(|| path.strip_prefix("Members["))?
+        .strip_suffix(']')?
+        .trim();
+    let (attr, rest) = filter.split_once(char::is_whitespace)?;
+    let (op, value) = rest.trim_start().split_once(char::is_whitespace)?;
+    if !attr.eq_ignore_ascii_case("value") || !op.eq_ignore_ascii_case("eq") {
+        return None;
+    }
+    value.trim().strip_prefix('"')?.strip_suffix('"')
+}
+
+fn parse_json(json: &str) -> Result<serde_json::Value, ScimParseError> {
+    serde_json::from_str(json).map_err(|e| ScimParseError::new(e.to_string()))
+}
+
+/// The membership edges one or more changes to a [`GroupSync`] added and
+/// removed, each as a (member, group) pair
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupSyncDelta {
+    added: BTreeSet<(EntityUid, EntityUid)>,
+    removed: BTreeSet<(EntityUid, EntityUid)>,
+}
+
+impl GroupSyncDelta {
+    /// The (member, group) edges added
+    pub fn added(&self) -> impl Iterator<Item = (&EntityUid, &EntityUid)> {
+        self.added.iter().map(|(member, group)| (member, group))
+    }
+
+    /// The (member, group) edges removed
+    pub fn removed(&self) -> impl Iterator<Item = (&EntityUid, &EntityUid)> {
+        self.removed.iter().map(|(member, group)| (member, group))
+    }
+
+    /// Whether no edges changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Combine with a later delta from the same [`GroupSync`], so that both
+    /// can be applied at once. An edge added by one and removed by the other
+    /// cancels out.
+    pub fn merge(&mut self, later: Self) {
+        for (member, group) in later.added {
+            self.add(member, group);
+        }
+        for (member, group) in later.removed {
+            self.remove(member, group);
+        }
+    }
+
+    fn add(&mut self, member: EntityUid, group: EntityUid) {
+        let edge = (member, group);
+        if !self.removed.remove(&edge) {
+            self.added.insert(edge);
+        }
+    }
+
+    fn remove(&mut self, member: EntityUid, group: EntityUid) {
+        let edge = (member, group);
+        if !self.added.remove(&edge) {
+            self.removed.insert(edge);
+        }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&EntityUid, &EntityUid)> {
+        self.added().chain(self.removed())
+    }
+}
+
+/// Error parsing SCIM group-membership data
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("invalid SCIM data: {message}")]
+pub struct ScimParseError {
+    message: String,
+}
+
+impl ScimParseError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// The reason the data is invalid
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
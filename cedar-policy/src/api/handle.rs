@@ -0,0 +1,229 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A handle to the policies, schema, and entities used for authorization,
+//! which can be swapped out while authorization requests are in flight.
+//!
+//! Reading the current [`AuthorizerSnapshot`] takes no locks: snapshots are
+//! immutable, and an update publishes a new snapshot by atomically swapping
+//! a pointer (using [`arc_swap`]), so a refresh of the policies never makes
+//! concurrent requests wait, and requests never make a refresh wait. An old
+//! snapshot is freed once the last request using it completes.
+//!
+//! Concurrent updates don't lose each other's changes. Each update computes
+//! the new snapshot from the current one, and if another update published a
+//! snapshot in the meantime, it is computed again from that snapshot (see
+//! [`AuthorizerHandle::update_snapshot`]).
+
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+use super::{
+    Authorizer, BundleError, BundleVerifier, Entities, PolicySet, Request, Response, Schema,
+    SignedBundle,
+};
+
+/// The policies, schema, and entities an [`AuthorizerHandle`] authorizes
+/// requests against at some point in time
+#[derive(Debug, Clone)]
+pub struct AuthorizerSnapshot {
+    policies: Arc<PolicySet>,
+    schema: Option<Arc<Schema>>,
+    entities: Arc<Entities>,
+}
+
+impl AuthorizerSnapshot {
+    /// Create a snapshot of `policies`, `schema`, and `entities`
+    pub fn new(policies: PolicySet, schema: Option<Schema>, entities: Entities) -> Self {
+        Self {
+            policies: Arc::new(policies),
+            schema: schema.map(Arc::new),
+            entities: Arc::new(entities),
+        }
+    }
+
+    /// The policies in this snapshot
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// The schema in this snapshot, if there is one
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_deref()
+    }
+
+    /// The entities in this snapshot
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// This snapshot with its policies replaced by `policies`, sharing the
+    /// schema and entities
+    #[must_use]
+    pub fn with_policies(&self, policies: Arc<PolicySet>) -> Self {
+        Self {
+            policies,
+            ..self.clone()
+        }
+    }
+
+    /// This snapshot with its schema replaced by `schema`, sharing the
+    /// policies and entities
+    #[must_use]
+    pub fn with_schema(&self, schema: Option<Arc<Schema>>) -> Self {
+        Self {
+            schema,
+            ..self.clone()
+        }
+    }
+
+    /// This snapshot with its entities replaced by `entities`, sharing the
+    /// policies and schema
+    #[must_use]
+    pub fn with_entities(&self, entities: Arc<Entities>) -> Self {
+        Self {
+            entities,
+            ..self.clone()
+        }
+    }
+}
+
+/// An [`Authorizer`] together with the policies, schema, and entities it
+/// authorizes requests against, for long-running services which need to
+/// roll out new policies or entity data without restarting.
+///
+/// Each request is authorized against a consistent [`AuthorizerSnapshot`].
+/// Updates install a new snapshot atomically: requests which already
+/// started keep using the snapshot they started with, and neither requests
+/// nor updates take locks or wait for each other (see
+/// [`AuthorizerHandle::update_snapshot`]). Updating one of the policies, schema, or
+/// entities shares the other two with the previous snapshot rather than
+/// copying them.
+///
+/// An `AuthorizerHandle` is `Sync`, so it can be shared between threads,
+/// e.g., in an [`Arc`].
+#[derive(Debug)]
+pub struct AuthorizerHandle {
+    authorizer: Authorizer,
+    current: ArcSwap<AuthorizerSnapshot>,
+}
+
+impl AuthorizerHandle {
+    /// Authorize requests with `authorizer` against `policies` and `entities`
+    pub fn new(authorizer: Authorizer, policies: PolicySet, entities: Entities) -> Self {
+        Self::from_snapshot(
+            authorizer,
+            AuthorizerSnapshot::new(policies, None, entities),
+        )
+    }
+
+    /// Authorize requests with `authorizer` against `snapshot`
+    pub fn from_snapshot(authorizer: Authorizer, snapshot: AuthorizerSnapshot) -> Self {
+        Self {
+            authorizer,
+            current: ArcSwap::from_pointee(snapshot),
+        }
+    }
+
+    /// Authorize requests with `authorizer` against the policies and schema
+    /// in `bundle`, and `entities`. This is the way to load a signed bundle:
+    /// its signature is checked with `verifier` first, and a bundle which was
+    /// tampered with, or signed with another key, is refused.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BundleError::InvalidSignature`] if the signature is not
+    /// valid, or another [`BundleError`] if the bundle can't be read.
+    pub fn from_signed_bundle(
+        authorizer: Authorizer,
+        bundle: &SignedBundle,
+        verifier: &dyn BundleVerifier,
+        entities: Entities,
+    ) -> Result<Self, BundleError> {
+        let bundle = bundle.verify(verifier)?;
+        let snapshot =
+            AuthorizerSnapshot::new(bundle.policy_set()?, Some(bundle.schema()?), entities);
+        Ok(Self::from_snapshot(authorizer, snapshot))
+    }
+
+    /// Get the current snapshot. The snapshot is unaffected by later
+    /// updates.
+    pub fn snapshot(&self) -> Arc<AuthorizerSnapshot> {
+        self.current.load_full()
+    }
+
+    /// Authorize `request` against the current snapshot
+    pub fn is_authorized(&self, request: &Request) -> Response {
+        let snapshot = self.current.load();
+        self.authorizer
+            .is_authorized(request, &snapshot.policies, &snapshot.entities)
+    }
+
+    /// Replace the policies used for new requests
+    pub fn update_policies(&self, policies: PolicySet) {
+        let policies = Arc::new(policies);
+        self.update_snapshot(|old| old.with_policies(Arc::clone(&policies)));
+    }
+
+    /// Replace the schema in the snapshot used for new requests
+    pub fn update_schema(&self, schema: Option<Schema>) {
+        let schema = schema.map(Arc::new);
+        self.update_snapshot(|old| old.with_schema(schema.clone()));
+    }
+
+    /// Replace the entities used for new requests
+    pub fn update_entities(&self, entities: Entities) {
+        let entities = Arc::new(entities);
+        self.update_snapshot(|old| old.with_entities(Arc::clone(&entities)));
+    }
+
+    /// Replace the whole snapshot used for new requests at once, returning
+    /// the previous snapshot
+    pub fn replace_snapshot(&self, snapshot: AuthorizerSnapshot) -> Arc<AuthorizerSnapshot> {
+        self.current.swap(Arc::new(snapshot))
+    }
+
+    /// Install the snapshot computed by `f` from the current one, returning
+    /// the snapshot it replaced.
+    ///
+    /// If another update installs a snapshot while `f` runs, `f` is called
+    /// again with that snapshot, so updates made concurrently are never
+    /// lost. So, `f` should do as little as possible, with any expensive
+    /// work, like parsing policies, done before calling this.
+    /// ```
+    /// # use cedar_policy::{Authorizer, AuthorizerHandle, Entities, PolicySet};
+    /// # use std::str::FromStr;
+    /// # use std::sync::Arc;
+    /// let handle = AuthorizerHandle::new(Authorizer::new(), PolicySet::new(), Entities::empty());
+    /// let extra = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+    /// // add policies, without overwriting policies added concurrently
+    /// handle.update_snapshot(|old| {
+    ///     let mut policies = old.policies().clone();
+    ///     for policy in extra.policies() {
+    ///         policies.add(policy.clone()).unwrap();
+    ///     }
+    ///     old.with_policies(Arc::new(policies))
+    /// });
+    /// assert_eq!(handle.snapshot().policies().policies().count(), 1);
+    /// ```
+    pub fn update_snapshot(
+        &self,
+        mut f: impl FnMut(&AuthorizerSnapshot) -> AuthorizerSnapshot,
+    ) -> Arc<AuthorizerSnapshot> {
+        self.current.rcu(|old| f(old))
+    }
+}
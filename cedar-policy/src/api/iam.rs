@@ -0,0 +1,897 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Translating AWS IAM identity and resource policies into Cedar policies and
+//! a schema fragment.
+//!
+//! Each statement becomes a `permit` (for `Allow`) or `forbid` (for `Deny`)
+//! policy; like IAM, Cedar denies requests no policy permits, and an explicit
+//! deny overrides any allow. Actions are entities of type `Action`, whose ids
+//! are the IAM action names, e.g., `Action::"s3:GetObject"`. Resources are
+//! entities whose ids are their ARNs, with an `arn` attribute holding the ARN
+//! again if resource patterns with wildcards are matched against it.
+//! Principals named by resource policies are entities whose ids are the
+//! principal ARNs (or service names, ...), with an `account` attribute
+//! matched by account principals. Condition keys are context attributes,
+//! named by the full key, e.g., `context["aws:SourceIp"]`.
+//!
+//! Statements using anything that can't be represented (`NotPrincipal`,
+//! policy variables, case-insensitive comparisons, ...) are not translated at
+//! all, since dropping some of the conditions of a statement would change
+//! which requests it applies to; each is reported as an [`IamImportIssue`].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use cedar_policy_core::ast::{self, CharClass, PatternElem};
+use miette::Diagnostic;
+use serde_json::json;
+use thiserror::Error;
+
+use super::{EntityTypeName, EntityUid, Policy, PolicyId, PolicySet, SchemaFragment};
+
+/// Translates IAM policies into Cedar; see [`IamImporter::import`].
+///
+/// ```
+/// # use cedar_policy::{IamImporter, PolicyId};
+/// let import = IamImporter::new()
+///     .attached_to(r#"Group::"developers""#.parse().unwrap())
+///     .known_actions(["s3:GetObject", "s3:GetObjectAcl", "s3:PutObject"])
+///     .import(r#"{
+///         "Version": "2012-10-17",
+///         "Statement": [
+///             {
+///                 "Sid": "ReadBuilds",
+///                 "Effect": "Allow",
+///                 "Action": "s3:Get*",
+///                 "Resource": "arn:aws:s3:::builds/*",
+///                 "Condition": { "Bool": { "aws:MultiFactorAuthPresent": "true" } }
+///             },
+///             {
+///                 "Sid": "NoUploadsOutsideBusinessHours",
+///                 "Effect": "Deny",
+///                 "Action": "s3:PutObject",
+///                 "Resource": "*",
+///                 "Condition": { "DateGreaterThan": { "aws:CurrentTime": "${aws:TokenIssueTime}" } }
+///             }
+///         ]
+///     }"#)
+///     .unwrap();
+/// let policy = import.policies().policy(&PolicyId::new("ReadBuilds")).unwrap();
+/// assert_eq!(
+///     policy.to_string(),
+///     r#"permit(principal in Group::"developers", action in [Action::"s3:GetObject", Action::"s3:GetObjectAcl"], resource) when { resource.arn like "arn:aws:s3:::builds/*" && (context has "aws:MultiFactorAuthPresent" && context["aws:MultiFactorAuthPresent"] == true) };"#
+/// );
+/// // Policy variables can't be represented, so the second statement is
+/// // reported and not translated
+/// assert_eq!(import.issues().len(), 1);
+/// assert_eq!(import.issues()[0].statement(), "NoUploadsOutsideBusinessHours");
+/// ```
+#[derive(Debug, Clone)]
+pub struct IamImporter {
+    principal_type: EntityTypeName,
+    resource_type: EntityTypeName,
+    attached_to: Option<EntityUid>,
+    known_actions: BTreeSet<String>,
+}
+
+impl Default for IamImporter {
+    fn default() -> Self {
+        #[allow(clippy::expect_used)]
+        let entity_type = |name| EntityTypeName::from_str(name).expect("valid entity type name");
+        Self {
+            principal_type: entity_type("Principal"),
+            resource_type: entity_type("Resource"),
+            attached_to: None,
+            known_actions: BTreeSet::new(),
+        }
+    }
+}
+
+impl IamImporter {
+    /// Create an importer with the default entity types, `Principal` and
+    /// `Resource`, for identity policies which aren't attached to anything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Translate principals to entities of type `entity_type`
+    #[must_use]
+    pub fn principal_type(mut self, entity_type: EntityTypeName) -> Self {
+        self.principal_type = entity_type;
+        self
+    }
+
+    /// Translate resources to entities of type `entity_type`
+    #[must_use]
+    pub fn resource_type(mut self, entity_type: EntityTypeName) -> Self {
+        self.resource_type = entity_type;
+        self
+    }
+
+    /// Translate an identity policy attached to `identity`, e.g., a user,
+    /// group, or role. Its statements (which don't have a `Principal`) apply
+    /// to principals `in` the identity.
+    #[must_use]
+    pub fn attached_to(mut self, identity: EntityUid) -> Self {
+        self.attached_to = Some(identity);
+        self
+    }
+
+    /// Actions to match action patterns with wildcards against, e.g.,
+    /// `s3:Get*`, and to declare in the schema, in addition to the actions
+    /// the policy names. IAM has no fixed list of actions, so patterns which
+    /// match none of these can't be translated.
+    #[must_use]
+    pub fn known_actions(mut self, actions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.known_actions
+            .extend(actions.into_iter().map(Into::into));
+        self
+    }
+
+    /// Translate the IAM policy document `json` into Cedar, as far as
+    /// possible.
+    ///
+    /// Statements which can't be translated are reported in
+    /// [`IamImport::issues`], and left out of the policies. The schema
+    /// declares the principal and resource entity types, the known actions,
+    /// and the context, with the condition keys the policies read, typed by
+    /// their condition operators.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IamParseError`] if `json` is not a JSON object with a
+    /// `Statement`.
+    pub fn import(&self, json: &str) -> Result<IamImport, IamParseError> {
+        let document: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| IamParseError {
+                message: e.to_string(),
+            })?;
+        let statements = match document.get("Statement") {
+            Some(serde_json::Value::Array(statements)) => statements.clone(),
+            Some(statement @ serde_json::Value::Object(_)) => vec![statement.clone()],
+            Some(_) => {
+                return Err(IamParseError {
+                    message: "`Statement` must be an object or an array".into(),
+                })
+            }
+            None => {
+                return Err(IamParseError {
+                    message: "expected an object with a `Statement`".into(),
+                })
+            }
+        };
+        let mut translator = Translator {
+            importer: self,
+            known_actions: self.known_actions.clone(),
+            policies: PolicySet::new(),
+            context: BTreeMap::new(),
+            principal_account: false,
+            resource_arn: false,
+            issues: Vec::new(),
+        };
+        // Actions the policy names are known, so that patterns can match them
+        for statement in &statements {
+            for key in ["Action", "NotAction"] {
+                if let Ok(actions) = strings(statement.get(key)) {
+                    translator.known_actions.extend(
+                        actions
+                            .into_iter()
+                            .filter(|action| !action.contains(['*', '?'])),
+                    );
+                }
+            }
+        }
+        for (index, statement) in statements.iter().enumerate() {
+            translator.statement(index, statement);
+        }
+        Ok(translator.finish())
+    }
+}
+
+/// The result of translating an IAM policy with an [`IamImporter`]
+#[derive(Debug, Clone)]
+pub struct IamImport {
+    policies: PolicySet,
+    schema: SchemaFragment,
+    issues: Vec<IamImportIssue>,
+}
+
+impl IamImport {
+    /// The Cedar policies translated from the IAM statements. Their ids are
+    /// the `Sid`s of the statements, or `statement0`, `statement1`, ... for
+    /// statements without one.
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// A schema fragment for the policies
+    pub fn schema(&self) -> &SchemaFragment {
+        &self.schema
+    }
+
+    /// The statements which could not be translated, in order
+    pub fn issues(&self) -> &[IamImportIssue] {
+        &self.issues
+    }
+
+    /// Consume the `IamImport`, returning the policies, the schema, and the
+    /// issues
+    pub fn into_parts(self) -> (PolicySet, SchemaFragment, Vec<IamImportIssue>) {
+        (self.policies, self.schema, self.issues)
+    }
+}
+
+/// An IAM statement which could not be translated into Cedar
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("{statement}: {message}")]
+pub struct IamImportIssue {
+    statement: String,
+    message: String,
+}
+
+impl IamImportIssue {
+    /// The `Sid` of the statement, or `statement<n>` for the `n`th statement
+    /// (from 0) if it has none
+    pub fn statement(&self) -> &str {
+        &self.statement
+    }
+
+    /// What couldn't be translated, and why
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Error parsing an IAM policy document
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("invalid IAM policy: {message}")]
+pub struct IamParseError {
+    message: String,
+}
+
+/// Types of condition keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ty {
+    String,
+    Long,
+    Bool,
+    Datetime,
+    Ipaddr,
+    Set(Box<Self>),
+}
+
+impl Ty {
+    fn json(&self) -> serde_json::Value {
+        match self {
+            Self::String => json!({ "type": "String" }),
+            Self::Long => json!({ "type": "Long" }),
+            Self::Bool => json!({ "type": "Boolean" }),
+            Self::Datetime => json!({ "type": "Extension", "name": "datetime" }),
+            Self::Ipaddr => json!({ "type": "Extension", "name": "ipaddr" }),
+            Self::Set(element) => json!({ "type": "Set", "element": element.json() }),
+        }
+    }
+
+    /// Render the condition value `value` as a Cedar literal of this type
+    fn literal(&self, value: &str) -> Result<String, String> {
+        match self {
+            Self::String | Self::Set(_) => Ok(quote(value)),
+            Self::Long => value.parse::<i64>().map(|i| i.to_string()).map_err(|_| {
+                format!("`{value}` is not an integer, and Cedar has no other numbers")
+            }),
+            Self::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" => Ok("true".into()),
+                "false" => Ok("false".into()),
+                _ => Err(format!("`{value}` is not a boolean")),
+            },
+            Self::Datetime => {
+                if is_datetime(value) {
+                    Ok(format!("datetime({})", quote(value)))
+                } else {
+                    Err(format!(
+                        "`{value}` is not a date in a format Cedar supports, e.g., \
+                         `2024-06-01T00:00:00Z`"
+                    ))
+                }
+            }
+            Self::Ipaddr => {
+                let (addr, prefix) = value.split_once('/').unwrap_or((value, ""));
+                let valid = addr.parse::<IpAddr>().is_ok()
+                    && (prefix.is_empty() || prefix.parse::<u8>().is_ok());
+                if valid {
+                    Ok(format!("ip({})", quote(value)))
+                } else {
+                    Err(format!("`{value}` is not an IP address or range"))
+                }
+            }
+        }
+    }
+}
+
+/// Whether `value` is a date, or a date and time, as the `datetime` extension
+/// parses them: `YYYY-MM-DD`, optionally followed by `Thh:mm:ss`,
+/// optionally `.sss`, and `Z` or an offset `+hhmm` or `-hhmm`
+fn is_datetime(value: &str) -> bool {
+    let matches = |s: &str, template: &str| {
+        s.len() == template.len()
+            && s.chars().zip(template.chars()).all(|(c, t)| match t {
+                'D' => c.is_ascii_digit(),
+                '+' => c == '+' || c == '-',
+                t => c == t,
+            })
+    };
+    let (date, time) = value.split_at(value.len().min(10));
+    if !matches(date, "DDDD-DD-DD") {
+        return false;
+    }
+    if time.is_empty() {
+        return true;
+    }
+    let (hms, rest) = time.split_at(time.len().min(9));
+    let rest = if rest.starts_with('.') {
+        rest.get(4..)
+            .filter(|_| matches(rest.get(..4).unwrap_or_default(), ".DDD"))
+    } else {
+        Some(rest)
+    };
+    matches(hms, "TDD:DD:DD") && rest.is_some_and(|rest| rest == "Z" || matches(rest, "+DDDD"))
+}
+
+/// Cedar string literal for `s`
+fn quote(s: &str) -> String {
+    ast::Expr::val(s).to_string()
+}
+
+/// The `like` pattern matching what the IAM pattern `s` matches, with `*`
+/// matching any sequence of characters and `?` any single character
+fn like_pattern(s: &str) -> ast::Pattern {
+    s.chars()
+        .map(|c| match c {
+            '*' => PatternElem::Wildcard,
+            '?' => PatternElem::Class(CharClass::new(false, [('\0', char::MAX)])),
+            c => PatternElem::Char(c),
+        })
+        .collect()
+}
+
+/// The strings in a value of an IAM policy which can be a string or an array
+/// of strings. Numbers and booleans are taken as strings, as IAM does in
+/// conditions.
+fn strings(value: Option<&serde_json::Value>) -> Result<Vec<String>, String> {
+    let string = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => Ok(s.clone()),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        serde_json::Value::Bool(b) => Ok(b.to_string()),
+        value => Err(format!("expected a string, found `{value}`")),
+    };
+    match value {
+        None => Ok(Vec::new()),
+        Some(serde_json::Value::Array(values)) => values.iter().map(string).collect(),
+        Some(value) => string(value).map(|s| vec![s]),
+    }
+}
+
+fn no_variables(values: &[String]) -> Result<(), String> {
+    if values.iter().any(|value| value.contains("${")) {
+        Err("policy variables (`${...}`) are not supported".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// `alternatives` joined with `||`, in parentheses if there's more than one
+fn any(alternatives: &[String]) -> String {
+    match alternatives {
+        [alternative] => alternative.clone(),
+        alternatives => format!("({})", alternatives.join(" || ")),
+    }
+}
+
+/// A statement translated into the parts of a Cedar policy
+#[derive(Debug, Default)]
+struct Statement {
+    principal: String,
+    action: String,
+    resource: String,
+    when: Vec<String>,
+    context: Vec<(String, Ty)>,
+    principal_account: bool,
+    resource_arn: bool,
+}
+
+#[derive(Debug)]
+struct Translator<'a> {
+    importer: &'a IamImporter,
+    known_actions: BTreeSet<String>,
+    policies: PolicySet,
+    context: BTreeMap<String, Ty>,
+    principal_account: bool,
+    resource_arn: bool,
+    issues: Vec<IamImportIssue>,
+}
+
+impl Translator<'_> {
+    fn report(&mut self, statement: &str, message: impl Into<String>) {
+        self.issues.push(IamImportIssue {
+            statement: statement.into(),
+            message: message.into(),
+        });
+    }
+
+    fn statement(&mut self, index: usize, statement: &serde_json::Value) {
+        let id = match statement.get("Sid") {
+            Some(serde_json::Value::String(sid)) if !sid.is_empty() => sid.clone(),
+            _ => format!("statement{index}"),
+        };
+        let effect = match statement.get("Effect").and_then(serde_json::Value::as_str) {
+            Some("Allow") => ast::Effect::Permit,
+            Some("Deny") => ast::Effect::Forbid,
+            _ => {
+                self.report(&id, "`Effect` must be `Allow` or `Deny`");
+                return;
+            }
+        };
+        let translated = self.translate(statement).and_then(|translated| {
+            let when = if translated.when.is_empty() {
+                String::new()
+            } else {
+                format!(" when {{ {} }}", translated.when.join(" && "))
+            };
+            let text = format!(
+                "{effect}(principal{}, action{}, resource{}){when};",
+                translated.principal, translated.action, translated.resource
+            );
+            let policy = Policy::parse(Some(PolicyId::new(&id)), &text)
+                .map_err(|e| format!("failed to translate: {e}"))?;
+            self.policies
+                .add(policy)
+                .map_err(|e| format!("failed to translate: {e}"))?;
+            Ok(translated)
+        });
+        match translated {
+            Ok(translated) => {
+                self.principal_account |= translated.principal_account;
+                self.resource_arn |= translated.resource_arn;
+                for (key, ty) in translated.context {
+                    let declared = self
+                        .context
+                        .entry(key.clone())
+                        .or_insert_with(|| ty.clone());
+                    if *declared != ty {
+                        let declared = format!("{declared:?}");
+                        self.report(
+                            &id,
+                            format!(
+                                "condition key `{key}` is used as both {declared} and {ty:?}; \
+                                 it is declared as {declared}"
+                            ),
+                        );
+                    }
+                }
+            }
+            Err(message) => {
+                let consequence = match effect {
+                    ast::Effect::Permit => "",
+                    ast::Effect::Forbid => {
+                        "; it denies access, so the Cedar policies allow more than the IAM policy"
+                    }
+                };
+                self.report(
+                    &id,
+                    format!("{message}; the statement is not translated{consequence}"),
+                );
+            }
+        }
+    }
+
+    fn translate(&self, statement: &serde_json::Value) -> Result<Statement, String> {
+        let serde_json::Value::Object(fields) = statement else {
+            return Err("a statement must be an object".into());
+        };
+        for key in fields.keys() {
+            match key.as_str() {
+                "Sid" | "Effect" | "Principal" | "Action" | "NotAction" | "Resource"
+                | "NotResource" | "Condition" => {}
+                "NotPrincipal" => return Err("`NotPrincipal` is not supported".into()),
+                key => return Err(format!("`{key}` is not supported")),
+            }
+        }
+        let mut translated = Statement::default();
+        self.principal(statement.get("Principal"), &mut translated)?;
+        self.actions(statement, &mut translated)?;
+        self.resources(statement, &mut translated)?;
+        match statement.get("Condition") {
+            None => {}
+            Some(serde_json::Value::Object(operators)) => {
+                for (operator, keys) in operators {
+                    let serde_json::Value::Object(keys) = keys else {
+                        return Err(format!("`{operator}` must map condition keys to values"));
+                    };
+                    for (key, values) in keys {
+                        let condition =
+                            condition(operator, key, &strings(Some(values))?, &mut translated)?;
+                        translated.when.push(condition);
+                    }
+                }
+            }
+            Some(_) => return Err("`Condition` must be an object".into()),
+        }
+        Ok(translated)
+    }
+
+    fn principal(
+        &self,
+        principal: Option<&serde_json::Value>,
+        translated: &mut Statement,
+    ) -> Result<(), String> {
+        let Some(principal) = principal else {
+            if let Some(identity) = &self.importer.attached_to {
+                translated.principal = format!(" in {identity}");
+            }
+            return Ok(());
+        };
+        let mut alternatives = Vec::new();
+        match principal {
+            serde_json::Value::String(s) if s == "*" => return Ok(()),
+            serde_json::Value::Object(kinds) => {
+                for (kind, values) in kinds {
+                    if !matches!(
+                        kind.as_str(),
+                        "AWS" | "Service" | "Federated" | "CanonicalUser"
+                    ) {
+                        return Err(format!("principals of kind `{kind}` are not supported"));
+                    }
+                    let values = strings(Some(values))?;
+                    no_variables(&values)?;
+                    for value in values {
+                        if value == "*" {
+                            return Ok(());
+                        }
+                        let account = value
+                            .strip_prefix("arn:aws:iam::")
+                            .and_then(|arn| arn.strip_suffix(":root"))
+                            .unwrap_or(&value);
+                        if kind == "AWS"
+                            && account.len() == 12
+                            && account.chars().all(|c| c.is_ascii_digit())
+                        {
+                            translated.principal_account = true;
+                            alternatives.push(format!("principal.account == {}", quote(account)));
+                        } else {
+                            alternatives.push(format!(
+                                "principal == {}::{}",
+                                self.importer.principal_type,
+                                quote(&value)
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => return Err("`Principal` must be `*` or an object".into()),
+        }
+        match alternatives.as_slice() {
+            [] => return Err("`Principal` names no principals".into()),
+            [alternative] if alternative.starts_with("principal ==") => {
+                translated.principal = alternative.trim_start_matches("principal").to_string();
+            }
+            alternatives => translated.when.push(any(alternatives)),
+        }
+        Ok(())
+    }
+
+    fn actions(
+        &self,
+        statement: &serde_json::Value,
+        translated: &mut Statement,
+    ) -> Result<(), String> {
+        let (patterns, negated) = match (statement.get("Action"), statement.get("NotAction")) {
+            (Some(actions), None) => (strings(Some(actions))?, false),
+            (None, Some(actions)) => (strings(Some(actions))?, true),
+            _ => return Err("a statement must have either `Action` or `NotAction`".into()),
+        };
+        no_variables(&patterns)?;
+        if patterns.iter().any(|pattern| pattern == "*") {
+            return if negated {
+                Err("`NotAction` of `*` matches no action".into())
+            } else {
+                Ok(())
+            };
+        }
+        let mut actions = BTreeSet::new();
+        for pattern in patterns {
+            // Action names are case-insensitive
+            let like = like_pattern(&pattern.to_lowercase());
+            let matching: Vec<&String> = self
+                .known_actions
+                .iter()
+                .filter(|action| like.wildcard_match(&action.to_lowercase()))
+                .collect();
+            if matching.is_empty() {
+                return Err(format!(
+                    "action pattern `{pattern}` matches none of the known actions"
+                ));
+            }
+            actions.extend(matching);
+        }
+        let uids: Vec<String> = actions
+            .into_iter()
+            .map(|action| format!("Action::{}", quote(action)))
+            .collect();
+        match (uids.as_slice(), negated) {
+            ([uid], false) => translated.action = format!(" == {uid}"),
+            (uids, false) => translated.action = format!(" in [{}]", uids.join(", ")),
+            (uids, true) => translated
+                .when
+                .push(format!("!(action in [{}])", uids.join(", "))),
+        }
+        Ok(())
+    }
+
+    fn resources(
+        &self,
+        statement: &serde_json::Value,
+        translated: &mut Statement,
+    ) -> Result<(), String> {
+        let (resources, negated) = match (statement.get("Resource"), statement.get("NotResource")) {
+            (Some(resources), None) => (strings(Some(resources))?, false),
+            (None, Some(resources)) => (strings(Some(resources))?, true),
+            (None, None) => return Ok(()),
+            (Some(_), Some(_)) => {
+                return Err("a statement can't have both `Resource` and `NotResource`".into())
+            }
+        };
+        no_variables(&resources)?;
+        if resources.iter().any(|resource| resource == "*") {
+            return if negated {
+                Err("`NotResource` of `*` matches no resource".into())
+            } else {
+                Ok(())
+            };
+        }
+        let alternatives: Vec<String> = resources
+            .iter()
+            .map(|resource| {
+                if resource.contains(['*', '?']) {
+                    translated.resource_arn = true;
+                    format!("resource.arn like \"{}\"", like_pattern(resource))
+                } else {
+                    format!(
+                        "resource == {}::{}",
+                        self.importer.resource_type,
+                        quote(resource)
+                    )
+                }
+            })
+            .collect();
+        match (alternatives.as_slice(), negated) {
+            ([alternative], false) if alternative.starts_with("resource ==") => {
+                translated.resource = alternative.trim_start_matches("resource").to_string();
+            }
+            (alternatives, false) => translated.when.push(any(alternatives)),
+            (alternatives, true) => translated
+                .when
+                .push(format!("!{}", any_parenthesized(alternatives))),
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> IamImport {
+        let principal = &self.importer.principal_type;
+        let resource = &self.importer.resource_type;
+        let mut principal_shape = serde_json::Map::new();
+        if self.principal_account {
+            principal_shape.insert("account".into(), Ty::String.json());
+        }
+        let mut resource_shape = serde_json::Map::new();
+        if self.resource_arn {
+            resource_shape.insert("arn".into(), Ty::String.json());
+        }
+        let shape = |attributes: serde_json::Map<String, serde_json::Value>| json!({ "type": "Record", "attributes": attributes });
+        let mut entity_types = vec![
+            (
+                principal.clone(),
+                json!({ "shape": shape(principal_shape) }),
+            ),
+            (resource.clone(), json!({ "shape": shape(resource_shape) })),
+        ];
+        if let Some(identity) = &self.importer.attached_to {
+            let identity = identity.type_name();
+            if identity != principal {
+                if let Some((_, principal)) = entity_types.first_mut() {
+                    principal["memberOfTypes"] = json!([identity.to_string()]);
+                }
+                entity_types.push((identity.clone(), json!({})));
+            }
+        }
+        let mut namespaces: BTreeMap<String, serde_json::Map<String, serde_json::Value>> =
+            BTreeMap::new();
+        for (ty, definition) in entity_types {
+            namespaces
+                .entry(ty.namespace())
+                .or_default()
+                .entry(ty.basename())
+                .or_insert(definition);
+        }
+        let context: serde_json::Map<String, serde_json::Value> = self
+            .context
+            .iter()
+            .map(|(key, ty)| {
+                let mut attribute = ty.json();
+                if let Some(attribute) = attribute.as_object_mut() {
+                    attribute.insert("required".into(), false.into());
+                }
+                (key.clone(), attribute)
+            })
+            .collect();
+        let actions: serde_json::Map<_, _> = self
+            .known_actions
+            .iter()
+            .map(|action| {
+                (
+                    action.clone(),
+                    json!({
+                        "appliesTo": {
+                            "principalTypes": [principal.to_string()],
+                            "resourceTypes": [resource.to_string()],
+                            "context": { "type": "Record", "attributes": context },
+                        }
+                    }),
+                )
+            })
+            .collect();
+        let mut schema = serde_json::Map::new();
+        for (namespace, entity_types) in namespaces {
+            schema.insert(
+                namespace,
+                json!({ "entityTypes": entity_types, "actions": {} }),
+            );
+        }
+        schema
+            .entry(String::new())
+            .or_insert_with(|| json!({ "entityTypes": {} }))
+            .as_object_mut()
+            .map(|global| global.insert("actions".into(), actions.into()));
+        let schema = match SchemaFragment::from_json_value(schema.into()) {
+            Ok(schema) => schema,
+            Err(e) => {
+                self.report("", format!("failed to build the schema fragment: {e}"));
+                #[allow(clippy::expect_used)]
+                SchemaFragment::from_json_value(json!({})).expect("empty schema is valid")
+            }
+        };
+        IamImport {
+            policies: self.policies,
+            schema,
+            issues: self.issues,
+        }
+    }
+}
+
+fn any_parenthesized(alternatives: &[String]) -> String {
+    format!("({})", alternatives.join(" || "))
+}
+
+/// Translate the condition applying `operator` to the condition key `key` and
+/// `values`.
+///
+/// A key which isn't in the request satisfies negated operators (e.g.,
+/// `StringNotEquals`) and operators ending in `IfExists`, but not others.
+fn condition(
+    operator: &str,
+    key: &str,
+    values: &[String],
+    translated: &mut Statement,
+) -> Result<String, String> {
+    no_variables(values)?;
+    let (qualifier, op) = match operator.split_once(':') {
+        Some((qualifier @ ("ForAnyValue" | "ForAllValues"), op)) => (Some(qualifier), op),
+        Some(_) => return Err(format!("condition operator `{operator}` is not supported")),
+        None => (None, operator),
+    };
+    let (op, if_exists) = op
+        .strip_suffix("IfExists")
+        .map_or((op, false), |op| (op, true));
+    let attr = format!("context[{}]", quote(key));
+    let has = format!("context has {}", quote(key));
+    if op == "Null" {
+        return match (qualifier, if_exists, values) {
+            (None, false, [value]) => match value.to_ascii_lowercase().as_str() {
+                "true" => Ok(format!("!({has})")),
+                "false" => Ok(has),
+                _ => Err(format!("`{value}` is not a boolean")),
+            },
+            _ => Err("`Null` must have a single `true` or `false` value".into()),
+        };
+    }
+    let (ty, negated, cmp) = match op {
+        "StringEquals" => (Ty::String, false, "=="),
+        "StringNotEquals" => (Ty::String, true, "=="),
+        "StringLike" | "ArnEquals" | "ArnLike" => (Ty::String, false, "like"),
+        "StringNotLike" | "ArnNotEquals" | "ArnNotLike" => (Ty::String, true, "like"),
+        "StringEqualsIgnoreCase" | "StringNotEqualsIgnoreCase" => {
+            return Err(format!(
+                "`{op}`: case-insensitive comparison has no Cedar equivalent"
+            ))
+        }
+        "NumericEquals" => (Ty::Long, false, "=="),
+        "NumericNotEquals" => (Ty::Long, true, "=="),
+        "NumericLessThan" => (Ty::Long, false, "<"),
+        "NumericLessThanEquals" => (Ty::Long, false, "<="),
+        "NumericGreaterThan" => (Ty::Long, false, ">"),
+        "NumericGreaterThanEquals" => (Ty::Long, false, ">="),
+        "DateEquals" => (Ty::Datetime, false, "=="),
+        "DateNotEquals" => (Ty::Datetime, true, "=="),
+        "DateLessThan" => (Ty::Datetime, false, "<"),
+        "DateLessThanEquals" => (Ty::Datetime, false, "<="),
+        "DateGreaterThan" => (Ty::Datetime, false, ">"),
+        "DateGreaterThanEquals" => (Ty::Datetime, false, ">="),
+        "Bool" => (Ty::Bool, false, "=="),
+        "IpAddress" => (Ty::Ipaddr, false, "isInRange"),
+        "NotIpAddress" => (Ty::Ipaddr, true, "isInRange"),
+        _ => return Err(format!("condition operator `{operator}` is not supported")),
+    };
+    if values.is_empty() {
+        return Err(format!("`{operator}` has no values for `{key}`"));
+    }
+    let missing_satisfies = negated || if_exists;
+    let guarded = |test: String| {
+        if missing_satisfies {
+            format!("(if {has} then {test} else true)")
+        } else {
+            format!("({has} && {test})")
+        }
+    };
+    if let Some(qualifier) = qualifier {
+        // Set operators are only supported for equality, where Cedar has
+        // `containsAny` and `containsAll`
+        if negated || cmp != "==" || matches!(ty, Ty::Datetime) {
+            return Err(format!("condition operator `{operator}` is not supported"));
+        }
+        let set = format!(
+            "[{}]",
+            values
+                .iter()
+                .map(|value| ty.literal(value))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ")
+        );
+        translated.context.push((key.into(), Ty::Set(Box::new(ty))));
+        return Ok(if qualifier == "ForAnyValue" {
+            guarded(format!("{attr}.containsAny({set})"))
+        } else {
+            // Like IAM, `ForAllValues` is satisfied by a missing key
+            format!("(if {has} then {set}.containsAll({attr}) else true)")
+        });
+    }
+    let tests = values
+        .iter()
+        .map(|value| match cmp {
+            "like" => Ok(format!("{attr} like \"{}\"", like_pattern(value))),
+            "isInRange" => Ok(format!("{attr}.isInRange({})", ty.literal(value)?)),
+            cmp => Ok(format!("{attr} {cmp} {}", ty.literal(value)?)),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    translated.context.push((key.into(), ty));
+    let test = any(&tests);
+    Ok(if negated {
+        // None of the values match
+        format!("!({has} && {test})")
+    } else {
+        guarded(test)
+    })
+}
@@ -0,0 +1,357 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A machine-readable inventory of a policy set, e.g., for periodic access
+//! reviews
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cedar_policy_core::ast;
+use serde_json::json;
+
+use super::bundle::sha256_hex;
+use super::{Effect, EntityTypeName, EntityUid, PolicyId, PolicySet};
+
+/// Whether a [`PolicyInventoryEntry`] is for a static policy, a template, or
+/// a template-linked policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PolicyInventoryKind {
+    /// A static policy
+    Static,
+    /// A template
+    Template,
+    /// A policy linked from a template
+    TemplateLinked,
+}
+
+impl PolicyInventoryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Static => "static",
+            Self::Template => "template",
+            Self::TemplateLinked => "templateLinked",
+        }
+    }
+}
+
+/// Metrics of how complex the conditions of a policy are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PolicyComplexity {
+    conditions: usize,
+    size: usize,
+    depth: usize,
+}
+
+impl PolicyComplexity {
+    /// The number of `when` and `unless` clauses
+    pub fn conditions(&self) -> usize {
+        self.conditions
+    }
+
+    /// The number of expression nodes in the clauses, or 0 if there are none
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// How deeply the expressions in the clauses are nested, or 0 if there
+    /// are none
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+/// The inventory entry of one static policy, template, or template-linked
+/// policy, as computed by [`PolicyInventory::new`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyInventoryEntry {
+    id: PolicyId,
+    kind: PolicyInventoryKind,
+    template_id: Option<PolicyId>,
+    /// The values linked to the slots of a template-linked policy
+    links: BTreeMap<String, String>,
+    effect: Effect,
+    scope: [String; 3],
+    annotations: BTreeMap<String, String>,
+    entity_types: BTreeSet<EntityTypeName>,
+    actions: BTreeSet<EntityUid>,
+    complexity: PolicyComplexity,
+    hash: String,
+}
+
+impl PolicyInventoryEntry {
+    /// The id of the policy or template
+    pub fn id(&self) -> &PolicyId {
+        &self.id
+    }
+
+    /// Whether this is a static policy, a template, or a template-linked
+    /// policy
+    pub fn kind(&self) -> PolicyInventoryKind {
+        self.kind
+    }
+
+    /// The template a template-linked policy is linked from
+    pub fn template_id(&self) -> Option<&PolicyId> {
+        self.template_id.as_ref()
+    }
+
+    /// The effect of the policy
+    pub fn effect(&self) -> Effect {
+        self.effect
+    }
+
+    /// The annotations of the policy, sorted by key
+    pub fn annotations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.annotations
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The entity types the policy mentions in entity literals, `is` tests,
+    /// and linked slot values, in sorted order
+    pub fn entity_types(&self) -> impl Iterator<Item = &EntityTypeName> {
+        self.entity_types.iter()
+    }
+
+    /// The actions the policy mentions, in sorted order
+    pub fn actions(&self) -> impl Iterator<Item = &EntityUid> {
+        self.actions.iter()
+    }
+
+    /// Metrics of how complex the conditions of the policy are
+    pub fn complexity(&self) -> PolicyComplexity {
+        self.complexity
+    }
+
+    /// The SHA-256 digest of the policy in a canonical text form, as a
+    /// lowercase hex string. It does not depend on the id of the policy, or
+    /// on formatting and comments, but changes whenever the annotations,
+    /// scope, or conditions of the policy do, or for a template-linked policy
+    /// its linked values.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let [principal, action, resource] = &self.scope;
+        let mut json = json!({
+            "id": self.id.to_string(),
+            "kind": self.kind.as_str(),
+            "effect": self.effect.to_string(),
+            "scope": {
+                "principal": principal,
+                "action": action,
+                "resource": resource,
+            },
+            "annotations": self.annotations,
+            "entityTypes": self.entity_types.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "actions": self.actions.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "complexity": {
+                "conditions": self.complexity.conditions,
+                "size": self.complexity.size,
+                "depth": self.complexity.depth,
+            },
+            "hash": self.hash,
+        });
+        if let (Some(template_id), Some(object)) = (&self.template_id, json.as_object_mut()) {
+            object.insert("template".into(), template_id.to_string().into());
+            object.insert("links".into(), json!(self.links));
+        }
+        json
+    }
+}
+
+/// A machine-readable inventory of the policies and templates of a
+/// [`PolicySet`].
+///
+/// The inventory records the effect, scope, and annotations of every static
+/// policy, template, and template-linked policy, the entity types and actions
+/// it mentions, metrics of its complexity, and a hash identifying its
+/// content.
+///
+/// ```
+/// # use cedar_policy::{PolicyInventory, PolicySet};
+/// let pset: PolicySet = r#"
+///     @owner("photos-team")
+///     permit(principal, action == Action::"view", resource is Photo)
+///     when { resource.public };
+/// "#.parse().unwrap();
+/// let inventory = PolicyInventory::new(&pset).to_json();
+/// let entry = &inventory["policies"][0];
+/// assert_eq!(entry["id"], "policy0");
+/// assert_eq!(entry["scope"]["resource"], "resource is Photo");
+/// assert_eq!(entry["annotations"]["owner"], "photos-team");
+/// assert_eq!(entry["complexity"]["conditions"], 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyInventory {
+    entries: Vec<PolicyInventoryEntry>,
+}
+
+impl PolicyInventory {
+    /// Take the inventory of `policies`
+    pub fn new(policies: &PolicySet) -> Self {
+        let templates = policies.templates().map(|t| {
+            let scope = [
+                t.ast.principal_constraint().to_string(),
+                t.ast.action_constraint().to_string(),
+                t.ast.resource_constraint().to_string(),
+            ];
+            let conditions = t.lossless.est().ok().map(|est| est.conditions().len());
+            entry(
+                t.id().clone(),
+                PolicyInventoryKind::Template,
+                None,
+                &t.ast,
+                scope,
+                conditions,
+                &ast::SlotEnv::new(),
+            )
+        });
+        let statics_and_links = policies.policies().map(|p| {
+            let scope = [
+                p.ast.principal_constraint().to_string(),
+                p.ast.action_constraint().to_string(),
+                p.ast.resource_constraint().to_string(),
+            ];
+            // Linked policies have the conditions of their template
+            let (kind, lossless) = p.template_id().map_or(
+                (PolicyInventoryKind::Static, Some(&p.lossless)),
+                |template_id| {
+                    (
+                        PolicyInventoryKind::TemplateLinked,
+                        policies.template(template_id).map(|t| &t.lossless),
+                    )
+                },
+            );
+            let conditions = lossless
+                .and_then(|lossless| lossless.est().ok())
+                .map(|est| est.conditions().len());
+            entry(
+                p.id().clone(),
+                kind,
+                p.template_id().cloned(),
+                p.ast.template(),
+                scope,
+                conditions,
+                p.ast.env(),
+            )
+        });
+        let mut entries = templates.chain(statics_and_links).collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.id.to_string());
+        Self { entries }
+    }
+
+    /// The entries of the inventory, sorted by id
+    pub fn entries(&self) -> impl Iterator<Item = &PolicyInventoryEntry> {
+        self.entries.iter()
+    }
+
+    /// Render the inventory as JSON: an object whose `policies` are the
+    /// entries of the inventory, each an object with the `id`, `kind`
+    /// (`static`, `template`, or `templateLinked`), `effect`, `scope`,
+    /// `annotations`, `entityTypes`, `actions`, `complexity`, and `hash` of
+    /// the policy. The entries of template-linked policies also have the id
+    /// of their `template` and their `links`, mapping slots to linked values.
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "policies": self.entries.iter().map(PolicyInventoryEntry::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// The inventory entry of the policy or template `id` with the body of the
+/// template `t`, where `conditions` is the number of its condition clauses if
+/// known and `env` its linked slot values
+fn entry(
+    id: PolicyId,
+    kind: PolicyInventoryKind,
+    template_id: Option<PolicyId>,
+    t: &ast::Template,
+    scope: [String; 3],
+    conditions: Option<usize>,
+    env: &ast::SlotEnv,
+) -> PolicyInventoryEntry {
+    let mut entity_types = env
+        .values()
+        .map(|uid| EntityTypeName(uid.entity_type().clone()))
+        .collect::<BTreeSet<_>>();
+    let mut actions = BTreeSet::new();
+    for e in t.condition().subexpressions() {
+        match e.expr_kind() {
+            ast::ExprKind::Lit(ast::Literal::EntityUID(uid)) if uid.is_action() => {
+                actions.insert(EntityUid(uid.as_ref().clone()));
+            }
+            ast::ExprKind::Lit(ast::Literal::EntityUID(uid)) => {
+                entity_types.insert(EntityTypeName(uid.entity_type().clone()));
+            }
+            ast::ExprKind::Is { entity_type, .. } => {
+                entity_types.insert(EntityTypeName(entity_type.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    let body = t.non_scope_constraints();
+    let is_trivial = matches!(
+        body.expr_kind(),
+        ast::ExprKind::Lit(ast::Literal::Bool(true))
+    );
+    let complexity = match conditions.unwrap_or_else(|| usize::from(!is_trivial)) {
+        0 => PolicyComplexity {
+            conditions: 0,
+            size: 0,
+            depth: 0,
+        },
+        conditions => PolicyComplexity {
+            conditions,
+            size: body.subexpressions().count(),
+            depth: depth(body),
+        },
+    };
+
+    let links = env
+        .iter()
+        .map(|(slot, uid)| (slot.to_string(), uid.to_string()))
+        .collect::<BTreeMap<_, _>>();
+    let canonical = std::iter::once(t.to_string())
+        .chain(links.iter().map(|(slot, uid)| format!("{slot} = {uid}")))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    PolicyInventoryEntry {
+        id,
+        kind,
+        template_id,
+        links,
+        effect: t.effect(),
+        scope,
+        annotations: t
+            .annotations()
+            .map(|(key, value)| (key.to_string(), value.as_ref().to_string()))
+            .collect(),
+        entity_types,
+        actions,
+        complexity,
+        hash: sha256_hex(canonical.as_bytes()),
+    }
+}
+
+/// The nesting depth of `e`, which is 1 for a literal or variable
+fn depth(e: &ast::Expr) -> usize {
+    1 + e.children().into_iter().map(depth).max().unwrap_or(0)
+}
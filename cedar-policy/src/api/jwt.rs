@@ -0,0 +1,656 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Mapping the claims of verified JSON Web Tokens to a principal and a
+//! request context
+
+use std::collections::{BTreeMap, HashSet};
+use std::str::FromStr;
+
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::SchemaType as CoreSchemaType;
+use cedar_policy_core::extensions::Extensions;
+use miette::Diagnostic;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{
+    Context, Entity, EntityId, EntityTypeName, EntityUid, RequestComponent, RestrictedExpression,
+    Schema, SchemaAttribute, SchemaType,
+};
+
+/// Maps the claims of a verified JWT to a principal entity, with attributes,
+/// and a request context, converting each claim to the type the [`Schema`]
+/// declares for its attribute.
+///
+/// Claims are named by their names, e.g., `email`, or by JSON pointers into
+/// the claims, e.g., `/realm_access/roles`. Claims are converted as follows:
+/// - to `String`s from strings, and entities from strings (their ids)
+/// - to `Long`s from integers, and from strings of integers
+/// - to `Bool`s from booleans, and from the strings `"true"` and `"false"`
+/// - to sets from arrays, from strings of elements separated by spaces (as
+///   in the `scope` claim), and from single elements (as in the `aud` claim)
+/// - to records from objects, leaving out the claims the record type doesn't
+///   declare
+/// - to extension values from the strings their constructors take, and to
+///   `datetime`s from numbers of seconds since the Unix epoch (as in the
+///   `exp` and `iat` claims)
+///
+/// Verifying the token is up to the caller; this only maps its claims.
+///
+/// ```
+/// # use cedar_policy::{ClaimsMapping, Schema};
+/// # use std::str::FromStr;
+/// let schema = Schema::from_str(r#"
+///     entity User { email: String, roles: Set<String> };
+///     entity Document;
+///     action view appliesTo {
+///         principal: User,
+///         resource: Document,
+///         context: { scopes: Set<String>, mfa?: Bool },
+///     };
+/// "#).unwrap();
+/// let mapping = ClaimsMapping::from_json_str(r#"{
+///     "principal": {
+///         "type": "User",
+///         "attributes": { "email": "email", "roles": "/realm_access/roles" }
+///     },
+///     "context": { "scopes": "scope", "mfa": "mfa" }
+/// }"#).unwrap();
+/// let claims = serde_json::json!({
+///     "sub": "alice",
+///     "email": "alice@example.com",
+///     "realm_access": { "roles": ["editor"] },
+///     "scope": "documents:read documents:write",
+/// });
+/// let mapped = mapping.map(&claims, &schema, &r#"Action::"view""#.parse().unwrap()).unwrap();
+/// assert_eq!(mapped.principal().uid().to_string(), r#"User::"alice""#);
+///
+/// let claims = serde_json::json!({ "sub": "bob", "email": 7, "realm_access": { "roles": "editor" } });
+/// let err = mapping.map(&claims, &schema, &r#"Action::"view""#.parse().unwrap()).unwrap_err();
+/// let problems: Vec<String> = err.problems().map(ToString::to_string).collect();
+/// assert_eq!(problems, [
+///     "claim `email` for attribute `email` of the principal has the wrong type: expected String, found `7`",
+///     "claim `scope` for attribute `scopes` of the context is missing",
+/// ]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClaimsMapping {
+    principal_type: EntityTypeName,
+    principal_id: String,
+    attributes: BTreeMap<String, String>,
+    context: BTreeMap<String, String>,
+}
+
+/// The JSON format of a [`ClaimsMapping`]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingJson {
+    principal: PrincipalJson,
+    #[serde(default)]
+    context: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PrincipalJson {
+    #[serde(rename = "type")]
+    ty: String,
+    id: Option<String>,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+}
+
+impl ClaimsMapping {
+    /// Create a mapping to principals of type `principal_type`, whose ids
+    /// are the `sub` claim, with no attributes, and an empty context
+    pub fn new(principal_type: EntityTypeName) -> Self {
+        Self {
+            principal_type,
+            principal_id: "sub".into(),
+            attributes: BTreeMap::new(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Take the id of the principal from `claim` instead of `sub`
+    #[must_use]
+    pub fn principal_id(mut self, claim: impl Into<String>) -> Self {
+        self.principal_id = claim.into();
+        self
+    }
+
+    /// Set the attribute `attr` of the principal to `claim`
+    #[must_use]
+    pub fn attribute(mut self, attr: impl Into<String>, claim: impl Into<String>) -> Self {
+        self.attributes.insert(attr.into(), claim.into());
+        self
+    }
+
+    /// Set the attribute `attr` of the context to `claim`
+    #[must_use]
+    pub fn context(mut self, attr: impl Into<String>, claim: impl Into<String>) -> Self {
+        self.context.insert(attr.into(), claim.into());
+        self
+    }
+
+    /// Parse a mapping from JSON of the form
+    /// `{ "principal": { "type": ..., "id": ..., "attributes": { ... } }, "context": { ... } }`,
+    /// where `id` (by default `sub`), `attributes`, and `context` are
+    /// optional, and `attributes` and `context` map attributes to claims.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ClaimsMappingConfigError`] if `json` is not of this form,
+    /// or the principal type is not a valid entity type name.
+    pub fn from_json_value(json: serde_json::Value) -> Result<Self, ClaimsMappingConfigError> {
+        let json: MappingJson =
+            serde_json::from_value(json).map_err(|e| ClaimsMappingConfigError {
+                message: e.to_string(),
+            })?;
+        let principal_type =
+            EntityTypeName::from_str(&json.principal.ty).map_err(|e| ClaimsMappingConfigError {
+                message: format!("invalid principal type `{}`: {e}", json.principal.ty),
+            })?;
+        Ok(Self {
+            principal_type,
+            principal_id: json.principal.id.unwrap_or_else(|| "sub".into()),
+            attributes: json.principal.attributes,
+            context: json.context,
+        })
+    }
+
+    /// Parse a mapping from a JSON string; see [`ClaimsMapping::from_json_value`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ClaimsMappingConfigError`] if `json` is not a valid
+    /// mapping.
+    pub fn from_json_str(json: &str) -> Result<Self, ClaimsMappingConfigError> {
+        let json = serde_json::from_str(json).map_err(|e| ClaimsMappingConfigError {
+            message: e.to_string(),
+        })?;
+        Self::from_json_value(json)
+    }
+
+    /// Map `claims` to a principal and a context for requests for `action`,
+    /// converting each claim to the type `schema` declares for its attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ClaimsMappingError`] with every problem found, such as
+    /// claims which are missing or can't be converted, and attributes the
+    /// schema doesn't declare, or requires but the mapping doesn't map.
+    pub fn map(
+        &self,
+        claims: &serde_json::Value,
+        schema: &Schema,
+        action: &EntityUid,
+    ) -> Result<MappedClaims, ClaimsMappingError> {
+        let mut mapper = Mapper {
+            claims,
+            problems: Vec::new(),
+        };
+        let id = match mapper.claim(&self.principal_id) {
+            Some(serde_json::Value::String(id)) => Some(id.clone()),
+            Some(serde_json::Value::Number(id)) if id.is_i64() || id.is_u64() => {
+                Some(id.to_string())
+            }
+            Some(found) => {
+                mapper.problems.push(ClaimsMappingProblem::WrongType {
+                    claim: self.principal_id.clone(),
+                    component: RequestComponent::Principal,
+                    attr: None,
+                    expected: "a string id".into(),
+                    found: abbreviate(found),
+                });
+                None
+            }
+            None => {
+                mapper.problems.push(ClaimsMappingProblem::MissingClaim {
+                    claim: self.principal_id.clone(),
+                    component: RequestComponent::Principal,
+                    attr: None,
+                });
+                None
+            }
+        };
+        let attrs = if let Some(declared) = schema.entity_type_attributes(&self.principal_type) {
+            mapper.attributes(RequestComponent::Principal, &self.attributes, &declared)
+        } else {
+            mapper
+                .problems
+                .push(ClaimsMappingProblem::UndeclaredPrincipalType(
+                    self.principal_type.clone(),
+                ));
+            None
+        };
+        let context =
+            if let Some(SchemaType::Record { attributes, .. }) = schema.action_context(action) {
+                mapper.attributes(RequestComponent::Context, &self.context, &attributes)
+            } else {
+                mapper
+                    .problems
+                    .push(ClaimsMappingProblem::UndeclaredAction(action.clone()));
+                None
+            };
+        let (Some(id), Some(attrs), Some(context), true) =
+            (id, attrs, context, mapper.problems.is_empty())
+        else {
+            return Err(ClaimsMappingError {
+                problems: mapper.problems,
+            });
+        };
+        let uid = EntityUid::from_type_name_and_id(self.principal_type.clone(), EntityId::new(id));
+        let principal =
+            Entity::new(uid, attrs.into_iter().collect(), HashSet::new()).map_err(|e| {
+                ClaimsMappingProblem::Evaluation {
+                    component: RequestComponent::Principal,
+                    message: e.to_string(),
+                }
+            });
+        let context = Context::from_pairs(context).map_err(|e| ClaimsMappingProblem::Evaluation {
+            component: RequestComponent::Context,
+            message: e.to_string(),
+        });
+        match (principal, context) {
+            (Ok(principal), Ok(context)) => Ok(MappedClaims { principal, context }),
+            (principal, context) => Err(ClaimsMappingError {
+                problems: principal.err().into_iter().chain(context.err()).collect(),
+            }),
+        }
+    }
+}
+
+/// The principal and context mapped from the claims of a JWT by a
+/// [`ClaimsMapping`]
+#[derive(Debug, Clone)]
+pub struct MappedClaims {
+    principal: Entity,
+    context: Context,
+}
+
+impl MappedClaims {
+    /// The principal, with its attributes. Add it to the entities of the
+    /// request.
+    pub fn principal(&self) -> &Entity {
+        &self.principal
+    }
+
+    /// The context of the request
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Consume the `MappedClaims`, returning the principal and the context
+    pub fn into_parts(self) -> (Entity, Context) {
+        (self.principal, self.context)
+    }
+}
+
+/// Error parsing a [`ClaimsMapping`] from JSON
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("invalid claims mapping: {message}")]
+pub struct ClaimsMappingConfigError {
+    message: String,
+}
+
+/// Every problem found mapping claims with a [`ClaimsMapping`]
+#[derive(Debug, Diagnostic, Error)]
+#[error("failed to map claims: {}", .problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct ClaimsMappingError {
+    /// Never empty
+    #[related]
+    problems: Vec<ClaimsMappingProblem>,
+}
+
+impl ClaimsMappingError {
+    /// The problems found, for the principal's id, then its attributes, then
+    /// the context, each in order of attribute name
+    pub fn problems(&self) -> impl Iterator<Item = &ClaimsMappingProblem> {
+        self.problems.iter()
+    }
+
+    /// Consume the error, returning the problems found
+    pub fn into_problems(self) -> impl Iterator<Item = ClaimsMappingProblem> {
+        self.problems.into_iter()
+    }
+}
+
+/// A problem found mapping claims with a [`ClaimsMapping`]
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum ClaimsMappingProblem {
+    /// A claim the mapping maps, or a field of a claim the schema requires,
+    /// is missing
+    #[error("claim `{claim}` {} is missing", for_attr(*.component, .attr.as_deref()))]
+    MissingClaim {
+        /// The claim, with the path to the missing field for fields of claims
+        claim: String,
+        /// Whether the claim is mapped to the principal or the context
+        component: RequestComponent,
+        /// The attribute the claim is mapped to, or `None` for the
+        /// principal's id
+        attr: Option<String>,
+    },
+    /// A claim can't be converted to the type the schema declares for its
+    /// attribute
+    #[error("claim `{claim}` {} has the wrong type: expected {expected}, found `{found}`", for_attr(*.component, .attr.as_deref()))]
+    WrongType {
+        /// The claim, with the path to the offending element or field for
+        /// elements of arrays and fields of objects, e.g., `groups/2`
+        claim: String,
+        /// Whether the claim is mapped to the principal or the context
+        component: RequestComponent,
+        /// The attribute the claim is mapped to, or `None` for the
+        /// principal's id
+        attr: Option<String>,
+        /// The type the claim is converted to
+        expected: String,
+        /// The claim's value, abbreviated if long
+        found: String,
+    },
+    /// The principal type is not declared in the schema
+    #[error("principal type `{0}` is not declared in the schema")]
+    UndeclaredPrincipalType(EntityTypeName),
+    /// The action is not declared in the schema
+    #[error("action `{0}` is not declared in the schema")]
+    UndeclaredAction(EntityUid),
+    /// The mapping maps a claim to an attribute the schema doesn't declare
+    #[error("the schema declares no attribute `{attr}` for the {component}")]
+    UndeclaredAttribute {
+        /// Whether the attribute is of the principal or the context
+        component: RequestComponent,
+        /// The attribute
+        attr: String,
+    },
+    /// The schema requires an attribute the mapping doesn't map any claim to
+    #[error("no claim is mapped to the required attribute `{attr}` of the {component}")]
+    UnmappedAttribute {
+        /// Whether the attribute is of the principal or the context
+        component: RequestComponent,
+        /// The attribute
+        attr: String,
+    },
+    /// The principal or context couldn't be built from the converted claims
+    #[error("failed to build the {component}: {message}")]
+    Evaluation {
+        /// Whether building the principal or the context failed
+        component: RequestComponent,
+        /// Why
+        message: String,
+    },
+}
+
+fn for_attr(component: RequestComponent, attr: Option<&str>) -> String {
+    attr.map_or_else(
+        || format!("for the id of the {component}"),
+        |attr| format!("for attribute `{attr}` of the {component}"),
+    )
+}
+
+/// `value` as JSON, abbreviated to at most 40 characters
+fn abbreviate(value: &serde_json::Value) -> String {
+    let json = value.to_string();
+    match json.char_indices().nth(40) {
+        Some((end, _)) => format!("{}...", &json[..end]),
+        None => json,
+    }
+}
+
+/// How `ty` is described in problems
+fn describe(ty: &SchemaType) -> String {
+    match ty {
+        SchemaType::Bool => "Bool".into(),
+        SchemaType::Long => "Long".into(),
+        SchemaType::String => "String".into(),
+        SchemaType::Set(element) => format!("Set<{}>", describe(element)),
+        SchemaType::Record { .. } => "a record".into(),
+        SchemaType::Entity(ty) => format!("an entity id of type `{ty}`"),
+        SchemaType::Extension(name) => format!("a `{name}` value"),
+    }
+}
+
+/// The date and time `seconds` after the Unix epoch, in the format of the
+/// `datetime` extension
+fn datetime_from_epoch(seconds: i64) -> String {
+    // Days to civil dates, from Howard Hinnant's `civil_from_days`
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[derive(Debug)]
+struct Mapper<'a> {
+    claims: &'a serde_json::Value,
+    problems: Vec<ClaimsMappingProblem>,
+}
+
+impl<'a> Mapper<'a> {
+    /// The claim named `claim`, or at the JSON pointer `claim`
+    fn claim(&self, claim: &str) -> Option<&'a serde_json::Value> {
+        if claim.starts_with('/') {
+            self.claims.pointer(claim)
+        } else {
+            self.claims.get(claim)
+        }
+    }
+
+    /// Convert the claims mapped to the attributes of the principal or
+    /// context, returning `None` if there were problems
+    fn attributes(
+        &mut self,
+        component: RequestComponent,
+        mapped: &BTreeMap<String, String>,
+        declared: &BTreeMap<String, SchemaAttribute>,
+    ) -> Option<Vec<(String, RestrictedExpression)>> {
+        let problems = self.problems.len();
+        for (attr, declared) in declared {
+            if declared.is_required() && !mapped.contains_key(attr) {
+                self.problems.push(ClaimsMappingProblem::UnmappedAttribute {
+                    component,
+                    attr: attr.clone(),
+                });
+            }
+        }
+        let mut values = Vec::new();
+        for (attr, claim) in mapped {
+            let Some(declared) = declared.get(attr) else {
+                self.problems
+                    .push(ClaimsMappingProblem::UndeclaredAttribute {
+                        component,
+                        attr: attr.clone(),
+                    });
+                continue;
+            };
+            let target = Target {
+                component,
+                attr,
+                path: claim.clone(),
+            };
+            match self.claim(claim) {
+                Some(value) => {
+                    if let Some(value) = self.convert(value, declared.ty(), &target) {
+                        values.push((attr.clone(), value));
+                    }
+                }
+                None if declared.is_required() => self.problems.push(target.missing()),
+                None => {}
+            }
+        }
+        (self.problems.len() == problems).then_some(values)
+    }
+
+    /// Convert `value` to `ty`, reporting a problem if it can't be
+    fn convert(
+        &mut self,
+        value: &serde_json::Value,
+        ty: &SchemaType,
+        target: &Target<'_>,
+    ) -> Option<RestrictedExpression> {
+        use serde_json::Value;
+        let converted = match (ty, value) {
+            (SchemaType::String, Value::String(s)) => {
+                Some(RestrictedExpression::new_string(s.clone()))
+            }
+            (SchemaType::Long, Value::Number(n)) => n.as_i64().map(RestrictedExpression::new_long),
+            (SchemaType::Long, Value::String(s)) => {
+                s.parse().ok().map(RestrictedExpression::new_long)
+            }
+            (SchemaType::Bool, Value::Bool(b)) => Some(RestrictedExpression::new_bool(*b)),
+            (SchemaType::Bool, Value::String(s)) => {
+                s.parse().ok().map(RestrictedExpression::new_bool)
+            }
+            (SchemaType::Entity(ty), Value::String(id)) => {
+                Some(RestrictedExpression::new_entity_uid(
+                    EntityUid::from_type_name_and_id(ty.clone(), EntityId::new(id)),
+                ))
+            }
+            (SchemaType::Set(element), Value::Array(values)) => {
+                let elements: Vec<_> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| self.convert(value, element, &target.child(&i.to_string())))
+                    .collect();
+                return elements
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(RestrictedExpression::new_set);
+            }
+            (SchemaType::Set(element), Value::String(s)) => {
+                let elements: Vec<_> = s
+                    .split_whitespace()
+                    .map(|s| self.convert(&Value::String(s.into()), element, target))
+                    .collect();
+                return elements
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(RestrictedExpression::new_set);
+            }
+            (SchemaType::Set(element), value) => {
+                return self
+                    .convert(value, element, target)
+                    .map(|element| RestrictedExpression::new_set([element]));
+            }
+            (
+                SchemaType::Record {
+                    attributes,
+                    additional_attributes: _,
+                },
+                Value::Object(fields),
+            ) => {
+                let mut record = Vec::new();
+                let mut valid = true;
+                for (attr, declared) in attributes {
+                    let child = target.child(attr);
+                    match fields.get(attr) {
+                        Some(value) => match self.convert(value, declared.ty(), &child) {
+                            Some(value) => record.push((attr.clone(), value)),
+                            None => valid = false,
+                        },
+                        None if declared.is_required() => {
+                            self.problems.push(child.missing());
+                            valid = false;
+                        }
+                        None => {}
+                    }
+                }
+                // Attributes are unique, as the keys of `attributes`
+                return if valid {
+                    RestrictedExpression::new_record(record).ok()
+                } else {
+                    None
+                };
+            }
+            (SchemaType::Extension(name), Value::String(s)) => extension_value(name, s),
+            (SchemaType::Extension(name), Value::Number(n)) if name == "datetime" => n
+                .as_i64()
+                .and_then(|seconds| extension_value(name, &datetime_from_epoch(seconds))),
+            _ => None,
+        };
+        if converted.is_none() {
+            self.problems.push(target.wrong_type(ty, value));
+        }
+        converted
+    }
+}
+
+/// The value of the extension type `name` its constructor constructs from
+/// `arg`, or `None` if there is no such constructor or it fails
+fn extension_value(name: &str, arg: &str) -> Option<RestrictedExpression> {
+    let constructor = Extensions::all_available().all_funcs().find(|f| {
+        f.is_constructor()
+            && matches!(f.return_type(), Some(CoreSchemaType::Extension { name: ty }) if ty.to_string() == name)
+            && matches!(f.arg_types(), [CoreSchemaType::String])
+    })?;
+    constructor.call(&[ast::Value::from(arg)]).ok()?;
+    Some(RestrictedExpression(
+        ast::RestrictedExpr::call_extension_fn(
+            constructor.name().clone(),
+            [ast::RestrictedExpr::val(arg)],
+        ),
+    ))
+}
+
+/// Where a value being converted goes, and where it came from
+#[derive(Debug)]
+struct Target<'a> {
+    component: RequestComponent,
+    attr: &'a str,
+    /// The claim, with the path to the value within it
+    path: String,
+}
+
+impl Target<'_> {
+    fn child(&self, key: &str) -> Self {
+        Self {
+            component: self.component,
+            attr: self.attr,
+            path: format!("{}/{key}", self.path),
+        }
+    }
+
+    fn missing(&self) -> ClaimsMappingProblem {
+        ClaimsMappingProblem::MissingClaim {
+            claim: self.path.clone(),
+            component: self.component,
+            attr: Some(self.attr.into()),
+        }
+    }
+
+    fn wrong_type(&self, ty: &SchemaType, value: &serde_json::Value) -> ClaimsMappingProblem {
+        ClaimsMappingProblem::WrongType {
+            claim: self.path.clone(),
+            component: self.component,
+            attr: Some(self.attr.into()),
+            expected: describe(ty),
+            found: abbreviate(value),
+        }
+    }
+}
@@ -0,0 +1,291 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Authorizing HTTP requests in `tower` services and `axum` handlers.
+//!
+//! An [`HttpRequestMapper`] supplied by the service maps each HTTP request to
+//! the Cedar [`Request`] authorizing it, which is authorized against the
+//! current snapshot of an [`AuthorizerHandle`], so that policies and entities
+//! can be refreshed while the service runs. Requests which are denied are
+//! answered with `403 Forbidden` without reaching the service.
+//!
+//! Decisions are logged by the [`DecisionLogger`](super::DecisionLogger)s
+//! registered on the handle's [`Authorizer`](super::Authorizer), if any.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use http::request::Parts;
+use http::StatusCode;
+
+use super::{AuthorizerHandle, Decision, Request, Response};
+
+/// Maps HTTP requests to the Cedar [`Request`]s authorizing them.
+///
+/// This is implemented for any `Fn(&http::request::Parts) ->
+/// Result<Request, http::StatusCode>` closure.
+pub trait HttpRequestMapper: Send + Sync {
+    /// Map the HTTP request with `parts` to the Cedar [`Request`] to authorize
+    /// it with, or return the status to reject it with without authorizing
+    /// it, e.g., [`StatusCode::UNAUTHORIZED`] if it isn't authenticated.
+    fn map_request(&self, parts: &Parts) -> Result<Request, StatusCode>;
+}
+
+impl<F: Fn(&Parts) -> Result<Request, StatusCode> + Send + Sync> HttpRequestMapper for F {
+    fn map_request(&self, parts: &Parts) -> Result<Request, StatusCode> {
+        self(parts)
+    }
+}
+
+/// Authorizes HTTP requests by mapping them to Cedar [`Request`]s with an
+/// [`HttpRequestMapper`] and authorizing those with an [`AuthorizerHandle`].
+///
+/// Cloning an `HttpAuthorizer` is cheap. Wrap a `tower` service with
+/// [`HttpAuthorizer::layer`], or, with the `axum` feature, extract
+/// [`Authorized`] in `axum` handlers.
+///
+/// ```
+/// # use cedar_policy::{Authorizer, AuthorizerHandle, Context, Entities, EntityUid, HttpAuthorizer, PolicySet, Request};
+/// # use std::str::FromStr;
+/// # use std::sync::Arc;
+/// let policies = PolicySet::from_str(r#"permit(principal, action == Action::"GET", resource);"#).unwrap();
+/// let handle = Arc::new(AuthorizerHandle::new(Authorizer::new(), policies, Entities::empty()));
+/// let authorizer = HttpAuthorizer::new(handle, |parts: &http::request::Parts| {
+///     let user = parts
+///         .headers
+///         .get("x-user")
+///         .and_then(|user| user.to_str().ok())
+///         .ok_or(http::StatusCode::UNAUTHORIZED)?;
+///     Request::new(
+///         EntityUid::from_type_name_and_id("User".parse().unwrap(), user.parse().unwrap()),
+///         EntityUid::from_type_name_and_id("Action".parse().unwrap(), parts.method.as_str().parse().unwrap()),
+///         EntityUid::from_type_name_and_id("Path".parse().unwrap(), parts.uri.path().parse().unwrap()),
+///         Context::empty(),
+///         None,
+///     )
+///     .map_err(|_| http::StatusCode::BAD_REQUEST)
+/// });
+///
+/// let (parts, ()) = http::Request::get("/photos").header("x-user", "alice").body(()).unwrap().into_parts();
+/// assert!(authorizer.authorize(&parts).is_ok());
+/// let (parts, ()) = http::Request::delete("/photos").header("x-user", "alice").body(()).unwrap().into_parts();
+/// assert_eq!(authorizer.authorize(&parts).unwrap_err(), http::StatusCode::FORBIDDEN);
+/// let (parts, ()) = http::Request::get("/photos").body(()).unwrap().into_parts();
+/// assert_eq!(authorizer.authorize(&parts).unwrap_err(), http::StatusCode::UNAUTHORIZED);
+/// ```
+#[derive(Clone)]
+pub struct HttpAuthorizer {
+    handle: Arc<AuthorizerHandle>,
+    mapper: Arc<dyn HttpRequestMapper>,
+}
+
+impl std::fmt::Debug for HttpAuthorizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpAuthorizer")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpAuthorizer {
+    /// Create an `HttpAuthorizer` which maps HTTP requests with `mapper` and
+    /// authorizes them against the current snapshot of `handle`
+    pub fn new(handle: Arc<AuthorizerHandle>, mapper: impl HttpRequestMapper + 'static) -> Self {
+        Self {
+            handle,
+            mapper: Arc::new(mapper),
+        }
+    }
+
+    /// Authorize the HTTP request with `parts`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the status to respond to the HTTP request with if it is not
+    /// authorized: the status returned by the [`HttpRequestMapper`] if it
+    /// couldn't be mapped, or [`StatusCode::FORBIDDEN`] if it was denied.
+    pub fn authorize(&self, parts: &Parts) -> Result<Authorized, StatusCode> {
+        let request = self.mapper.map_request(parts)?;
+        let response = self.handle.is_authorized(&request);
+        match response.decision() {
+            Decision::Allow => Ok(Authorized { request, response }),
+            Decision::Deny => Err(StatusCode::FORBIDDEN),
+        }
+    }
+
+    /// A `tower` [`Layer`](tower_layer::Layer) authorizing every request with
+    /// this `HttpAuthorizer` before passing it on to the service it wraps
+    pub fn layer(&self) -> AuthorizationLayer {
+        AuthorizationLayer {
+            authorizer: self.clone(),
+        }
+    }
+}
+
+/// An HTTP request which was allowed by an [`HttpAuthorizer`].
+///
+/// An [`AuthorizationLayer`] adds this to the
+/// [extensions](http::Request::extensions) of the requests it passes on. With
+/// the `axum` feature, it can be extracted in `axum` handlers, taking it from
+/// the extensions if the request passed through an [`AuthorizationLayer`],
+/// and otherwise authorizing the request with the [`HttpAuthorizer`] in the
+/// router's state (see `axum::extract::FromRef`).
+#[derive(Debug, Clone)]
+pub struct Authorized {
+    request: Request,
+    response: Response,
+}
+
+impl Authorized {
+    /// The Cedar request the HTTP request was mapped to
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// The response allowing the request, e.g., to see which policies
+    /// allowed it
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+}
+
+/// A `tower` [`Layer`](tower_layer::Layer) wrapping services with
+/// [`Authorization`]; see [`HttpAuthorizer::layer`]
+#[derive(Debug, Clone)]
+pub struct AuthorizationLayer {
+    authorizer: HttpAuthorizer,
+}
+
+impl<S> tower_layer::Layer<S> for AuthorizationLayer {
+    type Service = Authorization<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authorization {
+            inner,
+            authorizer: self.authorizer.clone(),
+        }
+    }
+}
+
+/// A `tower` [`Service`](tower_service::Service) which authorizes each HTTP
+/// request with an [`HttpAuthorizer`]
+///
+/// Allowed requests are passed on to the service it wraps; others get a
+/// response with an empty body and the status from
+/// [`HttpAuthorizer::authorize`].
+#[derive(Debug, Clone)]
+pub struct Authorization<S> {
+    inner: S,
+    authorizer: HttpAuthorizer,
+}
+
+impl<S, ReqBody, ResBody> tower_service::Service<http::Request<ReqBody>> for Authorization<S>
+where
+    S: tower_service::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = http::Response<ResBody>;
+    type Error = S::Error;
+    type Future = AuthorizationFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        let (mut parts, body) = request.into_parts();
+        match self.authorizer.authorize(&parts) {
+            Ok(authorized) => {
+                parts.extensions.insert(authorized);
+                AuthorizationFuture(FutureState::Authorized(Box::pin(
+                    self.inner.call(http::Request::from_parts(parts, body)),
+                )))
+            }
+            Err(status) => {
+                let mut response = http::Response::new(ResBody::default());
+                *response.status_mut() = status;
+                AuthorizationFuture(FutureState::Rejected(Some(response)))
+            }
+        }
+    }
+}
+
+/// Response future of [`Authorization`]
+pub struct AuthorizationFuture<F, B>(FutureState<F, B>);
+
+enum FutureState<F, B> {
+    /// The request was allowed and passed on to the wrapped service
+    Authorized(Pin<Box<F>>),
+    /// The request was not allowed. `None` once the response is returned.
+    Rejected(Option<http::Response<B>>),
+}
+
+// The wrapped future is boxed, and the response is never pinned
+impl<F, B> Unpin for AuthorizationFuture<F, B> {}
+
+impl<F, B> std::fmt::Debug for AuthorizationFuture<F, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            FutureState::Authorized(_) => f.write_str("AuthorizationFuture::Authorized"),
+            FutureState::Rejected(_) => f.write_str("AuthorizationFuture::Rejected"),
+        }
+    }
+}
+
+impl<F, B, E> Future for AuthorizationFuture<F, B>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = Result<http::Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.get_mut().0 {
+            FutureState::Authorized(future) => future.as_mut().poll(cx),
+            FutureState::Rejected(response) => response
+                .take()
+                .map_or(Poll::Pending, |response| Poll::Ready(Ok(response))),
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<S> axum_core::extract::FromRequestParts<S> for Authorized
+where
+    HttpAuthorizer: axum_core::extract::FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    // The signature `#[async_trait]` expands `async fn from_request_parts` to.
+    // Authorization doesn't wait on anything, so the future is ready at once.
+    fn from_request_parts<'parts, 'state, 'future>(
+        parts: &'parts mut Parts,
+        state: &'state S,
+    ) -> Pin<Box<dyn Future<Output = Result<Self, Self::Rejection>> + Send + 'future>>
+    where
+        'parts: 'future,
+        'state: 'future,
+        Self: 'future,
+    {
+        let authorized = parts.extensions.get::<Self>().map_or_else(
+            || <HttpAuthorizer as axum_core::extract::FromRef<S>>::from_ref(state).authorize(parts),
+            |authorized| Ok(authorized.clone()),
+        );
+        Box::pin(std::future::ready(authorized))
+    }
+}
@@ -0,0 +1,154 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Comparing the results of strict and permissive validation, for planning
+//! migrations from permissive to strict validation
+
+use std::collections::BTreeMap;
+
+use super::{PolicyId, ValidationError};
+
+/// How the strict and permissive validation of one policy differ, as computed
+/// by [`super::Validator::compare_validation_modes`]
+#[doc = include_str!("../../experimental_warning.md")]
+#[derive(Debug, Clone)]
+pub struct PolicyModeComparison {
+    id: PolicyId,
+    passes_strict: bool,
+    passes_permissive: bool,
+    strict_only_errors: Vec<ValidationError>,
+    permissive_only_errors: Vec<ValidationError>,
+}
+
+impl PolicyModeComparison {
+    /// The id of the policy or template
+    pub fn id(&self) -> &PolicyId {
+        &self.id
+    }
+
+    /// Whether the policy passes strict validation
+    pub fn passes_strict(&self) -> bool {
+        self.passes_strict
+    }
+
+    /// Whether the policy passes permissive validation
+    pub fn passes_permissive(&self) -> bool {
+        self.passes_permissive
+    }
+
+    /// The errors strict validation reports for the policy which permissive
+    /// validation does not. These must be fixed before the policy passes
+    /// strict validation.
+    pub fn strict_only_errors(&self) -> impl Iterator<Item = &ValidationError> {
+        self.strict_only_errors.iter()
+    }
+
+    /// The errors permissive validation reports for the policy which strict
+    /// validation does not
+    pub fn permissive_only_errors(&self) -> impl Iterator<Item = &ValidationError> {
+        self.permissive_only_errors.iter()
+    }
+}
+
+/// The differences between validating a policy set in strict and in
+/// permissive mode, as computed by
+/// [`super::Validator::compare_validation_modes`]
+#[doc = include_str!("../../experimental_warning.md")]
+#[derive(Debug, Clone)]
+pub struct ValidationModeComparison {
+    /// The policies whose errors differ between the modes, sorted by id
+    policies: Vec<PolicyModeComparison>,
+}
+
+impl ValidationModeComparison {
+    pub(super) fn new(
+        strict: cedar_policy_validator::ValidationResult,
+        permissive: cedar_policy_validator::ValidationResult,
+    ) -> Self {
+        let (strict, _) = strict.into_errors_and_warnings();
+        let (permissive, _) = permissive.into_errors_and_warnings();
+        let strict = strict.collect::<Vec<_>>();
+        let permissive = permissive.collect::<Vec<_>>();
+
+        let mut policies: BTreeMap<String, PolicyModeComparison> = BTreeMap::new();
+        for (errors, other_errors, is_strict) in
+            [(&strict, &permissive, true), (&permissive, &strict, false)]
+        {
+            for error in errors {
+                let only_in_this_mode = !other_errors.contains(error);
+                let error = ValidationError::from(error.clone());
+                let policy = policies
+                    .entry(error.policy_id().to_string())
+                    .or_insert_with(|| PolicyModeComparison {
+                        id: error.policy_id().clone(),
+                        passes_strict: true,
+                        passes_permissive: true,
+                        strict_only_errors: Vec::new(),
+                        permissive_only_errors: Vec::new(),
+                    });
+                match (is_strict, only_in_this_mode) {
+                    (true, true) => {
+                        policy.passes_strict = false;
+                        policy.strict_only_errors.push(error);
+                    }
+                    (true, false) => policy.passes_strict = false,
+                    (false, true) => {
+                        policy.passes_permissive = false;
+                        policy.permissive_only_errors.push(error);
+                    }
+                    (false, false) => policy.passes_permissive = false,
+                }
+            }
+        }
+        // Keep only the policies whose errors differ between the modes
+        policies.retain(|_, policy| {
+            !policy.strict_only_errors.is_empty() || !policy.permissive_only_errors.is_empty()
+        });
+        Self {
+            policies: policies.into_values().collect(),
+        }
+    }
+
+    /// Whether both modes report the same errors for every policy
+    pub fn modes_agree(&self) -> bool {
+        self.policies.is_empty()
+    }
+
+    /// The policies and templates whose errors differ between the modes,
+    /// sorted by id. These are the policies which pass one mode but not the
+    /// other, and the policies which fail both modes with different errors.
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyModeComparison> {
+        self.policies.iter()
+    }
+
+    /// The ids of the policies and templates which pass permissive validation
+    /// but not strict validation, sorted by id
+    pub fn failing_only_strict(&self) -> impl Iterator<Item = &PolicyId> {
+        self.policies
+            .iter()
+            .filter(|p| p.passes_permissive && !p.passes_strict)
+            .map(PolicyModeComparison::id)
+    }
+
+    /// The ids of the policies and templates which pass strict validation but
+    /// not permissive validation, sorted by id
+    pub fn failing_only_permissive(&self) -> impl Iterator<Item = &PolicyId> {
+        self.policies
+            .iter()
+            .filter(|p| p.passes_strict && !p.passes_permissive)
+            .map(PolicyModeComparison::id)
+    }
+}
@@ -0,0 +1,1595 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Best-effort translation of OPA/Rego policies into Cedar policies and a
+//! schema skeleton.
+//!
+//! The supported subset is `allow` and `deny` rules whose bodies compare
+//! fields of `input` with literals and with each other: `==`, `!=`, `=`,
+//! `<`, `<=`, `>`, `>=`, `in`, iteration with `[_]` compared with `==`,
+//! `not`, and the built-ins `startswith`, `endswith`, and `contains`. Each
+//! body of an `allow` rule becomes a `permit` policy, and each body of a
+//! `deny` rule a `forbid` policy. Rules using anything else (`some`, `data`,
+//! helper rules, local variables, comprehensions, other built-ins, ...) are
+//! not translated at all, since dropping some of the conditions of a rule
+//! would change which requests it applies to; each is reported as a
+//! [`RegoImportIssue`] locating the construct.
+//!
+//! Rego treats a reference to a missing field as undefined, making the
+//! expression containing it false, so the translated conditions test that
+//! attributes exist (with `has`) before reading them.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use cedar_policy_core::ast::{self, PatternElem};
+use cedar_policy_core::impl_diagnostic_from_source_loc_field;
+use cedar_policy_core::parser::Loc;
+use miette::Diagnostic;
+use serde_json::json;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use super::{EntityId, EntityTypeName, EntityUid, Policy, PolicyId, PolicySet, SchemaFragment};
+
+/// Translates Rego policies into Cedar; see [`RegoImporter::import`].
+///
+/// Fields of `input` are mapped to the request: by default, `input.principal`
+/// is the principal, of entity type `User`, `input.action` is the action, and
+/// `input.resource` is the resource, of entity type `Resource`. Any other
+/// field of `input` is a context attribute. Comparing the principal or
+/// resource field itself with a string compares the entity's id.
+///
+/// ```
+/// # use cedar_policy::RegoImporter;
+/// let import = RegoImporter::new()
+///     .principal("user", "User".parse().unwrap())
+///     .resource("document", "Document".parse().unwrap())
+///     .import(r#"
+///         package docs
+///
+///         default allow := false
+///
+///         allow if {
+///             input.action == "read"
+///             input.document.owner == input.user
+///         }
+///
+///         allow if {
+///             some group in input.user.groups
+///             group == "admins"
+///         }
+///     "#);
+/// assert_eq!(import.policies().policies().count(), 1);
+/// // `some` isn't supported, so the second rule is reported and not translated
+/// assert_eq!(import.issues().len(), 1);
+/// assert_eq!(import.issues()[0].line(), 12);
+/// println!("{}", import.schema().to_cedarschema().unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegoImporter {
+    principal: InputEntity,
+    action_field: String,
+    resource: InputEntity,
+}
+
+/// A field of `input` which is mapped to an entity of the request
+#[derive(Debug, Clone)]
+struct InputEntity {
+    field: String,
+    entity_type: EntityTypeName,
+}
+
+impl Default for RegoImporter {
+    fn default() -> Self {
+        #[allow(clippy::expect_used)]
+        let entity_type = |name| EntityTypeName::from_str(name).expect("valid entity type name");
+        Self {
+            principal: InputEntity {
+                field: "principal".into(),
+                entity_type: entity_type("User"),
+            },
+            action_field: "action".into(),
+            resource: InputEntity {
+                field: "resource".into(),
+                entity_type: entity_type("Resource"),
+            },
+        }
+    }
+}
+
+impl RegoImporter {
+    /// Create an importer with the default mapping of `input`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `input.<field>` to the principal, which is an entity of type
+    /// `entity_type`
+    #[must_use]
+    pub fn principal(mut self, field: impl Into<String>, entity_type: EntityTypeName) -> Self {
+        self.principal = InputEntity {
+            field: field.into(),
+            entity_type,
+        };
+        self
+    }
+
+    /// Map `input.<field>` to the action. Actions are entities of type
+    /// `Action`, whose ids are the strings the field is compared with.
+    #[must_use]
+    pub fn action(mut self, field: impl Into<String>) -> Self {
+        self.action_field = field.into();
+        self
+    }
+
+    /// Map `input.<field>` to the resource, which is an entity of type
+    /// `entity_type`
+    #[must_use]
+    pub fn resource(mut self, field: impl Into<String>, entity_type: EntityTypeName) -> Self {
+        self.resource = InputEntity {
+            field: field.into(),
+            entity_type,
+        };
+        self
+    }
+
+    /// Translate the Rego module `rego` into Cedar, as far as possible.
+    ///
+    /// Rules which can't be translated are reported in
+    /// [`RegoImport::issues`], and left out of the policies. The schema
+    /// declares the principal and resource entity types and the context, with
+    /// the attributes the policies read, typed by what they are compared
+    /// with (`String` if nothing says otherwise), and the actions the
+    /// policies name.
+    pub fn import(&self, rego: &str) -> RegoImport {
+        let mut translator = Translator {
+            importer: self,
+            src: Arc::from(rego),
+            text: rego,
+            policies: PolicySet::new(),
+            counts: BTreeMap::new(),
+            actions: Vec::new(),
+            attrs: BTreeMap::new(),
+            issues: Vec::new(),
+        };
+        match tokenize(rego) {
+            Ok(tokens) => translator.module(&tokens),
+            Err(unsupported) => translator.report(unsupported),
+        }
+        translator.finish()
+    }
+}
+
+/// The result of translating a Rego module with a [`RegoImporter`]
+#[derive(Debug, Clone)]
+pub struct RegoImport {
+    policies: PolicySet,
+    schema: SchemaFragment,
+    issues: Vec<RegoImportIssue>,
+}
+
+impl RegoImport {
+    /// The Cedar policies translated from the Rego rules. Their ids are the
+    /// name of the rule followed by the number of earlier bodies of rules
+    /// with that name, e.g., `allow0`, `allow1`, and `deny0`.
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// A schema skeleton for the policies
+    pub fn schema(&self) -> &SchemaFragment {
+        &self.schema
+    }
+
+    /// The constructs which could not be translated, in source order
+    pub fn issues(&self) -> &[RegoImportIssue] {
+        &self.issues
+    }
+
+    /// Consume the `RegoImport`, returning the policies, the schema, and the
+    /// issues
+    pub fn into_parts(self) -> (PolicySet, SchemaFragment, Vec<RegoImportIssue>) {
+        (self.policies, self.schema, self.issues)
+    }
+}
+
+/// A Rego construct which could not be translated into Cedar
+#[derive(Debug, Clone, Error)]
+#[error("{message}")]
+pub struct RegoImportIssue {
+    message: String,
+    loc: Loc,
+    line: usize,
+    column: usize,
+}
+
+impl RegoImportIssue {
+    /// What couldn't be translated, and why
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The line of the construct in the Rego source, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column of the construct in the Rego source, in characters,
+    /// starting from 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Diagnostic for RegoImportIssue {
+    impl_diagnostic_from_source_loc_field!(loc);
+}
+
+/// A construct which can't be translated, located by its byte range in the
+/// Rego source
+#[derive(Debug)]
+struct Unsupported {
+    span: Range<usize>,
+    message: String,
+}
+
+impl Unsupported {
+    fn new(span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Str(String),
+    Number(String),
+    Punct(&'static str),
+    Newline,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+impl Token {
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(&self.kind, TokenKind::Punct(p) if *p == punct)
+    }
+
+    fn is_ident(&self, ident: &str) -> bool {
+        matches!(&self.kind, TokenKind::Ident(i) if i == ident)
+    }
+}
+
+const PUNCTS: [&str; 26] = [
+    ":=", "==", "!=", "<=", ">=", "{", "}", "[", "]", "(", ")", ".", ",", ";", "=", "<", ">", "-",
+    "+", "*", "/", "%", "|", "&", ":", "!",
+];
+
+#[allow(clippy::too_many_lines)]
+fn tokenize(src: &str) -> Result<Vec<Token>, Unsupported> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            '\n' => TokenKind::Newline,
+            c if c.is_whitespace() => continue,
+            '#' => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                continue;
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => s.push('\n'),
+                            Some((_, 't')) => s.push('\t'),
+                            Some((_, 'r')) => s.push('\r'),
+                            Some((_, 'b')) => s.push('\u{8}'),
+                            Some((_, 'f')) => s.push('\u{c}'),
+                            Some((_, 'u')) => {
+                                let hex: String = (0..4)
+                                    .filter_map(|_| chars.next())
+                                    .map(|(_, c)| c)
+                                    .collect();
+                                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                    Some(c) => s.push(c),
+                                    None => {
+                                        return Err(Unsupported::new(
+                                            start..start + 1,
+                                            format!("invalid escape `\\u{hex}` in string"),
+                                        ))
+                                    }
+                                }
+                            }
+                            Some((_, c)) => s.push(c),
+                            None => break,
+                        },
+                        Some((_, '\n')) | None => {
+                            return Err(Unsupported::new(start..start + 1, "unterminated string"))
+                        }
+                        Some((_, c)) => s.push(c),
+                    }
+                }
+                TokenKind::Str(s)
+            }
+            '`' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '`')) => break,
+                        Some((_, c)) => s.push(c),
+                        None => {
+                            return Err(Unsupported::new(
+                                start..start + 1,
+                                "unterminated raw string",
+                            ))
+                        }
+                    }
+                }
+                TokenKind::Str(s)
+            }
+            c if c.is_ascii_digit() => {
+                let mut s = String::from(c);
+                while let Some((_, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '.')
+                {
+                    s.push(c);
+                }
+                TokenKind::Number(s)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut s = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    s.push(c);
+                }
+                TokenKind::Ident(s)
+            }
+            _ => {
+                let rest = src.get(start..).unwrap_or_default();
+                match PUNCTS.iter().find(|p| rest.starts_with(**p)) {
+                    Some(p) => {
+                        for _ in 1..p.len() {
+                            chars.next();
+                        }
+                        TokenKind::Punct(p)
+                    }
+                    None => {
+                        return Err(Unsupported::new(
+                            start..start + c.len_utf8(),
+                            format!("unexpected character `{c}`"),
+                        ))
+                    }
+                }
+            }
+        };
+        let end = chars.peek().map_or(src.len(), |(i, _)| *i);
+        let end = match kind {
+            TokenKind::Newline => start + 1,
+            _ => end,
+        };
+        tokens.push(Token {
+            kind,
+            span: start..end,
+        });
+    }
+    Ok(tokens)
+}
+
+/// Index of the token after the group opened by the token at `open`, which
+/// must be `{`, `[`, or `(`; or `tokens.len()` if the group isn't closed
+fn skip_group(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if ["{", "[", "("].iter().any(|p| token.is_punct(p)) {
+            depth += 1;
+        } else if ["}", "]", ")"].iter().any(|p| token.is_punct(p)) {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+    }
+    tokens.len()
+}
+
+/// Index of the next newline at the top level of `tokens`, from `from`
+fn line_end(tokens: &[Token], from: usize) -> usize {
+    let mut i = from;
+    while let Some(token) = tokens.get(i) {
+        match &token.kind {
+            TokenKind::Newline => return i,
+            TokenKind::Punct("{" | "[" | "(") => i = skip_group(tokens, i),
+            _ => i += 1,
+        }
+    }
+    tokens.len()
+}
+
+/// Byte range of `tokens[from..to]`
+fn span_of(tokens: &[Token], from: usize, to: usize) -> Range<usize> {
+    let start = tokens.get(from).map_or(0, |t| t.span.start);
+    let end = tokens
+        .get(from..to)
+        .and_then(<[Token]>::last)
+        .map_or(start, |t| t.span.end);
+    start..end
+}
+
+/// The part of the request a reference into `input` refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Root {
+    Principal,
+    Action,
+    Resource,
+    Context,
+}
+
+impl Root {
+    fn var(self) -> ast::Var {
+        match self {
+            Self::Principal => ast::Var::Principal,
+            Self::Action => ast::Var::Action,
+            Self::Resource => ast::Var::Resource,
+            Self::Context => ast::Var::Context,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Lit {
+    Str(SmolStr),
+    Long(i64),
+    Bool(bool),
+}
+
+impl Lit {
+    fn ty(&self) -> Ty {
+        match self {
+            Self::Str(_) => Ty::String,
+            Self::Long(_) => Ty::Long,
+            Self::Bool(_) => Ty::Bool,
+        }
+    }
+
+    fn expr(&self) -> ast::Expr {
+        match self {
+            Self::Str(s) => ast::Expr::val(s.clone()),
+            Self::Long(i) => ast::Expr::val(*i),
+            Self::Bool(b) => ast::Expr::val(*b),
+        }
+    }
+}
+
+/// An operand of a Rego expression
+#[derive(Debug, Clone)]
+enum Term {
+    /// The principal, action, or resource itself
+    Entity(Root),
+    /// An attribute of the principal, action, resource, or context; `iterate`
+    /// if it is followed by `[_]`, iterating over the elements of a set
+    Attr {
+        root: Root,
+        attrs: Vec<SmolStr>,
+        iterate: bool,
+    },
+    Lit(Lit),
+    Set(Vec<Lit>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+impl CmpOp {
+    fn flipped(self) -> Self {
+        match self {
+            Self::Less => Self::Greater,
+            Self::LessEq => Self::GreaterEq,
+            Self::Greater => Self::Less,
+            Self::GreaterEq => Self::LessEq,
+            op => op,
+        }
+    }
+
+    fn expr(self, lhs: ast::Expr, rhs: ast::Expr) -> ast::Expr {
+        match self {
+            Self::Eq => ast::Expr::is_eq(lhs, rhs),
+            Self::NotEq => ast::Expr::noteq(lhs, rhs),
+            Self::Less => ast::Expr::less(lhs, rhs),
+            Self::LessEq => ast::Expr::lesseq(lhs, rhs),
+            Self::Greater => ast::Expr::greater(lhs, rhs),
+            Self::GreaterEq => ast::Expr::greatereq(lhs, rhs),
+        }
+    }
+}
+
+/// Attribute type inferred for the schema skeleton
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ty {
+    String,
+    Long,
+    Bool,
+    Entity(EntityTypeName),
+    Set(Box<Self>),
+}
+
+impl Ty {
+    fn json(&self) -> serde_json::Value {
+        match self {
+            Self::String => json!({ "type": "String" }),
+            Self::Long => json!({ "type": "Long" }),
+            Self::Bool => json!({ "type": "Boolean" }),
+            Self::Entity(ty) => json!({ "type": "Entity", "name": ty.to_string() }),
+            Self::Set(element) => json!({ "type": "Set", "element": element.json() }),
+        }
+    }
+}
+
+/// Attributes read from the principal, resource, or context, nested as they
+/// are in the request
+#[derive(Debug, Default)]
+struct AttrTree {
+    /// `None` if nothing says what the type of the attribute is
+    ty: Option<Ty>,
+    attrs: BTreeMap<SmolStr, Self>,
+}
+
+impl AttrTree {
+    fn record_json(&self) -> serde_json::Value {
+        let attrs: serde_json::Map<_, _> = self
+            .attrs
+            .iter()
+            .map(|(attr, tree)| {
+                let mut json = if tree.attrs.is_empty() {
+                    tree.ty.as_ref().unwrap_or(&Ty::String).json()
+                } else {
+                    tree.record_json()
+                };
+                if let Some(json) = json.as_object_mut() {
+                    json.insert("required".into(), false.into());
+                }
+                (attr.to_string(), json)
+            })
+            .collect();
+        json!({ "type": "Record", "attributes": attrs })
+    }
+}
+
+/// A translated condition of a rule body
+#[derive(Debug)]
+enum Condition {
+    /// `principal`, `action`, or `resource` equal to an entity, which can go
+    /// in the scope of the policy
+    Scope(Root, EntityUid),
+    When(ast::Expr),
+}
+
+struct Translator<'a> {
+    importer: &'a RegoImporter,
+    src: std::sync::Arc<str>,
+    text: &'a str,
+    policies: PolicySet,
+    /// Number of policies translated from each rule
+    counts: BTreeMap<String, usize>,
+    /// Actions named by the policies, in order of appearance
+    actions: Vec<SmolStr>,
+    attrs: BTreeMap<Root, AttrTree>,
+    issues: Vec<RegoImportIssue>,
+}
+
+impl Translator<'_> {
+    fn report(&mut self, unsupported: Unsupported) {
+        let before = self.text.get(..unsupported.span.start).unwrap_or_default();
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .map_or(0, |line| line.chars().count())
+            + 1;
+        self.issues.push(RegoImportIssue {
+            message: unsupported.message,
+            loc: Loc::new(unsupported.span, std::sync::Arc::clone(&self.src)),
+            line,
+            column,
+        });
+    }
+
+    fn module(&mut self, tokens: &[Token]) {
+        let mut i = 0;
+        while let Some(token) = tokens.get(i) {
+            i = match &token.kind {
+                TokenKind::Newline => i + 1,
+                TokenKind::Ident(keyword) if keyword == "package" => line_end(tokens, i),
+                TokenKind::Ident(keyword) if keyword == "import" => {
+                    let end = line_end(tokens, i);
+                    let keywords = tokens
+                        .get(i + 1)
+                        .is_some_and(|t| t.is_ident("future") || t.is_ident("rego"));
+                    if !keywords {
+                        self.report(Unsupported::new(
+                            span_of(tokens, i, end),
+                            "imports are not supported; only the `future.keywords` and `rego.v1` imports are allowed",
+                        ));
+                    }
+                    end
+                }
+                TokenKind::Ident(keyword) if keyword == "default" => self.default(tokens, i),
+                TokenKind::Ident(_) => self.rule(tokens, i),
+                _ => {
+                    let end = line_end(tokens, i).max(i + 1);
+                    self.report(Unsupported::new(
+                        span_of(tokens, i, end),
+                        "unsupported Rego syntax",
+                    ));
+                    end
+                }
+            };
+        }
+    }
+
+    /// Check a `default <rule> = <value>` at `start`, returning the index of
+    /// the token after it
+    fn default(&mut self, tokens: &[Token], start: usize) -> usize {
+        let end = line_end(tokens, start);
+        let rule = tokens.get(start + 1);
+        let value = tokens.get(start + 3);
+        let is_false = value.is_some_and(|v| v.is_ident("false")) && end == start + 4;
+        match rule.map(|t| &t.kind) {
+            Some(TokenKind::Ident(name)) if (name == "allow" || name == "deny") && is_false => (),
+            Some(TokenKind::Ident(name)) if name == "allow" || name == "deny" => {
+                self.report(Unsupported::new(
+                    span_of(tokens, start, end),
+                    format!("only `false` is supported as the default of `{name}`, since Cedar denies requests no policy permits"),
+                ));
+            }
+            _ => self.report(Unsupported::new(
+                span_of(tokens, start, end),
+                "defaults are only supported for `allow` and `deny`",
+            )),
+        }
+        end
+    }
+
+    /// Translate the rule at `start`, returning the index of the token after
+    /// it
+    fn rule(&mut self, tokens: &[Token], start: usize) -> usize {
+        let name = match tokens.get(start).map(|t| &t.kind) {
+            Some(TokenKind::Ident(name)) => name.clone(),
+            _ => return start + 1,
+        };
+        // Find the body: `{ ... }`, or a single expression after `if`
+        let mut i = start + 1;
+        let mut after_if = false;
+        let body = loop {
+            match tokens.get(i) {
+                Some(t) if t.is_punct("{") => break Some((i + 1, skip_group(tokens, i) - 1)),
+                Some(t) if t.is_ident("if") => {
+                    after_if = true;
+                    i += 1;
+                }
+                Some(t) if after_if && !matches!(t.kind, TokenKind::Newline) => {
+                    break Some((i, line_end(tokens, i)))
+                }
+                Some(t) if matches!(t.kind, TokenKind::Newline) || t.is_punct(";") => break None,
+                Some(_) => i += 1,
+                None => break None,
+            }
+        };
+        let Some((body_start, body_end)) = body else {
+            let end = line_end(tokens, start).max(start + 1);
+            self.report(Unsupported::new(
+                span_of(tokens, start, end),
+                format!("rule `{name}` has no body"),
+            ));
+            return end;
+        };
+        let end = if tokens.get(body_end).is_some_and(|t| t.is_punct("}")) {
+            body_end + 1
+        } else {
+            body_end
+        };
+        let header_span = span_of(tokens, start + 1, i);
+        let mut header = tokens.get(start + 1..i).unwrap_or_default();
+        while let [rest @ .., last] = header {
+            if last.is_ident("if") {
+                header = rest;
+            } else {
+                break;
+            }
+        }
+        // The name of the variable holding the message of `deny[msg]` and
+        // `deny contains msg` rules, or `None` for boolean rules
+        let header: Option<Option<&str>> = match header {
+            [] => Some(None),
+            [eq, value] if (eq.is_punct("=") || eq.is_punct(":=")) && value.is_ident("true") => {
+                Some(None)
+            }
+            [open, msg, close] if open.is_punct("[") && close.is_punct("]") && name == "deny" => {
+                Some(Some(ident_name(msg)))
+            }
+            [contains, msg] if contains.is_ident("contains") && name == "deny" => {
+                Some(Some(ident_name(msg)))
+            }
+            _ => None,
+        };
+        if let Some(t) = tokens.get(end).filter(|t| t.is_ident("else")) {
+            self.report(Unsupported::new(t.span.clone(), "`else` is not supported"));
+            return line_end(tokens, end).max(end + 1);
+        }
+        let effect = match name.as_str() {
+            "allow" => ast::Effect::Permit,
+            "deny" => ast::Effect::Forbid,
+            _ => {
+                self.report(Unsupported::new(
+                    span_of(tokens, start, start + 1),
+                    format!("rule `{name}` is not supported; only `allow` and `deny` rules are translated"),
+                ));
+                return end;
+            }
+        };
+        let Some(message) = header else {
+            self.report(Unsupported::new(
+                header_span,
+                format!("rule `{name}` must be a boolean rule defined as `true`, or, for `deny`, a set of messages"),
+            ));
+            return end;
+        };
+        let message = message.filter(|msg| !msg.is_empty());
+        match self.body(
+            tokens.get(body_start..body_end).unwrap_or_default(),
+            message,
+        ) {
+            Ok(conditions) => self.add_policy(&name, effect, conditions),
+            Err(unsupported) => self.report(unsupported),
+        }
+        end
+    }
+
+    fn add_policy(&mut self, name: &str, effect: ast::Effect, conditions: Vec<Condition>) {
+        let mut scope: BTreeMap<Root, EntityUid> = BTreeMap::new();
+        let mut when = Vec::new();
+        for condition in conditions {
+            match condition {
+                Condition::Scope(root, uid) => {
+                    if let Some(existing) = scope.get(&root) {
+                        let var = ast::Expr::var(root.var());
+                        when.push(ast::Expr::is_eq(var, ast::Expr::val(existing.0.clone())));
+                    }
+                    scope.insert(root, uid);
+                }
+                Condition::When(expr) => when.push(expr),
+            }
+        }
+        let scope_constraint = |root| {
+            scope
+                .get(&root)
+                .map(|uid| format!(" == {uid}"))
+                .unwrap_or_default()
+        };
+        let condition = when
+            .into_iter()
+            .reduce(ast::Expr::and)
+            .map(|expr| format!(" when {{ {expr} }}"))
+            .unwrap_or_default();
+        let text = format!(
+            "{effect}(principal{}, action{}, resource{}){condition};",
+            scope_constraint(Root::Principal),
+            scope_constraint(Root::Action),
+            scope_constraint(Root::Resource),
+        );
+        let count = self.counts.entry(name.to_string()).or_default();
+        let id = PolicyId::new(format!("{name}{count}"));
+        *count += 1;
+        match Policy::parse(Some(id), &text) {
+            Ok(policy) => {
+                // Ids are unique, so adding the policy can't fail
+                let _ = self.policies.add(policy);
+            }
+            Err(e) => self.report(Unsupported::new(
+                0..0,
+                format!("failed to translate rule `{name}`: {e}"),
+            )),
+        }
+    }
+
+    /// Translate the statements of a rule body. Statements assigning the
+    /// `message` of a `deny` rule only say why the request is denied, so
+    /// they are skipped.
+    fn body(
+        &mut self,
+        tokens: &[Token],
+        message: Option<&str>,
+    ) -> Result<Vec<Condition>, Unsupported> {
+        let mut conditions = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let end = statement_end(tokens, i);
+            let statement = tokens.get(i..end).unwrap_or_default();
+            let assigns_message = match (message, statement) {
+                (Some(msg), [var, eq, ..]) => {
+                    var.is_ident(msg) && (eq.is_punct(":=") || eq.is_punct("="))
+                }
+                _ => false,
+            };
+            if !statement.is_empty() && !assigns_message {
+                conditions.push(self.statement(statement)?);
+            }
+            i = end + 1;
+        }
+        Ok(conditions)
+    }
+
+    fn statement(&mut self, tokens: &[Token]) -> Result<Condition, Unsupported> {
+        let span = span_of(tokens, 0, tokens.len());
+        match tokens.first() {
+            Some(t) if t.is_ident("some") || t.is_ident("every") => {
+                return Err(Unsupported::new(
+                    span,
+                    format!(
+                        "`{}` (iteration with variables) is not supported",
+                        ident_name(t)
+                    ),
+                ))
+            }
+            Some(t) if t.is_ident("not") => {
+                let rest = tokens.get(1..).unwrap_or_default();
+                let condition = self.expression(rest)?;
+                return Ok(Condition::When(ast::Expr::not(condition_expr(condition))));
+            }
+            _ => (),
+        }
+        if let Some(t) = tokens.iter().find(|t| t.is_ident("with")) {
+            return Err(Unsupported::new(t.span.clone(), "`with` is not supported"));
+        }
+        self.expression(tokens)
+    }
+
+    fn expression(&mut self, tokens: &[Token]) -> Result<Condition, Unsupported> {
+        let span = span_of(tokens, 0, tokens.len());
+        if let [name, open, ..] = tokens {
+            let builtin = ["startswith", "endswith", "contains"]
+                .into_iter()
+                .find(|f| name.is_ident(f));
+            if let Some(builtin) = builtin.filter(|_| open.is_punct("(")) {
+                if skip_group(tokens, 1) == tokens.len() {
+                    let args = tokens.get(2..tokens.len() - 1).unwrap_or_default();
+                    return self.builtin(builtin, args, span);
+                }
+            }
+        }
+        let mut parser = TermParser {
+            importer: self.importer,
+            tokens,
+            pos: 0,
+        };
+        let lhs = parser.term()?;
+        let op = match parser.peek() {
+            None => return self.truthy(lhs, span),
+            Some(t) => t.clone(),
+        };
+        parser.pos += 1;
+        let rhs = parser.term()?;
+        if let Some(t) = parser.peek() {
+            return Err(Unsupported::new(
+                t.span.start..span.end,
+                "unsupported Rego expression",
+            ));
+        }
+        let cmp = match &op.kind {
+            TokenKind::Punct("==" | "=") => Some(CmpOp::Eq),
+            TokenKind::Punct("!=") => Some(CmpOp::NotEq),
+            TokenKind::Punct("<") => Some(CmpOp::Less),
+            TokenKind::Punct("<=") => Some(CmpOp::LessEq),
+            TokenKind::Punct(">") => Some(CmpOp::Greater),
+            TokenKind::Punct(">=") => Some(CmpOp::GreaterEq),
+            TokenKind::Ident(i) if i == "in" => None,
+            TokenKind::Punct(":=") => {
+                return Err(Unsupported::new(
+                    op.span,
+                    "assignment to local variables is not supported",
+                ))
+            }
+            _ => return Err(Unsupported::new(op.span, "unsupported operator")),
+        };
+        match cmp {
+            Some(cmp) => self.compare(lhs, cmp, rhs, span),
+            None => self.membership(lhs, rhs, span),
+        }
+    }
+
+    /// A call to the string built-in `name` with `args`
+    fn builtin(
+        &mut self,
+        name: &str,
+        args: &[Token],
+        span: Range<usize>,
+    ) -> Result<Condition, Unsupported> {
+        let mut terms = Vec::new();
+        let mut i = 0;
+        while i < args.len() {
+            let mut end = i;
+            while let Some(token) = args.get(end).filter(|t| !t.is_punct(",")) {
+                end = match &token.kind {
+                    TokenKind::Punct("{" | "[" | "(") => skip_group(args, end),
+                    _ => end + 1,
+                };
+            }
+            let arg = args.get(i..end).unwrap_or_default();
+            let mut parser = TermParser {
+                importer: self.importer,
+                tokens: arg,
+                pos: 0,
+            };
+            terms.push(parser.term()?);
+            if let Some(t) = parser.peek() {
+                return Err(Unsupported::new(
+                    t.span.clone(),
+                    "unsupported Rego expression",
+                ));
+            }
+            i = end + 1;
+        }
+        match terms.as_slice() {
+            [Term::Attr {
+                root,
+                attrs,
+                iterate: false,
+            }, Term::Lit(Lit::Str(s))] => {
+                let chars = s.chars().map(PatternElem::Char);
+                let pattern: ast::Pattern = match name {
+                    "startswith" => chars.chain([PatternElem::Wildcard]).collect(),
+                    "endswith" => std::iter::once(PatternElem::Wildcard)
+                        .chain(chars)
+                        .collect(),
+                    _ => std::iter::once(PatternElem::Wildcard)
+                        .chain(chars)
+                        .chain([PatternElem::Wildcard])
+                        .collect(),
+                };
+                self.infer(*root, attrs, Ty::String, &span);
+                Ok(Condition::When(guarded(*root, attrs, |e| {
+                    ast::Expr::like(e, pattern)
+                })))
+            }
+            _ => Err(Unsupported::new(
+                span,
+                format!("`{name}` is only supported with a field of `input` and a string"),
+            )),
+        }
+    }
+
+    /// A term on its own, which holds if it's defined and not `false`
+    fn truthy(&mut self, term: Term, span: Range<usize>) -> Result<Condition, Unsupported> {
+        match term {
+            Term::Attr {
+                root,
+                attrs,
+                iterate: false,
+            } => {
+                self.infer(root, &attrs, Ty::Bool, &span);
+                Ok(Condition::When(guarded(root, &attrs, |e| e)))
+            }
+            Term::Lit(Lit::Bool(b)) => Ok(Condition::When(ast::Expr::val(b))),
+            _ => Err(Unsupported::new(
+                span,
+                "only comparisons and boolean fields of `input` are supported as conditions",
+            )),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn compare(
+        &mut self,
+        lhs: Term,
+        op: CmpOp,
+        rhs: Term,
+        span: Range<usize>,
+    ) -> Result<Condition, Unsupported> {
+        let ordering = !matches!(op, CmpOp::Eq | CmpOp::NotEq);
+        match (lhs, rhs) {
+            // Put literals on the right
+            (lhs @ (Term::Lit(_) | Term::Set(_)), rhs @ (Term::Entity(_) | Term::Attr { .. })) => {
+                self.compare(rhs, op.flipped(), lhs, span)
+            }
+            (Term::Entity(root), Term::Lit(Lit::Str(id))) if !ordering => {
+                let uid = self.entity_uid(root, &id);
+                Ok(match op {
+                    CmpOp::Eq => Condition::Scope(root, uid),
+                    _ => {
+                        Condition::When(op.expr(ast::Expr::var(root.var()), ast::Expr::val(uid.0)))
+                    }
+                })
+            }
+            (
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: false,
+                },
+                Term::Lit(lit),
+            ) => {
+                if ordering && !matches!(lit, Lit::Long(_)) {
+                    return Err(Unsupported::new(
+                        span,
+                        "ordering comparisons are only supported between numbers",
+                    ));
+                }
+                self.infer(root, &attrs, lit.ty(), &span);
+                Ok(Condition::When(guarded(root, &attrs, |e| {
+                    op.expr(e, lit.expr())
+                })))
+            }
+            (
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: true,
+                },
+                Term::Lit(lit),
+            ) if op == CmpOp::Eq => {
+                self.infer(root, &attrs, Ty::Set(Box::new(lit.ty())), &span);
+                Ok(Condition::When(guarded(root, &attrs, |e| {
+                    ast::Expr::contains(e, lit.expr())
+                })))
+            }
+            (
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: false,
+                },
+                Term::Entity(entity),
+            )
+            | (
+                Term::Entity(entity),
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: false,
+                },
+            ) if !ordering => {
+                if let Some(ty) = self.entity_type(entity) {
+                    self.infer(root, &attrs, Ty::Entity(ty), &span);
+                }
+                Ok(Condition::When(guarded(root, &attrs, |e| {
+                    op.expr(e, ast::Expr::var(entity.var()))
+                })))
+            }
+            (
+                Term::Attr {
+                    root: lroot,
+                    attrs: lattrs,
+                    iterate: false,
+                },
+                Term::Attr {
+                    root: rroot,
+                    attrs: rattrs,
+                    iterate: false,
+                },
+            ) => {
+                if ordering {
+                    self.infer(lroot, &lattrs, Ty::Long, &span);
+                    self.infer(rroot, &rattrs, Ty::Long, &span);
+                } else {
+                    self.declare(lroot, &lattrs);
+                    self.declare(rroot, &rattrs);
+                }
+                let compared = op.expr(value(lroot, &lattrs), value(rroot, &rattrs));
+                Ok(Condition::When(
+                    guard_only(lroot, &lattrs)
+                        .into_iter()
+                        .chain(guard_only(rroot, &rattrs))
+                        .chain([compared])
+                        .reduce(ast::Expr::and)
+                        .unwrap_or_else(|| ast::Expr::val(true)),
+                ))
+            }
+            (Term::Entity(_) | Term::Attr { .. }, Term::Set(_)) => Err(Unsupported::new(
+                span,
+                "comparing with a collection is not supported; use `in`",
+            )),
+            _ => Err(Unsupported::new(span, "unsupported comparison")),
+        }
+    }
+
+    /// `lhs in rhs`
+    fn membership(
+        &mut self,
+        lhs: Term,
+        rhs: Term,
+        span: Range<usize>,
+    ) -> Result<Condition, Unsupported> {
+        match (lhs, rhs) {
+            (Term::Entity(root), Term::Set(lits)) => {
+                let uids = lits
+                    .iter()
+                    .map(|lit| match lit {
+                        Lit::Str(id) => Ok(ast::Expr::val(self.entity_uid(root, id).0)),
+                        _ => Err(Unsupported::new(
+                            span.clone(),
+                            "entities can only be compared with strings",
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Condition::When(ast::Expr::contains(
+                    ast::Expr::set(uids),
+                    ast::Expr::var(root.var()),
+                )))
+            }
+            (
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: false,
+                },
+                Term::Set(lits),
+            ) => {
+                if let Some(lit) = lits.first() {
+                    self.infer(root, &attrs, lit.ty(), &span);
+                }
+                let set = ast::Expr::set(lits.iter().map(Lit::expr));
+                Ok(Condition::When(guarded(root, &attrs, |e| {
+                    ast::Expr::contains(set, e)
+                })))
+            }
+            (
+                Term::Lit(lit),
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: false,
+                },
+            ) => {
+                self.infer(root, &attrs, Ty::Set(Box::new(lit.ty())), &span);
+                Ok(Condition::When(guarded(root, &attrs, |e| {
+                    ast::Expr::contains(e, lit.expr())
+                })))
+            }
+            (
+                Term::Entity(entity),
+                Term::Attr {
+                    root,
+                    attrs,
+                    iterate: false,
+                },
+            ) => {
+                if let Some(ty) = self.entity_type(entity) {
+                    self.infer(root, &attrs, Ty::Set(Box::new(Ty::Entity(ty))), &span);
+                }
+                Ok(Condition::When(guarded(root, &attrs, |e| {
+                    ast::Expr::contains(e, ast::Expr::var(entity.var()))
+                })))
+            }
+            _ => Err(Unsupported::new(span, "unsupported use of `in`")),
+        }
+    }
+
+    fn entity_type(&self, root: Root) -> Option<EntityTypeName> {
+        match root {
+            Root::Principal => Some(self.importer.principal.entity_type.clone()),
+            Root::Resource => Some(self.importer.resource.entity_type.clone()),
+            Root::Action | Root::Context => None,
+        }
+    }
+
+    fn entity_uid(&mut self, root: Root, id: &str) -> EntityUid {
+        let ty = self.entity_type(root).unwrap_or_else(|| {
+            if !self.actions.iter().any(|a| a == id) {
+                self.actions.push(id.into());
+            }
+            #[allow(clippy::expect_used)]
+            EntityTypeName::from_str("Action").expect("valid entity type name")
+        });
+        EntityUid::from_type_name_and_id(ty, EntityId::new(id))
+    }
+
+    /// The attributes of `root` in the schema, which are those of the
+    /// principal if `root` is the resource and they have the same type
+    fn tree(&mut self, root: Root) -> &mut AttrTree {
+        let shared = root == Root::Resource
+            && self.importer.resource.entity_type == self.importer.principal.entity_type;
+        let root = if shared { Root::Principal } else { root };
+        self.attrs.entry(root).or_default()
+    }
+
+    /// Declare the attribute at `attrs` of `root`, without saying what type
+    /// it is
+    fn declare(&mut self, root: Root, attrs: &[SmolStr]) {
+        let mut tree = self.tree(root);
+        for attr in attrs {
+            tree = tree.attrs.entry(attr.clone()).or_default();
+        }
+    }
+
+    /// Record that the attribute at `attrs` of `root` has type `ty`
+    fn infer(&mut self, root: Root, attrs: &[SmolStr], ty: Ty, span: &Range<usize>) {
+        let mut tree = self.tree(root);
+        for attr in attrs {
+            tree = tree.attrs.entry(attr.clone()).or_default();
+        }
+        let conflict = match &tree.ty {
+            None => {
+                tree.ty = Some(ty);
+                None
+            }
+            Some(existing) if *existing == ty => None,
+            Some(existing) => Some(format!(
+                "`{}` is used as a {} here, but as a {} elsewhere; the schema declares it as a {}",
+                attrs.join("."),
+                ty_name(&ty),
+                ty_name(existing),
+                ty_name(existing),
+            )),
+        };
+        if let Some(message) = conflict {
+            self.report(Unsupported::new(span.clone(), message));
+        }
+    }
+
+    fn finish(mut self) -> RegoImport {
+        let principal = &self.importer.principal.entity_type;
+        let resource = &self.importer.resource.entity_type;
+        let empty = AttrTree::default();
+        let mut namespaces: BTreeMap<String, serde_json::Map<String, serde_json::Value>> =
+            BTreeMap::new();
+        for (ty, root) in [(principal, Root::Principal), (resource, Root::Resource)] {
+            let shape = self.attrs.get(&root).unwrap_or(&empty).record_json();
+            namespaces
+                .entry(ty.namespace())
+                .or_default()
+                .entry(ty.basename())
+                .or_insert_with(|| json!({ "shape": shape }));
+        }
+        let context = self
+            .attrs
+            .get(&Root::Context)
+            .unwrap_or(&empty)
+            .record_json();
+        let actions: serde_json::Map<_, _> = self
+            .actions
+            .iter()
+            .map(|action| {
+                (
+                    action.to_string(),
+                    json!({
+                        "appliesTo": {
+                            "principalTypes": [principal.to_string()],
+                            "resourceTypes": [resource.to_string()],
+                            "context": context,
+                        }
+                    }),
+                )
+            })
+            .collect();
+        let mut schema = serde_json::Map::new();
+        for (namespace, entity_types) in namespaces {
+            schema.insert(
+                namespace,
+                json!({ "entityTypes": entity_types, "actions": {} }),
+            );
+        }
+        schema
+            .entry(String::new())
+            .or_insert_with(|| json!({ "entityTypes": {} }))
+            .as_object_mut()
+            .map(|global| global.insert("actions".into(), actions.into()));
+        let schema = match SchemaFragment::from_json_value(schema.into()) {
+            Ok(schema) => schema,
+            Err(e) => {
+                self.report(Unsupported::new(
+                    0..0,
+                    format!("failed to build the schema skeleton: {e}"),
+                ));
+                #[allow(clippy::expect_used)]
+                SchemaFragment::from_json_value(json!({})).expect("empty schema is valid")
+            }
+        };
+        RegoImport {
+            policies: self.policies,
+            schema,
+            issues: self.issues,
+        }
+    }
+}
+
+fn ty_name(ty: &Ty) -> String {
+    match ty {
+        Ty::String => "string".into(),
+        Ty::Long => "number".into(),
+        Ty::Bool => "boolean".into(),
+        Ty::Entity(ty) => format!("`{ty}`"),
+        Ty::Set(element) => format!("set of {}", ty_name(element)),
+    }
+}
+
+fn ident_name(token: &Token) -> &str {
+    match &token.kind {
+        TokenKind::Ident(i) => i,
+        _ => "",
+    }
+}
+
+fn condition_expr(condition: Condition) -> ast::Expr {
+    match condition {
+        Condition::Scope(root, uid) => {
+            ast::Expr::is_eq(ast::Expr::var(root.var()), ast::Expr::val(uid.0))
+        }
+        Condition::When(expr) => expr,
+    }
+}
+
+/// The attribute at `attrs` of `root`
+fn value(root: Root, attrs: &[SmolStr]) -> ast::Expr {
+    attrs.iter().fold(ast::Expr::var(root.var()), |e, attr| {
+        ast::Expr::get_attr(e, attr.clone())
+    })
+}
+
+/// `f` applied to the attribute at `attrs` of `root`, guarded by checks
+/// that every attribute on the way exists
+fn guarded(root: Root, attrs: &[SmolStr], f: impl FnOnce(ast::Expr) -> ast::Expr) -> ast::Expr {
+    let value = f(value(root, attrs));
+    match guard_only(root, attrs) {
+        Some(guard) => ast::Expr::and(guard, value),
+        None => value,
+    }
+}
+
+/// Checks that every attribute on the way to the attribute at `attrs` of
+/// `root` exists, or `None` if there are no attributes
+fn guard_only(root: Root, attrs: &[SmolStr]) -> Option<ast::Expr> {
+    let mut object = ast::Expr::var(root.var());
+    let mut guard: Option<ast::Expr> = None;
+    for attr in attrs {
+        let has = ast::Expr::has_attr(object.clone(), attr.clone());
+        guard = Some(match guard {
+            Some(guard) => ast::Expr::and(guard, has),
+            None => has,
+        });
+        object = ast::Expr::get_attr(object, attr.clone());
+    }
+    guard
+}
+
+/// Index of the end of the statement starting at `from` in a rule body:
+/// the next newline or `;` at the top level
+fn statement_end(tokens: &[Token], from: usize) -> usize {
+    let mut i = from;
+    while let Some(token) = tokens.get(i) {
+        match &token.kind {
+            TokenKind::Newline | TokenKind::Punct(";") => return i,
+            TokenKind::Punct("{" | "[" | "(") => i = skip_group(tokens, i),
+            _ => i += 1,
+        }
+    }
+    tokens.len()
+}
+
+/// Parses the operands of a Rego expression
+struct TermParser<'a> {
+    importer: &'a RegoImporter,
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl TermParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn span_from(&self, start: usize) -> Range<usize> {
+        span_of(self.tokens, start, self.pos)
+    }
+
+    fn term(&mut self) -> Result<Term, Unsupported> {
+        let start = self.pos;
+        let Some(token) = self.next().cloned() else {
+            let end = self.tokens.last().map_or(0, |t| t.span.end);
+            return Err(Unsupported::new(end..end, "expected an operand"));
+        };
+        match token.kind {
+            TokenKind::Str(s) => Ok(Term::Lit(Lit::Str(s.into()))),
+            TokenKind::Number(n) => parse_long(&n, false, token.span).map(|i| Term::Lit(Lit::Long(i))),
+            TokenKind::Punct("-") => match self.next().cloned() {
+                Some(Token {
+                    kind: TokenKind::Number(n),
+                    span,
+                }) => parse_long(&n, true, token.span.start..span.end)
+                    .map(|i| Term::Lit(Lit::Long(i))),
+                _ => Err(Unsupported::new(self.span_from(start), "arithmetic is not supported")),
+            },
+            TokenKind::Ident(i) if i == "true" || i == "false" => {
+                Ok(Term::Lit(Lit::Bool(i == "true")))
+            }
+            TokenKind::Ident(i) if i == "input" => self.reference(start),
+            TokenKind::Ident(i) if i == "data" => Err(Unsupported::new(
+                token.span,
+                "references to `data` are not supported; Cedar policies only read the request and entities",
+            )),
+            TokenKind::Ident(i) if self.peek().is_some_and(|t| t.is_punct("(")) => {
+                self.call(&i, start)
+            }
+            TokenKind::Ident(i) => Err(Unsupported::new(
+                token.span,
+                format!("`{i}` is not supported; only references to `input` are translated, not local variables or other rules"),
+            )),
+            TokenKind::Punct(open @ ("[" | "{")) => {
+                let close = if open == "[" { "]" } else { "}" };
+                let mut lits = Vec::new();
+                loop {
+                    match self.next().cloned() {
+                        Some(t) if t.is_punct(close) => break,
+                        Some(t) if t.is_punct(",") || matches!(t.kind, TokenKind::Newline) => (),
+                        Some(t) => {
+                            self.pos -= 1;
+                            match self.term()? {
+                                Term::Lit(lit) => lits.push(lit),
+                                _ => {
+                                    return Err(Unsupported::new(
+                                        t.span,
+                                        "only literals are supported in collections",
+                                    ))
+                                }
+                            }
+                        }
+                        None => {
+                            return Err(Unsupported::new(
+                                self.span_from(start),
+                                "unterminated collection",
+                            ))
+                        }
+                    }
+                }
+                Ok(Term::Set(lits))
+            }
+            _ => Err(Unsupported::new(token.span, "unsupported Rego expression")),
+        }
+    }
+
+    /// A reference into `input`, whose `input` token is at `start`
+    fn reference(&mut self, start: usize) -> Result<Term, Unsupported> {
+        let mut path: Vec<SmolStr> = Vec::new();
+        let mut iterate = false;
+        loop {
+            match self.peek().cloned() {
+                Some(t) if t.is_punct(".") && !iterate => {
+                    self.pos += 1;
+                    match self.next().map(|t| t.kind.clone()) {
+                        Some(TokenKind::Ident(field)) => path.push(field.into()),
+                        _ => {
+                            return Err(Unsupported::new(
+                                self.span_from(start),
+                                "expected a field name",
+                            ))
+                        }
+                    }
+                }
+                Some(t) if t.is_punct("[") && !iterate => {
+                    self.pos += 1;
+                    match self.next().map(|t| t.kind.clone()) {
+                        Some(TokenKind::Str(field)) => path.push(field.into()),
+                        Some(TokenKind::Ident(underscore)) if underscore == "_" => iterate = true,
+                        _ => {
+                            return Err(Unsupported::new(
+                                self.span_from(start),
+                                "only string keys and `[_]` are supported in references",
+                            ))
+                        }
+                    }
+                    if !self.next().is_some_and(|t| t.is_punct("]")) {
+                        return Err(Unsupported::new(
+                            self.span_from(start),
+                            "only string keys and `[_]` are supported in references",
+                        ));
+                    }
+                }
+                Some(t) if (t.is_punct(".") || t.is_punct("[")) && iterate => {
+                    return Err(Unsupported::new(
+                        self.span_from(start),
+                        "`[_]` is only supported at the end of a reference",
+                    ))
+                }
+                _ => break,
+            }
+        }
+        let span = self.span_from(start);
+        let Some((field, attrs)) = path.split_first() else {
+            return Err(Unsupported::new(span, "`input` itself is not supported"));
+        };
+        let importer = self.importer;
+        let entity = if *field == importer.principal.field {
+            Some(Root::Principal)
+        } else if *field == importer.action_field {
+            Some(Root::Action)
+        } else if *field == importer.resource.field {
+            Some(Root::Resource)
+        } else {
+            None
+        };
+        match entity {
+            Some(Root::Action) if !attrs.is_empty() || iterate => Err(Unsupported::new(
+                span,
+                "only the action itself is supported, not its fields",
+            )),
+            Some(root) if attrs.is_empty() && !iterate => Ok(Term::Entity(root)),
+            Some(_) if attrs.is_empty() => Err(Unsupported::new(
+                span,
+                "iterating over the principal or resource is not supported",
+            )),
+            Some(root) => Ok(Term::Attr {
+                root,
+                attrs: attrs.to_vec(),
+                iterate,
+            }),
+            None => Ok(Term::Attr {
+                root: Root::Context,
+                attrs: path,
+                iterate,
+            }),
+        }
+    }
+
+    /// A call to the built-in `name`, whose name token is at `start`, as an
+    /// operand
+    fn call(&mut self, name: &str, start: usize) -> Result<Term, Unsupported> {
+        self.pos = skip_group(self.tokens, self.pos);
+        let message = match name {
+            "startswith" | "endswith" | "contains" => {
+                format!("`{name}` is only supported as a condition on its own")
+            }
+            _ => format!("built-in function `{name}` is not supported"),
+        };
+        Err(Unsupported::new(self.span_from(start), message))
+    }
+}
+
+fn parse_long(n: &str, negative: bool, span: Range<usize>) -> Result<i64, Unsupported> {
+    let parsed = if negative {
+        format!("-{n}").parse::<i64>()
+    } else {
+        n.parse::<i64>()
+    };
+    parsed.map_err(|_| {
+        Unsupported::new(
+            span,
+            format!("`{n}` is not supported; only integers in the range of Cedar's `Long` are"),
+        )
+    })
+}
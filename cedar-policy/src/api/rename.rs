@@ -0,0 +1,222 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Renaming entity types, actions, and attributes across a schema and a
+//! policy set, and qualifying the names in a policy set
+
+use cedar_policy_core::extensions::Extensions;
+use ref_cast::RefCast;
+
+use super::{EntityTypeName, EntityUid, PolicyId, RenameError, SchemaFragment};
+
+/// An entity type, action, or attribute to rename consistently across a
+/// [`SchemaFragment`] and the text of a policy set.
+///
+/// ```
+/// # use cedar_policy::{Rename, SchemaFragment};
+/// let (schema, _) = SchemaFragment::from_cedarschema_str(r#"
+///     entity User = { name: String };
+///     entity Photo;
+///     action view appliesTo { principal: User, resource: Photo };
+/// "#).unwrap();
+/// let policies = r#"permit(principal is User, action, resource) when { principal.name == "a" };"#;
+/// let rename = Rename::attribute("User".parse().unwrap(), "name", "username");
+/// let renamed = rename.apply(&schema, policies).unwrap();
+/// assert_eq!(
+///     renamed.policies(),
+///     r#"permit(principal is User, action, resource) when { principal.username == "a" };"#
+/// );
+/// assert!(renamed.schema().to_cedarschema().unwrap().contains("username"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename(cedar_policy_validator::Rename);
+
+impl Rename {
+    /// Rename the entity type `from` to `to`, which must be in the same
+    /// namespace
+    pub fn entity_type(from: EntityTypeName, to: EntityTypeName) -> Self {
+        Self(cedar_policy_validator::Rename::EntityType {
+            from: from.0,
+            to: to.0,
+        })
+    }
+
+    /// Rename the action `from` to have the entity id `to`
+    pub fn action(from: EntityUid, to: impl AsRef<str>) -> Self {
+        Self(cedar_policy_validator::Rename::Action {
+            from: from.0,
+            to: to.as_ref().into(),
+        })
+    }
+
+    /// Rename the attribute `from` of `entity_type` to `to`
+    pub fn attribute(
+        entity_type: EntityTypeName,
+        from: impl AsRef<str>,
+        to: impl AsRef<str>,
+    ) -> Self {
+        Self(cedar_policy_validator::Rename::Attribute {
+            entity_type: entity_type.0,
+            from: from.as_ref().into(),
+            to: to.as_ref().into(),
+        })
+    }
+
+    /// Apply the rename to `schema` and to the policy set source `policies`.
+    ///
+    /// The schema is rewritten completely. The policies are rewritten with
+    /// text edits, so their formatting and comments are kept. An attribute is
+    /// only renamed where the validator infers that it is accessed on the
+    /// renamed entity type; accesses on expressions which may also have
+    /// another type are left unchanged and reported by
+    /// [`Renamed::skipped`].
+    pub fn apply(&self, schema: &SchemaFragment, policies: &str) -> Result<Renamed, RenameError> {
+        let validator = cedar_policy_validator::Validator::new(
+            cedar_policy_validator::ValidatorSchema::from_schema_fragments(
+                [schema.value.clone()],
+                Extensions::all_available(),
+            )?,
+        );
+        let lossless = self.0.rename_in_schema(&schema.lossless)?;
+        let result = validator
+            .rename_in_policies(policies, &self.0)
+            .map_err(super::ParseErrors::from)?;
+        Ok(Renamed {
+            schema: SchemaFragment {
+                value: lossless.clone().try_into()?,
+                lossless,
+            },
+            policies: applied(&result, policies),
+            result,
+        })
+    }
+}
+
+/// The result of [`Rename::apply`]
+#[derive(Debug, Clone)]
+pub struct Renamed {
+    schema: SchemaFragment,
+    policies: String,
+    result: cedar_policy_validator::PolicyRename,
+}
+
+impl Renamed {
+    /// The renamed schema
+    pub fn schema(&self) -> &SchemaFragment {
+        &self.schema
+    }
+
+    /// The renamed policy set source
+    pub fn policies(&self) -> &str {
+        &self.policies
+    }
+
+    /// The edits to the original policy set source which produce
+    /// [`Renamed::policies`], sorted by position
+    pub fn edits(&self) -> impl Iterator<Item = &PolicyEdit> {
+        self.result.edits.iter().map(PolicyEdit::ref_cast)
+    }
+
+    /// Occurrences in the policies which were not renamed, because it could
+    /// not be done safely
+    pub fn skipped(&self) -> impl Iterator<Item = &SkippedOccurrence> {
+        self.result.skipped.iter().map(SkippedOccurrence::ref_cast)
+    }
+}
+
+/// The result of [`super::Validator::qualify_names`]
+#[derive(Debug, Clone)]
+pub struct QualifiedNames {
+    policies: String,
+    result: cedar_policy_validator::PolicyRename,
+}
+
+impl QualifiedNames {
+    pub(super) fn new(policies: &str, result: cedar_policy_validator::PolicyRename) -> Self {
+        Self {
+            policies: applied(&result, policies),
+            result,
+        }
+    }
+
+    /// The policy set source with the names qualified
+    pub fn policies(&self) -> &str {
+        &self.policies
+    }
+
+    /// The edits to the original policy set source which produce
+    /// [`QualifiedNames::policies`], sorted by position
+    pub fn edits(&self) -> impl Iterator<Item = &PolicyEdit> {
+        self.result.edits.iter().map(PolicyEdit::ref_cast)
+    }
+
+    /// References which were not qualified, because they could refer to
+    /// more than one declared name
+    pub fn skipped(&self) -> impl Iterator<Item = &SkippedOccurrence> {
+        self.result.skipped.iter().map(SkippedOccurrence::ref_cast)
+    }
+}
+
+/// Apply the edits in `result` to `policies`, the source they were computed
+/// from
+fn applied(result: &cedar_policy_validator::PolicyRename, policies: &str) -> String {
+    // PANIC SAFETY: the edits were computed from `policies`, so they fit it
+    #[allow(clippy::expect_used)]
+    result
+        .apply(policies)
+        .expect("edits should fit the source they were computed from")
+}
+
+/// A replacement of part of the source of a policy set, see
+/// [`Renamed::edits`] and [`QualifiedNames::edits`]
+#[repr(transparent)]
+#[derive(Debug, Clone, PartialEq, Eq, RefCast)]
+pub struct PolicyEdit(cedar_policy_validator::PolicyEdit);
+
+impl PolicyEdit {
+    /// The replaced part of the source
+    pub fn span(&self) -> miette::SourceSpan {
+        self.0.span
+    }
+
+    /// The replacement
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+}
+
+/// An occurrence of a renamed attribute or of a name to qualify which was
+/// left unchanged, see [`Renamed::skipped`] and [`QualifiedNames::skipped`]
+#[repr(transparent)]
+#[derive(Debug, Clone, PartialEq, Eq, RefCast)]
+pub struct SkippedOccurrence(cedar_policy_validator::SkippedOccurrence);
+
+impl SkippedOccurrence {
+    /// The policy or template containing the occurrence
+    pub fn policy_id(&self) -> &PolicyId {
+        PolicyId::ref_cast(&self.0.policy_id)
+    }
+
+    /// The part of the source of the expression containing the occurrence
+    pub fn span(&self) -> Option<miette::SourceSpan> {
+        self.0.loc.as_ref().map(|loc| loc.span)
+    }
+
+    /// Why the occurrence was left unchanged
+    pub fn reason(&self) -> &str {
+        &self.0.reason
+    }
+}
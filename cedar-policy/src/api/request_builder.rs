@@ -0,0 +1,306 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Building a [`Request`] one component at a time, validating each against a
+//! schema as it's set
+
+use cedar_policy_core::ast;
+use cedar_policy_core::extensions::Extensions;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::{Context, EntityUid, Request, RequestValidationError, Schema};
+
+/// Builder for a [`Request`] which validates each component against a
+/// [`Schema`] as it's set, and reports every problem with the request at
+/// once when it's built.
+///
+/// Principal and resource types are checked against the schema when they're
+/// set, and against the action once it's set. The context is checked against
+/// the action, after filling in any `@default`s the schema declares for it.
+/// Setting a component again replaces it, along with any problems found with
+/// it.
+///
+/// ```
+/// # use cedar_policy::{Context, Request, RequestBuilderProblem, Schema};
+/// # use std::str::FromStr;
+/// let schema = Schema::from_str(r#"
+///     entity User;
+///     entity Photo;
+///     action view appliesTo { principal: User, resource: Photo, context: { mfa: Bool } };
+/// "#).unwrap();
+/// let err = Request::validating_builder(&schema)
+///     .principal(r#"Photo::"vacation""#.parse().unwrap())
+///     .action(r#"Action::"view""#.parse().unwrap())
+///     .resource(r#"Photo::"vacation""#.parse().unwrap())
+///     .context(Context::empty())
+///     .build()
+///     .unwrap_err();
+/// // Both the principal and the context are reported
+/// assert_eq!(err.problems().count(), 2);
+/// ```
+#[derive(Debug)]
+pub struct ValidatingRequestBuilder<'s> {
+    schema: &'s Schema,
+    principal: Option<EntityUid>,
+    action: Option<EntityUid>,
+    resource: Option<EntityUid>,
+    context: Context,
+    problems: ComponentProblems,
+}
+
+/// The problem found with each component of a [`ValidatingRequestBuilder`],
+/// if any. Kept per component so that setting a component again clears the
+/// problems with its old value.
+#[derive(Debug, Default)]
+struct ComponentProblems {
+    principal: Option<cedar_policy_validator::RequestValidationError>,
+    action: Option<cedar_policy_validator::RequestValidationError>,
+    resource: Option<cedar_policy_validator::RequestValidationError>,
+    context: Option<cedar_policy_validator::RequestValidationError>,
+}
+
+impl<'s> ValidatingRequestBuilder<'s> {
+    /// Create a builder validating against `schema`, with no principal,
+    /// action, or resource, and an empty context
+    pub fn new(schema: &'s Schema) -> Self {
+        Self {
+            schema,
+            principal: None,
+            action: None,
+            resource: None,
+            context: Context::empty(),
+            problems: ComponentProblems::default(),
+        }
+    }
+
+    /// Set the principal, checking that its type is declared in the schema
+    /// and applies to the action (if set).
+    #[must_use]
+    pub fn principal(mut self, principal: EntityUid) -> Self {
+        self.principal = Some(principal);
+        self.check_principal();
+        self
+    }
+
+    /// Set the action, checking that it's declared in the schema, and
+    /// checking the principal, resource, and context (if set) against it.
+    #[must_use]
+    pub fn action(mut self, action: EntityUid) -> Self {
+        self.problems.action = self.schema.0.validate_request_action(action.as_ref()).err();
+        self.action = Some(action);
+        self.check_principal();
+        self.check_resource();
+        self.check_context();
+        self
+    }
+
+    /// Set the resource, checking that its type is declared in the schema and
+    /// applies to the action (if set).
+    #[must_use]
+    pub fn resource(mut self, resource: EntityUid) -> Self {
+        self.resource = Some(resource);
+        self.check_resource();
+        self
+    }
+
+    /// Set the context, checking it against the action (if set).
+    #[must_use]
+    pub fn context(mut self, context: Context) -> Self {
+        self.context = context;
+        self.check_context();
+        self
+    }
+
+    /// Create the [`Request`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RequestBuilderError`] listing every problem found with the
+    /// request: components which were never set, and components which don't
+    /// conform to the schema.
+    pub fn build(self) -> Result<Request, RequestBuilderError> {
+        let mut problems = Vec::new();
+        for (name, component) in [
+            (RequestComponent::Principal, &self.principal),
+            (RequestComponent::Action, &self.action),
+            (RequestComponent::Resource, &self.resource),
+        ] {
+            if component.is_none() {
+                problems.push(RequestBuilderProblem::Missing(name));
+            }
+        }
+        let ComponentProblems {
+            principal,
+            action,
+            resource,
+            context,
+        } = self.problems;
+        problems.extend(
+            [principal, action, resource, context]
+                .into_iter()
+                .flatten()
+                .map(|e| RequestBuilderProblem::Invalid(e.into())),
+        );
+        match (self.principal, self.action, self.resource) {
+            (Some(principal), Some(action), Some(resource)) if problems.is_empty() => {
+                let context = self
+                    .schema
+                    .0
+                    .fill_context_defaults(action.as_ref(), self.context.0);
+                // Every component has been validated already
+                #[allow(clippy::expect_used)]
+                let request = ast::Request::new::<cedar_policy_validator::ValidatorSchema>(
+                    (principal.into(), None),
+                    (action.into(), None),
+                    (resource.into(), None),
+                    context,
+                    None,
+                    Extensions::all_available(),
+                )
+                .expect("request without a schema is always valid");
+                Ok(Request(request))
+            }
+            _ => Err(RequestBuilderError { problems }),
+        }
+    }
+
+    fn check_principal(&mut self) {
+        self.problems.principal = self.principal.as_ref().and_then(|principal| {
+            self.schema
+                .0
+                .validate_request_principal(
+                    principal.as_ref(),
+                    self.action.as_ref().map(AsRef::as_ref),
+                )
+                .err()
+        });
+    }
+
+    fn check_resource(&mut self) {
+        self.problems.resource = self.resource.as_ref().and_then(|resource| {
+            self.schema
+                .0
+                .validate_request_resource(
+                    resource.as_ref(),
+                    self.action.as_ref().map(AsRef::as_ref),
+                )
+                .err()
+        });
+    }
+
+    fn check_context(&mut self) {
+        self.problems.context = self.action.as_ref().and_then(|action| {
+            let context = self
+                .schema
+                .0
+                .fill_context_defaults(action.as_ref(), self.context.0.clone());
+            self.schema
+                .0
+                .validate_request_context(&context, action.as_ref(), Extensions::all_available())
+                .err()
+        });
+    }
+}
+
+impl Request {
+    /// Create a [`ValidatingRequestBuilder`], which validates each component
+    /// of the request against `schema` as it's set
+    pub fn validating_builder(schema: &Schema) -> ValidatingRequestBuilder<'_> {
+        ValidatingRequestBuilder::new(schema)
+    }
+}
+
+/// Every problem found building a request with a [`ValidatingRequestBuilder`]
+#[derive(Debug, Diagnostic, Error)]
+#[error("request is invalid: {}", .problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct RequestBuilderError {
+    /// Never empty
+    #[related]
+    problems: Vec<RequestBuilderProblem>,
+}
+
+impl RequestBuilderError {
+    /// The problems with the request, in the order principal, action,
+    /// resource, context, with missing components first
+    pub fn problems(&self) -> impl Iterator<Item = &RequestBuilderProblem> {
+        self.problems.iter()
+    }
+
+    /// Consume the error, returning the problems with the request
+    pub fn into_problems(self) -> impl Iterator<Item = RequestBuilderProblem> {
+        self.problems.into_iter()
+    }
+}
+
+/// A problem with a request, found by a [`ValidatingRequestBuilder`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum RequestBuilderProblem {
+    /// A required component of the request was never set
+    #[error("request {0} was not set")]
+    Missing(RequestComponent),
+    /// A component of the request does not conform to the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Invalid(RequestValidationError),
+}
+
+impl RequestBuilderProblem {
+    /// The component of the request this problem is with
+    pub fn component(&self) -> RequestComponent {
+        match self {
+            Self::Missing(component) => *component,
+            Self::Invalid(
+                RequestValidationError::UndeclaredPrincipalType(_)
+                | RequestValidationError::InvalidPrincipalType(_),
+            ) => RequestComponent::Principal,
+            Self::Invalid(RequestValidationError::UndeclaredAction(_)) => RequestComponent::Action,
+            Self::Invalid(
+                RequestValidationError::UndeclaredResourceType(_)
+                | RequestValidationError::InvalidResourceType(_),
+            ) => RequestComponent::Resource,
+            Self::Invalid(
+                RequestValidationError::InvalidContext(_)
+                | RequestValidationError::TypeOfContext(_),
+            ) => RequestComponent::Context,
+        }
+    }
+}
+
+/// A component of a [`Request`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RequestComponent {
+    /// The principal
+    Principal,
+    /// The action
+    Action,
+    /// The resource
+    Resource,
+    /// The context
+    Context,
+}
+
+impl std::fmt::Display for RequestComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Principal => write!(f, "principal"),
+            Self::Action => write!(f, "action"),
+            Self::Resource => write!(f, "resource"),
+            Self::Context => write!(f, "context"),
+        }
+    }
+}
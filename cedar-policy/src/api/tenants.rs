@@ -0,0 +1,128 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Independent policy sets for many tenants, sharing one schema
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use smol_str::SmolStr;
+
+use super::{Authorizer, Entities, PolicySet, Request, Response, Schema};
+
+/// A collection of independent [`PolicySet`]s keyed by tenant id, for
+/// multi-tenant services where every tenant manages its own policies.
+///
+/// All tenants share a single (optional) [`Schema`], rather than each
+/// holding a copy of it. Looking up a tenant's policies is a hash map lookup,
+/// and each tenant's policies can be replaced independently while requests
+/// for that and other tenants are in flight: requests which already started
+/// keep using the policy set they started with.
+///
+/// Lookups take no locks. The map of tenants is immutable, and adding or
+/// removing a tenant publishes a new map atomically, retrying if another
+/// update published one in the meantime, like
+/// [`super::AuthorizerHandle::update_snapshot`]. Each update copies the map
+/// (but not the policy sets in it), so this suits services where tenants'
+/// policies are read far more often than they change.
+#[derive(Debug, Default)]
+pub struct TenantPolicySets {
+    schema: Option<Arc<Schema>>,
+    tenants: ArcSwap<HashMap<SmolStr, Arc<PolicySet>>>,
+}
+
+impl TenantPolicySets {
+    /// Create an empty collection, with no schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty collection whose tenants share `schema`
+    pub fn with_schema(schema: Schema) -> Self {
+        Self {
+            schema: Some(Arc::new(schema)),
+            tenants: ArcSwap::default(),
+        }
+    }
+
+    /// The schema shared by all tenants, if there is one
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_deref()
+    }
+
+    /// Get the current policies of `tenant`, or `None` if there is no such
+    /// tenant. The returned policy set is unaffected by later updates.
+    pub fn policy_set(&self, tenant: &str) -> Option<Arc<PolicySet>> {
+        self.tenants.load().get(tenant).cloned()
+    }
+
+    /// Add `tenant` with `policies`, or replace its policies if it already
+    /// exists, returning its previous policies
+    pub fn insert(
+        &self,
+        tenant: impl Into<SmolStr>,
+        policies: PolicySet,
+    ) -> Option<Arc<PolicySet>> {
+        let (tenant, policies) = (tenant.into(), Arc::new(policies));
+        let mut previous = None;
+        self.tenants.rcu(|tenants| {
+            let mut tenants = HashMap::clone(tenants);
+            previous = tenants.insert(tenant.clone(), Arc::clone(&policies));
+            tenants
+        });
+        previous
+    }
+
+    /// Remove `tenant`, returning its policies
+    pub fn remove(&self, tenant: &str) -> Option<Arc<PolicySet>> {
+        let mut previous = None;
+        self.tenants.rcu(|tenants| {
+            let mut tenants = HashMap::clone(tenants);
+            previous = tenants.remove(tenant);
+            tenants
+        });
+        previous
+    }
+
+    /// The ids of all tenants, in no particular order
+    pub fn tenants(&self) -> Vec<SmolStr> {
+        self.tenants.load().keys().cloned().collect()
+    }
+
+    /// The number of tenants
+    pub fn len(&self) -> usize {
+        self.tenants.load().len()
+    }
+
+    /// Are there no tenants?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Authorize `request` with `authorizer` against the current policies of
+    /// `tenant`, or return `None` if there is no such tenant
+    pub fn is_authorized(
+        &self,
+        authorizer: &Authorizer,
+        tenant: &str,
+        request: &Request,
+        entities: &Entities,
+    ) -> Option<Response> {
+        let policies = self.policy_set(tenant)?;
+        Some(authorizer.is_authorized(request, &policies, entities))
+    }
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Per-policy validation profiling
+
+use std::time::Duration;
+
+use super::PolicyId;
+
+/// How long validating one static policy or template took, and how much work
+/// typechecking it took, as computed by
+/// [`super::Validator::validate_with_profile`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationProfile {
+    policy_id: PolicyId,
+    duration: Duration,
+    request_envs: usize,
+    expressions: usize,
+}
+
+impl ValidationProfile {
+    /// The id of the policy or template
+    pub fn policy_id(&self) -> &PolicyId {
+        &self.policy_id
+    }
+
+    /// The time taken by all validation checks of the policy
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The number of request environments the policy was typechecked in, one
+    /// for each combination of principal type, action, and resource type (and
+    /// of linked slot types, for templates) in the schema
+    pub fn request_envs(&self) -> usize {
+        self.request_envs
+    }
+
+    /// The number of expressions typechecked, over all request environments
+    pub fn expressions(&self) -> usize {
+        self.expressions
+    }
+}
+
+impl From<cedar_policy_validator::ValidationProfile> for ValidationProfile {
+    fn from(profile: cedar_policy_validator::ValidationProfile) -> Self {
+        Self {
+            policy_id: PolicyId::new(profile.policy_id),
+            duration: profile.duration,
+            request_envs: profile.typecheck.request_envs,
+            expressions: profile.typecheck.expressions,
+        }
+    }
+}
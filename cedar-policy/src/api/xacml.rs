@@ -0,0 +1,1903 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Converting between XACML 3.0 policies and Cedar policies.
+//!
+//! The supported subset of XACML is rules whose targets and conditions compare
+//! attributes of the access subject, action, resource, and environment with
+//! literal strings, integers, and booleans: the `-equal`, `-is-in`, and
+//! `-one-and-only` functions for these types, `integer-greater-than` and
+//! friends, `string-starts-with`, `string-ends-with`, and `string-contains`,
+//! and `and`, `or`, and `not`. The `subject-id`, `action-id`, and
+//! `resource-id` attributes are the ids of the principal, action, and
+//! resource; other attributes of the subject and resource are attributes of
+//! the principal and resource, and environment attributes are context
+//! attributes, named by the last segment of their `AttributeId`. Attributes
+//! are assumed to be single-valued, except in the `-is-in` functions, which
+//! test whether a set contains a value.
+//!
+//! Each XACML rule becomes a Cedar policy, conditioned on the targets of the
+//! policies and policy sets containing it. Cedar combines policies with
+//! forbid-overrides semantics and denies requests no policy permits, which is
+//! what `deny-overrides` does with a PEP denying `NotApplicable` decisions;
+//! other combining algorithms are reported as lossy where they can give a
+//! different decision. Rules using anything outside the supported subset are
+//! not translated at all, since dropping some of the conditions of a rule
+//! would change which requests it applies to.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use cedar_policy_core::ast::{self, PatternElem};
+use miette::Diagnostic;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use super::{
+    ActionConstraint, EntityId, EntityTypeName, EntityUid, Policy, PolicyId, PolicySet,
+    PrincipalConstraint, ResourceConstraint,
+};
+
+const NAMESPACE: &str = "urn:oasis:names:tc:xacml:3.0:core:schema:wd-17";
+const SUBJECT_CATEGORY: &str = "urn:oasis:names:tc:xacml:1.0:subject-category:access-subject";
+const ACTION_CATEGORY: &str = "urn:oasis:names:tc:xacml:3.0:attribute-category:action";
+const RESOURCE_CATEGORY: &str = "urn:oasis:names:tc:xacml:3.0:attribute-category:resource";
+const ENVIRONMENT_CATEGORY: &str = "urn:oasis:names:tc:xacml:3.0:attribute-category:environment";
+const SUBJECT_ID: &str = "urn:oasis:names:tc:xacml:1.0:subject:subject-id";
+const ACTION_ID: &str = "urn:oasis:names:tc:xacml:1.0:action:action-id";
+const RESOURCE_ID: &str = "urn:oasis:names:tc:xacml:1.0:resource:resource-id";
+const DENY_OVERRIDES: &str = "urn:oasis:names:tc:xacml:3.0:rule-combining-algorithm:deny-overrides";
+
+/// Converts between XACML 3.0 and Cedar; see [`XacmlConverter::import`] and
+/// [`XacmlConverter::export`].
+///
+/// XACML identifies entities by id only, so the converter maps subject ids to
+/// principals of one entity type, `User` by default, and resource ids to
+/// resources of one entity type, `Resource` by default. Actions are entities
+/// of type `Action`.
+///
+/// ```
+/// # use cedar_policy::{PolicyId, XacmlConverter};
+/// let import = XacmlConverter::new()
+///     .resource_type("Document".parse().unwrap())
+///     .import(r#"
+///         <Policy xmlns="urn:oasis:names:tc:xacml:3.0:core:schema:wd-17" PolicyId="docs"
+///                 RuleCombiningAlgId="urn:oasis:names:tc:xacml:3.0:rule-combining-algorithm:deny-overrides">
+///           <Target/>
+///           <Rule RuleId="read" Effect="Permit">
+///             <Target>
+///               <AnyOf><AllOf>
+///                 <Match MatchId="urn:oasis:names:tc:xacml:1.0:function:string-equal">
+///                   <AttributeValue DataType="http://www.w3.org/2001/XMLSchema#string">read</AttributeValue>
+///                   <AttributeDesignator Category="urn:oasis:names:tc:xacml:3.0:attribute-category:action"
+///                       AttributeId="urn:oasis:names:tc:xacml:1.0:action:action-id"
+///                       DataType="http://www.w3.org/2001/XMLSchema#string" MustBePresent="false"/>
+///                 </Match>
+///               </AllOf></AnyOf>
+///             </Target>
+///           </Rule>
+///         </Policy>
+///     "#)
+///     .unwrap();
+/// let policy = import.policies().policy(&PolicyId::new("docs/read")).unwrap();
+/// assert_eq!(policy.to_string(), r#"permit(principal, action == Action::"read", resource);"#);
+/// assert!(import.issues().is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct XacmlConverter {
+    principal_type: EntityTypeName,
+    resource_type: EntityTypeName,
+}
+
+impl Default for XacmlConverter {
+    fn default() -> Self {
+        #[allow(clippy::expect_used)]
+        let entity_type = |name| EntityTypeName::from_str(name).expect("valid entity type name");
+        Self {
+            principal_type: entity_type("User"),
+            resource_type: entity_type("Resource"),
+        }
+    }
+}
+
+impl XacmlConverter {
+    /// Create a converter with the default entity types
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map XACML subjects to principals of type `entity_type`
+    #[must_use]
+    pub fn principal_type(mut self, entity_type: EntityTypeName) -> Self {
+        self.principal_type = entity_type;
+        self
+    }
+
+    /// Map XACML resources to resources of type `entity_type`
+    #[must_use]
+    pub fn resource_type(mut self, entity_type: EntityTypeName) -> Self {
+        self.resource_type = entity_type;
+        self
+    }
+
+    /// Translate the XACML `Policy` or `PolicySet` in `xml` into Cedar
+    /// policies, as far as possible.
+    ///
+    /// Rules which can't be translated are reported in
+    /// [`XacmlImport::issues`], and left out of the policies, as are policies
+    /// and policy sets whose targets can't be translated, with everything in
+    /// them. Constructs which are translated, but not exactly, are reported
+    /// too.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`XacmlParseError`] if `xml` is not well-formed XML with a
+    /// `Policy` or `PolicySet` root element, or nests elements more than 256
+    /// deep.
+    pub fn import(&self, xml: &str) -> Result<XacmlImport, XacmlParseError> {
+        let root = XmlReader::new(xml).document()?;
+        if root.name != "Policy" && root.name != "PolicySet" {
+            return Err(XacmlParseError {
+                message: format!(
+                    "expected a `Policy` or `PolicySet` root element, found `{}`",
+                    root.name
+                ),
+                line: root.line,
+                column: root.column,
+            });
+        }
+        let mut importer = Importer {
+            converter: self,
+            policies: PolicySet::new(),
+            issues: Vec::new(),
+        };
+        importer.node(&root, "", &[]);
+        Ok(XacmlImport {
+            policies: importer.policies,
+            issues: importer.issues,
+        })
+    }
+
+    /// Translate `policies` into an XACML `Policy` with one rule per Cedar
+    /// policy, in order of policy id, combined with `deny-overrides`, as far
+    /// as possible.
+    ///
+    /// Policies which can't be translated are reported in
+    /// [`XacmlExport::issues`], and left out of the XACML policy. Templates
+    /// aren't exported themselves, but the policies linked to them are.
+    pub fn export(&self, policies: &PolicySet) -> XacmlExport {
+        let mut exporter = Exporter {
+            converter: self,
+            issues: Vec::new(),
+        };
+        let mut root = Element::new("Policy")
+            .with_attr("xmlns", NAMESPACE)
+            .with_attr("PolicyId", "cedar")
+            .with_attr("Version", "1.0")
+            .with_attr("RuleCombiningAlgId", DENY_OVERRIDES)
+            .with_child(Element::new("Target"));
+        let mut policies: Vec<&Policy> = policies.policies().collect();
+        policies.sort_by_key(|policy| AsRef::<str>::as_ref(policy.id()));
+        for policy in policies {
+            match exporter.rule(policy) {
+                Ok(rule) => root.children.push(rule),
+                Err(message) => exporter.report(
+                    XacmlIssueKind::Unsupported,
+                    policy.id(),
+                    format!("{message}; the policy is not exported"),
+                ),
+            }
+        }
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        root.write(&mut xml, 0);
+        XacmlExport {
+            xml,
+            issues: exporter.issues,
+        }
+    }
+}
+
+/// The result of translating XACML with [`XacmlConverter::import`]
+#[derive(Debug, Clone)]
+pub struct XacmlImport {
+    policies: PolicySet,
+    issues: Vec<XacmlIssue>,
+}
+
+impl XacmlImport {
+    /// The Cedar policies translated from the XACML rules. The id of each is
+    /// the path of `PolicySetId`s, `PolicyId`, and `RuleId` leading to its
+    /// rule, joined with `/`.
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// The XACML elements which could not be translated, or not exactly, in
+    /// document order
+    pub fn issues(&self) -> &[XacmlIssue] {
+        &self.issues
+    }
+
+    /// Consume the `XacmlImport`, returning the policies and the issues
+    pub fn into_parts(self) -> (PolicySet, Vec<XacmlIssue>) {
+        (self.policies, self.issues)
+    }
+}
+
+/// The result of translating Cedar policies with [`XacmlConverter::export`]
+#[derive(Debug, Clone)]
+pub struct XacmlExport {
+    xml: String,
+    issues: Vec<XacmlIssue>,
+}
+
+impl XacmlExport {
+    /// The XACML policy document
+    pub fn xml(&self) -> &str {
+        &self.xml
+    }
+
+    /// The Cedar policies which could not be translated, or not exactly
+    pub fn issues(&self) -> &[XacmlIssue] {
+        &self.issues
+    }
+
+    /// Consume the `XacmlExport`, returning the XACML policy document and the
+    /// issues
+    pub fn into_parts(self) -> (String, Vec<XacmlIssue>) {
+        (self.xml, self.issues)
+    }
+}
+
+/// Something which could not be converted between XACML and Cedar, or not
+/// exactly
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("{element}: {message}")]
+pub struct XacmlIssue {
+    kind: XacmlIssueKind,
+    element: String,
+    message: String,
+    line: Option<usize>,
+}
+
+impl XacmlIssue {
+    /// Whether the element was left out, or translated inexactly
+    pub fn kind(&self) -> XacmlIssueKind {
+        self.kind
+    }
+
+    /// The element with the issue: when importing, the path of XACML ids
+    /// leading to it, joined with `/`, as in the ids of imported policies;
+    /// when exporting, the id of the Cedar policy
+    pub fn element(&self) -> &str {
+        &self.element
+    }
+
+    /// What couldn't be translated, and why
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// When importing, the line of the element in the XACML document,
+    /// starting from 1
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+}
+
+/// The kinds of [`XacmlIssue`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum XacmlIssueKind {
+    /// The element has no equivalent, and was left out of the translation
+    Unsupported,
+    /// The element was translated, but the translation can give different
+    /// decisions for some requests
+    Lossy,
+}
+
+/// Error parsing an XACML document
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("invalid XACML at line {line}, column {column}: {message}")]
+pub struct XacmlParseError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl XacmlParseError {
+    /// The line of the error in the XACML document, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column of the error in the XACML document, in characters,
+    /// starting from 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+/// An XML element. Only the local part of names is kept, without any
+/// namespace prefix.
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Self>,
+    text: String,
+    line: usize,
+    column: usize,
+}
+
+impl Element {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+            text: String::new(),
+            line: 0,
+            column: 0,
+        }
+    }
+
+    fn with_attr(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.attrs.push((name.into(), value.into()));
+        self
+    }
+
+    fn with_child(mut self, child: Self) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _)| local_name(attr) == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Self> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Write the element indented by `depth` levels. Text is only written
+    /// for elements without children.
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let _ = write!(out, "{indent}<{}", self.name);
+        for (name, value) in &self.attrs {
+            let _ = write!(out, " {name}=\"{}\"", escape(value));
+        }
+        if !self.children.is_empty() {
+            out.push_str(">\n");
+            for child in &self.children {
+                child.write(out, depth + 1);
+            }
+            let _ = writeln!(out, "{indent}</{}>", self.name);
+        } else if self.text.is_empty() {
+            out.push_str("/>\n");
+        } else {
+            let _ = writeln!(out, ">{}</{}>", escape(&self.text), self.name);
+        }
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// How deeply elements may be nested in an XACML document
+const MAX_NESTING_DEPTH: usize = 256;
+
+/// Reader for the XML used by XACML documents: elements, attributes, text,
+/// CDATA sections, comments, and processing instructions. Document type
+/// declarations are rejected.
+#[derive(Debug)]
+struct XmlReader<'a> {
+    src: &'a str,
+    pos: usize,
+    /// How many elements enclose the current position
+    depth: usize,
+    /// Byte offset, line and column of the last element start, so locating
+    /// the next one only scans the input in between
+    mark: (usize, usize, usize),
+}
+
+impl<'a> XmlReader<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            depth: 0,
+            mark: (0, 1, 1),
+        }
+    }
+
+    /// Line and column of the byte offset `pos`, which is usually after the
+    /// last offset located
+    fn locate(&mut self, pos: usize) -> (usize, usize) {
+        let (offset, mut line, mut column) = self.mark;
+        let Some(between) = self.src.get(offset..pos) else {
+            return line_and_column(self.src, pos);
+        };
+        for c in between.chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        self.mark = (pos, line, column);
+        (line, column)
+    }
+
+    fn rest(&self) -> &'a str {
+        self.src.get(self.pos..).unwrap_or_default()
+    }
+
+    fn error(&self, pos: usize, message: impl Into<String>) -> XacmlParseError {
+        let (line, column) = line_and_column(self.src, pos);
+        XacmlParseError {
+            message: message.into(),
+            line,
+            column,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip past the next `end`, reporting `what` as unterminated if there is
+    /// none
+    fn skip_past(&mut self, end: &str, what: &str) -> Result<&'a str, XacmlParseError> {
+        let rest = self.rest();
+        match rest.find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(rest.get(..i).unwrap_or_default())
+            }
+            None => Err(self.error(self.pos, format!("unterminated {what}"))),
+        }
+    }
+
+    /// Skip comments and processing instructions, returning whether there
+    /// was one
+    fn skip_misc(&mut self) -> Result<bool, XacmlParseError> {
+        if self.rest().starts_with("<!--") {
+            self.skip_past("-->", "comment")?;
+            Ok(true)
+        } else if self.rest().starts_with("<?") {
+            self.skip_past("?>", "processing instruction")?;
+            Ok(true)
+        } else if self.rest().starts_with("<!DOCTYPE") {
+            Err(self.error(self.pos, "document type declarations are not supported"))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn document(mut self) -> Result<Element, XacmlParseError> {
+        self.skip_whitespace();
+        while self.skip_misc()? {
+            self.skip_whitespace();
+        }
+        let root = self.element()?;
+        self.skip_whitespace();
+        while self.skip_misc()? {
+            self.skip_whitespace();
+        }
+        if self.rest().is_empty() {
+            Ok(root)
+        } else {
+            Err(self.error(self.pos, "unexpected content after the root element"))
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, XacmlParseError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/' | '<'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(self.pos, "expected a name"));
+        }
+        self.pos += len;
+        Ok(rest.get(..len).unwrap_or_default())
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), XacmlParseError> {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            Ok(())
+        } else {
+            Err(self.error(self.pos, format!("expected `{s}`")))
+        }
+    }
+
+    /// Parse the element starting at the current position
+    fn element(&mut self) -> Result<Element, XacmlParseError> {
+        let start = self.pos;
+        self.expect("<")?;
+        let name = self.name()?;
+        let (line, column) = self.locate(start);
+        let mut element = Element::new(local_name(name));
+        element.line = line;
+        element.column = column;
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let attr = self.name()?;
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let Some(quote @ ('"' | '\'')) = self.rest().chars().next() else {
+                return Err(self.error(self.pos, "expected a quoted attribute value"));
+            };
+            self.pos += 1;
+            let value_start = self.pos;
+            let raw = self.skip_past(&quote.to_string(), "attribute value")?;
+            element
+                .attrs
+                .push((attr.into(), self.unescape(raw, value_start)?));
+        }
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let close = self.name()?;
+                if close != name {
+                    return Err(self.error(
+                        self.pos - close.len(),
+                        format!("expected `</{name}>`, found `</{close}>`"),
+                    ));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                let text = self.skip_past("]]>", "CDATA section")?;
+                element.text.push_str(text);
+            } else if self.skip_misc()? {
+            } else if rest.starts_with('<') {
+                if self.depth == MAX_NESTING_DEPTH {
+                    return Err(self.error(
+                        self.pos,
+                        format!("elements are nested more than {MAX_NESTING_DEPTH} deep"),
+                    ));
+                }
+                self.depth += 1;
+                let child = self.element();
+                self.depth -= 1;
+                element.children.push(child?);
+            } else if rest.is_empty() {
+                return Err(self.error(self.pos, format!("unclosed element `{name}`")));
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let text_start = self.pos;
+                self.pos += len;
+                let text = self.unescape(rest.get(..len).unwrap_or_default(), text_start)?;
+                element.text.push_str(&text);
+            }
+        }
+    }
+
+    /// Replace the entity and character references in `raw`, which starts at
+    /// `start`
+    fn unescape(&self, raw: &str, start: usize) -> Result<String, XacmlParseError> {
+        let mut text = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(amp) = rest.find('&') {
+            text.push_str(rest.get(..amp).unwrap_or_default());
+            let after = rest.get(amp + 1..).unwrap_or_default();
+            let pos = start + (raw.len() - rest.len()) + amp;
+            let Some(semi) = after.find(';') else {
+                return Err(self.error(pos, "unterminated reference"));
+            };
+            let reference = after.get(..semi).unwrap_or_default();
+            let c = match reference {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => reference
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| reference.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            match c {
+                Some(c) => text.push(c),
+                None => return Err(self.error(pos, format!("unknown reference `&{reference};`"))),
+            }
+            rest = after.get(semi + 1..).unwrap_or_default();
+        }
+        text.push_str(rest);
+        Ok(text)
+    }
+}
+
+/// Line and column of the byte offset `pos` in `src`, both starting from 1
+fn line_and_column(src: &str, pos: usize) -> (usize, usize) {
+    let before = src.get(..pos).unwrap_or(src);
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or(before).chars().count() + 1;
+    (line, column)
+}
+
+/// The data types of attributes and literals which are supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataType {
+    String,
+    Integer,
+    Boolean,
+}
+
+impl DataType {
+    fn parse(uri: &str) -> Result<Self, String> {
+        match uri {
+            "http://www.w3.org/2001/XMLSchema#string" => Ok(Self::String),
+            "http://www.w3.org/2001/XMLSchema#integer" => Ok(Self::Integer),
+            "http://www.w3.org/2001/XMLSchema#boolean" => Ok(Self::Boolean),
+            _ => Err(format!("data type `{uri}` is not supported")),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Boolean => "boolean",
+        }
+    }
+
+    fn uri(self) -> String {
+        format!("http://www.w3.org/2001/XMLSchema#{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Lit {
+    Str(SmolStr),
+    Long(i64),
+    Bool(bool),
+}
+
+impl Lit {
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Str(_) => DataType::String,
+            Self::Long(_) => DataType::Integer,
+            Self::Bool(_) => DataType::Boolean,
+        }
+    }
+
+    fn expr(&self) -> ast::Expr {
+        match self {
+            Self::Str(s) => ast::Expr::val(s.clone()),
+            Self::Long(i) => ast::Expr::val(*i),
+            Self::Bool(b) => ast::Expr::val(*b),
+        }
+    }
+
+    fn element(&self) -> Element {
+        let text = match self {
+            Self::Str(s) => s.to_string(),
+            Self::Long(i) => i.to_string(),
+            Self::Bool(b) => b.to_string(),
+        };
+        Element::new("AttributeValue")
+            .with_attr("DataType", self.data_type().uri())
+            .with_text(text)
+    }
+}
+
+/// An attribute of the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Attr {
+    /// The id of the principal, action, or resource
+    Id(ast::Var),
+    /// A named attribute of the principal, action, resource, or context
+    Named(ast::Var, SmolStr),
+}
+
+impl Attr {
+    fn expr(&self) -> ast::Expr {
+        match self {
+            Self::Id(var) => ast::Expr::var(*var),
+            Self::Named(var, attr) => ast::Expr::get_attr(ast::Expr::var(*var), attr.clone()),
+        }
+    }
+
+    fn guard(&self) -> Option<ast::Expr> {
+        match self {
+            Self::Id(_) => None,
+            Self::Named(var, attr) => Some(ast::Expr::has_attr(ast::Expr::var(*var), attr.clone())),
+        }
+    }
+
+    fn designator(&self, data_type: DataType) -> Element {
+        let (category, id) = match self {
+            Self::Id(var) => (category(*var), id_attribute(*var).unwrap_or_default()),
+            Self::Named(var, attr) => (category(*var), attr.as_str()),
+        };
+        Element::new("AttributeDesignator")
+            .with_attr("Category", category)
+            .with_attr("AttributeId", id)
+            .with_attr("DataType", data_type.uri())
+            .with_attr("MustBePresent", "false")
+    }
+}
+
+fn category(var: ast::Var) -> &'static str {
+    match var {
+        ast::Var::Principal => SUBJECT_CATEGORY,
+        ast::Var::Action => ACTION_CATEGORY,
+        ast::Var::Resource => RESOURCE_CATEGORY,
+        ast::Var::Context => ENVIRONMENT_CATEGORY,
+    }
+}
+
+fn id_attribute(var: ast::Var) -> Option<&'static str> {
+    match var {
+        ast::Var::Principal => Some(SUBJECT_ID),
+        ast::Var::Action => Some(ACTION_ID),
+        ast::Var::Resource => Some(RESOURCE_ID),
+        ast::Var::Context => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Eq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+impl Cmp {
+    fn flipped(self) -> Self {
+        match self {
+            Self::Eq => Self::Eq,
+            Self::Less => Self::Greater,
+            Self::LessEq => Self::GreaterEq,
+            Self::Greater => Self::Less,
+            Self::GreaterEq => Self::LessEq,
+        }
+    }
+
+    fn expr(self, lhs: ast::Expr, rhs: ast::Expr) -> ast::Expr {
+        match self {
+            Self::Eq => ast::Expr::is_eq(lhs, rhs),
+            Self::Less => ast::Expr::less(lhs, rhs),
+            Self::LessEq => ast::Expr::lesseq(lhs, rhs),
+            Self::Greater => ast::Expr::greater(lhs, rhs),
+            Self::GreaterEq => ast::Expr::greatereq(lhs, rhs),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Eq => "equal",
+            Self::Less => "less-than",
+            Self::LessEq => "less-than-or-equal",
+            Self::Greater => "greater-than",
+            Self::GreaterEq => "greater-than-or-equal",
+        }
+    }
+}
+
+/// The `like` patterns of the string functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Like {
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+impl Like {
+    fn name(self) -> &'static str {
+        match self {
+            Self::StartsWith => "string-starts-with",
+            Self::EndsWith => "string-ends-with",
+            Self::Contains => "string-contains",
+        }
+    }
+
+    /// The function this is, in XACML 3.0
+    fn function_id(self) -> String {
+        format!("urn:oasis:names:tc:xacml:3.0:function:{}", self.name())
+    }
+}
+
+/// The supported XACML functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    And,
+    Or,
+    Not,
+    Cmp(DataType, Cmp),
+    IsIn(DataType),
+    OneAndOnly(DataType),
+    Like(Like),
+}
+
+impl Function {
+    fn parse(id: &str) -> Result<Self, String> {
+        let unsupported = || format!("function `{id}` is not supported");
+        let name = ["1.0", "2.0", "3.0"]
+            .iter()
+            .find_map(|version| {
+                id.strip_prefix("urn:oasis:names:tc:xacml:")
+                    .and_then(|id| id.strip_prefix(version))
+                    .and_then(|id| id.strip_prefix(":function:"))
+            })
+            .ok_or_else(unsupported)?;
+        match name {
+            "and" => return Ok(Self::And),
+            "or" => return Ok(Self::Or),
+            "not" => return Ok(Self::Not),
+            "string-starts-with" => return Ok(Self::Like(Like::StartsWith)),
+            "string-ends-with" => return Ok(Self::Like(Like::EndsWith)),
+            "string-contains" => return Ok(Self::Like(Like::Contains)),
+            "string-regexp-match" => {
+                return Err("regular expressions have no Cedar equivalent".into())
+            }
+            _ => {}
+        }
+        for data_type in [DataType::String, DataType::Integer, DataType::Boolean] {
+            let Some(op) = name
+                .strip_prefix(data_type.name())
+                .and_then(|name| name.strip_prefix('-'))
+            else {
+                continue;
+            };
+            return match (data_type, op) {
+                (_, "equal") => Ok(Self::Cmp(data_type, Cmp::Eq)),
+                (_, "is-in") => Ok(Self::IsIn(data_type)),
+                (_, "one-and-only") => Ok(Self::OneAndOnly(data_type)),
+                (DataType::Integer, "less-than") => Ok(Self::Cmp(data_type, Cmp::Less)),
+                (DataType::Integer, "less-than-or-equal") => Ok(Self::Cmp(data_type, Cmp::LessEq)),
+                (DataType::Integer, "greater-than") => Ok(Self::Cmp(data_type, Cmp::Greater)),
+                (DataType::Integer, "greater-than-or-equal") => {
+                    Ok(Self::Cmp(data_type, Cmp::GreaterEq))
+                }
+                _ => Err(unsupported()),
+            };
+        }
+        Err(unsupported())
+    }
+
+    fn id(self) -> String {
+        let name = match self {
+            Self::And => "and".into(),
+            Self::Or => "or".into(),
+            Self::Not => "not".into(),
+            Self::Cmp(data_type, cmp) => format!("{}-{}", data_type.name(), cmp.name()),
+            Self::IsIn(data_type) => format!("{}-is-in", data_type.name()),
+            Self::OneAndOnly(data_type) => format!("{}-one-and-only", data_type.name()),
+            Self::Like(like) => return like.function_id(),
+        };
+        format!("urn:oasis:names:tc:xacml:1.0:function:{name}")
+    }
+}
+
+/// An argument of a comparison
+#[derive(Debug, Clone)]
+enum Operand {
+    Lit(Lit),
+    Attr(Attr, DataType),
+}
+
+impl Operand {
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Lit(lit) => lit.data_type(),
+            Self::Attr(_, data_type) => *data_type,
+        }
+    }
+
+    fn expr(&self) -> ast::Expr {
+        match self {
+            Self::Lit(lit) => lit.expr(),
+            Self::Attr(attr, _) => attr.expr(),
+        }
+    }
+}
+
+/// Whether the descendants of an element include `Permit` and `Deny` rules
+fn effects(element: &Element) -> (bool, bool) {
+    element
+        .children
+        .iter()
+        .map(|child| match (child.name.as_str(), child.attr("Effect")) {
+            ("Rule", Some("Permit")) => (true, false),
+            ("Rule", Some("Deny")) => (false, true),
+            ("Policy" | "PolicySet", _) => effects(child),
+            _ => (false, false),
+        })
+        .fold((false, false), |(p1, d1), (p2, d2)| (p1 || p2, d1 || d2))
+}
+
+/// Translates XACML into Cedar
+#[derive(Debug)]
+struct Importer<'a> {
+    converter: &'a XacmlConverter,
+    policies: PolicySet,
+    issues: Vec<XacmlIssue>,
+}
+
+impl Importer<'_> {
+    fn report(
+        &mut self,
+        kind: XacmlIssueKind,
+        element: &Element,
+        path: &str,
+        message: impl Into<String>,
+    ) {
+        self.issues.push(XacmlIssue {
+            kind,
+            element: path.into(),
+            message: message.into(),
+            line: Some(element.line),
+        });
+    }
+
+    /// Translate the `PolicySet`, `Policy`, or `Rule` `element` in the
+    /// element with path `parent`, which applies when `inherited` hold
+    fn node(&mut self, element: &Element, parent: &str, inherited: &[ast::Expr]) {
+        let (id, algorithm) = match element.name.as_str() {
+            "PolicySet" => ("PolicySetId", "PolicyCombiningAlgId"),
+            "Policy" => ("PolicyId", "RuleCombiningAlgId"),
+            _ => ("RuleId", ""),
+        };
+        let id = element.attr(id).unwrap_or_default();
+        let path = if parent.is_empty() {
+            id.to_string()
+        } else {
+            format!("{parent}/{id}")
+        };
+        let mut conditions = inherited.to_vec();
+        match element.child("Target").map(|target| self.target(target)) {
+            Some(Ok(Some(target))) => conditions.push(target),
+            Some(Ok(None)) | None => {}
+            Some(Err(message)) => {
+                let skipped = if element.name == "Rule" {
+                    "the rule is not translated"
+                } else {
+                    "it is not translated, with everything in it"
+                };
+                self.report(
+                    XacmlIssueKind::Unsupported,
+                    element,
+                    &path,
+                    format!("in the target: {message}; {skipped}"),
+                );
+                return;
+            }
+        }
+        if element.name == "Rule" {
+            self.rule(element, &path, conditions);
+            return;
+        }
+        self.combining_algorithm(element, &path, element.attr(algorithm).unwrap_or_default());
+        for child in &element.children {
+            match (element.name.as_str(), child.name.as_str()) {
+                ("PolicySet", "PolicySet" | "Policy") | ("Policy", "Rule") => {
+                    self.node(child, &path, &conditions);
+                }
+                (
+                    _,
+                    "Target" | "Description" | "PolicyIssuer" | "PolicySetDefaults"
+                    | "PolicyDefaults" | "VariableDefinition",
+                ) => {}
+                (_, "ObligationExpressions" | "AdviceExpressions") => self.report(
+                    XacmlIssueKind::Lossy,
+                    child,
+                    &path,
+                    format!("`{}` have no Cedar equivalent and are dropped", child.name),
+                ),
+                (_, name) => self.report(
+                    XacmlIssueKind::Unsupported,
+                    child,
+                    &path,
+                    format!("`{name}` is not supported, and is not translated"),
+                ),
+            }
+        }
+    }
+
+    /// Report the combining algorithm of `element` if Cedar's
+    /// forbid-overrides semantics could give a different decision
+    fn combining_algorithm(&mut self, element: &Element, path: &str, algorithm: &str) {
+        let name = algorithm.rsplit(':').next().unwrap_or_default();
+        let (permit, deny) = effects(element);
+        let exact = match name {
+            "deny-overrides" | "ordered-deny-overrides" => true,
+            "deny-unless-permit" => !deny,
+            "permit-overrides" | "ordered-permit-overrides" | "first-applicable" => {
+                !(permit && deny)
+            }
+            _ => false,
+        };
+        if !exact {
+            self.report(
+                XacmlIssueKind::Lossy,
+                element,
+                path,
+                format!(
+                    "combining algorithm `{algorithm}` is approximated: in Cedar, forbid \
+                     policies override permit policies, and requests no policy permits are \
+                     denied"
+                ),
+            );
+        }
+    }
+
+    fn rule(&mut self, element: &Element, path: &str, mut conditions: Vec<ast::Expr>) {
+        let effect = match element.attr("Effect") {
+            Some("Permit") => ast::Effect::Permit,
+            Some("Deny") => ast::Effect::Forbid,
+            effect => {
+                self.report(
+                    XacmlIssueKind::Unsupported,
+                    element,
+                    path,
+                    format!(
+                        "effect `{}` is not supported; the rule is not translated",
+                        effect.unwrap_or_default()
+                    ),
+                );
+                return;
+            }
+        };
+        for child in &element.children {
+            match child.name.as_str() {
+                "Target" | "Description" => {}
+                "Condition" => {
+                    let expression = match child.children.as_slice() {
+                        [expression] => self.expression(expression),
+                        _ => Err("a condition must have exactly one expression".into()),
+                    };
+                    match expression {
+                        Ok(expression) => conditions.push(expression),
+                        Err(message) => {
+                            self.report(
+                                XacmlIssueKind::Unsupported,
+                                child,
+                                path,
+                                format!("in the condition: {message}; the rule is not translated"),
+                            );
+                            return;
+                        }
+                    }
+                }
+                "ObligationExpressions" | "AdviceExpressions" => self.report(
+                    XacmlIssueKind::Lossy,
+                    child,
+                    path,
+                    format!("`{}` have no Cedar equivalent and are dropped", child.name),
+                ),
+                name => {
+                    self.report(
+                        XacmlIssueKind::Unsupported,
+                        child,
+                        path,
+                        format!("`{name}` is not supported; the rule is not translated"),
+                    );
+                    return;
+                }
+            }
+        }
+        self.add_policy(element, path, effect, &conditions);
+    }
+
+    /// Add the policy translated from a rule. Conditions comparing the
+    /// principal, action, or resource with an entity go in its scope.
+    fn add_policy(
+        &mut self,
+        element: &Element,
+        path: &str,
+        effect: ast::Effect,
+        conditions: &[ast::Expr],
+    ) {
+        let mut conjuncts = Vec::new();
+        for condition in conditions {
+            flatten_and(condition, &mut conjuncts);
+        }
+        let mut principal = String::new();
+        let mut action = String::new();
+        let mut resource = String::new();
+        let mut when = Vec::new();
+        for conjunct in conjuncts {
+            if let Some((var, uid)) = scope_eq(conjunct) {
+                let scope = match var {
+                    ast::Var::Principal => &mut principal,
+                    ast::Var::Action => &mut action,
+                    _ => &mut resource,
+                };
+                if scope.is_empty() {
+                    *scope = format!(" == {uid}");
+                    continue;
+                }
+            }
+            if action.is_empty() {
+                if let Some(actions) = action_alternatives(conjunct) {
+                    action = format!(" in [{}]", actions.join(", "));
+                    continue;
+                }
+            }
+            when.push(conjunct.clone());
+        }
+        let condition = when
+            .into_iter()
+            .reduce(ast::Expr::and)
+            .map(|expr| format!(" when {{ {expr} }}"))
+            .unwrap_or_default();
+        let text = format!(
+            "{effect}(principal{principal}, action{action}, resource{resource}){condition};"
+        );
+        let result = match Policy::parse(Some(PolicyId::new(path)), &text) {
+            Ok(policy) => self.policies.add(policy).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            self.report(
+                XacmlIssueKind::Unsupported,
+                element,
+                path,
+                format!("failed to translate the rule: {e}"),
+            );
+        }
+    }
+
+    /// Translate a `Target`, which matches everything if it's empty
+    fn target(&self, target: &Element) -> Result<Option<ast::Expr>, String> {
+        let any_ofs = target
+            .children
+            .iter()
+            .map(|any_of| {
+                let all_ofs = any_of
+                    .children
+                    .iter()
+                    .map(|all_of| {
+                        all_of
+                            .children
+                            .iter()
+                            .map(|m| self.match_(m))
+                            .reduce(|a, b| Ok(ast::Expr::and(a?, b?)))
+                            .unwrap_or_else(|| Err("an `AllOf` must have a `Match`".into()))
+                    })
+                    .reduce(|a, b| Ok(ast::Expr::or(a?, b?)))
+                    .unwrap_or_else(|| Err("an `AnyOf` must have an `AllOf`".into()))?;
+                Ok(all_ofs)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(any_ofs.into_iter().reduce(ast::Expr::and))
+    }
+
+    /// Translate a `Match`, which applies its function to a literal and each
+    /// value of an attribute
+    fn match_(&self, element: &Element) -> Result<ast::Expr, String> {
+        if element.name != "Match" {
+            return Err(format!("expected `Match`, found `{}`", element.name));
+        }
+        let function = Function::parse(element.attr("MatchId").unwrap_or_default())?;
+        let value = element
+            .child("AttributeValue")
+            .ok_or("a `Match` must have an `AttributeValue`")?;
+        let value = Operand::Lit(Self::parse_literal(value)?);
+        let attr = match element.child("AttributeDesignator") {
+            Some(designator) => Self::parse_designator(designator)?,
+            None if element.child("AttributeSelector").is_some() => {
+                return Err("XPath attribute selectors are not supported".into())
+            }
+            None => return Err("a `Match` must have an `AttributeDesignator`".into()),
+        };
+        match function {
+            Function::Cmp(data_type, cmp) => self.compare(function, data_type, cmp, value, attr),
+            Function::Like(like) => Self::like_pattern(like, value, attr),
+            _ => Err(format!("`{}` can't be used in a `Match`", function.id())),
+        }
+    }
+
+    /// Translate an expression which must be boolean
+    fn expression(&self, element: &Element) -> Result<ast::Expr, String> {
+        match element.name.as_str() {
+            "Apply" => {}
+            "AttributeValue" => {
+                return match Self::parse_literal(element)? {
+                    Lit::Bool(b) => Ok(ast::Expr::val(b)),
+                    _ => Err("a condition must be boolean".into()),
+                };
+            }
+            "VariableReference" => return Err("variables are not supported".into()),
+            "AttributeSelector" => return Err("XPath attribute selectors are not supported".into()),
+            name => return Err(format!("`{name}` is not supported")),
+        }
+        let function = Function::parse(element.attr("FunctionId").unwrap_or_default())?;
+        let args: Vec<&Element> = element
+            .children
+            .iter()
+            .filter(|arg| arg.name != "Description")
+            .collect();
+        match (function, args.as_slice()) {
+            (Function::And, _) => args
+                .iter()
+                .map(|arg| self.expression(arg))
+                .reduce(|a, b| Ok(ast::Expr::and(a?, b?)))
+                .unwrap_or_else(|| Ok(ast::Expr::val(true))),
+            (Function::Or, _) => args
+                .iter()
+                .map(|arg| self.expression(arg))
+                .reduce(|a, b| Ok(ast::Expr::or(a?, b?)))
+                .unwrap_or_else(|| Ok(ast::Expr::val(false))),
+            (Function::Not, [arg]) => Ok(ast::Expr::not(self.expression(arg)?)),
+            (Function::Cmp(data_type, cmp), [lhs, rhs]) => self.compare(
+                function,
+                data_type,
+                cmp,
+                Self::parse_operand(lhs)?,
+                Self::parse_operand(rhs)?,
+            ),
+            (Function::IsIn(data_type), [value, bag]) => {
+                let value = Self::parse_operand(value)?;
+                let (attr, attr_type) = match bag.name.as_str() {
+                    "AttributeDesignator" => match Self::parse_designator(bag)? {
+                        Operand::Attr(attr, attr_type) => (attr, attr_type),
+                        Operand::Lit(_) => return Err("expected an attribute".into()),
+                    },
+                    _ => return Err("only attribute bags are supported in `-is-in`".into()),
+                };
+                check_type(function, data_type, value.data_type())?;
+                check_type(function, data_type, attr_type)?;
+                match (attr, value) {
+                    // An id is a single value
+                    (Attr::Id(var), value) => self.compare(
+                        function,
+                        data_type,
+                        Cmp::Eq,
+                        Operand::Attr(Attr::Id(var), attr_type),
+                        value,
+                    ),
+                    (attr, Operand::Lit(lit)) => Ok(guarded(
+                        &[&attr],
+                        ast::Expr::contains(attr.expr(), lit.expr()),
+                    )),
+                    (attr, Operand::Attr(value, _)) => Ok(guarded(
+                        &[&attr, &value],
+                        ast::Expr::contains(attr.expr(), value.expr()),
+                    )),
+                }
+            }
+            (Function::Like(like), [substring, string]) => Self::like_pattern(
+                like,
+                Self::parse_operand(substring)?,
+                Self::parse_operand(string)?,
+            ),
+            (Function::OneAndOnly(DataType::Boolean), [_]) => match Self::parse_operand(element)? {
+                Operand::Attr(attr, _) => Ok(guarded(&[&attr], attr.expr())),
+                Operand::Lit(lit) => Ok(lit.expr()),
+            },
+            _ => Err(format!(
+                "`{}` with {} arguments is not supported in a condition",
+                function.id(),
+                args.len()
+            )),
+        }
+    }
+
+    /// Translate an argument of a comparison, which must be a literal or an
+    /// attribute reduced to a single value with a `-one-and-only` function
+    fn parse_operand(element: &Element) -> Result<Operand, String> {
+        match element.name.as_str() {
+            "AttributeValue" => Ok(Operand::Lit(Self::parse_literal(element)?)),
+            "Apply" => {
+                let function = Function::parse(element.attr("FunctionId").unwrap_or_default())?;
+                match (function, element.children.as_slice()) {
+                    (Function::OneAndOnly(data_type), [designator])
+                        if designator.name == "AttributeDesignator" =>
+                    {
+                        let attr = Self::parse_designator(designator)?;
+                        check_type(function, data_type, attr.data_type())?;
+                        Ok(attr)
+                    }
+                    _ => Err(format!(
+                        "only literals and attributes are supported as arguments, found `{}`",
+                        function.id()
+                    )),
+                }
+            }
+            "AttributeDesignator" => Err(
+                "a bag of attribute values must be reduced with a `-one-and-only` function".into(),
+            ),
+            "VariableReference" => Err("variables are not supported".into()),
+            "AttributeSelector" => Err("XPath attribute selectors are not supported".into()),
+            name => Err(format!("`{name}` is not supported")),
+        }
+    }
+
+    fn parse_literal(element: &Element) -> Result<Lit, String> {
+        let data_type = DataType::parse(element.attr("DataType").unwrap_or_default())?;
+        let text = element.text.as_str();
+        match data_type {
+            DataType::String => Ok(Lit::Str(text.into())),
+            DataType::Integer => text
+                .trim()
+                .parse()
+                .map(Lit::Long)
+                .map_err(|_| format!("`{text}` is not an integer that fits in 64 bits")),
+            DataType::Boolean => match text.trim() {
+                "true" | "1" => Ok(Lit::Bool(true)),
+                "false" | "0" => Ok(Lit::Bool(false)),
+                _ => Err(format!("`{text}` is not a boolean")),
+            },
+        }
+    }
+
+    fn parse_designator(element: &Element) -> Result<Operand, String> {
+        let data_type = DataType::parse(element.attr("DataType").unwrap_or_default())?;
+        let var = match element.attr("Category").unwrap_or_default() {
+            SUBJECT_CATEGORY => ast::Var::Principal,
+            ACTION_CATEGORY => ast::Var::Action,
+            RESOURCE_CATEGORY => ast::Var::Resource,
+            ENVIRONMENT_CATEGORY => ast::Var::Context,
+            category => return Err(format!("attribute category `{category}` is not supported")),
+        };
+        let id = element.attr("AttributeId").unwrap_or_default();
+        let attr = if Some(id) == id_attribute(var) {
+            if data_type != DataType::String {
+                return Err(format!("`{id}` must be a string"));
+            }
+            Attr::Id(var)
+        } else {
+            let name = id.rsplit([':', '/', '#']).next().unwrap_or_default();
+            if name.is_empty() {
+                return Err(format!("attribute id `{id}` is not supported"));
+            }
+            Attr::Named(var, name.into())
+        };
+        Ok(Operand::Attr(attr, data_type))
+    }
+
+    fn entity(&self, var: ast::Var, id: &str) -> Result<ast::Expr, String> {
+        let entity_type = match var {
+            ast::Var::Principal => self.converter.principal_type.clone(),
+            ast::Var::Resource => self.converter.resource_type.clone(),
+            #[allow(clippy::expect_used)]
+            ast::Var::Action => EntityTypeName::from_str("Action").expect("valid entity type name"),
+            ast::Var::Context => return Err("the context has no id".into()),
+        };
+        Ok(ast::Expr::val(
+            EntityUid::from_type_name_and_id(entity_type, EntityId::new(id)).0,
+        ))
+    }
+
+    fn compare(
+        &self,
+        function: Function,
+        data_type: DataType,
+        cmp: Cmp,
+        lhs: Operand,
+        rhs: Operand,
+    ) -> Result<ast::Expr, String> {
+        check_type(function, data_type, lhs.data_type())?;
+        check_type(function, data_type, rhs.data_type())?;
+        // Put attributes first, which reads more naturally
+        let (cmp, lhs, rhs) = match (&lhs, &rhs) {
+            (Operand::Lit(_), Operand::Attr(..)) => (cmp.flipped(), rhs, lhs),
+            _ => (cmp, lhs, rhs),
+        };
+        match (lhs, rhs) {
+            (Operand::Attr(Attr::Id(var), _), Operand::Lit(Lit::Str(id))) if cmp == Cmp::Eq => Ok(
+                ast::Expr::is_eq(ast::Expr::var(var), self.entity(var, &id)?),
+            ),
+            (Operand::Attr(Attr::Id(var), _), _) | (_, Operand::Attr(Attr::Id(var), _)) => Err(
+                format!("the id of the {var} can only be compared for equality with a literal"),
+            ),
+            (lhs, rhs) => {
+                let attrs: Vec<&Attr> = [&lhs, &rhs]
+                    .into_iter()
+                    .filter_map(|operand| match operand {
+                        Operand::Attr(attr, _) => Some(attr),
+                        Operand::Lit(_) => None,
+                    })
+                    .collect();
+                Ok(guarded(&attrs, cmp.expr(lhs.expr(), rhs.expr())))
+            }
+        }
+    }
+
+    fn like_pattern(like: Like, substring: Operand, string: Operand) -> Result<ast::Expr, String> {
+        let (
+            Operand::Lit(Lit::Str(substring)),
+            Operand::Attr(attr @ Attr::Named(..), DataType::String),
+        ) = (substring, string)
+        else {
+            return Err(format!(
+                "`{}` is only supported with a literal and a string attribute",
+                like.name()
+            ));
+        };
+        let chars = substring.chars().map(PatternElem::Char);
+        let pattern: ast::Pattern = match like {
+            Like::StartsWith => chars
+                .chain(std::iter::once(PatternElem::Wildcard))
+                .collect(),
+            Like::EndsWith => std::iter::once(PatternElem::Wildcard)
+                .chain(chars)
+                .collect(),
+            Like::Contains => std::iter::once(PatternElem::Wildcard)
+                .chain(chars)
+                .chain(std::iter::once(PatternElem::Wildcard))
+                .collect(),
+        };
+        Ok(guarded(&[&attr], ast::Expr::like(attr.expr(), pattern)))
+    }
+}
+
+fn check_type(function: Function, expected: DataType, found: DataType) -> Result<(), String> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(format!(
+            "`{}` applied to {} {}",
+            function.id(),
+            if found == DataType::Integer {
+                "an"
+            } else {
+                "a"
+            },
+            found.name()
+        ))
+    }
+}
+
+/// `body`, after testing that the named attributes in `attrs` exist
+fn guarded(attrs: &[&Attr], body: ast::Expr) -> ast::Expr {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.guard())
+        .rev()
+        .fold(body, |body, guard| ast::Expr::and(guard, body))
+}
+
+fn flatten_and<'e>(expr: &'e ast::Expr, conjuncts: &mut Vec<&'e ast::Expr>) {
+    match expr.expr_kind() {
+        ast::ExprKind::And { left, right } => {
+            flatten_and(left, conjuncts);
+            flatten_and(right, conjuncts);
+        }
+        ast::ExprKind::Lit(ast::Literal::Bool(true)) => {}
+        _ => conjuncts.push(expr),
+    }
+}
+
+fn flatten_or<'e>(expr: &'e ast::Expr, disjuncts: &mut Vec<&'e ast::Expr>) {
+    match expr.expr_kind() {
+        ast::ExprKind::Or { left, right } => {
+            flatten_or(left, disjuncts);
+            flatten_or(right, disjuncts);
+        }
+        _ => disjuncts.push(expr),
+    }
+}
+
+/// The variable and entity of a `principal`, `action`, or `resource`
+/// equality
+fn scope_eq(expr: &ast::Expr) -> Option<(ast::Var, &ast::EntityUID)> {
+    match expr.expr_kind() {
+        ast::ExprKind::BinaryApp {
+            op: ast::BinaryOp::Eq,
+            arg1,
+            arg2,
+        } => match (arg1.expr_kind(), arg2.expr_kind()) {
+            (ast::ExprKind::Var(var), ast::ExprKind::Lit(ast::Literal::EntityUID(uid)))
+                if *var != ast::Var::Context =>
+            {
+                Some((*var, uid))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The actions of a disjunction of `action` equalities
+fn action_alternatives(expr: &ast::Expr) -> Option<Vec<String>> {
+    let mut disjuncts = Vec::new();
+    flatten_or(expr, &mut disjuncts);
+    if disjuncts.len() < 2 {
+        return None;
+    }
+    disjuncts
+        .into_iter()
+        .map(|disjunct| match scope_eq(disjunct) {
+            Some((ast::Var::Action, uid)) => Some(uid.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Translates Cedar into XACML
+#[derive(Debug)]
+struct Exporter<'a> {
+    converter: &'a XacmlConverter,
+    issues: Vec<XacmlIssue>,
+}
+
+impl Exporter<'_> {
+    fn report(&mut self, kind: XacmlIssueKind, id: &PolicyId, message: impl Into<String>) {
+        self.issues.push(XacmlIssue {
+            kind,
+            element: id.to_string(),
+            message: message.into(),
+            line: None,
+        });
+    }
+
+    fn rule(&mut self, policy: &Policy) -> Result<Element, String> {
+        let effect = match policy.effect() {
+            super::Effect::Permit => "Permit",
+            super::Effect::Forbid => "Deny",
+        };
+        let mut any_ofs = Vec::new();
+        match policy.principal_constraint() {
+            PrincipalConstraint::Any => {}
+            PrincipalConstraint::Eq(uid) => {
+                any_ofs.push(vec![self.id_match(ast::Var::Principal, &uid)?]);
+            }
+            PrincipalConstraint::Is(entity_type) => {
+                self.check_type(ast::Var::Principal, &entity_type)?;
+            }
+            PrincipalConstraint::In(_) | PrincipalConstraint::IsIn(..) => {
+                return Err("entity hierarchy (`principal in ...`) has no XACML equivalent".into())
+            }
+        }
+        match policy.action_constraint() {
+            ActionConstraint::Any => {}
+            ActionConstraint::Eq(uid) => any_ofs.push(vec![self.id_match(ast::Var::Action, &uid)?]),
+            ActionConstraint::In(uids) => {
+                let matches = uids
+                    .iter()
+                    .map(|uid| self.id_match(ast::Var::Action, uid))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.report(
+                    XacmlIssueKind::Lossy,
+                    policy.id(),
+                    "action groups have no XACML equivalent, so `action in [...]` is exported as \
+                     equality with one of the actions, not including their members",
+                );
+                any_ofs.push(matches);
+            }
+        }
+        match policy.resource_constraint() {
+            ResourceConstraint::Any => {}
+            ResourceConstraint::Eq(uid) => {
+                any_ofs.push(vec![self.id_match(ast::Var::Resource, &uid)?]);
+            }
+            ResourceConstraint::Is(entity_type) => {
+                self.check_type(ast::Var::Resource, &entity_type)?;
+            }
+            ResourceConstraint::In(_) | ResourceConstraint::IsIn(..) => {
+                return Err("entity hierarchy (`resource in ...`) has no XACML equivalent".into())
+            }
+        }
+        let target = any_ofs
+            .into_iter()
+            .fold(Element::new("Target"), |target, matches| {
+                let any_of = matches
+                    .into_iter()
+                    .fold(Element::new("AnyOf"), |any_of, m| {
+                        any_of.with_child(Element::new("AllOf").with_child(m))
+                    });
+                target.with_child(any_of)
+            });
+        let mut rule = Element::new("Rule")
+            .with_attr("RuleId", policy.id().to_string())
+            .with_attr("Effect", effect)
+            .with_child(target);
+        if let Some(condition) = self.condition(policy.ast.non_scope_constraints())? {
+            rule = rule.with_child(Element::new("Condition").with_child(condition));
+        }
+        Ok(rule)
+    }
+
+    fn check_type(&self, var: ast::Var, entity_type: &EntityTypeName) -> Result<(), String> {
+        let expected = match var {
+            ast::Var::Principal => &self.converter.principal_type,
+            ast::Var::Resource => &self.converter.resource_type,
+            _ => return Ok(()),
+        };
+        if entity_type == expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "XACML identifies entities by id only, so only {var}s of type `{expected}` are \
+                 supported, not `{entity_type}`"
+            ))
+        }
+    }
+
+    /// A `Match` testing that the id of the principal, action, or resource is
+    /// the id of `uid`
+    fn id_match(&self, var: ast::Var, uid: &EntityUid) -> Result<Element, String> {
+        let id = self.entity_id(var, uid)?;
+        Ok(Element::new("Match")
+            .with_attr("MatchId", Function::Cmp(DataType::String, Cmp::Eq).id())
+            .with_child(id.element())
+            .with_child(Attr::Id(var).designator(DataType::String)))
+    }
+
+    /// The id of `uid`, after checking that it's of the type of `var`
+    fn entity_id(&self, var: ast::Var, uid: &EntityUid) -> Result<Lit, String> {
+        if var == ast::Var::Action {
+            if uid.type_name().to_string() != "Action" {
+                return Err(format!(
+                    "only actions of type `Action` are supported, not `{}`",
+                    uid.type_name()
+                ));
+            }
+        } else {
+            self.check_type(var, uid.type_name())?;
+        }
+        Ok(Lit::Str(uid.id().as_ref().into()))
+    }
+
+    /// Translate the conditions of a policy, if any
+    fn condition(&self, expr: &ast::Expr) -> Result<Option<Element>, String> {
+        let mut conjuncts = Vec::new();
+        flatten_and(expr, &mut conjuncts);
+        if conjuncts.is_empty() {
+            Ok(None)
+        } else {
+            self.conjunction(&conjuncts).map(Some)
+        }
+    }
+
+    /// Translate a conjunction. Tests that attributes exist are left out if
+    /// another conjunct reads the attribute, since XACML functions don't
+    /// apply to missing attributes.
+    fn conjunction(&self, conjuncts: &[&ast::Expr]) -> Result<Element, String> {
+        let reads = |var: &ast::Var, attr: &SmolStr, conjunct: &ast::Expr| {
+            conjunct.subexpressions().any(|e| match e.expr_kind() {
+                ast::ExprKind::GetAttr { expr, attr: read } => {
+                    read == attr && matches!(expr.expr_kind(), ast::ExprKind::Var(v) if v == var)
+                }
+                _ => false,
+            })
+        };
+        let mut args = Vec::new();
+        for (i, conjunct) in conjuncts.iter().enumerate() {
+            if let ast::ExprKind::HasAttr { expr, attr } = conjunct.expr_kind() {
+                if let ast::ExprKind::Var(var) = expr.expr_kind() {
+                    let guard = conjuncts
+                        .iter()
+                        .enumerate()
+                        .any(|(j, other)| i != j && reads(var, attr, other));
+                    if guard {
+                        continue;
+                    }
+                }
+            }
+            args.push(self.expression(conjunct)?);
+        }
+        Ok(match <[Element; 1]>::try_from(args) {
+            Ok([arg]) => arg,
+            Err(args) => apply(Function::And, args),
+        })
+    }
+
+    fn expression(&self, expr: &ast::Expr) -> Result<Element, String> {
+        let unsupported = || format!("`{expr}` has no XACML equivalent");
+        match expr.expr_kind() {
+            ast::ExprKind::Lit(ast::Literal::Bool(b)) => Ok(Lit::Bool(*b).element()),
+            ast::ExprKind::And { .. } => {
+                let mut conjuncts = Vec::new();
+                flatten_and(expr, &mut conjuncts);
+                self.conjunction(&conjuncts)
+            }
+            ast::ExprKind::Or { .. } => {
+                let mut disjuncts = Vec::new();
+                flatten_or(expr, &mut disjuncts);
+                let args = disjuncts
+                    .into_iter()
+                    .map(|disjunct| self.expression(disjunct))
+                    .collect::<Result<_, _>>()?;
+                Ok(apply(Function::Or, args))
+            }
+            ast::ExprKind::UnaryApp {
+                op: ast::UnaryOp::Not,
+                arg,
+            } => match arg.expr_kind() {
+                // `>` and `>=` are parsed as negated `<=` and `<`
+                ast::ExprKind::BinaryApp {
+                    op: ast::BinaryOp::LessEq,
+                    arg1,
+                    arg2,
+                } => self.compare(Cmp::Greater, arg1, arg2),
+                ast::ExprKind::BinaryApp {
+                    op: ast::BinaryOp::Less,
+                    arg1,
+                    arg2,
+                } => self.compare(Cmp::GreaterEq, arg1, arg2),
+                _ => Ok(apply(Function::Not, vec![self.expression(arg)?])),
+            },
+            ast::ExprKind::BinaryApp { op, arg1, arg2 } => match op {
+                ast::BinaryOp::Eq => self.compare(Cmp::Eq, arg1, arg2),
+                ast::BinaryOp::Less => self.compare(Cmp::Less, arg1, arg2),
+                ast::BinaryOp::LessEq => self.compare(Cmp::LessEq, arg1, arg2),
+                ast::BinaryOp::Contains => match (Self::read_attr(arg1)?, Self::read_attr(arg2)?) {
+                    (Some(Attr::Named(var, attr)), None) => {
+                        let lit = literal(arg2).ok_or_else(unsupported)?;
+                        let data_type = lit.data_type();
+                        Ok(apply(
+                            Function::IsIn(data_type),
+                            vec![lit.element(), Attr::Named(var, attr).designator(data_type)],
+                        ))
+                    }
+                    _ => Err(unsupported()),
+                },
+                _ => Err(unsupported()),
+            },
+            ast::ExprKind::Like { expr: arg, pattern } => {
+                let Some(attr @ Attr::Named(..)) = Self::read_attr(arg)? else {
+                    return Err(unsupported());
+                };
+                let elems = pattern.get_elems();
+                let wildcard = |elem: Option<&PatternElem>| elem == Some(&PatternElem::Wildcard);
+                let (like, inner) = match (wildcard(elems.first()), wildcard(elems.last())) {
+                    (true, true) if elems.len() > 1 => {
+                        (Some(Like::Contains), elems.get(1..elems.len() - 1))
+                    }
+                    (true, false) => (Some(Like::EndsWith), elems.get(1..)),
+                    (false, true) => (Some(Like::StartsWith), elems.get(..elems.len() - 1)),
+                    (false, false) => (None, Some(elems)),
+                    _ => return Err(unsupported()),
+                };
+                let substring = inner
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|elem| match elem {
+                        PatternElem::Char(c) => Some(*c),
+                        _ => None,
+                    })
+                    .collect::<Option<String>>()
+                    .ok_or_else(unsupported)?;
+                let value = Lit::Str(substring.into()).element();
+                let designator = attr.designator(DataType::String);
+                Ok(match like {
+                    Some(like) => apply(
+                        Function::Like(like),
+                        vec![value, one_and_only(DataType::String, designator)],
+                    ),
+                    None => apply(
+                        Function::Cmp(DataType::String, Cmp::Eq),
+                        vec![one_and_only(DataType::String, designator), value],
+                    ),
+                })
+            }
+            ast::ExprKind::GetAttr { .. } => Self::read_attr(expr)?
+                .map(|attr| one_and_only(DataType::Boolean, attr.designator(DataType::Boolean)))
+                .ok_or_else(unsupported),
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// The attribute `expr` reads, if it's an attribute of the principal,
+    /// action, resource, or context, or the principal, action, or resource
+    /// itself; or `None` if it's something else
+    fn read_attr(expr: &ast::Expr) -> Result<Option<Attr>, String> {
+        match expr.expr_kind() {
+            ast::ExprKind::Var(ast::Var::Context) => {
+                Err("the context as a whole has no XACML equivalent".into())
+            }
+            ast::ExprKind::Var(var) => Ok(Some(Attr::Id(*var))),
+            ast::ExprKind::GetAttr { expr: object, attr } => match object.expr_kind() {
+                ast::ExprKind::Var(var) => Ok(Some(Attr::Named(*var, attr.clone()))),
+                _ => Err(format!("`{expr}` has no XACML equivalent")),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn compare(&self, cmp: Cmp, lhs: &ast::Expr, rhs: &ast::Expr) -> Result<Element, String> {
+        let unsupported = || format!("`{lhs}` compared with `{rhs}` has no XACML equivalent");
+        let (cmp, attr, value) = match (Self::read_attr(lhs)?, Self::read_attr(rhs)?) {
+            (Some(attr), None) => (cmp, attr, rhs),
+            (None, Some(attr)) => (cmp.flipped(), attr, lhs),
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "`{lhs}` compared with `{rhs}` can't be exported, since the type of the \
+                     attributes is unknown"
+                ))
+            }
+            (None, None) => return Err(unsupported()),
+        };
+        let lit = match (&attr, value.expr_kind()) {
+            (Attr::Id(var), ast::ExprKind::Lit(ast::Literal::EntityUID(uid))) if cmp == Cmp::Eq => {
+                self.entity_id(*var, &EntityUid(uid.as_ref().clone()))?
+            }
+            (Attr::Named(..), _) => literal(value).ok_or_else(unsupported)?,
+            _ => return Err(unsupported()),
+        };
+        let data_type = lit.data_type();
+        if cmp != Cmp::Eq && data_type != DataType::Integer {
+            return Err(unsupported());
+        }
+        Ok(apply(
+            Function::Cmp(data_type, cmp),
+            vec![
+                one_and_only(data_type, attr.designator(data_type)),
+                lit.element(),
+            ],
+        ))
+    }
+}
+
+fn literal(expr: &ast::Expr) -> Option<Lit> {
+    match expr.expr_kind() {
+        ast::ExprKind::Lit(ast::Literal::String(s)) => Some(Lit::Str(s.clone())),
+        ast::ExprKind::Lit(ast::Literal::Long(i)) => Some(Lit::Long(*i)),
+        ast::ExprKind::Lit(ast::Literal::Bool(b)) => Some(Lit::Bool(*b)),
+        _ => None,
+    }
+}
+
+fn apply(function: Function, args: Vec<Element>) -> Element {
+    let mut apply = Element::new("Apply").with_attr("FunctionId", function.id());
+    apply.children = args;
+    apply
+}
+
+fn one_and_only(data_type: DataType, designator: Element) -> Element {
+    apply(Function::OneAndOnly(data_type), vec![designator])
+}
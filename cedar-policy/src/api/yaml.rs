@@ -0,0 +1,175 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Loading schemas and entities written in YAML. A YAML document is read
+//! as the JSON value with the same structure, so YAML schemas and entities
+//! have exactly the structure of the JSON schema format and the JSON entity
+//! format, and go through the same validation. YAML comments, anchors and
+//! aliases, and `<<` merge keys may be used.
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::{entities_errors::EntitiesError, Entities, Schema, SchemaError, SchemaFragment};
+
+/// Error when a document is not valid YAML, or has no JSON equivalent
+#[derive(Debug, Clone, PartialEq, Eq, Diagnostic, Error)]
+#[error("invalid YAML: {message}")]
+pub struct YamlSyntaxError {
+    message: String,
+}
+
+impl YamlSyntaxError {
+    /// What went wrong, including the line and column of the error if known
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<serde_yaml::Error> for YamlSyntaxError {
+    fn from(err: serde_yaml::Error) -> Self {
+        Self {
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Errors reading a schema from YAML, with [`Schema::from_yaml_str`] and
+/// similar functions
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum SchemaYamlError {
+    /// The document is not valid YAML
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Yaml(#[from] YamlSyntaxError),
+    /// The document is not a valid schema in the JSON schema format
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Schema(#[from] SchemaError),
+}
+
+/// Errors reading entities from YAML, with [`Entities::from_yaml_str`] and
+/// [`Entities::from_yaml_file`]
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum EntitiesYamlError {
+    /// The document is not valid YAML
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Yaml(#[from] YamlSyntaxError),
+    /// The document does not declare valid entities in the JSON entity
+    /// format
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Entities(#[from] EntitiesError),
+}
+
+/// Read the YAML document `yaml` as the JSON value with the same structure
+fn yaml_to_json(mut yaml: serde_yaml::Value) -> Result<serde_json::Value, YamlSyntaxError> {
+    yaml.apply_merge()?;
+    serde_json::to_value(yaml).map_err(|err| YamlSyntaxError {
+        message: err.to_string(),
+    })
+}
+
+/// Parse the YAML document `src` as a JSON value
+fn parse_yaml_str(src: &str) -> Result<serde_json::Value, YamlSyntaxError> {
+    yaml_to_json(serde_yaml::from_str(src)?)
+}
+
+/// Parse the YAML document read from `file` as a JSON value
+fn parse_yaml_file(file: impl std::io::Read) -> Result<serde_json::Value, YamlSyntaxError> {
+    yaml_to_json(serde_yaml::from_reader(file)?)
+}
+
+impl Schema {
+    /// Create a [`Schema`] from a string containing YAML with the structure
+    /// of the JSON schema format.
+    ///
+    /// ```
+    /// # use cedar_policy::Schema;
+    /// let schema = Schema::from_yaml_str(r#"
+    /// Photos:
+    ///   entityTypes:
+    ///     User: {}
+    ///     Photo: {}
+    ///   actions:
+    ///     view:  # may apply to any user and photo
+    ///       appliesTo:
+    ///         principalTypes: [User]
+    ///         resourceTypes: [Photo]
+    /// "#).unwrap();
+    /// assert_eq!(schema.actions().count(), 1);
+    /// ```
+    pub fn from_yaml_str(src: &str) -> Result<Self, SchemaYamlError> {
+        Ok(Self::from_json_value(parse_yaml_str(src)?)?)
+    }
+
+    /// Create a [`Schema`] directly from a file containing YAML with the
+    /// structure of the JSON schema format.
+    pub fn from_yaml_file(file: impl std::io::Read) -> Result<Self, SchemaYamlError> {
+        Ok(Self::from_json_value(parse_yaml_file(file)?)?)
+    }
+}
+
+impl SchemaFragment {
+    /// Create a [`SchemaFragment`] from a string containing YAML with the
+    /// structure of the JSON schema format.
+    pub fn from_yaml_str(src: &str) -> Result<Self, SchemaYamlError> {
+        Ok(Self::from_json_value(parse_yaml_str(src)?)?)
+    }
+
+    /// Create a [`SchemaFragment`] directly from a file containing YAML
+    /// with the structure of the JSON schema format.
+    pub fn from_yaml_file(file: impl std::io::Read) -> Result<Self, SchemaYamlError> {
+        Ok(Self::from_json_value(parse_yaml_file(file)?)?)
+    }
+}
+
+impl Entities {
+    /// Parse entities from a string containing YAML with the structure of
+    /// the JSON entity format. A `schema` is handled identically to
+    /// [`Entities::from_json_str`].
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, EntityUid};
+    /// # use std::str::FromStr;
+    /// let entities = Entities::from_yaml_str(r#"
+    /// - uid: { type: User, id: alice }
+    ///   attrs:
+    ///     age: 19
+    ///   parents:
+    ///     - { type: Group, id: admins }
+    /// "#, None).unwrap();
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let admins = EntityUid::from_str(r#"Group::"admins""#).unwrap();
+    /// assert!(entities.is_ancestor_of(&admins, &alice));
+    /// ```
+    pub fn from_yaml_str(src: &str, schema: Option<&Schema>) -> Result<Self, EntitiesYamlError> {
+        Ok(Self::from_json_value(parse_yaml_str(src)?, schema)?)
+    }
+
+    /// Parse entities from a file containing YAML with the structure of the
+    /// JSON entity format. A `schema` is handled identically to
+    /// [`Entities::from_json_str`].
+    pub fn from_yaml_file(
+        file: impl std::io::Read,
+        schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesYamlError> {
+        Ok(Self::from_json_value(parse_yaml_file(file)?, schema)?)
+    }
+}
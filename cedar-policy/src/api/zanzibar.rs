@@ -0,0 +1,814 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Importing Zanzibar-style relationship tuples, as stored by `OpenFGA` and
+//! similar systems, as Cedar entities and template-linked policies.
+//!
+//! Every object in the tuples becomes an entity, whose type is the object
+//! type. Every relation of every object becomes an entity of the relation
+//! type (`Relation` by default), e.g., `Relation::"document:readme#viewer"`,
+//! whose members (descendants) are the users which have that relation
+//! with that object: the users of tuples `document:readme#viewer@user:alice`,
+//! the members of usersets `document:readme#viewer@group:eng#member`, and
+//! the users which have relations the model includes, such as
+//! `define viewer: [user] or editor` or `define viewer: viewer from parent`.
+//!
+//! Each relation name has a template
+//! `permit(principal in ?principal, action == Action::"viewer", resource == ?resource);`
+//! linked once per object and relation, so a check of whether `user:alice`
+//! has relation `viewer` with `document:readme` is the request with
+//! principal `user::"alice"`, action `Action::"viewer"`, and resource
+//! `document::"readme"`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+
+use cedar_policy_core::ast;
+use miette::Diagnostic;
+use smol_str::SmolStr;
+use thiserror::Error;
+
+use super::{
+    Entities, Entity, EntityId, EntityTypeName, EntityUid, Policy, PolicyId, PolicySet, SlotId,
+    Template,
+};
+
+/// Imports relationship tuples and a relation model; see
+/// [`ZanzibarImporter::import`].
+///
+/// ```
+/// # use cedar_policy::{Authorizer, Context, Decision, Request, ZanzibarImporter};
+/// let model = r#"
+/// model
+///   schema 1.1
+/// type user
+/// type group
+///   relations
+///     define member: [user]
+/// type document
+///   relations
+///     define editor: [user, group#member]
+///     define viewer: [user] or editor
+/// "#;
+/// let tuples = "
+/// group:eng#member@user:alice
+/// document:readme#editor@group:eng#member
+/// ";
+/// let import = ZanzibarImporter::new().import(model, tuples).unwrap();
+/// assert!(import.issues().is_empty());
+///
+/// let request = Request::new(
+///     r#"user::"alice""#.parse().unwrap(),
+///     r#"Action::"viewer""#.parse().unwrap(),
+///     r#"document::"readme""#.parse().unwrap(),
+///     Context::empty(),
+///     None,
+/// )
+/// .unwrap();
+/// let response = Authorizer::new().is_authorized(&request, import.policies(), import.entities());
+/// assert_eq!(response.decision(), Decision::Allow);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZanzibarImporter {
+    relation_type: EntityTypeName,
+}
+
+impl Default for ZanzibarImporter {
+    fn default() -> Self {
+        #[allow(clippy::expect_used)]
+        let relation_type = EntityTypeName::from_str("Relation").expect("valid entity type name");
+        Self { relation_type }
+    }
+}
+
+impl ZanzibarImporter {
+    /// Create an importer whose relation entities have type `Relation`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give relation entities the type `entity_type`, which must not be one of
+    /// the types of the model
+    #[must_use]
+    pub fn relation_type(mut self, entity_type: EntityTypeName) -> Self {
+        self.relation_type = entity_type;
+        self
+    }
+
+    /// Import the relationship tuples `tuples`, one `object#relation@user`
+    /// per line, where the `user` is an object (`user:alice`), all objects of
+    /// a type (`user:*`), or a userset (`group:eng#member`). Blank lines, and
+    /// lines starting with `#` or `//`, are ignored.
+    ///
+    /// `model` defines the types and their relations in the `OpenFGA` modeling
+    /// language. Relations are unions of directly related types (`[user,
+    /// user:*, group#member]`), other relations of the same object
+    /// (`editor`), and relations of related objects (`viewer from parent`).
+    /// Intersections (`and`), exclusions (`but not`), and conditions
+    /// (`with`) can't be represented by an entity hierarchy; relations using
+    /// them are reported in [`ZanzibarImport::issues`], and grant nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ZanzibarParseError`] if `model` or `tuples` is malformed,
+    /// or the model refers to types or relations it doesn't define.
+    pub fn import(&self, model: &str, tuples: &str) -> Result<ZanzibarImport, ZanzibarParseError> {
+        let mut importer = Importer {
+            relation_type: &self.relation_type,
+            model: Model::parse(model)?,
+            objects: BTreeSet::new(),
+            parents: BTreeMap::new(),
+            related: BTreeMap::new(),
+            wildcards: Vec::new(),
+            issues: Vec::new(),
+        };
+        importer.issues = std::mem::take(&mut importer.model.issues);
+        for (index, line) in tuples.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+            let tuple = Tuple::parse(line).ok_or_else(|| ZanzibarParseError {
+                input: ZanzibarInput::Tuples,
+                line: index + 1,
+                message: format!("expected a tuple `type:id#relation@user`, found `{line}`"),
+            })?;
+            importer.tuple(index + 1, tuple);
+        }
+        Ok(importer.finish())
+    }
+}
+
+/// The entities and policies imported by a [`ZanzibarImporter`]
+#[derive(Debug, Clone)]
+pub struct ZanzibarImport {
+    entities: Entities,
+    policies: PolicySet,
+    issues: Vec<ZanzibarImportIssue>,
+}
+
+impl ZanzibarImport {
+    /// The objects, and the relation entities of their relations
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// A template per relation, linked once per object and relation; and
+    /// for tuples granting a relation to all objects of a type, e.g.,
+    /// `document:readme#viewer@user:*`, a static policy for each relation it
+    /// implies
+    pub fn policies(&self) -> &PolicySet {
+        &self.policies
+    }
+
+    /// Relations of the model and tuples which could not be imported, in
+    /// order
+    pub fn issues(&self) -> &[ZanzibarImportIssue] {
+        &self.issues
+    }
+
+    /// Consume the `ZanzibarImport`, returning the entities, the policies,
+    /// and the issues
+    pub fn into_parts(self) -> (Entities, PolicySet, Vec<ZanzibarImportIssue>) {
+        (self.entities, self.policies, self.issues)
+    }
+}
+
+/// Which input of [`ZanzibarImporter::import`] an issue or error is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZanzibarInput {
+    /// The relation model
+    Model,
+    /// The relationship tuples
+    Tuples,
+}
+
+impl std::fmt::Display for ZanzibarInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Model => write!(f, "model"),
+            Self::Tuples => write!(f, "tuples"),
+        }
+    }
+}
+
+/// A relation or tuple which could not be imported
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("{message}")]
+pub struct ZanzibarImportIssue {
+    input: ZanzibarInput,
+    line: Option<usize>,
+    message: String,
+}
+
+impl ZanzibarImportIssue {
+    /// Whether the issue is in the model or the tuples
+    pub fn input(&self) -> ZanzibarInput {
+        self.input
+    }
+
+    /// The line of the issue in its input, starting from 1, unless it
+    /// concerns the tuples as a whole
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// What couldn't be imported, and why
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Error parsing a relation model or relationship tuples
+#[derive(Debug, Clone, Diagnostic, Error)]
+#[error("invalid {input} at line {line}: {message}")]
+pub struct ZanzibarParseError {
+    input: ZanzibarInput,
+    line: usize,
+    message: String,
+}
+
+impl ZanzibarParseError {
+    /// Whether the error is in the model or the tuples
+    pub fn input(&self) -> ZanzibarInput {
+        self.input
+    }
+
+    /// The line of the error in its input, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// A directly related type of a relation
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Direct {
+    /// `user`, i.e., objects of the type
+    Type(SmolStr),
+    /// `user:*`, i.e., all objects of the type at once
+    Wildcard(SmolStr),
+    /// `group#member`, i.e., usersets
+    Userset(SmolStr, SmolStr),
+}
+
+/// One of the relations whose union a relation is
+#[derive(Debug, Clone)]
+enum Term {
+    Direct(Vec<Direct>),
+    /// Another relation of the same object
+    Computed(SmolStr),
+    /// `relation from tupleset`: `relation` of the objects related to the
+    /// object by `tupleset`
+    FromTupleset {
+        relation: SmolStr,
+        tupleset: SmolStr,
+    },
+}
+
+#[derive(Debug)]
+struct Relation {
+    line: usize,
+    /// `None` if the relation can't be represented
+    terms: Option<Vec<Term>>,
+}
+
+#[derive(Debug, Default)]
+struct Model {
+    types: BTreeMap<SmolStr, (EntityTypeName, BTreeMap<SmolStr, Relation>)>,
+    issues: Vec<ZanzibarImportIssue>,
+}
+
+impl Model {
+    fn parse(model: &str) -> Result<Self, ZanzibarParseError> {
+        let error = |line: usize, message: String| ZanzibarParseError {
+            input: ZanzibarInput::Model,
+            line,
+            message,
+        };
+        let mut parsed = Self::default();
+        let mut current: Option<SmolStr> = None;
+        let mut in_condition = false;
+        for (index, text) in model.lines().enumerate() {
+            let line = index + 1;
+            let text = text.trim();
+            if in_condition {
+                in_condition = !text.ends_with('}');
+                continue;
+            }
+            if text.is_empty() || text.starts_with('#') || text.starts_with("//") {
+                continue;
+            }
+            let (keyword, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+            let rest = rest.trim();
+            match keyword {
+                "model" | "schema" | "module" => {}
+                "type" => {
+                    let entity_type = EntityTypeName::from_str(rest).map_err(|_| {
+                        error(
+                            line,
+                            format!("`{rest}` is not a valid Cedar entity type name"),
+                        )
+                    })?;
+                    if parsed.types.contains_key(rest) {
+                        return Err(error(
+                            line,
+                            format!("type `{rest}` is defined more than once"),
+                        ));
+                    }
+                    parsed
+                        .types
+                        .insert(rest.into(), (entity_type, BTreeMap::new()));
+                    current = Some(rest.into());
+                }
+                "relations" if current.is_some() => {}
+                "define" => {
+                    let Some((_, relations)) =
+                        current.as_ref().and_then(|ty| parsed.types.get_mut(ty))
+                    else {
+                        return Err(error(line, "`define` outside of a type".into()));
+                    };
+                    let Some((name, rewrite)) = rest.split_once(':') else {
+                        return Err(error(line, "expected `define relation: ...`".into()));
+                    };
+                    let name = name.trim();
+                    let terms = match parse_rewrite(rewrite.trim()) {
+                        Ok(terms) => Some(terms),
+                        Err(Rewrite::Invalid(message)) => return Err(error(line, message)),
+                        Err(Rewrite::Unsupported(message)) => {
+                            parsed.issues.push(ZanzibarImportIssue {
+                                input: ZanzibarInput::Model,
+                                line: Some(line),
+                                message: format!("relation `{name}` {message}; it grants nothing"),
+                            });
+                            None
+                        }
+                    };
+                    if relations
+                        .insert(name.into(), Relation { line, terms })
+                        .is_some()
+                    {
+                        return Err(error(
+                            line,
+                            format!("relation `{name}` is defined more than once"),
+                        ));
+                    }
+                }
+                "condition" => {
+                    parsed.issues.push(ZanzibarImportIssue {
+                        input: ZanzibarInput::Model,
+                        line: Some(line),
+                        message: "conditions are not supported".into(),
+                    });
+                    in_condition = !text.ends_with('}');
+                }
+                _ => return Err(error(line, format!("unexpected `{keyword}`"))),
+            }
+        }
+        parsed
+            .check()
+            .map_err(|(line, message)| error(line, message))?;
+        Ok(parsed)
+    }
+
+    /// Check that the relations only refer to types and relations which are
+    /// defined
+    fn check(&self) -> Result<(), (usize, String)> {
+        for (relations_of, (_, relations)) in &self.types {
+            for relation in relations.values() {
+                let check_relation = |ty: &SmolStr, name: &SmolStr| match self.types.get(ty) {
+                    None => Err((relation.line, format!("type `{ty}` is not defined"))),
+                    Some((_, relations)) if !relations.contains_key(name) => Err((
+                        relation.line,
+                        format!("type `{ty}` has no relation `{name}`"),
+                    )),
+                    Some(_) => Ok(()),
+                };
+                for term in relation.terms.iter().flatten() {
+                    match term {
+                        Term::Direct(directs) => {
+                            for direct in directs {
+                                match direct {
+                                    Direct::Type(ty) | Direct::Wildcard(ty) => {
+                                        if !self.types.contains_key(ty) {
+                                            return Err((
+                                                relation.line,
+                                                format!("type `{ty}` is not defined"),
+                                            ));
+                                        }
+                                    }
+                                    Direct::Userset(ty, name) => check_relation(ty, name)?,
+                                }
+                            }
+                        }
+                        Term::Computed(name) => check_relation(relations_of, name)?,
+                        Term::FromTupleset { tupleset, .. } => {
+                            check_relation(relations_of, tupleset)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn relation(&self, ty: &str, name: &str) -> Option<&Relation> {
+        self.types
+            .get(ty)
+            .and_then(|(_, relations)| relations.get(name))
+    }
+}
+
+enum Rewrite {
+    Invalid(String),
+    Unsupported(String),
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Parse the definition of a relation, a union of terms separated by `or`
+fn parse_rewrite(rewrite: &str) -> Result<Vec<Term>, Rewrite> {
+    let words: Vec<&str> = rewrite.split_whitespace().collect();
+    if words.contains(&"and") {
+        return Err(Rewrite::Unsupported("is an intersection (`and`)".into()));
+    }
+    if words.windows(2).any(|pair| pair == ["but", "not"]) {
+        return Err(Rewrite::Unsupported("is an exclusion (`but not`)".into()));
+    }
+    if words.contains(&"with") {
+        return Err(Rewrite::Unsupported("has a condition (`with`)".into()));
+    }
+    if rewrite.contains('(') {
+        return Err(Rewrite::Unsupported("has nested rewrites".into()));
+    }
+    rewrite
+        .split(" or ")
+        .map(str::trim)
+        .map(parse_term)
+        .collect()
+}
+
+fn invalid(term: &str) -> Rewrite {
+    Rewrite::Invalid(format!("unexpected `{term}` in relation definition"))
+}
+
+/// Parse a term of a union: directly related types, a relation of the same
+/// object, or `relation from tupleset`
+fn parse_term(term: &str) -> Result<Term, Rewrite> {
+    let Some(directs) = term.strip_prefix('[').and_then(|t| t.strip_suffix(']')) else {
+        return match term.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [relation] if is_identifier(relation) => Ok(Term::Computed((*relation).into())),
+            [relation, "from", tupleset] if is_identifier(relation) && is_identifier(tupleset) => {
+                Ok(Term::FromTupleset {
+                    relation: (*relation).into(),
+                    tupleset: (*tupleset).into(),
+                })
+            }
+            _ => Err(invalid(term)),
+        };
+    };
+    directs
+        .split(',')
+        .map(str::trim)
+        .map(
+            |direct| match (direct.strip_suffix(":*"), direct.split_once('#')) {
+                (Some(ty), _) => Ok(Direct::Wildcard(ty.into())),
+                (None, Some((ty, relation))) => Ok(Direct::Userset(ty.into(), relation.into())),
+                (None, None) if is_identifier(direct) => Ok(Direct::Type(direct.into())),
+                _ => Err(invalid(direct)),
+            },
+        )
+        .collect::<Result<_, _>>()
+        .map(Term::Direct)
+}
+
+/// An object, by type and id
+type Object = (SmolStr, SmolStr);
+
+/// A tuple `object#relation@user`
+#[derive(Debug)]
+struct Tuple {
+    object: Object,
+    relation: SmolStr,
+    user: User,
+}
+
+#[derive(Debug)]
+enum User {
+    Object(Object),
+    Wildcard(SmolStr),
+    Userset(Object, SmolStr),
+}
+
+impl Tuple {
+    fn parse(tuple: &str) -> Option<Self> {
+        let object = |s: &str| {
+            s.split_once(':')
+                .filter(|(ty, id)| !ty.is_empty() && !id.is_empty())
+                .map(|(ty, id)| (SmolStr::from(ty), SmolStr::from(id)))
+        };
+        let (left, user) = tuple.split_once('@')?;
+        let (left_object, relation) = left.rsplit_once('#')?;
+        let user = match user.rsplit_once('#') {
+            Some((user, relation)) if !relation.is_empty() => {
+                User::Userset(object(user)?, relation.into())
+            }
+            Some(_) => return None,
+            None => match object(user)? {
+                (ty, id) if id == "*" => User::Wildcard(ty),
+                user => User::Object(user),
+            },
+        };
+        Some(Self {
+            object: object(left_object)?,
+            relation: (!relation.is_empty()).then(|| relation.into())?,
+            user,
+        })
+    }
+}
+
+/// A node of the membership graph
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Object(Object),
+    Relation(Object, SmolStr),
+}
+
+#[derive(Debug)]
+struct Importer<'a> {
+    relation_type: &'a EntityTypeName,
+    model: Model,
+    objects: BTreeSet<Object>,
+    /// The relations each node is a member of
+    parents: BTreeMap<Node, BTreeSet<Node>>,
+    /// The objects related to each object by each relation, for
+    /// `relation from tupleset`
+    related: BTreeMap<(Object, SmolStr), BTreeSet<Object>>,
+    /// Relations granted to all objects of a type
+    wildcards: Vec<(Object, SmolStr, SmolStr)>,
+    issues: Vec<ZanzibarImportIssue>,
+}
+
+impl Importer<'_> {
+    fn report(&mut self, line: usize, message: String) {
+        self.issues.push(ZanzibarImportIssue {
+            input: ZanzibarInput::Tuples,
+            line: Some(line),
+            message,
+        });
+    }
+
+    fn tuple(&mut self, line: usize, tuple: Tuple) {
+        let Tuple {
+            object,
+            relation,
+            user,
+        } = tuple;
+        let Some(definition) = self.model.relation(&object.0, &relation) else {
+            let message = if self.model.types.contains_key(&object.0) {
+                format!("type `{}` has no relation `{relation}`", object.0)
+            } else {
+                format!("type `{}` is not defined", object.0)
+            };
+            self.report(line, message);
+            return;
+        };
+        // Tuples for relations which can't be represented grant nothing, as
+        // reported for the model
+        let Some(terms) = &definition.terms else {
+            return;
+        };
+        let allowed = terms.iter().any(|term| {
+            let Term::Direct(directs) = term else {
+                return false;
+            };
+            directs.iter().any(|direct| match (direct, &user) {
+                (Direct::Type(ty), User::Object((user_ty, _)))
+                | (Direct::Wildcard(ty), User::Wildcard(user_ty)) => ty == user_ty,
+                (Direct::Userset(ty, name), User::Userset((user_ty, _), user_relation)) => {
+                    ty == user_ty && name == user_relation
+                }
+                _ => false,
+            })
+        });
+        if !allowed {
+            self.report(
+                line,
+                format!(
+                    "relation `{relation}` of type `{}` can't relate this kind of user",
+                    object.0
+                ),
+            );
+            return;
+        }
+        self.objects.insert(object.clone());
+        let group = Node::Relation(object.clone(), relation.clone());
+        match user {
+            User::Object(user) => {
+                self.objects.insert(user.clone());
+                self.related
+                    .entry((object, relation))
+                    .or_default()
+                    .insert(user.clone());
+                self.parents
+                    .entry(Node::Object(user))
+                    .or_default()
+                    .insert(group);
+            }
+            User::Userset(user, user_relation) => {
+                self.objects.insert(user.clone());
+                self.parents
+                    .entry(Node::Relation(user, user_relation))
+                    .or_default()
+                    .insert(group);
+            }
+            User::Wildcard(ty) => self.wildcards.push((object, relation, ty)),
+        }
+    }
+
+    fn entity_type(&self, ty: &str) -> EntityTypeName {
+        // Every object type is a type of the model, which was parsed as an
+        // entity type name
+        #[allow(clippy::unwrap_used)]
+        self.model.types.get(ty).map(|(ty, _)| ty.clone()).unwrap()
+    }
+
+    fn entity_uid(&self, node: &Node) -> EntityUid {
+        match node {
+            Node::Object((ty, id)) => {
+                EntityUid::from_type_name_and_id(self.entity_type(ty), EntityId::new(id))
+            }
+            Node::Relation((ty, id), relation) => EntityUid::from_type_name_and_id(
+                self.relation_type.clone(),
+                EntityId::new(format!("{ty}:{id}#{relation}")),
+            ),
+        }
+    }
+
+    /// Add the members of the relations each relation includes to it, and
+    /// return the relations of every object
+    fn include_relations(&mut self) -> Vec<Node> {
+        let mut relations = Vec::new();
+        for object in &self.objects {
+            let Some((_, definitions)) = self.model.types.get(&object.0) else {
+                continue;
+            };
+            for (name, definition) in definitions {
+                let Some(terms) = &definition.terms else {
+                    continue;
+                };
+                relations.push(Node::Relation(object.clone(), name.clone()));
+                for term in terms {
+                    let included: Vec<Node> = match term {
+                        Term::Direct(_) => Vec::new(),
+                        Term::Computed(included) => {
+                            vec![Node::Relation(object.clone(), included.clone())]
+                        }
+                        Term::FromTupleset { relation, tupleset } => self
+                            .related
+                            .get(&(object.clone(), tupleset.clone()))
+                            .into_iter()
+                            .flatten()
+                            .filter(|related| self.model.relation(&related.0, relation).is_some())
+                            .map(|related| Node::Relation(related.clone(), relation.clone()))
+                            .collect(),
+                    };
+                    for included in included {
+                        self.parents
+                            .entry(included)
+                            .or_default()
+                            .insert(Node::Relation(object.clone(), name.clone()));
+                    }
+                }
+            }
+        }
+        relations
+    }
+
+    fn report_unlocated(&mut self, message: String) {
+        self.issues.push(ZanzibarImportIssue {
+            input: ZanzibarInput::Tuples,
+            line: None,
+            message,
+        });
+    }
+
+    /// Link the template of each relation for the relation's object
+    fn link_relations(&mut self, policies: &mut PolicySet, relations: &[Node]) {
+        let mut templates = BTreeSet::new();
+        for relation in relations {
+            let Node::Relation(object, name) = relation else {
+                continue;
+            };
+            if templates.insert(name.clone()) {
+                let text = format!(
+                    "permit(principal in ?principal, action == Action::{}, resource == ?resource);",
+                    ast::Expr::val(name.as_str())
+                );
+                #[allow(clippy::expect_used)]
+                let template = Template::parse(Some(PolicyId::new(name)), text)
+                    .expect("template with a string literal action is valid");
+                if let Err(e) = policies.add_template(template) {
+                    self.report_unlocated(format!("failed to add the template for `{name}`: {e}"));
+                }
+            }
+            let vals = HashMap::from([
+                (SlotId::principal(), self.entity_uid(relation)),
+                (
+                    SlotId::resource(),
+                    self.entity_uid(&Node::Object(object.clone())),
+                ),
+            ]);
+            let id = PolicyId::new(format!("{}:{}#{name}", object.0, object.1));
+            if let Err(e) = policies.link(PolicyId::new(name), id, vals) {
+                self.report_unlocated(format!("failed to link the template for `{name}`: {e}"));
+            }
+        }
+    }
+
+    /// Add a policy for every relation implied by each wildcard tuple: all
+    /// objects of the type have the relation with the tuple's object, and
+    /// every relation including it
+    fn wildcard_policies(&mut self, policies: &mut PolicySet) {
+        for (object, relation, ty) in std::mem::take(&mut self.wildcards) {
+            let mut implied = BTreeSet::new();
+            let mut pending = vec![Node::Relation(object, relation.clone())];
+            while let Some(node) = pending.pop() {
+                if implied.insert(node.clone()) {
+                    pending.extend(self.parents.get(&node).into_iter().flatten().cloned());
+                }
+            }
+            for node in implied {
+                let Node::Relation(implied_object, implied_relation) = &node else {
+                    continue;
+                };
+                let text = format!(
+                    "permit(principal is {}, action == Action::{}, resource == {});",
+                    self.entity_type(&ty),
+                    ast::Expr::val(implied_relation.as_str()),
+                    self.entity_uid(&Node::Object(implied_object.clone()))
+                );
+                let id = format!(
+                    "{}:{}#{implied_relation}@{ty}:*",
+                    implied_object.0, implied_object.1
+                );
+                let added = Policy::parse(Some(PolicyId::new(&id)), text)
+                    .map_err(|e| e.to_string())
+                    .and_then(|policy| policies.add(policy).map_err(|e| e.to_string()));
+                if let Err(e) = added {
+                    self.report_unlocated(format!("failed to add the policy `{id}`: {e}"));
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> ZanzibarImport {
+        let mut policies = PolicySet::new();
+        let relations = self.include_relations();
+        self.link_relations(&mut policies, &relations);
+        self.wildcard_policies(&mut policies);
+        let nodes = self
+            .objects
+            .iter()
+            .cloned()
+            .map(Node::Object)
+            .chain(relations)
+            .chain(self.parents.keys().cloned())
+            .collect::<BTreeSet<_>>();
+        let entities = nodes.iter().map(|node| {
+            let parents: HashSet<EntityUid> = self
+                .parents
+                .get(node)
+                .into_iter()
+                .flatten()
+                .map(|parent| self.entity_uid(parent))
+                .collect();
+            Entity::new_no_attrs(self.entity_uid(node), parents)
+        });
+        let entities = Entities::from_entities(entities, None).unwrap_or_else(|e| {
+            self.report_unlocated(format!("failed to build the entity hierarchy: {e}"));
+            Entities::empty()
+        });
+        ZanzibarImport {
+            entities,
+            policies,
+            issues: self.issues,
+        }
+    }
+}
@@ -641,7 +641,7 @@ mod test {
             } else if err.policy_id == PolicyId::new("ID2") {
                 assert_error_matches(
                     &err.error,
-                    "for policy `ID2`, unable to find an applicable action given the policy scope constraints",
+                    "for policy `ID2` (linked from template `ID1` with ?principal = User::\"bob\"), unable to find an applicable action given the policy scope constraints",
                     None,
                 );
             } else {
@@ -673,7 +673,7 @@ mod test {
         assert_eq!(errs[0].policy_id, PolicyId::new("ID2"));
         assert_error_matches(
             &errs[0].error,
-            "for policy `ID2`, unable to find an applicable action given the policy scope constraints",
+            "for policy `ID2` (linked from template `ID1` with ?principal = Photo::\"bob\"), unable to find an applicable action given the policy scope constraints",
             None
         );
     }
@@ -1369,6 +1369,56 @@ mod ancestors_tests {
         assert!(ans.contains(&b_euid));
         assert!(ans.contains(&a_euid));
     }
+
+    #[test]
+    fn test_descendants_and_roots() {
+        let a_euid: EntityUid = EntityUid::from_strs("test", "A");
+        let b_euid: EntityUid = EntityUid::from_strs("test", "b");
+        let c_euid: EntityUid = EntityUid::from_strs("test", "C");
+        let d_euid: EntityUid = EntityUid::from_strs("test", "D");
+        let a = Entity::new_no_attrs(a_euid.clone(), HashSet::new());
+        let b = Entity::new_no_attrs(b_euid.clone(), std::iter::once(a_euid.clone()).collect());
+        let c = Entity::new_no_attrs(c_euid.clone(), std::iter::once(b_euid.clone()).collect());
+        let d = Entity::new_no_attrs(d_euid.clone(), HashSet::new());
+        let es = Entities::from_entities([a, b, c, d], None).unwrap();
+        assert_eq!(
+            es.descendants(&a_euid).collect::<HashSet<_>>(),
+            HashSet::from([&b_euid, &c_euid])
+        );
+        assert_eq!(
+            es.descendants(&b_euid).collect::<HashSet<_>>(),
+            HashSet::from([&c_euid])
+        );
+        assert_eq!(es.descendants(&c_euid).count(), 0);
+        assert_eq!(
+            es.roots().collect::<HashSet<_>>(),
+            HashSet::from([&a_euid, &d_euid])
+        );
+    }
+
+    #[test]
+    fn test_cycle_path() {
+        let a_euid: EntityUid = EntityUid::from_strs("test", "A");
+        let b_euid: EntityUid = EntityUid::from_strs("test", "B");
+        let c_euid: EntityUid = EntityUid::from_strs("test", "C");
+        let a = Entity::new_no_attrs(a_euid.clone(), std::iter::once(b_euid.clone()).collect());
+        let b = Entity::new_no_attrs(b_euid, std::iter::once(c_euid.clone()).collect());
+        let c = Entity::new_no_attrs(c_euid, std::iter::once(a_euid).collect());
+        let msg = match Entities::from_entities([a, b, c], None) {
+            Err(entities_errors::EntitiesError::TransitiveClosureError(err)) => err.to_string(),
+            res => panic!("expected a transitive closure error, got {res:?}"),
+        };
+        assert!(
+            [
+                r#"`test::"A"` -> `test::"B"` -> `test::"C"` -> `test::"A"`"#,
+                r#"`test::"B"` -> `test::"C"` -> `test::"A"` -> `test::"B"`"#,
+                r#"`test::"C"` -> `test::"A"` -> `test::"B"` -> `test::"C"`"#,
+            ]
+            .iter()
+            .any(|path| msg.contains(path)),
+            "unexpected message: {msg}"
+        );
+    }
 }
 
 /// A few tests of validating entities.
@@ -2389,7 +2439,7 @@ mod schema_based_parsing_tests {
                         "isFullTime": true,
                         "numDirectReports": 3,
                         "department": "Sales",
-                        "manager": "34FB87",
+                        "manager": 34,
                         "hr_contacts": [
                             { "type": "HR", "id": "aaaaa" },
                             { "type": "HR", "id": "bbbbb" }
@@ -2413,7 +2463,7 @@ mod schema_based_parsing_tests {
             "",
             &Report::new(err),
             &ExpectedErrorMessageBuilder::error("error during entity deserialization")
-                .source(r#"in attribute `manager` on `Employee::"12UA45"`, expected a literal entity reference, but got `"34FB87"`"#)
+                .source(r#"in attribute `manager` on `Employee::"12UA45"`, expected a literal entity reference, but got `34`"#)
                 .help(r#"literal entity references can be made with `{ "type": "SomeType", "id": "SomeId" }`"#)
                 .build()
         );
@@ -2875,7 +2925,7 @@ mod schema_based_parsing_tests {
                         "isFullTime": true,
                         "numDirectReports": 3,
                         "department": "Sales",
-                        "manager": "34FB87",
+                        "manager": 34,
                         "hr_contacts": [
                             { "type": "HR", "id": "aaaaa" },
                             { "type": "HR", "id": "bbbbb" }
@@ -2900,7 +2950,7 @@ mod schema_based_parsing_tests {
             "",
             &Report::new(err),
             &ExpectedErrorMessageBuilder::error("error during entity deserialization")
-                .source(r#"in attribute `manager` on `Employee::"12UA45"`, expected a literal entity reference, but got `"34FB87"`"#)
+                .source(r#"in attribute `manager` on `Employee::"12UA45"`, expected a literal entity reference, but got `34`"#)
                 .help(r#"literal entity references can be made with `{ "type": "SomeType", "id": "SomeId" }`"#)
                 .build()
         );
@@ -3832,13 +3882,13 @@ fn partial_schema_unsupported() {
     use cool_asserts::assert_matches;
     use serde_json::json;
     assert_matches!(
-        Schema::from_json_value( json!({"": { "entityTypes": { "A": { "shape": { "type": "Record", "attributes": {}, "additionalAttributes": true } } }, "actions": {} }})),
+        Schema::from_json_value( json!({"": { "entityTypes": { "A": { "shape": { "type": "Record", "attributes": { "r": { "type": "Record", "attributes": {}, "additionalAttributes": true } } } } }, "actions": {} }})),
         Err(e) =>
             expect_err(
                 "",
                 &Report::new(e),
                 &ExpectedErrorMessageBuilder::error("unsupported feature used in schema")
-                    .source("records and entities with `additionalAttributes` are experimental, but the experimental `partial-validate` feature is not enabled")
+                    .source("records with `additionalAttributes` are experimental, but the experimental `partial-validate` feature is not enabled")
                     .build(),
             )
     );
@@ -5554,11 +5604,9 @@ mod authorization_error_tests {
         let entities = Entities::from_json_str(e, None).expect("entity error");
 
         let mut pset = PolicySet::new();
-        let static_policy = Policy::parse(
-            Some(PolicyId::new("id0")),
-            "permit(principal,action,resource) when {principal.foo == 1};",
-        )
-        .expect("Failed to parse");
+        let src = "permit(principal,action,resource) when {principal.foo == 1};";
+        let static_policy =
+            Policy::parse(Some(PolicyId::new("id0")), src).expect("Failed to parse");
         pset.add(static_policy).expect("Failed to add");
 
         let response = authorizer.is_authorized(&request, &pset, &entities);
@@ -5567,9 +5615,11 @@ mod authorization_error_tests {
         let errs = response.diagnostics().errors().collect::<Vec<_>>();
         assert_eq!(errs.len(), 1);
         expect_err(
-            "",
+            src,
             &Report::new(errs[0].clone()),
             &ExpectedErrorMessageBuilder::error(r#"error while evaluating policy `id0`: `Principal::"p"` does not have the attribute `foo`"#)
+                .help(r#"`Principal::"p"` does not have any attributes"#)
+                .exactly_one_underline("principal.foo")
                 .build(),
         );
     }
@@ -6531,3 +6581,8307 @@ mod reserved_keywords_in_policies {
         }
     }
 }
+
+mod applicable_policies_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_str(
+            r#"
+            entity Group;
+            entity User in [Group];
+            entity Folder;
+            entity Photo in [Folder];
+            action view appliesTo { principal: User, resource: Photo };
+            action edit appliesTo { principal: User, resource: [Photo, Folder] };
+            action all;
+            action "delete" in [all] appliesTo { principal: User, resource: Folder };
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn applicable_ids(pset: &PolicySet, env: &RequestEnv) -> HashSet<String> {
+        Validator::new(schema())
+            .applicable_policies(pset, env)
+            .map(|p| p.id().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn scopes_and_schema() {
+        let pset = PolicySet::from_str(
+            r#"
+            @id("any") permit(principal, action, resource);
+            @id("view") permit(principal, action == Action::"view", resource);
+            @id("group") permit(principal in Group::"admins", action, resource in Folder::"f");
+            @id("is_user") forbid(principal is User, action, resource is Photo) when { false };
+            @id("is_group") forbid(principal is Group, action, resource);
+            @id("all") permit(principal, action in Action::"all", resource);
+            @id("photo") permit(principal, action, resource == Photo::"p");
+            "#,
+        )
+        .unwrap();
+        let view_photo = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            "Photo".parse().unwrap(),
+        );
+        assert_eq!(
+            applicable_ids(&pset, &view_photo),
+            HashSet::from(
+                ["policy0", "policy1", "policy2", "policy3", "policy6"].map(String::from)
+            )
+        );
+        let delete_folder = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"delete""#.parse().unwrap(),
+            "Folder".parse().unwrap(),
+        );
+        assert_eq!(
+            applicable_ids(&pset, &delete_folder),
+            HashSet::from(["policy0", "policy2", "policy5"].map(String::from))
+        );
+    }
+
+    #[test]
+    fn unknown_action_or_disallowed_types() {
+        let pset = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+        let unknown_action = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"frobnicate""#.parse().unwrap(),
+            "Photo".parse().unwrap(),
+        );
+        assert!(applicable_ids(&pset, &unknown_action).is_empty());
+        let wrong_resource = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            "Folder".parse().unwrap(),
+        );
+        assert!(applicable_ids(&pset, &wrong_resource).is_empty());
+    }
+
+    #[test]
+    fn linked_policies() {
+        let mut pset =
+            PolicySet::from_str("permit(principal == ?principal, action, resource in ?resource);")
+                .unwrap();
+        pset.link(
+            PolicyId::new("policy0"),
+            PolicyId::new("linked_folder"),
+            HashMap::from([
+                (SlotId::principal(), r#"User::"alice""#.parse().unwrap()),
+                (SlotId::resource(), r#"Folder::"f""#.parse().unwrap()),
+            ]),
+        )
+        .unwrap();
+        pset.link(
+            PolicyId::new("policy0"),
+            PolicyId::new("linked_photo"),
+            HashMap::from([
+                (SlotId::principal(), r#"User::"alice""#.parse().unwrap()),
+                (SlotId::resource(), r#"Photo::"p""#.parse().unwrap()),
+            ]),
+        )
+        .unwrap();
+        let delete_folder = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"delete""#.parse().unwrap(),
+            "Folder".parse().unwrap(),
+        );
+        assert_eq!(
+            applicable_ids(&pset, &delete_folder),
+            HashSet::from(["linked_folder".to_string()])
+        );
+    }
+}
+
+mod lazy_attr_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn alice_with_counted_groups(calls: Arc<AtomicUsize>) -> Entity {
+        Entity::with_uid(r#"User::"alice""#.parse().unwrap()).with_lazy_attr("groups", move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            RestrictedExpression::from_str(r#"["admins", "staff"]"#).unwrap()
+        })
+    }
+
+    fn authorize(pset: &str, entities: &Entities) -> Response {
+        let alice: EntityUid = r#"User::"alice""#.parse().unwrap();
+        let request = Request::new(
+            alice.clone(),
+            r#"Action::"view""#.parse().unwrap(),
+            alice,
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        Authorizer::new().is_authorized(&request, &PolicySet::from_str(pset).unwrap(), entities)
+    }
+
+    #[test]
+    fn not_computed_unless_read() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let entities =
+            Entities::from_entities([alice_with_counted_groups(Arc::clone(&calls))], None).unwrap();
+        let response = authorize(
+            "permit(principal, action, resource) when { principal has groups };",
+            &entities,
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn computed_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let entities =
+            Entities::from_entities([alice_with_counted_groups(Arc::clone(&calls))], None).unwrap();
+        let pset = r#"
+            permit(principal, action, resource) when { principal.groups.contains("admins") };
+            permit(principal, action, resource) when { principal.groups.contains("staff") };
+        "#;
+        let copy = entities.clone();
+        let response = authorize(pset, &entities);
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.diagnostics().reason().count(), 2);
+        let response = authorize(pset, &copy);
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn error_surfaces_at_evaluation() {
+        let alice = Entity::with_uid(r#"User::"alice""#.parse().unwrap())
+            .with_lazy_attr("ip", || {
+                RestrictedExpression::from_str(r#"ip("bogus")"#).unwrap()
+            });
+        let entities = Entities::from_entities([alice], None).unwrap();
+        let response = authorize(
+            "permit(principal, action, resource) when { principal.ip.isLoopback() };",
+            &entities,
+        );
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+    }
+
+    #[test]
+    fn error_surfaces_when_all_attributes_are_needed() {
+        fn report(err: impl miette::Diagnostic + Send + Sync + 'static) -> String {
+            format!("{:?}", miette::Report::new(err))
+        }
+        let alice = Entity::with_uid(r#"User::"alice""#.parse().unwrap())
+            .with_lazy_attr("ip", || {
+                RestrictedExpression::from_str(r#"ip("bogus")"#).unwrap()
+            });
+        let expected = "invalid IP address: bogus";
+
+        let err = report(alice.to_json_value().unwrap_err());
+        assert!(err.contains(expected), "{err}");
+        let err = report(alice.to_cedar().unwrap_err());
+        assert!(err.contains(expected), "{err}");
+
+        let schema = Schema::from_str(
+            "entity User = { ip: ipaddr }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let err = report(Entities::from_entities([alice.clone()], Some(&schema)).unwrap_err());
+        assert!(err.contains(expected), "{err}");
+        assert!(!err.contains("missing"), "{err}");
+
+        assert_matches!(alice.clone().try_into_inner(), Err(err) => {
+            let err = report(err);
+            assert!(err.contains(expected), "{err}");
+        });
+        let (_, attrs, _) = alice.into_inner();
+        assert!(attrs.is_empty());
+    }
+}
+
+mod context_provider_tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Provides `time` and `risk`, recording which attributes it was asked for
+    #[derive(Default)]
+    struct Recording {
+        resolved: Mutex<Vec<String>>,
+    }
+
+    impl ContextProvider for Arc<Recording> {
+        fn attributes(&self) -> Vec<String> {
+            vec!["time".to_string(), "risk".to_string()]
+        }
+
+        fn resolve(&self, name: &str, _request: &Request) -> Option<RestrictedExpression> {
+            self.resolved.lock().unwrap().push(name.to_string());
+            match name {
+                "time" => Some(RestrictedExpression::new_long(1200)),
+                "risk" => Some(RestrictedExpression::new_long(7)),
+                _ => None,
+            }
+        }
+    }
+
+    fn request(context: Context) -> Request {
+        let euid: EntityUid = r#"User::"alice""#.parse().unwrap();
+        Request::new(euid.clone(), euid.clone(), euid, context, None).unwrap()
+    }
+
+    #[test]
+    fn resolves_only_attributes_read() {
+        let provider = Arc::new(Recording::default());
+        let authorizer = Authorizer::new().with_context_provider(Arc::clone(&provider));
+        let pset =
+            PolicySet::from_str("permit(principal, action, resource) when { context.time > 900 };")
+                .unwrap();
+        let response =
+            authorizer.is_authorized(&request(Context::empty()), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(*provider.resolved.lock().unwrap(), vec!["time".to_string()]);
+    }
+
+    #[test]
+    fn caller_context_takes_precedence() {
+        let provider = Arc::new(Recording::default());
+        let authorizer = Authorizer::new().with_context_provider(Arc::clone(&provider));
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { context.time > 900 && context.risk < 10 };",
+        )
+        .unwrap();
+        let context =
+            Context::from_pairs([("time".to_string(), RestrictedExpression::new_long(100))])
+                .unwrap();
+        let response = authorizer.is_authorized(&request(context), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(*provider.resolved.lock().unwrap(), vec!["risk".to_string()]);
+    }
+
+    #[test]
+    fn whole_context_use_resolves_everything() {
+        let provider = Arc::new(Recording::default());
+        let authorizer = Authorizer::new().with_context_provider(Arc::clone(&provider));
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { context == { "time": 1200, "risk": 7 } };"#,
+        )
+        .unwrap();
+        let response =
+            authorizer.is_authorized(&request(Context::empty()), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(provider.resolved.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn no_providers() {
+        let pset =
+            PolicySet::from_str("permit(principal, action, resource) when { context.time > 900 };")
+                .unwrap();
+        let response =
+            Authorizer::new().is_authorized(&request(Context::empty()), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+    }
+
+    #[test]
+    fn attributes_read_follow_policy_changes() {
+        let provider = Arc::new(Recording::default());
+        let authorizer = Authorizer::new().with_context_provider(Arc::clone(&provider));
+        let mut pset =
+            PolicySet::from_str("permit(principal, action, resource) when { context.time > 900 };")
+                .unwrap();
+        authorizer.is_authorized(&request(Context::empty()), &pset, &Entities::empty());
+        assert_eq!(*provider.resolved.lock().unwrap(), vec!["time".to_string()]);
+
+        pset.add(
+            Policy::parse(
+                Some(PolicyId::new("risky")),
+                "forbid(principal, action, resource) when { context.risk > 5 };",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        provider.resolved.lock().unwrap().clear();
+        let response =
+            authorizer.is_authorized(&request(Context::empty()), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(provider.resolved.lock().unwrap().len(), 2);
+    }
+
+    /// Provides `addr` and `risk`, with values that are invalid for the schema
+    /// used below
+    struct Invalid;
+
+    impl ContextProvider for Invalid {
+        fn attributes(&self) -> Vec<String> {
+            vec!["addr".to_string(), "risk".to_string()]
+        }
+
+        fn resolve(&self, name: &str, _request: &Request) -> Option<RestrictedExpression> {
+            match name {
+                "addr" => Some(RestrictedExpression::new_ip("bogus")),
+                "risk" => Some(RestrictedExpression::new_string("high".to_string())),
+                _ => None,
+            }
+        }
+    }
+
+    /// The context attribute named in the error of each erroring policy, with
+    /// whether the provided value failed to evaluate
+    fn provider_errors(response: &Response) -> Vec<(String, String, bool)> {
+        let mut errors: Vec<_> = response
+            .diagnostics()
+            .errors()
+            .map(|AuthorizationError::PolicyEvaluationError(e)| {
+                assert_eq!(e.category(), ErrorCategory::ContextProvider);
+                let EvaluationError::ContextProvider(error) = e.inner() else {
+                    panic!("expected a context provider error, got {e}");
+                };
+                (
+                    e.policy_id().to_string(),
+                    error.attr().to_string(),
+                    error.evaluation_error().is_some(),
+                )
+            })
+            .collect();
+        errors.sort();
+        errors
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { context.addr.isIpv4() };
+            forbid(principal, action, resource) when { context has risk && context.risk == "high" };
+            permit(principal, action, resource) when { context == {} };
+            permit(principal, action, resource) when { principal == User::"bob" };
+            "#,
+        )
+        .unwrap();
+        let (schema, _) = Schema::from_cedarschema_str(
+            r#"
+            entity User;
+            action view appliesTo {
+                principal: User,
+                resource: User,
+                context: { addr?: ipaddr, risk?: Long },
+            };
+            "#,
+        )
+        .unwrap();
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"User::"alice""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+
+        // Without a schema, only the value which fails to evaluate is invalid
+        let response = Authorizer::new()
+            .with_context_provider(Invalid)
+            .is_authorized(&request, &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(
+            provider_errors(&response),
+            vec![
+                ("policy0".to_string(), "addr".to_string(), true),
+                ("policy2".to_string(), "addr".to_string(), true),
+            ]
+        );
+
+        let response = Authorizer::new()
+            .with_context_provider(Invalid)
+            .with_context_schema(&schema)
+            .is_authorized(&request, &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(
+            provider_errors(&response),
+            vec![
+                ("policy0".to_string(), "addr".to_string(), true),
+                ("policy1".to_string(), "risk".to_string(), false),
+                ("policy2".to_string(), "addr".to_string(), true),
+            ]
+        );
+    }
+}
+
+mod decision_record_tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn records_each_decision() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        let authorizer = Authorizer::new()
+            .with_policy_set_version("v7")
+            .with_decision_logger(move |record: &DecisionRecord| {
+                sink.lock().unwrap().push(record.clone());
+            });
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource);
+            permit(principal, action == Action::"view", resource);
+            forbid(principal, action, resource) when { context.missing };
+            "#,
+        )
+        .unwrap();
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"p""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let before = SystemTime::now();
+        let timer = Instant::now();
+        let response = authorizer.is_authorized(&request, &pset, &Entities::empty());
+        let elapsed = timer.elapsed();
+        let after = SystemTime::now();
+        assert_eq!(response.decision(), Decision::Allow);
+
+        let records = std::mem::take(&mut *records.lock().unwrap());
+        assert_eq!(records.len(), 1);
+        let record = records.first().unwrap();
+        assert_eq!(record.principal(), Some(r#"User::"alice""#));
+        assert_eq!(record.action(), Some(r#"Action::"view""#));
+        assert_eq!(record.resource(), Some(r#"Photo::"p""#));
+        assert_eq!(record.decision(), Decision::Allow);
+        assert_eq!(
+            record.determining_policies().collect::<Vec<_>>(),
+            vec!["policy0", "policy1"]
+        );
+        assert_eq!(record.errors().count(), 1);
+        let millis = |t: SystemTime| t.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        assert!(millis(before) <= u128::from(record.timestamp_millis()));
+        assert!(u128::from(record.timestamp_millis()) <= millis(after));
+        assert!(u128::from(record.duration_micros()) <= elapsed.as_micros());
+        assert_eq!(record.policy_set_version(), Some("v7"));
+
+        let json = serde_json::to_value(record).unwrap();
+        assert_eq!(json.get("decision"), Some(&json!("allow")));
+        assert_eq!(json.get("policySetVersion"), Some(&json!("v7")));
+        let roundtripped: DecisionRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(&roundtripped, record);
+    }
+}
+
+mod redaction_tests {
+    use super::*;
+    use miette::Diagnostic;
+
+    /// The source code labeled by `diag`
+    fn snippet(diag: &dyn Diagnostic) -> String {
+        let label = diag.labels().unwrap().next().unwrap();
+        let contents = diag
+            .source_code()
+            .unwrap()
+            .read_span(label.inner(), 0, 0)
+            .unwrap();
+        String::from_utf8_lossy(contents.data()).into_owned()
+    }
+
+    fn request() -> Request {
+        Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"vacation""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { principal.ssn == "123-45-6789" };
+            permit(principal, action, resource) when { ip("10.0.0.300").isLoopback() };
+            "#,
+        )
+        .unwrap();
+        let errors = |authorizer: Authorizer| {
+            authorizer
+                .is_authorized(&request(), &pset, &Entities::empty())
+                .diagnostics()
+                .errors()
+                .map(|e| match e {
+                    AuthorizationError::PolicyEvaluationError(e) => {
+                        format!("{} / {}", e.inner(), snippet(e.inner()))
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let plain = errors(Authorizer::new()).join("\n");
+        assert!(plain.contains("alice"), "{plain}");
+        assert!(plain.contains("10.0.0.300"), "{plain}");
+
+        let redacted = errors(Authorizer::new().with_redacted_errors());
+        let joined = redacted.join("\n");
+        for secret in ["alice", "123-45-6789", "10.0.0.300"] {
+            assert!(!joined.contains(secret), "{joined}");
+        }
+        assert!(
+            joined.contains(r#"entity `User::"<redacted>"` does not exist"#),
+            "{joined}"
+        );
+        assert!(
+            joined.contains("`ipaddr` extension function: <redacted>"),
+            "{joined}"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ipaddr")]
+    fn validation_diagnostics() {
+        let schema = Schema::from_str(
+            "
+            entity User;
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { ip("10.0.0.300").isLoopback() };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        let err = result.validation_errors().next().unwrap();
+        assert!(err.to_string().contains("10.0.0.300"));
+
+        let redacted = result.redacted();
+        let err = redacted.validation_errors().next().unwrap();
+        assert!(!err.to_string().contains("10.0.0.300"), "{err}");
+        assert!(err.to_string().contains("<redacted>"), "{err}");
+        let snippet = snippet(err);
+        assert!(snippet.contains(r#"ip("**********")"#), "{snippet}");
+    }
+}
+
+mod sensitive_attribute_tests {
+    use super::*;
+
+    #[test]
+    fn pii_attributes_are_reported() {
+        let schema = Schema::from_str(
+            "
+            entity User {
+                @pii
+                ssn: String,
+                department: String,
+            };
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { principal.department == "hr" };
+            forbid(principal, action, resource) when { principal.ssn == "123-45-6789" };
+            "#,
+        )
+        .unwrap();
+
+        let validator = Validator::new(schema);
+        let result = validator.validate(&pset, ValidationMode::Strict);
+        let warnings = result
+            .validation_warnings()
+            .map(|w| match w {
+                ValidationWarning::SensitiveAttributeUse(w) => (
+                    w.policy_id().to_string(),
+                    w.attribute().to_string(),
+                    w.to_string(),
+                ),
+                w => panic!("unexpected warning: {w}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![(
+                "policy1".to_string(),
+                "ssn".to_string(),
+                "for policy `policy1`, sensitive attribute `ssn` of `User` is compared with a string literal".to_string()
+            )]
+        );
+
+        let validator = validator.with_sensitive_attribute_rules(SensitiveAttributeRules {
+            forbid_in_permit: true,
+            forbid_literal_comparison: false,
+        });
+        let result = validator.validate(&pset, ValidationMode::Strict);
+        assert_eq!(result.validation_warnings().count(), 0);
+    }
+}
+
+mod metrics_tests {
+    use super::*;
+
+    #[test]
+    fn policy_and_policy_set_metrics() {
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { principal.department == "hr" };
+            forbid(principal, action, resource) unless { context.ip in [1, 2, 3, 4] };
+            "#,
+        )
+        .unwrap();
+        let policy = pset.policy(&PolicyId::new("policy1")).unwrap();
+        let metrics = policy.metrics();
+        assert_eq!(metrics.attribute_dereferences(), 1);
+        assert_eq!(metrics.set_literal_sizes(), &[4]);
+        assert_eq!(metrics.depth(), 4);
+
+        let metrics = pset.metrics();
+        assert_eq!(metrics.policy_count(), 2);
+        assert_eq!(metrics.total_attribute_dereferences(), 2);
+        assert_eq!(metrics.largest_set_literal(), 4);
+        assert_eq!(metrics.max_depth(), 4);
+        assert_eq!(
+            serde_json::to_value(&metrics)
+                .unwrap()
+                .get("largestSetLiteral"),
+            Some(&serde_json::json!(4))
+        );
+    }
+}
+
+mod resource_limits_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use itertools::Itertools;
+    use miette::Diagnostic;
+
+    #[test]
+    fn parser_limits() {
+        let src = "
+            permit(principal, action, resource) when { [1, 2, 3].contains(1) };
+            permit(principal, action, resource) when { ((((1 + 1) + 1) + 1) + 1) == 5 };
+        ";
+        assert!(PolicySet::from_str_with_limits(src, &ResourceLimits::new()).is_ok());
+
+        let limit_errors = |limits: ResourceLimits| {
+            PolicySet::from_str_with_limits(src, &limits)
+                .unwrap_err()
+                .iter()
+                .map(|e| e.limit_error().unwrap().clone())
+                .collect::<Vec<_>>()
+        };
+        assert_matches!(
+            limit_errors(ResourceLimits::new().with_max_policy_text_size(10)).as_slice(),
+            [LimitError::PolicyTextTooLarge { limit: 10, .. }]
+        );
+        assert_matches!(
+            limit_errors(ResourceLimits::new().with_max_set_literal_size(2)).as_slice(),
+            [LimitError::SetLiteralTooLarge {
+                size: 3,
+                limit: 2,
+                ..
+            }]
+        );
+        assert_matches!(
+            limit_errors(ResourceLimits::new().with_max_expression_depth(4)).as_slice(),
+            [LimitError::ExpressionTooDeep { limit: 4, .. }]
+        );
+    }
+
+    #[test]
+    fn parse_without_source() {
+        let src = "permit(principal, action, resource) when { principal.nickname == \"al\" };";
+        let pset = PolicySet::from_str_without_source(src, &ResourceLimits::new()).unwrap();
+        assert_eq!(pset, PolicySet::from_str(src).unwrap());
+        assert_eq!(pset.to_string(), src);
+
+        let (schema, _) = Schema::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert_matches!(result.validation_errors().collect_vec().as_slice(), [error] => {
+            assert_matches!(error.labels().unwrap().collect_vec().as_slice(), [label] => {
+                assert_eq!(label.inner(), &miette::SourceSpan::from(43..61));
+            });
+            assert!(error.source_code().is_none());
+        });
+
+        let errs =
+            PolicySet::from_str_without_source("permit(", &ResourceLimits::new()).unwrap_err();
+        assert!(errs.source_code().is_some());
+    }
+
+    #[test]
+    fn entity_limit() {
+        let pset = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"vacation""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let entities = Entities::from_entities(
+            [
+                Entity::new_no_attrs(r#"User::"alice""#.parse().unwrap(), HashSet::new()),
+                Entity::new_no_attrs(r#"Photo::"vacation""#.parse().unwrap(), HashSet::new()),
+            ],
+            None,
+        )
+        .unwrap();
+
+        let authorizer =
+            Authorizer::new().with_limits(ResourceLimits::new().with_max_entity_store_size(2));
+        let response = authorizer.is_authorized(&request, &pset, &entities);
+        assert_eq!(response.decision(), Decision::Allow);
+
+        let authorizer =
+            Authorizer::new().with_limits(ResourceLimits::new().with_max_entity_store_size(1));
+        let response = authorizer.is_authorized(&request, &pset, &entities);
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_matches!(
+            response.diagnostics().errors().collect::<Vec<_>>().as_slice(),
+            [AuthorizationError::PolicyEvaluationError(e)] => {
+                assert_matches!(
+                    e.inner(),
+                    EvaluationError::LimitExceeded(LimitError::EntityStoreTooLarge { size: 2, limit: 1 })
+                );
+            }
+        );
+    }
+}
+
+mod cancellation_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::time::{Duration, Instant};
+
+    fn request() -> Request {
+        Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"vacation""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn assert_cancelled(response: &Response, reason: CancellationReason) {
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_matches!(
+            response.diagnostics().errors().collect::<Vec<_>>().as_slice(),
+            [AuthorizationError::PolicyEvaluationError(e)] => {
+                assert_matches!(e.inner(), EvaluationError::Cancelled(e) => {
+                    assert_eq!(e.reason(), reason);
+                });
+            }
+        );
+    }
+
+    #[test]
+    fn token_and_deadline() {
+        let pset = PolicySet::from_str(
+            "
+            permit(principal, action, resource);
+            forbid(principal, action, resource) when { context has blocked };
+            ",
+        )
+        .unwrap();
+        let authorizer = Authorizer::new();
+
+        let token = CancellationToken::new();
+        let cancellation = Cancellation::new()
+            .with_token(token.clone())
+            .with_timeout(Duration::from_secs(3600));
+        let response = authorizer.is_authorized_with_cancellation(
+            &request(),
+            &pset,
+            &Entities::empty(),
+            &cancellation,
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+
+        token.cancel();
+        let response = authorizer.is_authorized_with_cancellation(
+            &request(),
+            &pset,
+            &Entities::empty(),
+            &cancellation,
+        );
+        assert_cancelled(&response, CancellationReason::Cancelled);
+
+        let cancellation = Cancellation::new().with_deadline(Instant::now());
+        let response = authorizer.is_authorized_with_cancellation(
+            &request(),
+            &pset,
+            &Entities::empty(),
+            &cancellation,
+        );
+        assert_cancelled(&response, CancellationReason::DeadlineExceeded);
+    }
+}
+
+mod policy_set_sharing_tests {
+    use super::*;
+
+    #[test]
+    fn clones_are_copy_on_write() {
+        let original = PolicySet::from_str(
+            "
+            permit(principal, action, resource);
+            permit(principal == ?principal, action, resource);
+            ",
+        )
+        .unwrap();
+        let snapshot = original.clone();
+        assert_eq!(snapshot, original);
+
+        let mut modified = original.clone();
+        modified
+            .add(
+                Policy::parse(
+                    Some(PolicyId::new("extra")),
+                    "forbid(principal, action, resource);",
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        modified
+            .link(
+                PolicyId::new("policy1"),
+                PolicyId::new("link"),
+                HashMap::from([(SlotId::principal(), r#"User::"alice""#.parse().unwrap())]),
+            )
+            .unwrap();
+        modified.remove_static(PolicyId::new("policy0")).unwrap();
+
+        assert_eq!(modified.num_of_policies(), 2);
+        assert_eq!(original.num_of_policies(), 1);
+        assert_eq!(snapshot, original);
+        assert!(original.policy(&PolicyId::new("policy0")).is_some());
+        assert!(original.policy(&PolicyId::new("extra")).is_none());
+    }
+
+    #[test]
+    fn snapshots_are_shareable_across_threads() {
+        let pset = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+        let handles = (0..4)
+            .map(|_| {
+                let snapshot = pset.clone();
+                std::thread::spawn(move || snapshot.num_of_policies())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 1);
+        }
+    }
+}
+
+#[cfg(feature = "serde-validation")]
+mod validation_result_serde_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use miette::Diagnostic;
+
+    #[test]
+    fn round_trip() {
+        let schema = Schema::from_str(
+            "
+            entity User { name: String };
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { principal.age > 3 };
+            permit(principal, action, resource) when { principal.name == "ｐaypal" };
+            "#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(!result.validation_passed());
+
+        let json = serde_json::to_value(&result).unwrap();
+        let parsed: ValidationResult = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+
+        let summarize = |result: &ValidationResult| {
+            let errors = result
+                .validation_errors()
+                .map(|e| {
+                    let labels = e
+                        .labels()
+                        .map(|ls| ls.map(|l| (l.offset(), l.len())).collect::<Vec<_>>());
+                    (e.policy_id().clone(), e.to_string(), labels)
+                })
+                .collect::<Vec<_>>();
+            let warnings = result
+                .validation_warnings()
+                .map(|w| (w.policy_id().clone(), w.to_string()))
+                .collect::<Vec<_>>();
+            (errors, warnings)
+        };
+        assert_eq!(summarize(&parsed), summarize(&result));
+        assert_matches!(
+            parsed.validation_errors().next(),
+            Some(ValidationError::UnsafeAttributeAccess(_))
+        );
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::indexing_slicing)]
+mod schema_introspection_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn types_attributes_and_actions() {
+        let schema = Schema::from_str(
+            "
+            namespace App {
+                type Address = { street: String, zip?: Long };
+                entity User { address: Address, roles: Set<String>, manager?: User } tags ipaddr;
+                entity Doc;
+                action view appliesTo {
+                    principal: User,
+                    resource: Doc,
+                    context: { mfa: Bool, ip?: ipaddr },
+                };
+            }
+            ",
+        )
+        .unwrap();
+        let address = SchemaType::Record {
+            attributes: BTreeMap::from([
+                (
+                    "street".to_string(),
+                    SchemaAttribute::new(SchemaType::String, true),
+                ),
+                (
+                    "zip".to_string(),
+                    SchemaAttribute::new(SchemaType::Long, false),
+                ),
+            ]),
+            additional_attributes: false,
+        };
+
+        let user: EntityTypeName = "App::User".parse().unwrap();
+        let attrs = schema.entity_type_attributes(&user).unwrap();
+        assert_eq!(
+            attrs.keys().collect::<Vec<_>>(),
+            vec!["address", "manager", "roles"]
+        );
+        assert_eq!(attrs["address"].ty(), &address);
+        assert!(attrs["address"].is_required());
+        assert_eq!(
+            attrs["roles"].ty(),
+            &SchemaType::Set(Box::new(SchemaType::String))
+        );
+        assert_eq!(attrs["manager"].ty(), &SchemaType::Entity(user.clone()));
+        assert!(!attrs["manager"].is_required());
+        assert_eq!(
+            schema.entity_type_tags(&user),
+            Some(SchemaType::Extension("ipaddr".to_string()))
+        );
+
+        let doc: EntityTypeName = "App::Doc".parse().unwrap();
+        assert_eq!(schema.entity_type_attributes(&doc), Some(BTreeMap::new()));
+        assert_eq!(schema.entity_type_tags(&doc), None);
+        assert_eq!(
+            schema.entity_type_attributes(&"App::Missing".parse().unwrap()),
+            None
+        );
+
+        let view: EntityUid = r#"App::Action::"view""#.parse().unwrap();
+        assert_matches!(
+            schema.action_context(&view),
+            Some(SchemaType::Record { attributes, additional_attributes: false }) => {
+                assert_eq!(attributes["mfa"].ty(), &SchemaType::Bool);
+                assert_eq!(attributes["ip"].ty(), &SchemaType::Extension("ipaddr".to_string()));
+            }
+        );
+        assert_eq!(
+            schema.action_context(&r#"App::Action::"edit""#.parse().unwrap()),
+            None
+        );
+
+        assert_eq!(
+            schema.common_types().collect::<Vec<_>>(),
+            vec![("App::Address".to_string(), address)]
+        );
+    }
+}
+
+mod action_reachability_tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn unused_actions_are_flagged() {
+        let schema = Schema::from_str(
+            "
+            entity Team;
+            entity User in [Team];
+            entity Doc;
+            entity Device;
+            action view appliesTo { principal: User, resource: Doc };
+            action reboot appliesTo { principal: User, resource: Device };
+            ",
+        )
+        .unwrap();
+        let pset =
+            PolicySet::from_str(r#"permit(principal in Team::"admins", action, resource is Doc);"#)
+                .unwrap();
+        let reachability = Validator::new(schema).action_reachability(&pset);
+        let summary = reachability
+            .iter()
+            .map(|r| {
+                (
+                    r.action().to_string(),
+                    r.principal_types().map(ToString::to_string).collect_vec(),
+                    r.resource_types().map(ToString::to_string).collect_vec(),
+                    r.has_applicable_policy(),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    r#"Action::"reboot""#.to_string(),
+                    vec!["Team".to_string(), "User".to_string()],
+                    vec!["Device".to_string()],
+                    false
+                ),
+                (
+                    r#"Action::"view""#.to_string(),
+                    vec!["Team".to_string(), "User".to_string()],
+                    vec!["Doc".to_string()],
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unpermitted_actions() {
+        let schema = Schema::from_str(
+            "
+            entity User;
+            entity Doc;
+            action view, edit, delete appliesTo { principal: User, resource: Doc };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action == Action::"view", resource);
+            forbid(principal, action == Action::"edit", resource);
+            permit(principal, action == Action::"delete", resource is User);
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            Validator::new(schema)
+                .unpermitted_actions(&pset)
+                .iter()
+                .map(ToString::to_string)
+                .collect_vec(),
+            vec![r#"Action::"delete""#, r#"Action::"edit""#]
+        );
+    }
+}
+
+mod action_hierarchy_tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn names<'a>(euids: impl Iterator<Item = &'a EntityUid>) -> BTreeSet<String> {
+        euids.map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn transitive_membership() {
+        let schema = Schema::from_str(
+            r#"
+            namespace Admin {
+                action manage;
+            }
+            action read;
+            action write in [read, Admin::Action::"manage"];
+            action delete in write;
+            "#,
+        )
+        .unwrap();
+        let euid = |s: &str| EntityUid::from_str(s).unwrap();
+
+        assert_eq!(
+            schema
+                .action_ancestors(&euid(r#"Action::"delete""#))
+                .map(names),
+            Some(BTreeSet::from([
+                r#"Action::"read""#.to_string(),
+                r#"Action::"write""#.to_string(),
+                r#"Admin::Action::"manage""#.to_string(),
+            ]))
+        );
+        assert_eq!(
+            schema
+                .action_ancestors(&euid(r#"Action::"read""#))
+                .map(names),
+            Some(BTreeSet::new())
+        );
+        assert_eq!(
+            schema
+                .action_descendants(&euid(r#"Admin::Action::"manage""#))
+                .map(names),
+            Some(BTreeSet::from([
+                r#"Action::"delete""#.to_string(),
+                r#"Action::"write""#.to_string(),
+            ]))
+        );
+        assert_eq!(
+            schema
+                .action_ancestors(&euid(r#"Action::"missing""#))
+                .map(names),
+            None
+        );
+        assert_eq!(
+            schema
+                .action_descendants(&euid(r#"Action::"missing""#))
+                .map(names),
+            None
+        );
+    }
+
+    #[test]
+    fn errors_name_the_offending_edge() {
+        let err = Schema::from_str(
+            r#"
+            namespace Admin {
+                action manage;
+            }
+            action write in [Admin::Action::"mange"];
+            "#,
+        )
+        .unwrap_err();
+        expect_err(
+            "",
+            &Report::new(err),
+            &ExpectedErrorMessageBuilder::error(r#"undeclared action: Admin::Action::"mange""#)
+                .help(r#"`Action::"write"` is declared as a member of `Admin::Action::"mange"`; any actions appearing as parents need to be declared as actions"#)
+                .build(),
+        );
+
+        let err = Schema::from_str("action a in a;").unwrap_err();
+        expect_err(
+            "",
+            &Report::new(err),
+            &ExpectedErrorMessageBuilder::error(
+                r#"cycle in action hierarchy containing `Action::"a"`"#,
+            )
+            .help(r#"`Action::"a"` is a member of `Action::"a"`"#)
+            .build(),
+        );
+    }
+}
+
+mod deprecation_warning_tests {
+    use super::*;
+
+    #[test]
+    fn deprecated_action_names_replacement() {
+        let schema = Schema::from_str(
+            r#"
+            entity User;
+            @deprecated("Action::\"read\"")
+            action view appliesTo { principal: User, resource: User };
+            action read appliesTo { principal: User, resource: User };
+            "#,
+        )
+        .unwrap();
+        let src = r#"permit(principal, action == Action::"view", resource);"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+        let warning = result
+            .validation_warnings()
+            .find_map(|w| match w {
+                ValidationWarning::DeprecatedSchemaElementUse(w) => Some(w),
+                _ => None,
+            })
+            .expect("expected a deprecation warning");
+        assert_eq!(warning.replacement(), Some(r#"Action::"read""#));
+        expect_err(
+            src,
+            &Report::new(warning.clone()),
+            &ExpectedErrorMessageBuilder::error(
+                r#"for policy `policy0`, action `Action::"view"` is deprecated"#,
+            )
+            .help(r#"use `Action::"read"` instead"#)
+            .exactly_one_underline(src)
+            .build(),
+        );
+    }
+}
+
+mod open_entity_type_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_json_value(json!({"": {
+            "entityTypes": {
+                "User": {
+                    "shape": {
+                        "type": "Record",
+                        "attributes": { "name": { "type": "String" } },
+                        "additionalAttributes": true
+                    }
+                },
+                "Doc": {
+                    "shape": {
+                        "type": "Record",
+                        "attributes": { "owner": { "type": "Entity", "name": "User" } }
+                    }
+                }
+            },
+            "actions": {
+                "view": { "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["Doc"] } }
+            }
+        }}))
+        .unwrap()
+    }
+
+    #[test]
+    fn entity_parser_respects_open_shape() {
+        let schema = schema();
+        Entities::from_json_value(
+            json!([{
+                "uid": { "type": "User", "id": "alice" },
+                "attrs": { "name": "Alice", "nickname": "al" },
+                "parents": []
+            }]),
+            Some(&schema),
+        )
+        .unwrap();
+        let err = Entities::from_json_value(
+            json!([{
+                "uid": { "type": "Doc", "id": "d" },
+                "attrs": { "owner": { "__entity": { "type": "User", "id": "alice" } }, "title": "x" },
+                "parents": []
+            }]),
+            Some(&schema),
+        )
+        .unwrap_err();
+        expect_err(
+            "",
+            &Report::new(err),
+            &ExpectedErrorMessageBuilder::error("error during entity deserialization")
+                .source(
+                    r#"attribute `title` on `Doc::"d"` should not exist according to the schema"#,
+                )
+                .build(),
+        );
+    }
+
+    #[test]
+    fn validator_respects_open_shape() {
+        let validator = Validator::new(schema());
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { principal has nickname };",
+        )
+        .unwrap();
+        let result = validator.validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(result.validation_warnings().count(), 0);
+
+        let pset =
+            PolicySet::from_str("permit(principal, action, resource) when { resource has title };")
+                .unwrap();
+        let result = validator.validate(&pset, ValidationMode::Strict);
+        assert!(result
+            .validation_warnings()
+            .any(|w| matches!(w, ValidationWarning::ImpossiblePolicy(_))));
+    }
+}
+
+mod context_default_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn schema() -> Schema {
+        Schema::from_str(
+            r#"
+            entity User;
+            action view appliesTo {
+                principal: User,
+                resource: User,
+                context: {
+                    @default("web")
+                    channel?: String,
+                    @default("3")
+                    retries?: Long,
+                    note?: String,
+                }
+            };
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn request(context: Context, schema: &Schema) -> Request {
+        Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"User::"bob""#).unwrap(),
+            context,
+            Some(schema),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn defaults_are_filled_in() {
+        let schema = schema();
+        let req = request(Context::empty(), &schema);
+        let context = req.context().unwrap();
+        assert_eq!(
+            context.get("channel"),
+            Some(EvalResult::String("web".into()))
+        );
+        assert_eq!(context.get("retries"), Some(EvalResult::Long(3)));
+        assert_eq!(context.get("note"), None);
+
+        let req = request(
+            Context::from_pairs([(
+                "channel".into(),
+                RestrictedExpression::new_string("api".into()),
+            )])
+            .unwrap(),
+            &schema,
+        );
+        assert_eq!(
+            req.context().unwrap().get("channel"),
+            Some(EvalResult::String("api".into()))
+        );
+    }
+
+    #[test]
+    fn policies_may_rely_on_defaults() {
+        let schema = schema();
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { context.channel == "web" && context.retries < 5 };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema.clone()).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+
+        let response = Authorizer::new().is_authorized(
+            &request(Context::empty(), &schema),
+            &pset,
+            &Entities::empty(),
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+    }
+
+    #[test]
+    fn invalid_defaults() {
+        let err = Schema::from_str(
+            r#"entity User; action view appliesTo { principal: User, resource: User, context: { @default("many") retries?: Long } };"#,
+        )
+        .unwrap_err();
+        assert_matches!(
+            err,
+            CedarSchemaError::Schema(SchemaError::InvalidContextDefault(_))
+        );
+
+        let err = Schema::from_str(
+            r#"entity User; action view appliesTo { principal: User, resource: User, context: { @default("true") retries?: Long } };"#,
+        )
+        .unwrap_err();
+        expect_err(
+            "",
+            &Report::new(err),
+            &ExpectedErrorMessageBuilder::error(
+                r#"invalid default for context attribute `retries` of action `Action::"view"`: `true` does not have the attribute's declared type"#,
+            )
+            .help("defaults can only be declared for optional context attributes, and must have the attribute's type")
+            .build(),
+        );
+
+        let err = Schema::from_str(
+            r#"entity User; action view appliesTo { principal: User, resource: User, context: { @default("web") channel: String } };"#,
+        )
+        .unwrap_err();
+        assert_matches!(
+            err,
+            CedarSchemaError::Schema(SchemaError::InvalidContextDefault(_))
+        );
+    }
+}
+
+mod any_applies_to_tests {
+    use super::*;
+    use itertools::Itertools;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_str(
+            "
+            entity User;
+            entity Service;
+            action healthCheck appliesTo { principal: *, resource: * };
+            action view appliesTo { principal: User, resource: * };
+            ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn applies_to_every_entity_type() {
+        let schema = schema();
+        let health = EntityUid::from_str(r#"Action::"healthCheck""#).unwrap();
+        assert_eq!(
+            schema
+                .principals_for_action(&health)
+                .unwrap()
+                .map(ToString::to_string)
+                .sorted()
+                .collect_vec(),
+            vec!["Service", "User"]
+        );
+
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal is Service, action == Action::"healthCheck", resource is User);
+            permit(principal, action == Action::"view", resource is Service);
+            "#,
+        )
+        .unwrap();
+        let result = Validator::new(schema.clone()).validate(&pset, ValidationMode::Strict);
+        assert!(
+            result.validation_passed(),
+            "{:?}",
+            result.validation_errors().collect_vec()
+        );
+
+        Request::new(
+            EntityUid::from_str(r#"Service::"monitor""#).unwrap(),
+            health,
+            EntityUid::from_str(r#"Service::"db""#).unwrap(),
+            Context::empty(),
+            Some(&schema),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn conflicting_applies_to() {
+        let err = Schema::from_json_value(json!({"": {
+            "entityTypes": { "User": {} },
+            "actions": {
+                "view": {
+                    "appliesTo": {
+                        "principalTypes": ["User"],
+                        "anyPrincipal": true,
+                        "resourceTypes": ["User"]
+                    }
+                }
+            }
+        }}))
+        .unwrap_err();
+        expect_err(
+            "",
+            &Report::new(err),
+            &ExpectedErrorMessageBuilder::error(
+                r#"action `Action::"view"` applies to any principal type, but also lists specific principal types"#,
+            )
+            .help("either list the principal types the action applies to, or declare that it applies to any, but not both")
+            .build(),
+        );
+    }
+}
+
+mod validate_template_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn validator() -> Validator {
+        let schema = Schema::from_str(
+            "
+            entity Group;
+            entity User in Group = { name: String };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            ",
+        )
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[test]
+    fn reports_errors_in_unlinked_template() {
+        use itertools::Itertools;
+
+        let src = r#"permit(principal in ?principal, action == Action::"view", resource) when { principal.nmae == "alice" };"#;
+        let template = Template::parse(Some(PolicyId::new("t")), src).unwrap();
+        let result = validator().validate_template(&template, ValidationMode::Strict);
+        let err = result
+            .validation_errors()
+            .exactly_one()
+            .unwrap_or_else(|_| panic!("expected exactly one error"));
+        assert_eq!(err.policy_id(), &PolicyId::new("t"));
+        expect_err(
+            src,
+            &Report::new(err.clone()),
+            &ExpectedErrorMessageBuilder::error(
+                "for policy `t`, attribute `nmae` on entity type `User` not found",
+            )
+            .exactly_one_underline("principal.nmae")
+            .help("did you mean `name`?")
+            .build(),
+        );
+
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            r#"permit(principal in ?principal, action == Action::"view", resource) when { principal.name == "alice" };"#,
+        )
+        .unwrap();
+        let result = validator().validate_template(&template, ValidationMode::Strict);
+        assert!(result.validation_passed());
+    }
+
+    #[test]
+    fn declared_slot_types() {
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            r#"permit(principal in ?principal, action == Action::"view", resource);"#,
+        )
+        .unwrap();
+        let slot_types = |ty: &str| {
+            HashMap::from([(
+                SlotId::principal(),
+                HashSet::from([EntityTypeName::from_str(ty).unwrap()]),
+            )])
+        };
+
+        let result = validator().validate_template_with_slot_types(
+            &template,
+            &slot_types("Group"),
+            ValidationMode::Strict,
+        );
+        assert!(result.validation_passed_without_warnings());
+
+        let result = validator().validate_template_with_slot_types(
+            &template,
+            &slot_types("Doc"),
+            ValidationMode::Strict,
+        );
+        assert!(result.validation_passed());
+        assert_matches!(
+            result.validation_warnings().collect::<Vec<_>>().as_slice(),
+            [ValidationWarning::ImpossiblePolicy(_)]
+        );
+
+        let result = validator().validate_template_with_slot_types(
+            &template,
+            &slot_types("Grop"),
+            ValidationMode::Strict,
+        );
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::UnrecognizedEntityType(_)]
+        );
+    }
+}
+
+mod validation_mode_annotation_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn validator() -> Validator {
+        let schema = Schema::from_str(
+            "
+            entity User = { tags: Set<String> };
+            action view appliesTo { principal: User, resource: User };
+            ",
+        )
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[test]
+    fn annotation_overrides_mode() {
+        let src = r#"@validation("permissive") permit(principal, action, resource) when { principal.tags == [] };"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = validator().validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+        let warnings = result.validation_warnings().collect::<Vec<_>>();
+        assert_matches!(warnings.as_slice(), [ValidationWarning::ValidationModeOverride(w)] => {
+            assert_eq!(w.policy_id(), &PolicyId::new("policy0"));
+            expect_err(
+                src,
+                &Report::new((*w).clone()),
+                &ExpectedErrorMessageBuilder::error(
+                    "for policy `policy0`, validation mode is overridden to `permissive` by the `@validation` annotation",
+                )
+                .exactly_one_underline(r#"@validation("permissive")"#)
+                .build(),
+            );
+        });
+
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { principal.tags == [] };",
+        )
+        .unwrap();
+        let result = validator().validate(&pset, ValidationMode::Strict);
+        assert!(!result.validation_passed());
+    }
+
+    #[test]
+    fn invalid_annotation() {
+        let src = r#"@validation("lax") permit(principal, action, resource);"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = validator().validate(&pset, ValidationMode::Strict);
+        let errs = result.validation_errors().collect::<Vec<_>>();
+        assert_matches!(errs.as_slice(), [ValidationError::InvalidValidationModeAnnotation(e)] => {
+            expect_err(
+                src,
+                &Report::new((*e).clone()),
+                &ExpectedErrorMessageBuilder::error(
+                    "for policy `policy0`, `@validation` annotation has unsupported value `lax`",
+                )
+                .help(r#"the validation mode of a policy can be overridden with `@validation("strict")` or `@validation("permissive")`"#)
+                .exactly_one_underline(r#"@validation("lax")"#)
+                .build(),
+            );
+        });
+    }
+}
+
+mod entity_conformance_errors_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_str(
+            "
+            entity Group;
+            entity User in Group = { age: Long, name: String, email?: String };
+            ",
+        )
+        .unwrap()
+    }
+
+    fn entities() -> Entities {
+        Entities::from_json_value(
+            json!([
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "age": "old", "name": 1 },
+                    "parents": []
+                },
+                {
+                    "uid": { "type": "User", "id": "bob" },
+                    "attrs": { "name": "Bob", "nickname": "B" },
+                    "parents": [{ "type": "User", "id": "alice" }]
+                },
+                {
+                    "uid": { "type": "Grop", "id": "admins" },
+                    "attrs": {},
+                    "parents": []
+                },
+                {
+                    "uid": { "type": "User", "id": "carol" },
+                    "attrs": { "age": 40, "name": "Carol" },
+                    "parents": [{ "type": "Group", "id": "admins" }]
+                }
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn collects_all_errors() {
+        let errs = entities().conformance_errors(&schema(), usize::MAX);
+        let msgs = errs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            msgs,
+            vec![
+                r#"entity `Grop::"admins"` has type `Grop` which is not declared in the schema"#,
+                r#"in attribute `age` on `User::"alice"`, type mismatch: value was expected to have type long, but it actually has type string: `"old"`"#,
+                r#"in attribute `name` on `User::"alice"`, type mismatch: value was expected to have type string, but it actually has type long: `1`"#,
+                r#"expected entity `User::"bob"` to have attribute `age`, but it does not"#,
+                r#"attribute `nickname` on `User::"bob"` should not exist according to the schema"#,
+                r#"`User::"bob"` is not allowed to have an ancestor of type `User` according to the schema"#,
+            ]
+        );
+        assert!(entities().conformance_errors(&schema(), 0).is_empty());
+    }
+
+    #[test]
+    fn respects_cap() {
+        let errs = entities().conformance_errors(&schema(), 2);
+        assert_matches!(
+            errs.as_slice(),
+            [
+                conformance_errors::EntitySchemaConformanceError::UnexpectedEntityType(_),
+                conformance_errors::EntitySchemaConformanceError::TypeMismatch(_),
+            ]
+        );
+    }
+
+    #[test]
+    fn conforming_entities() {
+        let entities = Entities::from_json_value(
+            json!([
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "age": 30, "name": "Alice" },
+                    "parents": [{ "type": "Group", "id": "admins" }]
+                }
+            ]),
+            None,
+        )
+        .unwrap();
+        assert!(entities.conformance_errors(&schema(), 10).is_empty());
+    }
+}
+
+mod duplicate_policy_id_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use miette::Diagnostic;
+
+    #[test]
+    fn both_definitions_in_same_source() {
+        let src = "permit(principal, action, resource); forbid(principal, action, resource);";
+        let mut pset = PolicySet::new();
+        let policies = PolicySet::from_str(src).unwrap();
+        pset.add(policies.policy(&PolicyId::new("policy0")).unwrap().clone())
+            .unwrap();
+        let dup = policies
+            .policy(&PolicyId::new("policy1"))
+            .unwrap()
+            .new_id(PolicyId::new("policy0"));
+        assert_matches!(pset.add(dup), Err(PolicySetError::AlreadyDefined(e)) => {
+            assert_eq!(e.duplicate_id(), &PolicyId::new("policy0"));
+            expect_err(
+                src,
+                &Report::new(e),
+                &ExpectedErrorMessageBuilder::error("duplicate template or policy id `policy0`")
+                    .exactly_two_underlines_with_labels(
+                        ("permit(principal, action, resource);", "first defined here"),
+                        ("forbid(principal, action, resource);", "defined again here"),
+                    )
+                    .build(),
+            );
+        });
+    }
+
+    #[test]
+    fn definitions_in_different_sources() {
+        let first = "permit(principal, action, resource);";
+        let second = "forbid(principal == ?principal, action, resource);";
+        let mut pset = PolicySet::new();
+        pset.add(Policy::parse(Some(PolicyId::new("p")), first).unwrap())
+            .unwrap();
+        let dup = Template::parse(Some(PolicyId::new("p")), second).unwrap();
+        assert_matches!(pset.add_template(dup), Err(PolicySetError::AlreadyDefined(e)) => {
+            let related = e.related().unwrap().collect::<Vec<_>>();
+            assert_matches!(related.as_slice(), [existing] => {
+                assert_eq!(existing.to_string(), "`p` was first defined here");
+                let labels = existing.labels().unwrap().collect::<Vec<_>>();
+                assert_matches!(labels.as_slice(), [label] => {
+                    assert_eq!(label.offset(), 0);
+                    assert_eq!(label.len(), first.len());
+                });
+            });
+            expect_err(
+                second,
+                &Report::new(e),
+                &ExpectedErrorMessageBuilder::error("duplicate template or policy id `p`")
+                    .exactly_one_underline_with_label(second, "defined again here")
+                    .build(),
+            );
+        });
+    }
+}
+
+mod permit_conflicts_tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn conflicting_owners_are_reported() {
+        let schema = Schema::from_str(
+            "
+            entity User;
+            entity Doc;
+            entity Device;
+            action view appliesTo { principal: User, resource: [Doc, Device] };
+            action edit appliesTo { principal: User, resource: Doc };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"
+            @id("docs") @owner("docs-team")
+            permit(principal, action, resource is Doc);
+            @id("viewers") @owner("it-team")
+            permit(principal, action == Action::"view", resource);
+            @id("editors") @owner("docs-team")
+            permit(principal, action == Action::"edit", resource);
+            "#,
+        )
+        .unwrap();
+        let conflicts = Validator::new(schema).permit_conflicts(&pset, "owner");
+        let summary = conflicts
+            .iter()
+            .map(|c| {
+                let (p1, p2) = c.policies();
+                (
+                    p1.to_string(),
+                    p2.to_string(),
+                    c.owners(),
+                    c.shared_request_envs()
+                        .map(|env| {
+                            format!("{}, {}, {}", env.principal(), env.action(), env.resource())
+                        })
+                        .collect_vec(),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            vec![(
+                "policy0".to_string(),
+                "policy1".to_string(),
+                ("docs-team", "it-team"),
+                vec![r#"User, Action::"view", Doc"#.to_string()]
+            )]
+        );
+    }
+}
+
+mod policy_expiration_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn schema() -> Schema {
+        Schema::from_str("entity User; action view appliesTo { principal: User, resource: User };")
+            .unwrap()
+    }
+
+    const POLICIES: &str = r#"
+        @id("expired")
+        @expires("2024-12-01")
+        permit(principal, action, resource);
+        @id("soon")
+        @expires("2025-01-02")
+        permit(principal, action, resource);
+        @id("later")
+        @expires("2025-06-01")
+        permit(principal, action, resource);
+    "#;
+
+    fn pset() -> PolicySet {
+        PolicySet::from_str(POLICIES).unwrap()
+    }
+
+    fn rules() -> ExpirationRules {
+        ExpirationRules::new("2025-01-01".parse().unwrap())
+    }
+
+    #[test]
+    fn expired_policies_are_errors() {
+        let validator = Validator::new(schema()).with_expiration_rules(rules());
+        let result = validator.validate(&pset(), ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::ExpiredPolicy(e)] => {
+                assert_eq!(e.policy_id().to_string(), "policy0");
+                expect_err(
+                    POLICIES,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error("policy `policy0` expired on 2024-12-01")
+                        .help("remove the policy, or move its `@expires` date if it is still needed")
+                        .exactly_one_underline(r#"@expires("2024-12-01")"#)
+                        .build(),
+                );
+            }
+        );
+        assert_matches!(
+            result.validation_warnings().collect::<Vec<_>>().as_slice(),
+            [ValidationWarning::PolicyExpiring(w)] => {
+                assert_eq!(w.policy_id().to_string(), "policy1");
+                assert_eq!(w.days_remaining(), 1);
+                assert_eq!(w.to_string(), "policy `policy1` expires on 2025-01-02, in 1 day");
+            }
+        );
+    }
+
+    #[test]
+    fn expired_policies_as_warnings() {
+        let rules = ExpirationRules {
+            expired_is_error: false,
+            ..rules()
+        };
+        let validator = Validator::new(schema()).with_expiration_rules(rules);
+        let result = validator.validate(&pset(), ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(result.validation_warnings().count(), 2);
+    }
+
+    #[test]
+    fn list_expiring_policies() {
+        let summary = |rules: &ExpirationRules| {
+            pset()
+                .expiring_policies(rules)
+                .into_iter()
+                .map(|e| {
+                    (
+                        e.policy_id().to_string(),
+                        e.expires().to_string(),
+                        e.days_remaining(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(&rules()),
+            vec![
+                ("policy0".to_string(), "2024-12-01".to_string(), -31),
+                ("policy1".to_string(), "2025-01-02".to_string(), 1),
+            ]
+        );
+        let rules = ExpirationRules {
+            warn_within_days: 365,
+            ..rules()
+        };
+        assert_eq!(summary(&rules).len(), 3);
+    }
+}
+
+mod allowed_namespaces_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn other_tenant_namespace_is_rejected() {
+        let schema = Schema::from_str(
+            "
+            namespace TenantA {
+                entity User;
+                action view appliesTo { principal: User, resource: User };
+            }
+            namespace TenantB {
+                entity User;
+            }
+            ",
+        )
+        .unwrap();
+        let src = r#"permit(principal, action == TenantA::Action::"view", resource) when { resource in TenantB::User::"bob" };"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let validator = Validator::new(schema)
+            .with_allowed_namespaces([Some(EntityNamespace::from_str("TenantA").unwrap()), None]);
+        let result = validator.validate(&pset, ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::DisallowedNamespace(e)] => {
+                assert_eq!(e.entity_type().to_string(), "TenantB::User");
+                expect_err(
+                    src,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error(
+                        "for policy `policy0`, `TenantB::User` is not in an allowed namespace",
+                    )
+                    .help("policies may only reference entity types and actions in the empty namespace or namespace `TenantA`")
+                    .exactly_one_underline(r#"TenantB::User::"bob""#)
+                    .build(),
+                );
+            }
+        );
+    }
+}
+
+mod bundle_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use itertools::Itertools;
+    use serde_json::json;
+
+    /// Toy keyed checksum standing in for a real signature scheme such as
+    /// ed25519
+    struct ChecksumKey(u8);
+
+    impl ChecksumKey {
+        fn checksum(&self, message: &[u8]) -> Vec<u8> {
+            vec![message.iter().fold(self.0, |acc, b| acc.rotate_left(3) ^ b)]
+        }
+    }
+
+    impl BundleSigner for ChecksumKey {
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BundleSignatureError> {
+            Ok(self.checksum(message))
+        }
+    }
+
+    impl BundleVerifier for ChecksumKey {
+        fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), BundleSignatureError> {
+            if self.checksum(message) == signature {
+                Ok(())
+            } else {
+                Err(BundleSignatureError::new("checksum does not match"))
+            }
+        }
+    }
+
+    /// Signer which always fails, like an unreachable key management service
+    struct UnavailableKey;
+
+    impl BundleSigner for UnavailableKey {
+        fn sign(&self, _: &[u8]) -> Result<Vec<u8>, BundleSignatureError> {
+            Err(BundleSignatureError::new("key is unavailable"))
+        }
+    }
+
+    fn bundle() -> PolicyBundle {
+        let mut pset = PolicySet::from_str(
+            r#"
+            permit(principal == User::"alice", action, resource);
+            @id("template")
+            permit(principal == ?principal, action == Action::"view", resource);
+            "#,
+        )
+        .unwrap();
+        pset.link(
+            PolicyId::new("policy1"),
+            PolicyId::new("link"),
+            HashMap::from([(SlotId::principal(), r#"User::"bob""#.parse().unwrap())]),
+        )
+        .unwrap();
+        let schema = SchemaFragment::from_cedarschema_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap()
+        .0;
+        PolicyBundle::new(pset, schema).unwrap()
+    }
+
+    #[test]
+    fn signed_bundle_round_trips() {
+        let key = ChecksumKey(42);
+        let signed = bundle().sign(&key).unwrap();
+        let received =
+            SignedBundle::new(signed.bundle_bytes().to_vec(), signed.signature().to_vec());
+        let verified = received.verify(&key).unwrap();
+        assert_eq!(verified, bundle());
+        let pset = verified.policy_set().unwrap();
+        assert_eq!(pset.policies().count(), 2);
+        assert_eq!(pset.templates().count(), 1);
+        let schema = verified.schema().unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+    }
+
+    #[test]
+    fn tampered_bundle_is_rejected() {
+        let key = ChecksumKey(42);
+        let signed = bundle().sign(&key).unwrap();
+        let tampered = String::from_utf8(signed.bundle_bytes().to_vec())
+            .unwrap()
+            .replace("alice", "mallory");
+        let tampered = SignedBundle::new(tampered.into_bytes(), signed.signature().to_vec());
+        assert_matches!(
+            tampered.verify(&key),
+            Err(BundleError::InvalidSignature(e)) => assert_eq!(e.to_string(), "checksum does not match")
+        );
+        assert_matches!(
+            signed.verify(&ChecksumKey(7)),
+            Err(BundleError::InvalidSignature(_))
+        );
+        assert_matches!(
+            AuthorizerHandle::from_signed_bundle(
+                Authorizer::new(),
+                &tampered,
+                &key,
+                Entities::empty()
+            ),
+            Err(BundleError::InvalidSignature(_))
+        );
+        assert_matches!(bundle().sign(&UnavailableKey), Err(BundleError::Signing(_)));
+    }
+
+    #[test]
+    fn handle_from_signed_bundle() {
+        let key = ChecksumKey(42);
+        let signed = bundle().sign(&key).unwrap();
+        let handle = AuthorizerHandle::from_signed_bundle(
+            Authorizer::new(),
+            &signed,
+            &key,
+            Entities::empty(),
+        )
+        .unwrap();
+        let snapshot = handle.snapshot();
+        assert_eq!(snapshot.policies().policies().count(), 2);
+        assert!(snapshot.schema().is_some());
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"User::"bob""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(handle.is_authorized(&request).decision(), Decision::Allow);
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn ed25519_signatures() {
+        let key = Ed25519Signer::from_bytes(&[7; 32]);
+        let verifier = Ed25519Verifier::from_bytes(&key.verifier().to_bytes()).unwrap();
+        let signed = bundle().sign(&key).unwrap();
+        assert_eq!(signed.signature().len(), 64);
+        assert_eq!(signed.verify(&verifier).unwrap(), bundle());
+
+        let tampered = String::from_utf8(signed.bundle_bytes().to_vec())
+            .unwrap()
+            .replace("alice", "mallory");
+        let tampered = SignedBundle::new(tampered.into_bytes(), signed.signature().to_vec());
+        assert_matches!(
+            AuthorizerHandle::from_signed_bundle(Authorizer::new(), &tampered, &verifier, Entities::empty()),
+            Err(BundleError::InvalidSignature(e)) => assert_eq!(e.to_string(), "ed25519 signature does not match")
+        );
+        let other = Ed25519Signer::from_bytes(&[8; 32]).verifier();
+        assert_matches!(signed.verify(&other), Err(BundleError::InvalidSignature(_)));
+        let truncated = SignedBundle::new(signed.bundle_bytes().to_vec(), vec![0; 10]);
+        assert_matches!(
+            truncated.verify(&verifier),
+            Err(BundleError::InvalidSignature(e)) => assert_eq!(e.to_string(), "malformed ed25519 signature")
+        );
+    }
+    #[test]
+    fn archive_round_trips() {
+        let archive = bundle().pack().unwrap();
+        assert_eq!(PolicyBundle::unpack(&archive).unwrap(), bundle());
+        let manifest = PolicyBundle::manifest(&archive).unwrap();
+        assert_eq!(manifest.format_version(), 1);
+        assert_eq!(manifest.sdk_version(), get_sdk_version().to_string());
+        assert_eq!(manifest.lang_version(), get_lang_version().to_string());
+        assert_eq!(
+            manifest.hashes().map(|(section, _)| section).collect_vec(),
+            vec!["links", "policies", "schema", "templates"]
+        );
+        assert!(manifest.hashes().all(|(_, hash)| hash.len() == 64));
+    }
+
+    #[test]
+    fn archive_integrity_is_checked() {
+        let mut archive: serde_json::Value =
+            serde_json::from_slice(&bundle().pack().unwrap()).unwrap();
+        *archive.pointer_mut("/links/0/newId").unwrap() = json!("mallory");
+        assert_matches!(
+            PolicyBundle::unpack(&serde_json::to_vec(&archive).unwrap()),
+            Err(BundleError::IntegrityCheckFailed(section)) => assert_eq!(section, "links")
+        );
+        *archive.pointer_mut("/manifest/formatVersion").unwrap() = json!(2);
+        assert_matches!(
+            PolicyBundle::unpack(&serde_json::to_vec(&archive).unwrap()),
+            Err(BundleError::UnsupportedFormatVersion(2))
+        );
+    }
+}
+
+mod authorizer_handle_tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn request(principal: &str) -> Request {
+        Request::new(
+            EntityUid::from_str(principal).unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Doc::"plan""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn updates_apply_to_new_requests() {
+        let handle = AuthorizerHandle::new(
+            Authorizer::new(),
+            PolicySet::from_str(r#"permit(principal == User::"alice", action, resource);"#)
+                .unwrap(),
+            Entities::empty(),
+        );
+        let before = handle.snapshot();
+        assert_eq!(
+            handle
+                .is_authorized(&request(r#"User::"alice""#))
+                .decision(),
+            Decision::Allow
+        );
+        assert_eq!(
+            handle.is_authorized(&request(r#"User::"bob""#)).decision(),
+            Decision::Deny
+        );
+
+        handle.update_policies(
+            PolicySet::from_str(r#"permit(principal in Team::"admins", action, resource);"#)
+                .unwrap(),
+        );
+        assert_eq!(
+            handle.is_authorized(&request(r#"User::"bob""#)).decision(),
+            Decision::Deny
+        );
+        handle.update_entities(
+            Entities::from_json_value(
+                json!([{ "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [{ "type": "Team", "id": "admins" }] }]),
+                None,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            handle.is_authorized(&request(r#"User::"bob""#)).decision(),
+            Decision::Allow
+        );
+        assert_eq!(
+            handle
+                .is_authorized(&request(r#"User::"alice""#))
+                .decision(),
+            Decision::Deny
+        );
+
+        // Snapshots taken before an update are unaffected by it
+        assert_eq!(before.policies().policies().count(), 1);
+        assert!(before
+            .policies()
+            .policies()
+            .all(|p| p.to_string().contains("alice")));
+        assert_eq!(before.entities().iter().count(), 0);
+        assert!(before.schema().is_none());
+        assert_eq!(handle.snapshot().entities().iter().count(), 1);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let handle = Arc::new(AuthorizerHandle::new(
+            Authorizer::new(),
+            PolicySet::new(),
+            Entities::empty(),
+        ));
+        let readers = (0..4)
+            .map(|_| {
+                let handle = Arc::clone(&handle);
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        handle.is_authorized(&request(r#"User::"alice""#));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handle
+            .update_policies(PolicySet::from_str("permit(principal, action, resource);").unwrap());
+        handle.update_schema(Some(
+            Schema::from_cedarschema_str("entity User; entity Doc; action view appliesTo { principal: User, resource: Doc };")
+                .unwrap()
+                .0,
+        ));
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(
+            handle
+                .is_authorized(&request(r#"User::"alice""#))
+                .decision(),
+            Decision::Allow
+        );
+        assert!(handle.snapshot().schema().is_some());
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let handle = Arc::new(AuthorizerHandle::new(
+            Authorizer::new(),
+            PolicySet::new(),
+            Entities::empty(),
+        ));
+        let writers = (0..8)
+            .map(|i| {
+                let handle = Arc::clone(&handle);
+                std::thread::spawn(move || {
+                    let policy = Policy::parse(
+                        Some(PolicyId::new(format!("p{i}"))),
+                        "permit(principal, action, resource);",
+                    )
+                    .unwrap();
+                    handle.update_snapshot(|old| {
+                        let mut policies = old.policies().clone();
+                        policies.add(policy.clone()).unwrap();
+                        old.with_policies(Arc::new(policies))
+                    });
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(handle.snapshot().policies().num_of_policies(), 8);
+
+        let previous = handle.replace_snapshot(AuthorizerSnapshot::new(
+            PolicySet::new(),
+            None,
+            Entities::empty(),
+        ));
+        assert_eq!(previous.policies().num_of_policies(), 8);
+        assert_eq!(
+            handle
+                .is_authorized(&request(r#"User::"alice""#))
+                .decision(),
+            Decision::Deny
+        );
+    }
+}
+
+mod tenant_policy_sets_tests {
+    use super::*;
+
+    fn request() -> Request {
+        Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Doc::"plan""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tenants_are_independent() {
+        let schema = Schema::from_cedarschema_str(
+            "entity User; entity Doc; action view appliesTo { principal: User, resource: Doc };",
+        )
+        .unwrap()
+        .0;
+        let tenants = TenantPolicySets::with_schema(schema);
+        assert!(tenants.is_empty());
+        assert!(tenants.schema().is_some());
+        tenants.insert(
+            "acme",
+            PolicySet::from_str("permit(principal, action, resource);").unwrap(),
+        );
+        tenants.insert("globex", PolicySet::new());
+
+        let authorizer = Authorizer::new();
+        let decision = |tenant| {
+            tenants
+                .is_authorized(&authorizer, tenant, &request(), &Entities::empty())
+                .map(|response| response.decision())
+        };
+        assert_eq!(decision("acme"), Some(Decision::Allow));
+        assert_eq!(decision("globex"), Some(Decision::Deny));
+        assert_eq!(decision("initech"), None);
+
+        // Reloading one tenant leaves the others, and earlier lookups, alone
+        let acme = tenants.policy_set("acme").unwrap();
+        let previous = tenants.insert(
+            "acme",
+            PolicySet::from_str("forbid(principal, action, resource);").unwrap(),
+        );
+        assert_eq!(previous.as_ref(), Some(&acme));
+        assert_eq!(decision("acme"), Some(Decision::Deny));
+        assert_eq!(acme.num_of_policies(), 1);
+        tenants.insert(
+            "globex",
+            PolicySet::from_str("permit(principal, action, resource);").unwrap(),
+        );
+        assert_eq!(decision("globex"), Some(Decision::Allow));
+        assert_eq!(decision("acme"), Some(Decision::Deny));
+
+        assert_eq!(tenants.len(), 2);
+        assert!(tenants.remove("acme").is_some());
+        assert_eq!(tenants.tenants(), vec!["globex"]);
+    }
+
+    #[test]
+    fn concurrent_inserts_are_not_lost() {
+        let tenants = std::sync::Arc::new(TenantPolicySets::new());
+        let writers = (0..8)
+            .map(|i| {
+                let tenants = std::sync::Arc::clone(&tenants);
+                std::thread::spawn(move || {
+                    tenants.insert(format!("tenant{i}"), PolicySet::new());
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(tenants.len(), 8);
+        assert!(tenants.remove("initech").is_none());
+        assert_eq!(tenants.len(), 8);
+    }
+}
+
+mod memory_usage_tests {
+    use super::*;
+
+    #[test]
+    fn grows_with_contents() {
+        let empty = PolicySet::new().memory_usage();
+        assert_eq!(empty.total(), 0);
+        let pset = PolicySet::from_str(
+            r#"
+            @comment("a long annotation which is not stored inline")
+            permit(principal, action, resource) when { resource.owner == principal };
+            permit(principal == ?principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let usage = pset.memory_usage();
+        assert!(usage.asts() > 0);
+        assert!(usage.annotations() > 0);
+        assert!(usage.links() > 0);
+
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice Alison-Alistair Allen" }, "parents": [{ "type": "Team", "id": "admins" }] },
+                { "uid": { "type": "Team", "id": "admins" }, "attrs": {}, "parents": [] }
+            ]),
+            None,
+        )
+        .unwrap();
+        let usage = entities.memory_usage();
+        assert!(usage.uids() > 0);
+        assert!(usage.attribute_values() > 0);
+        assert!(usage.closure() > 0);
+        assert_eq!(Entities::empty().memory_usage().total(), 0);
+
+        let schema = Schema::from_cedarschema_str(
+            "entity Team; entity User in [Team] { name: String }; action view appliesTo { principal: User, resource: Team };",
+        )
+        .unwrap()
+        .0;
+        let usage = schema.memory_usage();
+        assert!(usage.entity_types() > 0);
+        assert!(usage.actions() > 0);
+        assert!(usage.hierarchy() > 0);
+        assert_eq!(usage.common_types(), 0);
+    }
+}
+
+mod profiling_tests {
+    use super::*;
+
+    #[test]
+    fn profiles_are_shared_between_authorizers() {
+        let profiler = EvaluationProfiler::new();
+        let policies = PolicySet::from_str(
+            "
+            permit(principal, action, resource);
+            forbid(principal, action, resource) when { context.a.b.c.d == 1 };
+            ",
+        )
+        .unwrap();
+        let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        let request = Request::new(
+            euid.clone(),
+            euid.clone(),
+            euid,
+            Context::from_json_value(
+                serde_json::json!({ "a": { "b": { "c": { "d": 2 } } } }),
+                None,
+            )
+            .unwrap(),
+            None,
+        )
+        .unwrap();
+        for authorizer in [
+            Authorizer::new().with_profiler(&profiler),
+            Authorizer::new().with_profiler(&profiler.clone()),
+        ] {
+            assert_eq!(
+                authorizer
+                    .is_authorized(&request, &policies, &Entities::empty())
+                    .decision(),
+                Decision::Allow
+            );
+        }
+        // Unprofiled authorizers record nothing
+        Authorizer::new().is_authorized(&request, &policies, &Entities::empty());
+
+        let simple = profiler.profile(&PolicyId::new("policy0")).unwrap();
+        let nested = profiler.profile(&PolicyId::new("policy1")).unwrap();
+        assert_eq!(simple.evaluations(), 2);
+        assert_eq!(nested.evaluations(), 2);
+        assert!(nested.max_node_visits() > simple.max_node_visits());
+        assert_eq!(
+            profiler
+                .profiles()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect::<HashSet<_>>(),
+            HashSet::from([PolicyId::new("policy0"), PolicyId::new("policy1")])
+        );
+        profiler.reset();
+        assert!(profiler.profile(&PolicyId::new("policy0")).is_none());
+    }
+}
+
+mod deterministic_order_tests {
+    use super::*;
+
+    #[test]
+    fn errors_in_policy_id_order() {
+        let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        let request =
+            Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap();
+        let ids = ["m", "b", "z", "a", "q", "k", "c", "x"];
+        let authorizer = Authorizer::new().with_deterministic_order();
+        for _ in 0..5 {
+            // Every policy set built from scratch, so that the order the
+            // policies are stored in varies
+            let mut policies = PolicySet::new();
+            for id in ids {
+                policies
+                    .add(
+                        Policy::parse(
+                            Some(PolicyId::new(id)),
+                            "permit(principal, action, resource) when { context.missing };",
+                        )
+                        .unwrap(),
+                    )
+                    .unwrap();
+            }
+            let response = authorizer.is_authorized(&request, &policies, &Entities::empty());
+            let errored = response
+                .diagnostics()
+                .errors()
+                .map(|e| match e {
+                    AuthorizationError::PolicyEvaluationError(e) => e.policy_id().to_string(),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(errored, ["a", "b", "c", "k", "m", "q", "x", "z"]);
+            let errored_permits = response
+                .diagnostics()
+                .errored_permits()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            assert_eq!(errored_permits, errored);
+        }
+    }
+
+    #[test]
+    fn reason_in_policy_id_order() {
+        let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        let request =
+            Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap();
+        let mut policies = PolicySet::new();
+        for id in ["m", "b", "z", "a", "q"] {
+            policies
+                .add(
+                    Policy::parse(
+                        Some(PolicyId::new(id)),
+                        "permit(principal, action, resource);",
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+        }
+        let response = Authorizer::new().is_authorized(&request, &policies, &Entities::empty());
+        let reason = response
+            .diagnostics()
+            .reason()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(reason, ["a", "b", "m", "q", "z"]);
+    }
+}
+
+mod overflow_behavior_tests {
+    use super::*;
+
+    fn request(quota: i64) -> Request {
+        let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        Request::new(
+            euid.clone(),
+            euid.clone(),
+            euid,
+            Context::from_pairs([("quota".into(), RestrictedExpression::new_long(quota))]).unwrap(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn saturating_arithmetic() {
+        let policies = PolicySet::from_str(
+            "
+            permit(principal, action, resource) when { context.quota * 1000 > 5000 };
+            permit(principal, action, resource) when { -context.quota - 1 < 0 };
+            ",
+        )
+        .unwrap();
+        let huge = request(i64::MAX / 2);
+
+        // By default, the overflowing policy fails to evaluate
+        let response = Authorizer::new().is_authorized(&huge, &policies, &Entities::empty());
+        assert_eq!(
+            response.diagnostics().reason().collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy1")]
+        );
+        assert_eq!(response.diagnostics().errors().count(), 1);
+        assert_eq!(response.diagnostics().saturated_policies().count(), 0);
+
+        let authorizer = Authorizer::new().with_overflow_behavior(OverflowBehavior::Saturate);
+        let response = authorizer.is_authorized(&huge, &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.diagnostics().errors().count(), 0);
+        assert_eq!(
+            response
+                .diagnostics()
+                .saturated_policies()
+                .collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy0")]
+        );
+
+        // No overflow, nothing saturated
+        let response = authorizer.is_authorized(&request(10), &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.diagnostics().saturated_policies().count(), 0);
+
+        // `i64::MIN * 1000` and `-i64::MIN` saturate as well
+        let response = authorizer.is_authorized(&request(i64::MIN), &policies, &Entities::empty());
+        assert_eq!(
+            response
+                .diagnostics()
+                .saturated_policies()
+                .collect::<HashSet<_>>(),
+            HashSet::from([&PolicyId::new("policy0"), &PolicyId::new("policy1")])
+        );
+    }
+
+    #[test]
+    fn saturating_division() {
+        // `i64::MIN / -1` is the only overflowing division
+        let policies = PolicySet::from_str(
+            "permit(principal, action, resource) when { context.quota / -1 == 9223372036854775807 };",
+        )
+        .unwrap();
+        let min = request(i64::MIN);
+
+        let response = Authorizer::new().is_authorized(&min, &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+        assert_eq!(response.diagnostics().saturated_policies().count(), 0);
+
+        let authorizer = Authorizer::new().with_overflow_behavior(OverflowBehavior::Saturate);
+        let response = authorizer.is_authorized(&min, &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.diagnostics().errors().count(), 0);
+        assert_eq!(
+            response
+                .diagnostics()
+                .saturated_policies()
+                .collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy0")]
+        );
+
+        // Dividing `i64::MIN` by anything else doesn't saturate
+        let policies = PolicySet::from_str(
+            "permit(principal, action, resource) when { context.quota / 2 < 0 };",
+        )
+        .unwrap();
+        let response = authorizer.is_authorized(&min, &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.diagnostics().saturated_policies().count(), 0);
+    }
+}
+
+mod arithmetic_overflow_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn guaranteed_overflow_is_a_validation_error() {
+        let schema = Schema::from_str(
+            "entity User; action view appliesTo { principal: User, resource: User, context: { n: Long } };",
+        )
+        .unwrap();
+        let src =
+            "permit(principal, action, resource) when { context.n < 9223372036854775807 + 1 };";
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema.clone()).validate(&pset, ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::ArithmeticOverflow(e)] => {
+                expect_err(
+                    src,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error(
+                        "for policy `policy0`, integer arithmetic on constants is guaranteed to overflow",
+                    )
+                    .help("the result must be between -9223372036854775808 and 9223372036854775807, otherwise evaluating the policy fails")
+                    .exactly_one_underline("9223372036854775807 + 1")
+                    .build(),
+                );
+            }
+        );
+
+        // Arithmetic involving non-constants may or may not overflow
+        let pset =
+            PolicySet::from_str("permit(principal, action, resource) when { context.n + 1 > 0 };")
+                .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed());
+    }
+}
+
+mod fold_constants_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn folded_policies_authorize_the_same() {
+        let mut pset = PolicySet::from_str(
+            "
+            permit(principal, action, resource) when { context.n > 60 * 60 };
+            permit(principal, action, resource) when { 1 > 2 } when { context.n == 0 };
+            forbid(principal, action, resource) when { context.n == 7 && 9223372036854775807 + 1 > 0 };
+            permit(principal == ?principal, action, resource) when { context.n < 2 * 5 };
+            ",
+        )
+        .unwrap();
+        pset.link(
+            PolicyId::new("policy3"),
+            PolicyId::new("link"),
+            HashMap::from([(
+                SlotId::principal(),
+                EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            )]),
+        )
+        .unwrap();
+        let folded = pset.fold_constants();
+
+        assert_eq!(
+            folded.policy(&PolicyId::new("policy0")).unwrap().to_string(),
+            "permit(\n  principal,\n  action,\n  resource\n) when {\n  !((context[\"n\"]) <= 3600)\n};"
+        );
+        assert_eq!(
+            folded
+                .policy(&PolicyId::new("policy1"))
+                .unwrap()
+                .to_string(),
+            "permit(\n  principal,\n  action,\n  resource\n) when {\n  false\n};"
+        );
+        assert!(folded.template(&PolicyId::new("policy3")).is_some());
+        assert!(folded.policy(&PolicyId::new("link")).is_some());
+
+        let authorizer = Authorizer::new();
+        for (principal, n) in [("alice", 0), ("alice", 7), ("bob", 3601), ("bob", 5)] {
+            let request = Request::new(
+                EntityUid::from_str(&format!(r#"User::"{principal}""#)).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(r#"Photo::"vacation""#).unwrap(),
+                Context::from_json_value(json!({ "n": n }), None).unwrap(),
+                None,
+            )
+            .unwrap();
+            let expected = authorizer.is_authorized(&request, &pset, &Entities::empty());
+            let actual = authorizer.is_authorized(&request, &folded, &Entities::empty());
+            assert_eq!(actual.decision(), expected.decision());
+            assert_eq!(
+                actual.diagnostics().reason().collect::<HashSet<_>>(),
+                expected.diagnostics().reason().collect::<HashSet<_>>()
+            );
+            assert_eq!(
+                actual.diagnostics().errors().count(),
+                expected.diagnostics().errors().count()
+            );
+        }
+    }
+}
+
+mod subexpression_cache_tests {
+    use super::*;
+
+    #[test]
+    fn cached_responses_are_unchanged() {
+        let pset = PolicySet::from_str(
+            "
+            permit(principal, action, resource) when { resource in principal.orgs && context.level > 1 };
+            permit(principal, action, resource) when { resource in principal.orgs && context.level > 2 };
+            forbid(principal, action, resource) when { resource in principal.orgs && principal.suspended };
+            ",
+        )
+        .unwrap();
+        let entities = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "orgs": [ { "__entity": { "type": "Org", "id": "a" } } ], "suspended": false }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "orgs": [], "suspended": true }, "parents": [] },
+                { "uid": { "type": "Org", "id": "a" }, "attrs": {}, "parents": [] }
+            ]"#,
+            None,
+        )
+        .unwrap();
+        let plain = Authorizer::new();
+        let cached = Authorizer::new().with_subexpression_cache();
+        for (principal, level) in [("alice", 3), ("alice", 0), ("bob", 3), ("carol", 3)] {
+            let request = Request::new(
+                EntityUid::from_str(&format!(r#"User::"{principal}""#)).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(r#"Org::"a""#).unwrap(),
+                Context::from_pairs([("level".into(), RestrictedExpression::new_long(level))])
+                    .unwrap(),
+                None,
+            )
+            .unwrap();
+            let expected = plain.is_authorized(&request, &pset, &entities);
+            let actual = cached.is_authorized(&request, &pset, &entities);
+            assert_eq!(actual.decision(), expected.decision());
+            assert_eq!(
+                actual.diagnostics().reason().collect::<HashSet<_>>(),
+                expected.diagnostics().reason().collect::<HashSet<_>>()
+            );
+            assert_eq!(
+                actual.diagnostics().errors().count(),
+                expected.diagnostics().errors().count()
+            );
+        }
+    }
+}
+
+mod is_type_list_tests {
+    use super::*;
+
+    #[test]
+    fn is_type_list() {
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { resource is [Doc, Sheet] && resource.public };",
+        )
+        .unwrap();
+        let (schema, _) = Schema::from_cedarschema_str(
+            "
+            entity User;
+            entity Doc { public: Bool };
+            entity Sheet { public: Bool };
+            entity Folder;
+            action view appliesTo { principal: User, resource: [Doc, Sheet, Folder] };
+            ",
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        let entities = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "Doc", "id": "d" }, "attrs": { "public": true }, "parents": [] },
+                { "uid": { "type": "Sheet", "id": "s" }, "attrs": { "public": true }, "parents": [] },
+                { "uid": { "type": "Folder", "id": "f" }, "attrs": {}, "parents": [] }
+            ]"#,
+            None,
+        )
+        .unwrap();
+        for (resource, decision) in [
+            (r#"Doc::"d""#, Decision::Allow),
+            (r#"Sheet::"s""#, Decision::Allow),
+            (r#"Folder::"f""#, Decision::Deny),
+        ] {
+            let request = Request::new(
+                EntityUid::from_str(r#"User::"alice""#).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(resource).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap();
+            let response = Authorizer::new().is_authorized(&request, &pset, &entities);
+            assert_eq!(response.decision(), decision, "{resource}");
+            assert_eq!(response.diagnostics().errors().count(), 0);
+        }
+    }
+}
+
+mod set_operation_tests {
+    use super::*;
+
+    #[test]
+    fn union_and_intersection() {
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when {
+                principal.roles.union(principal.delegated).intersection(resource.roles).containsAny(["editor", "owner"])
+            };"#,
+        )
+        .unwrap();
+        let (schema, _) = Schema::from_cedarschema_str(
+            "
+            entity User { roles: Set<String>, delegated: Set<String> };
+            entity Doc { roles: Set<String> };
+            action edit appliesTo { principal: User, resource: Doc };
+            ",
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        let entities = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "roles": ["viewer"], "delegated": ["editor"] }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "roles": ["editor"], "delegated": [] }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "d" }, "attrs": { "roles": ["viewer", "editor"] }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "e" }, "attrs": { "roles": ["viewer", "owner"] }, "parents": [] }
+            ]"#,
+            None,
+        )
+        .unwrap();
+        for (principal, resource, decision) in [
+            ("alice", "d", Decision::Allow),
+            ("bob", "d", Decision::Allow),
+            ("alice", "e", Decision::Deny),
+        ] {
+            let request = Request::new(
+                EntityUid::from_str(&format!(r#"User::"{principal}""#)).unwrap(),
+                EntityUid::from_str(r#"Action::"edit""#).unwrap(),
+                EntityUid::from_str(&format!(r#"Doc::"{resource}""#)).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap();
+            let response = Authorizer::new().is_authorized(&request, &pset, &entities);
+            assert_eq!(response.decision(), decision, "{principal} {resource}");
+            assert_eq!(response.diagnostics().errors().count(), 0);
+        }
+    }
+}
+
+mod get_tag_or_tests {
+    use super::*;
+
+    #[test]
+    fn get_tag_or() {
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { resource.getTagOr("clearance", 0) <= principal.clearance };"#,
+        )
+        .unwrap();
+        let (schema, _) = Schema::from_cedarschema_str(
+            "
+            entity User { clearance: Long };
+            entity Doc tags Long;
+            action view appliesTo { principal: User, resource: Doc };
+            ",
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        let entities = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "clearance": 2 }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "public" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Doc", "id": "secret" }, "attrs": {}, "parents": [], "tags": { "clearance": 3 } },
+                { "uid": { "type": "Doc", "id": "internal" }, "attrs": {}, "parents": [], "tags": { "clearance": 1 } }
+            ]"#,
+            None,
+        )
+        .unwrap();
+        for (resource, decision) in [
+            ("public", Decision::Allow),
+            ("secret", Decision::Deny),
+            ("internal", Decision::Allow),
+        ] {
+            let request = Request::new(
+                EntityUid::from_str(r#"User::"alice""#).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(&format!(r#"Doc::"{resource}""#)).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap();
+            let response = Authorizer::new().is_authorized(&request, &pset, &entities);
+            assert_eq!(response.decision(), decision, "{resource}");
+            assert_eq!(response.diagnostics().errors().count(), 0);
+        }
+    }
+}
+
+mod get_or_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn get_or() {
+        let (schema, _) = Schema::from_cedarschema_str(
+            "
+            entity User { tier?: String };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { principal.getOr("tier", "free") != "free" };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        // `tier` is not an attribute of `Service`, so a `Service` principal
+        // gets the default
+        let (schema, _) = Schema::from_cedarschema_str(
+            "
+            entity User { tier?: String };
+            entity Service;
+            entity Doc;
+            action view appliesTo { principal: [User, Service], resource: Doc };
+            ",
+        )
+        .unwrap();
+        let result = Validator::new(schema.clone()).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        // an attribute which no principal type declares is reported
+        let typo = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { principal.getOr("teir", "free") != "free" };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&typo, ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().next(),
+            Some(ValidationError::UnsafeAttributeAccess(_))
+        );
+
+        let entities = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "tier": "gold" }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] }
+            ]"#,
+            None,
+        )
+        .unwrap();
+        for (principal, decision) in [("alice", Decision::Allow), ("bob", Decision::Deny)] {
+            let request = Request::new(
+                EntityUid::from_str(&format!(r#"User::"{principal}""#)).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(r#"Doc::"d""#).unwrap(),
+                Context::empty(),
+                None,
+            )
+            .unwrap();
+            let response = Authorizer::new().is_authorized(&request, &pset, &entities);
+            assert_eq!(response.decision(), decision, "{principal}");
+            assert_eq!(response.diagnostics().errors().count(), 0);
+        }
+    }
+}
+
+mod like_char_class_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    #[test]
+    fn char_classes_and_repetition() {
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { context.id like "user-\[0-9]\{1,4}_\[a-z]*" };"#,
+        )
+        .unwrap();
+        for (id, decision) in [
+            ("user-1_alice", Decision::Allow),
+            ("user-1234_x", Decision::Allow),
+            ("user-12345_bob", Decision::Deny),
+            ("user-_bob", Decision::Deny),
+            ("user-1-bob", Decision::Deny),
+        ] {
+            let request = Request::new(
+                EntityUid::from_str(r#"User::"alice""#).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(r#"Doc::"d""#).unwrap(),
+                Context::from_pairs([("id".into(), RestrictedExpression::new_string(id.into()))])
+                    .unwrap(),
+                None,
+            )
+            .unwrap();
+            let response = Authorizer::new().is_authorized(&request, &pset, &Entities::empty());
+            assert_eq!(response.decision(), decision, "{id}");
+        }
+    }
+
+    #[test]
+    fn unescaped_brackets_are_literal() {
+        for (expr, expected) in [
+            (r#""dax" like "d[draft]*""#, false),
+            (r#""d[draft]x" like "d[draft]*""#, true),
+            (r#""a{id}b" like "*{id}*""#, true),
+            (r#""^-]}" like "^-]}""#, true),
+        ] {
+            let euid: EntityUid = r#"User::"alice""#.parse().unwrap();
+            let request =
+                Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap();
+            let expr = Expression::from_str(expr).unwrap();
+            assert_matches!(
+                eval_expression(&request, &Entities::empty(), &expr),
+                Ok(EvalResult::Bool(b)) => assert_eq!(b, expected, "{expr}")
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_pattern_from_json() {
+        let err = Policy::from_json(
+            Some(PolicyId::new("p")),
+            json!({
+                "effect": "permit",
+                "principal": { "op": "All" },
+                "action": { "op": "All" },
+                "resource": { "op": "All" },
+                "conditions": [{
+                    "kind": "when",
+                    "body": {
+                        "like": {
+                            "left": { ".": { "left": { "Var": "context" }, "attr": "id" } },
+                            "pattern": [{ "Repeat": { "pattern": [{ "Literal": "a" }], "min": 3, "max": 1 } }]
+                        }
+                    }
+                }]
+            }),
+        )
+        .unwrap_err();
+        expect_err(
+            "",
+            &miette::Report::new(err),
+            &ExpectedErrorMessageBuilder::error("error deserializing a policy/template from JSON")
+                .source("invalid repetition `\\{3,1}` in pattern: the minimum is greater than the maximum")
+                .build(),
+        );
+    }
+}
+
+mod division_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn quota_policy() {
+        let schema = Schema::from_str(
+            "entity User; action view appliesTo { principal: User, resource: User, context: { used: Long, quota: Long } };",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { context.used * 100 / context.quota < 80 };",
+        )
+        .unwrap();
+        let result = Validator::new(schema.clone()).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        let request = |used: i64, quota: i64| {
+            Request::new(
+                EntityUid::from_str(r#"User::"alice""#).unwrap(),
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                EntityUid::from_str(r#"User::"bob""#).unwrap(),
+                Context::from_pairs([
+                    ("used".into(), RestrictedExpression::new_long(used)),
+                    ("quota".into(), RestrictedExpression::new_long(quota)),
+                ])
+                .unwrap(),
+                Some(&schema),
+            )
+            .unwrap()
+        };
+        let authorizer = Authorizer::new();
+        for (used, quota, decision) in [(7, 10, Decision::Allow), (8, 10, Decision::Deny)] {
+            let response =
+                authorizer.is_authorized(&request(used, quota), &pset, &Entities::empty());
+            assert_eq!(response.decision(), decision, "{used} / {quota}");
+        }
+
+        // dividing by zero is an evaluation error
+        let response = authorizer.is_authorized(&request(1, 0), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_matches!(
+            response.diagnostics().errors().collect::<Vec<_>>().as_slice(),
+            [AuthorizationError::PolicyEvaluationError(e)] => {
+                assert_matches!(e.inner(), EvaluationError::DivisionByZero(_));
+            }
+        );
+    }
+
+    #[test]
+    fn constant_zero_divisor_is_a_validation_error() {
+        let schema = Schema::from_str(
+            "entity User; action view appliesTo { principal: User, resource: User, context: { n: Long } };",
+        )
+        .unwrap();
+        let src = "permit(principal, action, resource) when { context.n % 0 == 1 };";
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::DivisionByZero(e)] => {
+                expect_err(
+                    src,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error("for policy `policy0`, integer division by zero")
+                        .help("dividing by zero or taking the remainder of dividing by zero is an evaluation error")
+                        .exactly_one_underline("context.n % 0")
+                        .build(),
+                );
+            }
+        );
+    }
+}
+
+mod incompatible_entity_comparison_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn comparison_with_renamed_entity_type() {
+        let schema = Schema::from_str(
+            r"
+            entity User, Team;
+            entity Document { owner: User };
+            action view appliesTo { principal: User, resource: Document };
+            ",
+        )
+        .unwrap();
+        let src = r#"permit(principal, action, resource) when { principal == Team::"admins" || resource.owner == principal };"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+        assert_matches!(
+            result.validation_warnings().collect::<Vec<_>>().as_slice(),
+            [ValidationWarning::IncompatibleEntityComparison(w)] => {
+                expect_err(
+                    src,
+                    &Report::new(w.clone()),
+                    &ExpectedErrorMessageBuilder::error("for policy `policy0`, comparison is always false: an entity of type `User` can never be equal to an entity of type `Team`")
+                        .help("check whether one of these entity types was renamed or moved to a different namespace")
+                        .exactly_two_underlines_with_labels(
+                            ("principal", "an entity of type `User`"),
+                            (r#"Team::"admins""#, "an entity of type `Team`"),
+                        )
+                        .build(),
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn no_warning_when_types_coincide_in_some_request() {
+        let schema = Schema::from_str(
+            r"
+            entity Team;
+            entity User in [Team];
+            entity Document;
+            action view appliesTo { principal: [User, Team], resource: Document };
+            ",
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { principal == Team::"admins" || principal in Team::"admins" };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+        assert_eq!(result.validation_warnings().count(), 0, "{result:?}");
+    }
+}
+
+mod entity_uid_string_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    #[test]
+    fn eq_suggests_entity_literal() {
+        let schema = Schema::from_str(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let src = r#"permit(principal, action, resource) when { principal == "User::\"alice\"" };"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::IncompatibleTypes(e)] => {
+                expect_err(
+                    src,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error("the types String and User are not compatible")
+                        .help(r#"for policy `policy0`, both operands to a `==` expression must have compatible types. Strings are never equal to entities; try using the entity literal `User::"alice"` instead of a string"#)
+                        .exactly_one_underline(r#"principal == "User::\"alice\"""#)
+                        .build(),
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn in_suggests_entity_literal() {
+        let schema = Schema::from_str(
+            "entity Group; entity User in Group; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let src =
+            r#"permit(principal, action, resource) when { principal in "Group::\"admins\"" };"#;
+        let pset = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert_matches!(
+            result.validation_errors().collect::<Vec<_>>().as_slice(),
+            [ValidationError::UnexpectedType(e)] => {
+                expect_err(
+                    src,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error_starts_with("for policy `policy0`, unexpected type: expected")
+                        .help(r#"try using the entity literal `Group::"admins"` instead of a string"#)
+                        .exactly_one_underline(r#""Group::\"admins\"""#)
+                        .build(),
+                );
+            }
+        );
+    }
+}
+
+mod linked_policy_diagnostics_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    const SCHEMA: &str = "entity User; entity Photo { owner: User }; action view appliesTo { principal: User, resource: Photo };";
+    const TEMPLATE: &str =
+        "permit(principal == ?principal, action, resource in ?resource) when { resource.owner == principal };";
+
+    fn linked_pset(principal: &str, resource: &str) -> PolicySet {
+        let mut pset = PolicySet::new();
+        pset.add_template(Template::parse(Some(PolicyId::new("t0")), TEMPLATE).unwrap())
+            .unwrap();
+        pset.link(
+            PolicyId::new("t0"),
+            PolicyId::new("link0"),
+            HashMap::from([
+                (SlotId::principal(), EntityUid::from_str(principal).unwrap()),
+                (SlotId::resource(), EntityUid::from_str(resource).unwrap()),
+            ]),
+        )
+        .unwrap();
+        pset
+    }
+
+    #[test]
+    fn evaluation_error_reports_template_and_bindings() {
+        let pset = linked_pset(r#"User::"alice""#, r#"Photo::"vacation""#);
+        let entities = Entities::from_json_str(
+            r#"[{ "uid": { "type": "Photo", "id": "vacation" }, "attrs": {}, "parents": [] }]"#,
+            None,
+        )
+        .unwrap();
+        let request = Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"Photo::"vacation""#).unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &pset, &entities);
+        assert_matches!(
+            response.diagnostics().errors().collect::<Vec<_>>().as_slice(),
+            [AuthorizationError::PolicyEvaluationError(e)] => {
+                assert_eq!(e.policy_id(), &PolicyId::new("link0"));
+                assert_eq!(e.template_id(), Some(&PolicyId::new("t0")));
+                assert_eq!(
+                    e.template_links(),
+                    Some(HashMap::from([
+                        (SlotId::principal(), EntityUid::from_str(r#"User::"alice""#).unwrap()),
+                        (SlotId::resource(), EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+                    ]))
+                );
+                expect_err(
+                    TEMPLATE,
+                    &Report::new(e.clone()),
+                    &ExpectedErrorMessageBuilder::error(r#"error while evaluating policy `link0` (linked from template `t0` with ?principal = User::"alice", ?resource = Photo::"vacation"): `Photo::"vacation"` does not have the attribute `owner`"#)
+                        .help(r#"`Photo::"vacation"` does not have any attributes"#)
+                        .exactly_one_underline("resource.owner")
+                        .build(),
+                );
+            }
+        );
+    }
+
+    #[test]
+    fn validation_errors_point_into_template() {
+        let schema = Schema::from_str(SCHEMA).unwrap();
+        let pset = linked_pset(r#"User::"alice""#, r#"Usr::"bob""#);
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        let errs = result.validation_errors().collect::<Vec<_>>();
+        assert_eq!(errs.len(), 2, "{errs:?}");
+        assert_matches!(
+            errs.iter().find(|e| matches!(e, ValidationError::UnrecognizedEntityType(_))),
+            Some(e) => {
+                expect_err(
+                    TEMPLATE,
+                    &Report::new((*e).clone()),
+                    &ExpectedErrorMessageBuilder::error("for policy `link0`, unrecognized entity type `Usr`")
+                        .help("did you mean `User`?")
+                        .exactly_one_underline("?resource")
+                        .build(),
+                );
+            }
+        );
+        assert_matches!(
+            errs.iter().find(|e| matches!(e, ValidationError::InvalidActionApplication(_))),
+            Some(e) => {
+                expect_err(
+                    TEMPLATE,
+                    &Report::new((*e).clone()),
+                    &ExpectedErrorMessageBuilder::error(r#"for policy `link0` (linked from template `t0` with ?principal = User::"alice", ?resource = Usr::"bob"), unable to find an applicable action given the policy scope constraints"#)
+                        .exactly_one_underline(TEMPLATE)
+                        .build(),
+                );
+            }
+        );
+    }
+}
+
+mod provenance_tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const POLICIES: &str = r"permit(principal, action, resource);
+forbid(principal, action, resource)
+when { principal.suspended };
+permit(principal == ?principal, action, resource);
+";
+
+    fn provenance() -> Provenance {
+        Provenance::new()
+            .with_file("policies.cedar")
+            .with_revision("3f2a9c1")
+            .with_author("alice")
+    }
+
+    fn request() -> Request {
+        Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"p""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parsed_policies_record_their_line() {
+        let mut pset = PolicySet::from_str_with_provenance(POLICIES, provenance()).unwrap();
+        assert_eq!(
+            pset.provenance(&PolicyId::new("policy0")),
+            Some(&provenance().with_line(1))
+        );
+        assert_eq!(
+            pset.provenance(&PolicyId::new("policy1")),
+            Some(&provenance().with_line(2))
+        );
+        assert_eq!(
+            pset.template(&PolicyId::new("policy2"))
+                .and_then(Template::provenance),
+            Some(&provenance().with_line(4))
+        );
+
+        // links share the provenance of their template
+        pset.link(
+            PolicyId::new("policy2"),
+            PolicyId::new("link"),
+            HashMap::from([(SlotId::principal(), r#"User::"alice""#.parse().unwrap())]),
+        )
+        .unwrap();
+        assert_eq!(
+            pset.policy(&PolicyId::new("link"))
+                .and_then(Policy::provenance)
+                .map(ToString::to_string),
+            Some("policies.cedar:4 @ 3f2a9c1 by alice".to_string())
+        );
+
+        // an explicit line is kept
+        let pset =
+            PolicySet::from_str_with_provenance(POLICIES, provenance().with_line(40)).unwrap();
+        assert_eq!(
+            pset.provenance(&PolicyId::new("policy1")),
+            Some(&provenance().with_line(40))
+        );
+    }
+
+    #[test]
+    fn provenance_does_not_affect_equality() {
+        let p = Policy::parse(None, "permit(principal, action, resource);").unwrap();
+        let q = p.with_provenance(provenance());
+        assert_eq!(q.provenance(), Some(&provenance()));
+        assert_eq!(p.provenance(), None);
+        assert_eq!(p, q);
+    }
+
+    #[test]
+    fn validation_result_reports_provenance() {
+        let schema = Schema::from_str(
+            "entity User = { suspended: Bool }; entity Photo; action view appliesTo { principal: User, resource: Photo };",
+        )
+        .unwrap();
+        let mut pset =
+            PolicySet::from_str("permit(principal, action, resource) when { principal.suspnded };")
+                .unwrap();
+        pset.add(
+            Policy::parse(
+                Some(PolicyId::new("deployed")),
+                "permit(principal, action, resource) when { principal.suspnded };",
+            )
+            .unwrap()
+            .with_provenance(provenance()),
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&pset, ValidationMode::Strict);
+        assert_eq!(result.validation_errors().count(), 2);
+        assert_eq!(
+            result.provenance(&PolicyId::new("deployed")),
+            Some(&provenance())
+        );
+        assert_eq!(result.provenance(&PolicyId::new("policy0")), None);
+    }
+
+    #[test]
+    fn response_reports_provenance() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        let authorizer = Authorizer::new().with_decision_logger(move |record: &DecisionRecord| {
+            sink.lock().unwrap().push(record.clone());
+        });
+        let pset = PolicySet::from_str_with_provenance(POLICIES, provenance()).unwrap();
+        let response = authorizer.is_authorized(&request(), &pset, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(
+            response.diagnostics().provenance(&PolicyId::new("policy0")),
+            Some(&provenance().with_line(1))
+        );
+        // `policy1` errored, since `principal` does not exist
+        assert_eq!(
+            response.diagnostics().provenance(&PolicyId::new("policy1")),
+            Some(&provenance().with_line(2))
+        );
+
+        let records = std::mem::take(&mut *records.lock().unwrap());
+        let record = records.first().unwrap();
+        assert_eq!(
+            record.provenance("policy0"),
+            Some(&provenance().with_line(1))
+        );
+        assert_eq!(record.provenance("policy1"), None);
+        assert_eq!(
+            serde_json::to_value(record).unwrap().get("provenance"),
+            Some(&serde_json::json!({
+                "policy0": {
+                    "file": "policies.cedar",
+                    "line": 1,
+                    "revision": "3f2a9c1",
+                    "author": "alice",
+                }
+            }))
+        );
+    }
+}
+
+mod validate_stream_tests {
+    use super::*;
+
+    fn validator() -> Validator {
+        let schema: Schema = r"
+            entity User = { name: String };
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc };
+        "
+        .parse()
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[test]
+    fn reports_each_policy_as_it_is_checked() {
+        let template = Template::parse(
+            Some(PolicyId::new("t")),
+            r#"permit(principal, action == Action::"view", resource == ?resource) when { principal.name > 1 };"#,
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        pset.add_template(template).unwrap();
+        for (id, eid) in [("link0", r#"Doc::"a""#), ("link1", r#"User::"b""#)] {
+            pset.link(
+                PolicyId::new("t"),
+                PolicyId::new(id),
+                HashMap::from([(SlotId::resource(), eid.parse().unwrap())]),
+            )
+            .unwrap();
+        }
+        let valid = Policy::parse(
+            Some(PolicyId::new("valid")),
+            r#"permit(principal, action == Action::"view", resource);"#,
+        )
+        .unwrap()
+        .with_provenance(Provenance::new().with_file("valid.cedar"));
+        let policies = [
+            valid,
+            pset.policy(&PolicyId::new("link0")).unwrap().clone(),
+            pset.policy(&PolicyId::new("link1")).unwrap().clone(),
+        ];
+
+        let mut checked = Vec::new();
+        for (id, result) in validator().validate_stream(policies, ValidationMode::Strict) {
+            let error_ids = result
+                .validation_errors()
+                .map(|e| e.policy_id().to_string())
+                .collect::<Vec<_>>();
+            checked.push((id.to_string(), error_ids));
+        }
+        assert_eq!(
+            checked,
+            vec![
+                ("valid".to_string(), vec![]),
+                // the template error comes with the first link
+                ("link0".to_string(), vec!["t".to_string()]),
+                ("link1".to_string(), vec!["link1".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn results_have_provenance() {
+        let provenance = Provenance::new().with_file("bad.cedar").with_line(3);
+        let policy = Policy::parse(
+            Some(PolicyId::new("bad")),
+            r#"permit(principal, action == Action::"edit", resource);"#,
+        )
+        .unwrap()
+        .with_provenance(provenance.clone());
+        let (id, result) = validator()
+            .validate_stream([policy], ValidationMode::Strict)
+            .next()
+            .unwrap();
+        assert_eq!(id, PolicyId::new("bad"));
+        assert!(!result.validation_passed());
+        assert_eq!(result.provenance(&id), Some(&provenance));
+    }
+}
+
+mod parallel_parse_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn summary(pset: &PolicySet) -> Vec<(String, String)> {
+        let mut summary = pset
+            .policies()
+            .map(|p| (p.id().to_string(), p.to_string()))
+            .chain(
+                pset.templates()
+                    .map(|t| (t.id().to_string(), t.to_string())),
+            )
+            .collect::<Vec<_>>();
+        summary.sort();
+        summary
+    }
+
+    #[test]
+    fn same_as_parsing_concatenation() {
+        let sources = (0..100)
+            .map(|i| match i % 3 {
+                0 => format!(r#"permit(principal == User::"u{i}", action, resource);"#),
+                1 => format!(
+                    "forbid(principal, action, resource) when {{ {i} > 1 }};\npermit(principal, action, resource);"
+                ),
+                _ => format!(
+                    "permit(principal == ?principal, action, resource) when {{ resource.n == {i} }};"
+                ),
+            })
+            .collect::<Vec<_>>();
+        let parallel = PolicySet::from_strs_parallel(sources.iter().map(String::as_str)).unwrap();
+        let sequential = PolicySet::from_str(&sources.join("\n")).unwrap();
+        assert_eq!(summary(&parallel), summary(&sequential));
+        assert_eq!(parallel.num_of_policies(), 100);
+        assert_eq!(parallel.num_of_templates(), 33);
+    }
+
+    #[test]
+    fn errors_are_in_source_order() {
+        let sources = [
+            "permit(principal, action, resource);",
+            "permit(principal, action, resource) when { 1 + };",
+            "permit(principal, action, resource);",
+            "permit(principal == ?resource, action, resource);",
+        ];
+        let errs = PolicySet::from_strs_parallel(sources).unwrap_err();
+        let messages = errs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert_matches!(messages.first(), Some(msg) => {
+            assert!(msg.starts_with("unexpected token `}`"), "{msg}");
+        });
+        assert_matches!(messages.get(1), Some(msg) => {
+            assert!(msg.contains("?resource"), "{msg}");
+        });
+    }
+
+    #[test]
+    fn no_sources() {
+        let pset = PolicySet::from_strs_parallel([]).unwrap();
+        assert!(pset.is_empty());
+    }
+}
+
+#[cfg(feature = "directory")]
+mod from_directory_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::path::{Path, PathBuf};
+
+    /// A fresh directory containing `files`, given as relative path and contents
+    // PANIC SAFETY unit tests
+    #[allow(clippy::unwrap_used)]
+    fn policy_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cedar-from-directory-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (file, contents) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn sorted_ids(pset: &PolicySet) -> Vec<String> {
+        let mut ids: Vec<_> = pset
+            .policies()
+            .map(|p| p.id().to_string())
+            .chain(pset.templates().map(|t| t.id().to_string()))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn derives_ids_from_paths() {
+        let dir = policy_dir(
+            "ids",
+            &[
+                ("top.cedar", "permit(principal, action, resource);"),
+                (
+                    "photos/view.cedar",
+                    r#"permit(principal, action == Action::"view", resource);
+                    @id("no-delete")
+                    forbid(principal, action == Action::"delete", resource);
+                    forbid(principal, action, resource) when { principal.banned };"#,
+                ),
+                ("photos/readme.txt", "not a policy"),
+            ],
+        );
+        let pset = PolicySet::from_directory(&dir, "**/*.cedar").unwrap();
+        assert_eq!(
+            sorted_ids(&pset),
+            ["no-delete", "photos/view#0", "photos/view#2", "top"]
+        );
+        let policy = pset.policy(&PolicyId::new("photos/view#2")).unwrap();
+        assert_eq!(
+            policy.provenance().and_then(|p| p.file()),
+            Some(
+                Path::new("photos")
+                    .join("view.cedar")
+                    .to_string_lossy()
+                    .as_ref()
+            )
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn applies_links() {
+        let dir = policy_dir(
+            "links",
+            &[
+                (
+                    "templates/owner.cedar",
+                    "@id(\"owner\") permit(principal == ?principal, action, resource);",
+                ),
+                (
+                    "a.links.json",
+                    r#"[{ "templateId": "owner", "newId": "alice-owner",
+                         "values": { "?principal": { "type": "User", "id": "alice" } } }]"#,
+                ),
+            ],
+        );
+        let pset = PolicySet::from_directory(&dir, "**/*.{cedar,links.json}").unwrap();
+        assert_eq!(sorted_ids(&pset), ["alice-owner", "owner"]);
+        assert_eq!(
+            pset.policy(&PolicyId::new("alice-owner"))
+                .unwrap()
+                .template_id(),
+            Some(&PolicyId::new("owner"))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reports_every_bad_file() {
+        let dir = policy_dir(
+            "errors",
+            &[
+                ("a.cedar", "permit(principal, action, resource);"),
+                ("b.cedar", "permit(principal, action"),
+                ("c.cedar", "@id(\"a\") forbid(principal, action, resource);"),
+                ("d.links.json", "{}"),
+            ],
+        );
+        let errs = PolicySet::from_directory(&dir, "*").unwrap_err();
+        assert_matches!(errs, PolicyDirectoryError::Files(errs) => {
+            let kinds: Vec<_> = errs
+                .iter()
+                .map(|e| (e.path().file_name().unwrap().to_string_lossy().to_string(), e.kind()))
+                .collect();
+            assert_matches!(
+                kinds.as_slice(),
+                [
+                    (b, policy_directory_errors::PolicyFileErrorKind::Parse(_)),
+                    (c, policy_directory_errors::PolicyFileErrorKind::PolicySet(_)),
+                    (d, policy_directory_errors::PolicyFileErrorKind::Links(_)),
+                ] => {
+                    assert_eq!(b, "b.cedar");
+                    assert_eq!(c, "c.cedar");
+                    assert_eq!(d, "d.links.json");
+                }
+            );
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_glob() {
+        assert_matches!(
+            PolicySet::from_directory(std::env::temp_dir(), "[a"),
+            Err(PolicyDirectoryError::InvalidGlob(_))
+        );
+    }
+}
+
+mod entities_text_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use entities_errors::EntitiesError;
+    use serde_json::json;
+
+    #[test]
+    fn same_as_json() {
+        let text = Entities::from_cedar_str(
+            r#"
+            entity User::"alice" { name: "Alice", friends: [User::"bob"] } in [Group::"staff"];
+            entity User::"bob" in Group::"staff" tags { level: 2 };
+            entity Group::"staff";
+            "#,
+            None,
+        )
+        .unwrap();
+        let json = Entities::from_json_value(
+            json!([
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "name": "Alice", "friends": [{ "__entity": { "type": "User", "id": "bob" } }] },
+                    "parents": [{ "type": "Group", "id": "staff" }]
+                },
+                {
+                    "uid": { "type": "User", "id": "bob" },
+                    "attrs": {},
+                    "parents": [{ "type": "Group", "id": "staff" }],
+                    "tags": { "level": 2 }
+                },
+                { "uid": { "type": "Group", "id": "staff" }, "attrs": {}, "parents": [] }
+            ]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(text, json);
+        assert_eq!(
+            Entities::from_cedar_str(&text.to_cedar().unwrap(), None).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn to_cedar() {
+        let entities = Entities::from_cedar_str(
+            r#"entity User::"bob"; entity User::"alice" { b: 1, a: "x" } in Group::"staff";"#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            entities.to_cedar().unwrap(),
+            "entity User::\"alice\" {\n  a: \"x\",\n  b: 1\n} in [Group::\"staff\"];\nentity User::\"bob\";"
+        );
+        let alice = entities
+            .get(&EntityUid::from_str(r#"User::"alice""#).unwrap())
+            .unwrap();
+        assert_eq!(
+            alice.to_cedar().unwrap(),
+            "entity User::\"alice\" {\n  a: \"x\",\n  b: 1\n} in [Group::\"staff\"];"
+        );
+    }
+
+    #[test]
+    fn with_schema() {
+        let schema = Schema::from_str(
+            "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let entities =
+            Entities::from_cedar_str(r#"entity User::"alice" { age: 3 };"#, Some(&schema)).unwrap();
+        // the action entities from the schema are added
+        assert!(entities
+            .get(&EntityUid::from_str(r#"Action::"view""#).unwrap())
+            .is_some());
+        assert_matches!(
+            Entities::from_cedar_str(r#"entity User::"alice" { age: "old" };"#, Some(&schema)),
+            Err(EntitiesTextError::Entities(EntitiesError::InvalidEntity(_)))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_matches!(
+            Entities::from_cedar_str(r#"entity User::"alice" { age: 1 + 2 };"#, None),
+            Err(EntitiesTextError::Parse(_))
+        );
+        assert_matches!(
+            Entities::from_cedar_str(r#"entity User::"alice"; entity User::"alice";"#, None),
+            Err(EntitiesTextError::Entities(EntitiesError::Duplicate(_)))
+        );
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn evaluation_error() {
+        assert_matches!(
+            Entities::from_cedar_str(r#"entity User::"alice" { d: decimal("1.2.3") };"#, None),
+            Err(EntitiesTextError::Evaluation(err)) => {
+                assert_eq!(err.attr(), "d");
+            }
+        );
+    }
+}
+
+mod schema_doc_comments_tests {
+    use super::*;
+
+    const SRC: &str = r"// A user
+entity User = {
+    // the user's name
+    name: String,
+};
+
+// viewing
+// things
+action view appliesTo { principal: User, resource: User };
+";
+
+    #[test]
+    fn comments_survive_json_round_trip() {
+        let (fragment, _) = SchemaFragment::from_cedarschema_str_with_doc_comments(SRC).unwrap();
+        let json = fragment.to_json_string().unwrap();
+        assert!(
+            json.contains(r#""doc":"A user""#),
+            "unexpected JSON: {json}"
+        );
+        assert!(
+            json.contains(r#""doc":"viewing\nthings""#),
+            "unexpected JSON: {json}"
+        );
+
+        let fragment = SchemaFragment::from_json_str(&json).unwrap();
+        let printed = fragment.to_cedarschema_with_doc_comments().unwrap();
+        assert!(
+            printed.contains("// viewing\n// things\naction \"view\""),
+            "unexpected schema: {printed}"
+        );
+        let (reparsed, _) =
+            SchemaFragment::from_cedarschema_str_with_doc_comments(&printed).unwrap();
+        assert_eq!(reparsed.to_json_string().unwrap(), json);
+    }
+
+    #[test]
+    fn comments_dropped_by_default() {
+        let (fragment, _) = SchemaFragment::from_cedarschema_str(SRC).unwrap();
+        let json = fragment.to_json_string().unwrap();
+        assert!(!json.contains("doc"), "unexpected JSON: {json}");
+    }
+}
+
+mod typecheck_expression_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use std::collections::BTreeMap;
+
+    fn schema() -> Schema {
+        r"
+            entity Team;
+            entity User in Team = { name: String, manager?: User };
+            entity Photo = { owner: User, tags: Set<String> };
+            action view appliesTo {
+                principal: User,
+                resource: Photo,
+                context: { authenticated: Bool }
+            };
+        "
+        .parse()
+        .unwrap()
+    }
+
+    fn validator() -> Validator {
+        Validator::new(schema())
+    }
+
+    fn env(context: SchemaType) -> ExpressionEnv {
+        ExpressionEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            "Photo".parse().unwrap(),
+            context,
+        )
+    }
+
+    #[track_caller]
+    fn typecheck(
+        validator: &Validator,
+        src: &str,
+        env: &ExpressionEnv,
+    ) -> Result<SchemaType, ExpressionTypecheckError> {
+        let expr: Expression = src.parse().unwrap();
+        validator.typecheck_expression(&expr, env, ValidationMode::Strict)
+    }
+
+    #[test]
+    fn types() {
+        let validator = validator();
+        let context = SchemaType::Record {
+            attributes: BTreeMap::from([(
+                "level".to_string(),
+                SchemaAttribute::new(SchemaType::Long, true),
+            )]),
+            additional_attributes: false,
+        };
+        let env = env(context);
+        assert_eq!(
+            typecheck(&validator, "resource.owner", &env).unwrap(),
+            SchemaType::Entity("User".parse().unwrap())
+        );
+        assert_eq!(
+            typecheck(&validator, "resource.tags", &env).unwrap(),
+            SchemaType::Set(Box::new(SchemaType::String))
+        );
+        assert_eq!(
+            typecheck(&validator, "context.level * 2", &env).unwrap(),
+            SchemaType::Long
+        );
+        assert_eq!(
+            typecheck(&validator, "principal in resource.owner", &env).unwrap(),
+            SchemaType::Bool
+        );
+        assert_eq!(
+            typecheck(&validator, "action", &env).unwrap(),
+            SchemaType::Entity("Action".parse().unwrap())
+        );
+        assert_eq!(
+            typecheck(&validator, "{ a: 1, b: principal.name }", &env).unwrap(),
+            SchemaType::Record {
+                attributes: BTreeMap::from([
+                    (
+                        "a".to_string(),
+                        SchemaAttribute::new(SchemaType::Long, true)
+                    ),
+                    (
+                        "b".to_string(),
+                        SchemaAttribute::new(SchemaType::String, true)
+                    ),
+                ]),
+                additional_attributes: false,
+            }
+        );
+    }
+
+    #[test]
+    fn context_from_schema() {
+        let validator = validator();
+        let schema = schema();
+        let request_env = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            "Photo".parse().unwrap(),
+        );
+        let env = ExpressionEnv::from_request_env(&schema, &request_env).unwrap();
+        assert_eq!(
+            typecheck(&validator, "context.authenticated", &env).unwrap(),
+            SchemaType::Bool
+        );
+
+        let request_env = RequestEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"edit""#.parse().unwrap(),
+            "Photo".parse().unwrap(),
+        );
+        assert_eq!(ExpressionEnv::from_request_env(&schema, &request_env), None);
+    }
+
+    #[test]
+    fn errors() {
+        let validator = validator();
+        let empty = SchemaType::Record {
+            attributes: BTreeMap::new(),
+            additional_attributes: false,
+        };
+        let env = env(empty.clone());
+        assert_matches!(
+            typecheck(&validator, "principal.manager", &env),
+            Err(ExpressionTypecheckError::Validation(errs)) => {
+                assert_matches!(errs.as_slice(), [err @ ValidationError::UnsafeOptionalAttributeAccess(_)] => {
+                    assert_eq!(err.policy_id(), &PolicyId::new("expression"));
+                });
+            }
+        );
+        assert_matches!(
+            typecheck(&validator, "principal.name + 1 && context.missing", &env),
+            Err(ExpressionTypecheckError::Validation(errs)) => {
+                // both sides of the `&&` are reported
+                assert!(errs.len() >= 2, "unexpected errors: {errs:?}");
+            }
+        );
+
+        let env = ExpressionEnv::new(
+            "User".parse().unwrap(),
+            r#"Action::"edit""#.parse().unwrap(),
+            "Photo".parse().unwrap(),
+            empty,
+        );
+        assert_matches!(
+            typecheck(&validator, "principal", &env),
+            Err(ExpressionTypecheckError::UndeclaredAction(action)) => {
+                assert_eq!(action.to_string(), r#"Action::"edit""#);
+            }
+        );
+
+        let env = env_with_extension("notAnExtension");
+        assert_matches!(
+            typecheck(&validator, "context.x", &env),
+            Err(ExpressionTypecheckError::UnknownExtensionType(name)) => {
+                assert_eq!(name, "notAnExtension");
+            }
+        );
+    }
+
+    fn env_with_extension(name: &str) -> ExpressionEnv {
+        env(SchemaType::Record {
+            attributes: BTreeMap::from([(
+                "x".to_string(),
+                SchemaAttribute::new(SchemaType::Extension(name.to_string()), true),
+            )]),
+            additional_attributes: false,
+        })
+    }
+
+    #[cfg(feature = "ipaddr")]
+    #[test]
+    fn extension_context() {
+        let validator = validator();
+        let env = env_with_extension("ipaddr");
+        assert_eq!(
+            typecheck(&validator, r#"context.x.isInRange(ip("10.0.0.0/8"))"#, &env).unwrap(),
+            SchemaType::Bool
+        );
+        assert_eq!(
+            typecheck(&validator, "context.x", &env).unwrap(),
+            SchemaType::Extension("ipaddr".to_string())
+        );
+    }
+}
+
+mod evaluation_debugger_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn request() -> Request {
+        Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"trip.jpg""#.parse().unwrap(),
+            Context::from_pairs([("level".to_string(), RestrictedExpression::new_long(3))])
+                .unwrap(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn entities() -> Entities {
+        Entities::from_json_str(
+            r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice" }, "parents": [] }]"#,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn step_and_step_over() {
+        let expr: Expression = r#"principal.name == "Alice" && context.level > 2"#.parse().unwrap();
+        let mut debugger = EvaluationDebugger::for_expression(&request(), &entities(), &expr);
+        assert!(debugger.current().is_none());
+        assert!(!debugger.is_finished());
+        assert_eq!(debugger.result(), None);
+
+        let step = debugger.step().unwrap();
+        assert_eq!(step.depth(), 0);
+        assert!(!step.is_finished());
+        let step = debugger.step().unwrap();
+        assert_eq!(
+            step.expression().to_string(),
+            r#"(principal["name"]) == "Alice""#
+        );
+        assert_eq!(step.depth(), 1);
+
+        // stepping over the left operand lands on the step which finishes it
+        let step = debugger.step_over().unwrap();
+        assert!(step.is_finished());
+        assert_eq!(step.depth(), 1);
+        assert_eq!(step.value(), Some(EvalResult::Bool(true)));
+
+        debugger.continue_until(|_| false);
+        assert!(debugger.is_finished());
+        assert_eq!(debugger.result(), Some(Ok(EvalResult::Bool(true))));
+        assert!(debugger.step().is_none());
+
+        debugger.restart();
+        assert!(debugger.current().is_none());
+        assert_eq!(
+            debugger.step().unwrap().expression().to_string(),
+            debugger.steps().first().unwrap().expression().to_string()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let expr: Expression = "principal.missing || true".parse().unwrap();
+        let mut debugger = EvaluationDebugger::for_expression(&request(), &entities(), &expr);
+        let step = debugger
+            .continue_until(|step| step.error().is_some())
+            .unwrap();
+        assert_eq!(step.expression().to_string(), r#"principal["missing"]"#);
+        assert_eq!(step.value(), None);
+        debugger.continue_until(|_| false);
+        assert_matches!(debugger.result(), Some(Err(_)));
+    }
+
+    #[test]
+    fn policy_condition() {
+        let policy = Policy::parse(
+            None,
+            r#"permit(principal == User::"alice", action, resource) when { context.level < 2 };"#,
+        )
+        .unwrap();
+        let mut debugger = EvaluationDebugger::for_policy(&request(), &entities(), &policy);
+        let step = debugger
+            .continue_until(|step| {
+                step.is_finished()
+                    && step.expression().to_string() == "principal == User::\"alice\""
+            })
+            .unwrap();
+        assert_eq!(step.value(), Some(EvalResult::Bool(true)));
+        debugger.continue_until(|_| false);
+        assert_eq!(debugger.result(), Some(Ok(EvalResult::Bool(false))));
+    }
+}
+
+mod type_at_offset_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn validator() -> Validator {
+        let schema: Schema = r"
+            entity User = { name: String, manager?: User };
+            entity Admin = { name: String };
+            entity Photo = { owner: User };
+            action view appliesTo { principal: [User, Admin], resource: Photo };
+        "
+        .parse()
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[track_caller]
+    fn type_at(src: &str, needle: &str) -> Option<TypeAtOffset> {
+        let offset = src.find(needle).unwrap();
+        validator()
+            .type_at_offset(src, offset, ValidationMode::Strict)
+            .unwrap()
+    }
+
+    fn snippet<'a>(src: &'a str, at: &TypeAtOffset) -> &'a str {
+        &src[at.span().offset()..at.span().offset() + at.span().len()]
+    }
+
+    #[test]
+    fn finds_policy_containing_offset() {
+        let src = r#"
+            permit(principal is User, action, resource) when { resource.owner.name == "a" };
+            forbid(principal is Admin, action, resource) when { principal.name == "b" };
+        "#;
+        let at = type_at(src, r#"principal.name == "b""#).unwrap();
+        assert_eq!(snippet(src, &at), "principal");
+        let types = at
+            .types()
+            .map(|(env, ty)| (env.principal().to_string(), ty.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(types, vec![("Admin".to_string(), "Admin".to_string())]);
+
+        let at = type_at(src, "resource.owner").unwrap();
+        assert_eq!(snippet(src, &at), "resource");
+        let types = at
+            .schema_types()
+            .map(|(_, ty)| ty.cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![Some(SchemaType::Entity("Photo".parse().unwrap()))]
+        );
+    }
+
+    #[test]
+    fn type_per_request_env() {
+        let src = r"permit(principal, action, resource) when { principal has name };";
+        let at = type_at(src, "principal has").unwrap();
+        assert_eq!(snippet(src, &at), "principal");
+        let mut principals = at
+            .types()
+            .map(|(env, ty)| {
+                assert_eq!(env.principal().to_string(), ty);
+                env.principal().to_string()
+            })
+            .collect::<Vec<_>>();
+        principals.sort();
+        assert_eq!(principals, vec!["Admin", "User"]);
+    }
+
+    #[test]
+    fn no_expression() {
+        let src = "permit(principal, action, resource);\n\n// comment";
+        assert_matches!(type_at(src, "comment"), None);
+    }
+
+    #[test]
+    fn parse_error() {
+        assert_matches!(
+            validator().type_at_offset("permit(principal,", 0, ValidationMode::Strict),
+            Err(_)
+        );
+    }
+}
+
+mod completions_tests {
+    use super::*;
+
+    fn validator() -> Validator {
+        let schema: Schema = r"
+            entity Team;
+            entity User in Team = { name: String, manager?: User };
+            entity Photo = { owner: User };
+            action view, edit appliesTo { principal: User, resource: Photo };
+        "
+        .parse()
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[track_caller]
+    fn complete(src: &str) -> Vec<(CompletionKind, String, Option<String>)> {
+        validator()
+            .completions(src, src.len(), ValidationMode::Strict)
+            .items()
+            .map(|item| {
+                (
+                    item.kind(),
+                    item.label().to_string(),
+                    item.detail().map(ToString::to_string),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn attributes_with_types() {
+        assert_eq!(
+            complete("permit(principal, action, resource) when { resource.owner."),
+            vec![
+                (
+                    CompletionKind::Attribute,
+                    "manager".to_string(),
+                    Some("User (optional)".to_string())
+                ),
+                (
+                    CompletionKind::Attribute,
+                    "name".to_string(),
+                    Some("String".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn actions() {
+        assert_eq!(
+            complete(r#"permit(principal, action == Action::"e"#),
+            vec![(CompletionKind::Action, "edit".to_string(), None)]
+        );
+        let completions = validator().completions(
+            "permit(principal, action == ",
+            "permit(principal, action == ".len(),
+            ValidationMode::Strict,
+        );
+        assert_eq!(
+            completions
+                .items()
+                .map(Completion::label)
+                .collect::<Vec<_>>(),
+            vec![r#"Action::"edit""#, r#"Action::"view""#]
+        );
+    }
+
+    #[test]
+    fn cursor_in_middle() {
+        let src = "permit(principal is T, action, resource);";
+        let cursor = src.find(',').unwrap();
+        let completions = validator().completions(src, cursor, ValidationMode::Strict);
+        assert_eq!(completions.start(), cursor - 1);
+        assert_eq!(
+            completions
+                .items()
+                .map(Completion::label)
+                .collect::<Vec<_>>(),
+            vec!["Team"]
+        );
+    }
+}
+
+mod rename_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn schema() -> SchemaFragment {
+        SchemaFragment::from_cedarschema_str(
+            "
+            entity Team;
+            entity User in Team = { name: String };
+            entity Doc = { name: String, owner: User };
+            action view appliesTo { principal: [User, Team], resource: Doc };
+            ",
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn entity_type() {
+        let policies = r#"permit(principal is User, action, resource) when { resource.owner == User::"alice" };"#;
+        let renamed = Rename::entity_type("User".parse().unwrap(), "Member".parse().unwrap())
+            .apply(&schema(), policies)
+            .unwrap();
+        assert_eq!(
+            renamed.policies(),
+            r#"permit(principal is Member, action, resource) when { resource.owner == Member::"alice" };"#
+        );
+        assert_eq!(renamed.edits().count(), 2);
+        let schema = Schema::from_schema_fragments([renamed.schema().clone()]).unwrap();
+        assert!(schema.entity_types().any(|ty| ty.to_string() == "Member"));
+        assert!(!schema.entity_types().any(|ty| ty.to_string() == "User"));
+    }
+
+    #[test]
+    fn action() {
+        let policies = r#"permit(principal, action == Action::"view", resource);"#;
+        let renamed = Rename::action(r#"Action::"view""#.parse().unwrap(), "read")
+            .apply(&schema(), policies)
+            .unwrap();
+        assert_eq!(
+            renamed.policies(),
+            r#"permit(principal, action == Action::"read", resource);"#
+        );
+    }
+
+    #[test]
+    fn attribute_skipped() {
+        let policies = "permit(principal, action, resource) when { resource.name == (if principal is User then principal else resource).name };";
+        let renamed = Rename::attribute("User".parse().unwrap(), "name", "title")
+            .apply(&schema(), policies)
+            .unwrap();
+        assert_eq!(renamed.policies(), policies);
+        let skipped = renamed.skipped().collect::<Vec<_>>();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(
+            skipped.first().unwrap().policy_id(),
+            &PolicyId::new("policy0")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_matches!(
+            Rename::attribute("User".parse().unwrap(), "age", "years").apply(&schema(), ""),
+            Err(RenameError::UndeclaredAttribute { .. })
+        );
+        assert_matches!(
+            Rename::entity_type("User".parse().unwrap(), "Team".parse().unwrap())
+                .apply(&schema(), ""),
+            Err(RenameError::AlreadyDeclared(_))
+        );
+        assert_matches!(
+            Rename::action(r#"Action::"view""#.parse().unwrap(), "read")
+                .apply(&schema(), "permit("),
+            Err(RenameError::Parse(_))
+        );
+    }
+}
+
+mod find_references_tests {
+    use super::*;
+
+    fn validator() -> Validator {
+        let schema: Schema = r"
+            entity Team = { name: String };
+            entity User in Team = { name: String };
+            entity Doc = { owner: User };
+            action view appliesTo { principal: [User, Team], resource: Doc };
+        "
+        .parse()
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[track_caller]
+    fn references<'a>(src: &'a str, element: &SchemaElement) -> Vec<(&'a str, ReferenceKind)> {
+        validator()
+            .find_references(src, element)
+            .unwrap()
+            .iter()
+            .map(|r| {
+                let span = r.span();
+                (
+                    src.get(span.offset()..span.offset() + span.len()).unwrap(),
+                    r.kind(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn entity_types_and_actions() {
+        let src = r#"permit(principal is User, action == Action::"view", resource) when { resource.owner == User::"a" };"#;
+        assert_eq!(
+            references(src, &SchemaElement::EntityType("User".parse().unwrap())),
+            vec![
+                ("User", ReferenceKind::Definite),
+                ("User", ReferenceKind::Definite)
+            ]
+        );
+        assert_eq!(
+            references(
+                src,
+                &SchemaElement::Action(r#"Action::"view""#.parse().unwrap())
+            ),
+            vec![(r#"Action::"view""#, ReferenceKind::Definite)]
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        let src = "
+            permit(principal, action, resource) when { resource.owner.name == principal.name };
+        ";
+        let element = SchemaElement::Attribute {
+            entity_type: "User".parse().unwrap(),
+            attr: "name".to_string(),
+        };
+        assert_eq!(
+            references(src, &element),
+            vec![
+                ("name", ReferenceKind::Definite),
+                ("name", ReferenceKind::Possible)
+            ]
+        );
+        let references = validator().find_references(src, &element).unwrap();
+        assert_eq!(
+            references.first().unwrap().policy_id(),
+            &PolicyId::new("policy0")
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!(validator()
+            .find_references(
+                "permit(",
+                &SchemaElement::EntityType("User".parse().unwrap())
+            )
+            .is_err());
+    }
+}
+
+mod unused_attributes_tests {
+    use super::*;
+
+    fn validator() -> Validator {
+        let schema: Schema = r"
+            entity User = { name: String, email: String };
+            entity Doc = { owner: User };
+            action view appliesTo {
+                principal: User,
+                resource: Doc,
+                context: { ip: String, mfa: Bool }
+            };
+        "
+        .parse()
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    #[test]
+    fn entity_and_context_attributes() {
+        let pset: PolicySet = r"
+            permit(principal, action, resource)
+            when { resource.owner.name == principal.email && context.mfa };
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(
+            validator().unused_attributes(&pset),
+            vec![UnusedAttribute::Context {
+                action: r#"Action::"view""#.parse().unwrap(),
+                attr: "ip".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn templates() {
+        let mut pset = PolicySet::new();
+        pset.add_template(
+            Template::parse(
+                Some(PolicyId::new("t")),
+                "permit(principal == ?principal, action, resource) when { resource has owner };",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let unused = validator().unused_attributes(&pset);
+        assert!(!unused.contains(&UnusedAttribute::Entity {
+            entity_type: "Doc".parse().unwrap(),
+            attr: "owner".to_string(),
+        }));
+        assert_eq!(unused.len(), 4);
+    }
+}
+
+mod policy_docs_tests {
+    use super::*;
+
+    fn pset() -> PolicySet {
+        let mut pset: PolicySet = r#"
+            @doc("Owners may edit their photos")
+            @owner("photos-team")
+            permit(principal, action == Action::"edit", resource is Photo)
+            when { resource.owner == principal }
+            unless { resource.locked };
+
+            @id("admins")
+            permit(principal in Group::"admins", action, resource);
+
+            @owner("devices-team")
+            forbid(principal, action, resource == Device::"<lab>");
+
+            permit(principal == ?principal, action == Action::"view", resource in ?resource);
+        "#
+        .parse()
+        .unwrap();
+        pset.link(
+            PolicyId::new("policy3"),
+            PolicyId::new("link"),
+            HashMap::from([
+                (SlotId::principal(), r#"User::"alice""#.parse().unwrap()),
+                (SlotId::resource(), r#"Album::"a""#.parse().unwrap()),
+            ]),
+        )
+        .unwrap();
+        pset
+    }
+
+    #[test]
+    fn markdown_by_resource_type() {
+        let markdown = PolicyDocs::new(&pset()).to_markdown();
+        assert_eq!(
+            markdown,
+            r#"# Policies
+
+## Resource type `Device`
+
+### `policy2` (forbid policy)
+
+- **Principal:** `principal`
+- **Action:** `action`
+- **Resource:** `resource == Device::"<lab>"`
+- **Annotations:** `@owner("devices-team")`
+
+## Resource type `Photo`
+
+### `policy0` (permit policy)
+
+Owners may edit their photos
+
+- **Principal:** `principal`
+- **Action:** `action == Action::"edit"`
+- **Resource:** `resource is Photo`
+- **Annotations:** `@owner("photos-team")`
+
+**Conditions:**
+
+```cedar
+when { (resource["owner"]) == principal }
+unless { resource["locked"] }
+```
+
+## Any resource type
+
+### `policy1` (permit policy)
+
+- **Principal:** `principal in Group::"admins"`
+- **Action:** `action`
+- **Resource:** `resource`
+- **Annotations:** `@id("admins")`
+
+### `policy3` (permit template)
+
+- **Principal:** `principal == ?principal`
+- **Action:** `action == Action::"view"`
+- **Resource:** `resource in ?resource`
+- **Linked policies:** `link`
+"#
+        );
+    }
+
+    #[test]
+    fn grouped_with_schema() {
+        let schema: Schema = r"
+            entity Group;
+            entity User in Group;
+            entity Album;
+            entity Photo in Album = { owner: User, locked: Bool };
+            entity Device;
+            action view appliesTo { principal: User, resource: [Album, Photo] };
+            action edit appliesTo { principal: User, resource: Photo };
+            action reboot appliesTo { principal: User, resource: Device };
+        "
+        .parse()
+        .unwrap();
+        let pset = pset();
+        let markdown = PolicyDocs::new(&pset).with_schema(&schema).to_markdown();
+        let sections = markdown
+            .lines()
+            .filter(|line| line.starts_with("##"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            vec![
+                "## Resource type `Album`",
+                "### `policy1` (permit policy)",
+                "### `policy3` (permit template)",
+                "## Resource type `Device`",
+                "### `policy1` (permit policy)",
+                "### `policy2` (forbid policy)",
+                "## Resource type `Photo`",
+                "### `policy0` (permit policy)",
+                "### `policy1` (permit policy)",
+                "### `policy3` (permit template)",
+            ]
+        );
+    }
+
+    #[test]
+    fn html_by_annotation() {
+        let html = PolicyDocs::new(&pset())
+            .with_grouping(PolicyDocsGrouping::Annotation("owner".to_string()))
+            .with_doc_annotation("id")
+            .to_html();
+        let headings = html
+            .lines()
+            .filter(|line| line.starts_with("<h"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headings,
+            vec![
+                "<h1>Policies</h1>",
+                r#"<h2><code>@owner("devices-team")</code></h2>"#,
+                "<h3><code>policy2</code> (forbid policy)</h3>",
+                r#"<h2><code>@owner("photos-team")</code></h2>"#,
+                "<h3><code>policy0</code> (permit policy)</h3>",
+                "<h2>Without <code>@owner</code></h2>",
+                "<h3><code>policy1</code> (permit policy)</h3>",
+                "<h3><code>policy3</code> (permit template)</h3>",
+            ]
+        );
+        assert!(html.contains("<p>admins</p>"));
+        assert!(html.contains(
+            "<dt>Resource</dt><dd><code>resource == Device::&quot;&lt;lab&gt;&quot;</code></dd>"
+        ));
+        assert!(html.contains("<dt>Linked policy</dt><dd><code>link</code></dd>"));
+    }
+}
+
+mod dependency_graph_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn dependencies_and_dependents() {
+        let schema: Schema = r"
+            entity User = { name: String, manager: User };
+            entity Photo = { owner: User };
+            action view appliesTo {
+                principal: User,
+                resource: Photo,
+                context: { ip: ipaddr }
+            };
+        "
+        .parse()
+        .unwrap();
+        let pset: PolicySet = r#"
+            permit(principal, action == Action::"view", resource)
+            when { resource.owner == principal && context.ip.isLoopback() };
+            forbid(principal == ?principal, action, resource is Photo);
+        "#
+        .parse()
+        .unwrap();
+        let graph = Validator::new(schema).dependency_graph(&pset);
+        assert_eq!(
+            graph.policies().collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy0"), &PolicyId::new("policy1")]
+        );
+        assert_eq!(
+            graph
+                .dependencies(&PolicyId::new("policy0"))
+                .collect::<Vec<_>>(),
+            vec![
+                Dependency::Action(r#"Action::"view""#.parse().unwrap()),
+                Dependency::Attribute {
+                    entity_type: "Photo".parse().unwrap(),
+                    attr: "owner".into(),
+                },
+                Dependency::ContextAttribute {
+                    action: r#"Action::"view""#.parse().unwrap(),
+                    attr: "ip".into(),
+                },
+                Dependency::ExtensionFunction("isLoopback".into()),
+            ]
+        );
+        let photo = Dependency::EntityType("Photo".parse().unwrap());
+        assert_eq!(
+            graph.dependents(&photo).collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy1")]
+        );
+        assert_eq!(
+            graph.to_json(),
+            json!({
+                "policy0": [
+                    { "kind": "action", "action": r#"Action::"view""# },
+                    { "kind": "attribute", "entityType": "Photo", "attr": "owner" },
+                    { "kind": "contextAttribute", "action": r#"Action::"view""#, "attr": "ip" },
+                    { "kind": "extensionFunction", "function": "isLoopback" },
+                ],
+                "policy1": [{ "kind": "entityType", "entityType": "Photo" }],
+            })
+        );
+    }
+}
+
+mod policy_inventory_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pset() -> PolicySet {
+        let mut pset: PolicySet = r#"
+            @owner("photos-team")
+            permit(principal in Group::"admins", action in [Action::"view", Action::"edit"], resource)
+            when { resource.owner == principal || context.mfa }
+            unless { resource has locked && resource.locked };
+
+            forbid(principal == ?principal, action, resource is Photo in ?resource);
+        "#
+        .parse()
+        .unwrap();
+        pset.link(
+            PolicyId::new("policy1"),
+            PolicyId::new("link"),
+            HashMap::from([
+                (SlotId::principal(), r#"User::"alice""#.parse().unwrap()),
+                (SlotId::resource(), r#"Album::"trip""#.parse().unwrap()),
+            ]),
+        )
+        .unwrap();
+        pset
+    }
+
+    #[test]
+    fn entries() {
+        let inventory = PolicyInventory::new(&pset());
+        let entries = inventory.entries().collect::<Vec<_>>();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.id().to_string(), entry.kind(), entry.effect()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "link".to_string(),
+                    PolicyInventoryKind::TemplateLinked,
+                    Effect::Forbid
+                ),
+                (
+                    "policy0".to_string(),
+                    PolicyInventoryKind::Static,
+                    Effect::Permit
+                ),
+                (
+                    "policy1".to_string(),
+                    PolicyInventoryKind::Template,
+                    Effect::Forbid
+                ),
+            ]
+        );
+        let [link, policy, template] = entries.as_slice() else {
+            panic!("expected three entries");
+        };
+
+        assert_eq!(
+            policy.annotations().collect::<Vec<_>>(),
+            vec![("owner", "photos-team")]
+        );
+        assert_eq!(
+            policy
+                .entity_types()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["Group"]
+        );
+        assert_eq!(
+            policy
+                .actions()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![r#"Action::"edit""#, r#"Action::"view""#]
+        );
+        let complexity = policy.complexity();
+        assert_eq!(complexity.conditions(), 2);
+        assert_eq!(complexity.size(), 14);
+        assert_eq!(complexity.depth(), 5);
+
+        assert_eq!(template.complexity().conditions(), 0);
+        assert_eq!(template.complexity().size(), 0);
+        assert_eq!(
+            template
+                .entity_types()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["Photo"]
+        );
+        assert_eq!(link.template_id(), Some(&PolicyId::new("policy1")));
+        assert_eq!(
+            link.entity_types()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["Album", "Photo", "User"]
+        );
+    }
+
+    #[test]
+    fn hashes() {
+        let inventory = PolicyInventory::new(&pset());
+        let hashes = inventory
+            .entries()
+            .map(|entry| entry.hash().to_string())
+            .collect::<Vec<_>>();
+        assert!(hashes.iter().all(|hash| hash.len() == 64));
+        // The linked policy differs from its template by its linked values
+        assert_ne!(hashes.first(), hashes.last());
+
+        // The hash does not depend on the id or the formatting of the policy,
+        // but does on its contents
+        let reformatted: PolicySet = r#"
+            // Owned by the photos team
+            @owner("photos-team") permit(
+                principal in Group::"admins",
+                action in [Action::"view", Action::"edit"],
+                resource
+            ) when { resource.owner == principal || context.mfa } unless { resource has locked && resource.locked };
+            @owner("photos-team")
+            permit(principal in Group::"admins", action in [Action::"view", Action::"edit"], resource)
+            when { resource.owner == principal || context.mfa };
+        "#
+        .parse()
+        .unwrap();
+        let reformatted = PolicyInventory::new(&reformatted);
+        let reformatted = reformatted
+            .entries()
+            .map(|entry| entry.hash().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(reformatted.first(), hashes.get(1));
+        assert_ne!(reformatted.get(1), hashes.get(1));
+    }
+
+    #[test]
+    fn json() {
+        let json = PolicyInventory::new(&pset()).to_json();
+        let Some([link, policy, _]) = json
+            .get("policies")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+        else {
+            panic!("expected three entries");
+        };
+        assert_eq!(link.get("kind"), Some(&json!("templateLinked")));
+        assert_eq!(link.get("template"), Some(&json!("policy1")));
+        assert_eq!(
+            link.get("links"),
+            Some(&json!({ "?principal": r#"User::"alice""#, "?resource": r#"Album::"trip""# }))
+        );
+        assert_eq!(
+            link.get("scope"),
+            Some(&json!({
+                "principal": r#"principal == User::"alice""#,
+                "action": "action",
+                "resource": r#"resource is Photo in Album::"trip""#,
+            }))
+        );
+        assert_eq!(policy.get("kind"), Some(&json!("static")));
+        assert_eq!(policy.get("template"), None);
+        assert_eq!(
+            policy.get("actions"),
+            Some(&json!([r#"Action::"edit""#, r#"Action::"view""#]))
+        );
+        assert_eq!(
+            policy.get("complexity"),
+            Some(&json!({ "conditions": 2, "size": 14, "depth": 5 }))
+        );
+    }
+}
+
+#[cfg(feature = "permissive-validate")]
+mod validation_mode_comparison_tests {
+    use super::*;
+
+    #[test]
+    fn strict_only_errors() {
+        let schema: Schema = r"
+            entity User = { age: Long };
+            entity Admin;
+            entity Photo;
+            action view appliesTo { principal: [User, Admin], resource: Photo };
+        "
+        .parse()
+        .unwrap();
+        let pset: PolicySet = r#"
+            permit(principal, action, resource) when { [User::"a", Admin::"b"].contains(principal) };
+            permit(principal is User, action, resource) when { principal.age > 18 };
+            permit(principal, action, resource) when { resource.missing };
+            permit(principal, action, resource)
+            when { resource.missing && [User::"a", Admin::"b"].contains(principal) };
+        "#
+        .parse()
+        .unwrap();
+        let comparison = Validator::new(schema).compare_validation_modes(&pset);
+        assert!(!comparison.modes_agree());
+        // `policy1` passes both modes, and `policy2` fails both with the
+        // same error
+        assert_eq!(
+            comparison
+                .policies()
+                .map(|p| (
+                    p.id().to_string(),
+                    p.passes_strict(),
+                    p.passes_permissive(),
+                    p.strict_only_errors().count(),
+                    p.permissive_only_errors().count()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("policy0".to_string(), false, true, 1, 0),
+                ("policy3".to_string(), false, false, 1, 0),
+            ]
+        );
+        assert_eq!(
+            comparison.failing_only_strict().collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy0")]
+        );
+        assert_eq!(comparison.failing_only_permissive().count(), 0);
+    }
+
+    #[test]
+    fn modes_agree() {
+        let schema: Schema = "entity User; entity Photo; action view appliesTo { principal: User, resource: Photo };"
+            .parse()
+            .unwrap();
+        let pset: PolicySet = "permit(principal, action, resource) when { resource.missing };"
+            .parse()
+            .unwrap();
+        let comparison = Validator::new(schema).compare_validation_modes(&pset);
+        assert!(comparison.modes_agree());
+        assert_eq!(comparison.policies().count(), 0);
+    }
+}
+
+mod validation_profile_tests {
+    use super::*;
+
+    #[test]
+    fn profiles_every_policy_and_template() {
+        let schema: Schema = "
+            entity User;
+            entity Photo;
+            action view, edit appliesTo { principal: User, resource: Photo };
+        "
+        .parse()
+        .unwrap();
+        let pset = PolicySet::from_str_with_provenance(
+            "
+            permit(principal, action, resource);
+            permit(principal == ?principal, action, resource);
+            permit(principal, action, resource) when { principal.missing };
+            ",
+            Provenance::new().with_file("policies.cedar"),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let (result, profiles) = validator.validate_with_profile(&pset, ValidationMode::Strict);
+        assert_eq!(
+            result.validation_errors().count(),
+            validator
+                .validate(&pset, ValidationMode::Strict)
+                .validation_errors()
+                .count()
+        );
+        assert!(result.validation_errors().all(|e| result
+            .provenance(e.policy_id())
+            .and_then(Provenance::file)
+            == Some("policies.cedar")));
+
+        let mut ids = profiles
+            .iter()
+            .map(|p| p.policy_id().to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec!["policy0", "policy1", "policy2"]);
+        assert!(profiles
+            .iter()
+            .all(|p| p.request_envs() == 2 && p.expressions() > 0));
+        assert!(profiles
+            .windows(2)
+            .all(|w| matches!(w, [a, b] if a.duration() >= b.duration())));
+    }
+}
+
+mod qualify_names_tests {
+    use super::*;
+
+    #[test]
+    fn qualifies_names_and_reports_ambiguous_ones() {
+        let schema: Schema = "
+            namespace Acme::Photos {
+                entity User;
+                entity Photo;
+                action view appliesTo { principal: User, resource: Photo };
+            }
+            namespace Acme::Docs {
+                entity User;
+                entity Doc;
+                action view appliesTo { principal: User, resource: Doc };
+            }
+        "
+        .parse()
+        .unwrap();
+        let policies = r#"// Photos
+permit(principal, action == Photos::Action::"view", resource is Photo);
+permit(principal is User, action, resource);"#;
+        let qualified = Validator::new(schema).qualify_names(policies).unwrap();
+        assert_eq!(
+            qualified.policies(),
+            r#"// Photos
+permit(principal, action == Acme::Photos::Action::"view", resource is Acme::Photos::Photo);
+permit(principal is User, action, resource);"#
+        );
+        assert_eq!(qualified.edits().count(), 2);
+        match qualified.skipped().collect::<Vec<_>>().as_slice() {
+            [skipped] => {
+                assert_eq!(skipped.policy_id(), &PolicyId::new("policy1"));
+                assert_eq!(
+                    skipped.reason(),
+                    "`User` could refer to any of `Acme::Docs::User`, `Acme::Photos::User`"
+                );
+            }
+            skipped => panic!("expected one skipped reference, got {skipped:?}"),
+        }
+    }
+}
+
+mod csv_entities_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        "
+        entity Group;
+        entity User in [Group] = {
+            age: Long,
+            admin: Bool,
+            manager?: User,
+            ip?: __cedar::ipaddr,
+            roles: Set<String>,
+            address?: { city: String },
+        };
+        "
+        .parse()
+        .unwrap()
+    }
+
+    fn mapping() -> CsvMapping {
+        let mut mapping = CsvMapping::new("id");
+        mapping.type_column = Some("type".into());
+        for attr in ["age", "admin", "manager", "ip", "roles", "address"] {
+            mapping.attributes.insert(attr.into(), attr.into());
+        }
+        mapping.parents.push(CsvParentColumn {
+            column: "groups".into(),
+            entity_type: Some("Group".into()),
+        });
+        mapping
+    }
+
+    #[track_caller]
+    fn errors(src: &str) -> Vec<(Option<usize>, Option<String>)> {
+        match Entities::from_csv_str(src, &mapping(), Some(&schema())) {
+            Err(EntitiesCsvError::Csv(errs)) => errs
+                .iter()
+                .map(|e| (e.row(), e.column().map(str::to_string)))
+                .collect(),
+            result => panic!("expected CSV errors, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn converts_cells_to_schema_types() {
+        let entities = Entities::from_csv_str(
+            r#"type,id,age,admin,manager,ip,roles,address,groups
+User,alice,42,TRUE,"User::""carol""",10.0.0.1,eng; ops,"{""city"": ""Seattle""}",admins;staff
+User,bob,37,false,alice,,,,
+Group,admins,,,,,,,
+"#,
+            &mapping(),
+            Some(&schema()),
+        )
+        .unwrap();
+        let alice = entities
+            .get(&EntityUid::from_str(r#"User::"alice""#).unwrap())
+            .unwrap();
+        let attr = |entity: &Entity, attr: &str| entity.attr(attr).map(|v| v.unwrap().to_string());
+        assert_eq!(attr(alice, "age").as_deref(), Some("42"));
+        assert_eq!(attr(alice, "admin").as_deref(), Some("true"));
+        assert_eq!(attr(alice, "manager").as_deref(), Some(r#"User::"carol""#));
+        assert_eq!(attr(alice, "ip").as_deref(), Some(r#"ip("10.0.0.1")"#));
+        assert_eq!(attr(alice, "roles").as_deref(), Some(r#"["eng", "ops"]"#));
+        assert_eq!(
+            attr(alice, "address").as_deref(),
+            Some(r#"{"city": "Seattle"}"#)
+        );
+        assert!(entities.is_ancestor_of(
+            &EntityUid::from_str(r#"Group::"staff""#).unwrap(),
+            &EntityUid::from_str(r#"User::"alice""#).unwrap()
+        ));
+
+        let bob = entities
+            .get(&EntityUid::from_str(r#"User::"bob""#).unwrap())
+            .unwrap();
+        assert_eq!(attr(bob, "manager").as_deref(), Some(r#"User::"alice""#));
+        assert_eq!(attr(bob, "roles").as_deref(), Some("[]"));
+        assert_eq!(attr(bob, "ip"), None);
+    }
+
+    #[test]
+    fn reports_row_and_column_of_errors() {
+        assert_eq!(
+            errors(
+                "type,id,age,admin,manager,ip,roles,address,groups
+User,alice,old,yes,,not an ip,,,
+Robot,r2,,,,,,,
+User,bob,,true,,,,,
+"
+            ),
+            vec![
+                (Some(2), Some("admin".into())),
+                (Some(2), Some("age".into())),
+                (Some(2), Some("ip".into())),
+                (Some(3), Some("type".into())),
+                // `age` is required
+                (Some(4), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn loads_tsv_without_schema() {
+        let mut mapping = CsvMapping::new("id");
+        mapping.entity_type = Some("User".into());
+        mapping.attributes.insert("age".into(), "age".into());
+        mapping.delimiter = '\t';
+        let entities = Entities::from_csv_str("id\tage\nalice\t42\n", &mapping, None).unwrap();
+        let alice = entities
+            .get(&EntityUid::from_str(r#"User::"alice""#).unwrap())
+            .unwrap();
+        assert_matches!(alice.attr("age"), Some(Ok(EvalResult::String(s))) => {
+            assert_eq!(s, "42");
+        });
+    }
+
+    #[test]
+    fn mapping_from_json() {
+        let mapping: CsvMapping = serde_json::from_value(json!({
+            "typeColumn": "type",
+            "idColumn": "id",
+            "attributes": { "years": "age" },
+            "parents": [{ "column": "groups", "entityType": "Group" }],
+            "delimiter": "\t",
+        }))
+        .unwrap();
+        assert_eq!(mapping.type_column.as_deref(), Some("type"));
+        assert_eq!(mapping.delimiter, '\t');
+        assert_eq!(mapping.set_separator, ';');
+        assert!(
+            serde_json::from_value::<CsvMapping>(json!({ "idColumn": "id", "extra": 1 })).is_err()
+        );
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod arrow_entities_tests {
+    use super::*;
+    use arrow_array::builder::{ListBuilder, StringBuilder};
+    use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+    use std::sync::Arc;
+
+    fn schema() -> Schema {
+        "
+        entity User = {
+            age: Long,
+            manager?: User,
+            ip?: __cedar::ipaddr,
+            roles: Set<String>,
+        };
+        "
+        .parse()
+        .unwrap()
+    }
+
+    fn mapping() -> CsvMapping {
+        let mut mapping = CsvMapping::new("id");
+        mapping.entity_type = Some("User".into());
+        for attr in ["age", "manager", "ip", "roles"] {
+            mapping.attributes.insert(attr.into(), attr.into());
+        }
+        mapping
+    }
+
+    fn batch(ages: Vec<Option<i64>>) -> RecordBatch {
+        let mut roles = ListBuilder::new(StringBuilder::new());
+        roles.values().append_value("eng");
+        roles.values().append_value("ops");
+        roles.append(true);
+        roles.append(true);
+        RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(StringArray::from(vec!["alice", "bob"])) as ArrayRef,
+            ),
+            ("age", Arc::new(Int64Array::from(ages))),
+            (
+                "manager",
+                Arc::new(StringArray::from(vec![None, Some("alice")])),
+            ),
+            (
+                "ip",
+                Arc::new(StringArray::from(vec![Some("10.0.0.1"), None])),
+            ),
+            ("roles", Arc::new(roles.finish())),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn converts_columns_to_schema_types() {
+        let entities = Entities::from_record_batches(
+            &[batch(vec![Some(42), Some(37)])],
+            &mapping(),
+            Some(&schema()),
+        )
+        .unwrap();
+        let attr = |uid: &str, attr: &str| {
+            entities
+                .get(&EntityUid::from_str(uid).unwrap())
+                .and_then(|e| e.attr(attr))
+                .map(|v| v.unwrap().to_string())
+        };
+        assert_eq!(attr(r#"User::"alice""#, "age").as_deref(), Some("42"));
+        assert_eq!(
+            attr(r#"User::"alice""#, "ip").as_deref(),
+            Some(r#"ip("10.0.0.1")"#)
+        );
+        assert_eq!(
+            attr(r#"User::"alice""#, "roles").as_deref(),
+            Some(r#"["eng", "ops"]"#)
+        );
+        assert_eq!(
+            attr(r#"User::"bob""#, "manager").as_deref(),
+            Some(r#"User::"alice""#)
+        );
+        assert_eq!(attr(r#"User::"bob""#, "roles").as_deref(), Some("[]"));
+    }
+
+    #[test]
+    fn reports_rows_of_errors() {
+        match Entities::from_record_batches(
+            &[batch(vec![Some(42), None])],
+            &mapping(),
+            Some(&schema()),
+        ) {
+            Err(EntitiesCsvError::Csv(errs)) => {
+                assert_eq!(
+                    errs.iter().map(CsvError::row).collect::<Vec<_>>(),
+                    vec![Some(2)]
+                );
+            }
+            result => panic!("expected CSV errors, got {result:?}"),
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn reads_parquet() {
+        let batch = batch(vec![Some(42), Some(37)]);
+        let path = std::env::temp_dir().join(format!(
+            "cedar-policy-entities-{}.parquet",
+            std::process::id()
+        ));
+        let mut writer = parquet::arrow::ArrowWriter::try_new(
+            std::fs::File::create(&path).unwrap(),
+            batch.schema(),
+            None,
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let entities = Entities::from_parquet(
+            std::fs::File::open(&path).unwrap(),
+            &mapping(),
+            Some(&schema()),
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(entities.unwrap().iter().count(), 2);
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod batch_authorization_tests {
+    use super::*;
+    use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray};
+    use std::sync::Arc;
+
+    fn schema() -> Schema {
+        "
+        entity User;
+        entity Doc = { owner: User };
+        action view, edit appliesTo { principal: User, resource: Doc, context: { level: Long } };
+        "
+        .parse()
+        .unwrap()
+    }
+
+    fn policies() -> PolicySet {
+        r#"
+        permit(principal, action == Action::"view", resource) when { context.level > 1 };
+        permit(principal, action == Action::"edit", resource) when { resource.owner == principal };
+        "#
+        .parse()
+        .unwrap()
+    }
+
+    fn entities() -> Entities {
+        Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Doc", "id": "plan" }, "attrs": { "owner": { "__entity": { "type": "User", "id": "alice" } } }, "parents": [] }
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn columns() -> RequestColumns {
+        let mut columns = RequestColumns::new("user", "action", "doc");
+        columns.principal_type = Some("User".into());
+        columns.resource_type = Some("Doc".into());
+        columns.context.insert("level".into(), "level".into());
+        columns
+    }
+
+    fn batch(actions: Vec<Option<&str>>, levels: Vec<Option<i64>>) -> RecordBatch {
+        let users = vec!["alice"; actions.len()];
+        let docs = vec!["plan"; actions.len()];
+        RecordBatch::try_from_iter([
+            ("user", Arc::new(StringArray::from(users)) as ArrayRef),
+            ("action", Arc::new(StringArray::from(actions))),
+            ("doc", Arc::new(StringArray::from(docs))),
+            ("level", Arc::new(Int64Array::from(levels))),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn authorizes_every_row() {
+        let batch = batch(
+            vec![
+                Some(r#"Action::"view""#),
+                Some(r#"Action::"view""#),
+                Some(r#"Action::"edit""#),
+                Some(r#"Action::"view""#),
+                None,
+                Some(r#"Action::"view""#),
+            ],
+            vec![Some(2), Some(1), Some(0), Some(2), Some(2), None],
+        );
+        let result = Authorizer::new()
+            .is_authorized_batch(
+                &batch,
+                &columns(),
+                &policies(),
+                &entities(),
+                Some(&schema()),
+            )
+            .unwrap();
+        assert_eq!(
+            result.decisions(),
+            &BooleanArray::from(vec![
+                Some(true),
+                Some(false),
+                Some(true),
+                Some(true),
+                None,
+                None
+            ])
+        );
+        assert_eq!(
+            result
+                .errors()
+                .iter()
+                .map(BatchRequestError::row)
+                .collect::<Vec<_>>(),
+            vec![5, 6]
+        );
+        let [missing, invalid] = result.errors() else {
+            panic!("expected two errors, got {:?}", result.errors());
+        };
+        assert_eq!(missing.message(), "missing value in column `action`");
+        assert!(invalid.message().starts_with("invalid context"));
+        assert_eq!(result.distinct_requests(), 3);
+    }
+
+    #[test]
+    fn authorizes_without_schema_or_slicing() {
+        let mut policies = policies();
+        policies
+            .add_template(
+                Template::parse(
+                    Some(PolicyId::new("t")),
+                    "permit(principal == ?principal, action, resource);",
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        let batch = batch(
+            vec![Some(r#"Action::"view""#), Some(r#"Action::"edit""#)],
+            vec![Some(0), None],
+        );
+        let result = Authorizer::new()
+            .is_authorized_batch(&batch, &columns(), &policies, &entities(), None)
+            .unwrap();
+        assert_eq!(result.decisions(), &BooleanArray::from(vec![false, true]));
+        assert!(result.errors().is_empty());
+    }
+
+    #[test]
+    fn missing_column_is_an_error() {
+        let mut columns = columns();
+        columns
+            .context
+            .insert("clearance".into(), "clearance".into());
+        let err = Authorizer::new()
+            .is_authorized_batch(
+                &batch(vec![], vec![]),
+                &columns,
+                &policies(),
+                &entities(),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err.column(), "clearance");
+        assert_eq!(
+            err.to_string(),
+            "column `clearance`: no such column in the batch"
+        );
+    }
+}
+
+#[cfg(feature = "yaml")]
+mod yaml_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    const SCHEMA: &str = "
+Docs:
+  entityTypes:
+    User:
+      shape:
+        type: Record
+        attributes:
+          level: { type: Long }
+    Doc: {}
+  actions:
+    # Every action on documents applies to the same types
+    view: &doc_action
+      appliesTo:
+        principalTypes: [User]
+        resourceTypes: [Doc]
+    edit:
+      <<: *doc_action
+      memberOf: [{ id: view }]
+";
+
+    #[test]
+    fn schema_from_yaml() {
+        let schema = Schema::from_yaml_str(SCHEMA).unwrap();
+        let edit = EntityUid::from_str(r#"Docs::Action::"edit""#).unwrap();
+        assert_eq!(
+            schema
+                .principals_for_action(&edit)
+                .unwrap()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["Docs::User"]
+        );
+        assert_eq!(
+            SchemaFragment::from_yaml_file(SCHEMA.as_bytes())
+                .unwrap()
+                .namespaces()
+                .count(),
+            1
+        );
+
+        // The document must have the structure of the JSON schema format
+        assert_matches!(
+            Schema::from_yaml_str("Docs: { entityTypes: {}, actions: {}, types: [] }"),
+            Err(SchemaYamlError::Schema(_))
+        );
+    }
+
+    #[test]
+    fn entities_from_yaml() {
+        let schema = Schema::from_yaml_str(SCHEMA).unwrap();
+        let src = "
+- uid: { type: Docs::User, id: alice }
+  attrs: { level: 3 }  # comments are allowed
+  parents: []
+";
+        let entities = Entities::from_yaml_str(src, Some(&schema)).unwrap();
+        assert_eq!(
+            entities,
+            Entities::from_json_value(
+                json!([{ "uid": { "type": "Docs::User", "id": "alice" }, "attrs": { "level": 3 }, "parents": [] }]),
+                Some(&schema),
+            )
+            .unwrap()
+        );
+
+        let src = "
+- uid: { type: Docs::User, id: alice }
+  attrs: { level: high }
+  parents: []
+";
+        assert_matches!(
+            Entities::from_yaml_file(src.as_bytes(), Some(&schema)),
+            Err(EntitiesYamlError::Entities(_))
+        );
+    }
+
+    #[test]
+    fn invalid_yaml() {
+        assert_matches!(
+            Entities::from_yaml_str("- uid: [", None),
+            Err(EntitiesYamlError::Yaml(e)) => {
+                assert!(e.message().contains("at line 2 column 1"), "{}", e.message());
+            }
+        );
+    }
+}
+
+mod containment_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        "
+        entity Team;
+        entity User in [Team];
+        entity Doc in [Team];
+        action view, edit appliesTo { principal: User, resource: Doc, context: { mfa: Bool } };
+        "
+        .parse()
+        .unwrap()
+    }
+
+    fn entities(schema: &Schema) -> Entities {
+        Entities::from_json_value(
+            json!([
+                { "uid": { "type": "Team", "id": "docs" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Team", "id": "docs" }] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Doc", "id": "plan" }, "attrs": {}, "parents": [{ "type": "Team", "id": "docs" }] },
+                { "uid": { "type": "Doc", "id": "memo" }, "attrs": {}, "parents": [] }
+            ]),
+            Some(schema),
+        )
+        .unwrap()
+    }
+
+    /// The docs team may grant any access to their own documents, and only
+    /// with MFA
+    fn boundary() -> PolicySet {
+        r#"
+        permit(principal, action, resource in Team::"docs") when { context.mfa };
+        "#
+        .parse()
+        .unwrap()
+    }
+
+    fn contexts(check: ContainmentCheck<'_>) -> ContainmentCheck<'_> {
+        let contexts = || {
+            [true, false].map(|mfa| {
+                Context::from_pairs([("mfa".into(), RestrictedExpression::new_bool(mfa))]).unwrap()
+            })
+        };
+        check
+            .with_contexts(
+                EntityUid::from_str(r#"Action::"view""#).unwrap(),
+                contexts(),
+            )
+            .with_contexts(
+                EntityUid::from_str(r#"Action::"edit""#).unwrap(),
+                contexts(),
+            )
+    }
+
+    #[test]
+    fn contained_policies() {
+        let schema = schema();
+        let entities = entities(&schema);
+        let policies: PolicySet = r#"
+        permit(principal in Team::"docs", action == Action::"view", resource in Team::"docs") when { context.mfa };
+        forbid(principal, action, resource) unless { context.mfa };
+        permit(principal == User::"bob", action, resource == Doc::"plan");
+        "#
+        .parse()
+        .unwrap();
+        let result =
+            contexts(ContainmentCheck::new(&schema, &entities)).check(&policies, &boundary());
+        assert!(result.is_contained());
+        // 2 actions, 2 users, 2 documents, and 2 contexts
+        assert_eq!(result.requests_checked(), 16);
+        assert_eq!(result.invalid_requests(), 0);
+    }
+
+    #[test]
+    fn counterexamples() {
+        let schema = schema();
+        let entities = entities(&schema);
+        let policies: PolicySet = r#"
+        permit(principal, action == Action::"edit", resource) when { context.mfa };
+        "#
+        .parse()
+        .unwrap();
+        let result =
+            contexts(ContainmentCheck::new(&schema, &entities)).check(&policies, &boundary());
+        assert!(!result.is_contained());
+        let counterexamples = result
+            .counterexamples()
+            .map(|c| {
+                (
+                    c.request().principal().unwrap().to_string(),
+                    c.request().resource().unwrap().to_string(),
+                    c.policies().map(ToString::to_string).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            counterexamples,
+            [
+                (r#"User::"alice""#, r#"Doc::"memo""#),
+                (r#"User::"bob""#, r#"Doc::"memo""#)
+            ]
+            .into_iter()
+            .map(|(p, r)| (p.to_string(), r.to_string(), vec!["policy0".to_string()]))
+            .collect()
+        );
+
+        let result = contexts(ContainmentCheck::new(&schema, &entities))
+            .with_max_counterexamples(1)
+            .check(&policies, &boundary());
+        assert_eq!(result.counterexamples().count(), 1);
+    }
+
+    #[test]
+    fn invalid_requests_are_skipped() {
+        let schema = schema();
+        let entities = entities(&schema);
+        // Without contexts, requests have the empty context, which doesn't
+        // have the `mfa` attribute the schema requires
+        let result =
+            ContainmentCheck::new(&schema, &entities).check(&PolicySet::new(), &boundary());
+        assert!(result.is_contained());
+        assert_eq!(result.requests_checked(), 0);
+        assert_eq!(result.invalid_requests(), 8);
+        assert_eq!(result.invalid_actions().count(), 2);
+    }
+}
+
+mod decision_cache_key_tests {
+    use super::*;
+
+    fn request(context: Context) -> Request {
+        Request::new(
+            EntityUid::from_str(r#"User::"alice""#).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(r#"Photo::"trip""#).unwrap(),
+            context,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn context(pairs: &[(&str, &str)]) -> Context {
+        Context::from_pairs(
+            pairs
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), RestrictedExpression::from_str(v).unwrap())),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn canonical_form() {
+        let entities = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "b": 1, "a": [3, 2] }, "parents": [{ "type": "Group", "id": "z" }, { "type": "Group", "id": "a" }] }
+            ]"#,
+            None,
+        )
+        .unwrap();
+        let request = request(context(&[("ip", r#"ip("10.0.0.1")"#), ("mfa", "true")]));
+        assert_eq!(
+            DecisionCacheKey::canonical_form(&request, &entities, "v1").unwrap(),
+            r#"{"context":{"ip":{"__extn":{"arg":"10.0.0.1","fn":"ip"}},"mfa":true},"entities":[{"attrs":{"a":[2,3],"b":1},"parents":[{"id":"a","type":"Group"},{"id":"z","type":"Group"}],"uid":{"id":"alice","type":"User"}}],"policySetVersion":"v1","request":{"action":{"id":"view","type":"Action"},"principal":{"id":"alice","type":"User"},"resource":{"id":"trip","type":"Photo"}},"scheme":1}"#
+        );
+        // The key of this document must never change within a scheme
+        assert_eq!(
+            DecisionCacheKey::new(&request, &entities, "v1")
+                .unwrap()
+                .as_str(),
+            "4b76c58076717733689ae1189078c21afe2d4b88a5d76795398fe90cd04dd42b"
+        );
+    }
+
+    #[test]
+    fn independent_of_construction_order() {
+        let entities = |json: serde_json::Value| Entities::from_json_value(json, None).unwrap();
+        let e1 = entities(serde_json::json!([
+            { "uid": { "type": "User", "id": "alice" }, "attrs": { "x": 1, "y": [1, 2] }, "parents": [] },
+            { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [{ "type": "Group", "id": "a" }, { "type": "Group", "id": "b" }] }
+        ]));
+        let e2 = entities(serde_json::json!([
+            { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [{ "type": "Group", "id": "b" }, { "type": "Group", "id": "a" }] },
+            { "uid": { "type": "User", "id": "alice" }, "attrs": { "y": [2, 1], "x": 1 }, "parents": [] }
+        ]));
+        let r1 = request(context(&[
+            ("a", "1"),
+            ("b", r#"{ c: "d", e: [User::"x"] }"#),
+        ]));
+        let r2 = request(context(&[
+            ("b", r#"{ e: [User::"x"], c: "d" }"#),
+            ("a", "1"),
+        ]));
+        let key = DecisionCacheKey::new(&r1, &e1, "v1").unwrap();
+        assert_eq!(key, DecisionCacheKey::new(&r2, &e2, "v1").unwrap());
+
+        // Any change to the request, entities, or version changes the key
+        let r3 = request(context(&[
+            ("a", "2"),
+            ("b", r#"{ c: "d", e: [User::"x"] }"#),
+        ]));
+        assert_ne!(key, DecisionCacheKey::new(&r3, &e1, "v1").unwrap());
+        assert_ne!(
+            key,
+            DecisionCacheKey::new(&r1, &Entities::empty(), "v1").unwrap()
+        );
+        assert_ne!(key, DecisionCacheKey::new(&r1, &e1, "v2").unwrap());
+    }
+}
+
+#[cfg(feature = "partial-eval")]
+mod entity_fetch_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    fn store() -> Entities {
+        Entities::from_json_value(
+            json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Team", "id": "ops" }] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Team", "id": "ops" }, "attrs": {}, "parents": [{ "type": "Group", "id": "admins" }] },
+                { "uid": { "type": "Group", "id": "admins" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Doc", "id": "plan" }, "attrs": { "locked": false }, "parents": [] },
+                { "uid": { "type": "Doc", "id": "vault" }, "attrs": { "locked": true }, "parents": [] }
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn policies() -> PolicySet {
+        r#"
+        permit(principal in Group::"admins", action, resource);
+        forbid(principal, action, resource) when { resource.locked };
+        "#
+        .parse()
+        .unwrap()
+    }
+
+    fn request(principal: &str, resource: &str) -> Request {
+        Request::new(
+            EntityUid::from_str(principal).unwrap(),
+            EntityUid::from_str(r#"Action::"view""#).unwrap(),
+            EntityUid::from_str(resource).unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    /// Authorize `request`, returning the decision and the uids fetched
+    fn authorize(request: &Request) -> (Decision, Vec<String>) {
+        let store = store();
+        let log = RefCell::new(Vec::new());
+        let fetch = |uids: &[EntityUid]| {
+            log.borrow_mut()
+                .extend(uids.iter().map(ToString::to_string));
+            Ok(uids
+                .iter()
+                .filter_map(|uid| store.get(uid).cloned())
+                .collect())
+        };
+        let response = Authorizer::new()
+            .is_authorized_with_fetch(request, &policies(), &fetch, None)
+            .unwrap();
+        assert_eq!(response.response().diagnostics().errors().count(), 0);
+        let mut fetched = log.into_inner();
+        fetched.sort();
+        (response.response().decision(), fetched)
+    }
+
+    #[test]
+    fn fetches_needed_entities_and_ancestors() {
+        assert_eq!(
+            authorize(&request(r#"User::"alice""#, r#"Doc::"plan""#)),
+            (
+                Decision::Allow,
+                vec![
+                    r#"Doc::"plan""#.to_string(),
+                    r#"Group::"admins""#.to_string(),
+                    r#"Team::"ops""#.to_string(),
+                    r#"User::"alice""#.to_string(),
+                ]
+            )
+        );
+        assert_matches!(
+            authorize(&request(r#"User::"alice""#, r#"Doc::"vault""#)),
+            (Decision::Deny, _)
+        );
+        assert_eq!(
+            authorize(&request(r#"User::"bob""#, r#"Doc::"plan""#)).0,
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn missing_entities() {
+        // `principal in Group::"admins"` is false for entities which don't
+        // exist, as with a complete entity store
+        assert_eq!(
+            authorize(&request(r#"User::"ghost""#, r#"Doc::"plan""#)),
+            (
+                Decision::Deny,
+                vec![r#"Doc::"plan""#.to_string(), r#"User::"ghost""#.to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn fetch_errors() {
+        let fetcher =
+            |_: &[EntityUid]| -> Result<Vec<Entity>, Box<dyn std::error::Error + Send + Sync>> {
+                Err("connection refused".into())
+            };
+        assert_matches!(
+            Authorizer::new().is_authorized_with_fetch(
+                &request(r#"User::"alice""#, r#"Doc::"plan""#),
+                &policies(),
+                &fetcher,
+                None
+            ),
+            Err(EntityFetchError::Fetch(e)) => assert_eq!(e.to_string(), "connection refused")
+        );
+    }
+}
+
+mod denial_cause_tests {
+    use super::*;
+    use crate::evaluation_errors::EntityLoadErrorKind;
+    use cool_asserts::assert_matches;
+
+    fn alice() -> Entity {
+        Entity::with_uid(r#"User::"alice""#.parse().unwrap()).with_fallible_lazy_attr(
+            "level",
+            || {
+                Err(EntityLoadFailure::new(
+                    EntityLoadErrorKind::Timeout,
+                    "directory timed out",
+                ))
+            },
+        )
+    }
+
+    fn authorize(authorizer: &Authorizer, pset: &str, entities: &Entities) -> Response {
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"trip""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        authorizer.is_authorized(&request, &PolicySet::from_str(pset).unwrap(), entities)
+    }
+
+    fn categories(response: &Response) -> Vec<ErrorCategory> {
+        response
+            .diagnostics()
+            .errors()
+            .map(|AuthorizationError::PolicyEvaluationError(e)| e.category())
+            .collect()
+    }
+
+    #[test]
+    fn load_failure_of_permit() {
+        let entities = Entities::from_entities([alice()], None).unwrap();
+        let response = authorize(
+            &Authorizer::new(),
+            "permit(principal, action, resource) when { principal.level > 3 };",
+            &entities,
+        );
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.denial_cause(), Some(DenialCause::EvaluationFailed));
+        assert_eq!(
+            response
+                .diagnostics()
+                .errored_permits()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["policy0"]
+        );
+        assert_eq!(
+            categories(&response),
+            vec![ErrorCategory::EntityLoad(EntityLoadErrorKind::Timeout)]
+        );
+        let AuthorizationError::PolicyEvaluationError(error) =
+            response.diagnostics().errors().next().unwrap();
+        assert_matches!(error.inner(), EvaluationError::EntityLoad(e) => {
+            assert_eq!(e.entity().to_string(), r#"User::"alice""#);
+            assert_eq!(e.attr(), "level");
+            assert_eq!(e.message(), "directory timed out");
+            assert!(miette::Diagnostic::labels(e).is_some());
+        });
+        assert_eq!(
+            error.inner().to_string(),
+            r#"failed to load attribute `level` of entity `User::"alice"` (timed out): directory timed out"#
+        );
+    }
+
+    #[test]
+    fn denied_by_policy_despite_errors() {
+        let entities = Entities::from_entities([alice()], None).unwrap();
+        let response = authorize(
+            &Authorizer::new(),
+            "
+                permit(principal, action, resource) when { principal.level > 3 };
+                forbid(principal, action, resource);
+            ",
+            &entities,
+        );
+        assert_eq!(
+            response.denial_cause(),
+            Some(DenialCause::ForbiddenByPolicy)
+        );
+        assert_eq!(response.diagnostics().errored_permits().count(), 1);
+    }
+
+    #[test]
+    fn errors_in_forbids_and_no_permit() {
+        let entities = Entities::from_entities([alice()], None).unwrap();
+        let response = authorize(
+            &Authorizer::new(),
+            r#"
+                permit(principal, action, resource) when { false };
+                forbid(principal, action, resource) when { principal.level > 3 };
+                forbid(principal, action, resource) when { Photo::"trip".owner == principal };
+            "#,
+            &entities,
+        );
+        assert_eq!(response.denial_cause(), Some(DenialCause::NoPermitApplied));
+        assert_eq!(response.diagnostics().errored_permits().count(), 0);
+        let mut categories = categories(&response);
+        categories.sort_by_key(|c| format!("{c:?}"));
+        assert_eq!(
+            categories,
+            vec![
+                ErrorCategory::EntityLoad(EntityLoadErrorKind::Timeout),
+                ErrorCategory::MissingEntity,
+            ]
+        );
+    }
+
+    #[test]
+    fn allowed() {
+        let entities = Entities::from_entities([alice()], None).unwrap();
+        let response = authorize(
+            &Authorizer::new(),
+            r#"
+                permit(principal, action, resource);
+                permit(principal, action, resource) when { principal.level + "" == 3 };
+            "#,
+            &entities,
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.denial_cause(), None);
+    }
+
+    #[test]
+    fn cancelled() {
+        let entities = Entities::from_entities([alice()], None).unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Photo::"trip""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized_with_cancellation(
+            &request,
+            &PolicySet::from_str("forbid(principal, action, resource) when { 1 == 2 };").unwrap(),
+            &entities,
+            &Cancellation::new().with_token(token),
+        );
+        assert_eq!(response.denial_cause(), Some(DenialCause::EvaluationFailed));
+        assert_eq!(
+            categories(&response),
+            vec![ErrorCategory::Cancelled(CancellationReason::Cancelled)]
+        );
+    }
+
+    #[test]
+    fn redacted() {
+        let entities = Entities::from_entities([alice()], None).unwrap();
+        let response = authorize(
+            &Authorizer::new().with_redacted_errors(),
+            "permit(principal, action, resource) when { principal.level > 3 };",
+            &entities,
+        );
+        let AuthorizationError::PolicyEvaluationError(error) =
+            response.diagnostics().errors().next().unwrap();
+        assert_eq!(
+            error.category(),
+            ErrorCategory::EntityLoad(EntityLoadErrorKind::Timeout)
+        );
+        assert!(!error.inner().to_string().contains("alice"));
+        assert!(!error.inner().to_string().contains("directory"));
+    }
+}
+
+mod context_layers_tests {
+    use super::*;
+
+    fn context(json: serde_json::Value) -> Context {
+        Context::from_json_value(json, None).unwrap()
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let mut layers = ContextLayers::new()
+            .with_layer(
+                "service",
+                context(serde_json::json!({ "region": "eu", "mfa": false, "tier": 1 })),
+            )
+            .with_layer(
+                "auth",
+                context(serde_json::json!({ "mfa": true, "tier": 1 })),
+            );
+        layers.add_layer(
+            "request",
+            context(serde_json::json!({ "mfa": false, "ip": "10.0.0.1" })),
+        );
+        assert_eq!(
+            layers.layer_names().collect::<Vec<_>>(),
+            vec!["service", "auth", "request"]
+        );
+
+        let merged = layers.merge().unwrap();
+        let context = merged.context();
+        assert_eq!(context.get("region"), Some(EvalResult::String("eu".into())));
+        assert_eq!(context.get("mfa"), Some(EvalResult::Bool(false)));
+        assert_eq!(context.get("tier"), Some(EvalResult::Long(1)));
+        assert_eq!(
+            context.get("ip"),
+            Some(EvalResult::String("10.0.0.1".into()))
+        );
+
+        // `tier` has the same value in both layers, so it is no conflict
+        assert_eq!(merged.conflicts().len(), 1);
+        let conflict = merged.conflicts().first().unwrap();
+        assert_eq!(conflict.key(), "mfa");
+        assert_eq!(conflict.layers(), ["service", "auth", "request"]);
+        assert_eq!(
+            conflict.to_string(),
+            "context key `mfa` is set by layers `service`, `auth`, `request`; the value from `request` is used"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let merged = ContextLayers::new().merge().unwrap();
+        assert_eq!(merged.into_context().into_iter().count(), 0);
+    }
+
+    #[test]
+    fn merged_context_authorizes() {
+        let merged = ContextLayers::new()
+            .with_layer(
+                "service",
+                context(serde_json::json!({ "maintenance": true })),
+            )
+            .with_layer(
+                "request",
+                context(serde_json::json!({ "maintenance": false })),
+            )
+            .merge()
+            .unwrap();
+        let request = Request::new(
+            r#"User::"alice""#.parse().unwrap(),
+            r#"Action::"view""#.parse().unwrap(),
+            r#"Doc::"plan""#.parse().unwrap(),
+            merged.into_context(),
+            None,
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(
+            "permit(principal, action, resource) when { !context.maintenance };",
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+    }
+
+    #[cfg(feature = "partial-eval")]
+    #[test]
+    fn layers_with_unknowns() {
+        let partial = Context::from_pairs([
+            ("ip".to_string(), RestrictedExpression::new_unknown("ip")),
+            ("mfa".to_string(), RestrictedExpression::new_bool(true)),
+        ])
+        .unwrap();
+        let merged = ContextLayers::new()
+            .with_layer(
+                "service",
+                context(serde_json::json!({ "mfa": false, "region": "eu" })),
+            )
+            .with_layer("request", partial)
+            .merge()
+            .unwrap();
+        assert_eq!(merged.conflicts().len(), 1);
+        assert_eq!(merged.conflicts().first().unwrap().key(), "mfa");
+        let keys = merged
+            .into_context()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["ip", "mfa", "region"]);
+    }
+}
+
+mod action_attribute_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use entities::{conformance::err::EntitySchemaConformanceError, err::EntitiesError};
+    use smol_str::SmolStr;
+
+    const SCHEMA: &str = r#"
+        entity User;
+        entity Doc;
+        action view appliesTo { principal: User, resource: Doc } attributes {
+            category: "read",
+            level: 1,
+        };
+        action delete appliesTo { principal: User, resource: Doc } attributes {
+            category: "write",
+            level: 3,
+        };
+    "#;
+
+    fn schema() -> Schema {
+        Schema::from_cedarschema_str(SCHEMA).unwrap().0
+    }
+
+    #[test]
+    fn policies_typecheck_against_action_attributes() {
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { action.category == "read" && action.level < 2 };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema()).validate(&policies, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        // attributes are typed from their declared values
+        let policies = PolicySet::from_str(
+            "permit(principal, action, resource) when { action.level == \"high\" };",
+        )
+        .unwrap();
+        let result = Validator::new(schema()).validate(&policies, ValidationMode::Strict);
+        assert!(!result.validation_passed());
+
+        // neither action declares `owner`
+        let policies = PolicySet::from_str(
+            "permit(principal, action, resource) when { action.owner == \"ops\" };",
+        )
+        .unwrap();
+        let result = Validator::new(schema()).validate(&policies, ValidationMode::Strict);
+        assert!(!result.validation_passed());
+    }
+
+    #[test]
+    fn undeclared_action_attributes_name_the_actions() {
+        let schema = Schema::from_cedarschema_str(
+            r#"
+            entity User;
+            entity Doc;
+            action view appliesTo { principal: User, resource: Doc } attributes { category: "read" };
+            action audit appliesTo { principal: User, resource: Doc } attributes { retention: 30 };
+            action delete appliesTo { principal: User, resource: Doc };
+            "#,
+        )
+        .unwrap()
+        .0;
+        let src = r#"permit(principal, action, resource) when { action.category == "read" };"#;
+        let policies = PolicySet::from_str(src).unwrap();
+        let result = Validator::new(schema).validate(&policies, ValidationMode::Strict);
+        let errors = result.validation_errors().collect::<Vec<_>>();
+        assert_matches!(errors.as_slice(), [ValidationError::UndefinedActionAttribute(err)] => {
+            assert_eq!(err.attr(), "category");
+            assert_eq!(
+                err.actions().map(ToString::to_string).collect::<Vec<_>>(),
+                vec![r#"Action::"audit""#, r#"Action::"delete""#]
+            );
+            assert_eq!(
+                err.declared_on().map(ToString::to_string).collect::<Vec<_>>(),
+                vec![r#"Action::"view""#]
+            );
+            expect_err(
+                src,
+                &Report::new(err.clone()),
+                &ExpectedErrorMessageBuilder::error(
+                    r#"for policy `policy0`, attribute `category` is not declared on actions `Action::"audit"` and `Action::"delete"`"#,
+                )
+                .help(r#"attribute `category` is only declared on `Action::"view"`"#)
+                .exactly_one_underline("action.category")
+                .build(),
+            );
+        });
+    }
+
+    #[test]
+    fn authorization_uses_schema_action_attributes() {
+        let schema = schema();
+        let entities = schema.action_entities().unwrap();
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { action.category == "read" };"#,
+        )
+        .unwrap();
+        let request = |action: &str| {
+            Request::new(
+                r#"User::"alice""#.parse().unwrap(),
+                format!(r#"Action::"{action}""#).parse().unwrap(),
+                r#"Doc::"plan""#.parse().unwrap(),
+                Context::empty(),
+                Some(&schema),
+            )
+            .unwrap()
+        };
+        let authorizer = Authorizer::new();
+        assert_eq!(
+            authorizer
+                .is_authorized(&request("view"), &policies, &entities)
+                .decision(),
+            Decision::Allow
+        );
+        assert_eq!(
+            authorizer
+                .is_authorized(&request("delete"), &policies, &entities)
+                .decision(),
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn entity_data_must_agree_with_schema() {
+        let schema = schema();
+        let entities = serde_json::json!([
+            {
+                "uid": { "type": "Action", "id": "view" },
+                "attrs": { "category": "read", "level": 2 },
+                "parents": []
+            }
+        ]);
+        assert_matches!(
+            Entities::from_json_value(entities, Some(&schema)),
+            Err(EntitiesError::InvalidEntity(
+                EntitySchemaConformanceError::ActionDeclarationMismatch(err)
+            )) => {
+                assert_eq!(err.attr().map(SmolStr::as_str), Some("level"));
+                assert_eq!(
+                    err.to_string(),
+                    "definition of action `Action::\"view\"` does not match its schema declaration: attribute `level` differs"
+                );
+            }
+        );
+
+        // restating the schema's definition is fine
+        let entities = serde_json::json!([
+            {
+                "uid": { "type": "Action", "id": "view" },
+                "attrs": { "category": "read", "level": 1 },
+                "parents": []
+            }
+        ]);
+        assert_matches!(Entities::from_json_value(entities, Some(&schema)), Ok(_));
+    }
+
+    #[test]
+    fn schema_round_trips_action_attributes() {
+        let fragment = SchemaFragment::from_cedarschema_str(SCHEMA).unwrap().0;
+        let printed = fragment.to_cedarschema().unwrap();
+        assert!(
+            printed.contains("attributes {\n  \"category\": \"read\",\n  \"level\": 1,\n}"),
+            "{printed}"
+        );
+        let reparsed = SchemaFragment::from_cedarschema_str(&printed).unwrap().0;
+        assert_eq!(
+            reparsed.to_json_value().unwrap(),
+            fragment.to_json_value().unwrap()
+        );
+    }
+}
+
+mod entity_warnings_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use entities::{conformance::err::EntitySchemaConformanceError, err::EntitiesError};
+
+    fn schema() -> Schema {
+        Schema::from_cedarschema_str(
+            r#"
+            entity Team;
+            entity User in [Team] = { name: String, manager?: User, level: Long };
+            entity Doc = { owner: User };
+            action view appliesTo { principal: User, resource: Doc } attributes { category: "read" };
+            "#,
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn findings_are_warnings() {
+        let (entities, warnings) = Entities::from_json_value_with_warnings(
+            serde_json::json!([
+                // conforms, using schema-based parsing for `manager`
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "name": "Alice", "level": 3, "manager": { "type": "User", "id": "bob" } },
+                    "parents": [{ "type": "Team", "id": "ops" }]
+                },
+                // `level` is missing and `department` is not declared
+                {
+                    "uid": { "type": "User", "id": "bob" },
+                    "attrs": { "name": "Bob", "department": "ops" },
+                    "parents": []
+                },
+                // `level` has the wrong type
+                {
+                    "uid": { "type": "User", "id": "carol" },
+                    "attrs": { "name": "Carol", "level": "senior" },
+                    "parents": []
+                },
+                // `Folder` is not declared
+                {
+                    "uid": { "type": "Folder", "id": "root" },
+                    "attrs": { "path": "/" },
+                    "parents": []
+                },
+            ]),
+            &schema(),
+        )
+        .unwrap();
+        let warnings = warnings.collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 4, "{warnings:?}");
+        assert_matches!(
+            warnings.as_slice(),
+            [
+                EntitySchemaConformanceError::UnexpectedEntityType(_),
+                EntitySchemaConformanceError::MissingRequiredEntityAttr(_),
+                EntitySchemaConformanceError::UnexpectedEntityAttr(_),
+                EntitySchemaConformanceError::TypeMismatch(_),
+            ]
+        );
+
+        // The store has every entity, plus the schema's actions
+        assert_eq!(entities.iter().count(), 5);
+        let view = EntityUid::from_str(r#"Action::"view""#).unwrap();
+        assert!(entities.get(&view).is_some());
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        assert!(entities.is_ancestor_of(&EntityUid::from_str(r#"Team::"ops""#).unwrap(), &alice));
+
+        // and can be used for authorization
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { principal.manager == User::"bob" && action.category == "read" };"#,
+        )
+        .unwrap();
+        let request = Request::new(
+            alice,
+            view,
+            r#"Doc::"plan""#.parse().unwrap(),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &policies, &entities);
+        assert_eq!(response.decision(), Decision::Allow);
+    }
+
+    #[test]
+    fn conforming_entities_have_no_warnings() {
+        let json = r#"[
+            { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice", "level": 3 }, "parents": [] },
+            { "uid": { "type": "Doc", "id": "plan" }, "attrs": { "owner": { "type": "User", "id": "alice" } }, "parents": [] }
+        ]"#;
+        let (entities, warnings) = Entities::from_json_str_with_warnings(json, &schema()).unwrap();
+        assert_eq!(warnings.count(), 0);
+        assert_eq!(
+            entities,
+            Entities::from_json_str(json, Some(&schema())).unwrap()
+        );
+    }
+
+    #[test]
+    fn structural_errors_are_still_errors() {
+        assert!(matches!(
+            Entities::from_json_str_with_warnings("[{ \"uid\": 1 }]", &schema()),
+            Err(EntitiesError::Deserialization(_))
+        ));
+        assert!(matches!(
+            Entities::from_json_value_with_warnings(
+                serde_json::json!([
+                    { "uid": { "type": "Team", "id": "a" }, "attrs": {}, "parents": [] },
+                    { "uid": { "type": "Team", "id": "a" }, "attrs": {}, "parents": [] },
+                ]),
+                &schema()
+            ),
+            Err(EntitiesError::Duplicate(_))
+        ));
+    }
+
+    #[test]
+    fn from_entities() {
+        let bob = Entity::new(
+            r#"User::"bob""#.parse().unwrap(),
+            HashMap::from([("name".to_string(), RestrictedExpression::new_long(1))]),
+            HashSet::new(),
+        )
+        .unwrap();
+        // an action that disagrees with the schema is replaced by the schema's
+        let view = Entity::new(
+            r#"Action::"view""#.parse().unwrap(),
+            HashMap::from([(
+                "category".to_string(),
+                RestrictedExpression::new_string("write".into()),
+            )]),
+            HashSet::new(),
+        )
+        .unwrap();
+        let (entities, warnings) =
+            Entities::from_entities_with_warnings([bob, view], &schema()).unwrap();
+        let warnings = warnings.map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                r#"definition of action `Action::"view"` does not match its schema declaration: attribute `category` differs"#.to_string(),
+                r#"expected entity `User::"bob"` to have attribute `level`, but it does not"#.to_string(),
+                r#"in attribute `name` on `User::"bob"`, type mismatch: value was expected to have type string, but it actually has type long: `1`"#.to_string(),
+            ]
+        );
+        let view = entities.get(&r#"Action::"view""#.parse().unwrap()).unwrap();
+        assert_eq!(
+            view.attr("category").unwrap().unwrap(),
+            EvalResult::String("read".to_string())
+        );
+    }
+}
+
+mod validating_request_builder_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_str(
+            r#"
+            entity User;
+            entity Photo;
+            action view appliesTo {
+                principal: User,
+                resource: Photo,
+                context: { mfa: Bool, @default("eu") region?: String }
+            };
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn components(err: &RequestBuilderError) -> Vec<RequestComponent> {
+        err.problems()
+            .map(RequestBuilderProblem::component)
+            .collect()
+    }
+
+    #[test]
+    fn builds_valid_request_with_context_defaults() {
+        let schema = schema();
+        let request = Request::validating_builder(&schema)
+            .principal(EntityUid::from_strs("User", "alice"))
+            .action(EntityUid::from_strs("Action", "view"))
+            .resource(EntityUid::from_strs("Photo", "vacation"))
+            .context(
+                Context::from_pairs([("mfa".into(), RestrictedExpression::new_bool(true))])
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.principal(),
+            Some(&EntityUid::from_strs("User", "alice"))
+        );
+        assert_eq!(
+            request.context().unwrap().get("region"),
+            Some(EvalResult::String("eu".to_string()))
+        );
+    }
+
+    #[test]
+    fn accumulates_every_problem() {
+        let schema = schema();
+        let err = Request::validating_builder(&schema)
+            .principal(EntityUid::from_strs("Photo", "vacation"))
+            .action(EntityUid::from_strs("Action", "view"))
+            .resource(EntityUid::from_strs("Album", "trips"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            components(&err),
+            [
+                RequestComponent::Principal,
+                RequestComponent::Resource,
+                RequestComponent::Context
+            ]
+        );
+        assert!(matches!(
+            err.problems().next(),
+            Some(RequestBuilderProblem::Invalid(
+                RequestValidationError::InvalidPrincipalType(_)
+            ))
+        ));
+        assert!(matches!(
+            err.problems().nth(1),
+            Some(RequestBuilderProblem::Invalid(
+                RequestValidationError::UndeclaredResourceType(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn reports_missing_components() {
+        let schema = schema();
+        let err = Request::validating_builder(&schema)
+            .resource(EntityUid::from_strs("Photo", "vacation"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            components(&err),
+            [RequestComponent::Principal, RequestComponent::Action]
+        );
+        assert_eq!(
+            err.to_string(),
+            "request is invalid: request principal was not set; request action was not set"
+        );
+    }
+
+    #[test]
+    fn components_are_rechecked_against_the_action() {
+        let schema = schema();
+        // The principal type is declared, so there's nothing wrong with it
+        // until the action is set
+        let builder =
+            Request::validating_builder(&schema).principal(EntityUid::from_strs("Photo", "a"));
+        let err = builder
+            .action(EntityUid::from_strs("Action", "delete"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            components(&err),
+            [RequestComponent::Resource, RequestComponent::Action]
+        );
+
+        // Setting a component again clears the problems with its old value
+        let request = Request::validating_builder(&schema)
+            .principal(EntityUid::from_strs("Photo", "a"))
+            .action(EntityUid::from_strs("Action", "view"))
+            .principal(EntityUid::from_strs("User", "alice"))
+            .resource(EntityUid::from_strs("Photo", "vacation"))
+            .context(
+                Context::from_pairs([("mfa".into(), RestrictedExpression::new_bool(false))])
+                    .unwrap(),
+            )
+            .build();
+        assert!(request.is_ok());
+    }
+}
+
+#[cfg(feature = "tower")]
+mod middleware_tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::{Poll, Wake, Waker};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Responds `200 OK` with the principal of the request authorized by the
+    /// [`AuthorizationLayer`]
+    #[derive(Clone)]
+    struct Echo;
+
+    impl tower_service::Service<http::Request<()>> for Echo {
+        type Response = http::Response<String>;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<()>) -> Self::Future {
+            let principal = request
+                .extensions()
+                .get::<Authorized>()
+                .and_then(|authorized| authorized.request().principal())
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            std::future::ready(Ok(http::Response::new(principal)))
+        }
+    }
+
+    fn authorizer() -> HttpAuthorizer {
+        let policies = PolicySet::from_str(
+            r#"permit(principal == User::"alice", action == Action::"GET", resource);"#,
+        )
+        .unwrap();
+        let handle = Arc::new(AuthorizerHandle::new(
+            Authorizer::new(),
+            policies,
+            Entities::empty(),
+        ));
+        HttpAuthorizer::new(handle, |parts: &http::request::Parts| {
+            let user = parts
+                .headers
+                .get("x-user")
+                .and_then(|user| user.to_str().ok())
+                .ok_or(http::StatusCode::UNAUTHORIZED)?;
+            Request::new(
+                EntityUid::from_strs("User", user),
+                EntityUid::from_strs("Action", parts.method.as_str()),
+                EntityUid::from_strs("Path", parts.uri.path()),
+                Context::empty(),
+                None,
+            )
+            .map_err(|_| http::StatusCode::BAD_REQUEST)
+        })
+    }
+
+    fn call(request: http::Request<()>) -> http::Response<String> {
+        use tower_layer::Layer;
+        use tower_service::Service;
+        let mut service = authorizer().layer().layer(Echo);
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = std::task::Context::from_waker(&waker);
+        match pin!(service.call(request)).poll(&mut cx) {
+            Poll::Ready(Ok(response)) => response,
+            Poll::Ready(Err(e)) => match e {},
+            Poll::Pending => panic!("authorization should not wait"),
+        }
+    }
+
+    #[test]
+    fn layer_enforces_decisions() {
+        let allowed = call(
+            http::Request::get("/photos")
+                .header("x-user", "alice")
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(allowed.status(), http::StatusCode::OK);
+        assert_eq!(allowed.body(), r#"User::"alice""#);
+
+        let denied = call(
+            http::Request::get("/photos")
+                .header("x-user", "bob")
+                .body(())
+                .unwrap(),
+        );
+        assert_eq!(denied.status(), http::StatusCode::FORBIDDEN);
+        assert_eq!(denied.body(), "");
+
+        let unmapped = call(http::Request::get("/photos").body(()).unwrap());
+        assert_eq!(unmapped.status(), http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn decisions_are_logged() {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&log);
+        let handle = Arc::new(AuthorizerHandle::new(
+            Authorizer::new().with_decision_logger(move |record: &DecisionRecord| {
+                sink.lock().unwrap().push(record.clone());
+            }),
+            PolicySet::new(),
+            Entities::empty(),
+        ));
+        let authorizer = HttpAuthorizer::new(
+            handle,
+            |_: &http::request::Parts| -> Result<Request, http::StatusCode> {
+                let euid = EntityUid::from_strs("User", "alice");
+                Ok(Request::new(euid.clone(), euid.clone(), euid, Context::empty(), None).unwrap())
+            },
+        );
+        let (parts, ()) = http::Request::get("/").body(()).unwrap().into_parts();
+        assert_eq!(
+            authorizer.authorize(&parts).unwrap_err(),
+            http::StatusCode::FORBIDDEN
+        );
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::indexing_slicing)]
+#[cfg(feature = "rego")]
+mod rego_import_tests {
+    use super::*;
+
+    fn policy_text(import: &RegoImport, id: &str) -> String {
+        import
+            .policies()
+            .policy(&PolicyId::new(id))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn translates_allow_and_deny_rules() {
+        let import = RegoImporter::new().import(
+            r#"
+            package authz
+            import rego.v1
+
+            default allow := false
+
+            allow if {
+                input.principal == "alice"
+                input.action in {"read", "list"}
+                input.resource.public
+            }
+
+            allow if input.resource.owner == input.principal
+
+            deny[msg] {
+                input.resource.classification >= 3
+                not input.mfa
+                "auditor" in input.principal.roles
+                msg := sprintf("denied %v", [input.principal])
+            }
+            "#,
+        );
+        assert!(import.issues().is_empty(), "{:?}", import.issues());
+        assert_eq!(
+            policy_text(&import, "allow0"),
+            r#"permit(principal == User::"alice", action, resource) when { ([Action::"read", Action::"list"].contains(action)) && ((resource has "public") && (resource["public"])) };"#
+        );
+        assert_eq!(
+            policy_text(&import, "allow1"),
+            r#"permit(principal, action, resource) when { (resource has "owner") && ((resource["owner"]) == principal) };"#
+        );
+        assert!(policy_text(&import, "deny0").starts_with("forbid("));
+
+        // Requests are decided as the Rego policies would
+        let schema = Schema::from_schema_fragments([import.schema().clone()]).unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "roles": ["auditor"] }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Resource", "id": "report" }, "attrs": { "public": true, "owner": { "__entity": { "type": "User", "id": "bob" } }, "classification": 3 }, "parents": [] },
+            ]),
+            Some(&schema),
+        )
+        .unwrap();
+        let decide = |principal: &str, mfa: bool| {
+            let request = Request::new(
+                EntityUid::from_strs("User", principal),
+                EntityUid::from_strs("Action", "read"),
+                EntityUid::from_strs("Resource", "report"),
+                Context::from_pairs([("mfa".into(), RestrictedExpression::new_bool(mfa))]).unwrap(),
+                Some(&schema),
+            )
+            .unwrap();
+            Authorizer::new()
+                .is_authorized(&request, import.policies(), &entities)
+                .decision()
+        };
+        assert_eq!(decide("alice", true), Decision::Allow);
+        // The `deny` rule applies to auditors without MFA
+        assert_eq!(decide("alice", false), Decision::Deny);
+        // `bob` owns the resource
+        assert_eq!(decide("bob", false), Decision::Allow);
+    }
+
+    #[test]
+    fn infers_a_schema_skeleton() {
+        let import = RegoImporter::new()
+            .principal("user", EntityTypeName::from_str("App::User").unwrap())
+            .import(
+                r#"
+                allow {
+                    input.action == "view"
+                    input.user.department.name == "sales"
+                    input.user.level > 2
+                    input.resource.tags[_] == "shared"
+                    startswith(input.path, "/public/")
+                }
+                "#,
+            );
+        assert!(import.issues().is_empty(), "{:?}", import.issues());
+        let schema = import.schema().clone().to_json_value().unwrap();
+        let user = &schema["App"]["entityTypes"]["User"]["shape"]["attributes"];
+        assert_eq!(
+            user["department"]["attributes"]["name"],
+            serde_json::json!({ "type": "String", "required": false })
+        );
+        assert_eq!(user["level"]["type"], "Long");
+        let resource = &schema[""]["entityTypes"]["Resource"]["shape"]["attributes"];
+        assert_eq!(resource["tags"]["type"], "Set");
+        assert_eq!(resource["tags"]["element"]["type"], "String");
+        let view = &schema[""]["actions"]["view"]["appliesTo"];
+        assert_eq!(view["principalTypes"], serde_json::json!(["App::User"]));
+        assert_eq!(view["context"]["attributes"]["path"]["type"], "String");
+        assert!(
+            policy_text(&import, "allow0").contains(r#"like "/public/*""#),
+            "{}",
+            policy_text(&import, "allow0")
+        );
+    }
+
+    #[test]
+    fn reports_untranslatable_constructs() {
+        let src = r#"package authz
+import data.roles
+
+default allow := true
+
+allow if {
+    some role in data.roles[input.principal]
+    role == "admin"
+}
+
+allow if {
+    input.action == "read"
+    count(input.resource.viewers) > 0
+}
+
+is_admin if input.principal == "root"
+
+allow if input.action == "list"
+"#;
+        let import = RegoImporter::new().import(src);
+        let issues: Vec<_> = import
+            .issues()
+            .iter()
+            .map(|issue| (issue.line(), issue.column(), issue.message().to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (2, 1, "imports are not supported; only the `future.keywords` and `rego.v1` imports are allowed".to_string()),
+                (4, 1, "only `false` is supported as the default of `allow`, since Cedar denies requests no policy permits".to_string()),
+                (7, 5, "`some` (iteration with variables) is not supported".to_string()),
+                (13, 5, "built-in function `count` is not supported".to_string()),
+                (16, 1, "rule `is_admin` is not supported; only `allow` and `deny` rules are translated".to_string()),
+            ]
+        );
+        // Only the last rule is translated
+        assert_eq!(import.policies().policies().count(), 1);
+        assert_eq!(
+            policy_text(&import, "allow0"),
+            r#"permit(principal, action == Action::"list", resource);"#
+        );
+
+        let issue = import.issues().get(3).unwrap();
+        expect_err(
+            src,
+            &Report::new(issue.clone()),
+            &ExpectedErrorMessageBuilder::error("built-in function `count` is not supported")
+                .exactly_one_underline("count(input.resource.viewers)")
+                .build(),
+        );
+    }
+}
+
+#[cfg(feature = "xacml")]
+mod xacml_tests {
+    use super::*;
+
+    const STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+    fn policy_text(policies: &PolicySet, id: &str) -> String {
+        policies.policy(&PolicyId::new(id)).unwrap().to_string()
+    }
+
+    fn issues(issues: &[XacmlIssue]) -> Vec<(XacmlIssueKind, String, Option<usize>)> {
+        issues
+            .iter()
+            .map(|issue| (issue.kind(), issue.to_string(), issue.line()))
+            .collect()
+    }
+
+    #[test]
+    fn imports_rules_with_the_targets_containing_them() {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Photo sharing -->
+<xacml:PolicySet xmlns:xacml="urn:oasis:names:tc:xacml:3.0:core:schema:wd-17" PolicySetId="photos"
+    PolicyCombiningAlgId="urn:oasis:names:tc:xacml:3.0:policy-combining-algorithm:deny-overrides">
+  <xacml:Target>
+    <xacml:AnyOf><xacml:AllOf>
+      <xacml:Match MatchId="urn:oasis:names:tc:xacml:1.0:function:string-equal">
+        <xacml:AttributeValue DataType="{STRING}">photos</xacml:AttributeValue>
+        <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:3.0:attribute-category:resource"
+            AttributeId="urn:example:app" DataType="{STRING}" MustBePresent="false"/>
+      </xacml:Match>
+    </xacml:AllOf></xacml:AnyOf>
+  </xacml:Target>
+  <xacml:Policy PolicyId="viewing" RuleCombiningAlgId="urn:oasis:names:tc:xacml:3.0:rule-combining-algorithm:deny-overrides">
+    <xacml:Target>
+      <xacml:AnyOf>
+        <xacml:AllOf>
+          <xacml:Match MatchId="urn:oasis:names:tc:xacml:1.0:function:string-equal">
+            <xacml:AttributeValue DataType="{STRING}">view</xacml:AttributeValue>
+            <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:3.0:attribute-category:action"
+                AttributeId="urn:oasis:names:tc:xacml:1.0:action:action-id" DataType="{STRING}" MustBePresent="false"/>
+          </xacml:Match>
+        </xacml:AllOf>
+        <xacml:AllOf>
+          <xacml:Match MatchId="urn:oasis:names:tc:xacml:1.0:function:string-equal">
+            <xacml:AttributeValue DataType="{STRING}">comment</xacml:AttributeValue>
+            <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:3.0:attribute-category:action"
+                AttributeId="urn:oasis:names:tc:xacml:1.0:action:action-id" DataType="{STRING}" MustBePresent="false"/>
+          </xacml:Match>
+        </xacml:AllOf>
+      </xacml:AnyOf>
+    </xacml:Target>
+    <xacml:Rule RuleId="owner" Effect="Permit">
+      <xacml:Description>Owners can view their photos</xacml:Description>
+      <xacml:Target>
+        <xacml:AnyOf><xacml:AllOf>
+          <xacml:Match MatchId="urn:oasis:names:tc:xacml:1.0:function:string-equal">
+            <xacml:AttributeValue DataType="{STRING}">alice</xacml:AttributeValue>
+            <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:1.0:subject-category:access-subject"
+                AttributeId="urn:oasis:names:tc:xacml:1.0:subject:subject-id" DataType="{STRING}" MustBePresent="false"/>
+          </xacml:Match>
+        </xacml:AllOf></xacml:AnyOf>
+      </xacml:Target>
+    </xacml:Rule>
+    <xacml:Rule RuleId="adults" Effect="Permit">
+      <xacml:Condition>
+        <xacml:Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:and">
+          <xacml:Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:integer-greater-than-or-equal">
+            <xacml:Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:integer-one-and-only">
+              <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:1.0:subject-category:access-subject"
+                  AttributeId="urn:example:age" DataType="http://www.w3.org/2001/XMLSchema#integer" MustBePresent="false"/>
+            </xacml:Apply>
+            <xacml:AttributeValue DataType="http://www.w3.org/2001/XMLSchema#integer">18</xacml:AttributeValue>
+          </xacml:Apply>
+          <xacml:Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:string-is-in">
+            <xacml:AttributeValue DataType="{STRING}">friends</xacml:AttributeValue>
+            <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:3.0:attribute-category:resource"
+                AttributeId="urn:example:audiences" DataType="{STRING}" MustBePresent="false"/>
+          </xacml:Apply>
+        </xacml:Apply>
+      </xacml:Condition>
+    </xacml:Rule>
+    <xacml:Rule RuleId="drafts" Effect="Deny">
+      <xacml:Condition>
+        <xacml:Apply FunctionId="urn:oasis:names:tc:xacml:3.0:function:string-starts-with">
+          <xacml:AttributeValue DataType="{STRING}">drafts/</xacml:AttributeValue>
+          <xacml:Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:string-one-and-only">
+            <xacml:AttributeDesignator Category="urn:oasis:names:tc:xacml:3.0:attribute-category:resource"
+                AttributeId="urn:example:path" DataType="{STRING}" MustBePresent="false"/>
+          </xacml:Apply>
+        </xacml:Apply>
+      </xacml:Condition>
+    </xacml:Rule>
+  </xacml:Policy>
+</xacml:PolicySet>
+"#
+        );
+        let import = XacmlConverter::new()
+            .resource_type("Photo".parse().unwrap())
+            .import(&xml)
+            .unwrap();
+        assert_eq!(issues(import.issues()), []);
+        let policies = import.policies();
+        assert_eq!(policies.policies().count(), 3);
+        // The target of the policy set conditions every rule, and the action
+        // alternatives of the policy's target go in the scope
+        assert_eq!(
+            policy_text(policies, "photos/viewing/owner"),
+            r#"permit(principal == User::"alice", action in [Action::"view", Action::"comment"], resource) when { (resource has "app") && ((resource["app"]) == "photos") };"#
+        );
+        assert_eq!(
+            policy_text(policies, "photos/viewing/adults"),
+            r#"permit(principal, action in [Action::"view", Action::"comment"], resource) when { (((((resource has "app") && ((resource["app"]) == "photos")) && (principal has "age")) && (!((principal["age"]) < 18))) && (resource has "audiences")) && ((resource["audiences"]).contains("friends")) };"#
+        );
+        assert_eq!(
+            policy_text(policies, "photos/viewing/drafts"),
+            r#"forbid(principal, action in [Action::"view", Action::"comment"], resource) when { (((resource has "app") && ((resource["app"]) == "photos")) && (resource has "path")) && ((resource["path"]) like "drafts/*") };"#
+        );
+    }
+
+    #[test]
+    fn reports_unsupported_and_lossy_elements() {
+        let xml = format!(
+            r#"<Policy PolicyId="p" RuleCombiningAlgId="urn:oasis:names:tc:xacml:3.0:rule-combining-algorithm:permit-overrides">
+  <Target/>
+  <Rule RuleId="admins" Effect="Permit">
+    <Condition>
+      <Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:string-regexp-match">
+        <AttributeValue DataType="{STRING}">^admin-.*</AttributeValue>
+        <Apply FunctionId="urn:oasis:names:tc:xacml:1.0:function:string-one-and-only">
+          <AttributeDesignator Category="urn:oasis:names:tc:xacml:1.0:subject-category:access-subject"
+              AttributeId="urn:oasis:names:tc:xacml:1.0:subject:subject-id" DataType="{STRING}" MustBePresent="false"/>
+        </Apply>
+      </Apply>
+    </Condition>
+  </Rule>
+  <Rule RuleId="audited" Effect="Permit">
+    <ObligationExpressions>
+      <ObligationExpression ObligationId="urn:example:audit" FulfillOn="Permit"/>
+    </ObligationExpressions>
+  </Rule>
+  <Rule RuleId="weekend" Effect="Deny">
+    <Condition>
+      <VariableReference VariableId="isWeekend"/>
+    </Condition>
+  </Rule>
+  <PolicyIdReference>other</PolicyIdReference>
+</Policy>"#
+        );
+        let import = XacmlConverter::new().import(&xml).unwrap();
+        assert_eq!(
+            issues(import.issues()),
+            [
+                (XacmlIssueKind::Lossy, "p: combining algorithm `urn:oasis:names:tc:xacml:3.0:rule-combining-algorithm:permit-overrides` is approximated: in Cedar, forbid policies override permit policies, and requests no policy permits are denied".to_string(), Some(1)),
+                (XacmlIssueKind::Unsupported, "p/admins: in the condition: regular expressions have no Cedar equivalent; the rule is not translated".to_string(), Some(4)),
+                (XacmlIssueKind::Lossy, "p/audited: `ObligationExpressions` have no Cedar equivalent and are dropped".to_string(), Some(15)),
+                (XacmlIssueKind::Unsupported, "p/weekend: in the condition: variables are not supported; the rule is not translated".to_string(), Some(20)),
+                (XacmlIssueKind::Unsupported, "p: `PolicyIdReference` is not supported, and is not translated".to_string(), Some(24)),
+            ]
+        );
+        assert_eq!(import.policies().policies().count(), 1);
+        assert_eq!(
+            policy_text(import.policies(), "p/audited"),
+            "permit(principal, action, resource);"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = XacmlConverter::new()
+            .import("<Policy PolicyId=\"p\">\n  <Target>\n</Policy>")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid XACML at line 3, column 3: expected `</Target>`, found `</Policy>`"
+        );
+        assert_eq!((err.line(), err.column()), (3, 3));
+
+        let err = XacmlConverter::new()
+            .import("<?xml version=\"1.0\"?>\n<Request/>")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid XACML at line 2, column 1: expected a `Policy` or `PolicySet` root element, found `Request`"
+        );
+
+        let err = XacmlConverter::new()
+            .import("<Policy PolicyId=\"a &nbsp; b\"/>")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid XACML at line 1, column 21: unknown reference `&nbsp;`"
+        );
+
+        let nested = format!("<Policy>{}", "<a>".repeat(100_000));
+        let err = XacmlConverter::new().import(&nested).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid XACML at line 1, column 777: elements are nested more than 256 deep"
+        );
+    }
+
+    #[test]
+    fn locates_elements_in_large_documents() {
+        let references = "  <PolicyIdReference>other</PolicyIdReference>\n".repeat(20_000);
+        let xml = format!("<PolicySet PolicySetId=\"s\">\n{references}</PolicySet>");
+        let import = XacmlConverter::new().import(&xml).unwrap();
+        let lines = import
+            .issues()
+            .iter()
+            .filter(|issue| issue.message().contains("PolicyIdReference"))
+            .map(XacmlIssue::line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 20_000);
+        assert_eq!(lines.last(), Some(&Some(20_001)));
+    }
+
+    #[test]
+    fn exports_policies_as_rules() {
+        let policies = PolicySet::from_str(
+            r#"
+            permit(principal == User::"alice", action == Action::"view", resource)
+            when { resource.public || principal.age >= 18 };
+            forbid(principal, action in [Action::"delete", Action::"edit"], resource)
+            unless { context.mfa };
+            permit(principal in Group::"admins", action, resource);
+            permit(principal, action, resource is Document);
+            permit(principal, action, resource)
+            when { resource.path like "public/*" && context.hour < 18 };
+            "#,
+        )
+        .unwrap();
+        let export = XacmlConverter::new().export(&policies);
+        assert_eq!(
+            issues(export.issues()),
+            [
+                (XacmlIssueKind::Lossy, "policy1: action groups have no XACML equivalent, so `action in [...]` is exported as equality with one of the actions, not including their members".to_string(), None),
+                (XacmlIssueKind::Unsupported, "policy2: entity hierarchy (`principal in ...`) has no XACML equivalent; the policy is not exported".to_string(), None),
+                (XacmlIssueKind::Unsupported, "policy3: XACML identifies entities by id only, so only resources of type `Resource` are supported, not `Document`; the policy is not exported".to_string(), None),
+            ]
+        );
+        assert!(export
+            .xml()
+            .contains(r#"<Rule RuleId="policy0" Effect="Permit">"#));
+        assert!(export
+            .xml()
+            .contains(r#"<Rule RuleId="policy1" Effect="Deny">"#));
+        assert!(!export.xml().contains("policy2"));
+
+        // Importing the export gives equivalent policies
+        let import = XacmlConverter::new().import(export.xml()).unwrap();
+        assert_eq!(issues(import.issues()), []);
+        let imported = import.policies();
+        assert_eq!(imported.policies().count(), 3);
+        assert_eq!(
+            policy_text(imported, "cedar/policy0"),
+            r#"permit(principal == User::"alice", action == Action::"view", resource) when { ((resource has "public") && (resource["public"])) || ((principal has "age") && (!((principal["age"]) < 18))) };"#
+        );
+        assert_eq!(
+            policy_text(imported, "cedar/policy1"),
+            r#"forbid(principal, action in [Action::"delete", Action::"edit"], resource) when { !((context has "mfa") && (context["mfa"])) };"#
+        );
+        assert_eq!(
+            policy_text(imported, "cedar/policy4"),
+            r#"permit(principal, action, resource) when { (((resource has "path") && ((resource["path"]) like "public/*")) && (context has "hour")) && ((context["hour"]) < 18) };"#
+        );
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::indexing_slicing)]
+#[cfg(feature = "iam")]
+mod iam_import_tests {
+    use super::*;
+
+    fn policy_text(import: &IamImport, id: &str) -> String {
+        import
+            .policies()
+            .policy(&PolicyId::new(id))
+            .unwrap()
+            .to_string()
+    }
+
+    fn issues(import: &IamImport) -> Vec<String> {
+        import.issues().iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn translates_resource_policies() {
+        let import = IamImporter::new()
+            .known_actions(["s3:PutObject"])
+            .import(
+                r#"{
+                    "Version": "2012-10-17",
+                    "Statement": [
+                        {
+                            "Sid": "CrossAccountRead",
+                            "Effect": "Allow",
+                            "Principal": { "AWS": ["111122223333", "arn:aws:iam::444455556666:user/carol"] },
+                            "Action": ["s3:GetObject", "s3:ListBucket"],
+                            "Resource": ["arn:aws:s3:::reports", "arn:aws:s3:::reports/*"],
+                            "Condition": {
+                                "IpAddress": { "aws:SourceIp": "10.0.0.0/8" },
+                                "NumericLessThanIfExists": { "s3:max-keys": "100" }
+                            }
+                        },
+                        {
+                            "Sid": "RequireTls",
+                            "Effect": "Deny",
+                            "Principal": "*",
+                            "Action": "S3:*",
+                            "Resource": "*",
+                            "Condition": { "Bool": { "aws:SecureTransport": "false" } }
+                        },
+                        {
+                            "Effect": "Allow",
+                            "Principal": { "AWS": "arn:aws:iam::111122223333:root" },
+                            "NotAction": "s3:PutObject",
+                            "Resource": "arn:aws:s3:::public/?ndex.html"
+                        }
+                    ]
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(issues(&import), Vec::<String>::new());
+        assert_eq!(
+            policy_text(&import, "CrossAccountRead"),
+            r#"permit(principal, action in [Action::"s3:GetObject", Action::"s3:ListBucket"], resource) when { (principal.account == "111122223333" || principal == Principal::"arn:aws:iam::444455556666:user/carol") && (resource == Resource::"arn:aws:s3:::reports" || resource.arn like "arn:aws:s3:::reports/*") && (context has "aws:SourceIp" && context["aws:SourceIp"].isInRange(ip("10.0.0.0/8"))) && (if context has "s3:max-keys" then context["s3:max-keys"] < 100 else true) };"#
+        );
+        // Action patterns are matched against the known actions and those
+        // named in the policy, ignoring case
+        assert_eq!(
+            policy_text(&import, "RequireTls"),
+            r#"forbid(principal, action in [Action::"s3:GetObject", Action::"s3:ListBucket", Action::"s3:PutObject"], resource) when { (context has "aws:SecureTransport" && context["aws:SecureTransport"] == false) };"#
+        );
+        assert_eq!(
+            policy_text(&import, "statement2"),
+            r#"permit(principal, action, resource) when { principal.account == "111122223333" && !(action in [Action::"s3:PutObject"]) && resource.arn like "arn:aws:s3:::public/\[\0-\u{10ffff}]ndex.html" };"#
+        );
+
+        // The policies validate against the schema, and decide requests as
+        // IAM would
+        let schema = Schema::from_schema_fragments([import.schema().clone()]).unwrap();
+        let result =
+            Validator::new(schema.clone()).validate(import.policies(), ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "Principal", "id": "arn:aws:iam::111122223333:user/bob" }, "attrs": { "account": "111122223333" }, "parents": [] },
+                { "uid": { "type": "Principal", "id": "arn:aws:iam::444455556666:user/carol" }, "attrs": { "account": "444455556666" }, "parents": [] },
+                { "uid": { "type": "Principal", "id": "arn:aws:iam::444455556666:user/dave" }, "attrs": { "account": "444455556666" }, "parents": [] },
+                { "uid": { "type": "Resource", "id": "arn:aws:s3:::reports/q1" }, "attrs": { "arn": "arn:aws:s3:::reports/q1" }, "parents": [] },
+                { "uid": { "type": "Resource", "id": "arn:aws:s3:::public/index.html" }, "attrs": { "arn": "arn:aws:s3:::public/index.html" }, "parents": [] },
+            ]),
+            Some(&schema),
+        )
+        .unwrap();
+        let decide = |principal: &str, action: &str, resource: &str, context: serde_json::Value| {
+            let request = Request::new(
+                EntityUid::from_type_name_and_id(
+                    "Principal".parse().unwrap(),
+                    EntityId::new(principal),
+                ),
+                EntityUid::from_type_name_and_id("Action".parse().unwrap(), EntityId::new(action)),
+                EntityUid::from_type_name_and_id(
+                    "Resource".parse().unwrap(),
+                    EntityId::new(resource),
+                ),
+                Context::from_json_value(context, None).unwrap(),
+                None,
+            )
+            .unwrap();
+            Authorizer::new()
+                .is_authorized(&request, import.policies(), &entities)
+                .decision()
+        };
+        let from = |ip: &str, tls: bool| serde_json::json!({ "aws:SourceIp": { "__extn": { "fn": "ip", "arg": ip } }, "aws:SecureTransport": tls });
+        let bob = "arn:aws:iam::111122223333:user/bob";
+        let carol = "arn:aws:iam::444455556666:user/carol";
+        let dave = "arn:aws:iam::444455556666:user/dave";
+        let q1 = "arn:aws:s3:::reports/q1";
+        assert_eq!(
+            decide(bob, "s3:GetObject", q1, from("10.1.2.3", true)),
+            Decision::Allow
+        );
+        assert_eq!(
+            decide(carol, "s3:GetObject", q1, from("10.1.2.3", true)),
+            Decision::Allow
+        );
+        assert_eq!(
+            decide(dave, "s3:GetObject", q1, from("10.1.2.3", true)),
+            Decision::Deny
+        );
+        assert_eq!(
+            decide(bob, "s3:GetObject", q1, from("192.0.2.1", true)),
+            Decision::Deny
+        );
+        assert_eq!(
+            decide(bob, "s3:GetObject", q1, from("10.1.2.3", false)),
+            Decision::Deny
+        );
+        assert_eq!(
+            decide(
+                bob,
+                "s3:GetObject",
+                q1,
+                serde_json::json!({ "aws:SourceIp": { "__extn": { "fn": "ip", "arg": "10.1.2.3" } }, "s3:max-keys": 1000 })
+            ),
+            Decision::Deny
+        );
+        let index = "arn:aws:s3:::public/index.html";
+        assert_eq!(
+            decide(bob, "s3:GetObject", index, serde_json::json!({})),
+            Decision::Allow
+        );
+        assert_eq!(
+            decide(bob, "s3:PutObject", index, serde_json::json!({})),
+            Decision::Deny
+        );
+    }
+
+    #[test]
+    fn translates_identity_policies() {
+        let import = IamImporter::new()
+            .principal_type("User".parse().unwrap())
+            .resource_type("Bucket".parse().unwrap())
+            .attached_to(r#"Group::"auditors""#.parse().unwrap())
+            .import(
+                r#"{
+                    "Statement": {
+                        "Sid": "TaggedReads",
+                        "Effect": "Allow",
+                        "Action": "s3:GetObject",
+                        "Resource": "arn:aws:s3:::audit",
+                        "Condition": {
+                            "ForAnyValue:StringEquals": { "aws:TagKeys": ["audit", "compliance"] },
+                            "ForAllValues:StringEquals": { "aws:RequestedRegions": ["us-east-1", "us-west-2"] },
+                            "StringNotLike": { "aws:userid": "AIDA*" },
+                            "DateLessThan": { "aws:CurrentTime": "2030-01-01T00:00:00Z" },
+                            "Null": { "aws:TokenIssueTime": "false" }
+                        }
+                    }
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(issues(&import), Vec::<String>::new());
+        assert_eq!(
+            policy_text(&import, "TaggedReads"),
+            r#"permit(principal in Group::"auditors", action == Action::"s3:GetObject", resource == Bucket::"arn:aws:s3:::audit") when { (context has "aws:TagKeys" && context["aws:TagKeys"].containsAny(["audit", "compliance"])) && (if context has "aws:RequestedRegions" then ["us-east-1", "us-west-2"].containsAll(context["aws:RequestedRegions"]) else true) && !(context has "aws:userid" && context["aws:userid"] like "AIDA*") && (context has "aws:CurrentTime" && context["aws:CurrentTime"] < datetime("2030-01-01T00:00:00Z")) && context has "aws:TokenIssueTime" };"#
+        );
+
+        // Users are members of the group the policy is attached to
+        let schema = Schema::from_schema_fragments([import.schema().clone()]).unwrap();
+        let result = Validator::new(schema).validate(import.policies(), ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+        let schema = import.schema().clone().to_json_value().unwrap();
+        assert_eq!(
+            schema[""]["entityTypes"]["User"]["memberOfTypes"],
+            serde_json::json!(["Group"])
+        );
+        assert_eq!(
+            schema[""]["actions"]["s3:GetObject"]["appliesTo"]["context"]["attributes"]
+                ["aws:TagKeys"],
+            serde_json::json!({ "type": "Set", "element": { "type": "String" }, "required": false })
+        );
+    }
+
+    #[test]
+    fn reports_statements_it_cannot_translate() {
+        let import = IamImporter::new()
+            .import(
+                r#"{
+                    "Statement": [
+                        { "Sid": "Ok", "Effect": "Allow", "Action": "s3:GetObject", "Resource": "*" },
+                        { "Sid": "Variables", "Effect": "Allow", "Action": "s3:GetObject", "Resource": "arn:aws:s3:::home/${aws:username}/*" },
+                        { "Sid": "IgnoreCase", "Effect": "Deny", "Action": "s3:GetObject", "Resource": "*",
+                          "Condition": { "StringEqualsIgnoreCase": { "aws:PrincipalTag/team": "Audit" } } },
+                        { "Sid": "NotPrincipal", "Effect": "Allow", "NotPrincipal": { "AWS": "111122223333" }, "Action": "s3:GetObject", "Resource": "*" },
+                        { "Sid": "UnknownAction", "Effect": "Allow", "Action": "ec2:Describe*", "Resource": "*" },
+                        { "Sid": "Fraction", "Effect": "Allow", "Action": "s3:GetObject", "Resource": "*",
+                          "Condition": { "NumericLessThan": { "s3:max-keys": "10.5" } } },
+                        { "Effect": "Maybe", "Action": "s3:GetObject", "Resource": "*" },
+                        { "Sid": "Conflict", "Effect": "Allow", "Action": "s3:GetObject", "Resource": "*",
+                          "Condition": { "NumericEquals": { "aws:PrincipalTag/team": "7" } } },
+                        { "Sid": "Missing", "Effect": "Allow", "Action": "s3:GetObject", "Resource": "*",
+                          "Condition": { "StringEquals": { "aws:PrincipalTag/team": "audit" } } }
+                    ]
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(
+            issues(&import),
+            vec![
+                "Variables: policy variables (`${...}`) are not supported; the statement is not translated",
+                "IgnoreCase: `StringEqualsIgnoreCase`: case-insensitive comparison has no Cedar equivalent; the statement is not translated; it denies access, so the Cedar policies allow more than the IAM policy",
+                "NotPrincipal: `NotPrincipal` is not supported; the statement is not translated",
+                "UnknownAction: action pattern `ec2:Describe*` matches none of the known actions; the statement is not translated",
+                "Fraction: `10.5` is not an integer, and Cedar has no other numbers; the statement is not translated",
+                "statement6: `Effect` must be `Allow` or `Deny`",
+                "Missing: condition key `aws:PrincipalTag/team` is used as both Long and String; it is declared as Long",
+            ]
+        );
+        // `Missing` is translated anyway, but doesn't validate against the
+        // schema
+        assert_eq!(import.policies().policies().count(), 3);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            IamImporter::new().import("[]").unwrap_err().to_string(),
+            "invalid IAM policy: expected an object with a `Statement`"
+        );
+        assert_eq!(
+            IamImporter::new()
+                .import(r#"{ "Statement": "s3:GetObject" }"#)
+                .unwrap_err()
+                .to_string(),
+            "invalid IAM policy: `Statement` must be an object or an array"
+        );
+        assert!(IamImporter::new().import("{").is_err());
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::indexing_slicing)]
+#[cfg(feature = "zanzibar")]
+mod zanzibar_import_tests {
+    use super::*;
+
+    const MODEL: &str = r"
+model
+  schema 1.1
+
+type user
+
+type group
+  relations
+    define member: [user, group#member]
+
+type folder
+  relations
+    define viewer: [user, user:*]
+
+type document
+  relations
+    define parent: [folder]
+    define owner: [user]
+    define editor: [user, group#member] or owner
+    define viewer: [user] or editor or viewer from parent
+    define can_share: owner and editor
+";
+
+    fn check(import: &ZanzibarImport, user: &str, relation: &str, document: &str) -> bool {
+        let request = Request::new(
+            EntityUid::from_type_name_and_id("user".parse().unwrap(), EntityId::new(user)),
+            EntityUid::from_type_name_and_id("Action".parse().unwrap(), EntityId::new(relation)),
+            EntityUid::from_type_name_and_id("document".parse().unwrap(), EntityId::new(document)),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        Authorizer::new()
+            .is_authorized(&request, import.policies(), import.entities())
+            .decision()
+            == Decision::Allow
+    }
+
+    #[test]
+    fn imports_tuples_as_relation_hierarchy() {
+        let tuples = "
+            // Engineering includes the platform team
+            group:eng#member@group:platform#member
+            group:platform#member@user:alice
+            document:design#editor@group:eng#member
+            document:design#owner@user:bob
+            document:design#viewer@user:carol
+            document:roadmap#parent@folder:public
+            folder:public#viewer@user:*
+        ";
+        let import = ZanzibarImporter::new().import(MODEL, tuples).unwrap();
+        let issues: Vec<(ZanzibarInput, Option<usize>, String)> = import
+            .issues()
+            .iter()
+            .map(|issue| (issue.input(), issue.line(), issue.to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![(
+                ZanzibarInput::Model,
+                Some(21),
+                "relation `can_share` is an intersection (`and`); it grants nothing".into()
+            )]
+        );
+
+        // Through nested groups
+        assert!(check(&import, "alice", "editor", "design"));
+        assert!(check(&import, "alice", "viewer", "design"));
+        // Through computed relations
+        assert!(check(&import, "bob", "editor", "design"));
+        assert!(check(&import, "bob", "viewer", "design"));
+        assert!(!check(&import, "bob", "can_share", "design"));
+        assert!(check(&import, "carol", "viewer", "design"));
+        assert!(!check(&import, "carol", "editor", "design"));
+        assert!(!check(&import, "carol", "owner", "design"));
+        // Through the parent folder, which everyone can view
+        assert!(check(&import, "dave", "viewer", "roadmap"));
+        assert!(!check(&import, "dave", "editor", "roadmap"));
+        assert!(!check(&import, "dave", "viewer", "design"));
+
+        let uid = |s: &str| EntityUid::from_str(s).unwrap();
+        assert!(import.entities().is_ancestor_of(
+            &uid(r#"Relation::"document:design#viewer""#),
+            &uid(r#"user::"alice""#)
+        ));
+        let template = import
+            .policies()
+            .template(&PolicyId::new("editor"))
+            .unwrap();
+        assert_eq!(
+            template.to_string(),
+            r#"permit(principal in ?principal, action == Action::"editor", resource == ?resource);"#
+        );
+        let link = import
+            .policies()
+            .policy(&PolicyId::new("document:design#editor"))
+            .unwrap();
+        assert_eq!(link.template_id(), Some(&PolicyId::new("editor")));
+        assert_eq!(
+            import
+                .policies()
+                .policy(&PolicyId::new("document:roadmap#viewer@user:*"))
+                .unwrap()
+                .to_string(),
+            r#"permit(principal is user, action == Action::"viewer", resource == document::"roadmap");"#
+        );
+    }
+
+    #[test]
+    fn reports_tuples_it_cannot_import() {
+        let tuples = "
+            document:design#approver@user:alice
+            invoice:1#owner@user:alice
+            document:design#owner@group:eng#member
+            document:design#can_share@user:alice
+            group:a#member@group:b#member
+            group:b#member@group:a#member
+        ";
+        let import = ZanzibarImporter::new()
+            .relation_type("Rel".parse().unwrap())
+            .import(MODEL, tuples)
+            .unwrap();
+        let issues: Vec<(Option<usize>, String)> = import
+            .issues()
+            .iter()
+            .skip(1)
+            .map(|issue| (issue.line(), issue.to_string()))
+            .collect();
+        assert_eq!(
+            issues[0],
+            (Some(2), "type `document` has no relation `approver`".into())
+        );
+        assert_eq!(issues[1], (Some(3), "type `invoice` is not defined".into()));
+        assert_eq!(
+            issues[2],
+            (
+                Some(4),
+                "relation `owner` of type `document` can't relate this kind of user".into()
+            )
+        );
+        // Cyclic group membership can't be represented by an entity hierarchy
+        assert_eq!(issues.len(), 4);
+        assert_eq!(issues[3].0, None);
+        assert!(
+            issues[3]
+                .1
+                .starts_with("failed to build the entity hierarchy"),
+            "{}",
+            issues[3].1
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |model: &str, tuples: &str| {
+            let e = ZanzibarImporter::new().import(model, tuples).unwrap_err();
+            (e.input(), e.line(), e.to_string())
+        };
+        assert_eq!(
+            error(MODEL, "\ndocument:design#owner"),
+            (
+                ZanzibarInput::Tuples,
+                2,
+                "invalid tuples at line 2: expected a tuple `type:id#relation@user`, found `document:design#owner`".into()
+            )
+        );
+        assert_eq!(
+            error("type document\n  relations\n    define viewer: [user]", ""),
+            (
+                ZanzibarInput::Model,
+                3,
+                "invalid model at line 3: type `user` is not defined".into()
+            )
+        );
+        assert_eq!(
+            error("define viewer: [user]", ""),
+            (
+                ZanzibarInput::Model,
+                1,
+                "invalid model at line 1: `define` outside of a type".into()
+            )
+        );
+        assert_eq!(
+            error(
+                "type user\ntype doc\n  relations\n    define viewer: [user] or editor",
+                ""
+            ),
+            (
+                ZanzibarInput::Model,
+                4,
+                "invalid model at line 4: type `doc` has no relation `editor`".into()
+            )
+        );
+    }
+}
+
+// PANIC SAFETY unit tests
+#[allow(clippy::indexing_slicing)]
+#[cfg(feature = "jwt")]
+mod jwt_claims_tests {
+    use super::*;
+
+    const SCHEMA: &str = r"
+        entity User {
+            email: String,
+            age: Long,
+            verified: Bool,
+            roles: Set<String>,
+            manager?: User,
+            org: { id: String, tier?: Long },
+        };
+        entity Document;
+        action view appliesTo {
+            principal: User,
+            resource: Document,
+            context: {
+                scopes: Set<String>,
+                audience: Set<String>,
+                issued: datetime,
+                source_ip?: ipaddr,
+            },
+        };
+    ";
+
+    fn mapping() -> ClaimsMapping {
+        ClaimsMapping::new("User".parse().unwrap())
+            .principal_id("preferred_username")
+            .attribute("email", "email")
+            .attribute("age", "age")
+            .attribute("verified", "email_verified")
+            .attribute("roles", "/realm_access/roles")
+            .attribute("manager", "manager")
+            .attribute("org", "org")
+            .context("scopes", "scope")
+            .context("audience", "aud")
+            .context("issued", "iat")
+            .context("source_ip", "/client/ip")
+    }
+
+    fn view() -> EntityUid {
+        r#"Action::"view""#.parse().unwrap()
+    }
+
+    fn problems(claims: &serde_json::Value) -> Vec<String> {
+        let schema = Schema::from_str(SCHEMA).unwrap();
+        mapping()
+            .map(claims, &schema, &view())
+            .unwrap_err()
+            .problems()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn converts_claims_to_schema_types() {
+        let schema = Schema::from_str(SCHEMA).unwrap();
+        let claims = serde_json::json!({
+            "sub": "f81d4fae",
+            "preferred_username": "alice",
+            "email": "alice@example.com",
+            "age": "42",
+            "email_verified": "true",
+            "realm_access": { "roles": ["editor", "auditor"] },
+            "manager": "bob",
+            "org": { "id": "acme", "region": "eu" },
+            "scope": "docs:read docs:write",
+            "aud": "docs-service",
+            "iat": 1_700_000_000,
+            "client": { "ip": "10.1.2.3" },
+        });
+        let (principal, context) = mapping()
+            .map(&claims, &schema, &view())
+            .unwrap()
+            .into_parts();
+        assert_eq!(principal.uid().to_string(), r#"User::"alice""#);
+        assert_eq!(
+            principal.attr("age").unwrap().unwrap(),
+            EvalResult::Long(42)
+        );
+        assert_eq!(
+            principal.attr("verified").unwrap().unwrap(),
+            EvalResult::Bool(true)
+        );
+
+        // The principal and context conform to the schema, and policies can
+        // use the converted values
+        let entities = Entities::from_entities([principal.clone()], Some(&schema)).unwrap();
+        let request = Request::new(
+            principal.uid(),
+            view(),
+            r#"Document::"plan""#.parse().unwrap(),
+            context,
+            Some(&schema),
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when {
+                principal.age >= 18 &&
+                principal.verified &&
+                principal.roles.contains("auditor") &&
+                principal has manager &&
+                principal.manager == User::"bob" &&
+                principal.org.id == "acme" &&
+                context.scopes == ["docs:read", "docs:write"] &&
+                context.audience == ["docs-service"] &&
+                context.issued == datetime("2023-11-14T22:13:20Z") &&
+                context has source_ip &&
+                context.source_ip.isInRange(ip("10.0.0.0/8"))
+            };"#,
+        )
+        .unwrap();
+        let validation = Validator::new(schema).validate(&policies, ValidationMode::Strict);
+        assert!(validation.validation_passed(), "{validation:?}");
+        let response = Authorizer::new().is_authorized(&request, &policies, &entities);
+        assert_eq!(response.decision(), Decision::Allow, "{response:?}");
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        assert_eq!(
+            problems(&serde_json::json!({
+                "email": "alice@example.com",
+                "age": 4.5,
+                "email_verified": "yes",
+                "realm_access": { "roles": ["editor", 3] },
+                "org": { "tier": "gold" },
+                "scope": "docs:read",
+                "aud": ["docs-service"],
+                "iat": "yesterday",
+                "client": { "ip": "10.1.2.300" },
+            })),
+            vec![
+                "claim `preferred_username` for the id of the principal is missing",
+                "claim `age` for attribute `age` of the principal has the wrong type: expected Long, found `4.5`",
+                "claim `org/id` for attribute `org` of the principal is missing",
+                "claim `org/tier` for attribute `org` of the principal has the wrong type: expected Long, found `\"gold\"`",
+                "claim `/realm_access/roles/1` for attribute `roles` of the principal has the wrong type: expected String, found `3`",
+                "claim `email_verified` for attribute `verified` of the principal has the wrong type: expected Bool, found `\"yes\"`",
+                "claim `iat` for attribute `issued` of the context has the wrong type: expected a `datetime` value, found `\"yesterday\"`",
+                "claim `/client/ip` for attribute `source_ip` of the context has the wrong type: expected a `ipaddr` value, found `\"10.1.2.300\"`",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_mapping_schema_mismatches() {
+        let schema = Schema::from_str(SCHEMA).unwrap();
+        let claims = serde_json::json!({ "sub": "alice", "email": "alice@example.com" });
+        let problems = |mapping: ClaimsMapping, action: &EntityUid| -> Vec<String> {
+            mapping
+                .map(&claims, &schema, action)
+                .unwrap_err()
+                .problems()
+                .map(ToString::to_string)
+                .collect()
+        };
+        assert_eq!(
+            problems(
+                ClaimsMapping::new("User".parse().unwrap()).attribute("mail", "email"),
+                &r#"Action::"edit""#.parse().unwrap()
+            ),
+            vec![
+                "no claim is mapped to the required attribute `age` of the principal",
+                "no claim is mapped to the required attribute `email` of the principal",
+                "no claim is mapped to the required attribute `org` of the principal",
+                "no claim is mapped to the required attribute `roles` of the principal",
+                "no claim is mapped to the required attribute `verified` of the principal",
+                "the schema declares no attribute `mail` for the principal",
+                r#"action `Action::"edit"` is not declared in the schema"#,
+            ]
+        );
+        assert_eq!(
+            problems(ClaimsMapping::new("Admin".parse().unwrap()), &view())[0],
+            "principal type `Admin` is not declared in the schema"
+        );
+    }
+
+    #[test]
+    fn mapping_config_errors() {
+        let err = ClaimsMapping::from_json_str(r#"{ "principal": { "type": "User" }, "ctx": {} }"#)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid claims mapping: unknown field `ctx`"),
+            "{err}"
+        );
+        let err = ClaimsMapping::from_json_str(r#"{ "principal": { "type": "not a type" } }"#)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid claims mapping: invalid principal type `not a type`"),
+            "{err}"
+        );
+    }
+}
+
+#[cfg(feature = "group-sync")]
+mod group_sync_tests {
+    use super::*;
+
+    fn uid(src: &str) -> EntityUid {
+        EntityUid::from_str(src).unwrap()
+    }
+
+    fn sync() -> GroupSync {
+        GroupSync::new("User".parse().unwrap(), "Group".parse().unwrap())
+    }
+
+    fn edges<'a>(edges: impl Iterator<Item = (&'a EntityUid, &'a EntityUid)>) -> Vec<String> {
+        edges
+            .map(|(member, group)| format!("{member} in {group}"))
+            .collect()
+    }
+
+    #[test]
+    fn scim_changes_update_hierarchy() {
+        let mut entities = Entities::from_json_value(
+            serde_json::json!([
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "name": "Alice" },
+                    "parents": [{ "type": "Role", "id": "staff" }]
+                },
+                {
+                    "uid": { "type": "Group", "id": "eng" },
+                    "attrs": {},
+                    "parents": [{ "type": "Role", "id": "dev" }]
+                },
+            ]),
+            None,
+        )
+        .unwrap();
+        let mut sync = sync();
+        let delta = sync
+            .apply_scim_group(
+                r#"{
+                    "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+                    "Resources": [
+                        { "id": "eng", "members": [{ "value": "alice" }, { "value": "platform", "type": "Group" }] },
+                        { "id": "platform", "members": [{ "value": "bob", "type": "User" }] }
+                    ]
+                }"#,
+            )
+            .unwrap();
+        sync.apply(&delta, &mut entities, None).unwrap();
+        for (ancestor, member) in [
+            (r#"Group::"eng""#, r#"User::"alice""#),
+            (r#"Role::"dev""#, r#"User::"alice""#),
+            (r#"Role::"staff""#, r#"User::"alice""#),
+            (r#"Group::"eng""#, r#"User::"bob""#),
+            (r#"Role::"dev""#, r#"User::"bob""#),
+            (r#"Role::"dev""#, r#"Group::"platform""#),
+        ] {
+            assert!(
+                entities.is_ancestor_of(&uid(ancestor), &uid(member)),
+                "{member} in {ancestor}"
+            );
+        }
+        assert_eq!(
+            entities.get(&uid(r#"User::"alice""#)).unwrap().attr("name"),
+            Some(Ok(EvalResult::String("Alice".into())))
+        );
+
+        let delta = sync
+            .apply_scim_patch(
+                "eng",
+                r#"{
+                    "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                    "Operations": [
+                        { "op": "Remove", "path": "members[value eq \"platform\"]" },
+                        { "op": "replace", "path": "displayName", "value": "Engineering" }
+                    ]
+                }"#,
+            )
+            .unwrap();
+        assert_eq!(
+            edges(delta.removed()),
+            vec![r#"Group::"platform" in Group::"eng""#]
+        );
+        sync.apply(&delta, &mut entities, None).unwrap();
+        assert!(entities.is_ancestor_of(&uid(r#"Group::"platform""#), &uid(r#"User::"bob""#)));
+        assert!(!entities.is_ancestor_of(&uid(r#"Group::"eng""#), &uid(r#"User::"bob""#)));
+        assert!(!entities.is_ancestor_of(&uid(r#"Role::"dev""#), &uid(r#"User::"bob""#)));
+        assert!(entities.is_ancestor_of(&uid(r#"Role::"dev""#), &uid(r#"User::"alice""#)));
+    }
+
+    #[test]
+    fn apply_leaves_unaffected_entities_alone() {
+        let mut entities = Entities::from_json_value(
+            serde_json::json!([
+                {
+                    "uid": { "type": "User", "id": "carol" },
+                    "attrs": {},
+                    "parents": [{ "type": "Group", "id": "ops" }]
+                },
+                { "uid": { "type": "Group", "id": "ops" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Group", "id": "eng" }, "attrs": {}, "parents": [] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let carol = uid(r#"User::"carol""#);
+        let eng = uid(r#"Group::"eng""#);
+        let unaffected: *const Entity = entities.get(&carol).unwrap();
+        let untouched_group: *const Entity = entities.get(&eng).unwrap();
+
+        let mut sync = sync();
+        let delta = sync.apply_ldap_group("eng", ["alice"]);
+        sync.apply(&delta, &mut entities, None).unwrap();
+        assert!(entities.is_ancestor_of(&eng, &uid(r#"User::"alice""#)));
+        assert!(entities.is_ancestor_of(&uid(r#"Group::"ops""#), &carol));
+        // Neither the unrelated user nor the group the new member joined was
+        // rebuilt
+        assert!(std::ptr::eq(unaffected, entities.get(&carol).unwrap()));
+        assert!(std::ptr::eq(untouched_group, entities.get(&eng).unwrap()));
+    }
+
+    #[test]
+    fn scim_patch_operations() {
+        let mut sync = sync();
+        let eng = uid(r#"Group::"eng""#);
+        let mut delta = sync
+            .apply_scim_patch(
+                "eng",
+                r#"{ "Operations": [
+                    { "op": "add", "path": "members", "value": [{ "value": "alice" }, { "value": "bob" }] },
+                    { "op": "add", "value": { "members": [{ "value": "carol" }] } }
+                ] }"#,
+            )
+            .unwrap();
+        assert_eq!(sync.members_of(&eng).count(), 3);
+        delta.merge(
+            sync.apply_scim_patch(
+                "eng",
+                r#"{ "Operations": [
+                    { "op": "remove", "path": "members", "value": [{ "value": "carol" }] },
+                    { "op": "replace", "path": "members", "value": [{ "value": "bob" }, { "value": "dave" }] }
+                ] }"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            edges(delta.added()),
+            vec![
+                r#"User::"bob" in Group::"eng""#,
+                r#"User::"dave" in Group::"eng""#,
+            ]
+        );
+        assert_eq!(delta.removed().count(), 0);
+
+        let delta = sync
+            .apply_scim_patch(
+                "eng",
+                r#"{ "Operations": [{ "op": "remove", "path": "members" }] }"#,
+            )
+            .unwrap();
+        assert_eq!(delta.removed().count(), 2);
+        assert_eq!(sync.members_of(&eng).count(), 0);
+
+        let err = sync
+            .apply_scim_patch(
+                "eng",
+                r#"{ "Operations": [{ "op": "move", "path": "members", "value": [] }] }"#,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid SCIM data: unsupported operation `move`"
+        );
+        let err = sync.apply_scim_patch("eng", "{}").unwrap_err();
+        assert_eq!(err.message(), "expected an array of `Operations`");
+        let err = sync
+            .apply_scim_group(r#"{ "id": "eng", "members": [{ "display": "Alice" }] }"#)
+            .unwrap_err();
+        assert_eq!(err.message(), "a member has no `value`");
+    }
+
+    #[test]
+    fn ldap_changes_update_hierarchy() {
+        let mut sync = sync().ldap_group_base("ou=groups,dc=example,dc=com");
+        let alice = uid(r#"User::"uid=alice,ou=people,dc=example,dc=com""#);
+        let eng = uid(r#"Group::"cn=eng,ou=groups,dc=example,dc=com""#);
+        let staff = uid(r#"Group::"cn=staff,ou=groups,dc=example,dc=com""#);
+        let mut delta = sync.apply_ldap_group(
+            "cn=staff,ou=groups,dc=example,dc=com",
+            ["cn=eng,ou=Groups,dc=example,dc=com"],
+        );
+        delta.merge(sync.apply_ldap_member_of(
+            "uid=alice,ou=people,dc=example,dc=com",
+            ["cn=eng,ou=groups,dc=example,dc=com"],
+        ));
+        let mut entities = Entities::empty();
+        sync.apply(&delta, &mut entities, None).unwrap();
+        assert!(entities.is_ancestor_of(&eng, &alice));
+        // The member DN differs from the group's DN in case, so it names a
+        // different group
+        assert!(!entities.is_ancestor_of(&staff, &alice));
+
+        let delta = sync.apply_ldap_group(
+            "cn=staff,ou=groups,dc=example,dc=com",
+            ["cn=eng,ou=groups,dc=example,dc=com"],
+        );
+        sync.apply(&delta, &mut entities, None).unwrap();
+        assert!(entities.is_ancestor_of(&staff, &alice));
+
+        let delta = sync.remove_group(&eng);
+        assert_eq!(
+            edges(delta.removed()),
+            vec![
+                r#"Group::"cn=eng,ou=groups,dc=example,dc=com" in Group::"cn=staff,ou=groups,dc=example,dc=com""#,
+                r#"User::"uid=alice,ou=people,dc=example,dc=com" in Group::"cn=eng,ou=groups,dc=example,dc=com""#,
+            ]
+        );
+        sync.apply(&delta, &mut entities, None).unwrap();
+        assert!(!entities.is_ancestor_of(&eng, &alice));
+        assert!(!entities.is_ancestor_of(&staff, &alice));
+        assert!(entities.get(&eng).is_some());
+    }
+}